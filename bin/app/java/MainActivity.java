@@ -11,6 +11,30 @@ import android.widget.TextView;
 import android.view.inputmethod.BaseInputConnection;
 import java.util.HashMap;
 
+import android.content.Context;
+import android.content.SharedPreferences;
+import android.security.keystore.KeyGenParameterSpec;
+import android.security.keystore.KeyProperties;
+import android.util.Base64;
+import java.nio.ByteBuffer;
+import java.security.KeyStore;
+import javax.crypto.Cipher;
+import javax.crypto.KeyGenerator;
+import javax.crypto.SecretKey;
+import javax.crypto.spec.GCMParameterSpec;
+
+import android.content.ActivityNotFoundException;
+import android.graphics.Bitmap;
+import android.provider.MediaStore;
+import java.io.File;
+import java.io.FileOutputStream;
+
+import android.app.Notification;
+import android.app.NotificationChannel;
+import android.app.NotificationManager;
+import android.app.PendingIntent;
+import android.os.Build;
+
 import autosuggest.InvisibleInputView;
 import autosuggest.CustomInputConnection;
 
@@ -176,10 +200,169 @@ public int getKeyboardHeight() {
     return Math.max(0, imeInsets.bottom - navInsets.bottom);
 }
 
+// Returns the safe area inset for the given side (0=left, 1=top, 2=right,
+// 3=bottom) in pixels, covering system bars and any display cutout.
+public int getSafeAreaInset(int side) {
+    WindowInsets windowInsets = view.getRootWindowInsets();
+    if (windowInsets == null) {
+        return 0;
+    }
+    Insets insets = windowInsets.getInsets(
+        WindowInsets.Type.systemBars() | WindowInsets.Type.displayCutout());
+    switch (side) {
+        case 0: return insets.left;
+        case 1: return insets.top;
+        case 2: return insets.right;
+        case 3: return insets.bottom;
+        default: return 0;
+    }
+}
+
 public float getScreenDensity() {
     return getResources().getDisplayMetrics().density;
 }
 
+// Secrets are encrypted with an AES key which never leaves the Android
+// Keystore, and the ciphertexts are kept in private shared preferences.
+// Values cross JNI as base64 strings. keystoreGet() returns null for
+// missing secrets and an empty string when decryption fails.
+private static final String KEYSTORE_ALIAS = "darkfi_secure_storage";
+
+private SecretKey keystoreKey() throws Exception {
+    KeyStore ks = KeyStore.getInstance("AndroidKeyStore");
+    ks.load(null);
+    if (ks.containsAlias(KEYSTORE_ALIAS)) {
+        return ((KeyStore.SecretKeyEntry)ks.getEntry(KEYSTORE_ALIAS, null)).getSecretKey();
+    }
+    KeyGenerator gen = KeyGenerator.getInstance(KeyProperties.KEY_ALGORITHM_AES, "AndroidKeyStore");
+    gen.init(new KeyGenParameterSpec.Builder(
+        KEYSTORE_ALIAS, KeyProperties.PURPOSE_ENCRYPT | KeyProperties.PURPOSE_DECRYPT)
+        .setBlockModes(KeyProperties.BLOCK_MODE_GCM)
+        .setEncryptionPaddings(KeyProperties.ENCRYPTION_PADDING_NONE)
+        .build());
+    return gen.generateKey();
+}
+private SharedPreferences keystorePrefs() {
+    return getApplicationContext().getSharedPreferences(KEYSTORE_ALIAS, Context.MODE_PRIVATE);
+}
+
+public String keystoreGet(String name) {
+    String stored = keystorePrefs().getString(name, null);
+    if (stored == null) {
+        return null;
+    }
+    try {
+        ByteBuffer buf = ByteBuffer.wrap(Base64.decode(stored, Base64.NO_WRAP));
+        byte[] iv = new byte[12];
+        buf.get(iv);
+        byte[] ciphertext = new byte[buf.remaining()];
+        buf.get(ciphertext);
+
+        Cipher cipher = Cipher.getInstance("AES/GCM/NoPadding");
+        cipher.init(Cipher.DECRYPT_MODE, keystoreKey(), new GCMParameterSpec(128, iv));
+        cipher.updateAAD(name.getBytes());
+        return Base64.encodeToString(cipher.doFinal(ciphertext), Base64.NO_WRAP);
+    } catch (Exception e) {
+        Log.e("darkfi", "keystoreGet(" + name + ") failed", e);
+        return "";
+    }
+}
+public boolean keystoreSet(String name, String value) {
+    try {
+        Cipher cipher = Cipher.getInstance("AES/GCM/NoPadding");
+        cipher.init(Cipher.ENCRYPT_MODE, keystoreKey());
+        cipher.updateAAD(name.getBytes());
+        byte[] iv = cipher.getIV();
+        byte[] ciphertext = cipher.doFinal(Base64.decode(value, Base64.NO_WRAP));
+
+        ByteBuffer buf = ByteBuffer.allocate(iv.length + ciphertext.length);
+        buf.put(iv);
+        buf.put(ciphertext);
+        String stored = Base64.encodeToString(buf.array(), Base64.NO_WRAP);
+        return keystorePrefs().edit().putString(name, stored).commit();
+    } catch (Exception e) {
+        Log.e("darkfi", "keystoreSet(" + name + ") failed", e);
+        return false;
+    }
+}
+public boolean keystoreDelete(String name) {
+    return keystorePrefs().edit().remove(name).commit();
+}
+
+// QR scanning hands off to the camera app. The returned thumbnail is
+// written to the cache dir and decoded on the Rust side.
+private static final int QR_SCAN_REQUEST = 0x5152;
+
+native static void onQrScanResult(String path);
+
+public void startQrScan() {
+    runOnUiThread(new Runnable() {
+        @Override
+        public void run() {
+            Intent intent = new Intent(MediaStore.ACTION_IMAGE_CAPTURE);
+            try {
+                startActivityForResult(intent, QR_SCAN_REQUEST);
+            } catch (ActivityNotFoundException e) {
+                Log.w("darkfi", "startQrScan() no camera app available", e);
+                onQrScanResult(null);
+            }
+        }
+    });
+}
+// Notifications for messages and payments while the app is in the background
+private static final String NOTIFY_CHANNEL = "darkfi_notify";
+private int nextNotifyId = 1000;
+
+public void postNotification(String title, String body) {
+    if (hasWindowFocus()) {
+        return;
+    }
+    NotificationManager manager = getSystemService(NotificationManager.class);
+    if (manager.getNotificationChannel(NOTIFY_CHANNEL) == null) {
+        manager.createNotificationChannel(new NotificationChannel(
+            NOTIFY_CHANNEL,
+            "Messages and payments",
+            NotificationManager.IMPORTANCE_DEFAULT
+        ));
+    }
+
+    // Tapping the notification brings back the running activity
+    Intent intent = new Intent(this, MainActivity.class);
+    intent.setFlags(Intent.FLAG_ACTIVITY_SINGLE_TOP);
+    PendingIntent pending = PendingIntent.getActivity(
+        this, 0, intent, PendingIntent.FLAG_IMMUTABLE);
+
+    Notification notification = new Notification.Builder(this, NOTIFY_CHANNEL)
+        .setContentTitle(title)
+        .setContentText(body)
+        .setSmallIcon(android.R.drawable.ic_dialog_email)
+        .setContentIntent(pending)
+        .setAutoCancel(true)
+        .build();
+    manager.notify(nextNotifyId++, notification);
+}
+
+private void finishQrScan(int resultCode, Intent data) {
+    if (resultCode != RESULT_OK || data == null || data.getExtras() == null) {
+        onQrScanResult(null);
+        return;
+    }
+    Bitmap bitmap = (Bitmap)data.getExtras().get("data");
+    if (bitmap == null) {
+        onQrScanResult(null);
+        return;
+    }
+    File file = new File(getCacheDir(), "qrscan.png");
+    try (FileOutputStream out = new FileOutputStream(file)) {
+        bitmap.compress(Bitmap.CompressFormat.PNG, 100, out);
+    } catch (Exception e) {
+        Log.e("darkfi", "finishQrScan() unable to save photo", e);
+        onQrScanResult(null);
+        return;
+    }
+    onQrScanResult(file.getAbsolutePath());
+}
+
 //% END
 
 //% MAIN_ACTIVITY_ON_CREATE
@@ -195,5 +378,18 @@ view.clearFocus();
 Intent serviceIntent = new Intent(this, ForegroundService.class);
 startForegroundService(serviceIntent);
 
+// Needed for postNotification() since Android 13
+if (Build.VERSION.SDK_INT >= 33) {
+    requestPermissions(new String[] { "android.permission.POST_NOTIFICATIONS" }, 0);
+}
+
+//% END
+
+//% MAIN_ACTIVITY_ON_ACTIVITY_RESULT
+
+if (requestCode == QR_SCAN_REQUEST) {
+    finishQrScan(resultCode, data);
+}
+
 //% END
 
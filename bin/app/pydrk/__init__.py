@@ -1,6 +1,7 @@
 from .api import (Api, ErrorCode, SceneNodeType,
                   PropertyType, PropertySubType, Property,
                   vertex, face)
+from .client import Client
 from .host import HostApi
 from . import exc, serial
 
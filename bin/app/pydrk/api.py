@@ -1,4 +1,4 @@
-import zmq
+import os, zmq
 from collections import namedtuple
 from . import serial, exc, expr
 
@@ -42,6 +42,11 @@ class Command:
     GET_METHODS = 20
     GET_METHOD = 21
     CALL_METHOD = 22
+    UNDO = 25
+    REDO = 26
+    GET_UNDO_HISTORY = 27
+    GET_VERSION = 28
+    GET_SCHEMA = 29
 
 class SceneNodeType:
     NULL = 0
@@ -66,6 +71,12 @@ class SceneNodeType:
     EMOJI_PICKER = 19
     SETTING_ROOT = 20
     SETTING = 21
+    PALETTE_INPUT = 22
+    FLEX_BOX = 23
+    LIST_VIEW = 24
+    QR_CODE = 25
+    THEME_ROOT = 26
+    THEME_TOKEN = 27
     PLUGINS = 100
     PLUGIN = 101
 
@@ -160,6 +171,9 @@ class ErrorCode:
     SEXPR_EMPTY = 31
     SEXPR_GLOBAL_NOT_FOUND = 32
     CHANNEL_CLOSED = 36
+    NOTHING_TO_UNDO = 54
+    NOTHING_TO_REDO = 55
+    ZMQ_UNAUTHORIZED = 61
 
     @staticmethod
     def to_str(errc):
@@ -228,6 +242,12 @@ class ErrorCode:
                 return "sexpr_global_not_found"
             case ErrorCode.CHANNEL_CLOSED:
                 return "channel_closed"
+            case ErrorCode.NOTHING_TO_UNDO:
+                return "nothing_to_undo"
+            case ErrorCode.NOTHING_TO_REDO:
+                return "nothing_to_redo"
+            case ErrorCode.ZMQ_UNAUTHORIZED:
+                return "zmq_unauthorized"
 
 def vertex(x, y, r, g, b, a, u, v):
     buf = bytearray()
@@ -248,18 +268,40 @@ def face(idx1, idx2, idx3):
     serial.write_u32(buf, idx3)
     return buf
 
+PROTOCOL_VERSION = 2
+
+def load_auth_token():
+    # Same lookup as get_auth_token_path() in src/net.rs
+    token = os.environ.get("DARKFI_APP_ZMQ_TOKEN")
+    if token is not None:
+        return token
+    data_dir = os.environ.get("XDG_DATA_HOME",
+                              os.path.expanduser("~/.local/share"))
+    try:
+        with open(os.path.join(data_dir, "darkfi/app/zmq_token")) as f:
+            return f.read().strip()
+    except FileNotFoundError:
+        return None
+
 class Api:
 
-    def __init__(self, addr="127.0.0.1", port=9484):
+    # Mutating commands are rejected by the app without the token
+    def __init__(self, addr="127.0.0.1", port=9484, token=None):
         context = zmq.Context()
         self.socket = context.socket(zmq.REQ)
         #self.socket.setsockopt(zmq.IPV6, True)
         self.socket.connect(f"tcp://{addr}:{port}")
+        if token is None:
+            token = load_auth_token()
+        self.token = token
 
     def _make_request(self, cmd, payload):
         req_cmd = bytearray()
         serial.write_u8(req_cmd, cmd)
-        self.socket.send_multipart([req_cmd, payload])
+        req = [req_cmd, payload]
+        if self.token is not None:
+            req.append(self.token.encode())
+        self.socket.send_multipart(req)
 
         errc, reply = self.socket.recv_multipart()
         errc = int.from_bytes(errc, "little")
@@ -331,12 +373,41 @@ class Api:
                 raise exc.SExprGlobalNotFound
             case 36:
                 raise exc.ChannelClosed
+            case 54:
+                raise exc.NothingToUndo
+            case 55:
+                raise exc.NothingToRedo
+            case 61:
+                raise exc.Unauthorized
         return cursor
 
     def hello(self):
         response = self._make_request(Command.HELLO, bytearray())
         return serial.decode_str(response)
 
+    def get_version(self):
+        cur = self._make_request(Command.GET_VERSION, bytearray())
+        return serial.read_u32(cur)
+
+    def get_schema(self):
+        cur = self._make_request(Command.GET_SCHEMA, bytearray())
+        version = serial.read_u32(cur)
+
+        def read_item(cur):
+            return (serial.read_u8(cur), serial.decode_str(cur))
+
+        def read_cmd(cur):
+            i, name = read_item(cur)
+            return (i, name, bool(serial.read_u8(cur)))
+
+        return {
+            "version": version,
+            "property_types": serial.decode_arr(cur, read_item),
+            "property_subtypes": serial.decode_arr(cur, read_item),
+            "node_types": serial.decode_arr(cur, read_item),
+            "commands": serial.decode_arr(cur, read_cmd),
+        }
+
     def get_info(self, node_id):
         req = bytearray()
         serial.write_u32(req, node_id)
@@ -463,17 +534,18 @@ class Api:
         vals = serial.decode_arr(cur, prop_read_fn)
         return vals
 
-    def add_node(self, node_name, node_type):
+    def add_node(self, parent_path, node_name, node_type):
         req = bytearray()
+        serial.encode_str(req, parent_path)
         serial.encode_str(req, node_name)
         serial.write_u8(req, int(node_type))
         cur = self._make_request(Command.ADD_NODE, req)
         node_id = serial.read_u32(cur)
         return node_id
 
-    def remove_node(self, node_id):
+    def remove_node(self, node_path):
         req = bytearray()
-        serial.write_u32(req, node_id)
+        serial.encode_str(req, node_path)
         self._make_request(Command.REMOVE_NODE, req)
 
     def rename_node(self, node_id, node_name):
@@ -559,10 +631,9 @@ class Api:
         serial.write_u32(req, parent_id)
         self._make_request(Command.LINK_NODE, req)
 
-    def unlink_node(self, child_id, parent_id):
+    def unlink_node(self, node_path):
         req = bytearray()
-        serial.write_u32(req, child_id)
-        serial.write_u32(req, parent_id)
+        serial.encode_str(req, node_path)
         self._make_request(Command.UNLINK_NODE, req)
 
     def set_property_null(self, node_path, prop_name, i):
@@ -729,3 +800,23 @@ class Api:
         result = serial.decode_opt(cur, serial.decode_buf)
         return result
 
+    def undo(self):
+        try:
+            self._make_request(Command.UNDO, bytearray())
+        except exc.NothingToUndo:
+            return False
+        return True
+
+    def redo(self):
+        try:
+            self._make_request(Command.REDO, bytearray())
+        except exc.NothingToRedo:
+            return False
+        return True
+
+    def get_undo_history(self):
+        cur = self._make_request(Command.GET_UNDO_HISTORY, bytearray())
+        undo = serial.decode_arr(cur, serial.decode_str)
+        redo = serial.decode_arr(cur, serial.decode_str)
+        return (undo, redo)
+
@@ -0,0 +1,91 @@
+# High level wrapper around Api for scripts and automated UI tests.
+#
+#   c = Client()
+#   c.set("/setting/theme", "value", "light")
+#   c.wait_for_value("/theme", "mode", "light")
+import time
+from . import exc
+from .api import Api, PropertyType, PROTOCOL_VERSION
+
+class Client:
+
+    def __init__(self, addr="127.0.0.1", port=9484, token=None):
+        self.api = Api(addr, port, token)
+        version = self.api.get_version()
+        if version != PROTOCOL_VERSION:
+            raise Exception(f"app speaks protocol v{version}, "
+                            f"expected v{PROTOCOL_VERSION}")
+        self._prop_types = {}
+
+    def _prop_type(self, node_path, prop_name):
+        key = (node_path, prop_name)
+        if key not in self._prop_types:
+            for prop in self.api.get_properties(node_path):
+                self._prop_types[(node_path, prop.name)] = prop.type
+        try:
+            return self._prop_types[key]
+        except KeyError:
+            raise exc.PropertyNotFound
+
+    def get(self, node_path, prop_name, i=0):
+        return self.api.get_property_value(node_path, prop_name)[i]
+
+    def get_all(self, node_path, prop_name):
+        return self.api.get_property_value(node_path, prop_name)
+
+    def set(self, node_path, prop_name, val, i=0):
+        if val is None:
+            self.api.set_property_null(node_path, prop_name, i)
+            return
+        match self._prop_type(node_path, prop_name):
+            case PropertyType.BOOL:
+                self.api.set_property_bool(node_path, prop_name, i, val)
+            case PropertyType.UINT32:
+                self.api.set_property_u32(node_path, prop_name, i, val)
+            case PropertyType.FLOAT32:
+                self.api.set_property_f32(node_path, prop_name, i, val)
+            case PropertyType.STR:
+                self.api.set_property_str(node_path, prop_name, i, val)
+            case PropertyType.ENUM:
+                self.api.set_property_enum(node_path, prop_name, i, val)
+            case PropertyType.BUFFER:
+                self.api.set_property_buf(node_path, prop_name, i, val)
+            case PropertyType.SEXPR:
+                self.api.set_property_expr(node_path, prop_name, i, val)
+            case _:
+                raise exc.PropertyWrongType
+
+    def set_all(self, node_path, prop_name, vals):
+        for i, val in enumerate(vals):
+            self.set(node_path, prop_name, val, i)
+
+    def call(self, node_path, method_name, arg_data=bytearray()):
+        return self.api.call_method(node_path, method_name, arg_data)
+
+    def children(self, node_path):
+        return [name for name, _, _ in self.api.get_children(node_path)]
+
+    def exists(self, node_path):
+        return self.api.lookup_node_id(node_path) is not None
+
+    def add_node(self, parent_path, name, node_type):
+        return self.api.add_node(parent_path, name, node_type)
+
+    def remove_node(self, node_path):
+        self.api.remove_node(node_path)
+        self._prop_types = {k: v for k, v in self._prop_types.items()
+                            if not k[0].startswith(node_path)}
+
+    def wait_for(self, cond, timeout=5., interval=0.05):
+        """Poll cond() until it's truthy. Raises TimeoutError otherwise."""
+        deadline = time.monotonic() + timeout
+        while True:
+            if cond():
+                return
+            if time.monotonic() > deadline:
+                raise TimeoutError("condition not met")
+            time.sleep(interval)
+
+    def wait_for_value(self, node_path, prop_name, val, i=0, timeout=5.):
+        self.wait_for(lambda: self.get(node_path, prop_name, i) == val,
+                      timeout)
@@ -61,3 +61,10 @@ class SExprEmpty(Exception):
 class SExprGlobalNotFound(Exception):
     pass
 
+class NothingToUndo(Exception):
+    pass
+class NothingToRedo(Exception):
+    pass
+
+class Unauthorized(Exception):
+    pass
@@ -56,6 +56,7 @@ macro_rules! call_mainactivity_float_method {
 struct GlobalData {
     senders: HashMap<usize, async_channel::Sender<AndroidSuggestEvent>>,
     next_id: usize,
+    qr_scan_sender: Option<async_channel::Sender<Option<PathBuf>>>,
 }
 
 fn send(id: usize, ev: AndroidSuggestEvent) {
@@ -70,8 +71,9 @@ fn send(id: usize, ev: AndroidSuggestEvent) {
 unsafe impl Send for GlobalData {}
 unsafe impl Sync for GlobalData {}
 
-static GLOBALS: LazyLock<SyncMutex<GlobalData>> =
-    LazyLock::new(|| SyncMutex::new(GlobalData { senders: HashMap::new(), next_id: 0 }));
+static GLOBALS: LazyLock<SyncMutex<GlobalData>> = LazyLock::new(|| {
+    SyncMutex::new(GlobalData { senders: HashMap::new(), next_id: 0, qr_scan_sender: None })
+});
 
 #[no_mangle]
 pub unsafe extern "C" fn Java_darkfi_darkfi_1app_MainActivity_onInitEdit(
@@ -84,6 +86,22 @@ pub unsafe extern "C" fn Java_darkfi_darkfi_1app_MainActivity_onInitEdit(
     send(id, AndroidSuggestEvent::Init);
 }
 
+/// Called with the path of the captured photo, or null if the user cancelled
+#[no_mangle]
+pub unsafe extern "C" fn Java_darkfi_darkfi_1app_MainActivity_onQrScanResult(
+    env: *mut ndk_sys::JNIEnv,
+    _: ndk_sys::jobject,
+    path: ndk_sys::jobject,
+) {
+    let path =
+        if path.is_null() { None } else { Some(PathBuf::from(ndk_utils::get_utf_str!(env, path))) };
+    let Some(sender) = GLOBALS.lock().qr_scan_sender.take() else {
+        warn!(target: "android", "Discarding QR scan result with no listener: {path:?}");
+        return
+    };
+    let _ = sender.try_send(path);
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn Java_autosuggest_InvisibleInputView_onCreateInputConnect(
     _env: *mut ndk_sys::JNIEnv,
@@ -321,6 +339,42 @@ pub fn get_editable(id: usize) -> Option<Editable> {
     }
 }
 
+/// Launch the camera app. The photo path is sent once it returns.
+/// Starting a new scan replaces any pending one.
+pub fn start_qr_scan(sender: async_channel::Sender<Option<PathBuf>>) {
+    GLOBALS.lock().qr_scan_sender = Some(sender);
+    unsafe {
+        let env = android::attach_jni_env();
+        ndk_utils::call_void_method!(env, android::ACTIVITY, "startQrScan", "()V");
+    }
+}
+
+/// Post a system notification. Skipped while the app has focus since
+/// the in-app toast is shown instead.
+pub fn post_notification(title: &str, body: &str) {
+    let ctitle = std::ffi::CString::new(title).unwrap();
+    let cbody = std::ffi::CString::new(body).unwrap();
+    unsafe {
+        let env = android::attach_jni_env();
+
+        let new_string_utf = (**env).NewStringUTF.unwrap();
+        let delete_local_ref = (**env).DeleteLocalRef.unwrap();
+
+        let jtitle = new_string_utf(env, ctitle.as_ptr());
+        let jbody = new_string_utf(env, cbody.as_ptr());
+        ndk_utils::call_void_method!(
+            env,
+            android::ACTIVITY,
+            "postNotification",
+            "(Ljava/lang/String;Ljava/lang/String;)V",
+            jtitle,
+            jbody
+        );
+        delete_local_ref(env, jtitle);
+        delete_local_ref(env, jbody);
+    }
+}
+
 pub fn get_appdata_path() -> PathBuf {
     call_mainactivity_str_method!("getAppDataPath").into()
 }
@@ -332,6 +386,100 @@ pub fn get_keyboard_height() -> usize {
     call_mainactivity_int_method!("getKeyboardHeight", "()I") as usize
 }
 
+/// Safe area insets in pixels as `[left, top, right, bottom]`.
+/// Content drawn inside these margins may end up under a display
+/// cutout or the system bars.
+pub fn get_safe_area_insets() -> [f32; 4] {
+    let mut insets = [0.; 4];
+    for (side, inset) in insets.iter_mut().enumerate() {
+        *inset = call_mainactivity_int_method!("getSafeAreaInset", "(I)I", side as i32) as f32;
+    }
+    insets
+}
+
 pub fn get_screen_density() -> f32 {
     call_mainactivity_float_method!("getScreenDensity")
 }
+
+/// Retrieve a base64 encoded secret from the Android Keystore backed storage
+pub fn keystore_get(name: &str) -> Option<String> {
+    let cname = std::ffi::CString::new(name).unwrap();
+    unsafe {
+        let env = android::attach_jni_env();
+
+        let new_string_utf = (**env).NewStringUTF.unwrap();
+        let delete_local_ref = (**env).DeleteLocalRef.unwrap();
+
+        let jname = new_string_utf(env, cname.as_ptr());
+        let value = ndk_utils::call_object_method!(
+            env,
+            android::ACTIVITY,
+            "keystoreGet",
+            "(Ljava/lang/String;)Ljava/lang/String;",
+            jname
+        );
+        delete_local_ref(env, jname);
+
+        if value.is_null() {
+            return None
+        }
+        Some(ndk_utils::get_utf_str!(env, value).to_string())
+    }
+}
+
+/// Store a base64 encoded secret in the Android Keystore backed storage
+pub fn keystore_set(name: &str, value: &str) -> Option<()> {
+    let cname = std::ffi::CString::new(name).unwrap();
+    let cvalue = std::ffi::CString::new(value).unwrap();
+    let is_success = unsafe {
+        let env = android::attach_jni_env();
+
+        let new_string_utf = (**env).NewStringUTF.unwrap();
+        let delete_local_ref = (**env).DeleteLocalRef.unwrap();
+
+        let jname = new_string_utf(env, cname.as_ptr());
+        let jvalue = new_string_utf(env, cvalue.as_ptr());
+        let res = ndk_utils::call_bool_method!(
+            env,
+            android::ACTIVITY,
+            "keystoreSet",
+            "(Ljava/lang/String;Ljava/lang/String;)Z",
+            jname,
+            jvalue
+        );
+        delete_local_ref(env, jname);
+        delete_local_ref(env, jvalue);
+        res
+    };
+    if is_success == 0u8 {
+        None
+    } else {
+        Some(())
+    }
+}
+
+pub fn keystore_delete(name: &str) -> Option<()> {
+    let cname = std::ffi::CString::new(name).unwrap();
+    let is_success = unsafe {
+        let env = android::attach_jni_env();
+
+        let new_string_utf = (**env).NewStringUTF.unwrap();
+        let delete_local_ref = (**env).DeleteLocalRef.unwrap();
+
+        let jname = new_string_utf(env, cname.as_ptr());
+        let res = ndk_utils::call_bool_method!(
+            env,
+            android::ACTIVITY,
+            "keystoreDelete",
+            "(Ljava/lang/String;)Z",
+            jname
+        );
+        delete_local_ref(env, jname);
+        res
+    };
+    if is_success == 0u8 {
+        None
+    } else {
+        Some(())
+    }
+}
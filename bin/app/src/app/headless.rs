@@ -0,0 +1,394 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Runs widgets without a display, for integration tests.
+//!
+//! The gfx backend is replaced by [`HeadlessBackend`], input is injected
+//! through the [`GraphicsEventPublisher`] and [`Harness::settle()`] returns
+//! everything that would be on screen once the UI has gone idle.
+//!
+//! Each kind of event is handled by its own task in the window, so call
+//! `settle()` between events of different kinds when their order matters.
+
+use async_channel::Receiver;
+use darkfi::system::msleep;
+use miniquad::{KeyCode, KeyMods, MouseButton, TouchPhase};
+use sled_overlay::sled;
+use std::sync::{LazyLock, Mutex, MutexGuard, Once};
+
+use crate::{
+    app::node::{
+        create_chatview, create_layer, create_multiline_edit, create_singleline_edit, create_window,
+    },
+    expr,
+    gfx::{
+        gfxtag,
+        headless::{CapturedFrame, HeadlessBackend},
+        Dimension, EpochIndex, GraphicsEventPublisher, GraphicsEventPublisherPtr, GraphicsMethod,
+        Point, Rectangle, RenderApi,
+    },
+    plugin::PluginSettings,
+    prop::{
+        Property, PropertyAtomicGuard, PropertyFloat32, PropertySubType, PropertyType,
+        PropertyValue, Role,
+    },
+    scene::{Pimpl, SceneNode, SceneNodePtr, SceneNodeType},
+    text::TextShaper,
+    text2,
+    ui::{BaseEdit, BaseEditType, ChatView, Layer, Window, WindowPtr},
+    util::i18n::I18nBabelFish,
+    ExecutorPtr,
+};
+
+const SCREEN_SIZE: [f32; 2] = [800., 600.];
+
+/// How often settle() checks for new draws
+const SETTLE_POLL_MS: u64 = 10;
+/// The UI counts as idle after this many polls without any draws
+const SETTLE_QUIET_POLLS: usize = 10;
+/// Give up on UIs which never go idle, such as a blinking cursor
+const SETTLE_MAX_POLLS: usize = 500;
+
+type MethodChannel = (RenderApi, Receiver<(EpochIndex, GraphicsMethod)>);
+
+/// Glyph atlas pages are global and hold on to the RenderApi which created
+/// them, so all harnesses share one render channel. They run one at a time
+/// so their draw calls don't get mixed up.
+static RENDER_CHANNEL: LazyLock<MethodChannel> = LazyLock::new(|| {
+    let (method_send, method_recv) = async_channel::unbounded();
+    (RenderApi::new(method_send), method_recv)
+});
+static HARNESS_LOCK: Mutex<()> = Mutex::new(());
+static INIT_TXT_CTX: Once = Once::new();
+
+pub struct Harness {
+    pub ex: ExecutorPtr,
+    pub render_api: RenderApi,
+    pub event_pub: GraphicsEventPublisherPtr,
+    pub sg_root: SceneNodePtr,
+    pub window: SceneNodePtr,
+    /// Fills the window, widgets are added here
+    pub layer: SceneNodePtr,
+    pub window_scale: PropertyFloat32,
+    pub backend: HeadlessBackend,
+
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl Harness {
+    /// Must be awaited inside `ex.run()` since the UI tasks are spawned on `ex`
+    pub async fn new(ex: ExecutorPtr) -> Self {
+        // A failed test poisons the lock but leaves nothing broken behind
+        let lock = HARNESS_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        INIT_TXT_CTX.call_once(text2::init_txt_ctx);
+
+        let (render_api, method_recv) = RENDER_CHANNEL.clone();
+        let mut backend = HeadlessBackend::new(method_recv, Dimension::from(SCREEN_SIZE));
+        // Deletes left over from the previous harness
+        backend.drain();
+
+        let atom = &mut PropertyAtomicGuard::none();
+        let sg_root = SceneNode::root();
+
+        let setting_root = SceneNode::new("setting", SceneNodeType::SettingRoot).setup_null();
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let settings = PluginSettings {
+            setting_root: setting_root.clone(),
+            sled_tree: db.open_tree("settings").unwrap(),
+        };
+        settings.add_setting("scale", PropertyValue::Float32(1.));
+        settings.add_setting("debug_safe_area", PropertyValue::Bool(false));
+        settings.add_setting("debug_glyph_atlas", PropertyValue::Bool(false));
+        sg_root.link(setting_root.clone());
+
+        let window_scale = PropertyFloat32::wrap(
+            &setting_root.lookup_node("/scale").unwrap(),
+            Role::Internal,
+            "value",
+            0,
+        )
+        .unwrap();
+
+        let mut window = create_window("window");
+        let mut prop = Property::new("locale", PropertyType::Str, PropertySubType::Locale);
+        prop.set_defaults_str(vec!["en-US".to_string()]).unwrap();
+        window.add_property(prop).unwrap();
+        let prop = window.get_property("screen_size").unwrap();
+        prop.set_f32(atom, Role::App, 0, SCREEN_SIZE[0]).unwrap();
+        prop.set_f32(atom, Role::App, 1, SCREEN_SIZE[1]).unwrap();
+
+        let i18n_fish = I18nBabelFish::new(String::new(), "en-US");
+        let window = window
+            .setup(|me| Window::new(me, render_api.clone(), i18n_fish, setting_root.clone()))
+            .await;
+        sg_root.link(window.clone());
+
+        let layer = create_layer("view");
+        let prop = layer.get_property("rect").unwrap();
+        prop.set_f32(atom, Role::App, 0, 0.).unwrap();
+        prop.set_f32(atom, Role::App, 1, 0.).unwrap();
+        prop.set_expr(atom, Role::App, 2, expr::load_var("w")).unwrap();
+        prop.set_expr(atom, Role::App, 3, expr::load_var("h")).unwrap();
+        layer.set_property_bool(atom, Role::App, "is_visible", true).unwrap();
+        let layer = layer.setup(|me| Layer::new(me, render_api.clone())).await;
+        window.link(layer.clone());
+
+        Self {
+            ex,
+            render_api,
+            event_pub: GraphicsEventPublisher::new(),
+            sg_root,
+            window,
+            layer,
+            window_scale,
+            backend,
+            _lock: lock,
+        }
+    }
+
+    fn win(&self) -> WindowPtr {
+        match self.window.pimpl() {
+            Pimpl::Window(win) => win.clone(),
+            _ => panic!("wrong pimpl"),
+        }
+    }
+
+    /// Focused white text on a transparent background
+    pub async fn add_edit(
+        &self,
+        name: &str,
+        rect: Rectangle,
+        edit_type: BaseEditType,
+    ) -> SceneNodePtr {
+        let atom = &mut PropertyAtomicGuard::none();
+        let node = match edit_type {
+            BaseEditType::SingleLine => create_singleline_edit(name),
+            BaseEditType::MultiLine => {
+                let node = create_multiline_edit(name);
+                let prop = node.get_property("height_range").unwrap();
+                prop.set_f32(atom, Role::App, 0, 0.).unwrap();
+                prop.set_f32(atom, Role::App, 1, rect.h).unwrap();
+                node
+            }
+        };
+        node.set_property_bool(atom, Role::App, "is_active", true).unwrap();
+        node.set_property_bool(atom, Role::App, "is_focused", true).unwrap();
+
+        let prop = node.get_property("rect").unwrap();
+        for (i, val) in [rect.x, rect.y, rect.w, rect.h].into_iter().enumerate() {
+            prop.set_f32(atom, Role::App, i, val).unwrap();
+        }
+        node.set_property_f32(atom, Role::App, "baseline", 20.).unwrap();
+        node.set_property_f32(atom, Role::App, "font_size", 20.).unwrap();
+        node.set_property_f32(atom, Role::App, "cursor_ascent", 20.).unwrap();
+        node.set_property_f32(atom, Role::App, "select_ascent", 20.).unwrap();
+        for color in ["text_color", "text_hi_color", "cursor_color", "hi_bg_color"] {
+            let prop = node.get_property(color).unwrap();
+            for i in 0..4 {
+                prop.set_f32(atom, Role::App, i, 1.).unwrap();
+            }
+        }
+        node.set_property_u32(atom, Role::App, "z_index", 1).unwrap();
+
+        let node = node
+            .setup(|me| {
+                BaseEdit::new(me, self.window_scale.clone(), self.render_api.clone(), edit_type)
+            })
+            .await;
+        self.layer.link(node.clone());
+        node
+    }
+
+    /// The chat history is kept in a temporary db
+    pub async fn add_chatview(&self, name: &str, rect: Rectangle) -> SceneNodePtr {
+        let atom = &mut PropertyAtomicGuard::none();
+        let node = create_chatview(name);
+
+        let prop = node.get_property("rect").unwrap();
+        for (i, val) in [rect.x, rect.y, rect.w, rect.h].into_iter().enumerate() {
+            prop.set_f32(atom, Role::App, i, val).unwrap();
+        }
+        node.set_property_f32(atom, Role::App, "font_size", 20.).unwrap();
+        node.set_property_f32(atom, Role::App, "timestamp_font_size", 12.).unwrap();
+        node.set_property_f32(atom, Role::App, "timestamp_width", 80.).unwrap();
+        node.set_property_f32(atom, Role::App, "line_height", 30.).unwrap();
+        node.set_property_f32(atom, Role::App, "message_spacing", 10.).unwrap();
+        node.set_property_f32(atom, Role::App, "baseline", 20.).unwrap();
+        node.set_property_f32(atom, Role::App, "scroll_start_accel", 15.).unwrap();
+        node.set_property_f32(atom, Role::App, "scroll_resist", 0.9).unwrap();
+        node.set_property_u32(atom, Role::App, "z_index", 1).unwrap();
+        for color in ["timestamp_color", "text_color", "hi_bg_color"] {
+            let prop = node.get_property(color).unwrap();
+            for i in 0..4 {
+                prop.set_f32(atom, Role::App, i, 1.).unwrap();
+            }
+        }
+        let prop = node.get_property("nick_colors").unwrap();
+        for _ in 0..4 {
+            prop.push_f32(atom, Role::App, 1.).unwrap();
+        }
+
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let tree = db.open_tree(name).unwrap();
+        let node = node
+            .setup(|me| {
+                ChatView::new(
+                    me,
+                    tree,
+                    self.window_scale.clone(),
+                    self.render_api.clone(),
+                    TextShaper::new(),
+                )
+            })
+            .await;
+        self.layer.link(node.clone());
+        node
+    }
+
+    /// Same as `App::start()`, call once all widgets are added
+    pub async fn start(&mut self) {
+        let win = self.win();
+        win.init();
+        {
+            let atom = &mut self.render_api.make_guard(gfxtag!("Harness::start"));
+            win.draw(atom).await;
+        }
+        win.start(self.event_pub.clone(), self.ex.clone()).await;
+    }
+
+    /// Wait for the UI to go idle, then capture the frame
+    pub async fn settle(&mut self) -> CapturedFrame {
+        let mut quiet_polls = 0;
+        for _ in 0..SETTLE_MAX_POLLS {
+            msleep(SETTLE_POLL_MS).await;
+            if self.backend.drain() > 0 {
+                quiet_polls = 0;
+                continue
+            }
+            quiet_polls += 1;
+            if quiet_polls == SETTLE_QUIET_POLLS {
+                break
+            }
+        }
+        self.backend.capture()
+    }
+
+    pub fn type_text(&self, text: &str) {
+        for chr in text.chars() {
+            self.event_pub.notify_char(chr, KeyMods::default(), false);
+        }
+    }
+
+    pub fn press_key(&self, key: KeyCode, mods: KeyMods) {
+        self.event_pub.notify_key_down(key, mods, false);
+        self.event_pub.notify_key_up(key, mods);
+    }
+
+    pub fn click(&self, pos: Point) {
+        self.event_pub.notify_mouse_move(pos);
+        self.event_pub.notify_mouse_btn_down(MouseButton::Left, pos);
+        self.event_pub.notify_mouse_btn_up(MouseButton::Left, pos);
+    }
+
+    /// Widgets scroll whatever is under the mouse, so it's moved there first
+    pub async fn scroll(&mut self, pos: Point, delta_y: f32) -> CapturedFrame {
+        self.event_pub.notify_mouse_move(pos);
+        self.settle().await;
+        self.event_pub.notify_mouse_wheel(Point::new(0., delta_y));
+        self.settle().await
+    }
+
+    pub fn touch(&self, phase: TouchPhase, pos: Point) {
+        self.event_pub.notify_touch(phase, 0, pos);
+    }
+
+    pub fn resize(&mut self, size: Dimension) {
+        self.backend.set_screen_size(size);
+        self.event_pub.notify_resize(size);
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        self.win().stop();
+        self.backend.clear_draw_calls();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::chatview::MessageId;
+    use std::sync::Arc;
+
+    fn run<F: std::future::Future<Output = ()>>(test: impl FnOnce(ExecutorPtr) -> F) {
+        let ex = Arc::new(smol::Executor::new());
+        smol::block_on(ex.run(test(ex.clone())));
+    }
+
+    #[test]
+    fn edit_typing() {
+        run(|ex| async move {
+            let mut harness = Harness::new(ex).await;
+            let rect = Rectangle::new(10., 10., 400., 40.);
+            let edit = harness.add_edit("edit", rect, BaseEditType::SingleLine).await;
+            harness.start().await;
+            let frame = harness.settle().await;
+            assert!(frame.bounds("chatedit_txt_mesh").is_none());
+
+            harness.type_text("hello");
+            let frame = harness.settle().await;
+            assert_eq!(edit.get_property_str("text").unwrap(), "hello");
+            let hello = frame.bounds("chatedit_txt_mesh").unwrap();
+            assert!(rect.includes(&hello));
+
+            harness.press_key(KeyCode::Backspace, KeyMods::default());
+            let frame = harness.settle().await;
+            assert_eq!(edit.get_property_str("text").unwrap(), "hell");
+            let hell = frame.bounds("chatedit_txt_mesh").unwrap();
+            assert!(hell.w < hello.w);
+        });
+    }
+
+    #[test]
+    fn chatview_scroll() {
+        run(|ex| async move {
+            let mut harness = Harness::new(ex).await;
+            let rect = Rectangle::new(0., 0., 600., 300.);
+            let node = harness.add_chatview("chat", rect).await;
+            harness.start().await;
+
+            let Pimpl::ChatView(chatview) = node.pimpl() else { panic!("wrong pimpl") };
+            for i in 0..50 {
+                let msg_id = MessageId([i; 32]);
+                let text = format!("message {i}");
+                chatview.handle_insert_line(i as u64, msg_id, "anon".to_string(), text).await;
+            }
+            let frame = harness.settle().await;
+            assert_eq!(node.get_property_f32("scroll").unwrap(), 0.);
+            let before = frame.bounds("chatview_privmsg").unwrap();
+
+            let frame = harness.scroll(Point::new(300., 150.), 3.).await;
+            assert!(node.get_property_f32("scroll").unwrap() > 0.);
+            // Older messages move into view from the top
+            let after = frame.bounds("chatview_privmsg").unwrap();
+            assert!(after.y <= before.y);
+            assert!(frame.with_tag("chatview_privmsg").any(|mesh| mesh.visible_rect().is_some()));
+        });
+    }
+}
@@ -29,7 +29,10 @@ use crate::{
     error::Error,
     gfx::{gfxtag, EpochIndex, GraphicsEventPublisherPtr, RenderApi},
     plugin::PluginSettings,
-    prop::{Property, PropertyAtomicGuard, PropertySubType, PropertyType, PropertyValue, Role},
+    prop::{
+        Property, PropertyAtomicGuard, PropertySubType, PropertyType, PropertyValue, Role,
+        UndoHistory, UndoHistoryPtr,
+    },
     scene::{Pimpl, SceneNode, SceneNodePtr, SceneNodeType},
     text::TextShaperPtr,
     ui::{chatview, Window},
@@ -37,11 +40,18 @@ use crate::{
     ExecutorPtr,
 };
 
+#[cfg(test)]
+pub mod headless;
 pub mod locale;
 use locale::read_locale_ftl;
 mod node;
+use node::create_window;
+pub mod prefs;
+use prefs::{get_prefs_path, Prefs, PrefsPtr};
 mod schema;
 use schema::get_settingsdb_path;
+pub mod theme;
+use theme::{Theme, ThemePtr};
 
 macro_rules! d { ($($arg:tt)*) => { debug!(target: "app", $($arg)*); } }
 macro_rules! t { ($($arg:tt)*) => { trace!(target: "app", $($arg)*); } }
@@ -61,6 +71,9 @@ pub struct App {
     pub text_shaper: TextShaperPtr,
     pub tasks: SyncMutex<Vec<Task<()>>>,
     pub ex: ExecutorPtr,
+    pub undo_history: UndoHistoryPtr,
+    pub prefs: PrefsPtr,
+    pub theme: ThemePtr,
 }
 
 impl App {
@@ -70,7 +83,17 @@ impl App {
         text_shaper: TextShaperPtr,
         ex: ExecutorPtr,
     ) -> Arc<Self> {
-        Arc::new(Self { sg_root, ex, render_api, text_shaper, tasks: SyncMutex::new(vec![]) })
+        let theme = Theme::new(render_api.clone(), ex.clone());
+        Arc::new(Self {
+            sg_root,
+            ex,
+            render_api,
+            text_shaper,
+            tasks: SyncMutex::new(vec![]),
+            undo_history: UndoHistory::new(),
+            prefs: Prefs::load(get_prefs_path()),
+            theme,
+        })
     }
 
     /// Does not require miniquad to be init. Created the scene graph tree / schema and all
@@ -87,14 +110,10 @@ impl App {
             }
         };
 
-        let mut window = SceneNode::new("window", SceneNodeType::Window);
+        let mut window = create_window("window");
 
         let i18n_fish = self.setup_locale(&mut window);
 
-        let mut prop = Property::new("screen_size", PropertyType::Float32, PropertySubType::Pixel);
-        prop.set_array_len(2);
-        window.add_property(prop).unwrap();
-
         let setting_root = SceneNode::new("setting", SceneNodeType::SettingRoot);
         let setting_root = setting_root.setup_null();
         let settings_tree = db.open_tree("settings").unwrap();
@@ -111,19 +130,16 @@ impl App {
         d!("Setting window_scale to {window_scale}");
 
         settings.add_setting("scale", PropertyValue::Float32(window_scale));
-        //settings.load_settings();
-
-        // Save app settings in sled when they change
-        for setting_node in settings.setting_root.get_children().iter() {
-            let setting_sub = setting_node.get_property("value").unwrap().subscribe_modify();
-            let settings2 = settings.clone();
-            let setting_task = self.ex.spawn(async move {
-                while let Ok(_) = setting_sub.receive().await {
-                    settings2.save_settings();
-                }
-            });
-            self.tasks.lock().unwrap().push(setting_task);
-        }
+        settings.add_setting("debug_safe_area", PropertyValue::Bool(false));
+        settings.add_setting("debug_glyph_atlas", PropertyValue::Bool(false));
+        let theme_setting =
+            settings.add_setting("theme", PropertyValue::Str("dark".to_string())).unwrap();
+
+        // Stored values must be in place before the schema reads them
+        self.prefs.attach(&self.ex, &setting_root);
+
+        // Either "dark" or "light"
+        self.theme.start(theme_setting.get_property("value").unwrap());
 
         let window = window
             .setup(|me| {
@@ -133,6 +149,7 @@ impl App {
 
         self.sg_root.link(window.clone());
         self.sg_root.link(setting_root.clone());
+        self.sg_root.link(self.theme.root.clone());
 
         schema::test::make(&self, window.clone(), &i18n_fish).await;
 
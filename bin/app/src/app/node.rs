@@ -23,6 +23,53 @@ use crate::{
 
 macro_rules! t { ($($arg:tt)*) => { trace!(target: "app::node", $($arg)*); } }
 
+pub fn create_window(name: &str) -> SceneNode {
+    t!("create_window({name})");
+    let mut node = SceneNode::new(name, SceneNodeType::Window);
+
+    let mut prop = Property::new("screen_size", PropertyType::Float32, PropertySubType::Pixel);
+    prop.set_array_len(2);
+    node.add_property(prop).unwrap();
+
+    // Margins as [left, top, right, bottom] which content should avoid
+    // such as notches and the navigation bar. Set by the platform layer.
+    let mut prop = Property::new("safe_area_insets", PropertyType::Float32, PropertySubType::Pixel);
+    prop.set_array_len(4);
+    node.add_property(prop).unwrap();
+
+    // Text cursor of the focused editbox as [x, y, w, h] in screen pixels.
+    // The platform layer reads this to place the IME candidate window.
+    let mut prop = Property::new("ime_cursor_area", PropertyType::Float32, PropertySubType::Pixel);
+    prop.set_array_len(4);
+    node.add_property(prop).unwrap();
+
+    // Tween a Float32 property element of any node below the window
+    node.add_method(
+        "animate",
+        vec![
+            ("node", "Path of the node relative to the window", CallArgType::Str),
+            ("prop", "Property name", CallArgType::Str),
+            ("index", "Property index", CallArgType::Uint32),
+            ("to", "Target value", CallArgType::Float32),
+            ("duration", "Duration in milliseconds", CallArgType::Uint32),
+            ("easing", "linear, ease_in, ease_out, ease_in_out or back", CallArgType::Str),
+        ],
+        None,
+    )
+    .unwrap();
+    node.add_method(
+        "cancel_animation",
+        vec![
+            ("node", "Path of the node relative to the window", CallArgType::Str),
+            ("prop", "Property name", CallArgType::Str),
+        ],
+        None,
+    )
+    .unwrap();
+
+    node
+}
+
 pub fn create_layer(name: &str) -> SceneNode {
     t!("create_layer({name})");
     let mut node = SceneNode::new(name, SceneNodeType::Layer);
@@ -43,6 +90,147 @@ pub fn create_layer(name: &str) -> SceneNode {
     node
 }
 
+pub fn create_flexbox(name: &str) -> SceneNode {
+    t!("create_flexbox({name})");
+    let mut node = SceneNode::new(name, SceneNodeType::FlexBox);
+    let mut prop = Property::new("is_visible", PropertyType::Bool, PropertySubType::Null);
+    prop.set_defaults_bool(vec![true]).unwrap();
+    node.add_property(prop).unwrap();
+
+    let mut prop = Property::new("rect", PropertyType::Float32, PropertySubType::Pixel);
+    prop.set_array_len(4);
+    prop.allow_exprs();
+    node.add_property(prop).unwrap();
+
+    let prop = Property::new("z_index", PropertyType::Uint32, PropertySubType::Null);
+    node.add_property(prop).unwrap();
+
+    let prop = Property::new("priority", PropertyType::Uint32, PropertySubType::Null);
+    node.add_property(prop).unwrap();
+
+    // 0 = row, 1 = column
+    let mut prop = Property::new("direction", PropertyType::Uint32, PropertySubType::Null);
+    prop.set_range_u32(0, 1);
+    node.add_property(prop).unwrap();
+
+    // 0 = start, 1 = center, 2 = end, 3 = space between
+    let mut prop = Property::new("justify", PropertyType::Uint32, PropertySubType::Null);
+    prop.set_range_u32(0, 3);
+    node.add_property(prop).unwrap();
+
+    // 0 = start, 1 = center, 2 = end, 3 = stretch
+    let mut prop = Property::new("align", PropertyType::Uint32, PropertySubType::Null);
+    prop.set_range_u32(0, 3);
+    prop.set_defaults_u32(vec![3]).unwrap();
+    node.add_property(prop).unwrap();
+
+    let prop = Property::new("gap", PropertyType::Float32, PropertySubType::Pixel);
+    node.add_property(prop).unwrap();
+
+    // top, right, bottom, left
+    let mut prop = Property::new("padding", PropertyType::Float32, PropertySubType::Pixel);
+    prop.set_array_len(4);
+    node.add_property(prop).unwrap();
+
+    node
+}
+
+/// Add the properties a `FlexBox` reads from its children. Optional,
+/// children without them get an equal share of the space.
+pub fn add_flex_item_props(node: &mut SceneNode) {
+    let prop = Property::new("flex_basis", PropertyType::Float32, PropertySubType::Pixel);
+    node.add_property(prop).unwrap();
+
+    let mut prop = Property::new("flex_grow", PropertyType::Float32, PropertySubType::Null);
+    prop.set_defaults_f32(vec![1.]).unwrap();
+    node.add_property(prop).unwrap();
+
+    let mut prop = Property::new("flex_shrink", PropertyType::Float32, PropertySubType::Null);
+    prop.set_defaults_f32(vec![1.]).unwrap();
+    node.add_property(prop).unwrap();
+
+    let prop = Property::new("flex_cross", PropertyType::Float32, PropertySubType::Pixel);
+    node.add_property(prop).unwrap();
+
+    // top, right, bottom, left
+    let mut prop = Property::new("flex_margin", PropertyType::Float32, PropertySubType::Pixel);
+    prop.set_array_len(4);
+    node.add_property(prop).unwrap();
+}
+
+pub fn create_listview(name: &str) -> SceneNode {
+    t!("create_listview({name})");
+    let mut node = SceneNode::new(name, SceneNodeType::ListView);
+
+    let mut prop = Property::new("is_visible", PropertyType::Bool, PropertySubType::Null);
+    prop.set_defaults_bool(vec![true]).unwrap();
+    node.add_property(prop).unwrap();
+
+    let mut prop = Property::new("rect", PropertyType::Float32, PropertySubType::Pixel);
+    prop.set_array_len(4);
+    prop.allow_exprs();
+    node.add_property(prop).unwrap();
+
+    let prop = Property::new("z_index", PropertyType::Uint32, PropertySubType::Null);
+    node.add_property(prop).unwrap();
+
+    let prop = Property::new("priority", PropertyType::Uint32, PropertySubType::Null);
+    node.add_property(prop).unwrap();
+
+    let mut prop = Property::new("scroll", PropertyType::Float32, PropertySubType::Null);
+    prop.set_ui_text("Scroll", "Scroll down from the top");
+    prop.set_range_f32(0., f32::MAX);
+    node.add_property(prop).unwrap();
+
+    let mut prop = Property::new("row_height", PropertyType::Float32, PropertySubType::Pixel);
+    prop.set_range_f32(0., f32::MAX);
+    node.add_property(prop).unwrap();
+
+    let mut prop =
+        Property::new("scroll_start_accel", PropertyType::Float32, PropertySubType::Pixel);
+    prop.set_ui_text("Scroll Start Acceleration", "Initial acceleration when scrolling");
+    prop.set_defaults_f32(vec![4.]).unwrap();
+    node.add_property(prop).unwrap();
+
+    let mut prop = Property::new("scroll_resist", PropertyType::Float32, PropertySubType::Pixel);
+    prop.set_ui_text("Scroll Resistance", "How quickly scrolling speed is dampened");
+    prop.set_range_f32(0., 1.);
+    prop.set_defaults_f32(vec![0.9]).unwrap();
+    node.add_property(prop).unwrap();
+
+    let mut prop = Property::new("scrollbar_width", PropertyType::Float32, PropertySubType::Pixel);
+    prop.set_ui_text("Scrollbar Width", "Set to 0 to hide the scrollbar");
+    prop.set_defaults_f32(vec![4.]).unwrap();
+    node.add_property(prop).unwrap();
+
+    let mut prop = Property::new("scrollbar_color", PropertyType::Float32, PropertySubType::Color);
+    prop.set_array_len(4);
+    prop.set_range_f32(0., 1.);
+    prop.set_defaults_f32(vec![1., 1., 1., 0.4]).unwrap();
+    node.add_property(prop).unwrap();
+
+    node.add_method(
+        "insert_item",
+        vec![("index", "Index, past the end to append", CallArgType::Uint32)],
+        None,
+    )
+    .unwrap();
+    node.add_method(
+        "set_item_field",
+        vec![
+            ("index", "Index", CallArgType::Uint32),
+            ("path", "Path of the node inside the row", CallArgType::Str),
+            ("text", "Text", CallArgType::Str),
+        ],
+        None,
+    )
+    .unwrap();
+    node.add_method("remove_item", vec![("index", "Index", CallArgType::Uint32)], None).unwrap();
+    node.add_method("clear_items", vec![], None).unwrap();
+
+    node
+}
+
 pub fn create_vector_art(name: &str) -> SceneNode {
     t!("create_vector_art({name})");
     let mut node = SceneNode::new(name, SceneNodeType::VectorArt);
@@ -65,6 +253,43 @@ pub fn create_vector_art(name: &str) -> SceneNode {
     node
 }
 
+pub fn create_qrcode(name: &str) -> SceneNode {
+    t!("create_qrcode({name})");
+    let mut node = SceneNode::new(name, SceneNodeType::QrCode);
+
+    let mut prop = Property::new("is_visible", PropertyType::Bool, PropertySubType::Null);
+    prop.set_defaults_bool(vec![true]).unwrap();
+    node.add_property(prop).unwrap();
+
+    let mut prop = Property::new("rect", PropertyType::Float32, PropertySubType::Pixel);
+    prop.set_array_len(4);
+    prop.allow_exprs();
+    node.add_property(prop).unwrap();
+
+    let prop = Property::new("data", PropertyType::Str, PropertySubType::Null);
+    node.add_property(prop).unwrap();
+
+    let mut prop = Property::new("fg_color", PropertyType::Float32, PropertySubType::Color);
+    prop.set_array_len(4);
+    prop.set_range_f32(0., 1.);
+    prop.set_defaults_f32(vec![0., 0., 0., 1.]).unwrap();
+    node.add_property(prop).unwrap();
+
+    let mut prop = Property::new("bg_color", PropertyType::Float32, PropertySubType::Color);
+    prop.set_array_len(4);
+    prop.set_range_f32(0., 1.);
+    prop.set_defaults_f32(vec![1., 1., 1., 1.]).unwrap();
+    node.add_property(prop).unwrap();
+
+    let prop = Property::new("z_index", PropertyType::Uint32, PropertySubType::Null);
+    node.add_property(prop).unwrap();
+
+    let prop = Property::new("priority", PropertyType::Uint32, PropertySubType::Null);
+    node.add_property(prop).unwrap();
+
+    node
+}
+
 pub fn create_button(name: &str) -> SceneNode {
     t!("create_button({name})");
     let mut node = SceneNode::new(name, SceneNodeType::Button);
@@ -105,6 +330,25 @@ pub fn create_shortcut(name: &str) -> SceneNode {
     node
 }
 
+pub fn create_palette_input(name: &str) -> SceneNode {
+    t!("create_palette_input({name})");
+    let mut node = SceneNode::new(name, SceneNodeType::PaletteInput);
+
+    let prop = Property::new("query", PropertyType::Str, PropertySubType::Null);
+    node.add_property(prop).unwrap();
+
+    let prop = Property::new("priority", PropertyType::Uint32, PropertySubType::Null);
+    node.add_property(prop).unwrap();
+
+    node.add_signal("query_changed", "Search query was edited", vec![]).unwrap();
+    node.add_signal("select_prev", "Move selection up", vec![]).unwrap();
+    node.add_signal("select_next", "Move selection down", vec![]).unwrap();
+    node.add_signal("activate", "Run the selected item", vec![]).unwrap();
+    node.add_signal("close", "Dismiss the palette", vec![]).unwrap();
+
+    node
+}
+
 #[allow(dead_code)]
 pub fn create_gesture(name: &str) -> SceneNode {
     t!("create_gesture({name})");
@@ -357,6 +601,12 @@ pub fn create_baseedit(name: &str) -> SceneNode {
     node.add_method("focus", vec![], None).unwrap();
     node.add_method("unfocus", vec![], None).unwrap();
 
+    // Clipboard actions, used by the copy/paste menus
+    node.add_method("copy", vec![], None).unwrap();
+    node.add_method("cut", vec![], None).unwrap();
+    node.add_method("paste", vec![], None).unwrap();
+    node.add_method("select_all", vec![], None).unwrap();
+
     node
 }
 
@@ -484,6 +734,22 @@ pub fn create_chatview(name: &str) -> SceneNode {
     )
     .unwrap();
 
+    node.add_method(
+        "export",
+        vec![
+            ("channel", "Channel", CallArgType::Str),
+            ("format", "Format (html or md)", CallArgType::Str),
+            ("from", "From Timestamp", CallArgType::Uint64),
+            ("to", "To Timestamp", CallArgType::Uint64),
+        ],
+        None,
+    )
+    .unwrap();
+
+    // Copy the selected lines to the clipboard
+    node.add_method("copy", vec![], None).unwrap();
+    node.add_method("clear_selection", vec![], None).unwrap();
+
     node
 }
 
@@ -0,0 +1,260 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! User preferences persisted across restarts.
+//!
+//! Properties marked with [`Property::mark_user_pref()`] are saved to a
+//! versioned file in the app datadir whenever they change. The file is
+//! read once at startup and the values are applied to each subtree as it
+//! gets attached, so attach nodes before building anything which depends
+//! on them.
+//!
+//! Entries are keyed by `{node path}:{property name}`. Older files are
+//! upgraded by running the [`MIGRATIONS`] in order.
+//!
+//! [`Property::mark_user_pref()`]: crate::prop::Property::mark_user_pref
+
+use darkfi_serial::{deserialize, serialize, SerialDecodable, SerialEncodable};
+use parking_lot::Mutex as SyncMutex;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    prop::{PropertyAtomicGuard, PropertyPtr, PropertyType, PropertyValue, Role},
+    scene::SceneNodePtr,
+    ExecutorPtr,
+};
+
+macro_rules! d { ($($arg:tt)*) => { debug!(target: "app::prefs", $($arg)*); } }
+macro_rules! i { ($($arg:tt)*) => { info!(target: "app::prefs", $($arg)*); } }
+macro_rules! w { ($($arg:tt)*) => { warn!(target: "app::prefs", $($arg)*); } }
+
+#[cfg(target_os = "android")]
+mod paths {
+    use crate::android::get_appdata_path;
+    use std::path::PathBuf;
+
+    pub fn get_prefs_path() -> PathBuf {
+        get_appdata_path().join("prefs")
+    }
+
+    pub fn legacy_window_scale_path() -> PathBuf {
+        get_appdata_path().join("window_scale")
+    }
+    pub fn legacy_nick_path() -> PathBuf {
+        get_appdata_path().join("/nick.txt")
+    }
+}
+
+#[cfg(not(target_os = "android"))]
+mod paths {
+    use std::path::PathBuf;
+
+    pub fn get_prefs_path() -> PathBuf {
+        dirs::data_local_dir().unwrap().join("darkfi/app/prefs")
+    }
+
+    pub fn legacy_window_scale_path() -> PathBuf {
+        dirs::cache_dir().unwrap().join("darkfi/app/window_scale")
+    }
+    pub fn legacy_nick_path() -> PathBuf {
+        dirs::cache_dir().unwrap().join("darkfi/app/nick.txt")
+    }
+}
+
+pub use paths::get_prefs_path;
+
+/// Bump this and add a migration when the meaning of stored prefs changes
+const PREFS_VERSION: u32 = 1;
+
+/// `MIGRATIONS[i]` upgrades prefs from version `i` to `i + 1`
+const MIGRATIONS: [fn(&mut PrefsMap); PREFS_VERSION as usize] = [migrate_v0];
+
+#[derive(Clone, Debug, PartialEq, SerialEncodable, SerialDecodable)]
+enum PrefValue {
+    Bool(bool),
+    Uint32(u32),
+    Float32(f32),
+    Str(String),
+}
+
+type PrefsMap = BTreeMap<String, Vec<PrefValue>>;
+
+#[derive(SerialEncodable, SerialDecodable)]
+struct PrefsFile {
+    version: u32,
+    entries: Vec<(String, Vec<PrefValue>)>,
+}
+
+/// Version 0 kept the scale and nick in their own files
+fn migrate_v0(prefs: &mut PrefsMap) {
+    if let Ok(data) = std::fs::read(paths::legacy_window_scale_path()) {
+        if let Ok(scale) = deserialize::<f32>(&data) {
+            prefs.insert("/setting/scale:value".to_string(), vec![PrefValue::Float32(scale)]);
+        }
+    }
+    if let Ok(nick) = std::fs::read_to_string(paths::legacy_nick_path()) {
+        prefs.insert("/plugin/darkirc:nick".to_string(), vec![PrefValue::Str(nick)]);
+    }
+    let _ = std::fs::remove_file(paths::legacy_window_scale_path());
+    let _ = std::fs::remove_file(paths::legacy_nick_path());
+}
+
+pub type PrefsPtr = Arc<Prefs>;
+
+pub struct Prefs {
+    path: PathBuf,
+    values: SyncMutex<PrefsMap>,
+    tasks: SyncMutex<Vec<smol::Task<()>>>,
+}
+
+impl Prefs {
+    /// Read the prefs file, upgrading it if it was written by an older version
+    pub fn load(path: PathBuf) -> PrefsPtr {
+        let (version, mut values) = match std::fs::read(&path) {
+            Ok(data) => match deserialize::<PrefsFile>(&data) {
+                Ok(file) => (file.version, file.entries.into_iter().collect()),
+                Err(e) => {
+                    w!("Discarding unreadable prefs file {path:?}: {e}");
+                    (PREFS_VERSION, PrefsMap::new())
+                }
+            },
+            Err(_) => (0, PrefsMap::new()),
+        };
+
+        let self_ = Arc::new(Self {
+            path,
+            values: SyncMutex::new(PrefsMap::new()),
+            tasks: SyncMutex::new(vec![]),
+        });
+
+        if version > PREFS_VERSION {
+            w!("Prefs file is from a newer version ({version}), starting from defaults");
+            return self_
+        }
+        if version < PREFS_VERSION {
+            i!("Migrating prefs from version {version} to {PREFS_VERSION}");
+            for migrate in &MIGRATIONS[version as usize..] {
+                migrate(&mut values);
+            }
+        }
+        d!("Loaded {} prefs", values.len());
+        *self_.values.lock() = values;
+        if version < PREFS_VERSION {
+            self_.save();
+        }
+        self_
+    }
+
+    /// Apply the stored values to all marked properties in this subtree,
+    /// and save them again whenever they change.
+    pub fn attach(self: &Arc<Self>, ex: &ExecutorPtr, node: &SceneNodePtr) {
+        let path = node.get_full_path().unwrap();
+        for prop in &node.props {
+            if !prop.is_user_pref {
+                continue
+            }
+            let key = format!("{path}:{}", prop.name);
+            self.apply(&key, prop);
+
+            let sub = prop.subscribe_modify();
+            let prop = prop.clone();
+            let me = Arc::downgrade(self);
+            let task = ex.spawn(async move {
+                while sub.receive().await.is_ok() {
+                    let Some(self_) = me.upgrade() else { break };
+                    let Some(vals) = read_prop(&prop) else { continue };
+                    self_.values.lock().insert(key.clone(), vals);
+                    self_.save();
+                }
+            });
+            self.tasks.lock().push(task);
+        }
+
+        for child in node.get_children() {
+            self.attach(ex, &child);
+        }
+    }
+
+    fn apply(&self, key: &str, prop: &PropertyPtr) {
+        let Some(vals) = self.values.lock().get(key).cloned() else { return };
+        if vals.len() != prop.get_len() {
+            w!("Ignoring pref {key} with wrong length");
+            return
+        }
+
+        d!("Applying pref {key} = {vals:?}");
+        let atom = &mut PropertyAtomicGuard::none();
+        for (i, val) in vals.into_iter().enumerate() {
+            let res = match (prop.typ, val) {
+                (PropertyType::Bool, PrefValue::Bool(v)) => prop.set_bool(atom, Role::User, i, v),
+                (PropertyType::Uint32, PrefValue::Uint32(v)) => {
+                    prop.set_u32(atom, Role::User, i, v)
+                }
+                (PropertyType::Float32, PrefValue::Float32(v)) => {
+                    prop.set_f32(atom, Role::User, i, v)
+                }
+                (PropertyType::Str, PrefValue::Str(v)) => prop.set_str(atom, Role::User, i, v),
+                (PropertyType::Enum, PrefValue::Str(v)) => prop.set_enum(atom, Role::User, i, v),
+                (_, val) => {
+                    w!("Ignoring pref {key} with wrong type: {val:?}");
+                    return
+                }
+            };
+            if let Err(e) = res {
+                w!("Unable to apply pref {key}: {e}");
+            }
+        }
+    }
+
+    fn save(&self) {
+        let entries = self.values.lock().clone().into_iter().collect();
+        let data = serialize(&PrefsFile { version: PREFS_VERSION, entries });
+        if let Err(e) = write_atomic(&self.path, &data) {
+            w!("Unable to save prefs to {:?}: {e}", self.path);
+        }
+    }
+}
+
+fn read_prop(prop: &PropertyPtr) -> Option<Vec<PrefValue>> {
+    let mut vals = vec![];
+    for i in 0..prop.get_len() {
+        let val = match prop.get_value(i).ok()? {
+            PropertyValue::Bool(v) => PrefValue::Bool(v),
+            PropertyValue::Uint32(v) => PrefValue::Uint32(v),
+            PropertyValue::Float32(v) => PrefValue::Float32(v),
+            PropertyValue::Str(v) | PropertyValue::Enum(v) => PrefValue::Str(v),
+            _ => return None,
+        };
+        vals.push(val);
+    }
+    Some(vals)
+}
+
+/// Avoid leaving a truncated file behind if we get killed mid-write
+fn write_atomic(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let tmp_path = path.with_extension("tmp");
+    std::fs::write(&tmp_path, data)?;
+    std::fs::rename(tmp_path, path)
+}
@@ -177,6 +177,13 @@ fn android_keyboard_height() -> f32 {
     unreachable!()
 }
 
+/// Local midnight of a `YYYY-MM-DD` date, in millis
+fn parse_date(date: &str) -> Option<u64> {
+    let date = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+    let midnight = date.and_hms_opt(0, 0, 0)?.and_local_timezone(chrono::Local).earliest()?;
+    Some(midnight.timestamp_millis() as u64)
+}
+
 pub async fn make(
     app: &App,
     window: SceneNodePtr,
@@ -882,6 +889,69 @@ pub async fn make(
                 return
             }
 
+            // /identity new <label>, /identity use <label>, /identity bind <label>
+            if text.starts_with("/identity") {
+                let mut args = text.split_whitespace().skip(1);
+                let (Some(cmd), Some(label)) = (args.next(), args.next()) else {
+                    warn!(target: "app::chat", "Usage: /identity <new|use|bind> <label>");
+                    return
+                };
+
+                let mut data = vec![];
+                let (method, msg) = match cmd {
+                    "new" => ("create_identity", format!("Created identity <{label}>")),
+                    "use" => ("switch_identity", format!("Now speaking as <{label}>")),
+                    "bind" => {
+                        channel.encode(&mut data).unwrap();
+                        ("bind_identity", format!("Bound <{label}> to this channel"))
+                    }
+                    _ => {
+                        warn!(target: "app::chat", "Unknown /identity command: {cmd}");
+                        return
+                    }
+                };
+                label.encode(&mut data).unwrap();
+                info!(target: "app::chat", "Identity command: {method}({label})");
+                darkirc.call_method(method, data).await.unwrap();
+
+                let id: [u8; 32] = rand::random();
+                let mut data = vec![];
+                unixtime().encode(&mut data).unwrap();
+                id.encode(&mut data).unwrap();
+                "NOTICE".encode(&mut data).unwrap();
+                msg.encode(&mut data).unwrap();
+                chatview_node.call_method("insert_line", data).await.unwrap();
+
+                return
+            }
+
+            // /export <html|md> [from YYYY-MM-DD] [to YYYY-MM-DD]
+            if text.starts_with("/export") {
+                let mut args = text.split_whitespace().skip(1);
+                let format = args.next().unwrap_or("html");
+                // The end date is included
+                let range = (
+                    args.next().map(parse_date).unwrap_or(Some(0)),
+                    args.next()
+                        .map(|d| parse_date(d).map(|t| t + 86_400_000))
+                        .unwrap_or(Some(u64::MAX)),
+                );
+                let (Some(from), Some(to)) = range else {
+                    warn!(target: "app::chat", "Usage: /export <html|md> [from YYYY-MM-DD] [to YYYY-MM-DD]");
+                    return
+                };
+
+                info!(target: "app::chat", "Exporting #{channel} history as {format}");
+                let mut data = vec![];
+                channel.encode(&mut data).unwrap();
+                format.encode(&mut data).unwrap();
+                from.encode(&mut data).unwrap();
+                to.encode(&mut data).unwrap();
+                chatview_node.call_method("export", data).await.unwrap();
+
+                return
+            }
+
             // Limit line length
             if text.len() > 300 {
                 text.truncate(300);
@@ -21,9 +21,9 @@ use crate::{
         node::{create_button, create_layer, create_shortcut, create_text, create_vector_art},
         App,
     },
-    expr,
+    expr::{self, Compiler},
     gfx::gfxtag,
-    prop::{PropertyAtomicGuard, PropertyBool, PropertyFloat32, Role},
+    prop::{PropertyAtomicGuard, PropertyBool, PropertyFloat32, PropertyStr, Role},
     scene::{SceneNodePtr, Slot},
     ui::{Button, Layer, ShapeVertex, Shortcut, Text, VectorArt, VectorShape},
     util::i18n::I18nBabelFish,
@@ -37,6 +37,7 @@ mod android_ui_consts {
     pub const CHANNEL_LABEL_Y: f32 = 35.;
     pub const CHANNEL_LABEL_LINESPACE: f32 = 140.;
     pub const CHANNEL_LABEL_FONTSIZE: f32 = 44.;
+    pub const BADGE_X: f32 = 140.;
 }
 
 #[cfg(target_os = "android")]
@@ -58,10 +59,40 @@ mod ui_consts {
     pub const CHANNEL_LABEL_Y: f32 = 14.;
     pub const CHANNEL_LABEL_LINESPACE: f32 = 60.;
     pub const CHANNEL_LABEL_FONTSIZE: f32 = 22.;
+    pub const BADGE_X: f32 = 70.;
 }
 
 use ui_consts::*;
 
+/// Unread counter at the right of an entry, updated by the notify layer
+async fn add_badge(
+    app: &App,
+    layer_node: &SceneNodePtr,
+    name: &str,
+    y: f32,
+    window_scale: &PropertyFloat32,
+    i18n_fish: &I18nBabelFish,
+) -> PropertyStr {
+    let atom = &mut PropertyAtomicGuard::none();
+
+    let node = create_text(&format!("{name}_badge"));
+    let prop = node.get_property("rect").unwrap();
+    let code = Compiler::new().compile(format!("w - {BADGE_X}")).unwrap();
+    prop.set_expr(atom, Role::App, 0, code).unwrap();
+    prop.set_f32(atom, Role::App, 1, y + CHANNEL_LABEL_Y).unwrap();
+    prop.set_f32(atom, Role::App, 2, BADGE_X).unwrap();
+    prop.set_f32(atom, Role::App, 3, 200.).unwrap();
+    node.set_property_f32(atom, Role::App, "font_size", CHANNEL_LABEL_FONTSIZE).unwrap();
+    app.theme.bind_color(node.get_property("text_color").unwrap(), "badge");
+    node.set_property_u32(atom, Role::App, "z_index", 3).unwrap();
+
+    let node = node
+        .setup(|me| Text::new(me, window_scale.clone(), app.render_api.clone(), i18n_fish.clone()))
+        .await;
+    layer_node.link(node.clone());
+    PropertyStr::wrap(&node, Role::App, "text", 0).unwrap()
+}
+
 pub async fn make(app: &App, window: SceneNodePtr, i18n_fish: &I18nBabelFish) {
     let window_scale = PropertyFloat32::wrap(
         &app.sg_root.lookup_node("/setting/scale").unwrap(),
@@ -237,6 +268,8 @@ pub async fn make(app: &App, window: SceneNodePtr, i18n_fish: &I18nBabelFish) {
             .await;
         layer_node.link(node);
 
+        let badge = add_badge(app, &layer_node, channel, channel_y, &window_scale, i18n_fish).await;
+
         // Create the button
         let node = create_button(&(channel.to_string() + "_channel_btn"));
         node.set_property_bool(atom, Role::App, "is_active", true).unwrap();
@@ -261,6 +294,7 @@ pub async fn make(app: &App, window: SceneNodePtr, i18n_fish: &I18nBabelFish) {
             chatview_is_visible.set(atom, true);
             menu_is_visible.set(atom, false);
             set_normal_color(atom);
+            badge.set(atom, "");
         };
 
         let select_channel2 = select_channel.clone();
@@ -298,4 +332,91 @@ pub async fn make(app: &App, window: SceneNodePtr, i18n_fish: &I18nBabelFish) {
 
         channel_y += CHANNEL_LABEL_LINESPACE;
     }
+
+    // Wallet entry below the channels
+    let node = create_vector_art("wallet_label_bg");
+    let prop = node.get_property("rect").unwrap();
+    prop.set_f32(atom, Role::App, 0, 0.).unwrap();
+    prop.set_f32(atom, Role::App, 1, channel_y).unwrap();
+    prop.set_expr(atom, Role::App, 2, expr::load_var("w")).unwrap();
+    prop.set_f32(atom, Role::App, 3, CHANNEL_LABEL_LINESPACE).unwrap();
+    node.set_property_u32(atom, Role::App, "z_index", 0).unwrap();
+
+    let mut shape = VectorShape::new();
+    let (bg_color, sep_color) = match COLOR_SCHEME {
+        ColorScheme::DarkMode => ([0.05, 0.05, 0.05, 1.], [0.4, 0.4, 0.4, 1.]),
+        ColorScheme::PaperLight => ([1., 1., 1., 1.], [0.2, 0.2, 0.2, 1.]),
+    };
+    shape.add_filled_box(
+        expr::const_f32(0.),
+        expr::const_f32(0.),
+        expr::load_var("w"),
+        expr::const_f32(CHANNEL_LABEL_LINESPACE),
+        bg_color,
+    );
+    shape.add_filled_box(
+        expr::const_f32(0.),
+        expr::const_f32(CHANNEL_LABEL_LINESPACE - 1.),
+        expr::load_var("w"),
+        expr::const_f32(CHANNEL_LABEL_LINESPACE),
+        sep_color,
+    );
+    let node = node.setup(|me| VectorArt::new(me, shape, app.render_api.clone())).await;
+    layer_node.link(node);
+
+    let node = create_text("wallet_label");
+    let prop = node.get_property("rect").unwrap();
+    prop.set_f32(atom, Role::App, 0, CHANNEL_LABEL_X).unwrap();
+    prop.set_f32(atom, Role::App, 1, channel_y + CHANNEL_LABEL_Y).unwrap();
+    prop.set_f32(atom, Role::App, 2, 1000.).unwrap();
+    prop.set_f32(atom, Role::App, 3, 200.).unwrap();
+    node.set_property_u32(atom, Role::App, "z_index", 3).unwrap();
+    node.set_property_f32(atom, Role::App, "font_size", CHANNEL_LABEL_FONTSIZE).unwrap();
+    node.set_property_str(atom, Role::App, "text", "Wallet").unwrap();
+    let prop = node.get_property("text_color").unwrap();
+    if COLOR_SCHEME == ColorScheme::DarkMode {
+        prop.set_f32(atom, Role::App, 0, 0.65).unwrap();
+        prop.set_f32(atom, Role::App, 1, 0.87).unwrap();
+        prop.set_f32(atom, Role::App, 2, 0.83).unwrap();
+        prop.set_f32(atom, Role::App, 3, 1.).unwrap();
+    } else if COLOR_SCHEME == ColorScheme::PaperLight {
+        prop.set_f32(atom, Role::App, 0, 0.).unwrap();
+        prop.set_f32(atom, Role::App, 1, 0.).unwrap();
+        prop.set_f32(atom, Role::App, 2, 0.).unwrap();
+        prop.set_f32(atom, Role::App, 3, 1.).unwrap();
+    }
+    let node = node
+        .setup(|me| Text::new(me, window_scale.clone(), app.render_api.clone(), i18n_fish.clone()))
+        .await;
+    layer_node.link(node);
+
+    let badge = add_badge(app, &layer_node, "wallet", channel_y, &window_scale, i18n_fish).await;
+
+    let node = create_button("wallet_btn");
+    node.set_property_bool(atom, Role::App, "is_active", true).unwrap();
+    let prop = node.get_property("rect").unwrap();
+    prop.set_f32(atom, Role::App, 0, 0.).unwrap();
+    prop.set_f32(atom, Role::App, 1, channel_y).unwrap();
+    prop.set_expr(atom, Role::App, 2, expr::load_var("w")).unwrap();
+    prop.set_f32(atom, Role::App, 3, CHANNEL_LABEL_LINESPACE).unwrap();
+
+    let wallet_node = app.sg_root.lookup_node("/window/wallet_layer").unwrap();
+    let wallet_is_visible = PropertyBool::wrap(&wallet_node, Role::App, "is_visible", 0).unwrap();
+    let menu_is_visible = PropertyBool::wrap(&layer_node, Role::App, "is_visible", 0).unwrap();
+    let render_api = app.render_api.clone();
+    let (slot, recvr) = Slot::new("wallet_clicked");
+    node.register("click", slot).unwrap();
+    let listen_click = app.ex.spawn(async move {
+        while let Ok(_) = recvr.recv().await {
+            let atom = &mut render_api.make_guard(gfxtag!("wallet_clicked"));
+            info!(target: "app::menu", "clicked: wallet!");
+            wallet_is_visible.set(atom, true);
+            menu_is_visible.set(atom, false);
+            badge.set(atom, "");
+        }
+    });
+    app.tasks.lock().unwrap().push(listen_click);
+
+    let node = node.setup(|me| Button::new(me)).await;
+    layer_node.link(node);
 }
@@ -16,7 +16,6 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use darkfi_serial::Encodable;
 use sled_overlay::sled;
 use std::fs::File;
 
@@ -36,6 +35,9 @@ use crate::{
 
 mod chat;
 mod menu;
+mod notify;
+mod palette;
+mod wallet;
 //mod settings;
 pub mod test;
 
@@ -67,10 +69,6 @@ mod ui_consts {
         get_appdata_path().join("first_time")
     }
 
-    pub fn get_window_scale_filename() -> PathBuf {
-        get_appdata_path().join("window_scale")
-    }
-
     pub fn get_settingsdb_path() -> PathBuf {
         get_appdata_path().join("settings")
     }
@@ -91,10 +89,6 @@ mod desktop_paths {
         dirs::cache_dir().unwrap().join("darkfi/app/first_time")
     }
 
-    pub fn get_window_scale_filename() -> PathBuf {
-        dirs::cache_dir().unwrap().join("darkfi/app/window_scale")
-    }
-
     pub fn get_settingsdb_path() -> PathBuf {
         dirs::cache_dir().unwrap().join("darkfi/app/settings")
     }
@@ -148,14 +142,6 @@ pub async fn make(app: &App, window: SceneNodePtr, i18n_fish: &I18nBabelFish) {
         while let Ok(_) = recvr.recv().await {
             let scale = 0.9 * window_scale2.get_property_f32("value").unwrap();
 
-            let filename = get_window_scale_filename();
-            if let Some(parent) = filename.parent() {
-                let _ = std::fs::create_dir_all(parent);
-            }
-            if let Ok(mut file) = File::create(filename) {
-                scale.encode(&mut file).unwrap();
-            }
-
             let atom = &mut render_api.make_guard(gfxtag!("zoom_out shortcut"));
             window_scale2.set_property_f32(atom, Role::User, "value", scale).unwrap();
         }
@@ -176,14 +162,6 @@ pub async fn make(app: &App, window: SceneNodePtr, i18n_fish: &I18nBabelFish) {
         while let Ok(_) = recvr.recv().await {
             let scale = 1.1 * window_scale2.get_property_f32("value").unwrap();
 
-            let filename = get_window_scale_filename();
-            if let Some(parent) = filename.parent() {
-                let _ = std::fs::create_dir_all(parent);
-            }
-            if let Ok(mut file) = File::create(filename) {
-                scale.encode(&mut file).unwrap();
-            }
-
             let atom = &mut render_api.make_guard(gfxtag!("zoom_in shortcut"));
             window_scale2.set_property_f32(atom, Role::User, "value", scale).unwrap();
         }
@@ -192,6 +170,40 @@ pub async fn make(app: &App, window: SceneNodePtr, i18n_fish: &I18nBabelFish) {
     let node = node.setup(|me| Shortcut::new(me)).await;
     window.link(node);
 
+    let node = create_shortcut("undo_shortcut");
+    node.set_property_str(atom, Role::App, "key", "ctrl+z").unwrap();
+    node.set_property_u32(atom, Role::App, "priority", 10).unwrap();
+    let (slot, recvr) = Slot::new("undo_pressed");
+    node.register("shortcut", slot).unwrap();
+    let undo_history = app.undo_history.clone();
+    let render_api = app.render_api.clone();
+    let listen_undo = app.ex.spawn(async move {
+        while let Ok(_) = recvr.recv().await {
+            let atom = &mut render_api.make_guard(gfxtag!("undo shortcut"));
+            undo_history.undo(atom);
+        }
+    });
+    app.tasks.lock().unwrap().push(listen_undo);
+    let node = node.setup(|me| Shortcut::new(me)).await;
+    window.link(node);
+
+    let node = create_shortcut("redo_shortcut");
+    node.set_property_str(atom, Role::App, "key", "ctrl+shift+z").unwrap();
+    node.set_property_u32(atom, Role::App, "priority", 10).unwrap();
+    let (slot, recvr) = Slot::new("redo_pressed");
+    node.register("shortcut", slot).unwrap();
+    let undo_history = app.undo_history.clone();
+    let render_api = app.render_api.clone();
+    let listen_redo = app.ex.spawn(async move {
+        while let Ok(_) = recvr.recv().await {
+            let atom = &mut render_api.make_guard(gfxtag!("redo shortcut"));
+            undo_history.redo(atom);
+        }
+    });
+    app.tasks.lock().unwrap().push(listen_redo);
+    let node = node.setup(|me| Shortcut::new(me)).await;
+    window.link(node);
+
     /*
     let node = create_gesture("zoom_gesture");
     node.set_property_u32(atom, Role::App, "priority", 10).unwrap();
@@ -204,14 +216,6 @@ pub async fn make(app: &App, window: SceneNodePtr, i18n_fish: &I18nBabelFish) {
             let r = (distance - 1.) / 2. + 1.;
             let scale = r * window_scale.get_property_f32("value").unwrap();
 
-            let filename = get_window_scale_filename();
-            if let Some(parent) = filename.parent() {
-                let _ = std::fs::create_dir_all(parent);
-            }
-            if let Ok(mut file) = File::create(filename) {
-                scale.encode(&mut file).unwrap();
-            }
-
             let atom = &mut PropertyAtomicGuard::new();
             window_scale.set_property_f32(atom, Role::User, "value", scale);
         }
@@ -276,7 +280,7 @@ pub async fn make(app: &App, window: SceneNodePtr, i18n_fish: &I18nBabelFish) {
         //node.set_property_str(atom, Role::App, "path", BG_PATH).unwrap();
         node.set_property_str(atom, Role::App, "path", VID_PATH).unwrap();
         node.set_property_u32(atom, Role::App, "z_index", 0).unwrap();
-        //let node = node.setup(|me| Image::new(me, app.render_api.clone())).await;
+        //let node = node.setup(|me| Image::new(me, app.render_api.clone(), app.ex.clone())).await;
         //layer_node.link(node);
         node.set_property_u32(atom, Role::App, "length", 357).unwrap();
         let node = node.setup(|me| Video::new(me, app.render_api.clone(), app.ex.clone())).await;
@@ -527,7 +531,10 @@ pub async fn make(app: &App, window: SceneNodePtr, i18n_fish: &I18nBabelFish) {
         )
         .await;
     }
+    wallet::make(app, window.clone(), i18n_fish).await;
     menu::make(app, window.clone(), i18n_fish).await;
+    palette::make(app, window.clone(), i18n_fish).await;
+    notify::make(app, window.clone(), i18n_fish).await;
 
     // @@@ Debug stuff @@@
     //let chatview_node = app.sg_root.lookup_node("/window/dev_chat_layer").unwrap();
@@ -0,0 +1,302 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Overlay for toasts and notification badges.
+//!
+//! Any subsystem can call these methods on `/window/notify_layer`:
+//!
+//! * `toast(text)` shows a transient message at the bottom of the window.
+//! * `badge(name)` increments the badge `name`, shown as a counter by the
+//!   `{name}_badge` text node in the menu. Badges stay until cleared.
+//! * `clear_badge(name)` resets the badge.
+//! * `notify(name, title, body)` increments the badge and shows a toast.
+//!   On Android it also posts a system notification when the app is in
+//!   the background.
+
+use darkfi::system::msleep;
+use darkfi_serial::{deserialize, Decodable};
+use std::io::Cursor;
+
+#[cfg(target_os = "android")]
+use crate::android;
+use crate::{
+    app::{
+        node::{create_layer, create_text, create_vector_art},
+        App,
+    },
+    expr::{self, Compiler},
+    gfx::{gfxtag, RenderApi},
+    prop::{PropertyAtomicGuard, PropertyBool, PropertyFloat32, PropertyStr, Role},
+    scene::{CallArgType, MethodCallSub, SceneNodePtr},
+    ui::{Layer, Text, VectorArt, VectorShape},
+    util::i18n::I18nBabelFish,
+};
+
+use super::{ColorScheme, COLOR_SCHEME};
+
+macro_rules! d { ($($arg:tt)*) => { debug!(target: "app::notify", $($arg)*); } }
+macro_rules! w { ($($arg:tt)*) => { warn!(target: "app::notify", $($arg)*); } }
+
+#[cfg(any(target_os = "android", feature = "emulate-android"))]
+mod android_ui_consts {
+    pub const TOAST_HEIGHT: f32 = 120.;
+    pub const TOAST_MARGIN: f32 = 40.;
+    pub const TOAST_BOTTOM: f32 = 200.;
+    pub const TOAST_FONTSIZE: f32 = 36.;
+    pub const TOAST_RADIUS: f32 = 30.;
+}
+
+#[cfg(target_os = "android")]
+mod ui_consts {
+    pub use super::android_ui_consts::*;
+}
+
+#[cfg(feature = "emulate-android")]
+mod ui_consts {
+    pub use super::android_ui_consts::*;
+}
+
+#[cfg(all(
+    any(target_os = "linux", target_os = "macos", target_os = "windows"),
+    not(feature = "emulate-android")
+))]
+mod ui_consts {
+    pub const TOAST_HEIGHT: f32 = 50.;
+    pub const TOAST_MARGIN: f32 = 20.;
+    pub const TOAST_BOTTOM: f32 = 60.;
+    pub const TOAST_FONTSIZE: f32 = 18.;
+    pub const TOAST_RADIUS: f32 = 12.;
+}
+
+use ui_consts::*;
+
+/// How long each toast stays on screen in ms
+const TOAST_DURATION: u64 = 3000;
+/// Longer toasts get cut off with an ellipsis
+const TOAST_MAX_CHARS: usize = 80;
+
+const TOAST_BG_COLOR: [f32; 4] = match COLOR_SCHEME {
+    ColorScheme::DarkMode => [0.1, 0.18, 0.18, 0.95],
+    ColorScheme::PaperLight => [0.9, 0.9, 0.9, 0.95],
+};
+
+pub async fn make(app: &App, window: SceneNodePtr, i18n_fish: &I18nBabelFish) {
+    let window_scale = PropertyFloat32::wrap(
+        &app.sg_root.lookup_node("/setting/scale").unwrap(),
+        Role::Internal,
+        "value",
+        0,
+    )
+    .unwrap();
+    let atom = &mut PropertyAtomicGuard::none();
+
+    let mut layer_node = create_layer("notify_layer");
+    let prop = layer_node.get_property("rect").unwrap();
+    prop.set_f32(atom, Role::App, 0, 0.).unwrap();
+    prop.set_f32(atom, Role::App, 1, 0.).unwrap();
+    prop.set_expr(atom, Role::App, 2, expr::load_var("w")).unwrap();
+    prop.set_expr(atom, Role::App, 3, expr::load_var("h")).unwrap();
+    layer_node.set_property_bool(atom, Role::App, "is_visible", true).unwrap();
+    // Above every other view
+    layer_node.set_property_u32(atom, Role::App, "z_index", 10).unwrap();
+
+    layer_node.add_method("toast", vec![("text", "Text", CallArgType::Str)], None).unwrap();
+    layer_node.add_method("badge", vec![("name", "Badge name", CallArgType::Str)], None).unwrap();
+    layer_node
+        .add_method("clear_badge", vec![("name", "Badge name", CallArgType::Str)], None)
+        .unwrap();
+    layer_node
+        .add_method(
+            "notify",
+            vec![
+                ("name", "Badge name", CallArgType::Str),
+                ("title", "Title", CallArgType::Str),
+                ("body", "Body", CallArgType::Str),
+            ],
+            None,
+        )
+        .unwrap();
+
+    let layer_node = layer_node.setup(|me| Layer::new(me, app.render_api.clone())).await;
+    window.link(layer_node.clone());
+
+    let node = create_layer("toast");
+    let prop = node.get_property("rect").unwrap();
+    prop.set_f32(atom, Role::App, 0, TOAST_MARGIN).unwrap();
+    let code = Compiler::new().compile(format!("h - {}", TOAST_BOTTOM + TOAST_HEIGHT)).unwrap();
+    prop.set_expr(atom, Role::App, 1, code).unwrap();
+    let code = Compiler::new().compile(format!("w - {}", 2. * TOAST_MARGIN)).unwrap();
+    prop.set_expr(atom, Role::App, 2, code).unwrap();
+    prop.set_f32(atom, Role::App, 3, TOAST_HEIGHT).unwrap();
+    node.set_property_bool(atom, Role::App, "is_visible", false).unwrap();
+    let toast_node = node.setup(|me| Layer::new(me, app.render_api.clone())).await;
+    layer_node.link(toast_node.clone());
+
+    let node = create_vector_art("toast_bg");
+    let prop = node.get_property("rect").unwrap();
+    prop.set_f32(atom, Role::App, 0, 0.).unwrap();
+    prop.set_f32(atom, Role::App, 1, 0.).unwrap();
+    prop.set_expr(atom, Role::App, 2, expr::load_var("w")).unwrap();
+    prop.set_expr(atom, Role::App, 3, expr::load_var("h")).unwrap();
+    node.set_property_u32(atom, Role::App, "z_index", 0).unwrap();
+    let mut shape = VectorShape::new();
+    shape.add_rounded_box(
+        expr::const_f32(0.),
+        expr::const_f32(0.),
+        expr::load_var("w"),
+        expr::load_var("h"),
+        TOAST_RADIUS,
+        TOAST_BG_COLOR,
+    );
+    let node = node.setup(|me| VectorArt::new(me, shape, app.render_api.clone())).await;
+    toast_node.link(node);
+
+    let node = create_text("toast_text");
+    let prop = node.get_property("rect").unwrap();
+    prop.set_f32(atom, Role::App, 0, TOAST_MARGIN).unwrap();
+    prop.set_f32(atom, Role::App, 1, (TOAST_HEIGHT - TOAST_FONTSIZE) / 2.).unwrap();
+    let code = Compiler::new().compile(format!("w - {}", 2. * TOAST_MARGIN)).unwrap();
+    prop.set_expr(atom, Role::App, 2, code).unwrap();
+    prop.set_f32(atom, Role::App, 3, 2. * TOAST_FONTSIZE).unwrap();
+    node.set_property_f32(atom, Role::App, "font_size", TOAST_FONTSIZE).unwrap();
+    app.theme.bind_color(node.get_property("text_color").unwrap(), "toast_text");
+    node.set_property_u32(atom, Role::App, "z_index", 1).unwrap();
+    let node = node
+        .setup(|me| Text::new(me, window_scale.clone(), app.render_api.clone(), i18n_fish.clone()))
+        .await;
+    toast_node.link(node.clone());
+
+    // Toasts are shown one after another
+    let (toast_send, toast_recv) = async_channel::unbounded();
+    let toast_is_visible = PropertyBool::wrap(&toast_node, Role::App, "is_visible", 0).unwrap();
+    let toast_text = PropertyStr::wrap(&node, Role::App, "text", 0).unwrap();
+    let render_api = app.render_api.clone();
+    let show_toasts = app.ex.spawn(async move {
+        while let Ok(text) = toast_recv.recv().await {
+            {
+                let atom = &mut render_api.make_guard(gfxtag!("toast show"));
+                toast_text.set(atom, text);
+                toast_is_visible.set(atom, true);
+            }
+            msleep(TOAST_DURATION).await;
+            if toast_recv.is_empty() {
+                let atom = &mut render_api.make_guard(gfxtag!("toast hide"));
+                toast_is_visible.set(atom, false);
+            }
+        }
+    });
+    app.tasks.lock().unwrap().push(show_toasts);
+
+    let method_sub = layer_node.subscribe_method_call("toast").unwrap();
+    let toast_send2 = toast_send.clone();
+    let listen_toast = app.ex.spawn(async move {
+        while let Ok(method_call) = method_sub.receive().await {
+            let Ok(text) = deserialize::<String>(&method_call.data) else {
+                w!("toast() method invalid arg data");
+                continue
+            };
+            let _ = toast_send2.send(shorten(text)).await;
+        }
+    });
+    app.tasks.lock().unwrap().push(listen_toast);
+
+    let method_sub = layer_node.subscribe_method_call("badge").unwrap();
+    let sg_root = app.sg_root.clone();
+    let render_api = app.render_api.clone();
+    let listen_badge = app.ex.spawn(async move {
+        while let Some(name) = recv_badge_name(&method_sub).await {
+            increment_badge(&sg_root, &render_api, &name);
+        }
+    });
+    app.tasks.lock().unwrap().push(listen_badge);
+
+    let method_sub = layer_node.subscribe_method_call("clear_badge").unwrap();
+    let sg_root = app.sg_root.clone();
+    let render_api = app.render_api.clone();
+    let listen_clear = app.ex.spawn(async move {
+        while let Some(name) = recv_badge_name(&method_sub).await {
+            let Some(badge) = lookup_badge(&sg_root, &name) else { continue };
+            let atom = &mut render_api.make_guard(gfxtag!("clear badge"));
+            badge.set(atom, "");
+        }
+    });
+    app.tasks.lock().unwrap().push(listen_clear);
+
+    let method_sub = layer_node.subscribe_method_call("notify").unwrap();
+    let sg_root = app.sg_root.clone();
+    let render_api = app.render_api.clone();
+    let listen_notify = app.ex.spawn(async move {
+        while let Ok(method_call) = method_sub.receive().await {
+            fn decode_data(data: &[u8]) -> std::io::Result<(String, String, String)> {
+                let mut cur = Cursor::new(&data);
+                let name = String::decode(&mut cur)?;
+                let title = String::decode(&mut cur)?;
+                let body = String::decode(&mut cur)?;
+                Ok((name, title, body))
+            }
+            let Ok((name, title, body)) = decode_data(&method_call.data) else {
+                w!("notify() method invalid arg data");
+                continue
+            };
+            d!("notify({name}, {title}, {body})");
+
+            increment_badge(&sg_root, &render_api, &name);
+            let _ = toast_send.send(shorten(format!("{title}: {body}"))).await;
+            #[cfg(target_os = "android")]
+            android::post_notification(&title, &body);
+        }
+    });
+    app.tasks.lock().unwrap().push(listen_notify);
+}
+
+async fn recv_badge_name(sub: &MethodCallSub) -> Option<String> {
+    loop {
+        let method_call = sub.receive().await.ok()?;
+        match deserialize::<String>(&method_call.data) {
+            Ok(name) => return Some(name),
+            Err(_) => w!("Badge method invalid arg data"),
+        }
+    }
+}
+
+/// Badges live in the menu, so they are looked up on use
+fn lookup_badge(sg_root: &SceneNodePtr, name: &str) -> Option<PropertyStr> {
+    let path = format!("/window/menu_layer/{name}_badge");
+    let Some(node) = sg_root.lookup_node(&path) else {
+        w!("Badge {path} doesn't exist");
+        return None
+    };
+    PropertyStr::wrap(&node, Role::App, "text", 0).ok()
+}
+
+/// The badge text is the count itself so there is no state to keep in sync
+fn increment_badge(sg_root: &SceneNodePtr, render_api: &RenderApi, name: &str) {
+    let Some(badge) = lookup_badge(sg_root, name) else { return };
+    let count = badge.get().parse::<u32>().unwrap_or(0) + 1;
+    let atom = &mut render_api.make_guard(gfxtag!("increment badge"));
+    badge.set(atom, count.to_string());
+}
+
+fn shorten(text: String) -> String {
+    if text.chars().count() <= TOAST_MAX_CHARS {
+        return text
+    }
+    let mut text: String = text.chars().take(TOAST_MAX_CHARS - 1).collect();
+    text.push('…');
+    text
+}
@@ -0,0 +1,407 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::sync::{Arc, Mutex as SyncMutex};
+
+use crate::{
+    app::{
+        node::{
+            create_layer, create_palette_input, create_shortcut, create_text, create_vector_art,
+        },
+        App,
+    },
+    expr::{self, Compiler},
+    gfx::{gfxtag, RenderApi},
+    prop::{PropertyAtomicGuard, PropertyBool, PropertyFloat32, PropertyStr, Role},
+    scene::{SceneNodePtr, Slot},
+    ui::{Layer, PaletteInput, Shortcut, Text, VectorArt, VectorShape},
+    util::{fuzzy::fuzzy_filter, i18n::I18nBabelFish},
+};
+
+use super::{ColorScheme, CHANNELS, COLOR_SCHEME};
+
+#[cfg(any(target_os = "android", feature = "emulate-android"))]
+mod android_ui_consts {
+    pub const PALETTE_WIDTH: f32 = 1000.;
+    pub const PALETTE_TOP: f32 = 200.;
+    pub const PALETTE_PADDING: f32 = 40.;
+    pub const PALETTE_LINESPACE: f32 = 100.;
+    pub const PALETTE_FONTSIZE: f32 = 40.;
+}
+
+#[cfg(target_os = "android")]
+mod ui_consts {
+    pub use super::android_ui_consts::*;
+}
+
+#[cfg(feature = "emulate-android")]
+mod ui_consts {
+    pub use super::android_ui_consts::*;
+}
+
+#[cfg(all(
+    any(target_os = "linux", target_os = "macos", target_os = "windows"),
+    not(feature = "emulate-android")
+))]
+mod ui_consts {
+    pub const PALETTE_WIDTH: f32 = 500.;
+    pub const PALETTE_TOP: f32 = 100.;
+    pub const PALETTE_PADDING: f32 = 20.;
+    pub const PALETTE_LINESPACE: f32 = 44.;
+    pub const PALETTE_FONTSIZE: f32 = 20.;
+}
+
+use ui_consts::*;
+
+/// Max number of results shown at once
+const PALETTE_ROWS: usize = 8;
+
+#[derive(Clone, Copy)]
+enum PaletteAction {
+    Channel(&'static str),
+    ShowChannels,
+    ShowWallet,
+    ZoomIn,
+    ZoomOut,
+    ToggleTheme,
+}
+
+struct PaletteItem {
+    label: String,
+    action: PaletteAction,
+}
+
+fn palette_items() -> Vec<PaletteItem> {
+    let mut items: Vec<_> = CHANNELS
+        .iter()
+        .map(|channel| PaletteItem {
+            label: "#".to_string() + channel,
+            action: PaletteAction::Channel(channel),
+        })
+        .collect();
+    items.push(PaletteItem {
+        label: "Show channels".to_string(),
+        action: PaletteAction::ShowChannels,
+    });
+    items.push(PaletteItem { label: "Wallet".to_string(), action: PaletteAction::ShowWallet });
+    items.push(PaletteItem { label: "Zoom in".to_string(), action: PaletteAction::ZoomIn });
+    items.push(PaletteItem { label: "Zoom out".to_string(), action: PaletteAction::ZoomOut });
+    items.push(PaletteItem {
+        label: "Toggle theme".to_string(),
+        action: PaletteAction::ToggleTheme,
+    });
+    items
+}
+
+/// Results for the current query, and which of them is highlighted
+#[derive(Default)]
+struct PaletteState {
+    matches: Vec<usize>,
+    selected: usize,
+}
+
+struct Palette {
+    sg_root: SceneNodePtr,
+    render_api: RenderApi,
+    items: Vec<PaletteItem>,
+    state: SyncMutex<PaletteState>,
+
+    is_visible: PropertyBool,
+    query: PropertyStr,
+    query_text: PropertyStr,
+    rows: Vec<PropertyStr>,
+    cursor_is_visible: PropertyBool,
+    cursor_rect: SceneNodePtr,
+}
+
+impl Palette {
+    fn refresh(&self, atom: &mut PropertyAtomicGuard) {
+        let query = self.query.get();
+        let labels: Vec<&str> = self.items.iter().map(|item| item.label.as_str()).collect();
+
+        let mut state = self.state.lock().unwrap();
+        state.matches = fuzzy_filter(&query, &labels);
+        state.matches.truncate(PALETTE_ROWS);
+        state.selected = state.selected.min(state.matches.len().saturating_sub(1));
+
+        self.query_text.set(atom, "> ".to_string() + &query);
+        for (i, row) in self.rows.iter().enumerate() {
+            match state.matches.get(i) {
+                Some(idx) => row.set(atom, self.items[*idx].label.clone()),
+                None => row.set(atom, ""),
+            }
+        }
+
+        self.cursor_is_visible.set(atom, !state.matches.is_empty());
+        let cursor_y = PALETTE_LINESPACE * (state.selected + 1) as f32;
+        self.cursor_rect
+            .get_property("rect")
+            .unwrap()
+            .set_f32(atom, Role::App, 1, cursor_y)
+            .unwrap();
+    }
+
+    fn select(&self, delta: isize) {
+        {
+            let mut state = self.state.lock().unwrap();
+            let len = state.matches.len() as isize;
+            if len == 0 {
+                return
+            }
+            state.selected = (state.selected as isize + delta).rem_euclid(len) as usize;
+        }
+        let atom = &mut self.render_api.make_guard(gfxtag!("palette select"));
+        self.refresh(atom);
+    }
+
+    fn open(&self) {
+        let atom = &mut self.render_api.make_guard(gfxtag!("palette open"));
+        self.query.set(atom, "");
+        *self.state.lock().unwrap() = PaletteState::default();
+        self.refresh(atom);
+        self.is_visible.set(atom, true);
+    }
+
+    fn close(&self) {
+        let atom = &mut self.render_api.make_guard(gfxtag!("palette close"));
+        self.is_visible.set(atom, false);
+    }
+
+    fn toggle(&self) {
+        if self.is_visible.get() {
+            self.close()
+        } else {
+            self.open()
+        }
+    }
+
+    async fn activate(&self) {
+        let action = {
+            let state = self.state.lock().unwrap();
+            let Some(idx) = state.matches.get(state.selected) else { return };
+            self.items[*idx].action
+        };
+        self.close();
+
+        match action {
+            PaletteAction::Channel(channel) => {
+                info!(target: "app::palette", "switch to channel: {channel}");
+                self.show_view(&(channel.to_string() + "_chat_layer"));
+            }
+            PaletteAction::ShowChannels => self.show_view("menu_layer"),
+            PaletteAction::ShowWallet => self.show_view("wallet_layer"),
+            PaletteAction::ZoomIn => self.trigger_shortcut("zoom_in_shortcut").await,
+            PaletteAction::ZoomOut => self.trigger_shortcut("zoom_out_shortcut").await,
+            PaletteAction::ToggleTheme => self.toggle_theme(),
+        }
+    }
+
+    /// Flip between the dark and light palettes. The setting is a user pref so it sticks.
+    fn toggle_theme(&self) {
+        let node = self.sg_root.lookup_node("/setting/theme").unwrap();
+        let mode = node.get_property_str("value").unwrap();
+        let mode = if mode == "light" { "dark" } else { "light" };
+        let atom = &mut self.render_api.make_guard(gfxtag!("palette toggle_theme"));
+        node.set_property_str(atom, Role::User, "value", mode).unwrap();
+    }
+
+    /// Hide the channel list, every chat and the wallet, then show the given one
+    fn show_view(&self, name: &str) {
+        let atom = &mut self.render_api.make_guard(gfxtag!("palette show_view"));
+        let window = self.sg_root.lookup_node("/window").unwrap();
+        for node in window.get_children() {
+            if node.name.ends_with("_chat_layer") ||
+                node.name == "menu_layer" ||
+                node.name == "wallet_layer"
+            {
+                node.set_property_bool(atom, Role::App, "is_visible", node.name == name).unwrap();
+            }
+        }
+    }
+
+    /// Reuse the existing window shortcuts so behaviour stays the same
+    async fn trigger_shortcut(&self, name: &str) {
+        let node = self.sg_root.lookup_node("/window/".to_string() + name).unwrap();
+        node.trigger("shortcut", vec![]).await.unwrap();
+    }
+}
+
+pub async fn make(app: &App, window: SceneNodePtr, i18n_fish: &I18nBabelFish) {
+    let window_scale = PropertyFloat32::wrap(
+        &app.sg_root.lookup_node("/setting/scale").unwrap(),
+        Role::Internal,
+        "value",
+        0,
+    )
+    .unwrap();
+    let atom = &mut PropertyAtomicGuard::none();
+
+    let mut cc = Compiler::new();
+    cc.add_const_f32("PALETTE_WIDTH", PALETTE_WIDTH);
+
+    let (bg_color, cursor_color, text_color) = match COLOR_SCHEME {
+        ColorScheme::DarkMode => {
+            ([0.05, 0.05, 0.05, 0.95], [0.15, 0.2, 0.19, 1.], [1., 1., 1., 1.])
+        }
+        ColorScheme::PaperLight => ([1., 1., 1., 0.95], [0.85, 0.85, 0.85, 1.], [0., 0., 0., 1.]),
+    };
+    let height = PALETTE_LINESPACE * (PALETTE_ROWS + 1) as f32;
+
+    let layer_node = create_layer("palette_layer");
+    let prop = layer_node.get_property("rect").unwrap();
+    let code = cc.compile("(w - PALETTE_WIDTH) / 2").unwrap();
+    prop.set_expr(atom, Role::App, 0, code).unwrap();
+    prop.set_f32(atom, Role::App, 1, PALETTE_TOP).unwrap();
+    prop.set_f32(atom, Role::App, 2, PALETTE_WIDTH).unwrap();
+    prop.set_f32(atom, Role::App, 3, height).unwrap();
+    layer_node.set_property_bool(atom, Role::App, "is_visible", false).unwrap();
+    layer_node.set_property_u32(atom, Role::App, "z_index", 10).unwrap();
+    // Must see keys before the chat edit does
+    layer_node.set_property_u32(atom, Role::App, "priority", 5).unwrap();
+    let layer_node = layer_node.setup(|me| Layer::new(me, app.render_api.clone())).await;
+    window.link(layer_node.clone());
+
+    let node = create_vector_art("palette_bg");
+    let prop = node.get_property("rect").unwrap();
+    prop.set_f32(atom, Role::App, 0, 0.).unwrap();
+    prop.set_f32(atom, Role::App, 1, 0.).unwrap();
+    prop.set_expr(atom, Role::App, 2, expr::load_var("w")).unwrap();
+    prop.set_expr(atom, Role::App, 3, expr::load_var("h")).unwrap();
+    node.set_property_u32(atom, Role::App, "z_index", 0).unwrap();
+    let mut shape = VectorShape::new();
+    shape.add_filled_box(
+        expr::const_f32(0.),
+        expr::const_f32(0.),
+        expr::load_var("w"),
+        expr::load_var("h"),
+        bg_color,
+    );
+    shape.add_filled_box(
+        expr::const_f32(0.),
+        expr::const_f32(PALETTE_LINESPACE - 1.),
+        expr::load_var("w"),
+        expr::const_f32(PALETTE_LINESPACE),
+        [0.4, 0.4, 0.4, 1.],
+    );
+    let node = node.setup(|me| VectorArt::new(me, shape, app.render_api.clone())).await;
+    layer_node.link(node);
+
+    let node = create_vector_art("palette_cursor");
+    let prop = node.get_property("rect").unwrap();
+    prop.set_f32(atom, Role::App, 0, 0.).unwrap();
+    prop.set_f32(atom, Role::App, 1, PALETTE_LINESPACE).unwrap();
+    prop.set_expr(atom, Role::App, 2, expr::load_var("w")).unwrap();
+    prop.set_f32(atom, Role::App, 3, PALETTE_LINESPACE).unwrap();
+    node.set_property_u32(atom, Role::App, "z_index", 1).unwrap();
+    let mut shape = VectorShape::new();
+    shape.add_filled_box(
+        expr::const_f32(0.),
+        expr::const_f32(0.),
+        expr::load_var("w"),
+        expr::load_var("h"),
+        cursor_color,
+    );
+    let cursor_node = node.setup(|me| VectorArt::new(me, shape, app.render_api.clone())).await;
+    layer_node.link(cursor_node.clone());
+
+    // First line is the query, followed by the results
+    let mut texts = vec![];
+    for i in 0..=PALETTE_ROWS {
+        let name = if i == 0 { "palette_query".to_string() } else { format!("palette_row_{i}") };
+        let node = create_text(&name);
+        let prop = node.get_property("rect").unwrap();
+        prop.set_f32(atom, Role::App, 0, PALETTE_PADDING).unwrap();
+        let y = PALETTE_LINESPACE * i as f32 + (PALETTE_LINESPACE - PALETTE_FONTSIZE) / 2.;
+        prop.set_f32(atom, Role::App, 1, y).unwrap();
+        prop.set_f32(atom, Role::App, 2, PALETTE_WIDTH - 2. * PALETTE_PADDING).unwrap();
+        prop.set_f32(atom, Role::App, 3, PALETTE_LINESPACE).unwrap();
+        node.set_property_u32(atom, Role::App, "z_index", 2).unwrap();
+        node.set_property_f32(atom, Role::App, "font_size", PALETTE_FONTSIZE).unwrap();
+        node.set_property_str(atom, Role::App, "text", "").unwrap();
+        let prop = node.get_property("text_color").unwrap();
+        for (i, c) in text_color.into_iter().enumerate() {
+            prop.set_f32(atom, Role::App, i, c).unwrap();
+        }
+        let node = node
+            .setup(|me| {
+                Text::new(me, window_scale.clone(), app.render_api.clone(), i18n_fish.clone())
+            })
+            .await;
+        texts.push(PropertyStr::wrap(&node, Role::App, "text", 0).unwrap());
+        layer_node.link(node);
+    }
+    let query_text = texts.remove(0);
+
+    let input_node = create_palette_input("palette_input");
+    input_node.set_property_str(atom, Role::App, "query", "").unwrap();
+    let input_node = input_node.setup(|me| PaletteInput::new(me, app.render_api.clone())).await;
+    layer_node.link(input_node.clone());
+
+    let palette = Arc::new(Palette {
+        sg_root: app.sg_root.clone(),
+        render_api: app.render_api.clone(),
+        items: palette_items(),
+        state: SyncMutex::new(PaletteState::default()),
+        is_visible: PropertyBool::wrap(&layer_node, Role::App, "is_visible", 0).unwrap(),
+        query: PropertyStr::wrap(&input_node, Role::App, "query", 0).unwrap(),
+        query_text,
+        rows: texts,
+        cursor_is_visible: PropertyBool::wrap(&cursor_node, Role::App, "is_visible", 0).unwrap(),
+        cursor_rect: cursor_node,
+    });
+
+    for sig_name in ["query_changed", "select_prev", "select_next", "activate", "close"] {
+        let (slot, recvr) = Slot::new(format!("palette_{sig_name}"));
+        input_node.register(sig_name, slot).unwrap();
+        let palette = palette.clone();
+        let listen = app.ex.spawn(async move {
+            while let Ok(_) = recvr.recv().await {
+                match sig_name {
+                    "query_changed" => {
+                        palette.state.lock().unwrap().selected = 0;
+                        let atom = &mut palette.render_api.make_guard(gfxtag!("palette query"));
+                        palette.refresh(atom);
+                    }
+                    "select_prev" => palette.select(-1),
+                    "select_next" => palette.select(1),
+                    "activate" => palette.activate().await,
+                    "close" => palette.close(),
+                    _ => unreachable!(),
+                }
+            }
+        });
+        app.tasks.lock().unwrap().push(listen);
+    }
+
+    let node = create_shortcut("palette_shortcut");
+    #[cfg(not(target_os = "macos"))]
+    node.set_property_str(atom, Role::App, "key", "ctrl+k").unwrap();
+    #[cfg(target_os = "macos")]
+    node.set_property_str(atom, Role::App, "key", "logo+k").unwrap();
+    node.set_property_u32(atom, Role::App, "priority", 10).unwrap();
+    let (slot, recvr) = Slot::new("palette_pressed");
+    node.register("shortcut", slot).unwrap();
+    let listen = app.ex.spawn(async move {
+        while let Ok(_) = recvr.recv().await {
+            palette.toggle();
+        }
+    });
+    app.tasks.lock().unwrap().push(listen);
+    let node = node.setup(|me| Shortcut::new(me)).await;
+    window.link(node);
+}
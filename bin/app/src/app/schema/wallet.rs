@@ -0,0 +1,575 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_serial::Encodable;
+
+use crate::{
+    app::{
+        node::{
+            create_button, create_layer, create_listview, create_qrcode, create_shortcut,
+            create_singleline_edit, create_text, create_vector_art,
+        },
+        App,
+    },
+    expr::{self, Compiler},
+    gfx::gfxtag,
+    prop::{PropertyAtomicGuard, PropertyBool, PropertyFloat32, PropertyStr, Role},
+    scene::{SceneNodePtr, Slot},
+    shape,
+    ui::{
+        BaseEdit, BaseEditType, Button, Layer, ListView, QrCode, Shortcut, Text, VectorArt,
+        VectorShape,
+    },
+    util::{i18n::I18nBabelFish, qr},
+};
+
+use super::{ColorScheme, COLOR_SCHEME};
+
+#[cfg(any(target_os = "android", feature = "emulate-android"))]
+mod android_ui_consts {
+    pub const TOOLBAR_HEIGHT: f32 = 140.;
+    pub const BACKARROW_SCALE: f32 = 30.;
+    pub const BACKARROW_X: f32 = 50.;
+    pub const BACKARROW_Y: f32 = 70.;
+    pub const BACK_BTN_W: f32 = 120.;
+    pub const TITLE_X: f32 = 150.;
+    pub const TITLE_Y: f32 = 30.;
+    pub const FONTSIZE: f32 = 40.;
+    pub const HEADING_FONTSIZE: f32 = 32.;
+    pub const LINESPACE: f32 = 70.;
+    pub const MARGIN: f32 = 40.;
+    pub const ROW_HEIGHT: f32 = 90.;
+    pub const BALANCE_ROWS: usize = 5;
+    pub const AMOUNT_X: f32 = 500.;
+    pub const EDIT_X: f32 = 260.;
+    pub const EDIT_HEIGHT: f32 = 100.;
+    pub const EDIT_SPACING: f32 = 120.;
+    pub const EDIT_BASELINE: f32 = 62.;
+    pub const EDIT_CURSOR_ASCENT: f32 = 40.;
+    pub const EDIT_CURSOR_DESCENT: f32 = 16.;
+    pub const SENDBTN_W: f32 = 240.;
+    pub const SCANBTN_W: f32 = 200.;
+    pub const QR_SIZE: f32 = 360.;
+}
+
+#[cfg(target_os = "android")]
+mod ui_consts {
+    pub use super::android_ui_consts::*;
+}
+
+#[cfg(feature = "emulate-android")]
+mod ui_consts {
+    pub use super::android_ui_consts::*;
+}
+
+#[cfg(all(
+    any(target_os = "linux", target_os = "macos", target_os = "windows"),
+    not(feature = "emulate-android")
+))]
+mod ui_consts {
+    pub const TOOLBAR_HEIGHT: f32 = 60.;
+    pub const BACKARROW_SCALE: f32 = 15.;
+    pub const BACKARROW_X: f32 = 38.;
+    pub const BACKARROW_Y: f32 = 26.;
+    pub const BACK_BTN_W: f32 = 80.;
+    pub const TITLE_X: f32 = 100.;
+    pub const TITLE_Y: f32 = 12.;
+    pub const FONTSIZE: f32 = 20.;
+    pub const HEADING_FONTSIZE: f32 = 16.;
+    pub const LINESPACE: f32 = 30.;
+    pub const MARGIN: f32 = 20.;
+    pub const ROW_HEIGHT: f32 = 40.;
+    pub const BALANCE_ROWS: usize = 5;
+    pub const AMOUNT_X: f32 = 300.;
+    pub const EDIT_X: f32 = 130.;
+    pub const EDIT_HEIGHT: f32 = 40.;
+    pub const EDIT_SPACING: f32 = 50.;
+    pub const EDIT_BASELINE: f32 = 27.;
+    pub const EDIT_CURSOR_ASCENT: f32 = 20.;
+    pub const EDIT_CURSOR_DESCENT: f32 = 8.;
+    pub const SENDBTN_W: f32 = 120.;
+    pub const SCANBTN_W: f32 = 100.;
+    pub const QR_SIZE: f32 = 160.;
+}
+
+use ui_consts::*;
+
+const SEP_COLOR: [f32; 4] = match COLOR_SCHEME {
+    ColorScheme::DarkMode => [0.41, 0.6, 0.65, 1.],
+    ColorScheme::PaperLight => [0., 0.6, 0.65, 1.],
+};
+
+/// Fields of the send form as `(node name, label)`
+const SEND_FIELDS: [(&str, &str); 3] =
+    [("recipient_edit", "To"), ("token_edit", "Token"), ("amount_edit", "Amount")];
+
+/// Shared state for building the nodes of one parent
+#[derive(Clone)]
+struct Page<'a> {
+    app: &'a App,
+    layer: SceneNodePtr,
+    window_scale: PropertyFloat32,
+    i18n_fish: &'a I18nBabelFish,
+}
+
+impl Page<'_> {
+    async fn add_label(
+        &self,
+        name: &str,
+        x: f32,
+        y: f32,
+        font_size: f32,
+        text: &str,
+        color: &str,
+    ) -> SceneNodePtr {
+        let atom = &mut PropertyAtomicGuard::none();
+
+        let node = create_text(name);
+        let prop = node.get_property("rect").unwrap();
+        prop.set_f32(atom, Role::App, 0, x).unwrap();
+        prop.set_f32(atom, Role::App, 1, y).unwrap();
+        prop.set_expr(atom, Role::App, 2, expr::load_var("w")).unwrap();
+        prop.set_f32(atom, Role::App, 3, 2. * font_size).unwrap();
+        node.set_property_f32(atom, Role::App, "font_size", font_size).unwrap();
+        node.set_property_str(atom, Role::App, "text", text).unwrap();
+        self.app.theme.bind_color(node.get_property("text_color").unwrap(), color);
+        node.set_property_u32(atom, Role::App, "z_index", 3).unwrap();
+
+        let node = node
+            .setup(|me| {
+                Text::new(
+                    me,
+                    self.window_scale.clone(),
+                    self.app.render_api.clone(),
+                    self.i18n_fish.clone(),
+                )
+            })
+            .await;
+        self.layer.link(node.clone());
+        node
+    }
+
+    async fn add_edit(&self, name: &str, y: f32) -> SceneNodePtr {
+        let atom = &mut PropertyAtomicGuard::none();
+
+        let node = create_singleline_edit(name);
+        node.set_property_bool(atom, Role::App, "is_active", true).unwrap();
+
+        let prop = node.get_property("rect").unwrap();
+        prop.set_f32(atom, Role::App, 0, EDIT_X).unwrap();
+        prop.set_f32(atom, Role::App, 1, y).unwrap();
+        let code = Compiler::new().compile(format!("w - {}", EDIT_X + MARGIN)).unwrap();
+        prop.set_expr(atom, Role::App, 2, code).unwrap();
+        prop.set_f32(atom, Role::App, 3, EDIT_HEIGHT).unwrap();
+
+        let prop = node.get_property("padding").unwrap();
+        prop.set_f32(atom, Role::App, 0, MARGIN / 2.).unwrap();
+        prop.set_f32(atom, Role::App, 1, 0.).unwrap();
+        prop.set_f32(atom, Role::App, 2, MARGIN / 2.).unwrap();
+        prop.set_f32(atom, Role::App, 3, 0.).unwrap();
+
+        node.set_property_f32(atom, Role::App, "baseline", EDIT_BASELINE).unwrap();
+        node.set_property_f32(atom, Role::App, "font_size", FONTSIZE).unwrap();
+        let theme = &self.app.theme;
+        theme.bind_color(node.get_property("text_color").unwrap(), "text");
+        theme.bind_color(node.get_property("cursor_color").unwrap(), "cursor");
+        theme.bind_metric(node.get_property("cursor_width").unwrap(), 0, "cursor_width");
+        node.set_property_f32(atom, Role::App, "cursor_ascent", EDIT_CURSOR_ASCENT).unwrap();
+        node.set_property_f32(atom, Role::App, "cursor_descent", EDIT_CURSOR_DESCENT).unwrap();
+        node.set_property_f32(atom, Role::App, "select_ascent", EDIT_CURSOR_ASCENT).unwrap();
+        node.set_property_f32(atom, Role::App, "select_descent", EDIT_CURSOR_DESCENT).unwrap();
+        theme.bind_color(node.get_property("hi_bg_color").unwrap(), "selection_bg");
+        node.set_property_u32(atom, Role::App, "z_index", 4).unwrap();
+        node.set_property_u32(atom, Role::App, "priority", 3).unwrap();
+
+        let node = node
+            .setup(|me| {
+                BaseEdit::new(
+                    me,
+                    self.window_scale.clone(),
+                    self.app.render_api.clone(),
+                    BaseEditType::SingleLine,
+                )
+            })
+            .await;
+        self.layer.link(node.clone());
+        node
+    }
+}
+
+pub async fn make(app: &App, window: SceneNodePtr, i18n_fish: &I18nBabelFish) {
+    let window_scale = PropertyFloat32::wrap(
+        &app.sg_root.lookup_node("/setting/scale").unwrap(),
+        Role::Internal,
+        "value",
+        0,
+    )
+    .unwrap();
+    let atom = &mut PropertyAtomicGuard::none();
+
+    // Main view
+    let layer_node = create_layer("wallet_layer");
+    let prop = layer_node.get_property("rect").unwrap();
+    prop.set_f32(atom, Role::App, 0, 0.).unwrap();
+    prop.set_f32(atom, Role::App, 1, 0.).unwrap();
+    prop.set_expr(atom, Role::App, 2, expr::load_var("w")).unwrap();
+    prop.set_expr(atom, Role::App, 3, expr::load_var("h")).unwrap();
+    layer_node.set_property_bool(atom, Role::App, "is_visible", false).unwrap();
+    layer_node.set_property_u32(atom, Role::App, "z_index", 1).unwrap();
+    let layer_node = layer_node.setup(|me| Layer::new(me, app.render_api.clone())).await;
+    window.link(layer_node.clone());
+
+    let page = Page { app, layer: layer_node.clone(), window_scale, i18n_fish };
+
+    // Create the toolbar bg
+    let node = create_vector_art("toolbar_bg");
+    let prop = node.get_property("rect").unwrap();
+    prop.set_f32(atom, Role::App, 0, 0.).unwrap();
+    prop.set_f32(atom, Role::App, 1, 0.).unwrap();
+    prop.set_expr(atom, Role::App, 2, expr::load_var("w")).unwrap();
+    prop.set_f32(atom, Role::App, 3, TOOLBAR_HEIGHT).unwrap();
+    node.set_property_u32(atom, Role::App, "z_index", 2).unwrap();
+
+    let bg_color = match COLOR_SCHEME {
+        ColorScheme::DarkMode => [0., 0.11, 0.11, 1.],
+        ColorScheme::PaperLight => [1., 1., 1., 1.],
+    };
+    let mut shape = VectorShape::new();
+    shape.add_filled_box(
+        expr::const_f32(0.),
+        expr::const_f32(0.),
+        expr::load_var("w"),
+        expr::load_var("h"),
+        bg_color,
+    );
+    shape.add_filled_box(
+        expr::const_f32(BACK_BTN_W),
+        expr::const_f32(0.),
+        expr::const_f32(BACK_BTN_W + 1.),
+        expr::load_var("h"),
+        SEP_COLOR,
+    );
+    shape.add_filled_box(
+        expr::const_f32(0.),
+        expr::load_var("h"),
+        expr::load_var("w"),
+        Compiler::new().compile("h + 1").unwrap(),
+        SEP_COLOR,
+    );
+    let node = node.setup(|me| VectorArt::new(me, shape, app.render_api.clone())).await;
+    layer_node.link(node);
+
+    let node = create_vector_art("back_btn_bg");
+    let prop = node.get_property("rect").unwrap();
+    prop.set_f32(atom, Role::App, 0, BACKARROW_X).unwrap();
+    prop.set_f32(atom, Role::App, 1, BACKARROW_Y).unwrap();
+    prop.set_f32(atom, Role::App, 2, 500.).unwrap();
+    prop.set_f32(atom, Role::App, 3, 500.).unwrap();
+    node.set_property_u32(atom, Role::App, "z_index", 3).unwrap();
+
+    let shape = shape::create_back_arrow().scaled(BACKARROW_SCALE);
+    let node = node.setup(|me| VectorArt::new(me, shape, app.render_api.clone())).await;
+    layer_node.link(node);
+
+    page.add_label("title_label", TITLE_X, TITLE_Y, FONTSIZE * 1.2, "Wallet", "text").await;
+    let node = page.add_label("status", TITLE_X, TITLE_Y, HEADING_FONTSIZE, "", "text_dim").await;
+    // Keep it in the right half of the toolbar
+    let prop = node.get_property("rect").unwrap();
+    let code = Compiler::new().compile("w / 2").unwrap();
+    prop.set_expr(atom, Role::App, 0, code).unwrap();
+    prop.set_f32(atom, Role::App, 1, TITLE_Y + FONTSIZE / 4.).unwrap();
+
+    // Balances
+    let mut y = TOOLBAR_HEIGHT + MARGIN;
+    page.add_label("balances_label", MARGIN, y, HEADING_FONTSIZE, "Balances", "heading").await;
+    y += LINESPACE;
+
+    let list_h = ROW_HEIGHT * (BALANCE_ROWS - 1) as f32;
+    let node = create_listview("balances");
+    let prop = node.get_property("rect").unwrap();
+    prop.set_f32(atom, Role::App, 0, MARGIN).unwrap();
+    prop.set_f32(atom, Role::App, 1, y).unwrap();
+    let code = Compiler::new().compile(format!("w - {}", 2. * MARGIN)).unwrap();
+    prop.set_expr(atom, Role::App, 2, code).unwrap();
+    prop.set_f32(atom, Role::App, 3, list_h).unwrap();
+    node.set_property_f32(atom, Role::App, "row_height", ROW_HEIGHT).unwrap();
+    node.set_property_u32(atom, Role::App, "z_index", 3).unwrap();
+    let balances_node = node.setup(|me| ListView::new(me, app.render_api.clone())).await;
+    layer_node.link(balances_node.clone());
+
+    // Enough rows to cover the view plus one, recycled while scrolling
+    for i in 0..BALANCE_ROWS {
+        let node = create_layer(&format!("row_{i}"));
+        let prop = node.get_property("rect").unwrap();
+        prop.set_f32(atom, Role::App, 0, 0.).unwrap();
+        prop.set_f32(atom, Role::App, 1, 0.).unwrap();
+        prop.set_expr(atom, Role::App, 2, expr::load_var("w")).unwrap();
+        prop.set_f32(atom, Role::App, 3, ROW_HEIGHT).unwrap();
+        node.set_property_bool(atom, Role::App, "is_visible", true).unwrap();
+        let row_node = node.setup(|me| Layer::new(me, app.render_api.clone())).await;
+        balances_node.link(row_node.clone());
+
+        let row = Page { layer: row_node, ..page.clone() };
+        let text_y = (ROW_HEIGHT - FONTSIZE) / 2.;
+        row.add_label("token", 0., text_y, FONTSIZE, "", "heading").await;
+        row.add_label("amount", AMOUNT_X, text_y, FONTSIZE, "", "text").await;
+    }
+    y += list_h + MARGIN;
+
+    // Receive
+    page.add_label("receive_label", MARGIN, y, HEADING_FONTSIZE, "Receive", "heading").await;
+    y += LINESPACE;
+    // The wallet plugin fills in the address once loaded
+    page.add_label("receive_address", MARGIN, y, FONTSIZE, "", "text").await;
+    y += LINESPACE;
+
+    // Same for the QR code of the address
+    let node = create_qrcode("receive_qr");
+    let prop = node.get_property("rect").unwrap();
+    prop.set_f32(atom, Role::App, 0, MARGIN).unwrap();
+    prop.set_f32(atom, Role::App, 1, y).unwrap();
+    prop.set_f32(atom, Role::App, 2, QR_SIZE).unwrap();
+    prop.set_f32(atom, Role::App, 3, QR_SIZE).unwrap();
+    node.set_property_u32(atom, Role::App, "z_index", 3).unwrap();
+    app.theme.bind_color(node.get_property("fg_color").unwrap(), "qr_fg");
+    app.theme.bind_color(node.get_property("bg_color").unwrap(), "qr_bg");
+    let node = node.setup(|me| QrCode::new(me, app.render_api.clone())).await;
+    layer_node.link(node);
+    y += QR_SIZE + MARGIN;
+
+    // Send
+    page.add_label("send_label", MARGIN, y, HEADING_FONTSIZE, "Send", "heading").await;
+    page.add_label("scan_btn_label", EDIT_X, y, HEADING_FONTSIZE, "Scan QR", "heading").await;
+    let scan_y = y;
+    y += LINESPACE;
+
+    let node = create_vector_art("send_form_bg");
+    let prop = node.get_property("rect").unwrap();
+    prop.set_f32(atom, Role::App, 0, EDIT_X).unwrap();
+    prop.set_f32(atom, Role::App, 1, y).unwrap();
+    let code = Compiler::new().compile(format!("w - {}", EDIT_X + MARGIN)).unwrap();
+    prop.set_expr(atom, Role::App, 2, code).unwrap();
+    prop.set_f32(atom, Role::App, 3, EDIT_SPACING * (SEND_FIELDS.len() + 1) as f32).unwrap();
+    node.set_property_u32(atom, Role::App, "z_index", 2).unwrap();
+
+    let mut shape = VectorShape::new();
+    for i in 0..=SEND_FIELDS.len() {
+        let y1 = EDIT_SPACING * i as f32;
+        let y2 = y1 + EDIT_HEIGHT;
+        if i < SEND_FIELDS.len() {
+            shape.add_outline(
+                expr::const_f32(0.),
+                expr::const_f32(y1),
+                expr::load_var("w"),
+                expr::const_f32(y2),
+                1.,
+                SEP_COLOR,
+            );
+        } else {
+            // The send button
+            shape.add_filled_box(
+                expr::const_f32(0.),
+                expr::const_f32(y1),
+                expr::const_f32(SENDBTN_W),
+                expr::const_f32(y2),
+                SEP_COLOR,
+            );
+        }
+    }
+    let node = node.setup(|me| VectorArt::new(me, shape, app.render_api.clone())).await;
+    layer_node.link(node);
+
+    let text_y = (EDIT_HEIGHT - FONTSIZE) / 2.;
+    let mut edits = vec![];
+    for (name, label) in SEND_FIELDS {
+        page.add_label(&format!("{name}_label"), MARGIN, y + text_y, FONTSIZE, label, "text_dim")
+            .await;
+        edits.push(page.add_edit(name, y).await);
+        y += EDIT_SPACING;
+    }
+
+    // Only one field has the focus at a time
+    for (i, edit) in edits.iter().enumerate() {
+        let (slot, recvr) = Slot::new("focus_requested");
+        edit.register("focus_request", slot).unwrap();
+        let edit2 = edit.clone();
+        let listen_focus = app.ex.spawn(async move {
+            while let Ok(_) = recvr.recv().await {
+                edit2.call_method("focus", vec![]).await.unwrap();
+            }
+        });
+        app.tasks.lock().unwrap().push(listen_focus);
+
+        let is_focused = PropertyBool::wrap(edit, Role::App, "is_focused", 0).unwrap();
+        let is_focused_sub = is_focused.prop().subscribe_modify();
+        let others: Vec<_> =
+            edits.iter().enumerate().filter(|(j, _)| *j != i).map(|(_, e)| e.clone()).collect();
+        let focus_task = app.ex.spawn(async move {
+            while let Ok(_) = is_focused_sub.receive().await {
+                if !is_focused.get() {
+                    continue
+                }
+                for other in &others {
+                    if other.get_property_bool("is_focused").unwrap() {
+                        other.call_method("unfocus", vec![]).await.unwrap();
+                    }
+                }
+            }
+        });
+        app.tasks.lock().unwrap().push(focus_task);
+    }
+
+    // Fill in the recipient from a scanned QR code
+    let node = create_button("scan_btn");
+    node.set_property_bool(atom, Role::App, "is_active", true).unwrap();
+    let prop = node.get_property("rect").unwrap();
+    prop.set_f32(atom, Role::App, 0, EDIT_X).unwrap();
+    prop.set_f32(atom, Role::App, 1, scan_y).unwrap();
+    prop.set_f32(atom, Role::App, 2, SCANBTN_W).unwrap();
+    prop.set_f32(atom, Role::App, 3, LINESPACE).unwrap();
+
+    let recipient_edit = edits[0].clone();
+    let (slot, recvr) = Slot::new("scan_clicked");
+    node.register("click", slot).unwrap();
+    let listen_click = app.ex.spawn(async move {
+        while let Ok(_) = recvr.recv().await {
+            let Some(text) = qr::scan().await else {
+                info!(target: "app::wallet", "QR scan returned nothing");
+                continue
+            };
+            recipient_edit.call_method("select_all", vec![]).await.unwrap();
+            let mut data = vec![];
+            text.encode(&mut data).unwrap();
+            recipient_edit.call_method("insert_text", data).await.unwrap();
+        }
+    });
+    app.tasks.lock().unwrap().push(listen_click);
+
+    let node = node.setup(|me| Button::new(me)).await;
+    layer_node.link(node);
+
+    page.add_label("send_btn_label", EDIT_X + MARGIN, y + text_y, FONTSIZE, "Send", "text").await;
+    let send_status = page
+        .add_label("send_status", EDIT_X + SENDBTN_W + MARGIN, y + text_y, FONTSIZE, "", "text_dim")
+        .await;
+
+    let node = create_button("send_btn");
+    node.set_property_bool(atom, Role::App, "is_active", true).unwrap();
+    let prop = node.get_property("rect").unwrap();
+    prop.set_f32(atom, Role::App, 0, EDIT_X).unwrap();
+    prop.set_f32(atom, Role::App, 1, y).unwrap();
+    prop.set_f32(atom, Role::App, 2, SENDBTN_W).unwrap();
+    prop.set_f32(atom, Role::App, 3, EDIT_HEIGHT).unwrap();
+
+    let fields: Vec<_> =
+        edits.iter().map(|edit| PropertyStr::wrap(edit, Role::App, "text", 0).unwrap()).collect();
+    let send_status = PropertyStr::wrap(&send_status, Role::App, "text", 0).unwrap();
+    let sg_root = app.sg_root.clone();
+    let render_api = app.render_api.clone();
+    let (slot, recvr) = Slot::new("send_clicked");
+    node.register("click", slot).unwrap();
+    let listen_click = app.ex.spawn(async move {
+        while let Ok(_) = recvr.recv().await {
+            let values: Vec<String> = fields.iter().map(|field| field.get()).collect();
+            info!(target: "app::wallet", "Send {} {} to {}", values[2], values[1], values[0]);
+            let atom = &mut render_api.make_guard(gfxtag!("wallet send"));
+
+            if values.iter().any(|value| value.trim().is_empty()) {
+                send_status.set(atom, "Fill in all the fields");
+                continue
+            }
+
+            let Some(wallet) = sg_root.lookup_node("/plugin/wallet") else {
+                error!(target: "app::wallet", "Wallet plugin has not been loaded");
+                send_status.set(atom, "Wallet is not loaded");
+                continue
+            };
+
+            let mut data = vec![];
+            for value in &values {
+                value.encode(&mut data).unwrap();
+            }
+            wallet.call_method("send", data).await.unwrap();
+            send_status.set(atom, "Sending...");
+        }
+    });
+    app.tasks.lock().unwrap().push(listen_click);
+
+    let node = node.setup(|me| Button::new(me)).await;
+    layer_node.link(node);
+
+    // Create the back button
+    let node = create_button("back_btn");
+    node.set_property_bool(atom, Role::App, "is_active", true).unwrap();
+    let prop = node.get_property("rect").unwrap();
+    prop.set_f32(atom, Role::App, 0, 0.).unwrap();
+    prop.set_f32(atom, Role::App, 1, 0.).unwrap();
+    prop.set_f32(atom, Role::App, 2, BACK_BTN_W).unwrap();
+    prop.set_f32(atom, Role::App, 3, TOOLBAR_HEIGHT).unwrap();
+
+    // Menu doesn't exist yet so look it up in the callback.
+    let sg_root = app.sg_root.clone();
+    let wallet_is_visible = PropertyBool::wrap(&layer_node, Role::App, "is_visible", 0).unwrap();
+    let render_api = app.render_api.clone();
+    let goback = async move || {
+        info!(target: "app::wallet", "clicked back");
+        for edit in &edits {
+            edit.call_method("unfocus", vec![]).await.unwrap();
+        }
+
+        let atom = &mut render_api.make_guard(gfxtag!("wallet goback action"));
+        let menu_node = sg_root.lookup_node("/window/menu_layer").unwrap();
+        menu_node.set_property_bool(atom, Role::App, "is_visible", true).unwrap();
+        wallet_is_visible.set(atom, false);
+    };
+
+    let (slot, recvr) = Slot::new("back_clicked");
+    node.register("click", slot).unwrap();
+    let goback2 = goback.clone();
+    let listen_click = app.ex.spawn(async move {
+        while let Ok(_) = recvr.recv().await {
+            goback2().await;
+        }
+    });
+    app.tasks.lock().unwrap().push(listen_click);
+
+    let node = node.setup(|me| Button::new(me)).await;
+    layer_node.link(node);
+
+    // Create shortcut to go back as well
+    let node = create_shortcut("back_shortcut");
+    #[cfg(target_os = "android")]
+    node.set_property_str(atom, Role::App, "key", "back").unwrap();
+    #[cfg(target_os = "macos")]
+    node.set_property_str(atom, Role::App, "key", "logo+left").unwrap();
+    #[cfg(all(not(target_os = "android"), not(target_os = "macos")))]
+    node.set_property_str(atom, Role::App, "key", "alt+left").unwrap();
+    node.set_property_u32(atom, Role::App, "priority", 10).unwrap();
+
+    let (slot, recvr) = Slot::new("back_pressed");
+    node.register("shortcut", slot).unwrap();
+    let listen_enter = app.ex.spawn(async move {
+        while let Ok(_) = recvr.recv().await {
+            goback().await;
+        }
+    });
+    app.tasks.lock().unwrap().push(listen_enter);
+
+    let node = node.setup(|me| Shortcut::new(me)).await;
+    layer_node.link(node);
+}
@@ -0,0 +1,214 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Named color and metric tokens shared by the UI.
+//!
+//! Each token is a node below `/theme` with a `value` property. Widgets
+//! don't read tokens directly, instead their properties get bound with
+//! [`Theme::bind_color()`] or [`Theme::bind_metric()`] and follow the
+//! token through the normal property change notifications. Switching the
+//! `/setting/theme` value between `dark` and `light` swaps the palette at
+//! runtime.
+
+use parking_lot::Mutex as SyncMutex;
+use std::sync::Arc;
+
+use crate::{
+    gfx::{gfxtag, RenderApi},
+    prop::{
+        ModifyAction, Property, PropertyAtomicGuard, PropertyPtr, PropertySubType, PropertyType,
+        PropertyValue, Role,
+    },
+    scene::{SceneNode, SceneNodePtr, SceneNodeType},
+    ExecutorPtr,
+};
+
+macro_rules! d { ($($arg:tt)*) => { debug!(target: "app::theme", $($arg)*); } }
+macro_rules! w { ($($arg:tt)*) => { warn!(target: "app::theme", $($arg)*); } }
+
+#[derive(Clone, Copy)]
+enum Token {
+    Color([f32; 4]),
+    Metric(f32),
+}
+
+/// Both palettes must define the same tokens
+const DARK: &[(&str, Token)] = &[
+    ("text", Token::Color([1., 1., 1., 1.])),
+    ("text_dim", Token::Color([0.6, 0.6, 0.6, 1.])),
+    ("heading", Token::Color([0.65, 0.87, 0.83, 1.])),
+    ("separator", Token::Color([0.41, 0.6, 0.65, 1.])),
+    ("toolbar_bg", Token::Color([0., 0.11, 0.11, 1.])),
+    ("cursor", Token::Color([0.816, 0.627, 1., 1.])),
+    ("selection_bg", Token::Color([0., 0.27, 0.22, 1.])),
+    ("badge", Token::Color([0.36, 1., 0.51, 1.])),
+    ("toast_text", Token::Color([1., 1., 1., 1.])),
+    ("qr_fg", Token::Color([0., 0., 0., 1.])),
+    ("qr_bg", Token::Color([1., 1., 1., 1.])),
+    ("cursor_width", Token::Metric(4.)),
+];
+
+const LIGHT: &[(&str, Token)] = &[
+    ("text", Token::Color([0., 0., 0., 1.])),
+    ("text_dim", Token::Color([0.4, 0.4, 0.4, 1.])),
+    ("heading", Token::Color([0., 0.6, 0.65, 1.])),
+    ("separator", Token::Color([0., 0.6, 0.65, 1.])),
+    ("toolbar_bg", Token::Color([1., 1., 1., 1.])),
+    ("cursor", Token::Color([0.4, 0.2, 0.8, 1.])),
+    ("selection_bg", Token::Color([0.8, 0.9, 0.88, 1.])),
+    ("badge", Token::Color([0., 0.6, 0.65, 1.])),
+    ("toast_text", Token::Color([0., 0., 0., 1.])),
+    ("qr_fg", Token::Color([0., 0., 0., 1.])),
+    ("qr_bg", Token::Color([1., 1., 1., 1.])),
+    ("cursor_width", Token::Metric(4.)),
+];
+
+fn palette(mode: &str) -> &'static [(&'static str, Token)] {
+    match mode {
+        "light" => LIGHT,
+        "dark" => DARK,
+        _ => {
+            w!("Unknown theme '{mode}', using dark");
+            DARK
+        }
+    }
+}
+
+pub type ThemePtr = Arc<Theme>;
+
+pub struct Theme {
+    pub root: SceneNodePtr,
+    render_api: RenderApi,
+    ex: ExecutorPtr,
+    tasks: SyncMutex<Vec<smol::Task<()>>>,
+}
+
+impl Theme {
+    pub fn new(render_api: RenderApi, ex: ExecutorPtr) -> ThemePtr {
+        let mut root = SceneNode::new("theme", SceneNodeType::ThemeRoot);
+        let prop = Property::new("mode", PropertyType::Str, PropertySubType::Null);
+        root.add_property(prop).unwrap();
+        let root = root.setup_null();
+
+        for (name, token) in DARK {
+            let mut node = SceneNode::new(*name, SceneNodeType::ThemeToken);
+            let mut prop = match token {
+                Token::Color(_) => {
+                    let mut prop =
+                        Property::new("value", PropertyType::Float32, PropertySubType::Color);
+                    prop.set_array_len(4);
+                    prop.set_range_f32(0., 1.);
+                    prop
+                }
+                Token::Metric(_) => {
+                    Property::new("value", PropertyType::Float32, PropertySubType::Pixel)
+                }
+            };
+            prop.set_ui_text(*name, "Theme token");
+            node.add_property(prop).unwrap();
+            root.link(node.setup_null());
+        }
+
+        let self_ = Arc::new(Self { root, render_api, ex, tasks: SyncMutex::new(vec![]) });
+        self_.apply("dark", &mut PropertyAtomicGuard::none());
+        self_
+    }
+
+    /// Follow the given setting, which should be either `dark` or `light`
+    pub fn start(self: &Arc<Self>, mode: PropertyPtr) {
+        self.apply(&mode.get_str(0).unwrap(), &mut PropertyAtomicGuard::none());
+
+        let sub = mode.subscribe_modify();
+        let me = Arc::downgrade(self);
+        let task = self.ex.spawn(async move {
+            while sub.receive().await.is_ok() {
+                let Some(self_) = me.upgrade() else { break };
+                let atom = &mut self_.render_api.make_guard(gfxtag!("theme switch"));
+                self_.apply(&mode.get_str(0).unwrap(), atom);
+            }
+        });
+        self.tasks.lock().push(task);
+    }
+
+    fn apply(&self, mode: &str, atom: &mut PropertyAtomicGuard) {
+        d!("Applying {mode} theme");
+        self.root.set_property_str(atom, Role::App, "mode", mode).unwrap();
+        for (name, token) in palette(mode) {
+            let prop = self.token_prop(name);
+            match token {
+                Token::Color(color) => {
+                    for (i, c) in color.iter().enumerate() {
+                        prop.set_f32(atom, Role::App, i, *c).unwrap();
+                    }
+                }
+                Token::Metric(val) => prop.set_f32(atom, Role::App, 0, *val).unwrap(),
+            }
+        }
+    }
+
+    fn token_prop(&self, name: &str) -> PropertyPtr {
+        let Some(node) = self.root.lookup_node(format!("/{name}")) else {
+            panic!("unknown theme token: {name}")
+        };
+        node.get_property("value").unwrap()
+    }
+
+    /// Keep a color property in sync with a color token
+    pub fn bind_color(&self, prop: PropertyPtr, token: &str) {
+        let value = self.token_prop(token);
+        assert_eq!(value.get_len(), 4);
+        for i in 0..4 {
+            copy_f32(&value, i, &prop, i, &mut PropertyAtomicGuard::none());
+        }
+        self.follow(value, prop, None);
+    }
+
+    /// Keep element `i` of a property in sync with a metric token
+    pub fn bind_metric(&self, prop: PropertyPtr, i: usize, token: &str) {
+        let value = self.token_prop(token);
+        assert_eq!(value.get_len(), 1);
+        copy_f32(&value, 0, &prop, i, &mut PropertyAtomicGuard::none());
+        self.follow(value, prop, Some(i));
+    }
+
+    /// Copy each changed element. `dst_idx` overrides the destination index.
+    fn follow(&self, value: PropertyPtr, prop: PropertyPtr, dst_idx: Option<usize>) {
+        let sub = value.subscribe_modify();
+        let task = self.ex.spawn(async move {
+            while let Ok((_, action, batch)) = sub.receive().await {
+                let ModifyAction::Set(i) = action else { continue };
+                let atom = &mut batch.spawn();
+                copy_f32(&value, i, &prop, dst_idx.unwrap_or(i), atom);
+            }
+        });
+        self.tasks.lock().push(task);
+    }
+}
+
+fn copy_f32(
+    src: &PropertyPtr,
+    src_idx: usize,
+    dst: &PropertyPtr,
+    dst_idx: usize,
+    atom: &mut PropertyAtomicGuard,
+) {
+    let Ok(PropertyValue::Float32(val)) = src.get_value(src_idx) else { return };
+    if let Err(e) = dst.set_f32(atom, Role::App, dst_idx, val) {
+        w!("Unable to apply theme token to {}: {e}", dst.name);
+    }
+}
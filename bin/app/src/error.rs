@@ -104,6 +104,9 @@ pub enum Error {
     #[error("Node has a sibling with this name")]
     NodeSiblingNameConflict = 27,
 
+    #[error("Resource not found")]
+    ResourceNotFound = 29,
+
     #[error("S-expr global not found")]
     SExprGlobalNotFound = 32,
 
@@ -139,6 +142,54 @@ pub enum Error {
 
     #[error("Unknown anim ID")]
     GfxUnknownAnimID = 46,
+
+    #[error("Vault key is invalid")]
+    VaultKeyInvalid = 47,
+
+    #[error("Vault encryption failed")]
+    VaultEncryptFailed = 48,
+
+    #[error("Vault decryption failed")]
+    VaultDecryptFailed = 49,
+
+    #[error("Identity label is invalid")]
+    IdentityInvalidLabel = 50,
+
+    #[error("Identity already exists")]
+    IdentityAlreadyExists = 51,
+
+    #[error("Identity not found")]
+    IdentityNotFound = 52,
+
+    #[error("Secure storage failed")]
+    SecureStorageFailed = 53,
+
+    #[error("Nothing to undo")]
+    NothingToUndo = 54,
+
+    #[error("Nothing to redo")]
+    NothingToRedo = 55,
+
+    #[error("Pairing code is too short")]
+    DevSyncWeakCode = 56,
+
+    #[error("Unknown sync category")]
+    DevSyncUnknownCategory = 57,
+
+    #[error("Sync record is invalid")]
+    DevSyncInvalidRecord = 58,
+
+    #[error("Wallet key is invalid")]
+    WalletKeyInvalid = 59,
+
+    #[error("Wallet initialization failed")]
+    WalletInitFailed = 60,
+
+    #[error("Request needs a valid auth token")]
+    ZmqUnauthorized = 61,
+
+    #[error("Image could not be decoded")]
+    ImageDecodeFailed = 62,
 }
 
 impl From<sled::Error> for Error {
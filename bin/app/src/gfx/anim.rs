@@ -31,7 +31,7 @@ use std::{
     },
 };
 
-use super::{BufferId, DrawCall, GfxDrawCall, TextureId};
+use super::{BufferId, DrawCall, GfxBuffer, GfxDrawCall, TextureId};
 
 macro_rules! t { ($($arg:tt)*) => { trace!(target: "gfx::anim", $($arg)*); } }
 
@@ -46,6 +46,11 @@ impl Frame {
     pub fn new(duration: u32, dc: DrawCall) -> Self {
         Self { duration, dc }
     }
+
+    #[cfg(test)]
+    pub(super) fn draw_call(&self) -> &DrawCall {
+        &self.dc
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -75,7 +80,7 @@ impl GfxSeqAnim {
         frame_idx: usize,
         frame: Frame,
         textures: &HashMap<TextureId, miniquad::TextureId>,
-        buffers: &HashMap<BufferId, miniquad::BufferId>,
+        buffers: &HashMap<BufferId, GfxBuffer>,
     ) {
         assert!(frame_idx < self.frames.len());
         let duration = std::time::Duration::from_millis(frame.duration as u64);
@@ -84,6 +89,11 @@ impl GfxSeqAnim {
         //t!("got frame {frame_idx}");
     }
 
+    /// All frames received so far
+    pub(super) fn frame_dcs(&self) -> impl Iterator<Item = &GfxDrawCall> {
+        self.frames.iter().flatten().map(|frame| &frame.dc)
+    }
+
     pub fn tick(&mut self) -> Option<GfxDrawCall> {
         //t!("tick");
         let elapsed = self.timer.elapsed();
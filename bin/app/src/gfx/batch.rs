@@ -0,0 +1,251 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Merges consecutive meshes into shared buffers so they go out in a single
+//! draw call.
+//!
+//! Meshes are drawn in scene order, so two neighbours can share a draw call
+//! as long as they use the same texture and the same view. The model offset
+//! (the draw cursor) is baked into the vertex positions on the CPU, which is
+//! why the backend keeps a copy of every buffer's contents.
+
+use miniquad::{BufferId, BufferSource, BufferType, BufferUsage, RenderingBackend, TextureId};
+use std::time::{Duration, Instant};
+
+use super::{Point, Rectangle, Vertex};
+
+macro_rules! d { ($($arg:tt)*) => { debug!(target: "gfx::batch", $($arg)*); } }
+
+/// Indices are u16
+const MAX_VERTS: usize = u16::MAX as usize + 1;
+/// Smallest stream buffer we allocate, in elements
+const MIN_CAPACITY: usize = 1024;
+/// How often the draw call stats are logged
+const STATS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Meshes collected since the last flush
+pub(super) struct MeshBatch {
+    /// `None` means the white texture
+    pub texture: Option<TextureId>,
+    pub view: Rectangle,
+    pub scale: f32,
+    pub verts: Vec<Vertex>,
+    pub indices: Vec<u16>,
+}
+
+impl MeshBatch {
+    pub fn new() -> Self {
+        Self {
+            texture: None,
+            view: Rectangle::zero(),
+            scale: 1.,
+            verts: Vec::with_capacity(MIN_CAPACITY),
+            indices: Vec::with_capacity(MIN_CAPACITY),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.verts.is_empty()
+    }
+
+    /// Whether a mesh drawn with this state can be appended without a flush
+    pub fn accepts(
+        &self,
+        texture: Option<TextureId>,
+        view: Rectangle,
+        scale: f32,
+        verts_len: usize,
+    ) -> bool {
+        self.is_empty() ||
+            (self.texture == texture &&
+                self.view == view &&
+                self.scale == scale &&
+                self.verts.len() + verts_len <= MAX_VERTS)
+    }
+
+    /// Append a mesh drawn at `offset`. Call [`Self::accepts()`] first.
+    pub fn push(
+        &mut self,
+        texture: Option<TextureId>,
+        view: Rectangle,
+        scale: f32,
+        verts: &[Vertex],
+        indices: &[u16],
+        offset: Point,
+    ) {
+        if self.is_empty() {
+            self.texture = texture;
+            self.view = view;
+            self.scale = scale;
+        }
+        let base = self.verts.len() as u16;
+        self.verts.extend(verts.iter().map(|vert| {
+            let mut vert = vert.clone();
+            vert.set_pos(&(vert.pos() + offset));
+            vert
+        }));
+        self.indices.extend(indices.iter().map(|idx| base + idx));
+    }
+
+    pub fn clear(&mut self) {
+        self.verts.clear();
+        self.indices.clear();
+    }
+}
+
+struct StreamBuffer {
+    vertex: BufferId,
+    index: BufferId,
+    vertex_cap: usize,
+    index_cap: usize,
+}
+
+/// Pool of dynamic buffers the batches get uploaded into.
+/// Each flush within a frame uses its own pair so the driver never has to
+/// wait on a buffer that is still queued for drawing.
+pub(super) struct StreamBuffers {
+    bufs: Vec<StreamBuffer>,
+    next: usize,
+}
+
+impl StreamBuffers {
+    pub fn new() -> Self {
+        Self { bufs: vec![], next: 0 }
+    }
+
+    /// Start reusing buffers from the beginning of the pool
+    pub fn reset(&mut self) {
+        self.next = 0;
+    }
+
+    pub fn upload(
+        &mut self,
+        ctx: &mut Box<dyn RenderingBackend>,
+        verts: &[Vertex],
+        indices: &[u16],
+    ) -> (BufferId, BufferId) {
+        if self.next == self.bufs.len() {
+            self.bufs.push(StreamBuffer {
+                vertex: Self::new_buffer::<Vertex>(ctx, BufferType::VertexBuffer, MIN_CAPACITY),
+                index: Self::new_buffer::<u16>(ctx, BufferType::IndexBuffer, MIN_CAPACITY),
+                vertex_cap: MIN_CAPACITY,
+                index_cap: MIN_CAPACITY,
+            });
+        }
+        let buf = &mut self.bufs[self.next];
+        self.next += 1;
+
+        if buf.vertex_cap < verts.len() {
+            ctx.delete_buffer(buf.vertex);
+            buf.vertex_cap = verts.len().next_power_of_two();
+            buf.vertex = Self::new_buffer::<Vertex>(ctx, BufferType::VertexBuffer, buf.vertex_cap);
+        }
+        if buf.index_cap < indices.len() {
+            ctx.delete_buffer(buf.index);
+            buf.index_cap = indices.len().next_power_of_two();
+            buf.index = Self::new_buffer::<u16>(ctx, BufferType::IndexBuffer, buf.index_cap);
+        }
+
+        ctx.buffer_update(buf.vertex, BufferSource::slice(verts));
+        ctx.buffer_update(buf.index, BufferSource::slice(indices));
+        (buf.vertex, buf.index)
+    }
+
+    fn new_buffer<T>(
+        ctx: &mut Box<dyn RenderingBackend>,
+        typ: BufferType,
+        capacity: usize,
+    ) -> BufferId {
+        ctx.new_buffer(typ, BufferUsage::Stream, BufferSource::empty::<T>(capacity))
+    }
+}
+
+/// Counts how many meshes were submitted versus how many draw calls
+/// actually reached the backend.
+pub(super) struct BatchStats {
+    pub meshes: usize,
+    pub draws: usize,
+    frames: usize,
+    since: Instant,
+}
+
+impl BatchStats {
+    pub fn new() -> Self {
+        Self { meshes: 0, draws: 0, frames: 0, since: Instant::now() }
+    }
+
+    pub fn end_frame(&mut self) {
+        self.frames += 1;
+        if self.since.elapsed() < STATS_INTERVAL {
+            return
+        }
+        let frames = self.frames as f32;
+        d!(
+            "{:.1} meshes in {:.1} draw calls per frame over {} frames",
+            self.meshes as f32 / frames,
+            self.draws as f32 / frames,
+            self.frames
+        );
+        *self = Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quad(x: f32) -> Vec<Vertex> {
+        [[x, 0.], [x + 1., 0.], [x, 1.], [x + 1., 1.]]
+            .into_iter()
+            .map(|pos| Vertex { pos, color: [1., 1., 1., 1.], uv: [0., 0.] })
+            .collect()
+    }
+
+    #[test]
+    fn merge_meshes() {
+        let view = Rectangle::new(0., 0., 100., 100.);
+        let mut batch = MeshBatch::new();
+        let indices = [0, 1, 2, 2, 1, 3];
+
+        assert!(batch.accepts(None, view, 1., 4));
+        batch.push(None, view, 1., &quad(0.), &indices, Point::zero());
+        assert!(batch.accepts(None, view, 1., 4));
+        batch.push(None, view, 1., &quad(0.), &indices, Point::from([10., 5.]));
+
+        assert_eq!(batch.verts.len(), 8);
+        assert_eq!(batch.indices, [0, 1, 2, 2, 1, 3, 4, 5, 6, 6, 5, 7]);
+        assert_eq!(batch.verts[4].pos, [10., 5.]);
+        assert_eq!(batch.verts[7].pos, [11., 6.]);
+    }
+
+    #[test]
+    fn incompatible_meshes() {
+        let view = Rectangle::new(0., 0., 100., 100.);
+        let mut batch = MeshBatch::new();
+        batch.push(None, view, 1., &quad(0.), &[0, 1, 2], Point::zero());
+
+        // Different view or scale means a new draw call
+        assert!(!batch.accepts(None, Rectangle::new(0., 0., 50., 50.), 1., 4));
+        assert!(!batch.accepts(None, view, 2., 4));
+        // Indices would overflow u16
+        assert!(!batch.accepts(None, view, 1., MAX_VERTS));
+
+        batch.clear();
+        assert!(batch.accepts(None, view, 2., MAX_VERTS));
+    }
+}
@@ -0,0 +1,221 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Works out which part of the screen has to be redrawn.
+//!
+//! Property changes reach the backend as replaced draw calls. Each frame
+//! the scene is measured to find the screen area every draw call covers,
+//! and the damage is the old plus new area of the replaced ones. When
+//! nothing changed the frame is skipped.
+
+use std::collections::{HashMap, HashSet};
+
+use super::{anim::GfxSeqAnim, AnimId, DcId, GfxDrawCall, GfxDrawInstruction, Point, Rectangle};
+
+/// Number of buffers the window system flips between. After a change, the
+/// frame gets presented this many times so none of them shows stale content.
+const SWAP_CHAIN_LEN: u8 = 2;
+
+pub(super) enum FrameDamage {
+    /// Nothing to do, don't even commit the frame
+    Skip,
+    /// Scene is unchanged but the last frame must be shown again
+    Present,
+    /// Redraw this region, in physical pixels
+    Redraw(Rectangle),
+}
+
+pub(super) struct Damage {
+    /// Everything must be redrawn, e.g. after a resize
+    full: bool,
+    /// Draw calls replaced since the last frame
+    dirty_dcs: HashSet<DcId>,
+    /// Screen area covered by each draw call including its children
+    pub bounds: HashMap<DcId, Rectangle>,
+    /// Area of animations on screen, which get redrawn every frame
+    anim_area: Option<Rectangle>,
+    /// Animation frames changed so their area must be measured again
+    remeasure: bool,
+    present_frames: u8,
+}
+
+impl Damage {
+    pub fn new() -> Self {
+        Self {
+            full: true,
+            dirty_dcs: HashSet::new(),
+            bounds: HashMap::new(),
+            anim_area: None,
+            remeasure: false,
+            present_frames: 0,
+        }
+    }
+
+    pub fn mark_full(&mut self) {
+        self.full = true;
+    }
+
+    pub fn mark_dc(&mut self, dc_key: DcId) {
+        self.dirty_dcs.insert(dc_key);
+    }
+
+    pub fn mark_anim(&mut self) {
+        self.remeasure = true;
+    }
+
+    /// Whether animations are on screen, so frames must keep coming
+    pub fn has_anims(&self) -> bool {
+        self.anim_area.is_some()
+    }
+
+    pub fn take(
+        &mut self,
+        draw_calls: &HashMap<DcId, GfxDrawCall>,
+        anims: &HashMap<AnimId, GfxSeqAnim>,
+        screen: Rectangle,
+    ) -> FrameDamage {
+        let mut region = None;
+
+        if self.full || self.remeasure || !self.dirty_dcs.is_empty() {
+            let mut bounds = HashMap::new();
+            let mut anim_area = None;
+            let state = MeasureState { view: screen, scale: 1., cursor: Point::zero() };
+            let mut measure =
+                Measure { draw_calls, anims, bounds: &mut bounds, anim_area: &mut anim_area };
+            if let Some(area) = measure.draw_call(&draw_calls[&0], state) {
+                bounds.insert(0, area);
+            }
+
+            for dc_key in self.dirty_dcs.drain() {
+                for area in [self.bounds.get(&dc_key), bounds.get(&dc_key)].into_iter().flatten() {
+                    region = union(region, area);
+                }
+            }
+            if self.full {
+                region = Some(screen);
+            }
+            self.full = false;
+            self.remeasure = false;
+            self.bounds = bounds;
+            self.anim_area = anim_area;
+        }
+
+        if let Some(anim_area) = &self.anim_area {
+            region = union(region, anim_area);
+        }
+
+        // Round outwards to whole pixels
+        let region = region.and_then(|area| {
+            let x = area.x.floor();
+            let y = area.y.floor();
+            let area = Rectangle::new(x, y, (area.rhs() - x).ceil(), (area.bhs() - y).ceil());
+            screen.clip(&area)
+        });
+
+        match region {
+            Some(region) if region.w > 0. && region.h > 0. => {
+                self.present_frames = SWAP_CHAIN_LEN - 1;
+                FrameDamage::Redraw(region)
+            }
+            _ if self.present_frames > 0 => {
+                self.present_frames -= 1;
+                FrameDamage::Present
+            }
+            _ => FrameDamage::Skip,
+        }
+    }
+}
+
+fn union(region: Option<Rectangle>, area: &Rectangle) -> Option<Rectangle> {
+    match region {
+        Some(region) => Some(region.union(area)),
+        None => Some(*area),
+    }
+}
+
+#[derive(Clone, Copy)]
+struct MeasureState {
+    view: Rectangle,
+    scale: f32,
+    cursor: Point,
+}
+
+/// Walks the draw calls the same way `RenderContext` does, without drawing
+struct Measure<'a> {
+    draw_calls: &'a HashMap<DcId, GfxDrawCall>,
+    anims: &'a HashMap<AnimId, GfxSeqAnim>,
+    bounds: &'a mut HashMap<DcId, Rectangle>,
+    anim_area: &'a mut Option<Rectangle>,
+}
+
+impl Measure<'_> {
+    fn draw_call(&mut self, draw_call: &GfxDrawCall, old: MeasureState) -> Option<Rectangle> {
+        let (draw_calls, anims) = (self.draw_calls, self.anims);
+        let mut state = old;
+        let mut area = None;
+
+        for instr in &draw_call.instrs {
+            match instr {
+                GfxDrawInstruction::SetScale(scale) => {
+                    state.scale = *scale;
+                    state.view.w /= state.scale;
+                    state.view.h /= state.scale;
+                }
+                GfxDrawInstruction::Move(off) => state.cursor += *off,
+                GfxDrawInstruction::SetPos(pos) => state.cursor = old.cursor + *pos,
+                GfxDrawInstruction::ApplyView(view) => {
+                    state.view =
+                        old.view.clip(&(*view + state.cursor)).unwrap_or(Rectangle::zero());
+                    state.cursor = Point::zero();
+                }
+                GfxDrawInstruction::Draw(mesh) => {
+                    let Some(first) = mesh.verts.first() else { continue };
+                    let mut mesh_area = Rectangle::new(first.pos[0], first.pos[1], 0., 0.);
+                    for vert in mesh.verts.iter() {
+                        let pos = Rectangle::new(vert.pos[0], vert.pos[1], 0., 0.);
+                        mesh_area = mesh_area.union(&pos);
+                    }
+                    let mesh_area = mesh_area + state.view.pos() + state.cursor;
+                    if let Some(mesh_area) = state.view.clip(&mesh_area) {
+                        area = union(area, &(mesh_area * state.scale));
+                    }
+                }
+                GfxDrawInstruction::Animation(anim_id) => {
+                    let Some(anim) = anims.get(anim_id) else { continue };
+                    for frame_dc in anim.frame_dcs() {
+                        if let Some(frame_area) = self.draw_call(frame_dc, state) {
+                            area = union(area, &frame_area);
+                            *self.anim_area = union(*self.anim_area, &frame_area);
+                        }
+                    }
+                }
+                GfxDrawInstruction::EnableDebug => {}
+            }
+        }
+
+        for dc_key in &draw_call.dcs {
+            let Some(child) = draw_calls.get(dc_key) else { continue };
+            if let Some(child_area) = self.draw_call(child, state) {
+                self.bounds.insert(*dc_key, child_area);
+                area = union(area, &child_area);
+            }
+        }
+
+        area
+    }
+}
@@ -0,0 +1,389 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Stand-in for the miniquad `Stage` so the UI can run without a display.
+//!
+//! Methods sent through the [`RenderApi`](super::RenderApi) are applied to
+//! plain maps instead of the GPU. [`HeadlessBackend::capture()`] walks the
+//! draw call tree the same way the renderer does and returns every mesh
+//! with its position on screen, so tests can make assertions on what would
+//! have been drawn.
+
+use std::collections::HashMap;
+
+use super::{
+    anim::Frame as AnimFrame, AnimId, BufferId, DcId, DebugTag, Dimension, DrawCall,
+    DrawInstruction, EpochIndex, GraphicsMethod, Point, Rectangle, TextureId, Timestamp, Vertex,
+};
+use crate::prop::BatchGuardId;
+
+macro_rules! w { ($($arg:tt)*) => { warn!(target: "gfx::headless", $($arg)*); } }
+
+enum HeadlessBuffer {
+    Vertex(Vec<Vertex>),
+    Index(Vec<u16>),
+}
+
+/// A mesh as it would be drawn, in physical pixels
+#[derive(Clone, Debug)]
+pub struct CapturedMesh {
+    /// Key of the draw call containing the mesh
+    pub dc_key: DcId,
+    /// Debug string of that draw call
+    pub dc_name: &'static str,
+    /// Tag given when the vertex buffer was allocated
+    pub tag: DebugTag,
+    /// Vertices moved to their screen position
+    pub verts: Vec<Vertex>,
+    pub indices: Vec<u16>,
+    pub texture: Option<TextureId>,
+    /// Area the mesh is clipped to
+    pub view: Rectangle,
+}
+
+impl CapturedMesh {
+    /// Bounding box of the vertices
+    pub fn rect(&self) -> Rectangle {
+        let mut verts = self.verts.iter().map(|vert| vert.pos());
+        let Some(first) = verts.next() else { return Rectangle::zero() };
+        let (mut min, mut max) = (first, first);
+        for pos in verts {
+            min = Point::new(min.x.min(pos.x), min.y.min(pos.y));
+            max = Point::new(max.x.max(pos.x), max.y.max(pos.y));
+        }
+        Rectangle::new(min.x, min.y, max.x - min.x, max.y - min.y)
+    }
+
+    /// Part of the mesh which ends up on screen
+    pub fn visible_rect(&self) -> Option<Rectangle> {
+        self.rect().clip(&self.view)
+    }
+}
+
+/// All meshes of a frame in draw order
+#[derive(Clone, Debug, Default)]
+pub struct CapturedFrame {
+    pub meshes: Vec<CapturedMesh>,
+}
+
+impl CapturedFrame {
+    pub fn with_tag<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = &'a CapturedMesh> + 'a {
+        self.meshes.iter().filter(move |mesh| mesh.tag == Some(tag))
+    }
+
+    pub fn in_dc<'a>(&'a self, dc_name: &'a str) -> impl Iterator<Item = &'a CapturedMesh> + 'a {
+        self.meshes.iter().filter(move |mesh| mesh.dc_name == dc_name)
+    }
+
+    /// Union of the on screen area of every mesh with this tag
+    pub fn bounds(&self, tag: &str) -> Option<Rectangle> {
+        self.with_tag(tag)
+            .filter_map(|mesh| mesh.visible_rect())
+            .reduce(|acc, rect| acc.union(&rect))
+    }
+}
+
+pub struct HeadlessBackend {
+    method_recv: async_channel::Receiver<(EpochIndex, GraphicsMethod)>,
+    screen_size: Dimension,
+
+    textures: HashMap<TextureId, (u16, u16)>,
+    buffers: HashMap<BufferId, HeadlessBuffer>,
+    anims: HashMap<AnimId, Vec<Option<AnimFrame>>>,
+    draw_calls: HashMap<DcId, (Timestamp, DrawCall)>,
+    batches: HashMap<BatchGuardId, Vec<(Timestamp, Vec<(DcId, DrawCall)>)>>,
+}
+
+impl HeadlessBackend {
+    pub fn new(
+        method_recv: async_channel::Receiver<(EpochIndex, GraphicsMethod)>,
+        screen_size: Dimension,
+    ) -> Self {
+        Self {
+            method_recv,
+            screen_size,
+            textures: HashMap::new(),
+            buffers: HashMap::new(),
+            anims: HashMap::new(),
+            draw_calls: HashMap::new(),
+            batches: HashMap::new(),
+        }
+    }
+
+    pub fn set_screen_size(&mut self, screen_size: Dimension) {
+        self.screen_size = screen_size;
+    }
+
+    /// Forget the scene but keep resources, which may still be referenced
+    pub fn clear_draw_calls(&mut self) {
+        self.draw_calls.clear();
+        self.batches.clear();
+    }
+
+    /// Apply all pending methods, returning how many there were
+    pub fn drain(&mut self) -> usize {
+        let mut count = 0;
+        while let Ok((_epoch, method)) = self.method_recv.try_recv() {
+            self.process_method(method);
+            count += 1;
+        }
+        count
+    }
+
+    pub fn texture_size(&self, texture: TextureId) -> Option<(u16, u16)> {
+        self.textures.get(&texture).copied()
+    }
+
+    pub fn draw_call(&self, key: DcId) -> Option<&DrawCall> {
+        self.draw_calls.get(&key).map(|(_, dc)| dc)
+    }
+
+    fn process_method(&mut self, method: GraphicsMethod) {
+        match method {
+            GraphicsMethod::NewTexture((width, height, _, gtex_id, _)) => {
+                self.textures.insert(gtex_id, (width, height));
+            }
+            GraphicsMethod::DeleteTexture((gtex_id, _)) => {
+                self.textures.remove(&gtex_id);
+            }
+            GraphicsMethod::NewVertexBuffer((verts, gbuff_id, _)) => {
+                self.buffers.insert(gbuff_id, HeadlessBuffer::Vertex(verts));
+            }
+            GraphicsMethod::NewIndexBuffer((indices, gbuff_id, _)) => {
+                self.buffers.insert(gbuff_id, HeadlessBuffer::Index(indices));
+            }
+            GraphicsMethod::DeleteBuffer((gbuff_id, _, _)) => {
+                self.buffers.remove(&gbuff_id);
+            }
+            GraphicsMethod::NewSeqAnim { id, frames_len, .. } => {
+                self.anims.insert(id, vec![None; frames_len]);
+            }
+            GraphicsMethod::UpdateSeqAnim { id, frame_idx, frame, .. } => {
+                if let Some(frames) = self.anims.get_mut(&id) {
+                    frames[frame_idx] = Some(frame);
+                }
+            }
+            GraphicsMethod::DeleteSeqAnim((ganim_id, _)) => {
+                self.anims.remove(&ganim_id);
+            }
+            GraphicsMethod::ReplaceGfxDrawCalls { batch_id, timest, dcs } => {
+                self.batches.get_mut(&batch_id).unwrap().push((timest, dcs));
+            }
+            GraphicsMethod::StartBatch((batch_id, _)) => {
+                if self.batches.insert(batch_id, vec![]).is_some() {
+                    panic!("Batch {batch_id} already open!")
+                }
+            }
+            GraphicsMethod::EndBatch(batch_id) => {
+                for (timest, dcs) in self.batches.remove(&batch_id).unwrap() {
+                    self.replace_draw_calls(timest, dcs);
+                }
+            }
+        }
+    }
+
+    /// Same rules as the real backend: older draw calls never replace newer ones
+    fn replace_draw_calls(&mut self, timest: Timestamp, dcs: Vec<(DcId, DrawCall)>) {
+        for (key, dc) in dcs {
+            match self.draw_calls.get_mut(&key) {
+                Some(old) if old.0 >= timest => {}
+                Some(old) => *old = (timest, dc),
+                None => {
+                    self.draw_calls.insert(key, (timest, dc));
+                }
+            }
+        }
+    }
+
+    /// Walk the scene from the root draw call and collect every mesh
+    pub fn capture(&self) -> CapturedFrame {
+        let mut walk = Walk {
+            backend: self,
+            frame: CapturedFrame::default(),
+            scale: 1.,
+            view: Rectangle::new(0., 0., self.screen_size.w, self.screen_size.h),
+            cursor: Point::zero(),
+        };
+        if let Some((_, root)) = self.draw_calls.get(&0) {
+            walk.draw_call(0, root);
+        }
+        walk.frame
+    }
+}
+
+/// Mirrors `RenderContext::draw_call()` but records meshes instead of drawing them
+struct Walk<'a> {
+    backend: &'a HeadlessBackend,
+    frame: CapturedFrame,
+    scale: f32,
+    view: Rectangle,
+    cursor: Point,
+}
+
+impl Walk<'_> {
+    fn draw_call(&mut self, key: DcId, dc: &DrawCall) {
+        let old_scale = self.scale;
+        let old_view = self.view;
+        let old_cursor = self.cursor;
+
+        for instr in &dc.instrs {
+            match instr {
+                DrawInstruction::SetScale(scale) => {
+                    self.scale = *scale;
+                    self.view.w /= self.scale;
+                    self.view.h /= self.scale;
+                }
+                DrawInstruction::Move(off) => self.cursor += *off,
+                DrawInstruction::SetPos(pos) => self.cursor = old_cursor + *pos,
+                DrawInstruction::ApplyView(view) => {
+                    self.view = (*view + self.cursor).clip(&old_view).unwrap_or(Rectangle::zero());
+                    self.cursor = Point::zero();
+                }
+                DrawInstruction::Draw(mesh) => self.mesh(key, dc.debug_str, mesh),
+                DrawInstruction::Animation(anim_id) => {
+                    // Frame timing doesn't matter here, take the first one available
+                    let backend = self.backend;
+                    let frame = backend
+                        .anims
+                        .get(anim_id)
+                        .and_then(|frames| frames.iter().flatten().next());
+                    if let Some(frame) = frame {
+                        self.draw_call(key, frame.draw_call());
+                    }
+                }
+                DrawInstruction::EnableDebug => {}
+            }
+        }
+
+        let backend = self.backend;
+        let mut children: Vec<_> = dc
+            .dcs
+            .iter()
+            .filter_map(|key| backend.draw_calls.get(key).map(|(_, dc)| (*key, dc)))
+            .collect();
+        children.sort_by_key(|(_, dc)| dc.z_index);
+        for (key, child) in children {
+            self.draw_call(key, child);
+        }
+
+        self.scale = old_scale;
+        self.view = old_view;
+        self.cursor = old_cursor;
+    }
+
+    fn mesh(&mut self, dc_key: DcId, dc_name: &'static str, mesh: &super::DrawMesh) {
+        let buffers = &self.backend.buffers;
+        let (Some(HeadlessBuffer::Vertex(verts)), Some(HeadlessBuffer::Index(indices))) =
+            (buffers.get(&mesh.vertex_buffer.id), buffers.get(&mesh.index_buffer.id))
+        else {
+            w!("Mesh in draw call {dc_key} ({dc_name}) has missing buffers");
+            return
+        };
+
+        let origin = self.view.pos() + self.cursor;
+        let verts = verts
+            .iter()
+            .map(|vert| {
+                let mut vert = vert.clone();
+                vert.set_pos(&((vert.pos() + origin) * self.scale));
+                vert
+            })
+            .collect();
+
+        self.frame.meshes.push(CapturedMesh {
+            dc_key,
+            dc_name,
+            tag: mesh.vertex_buffer.tag,
+            verts,
+            indices: indices[..mesh.num_elements as usize].to_vec(),
+            texture: mesh.texture.as_ref().map(|texture| texture.id),
+            view: self.view * self.scale,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        gfx::{gfxtag, RenderApi},
+        mesh::{MeshBuilder, COLOR_RED},
+    };
+
+    fn make_backend() -> (RenderApi, HeadlessBackend) {
+        let (method_send, method_recv) = async_channel::unbounded();
+        let render_api = RenderApi::new(method_send);
+        let backend = HeadlessBackend::new(method_recv, Dimension::from([800., 600.]));
+        (render_api, backend)
+    }
+
+    fn make_box(render_api: &RenderApi, rect: [f32; 4]) -> DrawInstruction {
+        let mut mesh = MeshBuilder::new(gfxtag!("test_box"));
+        mesh.draw_filled_box(&Rectangle::from(rect), COLOR_RED);
+        DrawInstruction::Draw(mesh.alloc(render_api).draw_untextured())
+    }
+
+    #[test]
+    fn capture_applies_scale_and_offsets() {
+        let (render_api, mut backend) = make_backend();
+
+        let atom = render_api.make_guard(gfxtag!("test"));
+        let root = DrawCall::new(vec![DrawInstruction::SetScale(2.)], vec![1], 0, "root");
+        let child = DrawCall::new(
+            vec![
+                DrawInstruction::ApplyView(Rectangle::new(10., 10., 100., 100.)),
+                DrawInstruction::Move(Point::new(5., 0.)),
+                make_box(&render_api, [0., 0., 20., 10.]),
+            ],
+            vec![],
+            0,
+            "child",
+        );
+        render_api.replace_draw_calls(atom.batch_id, 1, vec![(0, root), (1, child)]);
+        drop(atom);
+        assert!(backend.drain() > 0);
+
+        let frame = backend.capture();
+        assert_eq!(frame.meshes.len(), 1);
+        let mesh = &frame.meshes[0];
+        assert_eq!(mesh.dc_name, "child");
+        assert_eq!(mesh.rect(), Rectangle::new(30., 20., 40., 20.));
+        assert_eq!(mesh.view, Rectangle::new(20., 20., 200., 200.));
+        assert_eq!(frame.bounds("test_box"), Some(Rectangle::new(30., 20., 40., 20.)));
+    }
+
+    #[test]
+    fn stale_draw_calls_are_ignored() {
+        let (render_api, mut backend) = make_backend();
+
+        let atom = render_api.make_guard(gfxtag!("test"));
+        let new = DrawCall::new(vec![make_box(&render_api, [0., 0., 1., 1.])], vec![], 0, "new");
+        render_api.replace_draw_calls(atom.batch_id, 2, vec![(0, new)]);
+        let old = DrawCall::new(vec![], vec![], 0, "old");
+        render_api.replace_draw_calls(atom.batch_id, 1, vec![(0, old)]);
+
+        // Nothing is applied until the batch ends
+        backend.drain();
+        assert!(backend.draw_call(0).is_none());
+
+        drop(atom);
+        backend.drain();
+        assert_eq!(backend.draw_call(0).unwrap().debug_str, "new");
+        assert_eq!(backend.capture().in_dc("new").count(), 1);
+    }
+}
@@ -157,7 +157,7 @@ impl Mul<f32> for Point {
     }
 }
 
-#[derive(Clone, Copy, SerialEncodable, SerialDecodable)]
+#[derive(Clone, Copy, PartialEq, SerialEncodable, SerialDecodable)]
 pub struct Rectangle {
     pub x: f32,
     pub y: f32,
@@ -273,6 +273,15 @@ impl Rectangle {
     pub fn includes(&self, child: &Self) -> bool {
         self.contains(child.pos()) && self.contains(child.corner())
     }
+
+    /// Smallest rectangle covering both
+    pub fn union(&self, other: &Self) -> Self {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let rhs = self.rhs().max(other.rhs());
+        let bhs = self.bhs().max(other.bhs());
+        Self { x, y, w: rhs - x, h: bhs - y }
+    }
 }
 
 impl From<[f32; 4]> for Rectangle {
@@ -25,9 +25,10 @@ use log::debug;
 use miniquad::native::egl;
 use miniquad::{
     conf, window, Backend, Bindings, BlendFactor, BlendState, BlendValue, BufferLayout,
-    BufferSource, BufferType, BufferUsage, Equation, EventHandler, KeyCode, KeyMods, MouseButton,
-    PassAction, Pipeline, PipelineParams, RenderingBackend, ShaderMeta, ShaderSource, TouchPhase,
-    UniformDesc, UniformType, VertexAttribute, VertexFormat,
+    BufferSource, BufferType, BufferUsage, Equation, EventHandler, FilterMode, KeyCode, KeyMods,
+    MouseButton, PassAction, Pipeline, PipelineParams, RenderPass, RenderingBackend, ShaderMeta,
+    ShaderSource, TextureFormat, TextureParams, TouchPhase, UniformDesc, UniformType,
+    VertexAttribute, VertexFormat,
 };
 use parking_lot::Mutex as SyncMutex;
 use std::{
@@ -44,7 +45,13 @@ use std::{
 
 pub mod anim;
 use anim::{Frame as AnimFrame, GfxSeqAnim};
+mod batch;
+use batch::{BatchStats, MeshBatch, StreamBuffers};
+mod damage;
+use damage::{Damage, FrameDamage};
 mod favico;
+#[cfg(test)]
+pub mod headless;
 mod linalg;
 pub use linalg::{Dimension, Point, Rectangle};
 mod shader;
@@ -61,6 +68,11 @@ use crate::{
 const DEBUG_RENDER: bool = false;
 const DEBUG_GFXAPI: bool = false;
 const DEBUG_TRAX: bool = false;
+/// Disable to draw every mesh with its own draw call, for comparing the stats
+const BATCH_MESHES: bool = true;
+/// Keep the last frame in an offscreen canvas and only redraw what changed.
+/// When disabled, every frame with changes is drawn in full.
+const PARTIAL_REDRAW: bool = true;
 
 #[macro_export]
 macro_rules! gfxtag {
@@ -364,19 +376,26 @@ impl DrawMesh {
     fn compile(
         self,
         textures: &HashMap<TextureId, miniquad::TextureId>,
-        buffers: &HashMap<BufferId, miniquad::BufferId>,
+        buffers: &HashMap<BufferId, GfxBuffer>,
         debug_str: &'static str,
     ) -> Option<GfxDrawMesh> {
-        let vertex_buffer_id = self.vertex_buffer.id;
-        let index_buffer_id = self.index_buffer.id;
+        let vertex_buffer = Self::try_get_buffer(buffers, self.vertex_buffer.id, debug_str)?;
+        let index_buffer = Self::try_get_buffer(buffers, self.index_buffer.id, debug_str)?;
+        let (GfxBufferData::Vertex(verts), GfxBufferData::Index(indices)) =
+            (vertex_buffer.data, index_buffer.data)
+        else {
+            panic!("Mismatched buffer types in mesh, debug={debug_str}")
+        };
         let _buffers_keep_alive = [self.vertex_buffer, self.index_buffer];
         let texture = match self.texture {
             Some(gfx_texture) => Self::try_get_texture(textures, gfx_texture, debug_str),
             None => None,
         };
         Some(GfxDrawMesh {
-            vertex_buffer: Self::try_get_buffer(buffers, vertex_buffer_id, debug_str)?,
-            index_buffer: Self::try_get_buffer(buffers, index_buffer_id, debug_str)?,
+            vertex_buffer: vertex_buffer.id,
+            index_buffer: index_buffer.id,
+            verts,
+            indices,
             _buffers_keep_alive,
             texture,
             num_elements: self.num_elements,
@@ -404,20 +423,20 @@ impl DrawMesh {
     }
 
     fn try_get_buffer(
-        buffers: &HashMap<BufferId, miniquad::BufferId>,
+        buffers: &HashMap<BufferId, GfxBuffer>,
         gfx_buffer_id: BufferId,
         debug_str: &'static str,
-    ) -> Option<miniquad::BufferId> {
-        let Some(mq_buffer_id) = buffers.get(&gfx_buffer_id) else {
+    ) -> Option<GfxBuffer> {
+        let Some(buffer) = buffers.get(&gfx_buffer_id) else {
             error!(target: "gfx", "Serious error: missing buffer ID={gfx_buffer_id}, debug={debug_str}");
             error!(target: "gfx", "Dumping buffers:");
-            for (gfx_buffer_id, buffer_id) in buffers {
-                error!(target: "gfx", "{gfx_buffer_id} => {buffer_id:?}");
+            for (gfx_buffer_id, buffer) in buffers {
+                error!(target: "gfx", "{gfx_buffer_id} => {:?}", buffer.id);
             }
 
             panic!("Missing buffer ID={gfx_buffer_id}")
         };
-        Some(*mq_buffer_id)
+        Some(buffer.clone())
     }
 }
 
@@ -473,7 +492,7 @@ impl DrawInstruction {
     fn compile(
         self,
         textures: &HashMap<TextureId, miniquad::TextureId>,
-        buffers: &HashMap<BufferId, miniquad::BufferId>,
+        buffers: &HashMap<BufferId, GfxBuffer>,
         debug_str: &'static str,
     ) -> Option<GfxDrawInstruction> {
         let instr = match self {
@@ -512,7 +531,7 @@ impl DrawCall {
     fn compile(
         self,
         textures: &HashMap<TextureId, miniquad::TextureId>,
-        buffers: &HashMap<BufferId, miniquad::BufferId>,
+        buffers: &HashMap<BufferId, GfxBuffer>,
         timest: Timestamp,
     ) -> Option<GfxDrawCall> {
         Some(GfxDrawCall {
@@ -528,16 +547,43 @@ impl DrawCall {
     }
 }
 
-#[derive(Clone, Debug)]
+/// Contents of a buffer as uploaded to the backend
+#[derive(Clone)]
+enum GfxBufferData {
+    Vertex(Arc<[Vertex]>),
+    Index(Arc<[u16]>),
+}
+
+/// The CPU side copy is needed to merge meshes into batches
+#[derive(Clone)]
+struct GfxBuffer {
+    id: miniquad::BufferId,
+    data: GfxBufferData,
+}
+
+#[derive(Clone)]
 struct GfxDrawMesh {
     vertex_buffer: miniquad::BufferId,
     index_buffer: miniquad::BufferId,
+    verts: Arc<[Vertex]>,
+    indices: Arc<[u16]>,
     /// Keeps the buffers alive for the duration of this draw call
     _buffers_keep_alive: [ManagedBufferPtr; 2],
     texture: Option<(ManagedTexturePtr, miniquad::TextureId)>,
     num_elements: i32,
 }
 
+impl std::fmt::Debug for GfxDrawMesh {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GfxDrawMesh")
+            .field("vertex_buffer", &self.vertex_buffer)
+            .field("index_buffer", &self.index_buffer)
+            .field("texture", &self.texture)
+            .field("num_elements", &self.num_elements)
+            .finish()
+    }
+}
+
 #[derive(Debug, Clone)]
 enum GfxDrawInstruction {
     SetScale(f32),
@@ -568,6 +614,17 @@ struct RenderContext<'a> {
     cursor: Point,
 
     anims: &'a mut HashMap<AnimId, GfxSeqAnim>,
+
+    batch: &'a mut MeshBatch,
+    stream_bufs: &'a mut StreamBuffers,
+    stats: &'a mut BatchStats,
+    /// Last viewport and scissor sent to the backend, in physical pixels
+    viewport: Option<([i32; 4], [i32; 4])>,
+
+    /// Only this region gets drawn, in physical pixels
+    damage: Rectangle,
+    /// Screen area of each draw call, used to skip the ones outside the damage
+    bounds: &'a HashMap<DcId, Rectangle>,
 }
 
 impl<'a> RenderContext<'a> {
@@ -578,15 +635,39 @@ impl<'a> RenderContext<'a> {
         if DEBUG_TRAX {
             get_trax().lock().set_curr(0);
         }
+        self.clear_damage();
         self.draw_call(&self.draw_calls[&0], 0, DEBUG_RENDER);
+        self.flush();
         if DEBUG_RENDER {
             debug!(target: "gfx", "RenderContext::draw() [DONE]");
         }
     }
 
-    fn apply_view(&mut self) {
+    /// The canvas keeps the previous frame, so wipe the area about to be redrawn
+    fn clear_damage(&mut self) {
+        let black = [0., 0., 0., 1.];
+        let verts = [self.damage.pos(), self.damage.top_right(), self.damage.bot_left()]
+            .into_iter()
+            .chain([self.damage.corner()])
+            .map(|pos| Vertex { pos: pos.as_arr(), color: black, uv: [0., 0.] })
+            .collect::<Vec<_>>();
+        self.batch.push(None, self.view, self.scale, &verts, &[0, 2, 1, 1, 2, 3], Point::zero());
+    }
+
+    /// Viewport and model are only sent to the backend right before drawing,
+    /// so moving around without drawing anything costs nothing.
+    /// Returns false when the view is empty and nothing should be drawn.
+    fn apply_state(&mut self, view: Rectangle, scale: f32, cursor: Point) -> bool {
+        if !self.apply_view(view, scale) {
+            return false
+        }
+        self.apply_model(view, cursor);
+        true
+    }
+
+    fn apply_view(&mut self, view: Rectangle, scale: f32) -> bool {
         // Actual physical view
-        let view = self.view * self.scale;
+        let view = view * scale;
         let (_, screen_height) = window::screen_size();
 
         let view_x = view.x.round() as i32;
@@ -597,22 +678,40 @@ impl<'a> RenderContext<'a> {
 
         // OpenGL does not like negative values here
         if view_w <= 0 || view_h <= 0 {
-            return
+            return false
         }
 
+        // Pixels outside the damage are left untouched
+        let Some(clip) = self.damage.clip(&view) else { return false };
+        let clip_x = clip.x.round() as i32;
+        let clip_y = (screen_height - (clip.y + clip.h)).round() as i32;
+        let clip_w = clip.w.round() as i32;
+        let clip_h = clip.h.round() as i32;
+        if clip_w <= 0 || clip_h <= 0 {
+            return false
+        }
+
+        let viewport = [view_x, view_y, view_w, view_h];
+        let scissor = [clip_x, clip_y, clip_w, clip_h];
+        if self.viewport == Some((viewport, scissor)) {
+            return true
+        }
+        self.viewport = Some((viewport, scissor));
+
         if DEBUG_RENDER {
             debug!(target: "gfx", "=> viewport {view_x} {view_y} {view_w} {view_h}");
         }
         self.ctx.apply_viewport(view_x, view_y, view_w, view_h);
-        self.ctx.apply_scissor_rect(view_x, view_y, view_w, view_h);
+        self.ctx.apply_scissor_rect(clip_x, clip_y, clip_w, clip_h);
+        true
     }
 
-    fn apply_model(&mut self) {
-        let off_x = self.cursor.x / self.view.w;
-        let off_y = self.cursor.y / self.view.h;
+    fn apply_model(&mut self, view: Rectangle, cursor: Point) {
+        let off_x = cursor.x / view.w;
+        let off_y = cursor.y / view.h;
 
-        let scale_w = 1. / self.view.w;
-        let scale_h = 1. / self.view.h;
+        let scale_w = 1. / view.w;
+        let scale_h = 1. / view.h;
 
         let model = glam::Mat4::from_translation(glam::Vec3::new(off_x, off_y, 0.)) *
             glam::Mat4::from_scale(glam::Vec3::new(scale_w, scale_h, 1.));
@@ -650,7 +749,6 @@ impl<'a> RenderContext<'a> {
                             self.cursor, self.scale, self.view
                         );
                     }
-                    self.apply_model();
                 }
                 GfxDrawInstruction::SetPos(pos) => {
                     self.cursor = old_cursor + *pos;
@@ -660,7 +758,6 @@ impl<'a> RenderContext<'a> {
                             self.cursor, self.scale, self.view
                         );
                     }
-                    self.apply_model();
                 }
                 GfxDrawInstruction::ApplyView(view) => {
                     // Adjust view relative to cursor
@@ -681,24 +778,17 @@ impl<'a> RenderContext<'a> {
                             self.scale, self.view
                         );
                     }
-                    self.apply_view();
-                    self.apply_model();
                 }
                 GfxDrawInstruction::Draw(mesh) => {
                     if is_debug {
                         debug!(target: "gfx", "{ws}draw({mesh:?})");
                     }
-                    let texture = match mesh.texture {
-                        Some((_, texture)) => texture,
-                        None => self.white_texture,
-                    };
-                    let bindings = Bindings {
-                        vertex_buffers: vec![mesh.vertex_buffer],
-                        index_buffer: mesh.index_buffer,
-                        images: vec![texture],
-                    };
-                    self.ctx.apply_bindings(&bindings);
-                    self.ctx.draw(0, mesh.num_elements, 1);
+                    self.stats.meshes += 1;
+                    if BATCH_MESHES {
+                        self.batch_mesh(mesh);
+                    } else {
+                        self.draw_mesh(mesh);
+                    }
                 }
                 GfxDrawInstruction::Animation(anim_id) => {
                     let anim = self.anims.get_mut(&anim_id).unwrap();
@@ -722,6 +812,11 @@ impl<'a> RenderContext<'a> {
         draw_calls.sort_unstable_by_key(|(_, dc)| dc.z_index);
 
         for (dc_key, dc) in draw_calls {
+            // Nothing of it is on screen within the damage
+            match self.bounds.get(dc_key) {
+                Some(area) if self.damage.clip(area).is_some() => {}
+                _ => continue,
+            }
             if DEBUG_TRAX {
                 get_trax().lock().set_curr(*dc_key);
             }
@@ -738,11 +833,69 @@ impl<'a> RenderContext<'a> {
         }
 
         self.view = old_view;
-        self.apply_view();
-
         self.cursor = old_cursor;
-        self.apply_model();
     }
+
+    fn batch_mesh(&mut self, mesh: &GfxDrawMesh) {
+        let texture = mesh.texture.as_ref().map(|(_, texture)| *texture);
+        if !self.batch.accepts(texture, self.view, self.scale, mesh.verts.len()) {
+            self.flush();
+        }
+        let indices = &mesh.indices[..mesh.num_elements as usize];
+        self.batch.push(texture, self.view, self.scale, &mesh.verts, indices, self.cursor);
+    }
+
+    /// Draw everything collected in the batch with a single call
+    fn flush(&mut self) {
+        if self.batch.is_empty() {
+            return
+        }
+        if self.apply_state(self.batch.view, self.batch.scale, Point::zero()) {
+            let (vertex_buffer, index_buffer) =
+                self.stream_bufs.upload(self.ctx, &self.batch.verts, &self.batch.indices);
+            let bindings = Bindings {
+                vertex_buffers: vec![vertex_buffer],
+                index_buffer,
+                images: vec![self.batch.texture.unwrap_or(self.white_texture)],
+            };
+            self.ctx.apply_bindings(&bindings);
+            self.ctx.draw(0, self.batch.indices.len() as i32, 1);
+            self.stats.draws += 1;
+        }
+        self.batch.clear();
+    }
+
+    fn draw_mesh(&mut self, mesh: &GfxDrawMesh) {
+        if !self.apply_state(self.view, self.scale, self.cursor) {
+            return
+        }
+        let texture = match mesh.texture {
+            Some((_, texture)) => texture,
+            None => self.white_texture,
+        };
+        let bindings = Bindings {
+            vertex_buffers: vec![mesh.vertex_buffer],
+            index_buffer: mesh.index_buffer,
+            images: vec![texture],
+        };
+        self.ctx.apply_bindings(&bindings);
+        self.ctx.draw(0, mesh.num_elements, 1);
+        self.stats.draws += 1;
+    }
+}
+
+/// Uniforms with the projection set and an empty model
+fn projection_uniforms() -> [u8; 128] {
+    // This will make the top left (0, 0) and the bottom right (1, 1)
+    // Default is (-1, 1) -> (1, -1)
+    let proj = glam::Mat4::from_translation(glam::Vec3::new(-1., 1., 0.)) *
+        glam::Mat4::from_scale(glam::Vec3::new(2., -2., 1.));
+
+    let mut uniforms_data = [0u8; 128];
+    let data: [u8; 64] = unsafe { std::mem::transmute_copy(&proj) };
+    uniforms_data[0..64].copy_from_slice(&data);
+    assert_eq!(128, 2 * UniformType::Mat4.size());
+    uniforms_data
 }
 
 type Timestamp = u64;
@@ -783,6 +936,16 @@ impl std::fmt::Debug for GraphicsMethod {
     }
 }
 
+/// Input method composition, used for CJK input and dead keys
+#[derive(Clone, Debug)]
+pub enum ImeEvent {
+    /// Text being composed, replacing any previous preedit. An empty string ends composing.
+    /// The cursor is a byte range within the preedit text.
+    Preedit { text: String, cursor: Option<(usize, usize)> },
+    /// Final text to insert, which also ends composing
+    Commit(String),
+}
+
 struct EventChannel<T> {
     sender: async_channel::Sender<T>,
     recvr: async_channel::Receiver<T>,
@@ -794,10 +957,20 @@ impl<T> EventChannel<T> {
         Self { sender, recvr }
     }
 
+    /// Events are dropped while the channel is full
+    fn lossy(cap: usize) -> Self {
+        let (sender, recvr) = async_channel::bounded(cap);
+        Self { sender, recvr }
+    }
+
     fn notify(&self, ev: T) {
         self.sender.try_send(ev).unwrap();
     }
 
+    fn notify_lossy(&self, ev: T) {
+        let _ = self.sender.try_send(ev);
+    }
+
     fn clone_recvr(&self) -> async_channel::Receiver<T> {
         self.recvr.clone()
     }
@@ -815,6 +988,8 @@ pub struct GraphicsEventPublisher {
     mouse_move: EventChannel<Point>,
     mouse_wheel: EventChannel<Point>,
     touch: EventChannel<(TouchPhase, u64, Point)>,
+    frame: EventChannel<()>,
+    ime: EventChannel<ImeEvent>,
 }
 
 pub type GraphicsEventResizeSub = async_channel::Receiver<Dimension>;
@@ -826,6 +1001,8 @@ pub type GraphicsEventMouseButtonUpSub = async_channel::Receiver<(MouseButton, P
 pub type GraphicsEventMouseMoveSub = async_channel::Receiver<Point>;
 pub type GraphicsEventMouseWheelSub = async_channel::Receiver<Point>;
 pub type GraphicsEventTouchSub = async_channel::Receiver<(TouchPhase, u64, Point)>;
+pub type GraphicsEventFrameSub = async_channel::Receiver<()>;
+pub type GraphicsEventImeSub = async_channel::Receiver<ImeEvent>;
 
 impl GraphicsEventPublisher {
     pub fn new() -> Arc<Self> {
@@ -839,43 +1016,55 @@ impl GraphicsEventPublisher {
             mouse_move: EventChannel::new(),
             mouse_wheel: EventChannel::new(),
             touch: EventChannel::new(),
+            // Slow consumers only care that a frame happened, not how many
+            frame: EventChannel::lossy(1),
+            ime: EventChannel::new(),
         })
     }
 
-    fn notify_resize(&self, screen_size: Dimension) {
+    // These are called by the backend, or by tests injecting synthetic input
+
+    pub fn notify_resize(&self, screen_size: Dimension) {
         self.resize.notify(screen_size);
     }
-    fn notify_key_down(&self, key: KeyCode, mods: KeyMods, repeat: bool) {
+    pub fn notify_key_down(&self, key: KeyCode, mods: KeyMods, repeat: bool) {
         let ev = (key, mods, repeat);
         self.key_down.notify(ev);
     }
-    fn notify_key_up(&self, key: KeyCode, mods: KeyMods) {
+    pub fn notify_key_up(&self, key: KeyCode, mods: KeyMods) {
         let ev = (key, mods);
         self.key_up.notify(ev);
     }
-    fn notify_char(&self, chr: char, mods: KeyMods, repeat: bool) {
+    pub fn notify_char(&self, chr: char, mods: KeyMods, repeat: bool) {
         let ev = (chr, mods, repeat);
         self.chr.notify(ev);
     }
-    fn notify_mouse_btn_down(&self, button: MouseButton, mouse_pos: Point) {
+    pub fn notify_mouse_btn_down(&self, button: MouseButton, mouse_pos: Point) {
         let ev = (button, mouse_pos);
         self.mouse_btn_down.notify(ev);
     }
-    fn notify_mouse_btn_up(&self, button: MouseButton, mouse_pos: Point) {
+    pub fn notify_mouse_btn_up(&self, button: MouseButton, mouse_pos: Point) {
         let ev = (button, mouse_pos);
         self.mouse_btn_up.notify(ev);
     }
 
-    fn notify_mouse_move(&self, mouse_pos: Point) {
+    pub fn notify_mouse_move(&self, mouse_pos: Point) {
         self.mouse_move.notify(mouse_pos);
     }
-    fn notify_mouse_wheel(&self, wheel_pos: Point) {
+    pub fn notify_mouse_wheel(&self, wheel_pos: Point) {
         self.mouse_wheel.notify(wheel_pos);
     }
-    fn notify_touch(&self, phase: TouchPhase, id: u64, touch_pos: Point) {
+    pub fn notify_touch(&self, phase: TouchPhase, id: u64, touch_pos: Point) {
         let ev = (phase, id, touch_pos);
         self.touch.notify(ev);
     }
+    pub fn notify_frame(&self) {
+        self.frame.notify_lossy(());
+    }
+    /// miniquad has no composition callbacks, so the platform input glue pushes these directly
+    pub fn notify_ime(&self, ev: ImeEvent) {
+        self.ime.notify(ev);
+    }
 
     pub fn subscribe_resize(&self) -> GraphicsEventResizeSub {
         self.resize.clone_recvr()
@@ -904,6 +1093,20 @@ impl GraphicsEventPublisher {
     pub fn subscribe_touch(&self) -> GraphicsEventTouchSub {
         self.touch.clone_recvr()
     }
+    /// Ticks once per update of the render loop, used to step animations
+    pub fn subscribe_frame(&self) -> GraphicsEventFrameSub {
+        self.frame.clone_recvr()
+    }
+    pub fn subscribe_ime(&self) -> GraphicsEventImeSub {
+        self.ime.clone_recvr()
+    }
+}
+
+/// Offscreen copy of the last frame, so unchanged parts don't need redrawing
+struct Canvas {
+    texture: miniquad::TextureId,
+    pass: RenderPass,
+    size: (u32, u32),
 }
 
 struct Stage {
@@ -911,14 +1114,23 @@ struct Stage {
     #[cfg(target_os = "android")]
     libegl: egl::LibEgl,
     pipeline: Pipeline,
+    /// Copies the canvas to the screen without blending
+    blit_pipeline: Pipeline,
     white_texture: miniquad::TextureId,
     draw_calls: HashMap<DcId, GfxDrawCall>,
     batches: HashMap<BatchGuardId, Vec<GraphicsMethod>>,
 
     textures: HashMap<TextureId, miniquad::TextureId>,
-    buffers: HashMap<BufferId, miniquad::BufferId>,
+    buffers: HashMap<BufferId, GfxBuffer>,
     anims: HashMap<AnimId, GfxSeqAnim>,
 
+    batch: MeshBatch,
+    stream_bufs: StreamBuffers,
+    batch_stats: BatchStats,
+
+    damage: Damage,
+    canvas: Option<Canvas>,
+
     epoch: EpochIndex,
     method_queue: Arc<SyncMutex<Vec<(EpochIndex, GraphicsMethod)>>>,
     event_pub: GraphicsEventPublisherPtr,
@@ -928,6 +1140,9 @@ struct Stage {
     ex: ExecutorPtr,
     #[cfg(target_os = "android")]
     refresh_task: Option<smol::Task<()>>,
+    /// Animations are on screen so the refresh task must keep waking us up
+    #[cfg(target_os = "android")]
+    has_anims: Arc<std::sync::atomic::AtomicBool>,
 }
 
 impl Stage {
@@ -992,16 +1207,14 @@ impl Stage {
             ..Default::default()
         };
 
-        let pipeline = ctx.new_pipeline(
-            &[BufferLayout::default()],
-            &[
-                VertexAttribute::new("in_pos", VertexFormat::Float2),
-                VertexAttribute::new("in_color", VertexFormat::Float4),
-                VertexAttribute::new("in_uv", VertexFormat::Float2),
-            ],
-            shader,
-            params,
-        );
+        let attrs = [
+            VertexAttribute::new("in_pos", VertexFormat::Float2),
+            VertexAttribute::new("in_color", VertexFormat::Float4),
+            VertexAttribute::new("in_uv", VertexFormat::Float2),
+        ];
+        let pipeline = ctx.new_pipeline(&[BufferLayout::default()], &attrs, shader, params);
+        let blit_pipeline =
+            ctx.new_pipeline(&[BufferLayout::default()], &attrs, shader, Default::default());
 
         #[cfg(target_os = "android")]
         let libegl = egl::LibEgl::try_load().expect("Cant load LibEGL");
@@ -1011,6 +1224,7 @@ impl Stage {
             #[cfg(target_os = "android")]
             libegl,
             pipeline,
+            blit_pipeline,
             white_texture,
             draw_calls: HashMap::from([(
                 0,
@@ -1022,6 +1236,13 @@ impl Stage {
             buffers: HashMap::new(),
             anims: HashMap::new(),
 
+            batch: MeshBatch::new(),
+            stream_bufs: StreamBuffers::new(),
+            batch_stats: BatchStats::new(),
+
+            damage: Damage::new(),
+            canvas: None,
+
             epoch,
             method_queue,
             event_pub,
@@ -1031,6 +1252,8 @@ impl Stage {
             ex,
             #[cfg(target_os = "android")]
             refresh_task: None,
+            #[cfg(target_os = "android")]
+            has_anims: Arc::new(std::sync::atomic::AtomicBool::new(false)),
         }
     }
 
@@ -1166,6 +1389,7 @@ impl Stage {
             //debug!(target: "gfx", "Invoked method: new_vertex_buffer({:?}, {}) -> {:?}",
             //       verts, gfx_buffer_id, buffer);
         }
+        let buffer = GfxBuffer { id: buffer, data: GfxBufferData::Vertex(verts.into()) };
         if let Some(_) = self.buffers.insert(gfx_buffer_id, buffer) {
             if DEBUG_TRAX {
                 get_trax().lock().put_stat(2);
@@ -1190,6 +1414,7 @@ impl Stage {
             //debug!(target: "gfx", "Invoked method: new_index_buffer({:?}, {}) -> {:?}",
             //       indices, gfx_buffer_id, buffer);
         }
+        let buffer = GfxBuffer { id: buffer, data: GfxBufferData::Index(indices.into()) };
         if let Some(_) = self.buffers.insert(gfx_buffer_id, buffer) {
             if DEBUG_TRAX {
                 get_trax().lock().put_stat(2);
@@ -1212,9 +1437,9 @@ impl Stage {
         };
         if DEBUG_GFXAPI {
             debug!(target: "gfx", "Invoked method: delete_buffer({} => {:?})",
-                   gfx_buffer_id, buffer);
+                   gfx_buffer_id, buffer.id);
         }
-        self.ctx.delete_buffer(buffer);
+        self.ctx.delete_buffer(buffer.id);
         if DEBUG_TRAX {
             get_trax().lock().put_stat(0);
         }
@@ -1249,6 +1474,7 @@ impl Stage {
             debug!(target: "gfx", "Invoked method: update_anim({gfx_anim_id}[{frame_idx}] => {frame:?})");
         }
         anim.set(frame_idx, frame, &self.textures, &self.buffers);
+        self.damage.mark_anim();
         Ok(())
     }
     fn method_delete_anim(&mut self, gfx_anim_id: AnimId) -> Result<()> {
@@ -1286,6 +1512,7 @@ impl Stage {
                             get_trax().lock().put_stat(0);
                         }
                         *old_val = val;
+                        self.damage.mark_dc(key);
                     } else {
                         trace!(target: "gfx", "Rejected stale draw_call {key}: {val:?}");
                         if DEBUG_TRAX {
@@ -1295,6 +1522,7 @@ impl Stage {
                 }
                 None => {
                     self.draw_calls.insert(key, val);
+                    self.damage.mark_dc(key);
                     if DEBUG_TRAX {
                         get_trax().lock().put_stat(1);
                     }
@@ -1343,6 +1571,96 @@ impl Stage {
         };
     }
 
+    /// (Re)create the canvas when the screen size changes
+    fn update_canvas(&mut self, width: u32, height: u32) {
+        if self.canvas.as_ref().is_some_and(|canvas| canvas.size == (width, height)) {
+            return
+        }
+        if let Some(canvas) = self.canvas.take() {
+            self.ctx.delete_render_pass(canvas.pass);
+            self.ctx.delete_texture(canvas.texture);
+        }
+        if width == 0 || height == 0 {
+            return
+        }
+
+        let texture = self.ctx.new_render_texture(TextureParams {
+            width,
+            height,
+            format: TextureFormat::RGBA8,
+            min_filter: FilterMode::Nearest,
+            mag_filter: FilterMode::Nearest,
+            ..Default::default()
+        });
+        let pass = self.ctx.new_render_pass(texture, None);
+        self.canvas = Some(Canvas { texture, pass, size: (width, height) });
+        self.damage.mark_full();
+    }
+
+    /// Draw the scene, limited to the given region of the current pass
+    fn render(&mut self, region: Rectangle) {
+        self.ctx.apply_pipeline(&self.pipeline);
+
+        let (screen_w, screen_h) = miniquad::window::screen_size();
+
+        // Mark all anims as invisible
+        for (_, anim) in self.anims.iter_mut() {
+            anim.is_visible = false;
+        }
+
+        let mut render_ctx = RenderContext {
+            ctx: &mut self.ctx,
+            draw_calls: &self.draw_calls,
+            uniforms_data: projection_uniforms(),
+            white_texture: self.white_texture,
+            scale: 1.,
+            view: Rectangle::from([0., 0., screen_w, screen_h]),
+            cursor: Point::from([0., 0.]),
+            anims: &mut self.anims,
+            batch: &mut self.batch,
+            stream_bufs: &mut self.stream_bufs,
+            stats: &mut self.batch_stats,
+            viewport: None,
+            damage: region,
+            bounds: &self.damage.bounds,
+        };
+        render_ctx.draw();
+    }
+
+    /// Copy the canvas to the screen
+    fn blit(&mut self, texture: miniquad::TextureId, screen_w: f32, screen_h: f32) {
+        self.ctx.apply_pipeline(&self.blit_pipeline);
+        let (w, h) = (screen_w as i32, screen_h as i32);
+        self.ctx.apply_viewport(0, 0, w, h);
+        self.ctx.apply_scissor_rect(0, 0, w, h);
+
+        let mut uniforms_data = projection_uniforms();
+        let model = glam::Mat4::from_scale(glam::Vec3::new(1. / screen_w, 1. / screen_h, 1.));
+        let data: [u8; 64] = unsafe { std::mem::transmute_copy(&model) };
+        uniforms_data[64..].copy_from_slice(&data);
+        self.ctx.apply_uniforms_from_bytes(uniforms_data.as_ptr(), uniforms_data.len());
+
+        // OpenGL render targets are stored bottom row first
+        let (top_v, bot_v) = match self.ctx.info().backend {
+            Backend::OpenGl => (1., 0.),
+            Backend::Metal => (0., 1.),
+        };
+        let white = [1., 1., 1., 1.];
+        let verts = [
+            Vertex { pos: [0., 0.], color: white, uv: [0., top_v] },
+            Vertex { pos: [screen_w, 0.], color: white, uv: [1., top_v] },
+            Vertex { pos: [0., screen_h], color: white, uv: [0., bot_v] },
+            Vertex { pos: [screen_w, screen_h], color: white, uv: [1., bot_v] },
+        ];
+        let indices = [0, 2, 1, 1, 2, 3];
+        let (vertex_buffer, index_buffer) =
+            self.stream_bufs.upload(&mut self.ctx, &verts, &indices);
+        let bindings =
+            Bindings { vertex_buffers: vec![vertex_buffer], index_buffer, images: vec![texture] };
+        self.ctx.apply_bindings(&bindings);
+        self.ctx.draw(0, indices.len() as i32, 1);
+    }
+
     fn egl_ctx_is_disabled(&self) -> bool {
         #[cfg(target_os = "android")]
         {
@@ -1508,11 +1826,15 @@ impl EventHandler for Stage {
 
         #[cfg(target_os = "android")]
         if self.refresh_task.is_none() {
-            // For animations do periodic refresh every 40 ms
+            // For animations do periodic refresh every 40 ms. Otherwise we only
+            // wake up when draw calls get replaced, so an idle UI draws nothing.
+            let has_anims = self.has_anims.clone();
             self.refresh_task = Some(self.ex.spawn(async move {
                 loop {
                     darkfi::system::msleep(40).await;
-                    miniquad::window::schedule_update();
+                    if has_anims.load(Ordering::Relaxed) {
+                        miniquad::window::schedule_update();
+                    }
                 }
             }));
         }
@@ -1522,6 +1844,7 @@ impl EventHandler for Stage {
         // Otherwise we will just see a black screen for a sec or so.
         if self.screen_was_off {
             self.screen_was_off = false;
+            self.damage.mark_full();
         } else {
             let methods = self.pruner.recv_all();
             assert!(methods.is_empty() || self.batches.is_empty());
@@ -1570,44 +1893,46 @@ impl EventHandler for Stage {
                 get_trax().lock().flush();
             }
         }
+
+        self.event_pub.notify_frame();
     }
 
     fn draw(&mut self) {
-        self.ctx.begin_default_pass(PassAction::clear_color(0., 0., 0., 1.));
-        self.ctx.apply_pipeline(&self.pipeline);
-
-        // This will make the top left (0, 0) and the bottom right (1, 1)
-        // Default is (-1, 1) -> (1, -1)
-        let proj = glam::Mat4::from_translation(glam::Vec3::new(-1., 1., 0.)) *
-            glam::Mat4::from_scale(glam::Vec3::new(2., -2., 1.));
-
-        let mut uniforms_data = [0u8; 128];
-        let data: [u8; 64] = unsafe { std::mem::transmute_copy(&proj) };
-        uniforms_data[0..64].copy_from_slice(&data);
-        //let data: [u8; 64] = unsafe { std::mem::transmute_copy(&model) };
-        //uniforms_data[64..].copy_from_slice(&data);
-        assert_eq!(128, 2 * UniformType::Mat4.size());
-
         let (screen_w, screen_h) = miniquad::window::screen_size();
+        let screen = Rectangle::from([0., 0., screen_w, screen_h]);
 
-        // Mark all anims as invisible
-        for (_, anim) in self.anims.iter_mut() {
-            anim.is_visible = false;
+        if PARTIAL_REDRAW {
+            self.update_canvas(screen_w as u32, screen_h as u32);
         }
 
-        let mut render_ctx = RenderContext {
-            ctx: &mut self.ctx,
-            draw_calls: &self.draw_calls,
-            uniforms_data,
-            white_texture: self.white_texture,
-            scale: 1.,
-            view: Rectangle::from([0., 0., screen_w, screen_h]),
-            cursor: Point::from([0., 0.]),
-            anims: &mut self.anims,
-        };
-        render_ctx.draw();
+        let damage = self.damage.take(&self.draw_calls, &self.anims, screen);
+        #[cfg(target_os = "android")]
+        self.has_anims.store(self.damage.has_anims(), Ordering::Relaxed);
+
+        self.stream_bufs.reset();
+        match (damage, self.canvas.as_ref().map(|canvas| canvas.pass)) {
+            (FrameDamage::Skip, _) => return,
+            (FrameDamage::Present, Some(_)) => {}
+            (FrameDamage::Redraw(region), Some(pass)) => {
+                self.ctx.begin_pass(Some(pass), PassAction::Nothing);
+                self.render(region);
+                self.ctx.end_render_pass();
+            }
+            // Without a canvas there is nothing kept from the last frame
+            (_, None) => {
+                self.ctx.begin_default_pass(PassAction::clear_color(0., 0., 0., 1.));
+                self.render(screen);
+            }
+        }
+
+        if let Some(canvas) = &self.canvas {
+            let texture = canvas.texture;
+            self.ctx.begin_default_pass(PassAction::Nothing);
+            self.blit(texture, screen_w, screen_h);
+        }
 
         self.ctx.commit_frame();
+        self.batch_stats.end_frame();
     }
 
     fn resize_event(&mut self, width: f32, height: f32) {
@@ -1621,6 +1946,7 @@ impl EventHandler for Stage {
             (height as i32).encode(&mut file).unwrap();
         }
 
+        self.damage.mark_full();
         self.event_pub.notify_resize(Dimension::from([width, height]));
     }
 
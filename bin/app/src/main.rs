@@ -70,11 +70,13 @@ use crate::{
 use net::ZeroMQAdapter;
 #[cfg(feature = "enable-plugins")]
 use {
-    darkfi_serial::{deserialize, Decodable, Encodable},
+    app::prefs::PrefsPtr,
+    darkfi_serial::{deserialize, serialize, Decodable, Encodable},
+    futures::FutureExt,
     gfx::RenderApi,
     prop::{PropertyBool, PropertyStr, Role},
     scene::{SceneNodePtr, Slot},
-    std::io::Cursor,
+    std::{collections::HashMap, io::Cursor},
     ui::chatview,
 };
 
@@ -178,8 +180,9 @@ impl God {
             let sg_root = sg_root.clone();
             let ex = bg_ex.clone();
             let render_api = render_api.clone();
+            let undo_history = app.undo_history.clone();
             let zmq_task = bg_ex.spawn(async {
-                let zmq_rpc = ZeroMQAdapter::new(sg_root, render_api, ex).await;
+                let zmq_rpc = ZeroMQAdapter::new(sg_root, render_api, undo_history, ex).await;
                 zmq_rpc.run().await;
             });
             bg_runtime.push_task(zmq_task);
@@ -190,8 +193,9 @@ impl God {
             let ex = bg_ex.clone();
             let cv = cv_app_is_setup.clone();
             let render_api = render_api.clone();
+            let prefs = app.prefs.clone();
             let plug_task = bg_ex.spawn(async move {
-                load_plugins(ex, sg_root, render_api, cv).await;
+                load_plugins(ex, sg_root, render_api, prefs, cv).await;
             });
             bg_runtime.push_task(plug_task);
         }
@@ -267,6 +271,7 @@ async fn load_plugins(
     ex: ExecutorPtr,
     sg_root: SceneNodePtr,
     render_api: RenderApi,
+    prefs: PrefsPtr,
     cv: Arc<CondVar>,
 ) {
     let plugin = SceneNode::new("plugin", SceneNodeType::PluginRoot);
@@ -326,7 +331,9 @@ async fn load_plugins(
             let node_path = format!("/window/menu_layer/{channel}_channel_label");
             let menu_label = sg_root2.lookup_node(&node_path).unwrap();
             let prop = menu_label.get_property("text_color").unwrap();
-            if msg.contains(&darkirc_nick.get()) {
+            let is_highlight = msg.contains(&darkirc_nick.get());
+            notify_message(&sg_root2, &channel, &nick, &msg, is_highlight).await;
+            if is_highlight {
                 // Nick highlight
                 prop.set_f32(atom, Role::App, 0, 0.56).unwrap();
                 prop.set_f32(atom, Role::App, 1, 0.61).unwrap();
@@ -342,6 +349,16 @@ async fn load_plugins(
         }
     });
 
+    let wallet = create_wallet("wallet");
+    let wallet = wallet
+        .setup(|me| async {
+            plugin::Wallet::new(me, ex.clone()).await.expect("Wallet pimpl setup")
+        })
+        .await;
+    let listen_wallet = relay_wallet(&ex, &sg_root, &wallet, render_api.clone(), cv.clone());
+    let listen_read_markers = relay_read_markers(&ex, &sg_root, &darkirc, cv.clone());
+    plugin.link(wallet);
+
     let (slot, recvr) = Slot::new("connect");
     darkirc.register("connect", slot).unwrap();
     let sg_root2 = sg_root.clone();
@@ -396,9 +413,214 @@ async fn load_plugins(
     });
 
     plugin.link(darkirc);
+    prefs.attach(&ex, &plugin);
 
     i!("Plugins loaded");
-    futures::join!(listen_recv, listen_connect);
+    futures::join!(listen_recv, listen_connect, listen_wallet, listen_read_markers);
+}
+
+#[cfg(feature = "enable-plugins")]
+const NOTIFY_PATH: &str = "/window/notify_layer";
+
+/// Count unread messages on the channel badge. Highlights also get a
+/// toast and a system notification.
+#[cfg(feature = "enable-plugins")]
+async fn notify_message(
+    sg_root: &SceneNodePtr,
+    channel: &str,
+    nick: &str,
+    msg: &str,
+    is_highlight: bool,
+) {
+    let Some(notify) = sg_root.lookup_node(NOTIFY_PATH) else { return };
+    let res = if is_highlight {
+        let mut data = vec![];
+        channel.encode(&mut data).unwrap();
+        format!("#{channel}").encode(&mut data).unwrap();
+        format!("{nick}: {msg}").encode(&mut data).unwrap();
+        notify.call_method("notify", data).await
+    } else {
+        notify.call_method("badge", serialize(&channel.to_string())).await
+    };
+    if let Err(err) = res {
+        error!(target: "app", "Unable to notify message on #{channel}: {err:?}");
+    }
+}
+
+/// Forward the wallet plugin signals to the wallet pages
+#[cfg(feature = "enable-plugins")]
+fn relay_wallet(
+    ex: &ExecutorPtr,
+    sg_root: &SceneNodePtr,
+    wallet: &SceneNodePtr,
+    render_api: RenderApi,
+    cv: Arc<CondVar>,
+) -> smol::Task<()> {
+    const WALLET_PATH: &str = "/window/wallet_layer";
+
+    let (balance_slot, balance_recvr) = Slot::new("wallet_balance");
+    wallet.register("balance", balance_slot).unwrap();
+    let (count_slot, count_recvr) = Slot::new("wallet_balance_count");
+    wallet.register("balance_count", count_slot).unwrap();
+    let (sent_slot, sent_recvr) = Slot::new("wallet_sent");
+    wallet.register("sent", sent_slot).unwrap();
+    let (connect_slot, connect_recvr) = Slot::new("wallet_connect");
+    wallet.register("connect", connect_slot).unwrap();
+
+    let sg_root = sg_root.clone();
+    let address = PropertyStr::wrap(wallet, Role::App, "address", 0).unwrap();
+    ex.spawn(async move {
+        cv.wait().await;
+        let Some(layer) = sg_root.lookup_node(WALLET_PATH) else {
+            d!("Ignoring wallet signals since {WALLET_PATH} doesn't exist");
+            return
+        };
+        let balances = layer.lookup_node("/balances").unwrap();
+        let address_text = layer.lookup_node("/receive_address").unwrap();
+        let address_qr = layer.lookup_node("/receive_qr").unwrap();
+        let status_text = layer.lookup_node("/status").unwrap();
+        let send_status_text = layer.lookup_node("/send_status").unwrap();
+        let notify = sg_root.lookup_node(NOTIFY_PATH);
+
+        {
+            let atom = &mut render_api.make_guard(gfxtag!("wallet address"));
+            address_text.set_property_str(atom, Role::App, "text", address.get()).unwrap();
+            address_qr.set_property_str(atom, Role::App, "data", address.get()).unwrap();
+        }
+
+        // Number of rows currently in the balances list
+        let mut n_items = 0;
+        // Balances seen so far. Changes after the first sync are notified.
+        let mut known_balances = HashMap::new();
+        let mut is_first_sync = true;
+        loop {
+            futures::select! {
+                data = balance_recvr.recv().fuse() => {
+                    let Ok(data) = data else { break };
+                    let mut cur = Cursor::new(&data);
+                    let idx = u32::decode(&mut cur).unwrap();
+                    let token = String::decode(&mut cur).unwrap();
+                    let amount = String::decode(&mut cur).unwrap();
+
+                    let prev = known_balances.insert(token.clone(), amount.clone());
+                    if !is_first_sync && prev.as_ref() != Some(&amount) {
+                        if let Some(notify) = &notify {
+                            let mut data = vec![];
+                            "wallet".encode(&mut data).unwrap();
+                            "Wallet".encode(&mut data).unwrap();
+                            format!("{token} balance is now {amount}").encode(&mut data).unwrap();
+                            notify.call_method("notify", data).await.unwrap();
+                        }
+                    }
+
+                    if idx >= n_items {
+                        balances.call_method("insert_item", serialize(&idx)).await.unwrap();
+                        n_items = idx + 1;
+                    }
+                    for (path, text) in [("/token", token), ("/amount", amount)] {
+                        let mut data = vec![];
+                        idx.encode(&mut data).unwrap();
+                        path.encode(&mut data).unwrap();
+                        text.encode(&mut data).unwrap();
+                        balances.call_method("set_item_field", data).await.unwrap();
+                    }
+                }
+                data = count_recvr.recv().fuse() => {
+                    let Ok(data) = data else { break };
+                    let count: u32 = deserialize(&data).unwrap();
+                    is_first_sync = false;
+                    while n_items > count {
+                        n_items -= 1;
+                        balances.call_method("remove_item", serialize(&n_items)).await.unwrap();
+                    }
+                }
+                data = sent_recvr.recv().fuse() => {
+                    let Ok(data) = data else { break };
+                    let (tx_hash, error): (String, String) = deserialize(&data).unwrap();
+                    let text = if error.is_empty() { format!("Sent {tx_hash}") } else { error };
+                    if let Some(notify) = &notify {
+                        notify.call_method("toast", serialize(&text)).await.unwrap();
+                    }
+                    let atom = &mut render_api.make_guard(gfxtag!("wallet sent"));
+                    send_status_text.set_property_str(atom, Role::App, "text", text).unwrap();
+                }
+                data = connect_recvr.recv().fuse() => {
+                    let Ok(data) = data else { break };
+                    let is_connected: bool = deserialize(&data).unwrap();
+                    let text = if is_connected { "Synced" } else { "Connecting to darkfid..." };
+                    let atom = &mut render_api.make_guard(gfxtag!("wallet connect"));
+                    status_text.set_property_str(atom, Role::App, "text", text).unwrap();
+                }
+            }
+        }
+    })
+}
+
+/// Opening a channel marks it read on the other paired devices too
+#[cfg(feature = "enable-plugins")]
+fn relay_read_markers(
+    ex: &ExecutorPtr,
+    sg_root: &SceneNodePtr,
+    darkirc: &SceneNodePtr,
+    cv: Arc<CondVar>,
+) -> smol::Task<()> {
+    const MENU_PATH: &str = "/window/menu_layer";
+    const BTN_SUFFIX: &str = "_channel_btn";
+
+    let (sync_slot, sync_recvr) = Slot::new("darkirc_sync");
+    darkirc.register("sync", sync_slot).unwrap();
+
+    let sg_root = sg_root.clone();
+    let darkirc = darkirc.clone();
+    ex.spawn(async move {
+        cv.wait().await;
+        let Some(menu) = sg_root.lookup_node(MENU_PATH) else {
+            d!("Ignoring read markers since {MENU_PATH} doesn't exist");
+            return
+        };
+
+        let mut clicks = vec![];
+        for btn in menu.get_children() {
+            let Some(channel) = btn.name.strip_suffix(BTN_SUFFIX) else { continue };
+            let (slot, recvr) = Slot::new(format!("{channel}_read"));
+            btn.register("click", slot).unwrap();
+            clicks.push((channel.to_string(), recvr));
+        }
+        if clicks.is_empty() {
+            return
+        }
+        let notify = sg_root.lookup_node(NOTIFY_PATH);
+
+        loop {
+            let click =
+                futures::future::select_all(clicks.iter().map(|(_, recvr)| Box::pin(recvr.recv())));
+            futures::select! {
+                data = sync_recvr.recv().fuse() => {
+                    let Ok(data) = data else { break };
+                    let (category, channel, _): (String, String, String) =
+                        deserialize(&data).unwrap();
+                    if category != "read_markers" {
+                        continue
+                    }
+                    if let Some(notify) = &notify {
+                        notify.call_method("clear_badge", serialize(&channel)).await.unwrap();
+                    }
+                }
+                (res, idx, _) = click.fuse() => {
+                    if res.is_err() {
+                        break
+                    }
+                    let mut data = vec![];
+                    "read_markers".encode(&mut data).unwrap();
+                    clicks[idx].0.encode(&mut data).unwrap();
+                    util::unixtime().to_string().encode(&mut data).unwrap();
+                    if let Err(err) = darkirc.call_method("set_sync_state", data).await {
+                        error!(target: "app", "Unable to sync read marker: {err:?}");
+                    }
+                }
+            }
+        }
+    })
 }
 
 pub fn create_darkirc(name: &str) -> SceneNode {
@@ -408,6 +630,7 @@ pub fn create_darkirc(name: &str) -> SceneNode {
     let mut prop = Property::new("nick", PropertyType::Str, PropertySubType::Null);
     prop.set_ui_text("Nick", "Nickname");
     prop.set_defaults_str(vec!["anon".to_string()]).unwrap();
+    prop.mark_user_pref();
     node.add_property(prop).unwrap();
 
     node.add_signal(
@@ -440,6 +663,106 @@ pub fn create_darkirc(name: &str) -> SceneNode {
     )
     .unwrap();
 
+    node.add_method("create_identity", vec![("label", "Label", CallArgType::Str)], None).unwrap();
+    node.add_method("switch_identity", vec![("label", "Label", CallArgType::Str)], None).unwrap();
+    node.add_method(
+        "bind_identity",
+        vec![("channel", "Channel", CallArgType::Str), ("label", "Label", CallArgType::Str)],
+        None,
+    )
+    .unwrap();
+
+    node.add_signal(
+        "sync",
+        "State changed by another paired device",
+        vec![
+            ("category", "Category", CallArgType::Str),
+            ("key", "Key", CallArgType::Str),
+            ("value", "Value", CallArgType::Str),
+        ],
+    )
+    .unwrap();
+
+    node.add_method("pair_device", vec![("code", "Pairing Code", CallArgType::Str)], None).unwrap();
+    node.add_method("unpair_device", vec![], None).unwrap();
+    node.add_method(
+        "set_sync_category",
+        vec![("category", "Category", CallArgType::Str), ("enabled", "Enabled", CallArgType::Bool)],
+        None,
+    )
+    .unwrap();
+    // Contacts, channels and read markers are kept by the UI. An empty
+    // value removes the key.
+    node.add_method(
+        "set_sync_state",
+        vec![
+            ("category", "Category", CallArgType::Str),
+            ("key", "Key", CallArgType::Str),
+            ("value", "Value", CallArgType::Str),
+        ],
+        None,
+    )
+    .unwrap();
+
+    node
+}
+
+pub fn create_wallet(name: &str) -> SceneNode {
+    t!("create_wallet({name})");
+    let mut node = SceneNode::new(name, SceneNodeType::Plugin);
+
+    let mut prop = Property::new("address", PropertyType::Str, PropertySubType::Null);
+    prop.set_ui_text("Address", "Default receive address");
+    node.add_property(prop).unwrap();
+
+    let mut prop = Property::new("height", PropertyType::Uint32, PropertySubType::Null);
+    prop.set_ui_text("Height", "Last scanned block height");
+    node.add_property(prop).unwrap();
+
+    let mut prop = Property::new("is_connected", PropertyType::Bool, PropertySubType::Null);
+    prop.set_ui_text("Is Connected", "Connected and synced with darkfid");
+    node.add_property(prop).unwrap();
+
+    node.add_signal(
+        "balance",
+        "Token balance, sent in order after every sync",
+        vec![
+            ("index", "Index", CallArgType::Uint32),
+            ("token", "Token", CallArgType::Str),
+            ("amount", "Amount", CallArgType::Str),
+        ],
+    )
+    .unwrap();
+    node.add_signal(
+        "balance_count",
+        "Number of balances, sent after the last one",
+        vec![("count", "Count", CallArgType::Uint32)],
+    )
+    .unwrap();
+    node.add_signal(
+        "connect",
+        "Connected to or disconnected from darkfid",
+        vec![("is_connected", "Is Connected", CallArgType::Bool)],
+    )
+    .unwrap();
+    node.add_signal(
+        "sent",
+        "Transfer broadcasted or failed",
+        vec![("tx_hash", "Tx Hash", CallArgType::Str), ("error", "Error", CallArgType::Str)],
+    )
+    .unwrap();
+
+    node.add_method(
+        "send",
+        vec![
+            ("recipient", "Recipient", CallArgType::Str),
+            ("token", "Token", CallArgType::Str),
+            ("amount", "Amount", CallArgType::Str),
+        ],
+        None,
+    )
+    .unwrap();
+
     node
 }
 
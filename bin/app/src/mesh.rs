@@ -16,8 +16,11 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
 use crate::gfx::{
-    DebugTag, DrawMesh, ManagedBufferPtr, ManagedTexturePtr, Point, Rectangle, RenderApi, Vertex,
+    DebugTag, Dimension, DrawMesh, ManagedBufferPtr, ManagedTexturePtr, Point, Rectangle,
+    RenderApi, Vertex,
 };
 
 pub type Color = [f32; 4];
@@ -40,6 +43,81 @@ pub const COLOR_BLACK: Color = [1., 1., 1., 1.];
 #[allow(dead_code)]
 pub const COLOR_GREY: Color = [0.5, 0.5, 0.5, 1.];
 
+/// Width in pixels of the edge fading out around rounded shapes
+pub const AA_FRINGE: f32 = 1.;
+
+/// Number of segments approximating an arc so each is about 2px long
+fn arc_segments(radius: f32, angle: f32) -> usize {
+    ((radius * angle / 2.).ceil() as usize).clamp(1, 64)
+}
+
+/// Outline of a rounded box going clockwise from the top-left corner.
+/// Each point is `(corner, offset from the corner, outward normal)` with
+/// corners numbered clockwise from the top-left.
+pub(crate) fn rounded_box_outline(radius: f32) -> Vec<(usize, Point, Point)> {
+    let segments = arc_segments(radius, FRAC_PI_2);
+    let mut outline = Vec::with_capacity(4 * (segments + 1));
+    for corner in 0..4 {
+        // Direction from the arc center towards the corner
+        let (sx, sy) = [(-1., -1.), (1., -1.), (1., 1.), (-1., 1.)][corner];
+        let center = Point::new(-sx * radius, -sy * radius);
+        let start = PI + corner as f32 * FRAC_PI_2;
+        for i in 0..=segments {
+            let angle = start + FRAC_PI_2 * i as f32 / segments as f32;
+            let normal = Point::new(angle.cos(), angle.sin());
+            outline.push((corner, center + normal * radius, normal));
+        }
+    }
+    outline
+}
+
+/// Outline of a circle as `(offset from the center, outward normal)`
+pub(crate) fn circle_outline(radius: f32) -> Vec<(Point, Point)> {
+    let segments = arc_segments(radius, TAU).max(8);
+    (0..segments)
+        .map(|i| {
+            let angle = TAU * i as f32 / segments as f32;
+            let normal = Point::new(angle.cos(), angle.sin());
+            (normal * radius, normal)
+        })
+        .collect()
+}
+
+/// Indices filling a convex outline of `n` points plus its fringe.
+/// Vertex `2i` is the inner point and `2i + 1` the transparent outer one.
+pub(crate) fn convex_aa_indices(n: usize) -> Vec<u16> {
+    let mut indices = Vec::with_capacity(3 * n.saturating_sub(2) + 6 * n);
+    for i in 1..n.saturating_sub(1) {
+        indices.extend([0, 2 * i, 2 * (i + 1)]);
+    }
+    for i in 0..n {
+        let j = (i + 1) % n;
+        indices.extend([2 * i, 2 * i + 1, 2 * j, 2 * j, 2 * i + 1, 2 * j + 1]);
+    }
+    indices.into_iter().map(|i| i as u16).collect()
+}
+
+/// Region of a texture drawn as a box whose borders keep their size while
+/// the middle stretches
+#[derive(Clone, Debug)]
+pub struct NinePatch {
+    pub uv: Rectangle,
+    pub tex_dim: Dimension,
+    /// `[top, right, bottom, left]` in texture pixels
+    pub border: [f32; 4],
+}
+
+impl NinePatch {
+    /// UV coords of the grid lines
+    pub(crate) fn uvs(&self) -> ([f32; 4], [f32; 4]) {
+        let [top, right, bottom, left] = self.border;
+        let (uv, dim) = (&self.uv, &self.tex_dim);
+        let us = [uv.x, uv.x + left / dim.w, uv.rhs() - right / dim.w, uv.rhs()];
+        let vs = [uv.y, uv.y + top / dim.h, uv.bhs() - bottom / dim.h, uv.bhs()];
+        (us, vs)
+    }
+}
+
 #[derive(Clone)]
 pub struct MeshInfo {
     pub vertex_buffer: ManagedBufferPtr,
@@ -154,6 +232,62 @@ impl MeshBuilder {
         self.draw_filled_box(&Rectangle::new(x1, y2 - thickness, dist_x, thickness), color);
     }
 
+    /// Fill a convex outline given as `(pos, outward normal)` with a faded edge
+    fn fill_convex_aa(&mut self, outline: impl Iterator<Item = (Point, Point)>, color: Color) {
+        let transparent = [color[0], color[1], color[2], 0.];
+        let uv = [0., 0.];
+
+        let mut verts = vec![];
+        for (pos, normal) in outline {
+            let inner = pos - normal * (AA_FRINGE / 2.);
+            let outer = pos + normal * (AA_FRINGE / 2.);
+            verts.push(Vertex { pos: inner.as_arr(), color, uv });
+            verts.push(Vertex { pos: outer.as_arr(), color: transparent, uv });
+        }
+        let indices = convex_aa_indices(verts.len() / 2);
+
+        self.append(verts, indices);
+    }
+
+    pub fn draw_rounded_box(&mut self, obj: &Rectangle, radius: f32, color: Color) {
+        let radius = radius.min(obj.w / 2.).min(obj.h / 2.).max(0.);
+        let corners = [obj.pos(), obj.top_right(), obj.corner(), obj.bot_left()];
+        let outline = rounded_box_outline(radius)
+            .into_iter()
+            .map(|(corner, off, normal)| (corners[corner] + off, normal));
+        self.fill_convex_aa(outline, color);
+    }
+
+    pub fn draw_circle(&mut self, center: Point, radius: f32, color: Color) {
+        let outline =
+            circle_outline(radius).into_iter().map(|(off, normal)| (center + off, normal));
+        self.fill_convex_aa(outline, color);
+    }
+
+    /// The borders are scaled down when the box is too small to fit them
+    pub fn draw_nine_patch(&mut self, obj: &Rectangle, patch: &NinePatch, color: Color) {
+        let [top, right, bottom, left] = patch.border;
+        let scale_x = (obj.w / (left + right)).min(1.);
+        let scale_y = (obj.h / (top + bottom)).min(1.);
+
+        let xs = [obj.x, obj.x + left * scale_x, obj.rhs() - right * scale_x, obj.rhs()];
+        let ys = [obj.y, obj.y + top * scale_y, obj.bhs() - bottom * scale_y, obj.bhs()];
+        let (us, vs) = patch.uvs();
+
+        for row in 0..3 {
+            for col in 0..3 {
+                let (w, h) = (xs[col + 1] - xs[col], ys[row + 1] - ys[row]);
+                if w <= 0. || h <= 0. {
+                    continue
+                }
+                let rect = Rectangle::new(xs[col], ys[row], w, h);
+                let uv =
+                    Rectangle::new(us[col], vs[row], us[col + 1] - us[col], vs[row + 1] - vs[row]);
+                self.draw_box(&rect, color, &uv);
+            }
+        }
+    }
+
     pub fn draw_line(&mut self, start: Point, end: Point, color: Color, thickness: f32) {
         let mut dir = end - start;
         dir.normalize();
@@ -16,20 +16,97 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+//! Scene graph inspection over ZeroMQ, used by `pydrk`.
+//!
+//! Requests are `[cmd:1] [payload]`, optionally followed by an auth token
+//! frame. Commands which change the scene or call methods are rejected
+//! unless the token matches the one stored at [`get_auth_token_path()`].
+//! Replies are `[errc:1] [reply]`.
+
 use async_lock::Mutex;
 use darkfi_serial::{async_trait, deserialize, Decodable, Encodable, SerialDecodable, VarInt};
-use std::{io::Cursor, sync::Arc};
+use rand::{rngs::OsRng, RngCore};
+use std::{io::Cursor, path::Path, sync::Arc};
 use zeromq::{Socket, SocketRecv, SocketSend};
 
 use crate::{
     error::{Error, Result},
     expr::SExprCode,
     gfx::{gfxtag, RenderApi},
-    prop::{PropertyType, Role},
-    scene::{SceneNodeId, SceneNodePtr, ScenePath},
+    prop::{PropertySubType, PropertyType, Role, SceneChange, UndoHistoryPtr},
+    scene::{SceneNode, SceneNodeId, SceneNodePtr, SceneNodeType, ScenePath},
     ExecutorPtr,
 };
 
+/// Bumped whenever commands or their encoding change
+const PROTOCOL_VERSION: u32 = 2;
+
+/// Overrides the stored token, handy for CI
+const AUTH_TOKEN_ENV: &str = "DARKFI_APP_ZMQ_TOKEN";
+
+#[cfg(target_os = "android")]
+pub fn get_auth_token_path() -> std::path::PathBuf {
+    crate::android::get_appdata_path().join("zmq_token")
+}
+#[cfg(not(target_os = "android"))]
+pub fn get_auth_token_path() -> std::path::PathBuf {
+    dirs::data_local_dir().unwrap().join("darkfi/app/zmq_token")
+}
+
+/// Use the token from the environment or the token file, creating one if missing
+fn load_auth_token(path: &Path) -> String {
+    if let Ok(token) = std::env::var(AUTH_TOKEN_ENV) {
+        return token
+    }
+    if let Ok(token) = std::fs::read_to_string(path) {
+        let token = token.trim();
+        if !token.is_empty() {
+            return token.to_string()
+        }
+    }
+
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let token: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Err(err) = write_private(path, &token) {
+        warn!(target: "req", "Unable to save zmq auth token to {path:?}: {err}");
+    }
+    token
+}
+
+#[cfg(unix)]
+fn write_private(path: &Path, data: &str) -> std::io::Result<()> {
+    use std::{io::Write, os::unix::fs::OpenOptionsExt};
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    file.write_all(data.as_bytes())
+}
+#[cfg(not(unix))]
+fn write_private(path: &Path, data: &str) -> std::io::Result<()> {
+    std::fs::write(path, data)
+}
+
+/// Compare without bailing at the first differing byte
+fn token_matches(token: &[u8], expected: &[u8]) -> bool {
+    token.len() == expected.len() &&
+        token.iter().zip(expected).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// Names of every variant of a `#[repr(u8)]` enum, found by trying to decode each value
+fn enum_schema<T: Decodable + std::fmt::Debug>() -> Vec<(u8, String)> {
+    (0..=u8::MAX)
+        .filter_map(|i| deserialize::<T>(&[i]).ok().map(|val| (i, format!("{val:?}"))))
+        .collect()
+}
+
 #[derive(Debug, SerialDecodable)]
 #[repr(u8)]
 enum Command {
@@ -56,6 +133,32 @@ enum Command {
     GetMethods = 20,
     GetMethod = 21,
     CallMethod = 22,
+    Undo = 25,
+    Redo = 26,
+    GetUndoHistory = 27,
+    GetVersion = 28,
+    GetSchema = 29,
+}
+
+impl Command {
+    /// Needs the auth token
+    fn is_mutation(&self) -> bool {
+        matches!(
+            self,
+            Self::AddNode |
+                Self::RemoveNode |
+                Self::RenameNode |
+                Self::AddProperty |
+                Self::LinkNode |
+                Self::UnlinkNode |
+                Self::SetPropertyValue |
+                Self::RegisterSlot |
+                Self::UnregisterSlot |
+                Self::CallMethod |
+                Self::Undo |
+                Self::Redo
+        )
+    }
 }
 
 // Missing calls todo:
@@ -77,24 +180,37 @@ pub struct ZeroMQAdapter {
     */
     sg_root: SceneNodePtr,
     render_api: RenderApi,
+    undo_history: UndoHistoryPtr,
     _ex: ExecutorPtr,
+    auth_token: String,
 
     zmq_rep: Mutex<zeromq::RepSocket>,
     _zmq_pub: Mutex<zeromq::PubSocket>,
 }
 
 impl ZeroMQAdapter {
-    pub async fn new(sg_root: SceneNodePtr, render_api: RenderApi, ex: ExecutorPtr) -> Arc<Self> {
+    pub async fn new(
+        sg_root: SceneNodePtr,
+        render_api: RenderApi,
+        undo_history: UndoHistoryPtr,
+        ex: ExecutorPtr,
+    ) -> Arc<Self> {
         let mut zmq_rep = zeromq::RepSocket::new();
         zmq_rep.bind("tcp://0.0.0.0:9484").await.unwrap();
 
         let mut zmq_pub = zeromq::PubSocket::new();
         zmq_pub.bind("tcp://0.0.0.0:9485").await.unwrap();
 
+        let token_path = get_auth_token_path();
+        let auth_token = load_auth_token(&token_path);
+        info!(target: "req", "zmq auth token is in {token_path:?}");
+
         Arc::new(Self {
             sg_root,
             render_api,
+            undo_history,
             _ex: ex,
+            auth_token,
             zmq_rep: Mutex::new(zmq_rep),
             _zmq_pub: Mutex::new(zmq_pub),
         })
@@ -103,16 +219,26 @@ impl ZeroMQAdapter {
     pub async fn run(self: Arc<Self>) {
         loop {
             let req = self.zmq_rep.lock().await.recv().await.unwrap();
-            assert_eq!(req.len(), 2);
+            assert!(req.len() == 2 || req.len() == 3);
             let cmd = req.get(0).unwrap().to_vec();
             assert_eq!(cmd.len(), 1);
             let payload = req.get(1).unwrap().to_vec();
+            let token = req.get(2).map(|token| token.to_vec());
 
-            let cmd = deserialize(&cmd).unwrap();
+            let cmd: Command = deserialize(&cmd).unwrap();
             debug!(target: "req", "zmq: {:?} {:?}", cmd, payload);
 
+            let is_authorized = match &token {
+                Some(token) => token_matches(token, self.auth_token.as_bytes()),
+                None => false,
+            };
             let self2 = self.clone();
-            match self2.process_request(cmd, payload).await {
+            let res = if cmd.is_mutation() && !is_authorized {
+                Err(Error::ZmqUnauthorized)
+            } else {
+                self2.process_request(cmd, payload).await
+            };
+            match res {
                 Ok(reply) => {
                     let mut m = zeromq::ZmqMessage::from(vec![0u8]);
                     m.push_back(reply.into());
@@ -143,6 +269,22 @@ impl ZeroMQAdapter {
                 assert_eq!(payload.len(), 0);
                 "hello".encode(&mut reply).unwrap();
             }
+            Command::GetVersion => {
+                debug!(target: "req", "{cmd:?}()");
+                PROTOCOL_VERSION.encode(&mut reply).unwrap();
+            }
+            Command::GetSchema => {
+                debug!(target: "req", "{cmd:?}()");
+                PROTOCOL_VERSION.encode(&mut reply).unwrap();
+                enum_schema::<PropertyType>().encode(&mut reply).unwrap();
+                enum_schema::<PropertySubType>().encode(&mut reply).unwrap();
+                enum_schema::<SceneNodeType>().encode(&mut reply).unwrap();
+                let cmds: Vec<_> = (0..=u8::MAX)
+                    .filter_map(|i| deserialize::<Command>(&[i]).ok().map(|cmd| (i, cmd)))
+                    .map(|(i, cmd)| (i, format!("{cmd:?}"), cmd.is_mutation()))
+                    .collect();
+                cmds.encode(&mut reply).unwrap();
+            }
             Command::GetInfo => {
                 /*
                 let node_id = SceneNodeId::decode(&mut cur).unwrap();
@@ -238,12 +380,14 @@ impl ZeroMQAdapter {
                 let prop_i = u32::decode(&mut cur).unwrap() as usize;
                 let prop_type = PropertyType::decode(&mut cur).unwrap();
                 debug!(target: "req", "{cmd:?}({node_path}, {prop_name}, {prop_i}, {prop_type:?})");
+                let desc = format!("set {node_path}:{prop_name}[{prop_i}]");
 
                 let node = self.sg_root.lookup_node(node_path).ok_or(Error::NodeNotFound)?;
                 let prop = node.get_property(&prop_name).ok_or(Error::PropertyNotFound)?;
 
                 let atom =
                     &mut self.render_api.make_guard(gfxtag!("ZeroMQAdapter::SetPropertyValue"));
+                atom.record_undo(self.undo_history.clone(), desc);
 
                 match prop_type {
                     PropertyType::Null => {
@@ -281,21 +425,39 @@ impl ZeroMQAdapter {
                 }
             }
             Command::AddNode => {
-                /*
+                let parent_path = String::decode(&mut cur).unwrap();
                 let node_name = String::decode(&mut cur).unwrap();
                 let node_type = SceneNodeType::decode(&mut cur).unwrap();
-                debug!(target: "req", "{:?}({}, {:?})", cmd, node_name, node_type);
+                debug!(target: "req", "{cmd:?}({parent_path}, {node_name}, {node_type:?})");
 
-                let node_id = scene_graph.add_node(&node_name, node_type).id;
-                node_id.encode(&mut reply).unwrap();
-                */
+                if node_name.is_empty() || node_name.contains('/') {
+                    return Err(Error::InvalidScenePath)
+                }
+                let path: ScenePath = parent_path.parse()?;
+                let parent = self.sg_root.lookup_node(path).ok_or(Error::NodeNotFound)?;
+                if parent.get_children().iter().any(|child| child.name == node_name) {
+                    return Err(Error::NodeChildNameConflict)
+                }
+
+                // Remote nodes have no pimpl, so they're only useful for holding data
+                let node = SceneNode::new(node_name.clone(), node_type).setup_null();
+                node.id.encode(&mut reply).unwrap();
+                let pos = parent.get_children().len();
+                parent.link(node.clone());
+
+                let atom = &mut self.render_api.make_guard(gfxtag!("ZeroMQAdapter::AddNode"));
+                let desc = format!("add {parent_path}/{node_name}");
+                atom.record_undo(self.undo_history.clone(), desc);
+                atom.record_scene_change(SceneChange::Link {
+                    parent: Arc::downgrade(&parent),
+                    child: node,
+                    pos,
+                });
             }
             Command::RemoveNode => {
-                /*
-                let node_id = SceneNodeId::decode(&mut cur).unwrap();
-                debug!(target: "req", "{:?}({})", cmd, node_id);
-                scene_graph.remove_node(node_id)?;
-                */
+                let node_path = String::decode(&mut cur).unwrap();
+                debug!(target: "req", "{cmd:?}({node_path})");
+                self.unlink_node(node_path, gfxtag!("ZeroMQAdapter::RemoveNode"))?;
             }
             Command::RenameNode => {
                 /*
@@ -437,12 +599,9 @@ impl ZeroMQAdapter {
                 */
             }
             Command::UnlinkNode => {
-                /*
-                let child_id = SceneNodeId::decode(&mut cur).unwrap();
-                let parent_id = SceneNodeId::decode(&mut cur).unwrap();
-                debug!(target: "req", "{:?}({}, {})", cmd, child_id, parent_id);
-                scene_graph.unlink(child_id, parent_id)?;
-                */
+                let node_path = String::decode(&mut cur).unwrap();
+                debug!(target: "req", "{cmd:?}({node_path})");
+                self.unlink_node(node_path, gfxtag!("ZeroMQAdapter::UnlinkNode"))?;
             }
             Command::GetSignals => {
                 /*
@@ -567,8 +726,45 @@ impl ZeroMQAdapter {
                 let result = node.call_method(&method_name, arg_data).await?;
                 result.encode(&mut reply).unwrap();
             }
+            Command::Undo => {
+                debug!(target: "req", "{cmd:?}()");
+                let atom = &mut self.render_api.make_guard(gfxtag!("ZeroMQAdapter::Undo"));
+                if !self.undo_history.undo(atom) {
+                    return Err(Error::NothingToUndo)
+                }
+            }
+            Command::Redo => {
+                debug!(target: "req", "{cmd:?}()");
+                let atom = &mut self.render_api.make_guard(gfxtag!("ZeroMQAdapter::Redo"));
+                if !self.undo_history.redo(atom) {
+                    return Err(Error::NothingToRedo)
+                }
+            }
+            Command::GetUndoHistory => {
+                debug!(target: "req", "{cmd:?}()");
+                let (undo, redo) = self.undo_history.descs();
+                undo.encode(&mut reply).unwrap();
+                redo.encode(&mut reply).unwrap();
+            }
         }
 
         Ok(reply)
     }
+
+    /// Nodes live as long as something references them, so removing is unlinking
+    fn unlink_node(&self, node_path: String, tag: Option<&'static str>) -> Result<()> {
+        let path: ScenePath = node_path.parse()?;
+        let node = self.sg_root.lookup_node(path).ok_or(Error::NodeNotFound)?;
+        let parent = node.get_parent().ok_or(Error::ParentNodeNotFound)?;
+        let (child, pos) = parent.unlink(node.id)?;
+
+        let atom = &mut self.render_api.make_guard(tag);
+        atom.record_undo(self.undo_history.clone(), format!("unlink {node_path}"));
+        atom.record_scene_change(SceneChange::Unlink {
+            parent: Arc::downgrade(&parent),
+            child,
+            pos,
+        });
+        Ok(())
+    }
 }
@@ -21,6 +21,7 @@ use darkfi::{
     event_graph::{
         self,
         proto::{EventPut, ProtocolEventGraph},
+        retention::RetentionPolicy,
         EventGraph, EventGraphPtr,
     },
     net::{session::SESSION_DEFAULT, settings::Settings as NetSettings, ChannelPtr, P2p, P2pPtr},
@@ -28,8 +29,8 @@ use darkfi::{
     Result as DarkFiResult,
 };
 use darkfi_serial::{
-    deserialize_async, serialize, serialize_async, AsyncEncodable, Decodable, Encodable,
-    SerialDecodable, SerialEncodable,
+    deserialize_async, deserialize_async_partial, serialize, serialize_async, AsyncEncodable,
+    Decodable, Encodable, SerialDecodable, SerialEncodable,
 };
 use sled_overlay::sled;
 use std::{
@@ -49,7 +50,13 @@ use crate::{
     ExecutorPtr,
 };
 
-use super::PluginSettings;
+use super::{
+    devsync::{DeviceSync, SyncCategory, SyncRecord, SYNC_MAGIC},
+    identity::{IdentitySignature, IdentityStore},
+    keystore::open_secure_storage,
+    vault::Vault,
+    PluginSettings,
+};
 
 const P2P_RETRY_TIME: u64 = 20;
 const COOLOFF_SLEEP_TIME: u64 = 20;
@@ -72,8 +79,8 @@ mod paths {
         get_external_storage_path().join("use_tor.txt")
     }
 
-    pub fn nick_filename() -> PathBuf {
-        get_appdata_path().join("/nick.txt")
+    pub fn secure_storage_path() -> PathBuf {
+        get_appdata_path().join("secrets")
     }
 
     pub fn p2p_datastore_path() -> PathBuf {
@@ -95,8 +102,8 @@ mod paths {
         dirs::data_local_dir().unwrap().join("darkfi/app/use_tor.txt")
     }
 
-    pub fn nick_filename() -> PathBuf {
-        dirs::cache_dir().unwrap().join("darkfi/app/nick.txt")
+    pub fn secure_storage_path() -> PathBuf {
+        dirs::data_local_dir().unwrap().join("darkfi/app/secrets")
     }
 
     pub fn p2p_datastore_path() -> PathBuf {
@@ -174,6 +181,8 @@ pub struct DarkIrc {
 
     seen_msgs: SyncMutex<SeenMessages>,
     nick: PropertyStr,
+    identities: IdentityStore,
+    devsync: DeviceSync,
 
     settings: PluginSettings,
 }
@@ -199,6 +208,16 @@ impl DarkIrc {
         let setting_tree = db.open_tree("settings")?;
         let settings = PluginSettings { setting_root, sled_tree: setting_tree };
 
+        let storage = open_secure_storage(&secure_storage_path())?;
+        let vault = Vault::open(db.open_tree("vault")?, storage.as_ref())?;
+        let identities = IdentityStore::new(vault, db.open_tree("identity_bindings")?);
+        let devsync = DeviceSync::new(
+            Vault::open(db.open_tree("devsync_vault")?, storage.as_ref())?,
+            db.open_tree("devsync")?,
+            db.open_tree("devsync_records")?,
+        )
+        .await?;
+
         let mut p2p_settings: NetSettings = Default::default();
         p2p_settings.app_version = semver::Version::parse("0.5.0").unwrap();
         if get_use_tor_filename().exists() {
@@ -255,6 +274,7 @@ impl DarkIrc {
             false,
             "darkirc_dag",
             1,
+            RetentionPolicy::default(),
             ex.clone(),
         )
         .await
@@ -266,10 +286,6 @@ impl DarkIrc {
             }
         };
 
-        if let Ok(prev_nick) = std::fs::read_to_string(nick_filename()) {
-            nick.set(&mut PropertyAtomicGuard::none(), prev_nick);
-        }
-
         let self_ = Arc::new(Self {
             node: node.clone(),
             tasks: OnceLock::new(),
@@ -279,6 +295,8 @@ impl DarkIrc {
 
             seen_msgs: SyncMutex::new(SeenMessages::new()),
             nick,
+            identities,
+            devsync,
             settings,
         });
         self_.clone().start(ex).await;
@@ -359,17 +377,40 @@ impl DarkIrc {
         loop {
             let ev = ev_sub.receive().await;
 
+            if let Some(envelope) = ev.content().strip_prefix(&SYNC_MAGIC) {
+                self.relay_sync(envelope).await;
+                continue
+            }
+
             // Try to deserialize the `Event`'s content into a `Privmsg`
-            let privmsg: Privmsg = match deserialize_async(ev.content()).await {
-                Ok(v) => v,
-                Err(e) => {
-                    e!("[IRC CLIENT] Failed deserializing incoming Privmsg event: {}", e);
-                    continue
-                }
-            };
+            let (privmsg, offset): (Privmsg, _) =
+                match deserialize_async_partial(ev.content()).await {
+                    Ok(v) => v,
+                    Err(e) => {
+                        e!("[IRC CLIENT] Failed deserializing incoming Privmsg event: {}", e);
+                        continue
+                    }
+                };
 
             let mut timest = ev.timestamp;
             let msg_id = privmsg.msg_id(timest);
+
+            // Messages authored by an identity carry a trailing signature.
+            // Unsigned messages are still accepted.
+            if offset < ev.content().len() {
+                let sig: IdentitySignature = match deserialize_async(&ev.content()[offset..]).await
+                {
+                    Ok(v) => v,
+                    Err(e) => {
+                        w!("Skipping Privmsg with malformed signature: {e}");
+                        continue
+                    }
+                };
+                if !sig.verify(&msg_id.0) {
+                    w!("Skipping Privmsg with invalid signature from {}", sig.public);
+                    continue
+                }
+            }
             t!(
                 "Relaying ev_id={:?}, ev={ev:?}, msg_id={msg_id}, privmsg={privmsg:?}, timest={timest}",
                 ev.id(),
@@ -464,15 +505,32 @@ impl DarkIrc {
     }
 
     async fn handle_send(&self, timest: Timestamp, channel: String, msg: String) {
-        let nick = self.nick.get();
+        // A channel bound to an identity uses its label as the nick
+        let identity = match self.identities.for_channel(&channel) {
+            Ok(identity) => identity,
+            Err(err) => {
+                e!("Unable to load identity for {channel}: {err}");
+                None
+            }
+        };
+        let nick = match &identity {
+            Some(identity) => identity.label.clone(),
+            None => self.nick.get(),
+        };
 
         // Send text to channel
         d!("Sending privmsg: {timest} {channel}: <{nick}> {msg}");
         let msg = Privmsg::new(channel, nick, msg);
+        let msg_id = msg.msg_id(timest);
+
+        let mut content = serialize_async(&msg).await;
+        if let Some(identity) = &identity {
+            content.extend(serialize_async(&identity.sign(&msg_id.0)).await);
+        }
+
         let evgr = self.event_graph.clone();
-        let mut event = event_graph::Event::new(serialize_async(&msg).await, &evgr).await;
+        let mut event = event_graph::Event::new(content, &evgr).await;
         event.timestamp = timest;
-        let msg_id = msg.msg_id(timest);
 
         // Keep track of our own messages so we don't apply timestamp correction to them
         // which messes up the msg id.
@@ -496,6 +554,184 @@ impl DarkIrc {
         self.p2p.broadcast(&EventPut(event)).await;
     }
 
+    async fn relay_sync(&self, envelope: &[u8]) {
+        let record = match self.devsync.receive(envelope) {
+            Ok(Some(record)) => record,
+            Ok(None) => return,
+            Err(err) => {
+                e!("Failed processing sync record: {err}");
+                return
+            }
+        };
+        d!("Applying sync record {}:{}", record.category.name(), record.key);
+
+        if let Err(err) = self.apply_sync(&record) {
+            w!("Unable to apply sync record {}:{}: {err}", record.category.name(), record.key);
+            return
+        }
+
+        // The UI applies its own state, a removed key is sent as empty
+        let node = self.node.upgrade().unwrap();
+        let value = record.value.unwrap_or_default();
+        let data = serialize(&(record.category.name().to_string(), record.key, value));
+        node.trigger("sync", data).await.unwrap();
+    }
+
+    fn apply_sync(&self, record: &SyncRecord) -> Result<()> {
+        match (record.category, &record.value) {
+            (SyncCategory::Nick, Some(nick)) => {
+                if nick.is_empty() {
+                    return Err(Error::DevSyncInvalidRecord)
+                }
+                self.nick.set(&mut PropertyAtomicGuard::none(), nick.clone());
+            }
+            (SyncCategory::Nick, None) => {}
+            (SyncCategory::Bindings, Some(label)) => self.identities.bind(&record.key, label)?,
+            (SyncCategory::Bindings, None) => self.identities.unbind(&record.key)?,
+            (SyncCategory::Contacts | SyncCategory::Channels | SyncCategory::ReadMarkers, _) => {}
+        }
+        Ok(())
+    }
+
+    /// Share a local change with the other paired devices
+    async fn publish_sync(&self, category: SyncCategory, key: &str, value: Option<String>) {
+        let content = match self.devsync.publish(category, key, value) {
+            Ok(Some(content)) => content,
+            Ok(None) => return,
+            Err(err) => {
+                e!("Failed recording sync change {}:{key}: {err}", category.name());
+                return
+            }
+        };
+        d!("Publishing sync record {}:{key}", category.name());
+
+        let evgr = self.event_graph.clone();
+        let event = event_graph::Event::new(content, &evgr).await;
+        if let Err(e) = evgr.dag_insert(&[event.clone()]).await {
+            e!("Failed inserting sync event to DAG: {e}");
+            return
+        }
+        self.p2p.broadcast(&EventPut(event)).await;
+    }
+
+    async fn process_identity_method(me: &Weak<Self>, sub: &MethodCallSub, method: &str) -> bool {
+        let Ok(method_call) = sub.receive().await else {
+            d!("Event relayer closed");
+            return false
+        };
+
+        t!("method called: {method}({method_call:?})");
+        assert!(method_call.send_res.is_none());
+
+        fn decode_args(data: &[u8], n: usize) -> std::io::Result<Vec<String>> {
+            let mut cur = Cursor::new(&data);
+            (0..n).map(|_| String::decode(&mut cur)).collect()
+        }
+
+        let Some(self_) = me.upgrade() else {
+            // Should not happen
+            panic!("self destroyed before identity method task was stopped!");
+        };
+
+        let n_args = if method == "bind_identity" { 2 } else { 1 };
+        let Ok(args) = decode_args(&method_call.data, n_args) else {
+            e!("{method}() method invalid arg data");
+            return true
+        };
+
+        let res = match method {
+            "create_identity" => self_.identities.create(&args[0]).map(|_| ()),
+            "switch_identity" => self_.identities.switch(&args[0]),
+            "bind_identity" => self_.identities.bind(&args[0], &args[1]),
+            _ => unreachable!(),
+        };
+        if let Err(err) = res {
+            w!("{method}({args:?}) failed: {err}");
+            return true
+        }
+
+        // Identities hold secret keys so they are never synced, only
+        // which label each channel uses.
+        match method {
+            "create_identity" => {}
+            "switch_identity" => {
+                let value = Some(args[0].clone());
+                self_.publish_sync(SyncCategory::Bindings, "*", value).await;
+            }
+            "bind_identity" => {
+                let value = Some(args[1].clone());
+                self_.publish_sync(SyncCategory::Bindings, &args[0], value).await;
+            }
+            _ => unreachable!(),
+        }
+
+        true
+    }
+
+    async fn process_devsync_method(me: &Weak<Self>, sub: &MethodCallSub, method: &str) -> bool {
+        let Ok(method_call) = sub.receive().await else {
+            d!("Event relayer closed");
+            return false
+        };
+
+        t!("method called: {method}({method_call:?})");
+        assert!(method_call.send_res.is_none());
+
+        let Some(self_) = me.upgrade() else {
+            // Should not happen
+            panic!("self destroyed before devsync method task was stopped!");
+        };
+
+        fn decode_category(data: &[u8]) -> std::io::Result<(String, bool)> {
+            let mut cur = Cursor::new(&data);
+            Ok((String::decode(&mut cur)?, bool::decode(&mut cur)?))
+        }
+        fn decode_state(data: &[u8]) -> std::io::Result<(String, String, String)> {
+            let mut cur = Cursor::new(&data);
+            Ok((String::decode(&mut cur)?, String::decode(&mut cur)?, String::decode(&mut cur)?))
+        }
+
+        let res = match method {
+            "pair_device" => {
+                let Ok(code) = String::decode(&mut Cursor::new(&method_call.data)) else {
+                    e!("{method}() method invalid arg data");
+                    return true
+                };
+                self_.devsync.pair(code).await
+            }
+            "unpair_device" => self_.devsync.unpair(),
+            "set_sync_category" => {
+                let Ok((category, enabled)) = decode_category(&method_call.data) else {
+                    e!("{method}() method invalid arg data");
+                    return true
+                };
+                SyncCategory::from_name(&category)
+                    .and_then(|category| self_.devsync.set_enabled(category, enabled))
+            }
+            "set_sync_state" => {
+                let Ok((category, key, value)) = decode_state(&method_call.data) else {
+                    e!("{method}() method invalid arg data");
+                    return true
+                };
+                match SyncCategory::from_name(&category) {
+                    Ok(category) if category.is_app_state() => {
+                        let value = if value.is_empty() { None } else { Some(value) };
+                        self_.publish_sync(category, &key, value).await;
+                        Ok(())
+                    }
+                    Ok(_) => Err(Error::DevSyncUnknownCategory),
+                    Err(err) => Err(err),
+                }
+            }
+            _ => unreachable!(),
+        };
+        if let Err(err) = res {
+            w!("{method}() failed: {err}");
+        }
+
+        true
+    }
+
     async fn apply_settings(self_: Arc<Self>, _: BatchGuardPtr) {
         self_.settings.save_settings();
 
@@ -524,11 +760,29 @@ impl DarkIrc {
         let send_method_task =
             ex.spawn(async move { while Self::process_send(&me2, &method_sub).await {} });
 
+        let mut identity_tasks = vec![];
+        for method in ["create_identity", "switch_identity", "bind_identity"] {
+            let method_sub = node.subscribe_method_call(method).unwrap();
+            let me2 = me.clone();
+            identity_tasks.push(ex.spawn(async move {
+                while Self::process_identity_method(&me2, &method_sub, method).await {}
+            }));
+        }
+
+        for method in ["pair_device", "unpair_device", "set_sync_category", "set_sync_state"] {
+            let method_sub = node.subscribe_method_call(method).unwrap();
+            let me2 = me.clone();
+            identity_tasks.push(ex.spawn(async move {
+                while Self::process_devsync_method(&me2, &method_sub, method).await {}
+            }));
+        }
+
         let mut on_modify = OnModify::new(ex.clone(), self.node.clone(), me.clone());
-        async fn save_nick(self_: Arc<DarkIrc>, _batch: BatchGuardPtr) {
-            let _ = std::fs::write(nick_filename(), self_.nick.get());
+        async fn sync_nick(self_: Arc<DarkIrc>, _batch: BatchGuardPtr) {
+            let nick = self_.nick.get();
+            self_.publish_sync(SyncCategory::Nick, "", Some(nick)).await;
         }
-        on_modify.when_change(self.nick.prop(), save_nick);
+        on_modify.when_change(self.nick.prop(), sync_nick);
 
         // `apply_settings` is triggered if any setting changes
         for setting_node in self.settings.setting_root.get_children().iter() {
@@ -546,6 +800,7 @@ impl DarkIrc {
         let dag_task = ex.spawn(self.clone().dag_sync(channel_sub));
 
         let mut tasks = vec![send_method_task, ev_task, dag_task];
+        tasks.append(&mut identity_tasks);
         tasks.append(&mut on_modify.tasks);
         self.tasks.set(tasks).unwrap();
     }
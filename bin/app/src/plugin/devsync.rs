@@ -0,0 +1,347 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Encrypted state sync between devices of the same user.
+//!
+//! Devices paired with the same code derive a shared keypair and publish
+//! [`SyncRecord`]s as encrypted event graph events. Only paired devices can
+//! read them, everybody else just relays opaque bytes. Concurrent edits are
+//! resolved with a Lamport clock, ties broken by the device ID, so every
+//! device converges on the same value.
+//!
+//! The event graph is public, so only state which is harmless to leak if the
+//! pairing code is ever guessed is synced. Secret keys never leave a device.
+//! Envelopes carry nothing identifying the sync group, every paired device
+//! trial decrypts them, and the code is stretched with Argon2 so guessing it
+//! offline is expensive.
+
+use argon2::Argon2;
+use darkfi_sdk::crypto::{note::AeadEncryptedNote, util::hash_to_base, PublicKey, SecretKey};
+use darkfi_serial::{deserialize, serialize, SerialDecodable, SerialEncodable};
+use rand::{rngs::OsRng, RngCore};
+use sled_overlay::sled;
+use std::sync::Mutex as SyncMutex;
+
+use super::vault::Vault;
+use crate::error::{Error, Result};
+
+macro_rules! d { ($($arg:tt)*) => { debug!(target: "plugin::devsync", $($arg)*); } }
+macro_rules! w { ($($arg:tt)*) => { warn!(target: "plugin::devsync", $($arg)*); } }
+
+/// Prefix of event content carrying a sync record. A `Privmsg` starts with
+/// the channel length, and `0xff` would mean a length over 2^32, so the two
+/// can never be confused.
+pub const SYNC_MAGIC: [u8; 4] = [0xff, b'd', b's', b'y'];
+
+/// Shortest pairing code accepted. Anyone knowing the code can read the
+/// synced state, so it must not be guessable.
+const MIN_PAIRING_CODE_LEN: usize = 16;
+
+/// Argon2 needs a salt. Both devices must derive the same key from only
+/// the code, so it's fixed.
+const PAIRING_SALT: &[u8] = b"DarkFi_DevSync__";
+
+const PAIRING_KEY: &[u8] = b"pairing_code";
+const DEVICE_ID_KEY: &[u8] = b"device_id";
+const CLOCK_KEY: &[u8] = b"clock";
+const DISABLED_PREFIX: &str = "disabled:";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, SerialEncodable, SerialDecodable)]
+pub enum SyncCategory {
+    Nick,
+    /// Which identity label is used on each channel. The identities
+    /// themselves are secret and must be created on each device.
+    Bindings,
+    Contacts,
+    Channels,
+    /// Timestamp of the last message read on each channel
+    ReadMarkers,
+}
+
+impl SyncCategory {
+    pub const ALL: [Self; 5] =
+        [Self::Nick, Self::Bindings, Self::Contacts, Self::Channels, Self::ReadMarkers];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Nick => "nick",
+            Self::Bindings => "bindings",
+            Self::Contacts => "contacts",
+            Self::Channels => "channels",
+            Self::ReadMarkers => "read_markers",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self> {
+        Self::ALL.into_iter().find(|c| c.name() == name).ok_or(Error::DevSyncUnknownCategory)
+    }
+
+    /// Categories kept by the UI rather than the plugin itself
+    pub fn is_app_state(&self) -> bool {
+        matches!(self, Self::Contacts | Self::Channels | Self::ReadMarkers)
+    }
+}
+
+/// A single key being set or removed on one of the devices
+#[derive(Clone, Debug, PartialEq, Eq, SerialEncodable, SerialDecodable)]
+pub struct SyncRecord {
+    pub category: SyncCategory,
+    pub key: String,
+    /// `None` removes the key
+    pub value: Option<String>,
+    pub clock: u64,
+    pub device: u64,
+}
+
+impl SyncRecord {
+    /// Whether this record wins over `other` for the same key
+    pub fn supersedes(&self, other: &Self) -> bool {
+        (self.clock, self.device) > (other.clock, other.device)
+    }
+
+    fn db_key(category: SyncCategory, key: &str) -> Vec<u8> {
+        format!("{}:{key}", category.name()).into_bytes()
+    }
+}
+
+/// Keys derived from the pairing code
+struct SyncKey {
+    secret: SecretKey,
+    public: PublicKey,
+}
+
+impl SyncKey {
+    /// Deliberately slow, run it off the executor
+    fn derive(code: &str) -> Result<Self> {
+        let mut stretched = [0u8; 32];
+        if let Err(e) =
+            Argon2::default().hash_password_into(code.as_bytes(), PAIRING_SALT, &mut stretched)
+        {
+            w!("Unable to stretch pairing code: {e}");
+            return Err(Error::DevSyncWeakCode)
+        }
+        let secret = SecretKey::from(hash_to_base(b"DarkFi_DevSync__", &[&stretched]));
+        let public = PublicKey::from_secret(secret);
+        Ok(Self { secret, public })
+    }
+
+    fn seal(&self, record: &SyncRecord) -> Result<Vec<u8>> {
+        let Ok(note) = AeadEncryptedNote::encrypt(record, &self.public, &mut OsRng) else {
+            return Err(Error::VaultEncryptFailed)
+        };
+        let mut content = SYNC_MAGIC.to_vec();
+        content.extend(serialize(&note));
+        Ok(content)
+    }
+
+    /// Decrypt an envelope, without the leading magic. Returns `None` for
+    /// envelopes belonging to another sync group.
+    fn open(&self, envelope: &[u8]) -> Option<SyncRecord> {
+        let note: AeadEncryptedNote = match deserialize(envelope) {
+            Ok(v) => v,
+            Err(e) => {
+                w!("Malformed sync envelope: {e}");
+                return None
+            }
+        };
+        // Most envelopes belong to other users, so failing is expected
+        note.decrypt(&self.secret).ok()
+    }
+}
+
+/// Device sync state. The pairing code lives in the vault, the clock,
+/// category selection and last applied records in a plain tree.
+pub struct DeviceSync {
+    vault: Vault,
+    state: sled::Tree,
+    records: sled::Tree,
+    device: u64,
+    key: SyncMutex<Option<SyncKey>>,
+}
+
+impl DeviceSync {
+    pub async fn new(vault: Vault, state: sled::Tree, records: sled::Tree) -> Result<Self> {
+        let device = match state.get(DEVICE_ID_KEY)? {
+            Some(bytes) => deserialize(&bytes).map_err(|_| Error::SledDbErr)?,
+            None => {
+                let device = OsRng.next_u64();
+                state.insert(DEVICE_ID_KEY, serialize(&device))?;
+                device
+            }
+        };
+
+        let key = match vault.get::<String>(PAIRING_KEY)? {
+            Some(code) => Some(smol::unblock(move || SyncKey::derive(&code)).await?),
+            None => None,
+        };
+        Ok(Self { vault, state, records, device, key: SyncMutex::new(key) })
+    }
+
+    pub fn is_paired(&self) -> bool {
+        self.key.lock().unwrap().is_some()
+    }
+
+    /// Join the sync group of every device using the same code
+    pub async fn pair(&self, code: String) -> Result<()> {
+        if code.len() < MIN_PAIRING_CODE_LEN {
+            return Err(Error::DevSyncWeakCode)
+        }
+        self.vault.insert(PAIRING_KEY, &code)?;
+        let key = smol::unblock(move || SyncKey::derive(&code)).await?;
+        *self.key.lock().unwrap() = Some(key);
+        d!("Device {:016x} paired", self.device);
+        Ok(())
+    }
+
+    pub fn unpair(&self) -> Result<()> {
+        self.vault.remove(PAIRING_KEY)?;
+        *self.key.lock().unwrap() = None;
+        Ok(())
+    }
+
+    pub fn is_enabled(&self, category: SyncCategory) -> bool {
+        let key = format!("{DISABLED_PREFIX}{}", category.name());
+        !matches!(self.state.contains_key(key), Ok(true))
+    }
+
+    /// Select whether a category is synced. All categories are by default.
+    pub fn set_enabled(&self, category: SyncCategory, enabled: bool) -> Result<()> {
+        let key = format!("{DISABLED_PREFIX}{}", category.name());
+        if enabled {
+            self.state.remove(key)?;
+        } else {
+            self.state.insert(key, vec![])?;
+        }
+        Ok(())
+    }
+
+    fn clock(&self) -> Result<u64> {
+        match self.state.get(CLOCK_KEY)? {
+            Some(bytes) => deserialize(&bytes).map_err(|_| Error::SledDbErr),
+            None => Ok(0),
+        }
+    }
+
+    fn latest(&self, category: SyncCategory, key: &str) -> Result<Option<SyncRecord>> {
+        match self.records.get(SyncRecord::db_key(category, key))? {
+            Some(bytes) => Ok(deserialize(&bytes).ok()),
+            None => Ok(None),
+        }
+    }
+
+    /// Record a local change, returning the event content to publish.
+    /// Returns `None` when unpaired, the category is not synced, or the
+    /// value is already the latest known one.
+    pub fn publish(
+        &self,
+        category: SyncCategory,
+        key: &str,
+        value: Option<String>,
+    ) -> Result<Option<Vec<u8>>> {
+        if !self.is_enabled(category) {
+            return Ok(None)
+        }
+        let guard = self.key.lock().unwrap();
+        let Some(sync_key) = guard.as_ref() else { return Ok(None) };
+
+        // Remote changes applied locally must not be echoed back
+        if let Some(latest) = self.latest(category, key)? {
+            if latest.value == value {
+                return Ok(None)
+            }
+        }
+
+        let clock = self.clock()? + 1;
+        self.state.insert(CLOCK_KEY, serialize(&clock))?;
+
+        let record =
+            SyncRecord { category, key: key.to_string(), value, clock, device: self.device };
+        self.records.insert(SyncRecord::db_key(category, key), serialize(&record))?;
+        sync_key.seal(&record).map(Some)
+    }
+
+    /// Decrypt a received envelope and run conflict resolution on it.
+    /// Returns the record only if it should be applied locally.
+    pub fn receive(&self, envelope: &[u8]) -> Result<Option<SyncRecord>> {
+        let record = {
+            let guard = self.key.lock().unwrap();
+            let Some(sync_key) = guard.as_ref() else { return Ok(None) };
+            let Some(record) = sync_key.open(envelope) else { return Ok(None) };
+            record
+        };
+
+        // Keep the clock ahead of anything we have seen, even for
+        // records we end up ignoring.
+        if record.clock > self.clock()? {
+            self.state.insert(CLOCK_KEY, serialize(&record.clock))?;
+        }
+
+        if !self.is_enabled(record.category) {
+            return Ok(None)
+        }
+        if let Some(latest) = self.latest(record.category, &record.key)? {
+            if !record.supersedes(&latest) {
+                d!("Ignoring stale sync record {}:{}", record.category.name(), record.key);
+                return Ok(None)
+            }
+        }
+
+        self.records
+            .insert(SyncRecord::db_key(record.category, &record.key), serialize(&record))?;
+        Ok(Some(record))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(clock: u64, device: u64) -> SyncRecord {
+        SyncRecord {
+            category: SyncCategory::Bindings,
+            key: "#dev".to_string(),
+            value: Some("alice".to_string()),
+            clock,
+            device,
+        }
+    }
+
+    #[test]
+    fn sync_record_ordering() {
+        assert!(record(2, 0).supersedes(&record(1, 9)));
+        assert!(record(1, 9).supersedes(&record(1, 3)));
+        assert!(!record(1, 3).supersedes(&record(1, 3)));
+    }
+
+    #[test]
+    fn sync_key_seal_open() {
+        let key = SyncKey::derive("correct horse battery staple").unwrap();
+        let other = SyncKey::derive("some other pairing code").unwrap();
+
+        let content = key.seal(&record(1, 1)).unwrap();
+        assert!(content.starts_with(&SYNC_MAGIC));
+
+        let envelope = &content[SYNC_MAGIC.len()..];
+        assert_eq!(key.open(envelope), Some(record(1, 1)));
+        assert_eq!(other.open(envelope), None);
+
+        // Nothing in the envelope is derived from the key alone
+        let again = key.seal(&record(1, 1)).unwrap();
+        assert_ne!(content, again);
+    }
+}
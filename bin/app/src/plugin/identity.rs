@@ -0,0 +1,144 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::crypto::{
+    schnorr::{SchnorrPublic, SchnorrSecret, Signature},
+    PublicKey, SecretKey,
+};
+use darkfi_serial::{SerialDecodable, SerialEncodable};
+use rand::rngs::OsRng;
+use sled_overlay::sled;
+
+use super::vault::Vault;
+use crate::error::{Error, Result};
+
+macro_rules! d { ($($arg:tt)*) => { debug!(target: "plugin::identity", $($arg)*); } }
+
+/// Binding key used for the active identity, which applies to every channel
+/// without an explicit binding.
+const DEFAULT_BINDING: &str = "*";
+
+/// A persona used to author messages. The label doubles as the nick.
+#[derive(Clone, SerialEncodable, SerialDecodable)]
+pub struct Identity {
+    pub label: String,
+    pub secret: SecretKey,
+}
+
+impl Identity {
+    pub fn public(&self) -> PublicKey {
+        PublicKey::from_secret(self.secret)
+    }
+
+    pub fn sign(&self, message: &[u8]) -> IdentitySignature {
+        IdentitySignature { public: self.public(), signature: self.secret.sign(message) }
+    }
+}
+
+/// Signature appended to outgoing messages authored by an [`Identity`]
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct IdentitySignature {
+    pub public: PublicKey,
+    pub signature: Signature,
+}
+
+impl IdentitySignature {
+    pub fn verify(&self, message: &[u8]) -> bool {
+        self.public.verify(message, &self.signature)
+    }
+}
+
+/// Identity keys live in the encrypted vault, while the channel bindings
+/// are kept in a plain tree since they only reference labels.
+pub struct IdentityStore {
+    vault: Vault,
+    bindings: sled::Tree,
+}
+
+impl IdentityStore {
+    pub fn new(vault: Vault, bindings: sled::Tree) -> Self {
+        Self { vault, bindings }
+    }
+
+    /// Create a new identity with a fresh keypair
+    pub fn create(&self, label: &str) -> Result<PublicKey> {
+        if label.is_empty() || label == DEFAULT_BINDING {
+            return Err(Error::IdentityInvalidLabel)
+        }
+        if self.get(label)?.is_some() {
+            return Err(Error::IdentityAlreadyExists)
+        }
+
+        let identity = Identity { label: label.to_string(), secret: SecretKey::random(&mut OsRng) };
+        self.vault.insert(label.as_bytes(), &identity)?;
+        d!("Created identity {label}: {}", identity.public());
+        Ok(identity.public())
+    }
+
+    /// Remove an identity along with all of its channel bindings
+    pub fn remove(&self, label: &str) -> Result<()> {
+        self.vault.remove(label.as_bytes())?;
+        for binding in self.bindings.iter() {
+            let (channel, bound) = binding?;
+            if bound.as_ref() == label.as_bytes() {
+                self.bindings.remove(channel)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, label: &str) -> Result<Option<Identity>> {
+        self.vault.get(label.as_bytes())
+    }
+
+    /// Labels of all stored identities
+    pub fn labels(&self) -> Vec<String> {
+        self.vault.keys().into_iter().filter_map(|k| String::from_utf8(k).ok()).collect()
+    }
+
+    /// Make `label` the identity used for channels without their own binding
+    pub fn switch(&self, label: &str) -> Result<()> {
+        self.bind(DEFAULT_BINDING, label)
+    }
+
+    pub fn bind(&self, channel: &str, label: &str) -> Result<()> {
+        if self.get(label)?.is_none() {
+            return Err(Error::IdentityNotFound)
+        }
+        self.bindings.insert(channel, label.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn unbind(&self, channel: &str) -> Result<()> {
+        self.bindings.remove(channel)?;
+        Ok(())
+    }
+
+    /// Identity which should author messages sent to `channel`, if any
+    pub fn for_channel(&self, channel: &str) -> Result<Option<Identity>> {
+        let label = match self.bindings.get(channel)? {
+            Some(label) => label,
+            None => match self.bindings.get(DEFAULT_BINDING)? {
+                Some(label) => label,
+                None => return Ok(None),
+            },
+        };
+        let Ok(label) = String::from_utf8(label.to_vec()) else { return Ok(None) };
+        self.get(&label)
+    }
+}
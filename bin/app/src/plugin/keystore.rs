@@ -0,0 +1,122 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::secure_storage::SecureStorage;
+use std::path::Path;
+
+use crate::error::Result;
+
+#[cfg(not(target_os = "android"))]
+macro_rules! e { ($($arg:tt)*) => { error!(target: "plugin::keystore", $($arg)*); } }
+#[cfg(not(target_os = "android"))]
+macro_rules! w { ($($arg:tt)*) => { warn!(target: "plugin::keystore", $($arg)*); } }
+
+/// Service name secrets are stored under in the OS keystore
+#[cfg(not(target_os = "android"))]
+const SERVICE: &str = "darkfi-app";
+
+/// Passphrase of the file storage, used when there is no OS keystore
+#[cfg(not(target_os = "android"))]
+const PASSPHRASE_ENV: &str = "DARKFI_APP_PASSPHRASE";
+
+/// Open the platform keystore, falling back to encrypted files in
+/// `fallback_dir` when it's unavailable. The files are encrypted with
+/// the passphrase from `DARKFI_APP_PASSPHRASE`, and opening fails when
+/// it isn't set, rather than storing the secrets unprotected.
+pub fn open_secure_storage(fallback_dir: &Path) -> Result<Box<dyn SecureStorage>> {
+    #[cfg(target_os = "android")]
+    {
+        let _ = fallback_dir;
+        Ok(Box::new(AndroidKeystoreStorage))
+    }
+
+    #[cfg(not(target_os = "android"))]
+    {
+        use crate::error::Error;
+        use darkfi_sdk::secure_storage::{platform_storage, EncryptedFileStorage};
+
+        match platform_storage(SERVICE) {
+            Ok(storage) => return Ok(storage),
+            Err(err) => w!("OS keystore unavailable, using file storage: {err}"),
+        }
+
+        // There is no passphrase prompt in the app, so it has to come from
+        // the environment.
+        let passphrase = match std::env::var(PASSPHRASE_ENV) {
+            Ok(passphrase) if !passphrase.is_empty() => passphrase,
+            _ => {
+                e!(
+                    "No OS keystore available, set {PASSPHRASE_ENV} to encrypt the secrets in {}",
+                    fallback_dir.display()
+                );
+                return Err(Error::SecureStorageFailed)
+            }
+        };
+
+        match EncryptedFileStorage::new(fallback_dir, passphrase.as_bytes()) {
+            Ok(storage) => Ok(Box::new(storage)),
+            Err(err) => {
+                w!("Unable to open file storage: {err}");
+                Err(Error::SecureStorageFailed)
+            }
+        }
+    }
+}
+
+/// [`SecureStorage`] backed by the Android Keystore through `MainActivity`
+#[cfg(target_os = "android")]
+pub struct AndroidKeystoreStorage;
+
+#[cfg(target_os = "android")]
+mod android_keystore {
+    use darkfi::util::encoding::base64;
+    use darkfi_sdk::{
+        error::{SecureStorageError, SecureStorageResult},
+        secure_storage::SecureStorage,
+    };
+
+    use super::AndroidKeystoreStorage;
+    use crate::android::{keystore_delete, keystore_get, keystore_set};
+
+    impl SecureStorage for AndroidKeystoreStorage {
+        fn get(&self, name: &str) -> SecureStorageResult<Option<Vec<u8>>> {
+            let Some(value) = keystore_get(name) else { return Ok(None) };
+            if value.is_empty() {
+                return Err(SecureStorageError::DecryptFailed(name.to_string()))
+            }
+            match base64::decode(&value) {
+                Some(secret) => Ok(Some(secret)),
+                None => Err(SecureStorageError::DecryptFailed(name.to_string())),
+            }
+        }
+
+        fn set(&self, name: &str, secret: &[u8]) -> SecureStorageResult<()> {
+            match keystore_set(name, &base64::encode(secret)) {
+                Some(()) => Ok(()),
+                None => Err(SecureStorageError::Backend(format!("Failed to store \"{name}\""))),
+            }
+        }
+
+        fn delete(&self, name: &str) -> SecureStorageResult<()> {
+            match keystore_delete(name) {
+                Some(()) => Ok(()),
+                None => Err(SecureStorageError::Backend(format!("Failed to delete \"{name}\""))),
+            }
+        }
+    }
+}
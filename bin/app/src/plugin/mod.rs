@@ -20,9 +20,17 @@ use sled_overlay::sled;
 use std::{array::TryFromSliceError, string::FromUtf8Error, sync::Arc};
 
 pub mod darkirc;
+pub mod devsync;
+pub mod identity;
+pub mod keystore;
+pub mod vault;
+pub mod wallet;
 #[cfg(feature = "enable-plugins")]
 pub use darkirc::DarkIrc;
 pub use darkirc::DarkIrcPtr;
+#[cfg(feature = "enable-plugins")]
+pub use wallet::Wallet;
+pub use wallet::WalletPtr;
 
 use darkfi::net::Settings as NetSettings;
 
@@ -36,12 +44,14 @@ pub struct PluginSettings {
     pub sled_tree: sled::Tree,
 }
 impl PluginSettings {
+    /// The value is marked as a user pref, see `app::prefs`
     pub fn add_setting(&self, name: &str, default: PropertyValue) -> Option<SceneNodePtr> {
         let atom = &mut PropertyAtomicGuard::none();
         let node = match default {
             PropertyValue::Bool(b) => {
                 let mut node = SceneNode::new(name, SceneNodeType::Setting);
-                let prop = Property::new("value", PropertyType::Bool, PropertySubType::Null);
+                let mut prop = Property::new("value", PropertyType::Bool, PropertySubType::Null);
+                prop.mark_user_pref();
                 node.add_property(prop).unwrap();
                 let prop = Property::new("default", PropertyType::Bool, PropertySubType::Null);
                 node.add_property(prop).unwrap();
@@ -51,7 +61,8 @@ impl PluginSettings {
             }
             PropertyValue::Uint32(u) => {
                 let mut node = SceneNode::new(name, SceneNodeType::Setting);
-                let prop = Property::new("value", PropertyType::Uint32, PropertySubType::Null);
+                let mut prop = Property::new("value", PropertyType::Uint32, PropertySubType::Null);
+                prop.mark_user_pref();
                 node.add_property(prop).unwrap();
                 let prop = Property::new("default", PropertyType::Uint32, PropertySubType::Null);
                 node.add_property(prop).unwrap();
@@ -61,7 +72,8 @@ impl PluginSettings {
             }
             PropertyValue::Float32(f) => {
                 let mut node = SceneNode::new(name, SceneNodeType::Setting);
-                let prop = Property::new("value", PropertyType::Float32, PropertySubType::Null);
+                let mut prop = Property::new("value", PropertyType::Float32, PropertySubType::Null);
+                prop.mark_user_pref();
                 node.add_property(prop).unwrap();
                 let prop = Property::new("default", PropertyType::Float32, PropertySubType::Null);
                 node.add_property(prop).unwrap();
@@ -71,7 +83,8 @@ impl PluginSettings {
             }
             PropertyValue::Str(s) => {
                 let mut node = SceneNode::new(name, SceneNodeType::Setting);
-                let prop = Property::new("value", PropertyType::Str, PropertySubType::Null);
+                let mut prop = Property::new("value", PropertyType::Str, PropertySubType::Null);
+                prop.mark_user_pref();
                 node.add_property(prop).unwrap();
                 let prop = Property::new("default", PropertyType::Str, PropertySubType::Null);
                 node.add_property(prop).unwrap();
@@ -262,6 +275,8 @@ impl PluginSettings {
             "net.time_with_no_connections",
             PropertyValue::Uint32(p2p_settings.time_with_no_connections as u32),
         );
+        // Comma separated seed URLs. Empty uses the built-in seeds.
+        self.add_setting("net.seeds", PropertyValue::Str(String::new()));
     }
 
     // Update a NetSettings from settings in the node tree
@@ -319,5 +334,17 @@ impl PluginSettings {
             .unwrap()
             .get_property_u32("value")
             .unwrap() as u64;
+
+        let seeds = self.get_setting("net.seeds").unwrap().get_property_str("value").unwrap();
+        let seeds: Vec<_> = seeds.split(',').map(str::trim).filter(|s| !s.is_empty()).collect();
+        if !seeds.is_empty() {
+            p2p_settings.seeds.clear();
+            for seed in seeds {
+                match url::Url::parse(seed) {
+                    Ok(url) => p2p_settings.seeds.push(url),
+                    Err(e) => warn!(target: "plugin", "Ignoring invalid seed '{seed}': {e}"),
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,97 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::{
+    crypto::{note::AeadEncryptedNote, PublicKey, SecretKey},
+    secure_storage::SecureStorage,
+};
+use darkfi_serial::{deserialize, serialize, Decodable, Encodable};
+use rand::rngs::OsRng;
+use sled_overlay::sled;
+
+use crate::error::{Error, Result};
+
+macro_rules! e { ($($arg:tt)*) => { error!(target: "plugin::vault", $($arg)*); } }
+
+/// Name of the vault secret in the secure storage
+const VAULT_SECRET: &str = "vault";
+
+/// Encrypted key/value storage on top of a sled tree.
+///
+/// Every value is encrypted to the vault public key, so whatever is stored
+/// here is unreadable without the vault secret which lives in the platform
+/// secure storage.
+pub struct Vault {
+    tree: sled::Tree,
+    secret: SecretKey,
+    public: PublicKey,
+}
+
+impl Vault {
+    /// Open the vault using the secret kept in `storage`, creating a new
+    /// one when it does not exist yet.
+    pub fn open(tree: sled::Tree, storage: &dyn SecureStorage) -> Result<Self> {
+        let generate = || serialize(&SecretKey::random(&mut OsRng));
+        let secret = match storage.get_or_create(VAULT_SECRET, &generate) {
+            Ok(bytes) => match deserialize::<SecretKey>(&bytes) {
+                Ok(secret) => secret,
+                Err(err) => {
+                    e!("Vault key is corrupted: {err}");
+                    return Err(Error::VaultKeyInvalid)
+                }
+            },
+            Err(err) => {
+                e!("Unable to load vault key: {err}");
+                return Err(Error::SecureStorageFailed)
+            }
+        };
+
+        let public = PublicKey::from_secret(secret);
+        Ok(Self { tree, secret, public })
+    }
+
+    pub fn get<D: Decodable>(&self, key: &[u8]) -> Result<Option<D>> {
+        let Some(bytes) = self.tree.get(key)? else { return Ok(None) };
+
+        let Ok(note) = deserialize::<AeadEncryptedNote>(&bytes) else {
+            return Err(Error::VaultDecryptFailed)
+        };
+        match note.decrypt(&self.secret) {
+            Ok(value) => Ok(Some(value)),
+            Err(_) => Err(Error::VaultDecryptFailed),
+        }
+    }
+
+    pub fn insert<E: Encodable>(&self, key: &[u8], value: &E) -> Result<()> {
+        let Ok(note) = AeadEncryptedNote::encrypt(value, &self.public, &mut OsRng) else {
+            return Err(Error::VaultEncryptFailed)
+        };
+        self.tree.insert(key, serialize(&note))?;
+        Ok(())
+    }
+
+    pub fn remove(&self, key: &[u8]) -> Result<()> {
+        self.tree.remove(key)?;
+        Ok(())
+    }
+
+    /// All keys currently stored in the vault
+    pub fn keys(&self) -> Vec<Vec<u8>> {
+        self.tree.iter().keys().filter_map(|k| k.ok()).map(|k| k.to_vec()).collect()
+    }
+}
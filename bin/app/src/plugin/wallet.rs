@@ -0,0 +1,419 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{
+    rpc::client::RpcClient,
+    system::{sleep, CondVar},
+    util::parse::encode_base10,
+};
+use darkfi_sdk::crypto::PublicKey;
+use darkfi_serial::{serialize, Decodable, Encodable};
+use drk::{money::BALANCE_BASE10_DECIMALS, Drk};
+use sled_overlay::sled;
+use std::{
+    io::Cursor,
+    str::FromStr,
+    sync::{Arc, OnceLock, Weak},
+};
+use url::Url;
+
+use crate::{
+    error::{Error, Result},
+    prop::{
+        BatchGuardPtr, PropertyAtomicGuard, PropertyBool, PropertyStr, PropertyUint32,
+        PropertyValue, Role,
+    },
+    scene::{MethodCallSub, Pimpl, SceneNode, SceneNodeType, SceneNodeWeak},
+    ui::OnModify,
+    ExecutorPtr,
+};
+
+use super::{keystore::open_secure_storage, PluginSettings};
+
+/// How often we scan darkfid for new blocks
+const SYNC_INTERVAL: u64 = 20;
+/// Wait before trying to reach darkfid again
+const RPC_RETRY_TIME: u64 = 10;
+
+/// darkfid JSON-RPC endpoint used until the user sets another one
+const DEFAULT_ENDPOINT: &str = "tcp://127.0.0.1:8340";
+
+/// Name of the wallet database key in the secure storage
+const WALLET_SECRET: &str = "wallet";
+
+#[cfg(target_os = "android")]
+mod paths {
+    use crate::android::get_appdata_path;
+    use std::path::PathBuf;
+
+    pub fn wallet_path() -> PathBuf {
+        get_appdata_path().join("wallet.db")
+    }
+    pub fn settings_path() -> PathBuf {
+        get_appdata_path().join("wallet_settings")
+    }
+    pub fn secure_storage_path() -> PathBuf {
+        get_appdata_path().join("secrets")
+    }
+}
+
+#[cfg(not(target_os = "android"))]
+mod paths {
+    use std::path::PathBuf;
+
+    pub fn wallet_path() -> PathBuf {
+        dirs::data_local_dir().unwrap().join("darkfi/app/wallet.db")
+    }
+    pub fn settings_path() -> PathBuf {
+        dirs::data_local_dir().unwrap().join("darkfi/app/wallet_settings")
+    }
+    pub fn secure_storage_path() -> PathBuf {
+        dirs::data_local_dir().unwrap().join("darkfi/app/secrets")
+    }
+}
+
+use paths::*;
+
+macro_rules! t { ($($arg:tt)*) => { trace!(target: "plugin::wallet", $($arg)*); } }
+macro_rules! d { ($($arg:tt)*) => { debug!(target: "plugin::wallet", $($arg)*); } }
+macro_rules! i { ($($arg:tt)*) => { info!(target: "plugin::wallet", $($arg)*); } }
+macro_rules! e { ($($arg:tt)*) => { error!(target: "plugin::wallet", $($arg)*); } }
+macro_rules! w { ($($arg:tt)*) => { warn!(target: "plugin::wallet", $($arg)*); } }
+
+pub type WalletPtr = Arc<Wallet>;
+
+/// Light wallet which keeps its own coins and follows the chain through
+/// a darkfid node's JSON-RPC.
+pub struct Wallet {
+    node: SceneNodeWeak,
+    tasks: OnceLock<Vec<smol::Task<()>>>,
+    ex: ExecutorPtr,
+
+    drk: async_lock::Mutex<Drk>,
+    /// Wakes the sync task up early, for example when the endpoint changes
+    resync: CondVar,
+
+    address: PropertyStr,
+    height: PropertyUint32,
+    is_connected: PropertyBool,
+
+    settings: PluginSettings,
+}
+
+impl Wallet {
+    pub async fn new(node: SceneNodeWeak, ex: ExecutorPtr) -> Result<Pimpl> {
+        let node_ref = &node.upgrade().unwrap();
+        let address = PropertyStr::wrap(node_ref, Role::Internal, "address", 0).unwrap();
+        let height = PropertyUint32::wrap(node_ref, Role::Internal, "height", 0).unwrap();
+        let is_connected = PropertyBool::wrap(node_ref, Role::Internal, "is_connected", 0).unwrap();
+
+        let setting_root = Arc::new(SceneNode::new("setting", SceneNodeType::SettingRoot));
+        node_ref.link(setting_root.clone());
+
+        let db = match sled::open(settings_path()) {
+            Ok(db) => db,
+            Err(err) => {
+                e!("Sled database '{}' failed to open: {err}!", settings_path().display());
+                return Err(Error::SledDbErr)
+            }
+        };
+        let settings = PluginSettings { setting_root, sled_tree: db.open_tree("settings")? };
+        settings.add_setting("rpc_endpoint", PropertyValue::Str(DEFAULT_ENDPOINT.to_string()));
+        settings.load_settings();
+
+        i!("Opening wallet");
+        let storage = open_secure_storage(&secure_storage_path())?;
+        let generate = || rand::random::<[u8; 32]>().to_vec();
+        let wallet_key: [u8; 32] = match storage.get_or_create(WALLET_SECRET, &generate) {
+            Ok(bytes) => match bytes.try_into() {
+                Ok(key) => key,
+                Err(_) => {
+                    e!("Wallet key is corrupted");
+                    return Err(Error::WalletKeyInvalid)
+                }
+            },
+            Err(err) => {
+                e!("Unable to load wallet key: {err}");
+                return Err(Error::SecureStorageFailed)
+            }
+        };
+
+        let wallet_path = wallet_path().into_os_string().into_string().unwrap();
+        let drk = match Drk::new(wallet_path, &wallet_key, None, None, ex.clone(), false).await {
+            Ok(drk) => drk,
+            Err(err) => {
+                e!("Unable to open wallet: {err}");
+                return Err(Error::WalletInitFailed)
+            }
+        };
+        let public = Self::initialize(&drk).await?;
+        address.set(&mut PropertyAtomicGuard::none(), public.to_string());
+
+        let self_ = Arc::new(Self {
+            node: node.clone(),
+            tasks: OnceLock::new(),
+            ex: ex.clone(),
+
+            drk: async_lock::Mutex::new(drk),
+            resync: CondVar::new(),
+
+            address,
+            height,
+            is_connected,
+
+            settings,
+        });
+        self_.clone().start(ex).await;
+        Ok(Pimpl::Wallet(self_))
+    }
+
+    /// Create the wallet tables, and a first keypair on a new wallet.
+    /// Returns the default address.
+    async fn initialize(drk: &Drk) -> Result<PublicKey> {
+        let res = async {
+            drk.initialize_wallet().await?;
+            drk.initialize_money().await?;
+            drk.initialize_dao().await?;
+            drk.initialize_deployooor()
+        };
+        if let Err(err) = res.await {
+            e!("Unable to initialize wallet: {err:?}");
+            return Err(Error::WalletInitFailed)
+        }
+
+        if let Ok(public) = drk.default_address().await {
+            return Ok(public)
+        }
+
+        i!("New wallet, generating a keypair");
+        if let Err(err) = drk.money_keygen().await {
+            e!("Keypair generation failed: {err:?}");
+            return Err(Error::WalletInitFailed)
+        }
+        if let Err(err) = drk.set_default_address(1) {
+            e!("Setting default address failed: {err:?}");
+            return Err(Error::WalletInitFailed)
+        }
+        drk.default_address().await.map_err(|err| {
+            e!("Unable to read default address: {err}");
+            Error::WalletInitFailed
+        })
+    }
+
+    fn endpoint(&self) -> Option<Url> {
+        let setting = self.settings.get_setting("rpc_endpoint").unwrap();
+        let endpoint = setting.get_property_str("value").unwrap();
+        match Url::parse(&endpoint) {
+            Ok(url) => Some(url),
+            Err(err) => {
+                w!("Invalid darkfid endpoint '{endpoint}': {err}");
+                None
+            }
+        }
+    }
+
+    async fn set_connected(&self, is_connected: bool) {
+        if self.is_connected.get() == is_connected {
+            return
+        }
+        self.is_connected.set(&mut PropertyAtomicGuard::none(), is_connected);
+        let node = self.node.upgrade().unwrap();
+        node.trigger("connect", serialize(&is_connected)).await.unwrap();
+    }
+
+    /// Connect to darkfid when needed, then scan new blocks and publish
+    /// the balances.
+    async fn sync(&self) -> bool {
+        let mut drk = self.drk.lock().await;
+
+        if drk.rpc_client.is_none() {
+            let Some(endpoint) = self.endpoint() else { return false };
+            d!("Connecting to darkfid at {endpoint}");
+            match RpcClient::new(endpoint.clone(), self.ex.clone()).await {
+                Ok(client) => drk.rpc_client = Some(client),
+                Err(err) => {
+                    w!("Unable to reach darkfid at {endpoint}: {err}");
+                    return false
+                }
+            }
+        }
+
+        if let Err(err) = drk.scan_blocks().await {
+            w!("Scanning blocks failed: {err:?}");
+            if let Some(client) = drk.rpc_client.take() {
+                client.stop().await;
+            }
+            return false
+        }
+
+        if let Ok((height, _)) = drk.get_last_scanned_block() {
+            self.height.set(&mut PropertyAtomicGuard::none(), height);
+        }
+
+        let balances = match drk.money_balance().await {
+            Ok(balances) => balances,
+            Err(err) => {
+                e!("Unable to read balances: {err}");
+                return true
+            }
+        };
+        let aliases = drk.get_aliases_mapped_by_token().await.unwrap_or_default();
+        drop(drk);
+
+        let mut balances: Vec<_> = balances
+            .into_iter()
+            .map(|(token_id, value)| {
+                let token = aliases.get(&token_id).cloned().unwrap_or(token_id);
+                (token, encode_base10(value, BALANCE_BASE10_DECIMALS))
+            })
+            .collect();
+        balances.sort();
+
+        let node = self.node.upgrade().unwrap();
+        for (idx, (token, amount)) in balances.iter().enumerate() {
+            let mut data = vec![];
+            (idx as u32).encode(&mut data).unwrap();
+            token.encode(&mut data).unwrap();
+            amount.encode(&mut data).unwrap();
+            node.trigger("balance", data).await.unwrap();
+        }
+        node.trigger("balance_count", serialize(&(balances.len() as u32))).await.unwrap();
+
+        true
+    }
+
+    async fn sync_loop(self: Arc<Self>) {
+        loop {
+            let is_synced = self.sync().await;
+            self.set_connected(is_synced).await;
+
+            let wait = if is_synced { SYNC_INTERVAL } else { RPC_RETRY_TIME };
+            smol::future::or(sleep(wait), self.resync.wait()).await;
+            self.resync.reset();
+        }
+    }
+
+    async fn process_send(me: &Weak<Self>, sub: &MethodCallSub) -> bool {
+        let Ok(method_call) = sub.receive().await else {
+            d!("Event relayer closed");
+            return false
+        };
+
+        t!("method called: send({method_call:?})");
+        assert!(method_call.send_res.is_none());
+
+        fn decode_data(data: &[u8]) -> std::io::Result<(String, String, String)> {
+            let mut cur = Cursor::new(&data);
+            let recipient = String::decode(&mut cur)?;
+            let token = String::decode(&mut cur)?;
+            let amount = String::decode(&mut cur)?;
+            Ok((recipient, token, amount))
+        }
+
+        let Ok((recipient, token, amount)) = decode_data(&method_call.data) else {
+            e!("send() method invalid arg data");
+            return true
+        };
+
+        let Some(self_) = me.upgrade() else {
+            // Should not happen
+            panic!("self destroyed before send_method_task was stopped!");
+        };
+
+        let (tx_hash, error) = match self_.handle_send(recipient, token, amount).await {
+            Ok(tx_hash) => (tx_hash, String::new()),
+            Err(err) => {
+                w!("Send failed: {err}");
+                (String::new(), err)
+            }
+        };
+
+        let node = self_.node.upgrade().unwrap();
+        node.trigger("sent", serialize(&(tx_hash, error))).await.unwrap();
+
+        // Show the spent coins straight away
+        self_.resync.notify();
+        true
+    }
+
+    /// Build and broadcast a transfer. Returns the tx hash, or a message
+    /// to show the user.
+    async fn handle_send(
+        &self,
+        recipient: String,
+        token: String,
+        amount: String,
+    ) -> std::result::Result<String, String> {
+        let Ok(recipient) = PublicKey::from_str(recipient.trim()) else {
+            return Err("Invalid recipient address".to_string())
+        };
+
+        let drk = self.drk.lock().await;
+        if drk.rpc_client.is_none() {
+            return Err("Not connected to darkfid".to_string())
+        }
+
+        let token_id = drk.get_token(token.trim().to_string()).await.map_err(|e| e.to_string())?;
+
+        d!("Sending {amount} {token} to {recipient}");
+        let tx = drk
+            .transfer(amount.trim(), token_id, recipient, None, None, false, false)
+            .await
+            .map_err(|e| e.to_string())?;
+        let tx_hash = drk.broadcast_tx(&tx).await.map_err(|e| e.to_string())?;
+        i!("Broadcasted transfer {tx_hash}");
+        Ok(tx_hash)
+    }
+
+    async fn apply_settings(self_: Arc<Self>, _: BatchGuardPtr) {
+        self_.settings.save_settings();
+
+        // Reconnect using the new endpoint
+        let mut drk = self_.drk.lock().await;
+        if let Some(client) = drk.rpc_client.take() {
+            client.stop().await;
+        }
+        drop(drk);
+        self_.resync.notify();
+    }
+
+    async fn start(self: Arc<Self>, ex: ExecutorPtr) {
+        let me = Arc::downgrade(&self);
+        let node = &self.node.upgrade().unwrap();
+
+        let method_sub = node.subscribe_method_call("send").unwrap();
+        let me2 = me.clone();
+        let send_method_task =
+            ex.spawn(async move { while Self::process_send(&me2, &method_sub).await {} });
+
+        let mut on_modify = OnModify::new(ex.clone(), self.node.clone(), me.clone());
+        // `apply_settings` is triggered if any setting changes
+        for setting_node in self.settings.setting_root.get_children().iter() {
+            on_modify.when_change(
+                setting_node.get_property("value").clone().unwrap(),
+                Self::apply_settings,
+            );
+        }
+
+        let sync_task = ex.spawn(self.clone().sync_loop());
+
+        let mut tasks = vec![send_method_task, sync_task];
+        tasks.append(&mut on_modify.tasks);
+        self.tasks.set(tasks).unwrap();
+    }
+}
@@ -21,7 +21,10 @@ use std::sync::{
     Arc,
 };
 
-use super::{ModifyAction, PropertyPtr, Role};
+use super::{
+    undo::{SceneChange, UndoHistoryPtr, UndoRecorder},
+    ModifyAction, PropertyPtr, Role,
+};
 
 static BATCH_ID: AtomicU32 = AtomicU32::new(0);
 
@@ -50,13 +53,14 @@ pub struct PropertyAtomicGuard {
     updates: Vec<(PropertyPtr, Role, ModifyAction)>,
     end_batch: Option<BatchGuardCb>,
     parent: Option<BatchGuardPtr>,
+    undo: Option<UndoRecorder>,
 }
 
 impl PropertyAtomicGuard {
     pub fn new(start_batch: BatchGuardCb, end_batch: BatchGuardCb) -> Self {
         let batch_id = BATCH_ID.fetch_add(1, Ordering::Relaxed);
         start_batch(batch_id);
-        Self { batch_id, updates: vec![], end_batch: Some(end_batch), parent: None }
+        Self { batch_id, updates: vec![], end_batch: Some(end_batch), parent: None, undo: None }
     }
 
     /// Should only be used when there's an explicit end_batch() called manually at the end
@@ -68,12 +72,40 @@ impl PropertyAtomicGuard {
     }
 
     pub(super) fn add(&mut self, prop: PropertyPtr, role: Role, action: ModifyAction) {
+        // Cache writes bump the generation themselves only when the value changed
+        if !matches!(action, ModifyAction::SetCache(_)) {
+            prop.bump_generation();
+        }
         self.updates.push((prop, role, action));
     }
+
+    /// Record all changes made through this guard in the undo history.
+    /// They are committed as a single transaction when the guard is dropped.
+    pub fn record_undo<S: Into<String>>(&mut self, history: UndoHistoryPtr, desc: S) {
+        self.undo = Some(UndoRecorder::new(history, desc.into()));
+    }
+
+    /// Called by property setters before modifying any value
+    pub(super) fn snapshot(&mut self, prop: &PropertyPtr) {
+        if let Some(undo) = &mut self.undo {
+            undo.snapshot(prop);
+        }
+    }
+
+    /// Structural changes aren't seen by the guard, so they must be recorded explicitly
+    pub fn record_scene_change(&mut self, change: SceneChange) {
+        if let Some(undo) = &mut self.undo {
+            undo.add_scene_change(change);
+        }
+    }
 }
 
 impl Drop for PropertyAtomicGuard {
     fn drop(&mut self) {
+        if let Some(undo) = self.undo.take() {
+            undo.commit();
+        }
+
         let guard = Arc::new(BatchGuard {
             id: self.batch_id,
             end_batch: self.end_batch.take(),
@@ -102,6 +134,7 @@ impl BatchGuard {
             updates: vec![],
             end_batch: Some(Box::new(|_| {})),
             parent: Some(self.clone()),
+            undo: None,
         }
     }
 }
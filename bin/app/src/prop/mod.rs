@@ -20,7 +20,10 @@ use crate::error::{Error, Result};
 use darkfi_serial::{async_trait, Encodable, FutAsyncWriteExt, SerialDecodable, SerialEncodable};
 use std::{
     io::Write,
-    sync::{Arc, Mutex as SyncMutex, Weak},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex as SyncMutex, Weak,
+    },
 };
 
 use crate::{
@@ -31,6 +34,8 @@ use crate::{
 
 mod guard;
 pub use guard::{BatchGuardId, BatchGuardPtr, PropertyAtomicGuard};
+mod undo;
+pub use undo::{SceneChange, UndoHistory, UndoHistoryPtr};
 mod wrap;
 pub use wrap::{
     PropertyBool, PropertyColor, PropertyDimension, PropertyFloat32, PropertyRect, PropertyStr,
@@ -231,6 +236,8 @@ pub struct Property {
 
     pub is_null_allowed: bool,
     pub is_expr_allowed: bool,
+    /// Saved across restarts by `app::prefs`
+    pub is_user_pref: bool,
 
     // Use 0 for unbounded length
     pub array_len: usize,
@@ -242,6 +249,9 @@ pub struct Property {
 
     on_modify: ModifyPublisher,
     depends: SyncMutex<Vec<PropertyDepend>>,
+
+    // Bumped whenever a value or cached value actually changes
+    generation: AtomicU64,
 }
 
 impl Property {
@@ -261,6 +271,7 @@ impl Property {
 
             is_null_allowed: false,
             is_expr_allowed: false,
+            is_user_pref: false,
 
             array_len: 1,
             min_val: None,
@@ -269,6 +280,8 @@ impl Property {
 
             on_modify: Publisher::new(),
             depends: SyncMutex::new(vec![]),
+
+            generation: AtomicU64::new(0),
         }
     }
 
@@ -324,6 +337,10 @@ impl Property {
         self.is_expr_allowed = true;
     }
 
+    pub fn mark_user_pref(&mut self) {
+        self.is_user_pref = true;
+    }
+
     fn check_defaults_len(&self, defaults_len: usize) -> Result<()> {
         if !self.is_bounded() || defaults_len != self.array_len {
             return Err(Error::PropertyWrongLen)
@@ -365,6 +382,7 @@ impl Property {
 
     /// This will clear all values, resetting them to the default
     pub fn clear_values(self: &Arc<Self>, atom: &mut PropertyAtomicGuard, role: Role) {
+        atom.snapshot(self);
         {
             let vals = &mut self.vals.lock().unwrap();
             vals.clear();
@@ -392,6 +410,7 @@ impl Property {
         role: Role,
         i: usize,
     ) -> Result<()> {
+        atom.snapshot(self);
         {
             let vals = &mut self.vals.lock().unwrap();
             if i >= vals.len() {
@@ -413,6 +432,7 @@ impl Property {
             return Err(Error::PropertyNullNotAllowed)
         }
 
+        atom.snapshot(self);
         let mut vals = self.vals.lock().unwrap();
         if i >= vals.len() {
             return Err(Error::PropertyWrongIndex)
@@ -431,6 +451,7 @@ impl Property {
         i: usize,
         val: bool,
     ) -> Result<()> {
+        atom.snapshot(self);
         self.set_raw_value(i, PropertyValue::Bool(val))?;
         atom.add(self.clone(), role, ModifyAction::Set(i));
         Ok(())
@@ -454,6 +475,7 @@ impl Property {
                 return Err(Error::PropertyOutOfRange)
             }
         }
+        atom.snapshot(self);
         self.set_raw_value(i, PropertyValue::Uint32(val))?;
         atom.add(self.clone(), role, ModifyAction::Set(i));
         Ok(())
@@ -477,6 +499,7 @@ impl Property {
                 return Err(Error::PropertyOutOfRange)
             }
         }
+        atom.snapshot(self);
         self.set_raw_value(i, PropertyValue::Float32(val))?;
         atom.add(self.clone(), role, ModifyAction::Set(i));
         Ok(())
@@ -488,6 +511,7 @@ impl Property {
         i: usize,
         val: S,
     ) -> Result<()> {
+        atom.snapshot(self);
         self.set_raw_value(i, PropertyValue::Str(val.into()))?;
         atom.add(self.clone(), role, ModifyAction::Set(i));
         Ok(())
@@ -506,6 +530,7 @@ impl Property {
         if !self.enum_items.as_ref().unwrap().contains(&val) {
             return Err(Error::PropertyWrongEnumItem)
         }
+        atom.snapshot(self);
         self.set_raw_value(i, PropertyValue::Enum(val.into()))?;
         atom.add(self.clone(), role, ModifyAction::Set(i));
         Ok(())
@@ -517,6 +542,7 @@ impl Property {
         i: usize,
         val: SceneNodeId,
     ) -> Result<()> {
+        atom.snapshot(self);
         self.set_raw_value(i, PropertyValue::SceneNodeId(val))?;
         atom.add(self.clone(), role, ModifyAction::Set(i));
         Ok(())
@@ -532,6 +558,7 @@ impl Property {
             if !self.is_expr_allowed {
                 return Err(Error::PropertySExprNotAllowed)
             }
+            atom.snapshot(self);
             let vals = &mut self.vals.lock().unwrap();
             if i >= vals.len() {
                 return Err(Error::PropertyWrongIndex)
@@ -563,7 +590,11 @@ impl Property {
         if i >= cache.len() {
             return Err(Error::PropertyWrongIndex)
         }
-        cache[i] = val;
+        // Exprs are re-evaluated on every draw, so only count real changes
+        if cache[i] != val {
+            cache[i] = val;
+            self.bump_generation();
+        }
         Ok(())
     }
     pub fn set_cache_f32(
@@ -630,6 +661,7 @@ impl Property {
             return Err(Error::PropertyIsBounded)
         }
 
+        atom.snapshot(self);
         let mut vals = self.vals.lock().unwrap();
         let i = vals.len();
         vals.push(value);
@@ -854,6 +886,19 @@ impl Property {
     pub fn get_depends(&self) -> Vec<PropertyDepend> {
         self.depends.lock().unwrap().clone()
     }
+
+    // Generation
+
+    /// Counter which changes whenever this property is modified.
+    /// Widgets compare it against the value seen on their last draw
+    /// to decide whether their draw calls need regenerating.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn bump_generation(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
 }
 
 impl std::fmt::Debug for Property {
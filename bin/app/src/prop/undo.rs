@@ -0,0 +1,210 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::sync::{Arc, Mutex as SyncMutex};
+
+use super::{ModifyAction, PropertyAtomicGuard, PropertyPtr, PropertyValue, PropertyWeak, Role};
+use crate::scene::{SceneNodePtr, SceneNodeWeak};
+
+macro_rules! d { ($($arg:tt)*) => { debug!(target: "prop::undo", $($arg)*); } }
+
+/// Oldest transactions get dropped past this limit
+const MAX_UNDO_LEN: usize = 200;
+
+/// Structural change of the scenegraph
+pub enum SceneChange {
+    /// `child` was linked under `parent` at `pos`
+    Link { parent: SceneNodeWeak, child: SceneNodePtr, pos: usize },
+    /// `child` was unlinked from `parent` where it was at `pos`
+    Unlink { parent: SceneNodeWeak, child: SceneNodePtr, pos: usize },
+}
+
+enum UndoChange {
+    Property { prop: PropertyWeak, before: Vec<PropertyValue>, after: Vec<PropertyValue> },
+    Scene(SceneChange),
+}
+
+/// All the changes made through a single `PropertyAtomicGuard`
+struct UndoTransaction {
+    desc: String,
+    changes: Vec<UndoChange>,
+}
+
+impl UndoTransaction {
+    fn apply(&self, atom: &mut PropertyAtomicGuard, reverse: bool) {
+        let changes: Box<dyn Iterator<Item = &UndoChange>> = if reverse {
+            Box::new(self.changes.iter().rev())
+        } else {
+            Box::new(self.changes.iter())
+        };
+
+        for change in changes {
+            match change {
+                UndoChange::Property { prop, before, after } => {
+                    // Property was destroyed along with its node
+                    let Some(prop) = prop.upgrade() else { continue };
+                    let vals = if reverse { before } else { after };
+                    restore_values(&prop, atom, vals.clone());
+                }
+                UndoChange::Scene(change) => {
+                    let (parent, child, pos, is_link) = match change {
+                        SceneChange::Link { parent, child, pos } => (parent, child, *pos, true),
+                        SceneChange::Unlink { parent, child, pos } => (parent, child, *pos, false),
+                    };
+                    let Some(parent) = parent.upgrade() else { continue };
+                    // Undoing a link is an unlink and vice versa
+                    if is_link == reverse {
+                        let _ = parent.unlink(child.id);
+                    } else {
+                        parent.link_at(child.clone(), pos);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Replace all the values of a property, notifying only what changed
+fn restore_values(prop: &PropertyPtr, atom: &mut PropertyAtomicGuard, vals: Vec<PropertyValue>) {
+    let mut prop_vals = prop.vals.lock().unwrap();
+    let actions = if prop_vals.len() == vals.len() {
+        (0..vals.len()).filter(|&i| prop_vals[i] != vals[i]).map(ModifyAction::Set).collect()
+    } else {
+        vec![ModifyAction::Clear]
+    };
+    *prop_vals = vals;
+    drop(prop_vals);
+
+    for action in actions {
+        atom.add(prop.clone(), Role::User, action);
+    }
+}
+
+/// Collects changes made through a `PropertyAtomicGuard` until it is dropped
+pub(super) struct UndoRecorder {
+    history: UndoHistoryPtr,
+    desc: String,
+    changes: Vec<UndoChange>,
+}
+
+impl UndoRecorder {
+    pub(super) fn new(history: UndoHistoryPtr, desc: String) -> Self {
+        Self { history, desc, changes: vec![] }
+    }
+
+    /// Remember the property values before they get modified.
+    /// Only the first snapshot of a property inside a transaction is kept.
+    pub(super) fn snapshot(&mut self, prop: &PropertyPtr) {
+        let prop_weak = Arc::downgrade(prop);
+        let exists = self.changes.iter().any(|change| match change {
+            UndoChange::Property { prop, .. } => prop.ptr_eq(&prop_weak),
+            UndoChange::Scene(_) => false,
+        });
+        if exists {
+            return
+        }
+
+        let before = prop.vals.lock().unwrap().clone();
+        self.changes.push(UndoChange::Property { prop: prop_weak, before, after: vec![] });
+    }
+
+    pub(super) fn add_scene_change(&mut self, change: SceneChange) {
+        self.changes.push(UndoChange::Scene(change));
+    }
+
+    pub(super) fn commit(mut self) {
+        for change in &mut self.changes {
+            let UndoChange::Property { prop, after, .. } = change else { continue };
+            if let Some(prop) = prop.upgrade() {
+                *after = prop.vals.lock().unwrap().clone();
+            }
+        }
+        // Drop no-op and failed modifications
+        self.changes.retain(|change| match change {
+            UndoChange::Property { before, after, .. } => before != after,
+            UndoChange::Scene(_) => true,
+        });
+
+        self.history.push(UndoTransaction { desc: self.desc, changes: self.changes });
+    }
+}
+
+pub type UndoHistoryPtr = Arc<UndoHistory>;
+
+/// Session undo/redo stacks.
+///
+/// Changes are only recorded for guards which opted in using
+/// `PropertyAtomicGuard::record_undo()`, so internal app updates such as
+/// layout or animations never end up in here.
+pub struct UndoHistory {
+    undo_stack: SyncMutex<Vec<UndoTransaction>>,
+    redo_stack: SyncMutex<Vec<UndoTransaction>>,
+}
+
+impl UndoHistory {
+    pub fn new() -> UndoHistoryPtr {
+        Arc::new(Self { undo_stack: SyncMutex::new(vec![]), redo_stack: SyncMutex::new(vec![]) })
+    }
+
+    fn push(&self, tx: UndoTransaction) {
+        if tx.changes.is_empty() {
+            return
+        }
+        d!("Recorded transaction: {}", tx.desc);
+
+        let mut undo_stack = self.undo_stack.lock().unwrap();
+        undo_stack.push(tx);
+        if undo_stack.len() > MAX_UNDO_LEN {
+            undo_stack.remove(0);
+        }
+        drop(undo_stack);
+
+        // A new edit invalidates whatever was undone before
+        self.redo_stack.lock().unwrap().clear();
+    }
+
+    /// Revert the last transaction. Returns false when there is nothing to undo.
+    pub fn undo(&self, atom: &mut PropertyAtomicGuard) -> bool {
+        let Some(tx) = self.undo_stack.lock().unwrap().pop() else { return false };
+        d!("Undo: {}", tx.desc);
+        tx.apply(atom, true);
+        self.redo_stack.lock().unwrap().push(tx);
+        true
+    }
+
+    /// Reapply the last undone transaction. Returns false when there is nothing to redo.
+    pub fn redo(&self, atom: &mut PropertyAtomicGuard) -> bool {
+        let Some(tx) = self.redo_stack.lock().unwrap().pop() else { return false };
+        d!("Redo: {}", tx.desc);
+        tx.apply(atom, false);
+        self.undo_stack.lock().unwrap().push(tx);
+        true
+    }
+
+    /// Descriptions of the undo and redo stacks, most recent last
+    pub fn descs(&self) -> (Vec<String>, Vec<String>) {
+        let undo = self.undo_stack.lock().unwrap().iter().map(|tx| tx.desc.clone()).collect();
+        let redo = self.redo_stack.lock().unwrap().iter().map(|tx| tx.desc.clone()).collect();
+        (undo, redo)
+    }
+
+    pub fn clear(&self) {
+        self.undo_stack.lock().unwrap().clear();
+        self.redo_stack.lock().unwrap().clear();
+    }
+}
@@ -117,6 +117,12 @@ pub enum SceneNodeType {
     EmojiPicker = 19,
     SettingRoot = 20,
     Setting = 21,
+    PaletteInput = 22,
+    FlexBox = 23,
+    ListView = 24,
+    QrCode = 25,
+    ThemeRoot = 26,
+    ThemeToken = 27,
     PluginRoot = 100,
     Plugin = 101,
 }
@@ -199,6 +205,36 @@ impl SceneNode {
         children.push(child);
     }
 
+    /// Link a child at a specific position among its siblings.
+    /// Used to restore unlinked nodes to the place they came from.
+    pub fn link_at(self: &Arc<Self>, child: SceneNodePtr, pos: usize) {
+        let mut childs_parent = child.parent.write().unwrap();
+        assert!(childs_parent.is_none());
+        *childs_parent = Some(Arc::downgrade(&self));
+        drop(childs_parent);
+
+        let mut children = self.children.write().unwrap();
+        let pos = std::cmp::min(pos, children.len());
+        children.insert(pos, child);
+    }
+
+    /// Unlink a child, returning it along with the position it had among its siblings.
+    pub fn unlink(&self, child_id: SceneNodeId) -> Result<(SceneNodePtr, usize)> {
+        let mut children = self.children.write().unwrap();
+        let Some(pos) = children.iter().position(|child| child.id == child_id) else {
+            return Err(Error::NodesNotLinked)
+        };
+        let child = children.remove(pos);
+        drop(children);
+
+        *child.parent.write().unwrap() = None;
+        Ok((child, pos))
+    }
+
+    pub fn get_parent(&self) -> Option<SceneNodePtr> {
+        self.parent.read().unwrap().as_ref()?.upgrade()
+    }
+
     pub fn get_children(&self) -> Vec<SceneNodePtr> {
         self.children.read().unwrap().clone()
     }
@@ -230,6 +266,12 @@ impl SceneNode {
         Ok(())
     }
 
+    /// Combined generation of all this node's properties.
+    /// Changes whenever any of them is modified.
+    pub fn generation(&self) -> u64 {
+        self.props.iter().fold(0, |acc, prop| acc.wrapping_add(prop.generation()))
+    }
+
     pub fn get_property(&self, name: &str) -> Option<PropertyPtr> {
         self.props.iter().find(|prop| prop.name == name).map(|prop| prop.clone())
     }
@@ -545,7 +587,12 @@ pub enum Pimpl {
     Shortcut(ui::ShortcutPtr),
     Gesture(ui::GesturePtr),
     EmojiPicker(ui::EmojiPickerPtr),
+    PaletteInput(ui::PaletteInputPtr),
+    FlexBox(ui::FlexBoxPtr),
+    ListView(ui::ListViewPtr),
+    QrCode(ui::QrCodePtr),
     DarkIrc(plugin::DarkIrcPtr),
+    Wallet(plugin::WalletPtr),
 }
 
 impl std::fmt::Debug for Pimpl {
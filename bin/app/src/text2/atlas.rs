@@ -16,76 +16,156 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use crate::gfx::{DebugTag, ManagedTexturePtr, Rectangle, RenderApi};
+use parking_lot::Mutex as SyncMutex;
+use std::{collections::HashMap, sync::LazyLock};
+
+use crate::gfx::{gfxtag, ManagedTexturePtr, Rectangle, RenderApi};
 
 /// Prevents render artifacts from aliasing.
 /// Even with aliasing turned off, some bleed still appears possibly
 /// due to UV coord calcs. Adding a gap perfectly fixes this.
 const ATLAS_GAP: usize = 2;
 
-/*
-/// Convenience wrapper fn. Use if rendering a single line of glyphs.
-pub fn make_texture_atlas(render_api: &RenderApi, glyphs: &Vec<Glyph>) -> RenderedAtlas {
-    let mut atlas = Atlas::new(render_api);
-    atlas.push(&glyphs);
-    atlas.make()
+/// Width and height of each page texture
+const PAGE_SIZE: usize = 1024;
+/// Once this many pages are in use, the least recently used page gets
+/// evicted to make room for new glyphs.
+const MAX_PAGES: usize = 4;
+
+/// Glyphs are shared by all text, so they're rasterized once and packed
+/// into a few large page textures.
+pub static GLYPH_ATLAS: LazyLock<SyncMutex<GlyphAtlas>> =
+    LazyLock::new(|| SyncMutex::new(GlyphAtlas::new()));
+
+/// A rasterized glyph is unique for a given font, size and variation.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GlyphKey {
+    pub font_id: u64,
+    pub font_index: u32,
+    /// Bits of the f32 font size
+    pub font_size: u32,
+    pub coords: Vec<i16>,
+    pub glyph_id: swash::GlyphId,
 }
-*/
 
-//pub struct Sprite(swash::scale::image::Image);
+#[derive(Clone)]
+pub struct GlyphInfo {
+    /// UV rectangle within the page texture.
+    pub uv_rect: Rectangle,
+    /// Placement of the sprite used to calc the rect
+    pub place: zeno::Placement,
+    pub is_color: bool,
+    /// Index of the page this glyph is stored in
+    pub page: usize,
+}
 
-/// Responsible for aggregating glyphs, and then producing a single software
-/// blitted texture usable in a single draw call.
-/// This makes OpenGL batch precomputation of meshes efficient.
-///
-/// ```rust
-///     let mut atlas = Atlas::new(&render_api);
-///     atlas.push(&glyphs);    // repeat as needed for shaped lines
-///     let atlas = atlas.make().unwrap();
-///     let uv = atlas.fetch_uv(glyph_id).unwrap();
-///     let atlas_texture_id = atlas.texture_id;
-/// ```
-pub struct Atlas<'a> {
-    scaler: swash::scale::Scaler<'a>,
-    glyph_ids: Vec<swash::GlyphId>,
-    sprites: Vec<swash::scale::image::Image>,
-    // LHS x pos of glyph
-    x_pos: Vec<usize>,
-
-    width: usize,
-    height: usize,
-
-    render_api: &'a RenderApi,
-    tag: DebugTag,
+/// Counters shown in the window debug overlay
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AtlasStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// Number of times a page texture was sent to the GPU
+    pub uploads: u64,
+    pub pages: usize,
+    pub glyphs: usize,
 }
 
-impl<'a> Atlas<'a> {
-    pub fn new(scaler: swash::scale::Scaler<'a>, render_api: &'a RenderApi, tag: DebugTag) -> Self {
+/// Page texture filled row by row. Glyphs are placed left to right on a
+/// shelf, and when a glyph doesn't fit anymore a new shelf is started below.
+struct Page {
+    pixels: Vec<u8>,
+    shelf_y: usize,
+    shelf_h: usize,
+    cursor_x: usize,
+
+    glyphs: Vec<GlyphKey>,
+    /// Dropped whenever glyphs are added. Textures in use by existing meshes
+    /// stay alive until those are dropped.
+    texture: Option<ManagedTexturePtr>,
+    last_used: u64,
+}
+
+impl Page {
+    fn new() -> Self {
+        let mut pixels = vec![255, 255, 255, 0].repeat(PAGE_SIZE * PAGE_SIZE);
+        // Lines and boxes drawn along with the text use this white pixel
+        pixels[3] = 255;
+
         Self {
-            scaler,
-            glyph_ids: vec![],
-            sprites: vec![],
-            x_pos: vec![],
-
-            width: ATLAS_GAP,
-            // Not really important to set a value here since it will
-            // get overwritten.
-            // FYI glyphs have a gap on all sides (top and bottom here).
-            height: 2 * ATLAS_GAP,
-
-            render_api,
-            tag,
+            pixels,
+            shelf_y: ATLAS_GAP,
+            shelf_h: 0,
+            cursor_x: ATLAS_GAP,
+            glyphs: vec![],
+            texture: None,
+            last_used: 0,
         }
     }
 
-    pub fn push_glyph(&mut self, glyph_id: swash::GlyphId) {
-        if self.glyph_ids.contains(&glyph_id) {
-            return
+    /// Find room for a sprite, returning its top left corner
+    fn alloc(&mut self, w: usize, h: usize) -> Option<(usize, usize)> {
+        if self.cursor_x + w + ATLAS_GAP > PAGE_SIZE {
+            self.shelf_y += self.shelf_h + ATLAS_GAP;
+            self.shelf_h = 0;
+            self.cursor_x = ATLAS_GAP;
+        }
+        if self.cursor_x + w + ATLAS_GAP > PAGE_SIZE || self.shelf_y + h + ATLAS_GAP > PAGE_SIZE {
+            return None
         }
 
-        self.glyph_ids.push(glyph_id);
+        let pos = (self.cursor_x, self.shelf_y);
+        self.cursor_x += w + ATLAS_GAP;
+        self.shelf_h = std::cmp::max(self.shelf_h, h);
+        Some(pos)
+    }
+}
+
+/// Glyph cache shared by all text rendering.
+///
+/// ```rust
+///     let mut atlas = GLYPH_ATLAS.lock();
+///     let pin = atlas.pin();
+///     let glyph_inf = atlas.fetch(&mut scaler, key, pin);
+///     let texture = atlas.texture(glyph_inf.page, &render_api);
+/// ```
+pub struct GlyphAtlas {
+    glyphs: HashMap<GlyphKey, GlyphInfo>,
+    pages: Vec<Page>,
+    /// Incremented on every fetch, used to find the least recently used page
+    tick: u64,
+    stats: AtlasStats,
+}
 
-        let rendered_glyph = swash::scale::Render::new(
+impl GlyphAtlas {
+    fn new() -> Self {
+        Self { glyphs: HashMap::new(), pages: vec![], tick: 0, stats: AtlasStats::default() }
+    }
+
+    /// Pages used after this call won't be evicted until the next call.
+    /// Call it before fetching the glyphs of a layout so they all stay valid.
+    pub fn pin(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    /// Get the glyph, rasterizing it on a miss.
+    /// The scaler must be built from the font and size given in the key.
+    pub fn fetch(
+        &mut self,
+        scaler: &mut swash::scale::Scaler,
+        key: GlyphKey,
+        pin: u64,
+    ) -> GlyphInfo {
+        self.tick += 1;
+        if let Some(glyph_inf) = self.glyphs.get(&key) {
+            self.stats.hits += 1;
+            self.pages[glyph_inf.page].last_used = self.tick;
+            return glyph_inf.clone()
+        }
+        self.stats.misses += 1;
+
+        let sprite = swash::scale::Render::new(
             // Select our source order
             &[
                 swash::scale::Source::ColorOutline(0),
@@ -95,105 +175,96 @@ impl<'a> Atlas<'a> {
         )
         // Select the simple alpha (non-subpixel) format
         .format(zeno::Format::Alpha)
-        .render(&mut self.scaler, glyph_id)
+        .render(scaler, key.glyph_id)
         .unwrap();
 
-        let glyph_width = rendered_glyph.placement.width as usize;
-        let glyph_height = rendered_glyph.placement.height as usize;
-
-        self.sprites.push(rendered_glyph);
-
-        self.x_pos.push(self.width);
-
-        // Gap on the top and bottom
-        let height = ATLAS_GAP + glyph_height + ATLAS_GAP;
-        self.height = std::cmp::max(height, self.height);
-
-        // Gap between glyphs and on both sides
-        self.width += glyph_width + ATLAS_GAP;
-    }
-
-    fn render(&self) -> Vec<u8> {
-        let mut atlas = vec![255, 255, 255, 0].repeat(self.width * self.height);
-        // For drawing debug lines we want a single white pixel.
-        // This is very useful to have in our texture for debugging.
-        atlas[0] = 255;
-        atlas[1] = 255;
-        atlas[2] = 255;
-        atlas[3] = 255;
-
-        let y = ATLAS_GAP;
-        // Copy all the sprites to our atlas.
-        // They should have ATLAS_GAP spacing on all sides to avoid bleeding.
-        for (sprite, x) in self.sprites.iter().zip(self.x_pos.iter()) {
-            copy_image(sprite, *x, y, &mut atlas, self.width);
+        let is_color = match sprite.content {
+            swash::scale::image::Content::Mask => false,
+            swash::scale::image::Content::SubpixelMask => unimplemented!(),
+            swash::scale::image::Content::Color => true,
+        };
+
+        let mut place = sprite.placement;
+        let (mut w, mut h) = (place.width as usize, place.height as usize);
+        if w + 2 * ATLAS_GAP > PAGE_SIZE || h + 2 * ATLAS_GAP > PAGE_SIZE {
+            warn!(target: "text::atlas", "Glyph {key:?} is too big for the atlas, skipping");
+            (place.width, place.height) = (0, 0);
+            (w, h) = (0, 0);
         }
 
-        atlas
+        let (page_idx, x, y) = self.alloc(w, h, pin);
+        let page = &mut self.pages[page_idx];
+        if w > 0 && h > 0 {
+            copy_image(&sprite, x, y, &mut page.pixels, PAGE_SIZE);
+        }
+        page.glyphs.push(key.clone());
+        page.texture = None;
+        page.last_used = self.tick;
+
+        let size = PAGE_SIZE as f32;
+        let uv_rect =
+            Rectangle::new(x as f32 / size, y as f32 / size, w as f32 / size, h as f32 / size);
+        let glyph_inf = GlyphInfo { uv_rect, place, is_color, page: page_idx };
+        self.glyphs.insert(key, glyph_inf.clone());
+        glyph_inf
     }
 
-    fn compute_uvs(&self) -> Vec<Rectangle> {
-        // UV coords are in the range [0, 1]
-        let mut uvs = Vec::with_capacity(self.sprites.len());
-
-        let (self_w, self_h) = (self.width as f32, self.height as f32);
-        let y = ATLAS_GAP as f32;
-
-        for (sprite, x) in self.sprites.iter().zip(self.x_pos.iter()) {
-            let x = *x as f32;
-            let sprite_w = sprite.placement.width as f32;
-            let sprite_h = sprite.placement.height as f32;
-
-            let uv = Rectangle {
-                x: x / self_w,
-                y: y / self_h,
-                w: sprite_w / self_w,
-                h: sprite_h / self_h,
-            };
-            uvs.push(uv);
+    fn alloc(&mut self, w: usize, h: usize, pin: u64) -> (usize, usize, usize) {
+        for (page_idx, page) in self.pages.iter_mut().enumerate() {
+            if let Some((x, y)) = page.alloc(w, h) {
+                return (page_idx, x, y)
+            }
         }
 
-        uvs
-    }
+        // Evict the least recently used page unless everything is in use
+        // by the text being rendered right now.
+        let lru = self
+            .pages
+            .iter()
+            .enumerate()
+            .filter(|(_, page)| page.last_used < pin)
+            .min_by_key(|(_, page)| page.last_used)
+            .map(|(page_idx, _)| page_idx);
+
+        let page_idx = match lru {
+            Some(page_idx) if self.pages.len() >= MAX_PAGES => {
+                let page = std::mem::replace(&mut self.pages[page_idx], Page::new());
+                for key in page.glyphs {
+                    self.glyphs.remove(&key);
+                }
+                self.stats.evictions += 1;
+                page_idx
+            }
+            _ => {
+                self.pages.push(Page::new());
+                self.pages.len() - 1
+            }
+        };
 
-    /// Debug method
-    #[allow(dead_code)]
-    pub fn dump(&self, output_path: &str) {
-        let atlas = self.render();
-        let img = image::RgbaImage::from_raw(self.width as u32, self.height as u32, atlas).unwrap();
-        img.save(output_path).unwrap();
+        let (x, y) = self.pages[page_idx].alloc(w, h).unwrap();
+        (page_idx, x, y)
     }
 
-    /// Invalidate this atlas and produce the finalized result.
-    /// Each glyph is given a sub-rect within the texture, accessible by calling
-    /// `rendered_atlas.fetch_uv(my_glyph_id)`.
-    /// The texture ID is a struct member: `rendered_atlas.texture_id`.
-    pub fn make(self) -> RenderedAtlas {
-        //if self.glyph_ids.is_empty() {
-        //    return Err(Error::AtlasIsEmpty)
-        //}
-
-        assert_eq!(self.glyph_ids.len(), self.sprites.len());
-        assert_eq!(self.glyph_ids.len(), self.x_pos.len());
-
-        let atlas = self.render();
-        let texture =
-            self.render_api.new_texture(self.width as u16, self.height as u16, atlas, self.tag);
-
-        let uv_rects = self.compute_uvs();
-        let glyph_ids = self.glyph_ids;
-
-        let mut infos = Vec::with_capacity(self.sprites.len());
-        for (uv_rect, sprite) in uv_rects.into_iter().zip(self.sprites.into_iter()) {
-            let is_color = match sprite.content {
-                swash::scale::image::Content::Mask => false,
-                swash::scale::image::Content::SubpixelMask => unimplemented!(),
-                swash::scale::image::Content::Color => true,
-            };
-            infos.push(GlyphInfo { uv_rect, place: sprite.placement, is_color });
+    /// Texture for a page, uploaded again if glyphs were added since
+    pub fn texture(&mut self, page_idx: usize, render_api: &RenderApi) -> ManagedTexturePtr {
+        let page = &mut self.pages[page_idx];
+        if let Some(texture) = &page.texture {
+            return texture.clone()
         }
 
-        RenderedAtlas { glyph_ids, infos, texture }
+        let texture = render_api.new_texture(
+            PAGE_SIZE as u16,
+            PAGE_SIZE as u16,
+            page.pixels.clone(),
+            gfxtag!("glyph_atlas"),
+        );
+        page.texture = Some(texture.clone());
+        self.stats.uploads += 1;
+        texture
+    }
+
+    pub fn stats(&self) -> AtlasStats {
+        AtlasStats { pages: self.pages.len(), glyphs: self.glyphs.len(), ..self.stats }
     }
 }
 
@@ -249,36 +320,3 @@ fn copy_image(
         }
     }
 }
-
-#[derive(Clone)]
-pub struct GlyphInfo {
-    /// UV rectangle within the texture.
-    pub uv_rect: Rectangle,
-    /// Placement of the sprite used to calc the rect
-    pub place: zeno::Placement,
-    pub is_color: bool,
-}
-
-/// Final result computed from `Atlas::make()`.
-#[derive(Clone)]
-pub struct RenderedAtlas {
-    glyph_ids: Vec<swash::GlyphId>,
-    infos: Vec<GlyphInfo>,
-    /// Allocated atlas texture.
-    pub texture: ManagedTexturePtr,
-}
-
-impl RenderedAtlas {
-    /// Get UV coords for a glyph within the rendered atlas.
-    pub fn fetch_uv(&self, glyph_id: swash::GlyphId) -> Option<&GlyphInfo> {
-        let glyphs_len = self.glyph_ids.len();
-        assert_eq!(glyphs_len, self.infos.len());
-
-        for i in 0..glyphs_len {
-            if self.glyph_ids[i] == glyph_id {
-                return Some(&self.infos[i])
-            }
-        }
-        None
-    }
-}
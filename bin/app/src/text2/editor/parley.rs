@@ -17,7 +17,7 @@
  */
 
 use crate::{
-    gfx::Point,
+    gfx::{Point, Rectangle},
     mesh::Color,
     prop::{PropertyAtomicGuard, PropertyColor, PropertyFloat32, PropertyStr},
     text2::{TextContext, FONT_STACK, TEXT_CTX},
@@ -64,6 +64,10 @@ impl Editor {
         self.refresh().await;
     }
     pub async fn on_buffer_changed(&mut self, atom: &mut PropertyAtomicGuard) {
+        // The preedit is part of the buffer, but isn't text until it's committed
+        if self.editor.is_composing() {
+            return
+        }
         self.text.set(atom, self.editor.raw_text());
     }
 
@@ -113,6 +117,35 @@ impl Editor {
         self.on_buffer_changed(atom).await;
     }
 
+    /// Replace the IME preedit, which parley draws underlined at the cursor
+    pub async fn set_compose(&mut self, txt: &str, cursor: Option<(usize, usize)>) {
+        let mut txt_ctx = TEXT_CTX.get().await;
+        let (font_ctx, layout_ctx) = txt_ctx.borrow();
+        let mut drv = self.editor.driver(font_ctx, layout_ctx);
+        if txt.is_empty() {
+            drv.clear_compose();
+        } else {
+            drv.set_compose(txt, cursor);
+        }
+    }
+
+    pub async fn clear_compose(&mut self) {
+        let mut txt_ctx = TEXT_CTX.get().await;
+        let (font_ctx, layout_ctx) = txt_ctx.borrow();
+        self.editor.driver(font_ctx, layout_ctx).clear_compose();
+    }
+
+    /// Area the IME candidate window should avoid, relative to the content
+    pub fn ime_cursor_area(&self) -> Rectangle {
+        let area = self.editor.ime_cursor_area();
+        Rectangle::new(
+            area.x0 as f32,
+            area.y0 as f32,
+            (area.x1 - area.x0) as f32,
+            (area.y1 - area.y0) as f32,
+        )
+    }
+
     pub fn driver<'a>(
         &'a mut self,
         txt_ctx: &'a mut TextContext,
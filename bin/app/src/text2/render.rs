@@ -16,12 +16,66 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::collections::HashMap;
+
 use crate::{
-    gfx::{DebugTag, DrawInstruction, DrawMesh, Point, Rectangle, RenderApi},
+    gfx::{DebugTag, DrawInstruction, Point, Rectangle, RenderApi},
     mesh::{Color, MeshBuilder, COLOR_WHITE},
 };
 
-use super::atlas::{Atlas, RenderedAtlas};
+use super::atlas::{GlyphAtlas, GlyphKey, GLYPH_ATLAS};
+
+/// Indices are u16 so start a new mesh before running out
+const MAX_MESH_VERTS: usize = 60_000;
+
+/// Meshes for a layout, one per atlas page the glyphs came from
+struct LayoutMeshes {
+    /// Underlines and debug lines, drawn untextured
+    lines: MeshBuilder,
+    pages: HashMap<usize, MeshBuilder>,
+    instrs: Vec<DrawInstruction>,
+    tag: DebugTag,
+}
+
+impl LayoutMeshes {
+    fn new(tag: DebugTag) -> Self {
+        Self { lines: MeshBuilder::new(tag), pages: HashMap::new(), instrs: vec![], tag }
+    }
+
+    fn page(
+        &mut self,
+        page_idx: usize,
+        atlas: &mut GlyphAtlas,
+        render_api: &RenderApi,
+    ) -> &mut MeshBuilder {
+        let is_full =
+            self.pages.get(&page_idx).map_or(false, |mesh| mesh.verts.len() > MAX_MESH_VERTS);
+        if is_full {
+            let mesh = self.pages.remove(&page_idx).unwrap();
+            let texture = atlas.texture(page_idx, render_api);
+            self.instrs
+                .push(DrawInstruction::Draw(mesh.alloc(render_api).draw_with_texture(texture)));
+        }
+        let tag = self.tag;
+        self.pages.entry(page_idx).or_insert_with(|| MeshBuilder::new(tag))
+    }
+
+    fn finish(mut self, atlas: &mut GlyphAtlas, render_api: &RenderApi) -> Vec<DrawInstruction> {
+        let mut instrs = vec![];
+        if !self.lines.verts.is_empty() {
+            instrs.push(DrawInstruction::Draw(self.lines.alloc(render_api).draw_untextured()));
+        }
+        instrs.append(&mut self.instrs);
+
+        let mut pages: Vec<_> = self.pages.into_iter().collect();
+        pages.sort_by_key(|(page_idx, _)| *page_idx);
+        for (page_idx, mesh) in pages {
+            let texture = atlas.texture(page_idx, render_api);
+            instrs.push(DrawInstruction::Draw(mesh.alloc(render_api).draw_with_texture(texture)));
+        }
+        instrs
+    }
+}
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct DebugRenderOptions(u32);
@@ -64,48 +118,72 @@ pub fn render_layout_with_opts(
     tag: DebugTag,
 ) -> Vec<DrawInstruction> {
     let mut scale_cx = swash::scale::ScaleContext::new();
-    let mut run_idx = 0;
-    let mut instrs = vec![];
+    let mut atlas = GLYPH_ATLAS.lock();
+    let pin = atlas.pin();
+
+    let mut meshes = LayoutMeshes::new(tag);
     for line in layout.lines() {
         for item in line.items() {
             match item {
                 parley::PositionedLayoutItem::GlyphRun(glyph_run) => {
-                    let mesh =
-                        render_glyph_run(&mut scale_cx, &glyph_run, run_idx, opts, render_api, tag);
-                    instrs.push(DrawInstruction::Draw(mesh));
-                    run_idx += 1;
+                    render_glyph_run(
+                        &mut scale_cx,
+                        &glyph_run,
+                        opts,
+                        &mut atlas,
+                        pin,
+                        &mut meshes,
+                        render_api,
+                    );
                 }
                 parley::PositionedLayoutItem::InlineBox(_) => {}
             }
         }
     }
-    instrs
+    meshes.finish(&mut atlas, render_api)
 }
 
 fn render_glyph_run(
     scale_ctx: &mut swash::scale::ScaleContext,
     glyph_run: &parley::GlyphRun<'_, Color>,
-    _run_idx: usize,
     opts: DebugRenderOptions,
+    atlas: &mut GlyphAtlas,
+    pin: u64,
+    meshes: &mut LayoutMeshes,
     render_api: &RenderApi,
-    tag: DebugTag,
-) -> DrawMesh {
+) {
     let mut run_x = glyph_run.offset();
     let run_y = glyph_run.baseline();
     let style = glyph_run.style();
     let color = style.brush;
-    //trace!(target: "text::render", "render_glyph_run run_idx={run_idx} baseline={run_y}");
-
-    let atlas = create_atlas(scale_ctx, glyph_run, render_api, tag);
-
-    let mut mesh = MeshBuilder::new(tag);
+    //trace!(target: "text::render", "render_glyph_run baseline={run_y}");
 
     if let Some(underline) = &style.underline {
-        render_underline(underline, glyph_run, &mut mesh);
+        render_underline(underline, glyph_run, &mut meshes.lines);
     }
 
+    let run = glyph_run.run();
+    let font = run.font();
+    let font_size = run.font_size();
+    let normalized_coords = run.normalized_coords();
+    let font_ref = swash::FontRef::from_index(font.data.as_ref(), font.index as usize).unwrap();
+
+    let mut scaler = scale_ctx
+        .builder(font_ref)
+        .size(font_size)
+        .hint(true)
+        .normalized_coords(normalized_coords)
+        .build();
+
     for glyph in glyph_run.glyphs() {
-        let glyph_inf = atlas.fetch_uv(glyph.id as u16).expect("missing glyph UV rect");
+        let key = GlyphKey {
+            font_id: font.data.id(),
+            font_index: font.index,
+            font_size: font_size.to_bits(),
+            coords: normalized_coords.to_vec(),
+            glyph_id: glyph.id as u16,
+        };
+        let glyph_inf = atlas.fetch(&mut scaler, key, pin);
 
         let glyph_x = run_x + glyph.x;
         let glyph_y = run_y - glyph.y;
@@ -119,21 +197,20 @@ fn render_glyph_run(
         );
 
         if opts.has(DebugRenderOptions::GLYPH) {
-            mesh.draw_outline(&glyph_rect, [0., 1., 0., 0.7], 1.);
+            meshes.lines.draw_outline(&glyph_rect, [0., 1., 0., 0.7], 1.);
         }
 
         let color = if glyph_inf.is_color { COLOR_WHITE } else { color };
+        let mesh = meshes.page(glyph_inf.page, atlas, render_api);
         mesh.draw_box(&glyph_rect, color, &glyph_inf.uv_rect);
     }
 
     if opts.has(DebugRenderOptions::BASELINE) {
-        mesh.draw_filled_box(
+        meshes.lines.draw_filled_box(
             &Rectangle::new(glyph_run.offset(), glyph_run.baseline(), glyph_run.advance(), 1.),
             [0., 0., 1., 0.7],
         );
     }
-
-    mesh.alloc(render_api).draw_with_texture(atlas.texture)
 }
 
 fn render_underline(
@@ -166,30 +243,3 @@ fn render_underline(
 
     mesh.draw_line(start, end, color, width);
 }
-
-fn create_atlas(
-    scale_ctx: &mut swash::scale::ScaleContext,
-    glyph_run: &parley::GlyphRun<'_, Color>,
-    render_api: &RenderApi,
-    tag: DebugTag,
-) -> RenderedAtlas {
-    let run = glyph_run.run();
-    let font = run.font();
-    let font_size = run.font_size();
-    let normalized_coords = run.normalized_coords();
-    let font_ref = swash::FontRef::from_index(font.data.as_ref(), font.index as usize).unwrap();
-
-    let scaler = scale_ctx
-        .builder(font_ref)
-        .size(font_size)
-        .hint(true)
-        .normalized_coords(normalized_coords)
-        .build();
-
-    let mut atlas = Atlas::new(scaler, render_api, tag);
-    for glyph in glyph_run.glyphs() {
-        atlas.push_glyph(glyph.id as u16);
-    }
-    //atlas.dump(&format!("/tmp/atlas_{run_idx}.png"));
-    atlas.make()
-}
@@ -0,0 +1,257 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Export of a channel's history to self-contained HTML or markdown files.
+
+use chrono::{Local, TimeZone};
+use darkfi_serial::deserialize;
+use sled_overlay::sled;
+use std::{fmt::Write, path::PathBuf};
+
+use super::{ChatMsg, Timestamp};
+
+/// Nick used for local status lines, which are not part of the history
+const NOTICE_NICK: &str = "NOTICE";
+
+/// Link extensions rendered as media references
+const MEDIA_EXTENSIONS: &[&str] =
+    &[".png", ".jpg", ".jpeg", ".gif", ".webp", ".mp4", ".webm", ".mp3", ".ogg"];
+
+const HTML_STYLE: &str = "body { font-family: sans-serif; max-width: 50em; margin: auto; }
+h2 { border-bottom: 1px solid #ccc; }
+.msg { margin: 0.2em 0; }
+.time { color: #888; }
+.nick { font-weight: bold; }
+.media { font-style: italic; }";
+
+#[cfg(target_os = "android")]
+pub fn get_export_path() -> PathBuf {
+    crate::android::get_external_storage_path().join("exports")
+}
+
+#[cfg(not(target_os = "android"))]
+pub fn get_export_path() -> PathBuf {
+    dirs::document_dir().unwrap_or_else(|| dirs::home_dir().unwrap()).join("darkfi/exports")
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    Html,
+    Markdown,
+}
+
+impl ExportFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "html" => Some(Self::Html),
+            "md" | "markdown" => Some(Self::Markdown),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Markdown => "md",
+        }
+    }
+}
+
+/// Load the messages in `[from, to)` from a chat tree, in order.
+/// Local status lines are skipped.
+pub fn load_range(tree: &sled::Tree, from: Timestamp, to: Timestamp) -> Vec<(Timestamp, ChatMsg)> {
+    let mut msgs = vec![];
+    for entry in tree.range(from.to_be_bytes()..to.to_be_bytes()) {
+        let Ok((k, v)) = entry else { continue };
+        let timest = Timestamp::from_be_bytes(k[..8].try_into().unwrap());
+        let Ok(msg) = deserialize::<ChatMsg>(&v) else {
+            warn!(target: "ui::chatview", "Skipping corrupted chat line at {timest}");
+            continue
+        };
+        if msg.nick == NOTICE_NICK {
+            continue
+        }
+        msgs.push((timest, msg));
+    }
+    msgs
+}
+
+/// Render the messages of `channel` into a self-contained document
+pub fn render(format: ExportFormat, channel: &str, msgs: &[(Timestamp, ChatMsg)]) -> String {
+    match format {
+        ExportFormat::Html => render_html(channel, msgs),
+        ExportFormat::Markdown => render_markdown(channel, msgs),
+    }
+}
+
+fn render_html(channel: &str, msgs: &[(Timestamp, ChatMsg)]) -> String {
+    let title = html_escape(&format!("#{channel}"));
+    let mut out = String::new();
+    writeln!(out, "<!DOCTYPE html>").unwrap();
+    writeln!(out, "<html><head><meta charset=\"utf-8\">").unwrap();
+    writeln!(out, "<title>{title}</title>").unwrap();
+    writeln!(out, "<style>\n{HTML_STYLE}\n</style>").unwrap();
+    writeln!(out, "</head><body>").unwrap();
+    writeln!(out, "<h1>{title}</h1>").unwrap();
+
+    let mut last_date = String::new();
+    for (timest, msg) in msgs {
+        let (date, time) = date_time(*timest);
+        if date != last_date {
+            writeln!(out, "<h2>{date}</h2>").unwrap();
+            last_date = date;
+        }
+
+        let mut text = String::new();
+        for (i, word) in msg.text.split(' ').enumerate() {
+            if i > 0 {
+                text.push(' ');
+            }
+            let escaped = html_escape(word);
+            match link_kind(word) {
+                Some(true) => write!(text, "<a class=\"media\" href=\"{escaped}\">{escaped}</a>"),
+                Some(false) => write!(text, "<a href=\"{escaped}\">{escaped}</a>"),
+                None => write!(text, "{escaped}"),
+            }
+            .unwrap();
+        }
+
+        writeln!(
+            out,
+            "<div class=\"msg\"><span class=\"time\">{time}</span> \
+             <span class=\"nick\">&lt;{}&gt;</span> {text}</div>",
+            html_escape(&msg.nick)
+        )
+        .unwrap();
+    }
+
+    writeln!(out, "</body></html>").unwrap();
+    out
+}
+
+fn render_markdown(channel: &str, msgs: &[(Timestamp, ChatMsg)]) -> String {
+    let mut out = String::new();
+    writeln!(out, "# \\#{}", md_escape(channel)).unwrap();
+
+    let mut last_date = String::new();
+    for (timest, msg) in msgs {
+        let (date, time) = date_time(*timest);
+        if date != last_date {
+            writeln!(out, "\n## {date}\n").unwrap();
+            last_date = date;
+        }
+
+        let text: Vec<String> = msg
+            .text
+            .split(' ')
+            .map(|word| match link_kind(word) {
+                Some(true) => format!("![media](<{word}>)"),
+                Some(false) => format!("<{word}>"),
+                None => md_escape(word),
+            })
+            .collect();
+
+        writeln!(out, "- `{time}` **{}** {}", md_escape(&msg.nick), text.join(" ")).unwrap();
+    }
+
+    out
+}
+
+/// Local date and time of a timestamp in millis
+fn date_time(timest: Timestamp) -> (String, String) {
+    let dt = Local.timestamp_millis_opt(timest as i64).unwrap();
+    (dt.format("%Y-%m-%d").to_string(), dt.format("%H:%M").to_string())
+}
+
+/// Whether a word is a link, and if so whether it points to media
+fn link_kind(word: &str) -> Option<bool> {
+    if !word.starts_with("https://") && !word.starts_with("http://") {
+        return None
+    }
+    let path = word.split(['?', '#']).next().unwrap().to_lowercase();
+    Some(MEDIA_EXTENSIONS.iter().any(|ext| path.ends_with(ext)))
+}
+
+fn html_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn md_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if "\\`*_{}[]<>()#+-.!|~".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(nick: &str, text: &str) -> ChatMsg {
+        ChatMsg { nick: nick.to_string(), text: text.to_string() }
+    }
+
+    #[test]
+    fn export_range_and_render() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let tree = db.open_tree("chat").unwrap();
+
+        let lines = [
+            (1_700_000_000_000u64, msg("alice", "hi <b>bob</b>")),
+            (1_700_000_060_000, msg("NOTICE", "You are now known as <alice>")),
+            (1_700_000_120_000, msg("bob", "look https://dark.fi/cat.png?x=1 and https://dark.fi")),
+            (1_800_000_000_000, msg("carol", "later")),
+        ];
+        for (timest, msg) in &lines {
+            let mut key = timest.to_be_bytes().to_vec();
+            key.extend([0u8; 32]);
+            tree.insert(key, darkfi_serial::serialize(msg)).unwrap();
+        }
+
+        // The end of the range is excluded, and so are notices
+        let msgs = load_range(&tree, 1_700_000_000_000, 1_800_000_000_000);
+        let nicks: Vec<_> = msgs.iter().map(|(_, m)| m.nick.as_str()).collect();
+        assert_eq!(nicks, ["alice", "bob"]);
+
+        let html = render(ExportFormat::Html, "dev", &msgs);
+        assert!(html.contains("hi &lt;b&gt;bob&lt;/b&gt;"));
+        assert!(html.contains("<a class=\"media\" href=\"https://dark.fi/cat.png?x=1\">"));
+        assert!(html.contains("<a href=\"https://dark.fi\">"));
+
+        let md = render(ExportFormat::Markdown, "dev", &msgs);
+        assert!(md.starts_with("# \\#dev\n"));
+        assert!(md.contains("hi \\<b\\>bob\\</b\\>"));
+        assert!(md.contains("![media](<https://dark.fi/cat.png?x=1>)"));
+        assert!(md.contains("<https://dark.fi>"));
+    }
+}
@@ -34,6 +34,8 @@ use std::{
     },
 };
 
+mod export;
+pub use export::{get_export_path, ExportFormat};
 mod page;
 use page::MessageBuffer;
 
@@ -57,9 +59,6 @@ macro_rules! t { ($($arg:tt)*) => { trace!(target: "ui::chatview", $($arg)*); }
 const EPSILON: f32 = 0.001;
 const BIG_EPSILON: f32 = 0.05;
 
-// Disable selecting lines for this release.
-const ENABLE_SELECT: bool = false;
-
 fn is_zero(x: f32) -> bool {
     x.abs() < EPSILON
 }
@@ -173,6 +172,9 @@ pub struct ChatView {
     speed: AtomicF32,
 
     mouse_btn_held: AtomicBool,
+    /// Where the current selection started, measured from the bottom
+    /// of the message buffer
+    select_anchor: SyncMutex<Option<f32>>,
 
     /// Triggers the background loading task to wake up.
     /// We use this since there should only ever be a single bg task loading.
@@ -268,6 +270,7 @@ impl ChatView {
             speed: AtomicF32::new(0.),
 
             mouse_btn_held: AtomicBool::new(false),
+            select_anchor: SyncMutex::new(None),
 
             bgload_cv,
 
@@ -339,11 +342,117 @@ impl ChatView {
         true
     }
 
-    /// Mark line as selected
-    async fn select_line(&self, batch_id: BatchGuardId, mut y: f32) {
-        let trace_id = rand::random();
-        t!("select_line({y}) [trace_id={trace_id}]");
-        // The cursor is inside the rect. We just have to find which line it clicked.
+    async fn process_export_method(me: &Weak<Self>, sub: &MethodCallSub) -> bool {
+        let Ok(method_call) = sub.receive().await else {
+            d!("Event relayer closed");
+            return false
+        };
+
+        t!("method called: export({method_call:?})");
+        assert!(method_call.send_res.is_none());
+
+        fn decode_data(data: &[u8]) -> std::io::Result<(String, String, Timestamp, Timestamp)> {
+            let mut cur = Cursor::new(&data);
+            let channel = String::decode(&mut cur)?;
+            let format = String::decode(&mut cur)?;
+            let from = Timestamp::decode(&mut cur)?;
+            let to = Timestamp::decode(&mut cur)?;
+            Ok((channel, format, from, to))
+        }
+
+        let Ok((channel, format, from, to)) = decode_data(&method_call.data) else {
+            error!(target: "ui::chatview", "export() method invalid arg data");
+            return true
+        };
+        let Some(format) = ExportFormat::from_name(&format) else {
+            error!(target: "ui::chatview", "export() unknown format: {format}");
+            return true
+        };
+
+        let Some(self_) = me.upgrade() else {
+            // Should not happen
+            panic!("self destroyed before export_method_task was stopped!");
+        };
+
+        self_.handle_export(&channel, format, from, to).await;
+        true
+    }
+
+    async fn process_copy_method(me: &Weak<Self>, sub: &MethodCallSub) -> bool {
+        let Ok(method_call) = sub.receive().await else {
+            d!("Event relayer closed");
+            return false
+        };
+
+        t!("method called: copy({method_call:?})");
+        assert!(method_call.send_res.is_none());
+        assert!(method_call.data.is_empty());
+
+        let Some(self_) = me.upgrade() else {
+            // Should not happen
+            panic!("self destroyed before copy_method_task was stopped!");
+        };
+
+        if self_.copy_selection().await {
+            let atom = self_.render_api.make_guard(gfxtag!("ChatView::process_copy_method"));
+            self_.clear_selection(atom.batch_id).await;
+        }
+        true
+    }
+
+    async fn process_clear_selection_method(me: &Weak<Self>, sub: &MethodCallSub) -> bool {
+        let Ok(method_call) = sub.receive().await else {
+            d!("Event relayer closed");
+            return false
+        };
+
+        t!("method called: clear_selection({method_call:?})");
+        assert!(method_call.send_res.is_none());
+        assert!(method_call.data.is_empty());
+
+        let Some(self_) = me.upgrade() else {
+            // Should not happen
+            panic!("self destroyed before clear_selection_method_task was stopped!");
+        };
+
+        let atom = self_.render_api.make_guard(gfxtag!("ChatView::process_clear_selection_method"));
+        self_.clear_selection(atom.batch_id).await;
+        true
+    }
+
+    /// Export the history in `[from, to)` to a file in the export dir,
+    /// then leave a notice in the chat saying where it went.
+    async fn handle_export(
+        &self,
+        channel: &str,
+        format: ExportFormat,
+        from: Timestamp,
+        to: Timestamp,
+    ) {
+        let msgs = export::load_range(&self.tree, from, to);
+        let doc = export::render(format, channel, &msgs);
+
+        let now = chrono::Local::now().format("%Y%m%d-%H%M%S");
+        let path = get_export_path().join(format!("{channel}-{now}.{}", format.extension()));
+        let res =
+            std::fs::create_dir_all(get_export_path()).and_then(|_| std::fs::write(&path, doc));
+
+        let notice = match res {
+            Ok(()) => {
+                d!("Exported {} lines to {path:?}", msgs.len());
+                format!("Exported {} messages to {}", msgs.len(), path.display())
+            }
+            Err(err) => {
+                error!(target: "ui::chatview", "Failed exporting history to {path:?}: {err}");
+                format!("Export failed: {err}")
+            }
+        };
+        self.handle_insert_line(unixtime(), MessageId(OsRng.gen()), "NOTICE".to_string(), notice)
+            .await;
+    }
+
+    /// Convert a screen y coord into its distance from the bottom of the buffer
+    fn buffer_y(&self, mut y: f32) -> f32 {
         let rect = self.rect.get();
 
         // y coord within widget's screen rect
@@ -351,12 +460,44 @@ impl ChatView {
         // The scroll is the position of the bottom of the rect on screen
         let scroll = self.scroll.get();
         // Now what is its distance from the absolute bottom
-        y = rect.h - y + scroll;
+        rect.h - y + scroll
+    }
+
+    /// Start a new selection at the line under the screen y coord
+    async fn start_select(&self, batch_id: BatchGuardId, y: f32) {
+        *self.select_anchor.lock() = Some(self.buffer_y(y));
+        self.select_to(batch_id, y).await;
+    }
+
+    /// Select all lines between the selection start and the screen y coord
+    async fn select_to(&self, batch_id: BatchGuardId, y: f32) {
+        let Some(anchor) = *self.select_anchor.lock() else { return };
+        let y = self.buffer_y(y);
+
+        let trace_id = rand::random();
+        t!("select_to({anchor}, {y}) [trace_id={trace_id}]");
+
+        let mut msgbuf = self.msgbuf.lock().await;
+        if msgbuf.select_range(anchor, y).await {
+            self.redraw_cached(batch_id, &mut msgbuf, trace_id).await;
+        }
+    }
+
+    async fn clear_selection(&self, batch_id: BatchGuardId) {
+        *self.select_anchor.lock() = None;
 
         let mut msgbuf = self.msgbuf.lock().await;
-        msgbuf.select_line(y).await;
+        if msgbuf.clear_selection() {
+            self.redraw_cached(batch_id, &mut msgbuf, rand::random()).await;
+        }
+    }
 
-        self.redraw_cached(batch_id, &mut msgbuf, trace_id).await;
+    /// Copy the selected lines to the clipboard. Returns false when
+    /// nothing is selected.
+    async fn copy_selection(&self) -> bool {
+        let Some(text) = self.msgbuf.lock().await.selected_text() else { return false };
+        miniquad::window::clipboard_set(&text);
+        true
     }
 
     fn end_touch_phase(&self, touch_y: f32) {
@@ -743,6 +884,22 @@ impl UIObject for ChatView {
             while Self::process_insert_unconf_line_method(&me2, &method_sub).await {}
         });
 
+        let method_sub = node_ref.subscribe_method_call("export").unwrap();
+        let me2 = me.clone();
+        let export_method_task =
+            ex.spawn(async move { while Self::process_export_method(&me2, &method_sub).await {} });
+
+        let method_sub = node_ref.subscribe_method_call("copy").unwrap();
+        let me2 = me.clone();
+        let copy_method_task =
+            ex.spawn(async move { while Self::process_copy_method(&me2, &method_sub).await {} });
+
+        let method_sub = node_ref.subscribe_method_call("clear_selection").unwrap();
+        let me2 = me.clone();
+        let clear_selection_method_task = ex.spawn(async move {
+            while Self::process_clear_selection_method(&me2, &method_sub).await {}
+        });
+
         let me2 = me.clone();
         let cv = self.motion_cv.clone();
         let motion_task = ex.spawn(async move {
@@ -801,8 +958,15 @@ impl UIObject for ChatView {
         on_modify.when_change(self.rect.prop(), redraw);
         //on_modify.when_change(self.debug.prop(), redraw);
 
-        let mut tasks =
-            vec![insert_line_method_task, insert_unconf_line_method_task, motion_task, bgload_task];
+        let mut tasks = vec![
+            insert_line_method_task,
+            insert_unconf_line_method_task,
+            export_method_task,
+            copy_method_task,
+            clear_selection_method_task,
+            motion_task,
+            bgload_task,
+        ];
         tasks.append(&mut on_modify.tasks);
 
         *self.tasks.lock() = tasks;
@@ -856,12 +1020,38 @@ impl UIObject for ChatView {
         })
     }
 
+    async fn handle_char(&self, key: char, mods: KeyMods, repeat: bool) -> bool {
+        if repeat {
+            return false
+        }
+
+        #[cfg(not(target_os = "macos"))]
+        let action_mod = mods.ctrl;
+
+        #[cfg(target_os = "macos")]
+        let action_mod = mods.logo;
+
+        if action_mod && key == 'c' {
+            return self.copy_selection().await
+        }
+
+        false
+    }
+
     async fn handle_key_down(&self, key: KeyCode, _mods: KeyMods, repeat: bool) -> bool {
         if repeat {
             return false
         }
 
         match key {
+            KeyCode::Escape => {
+                if self.select_anchor.lock().is_none() {
+                    return false
+                }
+                let atom = self.render_api.make_guard(gfxtag!("ChatView::handle_key_down"));
+                self.clear_selection(atom.batch_id).await;
+                return true
+            }
             KeyCode::PageUp => {
                 self.start_scroll(1. * self.key_scroll_speed.get());
                 return true
@@ -888,9 +1078,9 @@ impl UIObject for ChatView {
 
         let atom = self.render_api.make_guard(gfxtag!("ChatView::handle_mouse_btn_down"));
 
-        if ENABLE_SELECT {
-            self.select_line(atom.batch_id, mouse_pos.y).await;
-        }
+        // A click drops the selection, dragging starts a new one
+        self.clear_selection(atom.batch_id).await;
+        *self.mouse_pos.lock() = mouse_pos;
         self.mouse_btn_held.store(true, Ordering::Relaxed);
         true
     }
@@ -910,7 +1100,7 @@ impl UIObject for ChatView {
         t!("handle_mouse_move({mouse_pos:?})");
 
         // We store the mouse pos for use in handle_mouse_wheel()
-        *self.mouse_pos.lock() = mouse_pos.clone();
+        let press_pos = std::mem::replace(&mut *self.mouse_pos.lock(), mouse_pos.clone());
 
         if !self.mouse_btn_held.load(Ordering::Relaxed) {
             return false
@@ -921,10 +1111,11 @@ impl UIObject for ChatView {
             return false
         }
 
-        if ENABLE_SELECT {
-            let atom = &mut self.render_api.make_guard(gfxtag!("ChatView::handle_mouse_move"));
-            self.select_line(atom.batch_id, mouse_pos.y).await;
+        let atom = &mut self.render_api.make_guard(gfxtag!("ChatView::handle_mouse_move"));
+        if self.select_anchor.lock().is_none() {
+            self.start_select(atom.batch_id, press_pos.y).await;
         }
+        self.select_to(atom.batch_id, mouse_pos.y).await;
         false
     }
 
@@ -969,12 +1160,20 @@ impl UIObject for ChatView {
         match phase {
             TouchPhase::Started => {
                 self.touch_is_active.store(true, Ordering::Relaxed);
+                self.clear_selection(atom.batch_id).await;
 
                 let mut touch_info = self.touch_info.lock();
                 *touch_info = Some(TouchInfo::new(self.scroll.get(), touch_y));
             }
             TouchPhase::Moved => {
-                let (start_scroll, start_y, start_elapsed, do_update, is_select_mode) = {
+                let (
+                    start_scroll,
+                    start_y,
+                    start_elapsed,
+                    do_update,
+                    is_select_mode,
+                    select_started,
+                ) = {
                     let mut touch_info = self.touch_info.lock();
                     let Some(touch_info) = &mut *touch_info else { return false };
 
@@ -985,10 +1184,12 @@ impl UIObject for ChatView {
 
                     let start_elapsed =
                         touch_info.start_instant.elapsed().as_micros() as f32 / 1000.;
+                    let mut select_started = false;
                     if start_elapsed > select_hold_time && touch_info.is_select_mode.is_none() {
                         // Did we move?
                         if (touch_y - start_y).abs() < BIG_EPSILON {
                             touch_info.is_select_mode = Some(true);
+                            select_started = true;
                         } else {
                             touch_info.is_select_mode = Some(false);
                         }
@@ -1004,9 +1205,21 @@ impl UIObject for ChatView {
                         touch_info.last_instant = std::time::Instant::now();
                     }
 
-                    (start_scroll, start_y, start_elapsed, do_update, is_select_mode)
+                    (
+                        start_scroll,
+                        start_y,
+                        start_elapsed,
+                        do_update,
+                        is_select_mode,
+                        select_started,
+                    )
                 };
 
+                // Long press selects the line under the finger
+                if select_started {
+                    self.start_select(atom.batch_id, start_y).await;
+                }
+
                 t!("touch phase moved, is_select_mode={is_select_mode:?}");
 
                 // When scrolling if we suddenly grab the screen for more than a brief period
@@ -1023,9 +1236,7 @@ impl UIObject for ChatView {
 
                 // We are in selection mode so don't scroll the screen until touch phase ends.
                 if is_select_mode == Some(true) {
-                    if ENABLE_SELECT {
-                        self.select_line(atom.batch_id, touch_y).await;
-                    }
+                    self.select_to(atom.batch_id, touch_y).await;
                     return true
                 }
 
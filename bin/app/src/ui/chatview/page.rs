@@ -352,8 +352,13 @@ impl PrivMessage {
         self.mesh_cache = None;
     }
 
-    fn select(&mut self) {
-        self.is_selected = true;
+    fn set_selected(&mut self, is_selected: bool) -> bool {
+        if self.is_selected == is_selected {
+            return false
+        }
+        self.is_selected = is_selected;
+        self.clear_mesh();
+        true
     }
 }
 
@@ -587,17 +592,11 @@ impl Message {
         }
     }
 
-    fn is_date(&self) -> bool {
+    /// Returns whether the selection state changed
+    fn set_selected(&mut self, is_selected: bool) -> bool {
         match self {
-            Self::Priv(_) => false,
-            Self::Date(_) => true,
-        }
-    }
-
-    fn select(&mut self) {
-        match self {
-            Self::Priv(m) => m.select(),
-            Self::Date(_) => {}
+            Self::Priv(m) => m.set_selected(is_selected),
+            Self::Date(_) => false,
         }
     }
 
@@ -1022,32 +1021,56 @@ impl MessageBuffer {
         colors
     }
 
-    pub async fn select_line(&mut self, y: f32) {
+    /// Select every message overlapping the range between `y0` and `y1`,
+    /// and unselect the rest. Positions are measured from the bottom of
+    /// the buffer. Returns whether the selection changed.
+    pub async fn select_range(&mut self, y0: f32, y1: f32) -> bool {
         let line_height = self.line_height.get();
         let msg_spacing = self.msg_spacing.get();
+        let (lo, hi) = if y0 < y1 { (y0, y1) } else { (y1, y0) };
 
         let msgs = self.msgs_with_date();
         let mut msgs = pin!(msgs);
 
+        let mut changed = false;
         let mut current_pos = 0.;
         while let Some(msg) = msgs.next().await {
             let mesh_height = msg.height(line_height);
             let msg_bottom = current_pos;
             let msg_top = current_pos + mesh_height + msg_spacing;
 
-            if msg_bottom <= y && y <= msg_top {
-                // Do nothing
-                if msg.is_date() {
-                    break
-                }
-
-                msg.select();
-                msg.clear_mesh();
-                break
-            }
+            changed |= msg.set_selected(msg_bottom <= hi && lo <= msg_top);
 
             current_pos += msg_spacing;
             current_pos += mesh_height;
         }
+        changed
+    }
+
+    /// Unselect all messages. Returns whether anything was selected.
+    pub fn clear_selection(&mut self) -> bool {
+        let mut changed = false;
+        for msg in &mut self.msgs {
+            changed |= msg.set_selected(false);
+        }
+        changed
+    }
+
+    /// Selected messages from oldest to newest, one per line
+    pub fn selected_text(&self) -> Option<String> {
+        let lines: Vec<String> = self
+            .msgs
+            .iter()
+            .rev()
+            .filter_map(|msg| match msg {
+                Message::Priv(m) if m.is_selected => Some(format!("{m:?}")),
+                _ => None,
+            })
+            .collect();
+
+        if lines.is_empty() {
+            return None
+        }
+        Some(lines.join("\n"))
     }
 }
@@ -34,6 +34,8 @@ use std::{
     },
 };
 
+#[cfg(not(target_os = "android"))]
+use crate::gfx::ImeEvent;
 #[cfg(target_os = "android")]
 use crate::AndroidSuggestEvent;
 use crate::{
@@ -513,36 +515,21 @@ impl BaseEdit {
         #[cfg(target_os = "macos")]
         let action_mod = mods.logo;
 
-        match key {
-            'a' => {
-                if action_mod {
-                    let mut txt_ctx = text2::TEXT_CTX.get().await;
-                    let mut editor = self.lock_editor().await;
-                    let mut drv = editor.driver(&mut txt_ctx).unwrap();
+        if !action_mod {
+            return false
+        }
 
-                    drv.select_all();
-                    if let Some(seltext) = editor.selected_text() {
-                        self.select_text.clone().set_str(atom, Role::Internal, 0, seltext).unwrap();
-                    }
-                }
-            }
-            'c' => {
-                if action_mod {
-                    let editor = self.lock_editor().await;
-                    if let Some(txt) = editor.selected_text() {
-                        miniquad::window::clipboard_set(&txt);
-                    }
-                }
-            }
-            'v' => {
-                if action_mod {
-                    if let Some(txt) = miniquad::window::clipboard_get() {
-                        self.insert(&txt, atom).await;
-                        // Maybe insert should call this?
-                        self.behave.apply_cursor_scroll(atom).await;
-                    }
+        match key {
+            'a' => self.select_all(atom).await,
+            // Let the key through when there's nothing to copy,
+            // so the chatview can copy its own selection.
+            'c' => return self.copy().await,
+            'x' => {
+                if !self.cut(atom).await {
+                    return false
                 }
             }
+            'v' => self.paste(atom).await,
             _ => return false,
         }
 
@@ -550,6 +537,43 @@ impl BaseEdit {
         true
     }
 
+    async fn select_all(&self, atom: &mut PropertyAtomicGuard) {
+        let mut txt_ctx = text2::TEXT_CTX.get().await;
+        let mut editor = self.lock_editor().await;
+        let Some(mut drv) = editor.driver(&mut txt_ctx) else { return };
+
+        drv.select_all();
+        if let Some(seltext) = editor.selected_text() {
+            self.select_text.clone().set_str(atom, Role::Internal, 0, seltext).unwrap();
+        }
+    }
+
+    /// Copy the selection to the clipboard. Returns false when nothing is selected.
+    async fn copy(&self) -> bool {
+        let editor = self.lock_editor().await;
+        let Some(txt) = editor.selected_text() else { return false };
+        miniquad::window::clipboard_set(&txt);
+        true
+    }
+
+    /// Move the selection to the clipboard. Returns false when nothing is selected.
+    async fn cut(&self, atom: &mut PropertyAtomicGuard) -> bool {
+        if !self.copy().await {
+            return false
+        }
+        self.insert("", atom).await;
+        self.finish_select(atom);
+        true
+    }
+
+    async fn paste(&self, atom: &mut PropertyAtomicGuard) {
+        if let Some(txt) = miniquad::window::clipboard_get() {
+            self.insert(&txt, atom).await;
+            // Maybe insert should call this?
+            self.behave.apply_cursor_scroll(atom).await;
+        }
+    }
+
     async fn handle_key(
         &self,
         key: &KeyCode,
@@ -1207,6 +1231,38 @@ impl BaseEdit {
         true
     }
 
+    /// Handles the `copy`, `cut`, `paste` and `select_all` methods
+    async fn process_clipboard_method(me: &Weak<Self>, sub: &MethodCallSub, method: &str) -> bool {
+        let Ok(method_call) = sub.receive().await else {
+            debug!(target: "ui::chatedit", "Event relayer closed");
+            return false
+        };
+
+        t!("method called: {method}({method_call:?})");
+        assert!(method_call.send_res.is_none());
+        assert!(method_call.data.is_empty());
+
+        let Some(self_) = me.upgrade() else {
+            // Should not happen
+            panic!("self destroyed before {method}_method_task was stopped!");
+        };
+
+        let atom = &mut self_.render_api.make_guard(gfxtag!("BaseEdit::process_clipboard_method"));
+        match method {
+            "copy" => {
+                self_.copy().await;
+            }
+            "cut" => {
+                self_.cut(atom).await;
+            }
+            "paste" => self_.paste(atom).await,
+            "select_all" => self_.select_all(atom).await,
+            _ => unreachable!(),
+        }
+        self_.redraw(atom).await;
+        true
+    }
+
     async fn process_focus_method(me: &Weak<Self>, sub: &MethodCallSub) -> bool {
         let Ok(method_call) = sub.receive().await else {
             debug!(target: "ui::chatedit", "Event relayer closed");
@@ -1351,6 +1407,15 @@ impl UIObject for BaseEdit {
         let unfocus_task =
             ex.spawn(async move { while Self::process_unfocus_method(&me2, &method_sub).await {} });
 
+        let mut clipboard_tasks = vec![];
+        for method in ["copy", "cut", "paste", "select_all"] {
+            let method_sub = node_ref.subscribe_method_call(method).unwrap();
+            let me2 = me.clone();
+            clipboard_tasks.push(ex.spawn(async move {
+                while Self::process_clipboard_method(&me2, &method_sub, method).await {}
+            }));
+        }
+
         let mut on_modify = OnModify::new(ex.clone(), self.node.clone(), me.clone());
         on_modify.when_change(self.is_focused.prop(), Self::change_focus);
 
@@ -1466,6 +1531,7 @@ impl UIObject for BaseEdit {
 
         let mut tasks =
             vec![insert_text_task, focus_task, unfocus_task, blinking_cursor_task, sel_task];
+        tasks.append(&mut clipboard_tasks);
         tasks.append(&mut on_modify.tasks);
 
         #[cfg(target_os = "android")]
@@ -1513,6 +1579,35 @@ impl UIObject for BaseEdit {
         Some(self.make_draw_calls(trace_id, atom).await)
     }
 
+    // On Android composing goes through the composer view instead
+    #[cfg(not(target_os = "android"))]
+    async fn handle_ime(&self, ev: &ImeEvent) -> Option<Rectangle> {
+        t!("handle_ime({ev:?})");
+        if !self.is_focused.get() {
+            return None
+        }
+
+        let atom = &mut self.render_api.make_guard(gfxtag!("BaseEdit::handle_ime"));
+        let mut editor = self.lock_editor().await;
+        match ev {
+            ImeEvent::Preedit { text, cursor } => editor.set_compose(text, *cursor).await,
+            ImeEvent::Commit(text) => {
+                editor.clear_compose().await;
+                editor.insert(text, atom).await;
+            }
+        }
+        editor.refresh().await;
+        let area = editor.ime_cursor_area();
+        drop(editor);
+
+        self.pause_blinking();
+        self.behave.apply_cursor_scroll(atom).await;
+        self.redraw(atom).await;
+
+        let pos = self.rect.get().pos() + self.behave.inner_pos() + self.behave.scroll();
+        Some(area + pos)
+    }
+
     async fn handle_char(&self, key: char, mods: KeyMods, repeat: bool) -> bool {
         t!("handle_char({key}, {mods:?}, {repeat})");
         // First filter for only single digit keys
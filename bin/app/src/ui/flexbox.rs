@@ -0,0 +1,653 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Flexbox-style container laying out its children in a row or a column.
+//!
+//! Children get a slot along the main axis sized from their `flex_basis`,
+//! then grown or shrunk by their `flex_grow` and `flex_shrink` factors to
+//! fill the container. Each child is drawn inside its slot, so a child
+//! rect of `[0, 0, w, h]` fills the slot it was given. Children without
+//! the flex item properties (see `add_flex_item_props()`) share the free
+//! space equally.
+
+use async_trait::async_trait;
+use miniquad::{KeyCode, KeyMods, MouseButton, TouchPhase};
+use parking_lot::Mutex as SyncMutex;
+use rand::{rngs::OsRng, Rng};
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    gfx::{DrawCall, DrawInstruction, ImeEvent, Point, Rectangle, RenderApi},
+    prop::{
+        BatchGuardPtr, PropertyAtomicGuard, PropertyBool, PropertyFloat32, PropertyPtr,
+        PropertyRect, PropertyUint32, Role,
+    },
+    scene::{Pimpl, SceneNode as SceneNode3, SceneNodeId, SceneNodePtr, SceneNodeWeak},
+    util::{i18n::I18nBabelFish, unixtime},
+    ExecutorPtr,
+};
+
+use super::{
+    get_children_ordered, get_ui_object3, get_ui_object_ptr, DrawUpdate, OnModify, UIObject,
+};
+
+macro_rules! t { ($($arg:tt)*) => { trace!(target: "ui::flexbox", $($arg)*); } }
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    Row,
+    Column,
+}
+
+impl Direction {
+    fn from_u32(v: u32) -> Self {
+        match v {
+            1 => Self::Column,
+            _ => Self::Row,
+        }
+    }
+}
+
+/// Placement of the items along the main axis when there is free space left
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Justify {
+    Start,
+    Center,
+    End,
+    SpaceBetween,
+}
+
+impl Justify {
+    fn from_u32(v: u32) -> Self {
+        match v {
+            1 => Self::Center,
+            2 => Self::End,
+            3 => Self::SpaceBetween,
+            _ => Self::Start,
+        }
+    }
+}
+
+/// Placement of the items along the cross axis
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Align {
+    Start,
+    Center,
+    End,
+    Stretch,
+}
+
+impl Align {
+    fn from_u32(v: u32) -> Self {
+        match v {
+            0 => Self::Start,
+            1 => Self::Center,
+            2 => Self::End,
+            _ => Self::Stretch,
+        }
+    }
+}
+
+/// Container parameters. Padding is `[top, right, bottom, left]`.
+#[derive(Clone, Debug)]
+pub struct FlexParams {
+    pub direction: Direction,
+    pub justify: Justify,
+    pub align: Align,
+    pub gap: f32,
+    pub padding: [f32; 4],
+}
+
+/// Sizing of a single child. Margin is `[top, right, bottom, left]`.
+#[derive(Clone, Debug)]
+pub struct FlexItem {
+    /// Main axis size before growing or shrinking
+    pub basis: f32,
+    /// Share of the free space this item takes
+    pub grow: f32,
+    /// How much this item gives up when there isn't enough space,
+    /// weighted by its basis
+    pub shrink: f32,
+    /// Cross axis size, 0 to fill the container. Ignored when stretching.
+    pub cross: f32,
+    pub margin: [f32; 4],
+}
+
+impl Default for FlexItem {
+    fn default() -> Self {
+        Self { basis: 0., grow: 1., shrink: 1., cross: 0., margin: [0.; 4] }
+    }
+}
+
+impl FlexItem {
+    /// Read the flex item properties of a node, falling back to the defaults
+    fn from_node(node: &SceneNode3) -> Self {
+        let get = |name: &str, i: usize| node.get_property(name)?.get_f32(i).ok();
+
+        let def = Self::default();
+        let mut margin = def.margin;
+        for (i, m) in margin.iter_mut().enumerate() {
+            *m = get("flex_margin", i).unwrap_or(0.);
+        }
+
+        Self {
+            basis: get("flex_basis", 0).unwrap_or(def.basis),
+            grow: get("flex_grow", 0).unwrap_or(def.grow),
+            shrink: get("flex_shrink", 0).unwrap_or(def.shrink),
+            cross: get("flex_cross", 0).unwrap_or(def.cross),
+            margin,
+        }
+    }
+}
+
+/// Compute the slot of every item inside a container of size `w` x `h`.
+/// Slots are relative to the container.
+pub fn layout(params: &FlexParams, items: &[FlexItem], w: f32, h: f32) -> Vec<Rectangle> {
+    if items.is_empty() {
+        return vec![]
+    }
+
+    let [pad_top, pad_right, pad_bottom, pad_left] = params.padding;
+    let inner_w = (w - pad_left - pad_right).max(0.);
+    let inner_h = (h - pad_top - pad_bottom).max(0.);
+
+    let is_row = params.direction == Direction::Row;
+    let (main_len, cross_len) = if is_row { (inner_w, inner_h) } else { (inner_h, inner_w) };
+
+    // Margins as (main start, main end, cross start, cross end)
+    let margins = |item: &FlexItem| {
+        let [top, right, bottom, left] = item.margin;
+        if is_row {
+            (left, right, top, bottom)
+        } else {
+            (top, bottom, left, right)
+        }
+    };
+
+    let gaps = params.gap * (items.len() - 1) as f32;
+    let used: f32 = items
+        .iter()
+        .map(|item| {
+            let (start, end, _, _) = margins(item);
+            item.basis + start + end
+        })
+        .sum::<f32>() +
+        gaps;
+    let mut free = main_len - used;
+
+    let mut sizes: Vec<f32> = items.iter().map(|item| item.basis).collect();
+    if free > 0. {
+        let total_grow: f32 = items.iter().map(|item| item.grow).sum();
+        if total_grow > 0. {
+            for (size, item) in sizes.iter_mut().zip(items) {
+                *size += free * item.grow / total_grow;
+            }
+            free = 0.;
+        }
+    } else if free < 0. {
+        let total_shrink: f32 = items.iter().map(|item| item.shrink * item.basis).sum();
+        if total_shrink > 0. {
+            for (size, item) in sizes.iter_mut().zip(items) {
+                *size = (*size + free * item.shrink * item.basis / total_shrink).max(0.);
+            }
+            free = 0.;
+        }
+    }
+
+    let free = free.max(0.);
+    let (mut pos, spacing) = match params.justify {
+        Justify::Start => (0., params.gap),
+        Justify::Center => (free / 2., params.gap),
+        Justify::End => (free, params.gap),
+        Justify::SpaceBetween if items.len() > 1 => {
+            (0., params.gap + free / (items.len() - 1) as f32)
+        }
+        Justify::SpaceBetween => (0., params.gap),
+    };
+
+    let mut slots = Vec::with_capacity(items.len());
+    for (item, size) in items.iter().zip(sizes) {
+        let (main_start, main_end, cross_start, cross_end) = margins(item);
+        let avail = (cross_len - cross_start - cross_end).max(0.);
+
+        let cross_size =
+            if params.align == Align::Stretch || item.cross <= 0. { avail } else { item.cross };
+        let cross_pos = cross_start +
+            match params.align {
+                Align::Start | Align::Stretch => 0.,
+                Align::Center => (avail - cross_size) / 2.,
+                Align::End => avail - cross_size,
+            };
+
+        pos += main_start;
+        let slot = if is_row {
+            Rectangle::new(pad_left + pos, pad_top + cross_pos, size, cross_size)
+        } else {
+            Rectangle::new(pad_left + cross_pos, pad_top + pos, cross_size, size)
+        };
+        slots.push(slot);
+        pos += size + main_end + spacing;
+    }
+
+    slots
+}
+
+pub type FlexBoxPtr = Arc<FlexBox>;
+
+pub struct FlexBox {
+    node: SceneNodeWeak,
+    render_api: RenderApi,
+    tasks: SyncMutex<Vec<smol::Task<()>>>,
+    dc_key: u64,
+
+    is_visible: PropertyBool,
+    rect: PropertyRect,
+    z_index: PropertyUint32,
+    priority: PropertyUint32,
+    direction: PropertyUint32,
+    justify: PropertyUint32,
+    align: PropertyUint32,
+    gap: PropertyFloat32,
+    padding: PropertyPtr,
+
+    /// Draw call keys of the child slots
+    slot_keys: SyncMutex<HashMap<SceneNodeId, u64>>,
+    /// Slots computed on the last draw, used to route input events
+    slots: SyncMutex<HashMap<SceneNodeId, Rectangle>>,
+
+    parent_rect: SyncMutex<Option<Rectangle>>,
+}
+
+impl FlexBox {
+    pub async fn new(node: SceneNodeWeak, render_api: RenderApi) -> Pimpl {
+        let node_ref = &node.upgrade().unwrap();
+        t!("FlexBox::new({node_ref:?})");
+        let is_visible = PropertyBool::wrap(node_ref, Role::Internal, "is_visible", 0).unwrap();
+        let rect = PropertyRect::wrap(node_ref, Role::Internal, "rect").unwrap();
+        let z_index = PropertyUint32::wrap(node_ref, Role::Internal, "z_index", 0).unwrap();
+        let priority = PropertyUint32::wrap(node_ref, Role::Internal, "priority", 0).unwrap();
+        let direction = PropertyUint32::wrap(node_ref, Role::Internal, "direction", 0).unwrap();
+        let justify = PropertyUint32::wrap(node_ref, Role::Internal, "justify", 0).unwrap();
+        let align = PropertyUint32::wrap(node_ref, Role::Internal, "align", 0).unwrap();
+        let gap = PropertyFloat32::wrap(node_ref, Role::Internal, "gap", 0).unwrap();
+        let padding = node_ref.get_property("padding").unwrap();
+
+        let self_ = Arc::new(Self {
+            node,
+            render_api,
+            tasks: SyncMutex::new(vec![]),
+            dc_key: OsRng.gen(),
+
+            is_visible,
+            rect,
+            z_index,
+            priority,
+            direction,
+            justify,
+            align,
+            gap,
+            padding,
+
+            slot_keys: SyncMutex::new(HashMap::new()),
+            slots: SyncMutex::new(HashMap::new()),
+
+            parent_rect: SyncMutex::new(None),
+        });
+
+        Pimpl::FlexBox(self_)
+    }
+
+    fn get_children(&self) -> Vec<SceneNodePtr> {
+        let node = self.node.upgrade().unwrap();
+        get_children_ordered(&node)
+    }
+
+    fn params(&self) -> FlexParams {
+        let mut padding = [0.; 4];
+        for (i, p) in padding.iter_mut().enumerate() {
+            *p = self.padding.get_f32(i).unwrap();
+        }
+
+        FlexParams {
+            direction: Direction::from_u32(self.direction.get()),
+            justify: Justify::from_u32(self.justify.get()),
+            align: Align::from_u32(self.align.get()),
+            gap: self.gap.get(),
+            padding,
+        }
+    }
+
+    fn slot_key(&self, id: SceneNodeId) -> u64 {
+        *self.slot_keys.lock().entry(id).or_insert_with(|| OsRng.gen())
+    }
+
+    /// Convert a point relative to our parent into one relative to the child's slot
+    fn to_slot(&self, child: &SceneNode3, mut point: Point) -> Option<Point> {
+        let slot = self.slots.lock().get(&child.id).cloned()?;
+        point -= self.rect.get().pos();
+        point -= slot.pos();
+        Some(point)
+    }
+
+    async fn redraw(self: Arc<Self>, batch: BatchGuardPtr) {
+        let trace_id = rand::random();
+        let timest = unixtime();
+        t!("FlexBox::redraw({:?}) [trace_id={trace_id}]", self.node.upgrade().unwrap());
+        let Some(parent_rect) = self.parent_rect.lock().clone() else { return };
+
+        let atom = &mut batch.spawn();
+        let Some(draw_update) = self.get_draw_calls(parent_rect, trace_id, atom).await else {
+            error!(target: "ui::flexbox", "FlexBox failed to draw [trace_id={trace_id}]");
+            return
+        };
+        self.render_api.replace_draw_calls(batch.id, timest, draw_update.draw_calls);
+    }
+
+    async fn get_draw_calls(
+        &self,
+        parent_rect: Rectangle,
+        trace_id: u32,
+        atom: &mut PropertyAtomicGuard,
+    ) -> Option<DrawUpdate> {
+        self.rect.eval(atom, &parent_rect).ok()?;
+        let rect = self.rect.get();
+        t!("FlexBox::get_draw_calls() [rect={rect:?}, dc={}, trace_id={trace_id}]", self.dc_key);
+
+        // Lay out the children in the order they were linked
+        let children = self.node.upgrade().unwrap().get_children();
+        let items: Vec<FlexItem> = children.iter().map(|c| FlexItem::from_node(c)).collect();
+        let slots = layout(&self.params(), &items, rect.w, rect.h);
+
+        let mut draw_calls = vec![];
+        let mut child_calls = vec![];
+        let mut slot_cache = HashMap::new();
+
+        if self.is_visible.get() {
+            for (child, slot) in children.iter().zip(slots) {
+                slot_cache.insert(child.id, slot);
+
+                let obj = get_ui_object3(child);
+                let Some(mut draw_update) = obj.draw(slot, trace_id, atom).await else {
+                    t!("{child:?} draw returned none [trace_id={trace_id}]");
+                    continue
+                };
+                draw_calls.append(&mut draw_update.draw_calls);
+
+                // Each child draws relative to its slot
+                let slot_key = self.slot_key(child.id);
+                let dc = DrawCall::new(
+                    vec![DrawInstruction::ApplyView(slot)],
+                    vec![draw_update.key],
+                    self.z_index.get(),
+                    "flexbox_slot",
+                );
+                draw_calls.push((slot_key, dc));
+                child_calls.push(slot_key);
+            }
+        }
+        *self.slots.lock() = slot_cache;
+
+        let dc = DrawCall::new(
+            vec![DrawInstruction::ApplyView(rect)],
+            child_calls,
+            self.z_index.get(),
+            "flexbox",
+        );
+        draw_calls.push((self.dc_key, dc));
+        Some(DrawUpdate { key: self.dc_key, draw_calls })
+    }
+}
+
+#[async_trait]
+impl UIObject for FlexBox {
+    fn priority(&self) -> u32 {
+        self.priority.get()
+    }
+
+    fn init(&self) {
+        for child in self.get_children() {
+            let obj = get_ui_object3(&child);
+            obj.init();
+        }
+    }
+
+    async fn start(self: Arc<Self>, ex: ExecutorPtr) {
+        let me = Arc::downgrade(&self);
+
+        let mut on_modify = OnModify::new(ex.clone(), self.node.clone(), me.clone());
+        on_modify.when_change(self.is_visible.prop(), Self::redraw);
+        on_modify.when_change(self.rect.prop(), Self::redraw);
+        on_modify.when_change(self.z_index.prop(), Self::redraw);
+        on_modify.when_change(self.direction.prop(), Self::redraw);
+        on_modify.when_change(self.justify.prop(), Self::redraw);
+        on_modify.when_change(self.align.prop(), Self::redraw);
+        on_modify.when_change(self.gap.prop(), Self::redraw);
+        on_modify.when_change(self.padding.clone(), Self::redraw);
+
+        *self.tasks.lock() = on_modify.tasks;
+
+        for child in self.get_children() {
+            let obj = get_ui_object_ptr(&child);
+            obj.start(ex.clone()).await;
+        }
+    }
+
+    fn stop(&self) {
+        self.tasks.lock().clear();
+        *self.parent_rect.lock() = None;
+        self.slots.lock().clear();
+        for child in self.get_children() {
+            let obj = get_ui_object3(&child);
+            obj.stop();
+        }
+    }
+
+    async fn draw(
+        &self,
+        parent_rect: Rectangle,
+        trace_id: u32,
+        atom: &mut PropertyAtomicGuard,
+    ) -> Option<DrawUpdate> {
+        t!("FlexBox::draw({:?}) [trace_id={trace_id}]", self.node.upgrade().unwrap());
+        *self.parent_rect.lock() = Some(parent_rect);
+        self.get_draw_calls(parent_rect, trace_id, atom).await
+    }
+
+    async fn handle_char(&self, key: char, mods: KeyMods, repeat: bool) -> bool {
+        if !self.is_visible.get() {
+            return false
+        }
+        for child in self.get_children() {
+            let obj = get_ui_object3(&child);
+            if obj.handle_char(key, mods, repeat).await {
+                return true
+            }
+        }
+        false
+    }
+
+    async fn handle_ime(&self, ev: &ImeEvent) -> Option<Rectangle> {
+        if !self.is_visible.get() {
+            return None
+        }
+        for child in self.get_children() {
+            let obj = get_ui_object3(&child);
+            if let Some(area) = obj.handle_ime(ev).await {
+                let slot = self.slots.lock().get(&child.id).cloned()?;
+                return Some(area + self.rect.get().pos() + slot.pos())
+            }
+        }
+        None
+    }
+
+    async fn handle_key_down(&self, key: KeyCode, mods: KeyMods, repeat: bool) -> bool {
+        if !self.is_visible.get() {
+            return false
+        }
+        for child in self.get_children() {
+            let obj = get_ui_object3(&child);
+            if obj.handle_key_down(key, mods, repeat).await {
+                return true
+            }
+        }
+        false
+    }
+
+    async fn handle_key_up(&self, key: KeyCode, mods: KeyMods) -> bool {
+        if !self.is_visible.get() {
+            return false
+        }
+        for child in self.get_children() {
+            let obj = get_ui_object3(&child);
+            if obj.handle_key_up(key, mods).await {
+                return true
+            }
+        }
+        false
+    }
+
+    async fn handle_mouse_btn_down(&self, btn: MouseButton, mouse_pos: Point) -> bool {
+        if !self.is_visible.get() {
+            return false
+        }
+        for child in self.get_children() {
+            let Some(pos) = self.to_slot(&child, mouse_pos) else { continue };
+            let obj = get_ui_object3(&child);
+            if obj.handle_mouse_btn_down(btn, pos).await {
+                return true
+            }
+        }
+        false
+    }
+    async fn handle_mouse_btn_up(&self, btn: MouseButton, mouse_pos: Point) -> bool {
+        if !self.is_visible.get() {
+            return false
+        }
+        for child in self.get_children() {
+            let Some(pos) = self.to_slot(&child, mouse_pos) else { continue };
+            let obj = get_ui_object3(&child);
+            if obj.handle_mouse_btn_up(btn, pos).await {
+                return true
+            }
+        }
+        false
+    }
+    async fn handle_mouse_move(&self, mouse_pos: Point) -> bool {
+        if !self.is_visible.get() {
+            return false
+        }
+        for child in self.get_children() {
+            let Some(pos) = self.to_slot(&child, mouse_pos) else { continue };
+            let obj = get_ui_object3(&child);
+            if obj.handle_mouse_move(pos).await {
+                return true
+            }
+        }
+        false
+    }
+    async fn handle_mouse_wheel(&self, wheel_pos: Point) -> bool {
+        if !self.is_visible.get() {
+            return false
+        }
+        for child in self.get_children() {
+            let Some(pos) = self.to_slot(&child, wheel_pos) else { continue };
+            let obj = get_ui_object3(&child);
+            if obj.handle_mouse_wheel(pos).await {
+                return true
+            }
+        }
+        false
+    }
+    async fn handle_touch(&self, phase: TouchPhase, id: u64, touch_pos: Point) -> bool {
+        if !self.is_visible.get() {
+            return false
+        }
+        for child in self.get_children() {
+            let Some(pos) = self.to_slot(&child, touch_pos) else { continue };
+            let obj = get_ui_object3(&child);
+            if obj.handle_touch(phase, id, pos).await {
+                return true
+            }
+        }
+        false
+    }
+
+    fn set_i18n(&self, i18n_fish: &I18nBabelFish) {
+        for child in self.get_children() {
+            let obj = get_ui_object3(&child);
+            obj.set_i18n(i18n_fish);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(direction: Direction, justify: Justify, align: Align) -> FlexParams {
+        FlexParams { direction, justify, align, gap: 10., padding: [5.; 4] }
+    }
+
+    fn arr(rect: &Rectangle) -> [f32; 4] {
+        [rect.x, rect.y, rect.w, rect.h]
+    }
+
+    #[test]
+    fn flex_grow_and_shrink() {
+        let params = params(Direction::Row, Justify::Start, Align::Stretch);
+        let items = vec![
+            FlexItem { basis: 100., grow: 0., ..Default::default() },
+            FlexItem { basis: 0., grow: 1., ..Default::default() },
+            FlexItem { basis: 0., grow: 3., ..Default::default() },
+        ];
+
+        // Inner width 390, minus 2 gaps and the basis leaves 270 to grow into
+        let slots = layout(&params, &items, 400., 50.);
+        assert_eq!(arr(&slots[0]), [5., 5., 100., 40.]);
+        assert_eq!(arr(&slots[1]), [115., 5., 67.5, 40.]);
+        assert_eq!(arr(&slots[2]), [192.5, 5., 202.5, 40.]);
+
+        // Not enough space, shrink proportionally to the basis
+        let items = vec![
+            FlexItem { basis: 200., grow: 0., ..Default::default() },
+            FlexItem { basis: 100., grow: 0., ..Default::default() },
+        ];
+        let slots = layout(&params, &items, 170., 50.);
+        assert_eq!(slots[0].w, 100.);
+        assert_eq!(slots[1].w, 50.);
+        assert_eq!(slots[1].x, 115.);
+    }
+
+    #[test]
+    fn flex_justify_and_align() {
+        let params = params(Direction::Column, Justify::SpaceBetween, Align::Center);
+        let item = FlexItem { basis: 20., grow: 0., cross: 50., ..Default::default() };
+        let items = vec![item.clone(), item.clone(), item];
+
+        let slots = layout(&params, &items, 100., 110.);
+        assert_eq!(arr(&slots[0]), [25., 5., 50., 20.]);
+        assert_eq!(arr(&slots[1]), [25., 45., 50., 20.]);
+        assert_eq!(arr(&slots[2]), [25., 85., 50., 20.]);
+
+        let params = FlexParams { justify: Justify::End, align: Align::Stretch, ..params };
+        let items =
+            vec![FlexItem { basis: 20., grow: 0., margin: [0., 1., 2., 3.], ..Default::default() }];
+        let slots = layout(&params, &items, 100., 110.);
+        assert_eq!(arr(&slots[0]), [8., 83., 86., 20.]);
+    }
+}
@@ -0,0 +1,220 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use image::ImageReader;
+use parking_lot::Mutex as SyncMutex;
+use std::{
+    collections::HashMap,
+    io::Cursor,
+    sync::{Arc, LazyLock},
+};
+
+use crate::error::{Error, Result};
+
+macro_rules! d { ($($arg:tt)*) => { debug!(target: "ui::image::cache", $($arg)*); } }
+
+/// Upper bound on decoded pixel data kept around after images are unloaded
+const MAX_CACHE_BYTES: usize = 64 * 1024 * 1024;
+
+/// Decoded images are shared by all Image nodes, so the same file is only
+/// decoded once while it stays in the cache.
+pub static IMAGE_CACHE: LazyLock<SyncMutex<ImageCache>> =
+    LazyLock::new(|| SyncMutex::new(ImageCache::new(MAX_CACHE_BYTES)));
+
+pub type DecodedImagePtr = Arc<DecodedImage>;
+
+/// RGBA8 pixels
+pub struct DecodedImage {
+    pub width: u32,
+    pub height: u32,
+    pub bmp: Vec<u8>,
+}
+
+impl DecodedImage {
+    /// Split into tiles no larger than `tile_size`, row by row from the top left.
+    /// Returns `(x, y, w, h)` in pixels.
+    pub fn tiles(&self, tile_size: u32) -> Vec<(u32, u32, u32, u32)> {
+        let mut tiles = vec![];
+        for y in (0..self.height).step_by(tile_size as usize) {
+            for x in (0..self.width).step_by(tile_size as usize) {
+                let w = tile_size.min(self.width - x);
+                let h = tile_size.min(self.height - y);
+                tiles.push((x, y, w, h));
+            }
+        }
+        tiles
+    }
+
+    /// Copy out the pixels of a single tile
+    pub fn crop(&self, (x, y, w, h): (u32, u32, u32, u32)) -> Vec<u8> {
+        let stride = self.width as usize * 4;
+        let (x, w) = (x as usize * 4, w as usize * 4);
+        let mut bmp = Vec::with_capacity(w * h as usize);
+        for row in y as usize..(y + h) as usize {
+            let start = row * stride + x;
+            bmp.extend_from_slice(&self.bmp[start..start + w]);
+        }
+        bmp
+    }
+}
+
+struct Entry {
+    img: DecodedImagePtr,
+    last_used: u64,
+}
+
+pub struct ImageCache {
+    images: HashMap<String, Entry>,
+    tick: u64,
+    size: usize,
+    max_size: usize,
+}
+
+impl ImageCache {
+    fn new(max_size: usize) -> Self {
+        Self { images: HashMap::new(), tick: 0, size: 0, max_size }
+    }
+
+    pub fn get(&mut self, path: &str) -> Option<DecodedImagePtr> {
+        self.tick += 1;
+        let entry = self.images.get_mut(path)?;
+        entry.last_used = self.tick;
+        Some(entry.img.clone())
+    }
+
+    pub fn insert(&mut self, path: String, img: DecodedImagePtr) {
+        let img_size = img.bmp.len();
+        // Caching this would flush everything else
+        if img_size > self.max_size {
+            return
+        }
+
+        self.tick += 1;
+        let entry = Entry { img, last_used: self.tick };
+        if let Some(old) = self.images.insert(path, entry) {
+            self.size -= old.img.bmp.len();
+        }
+        self.size += img_size;
+
+        while self.size > self.max_size {
+            let lru = self
+                .images
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(path, _)| path.clone())
+                .unwrap();
+            d!("Evicting {lru}");
+            let entry = self.images.remove(&lru).unwrap();
+            self.size -= entry.img.bmp.len();
+        }
+    }
+}
+
+/// Fetch the image from the cache, otherwise decode it on the blocking
+/// thread pool so the executor isn't held up.
+pub async fn load(path: String) -> Result<DecodedImagePtr> {
+    if let Some(img) = IMAGE_CACHE.lock().get(&path) {
+        return Ok(img)
+    }
+
+    let img = {
+        let path = path.clone();
+        smol::unblock(move || decode(&path)).await?
+    };
+    let img = Arc::new(img);
+    IMAGE_CACHE.lock().insert(path, img.clone());
+    Ok(img)
+}
+
+fn decode(path: &str) -> Result<DecodedImage> {
+    let data = Arc::new(SyncMutex::new(None));
+    let data2 = data.clone();
+    let path2 = path.to_string();
+    miniquad::fs::load_file(path, move |res| match res {
+        Ok(res) => *data2.lock() = Some(res),
+        Err(err) => error!(target: "ui::image", "Unable to open image: {path2}: {err}"),
+    });
+    let Some(data) = data.lock().take() else { return Err(Error::ResourceNotFound) };
+
+    let img = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|_| Error::ImageDecodeFailed)?
+        .decode()
+        .map_err(|err| {
+            error!(target: "ui::image", "Unable to decode image: {path}: {err}");
+            Error::ImageDecodeFailed
+        })?
+        .to_rgba8();
+
+    Ok(DecodedImage { width: img.width(), height: img.height(), bmp: img.into_raw() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn img(width: u32, height: u32) -> DecodedImagePtr {
+        let bmp = (0..width * height * 4).map(|i| i as u8).collect();
+        Arc::new(DecodedImage { width, height, bmp })
+    }
+
+    #[test]
+    fn tiles_cover_image() {
+        let img = img(5, 3);
+        let tiles = img.tiles(2);
+        assert_eq!(
+            tiles,
+            vec![
+                (0, 0, 2, 2),
+                (2, 0, 2, 2),
+                (4, 0, 1, 2),
+                (0, 2, 2, 1),
+                (2, 2, 2, 1),
+                (4, 2, 1, 1)
+            ]
+        );
+
+        let total: usize = tiles.iter().map(|tile| img.crop(*tile).len()).sum();
+        assert_eq!(total, img.bmp.len());
+
+        // Pixel (3, 1) is the second pixel of the top row in tile (2, 0)
+        let tile = img.crop((2, 0, 2, 2));
+        let pixel = ((1 * 5 + 3) * 4) as usize;
+        assert_eq!(tile[8..12], img.bmp[pixel..pixel + 4]);
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = ImageCache::new(3 * 4 * 4);
+        cache.insert("a".to_string(), img(2, 2));
+        cache.insert("b".to_string(), img(2, 2));
+        cache.insert("c".to_string(), img(2, 2));
+        assert!(cache.get("a").is_some());
+
+        cache.insert("d".to_string(), img(2, 2));
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("c").is_some());
+        assert_eq!(cache.size, 3 * 4 * 4);
+
+        // Too big to ever fit
+        cache.insert("e".to_string(), img(4, 4));
+        assert!(cache.get("e").is_none());
+        assert!(cache.get("d").is_some());
+    }
+}
@@ -0,0 +1,293 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use async_trait::async_trait;
+use parking_lot::Mutex as SyncMutex;
+use rand::{rngs::OsRng, Rng};
+use std::sync::{Arc, Weak};
+
+use crate::{
+    gfx::{gfxtag, DrawCall, DrawInstruction, DrawMesh, ManagedTexturePtr, Rectangle, RenderApi},
+    mesh::{MeshBuilder, COLOR_DARKGREY, COLOR_WHITE},
+    prop::{BatchGuardPtr, PropertyAtomicGuard, PropertyRect, PropertyStr, PropertyUint32, Role},
+    scene::{Pimpl, SceneNodeWeak},
+    util::unixtime,
+    ExecutorPtr,
+};
+
+use super::{DrawCache, DrawTrace, DrawUpdate, OnModify, UIObject};
+
+mod cache;
+use cache::DecodedImagePtr;
+
+macro_rules! t { ($($arg:tt)*) => { trace!(target: "ui::image", $($arg)*); } }
+
+/// Larger images are uploaded as several textures. This keeps each upload
+/// short and stays under the max texture size of most GPUs.
+const TILE_SIZE: u32 = 1024;
+
+pub type ImagePtr = Arc<Image>;
+
+struct Tile {
+    /// Normalized to the image size, same as the uv prop
+    rect: Rectangle,
+    texture: ManagedTexturePtr,
+}
+
+pub struct Image {
+    node: SceneNodeWeak,
+    render_api: RenderApi,
+    tasks: SyncMutex<Vec<smol::Task<()>>>,
+    load_task: SyncMutex<Option<smol::Task<()>>>,
+    ex: ExecutorPtr,
+
+    /// Filled in as the image loads. Until then a placeholder is drawn.
+    tiles: SyncMutex<Vec<Tile>>,
+    dc_key: u64,
+    draw_cache: DrawCache,
+
+    rect: PropertyRect,
+    uv: PropertyRect,
+    z_index: PropertyUint32,
+    priority: PropertyUint32,
+    path: PropertyStr,
+
+    parent_rect: SyncMutex<Option<Rectangle>>,
+}
+
+impl Image {
+    pub async fn new(node: SceneNodeWeak, render_api: RenderApi, ex: ExecutorPtr) -> Pimpl {
+        t!("Image::new()");
+
+        let node_ref = &node.upgrade().unwrap();
+        let rect = PropertyRect::wrap(node_ref, Role::Internal, "rect").unwrap();
+        let uv = PropertyRect::wrap(node_ref, Role::Internal, "uv").unwrap();
+        let z_index = PropertyUint32::wrap(node_ref, Role::Internal, "z_index", 0).unwrap();
+        let priority = PropertyUint32::wrap(node_ref, Role::Internal, "priority", 0).unwrap();
+        let path = PropertyStr::wrap(node_ref, Role::Internal, "path", 0).unwrap();
+
+        let self_ = Arc::new(Self {
+            node,
+            render_api,
+            tasks: SyncMutex::new(vec![]),
+            load_task: SyncMutex::new(None),
+            ex,
+
+            tiles: SyncMutex::new(vec![]),
+            dc_key: OsRng.gen(),
+            draw_cache: DrawCache::default(),
+
+            rect,
+            uv,
+            z_index,
+            priority,
+            path,
+
+            parent_rect: SyncMutex::new(None),
+        });
+
+        Pimpl::Image(self_)
+    }
+
+    async fn reload(self: Arc<Self>, batch: BatchGuardPtr) {
+        self.load();
+        self.redraw(batch).await;
+    }
+
+    /// Drops the current image and starts loading the one at path.
+    /// The placeholder is drawn until the first tile is uploaded.
+    fn load(self: &Arc<Self>) {
+        let path = self.path.get();
+        self.tiles.lock().clear();
+        self.draw_cache.invalidate();
+
+        let me = Arc::downgrade(self);
+        let task = self.ex.spawn(async move {
+            let img = match cache::load(path.clone()).await {
+                Ok(img) => img,
+                Err(err) => {
+                    error!(target: "ui::image", "Failed to load image: {path}: {err}");
+                    return
+                }
+            };
+            Self::upload_tiles(me, img).await;
+        });
+        // Cancels any previous load
+        *self.load_task.lock() = Some(task);
+    }
+
+    async fn upload_tiles(me: Weak<Self>, img: DecodedImagePtr) {
+        let (width, height) = (img.width as f32, img.height as f32);
+        for tile in img.tiles(TILE_SIZE) {
+            let Some(self_) = me.upgrade() else { return };
+
+            let (x, y, w, h) = tile;
+            let bmp = img.crop(tile);
+            let texture = self_.render_api.new_texture(w as u16, h as u16, bmp, gfxtag!("img"));
+            let rect = Rectangle::new(
+                x as f32 / width,
+                y as f32 / height,
+                w as f32 / width,
+                h as f32 / height,
+            );
+            self_.tiles.lock().push(Tile { rect, texture });
+            self_.draw_cache.invalidate();
+
+            {
+                let atom = &mut self_.render_api.make_guard(gfxtag!("Image::upload_tiles"));
+                self_.redraw_atom(atom).await;
+            }
+            drop(self_);
+
+            // Let the rest of the UI run between tiles
+            smol::future::yield_now().await;
+        }
+    }
+
+    async fn redraw(self: Arc<Self>, batch: BatchGuardPtr) {
+        self.redraw_atom(&mut batch.spawn()).await;
+    }
+
+    async fn redraw_atom(&self, atom: &mut PropertyAtomicGuard) {
+        let trace: DrawTrace = rand::random();
+        let timest = unixtime();
+        t!("redraw({:?}) [trace={trace}]", self.node.upgrade().unwrap());
+        let Some(parent_rect) = self.parent_rect.lock().clone() else { return };
+
+        let Some(draw_update) = self.get_draw_calls(atom, parent_rect).await else {
+            error!(target: "ui::image", "Image failed to draw");
+            return
+        };
+        self.render_api.replace_draw_calls(atom.batch_id, timest, draw_update.draw_calls);
+        t!("redraw() DONE [trace={trace}]");
+    }
+
+    /// Called whenever any property changes.
+    fn regen_meshes(&self) -> Vec<DrawMesh> {
+        let rect = self.rect.get();
+        let uv = self.uv.get();
+        let tiles = self.tiles.lock();
+
+        if tiles.is_empty() {
+            let mesh_rect = Rectangle::from([0., 0., rect.w, rect.h]);
+            let mut mesh = MeshBuilder::new(gfxtag!("img_placeholder"));
+            mesh.draw_box(&mesh_rect, COLOR_DARKGREY, &Rectangle::zero());
+            return vec![mesh.alloc(&self.render_api).draw_untextured()]
+        }
+
+        let mut meshes = vec![];
+        for tile in tiles.iter() {
+            // Part of the tile which is inside the uv rect
+            let Some(visible) = uv.clip(&tile.rect) else { continue };
+            if visible.w <= 0. || visible.h <= 0. {
+                continue
+            }
+
+            let mesh_rect = Rectangle::new(
+                (visible.x - uv.x) / uv.w * rect.w,
+                (visible.y - uv.y) / uv.h * rect.h,
+                visible.w / uv.w * rect.w,
+                visible.h / uv.h * rect.h,
+            );
+            let tile_uv = Rectangle::new(
+                (visible.x - tile.rect.x) / tile.rect.w,
+                (visible.y - tile.rect.y) / tile.rect.h,
+                visible.w / tile.rect.w,
+                visible.h / tile.rect.h,
+            );
+
+            let mut mesh = MeshBuilder::new(gfxtag!("img"));
+            mesh.draw_box(&mesh_rect, COLOR_WHITE, &tile_uv);
+            meshes.push(mesh.alloc(&self.render_api).draw_with_texture(tile.texture.clone()));
+        }
+        meshes
+    }
+
+    async fn get_draw_calls(
+        &self,
+        atom: &mut PropertyAtomicGuard,
+        parent_rect: Rectangle,
+    ) -> Option<DrawUpdate> {
+        self.rect.eval(atom, &parent_rect).ok()?;
+        let rect = self.rect.get();
+        self.uv.eval(atom, &rect).ok()?;
+
+        let generation = self.node.upgrade()?.generation();
+        if let Some(draw_calls) = self.draw_cache.get(generation) {
+            return Some(DrawUpdate { key: self.dc_key, draw_calls })
+        }
+
+        let mut instrs = vec![DrawInstruction::Move(rect.pos())];
+        instrs.extend(self.regen_meshes().into_iter().map(DrawInstruction::Draw));
+
+        let draw_calls =
+            vec![(self.dc_key, DrawCall::new(instrs, vec![], self.z_index.get(), "img"))];
+        self.draw_cache.set(generation, draw_calls.clone());
+        Some(DrawUpdate { key: self.dc_key, draw_calls })
+    }
+}
+
+#[async_trait]
+impl UIObject for Image {
+    fn priority(&self) -> u32 {
+        self.priority.get()
+    }
+
+    async fn start(self: Arc<Self>, ex: ExecutorPtr) {
+        let me = Arc::downgrade(&self);
+        self.load();
+
+        let mut on_modify = OnModify::new(ex, self.node.clone(), me.clone());
+        on_modify.when_change(self.rect.prop(), Self::redraw);
+        on_modify.when_change(self.uv.prop(), Self::redraw);
+        on_modify.when_change(self.z_index.prop(), Self::redraw);
+        on_modify.when_change(self.path.prop(), Self::reload);
+
+        *self.tasks.lock() = on_modify.tasks;
+    }
+
+    fn stop(&self) {
+        self.tasks.lock().clear();
+        *self.load_task.lock() = None;
+        *self.parent_rect.lock() = None;
+        self.tiles.lock().clear();
+        self.draw_cache.invalidate();
+    }
+
+    async fn draw(
+        &self,
+        parent_rect: Rectangle,
+        trace: DrawTrace,
+        atom: &mut PropertyAtomicGuard,
+    ) -> Option<DrawUpdate> {
+        t!("Image::draw() [trace={trace}]");
+        *self.parent_rect.lock() = Some(parent_rect);
+        self.get_draw_calls(atom, parent_rect).await
+    }
+}
+
+impl Drop for Image {
+    fn drop(&mut self) {
+        let atom = self.render_api.make_guard(gfxtag!("Image::drop"));
+        self.render_api.replace_draw_calls(
+            atom.batch_id,
+            unixtime(),
+            vec![(self.dc_key, Default::default())],
+        );
+    }
+}
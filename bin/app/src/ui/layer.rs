@@ -23,7 +23,7 @@ use rand::{rngs::OsRng, Rng};
 use std::sync::Arc;
 
 use crate::{
-    gfx::{DrawCall, DrawInstruction, Point, Rectangle, RenderApi},
+    gfx::{DrawCall, DrawInstruction, ImeEvent, Point, Rectangle, RenderApi},
     prop::{BatchGuardPtr, PropertyAtomicGuard, PropertyBool, PropertyRect, PropertyUint32, Role},
     scene::{Pimpl, SceneNodePtr, SceneNodeWeak},
     util::{i18n::I18nBabelFish, unixtime},
@@ -219,6 +219,20 @@ impl UIObject for Layer {
         false
     }
 
+    async fn handle_ime(&self, ev: &ImeEvent) -> Option<Rectangle> {
+        if !self.is_visible.get() {
+            return None
+        }
+        for child in self.get_children() {
+            let obj = get_ui_object3(&child);
+            if let Some(area) = obj.handle_ime(ev).await {
+                t!("handle_ime({ev:?}) swallowed by {child:?}");
+                return Some(area + self.rect.get().pos())
+            }
+        }
+        None
+    }
+
     async fn handle_key_down(&self, key: KeyCode, mods: KeyMods, repeat: bool) -> bool {
         if !self.is_visible.get() {
             return false
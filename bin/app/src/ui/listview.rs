@@ -0,0 +1,828 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Scrollable list which only draws the rows currently in view.
+//!
+//! The children of a `ListView` are its rows, and they get recycled while
+//! scrolling: row `n` shows every item `i` where `i % rows == n`. There
+//! should be enough children to cover the view plus one.
+//!
+//! Items are lists of `(path, text)` fields. Showing an item in a row sets
+//! the `text` property of the node at each path below the row, so every
+//! item should set the same fields.
+
+use async_trait::async_trait;
+use atomic_float::AtomicF32;
+use darkfi::system::{msleep, CondVar};
+use darkfi_serial::Decodable;
+use miniquad::{MouseButton, TouchPhase};
+use parking_lot::Mutex as SyncMutex;
+use rand::{rngs::OsRng, Rng};
+use std::{
+    collections::VecDeque,
+    io::Cursor,
+    ops::Range,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Weak,
+    },
+    time::Instant,
+};
+
+use crate::{
+    gfx::{gfxtag, DrawCall, DrawInstruction, Point, Rectangle, RenderApi},
+    mesh::MeshBuilder,
+    prop::{
+        BatchGuardPtr, PropertyAtomicGuard, PropertyBool, PropertyColor, PropertyFloat32,
+        PropertyRect, PropertyUint32, Role,
+    },
+    scene::{MethodCallSub, Pimpl, SceneNodePtr, SceneNodeWeak, ScenePath},
+    util::{i18n::I18nBabelFish, unixtime},
+    ExecutorPtr,
+};
+
+use super::{get_ui_object3, get_ui_object_ptr, DrawUpdate, OnModify, UIObject};
+
+macro_rules! d { ($($arg:tt)*) => { debug!(target: "ui::listview", $($arg)*); } }
+macro_rules! t { ($($arg:tt)*) => { trace!(target: "ui::listview", $($arg)*); } }
+
+const EPSILON: f32 = 0.05;
+/// Touches moving less than this are taps on a row rather than scrolls
+const TAP_DIST: f32 = 8.;
+
+/// Fields of an item as `(path below the row, text)`
+type ListItem = Vec<(String, String)>;
+
+struct TouchInfo {
+    start_scroll: f32,
+    start_pos: Point,
+    /// Recent positions, used for flinging
+    samples: VecDeque<(Instant, f32)>,
+    /// Set once the touch moved too far to be a tap
+    is_drag: bool,
+}
+
+impl TouchInfo {
+    fn new(start_scroll: f32, pos: Point) -> Self {
+        Self {
+            start_scroll,
+            start_pos: pos,
+            samples: VecDeque::from([(Instant::now(), pos.y)]),
+            is_drag: false,
+        }
+    }
+
+    fn push_sample(&mut self, y: f32) {
+        self.samples.push_back((Instant::now(), y));
+
+        // Now drop all samples older than 40ms
+        while let Some((instant, _)) = self.samples.front() {
+            if instant.elapsed().as_micros() <= 40_000 {
+                break
+            }
+            self.samples.pop_front();
+        }
+    }
+
+    /// Speed in px/ms over the recent samples
+    fn fling_speed(&self, y: f32) -> Option<f32> {
+        let (instant, sample_y) = self.samples.front()?;
+        let time = instant.elapsed().as_micros() as f32 / 1000.;
+        // Ignore sub-ms events
+        if time < 1. {
+            return None
+        }
+        Some((y - sample_y) / time)
+    }
+}
+
+pub type ListViewPtr = Arc<ListView>;
+
+pub struct ListView {
+    node: SceneNodeWeak,
+    render_api: RenderApi,
+    tasks: SyncMutex<Vec<smol::Task<()>>>,
+    dc_key: u64,
+    scrollbar_dc_key: u64,
+
+    items: SyncMutex<Vec<ListItem>>,
+    /// Item currently shown by each row
+    bound: SyncMutex<Vec<Option<usize>>>,
+    /// Draw call keys of the rows from the last full draw
+    row_keys: SyncMutex<Vec<Option<u64>>>,
+    /// Draw call keys of the slots placing each row inside the list
+    slot_keys: SyncMutex<Vec<u64>>,
+
+    is_visible: PropertyBool,
+    rect: PropertyRect,
+    z_index: PropertyUint32,
+    priority: PropertyUint32,
+    scroll: PropertyFloat32,
+    row_height: PropertyFloat32,
+    scroll_start_accel: PropertyFloat32,
+    scroll_resist: PropertyFloat32,
+    scrollbar_width: PropertyFloat32,
+    scrollbar_color: PropertyColor,
+
+    mouse_pos: SyncMutex<Point>,
+    /// Where the mouse grabbed the scrollbar thumb while dragging it
+    scrollbar_grab: SyncMutex<Option<f32>>,
+    touch_info: SyncMutex<Option<TouchInfo>>,
+    touch_is_active: AtomicBool,
+
+    /// Kinetic scrolling
+    motion_cv: Arc<CondVar>,
+    speed: AtomicF32,
+
+    parent_rect: SyncMutex<Option<Rectangle>>,
+}
+
+impl ListView {
+    pub async fn new(node: SceneNodeWeak, render_api: RenderApi) -> Pimpl {
+        let node_ref = &node.upgrade().unwrap();
+        t!("ListView::new({node_ref:?})");
+        let is_visible = PropertyBool::wrap(node_ref, Role::Internal, "is_visible", 0).unwrap();
+        let rect = PropertyRect::wrap(node_ref, Role::Internal, "rect").unwrap();
+        let z_index = PropertyUint32::wrap(node_ref, Role::Internal, "z_index", 0).unwrap();
+        let priority = PropertyUint32::wrap(node_ref, Role::Internal, "priority", 0).unwrap();
+        let scroll = PropertyFloat32::wrap(node_ref, Role::Internal, "scroll", 0).unwrap();
+        let row_height = PropertyFloat32::wrap(node_ref, Role::Internal, "row_height", 0).unwrap();
+        let scroll_start_accel =
+            PropertyFloat32::wrap(node_ref, Role::Internal, "scroll_start_accel", 0).unwrap();
+        let scroll_resist =
+            PropertyFloat32::wrap(node_ref, Role::Internal, "scroll_resist", 0).unwrap();
+        let scrollbar_width =
+            PropertyFloat32::wrap(node_ref, Role::Internal, "scrollbar_width", 0).unwrap();
+        let scrollbar_color =
+            PropertyColor::wrap(node_ref, Role::Internal, "scrollbar_color").unwrap();
+
+        let self_ = Arc::new(Self {
+            node,
+            render_api,
+            tasks: SyncMutex::new(vec![]),
+            dc_key: OsRng.gen(),
+            scrollbar_dc_key: OsRng.gen(),
+
+            items: SyncMutex::new(vec![]),
+            bound: SyncMutex::new(vec![]),
+            row_keys: SyncMutex::new(vec![]),
+            slot_keys: SyncMutex::new(vec![]),
+
+            is_visible,
+            rect,
+            z_index,
+            priority,
+            scroll,
+            row_height,
+            scroll_start_accel,
+            scroll_resist,
+            scrollbar_width,
+            scrollbar_color,
+
+            mouse_pos: SyncMutex::new(Point::zero()),
+            scrollbar_grab: SyncMutex::new(None),
+            touch_info: SyncMutex::new(None),
+            touch_is_active: AtomicBool::new(false),
+
+            motion_cv: Arc::new(CondVar::new()),
+            speed: AtomicF32::new(0.),
+
+            parent_rect: SyncMutex::new(None),
+        });
+
+        Pimpl::ListView(self_)
+    }
+
+    async fn process_items_method(me: &Weak<Self>, sub: &MethodCallSub, method: &str) -> bool {
+        let Ok(method_call) = sub.receive().await else {
+            d!("Event relayer closed");
+            return false
+        };
+
+        t!("method called: {method}({method_call:?})");
+        assert!(method_call.send_res.is_none());
+
+        fn decode_index(data: &[u8]) -> std::io::Result<usize> {
+            let mut cur = Cursor::new(&data);
+            Ok(u32::decode(&mut cur)? as usize)
+        }
+        fn decode_field(data: &[u8]) -> std::io::Result<(usize, String, String)> {
+            let mut cur = Cursor::new(&data);
+            let idx = u32::decode(&mut cur)? as usize;
+            let path = String::decode(&mut cur)?;
+            let text = String::decode(&mut cur)?;
+            Ok((idx, path, text))
+        }
+
+        let Some(self_) = me.upgrade() else {
+            // Should not happen
+            panic!("self destroyed before {method}_method_task was stopped!");
+        };
+
+        let data = &method_call.data;
+        let is_ok = match method {
+            "insert_item" => decode_index(data).map(|idx| self_.insert_item(idx)).is_ok(),
+            "set_item_field" => decode_field(data)
+                .map(|(idx, path, text)| self_.set_item_field(idx, path, text))
+                .is_ok(),
+            "remove_item" => decode_index(data).map(|idx| self_.remove_item(idx)).is_ok(),
+            "clear_items" => {
+                self_.clear_items();
+                true
+            }
+            _ => unreachable!(),
+        };
+        if !is_ok {
+            error!(target: "ui::listview", "{method}() method invalid arg data");
+            return true
+        }
+
+        let atom = &mut self_.render_api.make_guard(gfxtag!("ListView::process_items_method"));
+        self_.scrollview(self_.scroll.get(), atom);
+        true
+    }
+
+    fn insert_item(&self, idx: usize) {
+        let mut items = self.items.lock();
+        let idx = idx.min(items.len());
+        items.insert(idx, vec![]);
+        self.bound.lock().clear();
+    }
+
+    fn set_item_field(&self, idx: usize, path: String, text: String) {
+        let mut items = self.items.lock();
+        let Some(item) = items.get_mut(idx) else {
+            error!(target: "ui::listview", "set_item_field(): no item at {idx}");
+            return
+        };
+        match item.iter_mut().find(|(field_path, _)| *field_path == path) {
+            Some(field) => field.1 = text,
+            None => item.push((path, text)),
+        }
+
+        // Show the change if the item is in view
+        for bound in self.bound.lock().iter_mut() {
+            if *bound == Some(idx) {
+                *bound = None;
+            }
+        }
+    }
+
+    fn remove_item(&self, idx: usize) {
+        let mut items = self.items.lock();
+        if idx >= items.len() {
+            error!(target: "ui::listview", "remove_item(): no item at {idx}");
+            return
+        }
+        items.remove(idx);
+        self.bound.lock().clear();
+    }
+
+    fn clear_items(&self) {
+        self.items.lock().clear();
+        self.bound.lock().clear();
+    }
+
+    fn rows(&self) -> Vec<SceneNodePtr> {
+        self.node.upgrade().unwrap().get_children()
+    }
+
+    fn total_height(&self) -> f32 {
+        self.items.lock().len() as f32 * self.row_height.get()
+    }
+
+    fn max_scroll(&self) -> f32 {
+        (self.total_height() - self.rect.get().h).max(0.)
+    }
+
+    /// Items in view, limited to the number of rows available
+    fn visible_items(&self, n_rows: usize) -> Range<usize> {
+        let row_h = self.row_height.get();
+        if row_h <= 0. || n_rows == 0 {
+            return 0..0
+        }
+
+        let n_items = self.items.lock().len();
+        let scroll = self.scroll.get();
+        let first = ((scroll / row_h).floor().max(0.) as usize).min(n_items);
+        let last = (((scroll + self.rect.get().h) / row_h).ceil() as usize).min(n_items);
+        if last - first > n_rows {
+            d!("Not enough rows to fill the view: {n_rows} < {}", last - first);
+            return first..first + n_rows
+        }
+        first..last
+    }
+
+    /// Show each visible item in its row. Returns `(row, item)` pairs.
+    fn bind_rows(
+        &self,
+        rows: &[SceneNodePtr],
+        atom: &mut PropertyAtomicGuard,
+    ) -> Vec<(usize, usize)> {
+        let range = self.visible_items(rows.len());
+
+        let items = self.items.lock();
+        let mut bound = self.bound.lock();
+        bound.resize(rows.len(), None);
+
+        let mut visible = vec![];
+        for idx in range {
+            let row = idx % rows.len();
+            if bound[row] != Some(idx) {
+                Self::bind(&rows[row], &items[idx], atom);
+                bound[row] = Some(idx);
+            }
+            visible.push((row, idx));
+        }
+        visible
+    }
+
+    fn bind(row: &SceneNodePtr, item: &ListItem, atom: &mut PropertyAtomicGuard) {
+        for (path, text) in item {
+            let Ok(scene_path) = path.parse::<ScenePath>() else {
+                warn!(target: "ui::listview", "Invalid item field path: {path}");
+                continue
+            };
+            let Some(node) = row.lookup_node(scene_path) else {
+                warn!(target: "ui::listview", "Item field {path} not found in {row:?}");
+                continue
+            };
+            if let Err(err) = node.set_property_str(atom, Role::App, "text", text.clone()) {
+                warn!(target: "ui::listview", "Unable to set item field {path}: {err:?}");
+            }
+        }
+    }
+
+    /// Visible rows with their position inside the list
+    fn visible_rows(&self) -> Vec<(SceneNodePtr, Point)> {
+        let rows = self.rows();
+        let range = self.visible_items(rows.len());
+        let row_h = self.row_height.get();
+        let scroll = self.scroll.get();
+
+        let bound = self.bound.lock();
+        let mut visible = vec![];
+        for (row, idx) in bound.iter().enumerate() {
+            let Some(idx) = idx else { continue };
+            if !range.contains(idx) {
+                continue
+            }
+            let pos = Point::new(0., *idx as f32 * row_h - scroll);
+            visible.push((rows[row].clone(), pos));
+        }
+        visible
+    }
+
+    /// Row under a point given relative to our parent, and the point inside the row
+    fn row_at(&self, point: Point) -> Option<(SceneNodePtr, Point)> {
+        let rect = self.rect.get();
+        if !rect.contains(point) {
+            return None
+        }
+        let point = point - rect.pos();
+        let row_h = self.row_height.get();
+        self.visible_rows()
+            .into_iter()
+            .find(|(_, pos)| point.y >= pos.y && point.y < pos.y + row_h)
+            .map(|(row, pos)| (row, point - pos))
+    }
+
+    /// Scrollbar thumb relative to the list. None when everything fits.
+    fn scrollbar_thumb(&self, rect: &Rectangle) -> Option<Rectangle> {
+        let width = self.scrollbar_width.get();
+        let total_h = self.total_height();
+        if width <= 0. || total_h <= rect.h {
+            return None
+        }
+
+        let thumb_h = (rect.h * rect.h / total_h).max(2. * width).min(rect.h);
+        let thumb_y = self.scroll.get() / (total_h - rect.h) * (rect.h - thumb_h);
+        Some(Rectangle::new(rect.w - width, thumb_y, width, thumb_h))
+    }
+
+    fn drag_scrollbar(&self, y: f32, grab: f32, atom: &mut PropertyAtomicGuard) {
+        let rect = self.rect.get();
+        let Some(thumb) = self.scrollbar_thumb(&rect) else { return };
+        let track_h = rect.h - thumb.h;
+        if track_h <= 0. {
+            return
+        }
+        self.scrollview((y - grab) / track_h * self.max_scroll(), atom);
+    }
+
+    /// Signal to begin scrolling
+    fn start_scroll(&self, y: f32) {
+        self.speed.fetch_add(y * self.scroll_start_accel.get(), Ordering::Relaxed);
+        self.motion_cv.notify();
+    }
+
+    async fn handle_movement(&self, atom: &mut PropertyAtomicGuard) {
+        loop {
+            msleep(10).await;
+
+            if self.touch_is_active.load(Ordering::Relaxed) {
+                return
+            }
+
+            // Apply constant decel to speed
+            let mut speed = self.speed.load(Ordering::Relaxed) * self.scroll_resist.get();
+            if speed.abs() < EPSILON {
+                speed = 0.;
+            }
+            self.speed.store(speed, Ordering::Relaxed);
+
+            // Finished
+            if speed == 0. {
+                return
+            }
+
+            // We reached the end so just stop
+            let dist = self.scrollview(self.scroll.get() + speed, atom);
+            if dist.abs() < EPSILON {
+                self.speed.store(0., Ordering::Relaxed);
+                return
+            }
+        }
+    }
+
+    /// Clamp and apply a new scroll value, returning the distance moved.
+    /// Rows are only redrawn when the item they show changes.
+    fn scrollview(&self, scroll: f32, atom: &mut PropertyAtomicGuard) -> f32 {
+        let scroll = scroll.clamp(0., self.max_scroll());
+        let dist = scroll - self.scroll.get();
+        self.scroll.set(atom, scroll);
+
+        // Not drawn yet
+        if self.parent_rect.lock().is_none() {
+            return dist
+        }
+
+        let rows = self.rows();
+        let visible = self.bind_rows(&rows, atom);
+        let draw_calls = self.get_view_calls(self.rect.get(), visible);
+        self.render_api.replace_draw_calls(atom.batch_id, unixtime(), draw_calls);
+        dist
+    }
+
+    /// Draw calls placing the visible rows and the scrollbar
+    fn get_view_calls(
+        &self,
+        rect: Rectangle,
+        visible: Vec<(usize, usize)>,
+    ) -> Vec<(u64, DrawCall)> {
+        let row_h = self.row_height.get();
+        let scroll = self.scroll.get();
+        let row_keys = self.row_keys.lock().clone();
+
+        let mut draw_calls = vec![];
+        let mut children = vec![];
+        for (row, idx) in visible {
+            let Some(Some(row_key)) = row_keys.get(row) else { continue };
+
+            let slot = Rectangle::new(0., idx as f32 * row_h - scroll, rect.w, row_h);
+            let slot_key = self.slot_key(row);
+            let dc = DrawCall::new(
+                vec![DrawInstruction::ApplyView(slot)],
+                vec![*row_key],
+                self.z_index.get(),
+                "listview_row",
+            );
+            draw_calls.push((slot_key, dc));
+            children.push(slot_key);
+        }
+
+        if let Some(thumb) = self.scrollbar_thumb(&rect) {
+            let mut mesh = MeshBuilder::new(gfxtag!("listview_scrollbar"));
+            mesh.draw_rounded_box(&thumb, thumb.w / 2., self.scrollbar_color.get());
+            let mesh = mesh.alloc(&self.render_api).draw_untextured();
+
+            let dc = DrawCall::new(
+                vec![DrawInstruction::Draw(mesh)],
+                vec![],
+                self.z_index.get() + 1,
+                "listview_scrollbar",
+            );
+            draw_calls.push((self.scrollbar_dc_key, dc));
+            children.push(self.scrollbar_dc_key);
+        }
+
+        let dc = DrawCall::new(
+            vec![DrawInstruction::ApplyView(rect)],
+            children,
+            self.z_index.get(),
+            "listview",
+        );
+        draw_calls.push((self.dc_key, dc));
+        draw_calls
+    }
+
+    fn slot_key(&self, row: usize) -> u64 {
+        let mut slot_keys = self.slot_keys.lock();
+        while slot_keys.len() <= row {
+            slot_keys.push(OsRng.gen());
+        }
+        slot_keys[row]
+    }
+
+    async fn redraw(self: Arc<Self>, batch: BatchGuardPtr) {
+        let trace_id = rand::random();
+        let timest = unixtime();
+        t!("ListView::redraw({:?}) [trace_id={trace_id}]", self.node.upgrade().unwrap());
+        let Some(parent_rect) = self.parent_rect.lock().clone() else { return };
+
+        let atom = &mut batch.spawn();
+        let Some(draw_update) = self.get_draw_calls(parent_rect, trace_id, atom).await else {
+            error!(target: "ui::listview", "ListView failed to draw [trace_id={trace_id}]");
+            return
+        };
+        self.render_api.replace_draw_calls(batch.id, timest, draw_update.draw_calls);
+    }
+
+    async fn get_draw_calls(
+        &self,
+        parent_rect: Rectangle,
+        trace_id: u32,
+        atom: &mut PropertyAtomicGuard,
+    ) -> Option<DrawUpdate> {
+        self.rect.eval(atom, &parent_rect).ok()?;
+        let rect = self.rect.get();
+        t!("ListView::get_draw_calls() [rect={rect:?}, dc={}, trace_id={trace_id}]", self.dc_key);
+
+        // Keep the scroll in range after a resize
+        self.scroll.set(atom, self.scroll.get().clamp(0., self.max_scroll()));
+
+        let rows = self.rows();
+        let visible = if self.is_visible.get() { self.bind_rows(&rows, atom) } else { vec![] };
+
+        // Every row is drawn once here so scrolling only has to move them
+        let row_rect = Rectangle::new(0., 0., rect.w, self.row_height.get());
+        let mut draw_calls = vec![];
+        let mut row_keys = vec![];
+        for row in &rows {
+            let obj = get_ui_object3(row);
+            match obj.draw(row_rect, trace_id, atom).await {
+                Some(mut draw_update) => {
+                    draw_calls.append(&mut draw_update.draw_calls);
+                    row_keys.push(Some(draw_update.key));
+                }
+                None => row_keys.push(None),
+            }
+        }
+        *self.row_keys.lock() = row_keys;
+
+        draw_calls.append(&mut self.get_view_calls(rect, visible));
+        Some(DrawUpdate { key: self.dc_key, draw_calls })
+    }
+}
+
+#[async_trait]
+impl UIObject for ListView {
+    fn priority(&self) -> u32 {
+        self.priority.get()
+    }
+
+    fn init(&self) {
+        for row in self.rows() {
+            let obj = get_ui_object3(&row);
+            obj.init();
+        }
+    }
+
+    async fn start(self: Arc<Self>, ex: ExecutorPtr) {
+        let me = Arc::downgrade(&self);
+        let node_ref = &self.node.upgrade().unwrap();
+
+        let mut tasks = vec![];
+        for method in ["insert_item", "set_item_field", "remove_item", "clear_items"] {
+            let method_sub = node_ref.subscribe_method_call(method).unwrap();
+            let me2 = me.clone();
+            tasks.push(ex.spawn(async move {
+                while Self::process_items_method(&me2, &method_sub, method).await {}
+            }));
+        }
+
+        let me2 = me.clone();
+        let cv = self.motion_cv.clone();
+        tasks.push(ex.spawn(async move {
+            loop {
+                cv.wait().await;
+                let Some(self_) = me2.upgrade() else {
+                    // Should not happen
+                    panic!("self destroyed before motion_task was stopped!");
+                };
+                let atom = &mut self_.render_api.make_guard(gfxtag!("ListView::motion_task"));
+                self_.handle_movement(atom).await;
+                cv.reset();
+            }
+        }));
+
+        let mut on_modify = OnModify::new(ex.clone(), self.node.clone(), me.clone());
+
+        async fn reload_view(self_: Arc<ListView>, batch: BatchGuardPtr) {
+            let atom = &mut batch.spawn();
+            self_.scrollview(self_.scroll.get(), atom);
+        }
+        on_modify.when_change(self.scroll.prop(), reload_view);
+
+        on_modify.when_change(self.is_visible.prop(), Self::redraw);
+        on_modify.when_change(self.rect.prop(), Self::redraw);
+        on_modify.when_change(self.z_index.prop(), Self::redraw);
+        on_modify.when_change(self.row_height.prop(), Self::redraw);
+        on_modify.when_change(self.scrollbar_width.prop(), Self::redraw);
+        on_modify.when_change(self.scrollbar_color.prop(), Self::redraw);
+
+        tasks.append(&mut on_modify.tasks);
+        *self.tasks.lock() = tasks;
+
+        for row in self.rows() {
+            let obj = get_ui_object_ptr(&row);
+            obj.start(ex.clone()).await;
+        }
+    }
+
+    fn stop(&self) {
+        self.tasks.lock().clear();
+        *self.parent_rect.lock() = None;
+        self.bound.lock().clear();
+        self.row_keys.lock().clear();
+        for row in self.rows() {
+            let obj = get_ui_object3(&row);
+            obj.stop();
+        }
+    }
+
+    async fn draw(
+        &self,
+        parent_rect: Rectangle,
+        trace_id: u32,
+        atom: &mut PropertyAtomicGuard,
+    ) -> Option<DrawUpdate> {
+        t!("ListView::draw({:?}) [trace_id={trace_id}]", self.node.upgrade().unwrap());
+        *self.parent_rect.lock() = Some(parent_rect);
+        self.get_draw_calls(parent_rect, trace_id, atom).await
+    }
+
+    async fn handle_mouse_btn_down(&self, btn: MouseButton, mouse_pos: Point) -> bool {
+        let rect = self.rect.get();
+        if !self.is_visible.get() || !rect.contains(mouse_pos) {
+            return false
+        }
+
+        // Stop any fling in progress
+        self.speed.store(0., Ordering::Relaxed);
+
+        // Clicking the scrollbar grabs the thumb, or jumps there when outside it
+        if btn == MouseButton::Left {
+            if let Some(thumb) = self.scrollbar_thumb(&rect) {
+                let point = mouse_pos - rect.pos();
+                if point.x >= thumb.x {
+                    let grab = if thumb.contains(point) { point.y - thumb.y } else { thumb.h / 2. };
+                    *self.scrollbar_grab.lock() = Some(grab);
+
+                    let atom = &mut self.render_api.make_guard(gfxtag!("ListView::mouse_btn_down"));
+                    self.drag_scrollbar(point.y, grab, atom);
+                    return true
+                }
+            }
+        }
+
+        let Some((row, pos)) = self.row_at(mouse_pos) else { return false };
+        let obj = get_ui_object3(&row);
+        obj.handle_mouse_btn_down(btn, pos).await
+    }
+
+    async fn handle_mouse_btn_up(&self, btn: MouseButton, mouse_pos: Point) -> bool {
+        if !self.is_visible.get() {
+            return false
+        }
+        if btn == MouseButton::Left && self.scrollbar_grab.lock().take().is_some() {
+            return true
+        }
+
+        let Some((row, pos)) = self.row_at(mouse_pos) else { return false };
+        let obj = get_ui_object3(&row);
+        obj.handle_mouse_btn_up(btn, pos).await
+    }
+
+    async fn handle_mouse_move(&self, mouse_pos: Point) -> bool {
+        *self.mouse_pos.lock() = mouse_pos;
+        if !self.is_visible.get() {
+            return false
+        }
+
+        let rect = self.rect.get();
+        let grab = self.scrollbar_grab.lock().clone();
+        if let Some(grab) = grab {
+            let atom = &mut self.render_api.make_guard(gfxtag!("ListView::mouse_move"));
+            self.drag_scrollbar(mouse_pos.y - rect.y, grab, atom);
+            return true
+        }
+
+        // All visible rows get it so they can clear any hover state
+        let point = mouse_pos - rect.pos();
+        let mut is_handled = false;
+        for (row, pos) in self.visible_rows() {
+            let obj = get_ui_object3(&row);
+            is_handled |= obj.handle_mouse_move(point - pos).await;
+        }
+        is_handled
+    }
+
+    async fn handle_mouse_wheel(&self, wheel_pos: Point) -> bool {
+        let mouse_pos = self.mouse_pos.lock().clone();
+        if !self.is_visible.get() || !self.rect.get().contains(mouse_pos) {
+            return false
+        }
+
+        let Some((row, pos)) = self.row_at(mouse_pos) else {
+            self.start_scroll(-wheel_pos.y);
+            return true
+        };
+        let obj = get_ui_object3(&row);
+        if !obj.handle_mouse_wheel(pos).await {
+            self.start_scroll(-wheel_pos.y);
+        }
+        true
+    }
+
+    async fn handle_touch(&self, phase: TouchPhase, id: u64, touch_pos: Point) -> bool {
+        // Ignore multi-touch
+        if id != 0 || !self.is_visible.get() {
+            return false
+        }
+
+        match phase {
+            TouchPhase::Started => {
+                if !self.rect.get().contains(touch_pos) {
+                    return false
+                }
+                self.touch_is_active.store(true, Ordering::Relaxed);
+                self.speed.store(0., Ordering::Relaxed);
+                *self.touch_info.lock() = Some(TouchInfo::new(self.scroll.get(), touch_pos));
+                true
+            }
+            TouchPhase::Moved => {
+                let scroll = {
+                    let mut touch_info = self.touch_info.lock();
+                    let Some(touch_info) = &mut *touch_info else { return false };
+                    touch_info.push_sample(touch_pos.y);
+
+                    if !touch_info.is_drag && touch_info.start_pos.dist(touch_pos) > TAP_DIST {
+                        touch_info.is_drag = true;
+                    }
+                    if !touch_info.is_drag {
+                        return true
+                    }
+                    touch_info.start_scroll - (touch_pos.y - touch_info.start_pos.y)
+                };
+
+                let atom = &mut self.render_api.make_guard(gfxtag!("ListView::handle_touch"));
+                self.scrollview(scroll, atom);
+                true
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.touch_is_active.store(false, Ordering::Relaxed);
+                let Some(touch_info) = self.touch_info.lock().take() else { return false };
+
+                if touch_info.is_drag {
+                    if let Some(speed) = touch_info.fling_speed(touch_pos.y) {
+                        self.start_scroll(-speed);
+                    }
+                    return true
+                }
+                if phase == TouchPhase::Cancelled {
+                    return true
+                }
+
+                // Short touch without moving, so pass it on to the row as a tap
+                let Some((row, pos)) = self.row_at(touch_info.start_pos) else { return true };
+                let obj = get_ui_object3(&row);
+                obj.handle_touch(TouchPhase::Started, id, pos).await;
+                obj.handle_touch(TouchPhase::Ended, id, pos).await;
+                true
+            }
+        }
+    }
+
+    fn set_i18n(&self, i18n_fish: &I18nBabelFish) {
+        for row in self.rows() {
+            let obj = get_ui_object3(&row);
+            obj.set_i18n(i18n_fish);
+        }
+    }
+}
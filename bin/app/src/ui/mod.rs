@@ -19,10 +19,11 @@
 use async_trait::async_trait;
 use futures::stream::{FuturesUnordered, StreamExt};
 use miniquad::{KeyCode, KeyMods, MouseButton, TouchPhase};
+use parking_lot::Mutex as SyncMutex;
 use std::sync::{Arc, Weak};
 
 use crate::{
-    gfx::{DrawCall, Point, Rectangle},
+    gfx::{DrawCall, ImeEvent, Point, Rectangle},
     prop::{BatchGuardPtr, ModifyAction, PropertyAtomicGuard, PropertyPtr, Role},
     scene::{Pimpl, SceneNode as SceneNode3, SceneNodePtr, SceneNodeWeak},
     util::i18n::I18nBabelFish,
@@ -37,6 +38,8 @@ mod edit;
 pub use edit::{BaseEdit, BaseEditPtr, BaseEditType};
 pub mod emoji_picker;
 pub use emoji_picker::{EmojiPicker, EmojiPickerPtr};
+mod flexbox;
+pub use flexbox::{FlexBox, FlexBoxPtr};
 mod gesture;
 pub use gesture::GesturePtr;
 mod image;
@@ -50,10 +53,17 @@ pub use vector_art::{
 };
 mod layer;
 pub use layer::{Layer, LayerPtr};
+mod listview;
+pub use listview::{ListView, ListViewPtr};
+mod palette;
+pub use palette::{PaletteInput, PaletteInputPtr};
+mod qrcode;
+pub use qrcode::{QrCode, QrCodePtr};
 mod shortcut;
 pub use shortcut::{Shortcut, ShortcutPtr};
 mod text;
 pub use text::{Text, TextPtr};
+pub mod tween;
 mod win;
 pub use win::{Window, WindowPtr};
 
@@ -85,6 +95,11 @@ pub trait UIObject: Sync {
     async fn handle_char(&self, _key: char, _mods: KeyMods, _repeat: bool) -> bool {
         false
     }
+    /// Returns the text cursor area in parent coordinates when the event was consumed,
+    /// which the window passes to the platform so it can place the candidate window.
+    async fn handle_ime(&self, _ev: &ImeEvent) -> Option<Rectangle> {
+        None
+    }
     async fn handle_key_down(&self, _key: KeyCode, _mods: KeyMods, _repeat: bool) -> bool {
         false
     }
@@ -115,6 +130,34 @@ pub struct DrawUpdate {
     pub draw_calls: Vec<(u64, DrawCall)>,
 }
 
+/// Draw calls from the last draw together with the property generation
+/// they were built from. Static widgets reuse them instead of rebuilding
+/// their meshes every time the scene gets drawn.
+#[derive(Default)]
+pub struct DrawCache {
+    inner: SyncMutex<Option<(u64, Vec<(u64, DrawCall)>)>>,
+}
+
+impl DrawCache {
+    /// Cached draw calls, if they were built at this generation
+    pub fn get(&self, generation: u64) -> Option<Vec<(u64, DrawCall)>> {
+        let inner = self.inner.lock();
+        let (gen, draw_calls) = inner.as_ref()?;
+        if *gen != generation {
+            return None
+        }
+        Some(draw_calls.clone())
+    }
+
+    pub fn set(&self, generation: u64, draw_calls: Vec<(u64, DrawCall)>) {
+        *self.inner.lock() = Some((generation, draw_calls));
+    }
+
+    pub fn invalidate(&self) {
+        *self.inner.lock() = None;
+    }
+}
+
 pub struct OnModify<T> {
     ex: ExecutorPtr,
     #[allow(dead_code)]
@@ -196,6 +239,9 @@ impl<T: Send + Sync + 'static> OnModify<T> {
 pub fn get_ui_object_ptr(node: &SceneNode3) -> Arc<dyn UIObject + Send> {
     match node.pimpl() {
         Pimpl::Layer(obj) => obj.clone(),
+        Pimpl::FlexBox(obj) => obj.clone(),
+        Pimpl::ListView(obj) => obj.clone(),
+        Pimpl::QrCode(obj) => obj.clone(),
         Pimpl::VectorArt(obj) => obj.clone(),
         Pimpl::Text(obj) => obj.clone(),
         Pimpl::Edit(obj) => obj.clone(),
@@ -206,12 +252,16 @@ pub fn get_ui_object_ptr(node: &SceneNode3) -> Arc<dyn UIObject + Send> {
         Pimpl::EmojiPicker(obj) => obj.clone(),
         Pimpl::Shortcut(obj) => obj.clone(),
         Pimpl::Gesture(obj) => obj.clone(),
+        Pimpl::PaletteInput(obj) => obj.clone(),
         _ => panic!("unhandled type for get_ui_object: {node:?}"),
     }
 }
 pub fn get_ui_object3<'a>(node: &'a SceneNode3) -> &'a dyn UIObject {
     match node.pimpl() {
         Pimpl::Layer(obj) => obj.as_ref(),
+        Pimpl::FlexBox(obj) => obj.as_ref(),
+        Pimpl::ListView(obj) => obj.as_ref(),
+        Pimpl::QrCode(obj) => obj.as_ref(),
         Pimpl::VectorArt(obj) => obj.as_ref(),
         Pimpl::Text(obj) => obj.as_ref(),
         Pimpl::Edit(obj) => obj.as_ref(),
@@ -222,6 +272,7 @@ pub fn get_ui_object3<'a>(node: &'a SceneNode3) -> &'a dyn UIObject {
         Pimpl::EmojiPicker(obj) => obj.as_ref(),
         Pimpl::Shortcut(obj) => obj.as_ref(),
         Pimpl::Gesture(obj) => obj.as_ref(),
+        Pimpl::PaletteInput(obj) => obj.as_ref(),
         _ => panic!("unhandled type for get_ui_object: {node:?}"),
     }
 }
@@ -0,0 +1,121 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use async_trait::async_trait;
+use miniquad::{KeyCode, KeyMods};
+use std::sync::Arc;
+
+use crate::{
+    gfx::{gfxtag, RenderApi},
+    prop::{PropertyStr, PropertyUint32, Role},
+    scene::{Pimpl, SceneNodeWeak},
+};
+
+use super::UIObject;
+
+macro_rules! d { ($($arg:tt)*) => { debug!(target: "ui::palette", $($arg)*); } }
+macro_rules! t { ($($arg:tt)*) => { trace!(target: "ui::palette", $($arg)*); } }
+
+pub type PaletteInputPtr = Arc<PaletteInput>;
+
+/// Keyboard input of the command palette.
+///
+/// Typed chars are accumulated in the `query` property, while navigation keys
+/// are turned into signals. It swallows every key it sees, so it should only
+/// live inside a layer which is visible while the palette is open.
+pub struct PaletteInput {
+    node: SceneNodeWeak,
+    render_api: RenderApi,
+    query: PropertyStr,
+    priority: PropertyUint32,
+}
+
+impl PaletteInput {
+    pub async fn new(node: SceneNodeWeak, render_api: RenderApi) -> Pimpl {
+        t!("PaletteInput::new()");
+
+        let node_ref = &node.upgrade().unwrap();
+        let query = PropertyStr::wrap(node_ref, Role::Internal, "query", 0).unwrap();
+        let priority = PropertyUint32::wrap(node_ref, Role::Internal, "priority", 0).unwrap();
+
+        let self_ = Arc::new(Self { node, render_api, query, priority });
+
+        Pimpl::PaletteInput(self_)
+    }
+
+    async fn trigger(&self, sig_name: &str) {
+        let node = self.node.upgrade().unwrap();
+        d!("Palette {sig_name}: {node:?}");
+        node.trigger(sig_name, vec![]).await.unwrap();
+    }
+}
+
+#[async_trait]
+impl UIObject for PaletteInput {
+    fn priority(&self) -> u32 {
+        self.priority.get()
+    }
+
+    async fn handle_char(&self, key: char, mods: KeyMods, _repeat: bool) -> bool {
+        t!("handle_char({key:?}, {mods:?})");
+        // Let shortcuts through
+        if mods.ctrl || mods.alt || mods.logo {
+            return false
+        }
+        if key.is_control() {
+            return true
+        }
+
+        let mut query = self.query.get();
+        query.push(key);
+        {
+            let atom = &mut self.render_api.make_guard(gfxtag!("PaletteInput::handle_char"));
+            self.query.set(atom, query);
+        }
+        self.trigger("query_changed").await;
+        true
+    }
+
+    async fn handle_key_down(&self, key: KeyCode, mods: KeyMods, _repeat: bool) -> bool {
+        t!("handle_key_down({key:?}, {mods:?})");
+        if mods.ctrl || mods.alt || mods.logo {
+            return false
+        }
+
+        match key {
+            KeyCode::Backspace => {
+                let mut query = self.query.get();
+                if query.pop().is_some() {
+                    {
+                        let atom =
+                            &mut self.render_api.make_guard(gfxtag!("PaletteInput::backspace"));
+                        self.query.set(atom, query);
+                    }
+                    self.trigger("query_changed").await;
+                }
+            }
+            KeyCode::Up => self.trigger("select_prev").await,
+            KeyCode::Down | KeyCode::Tab => self.trigger("select_next").await,
+            KeyCode::Enter | KeyCode::KpEnter => self.trigger("activate").await,
+            KeyCode::Escape => self.trigger("close").await,
+            // Everything else arrives through handle_char()
+            _ => return false,
+        }
+        true
+    }
+}
@@ -0,0 +1,240 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Draws the `data` property as a QR code, centered in `rect` as the
+//! largest square which fits. Empty data draws nothing.
+
+use async_trait::async_trait;
+use parking_lot::Mutex as SyncMutex;
+use rand::{rngs::OsRng, Rng};
+use std::sync::Arc;
+
+use crate::{
+    gfx::{gfxtag, DrawCall, DrawInstruction, Rectangle, RenderApi},
+    mesh::MeshBuilder,
+    prop::{
+        BatchGuardPtr, PropertyAtomicGuard, PropertyBool, PropertyColor, PropertyRect, PropertyStr,
+        PropertyUint32, Role,
+    },
+    scene::{Pimpl, SceneNodeWeak},
+    util::{qr, unixtime},
+    ExecutorPtr,
+};
+
+use super::{DrawCache, DrawTrace, DrawUpdate, OnModify, UIObject};
+
+macro_rules! t { ($($arg:tt)*) => { trace!(target: "ui::qrcode", $($arg)*); } }
+
+/// Width of the blank border in modules, as required by the spec
+const QUIET_ZONE: usize = 4;
+
+pub type QrCodePtr = Arc<QrCode>;
+
+pub struct QrCode {
+    node: SceneNodeWeak,
+    render_api: RenderApi,
+    tasks: SyncMutex<Vec<smol::Task<()>>>,
+
+    dc_key: u64,
+    draw_cache: DrawCache,
+
+    is_visible: PropertyBool,
+    rect: PropertyRect,
+    data: PropertyStr,
+    fg_color: PropertyColor,
+    bg_color: PropertyColor,
+    z_index: PropertyUint32,
+    priority: PropertyUint32,
+
+    parent_rect: SyncMutex<Option<Rectangle>>,
+}
+
+impl QrCode {
+    pub async fn new(node: SceneNodeWeak, render_api: RenderApi) -> Pimpl {
+        t!("QrCode::new()");
+
+        let node_ref = &node.upgrade().unwrap();
+        let is_visible = PropertyBool::wrap(node_ref, Role::Internal, "is_visible", 0).unwrap();
+        let rect = PropertyRect::wrap(node_ref, Role::Internal, "rect").unwrap();
+        let data = PropertyStr::wrap(node_ref, Role::Internal, "data", 0).unwrap();
+        let fg_color = PropertyColor::wrap(node_ref, Role::Internal, "fg_color").unwrap();
+        let bg_color = PropertyColor::wrap(node_ref, Role::Internal, "bg_color").unwrap();
+        let z_index = PropertyUint32::wrap(node_ref, Role::Internal, "z_index", 0).unwrap();
+        let priority = PropertyUint32::wrap(node_ref, Role::Internal, "priority", 0).unwrap();
+
+        let self_ = Arc::new(Self {
+            node,
+            render_api,
+            tasks: SyncMutex::new(vec![]),
+
+            dc_key: OsRng.gen(),
+            draw_cache: DrawCache::default(),
+
+            is_visible,
+            rect,
+            data,
+            fg_color,
+            bg_color,
+            z_index,
+            priority,
+
+            parent_rect: SyncMutex::new(None),
+        });
+
+        Pimpl::QrCode(self_)
+    }
+
+    fn node_path(&self) -> String {
+        format!("{:?}", self.node.upgrade().unwrap())
+    }
+
+    async fn redraw(self: Arc<Self>, batch: BatchGuardPtr) {
+        let trace = rand::random();
+        let timest = unixtime();
+        t!("QrCode::redraw({}) [trace={trace}]", self.node_path());
+        let Some(parent_rect) = self.parent_rect.lock().clone() else { return };
+
+        let atom = &mut batch.spawn();
+        let Some(draw_update) = self.get_draw_calls(atom, parent_rect, trace).await else {
+            error!(target: "ui::qrcode", "QrCode failed to draw [trace={trace}]");
+            return
+        };
+        self.render_api.replace_draw_calls(batch.id, timest, draw_update.draw_calls);
+    }
+
+    fn get_draw_instrs(&self) -> Vec<DrawInstruction> {
+        if !self.is_visible.get() {
+            t!("Skipping draw for invisible {}", self.node_path());
+            return vec![]
+        }
+
+        let data = self.data.get();
+        if data.is_empty() {
+            return vec![]
+        }
+        let Some(matrix) = qr::encode(&data) else { return vec![] };
+
+        let rect = self.rect.get();
+        let size = rect.w.min(rect.h);
+        let module = size / (matrix.width + 2 * QUIET_ZONE) as f32;
+        let x0 = (rect.w - size) / 2.;
+        let y0 = (rect.h - size) / 2.;
+
+        let mut mesh = MeshBuilder::new(gfxtag!("qrcode"));
+        mesh.draw_filled_box(&Rectangle::new(x0, y0, size, size), self.bg_color.get());
+
+        // Merge horizontal runs of dark modules to keep the vertex count down
+        let fg_color = self.fg_color.get();
+        for y in 0..matrix.width {
+            let mut x = 0;
+            while x < matrix.width {
+                if !matrix.is_dark(x, y) {
+                    x += 1;
+                    continue
+                }
+                let start = x;
+                while x < matrix.width && matrix.is_dark(x, y) {
+                    x += 1;
+                }
+                let run = Rectangle::new(
+                    x0 + (QUIET_ZONE + start) as f32 * module,
+                    y0 + (QUIET_ZONE + y) as f32 * module,
+                    (x - start) as f32 * module,
+                    module,
+                );
+                mesh.draw_filled_box(&run, fg_color);
+            }
+        }
+
+        let mesh = mesh.alloc(&self.render_api).draw_untextured();
+        vec![DrawInstruction::Move(rect.pos()), DrawInstruction::Draw(mesh)]
+    }
+
+    async fn get_draw_calls(
+        &self,
+        atom: &mut PropertyAtomicGuard,
+        parent_rect: Rectangle,
+        trace: DrawTrace,
+    ) -> Option<DrawUpdate> {
+        if let Err(e) = self.rect.eval(atom, &parent_rect) {
+            warn!(target: "ui::qrcode", "Rect eval failure: {e} [trace={trace}]");
+            return None
+        }
+
+        let generation = self.node.upgrade()?.generation();
+        if let Some(draw_calls) = self.draw_cache.get(generation) {
+            t!("Reusing cached draw calls [trace={trace}]");
+            return Some(DrawUpdate { key: self.dc_key, draw_calls })
+        }
+
+        let instrs = self.get_draw_instrs();
+        let draw_calls =
+            vec![(self.dc_key, DrawCall::new(instrs, vec![], self.z_index.get(), "qrcode"))];
+        self.draw_cache.set(generation, draw_calls.clone());
+        Some(DrawUpdate { key: self.dc_key, draw_calls })
+    }
+}
+
+#[async_trait]
+impl UIObject for QrCode {
+    fn priority(&self) -> u32 {
+        self.priority.get()
+    }
+
+    async fn start(self: Arc<Self>, ex: ExecutorPtr) {
+        let me = Arc::downgrade(&self);
+
+        let mut on_modify = OnModify::new(ex, self.node.clone(), me.clone());
+        on_modify.when_change(self.is_visible.prop(), Self::redraw);
+        on_modify.when_change(self.rect.prop(), Self::redraw);
+        on_modify.when_change(self.data.prop(), Self::redraw);
+        on_modify.when_change(self.fg_color.prop(), Self::redraw);
+        on_modify.when_change(self.bg_color.prop(), Self::redraw);
+        on_modify.when_change(self.z_index.prop(), Self::redraw);
+
+        *self.tasks.lock() = on_modify.tasks;
+    }
+
+    fn stop(&self) {
+        self.tasks.lock().clear();
+        *self.parent_rect.lock() = None;
+        self.draw_cache.invalidate();
+    }
+
+    async fn draw(
+        &self,
+        parent_rect: Rectangle,
+        trace: DrawTrace,
+        atom: &mut PropertyAtomicGuard,
+    ) -> Option<DrawUpdate> {
+        t!("QrCode::draw({}) [trace={trace}]", self.node_path());
+        *self.parent_rect.lock() = Some(parent_rect);
+        self.get_draw_calls(atom, parent_rect, trace).await
+    }
+}
+
+impl Drop for QrCode {
+    fn drop(&mut self) {
+        let atom = self.render_api.make_guard(gfxtag!("QrCode::drop"));
+        self.render_api.replace_draw_calls(
+            atom.batch_id,
+            unixtime(),
+            vec![(self.dc_key, Default::default())],
+        );
+    }
+}
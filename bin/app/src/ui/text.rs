@@ -33,7 +33,7 @@ use crate::{
     ExecutorPtr,
 };
 
-use super::{DrawTrace, DrawUpdate, OnModify, UIObject};
+use super::{DrawCache, DrawTrace, DrawUpdate, OnModify, UIObject};
 
 macro_rules! t { ($($arg:tt)*) => { trace!(target: "ui::text", $($arg)*); } }
 
@@ -46,6 +46,7 @@ pub struct Text {
     tasks: SyncMutex<Vec<smol::Task<()>>>,
 
     dc_key: u64,
+    draw_cache: DrawCache,
 
     rect: PropertyRect,
     z_index: PropertyUint32,
@@ -87,6 +88,7 @@ impl Text {
             i18n_fish,
             tasks: SyncMutex::new(vec![]),
             dc_key: OsRng.gen(),
+            draw_cache: DrawCache::default(),
 
             rect,
             z_index,
@@ -159,16 +161,20 @@ impl Text {
         self.rect.eval(atom, &parent_rect).ok()?;
         let rect = self.rect.get();
 
+        // window_scale lives on the window node so isn't covered by ours
+        let generation =
+            self.node.upgrade()?.generation().wrapping_add(self.window_scale.prop().generation());
+        if let Some(draw_calls) = self.draw_cache.get(generation) {
+            return Some(DrawUpdate { key: self.dc_key, draw_calls })
+        }
+
         let mut instrs = vec![DrawInstruction::Move(rect.pos())];
         instrs.append(&mut self.regen_mesh().await);
 
-        Some(DrawUpdate {
-            key: self.dc_key,
-            draw_calls: vec![(
-                self.dc_key,
-                DrawCall::new(instrs, vec![], self.z_index.get(), "text"),
-            )],
-        })
+        let draw_calls =
+            vec![(self.dc_key, DrawCall::new(instrs, vec![], self.z_index.get(), "text"))];
+        self.draw_cache.set(generation, draw_calls.clone());
+        Some(DrawUpdate { key: self.dc_key, draw_calls })
     }
 }
 
@@ -195,6 +201,7 @@ impl UIObject for Text {
     fn stop(&self) {
         self.tasks.lock().clear();
         *self.parent_rect.lock() = None;
+        self.draw_cache.invalidate();
     }
 
     async fn draw(
@@ -210,6 +217,7 @@ impl UIObject for Text {
 
     fn set_i18n(&self, i18n_fish: &I18nBabelFish) {
         self.i18n_fish.set(i18n_fish);
+        self.draw_cache.invalidate();
     }
 }
 
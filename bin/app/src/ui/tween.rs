@@ -0,0 +1,192 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Tweening of scene node properties.
+//!
+//! Any `Float32` property element can be animated, such as the x/y of a `rect`,
+//! the alpha channel of a color or the window `scale` setting. Tweens are stepped
+//! once per frame by the window, which subscribes to the render loop's frame events.
+
+use parking_lot::Mutex as SyncMutex;
+use std::{
+    sync::{Arc, LazyLock},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    error::Result,
+    prop::{PropertyAtomicGuard, PropertyPtr, Role},
+};
+
+macro_rules! t { ($($arg:tt)*) => { trace!(target: "ui::tween", $($arg)*); } }
+
+pub static TWEENER: LazyLock<SyncMutex<Tweener>> = LazyLock::new(|| SyncMutex::new(Tweener::new()));
+
+pub type TweenId = u32;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    /// Overshoots the target slightly before settling
+    Back,
+}
+
+impl Easing {
+    pub fn from_str(name: &str) -> Option<Self> {
+        match name {
+            "linear" => Some(Self::Linear),
+            "ease_in" => Some(Self::EaseIn),
+            "ease_out" => Some(Self::EaseOut),
+            "ease_in_out" => Some(Self::EaseInOut),
+            "back" => Some(Self::Back),
+            _ => None,
+        }
+    }
+
+    /// Maps progress `t` in [0, 1] to the eased progress
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0., 1.);
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t * t,
+            Self::EaseOut => 1. - (1. - t).powi(3),
+            Self::EaseInOut => {
+                if t < 0.5 {
+                    4. * t * t * t
+                } else {
+                    1. - (-2. * t + 2.).powi(3) / 2.
+                }
+            }
+            Self::Back => {
+                const C1: f32 = 1.70158;
+                const C3: f32 = C1 + 1.;
+                1. + C3 * (t - 1.).powi(3) + C1 * (t - 1.).powi(2)
+            }
+        }
+    }
+}
+
+struct Tween {
+    id: TweenId,
+    prop: PropertyPtr,
+    i: usize,
+    from: f32,
+    to: f32,
+    start: Instant,
+    duration: Duration,
+    easing: Easing,
+}
+
+impl Tween {
+    /// Returns the value at `now` and whether the tween has finished
+    fn value_at(&self, now: Instant) -> (f32, bool) {
+        let elapsed = now.saturating_duration_since(self.start);
+        if elapsed >= self.duration {
+            return (self.to, true)
+        }
+        let t = elapsed.as_secs_f32() / self.duration.as_secs_f32();
+        (self.from + (self.to - self.from) * self.easing.apply(t), false)
+    }
+}
+
+pub struct Tweener {
+    tweens: Vec<Tween>,
+    next_id: TweenId,
+}
+
+impl Tweener {
+    fn new() -> Self {
+        Self { tweens: vec![], next_id: 0 }
+    }
+
+    /// Animate `prop[i]` from its current value to `to`.
+    /// Replaces any tween already running on the same element, so retargeting
+    /// mid-animation continues smoothly from wherever the value currently is.
+    pub fn animate(
+        &mut self,
+        prop: PropertyPtr,
+        i: usize,
+        to: f32,
+        duration: Duration,
+        easing: Easing,
+    ) -> Result<TweenId> {
+        let from = prop.get_f32(i)?;
+        self.cancel_prop(&prop, Some(i));
+
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        t!("animate({}[{i}], {from} -> {to}, {duration:?}, {easing:?}) = {id}", prop.name);
+
+        let start = Instant::now();
+        self.tweens.push(Tween { id, prop, i, from, to, start, duration, easing });
+        Ok(id)
+    }
+
+    /// Stops a tween, leaving the property at its current value
+    pub fn cancel(&mut self, id: TweenId) -> bool {
+        let len = self.tweens.len();
+        self.tweens.retain(|tween| tween.id != id);
+        self.tweens.len() != len
+    }
+
+    /// Stops tweens on `prop`, either on element `i` or on all of them
+    pub fn cancel_prop(&mut self, prop: &PropertyPtr, i: Option<usize>) {
+        self.tweens
+            .retain(|tween| !Arc::ptr_eq(&tween.prop, prop) || i.is_some_and(|i| i != tween.i));
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.tweens.is_empty()
+    }
+
+    /// Advance all tweens to the current time and drop those that have finished
+    pub fn tick(&mut self, atom: &mut PropertyAtomicGuard) {
+        let now = Instant::now();
+        self.tweens.retain(|tween| {
+            let (val, is_done) = tween.value_at(now);
+            if let Err(err) = tween.prop.set_f32(atom, Role::App, tween.i, val) {
+                error!(target: "ui::tween", "Tween {} on {} failed: {err}", tween.id, tween.prop.name);
+                return false
+            }
+            !is_done
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn easing_endpoints() {
+        for easing in
+            [Easing::Linear, Easing::EaseIn, Easing::EaseOut, Easing::EaseInOut, Easing::Back]
+        {
+            assert!(easing.apply(0.).abs() < 1e-5, "{easing:?}");
+            assert!((easing.apply(1.) - 1.).abs() < 1e-5, "{easing:?}");
+        }
+        assert!((Easing::EaseInOut.apply(0.5) - 0.5).abs() < 1e-5);
+        assert!(Easing::EaseIn.apply(0.5) < 0.5);
+        assert!(Easing::EaseOut.apply(0.5) > 0.5);
+        // Overshoots the target near the end
+        assert!(Easing::Back.apply(0.9) > 1.);
+    }
+}
@@ -22,14 +22,14 @@ use rand::{rngs::OsRng, Rng};
 use std::sync::Arc;
 
 use crate::{
-    gfx::{gfxtag, DrawCall, DrawInstruction, DrawMesh, Rectangle, RenderApi},
+    gfx::{gfxtag, DrawCall, DrawInstruction, DrawMesh, ManagedTexturePtr, Rectangle, RenderApi},
     prop::{BatchGuardPtr, PropertyAtomicGuard, PropertyBool, PropertyRect, PropertyUint32, Role},
     scene::{Pimpl, SceneNodeWeak},
     util::unixtime,
     ExecutorPtr,
 };
 
-use super::{DrawTrace, DrawUpdate, OnModify, UIObject};
+use super::{DrawCache, DrawTrace, DrawUpdate, OnModify, UIObject};
 
 pub mod shape;
 use shape::VectorShape;
@@ -44,7 +44,9 @@ pub struct VectorArt {
     tasks: SyncMutex<Vec<smol::Task<()>>>,
 
     shape: VectorShape,
+    texture: Option<ManagedTexturePtr>,
     dc_key: u64,
+    draw_cache: DrawCache,
 
     is_visible: PropertyBool,
     rect: PropertyRect,
@@ -56,6 +58,26 @@ pub struct VectorArt {
 
 impl VectorArt {
     pub async fn new(node: SceneNodeWeak, shape: VectorShape, render_api: RenderApi) -> Pimpl {
+        Self::create(node, shape, None, render_api)
+    }
+
+    /// For shapes with nine-patches, the other parts of the shape should
+    /// map to an opaque white region of the texture.
+    pub async fn new_textured(
+        node: SceneNodeWeak,
+        shape: VectorShape,
+        texture: ManagedTexturePtr,
+        render_api: RenderApi,
+    ) -> Pimpl {
+        Self::create(node, shape, Some(texture), render_api)
+    }
+
+    fn create(
+        node: SceneNodeWeak,
+        shape: VectorShape,
+        texture: Option<ManagedTexturePtr>,
+        render_api: RenderApi,
+    ) -> Pimpl {
         t!("VectorArt::new()");
 
         let node_ref = &node.upgrade().unwrap();
@@ -70,7 +92,9 @@ impl VectorArt {
             tasks: SyncMutex::new(vec![]),
 
             shape,
+            texture,
             dc_key: OsRng.gen(),
+            draw_cache: DrawCache::default(),
 
             is_visible,
             rect,
@@ -115,7 +139,8 @@ impl VectorArt {
         //debug!(target: "ui::vector_art", "vec_draw_instrs {verts:?} | {indices:?} | {num_elements}");
         let vertex_buffer = self.render_api.new_vertex_buffer(verts, gfxtag!("vectorart"));
         let index_buffer = self.render_api.new_index_buffer(indices, gfxtag!("vectorart"));
-        let mesh = DrawMesh { vertex_buffer, index_buffer, texture: None, num_elements };
+        let texture = self.texture.clone();
+        let mesh = DrawMesh { vertex_buffer, index_buffer, texture, num_elements };
 
         vec![DrawInstruction::Move(rect.pos()), DrawInstruction::Draw(mesh)]
     }
@@ -130,14 +155,18 @@ impl VectorArt {
             warn!(target: "ui::vector_art", "Rect eval failure: {e} [trace={trace}]");
             return None
         }
+
+        let generation = self.node.upgrade()?.generation();
+        if let Some(draw_calls) = self.draw_cache.get(generation) {
+            t!("Reusing cached draw calls [trace={trace}]");
+            return Some(DrawUpdate { key: self.dc_key, draw_calls })
+        }
+
         let instrs = self.get_draw_instrs();
-        Some(DrawUpdate {
-            key: self.dc_key,
-            draw_calls: vec![(
-                self.dc_key,
-                DrawCall::new(instrs, vec![], self.z_index.get(), "vecart"),
-            )],
-        })
+        let draw_calls =
+            vec![(self.dc_key, DrawCall::new(instrs, vec![], self.z_index.get(), "vecart"))];
+        self.draw_cache.set(generation, draw_calls.clone());
+        Some(DrawUpdate { key: self.dc_key, draw_calls })
     }
 }
 
@@ -161,6 +190,7 @@ impl UIObject for VectorArt {
     fn stop(&self) {
         self.tasks.lock().clear();
         *self.parent_rect.lock() = None;
+        self.draw_cache.invalidate();
     }
 
     async fn draw(
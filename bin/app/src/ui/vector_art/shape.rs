@@ -19,8 +19,8 @@
 use crate::{
     error::Result,
     expr::{Op, SExprCode, SExprMachine, SExprVal},
-    gfx::Vertex,
-    mesh::Color,
+    gfx::{Rectangle, Vertex},
+    mesh::{circle_outline, convex_aa_indices, rounded_box_outline, Color, NinePatch, AA_FRINGE},
 };
 
 #[derive(Debug)]
@@ -28,15 +28,21 @@ pub struct ShapeVertex {
     x: SExprCode,
     y: SExprCode,
     color: Color,
+    uv: [f32; 2],
 }
 
 impl ShapeVertex {
     pub fn new(x: SExprCode, y: SExprCode, color: Color) -> Self {
-        Self { x, y, color }
+        Self { x, y, color, uv: [0., 0.] }
     }
 
     pub fn from_xy(x: f32, y: f32, color: Color) -> Self {
-        Self { x: vec![Op::ConstFloat32(x)], y: vec![Op::ConstFloat32(y)], color }
+        Self::new(vec![Op::ConstFloat32(x)], vec![Op::ConstFloat32(y)], color)
+    }
+
+    pub fn with_uv(mut self, uv: [f32; 2]) -> Self {
+        self.uv = uv;
+        self
     }
 
     pub fn scale(mut self, scale: f32) -> Self {
@@ -46,7 +52,7 @@ impl ShapeVertex {
         x.push(Op::Mul((Box::new(Op::ConstFloat32(scale)), Box::new(last_x))));
         let mut y = self.y;
         y.push(Op::Mul((Box::new(Op::ConstFloat32(scale)), Box::new(last_y))));
-        Self { x, y, color: self.color }
+        Self { x, y, color: self.color, uv: self.uv }
     }
 }
 
@@ -76,7 +82,7 @@ impl VectorShape {
                 pos[i] = machine.call()?.as_f32()?;
             }
 
-            let vert = Vertex { pos, color: shape_vert.color.clone(), uv: [0., 0.] };
+            let vert = Vertex { pos, color: shape_vert.color.clone(), uv: shape_vert.uv };
             verts.push(vert);
         }
         Ok(verts)
@@ -111,11 +117,25 @@ impl VectorShape {
         y2: SExprCode,
         color: [Color; 4],
     ) {
+        self.add_textured_box(x1, y1, x2, y2, color, &Rectangle::zero())
+    }
+
+    fn add_textured_box(
+        &mut self,
+        x1: SExprCode,
+        y1: SExprCode,
+        x2: SExprCode,
+        y2: SExprCode,
+        color: [Color; 4],
+        uv: &Rectangle,
+    ) {
+        let (u1, v1) = uv.pos().unpack();
+        let (u2, v2) = uv.corner().unpack();
         let mut verts = vec![
-            ShapeVertex::new(x1.clone(), y1.clone(), color[0]),
-            ShapeVertex::new(x2.clone(), y1.clone(), color[1]),
-            ShapeVertex::new(x1.clone(), y2.clone(), color[3]),
-            ShapeVertex::new(x2, y2, color[2]),
+            ShapeVertex::new(x1.clone(), y1.clone(), color[0]).with_uv([u1, v1]),
+            ShapeVertex::new(x2.clone(), y1.clone(), color[1]).with_uv([u2, v1]),
+            ShapeVertex::new(x1.clone(), y2.clone(), color[3]).with_uv([u1, v2]),
+            ShapeVertex::new(x2, y2, color[2]).with_uv([u2, v2]),
         ];
         let i = self.verts.len() as u16;
         let mut indices = vec![i + 0, i + 2, i + 1, i + 1, i + 2, i + 3];
@@ -173,6 +193,110 @@ impl VectorShape {
         );
     }
 
+    /// Fill a convex outline of `(x, y, outward normal)` with a faded edge
+    fn add_convex_aa(&mut self, outline: Vec<(SExprCode, SExprCode, [f32; 2])>, color: Color) {
+        let transparent = [color[0], color[1], color[2], 0.];
+        let fringe = AA_FRINGE / 2.;
+
+        let i = self.verts.len() as u16;
+        let mut indices = convex_aa_indices(outline.len());
+        for index in &mut indices {
+            *index += i;
+        }
+
+        for (x, y, [nx, ny]) in outline {
+            let inner_x = Self::sexpr_add(x.clone(), -nx * fringe).unwrap();
+            let inner_y = Self::sexpr_add(y.clone(), -ny * fringe).unwrap();
+            self.verts.push(ShapeVertex::new(inner_x, inner_y, color));
+
+            let outer_x = Self::sexpr_add(x, nx * fringe).unwrap();
+            let outer_y = Self::sexpr_add(y, ny * fringe).unwrap();
+            self.verts.push(ShapeVertex::new(outer_x, outer_y, transparent));
+        }
+        self.indices.append(&mut indices);
+    }
+
+    /// The radius isn't clamped since the box size is only known when drawing
+    pub fn add_rounded_box(
+        &mut self,
+        x1: SExprCode,
+        y1: SExprCode,
+        x2: SExprCode,
+        y2: SExprCode,
+        radius: f32,
+        color: Color,
+    ) {
+        // Corners go clockwise from top-left
+        let corners = [(&x1, &y1), (&x2, &y1), (&x2, &y2), (&x1, &y2)];
+        let outline = rounded_box_outline(radius.max(0.))
+            .into_iter()
+            .map(|(corner, off, normal)| {
+                let (x, y) = corners[corner];
+                (
+                    Self::sexpr_add(x.clone(), off.x).unwrap(),
+                    Self::sexpr_add(y.clone(), off.y).unwrap(),
+                    normal.as_arr(),
+                )
+            })
+            .collect();
+        self.add_convex_aa(outline, color);
+    }
+
+    pub fn add_circle(&mut self, x: SExprCode, y: SExprCode, radius: f32, color: Color) {
+        let outline = circle_outline(radius)
+            .into_iter()
+            .map(|(off, normal)| {
+                (
+                    Self::sexpr_add(x.clone(), off.x).unwrap(),
+                    Self::sexpr_add(y.clone(), off.y).unwrap(),
+                    normal.as_arr(),
+                )
+            })
+            .collect();
+        self.add_convex_aa(outline, color);
+    }
+
+    /// The texture is given to `VectorArt::new_textured()`
+    pub fn add_nine_patch(
+        &mut self,
+        x1: SExprCode,
+        y1: SExprCode,
+        x2: SExprCode,
+        y2: SExprCode,
+        patch: &NinePatch,
+        color: Color,
+    ) {
+        let [top, right, bottom, left] = patch.border;
+        let xs = [
+            x1.clone(),
+            Self::sexpr_add(x1, left).unwrap(),
+            Self::sexpr_add(x2.clone(), -right).unwrap(),
+            x2,
+        ];
+        let ys = [
+            y1.clone(),
+            Self::sexpr_add(y1, top).unwrap(),
+            Self::sexpr_add(y2.clone(), -bottom).unwrap(),
+            y2,
+        ];
+        let (us, vs) = patch.uvs();
+
+        for row in 0..3 {
+            for col in 0..3 {
+                let patch_uv =
+                    Rectangle::new(us[col], vs[row], us[col + 1] - us[col], vs[row + 1] - vs[row]);
+                self.add_textured_box(
+                    xs[col].clone(),
+                    ys[row].clone(),
+                    xs[col + 1].clone(),
+                    ys[row + 1].clone(),
+                    [color; 4],
+                    &patch_uv,
+                );
+            }
+        }
+    }
+
     pub fn scaled(self, scale: f32) -> Self {
         Self {
             verts: self.verts.into_iter().map(|v| v.scale(scale)).collect(),
@@ -16,27 +16,42 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use darkfi::system::msleep;
+use darkfi_serial::Decodable;
 use miniquad::{KeyCode, KeyMods, MouseButton, TouchPhase};
 use parking_lot::Mutex as SyncMutex;
-use std::sync::{Arc, Weak};
+use rand::{rngs::OsRng, Rng};
+use std::{
+    io::Cursor,
+    sync::{Arc, Weak},
+    time::Duration,
+};
 
 use crate::{
     app::locale::read_locale_ftl,
     gfx::{
-        gfxtag, DrawCall, DrawInstruction, GraphicsEventCharSub, GraphicsEventKeyDownSub,
-        GraphicsEventKeyUpSub, GraphicsEventMouseButtonDownSub, GraphicsEventMouseButtonUpSub,
-        GraphicsEventMouseMoveSub, GraphicsEventMouseWheelSub, GraphicsEventPublisherPtr,
-        GraphicsEventTouchSub, Point, Rectangle, RenderApi,
+        gfxtag, DrawCall, DrawInstruction, GraphicsEventCharSub, GraphicsEventFrameSub,
+        GraphicsEventImeSub, GraphicsEventKeyDownSub, GraphicsEventKeyUpSub,
+        GraphicsEventMouseButtonDownSub, GraphicsEventMouseButtonUpSub, GraphicsEventMouseMoveSub,
+        GraphicsEventMouseWheelSub, GraphicsEventPublisherPtr, GraphicsEventTouchSub, ImeEvent,
+        Point, Rectangle, RenderApi,
     },
+    mesh::{Color, MeshBuilder, COLOR_GREEN},
     prop::{
-        BatchGuardPtr, PropertyAtomicGuard, PropertyDimension, PropertyFloat32, PropertyStr, Role,
+        BatchGuardPtr, PropertyAtomicGuard, PropertyBool, PropertyDimension, PropertyFloat32,
+        PropertyPtr, PropertyStr, Role,
     },
-    scene::{Pimpl, SceneNodePtr, SceneNodeWeak},
+    scene::{MethodCallSub, Pimpl, SceneNodePtr, SceneNodeWeak},
+    text2::{atlas::GLYPH_ATLAS, render_layout, TEXT_CTX},
     util::{i18n::I18nBabelFish, unixtime},
     ExecutorPtr,
 };
 
-use super::{get_children_ordered, get_ui_object3, get_ui_object_ptr, OnModify};
+use super::{
+    get_children_ordered, get_ui_object3, get_ui_object_ptr,
+    tween::{Easing, TWEENER},
+    OnModify,
+};
 
 macro_rules! i { ($($arg:tt)*) => { info!(target: "ui::window", $($arg)*); } }
 macro_rules! d { ($($arg:tt)*) => { debug!(target: "ui::window", $($arg)*); } }
@@ -48,6 +63,9 @@ const EMULATE_TOUCH: bool = true;
 #[cfg(not(feature = "emulate-android"))]
 const EMULATE_TOUCH: bool = false;
 
+/// Fill for the unsafe margins when `debug_safe_area` is enabled
+const DEBUG_INSET_COLOR: Color = [1., 0., 0., 0.3];
+
 pub type WindowPtr = Arc<Window>;
 
 pub struct Window {
@@ -58,7 +76,16 @@ pub struct Window {
 
     locale: PropertyStr,
     screen_size: PropertyDimension,
+    safe_area_insets: PropertyPtr,
+    ime_cursor_area: PropertyPtr,
     scale: PropertyFloat32,
+    debug_safe_area: PropertyBool,
+    debug_glyph_atlas: PropertyBool,
+
+    /// Children are drawn inside this view, which excludes the safe area insets
+    content_dc_key: u64,
+    debug_dc_key: u64,
+    atlas_stats_dc_key: u64,
 }
 
 impl Window {
@@ -73,6 +100,8 @@ impl Window {
         let node_ref = &node.upgrade().unwrap();
         let locale = PropertyStr::wrap(node_ref, Role::Internal, "locale", 0).unwrap();
         let screen_size = PropertyDimension::wrap(node_ref, Role::Internal, "screen_size").unwrap();
+        let safe_area_insets = node_ref.get_property("safe_area_insets").unwrap();
+        let ime_cursor_area = node_ref.get_property("ime_cursor_area").unwrap();
         let scale = PropertyFloat32::wrap(
             &setting_root.lookup_node("/scale").unwrap(),
             Role::Internal,
@@ -80,6 +109,20 @@ impl Window {
             0,
         )
         .unwrap();
+        let debug_safe_area = PropertyBool::wrap(
+            &setting_root.lookup_node("/debug_safe_area").unwrap(),
+            Role::Internal,
+            "value",
+            0,
+        )
+        .unwrap();
+        let debug_glyph_atlas = PropertyBool::wrap(
+            &setting_root.lookup_node("/debug_glyph_atlas").unwrap(),
+            Role::Internal,
+            "value",
+            0,
+        )
+        .unwrap();
 
         let self_ = Arc::new(Self {
             node,
@@ -89,13 +132,22 @@ impl Window {
 
             locale,
             screen_size,
+            safe_area_insets,
+            ime_cursor_area,
             scale,
+            debug_safe_area,
+            debug_glyph_atlas,
+
+            content_dc_key: OsRng.gen(),
+            debug_dc_key: OsRng.gen(),
+            atlas_stats_dc_key: OsRng.gen(),
         });
 
         Pimpl::Window(self_)
     }
 
     pub fn init(&self) {
+        self.refresh_safe_area(&mut PropertyAtomicGuard::none());
         for child in self.get_children() {
             let obj = get_ui_object3(&child);
             obj.init();
@@ -127,6 +179,8 @@ impl Window {
                 let atom = &mut self_.render_api.make_guard(gfxtag!("Window::resize_task"));
                 // Now update the properties
                 screen_size2.set(atom, size);
+                // Insets change when the device rotates
+                self_.refresh_safe_area(atom);
 
                 self_.draw(atom).await;
             }
@@ -170,6 +224,46 @@ impl Window {
         let me2 = me.clone();
         let touch_task = ex.spawn(async move { while Self::process_touch(&me2, &ev_sub).await {} });
 
+        let ev_sub = event_pub.subscribe_ime();
+        let me2 = me.clone();
+        let ime_task = ex.spawn(async move { while Self::process_ime(&me2, &ev_sub).await {} });
+
+        let ev_sub = event_pub.subscribe_frame();
+        let me2 = me.clone();
+        let frame_task = ex.spawn(async move { while Self::process_frame(&me2, &ev_sub).await {} });
+
+        let node_ref = self.node.upgrade().unwrap();
+        let method_sub = node_ref.subscribe_method_call("animate").unwrap();
+        let me2 = me.clone();
+        let animate_task =
+            ex.spawn(async move { while Self::process_animate_method(&me2, &method_sub).await {} });
+
+        let method_sub = node_ref.subscribe_method_call("cancel_animation").unwrap();
+        let me2 = me.clone();
+        let cancel_animation_task = ex.spawn(async move {
+            while Self::process_cancel_animation_method(&me2, &method_sub).await {}
+        });
+
+        // Refresh the glyph atlas stats while they're shown
+        let me2 = me.clone();
+        let atlas_stats_task = ex.spawn(async move {
+            loop {
+                msleep(1000).await;
+                let Some(self_) = me2.upgrade() else {
+                    // Should not happen
+                    panic!("self destroyed before atlas_stats_task was stopped!");
+                };
+                if !self_.debug_glyph_atlas.get() {
+                    continue
+                }
+
+                let atom = self_.render_api.make_guard(gfxtag!("Window::atlas_stats_task"));
+                let dc = self_.atlas_stats_draw().await;
+                let draw_calls = vec![(self_.atlas_stats_dc_key, dc)];
+                self_.render_api.replace_draw_calls(atom.batch_id, unixtime(), draw_calls);
+            }
+        });
+
         async fn reload_locale(self_: Arc<Window>, batch: BatchGuardPtr) {
             let atom = &mut batch.spawn();
             self_.reload_locale(atom).await;
@@ -182,6 +276,9 @@ impl Window {
         let mut on_modify = OnModify::new(ex.clone(), self.node.clone(), me.clone());
         on_modify.when_change(self.locale.prop(), reload_locale);
         on_modify.when_change(self.scale.prop(), redraw);
+        on_modify.when_change(self.safe_area_insets.clone(), redraw);
+        on_modify.when_change(self.debug_safe_area.prop(), redraw);
+        on_modify.when_change(self.debug_glyph_atlas.prop(), redraw);
 
         let mut tasks = vec![
             resize_task,
@@ -193,6 +290,11 @@ impl Window {
             mouse_move_task,
             mouse_wheel_task,
             touch_task,
+            ime_task,
+            frame_task,
+            animate_task,
+            cancel_animation_task,
+            atlas_stats_task,
         ];
         tasks.append(&mut on_modify.tasks);
         *self.tasks.lock() = tasks;
@@ -334,6 +436,130 @@ impl Window {
         true
     }
 
+    async fn process_ime(me: &Weak<Self>, ev_sub: &GraphicsEventImeSub) -> bool {
+        let Ok(ev) = ev_sub.recv().await else {
+            t!("Event relayer closed");
+            return false
+        };
+
+        let Some(self_) = me.upgrade() else {
+            // Should not happen
+            panic!("self destroyed before ime_task was stopped!");
+        };
+
+        self_.handle_ime(ev).await;
+        true
+    }
+
+    async fn process_frame(me: &Weak<Self>, ev_sub: &GraphicsEventFrameSub) -> bool {
+        let Ok(()) = ev_sub.recv().await else {
+            t!("Event relayer closed");
+            return false
+        };
+
+        let Some(self_) = me.upgrade() else {
+            // Should not happen
+            panic!("self destroyed before frame_task was stopped!");
+        };
+
+        let mut tweener = TWEENER.lock();
+        if tweener.is_active() {
+            let atom = &mut self_.render_api.make_guard(gfxtag!("Window::process_frame"));
+            tweener.tick(atom);
+        }
+        drop(tweener);
+        true
+    }
+
+    async fn process_animate_method(me: &Weak<Self>, sub: &MethodCallSub) -> bool {
+        let Ok(method_call) = sub.receive().await else {
+            t!("Event relayer closed");
+            return false
+        };
+
+        t!("method called: animate({method_call:?})");
+        assert!(method_call.send_res.is_none());
+
+        fn decode_data(data: &[u8]) -> std::io::Result<(String, String, u32, f32, u32, String)> {
+            let mut cur = Cursor::new(&data);
+            let node_path = String::decode(&mut cur)?;
+            let prop_name = String::decode(&mut cur)?;
+            let i = u32::decode(&mut cur)?;
+            let to = f32::decode(&mut cur)?;
+            let duration = u32::decode(&mut cur)?;
+            let easing = String::decode(&mut cur)?;
+            Ok((node_path, prop_name, i, to, duration, easing))
+        }
+
+        let Ok((node_path, prop_name, i, to, duration, easing)) = decode_data(&method_call.data)
+        else {
+            error!(target: "ui::window", "animate() method invalid arg data");
+            return true
+        };
+
+        let Some(self_) = me.upgrade() else {
+            // Should not happen
+            panic!("self destroyed before animate_task was stopped!");
+        };
+
+        let Some(easing) = Easing::from_str(&easing) else {
+            error!(target: "ui::window", "animate(): unknown easing '{easing}'");
+            return true
+        };
+        let Some(prop) = self_.lookup_prop(&node_path, &prop_name) else { return true };
+
+        let duration = Duration::from_millis(duration as u64);
+        if let Err(err) = TWEENER.lock().animate(prop, i as usize, to, duration, easing) {
+            error!(target: "ui::window", "animate({node_path}:{prop_name}[{i}]) failed: {err}");
+        }
+        true
+    }
+
+    async fn process_cancel_animation_method(me: &Weak<Self>, sub: &MethodCallSub) -> bool {
+        let Ok(method_call) = sub.receive().await else {
+            t!("Event relayer closed");
+            return false
+        };
+
+        t!("method called: cancel_animation({method_call:?})");
+        assert!(method_call.send_res.is_none());
+
+        fn decode_data(data: &[u8]) -> std::io::Result<(String, String)> {
+            let mut cur = Cursor::new(&data);
+            let node_path = String::decode(&mut cur)?;
+            let prop_name = String::decode(&mut cur)?;
+            Ok((node_path, prop_name))
+        }
+
+        let Ok((node_path, prop_name)) = decode_data(&method_call.data) else {
+            error!(target: "ui::window", "cancel_animation() method invalid arg data");
+            return true
+        };
+
+        let Some(self_) = me.upgrade() else {
+            // Should not happen
+            panic!("self destroyed before cancel_animation_task was stopped!");
+        };
+
+        let Some(prop) = self_.lookup_prop(&node_path, &prop_name) else { return true };
+        TWEENER.lock().cancel_prop(&prop, None);
+        true
+    }
+
+    /// Find a property on a node below the window, such as `/content/page:rect`
+    fn lookup_prop(&self, node_path: &str, prop_name: &str) -> Option<PropertyPtr> {
+        let node = self.node.upgrade().unwrap();
+        let Some(node) = node.lookup_node(node_path) else {
+            error!(target: "ui::window", "No node found at '{node_path}'");
+            return None
+        };
+        let prop = node.get_property(prop_name);
+        if prop.is_none() {
+            error!(target: "ui::window", "Node '{node_path}' has no property '{prop_name}'");
+        }
+        prop
+    }
+
     fn get_children(&self) -> Vec<SceneNodePtr> {
         let node = self.node.upgrade().unwrap();
         get_children_ordered(&node)
@@ -348,6 +574,22 @@ impl Window {
         }
     }
 
+    async fn handle_ime(&self, ev: ImeEvent) {
+        for child in self.get_children() {
+            let obj = get_ui_object3(&child);
+            let Some(area) = obj.handle_ime(&ev).await else { continue };
+
+            // Publish the cursor area in screen coords for the platform IME
+            let [left, top, _, _] = self.safe_area();
+            let area = (area + Point::new(left, top)) * self.scale.get();
+            let atom = &mut self.render_api.make_guard(gfxtag!("Window::handle_ime"));
+            for (i, val) in [area.x, area.y, area.w, area.h].into_iter().enumerate() {
+                self.ime_cursor_area.set_f32(atom, Role::Internal, i, val).unwrap();
+            }
+            return
+        }
+    }
+
     async fn handle_key_down(&self, key: KeyCode, mods: KeyMods, repeat: bool) {
         for child in self.get_children() {
             let obj = get_ui_object3(&child);
@@ -366,10 +608,35 @@ impl Window {
         }
     }
 
+    /// Read the safe area insets from the platform layer
+    fn refresh_safe_area(&self, atom: &mut PropertyAtomicGuard) {
+        #[cfg(target_os = "android")]
+        {
+            let insets = crate::android::get_safe_area_insets();
+            d!("Safe area insets: {insets:?}");
+            for (i, inset) in insets.into_iter().enumerate() {
+                self.safe_area_insets.set_f32(atom, Role::Internal, i, inset).unwrap();
+            }
+        }
+        #[cfg(not(target_os = "android"))]
+        let _ = atom;
+    }
+
+    /// Safe area insets as `[left, top, right, bottom]` in virtual coords
+    fn safe_area(&self) -> [f32; 4] {
+        let scale = self.scale.get();
+        let mut insets = [0.; 4];
+        for (i, inset) in insets.iter_mut().enumerate() {
+            *inset = self.safe_area_insets.get_f32(i).unwrap() / scale;
+        }
+        insets
+    }
+
     /// Converts from screen to local coords
     fn local_scale(&self, point: &mut Point) {
-        point.x /= self.scale.get();
-        point.y /= self.scale.get();
+        let [left, top, _, _] = self.safe_area();
+        point.x = point.x / self.scale.get() - left;
+        point.y = point.y / self.scale.get() - top;
     }
 
     async fn handle_mouse_btn_down(&self, btn: MouseButton, mut mouse_pos: Point) {
@@ -445,8 +712,16 @@ impl Window {
         let timest = unixtime();
 
         let virt_size = self.screen_size.get() / self.scale.get();
-        let rect = Rectangle::from([0., 0., virt_size.w, virt_size.h]);
-        t!("Window::draw({rect:?}) [timest={timest}, trace_id={trace_id}]");
+        let [left, top, right, bottom] = self.safe_area();
+        let content_rect = Rectangle::from([
+            left,
+            top,
+            (virt_size.w - left - right).max(0.),
+            (virt_size.h - top - bottom).max(0.),
+        ]);
+        // Children lay themselves out relative to the safe area
+        let rect = Rectangle::from([0., 0., content_rect.w, content_rect.h]);
+        t!("Window::draw({content_rect:?}) [timest={timest}, trace_id={trace_id}]");
 
         let mut draw_calls = vec![];
         let mut child_calls = vec![];
@@ -462,8 +737,28 @@ impl Window {
             child_calls.push(draw_update.key);
         }
 
+        let content_dc = DrawCall::new(
+            vec![DrawInstruction::ApplyView(content_rect)],
+            child_calls,
+            0,
+            "win_content",
+        );
+        draw_calls.push((self.content_dc_key, content_dc));
+
+        let mut win_calls = vec![self.content_dc_key];
+        if self.debug_safe_area.get() {
+            let debug_dc = self.debug_safe_area_draw(virt_size.w, virt_size.h, content_rect);
+            draw_calls.push((self.debug_dc_key, debug_dc));
+            win_calls.push(self.debug_dc_key);
+        }
+        if self.debug_glyph_atlas.get() {
+            let stats_dc = self.atlas_stats_draw().await;
+            draw_calls.push((self.atlas_stats_dc_key, stats_dc));
+            win_calls.push(self.atlas_stats_dc_key);
+        }
+
         let dc =
-            DrawCall::new(vec![DrawInstruction::SetScale(self.scale.get())], child_calls, 0, "win");
+            DrawCall::new(vec![DrawInstruction::SetScale(self.scale.get())], win_calls, 0, "win");
         draw_calls.push((0, dc));
         //t!("  => {:?}", draw_calls);
 
@@ -472,6 +767,37 @@ impl Window {
         t!("Window::draw() - replaced draw call [timest={timest}, trace_id={trace_id}]");
     }
 
+    /// Shade the unsafe margins and outline the content area
+    fn debug_safe_area_draw(&self, w: f32, h: f32, content_rect: Rectangle) -> DrawCall {
+        let Rectangle { x, y, w: cw, h: ch } = content_rect;
+        let mut mesh = MeshBuilder::new(gfxtag!("win_safe_area"));
+        mesh.draw_filled_box(&Rectangle::from([0., 0., w, y]), DEBUG_INSET_COLOR);
+        mesh.draw_filled_box(&Rectangle::from([0., y + ch, w, h - y - ch]), DEBUG_INSET_COLOR);
+        mesh.draw_filled_box(&Rectangle::from([0., y, x, ch]), DEBUG_INSET_COLOR);
+        mesh.draw_filled_box(&Rectangle::from([x + cw, y, w - x - cw, ch]), DEBUG_INSET_COLOR);
+        mesh.draw_outline(&content_rect, COLOR_GREEN, 1.);
+        let mesh = mesh.alloc(&self.render_api).draw_untextured();
+        DrawCall::new(vec![DrawInstruction::Draw(mesh)], vec![], u32::MAX, "win_safe_area")
+    }
+
+    /// Glyph atlas cache stats in the top left corner
+    async fn atlas_stats_draw(&self) -> DrawCall {
+        let stats = GLYPH_ATLAS.lock().stats();
+        let text = format!(
+            "glyphs: {}  pages: {}  hits: {}  misses: {}  evictions: {}  uploads: {}",
+            stats.glyphs, stats.pages, stats.hits, stats.misses, stats.evictions, stats.uploads
+        );
+
+        let layout = {
+            let mut txt_ctx = TEXT_CTX.get().await;
+            txt_ctx.make_layout(&text, COLOR_GREEN, 14., 1., self.scale.get(), None, &[])
+        };
+
+        let mut instrs = vec![DrawInstruction::Move(Point::new(4., 4.))];
+        instrs.append(&mut render_layout(&layout, &self.render_api, gfxtag!("win_atlas_stats")));
+        DrawCall::new(instrs, vec![], u32::MAX, "win_atlas_stats")
+    }
+
     async fn reload_locale(&self, atom: &mut PropertyAtomicGuard) {
         /*
         let i18n_src = indoc::indoc! {"
@@ -0,0 +1,101 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+/// Score how well `query` fuzzy matches `candidate`.
+///
+/// Every char of the query must appear in the candidate in order, ignoring
+/// case. Returns `None` when it doesn't match, otherwise a score where
+/// higher is better. Consecutive runs and matches at the start of words
+/// are rewarded, while gaps are penalized.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i32> {
+    let query: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+    if query.is_empty() {
+        return Some(0)
+    }
+
+    let mut score = 0;
+    let mut qi = 0;
+    let mut last_match: Option<usize> = None;
+    let mut prev_char = None;
+
+    for (ci, c) in candidate.chars().flat_map(char::to_lowercase).enumerate() {
+        if qi < query.len() && c == query[qi] {
+            score += 1;
+
+            let is_word_start = match prev_char {
+                None => true,
+                Some(p) => !char::is_alphanumeric(p),
+            };
+            if is_word_start {
+                score += 8;
+            }
+
+            match last_match {
+                Some(last) if last + 1 == ci => score += 5,
+                Some(last) => score -= (ci - last - 1).min(3) as i32,
+                None => score -= ci.min(3) as i32,
+            }
+
+            last_match = Some(ci);
+            qi += 1;
+        }
+        prev_char = Some(c);
+    }
+
+    if qi < query.len() {
+        return None
+    }
+
+    Some(score)
+}
+
+/// Filter `candidates` against `query`, returning the indexes of the
+/// matching ones sorted by descending score. Ties keep their original order.
+pub fn fuzzy_filter<S: AsRef<str>>(query: &str, candidates: &[S]) -> Vec<usize> {
+    let mut matches: Vec<(usize, i32)> = candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(i, c)| fuzzy_score(query, c.as_ref()).map(|score| (i, score)))
+        .collect();
+    matches.sort_by(|(i1, s1), (i2, s2)| s2.cmp(s1).then(i1.cmp(i2)));
+    matches.into_iter().map(|(i, _)| i).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_match() {
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+        assert!(fuzzy_score("dev", "#dev").is_some());
+        assert!(fuzzy_score("DEV", "#dev").is_some());
+        assert!(fuzzy_score("phy", "#philosophy").is_some());
+        assert!(fuzzy_score("vd", "#dev").is_none());
+        assert!(fuzzy_score("devs", "#dev").is_none());
+
+        // Consecutive and word start matches win over scattered ones
+        assert!(fuzzy_score("ma", "#math").unwrap() > fuzzy_score("ma", "#gamma").unwrap());
+        assert!(fuzzy_score("zi", "Zoom in").unwrap() > fuzzy_score("zi", "#zzzi").unwrap());
+
+        let channels = ["#dev", "#media", "#hackers", "#memes", "#markets", "#math"];
+        assert_eq!(fuzzy_filter("ma", &channels), vec![4, 5, 1]);
+        assert_eq!(fuzzy_filter("", &channels), vec![0, 1, 2, 3, 4, 5]);
+        assert!(fuzzy_filter("xyz", &channels).is_empty());
+    }
+}
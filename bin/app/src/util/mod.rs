@@ -20,7 +20,9 @@
 use colored::Colorize;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+pub mod fuzzy;
 pub mod i18n;
+pub mod qr;
 mod rt;
 pub use rt::{AsyncRuntime, ExecutorPtr};
 
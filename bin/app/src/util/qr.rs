@@ -0,0 +1,103 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! QR code encoding and scanning.
+//!
+//! Scanning goes through the platform: Android captures a photo with the
+//! camera app, while desktop reads the clipboard which may contain either
+//! the text itself or a path to an image of the code.
+
+use std::path::Path;
+
+macro_rules! d { ($($arg:tt)*) => { debug!(target: "util::qr", $($arg)*); } }
+macro_rules! w { ($($arg:tt)*) => { warn!(target: "util::qr", $($arg)*); } }
+
+/// Square grid of modules, stored row by row.
+pub struct QrMatrix {
+    pub width: usize,
+    pub modules: Vec<bool>,
+}
+
+impl QrMatrix {
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.width + x]
+    }
+}
+
+pub fn encode(data: &str) -> Option<QrMatrix> {
+    let code = match qrcode::QrCode::new(data.as_bytes()) {
+        Ok(code) => code,
+        Err(e) => {
+            w!("Unable to encode QR code: {e}");
+            return None
+        }
+    };
+    let modules = code.to_colors().into_iter().map(|c| c == qrcode::Color::Dark).collect();
+    Some(QrMatrix { width: code.width(), modules })
+}
+
+/// Decode the first readable QR code found in an image file
+pub fn decode_image(path: &Path) -> Option<String> {
+    let img = match image::open(path) {
+        Ok(img) => img.to_luma8(),
+        Err(e) => {
+            w!("Unable to open image {path:?}: {e}");
+            return None
+        }
+    };
+    let mut img = rqrr::PreparedImage::prepare(img);
+    for grid in img.detect_grids() {
+        match grid.decode() {
+            Ok((_, content)) => return Some(content),
+            Err(e) => d!("Skipping unreadable grid in {path:?}: {e}"),
+        }
+    }
+    w!("No QR code found in {path:?}");
+    None
+}
+
+/// Ask the platform for a QR code and return its contents.
+/// Returns `None` if the user cancelled or nothing could be decoded.
+#[cfg(target_os = "android")]
+pub async fn scan() -> Option<String> {
+    let (sender, recvr) = async_channel::bounded(1);
+    crate::android::start_qr_scan(sender);
+    let path = recvr.recv().await.ok()??;
+    let content = decode_image(&path);
+    let _ = std::fs::remove_file(&path);
+    content
+}
+
+/// Ask the platform for a QR code and return its contents.
+/// Returns `None` if the user cancelled or nothing could be decoded.
+#[cfg(not(target_os = "android"))]
+pub async fn scan() -> Option<String> {
+    let text = miniquad::window::clipboard_get()?;
+    let text = text.trim();
+    if text.is_empty() {
+        return None
+    }
+
+    // Copying a file in most file managers gives a path or file:// URI
+    let path = Path::new(text.strip_prefix("file://").unwrap_or(text));
+    if path.is_file() {
+        d!("Importing QR code from {path:?}");
+        return decode_image(path)
+    }
+    Some(text.to_string())
+}
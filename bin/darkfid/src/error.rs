@@ -16,10 +16,13 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use darkfi::rpc::jsonrpc::{ErrorCode::ServerError, JsonError, JsonResult};
+pub use darkfi::rpc::jsonrpc::{server_error, server_error_with_details};
+use darkfi::rpc::jsonrpc::{ErrorCategory, ServerErrorKind};
 
 /// Custom RPC errors available for darkfid.
+/// These codes are part of the public API, so never renumber them.
 /// Please sort them sensefully.
+#[derive(Copy, Clone, Debug)]
 pub enum RpcError {
     // Transaction-related errors
     TxSimulationFail = -32110,
@@ -28,6 +31,7 @@ pub enum RpcError {
     // State-related errors,
     NotSynced = -32120,
     UnknownBlockHeight = -32121,
+    NullifierSetMismatch = -32122,
 
     // Parsing errors
     ParseError = -32190,
@@ -41,33 +45,42 @@ pub enum RpcError {
     PingFailed = -32300,
 }
 
-fn to_tuple(e: RpcError) -> (i32, String) {
-    let msg = match e {
-        // Transaction-related errors
-        RpcError::TxSimulationFail => "Failed simulating transaction state change",
-        RpcError::TxGasCalculationFail => "Failed to calculate transaction's gas",
-        // State-related errors
-        RpcError::NotSynced => "Blockchain is not synced",
-        RpcError::UnknownBlockHeight => "Did not find block height",
-        // Parsing errors
-        RpcError::ParseError => "Parse error",
-        // Contract-related errors
-        RpcError::ContractZkasDbNotFound => "zkas database not found for given contract",
-        RpcError::ContractStateNotFound => "Records not found for given contract state",
-        RpcError::ContractStateKeyNotFound => "Value not found for given contract state key",
-        // Misc errors
-        RpcError::PingFailed => "Miner daemon ping error",
-    };
-
-    (e as i32, msg.to_string())
-}
-
-pub fn server_error(e: RpcError, id: u16, msg: Option<&str>) -> JsonResult {
-    let (code, default_msg) = to_tuple(e);
+impl ServerErrorKind for RpcError {
+    fn code(&self) -> i32 {
+        *self as i32
+    }
 
-    if let Some(message) = msg {
-        return JsonError::new(ServerError(code), Some(message.to_string()), id).into()
+    fn message(&self) -> &'static str {
+        match self {
+            // Transaction-related errors
+            Self::TxSimulationFail => "Failed simulating transaction state change",
+            Self::TxGasCalculationFail => "Failed to calculate transaction's gas",
+            // State-related errors
+            Self::NotSynced => "Blockchain is not synced",
+            Self::UnknownBlockHeight => "Did not find block height",
+            Self::NullifierSetMismatch => "Nullifier set export is inconsistent",
+            // Parsing errors
+            Self::ParseError => "Parse error",
+            // Contract-related errors
+            Self::ContractZkasDbNotFound => "zkas database not found for given contract",
+            Self::ContractStateNotFound => "Records not found for given contract state",
+            Self::ContractStateKeyNotFound => "Value not found for given contract state key",
+            // Misc errors
+            Self::PingFailed => "Miner daemon ping error",
+        }
     }
 
-    JsonError::new(ServerError(code), Some(default_msg), id).into()
+    fn category(&self) -> ErrorCategory {
+        match self {
+            Self::TxSimulationFail |
+            Self::TxGasCalculationFail |
+            Self::UnknownBlockHeight |
+            Self::ParseError |
+            Self::ContractZkasDbNotFound |
+            Self::ContractStateNotFound |
+            Self::ContractStateKeyNotFound => ErrorCategory::Validation,
+            Self::NotSynced | Self::PingFailed => ErrorCategory::Net,
+            Self::NullifierSetMismatch => ErrorCategory::Internal,
+        }
+    }
 }
@@ -29,11 +29,12 @@ use darkfi::{
     net::settings::Settings,
     rpc::{
         jsonrpc::JsonSubscriber,
+        p2p_method::forward_p2p_events,
         server::{listen_and_serve, RequestHandler},
         settings::RpcSettings,
     },
     system::{ExecutorPtr, StoppableTask, StoppableTaskPtr},
-    validator::{Validator, ValidatorConfig, ValidatorPtr},
+    validator::{template::TxSelectionPolicy, Validator, ValidatorConfig, ValidatorPtr},
     Error, Result,
 };
 
@@ -41,7 +42,7 @@ use darkfi::{
 mod tests;
 
 mod error;
-use error::{server_error, RpcError};
+use error::{server_error, server_error_with_details, RpcError};
 
 /// JSON-RPC requests handler and methods
 mod rpc;
@@ -52,7 +53,13 @@ mod rpc_xmr;
 
 /// Validator async tasks
 pub mod task;
-use task::{consensus::ConsensusInitTaskConfig, consensus_init_task};
+use task::{
+    consensus::ConsensusInitTaskConfig, consensus_init_task, dao_events_task, scrubber_task,
+    ScrubberState,
+};
+
+/// Nullifier set export for auditing
+mod nullifiers;
 
 /// P2P net protocols
 mod proto;
@@ -69,6 +76,8 @@ pub struct DarkfiNode {
     validator: ValidatorPtr,
     /// Garbage collection task transactions batch size
     txs_batch_size: usize,
+    /// Block template transactions selection policy
+    tx_selection: Arc<dyn TxSelectionPolicy>,
     /// A map of various subscribers exporting live info from the blockchain
     subscribers: HashMap<&'static str, JsonSubscriber>,
     /// JSON-RPC connection tracker
@@ -77,6 +86,8 @@ pub struct DarkfiNode {
     rpc_client: Option<Mutex<MinerRpcClient>>,
     /// HTTP JSON-RPC connection tracker
     mm_rpc_connections: Mutex<HashSet<StoppableTaskPtr>>,
+    /// Blockchain integrity scrubber progress and findings
+    scrubber: Mutex<ScrubberState>,
 }
 
 impl DarkfiNode {
@@ -84,6 +95,7 @@ impl DarkfiNode {
         p2p_handler: DarkfidP2pHandlerPtr,
         validator: ValidatorPtr,
         txs_batch_size: usize,
+        tx_selection: Arc<dyn TxSelectionPolicy>,
         subscribers: HashMap<&'static str, JsonSubscriber>,
         rpc_client: Option<Mutex<MinerRpcClient>>,
     ) -> DarkfiNodePtr {
@@ -91,10 +103,12 @@ impl DarkfiNode {
             p2p_handler,
             validator,
             txs_batch_size,
+            tx_selection,
             subscribers,
             rpc_connections: Mutex::new(HashSet::new()),
             rpc_client,
             mm_rpc_connections: Mutex::new(HashSet::new()),
+            scrubber: Mutex::new(ScrubberState::default()),
         })
     }
 }
@@ -108,12 +122,18 @@ pub struct Darkfid {
     node: DarkfiNodePtr,
     /// `dnet` background task
     dnet_task: StoppableTaskPtr,
+    /// P2P events background task
+    p2p_events_task: StoppableTaskPtr,
     /// JSON-RPC background task
     rpc_task: StoppableTaskPtr,
     /// HTTP JSON-RPC background task
     mm_rpc_task: StoppableTaskPtr,
     /// Consensus protocol background task
     consensus_task: StoppableTaskPtr,
+    /// DAO events subscribers background task
+    dao_events_task: StoppableTaskPtr,
+    /// Blockchain integrity scrubber background task
+    scrubber_task: StoppableTaskPtr,
 }
 
 impl Darkfid {
@@ -121,12 +141,15 @@ impl Darkfid {
     ///
     /// Generates a new `DarkfiNode` for provided configuration,
     /// along with all the corresponding background tasks.
+    #[allow(clippy::too_many_arguments)]
     pub async fn init(
         sled_db: &sled_overlay::sled::Db,
         config: &ValidatorConfig,
         net_settings: &Settings,
         minerd_endpoint: &Option<Url>,
+        minerd_token: &Option<String>,
         txs_batch_size: &Option<usize>,
+        tx_selection: Arc<dyn TxSelectionPolicy>,
         ex: &ExecutorPtr,
     ) -> Result<DarkfidPtr> {
         info!(target: "darkfid::Darkfid::init", "Initializing a Darkfi daemon...");
@@ -154,28 +177,50 @@ impl Darkfid {
         subscribers.insert("txs", JsonSubscriber::new("blockchain.subscribe_txs"));
         subscribers.insert("proposals", JsonSubscriber::new("blockchain.subscribe_proposals"));
         subscribers.insert("dnet", JsonSubscriber::new("dnet.subscribe_events"));
+        subscribers.insert("p2p", JsonSubscriber::new("p2p.subscribe_events"));
+        subscribers.insert("dao_proposals", JsonSubscriber::new("dao.subscribe_proposals"));
+        subscribers.insert("dao_votes", JsonSubscriber::new("dao.subscribe_votes"));
 
         // Initialize JSON-RPC client to perform requests to minerd
         let rpc_client = match minerd_endpoint {
-            Some(endpoint) => {
-                Some(Mutex::new(MinerRpcClient::new(endpoint.clone(), ex.clone()).await))
-            }
+            Some(endpoint) => Some(Mutex::new(
+                MinerRpcClient::new(endpoint.clone(), minerd_token.clone(), ex.clone()).await,
+            )),
             None => None,
         };
 
         // Initialize node
-        let node =
-            DarkfiNode::new(p2p_handler, validator, txs_batch_size, subscribers, rpc_client).await;
+        let node = DarkfiNode::new(
+            p2p_handler,
+            validator,
+            txs_batch_size,
+            tx_selection,
+            subscribers,
+            rpc_client,
+        )
+        .await;
 
         // Generate the background tasks
         let dnet_task = StoppableTask::new();
+        let p2p_events_task = StoppableTask::new();
         let rpc_task = StoppableTask::new();
         let mm_rpc_task = StoppableTask::new();
         let consensus_task = StoppableTask::new();
+        let dao_events_task = StoppableTask::new();
+        let scrubber_task = StoppableTask::new();
 
         info!(target: "darkfid::Darkfid::init", "Darkfi daemon initialized successfully!");
 
-        Ok(Arc::new(Self { node, dnet_task, rpc_task, mm_rpc_task, consensus_task }))
+        Ok(Arc::new(Self {
+            node,
+            dnet_task,
+            p2p_events_task,
+            rpc_task,
+            mm_rpc_task,
+            consensus_task,
+            dao_events_task,
+            scrubber_task,
+        }))
     }
 
     /// Start the DarkFi daemon in the given executor, using the provided JSON-RPC listen url
@@ -219,6 +264,35 @@ impl Darkfid {
             executor.clone(),
         );
 
+        // Start the P2P events task
+        info!(target: "darkfid::Darkfid::start", "Starting P2P events subs task");
+        let p2p_sub = self.node.subscribers.get("p2p").unwrap().clone();
+        self.p2p_events_task.clone().start(
+            forward_p2p_events(self.node.p2p_handler.p2p.clone(), p2p_sub),
+            |res| async {
+                match res {
+                    Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                    Err(e) => error!(target: "darkfid::Darkfid::start", "Failed starting P2P events subs task: {e}"),
+                }
+            },
+            Error::DetachedTaskStopped,
+            executor.clone(),
+        );
+
+        // Start the DAO events task
+        info!(target: "darkfid::Darkfid::start", "Starting DAO events subs task");
+        self.dao_events_task.clone().start(
+            dao_events_task(self.node.clone()),
+            |res| async {
+                match res {
+                    Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                    Err(e) => error!(target: "darkfid::Darkfid::start", "Failed starting DAO events subs task: {e}"),
+                }
+            },
+            Error::DetachedTaskStopped,
+            executor.clone(),
+        );
+
         // Start the JSON-RPC task
         info!(target: "darkfid::Darkfid::start", "Starting JSON-RPC server");
         let node_ = self.node.clone();
@@ -285,6 +359,30 @@ impl Darkfid {
             executor.clone(),
         );
 
+        // Start the blockchain integrity scrubber, if enabled
+        if config.scrubber {
+            info!(target: "darkfid::Darkfid::start", "Starting blockchain integrity scrubber task");
+            self.scrubber_task.clone().start(
+                scrubber_task(self.node.clone()),
+                |res| async {
+                    match res {
+                        Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                        Err(e) => error!(target: "darkfid::Darkfid::start", "Failed starting blockchain integrity scrubber task: {e}"),
+                    }
+                },
+                Error::DetachedTaskStopped,
+                executor.clone(),
+            );
+        } else {
+            // Create a dummy task
+            self.scrubber_task.clone().start(
+                async { Ok(()) },
+                |_| async { /* Do nothing */ },
+                Error::DetachedTaskStopped,
+                executor.clone(),
+            );
+        }
+
         info!(target: "darkfid::Darkfid::start", "Darkfi daemon started successfully!");
         Ok(())
     }
@@ -297,6 +395,14 @@ impl Darkfid {
         info!(target: "darkfid::Darkfid::stop", "Stopping dnet subs task...");
         self.dnet_task.stop().await;
 
+        // Stop the P2P events task
+        info!(target: "darkfid::Darkfid::stop", "Stopping P2P events subs task...");
+        self.p2p_events_task.stop().await;
+
+        // Stop the DAO events task
+        info!(target: "darkfid::Darkfid::stop", "Stopping DAO events subs task...");
+        self.dao_events_task.stop().await;
+
         // Stop the JSON-RPC task
         info!(target: "darkfid::Darkfid::stop", "Stopping JSON-RPC server...");
         self.rpc_task.stop().await;
@@ -313,6 +419,10 @@ impl Darkfid {
         info!(target: "darkfid::Darkfid::stop", "Stopping consensus task...");
         self.consensus_task.stop().await;
 
+        // Stop the scrubber task
+        info!(target: "darkfid::Darkfid::stop", "Stopping blockchain integrity scrubber task...");
+        self.scrubber_task.stop().await;
+
         // Flush sled database data
         info!(target: "darkfid::Darkfid::stop", "Flushing sled database...");
         let flushed_bytes = self.node.validator.blockchain.sled_db.flush_async().await?;
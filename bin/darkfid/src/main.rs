@@ -28,12 +28,15 @@ use darkfi::{
     blockchain::BlockInfo,
     cli_desc,
     net::settings::SettingsOpt,
-    rpc::settings::RpcSettingsOpt,
+    rpc::{
+        client::auth_token,
+        settings::{PermissionClass, RpcSettings, RpcSettingsOpt},
+    },
     util::{
         encoding::base64,
         path::{expand_path, get_config_path},
     },
-    validator::{Validator, ValidatorConfig},
+    validator::{template::TxSelectionLimits, Validator, ValidatorConfig},
     Error, Result,
 };
 use darkfi_serial::deserialize_async;
@@ -49,6 +52,11 @@ const GENESIS_BLOCK_LOCALNET: &str = include_str!("../genesis_block_localnet");
 const GENESIS_BLOCK_TESTNET: &str = include_str!("../genesis_block_testnet");
 const GENESIS_BLOCK_MAINNET: &str = include_str!("../genesis_block_mainnet");
 
+/// JSON-RPC methods walking the whole chain. They are only available to
+/// `admin` connections, so they can't be used to exhaust the node.
+const CHAIN_WALK_METHODS: [&str; 2] =
+    ["blockchain.export_nullifiers", "blockchain.verify_nullifiers"];
+
 #[derive(Clone, Debug, Deserialize, StructOpt, StructOptToml)]
 #[serde(default)]
 #[structopt(name = "darkfid", about = cli_desc!())]
@@ -81,6 +89,10 @@ struct Args {
     /// Set log file to ouput into
     log: Option<String>,
 
+    #[structopt(long)]
+    /// Serve Prometheus metrics on this URL, e.g. tcp://127.0.0.1:9100
+    metrics_listen: Option<String>,
+
     #[structopt(short, parse(from_occurrences))]
     /// Increase verbosity (-vvv supported)
     verbose: u8,
@@ -103,6 +115,15 @@ pub struct BlockchainNetwork {
     /// minerd JSON-RPC endpoint
     minerd_endpoint: Option<Url>,
 
+    #[structopt(long)]
+    /// minerd JSON-RPC authentication token
+    minerd_token: Option<String>,
+
+    #[structopt(long)]
+    /// Path to the minerd JSON-RPC authentication cookie,
+    /// used when no token is configured
+    minerd_cookie: Option<String>,
+
     #[structopt(skip)]
     /// Optional JSON-RPC settings for p2pool merge mining requests
     mm_rpc: Option<RpcSettingsOpt>,
@@ -152,6 +173,23 @@ pub struct BlockchainNetwork {
     /// Garbage collection task transactions batch size
     txs_batch_size: Option<usize>,
 
+    #[structopt(long)]
+    /// Optional maximum number of transactions in a mined block
+    block_max_txs: Option<usize>,
+
+    #[structopt(long)]
+    /// Optional maximum total size of the transactions in a mined block, in bytes
+    block_max_size: Option<usize>,
+
+    #[structopt(long, default_value = "0")]
+    /// Minimum fee a transaction must pay to get included in a mined block
+    block_min_fee: u64,
+
+    #[structopt(long)]
+    /// Periodically check the stored blockchain records for corruption,
+    /// restoring the affected blocks from peers
+    scrubber: bool,
+
     #[structopt(flatten)]
     /// P2P network settings
     net: SettingsOpt,
@@ -250,13 +288,27 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
         return Ok(())
     }
 
+    // Configure the block template transactions selection
+    let tx_selection = TxSelectionLimits {
+        max_size: blockchain_config.block_max_size,
+        max_txs: blockchain_config.block_max_txs,
+        min_fee: blockchain_config.block_min_fee,
+        ..Default::default()
+    };
+
     // Generate the daemon
+    let minerd_token = auth_token(
+        blockchain_config.minerd_token.clone(),
+        blockchain_config.minerd_cookie.clone(),
+    )?;
     let daemon = Darkfid::init(
         &sled_db,
         &config,
         &blockchain_config.net.into(),
         &blockchain_config.minerd_endpoint,
+        &minerd_token,
         &blockchain_config.txs_batch_size,
+        Arc::new(tx_selection),
         &ex,
     )
     .await?;
@@ -271,11 +323,27 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
         spend_hook: blockchain_config.spend_hook,
         user_data: blockchain_config.user_data,
         bootstrap,
+        scrubber: blockchain_config.scrubber,
     };
+    // Restrict the methods walking the whole chain to admins, even if a
+    // prefix entry grants their namespace to others. Without
+    // authentication there are no admins, so they get disabled.
+    let mut rpc_settings: RpcSettings = blockchain_config.rpc.into();
+    for method in CHAIN_WALK_METHODS {
+        if rpc_settings.auth_enabled() {
+            rpc_settings.method_permissions.push((method.to_string(), PermissionClass::Admin));
+        } else {
+            rpc_settings.disabled_methods.push(method.to_string());
+        }
+    }
+    if !rpc_settings.auth_enabled() {
+        info!(target: "darkfid", "JSON-RPC authentication is disabled, disabling {CHAIN_WALK_METHODS:?}");
+    }
+
     daemon
         .start(
             &ex,
-            &blockchain_config.rpc.into(),
+            &rpc_settings,
             &blockchain_config.mm_rpc.map(|mm_rpc_opts| mm_rpc_opts.into()),
             &config,
         )
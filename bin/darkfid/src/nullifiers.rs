@@ -0,0 +1,246 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Nullifier set export for auditing.
+//!
+//! The export walks the canonical chain from genesis, collecting every
+//! nullifier revealed by `Money` calls, and folds each block into a
+//! running accumulator bound to the block header hash. Anyone holding the
+//! headers can recompute the accumulator, while the nullifiers SMT root
+//! ties the exported set to the `Money` contract state.
+
+use std::collections::{BTreeMap, HashSet};
+
+use darkfi::{
+    blockchain::{BlockInfo, Blockchain, HeaderHash},
+    Error, Result,
+};
+use darkfi_money_contract::{
+    model::{MoneyFeeParamsV1, MoneyTransferParamsV1, Nullifier},
+    MoneyFunction, MONEY_CONTRACT_INFO_TREE, MONEY_CONTRACT_LATEST_NULLIFIER_ROOT,
+    MONEY_CONTRACT_NULLIFIER_ROOTS_TREE,
+};
+use darkfi_sdk::{
+    crypto::{
+        smt::{MemoryStorageFp, PoseidonFp, SmtMemoryFp, EMPTY_NODES_FP},
+        MONEY_CONTRACT_ID,
+    },
+    pasta::{group::ff::PrimeField, pallas},
+};
+use darkfi_serial::{deserialize, SerialDecodable, SerialEncodable};
+use log::{debug, info};
+
+/// Nullifiers revealed by a single block
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct BlockNullifiers {
+    /// Block height
+    pub height: u32,
+    /// Block header hash
+    pub hash: HeaderHash,
+    /// Revealed nullifiers, in transaction order
+    pub nullifiers: Vec<Nullifier>,
+}
+
+/// Full nullifier set of the canonical chain up to a given block
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct NullifierSetExport {
+    /// Height of the last block included in the export
+    pub height: u32,
+    /// Header hash of the last block included in the export
+    pub hash: HeaderHash,
+    /// Blocks revealing at least one nullifier, in ascending height order
+    pub blocks: Vec<BlockNullifiers>,
+    /// Accumulator over all headers and their nullifiers since genesis
+    pub accumulator: [u8; 32],
+    /// Root of the nullifiers SMT containing the whole set
+    pub root: pallas::Base,
+}
+
+/// Fold a block into the accumulator. Blocks without nullifiers are
+/// folded too, so the accumulator commits to every header since genesis.
+fn accumulate(
+    acc: &[u8; 32],
+    height: u32,
+    hash: &HeaderHash,
+    nullifiers: &[Nullifier],
+) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(acc);
+    hasher.update(&height.to_le_bytes());
+    hasher.update(hash.inner());
+    hasher.update(&(nullifiers.len() as u64).to_le_bytes());
+    for nullifier in nullifiers {
+        hasher.update(&nullifier.to_bytes());
+    }
+    *hasher.finalize().as_bytes()
+}
+
+/// Compute the nullifiers SMT root of the given set
+fn smt_root(nullifiers: &[Nullifier]) -> Result<pallas::Base> {
+    let mut smt = SmtMemoryFp::new(MemoryStorageFp::new(), PoseidonFp::new(), &EMPTY_NODES_FP);
+    let leaves: Vec<_> = nullifiers.iter().map(|n| (n.inner(), n.inner())).collect();
+    if let Err(e) = smt.insert_batch(leaves) {
+        return Err(Error::Custom(format!("Failed to build nullifiers SMT: {e}")))
+    }
+    Ok(smt.root())
+}
+
+/// Grab all nullifiers revealed by `Money` calls in the given block
+fn block_nullifiers(block: &BlockInfo) -> Result<Vec<Nullifier>> {
+    let mut nullifiers = vec![];
+
+    for tx in &block.txs {
+        for call in &tx.calls {
+            if call.data.contract_id != MONEY_CONTRACT_ID || call.data.data.is_empty() {
+                continue
+            }
+
+            let data = &call.data.data;
+            match MoneyFunction::try_from(data[0])? {
+                MoneyFunction::FeeV1 => {
+                    let params: MoneyFeeParamsV1 = deserialize(&data[9..])?;
+                    nullifiers.push(params.input.nullifier);
+                }
+                MoneyFunction::TransferV1 | MoneyFunction::OtcSwapV1 => {
+                    let params: MoneyTransferParamsV1 = deserialize(&data[1..])?;
+                    nullifiers.extend(params.inputs.iter().map(|input| input.nullifier));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(nullifiers)
+}
+
+/// Export the nullifier set of the canonical chain, recomputing the
+/// accumulator from genesis. Be careful as this walks every block and
+/// keeps the whole set in memory.
+pub fn export_nullifier_set(blockchain: &Blockchain) -> Result<NullifierSetExport> {
+    let (last_height, last_hash) = blockchain.last()?;
+    info!(target: "darkfid::nullifiers::export_nullifier_set", "Exporting nullifier set up to block {last_height} ({last_hash})");
+
+    let mut blocks = vec![];
+    let mut all = vec![];
+    let mut accumulator = [0u8; 32];
+    for height in 0..=last_height {
+        let block = &blockchain.get_blocks_by_heights(&[height])?[0];
+        let hash = block.hash();
+        let nullifiers = block_nullifiers(block)?;
+        accumulator = accumulate(&accumulator, height, &hash, &nullifiers);
+
+        if nullifiers.is_empty() {
+            continue
+        }
+        debug!(target: "darkfid::nullifiers::export_nullifier_set", "Block {height} revealed {} nullifiers", nullifiers.len());
+        all.extend_from_slice(&nullifiers);
+        blocks.push(BlockNullifiers { height, hash, nullifiers });
+    }
+
+    let root = smt_root(&all)?;
+    info!(target: "darkfid::nullifiers::export_nullifier_set", "Exported {} nullifiers", all.len());
+
+    Ok(NullifierSetExport { height: last_height, hash: last_hash, blocks, accumulator, root })
+}
+
+/// Verify an exported nullifier set against our headers and the `Money`
+/// contract state. The accumulator gets recomputed over our own header
+/// hashes, so an export coming from a different chain gets rejected.
+pub fn verify_nullifier_set(blockchain: &Blockchain, export: &NullifierSetExport) -> Result<()> {
+    let mismatch = |msg: String| Err(Error::NullifierSetMismatch(msg));
+
+    // Index the exported blocks by height, rejecting unordered exports
+    let mut exported = BTreeMap::new();
+    let mut previous = None;
+    for block in &export.blocks {
+        if previous.is_some_and(|h| h >= block.height) || block.height > export.height {
+            return mismatch(format!("Block {} is out of order", block.height))
+        }
+        previous = Some(block.height);
+        exported.insert(block.height, block);
+    }
+
+    // Recompute the accumulator over our headers
+    let mut seen = HashSet::new();
+    let mut accumulator = [0u8; 32];
+    for height in 0..=export.height {
+        let hash = hash_at(blockchain, height)?;
+        let nullifiers: &[Nullifier] = match exported.get(&height) {
+            Some(block) => {
+                if block.hash != hash {
+                    return mismatch(format!("Header hash mismatch for block {height}"))
+                }
+                block.nullifiers.as_slice()
+            }
+            None => &[],
+        };
+
+        for nullifier in nullifiers {
+            if !seen.insert(nullifier.to_bytes()) {
+                return mismatch(format!("Nullifier {nullifier} appears twice"))
+            }
+        }
+        accumulator = accumulate(&accumulator, height, &hash, nullifiers);
+    }
+
+    if hash_at(blockchain, export.height)? != export.hash {
+        return mismatch(format!("Header hash mismatch for block {}", export.height))
+    }
+
+    if accumulator != export.accumulator {
+        return mismatch("Accumulator mismatch".to_string())
+    }
+
+    // Check the SMT root of the set
+    let all: Vec<Nullifier> = export.blocks.iter().flat_map(|b| b.nullifiers.clone()).collect();
+    if smt_root(&all)? != export.root {
+        return mismatch("Nullifiers SMT root mismatch".to_string())
+    }
+
+    // The root must be known to the Money contract. For an export of
+    // our current tip, it must be the latest one.
+    let contracts = &blockchain.contracts;
+    let root_bytes = export.root.to_repr();
+    if export.height == blockchain.last()?.0 {
+        let latest = contracts.get_state_tree_value(
+            &blockchain.sled_db,
+            &MONEY_CONTRACT_ID,
+            MONEY_CONTRACT_INFO_TREE,
+            MONEY_CONTRACT_LATEST_NULLIFIER_ROOT,
+        )?;
+        if latest != root_bytes {
+            return mismatch("Nullifiers SMT root is not the latest one".to_string())
+        }
+    } else if export.root != EMPTY_NODES_FP[0] {
+        let roots = contracts.lookup(
+            &blockchain.sled_db,
+            &MONEY_CONTRACT_ID,
+            MONEY_CONTRACT_NULLIFIER_ROOTS_TREE,
+        )?;
+        if !roots.contains_key(root_bytes)? {
+            return mismatch("Nullifiers SMT root is unknown".to_string())
+        }
+    }
+
+    Ok(())
+}
+
+/// Grab the header hash of the canonical block at given height
+fn hash_at(blockchain: &Blockchain, height: u32) -> Result<HeaderHash> {
+    Ok(blockchain.blocks.get_order(&[height], true)?[0].unwrap())
+}
@@ -31,7 +31,7 @@ use darkfi::{
             ProtocolGenericAction, ProtocolGenericHandler, ProtocolGenericHandlerPtr,
         },
         session::SESSION_DEFAULT,
-        Message, P2pPtr,
+        Message, MessagePriority, P2pPtr,
     },
     rpc::jsonrpc::JsonSubscriber,
     system::{ExecutorPtr, StoppableTask, StoppableTaskPtr},
@@ -60,7 +60,8 @@ impl_p2p_message!(
         threshold: 50,
         sleep_step: 500,
         expiry_time: NanoTimestamp::from_secs(5),
-    }
+    },
+    MessagePriority::High
 );
 
 /// Atomic pointer to the `ProtocolProposal` handler.
@@ -28,7 +28,8 @@ use darkfi::{
     net::P2pPtr,
     rpc::{
         client::RpcChadClient,
-        jsonrpc::{ErrorCode, JsonError, JsonRequest, JsonResponse, JsonResult},
+        health::{HandlerHealth, HealthStatus},
+        jsonrpc::{ErrorCode, JsonError, JsonRequest, JsonResponse, JsonResult, JsonSubscriber},
         p2p_method::HandlerP2p,
         server::RequestHandler,
     },
@@ -51,20 +52,23 @@ pub struct MmRpcHandler;
 /// so we can recreate it in case of an error.
 pub struct MinerRpcClient {
     endpoint: Url,
+    token: Option<String>,
     ex: ExecutorPtr,
     client: Option<RpcChadClient>,
 }
 
 impl MinerRpcClient {
-    pub async fn new(endpoint: Url, ex: ExecutorPtr) -> Self {
-        let client = match RpcChadClient::new(endpoint.clone(), ex.clone()).await {
+    pub async fn new(endpoint: Url, token: Option<String>, ex: ExecutorPtr) -> Self {
+        let client = match RpcChadClient::with_token(endpoint.clone(), token.clone(), ex.clone())
+            .await
+        {
             Ok(c) => Some(c),
             Err(_) => {
                 warn!(target: "darkfid::Darkfid::init", "Failed to initialize miner daemon rpc client, will try later");
                 None
             }
         };
-        Self { endpoint, ex, client }
+        Self { endpoint, token, ex, client }
     }
 
     /// Stop the client.
@@ -86,27 +90,51 @@ impl RequestHandler<DefaultRpcHandler> for DarkfiNode {
             // Miscellaneous methods
             // =====================
             "ping" => <DarkfiNode as RequestHandler<DefaultRpcHandler>>::pong(self, req.id, req.params).await,
+            "log.set_level" => <DarkfiNode as RequestHandler<DefaultRpcHandler>>::log_set_level(self, req.id, req.params).await,
             "clock" => self.clock(req.id, req.params).await,
             "ping_miner" => self.ping_miner(req.id, req.params).await,
             "dnet.switch" => self.dnet_switch(req.id, req.params).await,
             "dnet.subscribe_events" => self.dnet_subscribe_events(req.id, req.params).await,
             "p2p.get_info" => self.p2p_get_info(req.id, req.params).await,
+            "p2p.subscribe_events" => self.p2p_subscribe_events(req.id, req.params).await,
+
+            // ==============
+            // Health methods
+            // ==============
+            "health.ping" => self.health_ping(req.id, req.params).await,
+            "health.ready" => self.health_ready(req.id, req.params).await,
+            "health.info" => self.health_info(req.id, req.params).await,
 
             // ==================
             // Blockchain methods
             // ==================
             "blockchain.get_block" => self.blockchain_get_block(req.id, req.params).await,
             "blockchain.get_tx" => self.blockchain_get_tx(req.id, req.params).await,
+            "blockchain.get_block_tx_metrics" => self.blockchain_get_block_tx_metrics(req.id, req.params).await,
+            "blockchain.get_daily_tx_metrics" => self.blockchain_get_daily_tx_metrics(req.id, req.params).await,
+            "blockchain.get_block_gaps" => self.blockchain_get_block_gaps(req.id, req.params).await,
+            "blockchain.scrubber_status" => self.blockchain_scrubber_status(req.id, req.params).await,
+            "blockchain.get_quarantine" => self.blockchain_get_quarantine(req.id, req.params).await,
             "blockchain.last_confirmed_block" => self.blockchain_last_confirmed_block(req.id, req.params).await,
             "blockchain.best_fork_next_block_height" => self.blockchain_best_fork_next_block_height(req.id, req.params).await,
             "blockchain.block_target" => self.blockchain_block_target(req.id, req.params).await,
             "blockchain.lookup_zkas" => self.blockchain_lookup_zkas(req.id, req.params).await,
             "blockchain.get_contract_state" => self.blockchain_get_contract_state(req.id, req.params).await,
             "blockchain.get_contract_state_key" => self.blockchain_get_contract_state_key(req.id, req.params).await,
+            "blockchain.get_token_supply" => self.blockchain_get_token_supply(req.id, req.params).await,
+            "blockchain.get_state_diff" => self.blockchain_get_state_diff(req.id, req.params).await,
+            "blockchain.export_nullifiers" => self.blockchain_export_nullifiers(req.id, req.params).await,
+            "blockchain.verify_nullifiers" => self.blockchain_verify_nullifiers(req.id, req.params).await,
             "blockchain.subscribe_blocks" => self.blockchain_subscribe_blocks(req.id, req.params).await,
             "blockchain.subscribe_txs" =>  self.blockchain_subscribe_txs(req.id, req.params).await,
             "blockchain.subscribe_proposals" => self.blockchain_subscribe_proposals(req.id, req.params).await,
 
+            // ===========
+            // DAO methods
+            // ===========
+            "dao.subscribe_proposals" => self.dao_subscribe_proposals(req.id, req.params).await,
+            "dao.subscribe_votes" => self.dao_subscribe_votes(req.id, req.params).await,
+
             // ===================
             // Transaction methods
             // ===================
@@ -116,6 +144,11 @@ impl RequestHandler<DefaultRpcHandler> for DarkfiNode {
             "tx.clean_pending" => self.tx_clean_pending(req.id, req.params).await,
             "tx.calculate_fee" => self.tx_calculate_fee(req.id, req.params).await,
 
+            // ===========
+            // Fee methods
+            // ===========
+            "fee.estimate" => self.fee_estimate(req.id, req.params).await,
+
             // ==============
             // Invalid method
             // ==============
@@ -265,8 +298,12 @@ impl DarkfiNode {
                 sleep(10).await;
                 // Create a new client
                 let mut rpc_client = self.rpc_client.as_ref().unwrap().lock().await;
-                let Ok(client) =
-                    RpcChadClient::new(rpc_client.endpoint.clone(), rpc_client.ex.clone()).await
+                let Ok(client) = RpcChadClient::with_token(
+                    rpc_client.endpoint.clone(),
+                    rpc_client.token.clone(),
+                    rpc_client.ex.clone(),
+                )
+                .await
                 else {
                     error!(target: "darkfid::rpc::miner_daemon_request_with_retry", "Failed to initialize miner daemon rpc client, check if minerd is running");
                     drop(rpc_client);
@@ -285,4 +322,28 @@ impl HandlerP2p for DarkfiNode {
     fn p2p(&self) -> P2pPtr {
         self.p2p_handler.p2p.clone()
     }
+
+    fn p2p_events(&self) -> Option<JsonSubscriber> {
+        self.subscribers.get("p2p").cloned()
+    }
+}
+
+#[async_trait]
+impl HandlerHealth for DarkfiNode {
+    fn health_version(&self) -> (&'static str, &'static str) {
+        (env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    }
+
+    async fn health_status(&self) -> HealthStatus {
+        HealthStatus {
+            synced: *self.validator.synced.read().await,
+            peers: self.p2p_handler.p2p.hosts().channels().len(),
+            db_ok: self.validator.blockchain.last().is_ok(),
+        }
+    }
+
+    async fn health_chain_tip(&self) -> Option<(u32, String)> {
+        let (height, hash) = self.validator.blockchain.last().ok()?;
+        Some((height, hash.to_string()))
+    }
 }
@@ -16,25 +16,41 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::str::FromStr;
+use std::{collections::HashMap, str::FromStr};
 
+use darkfi_money_contract::{model::TokenId, MONEY_CONTRACT_TOKEN_SUPPLY_TREE};
 use darkfi_sdk::{
-    crypto::contract_id::{ContractId, SMART_CONTRACT_ZKAS_DB_NAME},
+    crypto::{
+        contract_id::{ContractId, SMART_CONTRACT_ZKAS_DB_NAME},
+        MONEY_CONTRACT_ID,
+    },
     tx::TransactionHash,
+    AsHex,
 };
 use darkfi_serial::{deserialize_async, serialize_async};
 use log::{debug, error};
 use tinyjson::JsonValue;
 
 use darkfi::{
+    blockchain::{contract_store::ZkasRecord, tx_store::DailyTxMetrics},
     rpc::jsonrpc::{
         ErrorCode::{InternalError, InvalidParams, ParseError},
         JsonError, JsonResponse, JsonResult,
     },
     util::encoding::base64,
+    Error,
+};
+
+use crate::{
+    nullifiers::{export_nullifier_set, verify_nullifier_set, NullifierSetExport},
+    server_error, DarkfiNode, RpcError,
 };
 
-use crate::{server_error, DarkfiNode, RpcError};
+/// Max amount of blocks `blockchain.get_state_diff` can go back
+const STATE_DIFF_MAX_DEPTH: u32 = 1000;
+
+/// Max amount of records `blockchain.get_state_diff` returns
+const STATE_DIFF_MAX_RECORDS: usize = 10000;
 
 impl DarkfiNode {
     // RPCAPI:
@@ -118,6 +134,237 @@ impl DarkfiNode {
         JsonResponse::new(JsonValue::String(tx_enc), id).into()
     }
 
+    // RPCAPI:
+    // Queries the metrics recorded when verifying the transactions of the
+    // block in the given height. Metrics are local to this node, and only
+    // exist for transactions it verified itself, so the producer transaction
+    // and transactions of blocks applied without verification have none.
+    //
+    // **Params:**
+    // * `array[0]`: `u32` Block height (as string)
+    //
+    // **Returns:**
+    // * Vector of optional [`TxMetrics`](https://darkrenaissance.github.io/darkfi/dev/darkfi/blockchain/tx_store/struct.TxMetrics.html)
+    //   structs, in the block transactions order, serialized into base64.
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.get_block_tx_metrics", "params": ["0"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": "ABCD...", "id": 1}
+    pub async fn blockchain_get_block_tx_metrics(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let Ok(block_height) = params[0].get::<String>().unwrap().parse::<u32>() else {
+            return JsonError::new(ParseError, None, id).into()
+        };
+
+        let blockchain = &self.validator.blockchain;
+        let block = match blockchain.blocks.get_order(&[block_height], true) {
+            Ok(hashes) => blockchain.blocks.get(&[hashes[0].unwrap()], true),
+            Err(Error::BlockHeightNotFound(_)) => {
+                return server_error(RpcError::UnknownBlockHeight, id, None)
+            }
+            Err(e) => Err(e),
+        };
+        let block = match block {
+            Ok(mut blocks) => blocks.remove(0).unwrap(),
+            Err(e) => {
+                error!(target: "darkfid::rpc::blockchain_get_block_tx_metrics", "Failed fetching block by height: {e}");
+                return JsonError::new(InternalError, None, id).into()
+            }
+        };
+
+        let metrics = match blockchain.transactions.get_metrics(&block.txs) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::blockchain_get_block_tx_metrics", "Failed fetching txs metrics: {e}");
+                return JsonError::new(InternalError, None, id).into()
+            }
+        };
+
+        let metrics = base64::encode(&serialize_async(&metrics).await);
+        JsonResponse::new(JsonValue::String(metrics), id).into()
+    }
+
+    // RPCAPI:
+    // Queries the transactions metrics aggregated over all the blocks
+    // added in the given day, counted in days since the UNIX epoch using
+    // the block timestamps. Days without any blocks return an empty
+    // aggregate.
+    //
+    // **Params:**
+    // * `array[0]`: `u64` Day index (as string)
+    //
+    // **Returns:**
+    // * [`DailyTxMetrics`](https://darkrenaissance.github.io/darkfi/dev/darkfi/blockchain/tx_store/struct.DailyTxMetrics.html)
+    //   struct serialized into base64.
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.get_daily_tx_metrics", "params": ["20000"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": "ABCD...", "id": 1}
+    pub async fn blockchain_get_daily_tx_metrics(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let Ok(day) = params[0].get::<String>().unwrap().parse::<u64>() else {
+            return JsonError::new(ParseError, None, id).into()
+        };
+
+        let metrics = match self.validator.blockchain.transactions.get_daily_metrics(day) {
+            Ok(v) => v.unwrap_or_else(DailyTxMetrics::default),
+            Err(e) => {
+                error!(target: "darkfid::rpc::blockchain_get_daily_tx_metrics", "Failed fetching daily txs metrics: {e}");
+                return JsonError::new(InternalError, None, id).into()
+            }
+        };
+
+        let metrics = base64::encode(&serialize_async(&metrics).await);
+        JsonResponse::new(JsonValue::String(metrics), id).into()
+    }
+
+    // RPCAPI:
+    // Queries the gaps recorded before the blocks in the given inclusive
+    // height range. A gap is recorded when a block arrives several block
+    // targets after its predecessor, along with the proposals this node
+    // observed in between. Blocks without a gap are omitted.
+    //
+    // **Params:**
+    // * `array[0]`: `u32` Start height (as string)
+    // * `array[1]`: `u32` End height (as string)
+    //
+    // **Returns:**
+    // * Vector of (`u32` height, [`BlockGap`](https://darkrenaissance.github.io/darkfi/dev/darkfi/blockchain/block_store/struct.BlockGap.html))
+    //   tuples serialized into base64.
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.get_block_gaps", "params": ["0", "1000"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": "ABCD...", "id": 1}
+    pub async fn blockchain_get_block_gaps(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 2 || !params[0].is_string() || !params[1].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let Ok(start) = params[0].get::<String>().unwrap().parse::<u32>() else {
+            return JsonError::new(ParseError, None, id).into()
+        };
+        let Ok(end) = params[1].get::<String>().unwrap().parse::<u32>() else {
+            return JsonError::new(ParseError, None, id).into()
+        };
+        if start > end {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let gaps = match self.validator.blockchain.blocks.get_gaps(start, end) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::blockchain_get_block_gaps", "Failed fetching block gaps: {e}");
+                return JsonError::new(InternalError, None, id).into()
+            }
+        };
+
+        let gaps = base64::encode(&serialize_async(&gaps).await);
+        JsonResponse::new(JsonValue::String(gaps), id).into()
+    }
+
+    // RPCAPI:
+    // Queries the progress of the background integrity scrubber, which
+    // re-hashes the stored blocks and transactions against their keys.
+    // Corrupt records get quarantined and their blocks are fetched again
+    // from peers.
+    //
+    // **Params:**
+    // * `None`
+    //
+    // **Returns:**
+    // * `height`        : Height the current pass has reached
+    // * `target`        : Chain height when the current pass started
+    // * `passes`        : Completed passes over the whole chain
+    // * `blocks_checked`: Blocks checked since startup
+    // * `restored`      : Blocks restored from peers since startup
+    // * `pending`       : Heights of quarantined blocks awaiting restoration
+    // * `findings`      : Most recent corrupt records found
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.scrubber_status", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"height": 1234, "target": 5678, ...}, "id": 1}
+    pub async fn blockchain_scrubber_status(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let state = self.scrubber.lock().await;
+        let pending =
+            state.pending.iter().map(|(height, _)| JsonValue::Number(*height as f64)).collect();
+        let findings = state
+            .findings
+            .iter()
+            .map(|f| {
+                JsonValue::Object(HashMap::from([
+                    ("height".to_string(), JsonValue::Number(f.height as f64)),
+                    ("tree".to_string(), JsonValue::String(f.tree.clone())),
+                    ("key".to_string(), JsonValue::String(f.key.hex())),
+                    ("corruption".to_string(), JsonValue::String(f.corruption.name().to_string())),
+                ]))
+            })
+            .collect();
+
+        let status = HashMap::from([
+            ("height".to_string(), JsonValue::Number(state.height as f64)),
+            ("target".to_string(), JsonValue::Number(state.target as f64)),
+            ("passes".to_string(), JsonValue::Number(state.passes as f64)),
+            ("blocks_checked".to_string(), JsonValue::Number(state.blocks_checked as f64)),
+            ("restored".to_string(), JsonValue::Number(state.restored as f64)),
+            ("pending".to_string(), JsonValue::Array(pending)),
+            ("findings".to_string(), JsonValue::Array(findings)),
+        ]);
+
+        JsonResponse::new(JsonValue::Object(status), id).into()
+    }
+
+    // RPCAPI:
+    // Queries the records the integrity scrubber quarantined for blocks
+    // in the given inclusive height range, including their raw bytes.
+    //
+    // **Params:**
+    // * `array[0]`: `u32` Start height (as string)
+    // * `array[1]`: `u32` End height (as string)
+    //
+    // **Returns:**
+    // * Vector of [`QuarantineRecord`](https://darkrenaissance.github.io/darkfi/dev/darkfi/blockchain/scrubber/struct.QuarantineRecord.html)
+    //   serialized into base64.
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.get_quarantine", "params": ["0", "1000"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": "ABCD...", "id": 1}
+    pub async fn blockchain_get_quarantine(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 2 || !params[0].is_string() || !params[1].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let Ok(start) = params[0].get::<String>().unwrap().parse::<u32>() else {
+            return JsonError::new(ParseError, None, id).into()
+        };
+        let Ok(end) = params[1].get::<String>().unwrap().parse::<u32>() else {
+            return JsonError::new(ParseError, None, id).into()
+        };
+        if start > end {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let records = match self.validator.blockchain.get_quarantined(start, end) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::blockchain_get_quarantine", "Failed fetching quarantine records: {e}");
+                return JsonError::new(InternalError, None, id).into()
+            }
+        };
+
+        let records = base64::encode(&serialize_async(&records).await);
+        JsonResponse::new(JsonValue::String(records), id).into()
+    }
+
     // RPCAPI:
     // Queries the blockchain database to find the last confirmed block.
     //
@@ -247,6 +494,37 @@ impl DarkfiNode {
         self.subscribers.get("proposals").unwrap().clone().into()
     }
 
+    // RPCAPI:
+    // Initializes a subscription to DAO proposals lifecycle events found in
+    // confirmed blocks. The event is either `proposed` or `executed`. Since
+    // votes are encrypted, a proposal passing is only observable once it
+    // gets executed.
+    //
+    // --> {"jsonrpc": "2.0", "method": "dao.subscribe_proposals", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "method": "dao.subscribe_proposals", "params": [`event`, `proposal_bulla`, `tx_hash`, `height`]}
+    pub async fn dao_subscribe_proposals(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        self.subscribers.get("dao_proposals").unwrap().clone().into()
+    }
+
+    // RPCAPI:
+    // Initializes a subscription to DAO votes found in confirmed blocks.
+    //
+    // --> {"jsonrpc": "2.0", "method": "dao.subscribe_votes", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "method": "dao.subscribe_votes", "params": [`proposal_bulla`, `tx_hash`, `height`]}
+    pub async fn dao_subscribe_votes(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        self.subscribers.get("dao_votes").unwrap().clone().into()
+    }
+
     // RPCAPI:
     // Performs a lookup of zkas bincodes for a given contract ID and returns all of
     // them, including their namespace.
@@ -298,12 +576,12 @@ impl DarkfiNode {
                 return JsonError::new(InternalError, None, id).into()
             };
 
-            let (zkbin, _): (Vec<u8>, Vec<u8>) = match deserialize_async(&zkas_bytes).await {
-                Ok(pair) => pair,
+            let record = match ZkasRecord::from_bytes(&zkas_bytes) {
+                Ok(record) => record,
                 Err(_) => return JsonError::new(InternalError, None, id).into(),
             };
 
-            let zkas_bincode = base64::encode(&zkbin);
+            let zkas_bincode = base64::encode(&record.zkbin);
             ret.push(JsonValue::Array(vec![
                 JsonValue::String(zkas_ns),
                 JsonValue::String(zkas_bincode),
@@ -418,4 +696,194 @@ impl DarkfiNode {
             }
         }
     }
+
+    // RPCAPI:
+    // Queries the Money contract state for the supply of a given token.
+    // Returns the supply policy declared by the token's first mint, along
+    // with the total amount minted so far. The minted amount is only tracked
+    // for fixed supply tokens, since reissuable mints keep their value private.
+    //
+    // **Params:**
+    // * `array[0]`: base58-encoded token ID string
+    //
+    // **Returns:**
+    // * `TokenSupplyInfo` struct serialized into base64
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.get_token_supply", "params": ["BZHK..."], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": "ABCD...", "id": 1}
+    pub async fn blockchain_get_token_supply(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let token_id = match TokenId::from_str(params[0].get::<String>().unwrap()) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::blockchain_get_token_supply", "Error decoding string to TokenId: {e}");
+                return JsonError::new(InvalidParams, None, id).into()
+            }
+        };
+
+        match self.validator.blockchain.contracts.get_state_tree_value(
+            &self.validator.blockchain.sled_db,
+            &MONEY_CONTRACT_ID,
+            MONEY_CONTRACT_TOKEN_SUPPLY_TREE,
+            &serialize_async(&token_id).await,
+        ) {
+            Ok(value) => JsonResponse::new(JsonValue::String(base64::encode(&value)), id).into(),
+            Err(e) => {
+                debug!(target: "darkfid::rpc::blockchain_get_token_supply", "Token {token_id} supply not found: {e}");
+                server_error(
+                    RpcError::ContractStateKeyNotFound,
+                    id,
+                    Some("Token has not been minted"),
+                )
+            }
+        }
+    }
+
+    // RPCAPI:
+    // Debugging method computing the contract state records that differ
+    // between two block heights, rebuilt from the stored state inverse
+    // diffs. The lower height can be at most `STATE_DIFF_MAX_DEPTH` blocks
+    // behind the last one, and at most `STATE_DIFF_MAX_RECORDS` records
+    // are returned, with the `truncated` flag set if more exist.
+    //
+    // **Params:**
+    // * `array[0]`: `u32` Lower block height (as string)
+    // * `array[1]`: `u32` Upper block height (as string)
+    //
+    // **Returns:**
+    // * [`StateDiff`](https://darkrenaissance.github.io/darkfi/dev/darkfi/blockchain/state_diff/struct.StateDiff.html)
+    //   struct serialized into base64.
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.get_state_diff", "params": ["10", "12"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": "ABCD...", "id": 1}
+    pub async fn blockchain_get_state_diff(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 2 || !params[0].is_string() || !params[1].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let Ok(from) = params[0].get::<String>().unwrap().parse::<u32>() else {
+            return JsonError::new(ParseError, None, id).into()
+        };
+        let Ok(to) = params[1].get::<String>().unwrap().parse::<u32>() else {
+            return JsonError::new(ParseError, None, id).into()
+        };
+
+        let last = match self.validator.blockchain.last() {
+            Ok((v, _)) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::blockchain_get_state_diff", "Failed fetching last block height: {e}");
+                return JsonError::new(InternalError, None, id).into()
+            }
+        };
+        if last.saturating_sub(from) > STATE_DIFF_MAX_DEPTH {
+            return JsonError::new(
+                InvalidParams,
+                Some(format!("Lower height must be within {STATE_DIFF_MAX_DEPTH} blocks")),
+                id,
+            )
+            .into()
+        }
+
+        let blockchain = self.validator.blockchain.clone();
+        let diff = smol::unblock(move || blockchain.state_diff(from, to, STATE_DIFF_MAX_RECORDS));
+        match diff.await {
+            Ok(diff) => JsonResponse::new(
+                JsonValue::String(base64::encode(&serialize_async(&diff).await)),
+                id,
+            )
+            .into(),
+            Err(e @ Error::InvalidStateDiffRange(..)) |
+            Err(e @ Error::BlockStateInverseDiffNotFound(_)) => {
+                server_error(RpcError::UnknownBlockHeight, id, Some(&e.to_string()))
+            }
+            Err(e) => {
+                error!(target: "darkfid::rpc::blockchain_get_state_diff", "Failed computing state diff: {e}");
+                JsonError::new(InternalError, None, id).into()
+            }
+        }
+    }
+
+    // RPCAPI:
+    // Maintenance method exporting the full nullifier set of the canonical
+    // chain, along with an accumulator commitment recomputed from genesis
+    // and the nullifiers SMT root. Walks the entire chain, so it is slow
+    // and only available to admin connections.
+    //
+    // **Params:**
+    // * `None`
+    //
+    // **Returns:**
+    // * Serialized [`NullifierSetExport`] object encoded with base64
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.export_nullifiers", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": "ABCD...", "id": 1}
+    pub async fn blockchain_export_nullifiers(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let blockchain = self.validator.blockchain.clone();
+        let export = match smol::unblock(move || export_nullifier_set(&blockchain)).await {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::blockchain_export_nullifiers", "Failed exporting nullifier set: {e}");
+                return JsonError::new(InternalError, None, id).into()
+            }
+        };
+
+        let export_enc = base64::encode(&serialize_async(&export).await);
+        JsonResponse::new(JsonValue::String(export_enc), id).into()
+    }
+
+    // RPCAPI:
+    // Maintenance method verifying an exported nullifier set against the
+    // node's headers and the `Money` contract state. Walks the entire
+    // chain, so it is only available to admin connections.
+    //
+    // **Params:**
+    // * `array[0]`: base64-encoded serialized [`NullifierSetExport`] object
+    //
+    // **Returns:**
+    // * `true` if the export is consistent, otherwise an error describing
+    //   the first inconsistency found
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.verify_nullifiers", "params": ["ABCD..."], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": true, "id": 1}
+    pub async fn blockchain_verify_nullifiers(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let export_enc = params[0].get::<String>().unwrap().trim();
+        let Some(export_bytes) = base64::decode(export_enc) else {
+            error!(target: "darkfid::rpc::blockchain_verify_nullifiers", "Failed decoding base64 export");
+            return server_error(RpcError::ParseError, id, None)
+        };
+        let export: NullifierSetExport = match deserialize_async(&export_bytes).await {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::blockchain_verify_nullifiers", "Failed deserializing export: {e}");
+                return server_error(RpcError::ParseError, id, None)
+            }
+        };
+
+        let blockchain = self.validator.blockchain.clone();
+        match smol::unblock(move || verify_nullifier_set(&blockchain, &export)).await {
+            Ok(()) => JsonResponse::new(JsonValue::Boolean(true), id).into(),
+            Err(Error::NullifierSetMismatch(msg)) => {
+                server_error(RpcError::NullifierSetMismatch, id, Some(&msg))
+            }
+            Err(e) => {
+                error!(target: "darkfid::rpc::blockchain_verify_nullifiers", "Failed verifying nullifier set: {e}");
+                JsonError::new(InternalError, None, id).into()
+            }
+        }
+    }
 }
@@ -16,21 +16,33 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use darkfi_money_contract::client::fee_v1::{FEE_RATE_GAS_UNIT, MIN_FEE_RATE};
 use darkfi_serial::deserialize_async;
 use log::{error, warn};
 use tinyjson::JsonValue;
 
 use darkfi::{
     rpc::jsonrpc::{
-        ErrorCode::{InternalError, InvalidParams},
+        ErrorCode::{InternalError, InvalidParams, ParseError},
         JsonError, JsonResponse, JsonResult,
     },
     tx::Transaction,
     util::encoding::base64,
+    validator::consensus::BLOCK_GAS_LIMIT,
 };
 
 use super::DarkfiNode;
-use crate::{server_error, RpcError};
+use crate::{server_error, server_error_with_details, RpcError};
+
+/// Number of most recent blocks whose transactions are used for fee estimation
+const FEE_ESTIMATE_BLOCKS: usize = 100;
+
+/// Auxiliary function to retrieve the fee a transaction pays in its
+/// `Money::Fee` call, if it has one.
+async fn tx_paid_fee(tx: &Transaction) -> Option<u64> {
+    let call = tx.calls.iter().find(|call| call.data.is_money_fee())?;
+    deserialize_async(call.data.data.get(1..9)?).await.ok()
+}
 
 impl DarkfiNode {
     // RPCAPI:
@@ -70,13 +82,16 @@ impl DarkfiNode {
         };
 
         // Simulate state transition
-        let result = self.validator.append_tx(&tx, false).await;
-        if result.is_err() {
+        if let Err(e) = self.validator.append_tx(&tx, false).await {
             error!(
-                target: "darkfid::rpc::tx_simulate", "Failed to validate state transition: {}",
-                result.err().unwrap()
+                target: "darkfid::rpc::tx_simulate", "Failed to validate state transition: {e}"
             );
-            return server_error(RpcError::TxSimulationFail, id, None)
+            return server_error_with_details(
+                RpcError::TxSimulationFail,
+                id,
+                None,
+                JsonValue::String(e.to_string()),
+            )
         };
 
         JsonResponse::new(JsonValue::Boolean(true), id).into()
@@ -130,7 +145,12 @@ impl DarkfiNode {
         // We'll perform the state transition check here.
         if let Err(e) = self.validator.append_tx(&tx, self.rpc_client.is_some()).await {
             error!(target: "darkfid::rpc::tx_broadcast", "{error_message}: {e}");
-            return server_error(RpcError::TxSimulationFail, id, None)
+            return server_error_with_details(
+                RpcError::TxSimulationFail,
+                id,
+                None,
+                JsonValue::String(e.to_string()),
+            )
         };
 
         self.p2p_handler.p2p.broadcast(&tx).await;
@@ -260,4 +280,103 @@ impl DarkfiNode {
 
         JsonResponse::new(JsonValue::Number(result.unwrap() as f64), id).into()
     }
+
+    // RPCAPI:
+    // Estimate the fee rate a transaction should pay to get included within
+    // the given number of blocks. Rates are expressed in fee units paid per
+    // `FEE_RATE_GAS_UNIT` gas, and can be turned into a fee value using
+    // `darkfi_money_contract::client::fee_v1::compute_fee_value`.
+    //
+    // The rates paid by the transactions of the last `FEE_ESTIMATE_BLOCKS`
+    // blocks get sorted, and the picked percentile grows with the number of
+    // blocks the pending transactions would fill, relative to the target.
+    // When no rates were recorded, the minimum fee rate is returned.
+    //
+    // **Params:**
+    // * `array[0]`: `u32` Target number of blocks (as string)
+    //
+    // **Returns:**
+    // * `u64` Suggested fee rate
+    //
+    // --> {"jsonrpc": "2.0", "method": "fee.estimate", "params": ["3"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": 10, "id": 1}
+    pub async fn fee_estimate(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let Ok(target_blocks) = params[0].get::<String>().unwrap().parse::<u64>() else {
+            return JsonError::new(ParseError, None, id).into()
+        };
+        if target_blocks == 0 {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        if !*self.validator.synced.read().await {
+            error!(target: "darkfid::rpc::fee_estimate", "Blockchain is not synced");
+            return server_error(RpcError::NotSynced, id, None)
+        }
+
+        // Grab the rates paid by recently included transactions
+        let blockchain = &self.validator.blockchain;
+        let blocks = match blockchain.get_last_n(FEE_ESTIMATE_BLOCKS) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::fee_estimate", "Failed fetching last blocks: {e}");
+                return JsonError::new(InternalError, None, id).into()
+            }
+        };
+
+        let mut rates = vec![];
+        for block in &blocks {
+            let txs_hashes: Vec<_> = block.txs.iter().map(|tx| tx.hash()).collect();
+            let metrics = match blockchain.transactions.get_metrics(&txs_hashes) {
+                Ok(v) => v,
+                Err(e) => {
+                    error!(target: "darkfid::rpc::fee_estimate", "Failed fetching txs metrics: {e}");
+                    return JsonError::new(InternalError, None, id).into()
+                }
+            };
+
+            // Transactions applied without verification have no metrics,
+            // and the producer transaction pays no fee.
+            for (tx, metrics) in block.txs.iter().zip(metrics) {
+                let Some(metrics) = metrics else { continue };
+                if metrics.gas_used == 0 {
+                    continue
+                }
+                let Some(paid) = tx_paid_fee(tx).await else { continue };
+                rates.push(paid.saturating_mul(FEE_RATE_GAS_UNIT) / metrics.gas_used);
+            }
+        }
+
+        if rates.is_empty() {
+            return JsonResponse::new(JsonValue::Number(MIN_FEE_RATE as f64), id).into()
+        }
+        rates.sort_unstable();
+
+        // Pending transactions gas is not known before verifying them, so
+        // we use the most their paid fee can cover at the minimum rate.
+        let pending_txs = match blockchain.get_pending_txs() {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::fee_estimate", "Failed fetching pending txs: {e}");
+                return JsonError::new(InternalError, None, id).into()
+            }
+        };
+        let mut pending_gas: u64 = 0;
+        for tx in &pending_txs {
+            let paid = tx_paid_fee(tx).await.unwrap_or_default();
+            pending_gas =
+                pending_gas.saturating_add(paid.saturating_mul(FEE_RATE_GAS_UNIT) / MIN_FEE_RATE);
+        }
+        let pending_blocks = pending_gas.div_ceil(BLOCK_GAS_LIMIT);
+
+        // Pick the percentile of the recent rates
+        let percentile = (pending_blocks * 100 / target_blocks).min(100) as usize;
+        let rate = rates[(rates.len() - 1) * percentile / 100].max(MIN_FEE_RATE);
+
+        JsonResponse::new(JsonValue::Number(rate as f64), id).into()
+    }
 }
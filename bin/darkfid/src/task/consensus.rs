@@ -48,6 +48,7 @@ pub struct ConsensusInitTaskConfig {
     pub spend_hook: Option<String>,
     pub user_data: Option<String>,
     pub bootstrap: u64,
+    pub scrubber: bool,
 }
 
 /// Sync the node consensus state and start the corresponding task, based on node type.
@@ -0,0 +1,119 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{blockchain::BlockInfo, util::encoding::base64, Result};
+use darkfi_dao_contract::{
+    model::{DaoExecParams, DaoProposeParams, DaoVoteParams},
+    DaoFunction,
+};
+use darkfi_sdk::crypto::DAO_CONTRACT_ID;
+use darkfi_serial::deserialize_async;
+use log::{debug, error, info};
+use tinyjson::JsonValue;
+
+use crate::DarkfiNodePtr;
+
+/// Async task watching confirmed blocks for DAO contract calls, and
+/// pushing proposal lifecycle events to the `dao.subscribe_proposals`
+/// and `dao.subscribe_votes` JSON-RPC subscribers.
+///
+/// Votes are encrypted, so a proposal passing is only observable once
+/// it gets executed, which is reported as the `executed` event.
+pub async fn dao_events_task(node: DarkfiNodePtr) -> Result<()> {
+    info!(target: "darkfid::task::dao_events_task", "Starting DAO events task...");
+
+    let blocks_sub = node.subscribers.get("blocks").unwrap().publisher.clone().subscribe().await;
+    let proposals_sub = node.subscribers.get("dao_proposals").unwrap();
+    let votes_sub = node.subscribers.get("dao_votes").unwrap();
+
+    loop {
+        let notification = blocks_sub.receive().await;
+        let Some(encoded_blocks) = notification.params.get::<Vec<JsonValue>>() else { continue };
+
+        for encoded_block in encoded_blocks {
+            let Some(encoded_block) = encoded_block.get::<String>() else { continue };
+            let Some(bytes) = base64::decode(encoded_block) else {
+                error!(target: "darkfid::task::dao_events_task", "Failed decoding block notification");
+                continue
+            };
+            let block: BlockInfo = match deserialize_async(&bytes).await {
+                Ok(b) => b,
+                Err(e) => {
+                    error!(target: "darkfid::task::dao_events_task", "Failed deserializing block: {e}");
+                    continue
+                }
+            };
+
+            for tx in &block.txs {
+                let tx_hash = JsonValue::String(tx.hash().to_string());
+                let height = JsonValue::Number(block.header.height as f64);
+
+                for call in &tx.calls {
+                    if call.data.contract_id != *DAO_CONTRACT_ID || call.data.data.is_empty() {
+                        continue
+                    }
+
+                    let params = &call.data.data[1..];
+                    let (event, bulla, is_vote) = match DaoFunction::try_from(call.data.data[0]) {
+                        Ok(DaoFunction::Propose) => {
+                            let Ok(p) = deserialize_async::<DaoProposeParams>(params).await else {
+                                continue
+                            };
+                            ("proposed", p.proposal_bulla, false)
+                        }
+                        Ok(DaoFunction::Vote) => {
+                            let Ok(p) = deserialize_async::<DaoVoteParams>(params).await else {
+                                continue
+                            };
+                            ("voted", p.proposal_bulla, true)
+                        }
+                        Ok(DaoFunction::Exec) => {
+                            let Ok(p) = deserialize_async::<DaoExecParams>(params).await else {
+                                continue
+                            };
+                            ("executed", p.proposal_bulla, false)
+                        }
+                        _ => continue,
+                    };
+
+                    debug!(
+                        target: "darkfid::task::dao_events_task",
+                        "Proposal {bulla} {event} in tx {}", tx.hash()
+                    );
+
+                    let bulla = JsonValue::String(bulla.to_string());
+                    if is_vote {
+                        votes_sub
+                            .notify(JsonValue::Array(vec![bulla, tx_hash.clone(), height.clone()]))
+                            .await;
+                        continue
+                    }
+
+                    proposals_sub
+                        .notify(JsonValue::Array(vec![
+                            JsonValue::String(event.to_string()),
+                            bulla,
+                            tx_hash.clone(),
+                            height.clone(),
+                        ]))
+                        .await;
+                }
+            }
+        }
+    }
+}
@@ -16,16 +16,18 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::sync::Arc;
+
 use darkfi::{
-    blockchain::{BlockInfo, Header, HeaderHash},
+    blockchain::{BlockInfo, HeaderHash},
     rpc::{jsonrpc::JsonNotification, util::JsonValue},
     system::{ExecutorPtr, StoppableTask, Subscription},
     tx::{ContractCallLeaf, Transaction, TransactionBuilder},
-    util::{encoding::base64, time::Timestamp},
+    util::encoding::base64,
     validator::{
         consensus::{Fork, Proposal},
+        template::{BlockTemplateBuilder, TxSelectionPolicy},
         utils::best_fork_index,
-        verification::apply_producer_transaction,
     },
     zk::{empty_witnesses, ProvingKey, ZkCircuit},
     zkas::ZkBinary,
@@ -35,7 +37,7 @@ use darkfi_money_contract::{
     client::pow_reward_v1::PoWRewardCallBuilder, MoneyFunction, MONEY_CONTRACT_ZKAS_MINT_NS_V1,
 };
 use darkfi_sdk::{
-    crypto::{poseidon_hash, FuncId, MerkleTree, PublicKey, SecretKey, MONEY_CONTRACT_ID},
+    crypto::{poseidon_hash, FuncId, PublicKey, SecretKey, MONEY_CONTRACT_ID},
     pasta::pallas,
     ContractCall,
 };
@@ -322,6 +324,7 @@ async fn mine_next_block(
         pk,
         node.validator.consensus.module.read().await.target,
         node.validator.verify_fees,
+        node.tx_selection.clone(),
     )
     .await?;
 
@@ -355,6 +358,7 @@ async fn mine_next_block(
 }
 
 /// Auxiliary function to generate next block in an atomic manner.
+#[allow(clippy::too_many_arguments)]
 async fn generate_next_block(
     extended_fork: &mut Fork,
     secret: &mut SecretKey,
@@ -363,17 +367,16 @@ async fn generate_next_block(
     pk: &ProvingKey,
     block_target: u32,
     verify_fees: bool,
+    tx_selection: Arc<dyn TxSelectionPolicy>,
 ) -> Result<(BigUint, BlockInfo)> {
-    // Grab forks' last block proposal(previous)
-    let last_proposal = extended_fork.last_proposal()?;
+    let builder = BlockTemplateBuilder::new(extended_fork, block_target, verify_fees)
+        .with_policy(tx_selection);
 
     // Grab forks' next block height
-    let next_block_height = last_proposal.block.header.height + 1;
+    let next_block_height = builder.next_height()?;
 
     // Grab forks' unproposed transactions
-    let (mut txs, _, fees, overlay) = extended_fork
-        .unproposed_txs(&extended_fork.blockchain, next_block_height, block_target, verify_fees)
-        .await?;
+    let selection = builder.select_txs().await?;
 
     // We are deriving the next secret key for optimization.
     // Next secret is the poseidon hash of:
@@ -383,43 +386,19 @@ async fn generate_next_block(
     *secret = SecretKey::from(next_secret);
 
     // Generate reward transaction
-    let tx = generate_transaction(next_block_height, fees, secret, recipient_config, zkbin, pk)?;
-
-    // Apply producer transaction in the overlay
-    let _ = apply_producer_transaction(
-        &overlay,
+    let tx = generate_transaction(
         next_block_height,
-        block_target,
-        &tx,
-        &mut MerkleTree::new(1),
-    )
-    .await?;
-    txs.push(tx);
-
-    // Grab the updated contracts states root
-    overlay.lock().unwrap().contracts.update_state_monotree(&mut extended_fork.state_monotree)?;
-    let Some(state_root) = extended_fork.state_monotree.get_headroot()? else {
-        return Err(Error::ContractsStatesRootNotFoundError);
-    };
-
-    // Drop new trees opened by the unproposed transactions overlay
-    overlay.lock().unwrap().overlay.lock().unwrap().purge_new_trees()?;
-
-    // Generate the new header
-    let mut header =
-        Header::new(last_proposal.hash, next_block_height, Timestamp::current_time(), 0);
-    header.state_root = state_root;
-
-    // Generate the block
-    let mut next_block = BlockInfo::new_empty(header);
-
-    // Add transactions to the block
-    next_block.append_txs(txs);
+        selection.fees,
+        secret,
+        recipient_config,
+        zkbin,
+        pk,
+    )?;
 
-    // Grab the next mine target
-    let target = extended_fork.module.next_mine_target()?;
+    // Generate the block template
+    let template = builder.build(selection, tx).await?;
 
-    Ok((target, next_block))
+    Ok((template.target, template.block))
 }
 
 /// Auxiliary function to generate a Money::PoWReward transaction.
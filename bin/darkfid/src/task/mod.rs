@@ -30,3 +30,9 @@ pub use unknown_proposal::handle_unknown_proposals;
 
 pub mod garbage_collect;
 pub use garbage_collect::garbage_collect_task;
+
+pub mod dao_events;
+pub use dao_events::dao_events_task;
+
+pub mod scrubber;
+pub use scrubber::{scrubber_task, ScrubberState};
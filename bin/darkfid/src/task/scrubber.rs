@@ -0,0 +1,228 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{
+    blockchain::{HeaderHash, ScrubFinding, SLED_BLOCK_ORDER_TREE},
+    system::sleep,
+    Result,
+};
+use darkfi_serial::deserialize;
+use log::{debug, error, info, warn};
+
+use crate::{
+    proto::{SyncRequest, SyncResponse, BATCH},
+    DarkfiNodePtr,
+};
+
+/// Blocks checked per scrubber batch
+const SCRUB_BATCH: u32 = 10;
+/// Seconds to pause between batches, keeping the disk load low
+const SCRUB_PAUSE: u64 = 1;
+/// Seconds to wait between passes over the whole chain
+const SCRUB_PASS_INTERVAL: u64 = 3600;
+/// Number of most recent findings kept for reporting
+const MAX_FINDINGS: usize = 100;
+
+/// Progress and findings of the integrity scrubber
+#[derive(Default)]
+pub struct ScrubberState {
+    /// Height the current pass has reached
+    pub height: u32,
+    /// Chain height when the current pass started
+    pub target: u32,
+    /// Completed passes over the whole chain
+    pub passes: u64,
+    /// Blocks checked since startup
+    pub blocks_checked: u64,
+    /// Most recent corrupt records found
+    pub findings: Vec<ScrubFinding>,
+    /// Quarantined blocks waiting to be restored from peers
+    pub pending: Vec<(u32, HeaderHash)>,
+    /// Blocks restored from peers since startup
+    pub restored: u64,
+}
+
+/// Async task periodically re-hashing the stored blockchain records,
+/// quarantining the corrupt ones and restoring their blocks from peers.
+/// Errors are logged and the affected block skipped, so a single bad
+/// record can't stop the scrubber.
+pub async fn scrubber_task(node: DarkfiNodePtr) -> Result<()> {
+    info!(target: "darkfid::task::scrubber_task", "Starting blockchain integrity scrubber...");
+
+    loop {
+        let target = match node.validator.blockchain.last() {
+            Ok((target, _)) => target,
+            Err(e) => {
+                error!(target: "darkfid::task::scrubber_task", "Failed retrieving last block: {e}");
+                sleep(SCRUB_PASS_INTERVAL).await;
+                continue
+            }
+        };
+        {
+            let mut state = node.scrubber.lock().await;
+            state.height = 0;
+            state.target = target;
+        }
+
+        let mut height = 0;
+        while height <= target {
+            let end = (height + SCRUB_BATCH - 1).min(target);
+            for h in height..=end {
+                if let Err(e) = scrub_height(&node, h).await {
+                    error!(target: "darkfid::task::scrubber_task", "Failed scrubbing block {h}: {e}");
+                }
+            }
+
+            {
+                let mut state = node.scrubber.lock().await;
+                state.height = end;
+                state.blocks_checked += (end - height + 1) as u64;
+            }
+
+            // Try restoring quarantined blocks while peers are around
+            restore_pending(&node).await;
+
+            sleep(SCRUB_PAUSE).await;
+            height = end + 1;
+        }
+
+        let mut state = node.scrubber.lock().await;
+        state.passes += 1;
+        info!(
+            target: "darkfid::task::scrubber_task",
+            "Scrubber pass #{} finished, {} blocks awaiting restoration",
+            state.passes, state.pending.len(),
+        );
+        drop(state);
+
+        sleep(SCRUB_PASS_INTERVAL).await;
+    }
+}
+
+/// Check the records of a block, quarantining the corrupt ones.
+/// The validator append lock is held throughout, so we never race
+/// with blocks getting appended to the canonical chain.
+async fn scrub_height(node: &DarkfiNodePtr, height: u32) -> Result<()> {
+    let _append_lock = node.validator.consensus.append_lock.write().await;
+
+    let findings = node.validator.blockchain.scrub_height(height)?;
+    if findings.is_empty() {
+        return Ok(())
+    }
+
+    quarantine(node, height, findings).await
+}
+
+/// Quarantine the corrupt records of a block and queue it for restoration
+async fn quarantine(node: &DarkfiNodePtr, height: u32, findings: Vec<ScrubFinding>) -> Result<()> {
+    for finding in &findings {
+        warn!(
+            target: "darkfid::task::scrubber",
+            "Block {height} has a {} record in {}", finding.corruption.name(), finding.tree,
+        );
+    }
+
+    // We need the block hash to ask peers for it, which we don't have
+    // when the order record itself is the corrupt one.
+    let hash = match node.validator.blockchain.blocks.order.get(height.to_be_bytes())? {
+        Some(bytes) => deserialize::<HeaderHash>(&bytes).ok(),
+        None => None,
+    };
+    let order_corrupt = findings.iter().any(|f| f.tree.as_bytes() == SLED_BLOCK_ORDER_TREE);
+
+    node.validator.blockchain.quarantine(&findings)?;
+
+    let mut state = node.scrubber.lock().await;
+    match hash {
+        Some(hash) if !order_corrupt => {
+            if !state.pending.iter().any(|(h, _)| *h == height) {
+                state.pending.push((height, hash));
+            }
+        }
+        _ => warn!(
+            target: "darkfid::task::scrubber",
+            "Block {height} order record is corrupt, it can't be restored from peers"
+        ),
+    }
+
+    state.findings.extend(findings);
+    let excess = state.findings.len().saturating_sub(MAX_FINDINGS);
+    state.findings.drain(..excess);
+
+    Ok(())
+}
+
+/// Ask our peers for the quarantined blocks and restore the ones
+/// matching our records.
+async fn restore_pending(node: &DarkfiNodePtr) {
+    // Avoid interfering with the sync task responses
+    if node.p2p_handler.p2p.is_syncing() {
+        return
+    }
+
+    let pending: Vec<HeaderHash> =
+        node.scrubber.lock().await.pending.iter().take(BATCH).map(|(_, h)| *h).collect();
+    if pending.is_empty() {
+        return
+    }
+
+    let comms_timeout = node.p2p_handler.p2p.settings().read().await.outbound_connect_timeout;
+    for peer in node.p2p_handler.p2p.hosts().channels() {
+        let Ok(response_sub) = peer.subscribe_msg::<SyncResponse>().await else {
+            debug!(target: "darkfid::task::scrubber::restore_pending", "Failure during `SyncResponse` communication setup with peer: {peer:?}");
+            continue
+        };
+
+        let request = SyncRequest { headers: pending.clone() };
+        if let Err(e) = peer.send(&request).await {
+            debug!(target: "darkfid::task::scrubber::restore_pending", "Failure during `SyncRequest` send to peer {peer:?}: {e}");
+            continue
+        };
+
+        let Ok(response) = response_sub.receive_with_timeout(comms_timeout).await else {
+            debug!(target: "darkfid::task::scrubber::restore_pending", "Timeout while waiting for `SyncResponse` from peer: {peer:?}");
+            continue
+        };
+
+        let mut state = node.scrubber.lock().await;
+        for block in &response.blocks {
+            let hash = block.hash();
+            let Some(index) = state.pending.iter().position(|(_, h)| *h == hash) else { continue };
+
+            let append_lock = node.validator.consensus.append_lock.write().await;
+            let restored = node.validator.blockchain.restore_block(block);
+            drop(append_lock);
+
+            if let Err(e) = restored {
+                debug!(target: "darkfid::task::scrubber::restore_pending", "Peer {peer:?} sent an unusable copy of block {hash}: {e}");
+                continue
+            }
+
+            info!(
+                target: "darkfid::task::scrubber::restore_pending",
+                "Restored quarantined block {} from peer", block.header.height,
+            );
+            state.pending.remove(index);
+            state.restored += 1;
+        }
+
+        if state.pending.is_empty() {
+            return
+        }
+    }
+}
@@ -41,6 +41,7 @@ use crate::{
 /// A checkpoint can be provided to ensure node syncs the correct sequence.
 pub async fn sync_task(node: &DarkfiNodePtr, checkpoint: Option<(u32, HeaderHash)>) -> Result<()> {
     info!(target: "darkfid::task::sync_task", "Starting blockchain sync...");
+    node.p2p_handler.p2p.set_syncing(true);
 
     // Grab blocks subscriber
     let block_sub = node.subscribers.get("blocks").unwrap();
@@ -77,6 +78,7 @@ pub async fn sync_task(node: &DarkfiNodePtr, checkpoint: Option<(u32, HeaderHash
     // further and will reorg if needed when a new proposal arrives.
     if common_tip_hash == [0u8; 32] {
         *node.validator.synced.write().await = true;
+        node.p2p_handler.p2p.set_syncing(false);
         info!(target: "darkfid::task::sync_task", "Blockchain synced!");
         return Ok(())
     }
@@ -134,6 +136,7 @@ pub async fn sync_task(node: &DarkfiNodePtr, checkpoint: Option<(u32, HeaderHash
     }
 
     *node.validator.synced.write().await = true;
+    node.p2p_handler.p2p.set_syncing(false);
     info!(target: "darkfid::task::sync_task", "Blockchain synced!");
     Ok(())
 }
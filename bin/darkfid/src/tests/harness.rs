@@ -26,6 +26,7 @@ use darkfi::{
     tx::{ContractCallLeaf, TransactionBuilder},
     validator::{
         consensus::{Fork, Proposal},
+        template::TxSelectionLimits,
         utils::deploy_native_contracts,
         verification::{apply_producer_transaction, verify_block},
         Validator, ValidatorConfig,
@@ -286,11 +287,19 @@ pub async fn generate_node(
     subscribers.insert("txs", JsonSubscriber::new("blockchain.subscribe_txs"));
     subscribers.insert("proposals", JsonSubscriber::new("blockchain.subscribe_proposals"));
     subscribers.insert("dnet", JsonSubscriber::new("dnet.subscribe_events"));
+    subscribers.insert("dao_proposals", JsonSubscriber::new("dao.subscribe_proposals"));
+    subscribers.insert("dao_votes", JsonSubscriber::new("dao.subscribe_votes"));
 
     let p2p_handler = DarkfidP2pHandler::init(settings, ex).await?;
-    let node =
-        DarkfiNode::new(p2p_handler.clone(), validator.clone(), 50, subscribers.clone(), None)
-            .await;
+    let node = DarkfiNode::new(
+        p2p_handler.clone(),
+        validator.clone(),
+        50,
+        Arc::new(TxSelectionLimits::default()),
+        subscribers.clone(),
+        None,
+    )
+    .await;
 
     p2p_handler.clone().start(ex, &validator, &subscribers).await?;
 
@@ -21,7 +21,10 @@ use std::sync::Arc;
 use darkfi::{
     net::Settings,
     rpc::settings::RpcSettings,
-    validator::{consensus::Fork, utils::best_fork_index, verification::verify_block},
+    validator::{
+        consensus::Fork, template::TxSelectionLimits, utils::best_fork_index,
+        verification::verify_block,
+    },
     Result,
 };
 use darkfi_contract_test_harness::init_logger;
@@ -41,6 +44,8 @@ mod unproposed_txs;
 
 mod metering;
 
+mod rpc_errors;
+
 async fn sync_blocks_real(ex: Arc<Executor<'static>>) -> Result<()> {
     init_logger();
 
@@ -196,6 +201,17 @@ async fn sync_blocks_real(ex: Arc<Executor<'static>>) -> Result<()> {
     assert_eq!(charlie_forks[0].diffs.len(), 2);
     assert_eq!(last_proposal, charlie_forks[0].proposals[1]);
 
+    // Confirmed blocks rewards must show up in the state diff
+    let (last_height, _) = alice.blockchain.last()?;
+    let diff = alice.blockchain.state_diff(0, last_height, usize::MAX)?;
+    assert!(!diff.records.is_empty());
+    assert!(!diff.truncated);
+    assert_eq!(diff.records, bob.blockchain.state_diff(0, last_height, usize::MAX)?.records);
+    let diff = alice.blockchain.state_diff(0, last_height, 1)?;
+    assert_eq!(diff.records.len(), 1);
+    assert!(diff.truncated);
+    assert!(alice.blockchain.state_diff(last_height, last_height, 1).is_err());
+
     // Thanks for reading
     Ok(())
 }
@@ -286,6 +302,7 @@ fn darkfid_programmatic_control() -> Result<()> {
                     spend_hook: None,
                     user_data: None,
                     bootstrap,
+                    scrubber: false,
                 };
                 let rpc_settings = RpcSettings {
                     listen: Url::parse("tcp://127.0.0.1:8240").unwrap(),
@@ -299,6 +316,8 @@ fn darkfid_programmatic_control() -> Result<()> {
                     &darkfi::net::Settings::default(),
                     &None,
                     &None,
+                    &None,
+                    Arc::new(TxSelectionLimits::default()),
                     &ex,
                 )
                 .await
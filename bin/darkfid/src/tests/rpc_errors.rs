@@ -0,0 +1,67 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::rpc::jsonrpc::{ErrorCategory, JsonError, JsonResult};
+use tinyjson::JsonValue;
+
+use crate::error::{server_error, server_error_with_details, RpcError};
+
+/// Parse an error result back from its wire representation
+fn roundtrip(result: JsonResult) -> JsonError {
+    let JsonResult::Error(error) = result else { panic!("Not a JsonResult::Error") };
+    let encoded = error.stringify().unwrap();
+    JsonError::try_from(&encoded.parse::<JsonValue>().unwrap()).unwrap()
+}
+
+#[test]
+fn rpc_error_roundtrip() {
+    // Clients match on these codes, so they must never change
+    let errors = [
+        (RpcError::TxSimulationFail, -32110, ErrorCategory::Validation),
+        (RpcError::TxGasCalculationFail, -32111, ErrorCategory::Validation),
+        (RpcError::NotSynced, -32120, ErrorCategory::Net),
+        (RpcError::UnknownBlockHeight, -32121, ErrorCategory::Validation),
+        (RpcError::NullifierSetMismatch, -32122, ErrorCategory::Internal),
+        (RpcError::ParseError, -32190, ErrorCategory::Validation),
+        (RpcError::ContractZkasDbNotFound, -32200, ErrorCategory::Validation),
+        (RpcError::ContractStateNotFound, -32201, ErrorCategory::Validation),
+        (RpcError::ContractStateKeyNotFound, -32202, ErrorCategory::Validation),
+        (RpcError::PingFailed, -32300, ErrorCategory::Net),
+    ];
+
+    for (i, (error, code, category)) in errors.into_iter().enumerate() {
+        let decoded = roundtrip(server_error(error, i as u16, None));
+        assert_eq!(decoded.id, i as u16);
+        assert_eq!(decoded.error.code, code);
+        assert_eq!(decoded.category(), Some(category));
+    }
+
+    // Custom messages and details survive the round trip
+    let details = JsonValue::String("Insufficient fee".to_string());
+    let decoded = roundtrip(server_error_with_details(
+        RpcError::TxSimulationFail,
+        42,
+        Some("Rejected"),
+        details.clone(),
+    ));
+    assert_eq!(decoded.error.code, -32110);
+    assert_eq!(decoded.error.message, "Rejected");
+    assert_eq!(decoded.category(), Some(ErrorCategory::Validation));
+    let JsonValue::Object(data) = decoded.error.data.unwrap() else { panic!("No data payload") };
+    assert_eq!(data["details"], details);
+}
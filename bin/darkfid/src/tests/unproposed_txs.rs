@@ -21,33 +21,37 @@
 //! The following are supported test cases:
 //! - Verifying the processing of unproposed transactions that are within the block transactions gas limit.
 //! - Verifying the processing of unproposed transactions that exceed the block transactions gas limit.
+//! - Verifying the selection of unproposed transactions is bounded by the configured gas, size and count limits.
 //!
 //! Please update the test to reflect any changes to the block transactions gas limit value.
 
-use darkfi::Result;
+use darkfi::{tx::Transaction, Result};
 use std::sync::Arc;
 
 use crate::tests::{Harness, HarnessConfig};
-use darkfi::validator::{consensus::BLOCK_GAS_LIMIT, utils::best_fork_index};
+use darkfi::validator::{
+    consensus::BLOCK_GAS_LIMIT, template::TxSelectionLimits, utils::best_fork_index, ValidatorPtr,
+};
 use darkfi_contract_test_harness::{init_logger, Holder, TestHarness};
 use darkfi_sdk::{crypto::BaseBlind, num_traits::One};
+use darkfi_serial::serialize;
 use num_bigint::BigUint;
 use rand::rngs::OsRng;
 use smol::Executor;
 
-/// Simulates the processing of a specified number of unproposed transactions, returning
-/// the total number of unproposed transactions and gas used.
-async fn simulate_unproposed_txs(
+/// Block height used to create and retrieve unproposed transactions
+const CURRENT_BLOCK_HEIGHT: u32 = 1;
+
+/// Creates a chain test harness, populating its validator mempool with the
+/// specified number of pending transactions.
+async fn populate_mempool(
     num_txs: u64,
     alice_url: String,
     bob_url: String,
     ex: Arc<Executor<'static>>,
-) -> Result<(u64, u64)> {
+) -> Result<Harness> {
     init_logger();
 
-    // Set current block height used to create and retrieve unproposed transactions
-    let current_block_height = 1;
-
     // Create chain test harness configuration
     let pow_target = 120;
     let pow_fixed_difficulty = Some(BigUint::one());
@@ -80,27 +84,100 @@ async fn simulate_unproposed_txs(
                 BaseBlind::random(&mut OsRng),
                 None,
                 None,
-                current_block_height,
+                CURRENT_BLOCK_HEIGHT,
             )
             .await?;
         validator.append_tx(&tx, true).await?;
     }
 
+    Ok(blockchain_test_harness)
+}
+
+/// Retrieves the unproposed transactions of the validator's best fork selected
+/// under the given limits, along with their total gas used.
+async fn select_unproposed_txs(
+    validator: &ValidatorPtr,
+    limits: &TxSelectionLimits,
+) -> Result<(Vec<Transaction>, u64)> {
     // Obtain fork
     let forks = validator.consensus.forks.read().await;
     let best_fork = &forks[best_fork_index(&forks)?];
 
     // Retrieve unproposed transactions
-    let (tx, total_gas_used, _, _) = best_fork
-        .unproposed_txs(
+    let (txs, total_gas_used, _, _) = best_fork
+        .unproposed_txs_with_policy(
             &best_fork.clone().blockchain,
-            current_block_height,
+            CURRENT_BLOCK_HEIGHT,
             validator.consensus.module.read().await.target,
             false,
+            limits,
         )
         .await?;
 
-    Ok((tx.len() as u64, total_gas_used))
+    Ok((txs, total_gas_used))
+}
+
+/// Simulates the processing of a specified number of unproposed transactions, returning
+/// the total number of unproposed transactions and gas used.
+async fn simulate_unproposed_txs(
+    num_txs: u64,
+    alice_url: String,
+    bob_url: String,
+    ex: Arc<Executor<'static>>,
+) -> Result<(u64, u64)> {
+    let harness = populate_mempool(num_txs, alice_url, bob_url, ex).await?;
+    let (txs, total_gas_used) =
+        select_unproposed_txs(&harness.alice.validator, &TxSelectionLimits::default()).await?;
+
+    Ok((txs.len() as u64, total_gas_used))
+}
+
+/// Verifies the unproposed transactions selection of a mempool holding
+/// 3 pending transactions is bounded by the configured limits.
+async fn verify_selection_limits(validator: &ValidatorPtr) -> Result<()> {
+    // Grab the gas used by each transaction, selecting them one more at a time
+    let (all_txs, _) = select_unproposed_txs(validator, &TxSelectionLimits::default()).await?;
+    assert_eq!(all_txs.len(), 3);
+    let mut cumulative_gas = vec![0];
+    for max_txs in 1..=all_txs.len() {
+        let limits = TxSelectionLimits { max_txs: Some(max_txs), ..Default::default() };
+        let (txs, gas_used) = select_unproposed_txs(validator, &limits).await?;
+        assert_eq!(txs, all_txs[..max_txs]);
+        cumulative_gas.push(gas_used);
+    }
+
+    // Reaching the gas limit exactly is fine
+    let limits = TxSelectionLimits { max_gas: cumulative_gas[2], ..Default::default() };
+    let (txs, gas_used) = select_unproposed_txs(validator, &limits).await?;
+    assert_eq!(txs, all_txs[..2]);
+    assert_eq!(gas_used, cumulative_gas[2]);
+
+    // Exceeding it ends the selection
+    let limits = TxSelectionLimits { max_gas: cumulative_gas[2] - 1, ..Default::default() };
+    let (txs, gas_used) = select_unproposed_txs(validator, &limits).await?;
+    assert_eq!(txs, all_txs[..1]);
+    assert_eq!(gas_used, cumulative_gas[1]);
+
+    // Reaching the size limit exactly is fine
+    let sizes: Vec<usize> = all_txs.iter().map(|tx| serialize(tx).len()).collect();
+    let limits = TxSelectionLimits { max_size: Some(sizes[0] + sizes[1]), ..Default::default() };
+    let (txs, _) = select_unproposed_txs(validator, &limits).await?;
+    assert_eq!(txs, all_txs[..2]);
+
+    // Transactions over the size limit are skipped
+    let min_size = *sizes.iter().min().unwrap();
+    let limits = TxSelectionLimits { max_size: Some(min_size - 1), ..Default::default() };
+    let (txs, gas_used) = select_unproposed_txs(validator, &limits).await?;
+    assert!(txs.is_empty());
+    assert_eq!(gas_used, 0);
+
+    // Fees are not verified, so no transaction pays the minimum fee
+    let limits = TxSelectionLimits { min_fee: 1, ..Default::default() };
+    let (txs, gas_used) = select_unproposed_txs(validator, &limits).await?;
+    assert!(txs.is_empty());
+    assert_eq!(gas_used, 0);
+
+    Ok(())
 }
 
 /// Tests the processing of unproposed transactions within `BLOCK_GAS_LIMIT`.
@@ -137,6 +214,37 @@ fn test_unproposed_txs_within_gas_limit() -> Result<()> {
     Ok(())
 }
 
+/// Tests the selection of unproposed transactions is bounded by the configured gas, size and
+/// transactions count limits, and skips transactions paying less than the minimum fee.
+#[test]
+fn test_unproposed_txs_selection_limits() -> Result<()> {
+    let ex = Arc::new(Executor::new());
+    let (signal, shutdown) = smol::channel::unbounded::<()>();
+
+    easy_parallel::Parallel::new().each(0..1, |_| smol::block_on(ex.run(shutdown.recv()))).finish(
+        || {
+            smol::block_on(async {
+                let harness = populate_mempool(
+                    3,
+                    "tcp+tls://127.0.0.1:18740".to_string(),
+                    "tcp+tls://127.0.0.1:18741".to_string(),
+                    ex.clone(),
+                )
+                .await
+                .unwrap();
+
+                // Verify test result
+                verify_selection_limits(&harness.alice.validator).await.unwrap();
+
+                // Shutdown spawned nodes
+                signal.send(()).await.unwrap();
+            });
+        },
+    );
+
+    Ok(())
+}
+
 /// Tests the processing of unproposed transactions with a mempool of transactions that collectively exceed `BLOCK_GAS_LIMIT`.
 ///
 /// Note: In this test scenario, the mempool is populated by pending transactions with an average gas usage of 9_851_647 gas.
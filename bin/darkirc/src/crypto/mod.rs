@@ -26,3 +26,6 @@ pub mod bcrypt;
 
 /// Rate-Limit nullifiers
 pub mod rln;
+
+/// X3DH-style key agreement and Double Ratchet, used for DM sessions.
+pub mod ratchet;
@@ -0,0 +1,385 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! X3DH-style key agreement and the Double Ratchet, used for
+//! end-to-end encrypted direct messages.
+//!
+//! Every party has a long-term identity key and a signed prekey,
+//! published together as a [`KeyBundle`]. Whoever starts a session
+//! combines the bundle with a fresh ephemeral key into a shared secret,
+//! see [`x3dh_initiate`] and [`x3dh_respond`]. The shared secret seeds
+//! a [`Ratchet`], which derives a new key for every message and mixes
+//! in a new Diffie-Hellman exchange every time the direction of the
+//! conversation changes, giving forward secrecy and break-in recovery.
+//!
+//! References:
+//! * <https://signal.org/docs/specifications/x3dh/>
+//! * <https://signal.org/docs/specifications/doubleratchet/>
+
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305,
+};
+use darkfi_sdk::crypto::{
+    diffie_hellman::sapling_ka_agree,
+    schnorr::{SchnorrPublic, SchnorrSecret, Signature},
+    PublicKey, SecretKey,
+};
+use darkfi_serial::{async_trait, serialize, SerialDecodable, SerialEncodable};
+use rand::rngs::OsRng;
+
+/// Maximum number of message keys kept around for messages
+/// which have not arrived yet
+pub const MAX_SKIP: u32 = 256;
+
+const PREKEY_CONTEXT: &[u8] = b"DarkIRC DM prekey";
+const X3DH_CONTEXT: &str = "DarkIRC 2025-01 DM X3DH";
+const ROOT_CONTEXT: &str = "DarkIRC 2025-01 DM ratchet root";
+
+/// Identity key and signed prekey of a party
+#[derive(Clone, Debug, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct KeyBundle {
+    /// Long-term identity public key
+    pub identity: PublicKey,
+    /// Prekey public key, signed by the identity key
+    pub prekey: PublicKey,
+    /// Identity signature over the prekey
+    pub signature: Signature,
+}
+
+impl KeyBundle {
+    pub fn new(identity: &SecretKey, prekey: &SecretKey) -> Self {
+        let prekey = PublicKey::from_secret(*prekey);
+        let signature = identity.sign(&[PREKEY_CONTEXT, &prekey.to_bytes()].concat());
+        Self { identity: PublicKey::from_secret(*identity), prekey, signature }
+    }
+
+    /// Verify the prekey was signed by the identity key
+    pub fn verify(&self) -> bool {
+        self.identity.verify(&[PREKEY_CONTEXT, &self.prekey.to_bytes()].concat(), &self.signature)
+    }
+}
+
+fn dh(secret: &SecretKey, public: &PublicKey) -> Option<[u8; 32]> {
+    sapling_ka_agree(secret, public).ok().map(|shared| shared.to_bytes())
+}
+
+fn x3dh_secret(dh1: &[u8; 32], dh2: &[u8; 32], dh3: &[u8; 32]) -> [u8; 32] {
+    blake3::derive_key(X3DH_CONTEXT, &[&dh1[..], dh2, dh3].concat())
+}
+
+/// Start a session with the owner of the given bundle. Returns the
+/// shared secret along with the ephemeral public key the other party
+/// needs to derive it, or `None` if the bundle is invalid.
+pub fn x3dh_initiate(identity: &SecretKey, bundle: &KeyBundle) -> Option<([u8; 32], PublicKey)> {
+    if !bundle.verify() {
+        return None
+    }
+
+    let ephemeral = SecretKey::random(&mut OsRng);
+    let dh1 = dh(identity, &bundle.prekey)?;
+    let dh2 = dh(&ephemeral, &bundle.identity)?;
+    let dh3 = dh(&ephemeral, &bundle.prekey)?;
+
+    Some((x3dh_secret(&dh1, &dh2, &dh3), PublicKey::from_secret(ephemeral)))
+}
+
+/// Derive the shared secret of a session started from our bundle
+pub fn x3dh_respond(
+    identity: &SecretKey,
+    prekey: &SecretKey,
+    their_identity: &PublicKey,
+    their_ephemeral: &PublicKey,
+) -> Option<[u8; 32]> {
+    let dh1 = dh(prekey, their_identity)?;
+    let dh2 = dh(identity, their_ephemeral)?;
+    let dh3 = dh(prekey, their_ephemeral)?;
+
+    Some(x3dh_secret(&dh1, &dh2, &dh3))
+}
+
+/// Root KDF, returning the new root key and a new chain key
+fn kdf_root(root_key: &[u8; 32], dh_out: &[u8; 32]) -> ([u8; 32], [u8; 32]) {
+    let mut out = [0u8; 64];
+    blake3::Hasher::new_derive_key(ROOT_CONTEXT)
+        .update(root_key)
+        .update(dh_out)
+        .finalize_xof()
+        .fill(&mut out);
+
+    (out[..32].try_into().unwrap(), out[32..].try_into().unwrap())
+}
+
+/// Chain KDF, returning the next chain key and a message key
+fn kdf_chain(chain_key: &[u8; 32]) -> ([u8; 32], [u8; 32]) {
+    let next = blake3::keyed_hash(chain_key, &[0x02]);
+    let message_key = blake3::keyed_hash(chain_key, &[0x01]);
+    (*next.as_bytes(), *message_key.as_bytes())
+}
+
+// Message keys are only ever used once, so a zero nonce is fine.
+fn seal(message_key: &[u8; 32], header: &MessageHeader, plaintext: &[u8]) -> Vec<u8> {
+    let payload = Payload { msg: plaintext, aad: &serialize(header) };
+    ChaCha20Poly1305::new(message_key.as_slice().into())
+        .encrypt([0u8; 12][..].into(), payload)
+        .unwrap()
+}
+
+fn open(message_key: &[u8; 32], header: &MessageHeader, ciphertext: &[u8]) -> Option<Vec<u8>> {
+    let payload = Payload { msg: ciphertext, aad: &serialize(header) };
+    ChaCha20Poly1305::new(message_key.as_slice().into()).decrypt([0u8; 12][..].into(), payload).ok()
+}
+
+/// Header sent in the clear along with every ratchet message
+#[derive(Clone, Debug, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct MessageHeader {
+    /// Current ratchet public key of the sender
+    pub dh: PublicKey,
+    /// Number of messages in the previous sending chain
+    pub pn: u32,
+    /// Message number in the current sending chain
+    pub n: u32,
+}
+
+/// Message encrypted by a [`Ratchet`]
+#[derive(Clone, Debug, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct RatchetMessage {
+    pub header: MessageHeader,
+    pub ciphertext: Vec<u8>,
+}
+
+/// Message key of a message which has not arrived yet
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+struct SkippedKey {
+    dh: PublicKey,
+    n: u32,
+    key: [u8; 32],
+}
+
+/// Double Ratchet session state
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct Ratchet {
+    dh_self: SecretKey,
+    dh_remote: Option<PublicKey>,
+    root_key: [u8; 32],
+    send_chain: Option<[u8; 32]>,
+    recv_chain: Option<[u8; 32]>,
+    send_n: u32,
+    recv_n: u32,
+    prev_n: u32,
+    skipped: Vec<SkippedKey>,
+}
+
+impl Ratchet {
+    /// Session of the party which ran [`x3dh_initiate`], using the
+    /// other party's prekey as their first ratchet key.
+    pub fn initiator(secret: [u8; 32], remote_prekey: PublicKey) -> Option<Self> {
+        let dh_self = SecretKey::random(&mut OsRng);
+        let (root_key, send_chain) = kdf_root(&secret, &dh(&dh_self, &remote_prekey)?);
+
+        Some(Self {
+            dh_self,
+            dh_remote: Some(remote_prekey),
+            root_key,
+            send_chain: Some(send_chain),
+            recv_chain: None,
+            send_n: 0,
+            recv_n: 0,
+            prev_n: 0,
+            skipped: vec![],
+        })
+    }
+
+    /// Session of the party which ran [`x3dh_respond`]. It can only
+    /// send once the first message of the initiator arrived.
+    pub fn responder(secret: [u8; 32], prekey: SecretKey) -> Self {
+        Self {
+            dh_self: prekey,
+            dh_remote: None,
+            root_key: secret,
+            send_chain: None,
+            recv_chain: None,
+            send_n: 0,
+            recv_n: 0,
+            prev_n: 0,
+            skipped: vec![],
+        }
+    }
+
+    /// Whether the session is able to send messages yet
+    pub fn can_send(&self) -> bool {
+        self.send_chain.is_some()
+    }
+
+    /// Encrypt a message, advancing the sending chain
+    pub fn encrypt(&mut self, plaintext: &[u8]) -> Option<RatchetMessage> {
+        let (chain, message_key) = kdf_chain(&self.send_chain?);
+        self.send_chain = Some(chain);
+
+        let header = MessageHeader {
+            dh: PublicKey::from_secret(self.dh_self),
+            pn: self.prev_n,
+            n: self.send_n,
+        };
+        self.send_n += 1;
+
+        let ciphertext = seal(&message_key, &header, plaintext);
+        Some(RatchetMessage { header, ciphertext })
+    }
+
+    /// Decrypt a message. The session state is only updated when
+    /// decryption succeeds, so garbage can't desync the session.
+    pub fn decrypt(&mut self, message: &RatchetMessage) -> Option<Vec<u8>> {
+        let mut state = self.clone();
+        let plaintext = state.try_decrypt(message)?;
+        *self = state;
+        Some(plaintext)
+    }
+
+    fn try_decrypt(&mut self, message: &RatchetMessage) -> Option<Vec<u8>> {
+        let header = &message.header;
+
+        // The message might be one we skipped earlier
+        if let Some(i) = self.skipped.iter().position(|k| k.dh == header.dh && k.n == header.n) {
+            let skipped = self.skipped.remove(i);
+            return open(&skipped.key, header, &message.ciphertext)
+        }
+
+        // A new ratchet key from the other party means they got our
+        // messages, so we step the Diffie-Hellman ratchet.
+        if self.dh_remote != Some(header.dh) {
+            self.skip(header.pn)?;
+            self.dh_ratchet(&header.dh)?;
+        }
+
+        self.skip(header.n)?;
+        let (chain, message_key) = kdf_chain(&self.recv_chain?);
+        self.recv_chain = Some(chain);
+        self.recv_n += 1;
+
+        open(&message_key, header, &message.ciphertext)
+    }
+
+    /// Store the message keys of the current receiving chain up to `until`
+    fn skip(&mut self, until: u32) -> Option<()> {
+        if self.recv_n.saturating_add(MAX_SKIP) < until {
+            return None
+        }
+
+        if let (Some(mut chain), Some(dh)) = (self.recv_chain, self.dh_remote) {
+            while self.recv_n < until {
+                let (next, key) = kdf_chain(&chain);
+                self.skipped.push(SkippedKey { dh, n: self.recv_n, key });
+                chain = next;
+                self.recv_n += 1;
+            }
+            self.recv_chain = Some(chain);
+        }
+
+        // Forget the oldest skipped keys once there are too many
+        let excess = self.skipped.len().saturating_sub(MAX_SKIP as usize);
+        self.skipped.drain(..excess);
+
+        Some(())
+    }
+
+    fn dh_ratchet(&mut self, remote: &PublicKey) -> Option<()> {
+        self.prev_n = self.send_n;
+        self.send_n = 0;
+        self.recv_n = 0;
+        self.dh_remote = Some(*remote);
+
+        let (root_key, recv_chain) = kdf_root(&self.root_key, &dh(&self.dh_self, remote)?);
+        self.dh_self = SecretKey::random(&mut OsRng);
+        let (root_key, send_chain) = kdf_root(&root_key, &dh(&self.dh_self, remote)?);
+
+        self.root_key = root_key;
+        self.recv_chain = Some(recv_chain);
+        self.send_chain = Some(send_chain);
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> (Ratchet, Ratchet) {
+        let alice_identity = SecretKey::random(&mut OsRng);
+        let bob_identity = SecretKey::random(&mut OsRng);
+        let bob_prekey = SecretKey::random(&mut OsRng);
+        let bundle = KeyBundle::new(&bob_identity, &bob_prekey);
+
+        let (alice_secret, ephemeral) = x3dh_initiate(&alice_identity, &bundle).unwrap();
+        let bob_secret = x3dh_respond(
+            &bob_identity,
+            &bob_prekey,
+            &PublicKey::from_secret(alice_identity),
+            &ephemeral,
+        )
+        .unwrap();
+        assert_eq!(alice_secret, bob_secret);
+
+        let alice = Ratchet::initiator(alice_secret, bundle.prekey).unwrap();
+        let bob = Ratchet::responder(bob_secret, bob_prekey);
+        (alice, bob)
+    }
+
+    #[test]
+    fn x3dh_rejects_forged_bundle() {
+        let identity = SecretKey::random(&mut OsRng);
+        let mut bundle = KeyBundle::new(&identity, &SecretKey::random(&mut OsRng));
+        assert!(bundle.verify());
+
+        bundle.prekey = PublicKey::from_secret(SecretKey::random(&mut OsRng));
+        assert!(!bundle.verify());
+        assert!(x3dh_initiate(&identity, &bundle).is_none());
+    }
+
+    #[test]
+    fn ratchet_conversation() {
+        let (mut alice, mut bob) = session();
+        assert!(!bob.can_send());
+        assert!(bob.encrypt(b"too early").is_none());
+
+        // Messages arriving out of order
+        let m0 = alice.encrypt(b"hi bob").unwrap();
+        let m1 = alice.encrypt(b"are you there?").unwrap();
+        assert_eq!(bob.decrypt(&m1).unwrap(), b"are you there?");
+        assert_eq!(bob.decrypt(&m0).unwrap(), b"hi bob");
+
+        // Replays and tampering are rejected without breaking the session
+        assert!(bob.decrypt(&m0).is_none());
+        let mut tampered = alice.encrypt(b"pay me").unwrap();
+        tampered.header.n += 1;
+        assert!(bob.decrypt(&tampered).is_none());
+
+        // Back and forth, stepping the DH ratchet
+        for i in 0..3 {
+            let msg = bob.encrypt(format!("pong {i}").as_bytes()).unwrap();
+            assert_eq!(alice.decrypt(&msg).unwrap(), format!("pong {i}").as_bytes());
+            let msg = alice.encrypt(format!("ping {i}").as_bytes()).unwrap();
+            assert_eq!(bob.decrypt(&msg).unwrap(), format!("ping {i}").as_bytes());
+        }
+
+        // Too many skipped messages
+        let mut msg = alice.encrypt(b"far ahead").unwrap();
+        msg.header.n += MAX_SKIP + 1;
+        assert!(bob.decrypt(&msg).is_none());
+    }
+}
@@ -18,7 +18,7 @@
 
 use crypto_box::{
     aead::{Aead, AeadCore},
-    ChaChaBox,
+    ChaChaBox, PublicKey, SecretKey,
 };
 use rand::rngs::OsRng;
 
@@ -57,3 +57,31 @@ pub fn try_decrypt(salt_box: &ChaChaBox, ciphertext: &[u8]) -> Option<Vec<u8>> {
 
     salt_box.decrypt((&ciphertext[0..24]).into(), &ciphertext[24..]).ok()
 }
+
+/// Encrypt given data to the given public key, using a fresh ephemeral
+/// secret key, so the recipient can decrypt it without knowing anything
+/// about the sender. Returns base58-encoded string of the ciphertext.
+/// Panics if encryption fails.
+///
+/// The format is `ephemeral_public||nonce||ciphertext`, where the ephemeral
+/// public key is 32 bytes large and the nonce is 24 bytes large.
+pub fn seal(public: &PublicKey, plaintext: &[u8]) -> String {
+    // Create a one-time box for the recipient
+    let ephemeral = SecretKey::generate(&mut OsRng);
+    let salt_box = ChaChaBox::new(public, &ephemeral);
+
+    // Generate the nonce
+    let nonce = ChaChaBox::generate_nonce(&mut OsRng);
+
+    // Encrypt
+    let mut ciphertext = salt_box.encrypt(&nonce, plaintext).unwrap();
+
+    // Concatenate
+    let mut concat = Vec::with_capacity(32 + 24 + ciphertext.len());
+    concat.extend_from_slice(ephemeral.public_key().as_bytes());
+    concat.extend_from_slice(nonce.as_slice());
+    concat.append(&mut ciphertext);
+
+    // Encode
+    bs58::encode(concat).into_string()
+}
@@ -0,0 +1,404 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! End-to-end encrypted direct messages.
+//!
+//! Two nicks agree on a session over the event graph with an X3DH-style
+//! handshake, and then exchange messages encrypted with the Double
+//! Ratchet, see [`crate::crypto::ratchet`]:
+//!
+//! 1. Alice runs `DM bob <bob identity>`, publishing an `Offer` with her
+//!    key bundle.
+//! 2. Bob runs `DM alice <alice identity>` to accept it, which publishes
+//!    an `Accept` with his ephemeral key and a first ratchet message.
+//! 3. Both sides now hold a session and exchange `Message`s.
+//!
+//! Every payload is sealed to the recipient identity key, and sent as a
+//! `Privmsg` whose fields look like any other encrypted message.
+//! Ratchet messages can only be decrypted once, so decrypted messages
+//! are kept by event ID, which is also how our own sent messages show
+//! up in the history.
+
+use std::collections::HashMap;
+
+use darkfi::{
+    event_graph::{Event, EventGraph},
+    Error, Result,
+};
+use darkfi_sdk::crypto::{note::AeadEncryptedNote, Keypair, PublicKey, SecretKey};
+use darkfi_serial::{async_trait, deserialize, serialize, SerialDecodable, SerialEncodable};
+use log::{debug, info, warn};
+use rand::{rngs::OsRng, RngCore};
+use sled_overlay::sled;
+use smol::lock::Mutex;
+
+use crate::{
+    crypto::ratchet::{x3dh_initiate, x3dh_respond, KeyBundle, Ratchet, RatchetMessage},
+    irc::OldPrivmsg,
+};
+
+/// Payload of a direct message event, sealed to the recipient
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+enum DmPayload {
+    /// Request to start a session
+    Offer { nick: String, bundle: KeyBundle },
+    /// Answer to an offer, starting the session
+    Accept { nick: String, identity: PublicKey, ephemeral: PublicKey, message: RatchetMessage },
+    /// Message within a session
+    Message { identity: PublicKey, message: RatchetMessage },
+}
+
+/// A DM contact and the state of our session with them
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct DmContact {
+    /// Identity public key of the contact
+    pub identity: PublicKey,
+    /// Established session, if any
+    pub session: Option<Ratchet>,
+    /// Pending offer of the contact, waiting for us to accept it
+    pub offer: Option<KeyBundle>,
+    /// Whether the session was started by us accepting their offer
+    pub accepted: bool,
+}
+
+/// A decrypted direct message
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct DmMessage {
+    /// Nick of the contact the conversation is with
+    pub contact: String,
+    /// Whether we sent the message
+    pub from_self: bool,
+    /// Message text
+    pub msg: String,
+}
+
+/// Result of the `DM` command
+pub enum DmStart {
+    /// An offer was created, waiting for the contact to accept it
+    Offered(Event),
+    /// The contact's offer was accepted and the session established
+    Accepted(Event),
+}
+
+/// Store of DM contacts, sessions and decrypted messages
+pub struct DmStore {
+    /// Our identity keypair
+    identity: Keypair,
+    /// Our signed prekey
+    prekey: Keypair,
+    /// Contacts keyed by nick
+    contacts: sled::Tree,
+    /// Decrypted messages keyed by event ID
+    messages: sled::Tree,
+    /// Serializes session updates
+    lock: Mutex<()>,
+}
+
+impl DmStore {
+    /// Open the DM store, generating our identity and prekey on first use
+    pub fn new(sled_db: &sled::Db) -> Result<Self> {
+        let keys = sled_db.open_tree("dm_keys")?;
+        let mut load_or_create = |name: &str| -> Result<Keypair> {
+            if let Some(bytes) = keys.get(name)? {
+                return Ok(Keypair::new(deserialize::<SecretKey>(&bytes)?))
+            }
+            let keypair = Keypair::random(&mut OsRng);
+            keys.insert(name, serialize(&keypair.secret))?;
+            Ok(keypair)
+        };
+
+        let identity = load_or_create("identity")?;
+        let prekey = load_or_create("prekey")?;
+        info!(target: "darkirc::dm", "DM identity: {}", identity.public);
+
+        Ok(Self {
+            identity,
+            prekey,
+            contacts: sled_db.open_tree("dm_contacts")?,
+            messages: sled_db.open_tree("dm_messages")?,
+            lock: Mutex::new(()),
+        })
+    }
+
+    /// Our identity public key, to be shared with contacts
+    pub fn identity(&self) -> PublicKey {
+        self.identity.public
+    }
+
+    /// All contacts keyed by nick
+    pub fn contacts(&self) -> Result<HashMap<String, DmContact>> {
+        let mut ret = HashMap::new();
+        for item in self.contacts.iter() {
+            let (nick, contact) = item?;
+            ret.insert(String::from_utf8_lossy(&nick).to_string(), deserialize(&contact)?);
+        }
+        Ok(ret)
+    }
+
+    fn contact(&self, nick: &str) -> Result<Option<DmContact>> {
+        match self.contacts.get(nick)? {
+            Some(bytes) => Ok(Some(deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn contact_by_identity(&self, identity: &PublicKey) -> Result<Option<(String, DmContact)>> {
+        Ok(self.contacts()?.into_iter().find(|(_, contact)| contact.identity == *identity))
+    }
+
+    fn set_contact(&self, nick: &str, contact: &DmContact) -> Result<()> {
+        self.contacts.insert(nick, serialize(contact))?;
+        Ok(())
+    }
+
+    /// Whether we know the given nick as a DM contact
+    pub fn is_contact(&self, nick: &str) -> bool {
+        self.contacts.contains_key(nick).unwrap_or(false)
+    }
+
+    /// Whether we can send messages to the given nick
+    pub fn can_send(&self, nick: &str) -> bool {
+        matches!(self.contact(nick), Ok(Some(DmContact { session: Some(ref s), .. })) if s.can_send())
+    }
+
+    /// Seal a payload to the recipient and wrap it into a `Privmsg`
+    /// looking like any other encrypted message.
+    fn seal(recipient: &PublicKey, payload: &DmPayload) -> Result<OldPrivmsg> {
+        let Ok(note) = AeadEncryptedNote::encrypt(payload, recipient, &mut OsRng) else {
+            return Err(Error::Custom("Failed encrypting DM payload".into()))
+        };
+
+        let dummy = || {
+            let mut bytes = [0u8; 64];
+            OsRng.fill_bytes(&mut bytes);
+            bs58::encode(bytes).into_string()
+        };
+
+        Ok(OldPrivmsg {
+            channel: dummy(),
+            nick: dummy(),
+            msg: bs58::encode(serialize(&note)).into_string(),
+        })
+    }
+
+    async fn to_event(privmsg: &OldPrivmsg, event_graph: &EventGraph) -> Event {
+        Event::new(serialize(privmsg), event_graph).await
+    }
+
+    /// Start a DM session with `nick`. If they already sent us an offer,
+    /// it gets accepted, otherwise we send them ours.
+    pub async fn start(
+        &self,
+        nick: &str,
+        identity: Option<PublicKey>,
+        our_nick: &str,
+        event_graph: &EventGraph,
+    ) -> Result<DmStart> {
+        let _lock = self.lock.lock().await;
+        let existing = self.contact(nick)?;
+
+        // Accept a pending offer
+        if let Some(DmContact { identity: their_identity, offer: Some(bundle), .. }) = &existing {
+            if identity.is_none() || identity == Some(*their_identity) {
+                let Some((secret, ephemeral)) = x3dh_initiate(&self.identity.secret, bundle) else {
+                    return Err(Error::Custom("Invalid DM offer".into()))
+                };
+                let Some(mut session) = Ratchet::initiator(secret, bundle.prekey) else {
+                    return Err(Error::Custom("Invalid DM offer".into()))
+                };
+                let Some(message) = session.encrypt(&[]) else {
+                    return Err(Error::Custom("Invalid DM offer".into()))
+                };
+
+                let payload = DmPayload::Accept {
+                    nick: our_nick.to_string(),
+                    identity: self.identity.public,
+                    ephemeral,
+                    message,
+                };
+                let event =
+                    Self::to_event(&Self::seal(their_identity, &payload)?, event_graph).await;
+
+                let contact = DmContact {
+                    identity: *their_identity,
+                    session: Some(session),
+                    offer: None,
+                    accepted: true,
+                };
+                self.set_contact(nick, &contact)?;
+                info!(target: "darkirc::dm", "Accepted DM offer of {nick}");
+                return Ok(DmStart::Accepted(event))
+            }
+        }
+
+        let Some(identity) = identity.or(existing.map(|c| c.identity)) else {
+            return Err(Error::Custom("Unknown DM identity".into()))
+        };
+
+        let bundle = KeyBundle::new(&self.identity.secret, &self.prekey.secret);
+        let payload = DmPayload::Offer { nick: our_nick.to_string(), bundle };
+        let event = Self::to_event(&Self::seal(&identity, &payload)?, event_graph).await;
+
+        let contact = DmContact { identity, session: None, offer: None, accepted: false };
+        self.set_contact(nick, &contact)?;
+        info!(target: "darkirc::dm", "Sent DM offer to {nick}");
+        Ok(DmStart::Offered(event))
+    }
+
+    /// Encrypt a message to a contact we have a session with
+    pub async fn encrypt(&self, nick: &str, msg: &str, event_graph: &EventGraph) -> Result<Event> {
+        let _lock = self.lock.lock().await;
+        let Some(mut contact) = self.contact(nick)? else {
+            return Err(Error::Custom(format!("No DM session with {nick}")))
+        };
+        let Some(message) = contact.session.as_mut().and_then(|s| s.encrypt(msg.as_bytes())) else {
+            return Err(Error::Custom(format!("No DM session with {nick}")))
+        };
+
+        let payload = DmPayload::Message { identity: self.identity.public, message };
+        let event = Self::to_event(&Self::seal(&contact.identity, &payload)?, event_graph).await;
+        self.set_contact(nick, &contact)?;
+
+        // We can't decrypt our own ratchet messages, so remember it
+        let dm = DmMessage { contact: nick.to_string(), from_self: true, msg: msg.to_string() };
+        self.messages.insert(event.id().as_bytes(), serialize(&dm))?;
+
+        Ok(event)
+    }
+
+    /// Try to decrypt a direct message event. Each event goes through
+    /// the ratchet once, later calls return the stored result.
+    pub async fn decrypt(
+        &self,
+        event_id: &blake3::Hash,
+        ciphertext: &[u8],
+    ) -> Result<Option<DmMessage>> {
+        let _lock = self.lock.lock().await;
+        if let Some(bytes) = self.messages.get(event_id.as_bytes())? {
+            return Ok(Some(deserialize(&bytes)?))
+        }
+
+        let Ok(note) = deserialize::<AeadEncryptedNote>(ciphertext) else { return Ok(None) };
+        let Ok(payload) = note.decrypt::<DmPayload>(&self.identity.secret) else { return Ok(None) };
+
+        let dm = match payload {
+            DmPayload::Offer { nick, bundle } => self.handle_offer(nick, bundle)?,
+            DmPayload::Accept { nick, identity, ephemeral, message } => {
+                self.handle_accept(nick, identity, ephemeral, message)?
+            }
+            DmPayload::Message { identity, message } => self.handle_message(identity, message)?,
+        };
+
+        if let Some(dm) = &dm {
+            self.messages.insert(event_id.as_bytes(), serialize(dm))?;
+        }
+
+        Ok(dm)
+    }
+
+    fn handle_offer(&self, nick: String, bundle: KeyBundle) -> Result<Option<DmMessage>> {
+        if !bundle.verify() {
+            warn!(target: "darkirc::dm", "Received DM offer with an invalid bundle");
+            return Ok(None)
+        }
+
+        // Keep the nick we already know the identity under
+        let (nick, contact) = match self.contact_by_identity(&bundle.identity)? {
+            Some((nick, contact)) => (nick, Some(contact)),
+            None => {
+                if self.is_contact(&nick) {
+                    warn!(target: "darkirc::dm", "Ignoring DM offer using the nick of a known contact: {nick}");
+                    return Ok(None)
+                }
+                (nick, None)
+            }
+        };
+
+        let contact = DmContact {
+            identity: bundle.identity,
+            session: contact.and_then(|c| c.session),
+            offer: Some(bundle.clone()),
+            accepted: false,
+        };
+        self.set_contact(&nick, &contact)?;
+
+        debug!(target: "darkirc::dm", "Received DM offer from {nick}");
+        let msg = format!(
+            "*** DM request from {nick} ({}). Use `/DM {nick}` to accept it.",
+            bundle.identity
+        );
+        Ok(Some(DmMessage { contact: nick, from_self: false, msg }))
+    }
+
+    fn handle_accept(
+        &self,
+        nick: String,
+        identity: PublicKey,
+        ephemeral: PublicKey,
+        message: RatchetMessage,
+    ) -> Result<Option<DmMessage>> {
+        // We only take accepts for offers we sent
+        let Some((nick, contact)) = self.contact_by_identity(&identity)? else {
+            warn!(target: "darkirc::dm", "Ignoring unexpected DM accept from {nick}");
+            return Ok(None)
+        };
+
+        // If both sides accepted each other's offer at the same time,
+        // the accept of the smaller identity wins on both ends.
+        if contact.accepted && self.identity.public.to_bytes() < identity.to_bytes() {
+            debug!(target: "darkirc::dm", "Keeping our DM session with {nick}");
+            return Ok(None)
+        }
+
+        let Some(secret) =
+            x3dh_respond(&self.identity.secret, &self.prekey.secret, &identity, &ephemeral)
+        else {
+            return Ok(None)
+        };
+        let mut session = Ratchet::responder(secret, self.prekey.secret);
+        if session.decrypt(&message).is_none() {
+            warn!(target: "darkirc::dm", "Failed decrypting DM accept from {nick}");
+            return Ok(None)
+        }
+
+        let contact = DmContact { identity, session: Some(session), offer: None, accepted: false };
+        self.set_contact(&nick, &contact)?;
+
+        info!(target: "darkirc::dm", "DM session with {nick} established");
+        let msg = format!("*** DM session with {nick} established.");
+        Ok(Some(DmMessage { contact: nick, from_self: false, msg }))
+    }
+
+    fn handle_message(
+        &self,
+        identity: PublicKey,
+        message: RatchetMessage,
+    ) -> Result<Option<DmMessage>> {
+        let Some((nick, mut contact)) = self.contact_by_identity(&identity)? else {
+            return Ok(None)
+        };
+        let Some(plaintext) = contact.session.as_mut().and_then(|s| s.decrypt(&message)) else {
+            warn!(target: "darkirc::dm", "Failed decrypting DM from {nick}");
+            return Ok(None)
+        };
+        self.set_contact(&nick, &contact)?;
+
+        let msg = String::from_utf8_lossy(&plaintext).to_string();
+        Ok(Some(DmMessage { contact: nick, from_self: false, msg }))
+    }
+}
@@ -0,0 +1,160 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! IRCv3 `CHATHISTORY` support
+//!
+//! Lets clients fetch messages of a channel or contact from the local
+//! DAG, see <https://ircv3.net/specs/extensions/chathistory>.
+//! Only `timestamp=` message references are supported, since our
+//! messages carry no IDs the clients would know about.
+
+use darkfi::util::time::DateTime;
+
+/// Maximum number of messages returned for a single request.
+/// Advertised to clients in `RPL_ISUPPORT`.
+pub const CHATHISTORY_LIMIT: usize = 100;
+
+/// A message found in the DAG for a given target
+pub struct HistoryMessage {
+    /// Event timestamp in milliseconds
+    pub timestamp: u64,
+    /// Sender nickname
+    pub nick: String,
+    /// Message line
+    pub line: String,
+}
+
+/// A `CHATHISTORY` subcommand along with its message references
+#[derive(Debug, PartialEq)]
+pub enum Selector {
+    /// `LATEST <target> <* | timestamp> <limit>`
+    Latest(Option<u64>),
+    /// `BEFORE <target> <timestamp> <limit>`
+    Before(u64),
+    /// `AFTER <target> <timestamp> <limit>`
+    After(u64),
+    /// `BETWEEN <target> <timestamp> <timestamp> <limit>`
+    Between(u64, u64),
+}
+
+impl Selector {
+    /// Parse a subcommand and its references. Returns `None` if they are
+    /// invalid, in which case the client should get `INVALID_PARAMS`.
+    pub fn parse(subcommand: &str, refs: &[&str]) -> Option<Self> {
+        match (subcommand, refs) {
+            ("LATEST", ["*"]) => Some(Self::Latest(None)),
+            ("LATEST", [r]) => Some(Self::Latest(Some(parse_reference(r)?))),
+            ("BEFORE", [r]) => Some(Self::Before(parse_reference(r)?)),
+            ("AFTER", [r]) => Some(Self::After(parse_reference(r)?)),
+            ("BETWEEN", [a, b]) => Some(Self::Between(parse_reference(a)?, parse_reference(b)?)),
+            _ => None,
+        }
+    }
+
+    /// Select at most `limit` messages out of `messages`, which must be
+    /// sorted by timestamp. References are exclusive, and the selection
+    /// is always returned in ascending order.
+    pub fn select<'a>(&self, messages: &'a [HistoryMessage], limit: usize) -> &'a [HistoryMessage] {
+        // Selections counting back from the upper bound keep the last
+        // messages, the ones counting forward keep the first ones.
+        let (after, before, from_end) = match *self {
+            Self::Latest(after) => (after, None, true),
+            Self::Before(before) => (None, Some(before), true),
+            Self::After(after) => (Some(after), None, false),
+            Self::Between(a, b) if a <= b => (Some(a), Some(b), false),
+            Self::Between(a, b) => (Some(b), Some(a), true),
+        };
+
+        let start = after.map_or(0, |ts| messages.partition_point(|m| m.timestamp <= ts));
+        let end =
+            before.map_or(messages.len(), |ts| messages.partition_point(|m| m.timestamp < ts));
+        if start >= end {
+            return &[]
+        }
+
+        if from_end {
+            &messages[end.saturating_sub(limit).max(start)..end]
+        } else {
+            &messages[start..end.min(start + limit)]
+        }
+    }
+}
+
+/// Parse a `timestamp=YYYY-MM-DDThh:mm:ss.sssZ` message reference
+/// into milliseconds since the UNIX epoch.
+fn parse_reference(reference: &str) -> Option<u64> {
+    let timestamp = reference.strip_prefix("timestamp=")?.strip_suffix('Z')?;
+    let (datetime, millis) = match timestamp.split_once('.') {
+        Some((datetime, millis)) if millis.len() == 3 => (datetime, millis.parse::<u64>().ok()?),
+        Some(_) => return None,
+        None => (timestamp, 0),
+    };
+
+    let dt = DateTime::from_timestamp_str(datetime).ok()?;
+
+    // Days since the epoch, from Howard Hinnant's days_from_civil
+    let (year, month) = (dt.year as i64, dt.month as i64);
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + dt.day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    if days < 0 {
+        return None
+    }
+
+    let secs = days as u64 * 86400 + dt.hour as u64 * 3600 + dt.min as u64 * 60 + dt.sec as u64;
+    Some(secs * 1000 + millis)
+}
+
+/// Format a millisecond timestamp for the IRCv3 `time` message tag
+pub fn server_time(timestamp: u64) -> String {
+    let dt = DateTime::from_timestamp(timestamp / 1000, 0);
+    format!("{dt}.{:03}Z", timestamp % 1000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chathistory_selection() {
+        let ts = 1_546_612_406_123;
+        assert_eq!(server_time(ts), "2019-01-04T14:33:26.123Z");
+        assert_eq!(parse_reference("timestamp=2019-01-04T14:33:26.123Z"), Some(ts));
+        assert_eq!(parse_reference("timestamp=2019-01-04T14:33:26Z"), Some(ts - 123));
+        assert_eq!(parse_reference("msgid=abc"), None);
+        assert_eq!(Selector::parse("LATEST", &["timestamp=1"]), None);
+
+        let messages: Vec<HistoryMessage> = (1..=10)
+            .map(|i| HistoryMessage { timestamp: i * 1000, nick: "a".into(), line: i.to_string() })
+            .collect();
+        let lines = |selector: Selector, limit| -> Vec<String> {
+            selector.select(&messages, limit).iter().map(|m| m.line.clone()).collect()
+        };
+
+        assert_eq!(lines(Selector::Latest(None), 3), ["8", "9", "10"]);
+        assert_eq!(lines(Selector::Latest(Some(9000)), 3), ["10"]);
+        assert_eq!(lines(Selector::Before(3000), 5), ["1", "2"]);
+        assert_eq!(lines(Selector::After(3000), 2), ["4", "5"]);
+        assert_eq!(lines(Selector::Between(2000, 9000), 2), ["3", "4"]);
+        assert_eq!(lines(Selector::Between(9000, 2000), 2), ["7", "8"]);
+        assert!(lines(Selector::Between(5000, 5000), 2).is_empty());
+    }
+}
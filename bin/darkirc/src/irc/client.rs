@@ -26,7 +26,7 @@ use std::{
 };
 
 use darkfi::{
-    event_graph::{proto::EventPut, Event, NULL_ID},
+    event_graph::{proto::EventPut, redact::AuthoredContent, Event, NULL_ID},
     system::Subscription,
     zk::{empty_witnesses, Proof, ProvingKey, ZkCircuit},
     zkas::ZkBinary,
@@ -34,7 +34,7 @@ use darkfi::{
 };
 use darkfi_sdk::{
     bridgetree::Position,
-    crypto::{pasta_prelude::PrimeField, poseidon_hash, MerkleTree},
+    crypto::{pasta_prelude::PrimeField, poseidon_hash, MerkleTree, SecretKey},
     pasta::pallas,
 };
 use darkfi_serial::{deserialize_async, serialize_async};
@@ -49,8 +49,9 @@ use smol::{
 };
 
 use super::{
+    channel_topic,
     server::{IrcServer, MAX_MSG_LEN},
-    Msg, NickServ, OldPrivmsg, SERVER_NAME,
+    Msg, NickServ, OldPrivmsg, Privmsg, SERVER_NAME,
 };
 use crate::crypto::rln::{
     closest_epoch, hash_event, RlnIdentity, RLN2_SIGNAL_ZKBIN, RLN_APP_IDENTIFIER,
@@ -70,6 +71,10 @@ pub enum ReplyType {
     Cap(String),
     /// NOTICE reply (from, to, what)
     Notice((String, String, String)),
+    /// Client reply with IRCv3 message tags (tags, nick, msg)
+    Tagged((String, String, String)),
+    /// Server message without a numeric, e.g. `BATCH` or `FAIL`
+    Message(String),
 }
 
 /// Stateful IRC client handler, used for each client connection
@@ -107,6 +112,10 @@ pub struct Client {
     pub seen: OnceCell<sled::Tree>,
     /// NickServ instance
     pub nickserv: Arc<NickServ>,
+    /// Mechanism of an ongoing SASL authentication
+    pub sasl: RwLock<Option<String>>,
+    /// Account we authenticated to over SASL, and its identity secret
+    pub account: Arc<RwLock<Option<(String, SecretKey)>>>,
 }
 
 impl Client {
@@ -116,11 +125,18 @@ impl Client {
         incoming: Subscription<Event>,
         addr: SocketAddr,
     ) -> Result<Self> {
-        let caps =
-            HashMap::from([("no-history".to_string(), false), ("no-autojoin".to_string(), false)]);
+        let caps = HashMap::from([
+            ("no-history".to_string(), false),
+            ("no-autojoin".to_string(), false),
+            ("batch".to_string(), false),
+            ("server-time".to_string(), false),
+            ("draft/chathistory".to_string(), false),
+            ("sasl".to_string(), false),
+        ]);
 
         let username = Arc::new(RwLock::new(String::from("*")));
         let nickname = Arc::new(RwLock::new(String::from("*")));
+        let account = Arc::new(RwLock::new(None));
 
         Ok(Self {
             server: server.clone(),
@@ -139,8 +155,11 @@ impl Client {
             caps: RwLock::new(caps),
             seen: OnceCell::new(),
             nickserv: Arc::new(
-                NickServ::new(username.clone(), nickname.clone(), server.clone()).await?,
+                NickServ::new(username.clone(), nickname.clone(), account.clone(), server.clone())
+                    .await?,
             ),
+            sasl: RwLock::new(None),
+            account,
         })
     }
 
@@ -315,19 +334,17 @@ impl Client {
                         continue
                     }
 
-                    // Try to deserialize the `Event`'s content into a `Privmsg`
-                    let mut privmsg = match Msg::deserialize(r.content()).await {
-                        Ok(Msg::V1(old_msg)) => old_msg.into_new(),
-                        Ok(Msg::V2(new_msg)) => new_msg,
+                    // Try to deserialize the `Event`'s content into a `Privmsg`,
+                    // and potentially decrypt it.
+                    let privmsg = match self.event_to_privmsg(&r).await {
+                        Ok(Some(privmsg)) => privmsg,
+                        Ok(None) => continue,
                         Err(e) => {
                             error!("[IRC CLIENT] Failed deserializing incoming Privmsg event: {e}");
                             continue
                         }
                     };
 
-                    // If successful, potentially decrypt it:
-                    self.server.try_decrypt(&mut privmsg, self.nickname.read().await.as_ref()).await;
-
                     // We should skip any attempts to contact services from the network.
                     if ["nickserv", "chanserv"].contains(&privmsg.nick.to_lowercase().as_str()) {
                         continue
@@ -340,7 +357,8 @@ impl Client {
                     let channels = self.channels.read().await;
                     let contacts = self.server.contacts.read().await;
                     if !channels.contains(&privmsg.channel) &&
-                        !contacts.contains_key(&privmsg.channel)
+                        !contacts.contains_key(&privmsg.channel) &&
+                        !self.server.darkirc.dm.is_contact(&privmsg.channel)
                     {
                         continue
                     }
@@ -393,6 +411,8 @@ impl Client {
             ReplyType::Notice((src, dst, msg)) => {
                 format!(":{src}!~anon@darkirc NOTICE {dst} :{msg}")
             }
+            ReplyType::Tagged((tags, nick, msg)) => format!("@{tags} :{nick}!~anon@darkirc {msg}"),
+            ReplyType::Message(msg) => format!(":{SERVER_NAME} {msg}"),
         };
 
         debug!("[{}] <-- {r}", self.addr);
@@ -456,10 +476,16 @@ impl Client {
 
         debug!("[{}] --> {cmd}{args}", self.addr);
 
+        // Events created by the command which need to be published
+        let mut events = vec![];
+
         // Handle the command. These implementations are in `command.rs`.
         let replies: Vec<ReplyType> = match cmd.as_str() {
             "ADMIN" => self.handle_cmd_admin(&args).await?,
+            "AUTHENTICATE" => self.handle_cmd_authenticate(&args).await?,
             "CAP" => self.handle_cmd_cap(&args).await?,
+            "CHATHISTORY" => self.handle_cmd_chathistory(&args).await?,
+            "DM" => self.handle_cmd_dm(&args, &mut events).await?,
             "INFO" => self.handle_cmd_info(&args).await?,
             "JOIN" => self.handle_cmd_join(&args, true).await?,
             "LIST" => self.handle_cmd_list(&args).await?,
@@ -470,7 +496,7 @@ impl Client {
             "PART" => self.handle_cmd_part(&args).await?,
             "PASS" => self.handle_cmd_pass(&args).await?,
             "PING" => self.handle_cmd_ping(&args).await?,
-            "PRIVMSG" => self.handle_cmd_privmsg(&args).await?,
+            "PRIVMSG" => self.handle_cmd_privmsg(&args, &mut events).await?,
             "REHASH" => self.handle_cmd_rehash(&args).await?,
             "TOPIC" => self.handle_cmd_topic(&args).await?,
             "USER" => self.handle_cmd_user(&args).await?,
@@ -487,6 +513,10 @@ impl Client {
             self.reply(writer, reply).await?;
         }
 
+        if !events.is_empty() {
+            return Ok(Some(events))
+        }
+
         // If the command was a PRIVMSG the client sent, we need to encrypt it and
         // create an Event to broadcast and return it from this function. So let's try.
         // We also do not allow sending unencrypted DMs. In that case, we send a notice
@@ -510,14 +540,14 @@ impl Client {
             if !args_queue.is_empty() {
                 for _ in 0..args_queue.len() {
                     let privmsg = args_queue.pop_front().unwrap();
-                    pending_events.push(self.privmsg_to_event(privmsg).await);
+                    pending_events.push(self.privmsg_to_event(privmsg).await?);
                 }
                 return Ok(Some(pending_events))
             }
 
             // If queue is empty, create an event and return it
             let privmsg = self.args_to_privmsg(args).await;
-            let event = self.privmsg_to_event(privmsg).await;
+            let event = self.privmsg_to_event(privmsg).await?;
 
             return Ok(Some(vec![event]))
         }
@@ -538,12 +568,85 @@ impl Client {
     }
 
     // Internal helper function that creates an Event from PRIVMSG arguments
-    async fn privmsg_to_event(&self, mut privmsg: OldPrivmsg) -> Event {
+    async fn privmsg_to_event(&self, mut privmsg: OldPrivmsg) -> Result<Event> {
+        // Messages to DM contacts go through their ratchet session,
+        // unless a contact saltbox is configured for them.
+        let dm = &self.server.darkirc.dm;
+        if !privmsg.channel.starts_with('#') &&
+            !self.server.contacts.read().await.contains_key(&privmsg.channel) &&
+            dm.can_send(&privmsg.channel)
+        {
+            return dm
+                .encrypt(&privmsg.channel, &privmsg.msg, &self.server.darkirc.event_graph)
+                .await
+        }
+
+        // Messages of unencrypted channels can be tagged with their
+        // topic, so light nodes following the channel pick them up.
+        let encrypted = self
+            .server
+            .channels
+            .read()
+            .await
+            .get(&privmsg.channel)
+            .is_some_and(|channel| channel.saltbox.is_some());
+        let topic = if self.server.darkirc.tag_channel_topics &&
+            privmsg.channel.starts_with('#') &&
+            !encrypted
+        {
+            channel_topic(&privmsg.channel)
+        } else {
+            NULL_ID
+        };
+
         // Encrypt the Privmsg if an encryption method is available.
         self.server.try_encrypt(&mut privmsg).await;
 
+        // Sign it if we are logged into an account.
+        let mut content = serialize_async(&privmsg).await;
+        if let Some((_, secret)) = &*self.account.read().await {
+            content = AuthoredContent::new(secret, content).to_content();
+        }
+
         // Build a DAG event and return it.
-        Event::new(serialize_async(&privmsg).await, &self.server.darkirc.event_graph).await
+        Ok(Event::with_topic(content, topic, &self.server.darkirc.event_graph).await)
+    }
+
+    /// Deserialize and potentially decrypt the `Privmsg` carried by an
+    /// event. Returns `None` for nick registrations, and for channel
+    /// messages using a nick owned by someone other than their author.
+    pub async fn event_to_privmsg(&self, event: &Event) -> Result<Option<Privmsg>> {
+        // Registrations might reach us before the registrations task,
+        // and are otherwise not meant for the IRC client.
+        if self.server.identities.record(event)? {
+            return Ok(None)
+        }
+
+        let (msg, author) = Msg::from_content(event.content()).await?;
+        let mut privmsg = match msg {
+            Msg::V1(old_msg) => old_msg.into_new(),
+            Msg::V2(new_msg) => new_msg,
+        };
+
+        let self_nickname = self.nickname.read().await.to_string();
+        self.server.try_decrypt(&mut privmsg, &self_nickname, &event.id()).await;
+
+        // Nicks of contacts are our own aliases, so only channel
+        // messages carry the nick their author is using.
+        if privmsg.channel.starts_with('#') {
+            let identities = &self.server.identities;
+            let event_graph = &self.server.darkirc.event_graph;
+            match identities.verify(&privmsg.nick, author.as_ref(), event, event_graph).await {
+                Ok(true) => {}
+                Ok(false) => return Ok(None),
+                Err(e) => {
+                    error!("[IRC CLIENT] Failed verifying nick owner of {}: {e}", privmsg.nick);
+                    return Ok(None)
+                }
+            }
+        }
+
+        Ok(Some(privmsg))
     }
 
     /// Atomically mark a message as seen for this client.
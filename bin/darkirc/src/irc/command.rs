@@ -49,18 +49,26 @@
 //! Some of the above commands could actually be implemented and could
 //! work in respect to the P2P network.
 
-use std::{collections::HashSet, sync::atomic::Ordering::SeqCst};
+use std::{
+    collections::HashSet,
+    str::{from_utf8, FromStr},
+    sync::atomic::Ordering::SeqCst,
+};
 
-use darkfi::Result;
+use darkfi::{event_graph::Event, util::encoding::base64, Result};
+use darkfi_sdk::crypto::PublicKey;
 use log::{error, info};
+use rand::{rngs::OsRng, RngCore};
 
 use super::{
+    chathistory::{server_time, HistoryMessage, Selector, CHATHISTORY_LIMIT},
     client::{Client, ReplyType},
+    identity::parse_sasl_plain,
     rpl::*,
     server::MAX_NICK_LEN,
-    IrcChannel, Msg, SERVER_NAME,
+    IrcChannel, SERVER_NAME,
 };
-use crate::crypto::bcrypt::bcrypt_hash_password;
+use crate::{crypto::bcrypt::bcrypt_hash_password, dm::DmStart};
 
 impl Client {
     /// `ADMIN [<server>]`
@@ -84,6 +92,123 @@ impl Client {
         Ok(replies)
     }
 
+    /// `AUTHENTICATE <mechanism | data>`
+    ///
+    /// IRCv3 SASL authentication to a local account, whose identity is then
+    /// used to sign our messages. Accounts are created with `--add-account`.
+    /// `PLAIN` logs in with the account password. `EXTERNAL` logs in without
+    /// one, naming the account by the authorization identity or the current
+    /// nick, and is only available on loopback connections, where the
+    /// connection itself is trusted.
+    pub async fn handle_cmd_authenticate(&self, args: &str) -> Result<Vec<ReplyType>> {
+        let nick = self.nickname.read().await.to_string();
+
+        let Some(param) = args.split_ascii_whitespace().next() else {
+            self.penalty.fetch_add(1, SeqCst);
+            return Ok(vec![ReplyType::Server((
+                ERR_NEEDMOREPARAMS,
+                format!("{nick} AUTHENTICATE :{INVALID_SYNTAX}"),
+            ))])
+        };
+
+        let fail = || {
+            vec![ReplyType::Server((ERR_SASLFAIL, format!("{nick} :SASL authentication failed")))]
+        };
+
+        if !*self.caps.read().await.get("sasl").unwrap() {
+            return Ok(fail())
+        }
+
+        if self.account.read().await.is_some() {
+            return Ok(vec![ReplyType::Server((
+                ERR_SASLALREADY,
+                format!("{nick} :You have already authenticated using SASL"),
+            ))])
+        }
+
+        // Without an ongoing exchange, the client is picking a mechanism
+        let pending = self.sasl.write().await.take();
+        let Some(mechanism) = pending else {
+            let mechanism = param.to_uppercase();
+            if mechanism != "PLAIN" && mechanism != "EXTERNAL" {
+                let mut replies = vec![ReplyType::Server((
+                    RPL_SASLMECHS,
+                    format!("{nick} PLAIN,EXTERNAL :are available SASL mechanisms"),
+                ))];
+                replies.extend(fail());
+                return Ok(replies)
+            }
+
+            *self.sasl.write().await = Some(mechanism);
+            return Ok(vec![ReplyType::Message("AUTHENTICATE +".to_string())])
+        };
+
+        if param == "*" {
+            return Ok(vec![ReplyType::Server((
+                ERR_SASLABORTED,
+                format!("{nick} :SASL authentication aborted"),
+            ))])
+        }
+
+        // A lone "+" is an empty response
+        let data = if param == "+" {
+            vec![]
+        } else {
+            let Some(data) = base64::decode(param) else { return Ok(fail()) };
+            data
+        };
+
+        let account = match mechanism.as_str() {
+            "PLAIN" => {
+                let Some((name, password)) = parse_sasl_plain(&data) else { return Ok(fail()) };
+                let account = self.server.identities.authenticate(name, password).await?;
+                account.map(|a| (name.to_string(), a))
+            }
+
+            "EXTERNAL" => {
+                if !self.addr.ip().is_loopback() {
+                    return Ok(fail())
+                }
+                let Ok(name) = from_utf8(&data) else { return Ok(fail()) };
+                let name = if name.is_empty() { nick.as_str() } else { name };
+                self.server.identities.account(name)?.map(|a| (name.to_string(), a))
+            }
+
+            _ => unreachable!(),
+        };
+
+        let Some((name, account)) = account else {
+            info!("SASL authentication failed for {nick}");
+            return Ok(fail())
+        };
+
+        *self.account.write().await = Some((name.clone(), account.secret));
+        info!("SASL authentication to account {name} successful");
+
+        let mut replies = vec![
+            ReplyType::Server((
+                RPL_LOGGEDIN,
+                format!("{nick} {nick}!~anon@darkirc {name} :You are now logged in as {name}"),
+            )),
+            ReplyType::Server((RPL_SASLSUCCESS, format!("{nick} :SASL authentication successful"))),
+        ];
+
+        // Let the user know if their nick is owned by someone else, since
+        // their messages would be dropped by other peers, or how to claim it.
+        let identity = PublicKey::from_secret(account.secret);
+        let event_graph = &self.server.darkirc.event_graph;
+        let notice = match self.server.identities.owner(&nick, None, event_graph).await? {
+            Some(owner) if owner == identity => None,
+            Some(owner) => Some(format!("Nick {nick} is owned by another identity: {owner}")),
+            None => Some(format!("Nick {nick} is not claimed, use /msg NickServ CLAIM")),
+        };
+        if let Some(notice) = notice {
+            replies.push(ReplyType::Notice(("NickServ".to_string(), nick.clone(), notice)));
+        }
+
+        Ok(replies)
+    }
+
     /// `CAP <args>`
     pub async fn handle_cmd_cap(&self, args: &str) -> Result<Vec<ReplyType>> {
         let mut tokens = args.split_ascii_whitespace();
@@ -192,6 +317,155 @@ impl Client {
         Ok(vec![ReplyType::Server((ERR_NEEDMOREPARAMS, format!("{nick} CAP :{INVALID_SYNTAX}")))])
     }
 
+    /// `CHATHISTORY <subcommand> <target> <reference> [<reference>] <limit>`
+    ///
+    /// IRCv3 extension used to fetch messages of the `<target>` channel or
+    /// contact from the DAG. Supports the `LATEST`, `BEFORE`, `AFTER` and
+    /// `BETWEEN` subcommands with `timestamp=` references. The returned
+    /// messages are wrapped in a batch if the `batch` CAP is enabled.
+    pub async fn handle_cmd_chathistory(&self, args: &str) -> Result<Vec<ReplyType>> {
+        if !self.registered.load(SeqCst) {
+            self.penalty.fetch_add(1, SeqCst);
+            return Ok(vec![ReplyType::Server((ERR_NOTREGISTERED, format!("* :{NOT_REGISTERED}")))])
+        }
+
+        let nick = self.nickname.read().await.to_string();
+        let tokens: Vec<&str> = args.split_ascii_whitespace().collect();
+
+        if tokens.len() < 4 {
+            self.penalty.fetch_add(1, SeqCst);
+            return Ok(vec![ReplyType::Server((
+                ERR_NEEDMOREPARAMS,
+                format!("{nick} CHATHISTORY :{INVALID_SYNTAX}"),
+            ))])
+        }
+
+        let subcommand = tokens[0].to_uppercase();
+        let target = tokens[1];
+        let (refs, limit) = tokens[2..].split_at(tokens.len() - 3);
+
+        let limit = match limit[0].parse::<usize>() {
+            Ok(limit) if limit > 0 => limit.min(CHATHISTORY_LIMIT),
+            _ => {
+                return Ok(vec![ReplyType::Message(format!(
+                    "FAIL CHATHISTORY INVALID_PARAMS {subcommand} :Invalid limit"
+                ))])
+            }
+        };
+
+        let Some(selector) = Selector::parse(&subcommand, refs) else {
+            return Ok(vec![ReplyType::Message(format!(
+                "FAIL CHATHISTORY INVALID_PARAMS {subcommand} :Invalid message references"
+            ))])
+        };
+
+        // Only joined channels and known contacts have a history
+        if !self.channels.read().await.contains(target) &&
+            !self.server.contacts.read().await.contains_key(target) &&
+            !self.server.darkirc.dm.is_contact(target)
+        {
+            return Ok(vec![ReplyType::Message(format!(
+                "FAIL CHATHISTORY INVALID_TARGET {subcommand} {target} :Messages could not be retrieved"
+            ))])
+        }
+
+        let messages = self.target_history(target).await;
+        let selection = selector.select(&messages, limit);
+
+        let caps = self.caps.read().await;
+        let batch = if *caps.get("batch").unwrap() {
+            Some(format!("{:08x}", OsRng.next_u32()))
+        } else {
+            None
+        };
+        let with_time = *caps.get("server-time").unwrap();
+        drop(caps);
+
+        let mut replies = vec![];
+        if let Some(batch) = &batch {
+            replies.push(ReplyType::Message(format!("BATCH +{batch} chathistory {target}")));
+        }
+
+        for message in selection {
+            let mut tags = vec![];
+            if let Some(batch) = &batch {
+                tags.push(format!("batch={batch}"));
+            }
+            if with_time {
+                tags.push(format!("time={}", server_time(message.timestamp)));
+            }
+
+            let msg = format!("PRIVMSG {target} :{}", message.line);
+            if tags.is_empty() {
+                replies.push(ReplyType::Client((message.nick.clone(), msg)));
+            } else {
+                replies.push(ReplyType::Tagged((tags.join(";"), message.nick.clone(), msg)));
+            }
+        }
+
+        if let Some(batch) = &batch {
+            replies.push(ReplyType::Message(format!("BATCH -{batch}")));
+        }
+
+        Ok(replies)
+    }
+
+    /// `DM [<nick> [<identity>]]`
+    ///
+    /// Starts an end-to-end encrypted DM session with `<nick>`. If `<nick>`
+    /// already sent us a DM request, it gets accepted. Otherwise, our request
+    /// is sent to the given `<identity>`. Without arguments, replies with our
+    /// own DM identity, which has to be shared with the contact.
+    ///
+    /// Events to publish are appended to `events`.
+    pub async fn handle_cmd_dm(
+        &self,
+        args: &str,
+        events: &mut Vec<Event>,
+    ) -> Result<Vec<ReplyType>> {
+        if !self.registered.load(SeqCst) {
+            self.penalty.fetch_add(1, SeqCst);
+            return Ok(vec![ReplyType::Server((ERR_NOTREGISTERED, format!("* :{NOT_REGISTERED}")))])
+        }
+
+        let nick = self.nickname.read().await.to_string();
+        let notice = |msg: String| ReplyType::Notice(("DM".to_string(), nick.clone(), msg));
+        let dm = &self.server.darkirc.dm;
+        let mut tokens = args.split_ascii_whitespace();
+
+        let Some(target) = tokens.next() else {
+            return Ok(vec![notice(format!("Your DM identity is {}", dm.identity()))])
+        };
+
+        if target.starts_with('#') || target == nick {
+            return Ok(vec![ReplyType::Server((ERR_NOSUCHNICK, format!("{nick} :{target}")))])
+        }
+
+        let identity = match tokens.next() {
+            Some(identity) => match PublicKey::from_str(identity) {
+                Ok(identity) => Some(identity),
+                Err(_) => return Ok(vec![notice(format!("Invalid DM identity: {identity}"))]),
+            },
+            None => None,
+        };
+
+        if !*self.server.darkirc.event_graph.synced.read().await {
+            return Ok(vec![notice("DAG is still syncing, try again later.".to_string())])
+        }
+
+        match dm.start(target, identity, &nick, &self.server.darkirc.event_graph).await {
+            Ok(DmStart::Offered(event)) => {
+                events.push(event);
+                Ok(vec![notice(format!("Sent DM request to {target}"))])
+            }
+            Ok(DmStart::Accepted(event)) => {
+                events.push(event);
+                Ok(vec![notice(format!("DM session with {target} established"))])
+            }
+            Err(e) => Ok(vec![notice(format!("Unable to start DM with {target}: {e}"))]),
+        }
+    }
+
     /// `INFO [<target>]`
     ///
     /// Gives information about the `<target>` server, or the current server if
@@ -615,8 +889,12 @@ impl Client {
     /// `PRIVMSG <msgtarget> <message>`
     ///
     /// Sends `<message>` to `<msgtarget>`. The target is usually a user or
-    /// a channel.
-    pub async fn handle_cmd_privmsg(&self, args: &str) -> Result<Vec<ReplyType>> {
+    /// a channel. Events published by IRC services are appended to `events`.
+    pub async fn handle_cmd_privmsg(
+        &self,
+        args: &str,
+        events: &mut Vec<Event>,
+    ) -> Result<Vec<ReplyType>> {
         if !self.registered.load(SeqCst) {
             self.penalty.fetch_add(1, SeqCst);
             return Ok(vec![ReplyType::Server((ERR_NOTREGISTERED, format!("* :{NOT_REGISTERED}")))])
@@ -659,12 +937,15 @@ impl Client {
 
         // Handle queries to NickServ
         if target.to_lowercase().as_str() == "nickserv" {
-            return self.nickserv.handle_query(message.strip_prefix(':').unwrap()).await
+            return self.nickserv.handle_query(message.strip_prefix(':').unwrap(), events).await
         }
 
         // If it's a DM and we don't have an encryption key, we will
         // refuse to send it. Send ERR_NORECIPIENT to the client.
-        if !target.starts_with('#') && !self.server.contacts.read().await.contains_key(target) {
+        if !target.starts_with('#') &&
+            !self.server.contacts.read().await.contains_key(target) &&
+            !self.server.darkirc.dm.can_send(target)
+        {
             return Ok(vec![ReplyType::Server((ERR_NOSUCHNICK, format!("{nick} :{target}")))])
         }
 
@@ -861,6 +1142,10 @@ impl Client {
                     env!("CARGO_PKG_VERSION")
                 ),
             )),
+            ReplyType::Server((
+                RPL_ISUPPORT,
+                format!("{nick} CHATHISTORY={CHATHISTORY_LIMIT} :are supported by this server"),
+            )),
         ];
 
         // Append the MOTD
@@ -930,15 +1215,8 @@ impl Client {
                 }
             }
 
-            // Try to deserialize it. (Here we skip errors)
-            let mut privmsg = match Msg::deserialize(event.content()).await {
-                Ok(Msg::V1(old_msg)) => old_msg.into_new(),
-                Ok(Msg::V2(new_msg)) => new_msg,
-                Err(_) => continue,
-            };
-
-            // Potentially decrypt the privmsg
-            self.server.try_decrypt(&mut privmsg, self.nickname.read().await.as_ref()).await;
+            // Try to deserialize and potentially decrypt it. (Here we skip errors)
+            let Ok(Some(privmsg)) = self.event_to_privmsg(event).await else { continue };
 
             // We should skip any attempts to contact services from the network.
             if ["nickserv", "chanserv"].contains(&privmsg.nick.to_lowercase().as_str()) {
@@ -949,7 +1227,10 @@ impl Client {
             // channels or contacts, add it as a reply and
             // mark it as seen in the seen_events tree.
             let contacts = self.server.contacts.read().await;
-            if !channels.contains(&privmsg.channel) && !contacts.contains_key(&privmsg.channel) {
+            if !channels.contains(&privmsg.channel) &&
+                !contacts.contains_key(&privmsg.channel) &&
+                !self.server.darkirc.dm.is_contact(&privmsg.channel)
+            {
                 continue
             }
 
@@ -981,4 +1262,32 @@ impl Client {
 
         Ok(replies)
     }
+
+    /// Internal function that scans the DAG and returns all messages
+    /// of the given channel or contact, sorted by timestamp.
+    async fn target_history(&self, target: &str) -> Vec<HistoryMessage> {
+        let mut messages = vec![];
+
+        for event in self.server.darkirc.event_graph.order_events().await {
+            let Ok(Some(privmsg)) = self.event_to_privmsg(&event).await else { continue };
+
+            if privmsg.channel != target ||
+                ["nickserv", "chanserv"].contains(&privmsg.nick.to_lowercase().as_str())
+            {
+                continue
+            }
+
+            for line in privmsg.msg.lines().filter(|line| !line.is_empty()) {
+                messages.push(HistoryMessage {
+                    timestamp: event.timestamp,
+                    nick: privmsg.nick.clone(),
+                    line: line.to_string(),
+                });
+            }
+        }
+
+        // Stable, so lines of the same message keep their order
+        messages.sort_by_key(|message| message.timestamp);
+        messages
+    }
 }
@@ -0,0 +1,400 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Nick identities
+//!
+//! Clients authenticate over SASL to a local account, which owns an
+//! identity keypair. Messages sent by authenticated clients are signed
+//! with it. A nick is claimed by publishing a signed [`NickRegistration`]
+//! event, and its owner is the author of the earliest registration still
+//! in the DAG, ordered by event timestamp and ID. Every node holding the
+//! same DAG therefore agrees on the owner, regardless of the order it
+//! received the events in.
+//!
+//! Channel messages using a registered nick which are newer than its
+//! registration and not signed by the owner are considered impersonation
+//! attempts and get dropped. Messages older than the registration are
+//! left alone, so claiming a nick doesn't rewrite history.
+//!
+//! Accounts are created locally with `darkirc --add-account <name>`.
+
+use std::{str::from_utf8, sync::Arc};
+
+use darkfi::{
+    event_graph::{redact::AuthoredContent, Event, EventGraph},
+    Error, Result,
+};
+use darkfi_sdk::crypto::{PublicKey, SecretKey};
+use darkfi_serial::{async_trait, deserialize, serialize, SerialDecodable, SerialEncodable};
+use log::{info, warn};
+use rand::rngs::OsRng;
+use sled_overlay::sled;
+
+use crate::IrcServer;
+
+/// Event content prefix marking a nick registration
+pub const NICK_REGISTRATION_MAGIC: [u8; 4] = [0x4e, 0x49, 0x43, 0x4b];
+
+/// Local SASL account
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct Account {
+    /// Identity secret key of the account
+    pub secret: SecretKey,
+    /// bcrypt hash of the account password
+    pub password: String,
+}
+
+/// Claim of a nick, published in the DAG signed by the claiming identity
+#[derive(Clone, Debug, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct NickRegistration {
+    /// The claimed nick, in lowercase
+    pub nick: String,
+}
+
+impl NickRegistration {
+    pub fn new(nick: &str) -> Self {
+        Self { nick: nick.to_lowercase() }
+    }
+
+    /// Event content carrying this registration signed with `secret`
+    pub fn to_content(&self, secret: &SecretKey) -> Vec<u8> {
+        let mut content = NICK_REGISTRATION_MAGIC.to_vec();
+        content.extend(serialize(self));
+        AuthoredContent::new(secret, content).to_content()
+    }
+
+    /// Parse a validly signed registration and its author out of event
+    /// content, if there is one
+    pub fn from_content(content: &[u8]) -> Option<(Self, PublicKey)> {
+        let authored = AuthoredContent::from_content(content)?;
+        let registration: Self =
+            deserialize(authored.content.strip_prefix(&NICK_REGISTRATION_MAGIC)?).ok()?;
+
+        if !authored.verify() {
+            return None
+        }
+
+        Some((registration, authored.author))
+    }
+}
+
+/// Parse the `<authzid> NUL <authcid> NUL <passwd>` response of the SASL
+/// `PLAIN` mechanism into the account name and password
+pub fn parse_sasl_plain(data: &[u8]) -> Option<(&str, &str)> {
+    let fields: Vec<&[u8]> = data.split(|b| *b == 0).collect();
+    let [_, name, password] = fields[..] else { return None };
+    let (Ok(name), Ok(password)) = (from_utf8(name), from_utf8(password)) else { return None };
+    if name.is_empty() {
+        return None
+    }
+    Some((name, password))
+}
+
+/// Key under which a registration is recorded. Nicks are length prefixed
+/// and timestamps big-endian, so the registrations of a nick are adjacent
+/// and sorted by their position in the DAG order.
+fn registration_key(nick: &str, timestamp: u64, event_id: &blake3::Hash) -> Vec<u8> {
+    let mut key = serialize(&nick.to_lowercase());
+    key.extend(timestamp.to_be_bytes());
+    key.extend(event_id.as_bytes());
+    key
+}
+
+/// Store of local accounts and of the nick registrations seen in the DAG
+pub struct NickIdentities {
+    /// Accounts keyed by name
+    accounts: sled::Tree,
+    /// Registering identity keyed by [`registration_key`]
+    registrations: sled::Tree,
+}
+
+impl NickIdentities {
+    pub fn new(sled_db: &sled::Db) -> Result<Self> {
+        Ok(Self {
+            accounts: sled_db.open_tree("darkirc_sasl_accounts")?,
+            registrations: sled_db.open_tree("darkirc_nick_registrations")?,
+        })
+    }
+
+    /// Fetch an account by name
+    pub fn account(&self, name: &str) -> Result<Option<Account>> {
+        match self.accounts.get(name)? {
+            Some(bytes) => Ok(Some(deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Create an account with a fresh identity. Returns `None` if an
+    /// account with the given name already exists.
+    pub async fn create_account(&self, name: &str, password: &str) -> Result<Option<Account>> {
+        if name.is_empty() {
+            return Err(Error::Custom("Account name can't be empty".into()))
+        }
+
+        let password = password.to_string();
+        let password = smol::unblock(move || bcrypt::hash(password, bcrypt::DEFAULT_COST))
+            .await
+            .map_err(|e| Error::Custom(format!("Failed hashing account password: {e}")))?;
+
+        let account = Account { secret: SecretKey::random(&mut OsRng), password };
+        if self
+            .accounts
+            .compare_and_swap(name, None as Option<&[u8]>, Some(serialize(&account)))?
+            .is_err()
+        {
+            return Ok(None)
+        }
+
+        info!(
+            target: "darkirc::irc::identity",
+            "Created account {name} with identity {}",
+            PublicKey::from_secret(account.secret),
+        );
+        Ok(Some(account))
+    }
+
+    /// Authenticate to an existing account with a password
+    pub async fn authenticate(&self, name: &str, password: &str) -> Result<Option<Account>> {
+        let Some(account) = self.account(name)? else { return Ok(None) };
+
+        // bcrypt is slow on purpose, so keep it off the executor
+        let password = password.to_string();
+        let hash = account.password.clone();
+        let valid = smol::unblock(move || bcrypt::verify(password, &hash).unwrap_or(false)).await;
+
+        Ok(valid.then_some(account))
+    }
+
+    /// Record the event if it is a nick registration. Returns `true` if
+    /// it was one.
+    pub fn record(&self, event: &Event) -> Result<bool> {
+        let Some((registration, author)) = NickRegistration::from_content(event.content()) else {
+            return Ok(false)
+        };
+
+        let key = registration_key(&registration.nick, event.timestamp, &event.id());
+        if self.registrations.insert(key, serialize(&author))?.is_none() {
+            info!(
+                target: "darkirc::irc::identity",
+                "Nick {} registered by {author} in event {}", registration.nick, event.id(),
+            );
+        }
+
+        Ok(true)
+    }
+
+    /// Owner of the given nick, as of the given message event if any.
+    /// Registrations which got pruned or redacted from the DAG are
+    /// forgotten along the way.
+    pub async fn owner(
+        &self,
+        nick: &str,
+        before: Option<&Event>,
+        event_graph: &EventGraph,
+    ) -> Result<Option<PublicKey>> {
+        let before = before.map(|event| registration_key(nick, event.timestamp, &event.id()));
+
+        for entry in self.registrations.scan_prefix(serialize(&nick.to_lowercase())) {
+            let (key, author) = entry?;
+            if let Some(before) = &before {
+                if key.as_ref() >= before.as_slice() {
+                    break
+                }
+            }
+
+            let id_bytes: [u8; 32] = key[key.len() - 32..].try_into().unwrap();
+            let event_id = blake3::Hash::from_bytes(id_bytes);
+            let registered = match event_graph.dag_get(&event_id).await? {
+                Some(event) => NickRegistration::from_content(event.content()).is_some(),
+                None => false,
+            };
+
+            if !registered {
+                self.registrations.remove(&key)?;
+                continue
+            }
+
+            return Ok(Some(deserialize(&author)?))
+        }
+
+        Ok(None)
+    }
+
+    /// Check if a channel message event using `nick` and signed by
+    /// `author` may be accepted
+    pub async fn verify(
+        &self,
+        nick: &str,
+        author: Option<&PublicKey>,
+        event: &Event,
+        event_graph: &EventGraph,
+    ) -> Result<bool> {
+        let Some(owner) = self.owner(nick, Some(event), event_graph).await? else {
+            return Ok(true)
+        };
+
+        if author == Some(&owner) {
+            return Ok(true)
+        }
+
+        warn!(
+            target: "darkirc::irc::identity",
+            "Dropping message {} using {nick}, which is owned by {owner}", event.id(),
+        );
+        Ok(false)
+    }
+}
+
+/// Background task recording the nick registrations in the DAG
+pub async fn registrations_task(server: Arc<IrcServer>) -> Result<()> {
+    let event_graph = &server.darkirc.event_graph;
+
+    // Subscribe before the scan, so no registration slips in between
+    let incoming = event_graph.event_pub.clone().subscribe().await;
+    for event in event_graph.order_events().await {
+        server.identities.record(&event)?;
+    }
+    info!(target: "darkirc::irc::identity", "Nick registrations task started");
+
+    loop {
+        let event = incoming.receive().await;
+        server.identities.record(&event)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::UNIX_EPOCH};
+
+    use darkfi::{
+        event_graph::retention::RetentionPolicy,
+        net::{P2p, Settings},
+    };
+    use smol::Executor;
+
+    use super::*;
+
+    #[test]
+    fn sasl_plain() {
+        assert_eq!(parse_sasl_plain(b"\0alice\0hunter2"), Some(("alice", "hunter2")));
+        assert_eq!(parse_sasl_plain(b"alice\0alice\0hunter2"), Some(("alice", "hunter2")));
+        assert_eq!(parse_sasl_plain(b"\0\0hunter2"), None);
+        assert_eq!(parse_sasl_plain(b"\0alice"), None);
+        assert_eq!(parse_sasl_plain(b"\0alice\0hunter2\0"), None);
+        assert_eq!(parse_sasl_plain(b"\0\xff\0hunter2"), None);
+    }
+
+    #[test]
+    fn accounts() -> Result<()> {
+        smol::block_on(async {
+            let sled_db = sled::Config::new().temporary(true).open()?;
+            let identities = NickIdentities::new(&sled_db)?;
+
+            // Accounts are not created by logging into them
+            assert!(identities.authenticate("alice", "hunter2").await?.is_none());
+            assert!(identities.account("alice")?.is_none());
+
+            let account = identities.create_account("alice", "hunter2").await?.unwrap();
+            assert!(identities.create_account("alice", "hunter3").await?.is_none());
+
+            let logged_in = identities.authenticate("alice", "hunter2").await?.unwrap();
+            assert_eq!(logged_in.secret, account.secret);
+            assert!(identities.authenticate("alice", "hunter3").await?.is_none());
+            assert!(identities.authenticate("bob", "hunter2").await?.is_none());
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn nick_ownership() -> Result<()> {
+        smol::block_on(async {
+            let ex = Arc::new(Executor::new());
+            let p2p = P2p::new(Settings::default(), ex.clone()).await?;
+            let sled_db = sled::Config::new().temporary(true).open()?;
+            let event_graph = EventGraph::new(
+                p2p,
+                sled_db.clone(),
+                "/tmp".into(),
+                false,
+                "dag",
+                1,
+                RetentionPolicy::default(),
+                ex,
+            )
+            .await?;
+            let identities = NickIdentities::new(&sled_db)?;
+
+            let alice = SecretKey::random(&mut OsRng);
+            let mallory = SecretKey::random(&mut OsRng);
+            let alice_pk = PublicKey::from_secret(alice);
+            let mallory_pk = PublicKey::from_secret(mallory);
+
+            // Events are stamped explicitly, to control their DAG order
+            let timestamp = UNIX_EPOCH.elapsed().unwrap().as_millis() as u64;
+            let event = |content: Vec<u8>, offset: u64| {
+                let event_graph = &event_graph;
+                async move {
+                    let mut event = Event::new(content, event_graph).await;
+                    event.timestamp = timestamp + offset;
+                    event_graph.dag_insert(&[event.clone()]).await.unwrap();
+                    event
+                }
+            };
+
+            // Unregistered nicks can be used by anyone
+            let early = event(b"hi".to_vec(), 0).await;
+            assert!(identities.verify("Alice", None, &early, &event_graph).await?);
+
+            // Registrations are only records of the DAG, so receiving the
+            // later one first doesn't change the outcome.
+            let mallory_reg = event(NickRegistration::new("alice").to_content(&mallory), 20).await;
+            let alice_reg = event(NickRegistration::new("Alice").to_content(&alice), 10).await;
+            assert!(identities.record(&mallory_reg)?);
+            assert!(identities.record(&alice_reg)?);
+            assert!(!identities.record(&early)?);
+            assert_eq!(identities.owner("ALICE", None, &event_graph).await?, Some(alice_pk));
+
+            // Messages older than the registration are kept
+            assert!(identities.verify("alice", None, &early, &event_graph).await?);
+            assert!(identities.verify("alice", Some(&mallory_pk), &early, &event_graph).await?);
+
+            // Newer ones must be signed by the owner
+            let late = event(b"hi".to_vec(), 30).await;
+            assert!(identities.verify("alice", Some(&alice_pk), &late, &event_graph).await?);
+            assert!(!identities.verify("alice", Some(&mallory_pk), &late, &event_graph).await?);
+            assert!(!identities.verify("alice", None, &late, &event_graph).await?);
+
+            // Between both registrations, only alice's one applies
+            let between = event(b"hi".to_vec(), 15).await;
+            assert_eq!(
+                identities.owner("alice", Some(&between), &event_graph).await?,
+                Some(alice_pk)
+            );
+
+            // Forged registrations are not recorded
+            let mut forged = alice_reg.clone();
+            forged.content = NickRegistration::new("bob").to_content(&alice);
+            let last = forged.content.len() - 1;
+            forged.content[last] ^= 1;
+            assert!(!identities.record(&forged)?);
+
+            Ok(())
+        })
+    }
+}
@@ -19,7 +19,11 @@
 use std::{collections::HashSet, sync::Arc};
 
 use crypto_box::ChaChaBox;
-use darkfi::{Error, Result};
+use darkfi::{
+    event_graph::{redact::AuthoredContent, topic_hash},
+    Error, Result,
+};
+use darkfi_sdk::crypto::PublicKey;
 use darkfi_serial::{async_trait, deserialize_async_partial, SerialDecodable, SerialEncodable};
 
 /// IRC client state
@@ -38,6 +42,12 @@ pub(crate) use services::nickserv::NickServ;
 /// IRC numerics and server replies
 pub(crate) mod rpl;
 
+/// IRCv3 CHATHISTORY support
+pub(crate) mod chathistory;
+
+/// SASL accounts and nick ownership
+pub(crate) mod identity;
+
 /// Hardcoded server name
 const SERVER_NAME: &str = "irc.dark.fi";
 
@@ -123,6 +133,26 @@ impl Msg {
 
         Err(Error::Custom("Unknown message format".into()))
     }
+
+    /// Deserialize event content, which is either a message or a message
+    /// signed by its author. Returns the message along with its author,
+    /// if it was signed.
+    pub async fn from_content(content: &[u8]) -> Result<(Self, Option<PublicKey>)> {
+        let Some(authored) = AuthoredContent::from_content(content) else {
+            return Ok((Self::deserialize(content).await?, None))
+        };
+
+        if !authored.verify() {
+            return Err(Error::Custom("Invalid message signature".into()))
+        }
+
+        Ok((Self::deserialize(&authored.content).await?, Some(authored.author)))
+    }
+}
+
+/// Event graph topic of an unencrypted IRC channel
+pub fn channel_topic(name: &str) -> blake3::Hash {
+    topic_hash(&name.to_lowercase())
 }
 
 /// IRC channel definition
@@ -39,6 +39,11 @@ pub const RPL_WELCOME: u16 = 001;
 /// Part of the post-registration greeting.
 pub const RPL_YOURHOST: u16 = 002;
 
+/// `<client> <1-13 tokens> :are supported by this server`
+///
+/// Advertises features supported by the server, sent after registration.
+pub const RPL_ISUPPORT: u16 = 005;
+
 /// `<client> <user modes>`
 ///
 /// Sent to a client to inform that client of their currently-set user modes.
@@ -217,3 +222,34 @@ pub const ERR_PASSWDMISMATCH: u16 = 464;
 /// Indicates that a MODE command affecting a user failed because they
 /// were trying to set or view modes for other users.
 pub const ERR_USERSDONTMATCH: u16 = 502;
+
+/// `<client> <nick>!<user>@<host> <account> :You are now logged in as <username>`
+///
+/// Sent when the client successfully authenticates to an account.
+pub const RPL_LOGGEDIN: u16 = 900;
+
+/// `<client> :SASL authentication successful`
+///
+/// Sent when the SASL authentication finished successfully.
+pub const RPL_SASLSUCCESS: u16 = 903;
+
+/// `<client> :SASL authentication failed`
+///
+/// Sent when the SASL authentication failed, because of invalid
+/// credentials or a malformed message.
+pub const ERR_SASLFAIL: u16 = 904;
+
+/// `<client> :SASL authentication aborted`
+///
+/// Sent when the client aborted the SASL authentication.
+pub const ERR_SASLABORTED: u16 = 906;
+
+/// `<client> :You have already authenticated using SASL`
+///
+/// Sent when the client attempts to authenticate twice.
+pub const ERR_SASLALREADY: u16 = 907;
+
+/// `<client> <mechanisms> :are available SASL mechanisms`
+///
+/// Sent when the client requests an unsupported SASL mechanism.
+pub const RPL_SASLMECHS: u16 = 908;
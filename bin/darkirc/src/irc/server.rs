@@ -49,7 +49,7 @@ use smol::{
 };
 use url::Url;
 
-use super::{client::Client, IrcChannel, IrcContact, Priv, Privmsg};
+use super::{client::Client, identity::NickIdentities, IrcChannel, IrcContact, Priv, Privmsg};
 use crate::{
     crypto::{
         rln::{RlnIdentity, RLN2_SIGNAL_ZKBIN, RLN2_SLASH_ZKBIN},
@@ -57,7 +57,7 @@ use crate::{
     },
     settings::{
         parse_autojoin_channels, parse_configured_channels, parse_configured_contacts,
-        parse_rln_identity,
+        parse_push_config, parse_rln_identity,
     },
     DarkIrc,
 };
@@ -96,6 +96,8 @@ pub struct IrcServer {
     pub rln_identity_store: sled::Tree,
     /// RLN Signal VerifyingKey
     pub rln_signal_vk: VerifyingKey,
+    /// SASL accounts and nick owners
+    pub identities: NickIdentities,
 }
 
 impl IrcServer {
@@ -153,6 +155,7 @@ impl IrcServer {
         // Open persistent dbs
         let server_store = darkirc.sled.open_tree("server_store")?;
         let rln_identity_store = darkirc.sled.open_tree("rln_identity_store")?;
+        let identities = NickIdentities::new(&darkirc.sled)?;
 
         // Generate RLN proving and verifying keys, if needed
         let rln_signal_zkbin = ZkBinary::decode(RLN2_SIGNAL_ZKBIN)?;
@@ -222,6 +225,7 @@ impl IrcServer {
             server_store,
             rln_identity_store,
             rln_signal_vk,
+            identities,
         });
 
         // Load any channel/contact configuration.
@@ -253,6 +257,9 @@ impl IrcServer {
         // Parse RLN identity
         let rln_identity = parse_rln_identity(&contents)?;
 
+        // Parse push notification gateway
+        let push = parse_push_config(&contents)?;
+
         // Persist unconfigured channels (joined from client, or autojoined without config)
         let channels = {
             let old_channels = self.channels.read().await.clone();
@@ -268,6 +275,7 @@ impl IrcServer {
         *self.channels.write().await = channels;
         *self.contacts.write().await = contacts;
         *self.rln_identity.write().await = rln_identity;
+        self.darkirc.push.configure(push).await?;
 
         Ok(())
     }
@@ -409,7 +417,14 @@ impl IrcServer {
     }
 
     /// Try decrypting a given potentially encrypted `Privmsg` object.
-    pub async fn try_decrypt(&self, privmsg: &mut Privmsg, self_nickname: &str) {
+    /// `event_id` is the ID of the event carrying it, used to look up
+    /// DM ratchet messages that were already decrypted.
+    pub async fn try_decrypt(
+        &self,
+        privmsg: &mut Privmsg,
+        self_nickname: &str,
+        event_id: &blake3::Hash,
+    ) {
         // If all fields have base58, then we can consider decrypting.
         let channel_ciphertext = match bs58::decode(&privmsg.channel).into_vec() {
             Ok(v) => v,
@@ -479,5 +494,20 @@ impl IrcServer {
             debug!("Successfully decrypted message from {name}");
             return
         }
+
+        // Finally, it might be a message for one of our DM sessions
+        match self.darkirc.dm.decrypt(event_id, &msg_ciphertext).await {
+            Ok(Some(dm)) => {
+                privmsg.nick =
+                    if dm.from_self { String::from(self_nickname) } else { dm.contact.clone() };
+                privmsg.channel = dm.contact;
+                privmsg.msg = dm.msg;
+                debug!("Successfully decrypted DM from {}", privmsg.channel);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                error!(target: "darkirc::irc::server::try_decrypt", "Failed handling DM: {e}")
+            }
+        }
     }
 }
@@ -21,13 +21,13 @@ use std::{
     sync::Arc,
 };
 
-use darkfi::Result;
-use darkfi_sdk::crypto::SecretKey;
+use darkfi::{event_graph::Event, Result};
+use darkfi_sdk::crypto::{PublicKey, SecretKey};
 use darkfi_serial::serialize_async;
 use smol::lock::RwLock;
 
 use super::{
-    super::{client::ReplyType, rpl::*},
+    super::{client::ReplyType, identity::NickRegistration, rpl::*},
     rln::RlnIdentity,
 };
 use crate::IrcServer;
@@ -45,6 +45,7 @@ The following commands are available:
   REGISTER      Register an account.
   DEREGISTER    Deregister an account.
   SET           Select an account to use.
+  CLAIM         Claim your current nick for your SASL account.
 
 For more information on a NickServ command, type:
 /msg NickServ HELP <command>
@@ -58,6 +59,8 @@ pub struct NickServ {
     pub _username: Arc<RwLock<String>>,
     /// Client nickname
     pub nickname: Arc<RwLock<String>>,
+    /// Client SASL account and its identity secret
+    pub account: Arc<RwLock<Option<(String, SecretKey)>>>,
     /// Pointer to parent `IrcServer`
     pub server: Arc<IrcServer>,
 }
@@ -68,14 +71,20 @@ impl NickServ {
     pub async fn new(
         _username: Arc<RwLock<String>>,
         nickname: Arc<RwLock<String>>,
+        account: Arc<RwLock<Option<(String, SecretKey)>>>,
         server: Arc<IrcServer>,
     ) -> Result<Self> {
-        Ok(Self { _username, nickname, server })
+        Ok(Self { _username, nickname, account, server })
     }
 
     /// Handle a `NickServ` query. This is the main command handler.
-    /// Called from `command::handle_cmd_privmsg`.
-    pub async fn handle_query(&self, query: &str) -> Result<Vec<ReplyType>> {
+    /// Called from `command::handle_cmd_privmsg`. Events to publish are
+    /// appended to `events`.
+    pub async fn handle_query(
+        &self,
+        query: &str,
+        events: &mut Vec<Event>,
+    ) -> Result<Vec<ReplyType>> {
         let nick = self.nickname.read().await.to_string();
         let mut tokens = query.split_ascii_whitespace();
 
@@ -91,6 +100,7 @@ impl NickServ {
             "REGISTER" => self.handle_register(&nick, &mut tokens).await,
             "DEREGISTER" => self.handle_deregister(&nick, &mut tokens).await,
             "SET" => self.handle_set(&nick, &mut tokens).await,
+            "CLAIM" => self.handle_claim(&nick, events).await,
             "HELP" => self.handle_help(&nick).await,
             _ => self.handle_invalid(&nick).await,
         }
@@ -234,6 +244,39 @@ impl NickServ {
         todo!()
     }
 
+    /// Handle the CLAIM command. The claim is a registration event signed
+    /// by the account identity, and the earliest one in the DAG wins.
+    pub async fn handle_claim(
+        &self,
+        nick: &str,
+        events: &mut Vec<Event>,
+    ) -> Result<Vec<ReplyType>> {
+        let notice = |msg: String| {
+            Ok(vec![ReplyType::Notice(("NickServ".to_string(), nick.to_string(), msg))])
+        };
+
+        let Some((_, secret)) = self.account.read().await.clone() else {
+            return notice("You need to log in over SASL to claim a nick.".to_string())
+        };
+        let identity = PublicKey::from_secret(secret);
+
+        let event_graph = &self.server.darkirc.event_graph;
+        match self.server.identities.owner(nick, None, event_graph).await? {
+            Some(owner) if owner == identity => {
+                return notice(format!("Nick {nick} is already yours."))
+            }
+            Some(owner) => {
+                return notice(format!("Nick {nick} is owned by another identity: {owner}"))
+            }
+            None => {}
+        }
+
+        let content = NickRegistration::new(nick).to_content(&secret);
+        events.push(Event::new(content, event_graph).await);
+
+        notice(format!("Claimed nick {nick} for identity {identity}."))
+    }
+
     /// Reply to the HELP command
     pub async fn handle_help(&self, nick: &str) -> Result<Vec<ReplyType>> {
         let replies = NICKSERV_USAGE
@@ -20,10 +20,13 @@ use std::{collections::HashSet, io::Write, path::PathBuf, sync::Arc};
 
 use darkfi::{
     async_daemonize, cli_desc,
-    event_graph::{proto::ProtocolEventGraph, EventGraph, EventGraphPtr},
+    event_graph::{
+        proto::ProtocolEventGraph, retention::RetentionPolicy, EventGraph, EventGraphPtr,
+    },
     net::{session::SESSION_DEFAULT, settings::SettingsOpt, P2p, P2pPtr},
     rpc::{
         jsonrpc::JsonSubscriber,
+        p2p_method::forward_p2p_events,
         server::{listen_and_serve, RequestHandler},
         settings::{RpcSettings, RpcSettingsOpt},
     },
@@ -31,7 +34,7 @@ use darkfi::{
     util::path::{expand_path, get_config_path},
     Error, Result,
 };
-use darkfi_sdk::crypto::pasta_prelude::PrimeField;
+use darkfi_sdk::crypto::{pasta_prelude::PrimeField, PublicKey};
 
 use log::{debug, error, info};
 use rand::rngs::OsRng;
@@ -46,7 +49,7 @@ const CONFIG_FILE_CONTENTS: &str = include_str!("../darkirc_config.toml");
 
 /// IRC server and client handler implementation
 mod irc;
-use irc::server::IrcServer;
+use irc::{channel_topic, identity::NickIdentities, server::IrcServer};
 
 /// Cryptography utilities
 mod crypto;
@@ -58,6 +61,14 @@ mod rpc;
 /// Settings utilities
 mod settings;
 
+/// Push notification gateway
+mod push;
+use push::PushGateway;
+
+/// End-to-end encrypted direct messages
+mod dm;
+use dm::DmStore;
+
 fn panic_hook(panic_info: &std::panic::PanicHookInfo) {
     error!("panic occurred: {panic_info}");
     error!("{}", std::backtrace::Backtrace::force_capture());
@@ -84,6 +95,10 @@ struct Args {
     /// Set log file output
     log: Option<String>,
 
+    #[structopt(long)]
+    /// Serve Prometheus metrics on this URL, e.g. tcp://127.0.0.1:9100
+    metrics_listen: Option<String>,
+
     #[structopt(long, default_value = "tcp://127.0.0.1:6667")]
     /// IRC server listen address
     irc_listen: Url,
@@ -126,6 +141,15 @@ struct Args {
     /// Flag to skip syncing the DAG (no history)
     skip_dag_sync: bool,
 
+    #[structopt(long)]
+    /// Tag messages of unencrypted channels with their topic, so light
+    /// nodes can sync them. Nodes without topic support can't read them.
+    tag_channel_topics: bool,
+
+    #[structopt(long, use_delimiter = true)]
+    /// Light mode: only sync the tagged messages of these channels
+    sync_channels: Vec<String>,
+
     #[structopt(long)]
     /// IRC Password (Encrypted with bcrypt-2b)
     password: Option<String>,
@@ -138,6 +162,10 @@ struct Args {
     /// List configured contacts.
     list_contacts: bool,
 
+    #[structopt(long)]
+    /// Create a SASL account with the given name, used to sign messages
+    add_account: Option<String>,
+
     #[structopt(flatten)]
     /// P2P network settings
     net: SettingsOpt,
@@ -158,20 +186,33 @@ pub struct DarkIrc {
     rpc_connections: Mutex<HashSet<StoppableTaskPtr>>,
     /// dnet JSON-RPC subscriber
     dnet_sub: JsonSubscriber,
+    /// P2P events JSON-RPC subscriber
+    p2p_sub: JsonSubscriber,
     /// deg JSON-RPC subscriber
     deg_sub: JsonSubscriber,
     /// Replay logs (DB) path
     replay_datastore: PathBuf,
+    /// DM contacts and sessions
+    dm: DmStore,
+    /// Push notification gateway registrations
+    push: PushGateway,
+    /// Tag messages of unencrypted channels with their topic
+    tag_channel_topics: bool,
 }
 
 impl DarkIrc {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         p2p: P2pPtr,
         sled: sled::Db,
         event_graph: EventGraphPtr,
         dnet_sub: JsonSubscriber,
+        p2p_sub: JsonSubscriber,
         deg_sub: JsonSubscriber,
         replay_datastore: PathBuf,
+        dm: DmStore,
+        push: PushGateway,
+        tag_channel_topics: bool,
     ) -> Self {
         Self {
             p2p,
@@ -179,8 +220,12 @@ impl DarkIrc {
             event_graph,
             rpc_connections: Mutex::new(HashSet::new()),
             dnet_sub,
+            p2p_sub,
             deg_sub,
             replay_datastore,
+            dm,
+            push,
+            tag_channel_topics,
         }
     }
 }
@@ -339,6 +384,34 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
             return Err(e.into());
         }
     };
+
+    if let Some(name) = args.add_account {
+        let mut pw = String::new();
+
+        print!("Enter account password: ");
+        std::io::stdout().flush()?;
+        std::io::stdin().read_line(&mut pw)?;
+
+        if let Some('\n') = pw.chars().next_back() {
+            pw.pop();
+        }
+        if let Some('\r') = pw.chars().next_back() {
+            pw.pop();
+        }
+
+        let identities = NickIdentities::new(&sled_db)?;
+        match identities.create_account(&name, &pw).await? {
+            Some(account) => println!(
+                "Created account {name} with identity {}",
+                PublicKey::from_secret(account.secret)
+            ),
+            None => println!("Account {name} already exists"),
+        }
+        sled_db.flush_async().await?;
+
+        return Ok(());
+    }
+
     let mut p2p_settings: darkfi::net::Settings = args.net.into();
     p2p_settings.app_version = semver::Version::parse(env!("CARGO_PKG_VERSION")).unwrap();
     let p2p = match P2p::new(p2p_settings, ex.clone()).await {
@@ -355,6 +428,7 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
         replay_mode,
         "darkirc_dag",
         1,
+        RetentionPolicy::default(),
         ex.clone(),
     )
     .await
@@ -366,6 +440,13 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
         }
     };
 
+    // Light nodes only follow the configured channels
+    if !args.sync_channels.is_empty() {
+        info!("Selectively syncing channels: {:?}", args.sync_channels);
+        let topics = args.sync_channels.iter().map(|channel| channel_topic(channel)).collect();
+        event_graph.set_topics(Some(topics)).await;
+    }
+
     let prune_task = event_graph.prune_task.get().unwrap();
 
     info!("Registering EventGraph P2P protocol");
@@ -402,6 +483,21 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
         ex.clone(),
     );
 
+    info!("Starting P2P events subs task");
+    let p2p_sub = JsonSubscriber::new("p2p.subscribe_events");
+    let p2p_events_task = StoppableTask::new();
+    p2p_events_task.clone().start(
+        forward_p2p_events(p2p.clone(), p2p_sub.clone()),
+        |res| async {
+            match res {
+                Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                Err(e) => panic!("{e}"),
+            }
+        },
+        Error::DetachedTaskStopped,
+        ex.clone(),
+    );
+
     info!("Starting deg subs task");
     let deg_sub = JsonSubscriber::new("deg.subscribe_events");
     let deg_sub_ = deg_sub.clone();
@@ -428,13 +524,19 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
 
     info!("Starting JSON-RPC server");
     let rpc_settings: RpcSettings = args.rpc.into();
+    let dm = DmStore::new(&sled_db)?;
+    let push = PushGateway::new(&sled_db)?;
     let darkirc = Arc::new(DarkIrc::new(
         p2p.clone(),
         sled_db.clone(),
         event_graph.clone(),
         dnet_sub,
+        p2p_sub,
         deg_sub,
         replay_datastore.clone(),
+        dm,
+        push,
+        args.tag_channel_topics,
     ));
     let darkirc_ = Arc::clone(&darkirc);
     let rpc_task = StoppableTask::new();
@@ -490,6 +592,34 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
         ex.clone(),
     );
 
+    info!("Starting push gateway task");
+    let push_task = StoppableTask::new();
+    push_task.clone().start(
+        push::push_task(irc_server.clone(), ex.clone()),
+        |res| async move {
+            match res {
+                Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                Err(e) => error!("Failed stopping push gateway task: {e}"),
+            }
+        },
+        Error::DetachedTaskStopped,
+        ex.clone(),
+    );
+
+    info!("Starting nick registrations task");
+    let registrations_task = StoppableTask::new();
+    registrations_task.clone().start(
+        irc::identity::registrations_task(irc_server.clone()),
+        |res| async move {
+            match res {
+                Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                Err(e) => error!("Failed stopping nick registrations task: {e}"),
+            }
+        },
+        Error::DetachedTaskStopped,
+        ex.clone(),
+    );
+
     info!("Starting P2P network");
     if let Err(e) = p2p.clone().start().await {
         error!("P2P failed to start: {e}");
@@ -527,10 +657,13 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
     info!("Stopping JSON-RPC server");
     rpc_task.stop().await;
     dnet_task.stop().await;
+    p2p_events_task.stop().await;
     deg_task.stop().await;
 
     info!("Stopping IRC server");
     irc_task.stop().await;
+    push_task.stop().await;
+    registrations_task.stop().await;
     prune_task.stop().await;
 
     info!("Flushing sled database...");
@@ -0,0 +1,563 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Push notification gateway.
+//!
+//! Mobile devices usually can't keep the P2P sockets alive in the
+//! background. A user-designated darkirc node, which holds the channel
+//! and contact keys, can instead watch incoming events for mentions and
+//! direct messages, and forward compact notifications to a push endpoint.
+//!
+//! Devices register their endpoint and filters with the gateway using a
+//! registration encrypted and authenticated with their device key, so
+//! only allowlisted devices can register and the filters never travel
+//! or rest in plaintext. The notifications are sealed to the device key,
+//! so the push service only ever sees opaque blobs.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use crypto_box::{ChaChaBox, PublicKey, SecretKey};
+use darkfi::{event_graph::Event, system::timeout::timeout, Error, Result};
+use darkfi_serial::{deserialize_async, serialize_async, SerialDecodable, SerialEncodable};
+use futures_rustls::{
+    rustls::{self, pki_types::ServerName},
+    TlsConnector,
+};
+use log::{debug, error, info, warn};
+use sled_overlay::sled;
+use smol::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    lock::{RwLock, Semaphore},
+    net::TcpStream,
+    Executor,
+};
+use url::Url;
+
+use crate::{
+    crypto::saltbox,
+    irc::{server::IrcServer, Msg, Privmsg},
+};
+
+/// Max amount of characters of the message included in a notification
+pub const PUSH_PREVIEW_LEN: usize = 64;
+
+/// Timeout for a push endpoint request, in seconds
+const PUSH_TIMEOUT: u64 = 10;
+
+/// Max amount of deliveries in flight, further notifications get dropped
+const PUSH_MAX_DELIVERIES: usize = 32;
+
+/// Nickname used when decrypting, so our own DMs can be recognized
+const SELF_NICK: &str = "*";
+
+/// Push gateway configuration, set from the `[push]` config section
+#[derive(Clone)]
+pub struct PushConfig {
+    /// Gateway secret key, devices encrypt their registrations to its public key
+    pub secret: SecretKey,
+    /// Public keys of the devices allowed to register
+    pub devices: HashSet<[u8; 32]>,
+    /// TLS client configuration used for https:// endpoints
+    pub tls: Option<Arc<rustls::ClientConfig>>,
+}
+
+/// Device registration plaintext, as encrypted by the device
+#[derive(Clone, Debug, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct PushRegistration {
+    /// UNIX timestamp of the registration, older ones can't replace newer ones
+    pub timestamp: u64,
+    /// http:// or https:// endpoint the notifications get posted to
+    pub endpoint: String,
+    /// Nicknames triggering a notification when mentioned
+    pub mentions: Vec<String>,
+    /// Notify on every direct message
+    pub dms: bool,
+    /// Channels where every message triggers a notification
+    pub channels: Vec<String>,
+}
+
+/// Notification plaintext, as decrypted by the device
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct PushNotification {
+    /// ID of the event the notification refers to
+    pub event_id: [u8; 32],
+    /// Event timestamp
+    pub timestamp: u64,
+    /// Channel or contact the message was sent to
+    pub channel: String,
+    /// Message author
+    pub nick: String,
+    /// First characters of the message
+    pub preview: String,
+}
+
+impl PushRegistration {
+    /// A registration without any filter unregisters the device
+    pub fn is_empty(&self) -> bool {
+        self.mentions.is_empty() && !self.dms && self.channels.is_empty()
+    }
+
+    /// Check if a decrypted `Privmsg` should trigger a notification
+    pub fn matches(&self, privmsg: &Privmsg, is_dm: bool) -> bool {
+        if is_dm {
+            return self.dms
+        }
+
+        // Don't notify about our own messages
+        if self.mentions.iter().any(|m| m.eq_ignore_ascii_case(&privmsg.nick)) {
+            return false
+        }
+
+        if self.channels.contains(&privmsg.channel) {
+            return true
+        }
+
+        privmsg
+            .msg
+            .split(|c: char| !c.is_alphanumeric() && c != '_' && c != '-')
+            .any(|word| self.mentions.iter().any(|m| m.eq_ignore_ascii_case(word)))
+    }
+}
+
+/// Open a registration encrypted by a device to the gateway key.
+///
+/// The format is `device_public||nonce||ciphertext`, where the device
+/// public key is 32 bytes large and the nonce is 24 bytes large. The
+/// ciphertext is made with `ChaChaBox(gateway_public, device_secret)`,
+/// so a successful decryption also authenticates the device.
+pub async fn open_registration(
+    config: &PushConfig,
+    payload: &[u8],
+) -> Result<([u8; 32], PushRegistration)> {
+    if payload.len() < 32 + 25 {
+        return Err(Error::Custom("Push registration too short".into()))
+    }
+
+    let device: [u8; 32] = payload[..32].try_into().unwrap();
+    if !config.devices.contains(&device) {
+        return Err(Error::Custom("Push registration from unknown device".into()))
+    }
+
+    let salt_box = ChaChaBox::new(&PublicKey::from(device), &config.secret);
+    let Some(plaintext) = saltbox::try_decrypt(&salt_box, &payload[32..]) else {
+        return Err(Error::Custom("Push registration failed to decrypt".into()))
+    };
+
+    let mut registration: PushRegistration = deserialize_async(&plaintext).await?;
+    if !registration.is_empty() {
+        let Ok(endpoint) = Url::parse(&registration.endpoint) else {
+            return Err(Error::Custom("Push endpoint not a valid URL".into()))
+        };
+        match endpoint.scheme() {
+            "http" => {}
+            "https" if config.tls.is_some() => {}
+            "https" => return Err(Error::Custom("Push gateway has no TLS roots".into())),
+            _ => return Err(Error::Custom("Push endpoint must use http:// or https://".into())),
+        }
+        if endpoint.host_str().is_none() {
+            return Err(Error::Custom("Push endpoint has no host".into()))
+        }
+    }
+
+    registration.mentions.iter_mut().for_each(|m| *m = m.to_lowercase());
+    Ok((device, registration))
+}
+
+/// Device registrations and the deliveries made to them
+pub struct PushGateway {
+    /// Gateway configuration, `None` when the gateway is disabled
+    config: RwLock<Option<PushConfig>>,
+    /// Opened registrations of the allowlisted devices
+    registrations: RwLock<HashMap<[u8; 32], PushRegistration>>,
+    /// Encrypted registrations keyed by device public key
+    tree: sled::Tree,
+    /// Bounds the amount of deliveries in flight
+    deliveries: Arc<Semaphore>,
+}
+
+impl PushGateway {
+    /// Open the push registrations store
+    pub fn new(sled_db: &sled::Db) -> Result<Self> {
+        Ok(Self {
+            config: RwLock::new(None),
+            registrations: RwLock::new(HashMap::new()),
+            tree: sled_db.open_tree("push_registrations")?,
+            deliveries: Arc::new(Semaphore::new(PUSH_MAX_DELIVERIES)),
+        })
+    }
+
+    /// Replace the gateway configuration, and reopen the stored registrations
+    /// with it. Registrations of devices which aren't allowlisted anymore are
+    /// kept on disk, but ignored.
+    pub async fn configure(&self, config: Option<PushConfig>) -> Result<()> {
+        let mut registrations = HashMap::new();
+        if let Some(config) = &config {
+            let payloads: Vec<sled::IVec> =
+                self.tree.iter().values().collect::<std::result::Result<_, _>>()?;
+            for payload in payloads {
+                match open_registration(config, &payload).await {
+                    Ok((device, registration)) => {
+                        registrations.insert(device, registration);
+                    }
+                    Err(e) => debug!(target: "darkirc::push", "Skipping stored registration: {e}"),
+                }
+            }
+            info!(target: "darkirc::push", "Push gateway serving {} devices", registrations.len());
+        }
+
+        *self.config.write().await = config;
+        *self.registrations.write().await = registrations;
+        Ok(())
+    }
+
+    /// Register a device, or unregister it if the registration has no filters
+    pub async fn register(&self, payload: &[u8]) -> Result<()> {
+        let Some(config) = self.config.read().await.clone() else {
+            return Err(Error::Custom("Push gateway is not configured".into()))
+        };
+
+        let (device, registration) = open_registration(&config, payload).await?;
+
+        let mut registrations = self.registrations.write().await;
+        if let Some(current) = registrations.get(&device) {
+            if current.timestamp >= registration.timestamp {
+                return Err(Error::Custom("Push registration is outdated".into()))
+            }
+        }
+
+        if registration.is_empty() {
+            self.tree.remove(device)?;
+            registrations.remove(&device);
+            info!(target: "darkirc::push", "Unregistered push device {}", bs58::encode(device).into_string());
+            return Ok(())
+        }
+
+        self.tree.insert(device, payload)?;
+        registrations.insert(device, registration);
+        info!(target: "darkirc::push", "Registered push device {}", bs58::encode(device).into_string());
+        Ok(())
+    }
+
+    /// Spawn deliveries of the notification to the matching devices.
+    /// Notifications get dropped when too many deliveries are in flight,
+    /// so a slow endpoint can't stall the gateway.
+    async fn notify(
+        &self,
+        event: &Event,
+        privmsg: &Privmsg,
+        is_dm: bool,
+        ex: &Arc<Executor<'static>>,
+    ) {
+        let Some(config) = self.config.read().await.clone() else { return };
+
+        let notification = PushNotification {
+            event_id: *event.id().as_bytes(),
+            timestamp: event.timestamp,
+            channel: privmsg.channel.clone(),
+            nick: privmsg.nick.clone(),
+            preview: privmsg
+                .msg
+                .lines()
+                .next()
+                .unwrap_or("")
+                .chars()
+                .take(PUSH_PREVIEW_LEN)
+                .collect(),
+        };
+        let plaintext = serialize_async(&notification).await;
+
+        for (device, registration) in self.registrations.read().await.iter() {
+            if !registration.matches(privmsg, is_dm) {
+                continue
+            }
+
+            let Ok(endpoint) = Url::parse(&registration.endpoint) else { continue };
+            let Some(permit) = self.deliveries.try_acquire_arc() else {
+                warn!(target: "darkirc::push", "Too many push deliveries in flight, dropping notification for event {}", event.id());
+                return
+            };
+
+            let body = saltbox::seal(&PublicKey::from(*device), &plaintext);
+            let tls = config.tls.clone();
+
+            debug!(target: "darkirc::push", "Forwarding notification for event {}", event.id());
+            ex.spawn(async move {
+                if let Err(e) = deliver(&endpoint, tls, &body).await {
+                    error!(target: "darkirc::push", "Failed sending push notification to {endpoint}: {e}");
+                }
+                drop(permit);
+            })
+            .detach();
+        }
+    }
+}
+
+/// Build the HTTP request posting the given body to the endpoint
+fn build_request(endpoint: &Url, body: &str) -> Result<String> {
+    let Some(host) = endpoint.host_str() else {
+        return Err(Error::Custom("Push endpoint has no host".into()))
+    };
+    let host = match endpoint.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_string(),
+    };
+    let path = match endpoint.query() {
+        Some(query) => format!("{}?{query}", endpoint.path()),
+        None => endpoint.path().to_string(),
+    };
+
+    Ok(format!(
+        "POST {path} HTTP/1.1\r\nHost: {host}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    ))
+}
+
+/// Write the request to the stream and check the reply status
+async fn post<S: AsyncRead + AsyncWrite + Unpin>(mut stream: S, request: &str) -> Result<()> {
+    stream.write_all(request.as_bytes()).await?;
+    stream.flush().await?;
+
+    // We only care about the status line, i.e. "HTTP/1.1 200"
+    let mut status = [0u8; 12];
+    stream.read_exact(&mut status).await?;
+
+    if !status.starts_with(b"HTTP/") || status[9] != b'2' {
+        let status = String::from_utf8_lossy(&status);
+        return Err(Error::Custom(format!("Push endpoint replied with: {status}")))
+    }
+
+    Ok(())
+}
+
+/// Post an encrypted notification to the push endpoint
+async fn deliver(endpoint: &Url, tls: Option<Arc<rustls::ClientConfig>>, body: &str) -> Result<()> {
+    let request = build_request(endpoint, body)?;
+    let host = endpoint.host_str().unwrap().to_string();
+    let port = endpoint.port_or_known_default().unwrap_or(80);
+
+    timeout(Duration::from_secs(PUSH_TIMEOUT), async {
+        let stream = TcpStream::connect((host.as_str(), port)).await?;
+        if endpoint.scheme() != "https" {
+            return post(stream, &request).await
+        }
+
+        let Some(tls) = tls else {
+            return Err(Error::Custom("Push gateway has no TLS roots".into()))
+        };
+        let Ok(server_name) = ServerName::try_from(host) else {
+            return Err(Error::Custom("Push endpoint host not a valid server name".into()))
+        };
+        let stream = TlsConnector::from(tls).connect(server_name, stream).await?;
+        post(stream, &request).await
+    })
+    .await?
+}
+
+/// Async task watching incoming events and forwarding the ones matching
+/// the registered push filters.
+pub async fn push_task(server: Arc<IrcServer>, ex: Arc<Executor<'static>>) -> Result<()> {
+    let incoming = server.darkirc.event_graph.event_pub.clone().subscribe().await;
+    info!(target: "darkirc::push", "Push gateway task started");
+
+    loop {
+        let event = incoming.receive().await;
+
+        // Don't bother decrypting when nobody is registered
+        if server.darkirc.push.registrations.read().await.is_empty() {
+            continue
+        }
+
+        let mut privmsg = match Msg::from_content(event.content()).await {
+            Ok((Msg::V1(old_msg), _)) => old_msg.into_new(),
+            Ok((Msg::V2(new_msg), _)) => new_msg,
+            Err(_) => continue,
+        };
+
+        server.try_decrypt(&mut privmsg, SELF_NICK, &event.id()).await;
+        if privmsg.nick == SELF_NICK {
+            continue
+        }
+
+        // Channels are prefixed, decrypted contacts and DMs are nicks
+        let is_dm = !privmsg.channel.starts_with('#');
+        server.darkirc.push.notify(&event, &privmsg, is_dm, &ex).await;
+    }
+}
+
+/// Seal a registration to the gateway, as a device would
+#[cfg(test)]
+fn seal_registration(
+    device_secret: &SecretKey,
+    gateway_public: &PublicKey,
+    registration: &PushRegistration,
+) -> Vec<u8> {
+    use crypto_box::aead::{Aead, AeadCore};
+
+    let salt_box = ChaChaBox::new(gateway_public, device_secret);
+    let nonce = ChaChaBox::generate_nonce(&mut rand::rngs::OsRng);
+    let ciphertext = salt_box.encrypt(&nonce, darkfi_serial::serialize(registration).as_slice());
+
+    let mut payload = device_secret.public_key().to_bytes().to_vec();
+    payload.extend_from_slice(nonce.as_slice());
+    payload.extend_from_slice(&ciphertext.unwrap());
+    payload
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::OsRng;
+
+    use super::*;
+
+    fn privmsg(channel: &str, nick: &str, msg: &str) -> Privmsg {
+        Privmsg {
+            version: 0,
+            msg_type: 0,
+            channel: channel.to_string(),
+            nick: nick.to_string(),
+            msg: msg.to_string(),
+        }
+    }
+
+    fn registration(timestamp: u64) -> PushRegistration {
+        PushRegistration {
+            timestamp,
+            endpoint: "http://127.0.0.1:8080/UP?token=darkirc".to_string(),
+            mentions: vec!["Satoshi".to_string()],
+            dms: true,
+            channels: vec!["#dev".to_string()],
+        }
+    }
+
+    #[test]
+    fn registration_filters() {
+        let mut reg = registration(1);
+        reg.mentions = vec!["satoshi".to_string()];
+
+        assert!(reg.matches(&privmsg("#random", "hal", "hey satoshi, ping"), false));
+        assert!(reg.matches(&privmsg("#random", "hal", "SATOSHI: ping"), false));
+        assert!(!reg.matches(&privmsg("#random", "hal", "satoshinakamoto"), false));
+        assert!(reg.matches(&privmsg("#dev", "hal", "anything"), false));
+        assert!(!reg.matches(&privmsg("#dev", "Satoshi", "our own message"), false));
+        assert!(reg.matches(&privmsg("hal", "hal", "hi"), true));
+
+        reg.dms = false;
+        assert!(!reg.matches(&privmsg("hal", "hal", "hi"), true));
+
+        reg.mentions.clear();
+        reg.channels.clear();
+        assert!(reg.is_empty());
+    }
+
+    #[test]
+    fn request_building() -> Result<()> {
+        let endpoint = Url::parse("https://push.example.org/UP?token=abc")?;
+        let request = build_request(&endpoint, "body")?;
+        assert!(request.starts_with("POST /UP?token=abc HTTP/1.1\r\n"));
+        assert!(request.contains("\r\nHost: push.example.org\r\n"));
+        assert!(request.contains("\r\nContent-Length: 4\r\n"));
+        assert!(request.ends_with("\r\n\r\nbody"));
+
+        let endpoint = Url::parse("http://127.0.0.1:8080/")?;
+        let request = build_request(&endpoint, "")?;
+        assert!(request.contains("\r\nHost: 127.0.0.1:8080\r\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn registration_roundtrip() -> Result<()> {
+        smol::block_on(async {
+            let gateway = SecretKey::generate(&mut OsRng);
+            let device = SecretKey::generate(&mut OsRng);
+            let stranger = SecretKey::generate(&mut OsRng);
+
+            let mut devices = HashSet::new();
+            devices.insert(device.public_key().to_bytes());
+            let config = PushConfig { secret: gateway.clone(), devices, tls: None };
+
+            // Allowlisted devices can register, and mentions are normalized
+            let payload = seal_registration(&device, &gateway.public_key(), &registration(1));
+            let (public, opened) = open_registration(&config, &payload).await?;
+            assert_eq!(public, device.public_key().to_bytes());
+            assert_eq!(opened.mentions, vec!["satoshi".to_string()]);
+
+            // Unknown devices are rejected
+            let payload = seal_registration(&stranger, &gateway.public_key(), &registration(1));
+            assert!(open_registration(&config, &payload).await.is_err());
+
+            // Registrations made by someone else for an allowlisted device don't decrypt
+            let mut payload = seal_registration(&stranger, &gateway.public_key(), &registration(1));
+            payload[..32].copy_from_slice(&device.public_key().to_bytes());
+            assert!(open_registration(&config, &payload).await.is_err());
+
+            // https:// endpoints need TLS roots
+            let mut reg = registration(1);
+            reg.endpoint = "https://push.example.org/UP".to_string();
+            let payload = seal_registration(&device, &gateway.public_key(), &reg);
+            assert!(open_registration(&config, &payload).await.is_err());
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn registration_store() -> Result<()> {
+        smol::block_on(async {
+            let sled_db = sled::Config::new().temporary(true).open()?;
+            let gateway = SecretKey::generate(&mut OsRng);
+            let device = SecretKey::generate(&mut OsRng);
+
+            let mut devices = HashSet::new();
+            devices.insert(device.public_key().to_bytes());
+            let config = PushConfig { secret: gateway.clone(), devices, tls: None };
+
+            let push = PushGateway::new(&sled_db)?;
+            let payload = seal_registration(&device, &gateway.public_key(), &registration(2));
+            assert!(push.register(&payload).await.is_err());
+
+            push.configure(Some(config.clone())).await?;
+            push.register(&payload).await?;
+            assert_eq!(push.registrations.read().await.len(), 1);
+
+            // Replaying an older registration is rejected
+            let payload = seal_registration(&device, &gateway.public_key(), &registration(1));
+            assert!(push.register(&payload).await.is_err());
+
+            // Registrations survive a restart
+            let push = PushGateway::new(&sled_db)?;
+            push.configure(Some(config)).await?;
+            assert_eq!(push.registrations.read().await.len(), 1);
+
+            // Registering without filters unregisters
+            let mut reg = registration(3);
+            reg.mentions.clear();
+            reg.dms = false;
+            reg.channels.clear();
+            let payload = seal_registration(&device, &gateway.public_key(), &reg);
+            push.register(&payload).await?;
+            assert!(push.registrations.read().await.is_empty());
+            assert!(push.tree.is_empty());
+
+            Ok(())
+        })
+    }
+}
@@ -15,24 +15,29 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-use std::collections::HashSet;
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
 
 use async_trait::async_trait;
 use darkfi::{
-    event_graph::util::recreate_from_replayer_log,
+    event_graph::{proto::EventPut, util::recreate_from_replayer_log},
     net::P2pPtr,
     rpc::{
-        jsonrpc::{ErrorCode, JsonError, JsonRequest, JsonResponse, JsonResult},
+        health::{HandlerHealth, HealthStatus},
+        jsonrpc::{ErrorCode, JsonError, JsonRequest, JsonResponse, JsonResult, JsonSubscriber},
         p2p_method::HandlerP2p,
         server::RequestHandler,
         util::JsonValue,
     },
     system::StoppableTaskPtr,
 };
-use log::debug;
+use darkfi_sdk::crypto::PublicKey;
+use log::{debug, error};
 use smol::lock::MutexGuard;
 
-use super::DarkIrc;
+use super::{dm::DmStart, DarkIrc};
 
 #[async_trait]
 impl RequestHandler<()> for DarkIrc {
@@ -41,15 +46,27 @@ impl RequestHandler<()> for DarkIrc {
 
         match req.method.as_str() {
             "ping" => self.pong(req.id, req.params).await,
+            "log.set_level" => self.log_set_level(req.id, req.params).await,
             "dnet.switch" => self.dnet_switch(req.id, req.params).await,
             "dnet.subscribe_events" => self.dnet_subscribe_events(req.id, req.params).await,
             "p2p.get_info" => self.p2p_get_info(req.id, req.params).await,
+            "p2p.subscribe_events" => self.p2p_subscribe_events(req.id, req.params).await,
+
+            "health.ping" => self.health_ping(req.id, req.params).await,
+            "health.ready" => self.health_ready(req.id, req.params).await,
+            "health.info" => self.health_info(req.id, req.params).await,
 
             "deg.switch" => self.deg_switch(req.id, req.params).await,
             "deg.subscribe_events" => self.deg_subscribe_events(req.id, req.params).await,
             "eventgraph.get_info" => self.eg_get_info(req.id, req.params).await,
             "eventgraph.replay" => self.eg_rep_info(req.id, req.params).await,
 
+            "dm.identity" => self.dm_identity(req.id, req.params).await,
+            "dm.contacts" => self.dm_contacts(req.id, req.params).await,
+            "dm.start" => self.dm_start(req.id, req.params).await,
+
+            "push.register" => self.push_register(req.id, req.params).await,
+
             _ => JsonError::new(ErrorCode::MethodNotFound, None, req.id).into(),
         }
     }
@@ -167,10 +184,159 @@ impl DarkIrc {
 
         recreate_from_replayer_log(&self.replay_datastore).await
     }
+
+    // RPCAPI:
+    // Get our DM identity public key, which has to be shared with
+    // contacts so they can start a DM session with us.
+    //
+    // --> {"jsonrpc": "2.0", "method": "dm.identity", "params": [], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": "identity", "id": 42}
+    async fn dm_identity(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        JsonResponse::new(JsonValue::String(self.dm.identity().to_string()), id).into()
+    }
+
+    // RPCAPI:
+    // List DM contacts along with their identity and session state,
+    // which is one of `offered`, `pending` or `established`.
+    //
+    // --> {"jsonrpc": "2.0", "method": "dm.contacts", "params": [], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": {"nick": ["identity", "established"]}, "id": 42}
+    async fn dm_contacts(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let contacts = match self.dm.contacts() {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkirc::rpc", "Failed reading DM contacts: {e}");
+                return JsonError::new(ErrorCode::InternalError, None, id).into()
+            }
+        };
+
+        let mut ret = HashMap::new();
+        for (nick, contact) in contacts {
+            let state = match (&contact.session, &contact.offer) {
+                (Some(_), _) => "established",
+                (None, Some(_)) => "pending",
+                (None, None) => "offered",
+            };
+            let contact = JsonValue::Array(vec![
+                JsonValue::String(contact.identity.to_string()),
+                JsonValue::String(state.to_string()),
+            ]);
+            ret.insert(nick, contact);
+        }
+
+        JsonResponse::new(JsonValue::Object(ret), id).into()
+    }
+
+    // RPCAPI:
+    // Start a DM session with the given nick, using `our_nick` as our own
+    // nickname. If the nick already sent us a request it gets accepted,
+    // otherwise our request is sent to the given identity. Returns `true`
+    // if the session got established, and `false` if a request was sent.
+    //
+    // --> {"jsonrpc": "2.0", "method": "dm.start", "params": ["nick", "our_nick", "identity"], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": false, "id": 42}
+    async fn dm_start(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() < 2 || params.len() > 3 || !params.iter().all(|p| p.is_string()) {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let nick = params[0].get::<String>().unwrap();
+        let our_nick = params[1].get::<String>().unwrap();
+        if nick.starts_with('#') {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let identity = match params.get(2) {
+            Some(identity) => match PublicKey::from_str(identity.get::<String>().unwrap()) {
+                Ok(identity) => Some(identity),
+                Err(_) => return JsonError::new(ErrorCode::InvalidParams, None, id).into(),
+            },
+            None => None,
+        };
+
+        if !*self.event_graph.synced.read().await {
+            return JsonError::new(ErrorCode::InternalError, None, id).into()
+        }
+
+        let (event, established) =
+            match self.dm.start(nick, identity, our_nick, &self.event_graph).await {
+                Ok(DmStart::Offered(event)) => (event, false),
+                Ok(DmStart::Accepted(event)) => (event, true),
+                Err(e) => {
+                    error!(target: "darkirc::rpc", "Failed starting DM with {nick}: {e}");
+                    return JsonError::new(ErrorCode::InternalError, None, id).into()
+                }
+            };
+
+        if let Err(e) = self.event_graph.dag_insert(&[event.clone()]).await {
+            error!(target: "darkirc::rpc", "Failed inserting DM event to DAG: {e}");
+            return JsonError::new(ErrorCode::InternalError, None, id).into()
+        }
+        self.p2p.broadcast(&EventPut(event)).await;
+
+        JsonResponse::new(JsonValue::Boolean(established), id).into()
+    }
+
+    // RPCAPI:
+    // Register a device with the push notification gateway, or replace its
+    // endpoint and filters. The registration is base58-encoded and encrypted
+    // by the device to the gateway public key, see `push::open_registration`.
+    // A registration without any filters unregisters the device.
+    // Returns `true` on success.
+    //
+    // --> {"jsonrpc": "2.0", "method": "push.register", "params": ["registration"], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": true, "id": 42}
+    async fn push_register(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let Ok(payload) = bs58::decode(params[0].get::<String>().unwrap()).into_vec() else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+
+        if let Err(e) = self.push.register(&payload).await {
+            error!(target: "darkirc::rpc", "Failed registering push device: {e}");
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        JsonResponse::new(JsonValue::Boolean(true), id).into()
+    }
 }
 
 impl HandlerP2p for DarkIrc {
     fn p2p(&self) -> P2pPtr {
         self.p2p.clone()
     }
+
+    fn p2p_events(&self) -> Option<JsonSubscriber> {
+        Some(self.p2p_sub.clone())
+    }
+}
+
+#[async_trait]
+impl HandlerHealth for DarkIrc {
+    fn health_version(&self) -> (&'static str, &'static str) {
+        (env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    }
+
+    async fn health_status(&self) -> HealthStatus {
+        HealthStatus {
+            synced: *self.event_graph.synced.read().await,
+            peers: self.p2p.hosts().channels().len(),
+            db_ok: self.event_graph.dag_readable(),
+        }
+    }
 }
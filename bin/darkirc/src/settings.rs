@@ -18,20 +18,27 @@
 
 use std::{
     collections::{HashMap, HashSet},
+    fs::File,
+    io::BufReader,
     sync::Arc,
     time::UNIX_EPOCH,
 };
 
 use crypto_box::PublicKey;
-use darkfi::{Error::ParseFailed, Result};
+use darkfi::{util::path::expand_path, Error::ParseFailed, Result};
 use darkfi_sdk::{crypto::pasta_prelude::PrimeField, pasta::pallas};
-use log::info;
+use futures_rustls::rustls;
+use log::{error, info, warn};
 
 use crate::{
     crypto::rln::{closest_epoch, RlnIdentity},
     irc::{IrcChannel, IrcContact},
+    push::PushConfig,
 };
 
+/// Default TLS roots bundle used for https:// push endpoints
+const PUSH_DEFAULT_CA_FILE: &str = "/etc/ssl/certs/ca-certificates.crt";
+
 /// Parse configured autojoin channels from a TOML map.
 ///
 /// ```toml
@@ -296,3 +303,100 @@ pub fn parse_configured_channels(data: &toml::Value) -> Result<HashMap<String, I
 
     Ok(ret)
 }
+
+/// Parse the push notification gateway configuration from a TOML map.
+/// The TLS roots used for https:// endpoints are read from `ca_file`,
+/// defaulting to the system bundle.
+///
+/// ```toml
+/// [push]
+/// secret = "A3mLrq4aW9UkFVY4zCfR2aLdEEWVUdH4u8v4o2dgi4kC"
+/// devices = ["7iTddcopP2pkvszFjbFUr7MwTcMSKZkYP6zUan22pxfX"]
+/// ca_file = "/etc/ssl/certs/ca-certificates.crt"
+/// ```
+pub fn parse_push_config(data: &toml::Value) -> Result<Option<PushConfig>> {
+    let Some(table) = data.as_table() else { return Err(ParseFailed("TOML not a map")) };
+    let Some(push) = table.get("push") else { return Ok(None) };
+
+    let Some(secret) = push.get("secret") else {
+        return Err(ParseFailed(
+            "Push gateway secret missing. \
+        You can generate a keypair with: 'darkirc --gen-chacha-keypair'",
+        ))
+    };
+    let Some(secret) = secret.as_str() else {
+        return Err(ParseFailed("Push gateway secret not a string"))
+    };
+    let Ok(secret_bytes) = bs58::decode(secret).into_vec() else {
+        return Err(ParseFailed("Push gateway secret not valid base58"))
+    };
+    if secret_bytes.len() != 32 {
+        return Err(ParseFailed("Push gateway secret not 32 bytes long"))
+    }
+    let secret_bytes: [u8; 32] = secret_bytes.try_into().unwrap();
+    let secret = crypto_box::SecretKey::from(secret_bytes);
+
+    let mut devices = HashSet::new();
+    let Some(items) = push.get("devices") else {
+        return Err(ParseFailed("Push gateway devices missing"))
+    };
+    let Some(items) = items.as_array() else {
+        return Err(ParseFailed("Push gateway devices not an array"))
+    };
+    for item in items {
+        let Some(device) = item.as_str() else {
+            return Err(ParseFailed("Push gateway device not a string"))
+        };
+        let Ok(public_bytes) = bs58::decode(device).into_vec() else {
+            return Err(ParseFailed("Push gateway device not valid base58"))
+        };
+        if public_bytes.len() != 32 {
+            return Err(ParseFailed("Push gateway device not 32 bytes long"))
+        }
+        devices.insert(public_bytes.try_into().unwrap());
+    }
+
+    let (ca_file, required) = match push.get("ca_file") {
+        Some(ca_file) => {
+            let Some(ca_file) = ca_file.as_str() else {
+                return Err(ParseFailed("Push gateway ca_file not a string"))
+            };
+            (ca_file, true)
+        }
+        None => (PUSH_DEFAULT_CA_FILE, false),
+    };
+
+    // https:// endpoints are only refused later on if the roots are missing,
+    // unless they were explicitly configured.
+    let tls = match load_tls_roots(ca_file) {
+        Ok(roots) => Some(Arc::new(
+            rustls::ClientConfig::builder().with_root_certificates(roots).with_no_client_auth(),
+        )),
+        Err(e) if required => {
+            error!("Failed loading push gateway ca_file {ca_file}: {e}");
+            return Err(ParseFailed("Push gateway ca_file could not be loaded"))
+        }
+        Err(e) => {
+            warn!("Push gateway can't use https:// endpoints, failed loading {ca_file}: {e}");
+            None
+        }
+    };
+
+    info!("Configured push gateway for {} devices", devices.len());
+    Ok(Some(PushConfig { secret, devices, tls }))
+}
+
+/// Read the PEM certificates of the given file into a root store
+fn load_tls_roots(ca_file: &str) -> Result<rustls::RootCertStore> {
+    let f = File::open(expand_path(ca_file)?)?;
+    let mut reader = BufReader::new(f);
+
+    let mut roots = rustls::RootCertStore::empty();
+    let certs = rustls_pemfile::certs(&mut reader).collect::<std::io::Result<Vec<_>>>()?;
+    let (added, _) = roots.add_parsable_certificates(certs);
+    if added == 0 {
+        return Err(ParseFailed("No certificates found"))
+    }
+
+    Ok(roots)
+}
@@ -155,6 +155,12 @@ class Model:
                 key = (f'{name}', 'outbound')
                 event[key] = f'peer discovery: {state} (attempt {attempt})'
                 logging.debug(f'{current_time}  peer_discovery: {state} (attempt {attempt})')
+            case 'peer_penalized':
+                chan = info.get('chan')
+                addr = chan.get('addr')
+                misbehavior = info['misbehavior']
+                score = info['score']
+                logging.debug(f'{current_time}  penalized {addr}: {misbehavior} (score {score})')
 
 
     def add_lilith(self, lilith):
@@ -34,7 +34,15 @@ CREATE TABLE IF NOT EXISTS BZHKGQ26bzmBithTQYTJtjo2QdCqpkR9tjSBopT4yf4o_money_co
 	secret BLOB NOT NULL,
 	leaf_position BLOB NOT NULL,
 	memo BLOB,
-	spent_tx_hash TEXT DEFAULT '-'
+	spent_tx_hash TEXT DEFAULT '-',
+	sender BLOB
+);
+
+-- Unspent balance per token, derived from the coins table
+-- after every scanned block or wallet coins update
+CREATE TABLE IF NOT EXISTS BZHKGQ26bzmBithTQYTJtjo2QdCqpkR9tjSBopT4yf4o_money_balances (
+	token_id BLOB PRIMARY KEY NOT NULL,
+	balance BLOB NOT NULL
 );
 
 -- Arbitrary tokens
@@ -50,3 +58,32 @@ CREATE TABLE IF NOT EXISTS BZHKGQ26bzmBithTQYTJtjo2QdCqpkR9tjSBopT4yf4o_money_al
 	alias BLOB PRIMARY KEY NOT NULL,
 	token_id BLOB NOT NULL
 );
+
+-- Addresses we monitor without spending from them, optionally
+-- along with the secret key used to trial-decrypt their notes
+CREATE TABLE IF NOT EXISTS BZHKGQ26bzmBithTQYTJtjo2QdCqpkR9tjSBopT4yf4o_money_watched (
+	public BLOB PRIMARY KEY NOT NULL,
+	label TEXT NOT NULL,
+	view_secret BLOB
+);
+
+-- Coins paying to watched addresses
+CREATE TABLE IF NOT EXISTS BZHKGQ26bzmBithTQYTJtjo2QdCqpkR9tjSBopT4yf4o_money_watched_coins (
+	coin BLOB PRIMARY KEY NOT NULL,
+	public BLOB NOT NULL,
+	value BLOB,
+	token_id BLOB,
+	is_decrypted INTEGER NOT NULL,
+	tx_hash TEXT DEFAULT '-'
+);
+
+-- Coins of an offline signer, imported from their openings, see `offline.rs`
+CREATE TABLE IF NOT EXISTS BZHKGQ26bzmBithTQYTJtjo2QdCqpkR9tjSBopT4yf4o_money_offline_coins (
+	coin BLOB PRIMARY KEY NOT NULL,
+	public BLOB NOT NULL,
+	value BLOB NOT NULL,
+	token_id BLOB NOT NULL,
+	nullifier BLOB NOT NULL,
+	is_spent INTEGER NOT NULL,
+	spent_tx_hash TEXT DEFAULT '-'
+);
@@ -172,6 +172,16 @@ pub fn generate_completions(shell: &str) -> Result<()> {
         coins,
     ]);
 
+    // Unlock
+    let unlock = SubCommand::with_name("unlock").about(
+        "Unlock the wallet with a passphrase read from stdin, keeping \
+         it unlocked until it has been idle for `lock_timeout` seconds",
+    );
+
+    // Lock
+    let lock = SubCommand::with_name("lock")
+        .about("Lock the wallet, so it can't be used until unlocked again");
+
     // Spend
     let spend = SubCommand::with_name("spend")
         .about("Read a transaction from stdin and mark its input coins as spent");
@@ -184,9 +194,10 @@ pub fn generate_completions(shell: &str) -> Result<()> {
     // Transfer
     let amount = Arg::with_name("amount").help("Amount to send");
 
-    let token = Arg::with_name("token").help("Token ID to send");
+    let token = Arg::with_name("token")
+        .help("Token ID to send, or `-` to use the recipient contact default token");
 
-    let recipient = Arg::with_name("recipient").help("Recipient address");
+    let recipient = Arg::with_name("recipient").help("Recipient address or contact name");
 
     let spend_hook = Arg::with_name("spend-hook").help("Optional contract spend hook to use");
 
@@ -196,6 +207,15 @@ pub fn generate_completions(shell: &str) -> Result<()> {
         .long("half-split")
         .help("Split the output coin into two equal halves");
 
+    let unsigned = Arg::with_name("unsigned")
+        .long("unsigned")
+        .help("Spend offline signer coins, creating an unsigned transfer to sign offline");
+
+    let fee = Arg::with_name("fee")
+        .long("fee")
+        .takes_value(true)
+        .help("Fee to pay for an unsigned transfer");
+
     let transfer =
         SubCommand::with_name("transfer").about("Create a payment transaction").args(&vec![
             amount.clone(),
@@ -204,8 +224,14 @@ pub fn generate_completions(shell: &str) -> Result<()> {
             spend_hook.clone(),
             user_data.clone(),
             half_split,
+            unsigned,
+            fee,
         ]);
 
+    // SignTransfer
+    let sign_transfer = SubCommand::with_name("sign-transfer")
+        .about("Read an unsigned transfer from stdin, then prove and sign it");
+
     // Otc
     let value_pair = Arg::with_name("value-pair")
         .short("v")
@@ -315,9 +341,14 @@ pub fn generate_completions(shell: &str) -> Result<()> {
             user_data.clone(),
         ]);
 
+    let call = Arg::with_name("call")
+        .long("call")
+        .takes_value(true)
+        .help("Optional base64-encoded contract call the proposal will make when executed");
+
     let propose_generic = SubCommand::with_name("propose-generic")
         .about("Create a generic proposal for a DAO")
-        .args(&vec![name.clone(), duration, user_data.clone()]);
+        .args(&vec![name.clone(), duration, user_data.clone(), call]);
 
     let proposals =
         SubCommand::with_name("proposals").about("List DAO proposals").args(&vec![name]);
@@ -337,6 +368,16 @@ pub fn generate_completions(shell: &str) -> Result<()> {
     let proposal_import = SubCommand::with_name("proposal-import")
         .about("Import a base64 encoded and encrypted proposal from stdin");
 
+    let author = Arg::with_name("author").help("Name to post the comment with");
+
+    let comment = SubCommand::with_name("comment")
+        .about("Encrypt a comment on a proposal, read from stdin, into a discussion event")
+        .args(&vec![bulla.clone(), author]);
+
+    let comments = SubCommand::with_name("comments")
+        .about("View the comments on a proposal, out of discussion events read from stdin")
+        .args(&vec![bulla.clone()]);
+
     let vote = Arg::with_name("vote").help("Vote (0 for NO, 1 for YES)");
 
     let vote_weight =
@@ -369,6 +410,8 @@ pub fn generate_completions(shell: &str) -> Result<()> {
         proposals,
         proposal,
         proposal_import,
+        comment,
+        comments,
         vote,
         exec,
         spend_hook_cmd,
@@ -407,6 +450,25 @@ pub fn generate_completions(shell: &str) -> Result<()> {
         .about("Fetch broadcasted transactions history")
         .args(&vec![tx_hash, encode]);
 
+    let tx_hash = Arg::with_name("tx-hash").help("Transaction hash of the history record");
+
+    let label = Arg::with_name("label").help("Label to set, clears existing label if omitted");
+
+    let label_tx = SubCommand::with_name("label-tx")
+        .about("Set or clear the label of a transactions history record")
+        .args(&vec![tx_hash, label]);
+
+    let format = Arg::with_name("format")
+        .long("format")
+        .takes_value(true)
+        .help("Export format: csv or json (default: csv)");
+
+    let output = Arg::with_name("output").help("File to write the export to (default: stdout)");
+
+    let export_history = SubCommand::with_name("export-history")
+        .about("Export transactions history with value movements and labels")
+        .args(&vec![format, output]);
+
     let clear_reverted =
         SubCommand::with_name("clear-reverted").about("Remove reverted transactions from history");
 
@@ -416,9 +478,16 @@ pub fn generate_completions(shell: &str) -> Result<()> {
         .about("Fetch scanned blocks records")
         .args(&vec![height]);
 
-    let explorer = SubCommand::with_name("explorer")
-        .about("Explorer related subcommands")
-        .subcommands(vec![fetch_tx, simulate_tx, txs_history, clear_reverted, scanned_blocks]);
+    let explorer =
+        SubCommand::with_name("explorer").about("Explorer related subcommands").subcommands(vec![
+            fetch_tx,
+            simulate_tx,
+            txs_history,
+            label_tx,
+            export_history,
+            clear_reverted,
+            scanned_blocks,
+        ]);
 
     // Alias
     let alias = Arg::with_name("alias").help("Token alias");
@@ -454,6 +523,61 @@ pub fn generate_completions(shell: &str) -> Result<()> {
         .about("Manage Token aliases")
         .subcommands(vec![add, show, remove]);
 
+    // Offline
+    let from = Arg::with_name("from").help("Height to start from, the signer's last scanned block");
+
+    let export_blocks = SubCommand::with_name("export-blocks")
+        .about(
+            "Fetch blocks from darkfid, starting from given height, for the offline signer to scan",
+        )
+        .arg(from);
+
+    let import_blocks = SubCommand::with_name("import-blocks")
+        .about("Read blocks from stdin and scan them, on the offline signer");
+
+    let export_coins = SubCommand::with_name("export-coins")
+        .about("Print the openings of our unspent coins, for a watch-only wallet to track them");
+
+    let import_coins = SubCommand::with_name("import-coins")
+        .about("Read coin openings of an offline signer from stdin and track their coins");
+
+    let coins = SubCommand::with_name("coins").about("Show the tracked offline signer coins");
+
+    let offline = SubCommand::with_name("offline")
+        .about("Watch-only accounts and offline signing")
+        .subcommands(vec![export_blocks, import_blocks, export_coins, import_coins, coins]);
+
+    // Contacts
+    let name = Arg::with_name("name").help("Contact name");
+
+    let address = Arg::with_name("address").help("Contact address");
+
+    let notes = Arg::with_name("notes")
+        .short("n")
+        .long("notes")
+        .takes_value(true)
+        .help("Optional notes about the contact");
+
+    let token = Arg::with_name("token")
+        .short("t")
+        .long("token")
+        .takes_value(true)
+        .help("Optional Token ID or alias to send the contact by default");
+
+    let add = SubCommand::with_name("add")
+        .about("Create or replace a contact")
+        .args(&vec![name, address, notes, token]);
+
+    let list = SubCommand::with_name("list").about("List all the contacts in the wallet");
+
+    let name = Arg::with_name("name").help("Contact name to remove");
+
+    let remove = SubCommand::with_name("remove").about("Remove a contact").arg(name);
+
+    let contacts = SubCommand::with_name("contacts")
+        .about("Manage the wallet address book")
+        .subcommands(vec![add, list, remove]);
+
     // Token
     let secret_key = Arg::with_name("secret-key").help("Mint authority secret key");
 
@@ -473,22 +597,35 @@ pub fn generate_completions(shell: &str) -> Result<()> {
 
     let amount = Arg::with_name("amount").help("Amount to mint");
 
-    let recipient = Arg::with_name("recipient").help("Recipient of the minted tokens");
+    let recipient =
+        Arg::with_name("recipient").help("Recipient address or contact name of the minted tokens");
+
+    let max_supply = Arg::with_name("max-supply")
+        .long("max-supply")
+        .takes_value(true)
+        .help("Cap the token supply to this amount on its first mint");
 
     let mint = SubCommand::with_name("mint")
         .about("Mint tokens")
-        .args(&vec![token, amount, recipient, spend_hook, user_data]);
+        .args(&vec![token, amount, recipient, spend_hook, user_data, max_supply]);
 
     let token = Arg::with_name("token").help("Token ID to freeze");
 
     let freeze = SubCommand::with_name("freeze").about("Freeze a token mint").arg(token);
 
+    let token = Arg::with_name("token").help("Token ID to query");
+
+    let supply = SubCommand::with_name("supply")
+        .about("Show a token's supply policy and minted amount")
+        .arg(token);
+
     let token = SubCommand::with_name("token").about("Token functionalities").subcommands(vec![
         import,
         generate_mint,
         list,
         mint,
         freeze,
+        supply,
     ]);
 
     // Main arguments
@@ -508,9 +645,12 @@ pub fn generate_completions(shell: &str) -> Result<()> {
         ping,
         completions,
         wallet,
+        unlock,
+        lock,
         spend,
         unspend,
         transfer,
+        sign_transfer,
         otc,
         attach_fee,
         inspect,
@@ -520,6 +660,8 @@ pub fn generate_completions(shell: &str) -> Result<()> {
         scan,
         explorer,
         alias,
+        contacts,
+        offline,
         token,
     ];
 
@@ -0,0 +1,166 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::str::FromStr;
+
+use rusqlite::types::Value;
+
+use darkfi::{Error, Result};
+use darkfi_money_contract::model::TokenId;
+use darkfi_sdk::crypto::PublicKey;
+use darkfi_serial::{deserialize_async, serialize_async};
+
+use crate::{error::WalletDbResult, Drk};
+
+// Wallet SQL table constant names. These have to represent the `wallet.sql`
+// SQL schema.
+const WALLET_CONTACTS_TABLE: &str = "contacts";
+const WALLET_CONTACTS_COL_NAME: &str = "name";
+const WALLET_CONTACTS_COL_ADDRESS: &str = "address";
+const WALLET_CONTACTS_COL_NOTES: &str = "notes";
+const WALLET_CONTACTS_COL_TOKEN_ID: &str = "token_id";
+
+/// An address book record
+#[derive(Clone, Debug)]
+pub struct Contact {
+    pub name: String,
+    pub address: PublicKey,
+    pub notes: Option<String>,
+    /// Token sent to the contact when none is provided
+    pub token_id: Option<TokenId>,
+}
+
+impl Drk {
+    /// Create or replace an address book record.
+    /// Names that are valid addresses themselves are rejected, so a
+    /// recipient string always has a single meaning.
+    pub async fn add_contact(&self, contact: &Contact) -> Result<()> {
+        if contact.name.is_empty() || PublicKey::from_str(&contact.name).is_ok() {
+            return Err(Error::Custom(format!("Invalid contact name: {}", contact.name)))
+        }
+
+        println!("Adding contact {} with address {}", contact.name, contact.address);
+        let query = format!(
+            "INSERT OR REPLACE INTO {WALLET_CONTACTS_TABLE} ({WALLET_CONTACTS_COL_NAME}, {WALLET_CONTACTS_COL_ADDRESS}, {WALLET_CONTACTS_COL_NOTES}, {WALLET_CONTACTS_COL_TOKEN_ID}) VALUES (?1, ?2, ?3, ?4);"
+        );
+        let token_id = match contact.token_id {
+            Some(token_id) => Some(serialize_async(&token_id).await),
+            None => None,
+        };
+        if let Err(e) = self.wallet.exec_sql(
+            &query,
+            rusqlite::params![
+                contact.name,
+                serialize_async(&contact.address).await,
+                contact.notes,
+                token_id,
+            ],
+        ) {
+            return Err(Error::DatabaseError(format!(
+                "[add_contact] Contact insertion failed: {e:?}"
+            )))
+        }
+
+        Ok(())
+    }
+
+    /// Fetch all address book records, sorted by name.
+    pub async fn get_contacts(&self) -> Result<Vec<Contact>> {
+        let rows = match self.wallet.query_multiple(WALLET_CONTACTS_TABLE, &[], &[]) {
+            Ok(r) => r,
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[get_contacts] Contacts retrieval failed: {e:?}"
+                )))
+            }
+        };
+
+        let mut ret = Vec::with_capacity(rows.len());
+        for row in rows {
+            let Value::Text(ref name) = row[0] else {
+                return Err(Error::ParseFailed("[get_contacts] Name parsing failed"))
+            };
+
+            let Value::Blob(ref address_bytes) = row[1] else {
+                return Err(Error::ParseFailed("[get_contacts] Address bytes parsing failed"))
+            };
+            let address = deserialize_async(address_bytes).await?;
+
+            let notes = match row[2] {
+                Value::Text(ref notes) => Some(notes.clone()),
+                Value::Null => None,
+                _ => return Err(Error::ParseFailed("[get_contacts] Notes parsing failed")),
+            };
+
+            let token_id = match row[3] {
+                Value::Blob(ref token_id_bytes) => Some(deserialize_async(token_id_bytes).await?),
+                Value::Null => None,
+                _ => return Err(Error::ParseFailed("[get_contacts] TokenId bytes parsing failed")),
+            };
+
+            ret.push(Contact { name: name.clone(), address, notes, token_id });
+        }
+        ret.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(ret)
+    }
+
+    /// Remove provided address book record from the wallet database.
+    pub async fn remove_contact(&self, name: &str) -> WalletDbResult<()> {
+        println!("Removing contact: {name}");
+        let query =
+            format!("DELETE FROM {WALLET_CONTACTS_TABLE} WHERE {WALLET_CONTACTS_COL_NAME} = ?1;");
+        self.wallet.exec_sql(&query, rusqlite::params![name])
+    }
+
+    /// Retrieve the address book record matching provided name.
+    /// An exact name match is preferred, otherwise the name must be a
+    /// prefix of exactly one contact name.
+    pub async fn get_contact(&self, name: &str) -> Result<Contact> {
+        let contacts = self.get_contacts().await?;
+        if let Some(contact) = contacts.iter().find(|c| c.name == name) {
+            return Ok(contact.clone())
+        }
+
+        let mut matches: Vec<Contact> =
+            contacts.into_iter().filter(|c| c.name.starts_with(name)).collect();
+        match matches.len() {
+            0 => Err(Error::Custom(format!("Contact not found: {name}"))),
+            1 => Ok(matches.remove(0)),
+            _ => {
+                let names: Vec<String> = matches.into_iter().map(|c| c.name).collect();
+                Err(Error::Custom(format!(
+                    "Ambiguous contact name {name}, matches: {}",
+                    names.join(", ")
+                )))
+            }
+        }
+    }
+
+    /// Retrieve recipient by provided string.
+    /// Input string represents either an address or a contact name.
+    /// Returns the address along with the contact default token, if any.
+    pub async fn get_recipient(&self, input: &str) -> Result<(PublicKey, Option<TokenId>)> {
+        if let Ok(address) = PublicKey::from_str(input) {
+            return Ok((address, None))
+        }
+
+        let contact = self.get_contact(input).await?;
+        Ok((contact.address, contact.token_id))
+    }
+}
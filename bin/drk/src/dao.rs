@@ -25,7 +25,10 @@ use rusqlite::types::Value;
 
 use darkfi::{
     tx::{ContractCallLeaf, Transaction, TransactionBuilder},
-    util::parse::{decode_base10, encode_base10},
+    util::{
+        parse::{decode_base10, encode_base10},
+        time::Timestamp,
+    },
     zk::{empty_witnesses, halo2::Field, ProvingKey, ZkCircuit},
     zkas::ZkBinary,
     Error, Result,
@@ -33,18 +36,20 @@ use darkfi::{
 use darkfi_dao_contract::{
     blockwindow,
     client::{
-        make_mint_call, DaoAuthMoneyTransferCall, DaoExecCall, DaoProposeCall,
-        DaoProposeStakeInput, DaoVoteCall, DaoVoteInput,
+        discussion_tag, make_mint_call, DaoAuthMoneyTransferCall, DaoComment, DaoDiscussionEvent,
+        DaoDiscussions, DaoExecCall, DaoMember, DaoMemberSet, DaoProposeCall, DaoProposeStakeInput,
+        DaoVoteCall, DaoVoteInput, DaoVoteMemberCall, DaoVoteMemberInput,
     },
     model::{
-        Dao, DaoAuthCall, DaoBulla, DaoExecParams, DaoMintParams, DaoProposal, DaoProposalBulla,
-        DaoProposeParams, DaoVoteParams,
+        Dao, DaoAuthCall, DaoBulla, DaoClaimStreamParams, DaoExecParams, DaoMintParams,
+        DaoProposal, DaoProposalBulla, DaoProposeParams, DaoVoteParams, VecAuthCallCommit,
     },
     DaoFunction, DAO_CONTRACT_ZKAS_DAO_AUTH_MONEY_TRANSFER_ENC_COIN_NS,
     DAO_CONTRACT_ZKAS_DAO_AUTH_MONEY_TRANSFER_NS, DAO_CONTRACT_ZKAS_DAO_EARLY_EXEC_NS,
     DAO_CONTRACT_ZKAS_DAO_EXEC_NS, DAO_CONTRACT_ZKAS_DAO_MINT_NS,
     DAO_CONTRACT_ZKAS_DAO_PROPOSE_INPUT_NS, DAO_CONTRACT_ZKAS_DAO_PROPOSE_MAIN_NS,
-    DAO_CONTRACT_ZKAS_DAO_VOTE_INPUT_NS, DAO_CONTRACT_ZKAS_DAO_VOTE_MAIN_NS,
+    DAO_CONTRACT_ZKAS_DAO_VOTE_INPUT_MEMBER_NS, DAO_CONTRACT_ZKAS_DAO_VOTE_INPUT_NS,
+    DAO_CONTRACT_ZKAS_DAO_VOTE_MAIN_NS,
 };
 use darkfi_money_contract::{
     client::transfer_v1::{select_coins, TransferCallBuilder, TransferCallInput},
@@ -55,6 +60,7 @@ use darkfi_money_contract::{
 use darkfi_sdk::{
     bridgetree,
     crypto::{
+        note::EncryptableNote,
         poseidon_hash,
         smt::{MemoryStorageFp, PoseidonFp, SmtMemoryFp, EMPTY_NODES_FP},
         util::{fp_mod_fv, fp_to_u64},
@@ -141,6 +147,69 @@ pub struct DaoParams {
     pub exec_secret_key: Option<SecretKey>,
     /// DAO strongly supported proposals executor secret key
     pub early_exec_secret_key: Option<SecretKey>,
+    /// Members of an allowlist DAO, which they need to vote
+    pub members: Vec<DaoMember>,
+}
+
+#[derive(SerialEncodable, SerialDecodable)]
+/// `Dao` as encoded by wallets before allowlist DAOs were introduced
+struct LegacyDao {
+    proposer_limit: u64,
+    quorum: u64,
+    early_exec_quorum: u64,
+    approval_ratio_quot: u64,
+    approval_ratio_base: u64,
+    gov_token_id: TokenId,
+    notes_public_key: PublicKey,
+    proposer_public_key: PublicKey,
+    proposals_public_key: PublicKey,
+    votes_public_key: PublicKey,
+    exec_public_key: PublicKey,
+    early_exec_public_key: PublicKey,
+    bulla_blind: BaseBlind,
+}
+
+#[derive(SerialEncodable, SerialDecodable)]
+/// `DaoParams` as encoded by wallets before allowlist DAOs were introduced
+struct LegacyDaoParams {
+    dao: LegacyDao,
+    notes_secret_key: Option<SecretKey>,
+    proposer_secret_key: Option<SecretKey>,
+    proposals_secret_key: Option<SecretKey>,
+    votes_secret_key: Option<SecretKey>,
+    exec_secret_key: Option<SecretKey>,
+    early_exec_secret_key: Option<SecretKey>,
+}
+
+impl From<LegacyDaoParams> for DaoParams {
+    fn from(legacy: LegacyDaoParams) -> Self {
+        let dao = Dao {
+            proposer_limit: legacy.dao.proposer_limit,
+            quorum: legacy.dao.quorum,
+            early_exec_quorum: legacy.dao.early_exec_quorum,
+            approval_ratio_quot: legacy.dao.approval_ratio_quot,
+            approval_ratio_base: legacy.dao.approval_ratio_base,
+            gov_token_id: legacy.dao.gov_token_id,
+            notes_public_key: legacy.dao.notes_public_key,
+            proposer_public_key: legacy.dao.proposer_public_key,
+            proposals_public_key: legacy.dao.proposals_public_key,
+            votes_public_key: legacy.dao.votes_public_key,
+            exec_public_key: legacy.dao.exec_public_key,
+            early_exec_public_key: legacy.dao.early_exec_public_key,
+            membership_root: None,
+            bulla_blind: legacy.dao.bulla_blind,
+        };
+        Self {
+            dao,
+            notes_secret_key: legacy.notes_secret_key,
+            proposer_secret_key: legacy.proposer_secret_key,
+            proposals_secret_key: legacy.proposals_secret_key,
+            votes_secret_key: legacy.votes_secret_key,
+            exec_secret_key: legacy.exec_secret_key,
+            early_exec_secret_key: legacy.early_exec_secret_key,
+            members: vec![],
+        }
+    }
 }
 
 impl DaoParams {
@@ -154,6 +223,7 @@ impl DaoParams {
         approval_ratio_base: u64,
         approval_ratio_quot: u64,
         gov_token_id: TokenId,
+        membership_root: Option<MerkleNode>,
         notes_secret_key: Option<SecretKey>,
         notes_public_key: PublicKey,
         proposer_secret_key: Option<SecretKey>,
@@ -207,6 +277,7 @@ impl DaoParams {
             votes_public_key,
             exec_public_key,
             early_exec_public_key,
+            membership_root,
             bulla_blind,
         };
         Self {
@@ -217,9 +288,25 @@ impl DaoParams {
             votes_secret_key,
             exec_secret_key,
             early_exec_secret_key,
+            members: vec![],
         }
     }
 
+    /// Decode `DaoParams` stored in the wallet along with their DAO
+    /// `bulla`. Params stored before allowlist DAOs were introduced
+    /// don't decode to the stored bulla, and get decoded in their
+    /// legacy format instead.
+    pub async fn decode_stored(bytes: &[u8], bulla: &DaoBulla) -> Result<Self> {
+        if let Ok(params) = deserialize_async::<Self>(bytes).await {
+            if params.dao.to_bulla() == *bulla {
+                return Ok(params)
+            }
+        }
+
+        let legacy: LegacyDaoParams = deserialize_async(bytes).await?;
+        Ok(legacy.into())
+    }
+
     /// Parse provided toml string into `DaoParams`.
     /// If a specific secret key is provided, the corresponding public key
     /// will be derived from it and ignore the provided one.
@@ -286,6 +373,19 @@ impl DaoParams {
         };
         let gov_token_id = TokenId::from_str(gov_token_id)?;
 
+        let membership_root = match table.get("membership_root") {
+            Some(membership_root) => {
+                let Some(membership_root) = membership_root.as_str() else {
+                    return Err(Error::ParseFailed("Invalid membership root: Not a string"))
+                };
+                let Ok(membership_root) = MerkleNode::from_str(membership_root) else {
+                    return Err(Error::ParseFailed("Invalid membership root: Decoding failed"))
+                };
+                Some(membership_root)
+            }
+            None => None,
+        };
+
         let Some(bulla_blind) = table.get("bulla_blind") else {
             return Err(Error::ParseFailed("TOML does not contain bulla blind"))
         };
@@ -294,6 +394,59 @@ impl DaoParams {
         };
         let bulla_blind = BaseBlind::from_str(bulla_blind)?;
 
+        let mut members = vec![];
+        if let Some(entries) = table.get("members") {
+            let Some(entries) = entries.as_array() else {
+                return Err(Error::ParseFailed("Invalid members: Not an array"))
+            };
+            for entry in entries {
+                let Some(public_key) = entry.get("public_key").and_then(|v| v.as_str()) else {
+                    return Err(Error::ParseFailed("Invalid member public key: Not a string"))
+                };
+                let Ok(public_key) = PublicKey::from_str(public_key) else {
+                    return Err(Error::ParseFailed("Invalid member public key: Decoding failed"))
+                };
+
+                let Some(weight) = entry.get("weight").and_then(|v| v.as_str()) else {
+                    return Err(Error::ParseFailed("Invalid member weight: Not a string"))
+                };
+                if f64::from_str(weight).is_err() {
+                    return Err(Error::ParseFailed(
+                        "Invalid member weight: Cannot be parsed to float",
+                    ))
+                }
+                let weight = decode_base10(weight, BALANCE_BASE10_DECIMALS, true)?;
+
+                // Members without an explicit blind get one derived from the
+                // bulla blind, so every DAO member computes the same root.
+                let blind = match entry.get("blind") {
+                    Some(blind) => {
+                        let Some(blind) = blind.as_str() else {
+                            return Err(Error::ParseFailed("Invalid member blind: Not a string"))
+                        };
+                        BaseBlind::from_str(blind)?
+                    }
+                    None => {
+                        let (pub_x, pub_y) = public_key.xy();
+                        Blind(poseidon_hash([bulla_blind.inner(), pub_x, pub_y]))
+                    }
+                };
+
+                members.push(DaoMember { public_key, weight, blind });
+            }
+        }
+
+        // When the members are known, they define the allowlist root
+        let membership_root = if members.is_empty() {
+            membership_root
+        } else {
+            let root = DaoMemberSet::from_members(&members).root();
+            if membership_root.is_some_and(|membership_root| membership_root != root) {
+                return Err(Error::ParseFailed("Membership root does not match the members"))
+            }
+            Some(root)
+        };
+
         // Grab DAO actions keypairs
         let notes_secret_key = match table.get("notes_secret_key") {
             Some(notes_secret_key) => {
@@ -463,13 +616,14 @@ impl DaoParams {
             }
         };
 
-        Ok(Self::new(
+        let mut params = Self::new(
             proposer_limit,
             quorum,
             early_exec_quorum,
             approval_ratio_base,
             approval_ratio_quot,
             gov_token_id,
+            membership_root,
             notes_secret_key,
             notes_public_key,
             proposer_secret_key,
@@ -483,7 +637,10 @@ impl DaoParams {
             early_exec_secret_key,
             early_exec_public_key,
             bulla_blind,
-        ))
+        );
+        params.members = members;
+
+        Ok(params)
     }
 
     /// Generate a toml string containing the DAO configuration.
@@ -518,6 +675,10 @@ impl DaoParams {
             approval_ratio = {}\n\n\
             ## DAO's governance token ID\n\
             gov_token_id = \"{}\"\n\n\
+            ## Root of the DAO members allowlist. When set, votes are weighted\n\
+            ## by membership instead of governance token holdings. It gets\n\
+            ## derived from the `[[members]]` entries, when they are listed.\n\
+            {}\n\n\
             ## Bulla blind\n\
             bulla_blind = \"{}\"\n\n",
             encode_base10(self.dao.proposer_limit, BALANCE_BASE10_DECIMALS),
@@ -525,6 +686,10 @@ impl DaoParams {
             encode_base10(self.dao.early_exec_quorum, BALANCE_BASE10_DECIMALS),
             self.dao.approval_ratio_quot as f64 / self.dao.approval_ratio_base as f64,
             self.dao.gov_token_id,
+            match self.dao.membership_root {
+                Some(root) => format!("membership_root = \"{root}\""),
+                None => "#membership_root = \"\"".to_string(),
+            },
             self.dao.bulla_blind,
         );
 
@@ -584,6 +749,24 @@ impl DaoParams {
             toml += &format!("\nearly_exec_secret_key = \"{secret_key}\"")
         }
 
+        // Allowlist members
+        if !self.members.is_empty() {
+            toml += "\n\n## ====== DAO allowlist members =====\n\n\
+                ## Members vote with their weight, using the secret key of their public key.\n\
+                ## The list must be kept in order, since it defines the membership root.";
+        }
+        for member in &self.members {
+            toml += &format!(
+                "\n\n[[members]]\n\
+                public_key = \"{}\"\n\
+                weight = \"{}\"\n\
+                blind = \"{}\"",
+                member.public_key,
+                encode_base10(member.weight, BALANCE_BASE10_DECIMALS),
+                member.blind,
+            );
+        }
+
         toml
     }
 }
@@ -615,9 +798,13 @@ impl fmt::Display for DaoParams {
             Some(secret_key) => format!("{secret_key}"),
             None => "None".to_string(),
         };
+        let membership_root = match self.dao.membership_root {
+            Some(root) => format!("{root}"),
+            None => "None".to_string(),
+        };
 
         let s = format!(
-            "{}\n{}\n{}: {} ({})\n{}: {} ({})\n{}: {} ({})\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}",
+            "{}\n{}\n{}: {} ({})\n{}: {} ({})\n{}: {} ({})\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}",
             "DAO Parameters",
             "==============",
             "Proposer limit",
@@ -633,6 +820,8 @@ impl fmt::Display for DaoParams {
             self.dao.approval_ratio_quot as f64 / self.dao.approval_ratio_base as f64,
             "Governance Token ID",
             self.dao.gov_token_id,
+            "Membership root",
+            membership_root,
             "Notes Public key",
             self.dao.notes_public_key,
             "Notes Secret key",
@@ -723,6 +912,10 @@ impl fmt::Display for DaoRecord {
             Some(secret_key) => format!("{secret_key}"),
             None => "None".to_string(),
         };
+        let membership_root = match self.params.dao.membership_root {
+            Some(root) => format!("{root}"),
+            None => "None".to_string(),
+        };
 
         // Grab mint information
         let leaf_position = match self.leaf_position {
@@ -739,7 +932,7 @@ impl fmt::Display for DaoRecord {
         };
 
         let s = format!(
-            "{}\n{}\n{}: {}\n{}: {}\n{}: {} ({})\n{}: {} ({})\n{}: {} ({})\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}",
+            "{}\n{}\n{}: {}\n{}: {}\n{}: {} ({})\n{}: {} ({})\n{}: {} ({})\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}",
             "DAO Parameters",
             "==============",
             "Name",
@@ -759,6 +952,8 @@ impl fmt::Display for DaoRecord {
             self.params.dao.approval_ratio_quot as f64 / self.params.dao.approval_ratio_base as f64,
             "Governance Token ID",
             self.params.dao.gov_token_id,
+            "Membership root",
+            membership_root,
             "Notes Public key",
             self.params.dao.notes_public_key,
             "Notes Secret key",
@@ -995,6 +1190,11 @@ impl Drk {
 
     /// Auxiliary function to parse a `DAO_DAOS_TABLE` record.
     async fn parse_dao_record(&self, row: &[Value]) -> Result<DaoRecord> {
+        let Value::Blob(ref bulla_bytes) = row[0] else {
+            return Err(Error::ParseFailed("[parse_dao_record] Bulla bytes parsing failed"))
+        };
+        let bulla = deserialize_async(bulla_bytes).await?;
+
         let Value::Text(ref name) = row[1] else {
             return Err(Error::ParseFailed("[parse_dao_record] Name parsing failed"))
         };
@@ -1003,7 +1203,7 @@ impl Drk {
         let Value::Blob(ref params_bytes) = row[2] else {
             return Err(Error::ParseFailed("[parse_dao_record] Params bytes parsing failed"))
         };
-        let params = deserialize_async(params_bytes).await?;
+        let params = DaoParams::decode_stored(params_bytes, &bulla).await?;
 
         let leaf_position = match row[3] {
             Value::Blob(ref leaf_position_bytes) => {
@@ -1257,10 +1457,27 @@ impl Drk {
             let Some(proposals_secret_key) = dao.params.proposals_secret_key else { continue };
 
             // Try to decrypt the proposal note
-            let Ok(note) = params.note.decrypt::<DaoProposal>(&proposals_secret_key) else {
+            let Ok(note) = DaoProposal::decrypt_note(&params.note, &proposals_secret_key) else {
                 continue
             };
 
+            // The AEAD note is not enforced by the contract, so make sure it matches
+            // the verifiable note, which the propose proof binds to the proposal.
+            let Ok(values) = params.verifiable_note.decrypt_unsafe(&proposals_secret_key) else {
+                continue
+            };
+            if values !=
+                [
+                    note.auth_calls.commit(),
+                    pallas::Base::from(note.duration_blockwindows),
+                    note.user_data,
+                    note.blind.inner(),
+                ]
+            {
+                eprintln!("[apply_dao_propose_data] DAO proposal note doesn't match its verifiable note, skipping");
+                continue
+            }
+
             // We managed to decrypt it. Let's place this in a proper ProposalRecord object
             println!("[apply_dao_propose_data] Managed to decrypt DAO proposal note");
 
@@ -1445,6 +1662,25 @@ impl Drk {
         Ok(true)
     }
 
+    /// Auxiliary function to apply `DaoFunction::ClaimStream` call data to the wallet.
+    /// Stream coins are tracked by the Money contract scanning, so we only check
+    /// if the claimed stream is one paying into our wallet.
+    /// Returns a flag indicating if the provided call refers to our own wallet.
+    async fn apply_dao_claim_stream_data(&self, params: DaoClaimStreamParams) -> Result<bool> {
+        let stream_bulla = params.stream_bulla.inner();
+        let coins = self.get_coins(true).await?;
+        if !coins.iter().any(|(coin, _, _)| coin.note.user_data == stream_bulla) {
+            return Ok(false)
+        }
+
+        println!(
+            "[apply_dao_claim_stream_data] Stream {} claimed up to {}",
+            params.stream_bulla, params.claimed,
+        );
+
+        Ok(true)
+    }
+
     /// Append data related to DAO contract transactions into the wallet database,
     /// and store their inverse queries into the cache.
     /// Returns a flag indicating if the provided data refer to our own wallet.
@@ -1481,6 +1717,17 @@ impl Drk {
                 // Does nothing, just verifies the other calls are correct
                 Ok(false)
             }
+            DaoFunction::AuthCreateStream => {
+                println!("[apply_tx_dao_data] Found Dao::AuthCreateStream call");
+                // Does nothing, just verifies the exec creates the stream coin,
+                // which is picked up by the Money contract scanning.
+                Ok(false)
+            }
+            DaoFunction::ClaimStream => {
+                println!("[apply_tx_dao_data] Found Dao::ClaimStream call");
+                let params: DaoClaimStreamParams = deserialize_async(&data[1..]).await?;
+                self.apply_dao_claim_stream_data(params).await
+            }
         }
     }
 
@@ -2267,12 +2514,14 @@ impl Drk {
         Ok(proposal_record)
     }
 
-    /// Create a DAO generic proposal.
+    /// Create a DAO generic proposal. If a contract call is provided,
+    /// the proposal commits to it and executing the proposal makes it.
     pub async fn dao_propose_generic(
         &self,
         name: &str,
         duration_blockwindows: u64,
         user_data: Option<pallas::Base>,
+        call: Option<ContractCall>,
     ) -> Result<ProposalRecord> {
         // Fetch DAO and check its deployed
         let dao = self.get_dao_by_name(name).await?;
@@ -2295,9 +2544,23 @@ impl Drk {
         let block_target = self.get_block_target().await?;
         let creation_blockwindow = blockwindow(next_block_height, block_target);
 
+        // Generic proposals commit to the call they will make, while
+        // its plaintext data are shared between the members.
+        let (auth_calls, data) = match call {
+            Some(call) => {
+                let Some(auth_call) = DaoAuthCall::generic(call.contract_id, &call.data) else {
+                    return Err(Error::Custom(
+                        "[dao_propose_generic] Contract call data is empty".to_string(),
+                    ))
+                };
+                (vec![auth_call], Some(serialize_async(&call).await))
+            }
+            None => (vec![], None),
+        };
+
         // Create the actual proposal
         let proposal = DaoProposal {
-            auth_calls: vec![],
+            auth_calls,
             creation_blockwindow,
             duration_blockwindows,
             user_data: user_data.unwrap_or(pallas::Base::ZERO),
@@ -2307,7 +2570,7 @@ impl Drk {
 
         let proposal_record = ProposalRecord {
             proposal,
-            data: None,
+            data,
             leaf_position: None,
             money_snapshot_tree: None,
             nullifiers_smt_snapshot: None,
@@ -2325,12 +2588,61 @@ impl Drk {
         Ok(proposal_record)
     }
 
+    /// Encrypt a comment on a DAO proposal into a discussion event, to be
+    /// published on the event graph.
+    pub async fn dao_proposal_comment(
+        &self,
+        bulla: &DaoProposalBulla,
+        author: String,
+        body: String,
+    ) -> Result<DaoDiscussionEvent> {
+        let proposal = self.get_dao_proposal_by_bulla(bulla).await?;
+        let dao = self.get_dao_by_bulla(&proposal.proposal.dao_bulla).await?;
+
+        let comment = DaoComment { author, body, timestamp: Timestamp::current_time().inner() };
+        DaoDiscussionEvent::new(&dao.params.dao, &proposal.proposal, &comment)
+    }
+
+    /// Decrypt the comments on a DAO proposal out of the given discussion
+    /// events. Events of other threads are skipped, and the thread gets
+    /// pruned once the proposal has expired.
+    pub async fn dao_proposal_comments(
+        &self,
+        bulla: &DaoProposalBulla,
+        events: &[DaoDiscussionEvent],
+    ) -> Result<Vec<DaoComment>> {
+        let proposal = self.get_dao_proposal_by_bulla(bulla).await?;
+        let dao = self.get_dao_by_bulla(&proposal.proposal.dao_bulla).await?;
+        let Some(proposals_secret_key) = dao.params.proposals_secret_key else {
+            return Err(Error::Custom(
+                "[dao_proposal_comments] We need the proposals secret key to read comments of this DAO"
+                    .to_string(),
+            ))
+        };
+
+        let tag = discussion_tag(&dao.params.dao, bulla);
+        let mut discussions = DaoDiscussions::new(proposals_secret_key);
+        for event in events.iter().filter(|e| e.tag == tag) {
+            if let Err(e) = discussions.insert(event) {
+                eprintln!("[dao_proposal_comments] Skipping malformed comment: {e}");
+            }
+        }
+
+        // Retrieve next block height and current block time target,
+        // to compute their window.
+        let next_block_height = self.get_next_block_height().await?;
+        let block_target = self.get_block_target().await?;
+        discussions.prune(blockwindow(next_block_height, block_target));
+
+        Ok(discussions.get(&dao.params.dao, bulla))
+    }
+
     /// Create a DAO transfer proposal transaction.
     pub async fn dao_transfer_proposal_tx(&self, proposal: &ProposalRecord) -> Result<Transaction> {
         // Check we know the plaintext data
         if proposal.data.is_none() {
             return Err(Error::Custom(
-                "[dao_transfer_proposal_tx] Proposal plainext data is empty".to_string(),
+                "[dao_transfer_proposal_tx] Proposal plaintext data is empty".to_string(),
             ))
         }
         let proposal_coinattrs: CoinAttributes =
@@ -2791,28 +3103,13 @@ impl Drk {
             }
         }
 
-        // Fetch our own governance OwnCoins to see what our balance is
-        let gov_owncoins = self.get_token_coins(&dao.params.dao.gov_token_id).await?;
-        if gov_owncoins.is_empty() {
-            return Err(Error::Custom(format!(
-                "[dao_vote] Did not find any governance {} coins in wallet",
-                dao.params.dao.gov_token_id
-            )))
+        // Members of allowlist DAOs vote with their whole weight
+        if dao.params.dao.membership_root.is_some() && weight.is_some() {
+            return Err(Error::Custom(
+                "[dao_vote] Allowlist DAO members can't vote with a fractional weight".to_string(),
+            ))
         }
 
-        // Find which governance coins we can use
-        let gov_owncoins_to_use = match weight {
-            Some(_weight) => {
-                // TODO: Build a proper coin selection algorithm so that we can use a
-                // coins combination that matches the requested weight
-                return Err(Error::Custom(
-                    "[dao_vote] Fractional vote weight not supported yet".to_string(),
-                ))
-            }
-            // If no weight was specified, use them all
-            None => gov_owncoins,
-        };
-
         // Now we need to do a lookup for the zkas proof bincodes, and create
         // the circuit objects and proving keys so we can build the transaction.
         // We also do this through the RPC. First we grab the fee call from money.
@@ -2833,82 +3130,198 @@ impl Drk {
         // Now we grab the DAO bins
         let zkas_bins = self.lookup_zkas(&DAO_CONTRACT_ID).await?;
 
-        let Some(dao_vote_burn_zkbin) =
-            zkas_bins.iter().find(|x| x.0 == DAO_CONTRACT_ZKAS_DAO_VOTE_INPUT_NS)
-        else {
-            return Err(Error::Custom("[dao_vote] DAO Vote Burn circuit not found".to_string()))
-        };
-
         let Some(dao_vote_main_zkbin) =
             zkas_bins.iter().find(|x| x.0 == DAO_CONTRACT_ZKAS_DAO_VOTE_MAIN_NS)
         else {
             return Err(Error::Custom("[dao_vote] DAO Vote Main circuit not found".to_string()))
         };
 
-        let dao_vote_burn_zkbin = ZkBinary::decode(&dao_vote_burn_zkbin.1)?;
         let dao_vote_main_zkbin = ZkBinary::decode(&dao_vote_main_zkbin.1)?;
 
-        let dao_vote_burn_circuit =
-            ZkCircuit::new(empty_witnesses(&dao_vote_burn_zkbin)?, &dao_vote_burn_zkbin);
         let dao_vote_main_circuit =
             ZkCircuit::new(empty_witnesses(&dao_vote_main_zkbin)?, &dao_vote_main_zkbin);
 
-        // Creating DAO VoteBurn and VoteMain circuits proving keys
-        let dao_vote_burn_pk = ProvingKey::build(dao_vote_burn_zkbin.k, &dao_vote_burn_circuit);
+        // Creating DAO VoteMain circuit proving key
         let dao_vote_main_pk = ProvingKey::build(dao_vote_main_zkbin.k, &dao_vote_main_circuit);
 
-        // Now create the parameters for the vote tx
-        let signature_secret = SecretKey::random(&mut OsRng);
-        let mut inputs = Vec::with_capacity(gov_owncoins_to_use.len());
-        for gov_owncoin in gov_owncoins_to_use {
-            let nullifier = poseidon_hash([gov_owncoin.secret.inner(), gov_owncoin.coin.inner()]);
-            let vote_nullifier =
-                poseidon_hash([nullifier, gov_owncoin.secret.inner(), proposal_bulla.inner()]);
-            if votes_nullifiers.contains(&vote_nullifier.into()) {
-                return Err(Error::Custom("[dao_vote] Duplicate input nullifier found".to_string()))
-            };
-
-            let input = DaoVoteInput {
-                secret: gov_owncoin.secret,
-                note: gov_owncoin.note.clone(),
-                leaf_position: gov_owncoin.leaf_position,
-                merkle_path: proposal
-                    .money_snapshot_tree
-                    .as_ref()
-                    .unwrap()
-                    .witness(gov_owncoin.leaf_position, 0)
-                    .unwrap(),
-                signature_secret,
-            };
-            inputs.push(input);
-        }
-
         // Retrieve next block height and current block time target,
         // to compute their window.
         let next_block_height = self.get_next_block_height().await?;
         let block_target = self.get_block_target().await?;
         let current_blockwindow = blockwindow(next_block_height, block_target);
 
-        // Generate the Money nullifiers Sparse Merkle Tree
-        let store = MemoryStorageFp { tree: proposal.nullifiers_smt_snapshot.unwrap() };
-        let money_null_smt = SmtMemoryFp::new(store, PoseidonFp::new(), &EMPTY_NODES_FP);
+        let signature_secret = SecretKey::random(&mut OsRng);
+        let (params, proofs) = match dao.params.dao.membership_root {
+            // Allowlist DAO members prove their membership
+            Some(membership_root) => {
+                let members = DaoMemberSet::from_members(&dao.params.members);
+                if dao.params.members.is_empty() || members.root() != membership_root {
+                    return Err(Error::Custom(
+                        "[dao_vote] DAO members list is missing or doesn't match its root"
+                            .to_string(),
+                    ))
+                }
 
-        // Create the vote call
-        let call = DaoVoteCall {
-            money_null_smt: &money_null_smt,
-            inputs,
-            vote_option,
-            proposal: proposal.proposal.clone(),
-            dao: dao.params.dao.clone(),
-            current_blockwindow,
-        };
+                // Find which of our keys are allowlisted
+                let mut inputs = vec![];
+                for secret in self.get_money_secrets().await? {
+                    let public_key = PublicKey::from_secret(secret);
+                    let Some((leaf_position, merkle_path)) = members.witness(&public_key) else {
+                        continue
+                    };
+                    let member = members.members()[u64::from(leaf_position) as usize];
+
+                    let vote_nullifier = poseidon_hash([
+                        secret.inner(),
+                        member.to_leaf().inner(),
+                        proposal_bulla.inner(),
+                    ]);
+                    if votes_nullifiers.contains(&vote_nullifier.into()) {
+                        return Err(Error::Custom(format!(
+                            "[dao_vote] Member {public_key} has already voted"
+                        )))
+                    }
+
+                    inputs.push(DaoVoteMemberInput {
+                        secret,
+                        member,
+                        leaf_position,
+                        merkle_path,
+                        signature_secret,
+                    });
+                }
+                if inputs.is_empty() {
+                    return Err(Error::Custom(
+                        "[dao_vote] None of our keys is a member of the DAO".to_string(),
+                    ))
+                }
 
-        let (params, proofs) = call.make(
-            &dao_vote_burn_zkbin,
-            &dao_vote_burn_pk,
-            &dao_vote_main_zkbin,
-            &dao_vote_main_pk,
-        )?;
+                let Some(dao_vote_member_zkbin) =
+                    zkas_bins.iter().find(|x| x.0 == DAO_CONTRACT_ZKAS_DAO_VOTE_INPUT_MEMBER_NS)
+                else {
+                    return Err(Error::Custom(
+                        "[dao_vote] DAO Vote Member circuit not found".to_string(),
+                    ))
+                };
+                let dao_vote_member_zkbin = ZkBinary::decode(&dao_vote_member_zkbin.1)?;
+                let dao_vote_member_circuit = ZkCircuit::new(
+                    empty_witnesses(&dao_vote_member_zkbin)?,
+                    &dao_vote_member_zkbin,
+                );
+
+                // Creating DAO VoteInputMember circuit proving key
+                let dao_vote_member_pk =
+                    ProvingKey::build(dao_vote_member_zkbin.k, &dao_vote_member_circuit);
+
+                // Create the vote call
+                let call = DaoVoteMemberCall {
+                    inputs,
+                    vote_option,
+                    proposal: proposal.proposal.clone(),
+                    dao: dao.params.dao.clone(),
+                    current_blockwindow,
+                };
+
+                call.make(
+                    &dao_vote_member_zkbin,
+                    &dao_vote_member_pk,
+                    &dao_vote_main_zkbin,
+                    &dao_vote_main_pk,
+                )?
+            }
+
+            // Token based DAOs vote with governance coins
+            None => {
+                // Fetch our own governance OwnCoins to see what our balance is
+                let gov_owncoins = self.get_token_coins(&dao.params.dao.gov_token_id).await?;
+                if gov_owncoins.is_empty() {
+                    return Err(Error::Custom(format!(
+                        "[dao_vote] Did not find any governance {} coins in wallet",
+                        dao.params.dao.gov_token_id
+                    )))
+                }
+
+                // Find which governance coins we can use
+                let gov_owncoins_to_use = match weight {
+                    Some(_weight) => {
+                        // TODO: Build a proper coin selection algorithm so that we can use a
+                        // coins combination that matches the requested weight
+                        return Err(Error::Custom(
+                            "[dao_vote] Fractional vote weight not supported yet".to_string(),
+                        ))
+                    }
+                    // If no weight was specified, use them all
+                    None => gov_owncoins,
+                };
+
+                let Some(dao_vote_burn_zkbin) =
+                    zkas_bins.iter().find(|x| x.0 == DAO_CONTRACT_ZKAS_DAO_VOTE_INPUT_NS)
+                else {
+                    return Err(Error::Custom(
+                        "[dao_vote] DAO Vote Burn circuit not found".to_string(),
+                    ))
+                };
+                let dao_vote_burn_zkbin = ZkBinary::decode(&dao_vote_burn_zkbin.1)?;
+                let dao_vote_burn_circuit =
+                    ZkCircuit::new(empty_witnesses(&dao_vote_burn_zkbin)?, &dao_vote_burn_zkbin);
+
+                // Creating DAO VoteBurn circuit proving key
+                let dao_vote_burn_pk =
+                    ProvingKey::build(dao_vote_burn_zkbin.k, &dao_vote_burn_circuit);
+
+                // Now create the parameters for the vote tx
+                let mut inputs = Vec::with_capacity(gov_owncoins_to_use.len());
+                for gov_owncoin in gov_owncoins_to_use {
+                    let nullifier =
+                        poseidon_hash([gov_owncoin.secret.inner(), gov_owncoin.coin.inner()]);
+                    let vote_nullifier = poseidon_hash([
+                        nullifier,
+                        gov_owncoin.secret.inner(),
+                        proposal_bulla.inner(),
+                    ]);
+                    if votes_nullifiers.contains(&vote_nullifier.into()) {
+                        return Err(Error::Custom(
+                            "[dao_vote] Duplicate input nullifier found".to_string(),
+                        ))
+                    };
+
+                    let input = DaoVoteInput {
+                        secret: gov_owncoin.secret,
+                        note: gov_owncoin.note.clone(),
+                        leaf_position: gov_owncoin.leaf_position,
+                        merkle_path: proposal
+                            .money_snapshot_tree
+                            .as_ref()
+                            .unwrap()
+                            .witness(gov_owncoin.leaf_position, 0)
+                            .unwrap(),
+                        signature_secret,
+                    };
+                    inputs.push(input);
+                }
+
+                // Generate the Money nullifiers Sparse Merkle Tree
+                let store = MemoryStorageFp { tree: proposal.nullifiers_smt_snapshot.unwrap() };
+                let money_null_smt = SmtMemoryFp::new(store, PoseidonFp::new(), &EMPTY_NODES_FP);
+
+                // Create the vote call
+                let call = DaoVoteCall {
+                    money_null_smt: &money_null_smt,
+                    inputs,
+                    vote_option,
+                    proposal: proposal.proposal.clone(),
+                    dao: dao.params.dao.clone(),
+                    current_blockwindow,
+                };
+
+                call.make(
+                    &dao_vote_burn_zkbin,
+                    &dao_vote_burn_pk,
+                    &dao_vote_main_zkbin,
+                    &dao_vote_main_pk,
+                )?
+            }
+        };
 
         // Encode the call
         let mut data = vec![DaoFunction::Vote as u8];
@@ -2968,7 +3381,7 @@ impl Drk {
         // Check we know the plaintext data and they are valid
         if proposal.data.is_none() {
             return Err(Error::Custom(
-                "[dao_exec_transfer] Proposal plainext data is empty".to_string(),
+                "[dao_exec_transfer] Proposal plaintext data is empty".to_string(),
             ))
         }
         let proposal_coinattrs: CoinAttributes =
@@ -3183,6 +3596,7 @@ impl Drk {
             clear_inputs: vec![],
             inputs,
             outputs,
+            sender: None,
             mint_zkbin: mint_zkbin.clone(),
             mint_pk: mint_pk.clone(),
             burn_zkbin: burn_zkbin.clone(),
@@ -3340,6 +3754,19 @@ impl Drk {
             ))
         }
 
+        // Grab the committed contract call, if any
+        let child_calls: Vec<ContractCall> = match &proposal.data {
+            Some(data) if !proposal.proposal.auth_calls.is_empty() => {
+                vec![deserialize_async(data).await?]
+            }
+            _ => vec![],
+        };
+        if child_calls.len() != proposal.proposal.auth_calls.len() {
+            return Err(Error::Custom(
+                "[dao_exec_generic] Proposal plaintext data is empty".to_string(),
+            ))
+        }
+
         // Check proposal is approved
         let votes = self.get_dao_proposal_votes(&proposal.bulla()).await?;
         let mut yes_vote_value = 0;
@@ -3428,17 +3855,33 @@ impl Drk {
         exec_params.encode_async(&mut data).await?;
         let exec_call = ContractCall { contract_id: *DAO_CONTRACT_ID, data };
 
+        // The committed call, if any, becomes the child of the exec call
+        let mut children = vec![];
+        for call in &child_calls {
+            children.push(DarkTree::new(
+                ContractCallLeaf { call: call.clone(), proofs: vec![] },
+                vec![],
+                None,
+                None,
+            ));
+        }
+
         // Create the TransactionBuilder containing above calls
         let mut tx_builder = TransactionBuilder::new(
             ContractCallLeaf { call: exec_call, proofs: exec_proofs },
-            vec![],
+            children,
         )?;
 
         // We first have to execute the fee-less tx to gather its used gas, and then we feed
         // it into the fee-creating function.
         let mut tx = tx_builder.build()?;
+        tx.signatures = vec![];
+        for _ in &child_calls {
+            let sigs = tx.create_sigs(&[])?;
+            tx.signatures.push(sigs);
+        }
         let exec_sigs = tx.create_sigs(&[exec_signature_secret])?;
-        tx.signatures = vec![exec_sigs];
+        tx.signatures.push(exec_sigs);
 
         let (fee_call, fee_proofs, fee_secrets) =
             self.append_fee_call(&tx, &tree, &fee_pk, &fee_zkbin, None).await?;
@@ -3448,6 +3891,10 @@ impl Drk {
 
         // Now build the actual transaction and sign it with all necessary keys.
         let mut tx = tx_builder.build()?;
+        for _ in &child_calls {
+            let sigs = tx.create_sigs(&[])?;
+            tx.signatures.push(sigs);
+        }
         let sigs = tx.create_sigs(&[exec_signature_secret])?;
         tx.signatures.push(sigs);
         let sigs = tx.create_sigs(&fee_secrets)?;
@@ -3456,3 +3903,122 @@ impl Drk {
         Ok(tx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use darkfi_dao_contract::client::DaoMember;
+    use darkfi_money_contract::model::DARK_TOKEN_ID;
+    use darkfi_sdk::crypto::{BaseBlind, Keypair, PublicKey, SecretKey};
+    use darkfi_serial::serialize;
+    use rand::rngs::OsRng;
+
+    use super::{DaoParams, LegacyDao, LegacyDaoParams};
+
+    fn dao_params() -> DaoParams {
+        // Public keys get derived from the provided secret keys
+        let secret = || Some(SecretKey::random(&mut OsRng));
+        let public = PublicKey::from_secret(SecretKey::random(&mut OsRng));
+        DaoParams::new(
+            1,
+            10,
+            20,
+            2,
+            1,
+            *DARK_TOKEN_ID,
+            None,
+            secret(),
+            public,
+            secret(),
+            public,
+            secret(),
+            public,
+            secret(),
+            public,
+            secret(),
+            public,
+            secret(),
+            public,
+            BaseBlind::random(&mut OsRng),
+        )
+    }
+
+    #[test]
+    fn test_decode_stored_legacy_params() {
+        smol::block_on(async {
+            let params = dao_params();
+            let legacy = LegacyDaoParams {
+                dao: LegacyDao {
+                    proposer_limit: params.dao.proposer_limit,
+                    quorum: params.dao.quorum,
+                    early_exec_quorum: params.dao.early_exec_quorum,
+                    approval_ratio_quot: params.dao.approval_ratio_quot,
+                    approval_ratio_base: params.dao.approval_ratio_base,
+                    gov_token_id: params.dao.gov_token_id,
+                    notes_public_key: params.dao.notes_public_key,
+                    proposer_public_key: params.dao.proposer_public_key,
+                    proposals_public_key: params.dao.proposals_public_key,
+                    votes_public_key: params.dao.votes_public_key,
+                    exec_public_key: params.dao.exec_public_key,
+                    early_exec_public_key: params.dao.early_exec_public_key,
+                    bulla_blind: params.dao.bulla_blind,
+                },
+                notes_secret_key: params.notes_secret_key,
+                proposer_secret_key: params.proposer_secret_key,
+                proposals_secret_key: params.proposals_secret_key,
+                votes_secret_key: params.votes_secret_key,
+                exec_secret_key: params.exec_secret_key,
+                early_exec_secret_key: params.early_exec_secret_key,
+            };
+
+            // Params stored by older wallets keep their values
+            let bulla = params.dao.to_bulla();
+            let decoded = DaoParams::decode_stored(&serialize(&legacy), &bulla).await.unwrap();
+            assert_eq!(decoded.dao.to_bulla(), params.dao.to_bulla());
+            assert_eq!(decoded.dao.approval_ratio_quot, 1);
+            assert_eq!(decoded.dao.approval_ratio_base, 2);
+            assert_eq!(decoded.votes_secret_key, params.votes_secret_key);
+            assert!(decoded.members.is_empty());
+
+            // Current params decode against their own bulla
+            let decoded = DaoParams::decode_stored(&serialize(&params), &params.dao.to_bulla())
+                .await
+                .unwrap();
+            assert_eq!(decoded.dao.to_bulla(), params.dao.to_bulla());
+        });
+    }
+
+    #[test]
+    fn test_allowlist_params_toml() {
+        let mut params = dao_params();
+        let members = [Keypair::random(&mut OsRng), Keypair::random(&mut OsRng)];
+        let toml = format!(
+            "{}\n\n[[members]]\npublic_key = \"{}\"\nweight = \"3\"\n\n\
+             [[members]]\npublic_key = \"{}\"\nweight = \"1.5\"\n",
+            params.toml_str(),
+            members[0].public,
+            members[1].public,
+        );
+
+        // The membership root gets derived from the listed members
+        let parsed = DaoParams::from_toml_str(&toml).unwrap();
+        assert_eq!(parsed.members.len(), 2);
+        assert_eq!(parsed.members[0].public_key, members[0].public);
+        assert_eq!(parsed.members[0].weight, 300000000);
+        assert_eq!(parsed.members[1].weight, 150000000);
+        assert!(parsed.dao.membership_root.is_some());
+
+        // And survive a round trip through their exported form
+        let reparsed = DaoParams::from_toml_str(&parsed.toml_str()).unwrap();
+        assert_eq!(reparsed.dao.to_bulla(), parsed.dao.to_bulla());
+        assert_eq!(reparsed.members[1].blind, parsed.members[1].blind);
+
+        // A given root must match the members
+        params.members = vec![DaoMember {
+            public_key: members[0].public,
+            weight: 1,
+            blind: BaseBlind::random(&mut OsRng),
+        }];
+        params.dao.membership_root = Some(super::DaoMemberSet::new().root());
+        assert!(DaoParams::from_toml_str(&params.toml_str()).is_err());
+    }
+}
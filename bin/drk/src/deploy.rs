@@ -16,19 +16,27 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::str::FromStr;
+
 use lazy_static::lazy_static;
 use rand::rngs::OsRng;
 
 use darkfi::{
-    tx::{ContractCallLeaf, Transaction, TransactionBuilder},
+    tx::{
+        ContractCallLeaf, ExternalSigner, SecretKeySigner, Transaction, TransactionBuilder,
+        TransactionSigner,
+    },
+    zk::{proof::ProvingKey, vm::ZkCircuit, vm_heap::empty_witnesses},
+    zkas::ZkBinary,
     Error, Result,
 };
 use darkfi_deployooor_contract::{
     client::{deploy_v1::DeployCallBuilder, lock_v1::LockCallBuilder},
     DeployFunction,
 };
+use darkfi_money_contract::MONEY_CONTRACT_ZKAS_FEE_NS_V1;
 use darkfi_sdk::{
-    crypto::{ContractId, Keypair, DEPLOYOOOR_CONTRACT_ID},
+    crypto::{ContractId, Keypair, PublicKey, DEPLOYOOOR_CONTRACT_ID, MONEY_CONTRACT_ID},
     ContractCall,
 };
 use darkfi_serial::{deserialize_async, serialize_async, AsyncEncodable};
@@ -132,54 +140,126 @@ impl Drk {
         Ok(keypair)
     }
 
-    /// Create a feeless contract deployment transaction.
+    /// Retrieve the public key of a deploy authority, along with the signer
+    /// holding its secret key. Wallet deploy authorities are referenced by
+    /// their index, while deploy authorities held by the external signer
+    /// command are referenced by their public key.
+    pub async fn deploy_auth_signer(
+        &self,
+        deploy_auth: &str,
+        external_signer: Option<&str>,
+    ) -> Result<(PublicKey, Box<dyn TransactionSigner>)> {
+        if let Ok(idx) = deploy_auth.parse::<u64>() {
+            let keypair = self.get_deploy_auth(idx).await?;
+            return Ok((keypair.public, Box::new(SecretKeySigner(vec![keypair.secret]))))
+        }
+
+        let Ok(public) = PublicKey::from_str(deploy_auth) else {
+            return Err(Error::ParseFailed("Invalid deploy authority index or public key"))
+        };
+
+        let Some(command) = external_signer else {
+            return Err(Error::Custom(
+                "Deploy authority public keys require a configured external signer".to_string(),
+            ))
+        };
+        let mut command = command.split_whitespace();
+        let Some(program) = command.next() else {
+            return Err(Error::ParseFailed("Empty external signer command"))
+        };
+        let args: Vec<String> = command.map(String::from).collect();
+
+        Ok((public, Box::new(ExternalSigner::new(program, &args)?)))
+    }
+
+    /// Build the transaction of a deploy authority call, paying its fee,
+    /// and sign the call through the deploy authority signer.
+    async fn deploy_auth_tx(
+        &self,
+        call: ContractCall,
+        deploy_public: PublicKey,
+        signer: &dyn TransactionSigner,
+    ) -> Result<Transaction> {
+        // Now we need to do a lookup for the zkas proof bincodes, and create
+        // the circuit objects and proving keys so we can build the transaction.
+        // We also do this through the RPC.
+        let zkas_bins = self.lookup_zkas(&MONEY_CONTRACT_ID).await?;
+
+        let Some(fee_zkbin) = zkas_bins.iter().find(|x| x.0 == MONEY_CONTRACT_ZKAS_FEE_NS_V1)
+        else {
+            return Err(Error::Custom("Fee circuit not found".to_string()))
+        };
+
+        let fee_zkbin = ZkBinary::decode(&fee_zkbin.1)?;
+        let fee_circuit = ZkCircuit::new(empty_witnesses(&fee_zkbin)?, &fee_zkbin);
+
+        // Creating Fee circuit proving key
+        let fee_pk = ProvingKey::build(fee_zkbin.k, &fee_circuit);
+
+        // Create the TransactionBuilder containing above call
+        let mut tx_builder =
+            TransactionBuilder::new(ContractCallLeaf { call, proofs: vec![] }, vec![])?;
+
+        // We first have to execute the fee-less tx to gather its used gas, and then we feed
+        // it into the fee-creating function.
+        let mut tx = tx_builder.build()?;
+        let sigs = tx.create_sigs_with(signer, &[deploy_public]).await?;
+        tx.signatures.push(sigs);
+
+        let tree = self.get_money_tree().await?;
+        let (fee_call, fee_proofs, fee_secrets) =
+            self.append_fee_call(&tx, &tree, &fee_pk, &fee_zkbin, None).await?;
+
+        // Append the fee call to the transaction
+        tx_builder.append(ContractCallLeaf { call: fee_call, proofs: fee_proofs }, vec![])?;
+
+        // Now build the actual transaction and sign it with all necessary keys.
+        let mut tx = tx_builder.build()?;
+        let sigs = tx.create_sigs_with(signer, &[deploy_public]).await?;
+        tx.signatures.push(sigs);
+        let sigs = tx.create_sigs(&fee_secrets)?;
+        tx.signatures.push(sigs);
+
+        Ok(tx)
+    }
+
+    /// Create a contract deployment transaction, signed by the deploy
+    /// authority through the given signer.
     pub async fn deploy_contract(
         &self,
-        deploy_auth: u64,
+        deploy_public: PublicKey,
+        signer: &dyn TransactionSigner,
         wasm_bincode: Vec<u8>,
         deploy_ix: Vec<u8>,
     ) -> Result<Transaction> {
-        // Fetch the keypair
-        let deploy_keypair = self.get_deploy_auth(deploy_auth).await?;
-
         // Create the contract call
-        let deploy_call = DeployCallBuilder { deploy_keypair, wasm_bincode, deploy_ix };
+        let deploy_call = DeployCallBuilder { deploy_public, wasm_bincode, deploy_ix };
         let deploy_debris = deploy_call.build()?;
 
         // Encode the call
         let mut data = vec![DeployFunction::DeployV1 as u8];
         deploy_debris.params.encode_async(&mut data).await?;
         let call = ContractCall { contract_id: *DEPLOYOOOR_CONTRACT_ID, data };
-        let mut tx_builder =
-            TransactionBuilder::new(ContractCallLeaf { call, proofs: vec![] }, vec![])?;
 
-        let mut tx = tx_builder.build()?;
-        let sigs = tx.create_sigs(&[deploy_keypair.secret])?;
-        tx.signatures = vec![sigs];
-
-        Ok(tx)
+        self.deploy_auth_tx(call, deploy_public, signer).await
     }
 
-    /// Create a feeless contract redeployment lock transaction.
-    pub async fn lock_contract(&self, deploy_auth: u64) -> Result<Transaction> {
-        // Fetch the keypair
-        let deploy_keypair = self.get_deploy_auth(deploy_auth).await?;
-
+    /// Create a contract redeployment lock transaction, signed by the
+    /// deploy authority through the given signer.
+    pub async fn lock_contract(
+        &self,
+        deploy_public: PublicKey,
+        signer: &dyn TransactionSigner,
+    ) -> Result<Transaction> {
         // Create the contract call
-        let lock_call = LockCallBuilder { deploy_keypair };
+        let lock_call = LockCallBuilder { deploy_public };
         let lock_debris = lock_call.build()?;
 
         // Encode the call
         let mut data = vec![DeployFunction::LockV1 as u8];
         lock_debris.params.encode_async(&mut data).await?;
         let call = ContractCall { contract_id: *DEPLOYOOOR_CONTRACT_ID, data };
-        let mut tx_builder =
-            TransactionBuilder::new(ContractCallLeaf { call, proofs: vec![] }, vec![])?;
 
-        let mut tx = tx_builder.build()?;
-        let sigs = tx.create_sigs(&[deploy_keypair.secret])?;
-        tx.signatures = vec![sigs];
-
-        Ok(tx)
+        self.deploy_auth_tx(call, deploy_public, signer).await
     }
 }
@@ -32,6 +32,8 @@ pub enum WalletDbError {
 
     // Configuration related errors
     PragmaUpdateError = -32120,
+    KeyDerivationFailed = -32121,
+    InvalidKey = -32122,
 
     // Query execution related errors
     QueryPreparationFailed = -32130,
@@ -51,6 +53,8 @@ impl std::fmt::Display for WalletDbError {
             WalletDbError::ConnectionFailed => write!(f, "WalletDbError::ConnectionFailed"),
             WalletDbError::FailedToAquireLock => write!(f, "WalletDbError::FailedToAquireLock"),
             WalletDbError::PragmaUpdateError => write!(f, "WalletDbError::PragmaUpdateError"),
+            WalletDbError::KeyDerivationFailed => write!(f, "WalletDbError::KeyDerivationFailed"),
+            WalletDbError::InvalidKey => write!(f, "WalletDbError::InvalidKey"),
             WalletDbError::QueryPreparationFailed => {
                 write!(f, "WalletDbError::QueryPreparationFailed")
             }
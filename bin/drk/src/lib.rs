@@ -44,12 +44,21 @@ pub mod cli_util;
 /// Wallet functionality related to Money
 pub mod money;
 
+/// Watch-only address monitoring
+pub mod watch;
+
+/// Watch-only accounts and offline signing
+pub mod offline;
+
 /// Wallet functionality related to Dao
 pub mod dao;
 
 /// Wallet functionality related to Deployooor
 pub mod deploy;
 
+/// Wallet address book
+pub mod contacts;
+
 /// Wallet functionality related to transactions history
 pub mod txs_history;
 
@@ -71,10 +80,13 @@ pub struct Drk {
 }
 
 impl Drk {
+    /// Open the wallet database at `wallet_path`, encrypted with `wallet_key`.
+    /// Use [`walletdb::derive_wallet_key`] to derive the key from a passphrase.
     pub async fn new(
         wallet_path: String,
-        wallet_pass: String,
+        wallet_key: &[u8; 32],
         endpoint: Option<Url>,
+        rpc_token: Option<String>,
         ex: Arc<smol::Executor<'static>>,
         fun: bool,
     ) -> Result<Self> {
@@ -85,13 +97,19 @@ impl Drk {
                 fs::create_dir_all(parent)?;
             }
         }
-        let Ok(wallet) = WalletDb::new(Some(wallet_path), Some(&wallet_pass)) else {
-            return Err(Error::DatabaseError(format!("{}", WalletDbError::InitializationFailed)));
+        let wallet = match WalletDb::new(Some(wallet_path), Some(wallet_key)) {
+            Ok(w) => w,
+            Err(WalletDbError::InvalidKey) => {
+                return Err(Error::DatabaseError(format!("{}", WalletDbError::InvalidKey)))
+            }
+            Err(_) => {
+                return Err(Error::DatabaseError(format!("{}", WalletDbError::InitializationFailed)))
+            }
         };
 
         // Initialize rpc client
         let rpc_client = if let Some(endpoint) = endpoint {
-            Some(RpcClient::new(endpoint, ex).await?)
+            Some(RpcClient::with_token(endpoint, rpc_token, ex).await?)
         } else {
             None
         };
@@ -114,6 +132,7 @@ impl Drk {
         self.reset_money_tree().await?;
         self.reset_money_smt()?;
         self.reset_money_coins()?;
+        self.reset_offline_coins()?;
         self.reset_mint_authorities()?;
         self.reset_dao_trees().await?;
         self.reset_daos().await?;
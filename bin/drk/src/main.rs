@@ -25,30 +25,36 @@ use std::{
 
 use log::info;
 use prettytable::{format, row, Table};
-use rand::rngs::OsRng;
+use rand::{rngs::OsRng, RngCore};
 use smol::{fs::read_to_string, stream::StreamExt};
 use structopt_toml::{serde::Deserialize, structopt::StructOpt, StructOptToml};
 use url::Url;
 
 use darkfi::{
-    async_daemonize, cli_desc,
+    async_daemonize,
+    blockchain::BlockInfo,
+    cli_desc,
+    rpc::client::auth_token,
     util::{
         encoding::base64,
         parse::{decode_base10, encode_base10},
         path::{expand_path, get_config_path},
+        time::Timestamp,
     },
     zk::halo2::Field,
     Error, Result,
 };
 use darkfi_dao_contract::{blockwindow, model::DaoProposalBulla, DaoFunction};
-use darkfi_money_contract::model::{Coin, CoinAttributes, TokenId};
+use darkfi_money_contract::model::{Coin, CoinAttributes, TokenId, TokenSupply};
 use darkfi_sdk::{
     crypto::{
         note::AeadEncryptedNote, BaseBlind, FuncId, FuncRef, Keypair, PublicKey, SecretKey,
         DAO_CONTRACT_ID,
     },
     pasta::{group::ff::PrimeField, pallas},
+    secure_storage::{platform_storage, EncryptedFileStorage, SecureStorage},
     tx::TransactionHash,
+    AsHex, ContractCall,
 };
 use darkfi_serial::{deserialize_async, serialize_async};
 
@@ -56,15 +62,22 @@ use drk::{
     cli_util::{
         generate_completions, kaching, parse_token_pair, parse_tx_from_stdin, parse_value_pair,
     },
+    contacts::Contact,
     dao::{DaoParams, ProposalRecord},
     money::BALANCE_BASE10_DECIMALS,
+    offline::{CoinOpening, UnsignedTransfer},
     swap::PartialSwapData,
+    txs_history::{txs_history_to_csv, txs_history_to_json},
+    walletdb::derive_wallet_key,
     Drk,
 };
 
 const CONFIG_FILE: &str = "drk_config.toml";
 const CONFIG_FILE_CONTENTS: &str = include_str!("../drk_config.toml");
 
+/// Passphrase of the keystore files, used when there is no OS keystore
+const KEYSTORE_PASSPHRASE_ENV: &str = "DRK_KEYSTORE_PASSPHRASE";
+
 // Dev Note: when adding/modifying args here,
 // don't forget to update cli_util::generate_completions()
 #[derive(Clone, Debug, Deserialize, StructOpt, StructOptToml)]
@@ -91,6 +104,10 @@ struct Args {
     /// Set log file to ouput into
     log: Option<String>,
 
+    #[structopt(long)]
+    /// Serve Prometheus metrics on this URL, e.g. tcp://127.0.0.1:9100
+    metrics_listen: Option<String>,
+
     #[structopt(short, parse(from_occurrences))]
     /// Increase verbosity (-vvv supported)
     verbose: u8,
@@ -155,6 +172,13 @@ enum Subcmd {
         coins: bool,
     },
 
+    /// Unlock the wallet with a passphrase read from stdin, keeping
+    /// it unlocked until it has been idle for `lock_timeout` seconds
+    Unlock,
+
+    /// Lock the wallet, so it can't be used until unlocked again
+    Lock,
+
     /// Read a transaction from stdin and mark its input coins as spent
     Spend,
 
@@ -169,10 +193,10 @@ enum Subcmd {
         /// Amount to send
         amount: String,
 
-        /// Token ID to send
+        /// Token ID to send, or `-` to use the recipient contact default token
         token: String,
 
-        /// Recipient address
+        /// Recipient address or contact name
         recipient: String,
 
         /// Optional contract spend hook to use
@@ -184,8 +208,23 @@ enum Subcmd {
         #[structopt(long)]
         /// Split the output coin into two equal halves
         half_split: bool,
+
+        #[structopt(long)]
+        /// Reveal our address to the recipient, inside the encrypted note
+        disclose_sender: bool,
+
+        #[structopt(long)]
+        /// Spend offline signer coins, creating an unsigned transfer to sign offline
+        unsigned: bool,
+
+        #[structopt(long)]
+        /// Fee to pay for an unsigned transfer
+        fee: Option<String>,
     },
 
+    /// Read an unsigned transfer from stdin, then prove and sign it
+    SignTransfer,
+
     /// OTC atomic swap
     Otc {
         #[structopt(subcommand)]
@@ -206,6 +245,7 @@ enum Subcmd {
     /// through their transactions to see if there's any that interest us.
     /// With `drk` we look at transactions calling the money contract so we can
     /// find coins sent to us and fill our wallet with the necessary metadata.
+    /// Blocks missed since the last scan are scanned before subscribing.
     Subscribe,
 
     /// DAO functionalities
@@ -236,6 +276,27 @@ enum Subcmd {
         command: AliasSubcmd,
     },
 
+    /// Manage the wallet address book
+    Contacts {
+        #[structopt(subcommand)]
+        /// Sub command to execute
+        command: ContactsSubcmd,
+    },
+
+    /// Monitor addresses without holding their keys
+    Watch {
+        #[structopt(subcommand)]
+        /// Sub command to execute
+        command: WatchSubcmd,
+    },
+
+    /// Watch-only accounts and offline signing
+    Offline {
+        #[structopt(subcommand)]
+        /// Sub command to execute
+        command: OfflineSubcmd,
+    },
+
     /// Token functionalities
     Token {
         #[structopt(subcommand)]
@@ -336,7 +397,7 @@ enum DaoSubcmd {
         /// Token ID to send
         token: String,
 
-        /// Recipient address
+        /// Recipient address or contact name
         recipient: String,
 
         /// Optional contract spend hook to use
@@ -356,6 +417,10 @@ enum DaoSubcmd {
 
         /// Optional user data to use
         user_data: Option<String>,
+
+        #[structopt(long)]
+        /// Optional base64-encoded contract call the proposal will make when executed
+        call: Option<String>,
     },
 
     /// List DAO proposals
@@ -381,6 +446,23 @@ enum DaoSubcmd {
     /// Import a base64 encoded and encrypted proposal from stdin
     ProposalImport,
 
+    /// Encrypt a comment on a proposal, read from stdin, into a base64
+    /// encoded discussion event to publish on the event graph
+    Comment {
+        /// Bulla identifier for the proposal
+        bulla: String,
+
+        /// Name to post the comment with
+        author: String,
+    },
+
+    /// View the comments on a proposal, out of base64 encoded
+    /// discussion events read from stdin, one per line
+    Comments {
+        /// Bulla identifier for the proposal
+        bulla: String,
+    },
+
     /// Vote on a given proposal
     Vote {
         /// Bulla identifier for the proposal
@@ -436,6 +518,25 @@ enum ExplorerSubcmd {
         encode: bool,
     },
 
+    /// Set or clear the label of a transactions history record
+    LabelTx {
+        /// Transaction hash of the history record
+        tx_hash: String,
+
+        /// Label to set, clears existing label if omitted
+        label: Option<String>,
+    },
+
+    /// Export transactions history with value movements and labels
+    ExportHistory {
+        #[structopt(long, default_value = "csv")]
+        /// Export format: csv or json
+        format: String,
+
+        /// File to write the export to (default: stdout)
+        output: Option<String>,
+    },
+
     /// Remove reverted transactions from history
     ClearReverted,
 
@@ -476,6 +577,92 @@ enum AliasSubcmd {
     },
 }
 
+#[derive(Clone, Debug, Deserialize, StructOpt)]
+enum ContactsSubcmd {
+    /// Create or replace a contact
+    Add {
+        /// Contact name
+        name: String,
+
+        /// Contact address
+        address: String,
+
+        #[structopt(short, long)]
+        /// Optional notes about the contact
+        notes: Option<String>,
+
+        #[structopt(short, long)]
+        /// Optional Token ID or alias to send the contact by default
+        token: Option<String>,
+    },
+
+    /// List all the contacts in the wallet
+    List,
+
+    /// Remove a contact
+    Remove {
+        /// Contact name to remove
+        name: String,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize, StructOpt)]
+enum WatchSubcmd {
+    /// Start monitoring an address
+    Add {
+        /// Address to watch
+        address: String,
+
+        /// Optional label for the address
+        label: Option<String>,
+
+        /// Read the address secret key from stdin, to detect payments
+        /// from anyone by decrypting their notes. The wallet never spends
+        /// with it.
+        #[structopt(long)]
+        view: bool,
+    },
+
+    /// List all watched addresses
+    List,
+
+    /// Stop monitoring an address
+    Remove {
+        /// Address to stop watching
+        address: String,
+    },
+
+    /// Show coins found paying to watched addresses.
+    /// Payments from third parties are only detected for addresses watched
+    /// with --view, since notes are only readable by their recipient.
+    /// Otherwise, coins show up when this wallet built the payment, or when
+    /// one of its keys could decrypt them.
+    Coins,
+}
+
+#[derive(Clone, Debug, Deserialize, StructOpt)]
+enum OfflineSubcmd {
+    /// Fetch blocks from darkfid, starting from given height,
+    /// for the offline signer to scan
+    ExportBlocks {
+        /// Height to start from, the signer's last scanned block
+        from: u32,
+    },
+
+    /// Read blocks from stdin and scan them, on the offline signer
+    ImportBlocks,
+
+    /// Print the openings of our unspent coins, for a watch-only
+    /// wallet to track them
+    ExportCoins,
+
+    /// Read coin openings of an offline signer from stdin and track their coins
+    ImportCoins,
+
+    /// Show the tracked offline signer coins
+    Coins,
+}
+
 #[derive(Clone, Debug, Deserialize, StructOpt)]
 enum TokenSubcmd {
     /// Import a mint authority
@@ -501,7 +688,7 @@ enum TokenSubcmd {
         /// Amount to mint
         amount: String,
 
-        /// Recipient of the minted tokens
+        /// Recipient address or contact name of the minted tokens
         recipient: String,
 
         /// Optional contract spend hook to use
@@ -509,6 +696,10 @@ enum TokenSubcmd {
 
         /// Optional user data to use
         user_data: Option<String>,
+
+        #[structopt(long)]
+        /// Cap the token supply to this amount (first mint only)
+        max_supply: Option<String>,
     },
 
     /// Freeze a token mint
@@ -516,6 +707,12 @@ enum TokenSubcmd {
         /// Token ID to freeze
         token: String,
     },
+
+    /// Show a token's supply policy and minted amount
+    Supply {
+        /// Token ID to query
+        token: String,
+    },
 }
 
 #[derive(Clone, Debug, Deserialize, StructOpt)]
@@ -528,8 +725,9 @@ enum ContractSubcmd {
 
     /// Deploy a smart contract
     Deploy {
-        /// Contract ID (deploy authority)
-        deploy_auth: u64,
+        /// Contract ID (deploy authority) index, or the deploy
+        /// authority public key held by the external signer
+        deploy_auth: String,
 
         /// Path to contract wasm bincode
         wasm_path: String,
@@ -540,8 +738,9 @@ enum ContractSubcmd {
 
     /// Lock a smart contract
     Lock {
-        /// Contract ID (deploy authority)
-        deploy_auth: u64,
+        /// Contract ID (deploy authority) index, or the deploy
+        /// authority public key held by the external signer
+        deploy_auth: String,
     },
 }
 
@@ -555,12 +754,34 @@ struct BlockchainNetwork {
     wallet_path: String,
 
     #[structopt(long, default_value = "changeme")]
-    /// Password for the wallet database
+    /// Password for the wallet database.
+    /// Leave empty to keep a generated password in the OS keystore,
+    /// or in files encrypted with `DRK_KEYSTORE_PASSPHRASE` without one.
     wallet_pass: String,
 
+    #[structopt(long, default_value = "0")]
+    /// Seconds of inactivity after which the wallet locks itself.
+    /// When set, `wallet_pass` is ignored and the wallet has to
+    /// be unlocked with `drk unlock` instead.
+    lock_timeout: u64,
+
     #[structopt(short, long, default_value = "tcp://127.0.0.1:8240")]
     /// darkfid JSON-RPC endpoint
     endpoint: Url,
+
+    #[structopt(long)]
+    /// darkfid JSON-RPC authentication token
+    rpc_token: Option<String>,
+
+    #[structopt(long)]
+    /// Path to the darkfid JSON-RPC authentication cookie,
+    /// used when no token is configured
+    rpc_cookie: Option<String>,
+
+    #[structopt(long)]
+    /// External signer command holding contract deploy authority keys,
+    /// see `darkfi::tx::ExternalSigner` for its protocol
+    external_signer: Option<String>,
 }
 
 /// Auxiliary function to parse darkfid configuration file and extract requested
@@ -610,17 +831,34 @@ async fn parse_blockchain_config(
 async fn new_wallet(
     wallet_path: String,
     wallet_pass: String,
+    lock_timeout: u64,
     endpoint: Option<Url>,
+    rpc_token: Option<String>,
     ex: Arc<smol::Executor<'static>>,
     fun: bool,
 ) -> Drk {
-    // Script kiddies protection
-    if wallet_pass == "changeme" {
-        eprintln!("Please don't use default wallet password...");
-        exit(2);
-    }
+    let wallet_key = if lock_timeout > 0 {
+        // Lockable wallets use the key cached by `drk unlock`
+        let Some(key) = session_wallet_key(&wallet_path, lock_timeout) else {
+            eprintln!("Wallet is locked, please unlock it using `drk unlock`");
+            exit(2);
+        };
+        key
+    } else {
+        // Script kiddies protection
+        if wallet_pass == "changeme" {
+            eprintln!("Please don't use default wallet password...");
+            exit(2);
+        }
+
+        // An empty password means it's kept in the OS keystore
+        let wallet_pass =
+            if wallet_pass.is_empty() { keystore_wallet_pass(&wallet_path) } else { wallet_pass };
+
+        derive_key(&wallet_path, &wallet_pass)
+    };
 
-    match Drk::new(wallet_path, wallet_pass, endpoint, ex, fun).await {
+    match Drk::new(wallet_path, &wallet_key, endpoint, rpc_token, ex, fun).await {
         Ok(wallet) => wallet,
         Err(e) => {
             eprintln!("Error initializing wallet: {e:?}");
@@ -629,6 +867,135 @@ async fn new_wallet(
     }
 }
 
+/// Auxiliary function to derive the wallet database key from its passphrase.
+fn derive_key(wallet_path: &str, wallet_pass: &str) -> [u8; 32] {
+    let path = match expand_path(wallet_path) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("Error expanding wallet path: {e}");
+            exit(2);
+        }
+    };
+
+    match derive_wallet_key(&path, wallet_pass) {
+        Ok(key) => key,
+        Err(e) => {
+            eprintln!("Error deriving wallet key: {e}");
+            exit(2);
+        }
+    }
+}
+
+/// Auxiliary function to open the OS keystore. When it's not available,
+/// secrets are kept in encrypted files next to the wallet instead, using
+/// the passphrase from `DRK_KEYSTORE_PASSPHRASE`, exiting if it isn't set.
+fn open_keystore(wallet_path: &str, fallback: &str) -> Box<dyn SecureStorage> {
+    let err = match platform_storage("darkfi-drk") {
+        Ok(s) => return s,
+        Err(e) => e,
+    };
+
+    let passphrase = match std::env::var(KEYSTORE_PASSPHRASE_ENV) {
+        Ok(p) if !p.is_empty() => p,
+        _ => {
+            eprintln!("Error opening OS keystore: {err}");
+            eprintln!("{fallback}");
+            eprintln!(
+                "Alternatively, set {KEYSTORE_PASSPHRASE_ENV} to keep it in encrypted files."
+            );
+            exit(2);
+        }
+    };
+
+    let dir = match expand_path(wallet_path) {
+        Ok(p) => p.with_file_name("keystore"),
+        Err(e) => {
+            eprintln!("Error expanding wallet path: {e}");
+            exit(2);
+        }
+    };
+
+    match EncryptedFileStorage::new(&dir, passphrase.as_bytes()) {
+        Ok(s) => Box::new(s),
+        Err(e) => {
+            eprintln!("Error opening keystore files in {}: {e}", dir.display());
+            exit(2);
+        }
+    }
+}
+
+/// Auxiliary function to build the OS keystore entry name of a wallet.
+/// Entries are bound to the wallet path, so multiple wallets can coexist.
+fn keystore_name(prefix: &str, wallet_path: &str) -> String {
+    match expand_path(wallet_path) {
+        Ok(p) => format!("{prefix}:{}", p.display()),
+        Err(_) => format!("{prefix}:{wallet_path}"),
+    }
+}
+
+/// Auxiliary function to retrieve the wallet key of an unlocked wallet
+/// session from the OS keystore. Sessions are stored as the key followed
+/// by their expiry timestamp, which gets pushed back on every use, so the
+/// wallet locks once it has been idle for `lock_timeout` seconds.
+fn session_wallet_key(wallet_path: &str, lock_timeout: u64) -> Option<[u8; 32]> {
+    let storage = open_keystore(wallet_path, "Wallet locking requires a keystore.");
+    let name = keystore_name("session", wallet_path);
+
+    let session = match storage.get(&name) {
+        Ok(s) => s?,
+        Err(e) => {
+            eprintln!("Error retrieving wallet session from OS keystore: {e}");
+            exit(2);
+        }
+    };
+    if session.len() != 40 {
+        let _ = storage.delete(&name);
+        return None
+    }
+
+    let key: [u8; 32] = session[..32].try_into().unwrap();
+    let expiry = u64::from_le_bytes(session[32..].try_into().unwrap());
+    let now = Timestamp::current_time().inner();
+    if now >= expiry {
+        let _ = storage.delete(&name);
+        return None
+    }
+
+    store_session(&*storage, &name, &key, now + lock_timeout);
+    Some(key)
+}
+
+/// Auxiliary function to store an unlocked wallet session in the OS keystore.
+fn store_session(storage: &dyn SecureStorage, name: &str, key: &[u8; 32], expiry: u64) {
+    let mut session = key.to_vec();
+    session.extend_from_slice(&expiry.to_le_bytes());
+    if let Err(e) = storage.set(name, &session) {
+        eprintln!("Error storing wallet session in OS keystore: {e}");
+        exit(2);
+    }
+}
+
+/// Auxiliary function to retrieve the wallet password from the OS keystore,
+/// generating a new random one on first use.
+fn keystore_wallet_pass(wallet_path: &str) -> String {
+    let storage = open_keystore(wallet_path, "Please configure a wallet password instead.");
+    let name = keystore_name("wallet", wallet_path);
+
+    let generate = || {
+        let mut pass = [0u8; 32];
+        OsRng.fill_bytes(&mut pass);
+        pass.to_vec()
+    };
+
+    match storage.get_or_create(&name, &generate) {
+        Ok(pass) => pass.hex(),
+        Err(e) => {
+            eprintln!("Error retrieving wallet password from OS keystore: {e}");
+            exit(2);
+        }
+    }
+}
+
 async_daemonize!(realmain);
 async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
     // Grab blockchain network configuration
@@ -641,6 +1008,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
             return Err(Error::UnsupportedChain)
         }
     };
+    let rpc_token =
+        auth_token(blockchain_config.rpc_token.clone(), blockchain_config.rpc_cookie.clone())?;
 
     match args.command {
         Subcmd::Kaching => {
@@ -652,11 +1021,74 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
             Ok(())
         }
 
+        Subcmd::Unlock => {
+            if blockchain_config.lock_timeout == 0 {
+                eprintln!("Wallet locking is disabled, please configure `lock_timeout` first");
+                exit(2);
+            }
+
+            eprintln!("Enter wallet passphrase:");
+            let mut passphrase = String::new();
+            if let Err(e) = stdin().read_line(&mut passphrase) {
+                eprintln!("Error reading passphrase from stdin: {e}");
+                exit(2);
+            }
+            let passphrase = passphrase.trim_end_matches(['\r', '\n']);
+            if passphrase.is_empty() {
+                eprintln!("Passphrase can't be empty");
+                exit(2);
+            }
+
+            // Make sure the key opens the wallet before keeping it around
+            let wallet_key = derive_key(&blockchain_config.wallet_path, passphrase);
+            if let Err(e) = Drk::new(
+                blockchain_config.wallet_path.clone(),
+                &wallet_key,
+                None,
+                None,
+                ex,
+                args.fun,
+            )
+            .await
+            {
+                eprintln!("Error unlocking wallet: {e:?}");
+                exit(2);
+            }
+
+            let storage = open_keystore(
+                &blockchain_config.wallet_path,
+                "Wallet locking requires a keystore.",
+            );
+            let name = keystore_name("session", &blockchain_config.wallet_path);
+            let expiry = Timestamp::current_time().inner() + blockchain_config.lock_timeout;
+            store_session(&*storage, &name, &wallet_key, expiry);
+            println!("Wallet unlocked");
+
+            Ok(())
+        }
+
+        Subcmd::Lock => {
+            let storage = open_keystore(
+                &blockchain_config.wallet_path,
+                "Wallet locking requires a keystore.",
+            );
+            let name = keystore_name("session", &blockchain_config.wallet_path);
+            if let Err(e) = storage.delete(&name) {
+                eprintln!("Error removing wallet session from OS keystore: {e}");
+                exit(2);
+            }
+            println!("Wallet locked");
+
+            Ok(())
+        }
+
         Subcmd::Ping => {
             let drk = new_wallet(
                 blockchain_config.wallet_path,
                 blockchain_config.wallet_pass,
+                blockchain_config.lock_timeout,
                 Some(blockchain_config.endpoint),
+                rpc_token.clone(),
                 ex,
                 args.fun,
             )
@@ -698,6 +1130,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
             let drk = new_wallet(
                 blockchain_config.wallet_path,
                 blockchain_config.wallet_pass,
+                blockchain_config.lock_timeout,
+                None,
                 None,
                 ex,
                 args.fun,
@@ -875,6 +1309,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     "Value",
                     "Spend Hook",
                     "User Data",
+                    "Sender",
                     "Spent TX"
                 ]);
                 for coin in coins {
@@ -897,6 +1332,11 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                         String::from("-")
                     };
 
+                    let sender = match coin.0.sender() {
+                        Some(sender) => format!("{sender}"),
+                        None => String::from("-"),
+                    };
+
                     table.add_row(row![
                         bs58::encode(&serialize_async(&coin.0.coin.inner()).await)
                             .into_string()
@@ -911,6 +1351,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                         ),
                         spend_hook,
                         user_data,
+                        sender,
                         coin.2
                     ]);
                 }
@@ -929,6 +1370,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
             let drk = new_wallet(
                 blockchain_config.wallet_path,
                 blockchain_config.wallet_pass,
+                blockchain_config.lock_timeout,
+                None,
                 None,
                 ex,
                 args.fun,
@@ -964,6 +1407,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
             let drk = new_wallet(
                 blockchain_config.wallet_path,
                 blockchain_config.wallet_pass,
+                blockchain_config.lock_timeout,
+                None,
                 None,
                 ex,
                 args.fun,
@@ -977,11 +1422,23 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
             Ok(())
         }
 
-        Subcmd::Transfer { amount, token, recipient, spend_hook, user_data, half_split } => {
+        Subcmd::Transfer {
+            amount,
+            token,
+            recipient,
+            spend_hook,
+            user_data,
+            half_split,
+            disclose_sender,
+            unsigned,
+            fee,
+        } => {
             let drk = new_wallet(
                 blockchain_config.wallet_path,
                 blockchain_config.wallet_pass,
+                blockchain_config.lock_timeout,
                 Some(blockchain_config.endpoint),
+                rpc_token.clone(),
                 ex,
                 args.fun,
             )
@@ -992,7 +1449,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 exit(2);
             }
 
-            let rcpt = match PublicKey::from_str(&recipient) {
+            let (rcpt, default_token) = match drk.get_recipient(&recipient).await {
                 Ok(r) => r,
                 Err(e) => {
                     eprintln!("Invalid recipient: {e:?}");
@@ -1000,11 +1457,19 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 }
             };
 
-            let token_id = match drk.get_token(token).await {
-                Ok(t) => t,
-                Err(e) => {
-                    eprintln!("Invalid token alias: {e:?}");
+            let token_id = if token == "-" {
+                let Some(token_id) = default_token else {
+                    eprintln!("Recipient has no default token");
                     exit(2);
+                };
+                token_id
+            } else {
+                match drk.get_token(token).await {
+                    Ok(t) => t,
+                    Err(e) => {
+                        eprintln!("Invalid token alias: {e:?}");
+                        exit(2);
+                    }
                 }
             };
 
@@ -1040,8 +1505,51 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 None => None,
             };
 
+            if unsigned {
+                let Some(fee) = fee else {
+                    eprintln!("Unsigned transfers need a --fee, as it can't be computed unsigned");
+                    exit(2);
+                };
+                if let Err(e) = f64::from_str(&fee) {
+                    eprintln!("Invalid fee: {e:?}");
+                    exit(2);
+                }
+
+                let unsigned_tx = match drk
+                    .build_unsigned_transfer(
+                        &amount,
+                        token_id,
+                        rcpt,
+                        spend_hook,
+                        user_data,
+                        half_split,
+                        disclose_sender,
+                        &fee,
+                    )
+                    .await
+                {
+                    Ok(u) => u,
+                    Err(e) => {
+                        eprintln!("Failed to create unsigned payment transaction: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                println!("{}", base64::encode(&serialize_async(&unsigned_tx).await));
+
+                return drk.stop_rpc_client().await
+            }
+
             let tx = match drk
-                .transfer(&amount, token_id, rcpt, spend_hook, user_data, half_split)
+                .transfer(
+                    &amount,
+                    token_id,
+                    rcpt,
+                    spend_hook,
+                    user_data,
+                    half_split,
+                    disclose_sender,
+                )
                 .await
             {
                 Ok(t) => t,
@@ -1056,12 +1564,53 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
             drk.stop_rpc_client().await
         }
 
+        Subcmd::SignTransfer => {
+            let mut buf = String::new();
+            stdin().read_to_string(&mut buf)?;
+            let Some(bytes) = base64::decode(buf.trim()) else {
+                eprintln!("Failed to decode unsigned transfer");
+                exit(2);
+            };
+
+            let unsigned_tx: UnsignedTransfer = match deserialize_async(&bytes).await {
+                Ok(u) => u,
+                Err(e) => {
+                    eprintln!("Failed to deserialize unsigned transfer: {e:?}");
+                    exit(2);
+                }
+            };
+
+            // Signing happens offline, so we don't connect to darkfid
+            let drk = new_wallet(
+                blockchain_config.wallet_path,
+                blockchain_config.wallet_pass,
+                blockchain_config.lock_timeout,
+                None,
+                None,
+                ex,
+                args.fun,
+            )
+            .await;
+            let tx = match drk.sign_unsigned_transfer(unsigned_tx).await {
+                Ok(tx) => tx,
+                Err(e) => {
+                    eprintln!("Failed to sign unsigned transfer: {e:?}");
+                    exit(2);
+                }
+            };
+
+            println!("{}", base64::encode(&serialize_async(&tx).await));
+            Ok(())
+        }
+
         Subcmd::Otc { command } => match command {
             OtcSubcmd::Init { value_pair, token_pair } => {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
                     Some(blockchain_config.endpoint),
+                    rpc_token.clone(),
                     ex,
                     args.fun,
                 )
@@ -1094,7 +1643,9 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
                     Some(blockchain_config.endpoint),
+                    rpc_token.clone(),
                     ex,
                     args.fun,
                 )
@@ -1122,6 +1673,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
+                    None,
                     None,
                     ex,
                     args.fun,
@@ -1141,6 +1694,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
+                    None,
                     None,
                     ex,
                     args.fun,
@@ -1193,6 +1748,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
+                    None,
                     None,
                     ex,
                     args.fun,
@@ -1221,6 +1778,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     approval_ratio_base,
                     approval_ratio_quot,
                     gov_token_id,
+                    None,
                     Some(notes_keypair.secret),
                     notes_keypair.public,
                     Some(proposer_keypair.secret),
@@ -1258,6 +1816,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
+                    None,
                     None,
                     ex,
                     args.fun,
@@ -1279,6 +1839,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
+                    None,
                     None,
                     ex,
                     args.fun,
@@ -1296,6 +1858,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
+                    None,
                     None,
                     ex,
                     args.fun,
@@ -1313,6 +1877,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
+                    None,
                     None,
                     ex,
                     args.fun,
@@ -1364,7 +1930,9 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
                     Some(blockchain_config.endpoint),
+                    rpc_token.clone(),
                     ex,
                     args.fun,
                 )
@@ -1393,7 +1961,9 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
                     Some(blockchain_config.endpoint),
+                    rpc_token.clone(),
                     ex,
                     args.fun,
                 )
@@ -1404,8 +1974,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     exit(2);
                 }
 
-                let rcpt = match PublicKey::from_str(&recipient) {
-                    Ok(r) => r,
+                let rcpt = match drk.get_recipient(&recipient).await {
+                    Ok((r, _)) => r,
                     Err(e) => {
                         eprintln!("Invalid recipient: {e:?}");
                         exit(2);
@@ -1470,11 +2040,13 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 drk.stop_rpc_client().await
             }
 
-            DaoSubcmd::ProposeGeneric { name, duration, user_data } => {
+            DaoSubcmd::ProposeGeneric { name, duration, user_data, call } => {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
                     Some(blockchain_config.endpoint),
+                    rpc_token.clone(),
                     ex,
                     args.fun,
                 )
@@ -1501,12 +2073,31 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     None => None,
                 };
 
-                let proposal = match drk.dao_propose_generic(&name, duration, user_data).await {
-                    Ok(p) => p,
-                    Err(e) => {
-                        eprintln!("Failed to create DAO transfer proposal: {e:?}");
-                        exit(2);
-                    }
+                let call = match call {
+                    Some(c) => {
+                        let Some(bytes) = base64::decode(&c) else {
+                            eprintln!("Invalid contract call base64 encoding");
+                            exit(2);
+                        };
+
+                        match deserialize_async::<ContractCall>(&bytes).await {
+                            Ok(c) if !c.data.is_empty() => Some(c),
+                            _ => {
+                                eprintln!("Invalid contract call");
+                                exit(2);
+                            }
+                        }
+                    }
+                    None => None,
+                };
+
+                let proposal = match drk.dao_propose_generic(&name, duration, user_data, call).await
+                {
+                    Ok(p) => p,
+                    Err(e) => {
+                        eprintln!("Failed to create DAO generic proposal: {e:?}");
+                        exit(2);
+                    }
                 };
 
                 println!("Generated proposal: {}", proposal.bulla());
@@ -1518,6 +2109,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
+                    None,
                     None,
                     ex,
                     args.fun,
@@ -1544,7 +2137,9 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
                     Some(blockchain_config.endpoint),
+                    rpc_token.clone(),
                     ex,
                     args.fun,
                 )
@@ -1586,8 +2181,14 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                         }
                     }
 
-                    // If proposal has no auth calls, we consider it a generic one
-                    if proposal.proposal.auth_calls.is_empty() {
+                    // If proposal has no auth calls, or only generic ones,
+                    // we consider it a generic one
+                    if proposal
+                        .proposal
+                        .auth_calls
+                        .iter()
+                        .all(|c| c.generic_calldata_hash().is_some())
+                    {
                         let tx = match drk.dao_generic_proposal_tx(&proposal).await {
                             Ok(tx) => tx,
                             Err(e) => {
@@ -1618,6 +2219,15 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                         continue;
                     }
 
+                    if let Some(hash) = call.generic_calldata_hash() {
+                        contract_calls.push_str(&format!(
+                            "\n\t\t{}: {}\n\n",
+                            "Calldata hash",
+                            hash.hex()
+                        ));
+                        continue;
+                    }
+
                     if call.function_code == DaoFunction::AuthMoneyTransfer as u8 {
                         // We know that the plaintext data live in the data plaintext vec
                         if proposal.data.is_none() {
@@ -1751,6 +2361,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
+                    None,
                     None,
                     ex,
                     args.fun,
@@ -1788,6 +2400,111 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 exit(2);
             }
 
+            DaoSubcmd::Comment { bulla, author } => {
+                let bulla = match DaoProposalBulla::from_str(&bulla) {
+                    Ok(b) => b,
+                    Err(e) => {
+                        eprintln!("Invalid proposal bulla: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                let mut body = String::new();
+                stdin().read_to_string(&mut body)?;
+                let body = body.trim().to_string();
+                if body.is_empty() {
+                    eprintln!("Comment is empty");
+                    exit(2);
+                }
+
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
+                    None,
+                    None,
+                    ex,
+                    args.fun,
+                )
+                .await;
+
+                let event = match drk.dao_proposal_comment(&bulla, author, body).await {
+                    Ok(e) => e,
+                    Err(e) => {
+                        eprintln!("Failed to create comment: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                println!("{}", base64::encode(&serialize_async(&event).await));
+                Ok(())
+            }
+
+            DaoSubcmd::Comments { bulla } => {
+                let bulla = match DaoProposalBulla::from_str(&bulla) {
+                    Ok(b) => b,
+                    Err(e) => {
+                        eprintln!("Invalid proposal bulla: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                let mut events = vec![];
+                for (i, line) in stdin().lines().enumerate() {
+                    let line = line?;
+                    if line.trim().is_empty() {
+                        continue
+                    }
+                    let Some(bytes) = base64::decode(line.trim()) else {
+                        eprintln!("Warning: Failed to decode discussion event on line {i}");
+                        continue
+                    };
+                    let Ok(event) = deserialize_async(&bytes).await else {
+                        eprintln!("Warning: Failed to deserialize discussion event on line {i}");
+                        continue
+                    };
+                    events.push(event);
+                }
+
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
+                    Some(blockchain_config.endpoint),
+                    rpc_token.clone(),
+                    ex,
+                    args.fun,
+                )
+                .await;
+
+                let comments = match drk.dao_proposal_comments(&bulla, &events).await {
+                    Ok(c) => c,
+                    Err(e) => {
+                        eprintln!("Failed to read comments: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                if comments.is_empty() {
+                    println!("No comments found");
+                    return drk.stop_rpc_client().await
+                }
+
+                let mut table = Table::new();
+                table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+                table.set_titles(row!["Timestamp", "Author", "Comment"]);
+                for comment in comments {
+                    table.add_row(row![
+                        Timestamp::from_u64(comment.timestamp),
+                        comment.author,
+                        comment.body
+                    ]);
+                }
+                println!("{table}");
+
+                drk.stop_rpc_client().await
+            }
+
             DaoSubcmd::Vote { bulla, vote, vote_weight } => {
                 let bulla = match DaoProposalBulla::from_str(&bulla) {
                     Ok(b) => b,
@@ -1817,7 +2534,9 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
                     Some(blockchain_config.endpoint),
+                    rpc_token.clone(),
                     ex,
                     args.fun,
                 )
@@ -1846,7 +2565,9 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
                     Some(blockchain_config.endpoint),
+                    rpc_token.clone(),
                     ex,
                     args.fun,
                 )
@@ -1870,8 +2591,10 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     }
                 }
 
-                // If proposal has no auth calls, we consider it a generic one
-                if proposal.proposal.auth_calls.is_empty() {
+                // If proposal has no auth calls, or only generic ones,
+                // we consider it a generic one
+                if proposal.proposal.auth_calls.iter().all(|c| c.generic_calldata_hash().is_some())
+                {
                     let tx = match drk.dao_exec_generic(&proposal, early).await {
                         Ok(tx) => tx,
                         Err(e) => {
@@ -1905,7 +2628,9 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
             let drk = new_wallet(
                 blockchain_config.wallet_path,
                 blockchain_config.wallet_pass,
+                blockchain_config.lock_timeout,
                 Some(blockchain_config.endpoint),
+                rpc_token.clone(),
                 ex,
                 args.fun,
             )
@@ -1934,7 +2659,9 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
             let drk = new_wallet(
                 blockchain_config.wallet_path,
                 blockchain_config.wallet_pass,
+                blockchain_config.lock_timeout,
                 Some(blockchain_config.endpoint),
+                rpc_token.clone(),
                 ex,
                 args.fun,
             )
@@ -1967,13 +2694,15 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
             let drk = new_wallet(
                 blockchain_config.wallet_path,
                 blockchain_config.wallet_pass,
+                blockchain_config.lock_timeout,
                 Some(blockchain_config.endpoint.clone()),
+                rpc_token.clone(),
                 ex.clone(),
                 args.fun,
             )
             .await;
 
-            if let Err(e) = drk.subscribe_blocks(blockchain_config.endpoint, ex).await {
+            if let Err(e) = drk.subscribe_blocks(blockchain_config.endpoint, rpc_token, ex).await {
                 eprintln!("Block subscription failed: {e:?}");
                 exit(2);
             }
@@ -1985,7 +2714,9 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
             let drk = new_wallet(
                 blockchain_config.wallet_path,
                 blockchain_config.wallet_pass,
+                blockchain_config.lock_timeout,
                 Some(blockchain_config.endpoint),
+                rpc_token.clone(),
                 ex,
                 args.fun,
             )
@@ -2014,7 +2745,9 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
                     Some(blockchain_config.endpoint),
+                    rpc_token.clone(),
                     ex,
                     args.fun,
                 )
@@ -2055,7 +2788,9 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
                     Some(blockchain_config.endpoint),
+                    rpc_token.clone(),
                     ex,
                     args.fun,
                 )
@@ -2079,6 +2814,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
+                    None,
                     None,
                     ex,
                     args.fun,
@@ -2095,25 +2832,87 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
 
                     println!("Transaction ID: {tx_hash}");
                     println!("Status: {status}");
+                    let entries = drk.get_txs_history_entries().await?;
+                    if let Some(entry) = entries.iter().find(|e| e.tx_hash == tx_hash) {
+                        if let Some(ref label) = entry.label {
+                            println!("Label: {label}");
+                        }
+                        for flow in &entry.flows {
+                            println!(
+                                "Token {}: received {}, sent {}",
+                                flow.token_id,
+                                encode_base10(flow.received, BALANCE_BASE10_DECIMALS),
+                                encode_base10(flow.sent, BALANCE_BASE10_DECIMALS),
+                            );
+                        }
+                    }
                     println!("{tx:?}");
 
                     return Ok(())
                 }
 
-                let map = match drk.get_txs_history() {
-                    Ok(m) => m,
+                let entries = match drk.get_txs_history_entries().await {
+                    Ok(e) => e,
                     Err(e) => {
                         eprintln!("Failed to retrieve transactions history records: {e:?}");
                         exit(2);
                     }
                 };
+                let aliases_map = drk.get_aliases_mapped_by_token().await?;
 
                 // Create a prettytable with the new data:
                 let mut table = Table::new();
                 table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
-                table.set_titles(row!["Transaction Hash", "Status"]);
-                for (txs_hash, status) in map.iter() {
-                    table.add_row(row![txs_hash, status]);
+                table.set_titles(row![
+                    "Transaction Hash",
+                    "Status",
+                    "Height",
+                    "Token ID",
+                    "Aliases",
+                    "Received",
+                    "Sent",
+                    "Counterparty",
+                    "Label"
+                ]);
+                for entry in entries.iter() {
+                    let label = entry.label.clone().unwrap_or_else(|| String::from("-"));
+                    if entry.flows.is_empty() {
+                        table.add_row(row![
+                            entry.tx_hash,
+                            entry.status,
+                            "-",
+                            "-",
+                            "-",
+                            "-",
+                            "-",
+                            "-",
+                            label
+                        ]);
+                        continue
+                    }
+
+                    for flow in entry.flows.iter() {
+                        let token_id = flow.token_id.to_string();
+                        let aliases = match aliases_map.get(&token_id) {
+                            Some(a) => a.clone(),
+                            None => String::from("-"),
+                        };
+                        let counterparty = match flow.counterparty {
+                            Some(ref c) => c.to_string(),
+                            None => String::from("-"),
+                        };
+                        table.add_row(row![
+                            entry.tx_hash,
+                            entry.status,
+                            flow.height,
+                            token_id,
+                            aliases,
+                            encode_base10(flow.received, BALANCE_BASE10_DECIMALS),
+                            encode_base10(flow.sent, BALANCE_BASE10_DECIMALS),
+                            counterparty,
+                            label
+                        ]);
+                    }
                 }
 
                 if table.is_empty() {
@@ -2125,10 +2924,74 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 Ok(())
             }
 
+            ExplorerSubcmd::LabelTx { tx_hash, label } => {
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
+                    None,
+                    None,
+                    ex,
+                    args.fun,
+                )
+                .await;
+
+                if let Err(e) = drk.set_tx_label(&tx_hash, label.as_deref()) {
+                    eprintln!("Failed to update transaction label: {e:?}");
+                    exit(2);
+                };
+
+                Ok(())
+            }
+
+            ExplorerSubcmd::ExportHistory { format, output } => {
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
+                    None,
+                    None,
+                    ex,
+                    args.fun,
+                )
+                .await;
+
+                let entries = match drk.get_txs_history_entries().await {
+                    Ok(e) => e,
+                    Err(e) => {
+                        eprintln!("Failed to retrieve transactions history records: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                let export = match format.as_str() {
+                    "csv" => txs_history_to_csv(&entries),
+                    "json" => txs_history_to_json(&entries).stringify()?,
+                    _ => {
+                        eprintln!("Unsupported export format: {format}");
+                        exit(2);
+                    }
+                };
+
+                match output {
+                    Some(path) => {
+                        if let Err(e) = std::fs::write(&path, export) {
+                            eprintln!("Failed to write export to {path}: {e:?}");
+                            exit(2);
+                        }
+                    }
+                    None => println!("{export}"),
+                }
+
+                Ok(())
+            }
+
             ExplorerSubcmd::ClearReverted => {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
+                    None,
                     None,
                     ex,
                     args.fun,
@@ -2147,6 +3010,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
+                    None,
                     None,
                     ex,
                     args.fun,
@@ -2212,6 +3077,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
+                    None,
                     None,
                     ex,
                     args.fun,
@@ -2240,6 +3107,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
+                    None,
                     None,
                     ex,
                     args.fun,
@@ -2268,6 +3137,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
+                    None,
                     None,
                     ex,
                     args.fun,
@@ -2282,6 +3153,355 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
             }
         },
 
+        Subcmd::Contacts { command } => {
+            let drk = new_wallet(
+                blockchain_config.wallet_path,
+                blockchain_config.wallet_pass,
+                blockchain_config.lock_timeout,
+                None,
+                None,
+                ex,
+                args.fun,
+            )
+            .await;
+
+            match command {
+                ContactsSubcmd::Add { name, address, notes, token } => {
+                    let address = match PublicKey::from_str(&address) {
+                        Ok(a) => a,
+                        Err(e) => {
+                            eprintln!("Invalid address: {e:?}");
+                            exit(2);
+                        }
+                    };
+
+                    let token_id = match token {
+                        Some(t) => match drk.get_token(t).await {
+                            Ok(t) => Some(t),
+                            Err(e) => {
+                                eprintln!("Invalid token alias: {e:?}");
+                                exit(2);
+                            }
+                        },
+                        None => None,
+                    };
+
+                    let contact = Contact { name, address, notes, token_id };
+                    if let Err(e) = drk.add_contact(&contact).await {
+                        eprintln!("Failed to add contact: {e:?}");
+                        exit(2);
+                    }
+                }
+
+                ContactsSubcmd::List => {
+                    let contacts = drk.get_contacts().await?;
+                    let aliases_map = drk.get_aliases_mapped_by_token().await?;
+
+                    // Create a prettytable with the new data:
+                    let mut table = Table::new();
+                    table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+                    table.set_titles(row!["Name", "Address", "Default Token", "Notes"]);
+                    for contact in contacts {
+                        let token = match contact.token_id {
+                            Some(token_id) => match aliases_map.get(&token_id.to_string()) {
+                                Some(aliases) => format!("{token_id} ({aliases})"),
+                                None => token_id.to_string(),
+                            },
+                            None => String::from("-"),
+                        };
+                        let notes = contact.notes.unwrap_or(String::from("-"));
+                        table.add_row(row![contact.name, contact.address, token, notes]);
+                    }
+
+                    if table.is_empty() {
+                        println!("No contacts found");
+                    } else {
+                        println!("{table}");
+                    }
+                }
+
+                ContactsSubcmd::Remove { name } => {
+                    if let Err(e) = drk.remove_contact(&name).await {
+                        eprintln!("Failed to remove contact: {e:?}");
+                        exit(2);
+                    }
+                }
+            }
+
+            Ok(())
+        }
+
+        Subcmd::Watch { command } => {
+            let drk = new_wallet(
+                blockchain_config.wallet_path,
+                blockchain_config.wallet_pass,
+                blockchain_config.lock_timeout,
+                None,
+                None,
+                ex,
+                args.fun,
+            )
+            .await;
+
+            match command {
+                WatchSubcmd::Add { address, label, view } => {
+                    let public = match PublicKey::from_str(&address) {
+                        Ok(p) => p,
+                        Err(e) => {
+                            eprintln!("Invalid address: {e:?}");
+                            exit(2);
+                        }
+                    };
+
+                    let view_secret = if view {
+                        let mut buf = String::new();
+                        stdin().read_to_string(&mut buf)?;
+                        match SecretKey::from_str(buf.trim()) {
+                            Ok(s) => Some(s),
+                            Err(e) => {
+                                eprintln!("Invalid view secret: {e:?}");
+                                exit(2);
+                            }
+                        }
+                    } else {
+                        None
+                    };
+
+                    let label = label.unwrap_or_default();
+                    if let Err(e) =
+                        drk.add_watched_address(&public, &label, view_secret.as_ref()).await
+                    {
+                        eprintln!("Failed to add watched address: {e:?}");
+                        exit(2);
+                    }
+
+                    Ok(())
+                }
+
+                WatchSubcmd::List => {
+                    let addresses = drk.get_watched_addresses().await?;
+
+                    // Create a prettytable with the new data:
+                    let mut table = Table::new();
+                    table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+                    table.set_titles(row!["Address", "Label", "View key"]);
+                    for address in addresses.iter() {
+                        let view = if address.view_secret.is_some() { "Yes" } else { "No" };
+                        table.add_row(row![address.public, address.label, view]);
+                    }
+
+                    if table.is_empty() {
+                        println!("No watched addresses found");
+                    } else {
+                        println!("{table}");
+                    }
+
+                    Ok(())
+                }
+
+                WatchSubcmd::Remove { address } => {
+                    let public = match PublicKey::from_str(&address) {
+                        Ok(p) => p,
+                        Err(e) => {
+                            eprintln!("Invalid address: {e:?}");
+                            exit(2);
+                        }
+                    };
+
+                    if let Err(e) = drk.remove_watched_address(&public).await {
+                        eprintln!("Failed to remove watched address: {e:?}");
+                        exit(2);
+                    }
+
+                    Ok(())
+                }
+
+                WatchSubcmd::Coins => {
+                    let coins = drk.get_watched_coins().await?;
+                    let aliases_map = drk.get_aliases_mapped_by_token().await?;
+
+                    // Create a prettytable with the new data:
+                    let mut table = Table::new();
+                    table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+                    table.set_titles(row![
+                        "Coin",
+                        "Address",
+                        "Token ID",
+                        "Aliases",
+                        "Value",
+                        "Verified",
+                        "Transaction"
+                    ]);
+                    for coin in coins.iter() {
+                        let (token_id, aliases) = match coin.token_id {
+                            Some(token_id) => {
+                                let aliases = match aliases_map.get(&token_id.to_string()) {
+                                    Some(a) => a.clone(),
+                                    None => "-".to_string(),
+                                };
+                                (token_id.to_string(), aliases)
+                            }
+                            None => ("-".to_string(), "-".to_string()),
+                        };
+                        let value = match coin.value {
+                            Some(v) => encode_base10(v, BALANCE_BASE10_DECIMALS),
+                            None => "-".to_string(),
+                        };
+
+                        table.add_row(row![
+                            bs58::encode(&serialize_async(&coin.coin.inner()).await)
+                                .into_string()
+                                .to_string(),
+                            coin.public,
+                            token_id,
+                            aliases,
+                            value,
+                            coin.is_decrypted,
+                            coin.tx_hash
+                        ]);
+                    }
+
+                    if table.is_empty() {
+                        println!("No watched coins found");
+                    } else {
+                        println!("{table}");
+                    }
+
+                    Ok(())
+                }
+            }
+        }
+
+        Subcmd::Offline { command } => {
+            // Only exporting blocks needs darkfid, the rest can run offline
+            let endpoint = match command {
+                OfflineSubcmd::ExportBlocks { .. } => Some(blockchain_config.endpoint),
+                _ => None,
+            };
+            let drk = new_wallet(
+                blockchain_config.wallet_path,
+                blockchain_config.wallet_pass,
+                blockchain_config.lock_timeout,
+                endpoint,
+                rpc_token.clone(),
+                ex,
+                args.fun,
+            )
+            .await;
+
+            match command {
+                OfflineSubcmd::ExportBlocks { from } => {
+                    let blocks = match drk.export_blocks(from).await {
+                        Ok(b) => b,
+                        Err(e) => {
+                            eprintln!("Failed to export blocks: {e:?}");
+                            exit(2);
+                        }
+                    };
+
+                    println!("{}", base64::encode(&serialize_async(&blocks).await));
+
+                    drk.stop_rpc_client().await
+                }
+
+                OfflineSubcmd::ImportBlocks => {
+                    let mut buf = String::new();
+                    stdin().read_to_string(&mut buf)?;
+                    let Some(bytes) = base64::decode(buf.trim()) else {
+                        eprintln!("Failed to decode blocks");
+                        exit(2);
+                    };
+
+                    let blocks: Vec<BlockInfo> = match deserialize_async(&bytes).await {
+                        Ok(b) => b,
+                        Err(e) => {
+                            eprintln!("Failed to deserialize blocks: {e:?}");
+                            exit(2);
+                        }
+                    };
+
+                    if let Err(e) = drk.import_blocks(&blocks).await {
+                        eprintln!("Failed to import blocks: {e:?}");
+                        exit(2);
+                    }
+
+                    Ok(())
+                }
+
+                OfflineSubcmd::ExportCoins => {
+                    let openings = drk.export_coin_openings().await?;
+                    println!("{}", base64::encode(&serialize_async(&openings).await));
+
+                    Ok(())
+                }
+
+                OfflineSubcmd::ImportCoins => {
+                    let mut buf = String::new();
+                    stdin().read_to_string(&mut buf)?;
+                    let Some(bytes) = base64::decode(buf.trim()) else {
+                        eprintln!("Failed to decode coin openings");
+                        exit(2);
+                    };
+
+                    let openings: Vec<CoinOpening> = match deserialize_async(&bytes).await {
+                        Ok(o) => o,
+                        Err(e) => {
+                            eprintln!("Failed to deserialize coin openings: {e:?}");
+                            exit(2);
+                        }
+                    };
+
+                    match drk.import_coin_openings(&openings).await {
+                        Ok(n) => println!("Imported {n} new offline coin(s)"),
+                        Err(e) => {
+                            eprintln!("Failed to import coin openings: {e:?}");
+                            exit(2);
+                        }
+                    }
+
+                    Ok(())
+                }
+
+                OfflineSubcmd::Coins => {
+                    let coins = drk.get_offline_coins(true).await?;
+                    let aliases_map = drk.get_aliases_mapped_by_token().await?;
+
+                    // Create a prettytable with the new data:
+                    let mut table = Table::new();
+                    table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+                    table.set_titles(row![
+                        "Coin", "Address", "Token ID", "Aliases", "Value", "Spent", "Spent TX"
+                    ]);
+                    for coin in coins.iter() {
+                        let aliases = match aliases_map.get(&coin.token_id.to_string()) {
+                            Some(a) => a.clone(),
+                            None => "-".to_string(),
+                        };
+
+                        table.add_row(row![
+                            bs58::encode(&serialize_async(&coin.coin.inner()).await)
+                                .into_string()
+                                .to_string(),
+                            coin.public,
+                            coin.token_id,
+                            aliases,
+                            encode_base10(coin.value, BALANCE_BASE10_DECIMALS),
+                            coin.is_spent,
+                            coin.spent_tx_hash
+                        ]);
+                    }
+
+                    if table.is_empty() {
+                        println!("No offline coins found");
+                    } else {
+                        println!("{table}");
+                    }
+
+                    Ok(())
+                }
+            }
+        }
+
         Subcmd::Token { command } => match command {
             TokenSubcmd::Import { secret_key, token_blind } => {
                 let mint_authority = match SecretKey::from_str(&secret_key) {
@@ -2303,6 +3523,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
+                    None,
                     None,
                     ex,
                     args.fun,
@@ -2318,6 +3540,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
+                    None,
                     None,
                     ex,
                     args.fun,
@@ -2335,6 +3559,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
+                    None,
                     None,
                     ex,
                     args.fun,
@@ -2377,11 +3603,13 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 Ok(())
             }
 
-            TokenSubcmd::Mint { token, amount, recipient, spend_hook, user_data } => {
+            TokenSubcmd::Mint { token, amount, recipient, spend_hook, user_data, max_supply } => {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
                     Some(blockchain_config.endpoint),
+                    rpc_token.clone(),
                     ex,
                     args.fun,
                 )
@@ -2392,8 +3620,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     exit(2);
                 }
 
-                let rcpt = match PublicKey::from_str(&recipient) {
-                    Ok(r) => r,
+                let rcpt = match drk.get_recipient(&recipient).await {
+                    Ok((r, _)) => r,
                     Err(e) => {
                         eprintln!("Invalid recipient: {e:?}");
                         exit(2);
@@ -2440,7 +3668,23 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     None => None,
                 };
 
-                let tx = match drk.mint_token(&amount, rcpt, token_id, spend_hook, user_data).await
+                if let Some(ref max_supply) = max_supply {
+                    if let Err(e) = f64::from_str(max_supply) {
+                        eprintln!("Invalid max supply: {e:?}");
+                        exit(2);
+                    }
+                }
+
+                let tx = match drk
+                    .mint_token(
+                        &amount,
+                        rcpt,
+                        token_id,
+                        max_supply.as_deref(),
+                        spend_hook,
+                        user_data,
+                    )
+                    .await
                 {
                     Ok(tx) => tx,
                     Err(e) => {
@@ -2458,7 +3702,9 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
                     Some(blockchain_config.endpoint),
+                    rpc_token.clone(),
                     ex,
                     args.fun,
                 )
@@ -2483,6 +3729,57 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
 
                 drk.stop_rpc_client().await
             }
+
+            TokenSubcmd::Supply { token } => {
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
+                    Some(blockchain_config.endpoint),
+                    rpc_token.clone(),
+                    ex,
+                    args.fun,
+                )
+                .await;
+                let token_id = match drk.get_token(token).await {
+                    Ok(t) => t,
+                    Err(e) => {
+                        eprintln!("Invalid Token ID: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                let info = match drk.get_token_supply(&token_id).await {
+                    Ok(info) => info,
+                    Err(e) => {
+                        eprintln!("Failed to fetch token supply: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                // Reissuable mints keep their value private, so only
+                // fixed supply tokens have their minted amount tracked.
+                let (max_supply, minted) = match info.supply {
+                    TokenSupply::Fixed(max) => (
+                        encode_base10(max, BALANCE_BASE10_DECIMALS),
+                        encode_base10(info.minted, BALANCE_BASE10_DECIMALS),
+                    ),
+                    TokenSupply::Reissuable => ("-".to_string(), "-".to_string()),
+                };
+
+                let mut table = Table::new();
+                table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+                table.set_titles(row!["Token ID", "Supply", "Max Supply", "Minted"]);
+                table.add_row(row![
+                    token_id,
+                    if info.supply == TokenSupply::Reissuable { "Reissuable" } else { "Fixed" },
+                    max_supply,
+                    minted
+                ]);
+                println!("{table}");
+
+                drk.stop_rpc_client().await
+            }
         },
 
         Subcmd::Contract { command } => match command {
@@ -2490,6 +3787,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
+                    None,
                     None,
                     ex,
                     args.fun,
@@ -2508,6 +3807,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
+                    None,
                     None,
                     ex,
                     args.fun,
@@ -2540,23 +3841,34 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
                     Some(blockchain_config.endpoint),
+                    rpc_token.clone(),
                     ex,
                     args.fun,
                 )
                 .await;
 
-                let mut tx = match drk.deploy_contract(deploy_auth, wasm_bin, deploy_ix).await {
+                let (deploy_public, signer) = match drk
+                    .deploy_auth_signer(&deploy_auth, blockchain_config.external_signer.as_deref())
+                    .await
+                {
                     Ok(v) => v,
                     Err(e) => {
-                        eprintln!("Error creating contract deployment tx: {e}");
+                        eprintln!("Error retrieving deploy authority: {e}");
                         exit(2);
                     }
                 };
 
-                if let Err(e) = drk.attach_fee(&mut tx).await {
-                    eprintln!("Failed to attach the fee call to the transaction: {e:?}");
-                    exit(2);
+                let tx = match drk
+                    .deploy_contract(deploy_public, signer.as_ref(), wasm_bin, deploy_ix)
+                    .await
+                {
+                    Ok(v) => v,
+                    Err(e) => {
+                        eprintln!("Error creating contract deployment tx: {e}");
+                        exit(2);
+                    }
                 };
 
                 println!("{}", base64::encode(&serialize_async(&tx).await));
@@ -2568,23 +3880,31 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
+                    blockchain_config.lock_timeout,
                     Some(blockchain_config.endpoint),
+                    rpc_token.clone(),
                     ex,
                     args.fun,
                 )
                 .await;
 
-                let mut tx = match drk.lock_contract(deploy_auth).await {
+                let (deploy_public, signer) = match drk
+                    .deploy_auth_signer(&deploy_auth, blockchain_config.external_signer.as_deref())
+                    .await
+                {
                     Ok(v) => v,
                     Err(e) => {
-                        eprintln!("Error creating contract lock tx: {e}");
+                        eprintln!("Error retrieving deploy authority: {e}");
                         exit(2);
                     }
                 };
 
-                if let Err(e) = drk.attach_fee(&mut tx).await {
-                    eprintln!("Failed to attach the fee call to the transaction: {e:?}");
-                    exit(2);
+                let tx = match drk.lock_contract(deploy_public, signer.as_ref()).await {
+                    Ok(v) => v,
+                    Err(e) => {
+                        eprintln!("Error creating contract lock tx: {e}");
+                        exit(2);
+                    }
                 };
 
                 println!("{}", base64::encode(&serialize_async(&tx).await));
@@ -34,7 +34,7 @@ use darkfi_money_contract::{
     client::{
         compute_remainder_blind,
         fee_v1::{create_fee_proof, FeeCallInput, FeeCallOutput, FEE_CALL_GAS},
-        MoneyNote, OwnCoin,
+        MoneyNote, MoneyNoteSender, OwnCoin,
     },
     model::{
         Coin, Input, MoneyAuthTokenFreezeParamsV1, MoneyAuthTokenMintParamsV1, MoneyFeeParamsV1,
@@ -46,7 +46,7 @@ use darkfi_money_contract::{
 use darkfi_sdk::{
     bridgetree,
     crypto::{
-        note::AeadEncryptedNote,
+        note::{AeadEncryptedNote, EncryptableNote},
         pasta_prelude::PrimeField,
         smt::{PoseidonFp, EMPTY_NODES_FP},
         BaseBlind, FuncId, Keypair, MerkleNode, MerkleTree, PublicKey, ScalarBlind, SecretKey,
@@ -61,7 +61,7 @@ use darkfi_serial::{deserialize_async, serialize_async, AsyncEncodable};
 use crate::{
     cli_util::kaching,
     convert_named_params,
-    error::WalletDbResult,
+    error::{WalletDbError, WalletDbResult},
     walletdb::{WalletSmt, WalletStorage},
     Drk,
 };
@@ -76,10 +76,18 @@ lazy_static! {
         format!("{}_money_keys", MONEY_CONTRACT_ID.to_string());
     pub static ref MONEY_COINS_TABLE: String =
         format!("{}_money_coins", MONEY_CONTRACT_ID.to_string());
+    pub static ref MONEY_BALANCES_TABLE: String =
+        format!("{}_money_balances", MONEY_CONTRACT_ID.to_string());
     pub static ref MONEY_TOKENS_TABLE: String =
         format!("{}_money_tokens", MONEY_CONTRACT_ID.to_string());
     pub static ref MONEY_ALIASES_TABLE: String =
         format!("{}_money_aliases", MONEY_CONTRACT_ID.to_string());
+    pub static ref MONEY_WATCHED_TABLE: String =
+        format!("{}_money_watched", MONEY_CONTRACT_ID.to_string());
+    pub static ref MONEY_WATCHED_COINS_TABLE: String =
+        format!("{}_money_watched_coins", MONEY_CONTRACT_ID.to_string());
+    pub static ref MONEY_OFFLINE_COINS_TABLE: String =
+        format!("{}_money_offline_coins", MONEY_CONTRACT_ID.to_string());
 }
 
 // MONEY_TREE_TABLE
@@ -109,6 +117,11 @@ pub const MONEY_COINS_COL_SECRET: &str = "secret";
 pub const MONEY_COINS_COL_LEAF_POSITION: &str = "leaf_position";
 pub const MONEY_COINS_COL_MEMO: &str = "memo";
 pub const MONEY_COINS_COL_SPENT_TX_HASH: &str = "spent_tx_hash";
+pub const MONEY_COINS_COL_SENDER: &str = "sender";
+
+// MONEY_BALANCES_TABLE
+pub const MONEY_BALANCES_COL_TOKEN_ID: &str = "token_id";
+pub const MONEY_BALANCES_COL_BALANCE: &str = "balance";
 
 // MONEY_TOKENS_TABLE
 pub const MONEY_TOKENS_COL_TOKEN_ID: &str = "token_id";
@@ -120,6 +133,28 @@ pub const MONEY_TOKENS_COL_IS_FROZEN: &str = "is_frozen";
 pub const MONEY_ALIASES_COL_ALIAS: &str = "alias";
 pub const MONEY_ALIASES_COL_TOKEN_ID: &str = "token_id";
 
+// MONEY_WATCHED_TABLE
+pub const MONEY_WATCHED_COL_PUBLIC: &str = "public";
+pub const MONEY_WATCHED_COL_LABEL: &str = "label";
+pub const MONEY_WATCHED_COL_VIEW_SECRET: &str = "view_secret";
+
+// MONEY_WATCHED_COINS_TABLE
+pub const MONEY_WATCHED_COINS_COL_COIN: &str = "coin";
+pub const MONEY_WATCHED_COINS_COL_PUBLIC: &str = "public";
+pub const MONEY_WATCHED_COINS_COL_VALUE: &str = "value";
+pub const MONEY_WATCHED_COINS_COL_TOKEN_ID: &str = "token_id";
+pub const MONEY_WATCHED_COINS_COL_IS_DECRYPTED: &str = "is_decrypted";
+pub const MONEY_WATCHED_COINS_COL_TX_HASH: &str = "tx_hash";
+
+// MONEY_OFFLINE_COINS_TABLE
+pub const MONEY_OFFLINE_COINS_COL_COIN: &str = "coin";
+pub const MONEY_OFFLINE_COINS_COL_PUBLIC: &str = "public";
+pub const MONEY_OFFLINE_COINS_COL_VALUE: &str = "value";
+pub const MONEY_OFFLINE_COINS_COL_TOKEN_ID: &str = "token_id";
+pub const MONEY_OFFLINE_COINS_COL_NULLIFIER: &str = "nullifier";
+pub const MONEY_OFFLINE_COINS_COL_IS_SPENT: &str = "is_spent";
+pub const MONEY_OFFLINE_COINS_COL_SPENT_TX_HASH: &str = "spent_tx_hash";
+
 pub const BALANCE_BASE10_DECIMALS: usize = 8;
 
 impl Drk {
@@ -129,6 +164,35 @@ impl Drk {
         let wallet_schema = include_str!("../money.sql");
         self.wallet.exec_batch_sql(wallet_schema)?;
 
+        // Wallets created before notes could disclose their sender
+        // are missing its column, so we append it to their coins table.
+        let columns = self
+            .wallet
+            .query_custom(&format!("PRAGMA table_info({});", *MONEY_COINS_TABLE), &[])?;
+        let sender_column = Value::Text(MONEY_COINS_COL_SENDER.to_string());
+        if !columns.iter().any(|column| column.get(1) == Some(&sender_column)) {
+            println!("Adding sender column to the Money coins table");
+            let query = format!(
+                "ALTER TABLE {} ADD COLUMN {} BLOB;",
+                *MONEY_COINS_TABLE, MONEY_COINS_COL_SENDER
+            );
+            self.wallet.exec_sql(&query, &[])?;
+        }
+
+        // Same for watched addresses created before they could hold a view secret
+        let columns = self
+            .wallet
+            .query_custom(&format!("PRAGMA table_info({});", *MONEY_WATCHED_TABLE), &[])?;
+        let view_secret_column = Value::Text(MONEY_WATCHED_COL_VIEW_SECRET.to_string());
+        if !columns.iter().any(|column| column.get(1) == Some(&view_secret_column)) {
+            println!("Adding view secret column to the Money watched table");
+            let query = format!(
+                "ALTER TABLE {} ADD COLUMN {} BLOB;",
+                *MONEY_WATCHED_TABLE, MONEY_WATCHED_COL_VIEW_SECRET
+            );
+            self.wallet.exec_sql(&query, &[])?;
+        }
+
         // Check if we have to initialize the Merkle tree.
         // We check if we find a row in the tree table, and if not, we create a
         // new tree and push it into the table.
@@ -148,6 +212,12 @@ impl Drk {
         // Insert DRK alias
         self.add_alias("DRK".to_string(), *DARK_TOKEN_ID).await?;
 
+        // Wallets created before the balances table existed get it filled here
+        if let Err(e) = self.refresh_money_balances().await {
+            eprintln!("[initialize_money] Refreshing balances failed: {e:?}");
+            return Err(WalletDbError::GenericError)
+        }
+
         Ok(())
     }
 
@@ -355,23 +425,70 @@ impl Drk {
 
     /// Fetch known unspent balances from the wallet and return them as a hashmap.
     pub async fn money_balance(&self) -> Result<HashMap<String, u64>> {
+        let rows = match self.wallet.query_multiple(&MONEY_BALANCES_TABLE, &[], &[]) {
+            Ok(r) => r,
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[money_balance] Balances retrieval failed: {e:?}"
+                )))
+            }
+        };
+
+        let mut balmap = HashMap::with_capacity(rows.len());
+        for row in rows {
+            let Value::Blob(ref token_id_bytes) = row[0] else {
+                return Err(Error::ParseFailed("[money_balance] Token ID bytes parsing failed"))
+            };
+            let token_id: TokenId = deserialize_async(token_id_bytes).await?;
+
+            let Value::Blob(ref balance_bytes) = row[1] else {
+                return Err(Error::ParseFailed("[money_balance] Balance bytes parsing failed"))
+            };
+            let balance: u64 = deserialize_async(balance_bytes).await?;
+
+            balmap.insert(token_id.to_string(), balance);
+        }
+
+        Ok(balmap)
+    }
+
+    /// Recompute the per token balances table from the unspent coins
+    /// in the wallet. This has to be called whenever coins get added,
+    /// spent or reverted.
+    pub async fn refresh_money_balances(&self) -> Result<()> {
         let mut coins = self.get_coins(false).await?;
         coins.retain(|x| x.0.note.spend_hook == FuncId::none());
 
         // Fill this map with balances
-        let mut balmap: HashMap<String, u64> = HashMap::new();
-
+        let mut balmap: HashMap<String, (TokenId, u64)> = HashMap::new();
         for coin in coins {
-            let mut value = coin.0.note.value;
+            let token_id = coin.0.note.token_id;
+            balmap.entry(token_id.to_string()).or_insert((token_id, 0)).1 += coin.0.note.value;
+        }
 
-            if let Some(prev) = balmap.get(&coin.0.note.token_id.to_string()) {
-                value += prev;
-            }
+        if let Err(e) =
+            self.wallet.exec_sql(&format!("DELETE FROM {};", *MONEY_BALANCES_TABLE), &[])
+        {
+            return Err(Error::DatabaseError(format!(
+                "[refresh_money_balances] Clearing balances failed: {e:?}"
+            )))
+        }
 
-            balmap.insert(coin.0.note.token_id.to_string(), value);
+        let query = format!(
+            "INSERT INTO {} ({}, {}) VALUES (?1, ?2);",
+            *MONEY_BALANCES_TABLE, MONEY_BALANCES_COL_TOKEN_ID, MONEY_BALANCES_COL_BALANCE,
+        );
+        for (token_id, balance) in balmap.values() {
+            let params =
+                rusqlite::params![serialize_async(token_id).await, serialize_async(balance).await];
+            if let Err(e) = self.wallet.exec_sql(&query, params) {
+                return Err(Error::DatabaseError(format!(
+                    "[refresh_money_balances] Inserting balance failed: {e:?}"
+                )))
+            }
         }
 
-        Ok(balmap)
+        Ok(())
     }
 
     /// Fetch all coins and their metadata related to the Money contract from the wallet.
@@ -556,6 +673,13 @@ impl Drk {
             ))
         };
 
+        // Coins stored before the sender column was added have no value
+        let sender: Option<MoneyNoteSender> = match row[13] {
+            Value::Blob(ref sender_bytes) => deserialize_async(sender_bytes).await?,
+            Value::Null => None,
+            _ => return Err(Error::ParseFailed("[parse_coin_record] Sender bytes parsing failed")),
+        };
+
         let note = MoneyNote {
             value,
             token_id,
@@ -565,6 +689,7 @@ impl Drk {
             value_blind,
             token_blind,
             memo: memo.clone(),
+            sender,
         };
 
         Ok((OwnCoin { coin, note, secret, leaf_position }, is_spent, spent_tx_hash.clone()))
@@ -664,7 +789,14 @@ impl Drk {
         self.wallet.exec_sql(
             &query,
             rusqlite::params![is_spend, "-", serialize_async(&coin.inner()).await],
-        )
+        )?;
+
+        if let Err(e) = self.refresh_money_balances().await {
+            eprintln!("[unspend_coin] Refreshing balances failed: {e:?}");
+            return Err(WalletDbError::GenericError)
+        }
+
+        Ok(())
     }
 
     /// Replace the Money Merkle tree in the wallet.
@@ -831,13 +963,14 @@ impl Drk {
 
     /// Append data related to Money contract transactions into the wallet database,
     /// and store their inverse queries into the cache.
-    /// Returns a flag indicating if the provided data refer to our own wallet.
+    /// Returns a flag indicating if the provided data refer to our own wallet,
+    /// along with the coins we received from them.
     pub async fn apply_tx_money_data(
         &self,
         call_idx: usize,
         calls: &[DarkLeaf<ContractCall>],
         tx_hash: &String,
-    ) -> Result<bool> {
+    ) -> Result<(bool, Vec<OwnCoin>)> {
         let (nullifiers, coins, notes, freezes) = self.parse_money_call(call_idx, calls).await?;
         let secrets = self.get_money_secrets().await?;
         let dao_notes_secrets = self.get_dao_notes_secrets().await?;
@@ -851,7 +984,7 @@ impl Drk {
 
             // Attempt to decrypt the note
             for secret in secrets.iter().chain(dao_notes_secrets.iter()) {
-                if let Ok(note) = note.decrypt::<MoneyNote>(secret) {
+                if let Ok(note) = MoneyNote::decrypt_note(&note, secret) {
                     println!("[apply_tx_money_data] Successfully decrypted a Money Note");
                     println!("[apply_tx_money_data] Witnessing coin in Merkle tree");
                     let leaf_position = tree.mark().unwrap();
@@ -871,10 +1004,12 @@ impl Drk {
         }
         self.smt_insert(&nullifiers)?;
         let wallet_spent_coins = self.mark_spent_coins(&nullifiers, tx_hash).await?;
+        self.apply_tx_watched_coins(&coins, &notes, &owncoins, tx_hash).await?;
+        self.apply_tx_offline_coins(&nullifiers, tx_hash).await?;
 
         // This is the SQL query we'll be executing to insert new coins into the wallet
         let query = format!(
-            "INSERT INTO {} ({}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12);",
+            "INSERT INTO {} ({}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13);",
             *MONEY_COINS_TABLE,
             MONEY_COINS_COL_COIN,
             MONEY_COINS_COL_IS_SPENT,
@@ -888,6 +1023,7 @@ impl Drk {
             MONEY_COINS_COL_SECRET,
             MONEY_COINS_COL_LEAF_POSITION,
             MONEY_COINS_COL_MEMO,
+            MONEY_COINS_COL_SENDER,
         );
 
         // This is its inverse query
@@ -926,6 +1062,7 @@ impl Drk {
                 serialize_async(&owncoin.secret).await,
                 serialize_async(&owncoin.leaf_position).await,
                 serialize_async(&owncoin.note.memo).await,
+                serialize_async(&owncoin.note.sender).await,
             ];
 
             if let Err(e) = self.wallet.exec_sql(&query, params) {
@@ -989,7 +1126,7 @@ impl Drk {
             kaching().await;
         }
 
-        Ok(wallet_spent_coins || !owncoins.is_empty() || !freezes.is_empty())
+        Ok((wallet_spent_coins || !owncoins.is_empty() || !freezes.is_empty(), owncoins))
     }
 
     /// Auxiliary function to  grab all the nullifiers from a transaction money call.
@@ -1036,7 +1173,7 @@ impl Drk {
             self.mark_spent_coins(&nullifiers, &tx_hash).await?;
         }
 
-        Ok(())
+        self.refresh_money_balances().await
     }
 
     /// Mark a coin in the wallet as spent, and store its inverse query into the cache.
@@ -1153,6 +1290,8 @@ impl Drk {
         println!("Resetting coins");
         let query = format!("DELETE FROM {};", *MONEY_COINS_TABLE);
         self.wallet.exec_sql(&query, &[])?;
+        let query = format!("DELETE FROM {};", *MONEY_BALANCES_TABLE);
+        self.wallet.exec_sql(&query, &[])?;
         println!("Successfully reset coins");
 
         Ok(())
@@ -1200,7 +1339,31 @@ impl Drk {
             return Err(Error::Custom("Not enough native tokens to pay for fees".to_string()))
         }
 
-        let coin = &available_coins[0];
+        self.create_fee_call(
+            &available_coins[0],
+            required_fee,
+            money_merkle_tree,
+            fee_pk,
+            fee_zkbin,
+        )
+        .await
+    }
+
+    /// Create a `Money::Fee` call paying `required_fee` with provided coin,
+    /// sending the change back to the coin owner.
+    ///
+    /// Returns the `Fee` call, and all necessary data and parameters related.
+    pub async fn create_fee_call(
+        &self,
+        coin: &OwnCoin,
+        required_fee: u64,
+        money_merkle_tree: &MerkleTree,
+        fee_pk: &ProvingKey,
+        fee_zkbin: &ZkBinary,
+    ) -> Result<(ContractCall, Vec<Proof>, Vec<SecretKey>)> {
+        if coin.note.value <= required_fee {
+            return Err(Error::Custom("Not enough native tokens to pay for fees".to_string()))
+        }
         let change_value = coin.note.value - required_fee;
 
         // Input and output setup
@@ -1253,9 +1416,10 @@ impl Drk {
             value_blind: output_value_blind,
             token_blind,
             memo: vec![],
+            sender: None,
         };
 
-        let encrypted_note = AeadEncryptedNote::encrypt(&note, &output.public_key, &mut OsRng)?;
+        let encrypted_note = note.encrypt_note(&output.public_key, &mut OsRng)?;
 
         let params = MoneyFeeParamsV1 {
             input: Input {
@@ -1346,3 +1510,72 @@ impl Drk {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use darkfi::zk::halo2::Field;
+    use darkfi_money_contract::model::DARK_TOKEN_ID;
+    use darkfi_sdk::{
+        crypto::{BaseBlind, FuncId, MerkleNode, MerkleTree, ScalarBlind, SecretKey},
+        pasta::pallas,
+    };
+    use darkfi_serial::serialize;
+    use rand::rngs::OsRng;
+
+    use super::{MONEY_COINS_COL_SENDER, MONEY_COINS_TABLE};
+    use crate::{walletdb::WalletDb, Drk};
+
+    #[test]
+    fn test_coins_sender_migration() {
+        smol::block_on(async {
+            let wallet = WalletDb::new(None, None).unwrap();
+            let drk = Drk { wallet, rpc_client: None, fun: false };
+            drk.initialize_wallet().await.unwrap();
+            drk.initialize_money().await.unwrap();
+
+            // Drop the sender column to get a coins table like
+            // the ones created by older wallets, and store a coin.
+            let query = format!(
+                "ALTER TABLE {} DROP COLUMN {};",
+                *MONEY_COINS_TABLE, MONEY_COINS_COL_SENDER
+            );
+            drk.wallet.exec_sql(&query, &[]).unwrap();
+
+            let mut tree = MerkleTree::new(1);
+            tree.append(MerkleNode::from(pallas::Base::random(&mut OsRng)));
+            let leaf_position = tree.mark().unwrap();
+            let query = format!(
+                "INSERT INTO {} VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13);",
+                *MONEY_COINS_TABLE
+            );
+            let params = rusqlite::params![
+                serialize(&pallas::Base::random(&mut OsRng)),
+                0,
+                serialize(&42u64),
+                serialize(&*DARK_TOKEN_ID),
+                serialize(&FuncId::none()),
+                serialize(&pallas::Base::ZERO),
+                serialize(&BaseBlind::random(&mut OsRng)),
+                serialize(&ScalarBlind::random(&mut OsRng)),
+                serialize(&BaseBlind::random(&mut OsRng)),
+                serialize(&SecretKey::random(&mut OsRng)),
+                serialize(&leaf_position),
+                serialize(&Vec::<u8>::new()),
+                "-",
+            ];
+            drk.wallet.exec_sql(&query, params).unwrap();
+
+            // Initializing the wallet again adds the column back,
+            // and the stored coin has no sender.
+            drk.initialize_money().await.unwrap();
+            let coins = drk.get_coins(true).await.unwrap();
+            assert_eq!(coins.len(), 1);
+            assert_eq!(coins[0].0.note.value, 42);
+            assert!(coins[0].0.note.sender.is_none());
+
+            // Running the migration again is a no-op
+            drk.initialize_money().await.unwrap();
+            assert_eq!(drk.get_coins(true).await.unwrap().len(), 1);
+        });
+    }
+}
@@ -0,0 +1,791 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Watch-only accounts and offline signing.
+//!
+//! Money notes carry no view tag and are only readable with the address
+//! secret, which also derives the coin nullifiers, so DarkFi has no
+//! viewing key that could be handed to an online machine without also
+//! handing over the spending key. Watch-only accounts therefore hold no
+//! key material at all:
+//!
+//! 1. The online wallet exports the blocks it fetched from darkfid with
+//!    [`Drk::export_blocks`], and the offline signer scans them using
+//!    [`Drk::import_blocks`].
+//! 2. The signer exports its unspent [`CoinOpening`]s, which the online
+//!    wallet imports with [`Drk::import_coin_openings`]. Openings are
+//!    checked against their coin commitment, and their spend status gets
+//!    tracked through their nullifiers while scanning.
+//! 3. The online wallet builds an [`UnsignedTransfer`] referencing those
+//!    coins, carrying the zkas circuits so the signer never has to talk
+//!    to darkfid.
+//! 4. The signer proves and signs it with [`Drk::sign_unsigned_transfer`],
+//!    witnessing the inputs against its own Money Merkle tree.
+
+use rusqlite::types::Value;
+
+use darkfi::{
+    blockchain::BlockInfo,
+    tx::{ContractCallLeaf, Transaction, TransactionBuilder},
+    util::parse::{decode_base10, encode_base10},
+    zk::{proof::ProvingKey, vm::ZkCircuit, vm_heap::empty_witnesses},
+    zkas::ZkBinary,
+    Error, Result,
+};
+use darkfi_money_contract::{
+    client::{transfer_v1::make_transfer_call, MoneyNote, OwnCoin},
+    model::{Coin, CoinAttributes, Nullifier, TokenId, DARK_TOKEN_ID},
+    MoneyFunction, MONEY_CONTRACT_ZKAS_BURN_NS_V1, MONEY_CONTRACT_ZKAS_FEE_NS_V1,
+    MONEY_CONTRACT_ZKAS_MINT_NS_V1,
+};
+use darkfi_sdk::{
+    crypto::{
+        contract_id::MONEY_CONTRACT_ID,
+        smt::{PoseidonFp, EMPTY_NODES_FP},
+        FuncId, Keypair, PublicKey,
+    },
+    pasta::pallas,
+    tx::ContractCall,
+};
+use darkfi_serial::{
+    async_trait, deserialize_async, serialize_async, AsyncEncodable, SerialDecodable,
+    SerialEncodable,
+};
+
+use crate::{
+    convert_named_params,
+    error::WalletDbResult,
+    money::{
+        BALANCE_BASE10_DECIMALS, MONEY_OFFLINE_COINS_COL_COIN, MONEY_OFFLINE_COINS_COL_IS_SPENT,
+        MONEY_OFFLINE_COINS_COL_NULLIFIER, MONEY_OFFLINE_COINS_COL_PUBLIC,
+        MONEY_OFFLINE_COINS_COL_SPENT_TX_HASH, MONEY_OFFLINE_COINS_COL_TOKEN_ID,
+        MONEY_OFFLINE_COINS_COL_VALUE, MONEY_OFFLINE_COINS_TABLE, MONEY_SMT_COL_KEY,
+        MONEY_SMT_COL_VALUE, MONEY_SMT_TABLE,
+    },
+    walletdb::{WalletSmt, WalletStorage},
+    Drk,
+};
+
+/// Current [`UnsignedTransfer`] format version
+pub const UNSIGNED_TRANSFER_VERSION: u8 = 1;
+
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+/// An unspent coin of the offline signer, exported so a watch-only
+/// wallet can track it. It contains no key material.
+pub struct CoinOpening {
+    /// Address owning the coin
+    pub public: PublicKey,
+    pub coin: Coin,
+    pub note: MoneyNote,
+    /// Nullifier revealed when the coin gets spent
+    pub nullifier: Nullifier,
+}
+
+impl From<&OwnCoin> for CoinOpening {
+    fn from(coin: &OwnCoin) -> Self {
+        Self {
+            public: PublicKey::from_secret(coin.secret),
+            coin: coin.coin,
+            note: coin.note.clone(),
+            nullifier: coin.nullifier(),
+        }
+    }
+}
+
+impl CoinOpening {
+    /// Check the opening matches its coin commitment. The nullifier can't
+    /// be verified without the secret key, so a wrong one only results in
+    /// the coin never showing up as spent.
+    pub fn verify(&self) -> bool {
+        let coin = CoinAttributes {
+            public_key: self.public,
+            value: self.note.value,
+            token_id: self.note.token_id,
+            spend_hook: self.note.spend_hook,
+            user_data: self.note.user_data,
+            blind: self.note.coin_blind,
+        }
+        .to_coin();
+
+        coin == self.coin
+    }
+}
+
+/// A coin of the offline signer, tracked by a watch-only wallet
+#[derive(Debug, Clone)]
+pub struct OfflineCoin {
+    pub coin: Coin,
+    pub public: PublicKey,
+    pub value: u64,
+    pub token_id: TokenId,
+    pub nullifier: Nullifier,
+    pub is_spent: bool,
+    /// Hash of the transaction spending the coin, `-` if not known
+    pub spent_tx_hash: String,
+}
+
+#[derive(SerialEncodable, SerialDecodable)]
+/// A payment built by a watch-only wallet, to be proven and signed
+/// on the machine holding the input keys.
+pub struct UnsignedTransfer {
+    pub version: u8,
+    pub recipient: PublicKey,
+    pub amount: u64,
+    pub token_id: TokenId,
+    pub spend_hook: Option<FuncId>,
+    pub user_data: Option<pallas::Base>,
+    pub half_split: bool,
+    pub disclose_sender: bool,
+    /// Coins to spend for the payment, change goes back to the first one's owner
+    pub inputs: Vec<Coin>,
+    /// Native token coin paying the fee
+    pub fee_input: Coin,
+    /// Fee to pay, since it can't be computed without the proofs
+    pub fee: u64,
+    pub mint_zkbin: Vec<u8>,
+    pub burn_zkbin: Vec<u8>,
+    pub fee_zkbin: Vec<u8>,
+}
+
+impl Drk {
+    /// Fetch the blocks from provided height up to the last confirmed
+    /// one, for an offline signer to scan.
+    pub async fn export_blocks(&self, from: u32) -> Result<Vec<BlockInfo>> {
+        let (last_height, _) = self.get_last_confirmed_block().await?;
+        let mut blocks = vec![];
+        for height in from..=last_height {
+            blocks.push(self.get_block_by_height(height).await?);
+        }
+
+        Ok(blocks)
+    }
+
+    /// Scan blocks exported by an online wallet. Blocks we have already
+    /// scanned are skipped, unless their hash differs, in which case we
+    /// revert to their previous height and scan from there.
+    pub async fn import_blocks(&self, blocks: &[BlockInfo]) -> Result<()> {
+        for block in blocks {
+            let height = block.header.height;
+            let hash = block.hash().to_string();
+
+            let (last_height, last_hash) = match self.get_last_scanned_block() {
+                Ok(last) => last,
+                Err(e) => {
+                    return Err(Error::DatabaseError(format!(
+                        "[import_blocks] Last scanned block retrieval failed: {e:?}"
+                    )))
+                }
+            };
+
+            // Nothing scanned yet, so we must start from genesis
+            if last_hash == "-" {
+                if height != 0 {
+                    return Err(Error::Custom(format!(
+                        "Wallet has no scanned blocks, export blocks starting from 0 instead of {height}"
+                    )))
+                }
+                if let Err(e) = self.reset().await {
+                    return Err(Error::DatabaseError(format!(
+                        "[import_blocks] Wallet reset failed: {e:?}"
+                    )))
+                }
+                self.scan_block(block).await?;
+                continue
+            }
+
+            if height <= last_height {
+                let scanned_hash = match self.get_scanned_block_record(height) {
+                    Ok((_, h, _)) => h,
+                    Err(e) => {
+                        return Err(Error::DatabaseError(format!(
+                            "[import_blocks] Scanned block retrieval failed: {e:?}"
+                        )))
+                    }
+                };
+                if scanned_hash == hash {
+                    continue
+                }
+
+                // A reorg has happened, so revert to the previous height
+                println!("Block {height} differs from the scanned one, reverting wallet state");
+                let reverted = if height == 0 {
+                    self.reset().await
+                } else {
+                    self.reset_to_height(height - 1).await
+                };
+                if let Err(e) = reverted {
+                    return Err(Error::DatabaseError(format!(
+                        "[import_blocks] Reverting wallet state failed: {e:?}"
+                    )))
+                }
+            } else if height != last_height + 1 || block.header.previous.to_string() != last_hash {
+                return Err(Error::Custom(format!(
+                    "Block {height} doesn't extend last scanned block {last_height} - {last_hash}"
+                )))
+            }
+
+            self.scan_block(block).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Export the openings of our unspent coins, so a watch-only wallet
+    /// can track them.
+    pub async fn export_coin_openings(&self) -> Result<Vec<CoinOpening>> {
+        let coins = self.get_coins(false).await?;
+        Ok(coins
+            .iter()
+            .filter(|(coin, _, _)| coin.note.spend_hook == FuncId::none())
+            .map(|(coin, _, _)| CoinOpening::from(coin))
+            .collect())
+    }
+
+    /// Import coin openings exported by an offline signer. Openings not
+    /// matching their coin commitment are rejected, while coins whose
+    /// nullifier we have already seen get imported as spent.
+    /// Returns the number of new coins.
+    pub async fn import_coin_openings(&self, openings: &[CoinOpening]) -> Result<usize> {
+        if let Some(opening) = openings.iter().find(|o| !o.verify()) {
+            return Err(Error::Custom(format!(
+                "Coin opening of {} doesn't match its commitment",
+                opening.coin
+            )))
+        }
+
+        let store = WalletStorage::new(
+            &self.wallet,
+            &MONEY_SMT_TABLE,
+            MONEY_SMT_COL_KEY,
+            MONEY_SMT_COL_VALUE,
+        );
+        let smt = WalletSmt::new(store, PoseidonFp::new(), &EMPTY_NODES_FP);
+
+        let known = self.get_offline_coins(true).await?;
+        let query = format!(
+            "INSERT INTO {} ({}, {}, {}, {}, {}, {}) VALUES (?1, ?2, ?3, ?4, ?5, ?6);",
+            *MONEY_OFFLINE_COINS_TABLE,
+            MONEY_OFFLINE_COINS_COL_COIN,
+            MONEY_OFFLINE_COINS_COL_PUBLIC,
+            MONEY_OFFLINE_COINS_COL_VALUE,
+            MONEY_OFFLINE_COINS_COL_TOKEN_ID,
+            MONEY_OFFLINE_COINS_COL_NULLIFIER,
+            MONEY_OFFLINE_COINS_COL_IS_SPENT,
+        );
+
+        let mut imported = 0;
+        for opening in openings {
+            if known.iter().any(|c| c.coin == opening.coin) {
+                continue
+            }
+
+            let is_spent = smt.contains(&opening.nullifier.inner());
+            let params = rusqlite::params![
+                serialize_async(&opening.coin.inner()).await,
+                serialize_async(&opening.public).await,
+                serialize_async(&opening.note.value).await,
+                serialize_async(&opening.note.token_id).await,
+                serialize_async(&opening.nullifier).await,
+                is_spent,
+            ];
+            if let Err(e) = self.wallet.exec_sql(&query, params) {
+                return Err(Error::DatabaseError(format!(
+                    "[import_coin_openings] Inserting offline coin failed: {e:?}"
+                )))
+            }
+            imported += 1;
+        }
+
+        Ok(imported)
+    }
+
+    /// Fetch all the offline signer coins tracked by the wallet.
+    /// Optionally also fetch spent ones.
+    pub async fn get_offline_coins(&self, fetch_spent: bool) -> Result<Vec<OfflineCoin>> {
+        let query = if fetch_spent {
+            self.wallet.query_multiple(&MONEY_OFFLINE_COINS_TABLE, &[], &[])
+        } else {
+            self.wallet.query_multiple(
+                &MONEY_OFFLINE_COINS_TABLE,
+                &[],
+                convert_named_params! {(MONEY_OFFLINE_COINS_COL_IS_SPENT, false)},
+            )
+        };
+
+        let rows = match query {
+            Ok(r) => r,
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[get_offline_coins] Offline coins retrieval failed: {e:?}"
+                )))
+            }
+        };
+
+        let mut ret = Vec::with_capacity(rows.len());
+        for row in rows {
+            ret.push(self.parse_offline_coin(&row).await?);
+        }
+
+        Ok(ret)
+    }
+
+    /// Auxiliary function to parse a `MONEY_OFFLINE_COINS_TABLE` record.
+    async fn parse_offline_coin(&self, row: &[Value]) -> Result<OfflineCoin> {
+        let Value::Blob(ref coin_bytes) = row[0] else {
+            return Err(Error::ParseFailed("[parse_offline_coin] Coin bytes parsing failed"))
+        };
+        let coin = deserialize_async(coin_bytes).await?;
+
+        let Value::Blob(ref public_bytes) = row[1] else {
+            return Err(Error::ParseFailed("[parse_offline_coin] Public key bytes parsing failed"))
+        };
+        let public = deserialize_async(public_bytes).await?;
+
+        let Value::Blob(ref value_bytes) = row[2] else {
+            return Err(Error::ParseFailed("[parse_offline_coin] Value bytes parsing failed"))
+        };
+        let value = deserialize_async(value_bytes).await?;
+
+        let Value::Blob(ref token_id_bytes) = row[3] else {
+            return Err(Error::ParseFailed("[parse_offline_coin] Token ID bytes parsing failed"))
+        };
+        let token_id = deserialize_async(token_id_bytes).await?;
+
+        let Value::Blob(ref nullifier_bytes) = row[4] else {
+            return Err(Error::ParseFailed("[parse_offline_coin] Nullifier bytes parsing failed"))
+        };
+        let nullifier = deserialize_async(nullifier_bytes).await?;
+
+        let Value::Integer(is_spent) = row[5] else {
+            return Err(Error::ParseFailed("[parse_offline_coin] Is spent parsing failed"))
+        };
+
+        let Value::Text(ref spent_tx_hash) = row[6] else {
+            return Err(Error::ParseFailed(
+                "[parse_offline_coin] Spent transaction hash parsing failed",
+            ))
+        };
+
+        Ok(OfflineCoin {
+            coin,
+            public,
+            value,
+            token_id,
+            nullifier,
+            is_spent: is_spent > 0,
+            spent_tx_hash: spent_tx_hash.clone(),
+        })
+    }
+
+    /// Mark the offline signer coins matching the nullifiers of a scanned
+    /// transaction as spent, and store their inverse queries into the cache.
+    pub async fn apply_tx_offline_coins(
+        &self,
+        nullifiers: &[Nullifier],
+        tx_hash: &String,
+    ) -> Result<()> {
+        if nullifiers.is_empty() {
+            return Ok(())
+        }
+
+        let query = format!(
+            "UPDATE {} SET {} = 1, {} = ?1 WHERE {} = ?2;",
+            *MONEY_OFFLINE_COINS_TABLE,
+            MONEY_OFFLINE_COINS_COL_IS_SPENT,
+            MONEY_OFFLINE_COINS_COL_SPENT_TX_HASH,
+            MONEY_OFFLINE_COINS_COL_COIN,
+        );
+        for coin in self.get_offline_coins(false).await? {
+            if !nullifiers.contains(&coin.nullifier) {
+                continue
+            }
+
+            let key = serialize_async(&coin.coin.inner()).await;
+            let inverse = match self.wallet.create_prepared_statement(
+                &format!(
+                    "UPDATE {} SET {} = 0, {} = '-' WHERE {} = ?1;",
+                    *MONEY_OFFLINE_COINS_TABLE,
+                    MONEY_OFFLINE_COINS_COL_IS_SPENT,
+                    MONEY_OFFLINE_COINS_COL_SPENT_TX_HASH,
+                    MONEY_OFFLINE_COINS_COL_COIN,
+                ),
+                rusqlite::params![key],
+            ) {
+                Ok(i) => i,
+                Err(e) => {
+                    return Err(Error::DatabaseError(format!(
+                        "[apply_tx_offline_coins] Creating inverse query failed: {e:?}"
+                    )))
+                }
+            };
+            if let Err(e) = self.wallet.exec_sql(&query, rusqlite::params![tx_hash, key]) {
+                return Err(Error::DatabaseError(format!(
+                    "[apply_tx_offline_coins] Marking offline coin spent failed: {e:?}"
+                )))
+            }
+            if let Err(e) = self.wallet.cache_inverse(inverse) {
+                return Err(Error::DatabaseError(format!(
+                    "[apply_tx_offline_coins] Storing inverse query failed: {e:?}"
+                )))
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Mark all offline signer coins as unspent, so their status gets
+    /// recomputed while rescanning. The coins themselves are kept, since
+    /// they can't be rediscovered without the signer.
+    pub fn reset_offline_coins(&self) -> WalletDbResult<()> {
+        println!("Resetting offline coins spent status");
+        let query = format!(
+            "UPDATE {} SET {} = 0, {} = '-';",
+            *MONEY_OFFLINE_COINS_TABLE,
+            MONEY_OFFLINE_COINS_COL_IS_SPENT,
+            MONEY_OFFLINE_COINS_COL_SPENT_TX_HASH,
+        );
+        self.wallet.exec_sql(&query, &[])?;
+        println!("Successfully reset offline coins spent status");
+
+        Ok(())
+    }
+
+    /// Select the offline signer coins paying `amount` of provided token,
+    /// along with a distinct native token coin paying `fee`.
+    pub async fn select_offline_coins(
+        &self,
+        token_id: &TokenId,
+        amount: u64,
+        fee: u64,
+    ) -> Result<(Vec<OfflineCoin>, OfflineCoin)> {
+        let mut coins = self.get_offline_coins(false).await?;
+        coins.sort_by(|a, b| b.value.cmp(&a.value));
+
+        // Select the largest coins until we cover the amount
+        let mut inputs = vec![];
+        let mut balance = 0;
+        for coin in coins.iter().filter(|c| c.token_id == *token_id) {
+            if balance >= amount {
+                break
+            }
+            balance += coin.value;
+            inputs.push(coin.clone());
+        }
+        if balance < amount {
+            return Err(Error::Custom(format!(
+                "Not enough offline balance for token ID: {token_id}, found: {}",
+                encode_base10(balance, BALANCE_BASE10_DECIMALS)
+            )))
+        }
+
+        // Find a native token coin paying the fee, which isn't one of our inputs
+        let Some(fee_coin) = coins.into_iter().rev().find(|c| {
+            c.token_id == *DARK_TOKEN_ID &&
+                c.value > fee &&
+                !inputs.iter().any(|i| i.coin == c.coin)
+        }) else {
+            return Err(Error::Custom("Not enough native tokens to pay for fees".to_string()))
+        };
+
+        Ok((inputs, fee_coin))
+    }
+
+    /// Create an unsigned payment spending offline signer coins, to be
+    /// signed using [`Drk::sign_unsigned_transfer`].
+    #[allow(clippy::too_many_arguments)]
+    pub async fn build_unsigned_transfer(
+        &self,
+        amount: &str,
+        token_id: TokenId,
+        recipient: PublicKey,
+        spend_hook: Option<FuncId>,
+        user_data: Option<pallas::Base>,
+        half_split: bool,
+        disclose_sender: bool,
+        fee: &str,
+    ) -> Result<UnsignedTransfer> {
+        let amount = decode_base10(amount, BALANCE_BASE10_DECIMALS, false)?;
+        let fee = decode_base10(fee, BALANCE_BASE10_DECIMALS, false)?;
+        let (inputs, fee_coin) = self.select_offline_coins(&token_id, amount, fee).await?;
+
+        // The signer is offline, so we ship the circuits along
+        let zkas_bins = self.lookup_zkas(&MONEY_CONTRACT_ID).await?;
+        let find_zkbin = |namespace: &str| match zkas_bins.iter().find(|x| x.0 == namespace) {
+            Some(zkbin) => Ok(zkbin.1.clone()),
+            None => Err(Error::Custom(format!("{namespace} circuit not found"))),
+        };
+
+        Ok(UnsignedTransfer {
+            version: UNSIGNED_TRANSFER_VERSION,
+            recipient,
+            amount,
+            token_id,
+            spend_hook,
+            user_data,
+            half_split,
+            disclose_sender,
+            inputs: inputs.iter().map(|c| c.coin).collect(),
+            fee_input: fee_coin.coin,
+            fee,
+            mint_zkbin: find_zkbin(MONEY_CONTRACT_ZKAS_MINT_NS_V1)?,
+            burn_zkbin: find_zkbin(MONEY_CONTRACT_ZKAS_BURN_NS_V1)?,
+            fee_zkbin: find_zkbin(MONEY_CONTRACT_ZKAS_FEE_NS_V1)?,
+        })
+    }
+
+    /// Prove and sign an [`UnsignedTransfer`] spending our coins.
+    /// This doesn't need a darkfid connection, so it can run offline.
+    pub async fn sign_unsigned_transfer(&self, unsigned: UnsignedTransfer) -> Result<Transaction> {
+        if unsigned.version != UNSIGNED_TRANSFER_VERSION {
+            return Err(Error::Custom(format!(
+                "Unsupported unsigned transfer version: {}",
+                unsigned.version
+            )))
+        }
+        if unsigned.inputs.is_empty() {
+            return Err(Error::Custom("Unsigned transfer has no inputs".to_string()))
+        }
+
+        // Find the input coins in our wallet
+        let wallet_coins = self.get_coins(false).await?;
+        let own_coin = |coin: &Coin| match wallet_coins.iter().find(|(c, _, _)| c.coin == *coin) {
+            Some((c, _, _)) => Ok(c.clone()),
+            None => Err(Error::Custom(format!(
+                "Coin {coin} is not an unspent coin of this wallet, import the latest blocks"
+            ))),
+        };
+        let owncoins = unsigned.inputs.iter().map(own_coin).collect::<Result<Vec<_>>>()?;
+        let fee_coin = own_coin(&unsigned.fee_input)?;
+        let keypair = Keypair::new(owncoins[0].secret);
+        let tree = self.get_money_tree().await?;
+
+        let mint_zkbin = ZkBinary::decode(&unsigned.mint_zkbin)?;
+        let burn_zkbin = ZkBinary::decode(&unsigned.burn_zkbin)?;
+        let fee_zkbin = ZkBinary::decode(&unsigned.fee_zkbin)?;
+
+        let mint_circuit = ZkCircuit::new(empty_witnesses(&mint_zkbin)?, &mint_zkbin);
+        let burn_circuit = ZkCircuit::new(empty_witnesses(&burn_zkbin)?, &burn_zkbin);
+        let fee_circuit = ZkCircuit::new(empty_witnesses(&fee_zkbin)?, &fee_zkbin);
+
+        // Creating Mint, Burn and Fee circuits proving keys
+        let mint_pk = ProvingKey::build(mint_zkbin.k, &mint_circuit);
+        let burn_pk = ProvingKey::build(burn_zkbin.k, &burn_circuit);
+        let fee_pk = ProvingKey::build(fee_zkbin.k, &fee_circuit);
+
+        // Building transaction parameters
+        let (params, secrets, _) = make_transfer_call(
+            keypair,
+            unsigned.recipient,
+            unsigned.amount,
+            unsigned.token_id,
+            owncoins,
+            tree.clone(),
+            unsigned.spend_hook,
+            unsigned.user_data,
+            mint_zkbin,
+            mint_pk,
+            burn_zkbin,
+            burn_pk,
+            unsigned.half_split,
+            unsigned.disclose_sender,
+        )?;
+
+        // Encode the call
+        let mut data = vec![MoneyFunction::TransferV1 as u8];
+        params.encode_async(&mut data).await?;
+        let call = ContractCall { contract_id: *MONEY_CONTRACT_ID, data };
+
+        // Create the TransactionBuilder containing the `Transfer` call
+        // and append the fee call paying the provided fee
+        let mut tx_builder =
+            TransactionBuilder::new(ContractCallLeaf { call, proofs: secrets.proofs }, vec![])?;
+        let (fee_call, fee_proofs, fee_secrets) =
+            self.create_fee_call(&fee_coin, unsigned.fee, &tree, &fee_pk, &fee_zkbin).await?;
+        tx_builder.append(ContractCallLeaf { call: fee_call, proofs: fee_proofs }, vec![])?;
+
+        // Now build the actual transaction and sign it with all necessary keys.
+        let mut tx = tx_builder.build()?;
+        let sigs = tx.create_sigs(&secrets.signature_secrets)?;
+        tx.signatures.push(sigs);
+        let sigs = tx.create_sigs(&fee_secrets)?;
+        tx.signatures.push(sigs);
+
+        Ok(tx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use darkfi::zk::halo2::Field;
+    use darkfi_money_contract::{
+        client::MoneyNote,
+        model::{CoinAttributes, Nullifier, TokenId, DARK_TOKEN_ID},
+    };
+    use darkfi_sdk::{
+        crypto::{BaseBlind, FuncId, PublicKey, ScalarBlind, SecretKey},
+        pasta::pallas,
+    };
+    use rand::rngs::OsRng;
+
+    use super::{CoinOpening, UnsignedTransfer, UNSIGNED_TRANSFER_VERSION};
+    use crate::{walletdb::WalletDb, Drk};
+
+    async fn test_drk() -> Drk {
+        let wallet = WalletDb::new(None, None).unwrap();
+        let drk = Drk { wallet, rpc_client: None, fun: false };
+        drk.initialize_wallet().await.unwrap();
+        drk.initialize_money().await.unwrap();
+        drk
+    }
+
+    fn coin_opening(value: u64, token_id: TokenId) -> CoinOpening {
+        let public = PublicKey::from_secret(SecretKey::random(&mut OsRng));
+        let note = MoneyNote {
+            value,
+            token_id,
+            spend_hook: FuncId::none(),
+            user_data: pallas::Base::ZERO,
+            coin_blind: BaseBlind::random(&mut OsRng),
+            value_blind: ScalarBlind::random(&mut OsRng),
+            token_blind: BaseBlind::random(&mut OsRng),
+            memo: vec![],
+            sender: None,
+        };
+        let coin = CoinAttributes {
+            public_key: public,
+            value,
+            token_id,
+            spend_hook: note.spend_hook,
+            user_data: note.user_data,
+            blind: note.coin_blind,
+        }
+        .to_coin();
+        let nullifier = Nullifier::from(pallas::Base::random(&mut OsRng));
+
+        CoinOpening { public, coin, note, nullifier }
+    }
+
+    #[test]
+    fn test_import_coin_openings() {
+        smol::block_on(async {
+            let drk = test_drk().await;
+            let opening = coin_opening(42, *DARK_TOKEN_ID);
+
+            // Forged values don't match the coin commitment
+            let mut forged = opening.clone();
+            forged.note.value = 4200;
+            assert!(!forged.verify());
+            assert!(drk.import_coin_openings(&[forged]).await.is_err());
+            assert!(drk.get_offline_coins(true).await.unwrap().is_empty());
+
+            // Importing the same opening twice only stores it once
+            assert_eq!(drk.import_coin_openings(&[opening.clone()]).await.unwrap(), 1);
+            assert_eq!(drk.import_coin_openings(&[opening.clone()]).await.unwrap(), 0);
+
+            let coins = drk.get_offline_coins(false).await.unwrap();
+            assert_eq!(coins.len(), 1);
+            assert_eq!(coins[0].coin, opening.coin);
+            assert_eq!(coins[0].value, 42);
+            assert_eq!(coins[0].nullifier, opening.nullifier);
+
+            // Imported coins with an already seen nullifier are spent
+            let spent = coin_opening(7, *DARK_TOKEN_ID);
+            drk.smt_insert(&[spent.nullifier]).unwrap();
+            drk.import_coin_openings(&[spent]).await.unwrap();
+            assert_eq!(drk.get_offline_coins(false).await.unwrap().len(), 1);
+            assert_eq!(drk.get_offline_coins(true).await.unwrap().len(), 2);
+        });
+    }
+
+    #[test]
+    fn test_select_offline_coins() {
+        smol::block_on(async {
+            let drk = test_drk().await;
+            let token_id = TokenId::from(pallas::Base::random(&mut OsRng));
+            let big = coin_opening(100, token_id);
+            let small = coin_opening(30, token_id);
+            let fee = coin_opening(5, *DARK_TOKEN_ID);
+            drk.import_coin_openings(&[big.clone(), small.clone(), fee.clone()]).await.unwrap();
+
+            // Largest coins get selected first, fee is paid by a native coin
+            let (inputs, fee_coin) = drk.select_offline_coins(&token_id, 90, 1).await.unwrap();
+            assert_eq!(inputs.len(), 1);
+            assert_eq!(inputs[0].coin, big.coin);
+            assert_eq!(fee_coin.coin, fee.coin);
+
+            let (inputs, _) = drk.select_offline_coins(&token_id, 120, 1).await.unwrap();
+            assert_eq!(inputs.len(), 2);
+            assert!(drk.select_offline_coins(&token_id, 131, 1).await.is_err());
+            assert!(drk.select_offline_coins(&token_id, 90, 5).await.is_err());
+
+            // The fee coin is never one of the inputs
+            assert!(drk.select_offline_coins(&DARK_TOKEN_ID, 5, 1).await.is_err());
+
+            // Spent coins are no longer selected, until a reset
+            drk.apply_tx_offline_coins(&[big.nullifier], &"tx".to_string()).await.unwrap();
+            let coins = drk.get_offline_coins(true).await.unwrap();
+            let spent = coins.iter().find(|c| c.coin == big.coin).unwrap();
+            assert!(spent.is_spent);
+            assert_eq!(spent.spent_tx_hash, "tx");
+            assert!(drk.select_offline_coins(&token_id, 90, 1).await.is_err());
+
+            drk.reset_offline_coins().unwrap();
+            assert!(drk.select_offline_coins(&token_id, 90, 1).await.is_ok());
+        });
+    }
+
+    #[test]
+    fn test_sign_unsigned_transfer() {
+        smol::block_on(async {
+            let drk = test_drk().await;
+            let opening = coin_opening(42, *DARK_TOKEN_ID);
+            let unsigned = |version, inputs| UnsignedTransfer {
+                version,
+                recipient: opening.public,
+                amount: 1,
+                token_id: *DARK_TOKEN_ID,
+                spend_hook: None,
+                user_data: None,
+                half_split: false,
+                disclose_sender: false,
+                inputs,
+                fee_input: opening.coin,
+                fee: 1,
+                mint_zkbin: vec![],
+                burn_zkbin: vec![],
+                fee_zkbin: vec![],
+            };
+
+            // Unknown versions and empty transfers are refused
+            assert!(drk.sign_unsigned_transfer(unsigned(0, vec![opening.coin])).await.is_err());
+            assert!(drk
+                .sign_unsigned_transfer(unsigned(UNSIGNED_TRANSFER_VERSION, vec![]))
+                .await
+                .is_err());
+
+            // Coins the signer doesn't own are refused, even with their opening
+            // imported, since that carries no key
+            drk.import_coin_openings(&[opening.clone()]).await.unwrap();
+            let err = drk
+                .sign_unsigned_transfer(unsigned(UNSIGNED_TRANSFER_VERSION, vec![opening.coin]))
+                .await
+                .unwrap_err();
+            assert!(format!("{err:?}").contains("is not an unspent coin of this wallet"));
+        });
+    }
+}
@@ -32,6 +32,7 @@ use darkfi::{
     util::encoding::base64,
     Error, Result,
 };
+use darkfi_money_contract::model::{TokenId, TokenSupplyInfo};
 use darkfi_sdk::{
     crypto::{ContractId, DAO_CONTRACT_ID, DEPLOYOOOR_CONTRACT_ID, MONEY_CONTRACT_ID},
     tx::TransactionHash,
@@ -45,7 +46,9 @@ use crate::{
 
 impl Drk {
     /// Subscribes to darkfid's JSON-RPC notification endpoint that serves
-    /// new confirmed blocks. Upon receiving them, all the transactions are
+    /// new confirmed blocks. Any blocks missing from the wallet are scanned
+    /// first, so the subscription can be started at any point. Upon
+    /// receiving new blocks, all the transactions are
     /// scanned and we check if any of them call the money contract, and if
     /// the payments are intended for us. If so, we decrypt them and append
     /// the metadata to our wallet. If a reorg block is received, we revert
@@ -55,25 +58,19 @@ impl Drk {
     pub async fn subscribe_blocks(
         &self,
         endpoint: Url,
+        rpc_token: Option<String>,
         ex: Arc<smol::Executor<'static>>,
     ) -> Result<()> {
-        // Grab last confirmed block height
-        let (last_confirmed_height, _) = self.get_last_confirmed_block().await?;
-
-        // Handle genesis(0) block
-        if last_confirmed_height == 0 {
-            if let Err(e) = self.scan_blocks().await {
-                return Err(Error::DatabaseError(format!(
-                    "[subscribe_blocks] Scanning from genesis block failed: {e:?}"
-                )))
-            }
+        // Catch up with the chain, handling any reorgs since our last scan
+        println!("Scanning blocks missing from the wallet");
+        if let Err(e) = self.scan_blocks().await {
+            return Err(Error::DatabaseError(format!(
+                "[subscribe_blocks] Scanning missing blocks failed: {e:?}"
+            )))
         }
 
-        // Grab last confirmed block again
-        let (last_confirmed_height, last_confirmed_hash) = self.get_last_confirmed_block().await?;
-
         // Grab last scanned block
-        let (mut last_scanned_height, last_scanned_hash) = match self.get_last_scanned_block() {
+        let (mut last_scanned_height, _) = match self.get_last_scanned_block() {
             Ok(last) => last,
             Err(e) => {
                 return Err(Error::DatabaseError(format!(
@@ -82,16 +79,6 @@ impl Drk {
             }
         };
 
-        // Check if other blocks have been created
-        if last_confirmed_height != last_scanned_height || last_confirmed_hash != last_scanned_hash
-        {
-            eprintln!("Warning: Last scanned block is not the last confirmed block.");
-            eprintln!("You should first fully scan the blockchain, and then subscribe");
-            return Err(Error::DatabaseError(
-                "[subscribe_blocks] Blockchain not fully scanned".to_string(),
-            ))
-        }
-
         println!("Subscribing to receive notifications of incoming blocks");
         let publisher = Publisher::new();
         let subscription = publisher.clone().subscribe().await;
@@ -100,7 +87,7 @@ impl Drk {
         StoppableTask::new().start(
             // Weird hack to prevent lifetimes hell
             async move {
-                let rpc_client = RpcClient::new(endpoint, _ex).await?;
+                let rpc_client = RpcClient::with_token(endpoint, rpc_token, _ex).await?;
                 let req = JsonRequest::new("blockchain.subscribe_blocks", JsonValue::Array(vec![]));
                 rpc_client.subscribe(req, _publisher).await
             },
@@ -183,6 +170,28 @@ impl Drk {
                             }
                         }
 
+                        // Blocks confirmed while we were catching up, or
+                        // while the subscription was being established,
+                        // are not notified, so we fetch them ourselves.
+                        while last_scanned_height + 1 < block.header.height {
+                            let height = last_scanned_height + 1;
+                            println!("Requesting missed block {height}...");
+                            let missed = match self.get_block_by_height(height).await {
+                                Ok(b) => b,
+                                Err(e) => {
+                                    return Err(Error::Custom(format!(
+                                        "[subscribe_blocks] RPC client request failed: {e:?}"
+                                    )))
+                                }
+                            };
+                            if let Err(e) = self.scan_block(&missed).await {
+                                return Err(Error::DatabaseError(format!(
+                                    "[subscribe_blocks] Scanning block failed: {e:?}"
+                                )))
+                            }
+                            last_scanned_height = height;
+                        }
+
                         if let Err(e) = self.scan_block(&block).await {
                             return Err(Error::DatabaseError(format!(
                                 "[subscribe_blocks] Scanning block failed: {e:?}"
@@ -214,7 +223,7 @@ impl Drk {
     /// `scan_block` will go over over transactions in a block and handle their calls
     /// based on the called contract. Additionally, will update `last_scanned_block` to
     /// the provided block height and will store its height, hash and inverse query.
-    async fn scan_block(&self, block: &BlockInfo) -> Result<()> {
+    pub async fn scan_block(&self, block: &BlockInfo) -> Result<()> {
         // Reset wallet inverse cache state
         self.reset_inverse_cache().await?;
 
@@ -228,13 +237,17 @@ impl Drk {
             let tx_hash = tx.hash();
             let tx_hash_string = tx_hash.to_string();
             let mut wallet_tx = false;
+            let mut received = vec![];
             println!("[scan_block] Processing transaction: {tx_hash_string}");
             for (i, call) in tx.calls.iter().enumerate() {
                 if call.data.contract_id == *MONEY_CONTRACT_ID {
                     println!("[scan_block] Found Money contract in call {i}");
-                    if self.apply_tx_money_data(i, &tx.calls, &tx_hash_string).await? {
+                    let (own_data, owncoins) =
+                        self.apply_tx_money_data(i, &tx.calls, &tx_hash_string).await?;
+                    if own_data {
                         wallet_tx = true;
                     };
+                    received.extend(owncoins);
                     continue
                 }
 
@@ -257,7 +270,11 @@ impl Drk {
             }
 
             // If this is our wallet tx we mark it for update
+            // and keep track of its value movements
             if wallet_tx {
+                let spent = self.get_transaction_coins(&tx_hash_string).await?;
+                self.put_tx_history_flows(&tx_hash_string, block.header.height, &received, &spent)
+                    .await?;
                 wallet_txs.push(tx);
             }
         }
@@ -269,6 +286,11 @@ impl Drk {
             )))
         }
 
+        // Update per token balances if any of our coins changed
+        if !wallet_txs.is_empty() {
+            self.refresh_money_balances().await?;
+        }
+
         // Store this block rollback query
         self.store_inverse_cache(block.header.height, &block.hash().to_string())?;
 
@@ -370,8 +392,8 @@ impl Drk {
         }
     }
 
-    // Queries darkfid for last confirmed block.
-    async fn get_last_confirmed_block(&self) -> Result<(u32, String)> {
+    /// Queries darkfid for last confirmed block.
+    pub async fn get_last_confirmed_block(&self) -> Result<(u32, String)> {
         let rep = self
             .darkfid_daemon_request("blockchain.last_confirmed_block", &JsonValue::Array(vec![]))
             .await?;
@@ -382,8 +404,8 @@ impl Drk {
         Ok((height, hash))
     }
 
-    // Queries darkfid for a block with given height.
-    async fn get_block_by_height(&self, height: u32) -> Result<BlockInfo> {
+    /// Queries darkfid for a block with given height.
+    pub async fn get_block_by_height(&self, height: u32) -> Result<BlockInfo> {
         let params = self
             .darkfid_daemon_request(
                 "blockchain.get_block",
@@ -467,6 +489,15 @@ impl Drk {
         Ok(ret)
     }
 
+    /// Queries darkfid for the supply policy and minted amount of given token.
+    pub async fn get_token_supply(&self, token_id: &TokenId) -> Result<TokenSupplyInfo> {
+        let params = JsonValue::Array(vec![JsonValue::String(format!("{token_id}"))]);
+        let rep = self.darkfid_daemon_request("blockchain.get_token_supply", &params).await?;
+        let bytes = base64::decode(rep.get::<String>().unwrap()).unwrap();
+        let supply = deserialize_async(&bytes).await?;
+        Ok(supply)
+    }
+
     /// Queries darkfid for given transaction's required fee.
     pub async fn get_tx_fee(&self, tx: &Transaction, include_fee: bool) -> Result<u64> {
         let params = JsonValue::Array(vec![
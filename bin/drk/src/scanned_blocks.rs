@@ -144,6 +144,12 @@ impl Drk {
             self.wallet.exec_batch_sql(&query)?;
         }
 
+        // Balances are derived from the coins, so recompute them
+        if let Err(e) = self.refresh_money_balances().await {
+            eprintln!("[reset_to_height] Refreshing balances failed: {e:?}");
+            return Err(WalletDbError::GenericError)
+        }
+
         println!("Successfully reset wallet state");
         Ok(())
     }
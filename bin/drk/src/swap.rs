@@ -33,8 +33,8 @@ use darkfi_money_contract::{
 };
 use darkfi_sdk::{
     crypto::{
-        contract_id::MONEY_CONTRACT_ID, pedersen::pedersen_commitment_u64, poseidon_hash,
-        BaseBlind, Blind, FuncId, PublicKey, ScalarBlind, SecretKey,
+        contract_id::MONEY_CONTRACT_ID, note::EncryptableNote, pedersen::pedersen_commitment_u64,
+        poseidon_hash, BaseBlind, Blind, FuncId, PublicKey, ScalarBlind, SecretKey,
     },
     pasta::pallas,
     tx::ContractCall,
@@ -337,7 +337,7 @@ impl Drk {
             println!("Trying to decrypt note in output {output_idx}");
 
             for secret in &secret_keys {
-                if let Ok(d_note) = output.note.decrypt::<MoneyNote>(secret) {
+                if let Ok(d_note) = MoneyNote::decrypt_note(&output.note, secret) {
                     let s: SecretKey = deserialize_async(&d_note.memo).await?;
                     skey = Some(s);
                     note = Some(d_note);
@@ -436,7 +436,9 @@ impl Drk {
 
         // Try to decrypt the first note
         for secret in &secret_keys {
-            let Ok(note) = &params.outputs[0].note.decrypt::<MoneyNote>(secret) else { continue };
+            let Ok(note) = &MoneyNote::decrypt_note(&params.outputs[0].note, secret) else {
+                continue
+            };
 
             // Sign the swap transaction
             let skey: SecretKey = deserialize_async(&note.memo).await?;
@@ -456,7 +458,9 @@ impl Drk {
 
         // Try to decrypt the second note
         for secret in &secret_keys {
-            let Ok(note) = &params.outputs[1].note.decrypt::<MoneyNote>(secret) else { continue };
+            let Ok(note) = &MoneyNote::decrypt_note(&params.outputs[1].note, secret) else {
+                continue
+            };
 
             // Sign the swap transaction
             let skey: SecretKey = deserialize_async(&note.memo).await?;
@@ -31,7 +31,7 @@ use darkfi_money_contract::{
         auth_token_freeze_v1::AuthTokenFreezeCallBuilder,
         auth_token_mint_v1::AuthTokenMintCallBuilder, token_mint_v1::TokenMintCallBuilder,
     },
-    model::{CoinAttributes, TokenAttributes, TokenId},
+    model::{CoinAttributes, TokenAttributes, TokenId, TokenSupply},
     MoneyFunction, MONEY_CONTRACT_ZKAS_AUTH_TOKEN_MINT_NS_V1, MONEY_CONTRACT_ZKAS_FEE_NS_V1,
     MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1,
 };
@@ -218,11 +218,33 @@ impl Drk {
         amount: &str,
         recipient: PublicKey,
         token_id: TokenId,
+        max_supply: Option<&str>,
         spend_hook: Option<FuncId>,
         user_data: Option<pallas::Base>,
     ) -> Result<Transaction> {
-        // Decode provided amount
+        // Decode provided amount and supply policy
         let amount = decode_base10(amount, BALANCE_BASE10_DECIMALS, false)?;
+        let max_supply = match max_supply {
+            Some(max_supply) => Some(decode_base10(max_supply, BALANCE_BASE10_DECIMALS, false)?),
+            None => None,
+        };
+
+        // Tokens that were already minted must keep their declared supply policy
+        let supply = match self.get_token_supply(&token_id).await {
+            Ok(info) => {
+                if max_supply.is_some() && Some(info.supply) != max_supply.map(TokenSupply::Fixed) {
+                    return Err(Error::Custom(format!(
+                        "Token was already minted with supply {:?}",
+                        info.supply
+                    )))
+                }
+                info.supply
+            }
+            Err(_) => match max_supply {
+                Some(max_supply) => TokenSupply::Fixed(max_supply),
+                None => TokenSupply::Reissuable,
+            },
+        };
 
         // Grab token ID mint authority and attributes
         let token_mint_authority = self.get_token_mint_authority(&token_id).await?;
@@ -293,7 +315,7 @@ impl Drk {
         let auth_call = ContractCall { contract_id: *MONEY_CONTRACT_ID, data };
 
         // Create the minting call
-        let builder = TokenMintCallBuilder { coin_attrs, token_attrs, mint_zkbin, mint_pk };
+        let builder = TokenMintCallBuilder { coin_attrs, token_attrs, supply, mint_zkbin, mint_pk };
         let mint_debris = builder.build()?;
         let mut data = vec![MoneyFunction::TokenMintV1 as u8];
         mint_debris.params.encode_async(&mut data).await?;
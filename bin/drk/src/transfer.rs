@@ -46,6 +46,7 @@ impl Drk {
         spend_hook: Option<FuncId>,
         user_data: Option<pallas::Base>,
         half_split: bool,
+        disclose_sender: bool,
     ) -> Result<Transaction> {
         // First get all unspent OwnCoins to see what our balance is
         let owncoins = self.get_token_coins(&token_id).await?;
@@ -123,8 +124,12 @@ impl Drk {
             burn_zkbin,
             burn_pk,
             half_split,
+            disclose_sender,
         )?;
 
+        // Keep track of payments to addresses we are watching
+        self.track_watched_outputs(&recipient, &params.outputs, &secrets.output_notes).await?;
+
         // Encode the call
         let mut data = vec![MoneyFunction::TransferV1 as u8];
         params.encode_async(&mut data).await?;
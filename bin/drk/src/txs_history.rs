@@ -16,14 +16,19 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::collections::HashMap;
+
 use rusqlite::types::Value;
 
-use darkfi::{tx::Transaction, Error, Result};
+use darkfi::{rpc::util::JsonValue, tx::Transaction, util::parse::encode_base10, Error, Result};
+use darkfi_money_contract::{client::OwnCoin, model::TokenId};
+use darkfi_sdk::crypto::PublicKey;
 use darkfi_serial::{deserialize_async, serialize_async};
 
 use crate::{
     convert_named_params,
     error::{WalletDbError, WalletDbResult},
+    money::BALANCE_BASE10_DECIMALS,
     Drk,
 };
 
@@ -34,6 +39,48 @@ const WALLET_TXS_HISTORY_COL_TX_HASH: &str = "transaction_hash";
 const WALLET_TXS_HISTORY_COL_STATUS: &str = "status";
 const WALLET_TXS_HISTORY_COL_TX: &str = "tx";
 
+const WALLET_TXS_HISTORY_FLOWS_TABLE: &str = "transactions_history_flows";
+const WALLET_TXS_HISTORY_FLOWS_COL_TX_HASH: &str = "transaction_hash";
+const WALLET_TXS_HISTORY_FLOWS_COL_TOKEN_ID: &str = "token_id";
+const WALLET_TXS_HISTORY_FLOWS_COL_HEIGHT: &str = "height";
+const WALLET_TXS_HISTORY_FLOWS_COL_RECEIVED: &str = "received";
+const WALLET_TXS_HISTORY_FLOWS_COL_SENT: &str = "sent";
+const WALLET_TXS_HISTORY_FLOWS_COL_COUNTERPARTY: &str = "counterparty";
+
+const WALLET_TXS_LABELS_TABLE: &str = "transactions_labels";
+const WALLET_TXS_LABELS_COL_TX_HASH: &str = "transaction_hash";
+const WALLET_TXS_LABELS_COL_LABEL: &str = "label";
+
+/// Value movement of a transaction history record, for a single token.
+#[derive(Clone, Debug)]
+pub struct TxHistoryFlow {
+    /// Token the values refer to
+    pub token_id: TokenId,
+    /// Block height the transaction got confirmed in
+    pub height: u32,
+    /// Sum of our coins created by the transaction
+    pub received: u64,
+    /// Sum of our coins spent by the transaction
+    pub sent: u64,
+    /// Sender of the received coins, when their notes revealed it
+    pub counterparty: Option<PublicKey>,
+}
+
+impl TxHistoryFlow {
+    fn new(token_id: TokenId, height: u32) -> Self {
+        Self { token_id, height, received: 0, sent: 0, counterparty: None }
+    }
+}
+
+/// A transaction history record along with its value movements and label.
+#[derive(Clone, Debug)]
+pub struct TxHistoryEntry {
+    pub tx_hash: String,
+    pub status: String,
+    pub label: Option<String>,
+    pub flows: Vec<TxHistoryFlow>,
+}
+
 impl Drk {
     /// Insert or update a `Transaction` history record into the wallet,
     /// with the provided status, and store its inverse query into the cache.
@@ -143,9 +190,242 @@ impl Drk {
         Ok(ret)
     }
 
+    /// Insert the per token value movements of a confirmed transaction
+    /// into the wallet, and store their inverse queries into the cache.
+    /// `received` are our coins the transaction created, and `spent`
+    /// our coins it consumed.
+    pub async fn put_tx_history_flows(
+        &self,
+        tx_hash: &str,
+        height: u32,
+        received: &[OwnCoin],
+        spent: &[OwnCoin],
+    ) -> Result<()> {
+        let mut flows: HashMap<String, TxHistoryFlow> = HashMap::new();
+        for coin in received {
+            let flow = flows
+                .entry(coin.note.token_id.to_string())
+                .or_insert_with(|| TxHistoryFlow::new(coin.note.token_id, height));
+            flow.received += coin.note.value;
+
+            // Skip senders we can't verify, and our own change outputs
+            if let Some(sender) = &coin.note.sender {
+                if sender.public_key != PublicKey::from_secret(coin.secret) &&
+                    sender.verify(&coin.coin)
+                {
+                    flow.counterparty = Some(sender.public_key);
+                }
+            }
+        }
+        for coin in spent {
+            let flow = flows
+                .entry(coin.note.token_id.to_string())
+                .or_insert_with(|| TxHistoryFlow::new(coin.note.token_id, height));
+            flow.sent += coin.note.value;
+        }
+
+        let query = format!(
+            "INSERT OR REPLACE INTO {WALLET_TXS_HISTORY_FLOWS_TABLE} ({WALLET_TXS_HISTORY_FLOWS_COL_TX_HASH}, {WALLET_TXS_HISTORY_FLOWS_COL_TOKEN_ID}, {WALLET_TXS_HISTORY_FLOWS_COL_HEIGHT}, {WALLET_TXS_HISTORY_FLOWS_COL_RECEIVED}, {WALLET_TXS_HISTORY_FLOWS_COL_SENT}, {WALLET_TXS_HISTORY_FLOWS_COL_COUNTERPARTY}) VALUES (?1, ?2, ?3, ?4, ?5, ?6);"
+        );
+        let inverse_query = format!(
+            "DELETE FROM {WALLET_TXS_HISTORY_FLOWS_TABLE} WHERE {WALLET_TXS_HISTORY_FLOWS_COL_TX_HASH} = ?1 AND {WALLET_TXS_HISTORY_FLOWS_COL_TOKEN_ID} = ?2;"
+        );
+
+        for flow in flows.values() {
+            let token_id = serialize_async(&flow.token_id).await;
+            let counterparty = match flow.counterparty {
+                Some(ref c) => Some(serialize_async(c).await),
+                None => None,
+            };
+
+            // Create its inverse query
+            let inverse = match self
+                .wallet
+                .create_prepared_statement(&inverse_query, rusqlite::params![tx_hash, token_id])
+            {
+                Ok(q) => q,
+                Err(e) => {
+                    return Err(Error::DatabaseError(format!(
+                        "[put_tx_history_flows] Creating flow insert inverse query failed: {e:?}"
+                    )))
+                }
+            };
+
+            // Execute the query
+            let params = rusqlite::params![
+                tx_hash,
+                token_id,
+                flow.height,
+                serialize_async(&flow.received).await,
+                serialize_async(&flow.sent).await,
+                counterparty,
+            ];
+            if let Err(e) = self.wallet.exec_sql(&query, params) {
+                return Err(Error::DatabaseError(format!(
+                    "[put_tx_history_flows] Inserting flow failed: {e:?}"
+                )))
+            }
+
+            // Store its inverse
+            if let Err(e) = self.wallet.cache_inverse(inverse) {
+                return Err(Error::DatabaseError(format!(
+                    "[put_tx_history_flows] Inserting inverse query into cache failed: {e:?}"
+                )))
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fetch all transactions history records, along with their
+    /// value movements and labels.
+    pub async fn get_txs_history_entries(&self) -> Result<Vec<TxHistoryEntry>> {
+        let records = match self.get_txs_history() {
+            Ok(r) => r,
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[get_txs_history_entries] Transactions history retrieval failed: {e:?}"
+                )))
+            }
+        };
+
+        let rows = match self.wallet.query_multiple(
+            WALLET_TXS_LABELS_TABLE,
+            &[WALLET_TXS_LABELS_COL_TX_HASH, WALLET_TXS_LABELS_COL_LABEL],
+            &[],
+        ) {
+            Ok(r) => r,
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[get_txs_history_entries] Transactions labels retrieval failed: {e:?}"
+                )))
+            }
+        };
+        let mut labels = HashMap::with_capacity(rows.len());
+        for row in rows {
+            let (Value::Text(tx_hash), Value::Text(label)) = (&row[0], &row[1]) else {
+                return Err(Error::ParseFailed("[get_txs_history_entries] Label parsing failed"))
+            };
+            labels.insert(tx_hash.clone(), label.clone());
+        }
+
+        let rows = match self.wallet.query_multiple(
+            WALLET_TXS_HISTORY_FLOWS_TABLE,
+            &[
+                WALLET_TXS_HISTORY_FLOWS_COL_TX_HASH,
+                WALLET_TXS_HISTORY_FLOWS_COL_TOKEN_ID,
+                WALLET_TXS_HISTORY_FLOWS_COL_HEIGHT,
+                WALLET_TXS_HISTORY_FLOWS_COL_RECEIVED,
+                WALLET_TXS_HISTORY_FLOWS_COL_SENT,
+                WALLET_TXS_HISTORY_FLOWS_COL_COUNTERPARTY,
+            ],
+            &[],
+        ) {
+            Ok(r) => r,
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[get_txs_history_entries] Transactions flows retrieval failed: {e:?}"
+                )))
+            }
+        };
+        let mut flows: HashMap<String, Vec<TxHistoryFlow>> = HashMap::new();
+        for row in rows {
+            let Value::Text(ref tx_hash) = row[0] else {
+                return Err(Error::ParseFailed(
+                    "[get_txs_history_entries] Transaction hash parsing failed",
+                ))
+            };
+
+            let Value::Blob(ref token_id_bytes) = row[1] else {
+                return Err(Error::ParseFailed("[get_txs_history_entries] Token ID parsing failed"))
+            };
+            let token_id: TokenId = deserialize_async(token_id_bytes).await?;
+
+            let Value::Integer(height) = row[2] else {
+                return Err(Error::ParseFailed("[get_txs_history_entries] Height parsing failed"))
+            };
+            let Ok(height) = u32::try_from(height) else {
+                return Err(Error::ParseFailed("[get_txs_history_entries] Height parsing failed"))
+            };
+
+            let Value::Blob(ref received_bytes) = row[3] else {
+                return Err(Error::ParseFailed(
+                    "[get_txs_history_entries] Received value parsing failed",
+                ))
+            };
+            let received: u64 = deserialize_async(received_bytes).await?;
+
+            let Value::Blob(ref sent_bytes) = row[4] else {
+                return Err(Error::ParseFailed(
+                    "[get_txs_history_entries] Sent value parsing failed",
+                ))
+            };
+            let sent: u64 = deserialize_async(sent_bytes).await?;
+
+            let counterparty = match row[5] {
+                Value::Blob(ref bytes) => Some(deserialize_async(bytes).await?),
+                Value::Null => None,
+                _ => {
+                    return Err(Error::ParseFailed(
+                        "[get_txs_history_entries] Counterparty parsing failed",
+                    ))
+                }
+            };
+
+            flows.entry(tx_hash.clone()).or_default().push(TxHistoryFlow {
+                token_id,
+                height,
+                received,
+                sent,
+                counterparty,
+            });
+        }
+
+        let mut ret = Vec::with_capacity(records.len());
+        for (tx_hash, status) in records {
+            ret.push(TxHistoryEntry {
+                label: labels.remove(&tx_hash),
+                flows: flows.remove(&tx_hash).unwrap_or_default(),
+                tx_hash,
+                status,
+            });
+        }
+
+        Ok(ret)
+    }
+
+    /// Set or clear the label of a transaction history record.
+    /// Labels are kept across wallet rescans.
+    pub fn set_tx_label(&self, tx_hash: &str, label: Option<&str>) -> WalletDbResult<()> {
+        // Make sure the record exists
+        self.wallet.query_single(
+            WALLET_TXS_HISTORY_TABLE,
+            &[WALLET_TXS_HISTORY_COL_TX_HASH],
+            convert_named_params! {(WALLET_TXS_HISTORY_COL_TX_HASH, tx_hash)},
+        )?;
+
+        match label {
+            Some(label) => {
+                let query = format!(
+                    "INSERT OR REPLACE INTO {WALLET_TXS_LABELS_TABLE} ({WALLET_TXS_LABELS_COL_TX_HASH}, {WALLET_TXS_LABELS_COL_LABEL}) VALUES (?1, ?2);"
+                );
+                self.wallet.exec_sql(&query, rusqlite::params![tx_hash, label])
+            }
+            None => {
+                let query = format!(
+                    "DELETE FROM {WALLET_TXS_LABELS_TABLE} WHERE {WALLET_TXS_LABELS_COL_TX_HASH} = ?1;"
+                );
+                self.wallet.exec_sql(&query, rusqlite::params![tx_hash])
+            }
+        }
+    }
+
     /// Reset the transaction history records in the wallet.
+    /// User labels are kept, so they reattach after a rescan.
     pub fn reset_tx_history(&self) -> WalletDbResult<()> {
         println!("Resetting transactions history");
+        let query = format!("DELETE FROM {WALLET_TXS_HISTORY_FLOWS_TABLE};");
+        self.wallet.exec_sql(&query, &[])?;
         let query = format!("DELETE FROM {WALLET_TXS_HISTORY_TABLE};");
         self.wallet.exec_sql(&query, &[])?;
         println!("Successfully reset transactions history");
@@ -157,6 +437,12 @@ impl Drk {
     /// that have been reverted.
     pub fn remove_reverted_txs(&self) -> WalletDbResult<()> {
         println!("Removing reverted transactions history records");
+        for table in [WALLET_TXS_HISTORY_FLOWS_TABLE, WALLET_TXS_LABELS_TABLE] {
+            let query = format!(
+                "DELETE FROM {table} WHERE {WALLET_TXS_HISTORY_COL_TX_HASH} IN (SELECT {WALLET_TXS_HISTORY_COL_TX_HASH} FROM {WALLET_TXS_HISTORY_TABLE} WHERE {WALLET_TXS_HISTORY_COL_STATUS} = 'Reverted');"
+            );
+            self.wallet.exec_sql(&query, &[])?;
+        }
         let query = format!(
             "DELETE FROM {WALLET_TXS_HISTORY_TABLE} WHERE {WALLET_TXS_HISTORY_COL_STATUS} = 'Reverted';"
         );
@@ -166,3 +452,82 @@ impl Drk {
         Ok(())
     }
 }
+
+/// Auxiliary function to escape a CSV field.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        return format!("\"{}\"", field.replace('"', "\"\""))
+    }
+    field.to_string()
+}
+
+/// Export transactions history entries as CSV, one row per token flow.
+/// Records without any flow, like pending or DAO only transactions,
+/// get a single row with empty value columns.
+pub fn txs_history_to_csv(entries: &[TxHistoryEntry]) -> String {
+    let mut csv =
+        String::from("transaction_hash,status,height,token_id,received,sent,counterparty,label\n");
+    for entry in entries {
+        let label = csv_field(entry.label.as_deref().unwrap_or_default());
+        if entry.flows.is_empty() {
+            csv.push_str(&format!("{},{},,,,,,{label}\n", entry.tx_hash, entry.status));
+            continue
+        }
+
+        for flow in &entry.flows {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{label}\n",
+                entry.tx_hash,
+                entry.status,
+                flow.height,
+                flow.token_id,
+                encode_base10(flow.received, BALANCE_BASE10_DECIMALS),
+                encode_base10(flow.sent, BALANCE_BASE10_DECIMALS),
+                flow.counterparty.as_ref().map(|c| c.to_string()).unwrap_or_default(),
+            ));
+        }
+    }
+
+    csv
+}
+
+/// Export transactions history entries as a JSON array.
+/// Values are encoded as decimal strings to avoid losing precision.
+pub fn txs_history_to_json(entries: &[TxHistoryEntry]) -> JsonValue {
+    let mut ret = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let mut flows = Vec::with_capacity(entry.flows.len());
+        for flow in &entry.flows {
+            let counterparty = match flow.counterparty {
+                Some(ref c) => JsonValue::String(c.to_string()),
+                None => JsonValue::Null,
+            };
+            flows.push(JsonValue::Object(HashMap::from([
+                ("token_id".to_string(), JsonValue::String(flow.token_id.to_string())),
+                ("height".to_string(), JsonValue::Number(flow.height as f64)),
+                (
+                    "received".to_string(),
+                    JsonValue::String(encode_base10(flow.received, BALANCE_BASE10_DECIMALS)),
+                ),
+                (
+                    "sent".to_string(),
+                    JsonValue::String(encode_base10(flow.sent, BALANCE_BASE10_DECIMALS)),
+                ),
+                ("counterparty".to_string(), counterparty),
+            ])));
+        }
+
+        let label = match entry.label {
+            Some(ref l) => JsonValue::String(l.clone()),
+            None => JsonValue::Null,
+        };
+        ret.push(JsonValue::Object(HashMap::from([
+            ("transaction_hash".to_string(), JsonValue::String(entry.tx_hash.clone())),
+            ("status".to_string(), JsonValue::String(entry.status.clone())),
+            ("label".to_string(), label),
+            ("flows".to_string(), JsonValue::Array(flows)),
+        ])));
+    }
+
+    JsonValue::Array(ret)
+}
@@ -17,10 +17,13 @@
  */
 
 use std::{
-    path::PathBuf,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
 };
 
+use argon2::Argon2;
 use darkfi_sdk::{
     crypto::{
         pasta_prelude::PrimeField,
@@ -28,9 +31,11 @@ use darkfi_sdk::{
     },
     error::{ContractError, ContractResult},
     pasta::pallas,
+    AsHex,
 };
-use log::{debug, error};
+use log::{debug, error, info};
 use num_bigint::BigUint;
+use rand::{rngs::OsRng, RngCore};
 use rusqlite::{
     types::{ToSql, Value},
     Connection,
@@ -40,6 +45,119 @@ use crate::error::{WalletDbError, WalletDbResult};
 
 pub type WalletPtr = Arc<WalletDb>;
 
+/// Key derivation salt length in bytes
+const SALT_SIZE: usize = 16;
+
+/// Derive the wallet database encryption key from a passphrase using Argon2.
+///
+/// The salt is kept next to the database in a `.salt` file and created on
+/// first use. Wallets created before key derivation existed were keyed with
+/// the raw passphrase, so they get re-keyed here when their salt is missing.
+///
+/// A new salt is first written to a `.salt.tmp` file and only moved in place
+/// once the database uses it, so an interrupted re-key gets picked up again
+/// from the temporary salt on the next run.
+pub fn derive_wallet_key(path: &Path, passphrase: &str) -> WalletDbResult<[u8; 32]> {
+    let salt_path = PathBuf::from(format!("{}.salt", path.display()));
+
+    if salt_path.exists() {
+        let Ok(salt) = fs::read(&salt_path) else {
+            error!(target: "walletdb::derive_wallet_key", "[WalletDb] Failed reading salt file");
+            return Err(WalletDbError::KeyDerivationFailed)
+        };
+        return argon2_key(passphrase, &salt)
+    }
+
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+
+    // Reuse the salt of an interrupted re-key, since the database
+    // might already be keyed with it.
+    let tmp_path = salt_path.with_extension("salt.tmp");
+    let salt = match fs::read(&tmp_path) {
+        Ok(salt) if salt.len() == SALT_SIZE => salt,
+        _ => {
+            let mut salt = [0u8; SALT_SIZE];
+            OsRng.fill_bytes(&mut salt);
+            if let Err(e) = write_synced(&tmp_path, &salt) {
+                error!(target: "walletdb::derive_wallet_key", "[WalletDb] Failed writing salt file: {e}");
+                return Err(WalletDbError::KeyDerivationFailed)
+            }
+            salt.to_vec()
+        }
+    };
+    let key = argon2_key(passphrase, &salt)?;
+
+    if path.exists() && !opens_with_key(path, &raw_key(&key)) {
+        info!(target: "walletdb::derive_wallet_key", "[WalletDb] Re-keying legacy wallet at {path:?}");
+        rekey_legacy(path, passphrase, &key)?;
+    }
+
+    if let Err(e) = fs::rename(&tmp_path, &salt_path) {
+        error!(target: "walletdb::derive_wallet_key", "[WalletDb] Failed writing salt file: {e}");
+        return Err(WalletDbError::KeyDerivationFailed)
+    }
+
+    Ok(key)
+}
+
+/// Auxiliary function to write a file and flush it to disk.
+fn write_synced(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    file.write_all(data)?;
+    file.sync_all()
+}
+
+/// Auxiliary function to check if the database at `path` opens with `key`.
+fn opens_with_key(path: &Path, key: &str) -> bool {
+    let Ok(conn) = Connection::open(path) else { return false };
+    conn.pragma_update(None, "key", key).is_ok() &&
+        conn.query_row("SELECT count(*) FROM sqlite_master;", [], |_| Ok(())).is_ok()
+}
+
+/// Auxiliary function to run Argon2 over a passphrase and salt.
+fn argon2_key(passphrase: &str, salt: &[u8]) -> WalletDbResult<[u8; 32]> {
+    let mut key = [0u8; 32];
+    if let Err(e) = Argon2::default().hash_password_into(passphrase.as_bytes(), salt, &mut key) {
+        error!(target: "walletdb::argon2_key", "[WalletDb] Key derivation failed: {e}");
+        return Err(WalletDbError::KeyDerivationFailed)
+    }
+    Ok(key)
+}
+
+/// Auxiliary function to format a key as an SQLCipher raw key,
+/// which skips SQLCipher's own passphrase derivation.
+fn raw_key(key: &[u8; 32]) -> String {
+    format!("x'{}'", key.hex())
+}
+
+/// Auxiliary function to replace the raw passphrase key of
+/// a legacy wallet with the derived one.
+fn rekey_legacy(path: &Path, passphrase: &str, key: &[u8; 32]) -> WalletDbResult<()> {
+    let Ok(conn) = Connection::open(path) else { return Err(WalletDbError::ConnectionFailed) };
+    if let Err(e) = conn.pragma_update(None, "key", passphrase) {
+        error!(target: "walletdb::rekey_legacy", "[WalletDb] Pragma update failed: {e}");
+        return Err(WalletDbError::PragmaUpdateError)
+    }
+    check_key(&conn)?;
+    if let Err(e) = conn.pragma_update(None, "rekey", raw_key(key)) {
+        error!(target: "walletdb::rekey_legacy", "[WalletDb] Pragma update failed: {e}");
+        return Err(WalletDbError::PragmaUpdateError)
+    }
+    Ok(())
+}
+
+/// Auxiliary function to check the connection key can read the database,
+/// since SQLCipher only fails on first access.
+fn check_key(conn: &Connection) -> WalletDbResult<()> {
+    if let Err(e) = conn.query_row("SELECT count(*) FROM sqlite_master;", [], |_| Ok(())) {
+        error!(target: "walletdb::check_key", "[WalletDb] Wallet key check failed: {e}");
+        return Err(WalletDbError::InvalidKey)
+    }
+    Ok(())
+}
+
 /// Structure representing base wallet database operations.
 pub struct WalletDb {
     /// Connection to the SQLite database.
@@ -51,7 +169,8 @@ pub struct WalletDb {
 
 impl WalletDb {
     /// Create a new wallet database handler. If `path` is `None`, create it in memory.
-    pub fn new(path: Option<PathBuf>, password: Option<&str>) -> WalletDbResult<WalletPtr> {
+    /// If `key` is provided, the database is encrypted with it, see [`derive_wallet_key`].
+    pub fn new(path: Option<PathBuf>, key: Option<&[u8; 32]>) -> WalletDbResult<WalletPtr> {
         let Ok(conn) = (match path.clone() {
             Some(p) => Connection::open(p),
             None => Connection::open_in_memory(),
@@ -59,11 +178,12 @@ impl WalletDb {
             return Err(WalletDbError::ConnectionFailed);
         };
 
-        if let Some(password) = password {
-            if let Err(e) = conn.pragma_update(None, "key", password) {
+        if let Some(key) = key {
+            if let Err(e) = conn.pragma_update(None, "key", raw_key(key)) {
                 error!(target: "walletdb::new", "[WalletDb] Pragma update failed: {e}");
                 return Err(WalletDbError::PragmaUpdateError);
             };
+            check_key(&conn)?;
         }
         if let Err(e) = conn.pragma_update(None, "foreign_keys", "ON") {
             error!(target: "walletdb::new", "[WalletDb] Pragma update failed: {e}");
@@ -588,14 +708,15 @@ mod tests {
         crypto::smt::{gen_empty_nodes, util::FieldHasher, PoseidonFp, SparseMerkleTree},
         pasta::pallas,
     };
-    use rand::rngs::OsRng;
-    use rusqlite::types::Value;
+    use rand::{rngs::OsRng, RngCore};
+    use rusqlite::{types::Value, Connection};
+    use std::{fs, path::PathBuf};
 
-    use crate::walletdb::{WalletDb, WalletStorage};
+    use crate::walletdb::{derive_wallet_key, WalletDb, WalletStorage};
 
     #[test]
     fn test_mem_wallet() {
-        let wallet = WalletDb::new(None, Some("foobar")).unwrap();
+        let wallet = WalletDb::new(None, Some(&[42u8; 32])).unwrap();
         wallet
             .exec_batch_sql(
                 "CREATE TABLE mista ( numba INTEGER ); INSERT INTO mista ( numba ) VALUES ( 42 );",
@@ -614,6 +735,49 @@ mod tests {
         assert_eq!(numba, 42);
     }
 
+    #[test]
+    fn test_legacy_wallet_rekey() {
+        let dir = std::env::temp_dir().join(format!("drk-walletdb-{}", OsRng.next_u64()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("wallet.db");
+        let salt_path = PathBuf::from(format!("{}.salt", path.display()));
+        let tmp_path = salt_path.with_extension("salt.tmp");
+
+        // Create a legacy wallet keyed with the raw passphrase
+        let conn = Connection::open(&path).unwrap();
+        conn.pragma_update(None, "key", "hunter2").unwrap();
+        conn.execute_batch(
+            "CREATE TABLE mista ( numba INTEGER ); INSERT INTO mista ( numba ) VALUES ( 42 );",
+        )
+        .unwrap();
+        drop(conn);
+
+        // The first derivation re-keys it and keeps the salt
+        let key = derive_wallet_key(&path, "hunter2").unwrap();
+        assert!(salt_path.exists());
+        assert!(!tmp_path.exists());
+        let wallet = WalletDb::new(Some(path.clone()), Some(&key)).unwrap();
+        let ret = wallet.query_single("mista", &["numba"], &[]).unwrap();
+        assert_eq!(ret[0], Value::Integer(42));
+        drop(wallet);
+        assert_eq!(derive_wallet_key(&path, "hunter2").unwrap(), key);
+
+        // A re-key interrupted before the salt was moved in place
+        // resumes with the temporary salt
+        fs::rename(&salt_path, &tmp_path).unwrap();
+        assert_eq!(derive_wallet_key(&path, "hunter2").unwrap(), key);
+        assert!(salt_path.exists());
+        assert!(!tmp_path.exists());
+        assert!(WalletDb::new(Some(path.clone()), Some(&key)).is_ok());
+
+        // A wrong passphrase neither opens nor re-keys the wallet
+        fs::remove_file(&salt_path).unwrap();
+        assert!(derive_wallet_key(&path, "hunter3").is_err());
+        assert!(WalletDb::new(Some(path), Some(&key)).is_ok());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
     #[test]
     fn test_query_single() {
         let wallet = WalletDb::new(None, None).unwrap();
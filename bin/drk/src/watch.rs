@@ -0,0 +1,542 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Watch-only monitoring of addresses we don't spend from.
+//!
+//! Money notes are encrypted to the recipient and carry no view tag, so
+//! a bare public key can't be matched against arbitrary outputs. Watched
+//! addresses can therefore hold a view secret, the key their notes get
+//! encrypted to, which is only ever used to trial-decrypt the outputs of
+//! scanned transactions. Its coins are tracked here, never added to the
+//! wallet balance, and never spent. Note decryption uses the address
+//! secret key itself, so the view secret has to be protected like one.
+//!
+//! Addresses watched without a view secret only get linked to coins whose
+//! attributes are known to the wallet: either because the wallet built the
+//! payment itself, or because one of the wallet keys could decrypt its note.
+
+use rusqlite::types::Value;
+
+use darkfi::{util::parse::encode_base10, Error, Result};
+use darkfi_money_contract::{
+    client::{MoneyNote, OwnCoin},
+    model::{Coin, CoinAttributes, Output, TokenId},
+};
+use darkfi_sdk::crypto::{
+    note::{AeadEncryptedNote, EncryptableNote},
+    PublicKey, SecretKey,
+};
+use darkfi_serial::{deserialize_async, serialize_async};
+
+use crate::{
+    convert_named_params,
+    error::{WalletDbError, WalletDbResult},
+    money::{
+        BALANCE_BASE10_DECIMALS, MONEY_WATCHED_COINS_COL_COIN,
+        MONEY_WATCHED_COINS_COL_IS_DECRYPTED, MONEY_WATCHED_COINS_COL_PUBLIC,
+        MONEY_WATCHED_COINS_COL_TOKEN_ID, MONEY_WATCHED_COINS_COL_TX_HASH,
+        MONEY_WATCHED_COINS_COL_VALUE, MONEY_WATCHED_COINS_TABLE, MONEY_WATCHED_COL_LABEL,
+        MONEY_WATCHED_COL_PUBLIC, MONEY_WATCHED_COL_VIEW_SECRET, MONEY_WATCHED_TABLE,
+    },
+    Drk,
+};
+
+/// An address we are watching
+pub struct WatchedAddress {
+    pub public: PublicKey,
+    pub label: String,
+    /// Secret key used to trial-decrypt the notes paying to the address
+    pub view_secret: Option<SecretKey>,
+}
+
+/// A coin paying to a watched address
+pub struct WatchedCoin {
+    pub coin: Coin,
+    pub public: PublicKey,
+    /// Value of the coin, if known
+    pub value: Option<u64>,
+    /// Token ID of the coin, if known
+    pub token_id: Option<TokenId>,
+    /// Flag indicating the value and token were verified by decrypting the note
+    pub is_decrypted: bool,
+    /// Hash of the transaction the coin was found in, `-` if not seen on chain yet
+    pub tx_hash: String,
+}
+
+impl Drk {
+    /// Start monitoring provided address. When a view secret is provided,
+    /// it has to belong to the address, and payments from anyone to the
+    /// address get detected by trial-decrypting their notes with it.
+    pub async fn add_watched_address(
+        &self,
+        public: &PublicKey,
+        label: &str,
+        view_secret: Option<&SecretKey>,
+    ) -> Result<()> {
+        let view_secret = match view_secret {
+            Some(secret) if PublicKey::from_secret(*secret) != *public => {
+                return Err(Error::Custom("View secret doesn't match the address".to_string()))
+            }
+            Some(secret) => Some(serialize_async(secret).await),
+            None => None,
+        };
+
+        println!("Watching address {public}");
+        let query = format!(
+            "INSERT OR REPLACE INTO {} ({}, {}, {}) VALUES (?1, ?2, ?3);",
+            *MONEY_WATCHED_TABLE,
+            MONEY_WATCHED_COL_PUBLIC,
+            MONEY_WATCHED_COL_LABEL,
+            MONEY_WATCHED_COL_VIEW_SECRET,
+        );
+        let params = rusqlite::params![serialize_async(public).await, label, view_secret];
+        if let Err(e) = self.wallet.exec_sql(&query, params) {
+            return Err(Error::DatabaseError(format!(
+                "[add_watched_address] Inserting watched address failed: {e:?}"
+            )))
+        }
+
+        Ok(())
+    }
+
+    /// Stop monitoring provided address.
+    pub async fn remove_watched_address(&self, public: &PublicKey) -> WalletDbResult<()> {
+        println!("Removing watched address {public}");
+        let query = format!(
+            "DELETE FROM {} WHERE {} = ?1;",
+            *MONEY_WATCHED_TABLE, MONEY_WATCHED_COL_PUBLIC
+        );
+        self.wallet.exec_sql(&query, rusqlite::params![serialize_async(public).await])
+    }
+
+    /// Fetch all watched addresses.
+    pub async fn get_watched_addresses(&self) -> Result<Vec<WatchedAddress>> {
+        let rows = match self.wallet.query_multiple(&MONEY_WATCHED_TABLE, &[], &[]) {
+            Ok(r) => r,
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[get_watched_addresses] Watched addresses retrieval failed: {e:?}"
+                )))
+            }
+        };
+
+        let mut ret = Vec::with_capacity(rows.len());
+        for row in rows {
+            let Value::Blob(ref public_bytes) = row[0] else {
+                return Err(Error::ParseFailed(
+                    "[get_watched_addresses] Public key bytes parsing failed",
+                ))
+            };
+            let public = deserialize_async(public_bytes).await?;
+
+            let Value::Text(ref label) = row[1] else {
+                return Err(Error::ParseFailed("[get_watched_addresses] Label parsing failed"))
+            };
+
+            let view_secret = match row[2] {
+                Value::Blob(ref secret_bytes) => Some(deserialize_async(secret_bytes).await?),
+                Value::Null => None,
+                _ => {
+                    return Err(Error::ParseFailed(
+                        "[get_watched_addresses] View secret bytes parsing failed",
+                    ))
+                }
+            };
+
+            ret.push(WatchedAddress { public, label: label.clone(), view_secret });
+        }
+
+        Ok(ret)
+    }
+
+    /// Fetch all known coins paying to watched addresses.
+    pub async fn get_watched_coins(&self) -> Result<Vec<WatchedCoin>> {
+        let rows = match self.wallet.query_multiple(&MONEY_WATCHED_COINS_TABLE, &[], &[]) {
+            Ok(r) => r,
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[get_watched_coins] Watched coins retrieval failed: {e:?}"
+                )))
+            }
+        };
+
+        let mut ret = Vec::with_capacity(rows.len());
+        for row in rows {
+            ret.push(self.parse_watched_coin(&row).await?);
+        }
+
+        Ok(ret)
+    }
+
+    /// Auxiliary function to parse a `MONEY_WATCHED_COINS_TABLE` record.
+    async fn parse_watched_coin(&self, row: &[Value]) -> Result<WatchedCoin> {
+        let Value::Blob(ref coin_bytes) = row[0] else {
+            return Err(Error::ParseFailed("[parse_watched_coin] Coin bytes parsing failed"))
+        };
+        let coin = deserialize_async(coin_bytes).await?;
+
+        let Value::Blob(ref public_bytes) = row[1] else {
+            return Err(Error::ParseFailed("[parse_watched_coin] Public key bytes parsing failed"))
+        };
+        let public = deserialize_async(public_bytes).await?;
+
+        let value = match row[2] {
+            Value::Blob(ref value_bytes) => Some(deserialize_async(value_bytes).await?),
+            Value::Null => None,
+            _ => return Err(Error::ParseFailed("[parse_watched_coin] Value bytes parsing failed")),
+        };
+
+        let token_id = match row[3] {
+            Value::Blob(ref token_id_bytes) => Some(deserialize_async(token_id_bytes).await?),
+            Value::Null => None,
+            _ => {
+                return Err(Error::ParseFailed("[parse_watched_coin] Token ID bytes parsing failed"))
+            }
+        };
+
+        let Value::Integer(is_decrypted) = row[4] else {
+            return Err(Error::ParseFailed("[parse_watched_coin] Is decrypted parsing failed"))
+        };
+
+        let Value::Text(ref tx_hash) = row[5] else {
+            return Err(Error::ParseFailed("[parse_watched_coin] Transaction hash parsing failed"))
+        };
+
+        Ok(WatchedCoin {
+            coin,
+            public,
+            value,
+            token_id,
+            is_decrypted: is_decrypted == 1,
+            tx_hash: tx_hash.clone(),
+        })
+    }
+
+    /// Remember the outputs of a payment we built which pay to a watched
+    /// address, so we can alert when they show up on chain. The coins are
+    /// recomputed from their attributes, so only outputs actually paying
+    /// to the recipient get stored.
+    pub async fn track_watched_outputs(
+        &self,
+        recipient: &PublicKey,
+        outputs: &[Output],
+        notes: &[MoneyNote],
+    ) -> Result<()> {
+        if !self.get_watched_addresses().await?.iter().any(|w| w.public == *recipient) {
+            return Ok(())
+        }
+
+        let query = format!(
+            "INSERT OR IGNORE INTO {} ({}, {}, {}, {}, {}) VALUES (?1, ?2, ?3, ?4, 0);",
+            *MONEY_WATCHED_COINS_TABLE,
+            MONEY_WATCHED_COINS_COL_COIN,
+            MONEY_WATCHED_COINS_COL_PUBLIC,
+            MONEY_WATCHED_COINS_COL_VALUE,
+            MONEY_WATCHED_COINS_COL_TOKEN_ID,
+            MONEY_WATCHED_COINS_COL_IS_DECRYPTED,
+        );
+
+        for (output, note) in outputs.iter().zip(notes.iter()) {
+            let coin = CoinAttributes {
+                public_key: *recipient,
+                value: note.value,
+                token_id: note.token_id,
+                spend_hook: note.spend_hook,
+                user_data: note.user_data,
+                blind: note.coin_blind,
+            }
+            .to_coin();
+
+            if coin != output.coin {
+                continue
+            }
+
+            let params = rusqlite::params![
+                serialize_async(&coin.inner()).await,
+                serialize_async(recipient).await,
+                serialize_async(&note.value).await,
+                serialize_async(&note.token_id).await,
+            ];
+            if let Err(e) = self.wallet.exec_sql(&query, params) {
+                return Err(Error::DatabaseError(format!(
+                    "[track_watched_outputs] Inserting watched coin failed: {e:?}"
+                )))
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check the coins of a scanned transaction against the watched
+    /// addresses, emit alerts for matches, and store their inverse
+    /// queries into the cache.
+    pub async fn apply_tx_watched_coins(
+        &self,
+        coins: &[Coin],
+        notes: &[AeadEncryptedNote],
+        owncoins: &[OwnCoin],
+        tx_hash: &String,
+    ) -> Result<()> {
+        let watched = self.get_watched_addresses().await?;
+        if watched.is_empty() {
+            return Ok(())
+        }
+
+        // Coins we knew about beforehand, like payments we built ourselves
+        let mut alerted = vec![];
+        for coin in coins {
+            let key = serialize_async(&coin.inner()).await;
+            let row = match self.wallet.query_single(
+                &MONEY_WATCHED_COINS_TABLE,
+                &[],
+                convert_named_params! {(MONEY_WATCHED_COINS_COL_COIN, key)},
+            ) {
+                Ok(r) => r,
+                Err(WalletDbError::RowNotFound) => continue,
+                Err(e) => {
+                    return Err(Error::DatabaseError(format!(
+                        "[apply_tx_watched_coins] Watched coin retrieval failed: {e:?}"
+                    )))
+                }
+            };
+
+            let mut watched_coin = self.parse_watched_coin(&row).await?;
+            if watched_coin.tx_hash != "-" {
+                continue
+            }
+
+            if let Err(e) = self.mark_watched_coin_seen(&key, tx_hash) {
+                return Err(Error::DatabaseError(format!(
+                    "[apply_tx_watched_coins] Updating watched coin failed: {e:?}"
+                )))
+            }
+            watched_coin.tx_hash = tx_hash.clone();
+            alerted.push(*coin);
+
+            let label = watched.iter().find(|w| w.public == watched_coin.public).map(|w| &w.label);
+            self.watch_alert(&watched_coin, label);
+        }
+
+        // Coins one of our keys could decrypt
+        let mut decrypted: Vec<(Coin, MoneyNote, PublicKey)> = owncoins
+            .iter()
+            .map(|o| (o.coin, o.note.clone(), PublicKey::from_secret(o.secret)))
+            .collect();
+
+        // Coins one of the watched view secrets could decrypt
+        for (coin, note) in coins.iter().zip(notes.iter()) {
+            for address in &watched {
+                let Some(view_secret) = &address.view_secret else { continue };
+                if let Ok(note) = MoneyNote::decrypt_note(note, view_secret) {
+                    decrypted.push((*coin, note, address.public));
+                    break
+                }
+            }
+        }
+
+        for (coin, note, public) in decrypted {
+            if alerted.contains(&coin) {
+                continue
+            }
+
+            let Some(address) = watched.iter().find(|w| w.public == public) else { continue };
+
+            if let Err(e) = self.insert_decrypted_watched_coin(&coin, &note, &public, tx_hash).await
+            {
+                return Err(Error::DatabaseError(format!(
+                    "[apply_tx_watched_coins] Inserting watched coin failed: {e:?}"
+                )))
+            }
+            alerted.push(coin);
+
+            let watched_coin = WatchedCoin {
+                coin,
+                public,
+                value: Some(note.value),
+                token_id: Some(note.token_id),
+                is_decrypted: true,
+                tx_hash: tx_hash.clone(),
+            };
+            self.watch_alert(&watched_coin, Some(&address.label));
+        }
+
+        Ok(())
+    }
+
+    /// Set the transaction hash of a tracked watched coin, and store its
+    /// inverse query into the cache.
+    fn mark_watched_coin_seen(&self, key: &[u8], tx_hash: &String) -> WalletDbResult<()> {
+        let query = format!(
+            "UPDATE {} SET {} = ?1 WHERE {} = ?2;",
+            *MONEY_WATCHED_COINS_TABLE,
+            MONEY_WATCHED_COINS_COL_TX_HASH,
+            MONEY_WATCHED_COINS_COL_COIN,
+        );
+        let inverse = self.wallet.create_prepared_statement(
+            &format!(
+                "UPDATE {} SET {} = '-' WHERE {} = ?1;",
+                *MONEY_WATCHED_COINS_TABLE,
+                MONEY_WATCHED_COINS_COL_TX_HASH,
+                MONEY_WATCHED_COINS_COL_COIN,
+            ),
+            rusqlite::params![key],
+        )?;
+        self.wallet.exec_sql(&query, rusqlite::params![tx_hash, key])?;
+        self.wallet.cache_inverse(inverse)
+    }
+
+    /// Insert a decrypted coin paying to a watched address, and store its
+    /// inverse query into the cache.
+    async fn insert_decrypted_watched_coin(
+        &self,
+        coin: &Coin,
+        note: &MoneyNote,
+        public: &PublicKey,
+        tx_hash: &String,
+    ) -> WalletDbResult<()> {
+        let query = format!(
+            "INSERT OR IGNORE INTO {} ({}, {}, {}, {}, {}, {}) VALUES (?1, ?2, ?3, ?4, 1, ?5);",
+            *MONEY_WATCHED_COINS_TABLE,
+            MONEY_WATCHED_COINS_COL_COIN,
+            MONEY_WATCHED_COINS_COL_PUBLIC,
+            MONEY_WATCHED_COINS_COL_VALUE,
+            MONEY_WATCHED_COINS_COL_TOKEN_ID,
+            MONEY_WATCHED_COINS_COL_IS_DECRYPTED,
+            MONEY_WATCHED_COINS_COL_TX_HASH,
+        );
+        let key = serialize_async(&coin.inner()).await;
+        let inverse = self.wallet.create_prepared_statement(
+            &format!(
+                "DELETE FROM {} WHERE {} = ?1;",
+                *MONEY_WATCHED_COINS_TABLE, MONEY_WATCHED_COINS_COL_COIN,
+            ),
+            rusqlite::params![key],
+        )?;
+        let params = rusqlite::params![
+            key,
+            serialize_async(public).await,
+            serialize_async(&note.value).await,
+            serialize_async(&note.token_id).await,
+            tx_hash,
+        ];
+        self.wallet.exec_sql(&query, params)?;
+        self.wallet.cache_inverse(inverse)
+    }
+
+    /// Print an alert for a coin paying to a watched address.
+    fn watch_alert(&self, coin: &WatchedCoin, label: Option<&String>) {
+        let label = match label {
+            Some(l) if !l.is_empty() => format!(" ({l})"),
+            _ => String::new(),
+        };
+        let amount = match (coin.value, coin.token_id) {
+            (Some(value), Some(token_id)) => {
+                format!("{} {token_id}", encode_base10(value, BALANCE_BASE10_DECIMALS))
+            }
+            _ => "unknown amount".to_string(),
+        };
+        let verified = if coin.is_decrypted { "" } else { " [unverified amount]" };
+
+        println!(
+            "[WATCH] Address {}{label} received {amount}{verified} in transaction {}",
+            coin.public, coin.tx_hash,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use darkfi::zk::halo2::Field;
+    use darkfi_money_contract::{
+        client::MoneyNote,
+        model::{CoinAttributes, DARK_TOKEN_ID},
+    };
+    use darkfi_sdk::{
+        crypto::{note::AeadEncryptedNote, BaseBlind, FuncId, Keypair, ScalarBlind},
+        pasta::pallas,
+    };
+    use rand::rngs::OsRng;
+
+    use crate::{walletdb::WalletDb, Drk};
+
+    #[test]
+    fn test_watched_view_secret() {
+        smol::block_on(async {
+            let wallet = WalletDb::new(None, None).unwrap();
+            let drk = Drk { wallet, rpc_client: None, fun: false };
+            drk.initialize_wallet().await.unwrap();
+            drk.initialize_money().await.unwrap();
+
+            let donations = Keypair::random(&mut OsRng);
+            let other = Keypair::random(&mut OsRng);
+
+            // A view secret has to belong to its address
+            assert!(drk
+                .add_watched_address(&donations.public, "donations", Some(&other.secret))
+                .await
+                .is_err());
+            drk.add_watched_address(&donations.public, "donations", Some(&donations.secret))
+                .await
+                .unwrap();
+            drk.add_watched_address(&other.public, "other", None).await.unwrap();
+
+            // Build third-party payments to both addresses
+            let mut coins = vec![];
+            let mut notes = vec![];
+            for (public, value) in [(donations.public, 42), (other.public, 69)] {
+                let note = MoneyNote {
+                    value,
+                    token_id: *DARK_TOKEN_ID,
+                    spend_hook: FuncId::none(),
+                    user_data: pallas::Base::ZERO,
+                    coin_blind: BaseBlind::random(&mut OsRng),
+                    value_blind: ScalarBlind::random(&mut OsRng),
+                    token_blind: BaseBlind::random(&mut OsRng),
+                    memo: vec![],
+                    sender: None,
+                };
+                let coin = CoinAttributes {
+                    public_key: public,
+                    value,
+                    token_id: note.token_id,
+                    spend_hook: note.spend_hook,
+                    user_data: note.user_data,
+                    blind: note.coin_blind,
+                }
+                .to_coin();
+                coins.push(coin);
+                notes.push(AeadEncryptedNote::encrypt(&note, &public, &mut OsRng).unwrap());
+            }
+
+            let tx_hash = "tx".to_string();
+            drk.apply_tx_watched_coins(&coins, &notes, &[], &tx_hash).await.unwrap();
+
+            // Only the address watched with its view secret detects its payment
+            let watched = drk.get_watched_coins().await.unwrap();
+            assert_eq!(watched.len(), 1);
+            assert_eq!(watched[0].coin, coins[0]);
+            assert_eq!(watched[0].public, donations.public);
+            assert_eq!(watched[0].value, Some(42));
+            assert_eq!(watched[0].token_id, Some(*DARK_TOKEN_ID));
+            assert!(watched[0].is_decrypted);
+
+            // The watched coin never counts towards the wallet balance
+            assert!(drk.get_coins(false).await.unwrap().is_empty());
+        })
+    }
+}
@@ -16,3 +16,30 @@ CREATE TABLE IF NOT EXISTS transactions_history (
     status TEXT NOT NULL,
 	tx BLOB NOT NULL
 );
+
+-- Per token value movements of our transactions history records
+CREATE TABLE IF NOT EXISTS transactions_history_flows (
+    transaction_hash TEXT NOT NULL,
+    token_id BLOB NOT NULL,
+    height INTEGER NOT NULL,
+    received BLOB NOT NULL,
+    sent BLOB NOT NULL,
+    -- Sender public key, if the received note revealed it
+    counterparty BLOB,
+    PRIMARY KEY (transaction_hash, token_id)
+);
+
+-- User labels of transactions history records
+CREATE TABLE IF NOT EXISTS transactions_labels (
+    transaction_hash TEXT PRIMARY KEY NOT NULL,
+    label TEXT NOT NULL
+);
+
+-- Address book
+CREATE TABLE IF NOT EXISTS contacts (
+    name TEXT PRIMARY KEY NOT NULL,
+    address BLOB NOT NULL,
+    notes TEXT,
+    -- Token sent to the contact when none is provided
+    token_id BLOB
+);
@@ -80,6 +80,10 @@ struct Args {
     /// Set log file to output to
     log: Option<String>,
 
+    #[structopt(long)]
+    /// Serve Prometheus metrics on this URL, e.g. tcp://127.0.0.1:9100
+    metrics_listen: Option<String>,
+
     #[structopt(short, parse(from_occurrences))]
     /// Increase verbosity (-vvv supported)
     verbose: u8,
@@ -27,7 +27,10 @@ use darkfi::{
 };
 use darkfi_serial::deserialize_async;
 
-use crate::{rpc::DarkfidRpcClient, Explorerd};
+use crate::{
+    rpc::{parse_pagination_params, to_paginated_json, DarkfidRpcClient},
+    Explorerd,
+};
 
 impl DarkfidRpcClient {
     /// Retrieves a block from at a given height returning the corresponding [`BlockInfo`].
@@ -132,6 +135,33 @@ impl Explorerd {
         }
     }
 
+    // RPCAPI:
+    // Queries the database to retrieve a page of blocks, most recent first.
+    // Returns the readable blocks of the page along with the total block count upon success.
+    //
+    // **Params:**
+    // * `array[0]`: `u64` Page number, starting from 0 for the latest blocks
+    // * `array[1]`: `u64` Page size, between 1 and 100
+    //
+    // **Returns:**
+    // * Object containing the `BlockRecord` array as `items`, along with `page`,
+    //   `page_size` and `total`, encoded into a JSON.
+    //
+    // **Example API Usage:**
+    // --> {"jsonrpc": "2.0", "method": "blocks.get_blocks_page", "params": [0, 20], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"items": [...], "page": 0, "page_size": 20, "total": 1024}, "id": 1}
+    pub async fn blocks_get_blocks_page(&self, params: &JsonValue) -> Result<JsonValue> {
+        // Extract the pagination parameters
+        let (page, page_size) = parse_pagination_params(params, 0)?;
+
+        // Fetch the blocks page
+        let (blocks, total) = self.service.get_blocks_page(page, page_size)?;
+
+        // Transform blocks to `JsonValue` and return result
+        let json_blocks = blocks.into_iter().map(|block| block.to_json_array()).collect();
+        Ok(to_paginated_json(json_blocks, page, page_size, total))
+    }
+
     // RPCAPI:
     // Queries the database to retrieve the block corresponding to the provided hash.
     // Returns the readable block upon success.
@@ -16,7 +16,11 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{collections::HashSet, sync::Arc, time::Instant};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Instant,
+};
 
 use async_trait::async_trait;
 use log::{debug, error, trace, warn};
@@ -29,7 +33,8 @@ use darkfi::{
     rpc::{
         client::RpcClient,
         jsonrpc::{
-            validate_empty_params, ErrorCode, JsonError, JsonRequest, JsonResponse, JsonResult,
+            parse_json_array_number, validate_empty_params, ErrorCode, JsonError, JsonRequest,
+            JsonResponse, JsonResult,
         },
         server::RequestHandler,
     },
@@ -54,6 +59,9 @@ pub mod statistics;
 /// RPC handlers for transaction data, lookups, and processing
 pub mod transactions;
 
+/// Maximum number of items a paginated RPC method returns per page
+pub const MAX_PAGE_SIZE: usize = 100;
+
 #[async_trait]
 impl RequestHandler<()> for Explorerd {
     /// Handles an incoming JSON-RPC request by executing the appropriate individual request handler
@@ -89,6 +97,9 @@ impl RequestHandler<()> for Explorerd {
         if method == "ping" {
             return self.pong(req.id, params.clone()).await
         }
+        if method == "log.set_level" {
+            return self.log_set_level(req.id, params.clone()).await
+        }
 
         // Match all other methods
         let result = match req.method.as_str() {
@@ -100,6 +111,7 @@ impl RequestHandler<()> for Explorerd {
                 self.blocks_get_blocks_in_heights_range(params).await
             }
             "blocks.get_block_by_hash" => self.blocks_get_block_by_hash(params).await,
+            "blocks.get_blocks_page" => self.blocks_get_blocks_page(params).await,
 
             // =====================
             // Transactions methods
@@ -110,6 +122,9 @@ impl RequestHandler<()> for Explorerd {
             "transactions.get_transaction_by_hash" => {
                 self.transactions_get_transaction_by_hash(params).await
             }
+            "transactions.get_transactions_by_contract_id" => {
+                self.transactions_get_transactions_by_contract_id(params).await
+            }
 
             // =====================
             // Statistics methods
@@ -121,6 +136,7 @@ impl RequestHandler<()> for Explorerd {
             "statistics.get_latest_metric_statistics" => {
                 self.statistics_get_latest_metric_statistics(params).await
             }
+            "statistics.get_daily_statistics" => self.statistics_get_daily_statistics(params).await,
 
             // =====================
             // Contract methods
@@ -313,6 +329,38 @@ impl Explorerd {
     }
 }
 
+/// Auxiliary function that parses the `page` and `page_size` pagination parameters starting at
+/// the provided `index` of `params`, ensuring the page size is between 1 and [`MAX_PAGE_SIZE`].
+pub fn parse_pagination_params(params: &JsonValue, index: usize) -> Result<(usize, usize)> {
+    let page = parse_json_array_number("page", index, params)? as usize;
+    let page_size = parse_json_array_number("page_size", index + 1, params)? as usize;
+
+    if page_size == 0 || page_size > MAX_PAGE_SIZE {
+        return Err(RpcError::InvalidJson(format!(
+            "Invalid page size: {page_size}, must be between 1 and {MAX_PAGE_SIZE}"
+        ))
+        .into());
+    }
+
+    Ok((page, page_size))
+}
+
+/// Auxiliary function that builds a paginated RPC result, containing the page `items` along
+/// with the requested `page`, `page_size` and the `total` number of items available.
+pub fn to_paginated_json(
+    items: Vec<JsonValue>,
+    page: usize,
+    page_size: usize,
+    total: usize,
+) -> JsonValue {
+    JsonValue::Object(HashMap::from([
+        ("items".to_string(), JsonValue::Array(items)),
+        ("page".to_string(), JsonValue::Number(page as f64)),
+        ("page_size".to_string(), JsonValue::Number(page_size as f64)),
+        ("total".to_string(), JsonValue::Number(total as f64)),
+    ]))
+}
+
 /// Auxiliary function that logs RPC request failures by generating a structured log message
 /// containing the provided `req_method`, `params`, and `error` details. Constructs a log target
 /// specific to the request method, formats the error message by stringifying the JSON parameters
@@ -20,9 +20,13 @@ use std::vec::Vec;
 
 use tinyjson::JsonValue;
 
-use darkfi::{rpc::jsonrpc::validate_empty_params, Result};
+use darkfi::{
+    error::RpcError,
+    rpc::jsonrpc::{parse_json_array_number, validate_empty_params},
+    Result,
+};
 
-use crate::Explorerd;
+use crate::{rpc::MAX_PAGE_SIZE, Explorerd};
 
 impl Explorerd {
     // RPCAPI:
@@ -102,6 +106,37 @@ impl Explorerd {
         // Convert the retrieved metrics into a JSON array and return it
         Ok(statistics.to_json_array())
     }
+
+    // RPCAPI:
+    // Queries the database to retrieve the daily statistics of the last N days with chain activity.
+    // Returns the daily statistics, most recent first, upon success. Each entry contains the UNIX
+    // timestamp of the start of the day, followed by its block, transaction and contract call counts.
+    //
+    // **Params:**
+    // * `array[0]`: `u64` Number of days to retrieve, between 1 and 100
+    //
+    // **Returns:**
+    // * `DailyStats` array encoded into a JSON.
+    //
+    // **Example API Usage:**
+    // --> {"jsonrpc": "2.0", "method": "statistics.get_daily_statistics", "params": [7], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": [[1732060800, 1440, 3021, 6550], ...], "id": 1}
+    pub async fn statistics_get_daily_statistics(&self, params: &JsonValue) -> Result<JsonValue> {
+        // Extract the number of days to fetch
+        let days = parse_json_array_number("days", 0, params)? as usize;
+        if days == 0 || days > MAX_PAGE_SIZE {
+            return Err(RpcError::InvalidJson(format!(
+                "Invalid number of days: {days}, must be between 1 and {MAX_PAGE_SIZE}"
+            ))
+            .into());
+        }
+
+        // Retrieve the daily statistics
+        let statistics = self.service.get_daily_statistics(days)?;
+
+        // Convert each daily statistic into a JSON array, returning the collected array
+        Ok(JsonValue::Array(statistics.iter().map(|s| s.to_json_array()).collect()))
+    }
 }
 
 #[cfg(test)]
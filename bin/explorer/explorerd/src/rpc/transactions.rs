@@ -19,9 +19,15 @@
 use tinyjson::JsonValue;
 
 use darkfi::{rpc::jsonrpc::parse_json_array_string, Result};
-use darkfi_sdk::tx::TransactionHash;
+use std::str::FromStr;
 
-use crate::{error::ExplorerdError, Explorerd};
+use darkfi_sdk::{crypto::ContractId, tx::TransactionHash};
+
+use crate::{
+    error::ExplorerdError,
+    rpc::{parse_pagination_params, to_paginated_json},
+    Explorerd,
+};
 
 impl Explorerd {
     // RPCAPI:
@@ -82,6 +88,46 @@ impl Explorerd {
             None => Ok(JsonValue::Array(vec![])),
         }
     }
+
+    // RPCAPI:
+    // Queries the database to retrieve a page of transactions calling the provided contract,
+    // most recent first. Returns the readable transactions of the page along with the total
+    // number of transactions calling the contract upon success.
+    //
+    // **Params:**
+    // * `array[0]`: `String` Contract ID
+    // * `array[1]`: `u64` Page number, starting from 0 for the latest transactions
+    // * `array[2]`: `u64` Page size, between 1 and 100
+    //
+    // **Returns:**
+    // * Object containing the `TransactionRecord` array as `items`, along with `page`,
+    //   `page_size` and `total`, encoded into a JSON.
+    //
+    // **Example API Usage:**
+    // --> {"jsonrpc": "2.0", "method": "transactions.get_transactions_by_contract_id", "params": ["BZH...m1a", 0, 20], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"items": [...], "page": 0, "page_size": 20, "total": 42}, "id": 1}
+    pub async fn transactions_get_transactions_by_contract_id(
+        &self,
+        params: &JsonValue,
+    ) -> Result<JsonValue> {
+        // Extract contract ID
+        let contract_id_str = parse_json_array_string("contract_id", 0, params)?;
+
+        // Convert the contract string to a `ContractId` instance
+        let contract_id = ContractId::from_str(&contract_id_str)
+            .map_err(|_| ExplorerdError::InvalidContractId(contract_id_str))?;
+
+        // Extract the pagination parameters
+        let (page, page_size) = parse_pagination_params(params, 1)?;
+
+        // Retrieve the transactions page calling the contract
+        let (transactions, total) =
+            self.service.get_transactions_by_contract_id(&contract_id, page, page_size)?;
+
+        // Convert transactions into a JSON array, return result
+        let json_txs = transactions.iter().map(|tx| tx.to_json_array()).collect();
+        Ok(to_paginated_json(json_txs, page, page_size, total))
+    }
 }
 
 #[cfg(test)]
@@ -90,8 +136,14 @@ impl Explorerd {
 /// including cases with missing values, unsupported types, and unparsable inputs.
 mod tests {
 
+    use tinyjson::JsonValue;
+
+    use darkfi::rpc::jsonrpc::ErrorCode;
+    use darkfi_sdk::crypto::MONEY_CONTRACT_ID;
+
     use crate::test_utils::{
-        setup, validate_invalid_rpc_header_hash, validate_invalid_rpc_tx_hash,
+        setup, validate_invalid_rpc_contract_id, validate_invalid_rpc_header_hash,
+        validate_invalid_rpc_parameter, validate_invalid_rpc_tx_hash,
     };
 
     #[test]
@@ -124,4 +176,44 @@ mod tests {
             validate_invalid_rpc_tx_hash(&explorerd, rpc_method);
         });
     }
+
+    #[test]
+    /// Tests the handling of invalid parameters for the `transactions.get_transactions_by_contract_id`
+    /// JSON-RPC method. Verifies that an invalid `contract_id` and out of range page sizes result in
+    /// an appropriate error.
+    fn test_transactions_get_transactions_by_contract_id() {
+        smol::block_on(async {
+            // Define the RPC method name
+            let rpc_method = "transactions.get_transactions_by_contract_id";
+
+            // Set up the explorerd
+            let explorerd = setup();
+
+            // Validate when provided with an invalid contract ID
+            validate_invalid_rpc_contract_id(&explorerd, rpc_method);
+
+            // Validate when the pagination parameters are missing
+            let contract_id = JsonValue::String(MONEY_CONTRACT_ID.to_string());
+            validate_invalid_rpc_parameter(
+                &explorerd,
+                rpc_method,
+                &[contract_id.clone()],
+                ErrorCode::InvalidParams.code(),
+                "Parameter 'page' at index 1 is missing",
+            )
+            .await;
+
+            // Validate when provided with an out of range page size
+            for page_size in [0.0, 101.0] {
+                validate_invalid_rpc_parameter(
+                    &explorerd,
+                    rpc_method,
+                    &[contract_id.clone(), JsonValue::Number(0.0), JsonValue::Number(page_size)],
+                    ErrorCode::InvalidParams.code(),
+                    &format!("Invalid page size: {page_size}, must be between 1 and 100"),
+                )
+                .await;
+            }
+        });
+    }
 }
@@ -36,7 +36,7 @@ use darkfi_sdk::{
 };
 use darkfi_serial::AsyncEncodable;
 
-use crate::{error::ExplorerdError, ExplorerService};
+use crate::{error::ExplorerdError, store::indexes::IndexedTx, ExplorerService};
 
 #[derive(Debug, Clone)]
 /// Structure representing a block record.
@@ -122,7 +122,7 @@ impl ExplorerService {
     /// latest [`GasMetrics`] for non-genesis blocks and for transactions that are not
     /// PoW rewards. PoW reward transactions update the contract runtime state as required.
     /// After processing all transactions, the block is permanently persisted to
-    /// the explorer database.
+    /// the explorer database and its transactions are added to the explorer indexes.
     pub async fn put_block(&self, block: &BlockInfo) -> Result<()> {
         let blockchain_overlay = BlockchainOverlay::new(&self.db.blockchain)?;
         let mut tree = MerkleTree::new(1);
@@ -201,6 +201,33 @@ impl ExplorerService {
         blockchain_overlay.lock().unwrap().overlay.lock().unwrap().apply()?;
         debug!(target: "explorerd::blocks::put_block", "Added block {block:?}");
 
+        // Index the block transactions. Blocks may be put again after a reorg,
+        // so only the ones above the indexed height get indexed.
+        self.index_blocks()?;
+
+        Ok(())
+    }
+
+    /// Indexes the blocks above the last indexed height, up to the last stored block.
+    /// The indexed height gets persisted along with each block indexes, so indexing
+    /// resumes from where it stopped if the explorer went down in between.
+    pub fn index_blocks(&self) -> Result<()> {
+        let block_store = &self.db.blockchain.blocks;
+        if block_store.is_empty() {
+            return Ok(())
+        }
+
+        let (last_height, _) = block_store.get_last()?;
+        let from = self.db.index_store.indexed_height()?.map_or(0, |h| h + 1);
+        for height in from..=last_height {
+            let block = &self.db.blockchain.get_blocks_by_heights(&[height])?[0];
+            self.db.index_store.insert_block(
+                height,
+                &block.header.timestamp,
+                &indexed_txs(block),
+            )?;
+        }
+
         Ok(())
     }
 
@@ -295,6 +322,34 @@ impl ExplorerService {
         Ok(block_records)
     }
 
+    /// Fetch a page of blocks from the database, most recent first, along with the total block count.
+    /// Page `0` contains the latest `page_size` blocks.
+    pub fn get_blocks_page(
+        &self,
+        page: usize,
+        page_size: usize,
+    ) -> Result<(Vec<BlockRecord>, usize)> {
+        let total = self.get_block_count();
+
+        // Return an empty page when no blocks exist or the page is out of range
+        let skip = page.saturating_mul(page_size);
+        if page_size == 0 || skip >= total {
+            return Ok((vec![], total));
+        }
+
+        // Block heights are contiguous, so the page maps to a heights range
+        let (last_height, _) = self.db.blockchain.blocks.get_last().map_err(|e| {
+            Error::DatabaseError(format!("[get_blocks_page] Block retrieval failed: {e:?}"))
+        })?;
+        let end = last_height - skip as u32;
+        let start = end.saturating_sub(page_size as u32 - 1);
+
+        let mut block_records = self.get_by_range(start, end)?;
+        block_records.reverse();
+
+        Ok((block_records, total))
+    }
+
     /// Resets the [`ExplorerDb::blockchain::blocks`] and [`ExplorerDb::blockchain::transactions`]
     /// trees to a specified height by removing entries above the `reset_height`, returning a result
     /// that indicates success or failure.
@@ -348,6 +403,19 @@ impl ExplorerService {
             })
             .collect();
 
+        // Remove the reset blocks from the explorer indexes
+        let indexed_height = self.db.index_store.indexed_height()?;
+        for block_info in block_infos_to_reset.iter().rev() {
+            if indexed_height.is_none_or(|h| block_info.header.height > h) {
+                continue
+            }
+            self.db.index_store.remove_block(
+                block_info.header.height,
+                &block_info.header.timestamp,
+                &indexed_txs(block_info),
+            )?;
+        }
+
         // Perform the reset operation atomically using a sled transaction
         let tx_result = (&block_store.main, &block_store.order, &block_store.difficulty, &tx_store.main, &tx_store.location)
             .transaction(|(block_main, block_order, block_difficulty, tx_main, tx_location)| {
@@ -393,3 +461,16 @@ impl ExplorerService {
         tx_result
     }
 }
+
+/// Auxiliary function to collect the transactions of a [`BlockInfo`] along with the
+/// contract IDs they call, in the form expected by the explorer index store.
+fn indexed_txs(block: &BlockInfo) -> Vec<IndexedTx> {
+    block
+        .txs
+        .iter()
+        .map(|tx| IndexedTx {
+            tx_hash: tx.hash(),
+            contract_ids: tx.calls.iter().map(|call| call.data.contract_id).collect(),
+        })
+        .collect()
+}
@@ -18,7 +18,7 @@
 
 use std::sync::Arc;
 
-use log::debug;
+use log::{debug, info};
 
 use darkfi::Result;
 
@@ -50,6 +50,7 @@ pub mod sync;
 /// - Contracts: Handling native and user contract data, source code, tar files, and metadata.
 /// - Metrics: Providing metric-related data over the life of the chain.
 /// - Transactions: Synchronization, calculating gas data, retrieval, counting, and related block information.
+/// - Indexes: Paging through blocks and contract calls, and daily chain activity statistics.
 pub struct ExplorerService {
     /// Explorer database instance
     pub db: ExplorerDb,
@@ -72,6 +73,22 @@ impl ExplorerService {
         self.deploy_native_contracts().await?;
         self.load_native_contract_sources()?;
         self.load_native_contract_metadata()?;
+        self.init_indexes()?;
+        Ok(())
+    }
+
+    /// Brings the explorer indexes up to date with the stored blocks. Databases without
+    /// an indexed height predate it and only had new blocks indexed, so their indexes
+    /// get rebuilt from genesis.
+    fn init_indexes(&self) -> Result<()> {
+        if self.db.index_store.indexed_height()?.is_none() {
+            self.db.index_store.reset()?;
+        }
+
+        info!(target: "explorerd::init_indexes", "Indexing stored blocks");
+        self.index_blocks()?;
+        info!(target: "explorerd::init_indexes", "Indexed blocks up to height {:?}", self.db.index_store.indexed_height()?);
+
         Ok(())
     }
 
@@ -89,6 +106,7 @@ impl ExplorerService {
             0 => {
                 self.reset_blocks()?;
                 self.reset_transactions()?;
+                self.db.index_store.reset()?;
                 debug!(target: "explorerd::reset_explorer_state", "Reset explorer state to accept a new genesis block");
             }
             // Reset for all other heights
@@ -21,7 +21,13 @@ use tinyjson::JsonValue;
 use darkfi::{Error, Result};
 use darkfi_sdk::blockchain::block_epoch;
 
-use crate::{service::ExplorerService, store::metrics::GasMetrics};
+use crate::{
+    service::ExplorerService,
+    store::{
+        indexes::{DailyStats, SECONDS_PER_DAY},
+        metrics::GasMetrics,
+    },
+};
 
 #[derive(Debug, Clone)]
 /// Structure representing basic statistic extracted from the database.
@@ -85,6 +91,19 @@ impl MetricStatistics {
         ])
     }
 }
+
+impl DailyStats {
+    /// Auxiliary function to convert [`DailyStats`] into a [`JsonValue`] array.
+    /// The day is converted back to the UNIX timestamp of its start.
+    pub fn to_json_array(&self) -> JsonValue {
+        JsonValue::Array(vec![
+            JsonValue::Number((self.day * SECONDS_PER_DAY) as f64),
+            JsonValue::Number(self.blocks as f64),
+            JsonValue::Number(self.txs as f64),
+            JsonValue::Number(self.contract_calls as f64),
+        ])
+    }
+}
 impl ExplorerService {
     /// Fetches the latest [`BaseStatistics`] from the explorer database, or returns `None` if no block exists.
     pub fn get_base_statistics(&self) -> Result<Option<BaseStatistics>> {
@@ -137,4 +156,13 @@ impl ExplorerService {
             None => Ok(MetricStatistics::default()),
         }
     }
+
+    /// Fetches the [`DailyStats`] of the last `n` days with recorded activity, most recent first.
+    pub fn get_daily_statistics(&self, n: usize) -> Result<Vec<DailyStats>> {
+        self.db.index_store.get_last_n_daily_stats(n).map_err(|e| {
+            Error::DatabaseError(format!(
+                "[get_daily_statistics] Retrieving daily statistics failed: {e:?}"
+            ))
+        })
+    }
 }
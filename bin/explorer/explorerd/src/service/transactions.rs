@@ -25,7 +25,7 @@ use tinyjson::JsonValue;
 use darkfi::{
     blockchain::{
         BlockInfo, BlockchainOverlay, HeaderHash, SLED_PENDING_TX_ORDER_TREE, SLED_PENDING_TX_TREE,
-        SLED_TX_LOCATION_TREE, SLED_TX_TREE,
+        SLED_TX_LOCATION_TREE, SLED_TX_METRICS_DAILY_TREE, SLED_TX_METRICS_TREE, SLED_TX_TREE,
     },
     error::TxVerifyFailed,
     runtime::vm_runtime::Runtime,
@@ -90,8 +90,14 @@ impl ExplorerService {
     /// Resets transactions in the database by clearing transaction-related trees, returning an Ok result on success.
     pub fn reset_transactions(&self) -> Result<()> {
         // Initialize transaction trees to reset
-        let trees_to_reset =
-            [SLED_TX_TREE, SLED_TX_LOCATION_TREE, SLED_PENDING_TX_TREE, SLED_PENDING_TX_ORDER_TREE];
+        let trees_to_reset = [
+            SLED_TX_TREE,
+            SLED_TX_LOCATION_TREE,
+            SLED_PENDING_TX_TREE,
+            SLED_PENDING_TX_ORDER_TREE,
+            SLED_TX_METRICS_TREE,
+            SLED_TX_METRICS_DAILY_TREE,
+        ];
 
         // Iterate over each associated transaction tree and delete its contents
         for tree_name in &trees_to_reset {
@@ -186,6 +192,42 @@ impl ExplorerService {
         tx_opt.as_ref().map(|tx| self.to_tx_record(None, tx)).transpose()
     }
 
+    /// Fetches a page of transactions calling the provided [`ContractId`], most recent first.
+    ///
+    /// This function looks up the transaction hashes in the explorer contract calls index
+    /// and transforms the corresponding transactions into [`TransactionRecord`]s. Along with
+    /// the records, it returns the total number of transactions calling the contract.
+    pub fn get_transactions_by_contract_id(
+        &self,
+        contract_id: &ContractId,
+        page: usize,
+        page_size: usize,
+    ) -> Result<(Vec<TransactionRecord>, usize)> {
+        // Fetch the page of transaction hashes from the index
+        let (tx_hashes, total) =
+            self.db.index_store.get_contract_calls(contract_id, page, page_size).map_err(|e| {
+                Error::DatabaseError(format!(
+                    "[get_transactions_by_contract_id] Contract calls retrieval failed: {e:?}"
+                ))
+            })?;
+
+        // Fetch the indexed transactions, which must exist in the transactions store
+        let txs = self.db.blockchain.transactions.get(&tx_hashes, true).map_err(|e| {
+            Error::DatabaseError(format!(
+                "[get_transactions_by_contract_id] Transactions retrieval failed: {e:?}"
+            ))
+        })?;
+
+        // Transform the found `Transactions` into a vector of `TransactionRecords`
+        let txs_records = txs
+            .iter()
+            .flatten()
+            .map(|tx| self.to_tx_record(None, tx))
+            .collect::<Result<Vec<TransactionRecord>>>()?;
+
+        Ok((txs_records, total))
+    }
+
     /// Fetches the [`BlockInfo`] associated with a given transaction hash.
     ///
     /// This auxiliary function first fetches the location of the transaction in the blockchain.
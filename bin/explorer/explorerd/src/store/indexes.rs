@@ -0,0 +1,486 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use log::debug;
+use sled_overlay::sled::{
+    self,
+    transaction::{ConflictableTransactionError, TransactionalTree},
+    Transactional,
+};
+
+use darkfi::{util::time::Timestamp, Error, Result};
+use darkfi_sdk::{crypto::ContractId, tx::TransactionHash};
+use darkfi_serial::{async_trait, deserialize, serialize, SerialDecodable, SerialEncodable};
+
+/// Contract calls tree name.
+pub const SLED_CONTRACT_CALLS_TREE: &[u8] = b"_contract_calls";
+
+/// Contract calls counters tree name.
+pub const SLED_CONTRACT_CALL_COUNTS_TREE: &[u8] = b"_contract_call_counts";
+
+/// Daily statistics tree name.
+pub const SLED_DAILY_STATS_TREE: &[u8] = b"_daily_stats";
+
+/// Index info tree name.
+pub const SLED_INDEX_INFO_TREE: &[u8] = b"_index_info";
+
+/// Key of the last indexed block height in the index info tree.
+pub const INDEXED_HEIGHT_KEY: &[u8] = b"indexed_height";
+
+/// Number of seconds in a day, used to bucket the daily statistics.
+pub const SECONDS_PER_DAY: u64 = 86400;
+
+#[derive(Debug, Clone, Default, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+/// Represents the chain activity accumulated within a single UTC day.
+pub struct DailyStats {
+    /// Day index, the number of days since the UNIX epoch
+    pub day: u64,
+    /// Number of blocks added within the day
+    pub blocks: u64,
+    /// Number of transactions added within the day
+    pub txs: u64,
+    /// Number of contract calls added within the day
+    pub contract_calls: u64,
+}
+
+/// A transaction as seen by the [`IndexStore`]. Its index within the block is given by
+/// its position in the slice passed to the store.
+pub struct IndexedTx {
+    /// Transaction hash
+    pub tx_hash: TransactionHash,
+    /// Contract IDs of every call in the transaction, in call order
+    pub contract_ids: Vec<ContractId>,
+}
+
+/// The `IndexStore` maintains the secondary indexes the explorer needs on top of the
+/// blockchain data, so they can be served without scanning every block.
+///
+/// It organizes data into four sled trees: one mapping contract IDs to the transactions
+/// calling them, numbered in chronological order, one counting the indexed transactions
+/// of each contract, one accumulating activity statistics per day, and one recording
+/// the last indexed block height. Blocks get indexed in ascending height order and
+/// removed from the top, so the numbering of each contract stays contiguous and
+/// pages map to key ranges.
+#[derive(Clone)]
+pub struct IndexStore {
+    /// Sled tree for indexing contract calls, utilizing `contract_id || seq` as keys and
+    /// `height || tx_index || tx_hash` as values, where `seq` is the big endian number of
+    /// transactions calling the contract before this one.
+    pub contract_calls: sled::Tree,
+
+    /// Sled tree for counting contract calls, utilizing contract IDs as keys and the big
+    /// endian number of indexed transactions calling them as values.
+    pub contract_call_counts: sled::Tree,
+
+    /// Sled tree for storing daily statistics, utilizing the big endian day index as keys
+    /// and serialized [`DailyStats`] as values.
+    pub daily_stats: sled::Tree,
+
+    /// Sled tree for storing index info, like the last indexed block height.
+    pub info: sled::Tree,
+}
+
+impl IndexStore {
+    /// Creates an [`IndexStore`] instance by opening the necessary trees in the provided sled database [`Db`]
+    pub fn new(db: &sled::Db) -> Result<Self> {
+        let contract_calls = db.open_tree(SLED_CONTRACT_CALLS_TREE)?;
+        let contract_call_counts = db.open_tree(SLED_CONTRACT_CALL_COUNTS_TREE)?;
+        let daily_stats = db.open_tree(SLED_DAILY_STATS_TREE)?;
+        let info = db.open_tree(SLED_INDEX_INFO_TREE)?;
+
+        Ok(Self { contract_calls, contract_call_counts, daily_stats, info })
+    }
+
+    /// Fetches the height of the last indexed block, if any.
+    pub fn indexed_height(&self) -> Result<Option<u32>> {
+        match self.info.get(INDEXED_HEIGHT_KEY)? {
+            Some(bytes) => {
+                Ok(Some(u32::from_be_bytes(bytes.as_ref().try_into().map_err(|_| {
+                    Error::DatabaseError("[indexed_height] Invalid height bytes".to_string())
+                })?)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Indexes the transactions of a block at the given `height` and [`Timestamp`].
+    /// Blocks must be indexed in ascending height order, right after the last indexed one.
+    /// All indexes and the indexed height are updated atomically.
+    pub fn insert_block(
+        &self,
+        height: u32,
+        timestamp: &Timestamp,
+        txs: &[IndexedTx],
+    ) -> Result<()> {
+        let expected = self.indexed_height()?.map_or(0, |h| h + 1);
+        if height != expected {
+            return Err(Error::DatabaseError(format!(
+                "[insert_block] Expected block {expected} to be indexed next, got {height}"
+            )))
+        }
+
+        let entries = contract_call_entries(txs);
+        let mut stats = self.get_daily_stats(timestamp)?;
+        stats.blocks += 1;
+        stats.txs += txs.len() as u64;
+        stats.contract_calls += txs.iter().map(|tx| tx.contract_ids.len() as u64).sum::<u64>();
+
+        self.transaction(|calls, counts, daily_stats, info| {
+            for (contract_id, tx_index, tx_hash) in &entries {
+                let count = get_count(counts, contract_id)?;
+                calls.insert(
+                    contract_call_key(contract_id, count),
+                    contract_call_value(height, *tx_index, tx_hash),
+                )?;
+                counts
+                    .insert(contract_id.to_bytes().to_vec(), (count + 1).to_be_bytes().to_vec())?;
+            }
+            daily_stats.insert(stats.day.to_be_bytes().to_vec(), serialize(&stats))?;
+            info.insert(INDEXED_HEIGHT_KEY, height.to_be_bytes().to_vec())?;
+            Ok(())
+        })?;
+        debug!(target: "explorerd::index_store::insert_block", "Indexed {} txs at height {height}", txs.len());
+
+        Ok(())
+    }
+
+    /// Reverts the indexing of the last block added with [`IndexStore::insert_block`],
+    /// used when the explorer rolls back blocks after a reorg.
+    pub fn remove_block(
+        &self,
+        height: u32,
+        timestamp: &Timestamp,
+        txs: &[IndexedTx],
+    ) -> Result<()> {
+        if self.indexed_height()? != Some(height) {
+            return Err(Error::DatabaseError(format!(
+                "[remove_block] Block {height} is not the last indexed one"
+            )))
+        }
+
+        let entries = contract_call_entries(txs);
+        let mut stats = self.get_daily_stats(timestamp)?;
+        stats.blocks = stats.blocks.saturating_sub(1);
+        stats.txs = stats.txs.saturating_sub(txs.len() as u64);
+        stats.contract_calls = stats
+            .contract_calls
+            .saturating_sub(txs.iter().map(|tx| tx.contract_ids.len() as u64).sum::<u64>());
+
+        self.transaction(|calls, counts, daily_stats, info| {
+            for (contract_id, _, _) in entries.iter().rev() {
+                let count = get_count(counts, contract_id)?.saturating_sub(1);
+                calls.remove(contract_call_key(contract_id, count))?;
+                if count == 0 {
+                    counts.remove(contract_id.to_bytes().to_vec())?;
+                } else {
+                    counts.insert(contract_id.to_bytes().to_vec(), count.to_be_bytes().to_vec())?;
+                }
+            }
+            daily_stats.insert(stats.day.to_be_bytes().to_vec(), serialize(&stats))?;
+            match height.checked_sub(1) {
+                Some(previous) => {
+                    info.insert(INDEXED_HEIGHT_KEY, previous.to_be_bytes().to_vec())?
+                }
+                None => info.remove(INDEXED_HEIGHT_KEY)?,
+            };
+            Ok(())
+        })?;
+        debug!(target: "explorerd::index_store::remove_block", "Removed {} indexed txs at height {height}", txs.len());
+
+        Ok(())
+    }
+
+    /// Fetches a page of transaction hashes calling the provided [`ContractId`], most recent first.
+    /// Returns the hashes along with the total number of indexed transactions for the contract.
+    pub fn get_contract_calls(
+        &self,
+        contract_id: &ContractId,
+        page: usize,
+        page_size: usize,
+    ) -> Result<(Vec<TransactionHash>, usize)> {
+        let total = match self.contract_call_counts.get(contract_id.to_bytes())? {
+            Some(bytes) => parse_count(&bytes)?,
+            None => 0,
+        };
+
+        // Pages map to a range of sequence numbers
+        let skip = (page as u64).saturating_mul(page_size as u64);
+        if page_size == 0 || skip >= total {
+            return Ok((vec![], total as usize))
+        }
+        let end = total - skip;
+        let start = end.saturating_sub(page_size as u64);
+
+        let mut hashes = Vec::with_capacity(page_size);
+        let range = contract_call_key(contract_id, start)..contract_call_key(contract_id, end);
+        for entry in self.contract_calls.range(range).rev() {
+            let (_, value) = entry?;
+            let bytes: [u8; 32] =
+                value.get(6..).and_then(|b| b.try_into().ok()).ok_or_else(|| {
+                    Error::DatabaseError("[get_contract_calls] Invalid tx hash bytes".to_string())
+                })?;
+            hashes.push(TransactionHash(bytes));
+        }
+
+        Ok((hashes, total as usize))
+    }
+
+    /// Fetches the [`DailyStats`] of the day the provided [`Timestamp`] falls in,
+    /// returning empty statistics if nothing was recorded that day.
+    pub fn get_daily_stats(&self, timestamp: &Timestamp) -> Result<DailyStats> {
+        let day = timestamp.inner() / SECONDS_PER_DAY;
+        match self.daily_stats.get(day.to_be_bytes())? {
+            Some(bytes) => deserialize(&bytes).map_err(Error::from),
+            None => Ok(DailyStats { day, ..Default::default() }),
+        }
+    }
+
+    /// Fetches the last `n` recorded [`DailyStats`], most recent first.
+    pub fn get_last_n_daily_stats(&self, n: usize) -> Result<Vec<DailyStats>> {
+        self.daily_stats
+            .iter()
+            .rev()
+            .take(n)
+            .map(|iter_result| match iter_result {
+                Ok((_, stats_bytes)) => deserialize(&stats_bytes).map_err(Error::from),
+                Err(e) => Err(Error::from(e)),
+            })
+            .collect()
+    }
+
+    /// Clears all indexes, used when the explorer resets to accept a new genesis block
+    /// or has to index its blocks from scratch.
+    pub fn reset(&self) -> Result<()> {
+        self.contract_calls.clear()?;
+        self.contract_call_counts.clear()?;
+        self.daily_stats.clear()?;
+        self.info.clear()?;
+        Ok(())
+    }
+
+    /// Auxiliary function to atomically apply changes across the index trees.
+    fn transaction(
+        &self,
+        f: impl Fn(
+            &TransactionalTree,
+            &TransactionalTree,
+            &TransactionalTree,
+            &TransactionalTree,
+        ) -> std::result::Result<(), ConflictableTransactionError<Error>>,
+    ) -> Result<()> {
+        (&self.contract_calls, &self.contract_call_counts, &self.daily_stats, &self.info)
+            .transaction(|(calls, counts, daily_stats, info)| f(calls, counts, daily_stats, info))
+            .map_err(|e| Error::DatabaseError(format!("[IndexStore] Transaction failed: {e:?}")))
+    }
+}
+
+/// Auxiliary function to list the contract calls of the provided transactions, as
+/// `(contract_id, tx_index, tx_hash)` entries in chronological order. Multiple calls
+/// of a transaction to the same contract map to a single entry.
+fn contract_call_entries(txs: &[IndexedTx]) -> Vec<(ContractId, u16, TransactionHash)> {
+    let mut entries = vec![];
+    for (tx_index, tx) in txs.iter().enumerate() {
+        let mut seen = vec![];
+        for contract_id in &tx.contract_ids {
+            if seen.contains(contract_id) {
+                continue
+            }
+            seen.push(*contract_id);
+            entries.push((*contract_id, tx_index as u16, tx.tx_hash));
+        }
+    }
+    entries
+}
+
+/// Auxiliary function to parse a big endian contract calls counter.
+fn parse_count(bytes: &[u8]) -> Result<u64> {
+    Ok(u64::from_be_bytes(bytes.try_into().map_err(|_| {
+        Error::DatabaseError("[IndexStore] Invalid contract calls count bytes".to_string())
+    })?))
+}
+
+/// Auxiliary function to read a contract calls counter within a transaction.
+fn get_count(
+    counts: &TransactionalTree,
+    contract_id: &ContractId,
+) -> std::result::Result<u64, ConflictableTransactionError<Error>> {
+    match counts.get(contract_id.to_bytes())? {
+        Some(bytes) => parse_count(&bytes).map_err(ConflictableTransactionError::Abort),
+        None => Ok(0),
+    }
+}
+
+/// Auxiliary function to build a contract calls tree key. Sequence numbers are stored
+/// big endian so that keys sharing a contract ID prefix are sorted chronologically.
+fn contract_call_key(contract_id: &ContractId, seq: u64) -> Vec<u8> {
+    let mut key = Vec::with_capacity(40);
+    key.extend_from_slice(&contract_id.to_bytes());
+    key.extend_from_slice(&seq.to_be_bytes());
+    key
+}
+
+/// Auxiliary function to build a contract calls tree value.
+fn contract_call_value(height: u32, tx_index: u16, tx_hash: &TransactionHash) -> Vec<u8> {
+    let mut value = Vec::with_capacity(38);
+    value.extend_from_slice(&height.to_be_bytes());
+    value.extend_from_slice(&tx_index.to_be_bytes());
+    value.extend_from_slice(tx_hash.inner());
+    value
+}
+
+#[cfg(test)]
+/// Tests for the explorer [`IndexStore`], covering contract call pagination,
+/// daily statistics accumulation and block removal.
+mod tests {
+    use darkfi_sdk::crypto::{DAO_CONTRACT_ID, MONEY_CONTRACT_ID};
+
+    use super::*;
+    use crate::test_utils::init_logger;
+
+    /// Fixed timestamp in seconds since UNIX epoch.
+    const FIXED_TIMESTAMP: u64 = 1732042800;
+
+    /// Number of heights to simulate.
+    const HEIGHT: u32 = 10;
+
+    /// Tests inserting blocks and paging through the indexed contract calls, most recent first.
+    #[test]
+    fn test_contract_calls_pagination() -> Result<()> {
+        let store = setup()?;
+        load_blocks(&store)?;
+
+        // Every block calls Money, every even block also calls DAO
+        let (first_page, total) = store.get_contract_calls(&MONEY_CONTRACT_ID, 0, 4)?;
+        assert_eq!(total, HEIGHT as usize);
+        assert_eq!(first_page, vec![tx_hash(9), tx_hash(8), tx_hash(7), tx_hash(6)]);
+
+        let (last_page, _) = store.get_contract_calls(&MONEY_CONTRACT_ID, 2, 4)?;
+        assert_eq!(last_page, vec![tx_hash(1), tx_hash(0)]);
+
+        let (dao_page, dao_total) = store.get_contract_calls(&DAO_CONTRACT_ID, 0, 10)?;
+        assert_eq!(dao_total, 5);
+        assert_eq!(dao_page, vec![tx_hash(8), tx_hash(6), tx_hash(4), tx_hash(2), tx_hash(0)]);
+
+        // Pages past the end are empty
+        let (empty_page, total) = store.get_contract_calls(&MONEY_CONTRACT_ID, 3, 4)?;
+        assert!(empty_page.is_empty());
+        assert_eq!(total, HEIGHT as usize);
+
+        Ok(())
+    }
+
+    /// Tests the indexed height marker, which only allows indexing the next block
+    /// and removing the last one.
+    #[test]
+    fn test_indexed_height() -> Result<()> {
+        let store = setup()?;
+        let timestamp = Timestamp::from_u64(FIXED_TIMESTAMP);
+        assert_eq!(store.indexed_height()?, None);
+        assert!(store.insert_block(1, &timestamp, &[indexed_tx(1)]).is_err());
+
+        load_blocks(&store)?;
+        assert_eq!(store.indexed_height()?, Some(HEIGHT - 1));
+        assert!(store.insert_block(HEIGHT - 1, &timestamp, &[indexed_tx(HEIGHT - 1)]).is_err());
+        assert!(store.remove_block(HEIGHT - 2, &timestamp, &[indexed_tx(HEIGHT - 2)]).is_err());
+
+        // Multiple calls to the same contract within a transaction are indexed once
+        let tx = || IndexedTx {
+            tx_hash: tx_hash(HEIGHT),
+            contract_ids: vec![*MONEY_CONTRACT_ID, *MONEY_CONTRACT_ID],
+        };
+        store.insert_block(HEIGHT, &timestamp, &[tx()])?;
+        assert_eq!(store.indexed_height()?, Some(HEIGHT));
+        assert_eq!(
+            store.get_contract_calls(&MONEY_CONTRACT_ID, 0, 2)?,
+            (vec![tx_hash(HEIGHT), tx_hash(HEIGHT - 1)], HEIGHT as usize + 1)
+        );
+
+        // Removing every block clears the marker and the counters
+        store.remove_block(HEIGHT, &timestamp, &[tx()])?;
+        for height in (0..HEIGHT).rev() {
+            store.remove_block(height, &timestamp, &[indexed_tx(height)])?;
+        }
+        assert_eq!(store.indexed_height()?, None);
+        assert!(store.contract_calls.is_empty());
+        assert!(store.contract_call_counts.is_empty());
+
+        Ok(())
+    }
+
+    /// Tests daily statistics are accumulated on insert and reverted on removal.
+    #[test]
+    fn test_daily_stats() -> Result<()> {
+        let store = setup()?;
+        load_blocks(&store)?;
+
+        let timestamp = Timestamp::from_u64(FIXED_TIMESTAMP);
+        let stats = store.get_daily_stats(&timestamp)?;
+        assert_eq!(stats.day, FIXED_TIMESTAMP / SECONDS_PER_DAY);
+        assert_eq!(stats.blocks, HEIGHT as u64);
+        assert_eq!(stats.txs, HEIGHT as u64);
+        assert_eq!(stats.contract_calls, 15);
+        assert_eq!(store.get_last_n_daily_stats(5)?, vec![stats]);
+
+        // Roll back the last block, which only called Money
+        let last = HEIGHT - 1;
+        store.remove_block(last, &timestamp, &[indexed_tx(last)])?;
+        let stats = store.get_daily_stats(&timestamp)?;
+        assert_eq!(stats.blocks, HEIGHT as u64 - 1);
+        assert_eq!(stats.contract_calls, 14);
+        assert_eq!(store.get_contract_calls(&MONEY_CONTRACT_ID, 0, 1)?.0, vec![tx_hash(8)]);
+
+        store.reset()?;
+        assert!(store.get_last_n_daily_stats(5)?.is_empty());
+
+        Ok(())
+    }
+
+    /// Sets up a test case by initializing the logger and returning an index store
+    /// backed by a temporary database.
+    fn setup() -> Result<IndexStore> {
+        init_logger(simplelog::LevelFilter::Off, vec!["sled", "runtime", "net"]);
+
+        let db =
+            sled::Config::new().temporary(true).open().expect("Unable to open test sled database");
+
+        IndexStore::new(&db)
+    }
+
+    /// Loads [`HEIGHT`] blocks containing a single transaction each, all within the same day.
+    fn load_blocks(store: &IndexStore) -> Result<()> {
+        for height in 0..HEIGHT {
+            let timestamp = Timestamp::from_u64(FIXED_TIMESTAMP + height as u64 * 60);
+            store.insert_block(height, &timestamp, &[indexed_tx(height)])?;
+        }
+        Ok(())
+    }
+
+    /// Creates a test transaction for `height`, calling Money and, on even heights, DAO.
+    fn indexed_tx(height: u32) -> IndexedTx {
+        let mut contract_ids = vec![*MONEY_CONTRACT_ID];
+        if height % 2 == 0 {
+            contract_ids.push(*DAO_CONTRACT_ID);
+        }
+        IndexedTx { tx_hash: tx_hash(height), contract_ids }
+    }
+
+    /// Creates a deterministic test transaction hash for `height`.
+    fn tx_hash(height: u32) -> TransactionHash {
+        TransactionHash([height as u8; 32])
+    }
+}
@@ -26,7 +26,9 @@ use darkfi::{blockchain::Blockchain, error::Result, util::path::expand_path};
 
 use darkfi_sdk::crypto::{DAO_CONTRACT_ID, DEPLOYOOOR_CONTRACT_ID, MONEY_CONTRACT_ID};
 
-use crate::store::{contract_metadata::ContractMetaStore, metrics::MetricsStore};
+use crate::store::{
+    contract_metadata::ContractMetaStore, indexes::IndexStore, metrics::MetricsStore,
+};
 
 /// Stores, manages, and provides access to explorer metrics
 pub mod metrics;
@@ -34,6 +36,9 @@ pub mod metrics;
 /// Stores, manages, and provides access to contract metadata
 pub mod contract_metadata;
 
+/// Maintains contract call indexes and daily statistics
+pub mod indexes;
+
 /// Represents the explorer database backed by a `sled` database connection, responsible for maintaining
 /// persistent state required for blockchain exploration. It serves as the core data layer for the Explorer application,
 /// storing and managing blockchain data, metrics, and contract-related information.
@@ -46,6 +51,8 @@ pub struct ExplorerDb {
     pub metrics_store: MetricsStore,
     /// Store for managing contract metadata, source code, and related data
     pub contract_meta_store: ContractMetaStore,
+    /// Store for contract call indexes and daily statistics
+    pub index_store: IndexStore,
 }
 
 impl ExplorerDb {
@@ -56,8 +63,9 @@ impl ExplorerDb {
         let blockchain = Blockchain::new(&sled_db)?;
         let metrics_store = MetricsStore::new(&sled_db)?;
         let contract_meta_store = ContractMetaStore::new(&sled_db)?;
+        let index_store = IndexStore::new(&sled_db)?;
         info!(target: "explorerd", "Initialized explorer database {}: block count: {}, tx count: {}", db_path.display(), blockchain.len(), blockchain.txs_len());
-        Ok(Self { sled_db, blockchain, metrics_store, contract_meta_store })
+        Ok(Self { sled_db, blockchain, metrics_store, contract_meta_store, index_store })
     }
 }
 
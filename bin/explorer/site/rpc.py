@@ -118,6 +118,14 @@ async def get_metric_statistics():
     """Retrieves metrics statistics."""
     return await query("statistics.get_metric_statistics", [])
 
+async def get_daily_statistics(days: int):
+    """Retrieves the daily statistics of the last given number of days."""
+    return await query("statistics.get_daily_statistics", [days])
+
+async def get_blocks_page(page: int, page_size: int):
+    """Retrieves a page of blocks, most recent first."""
+    return await query("blocks.get_blocks_page", [page, page_size])
+
 async def get_block(header_hash: str):
     """Retrieves block information for a given header hash."""
     return await query("blocks.get_block_by_hash", [header_hash])
@@ -131,6 +139,10 @@ async def get_transaction(transaction_hash: str):
     """Retrieves transaction information for a given transaction hash."""
     return await query("transactions.get_transaction_by_hash", [transaction_hash])
 
+async def get_contract_transactions(contract_id: str, page: int, page_size: int):
+    """Retrieves a page of transactions calling a given contract ID, most recent first."""
+    return await query("transactions.get_transactions_by_contract_id", [contract_id, page, page_size])
+
 async def get_native_contracts():
     """Retrieves native contracts."""
     return await query("contracts.get_native_contracts", [])
@@ -0,0 +1,244 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::{HashMap, VecDeque};
+
+use rand::{rngs::OsRng, RngCore};
+use smol::lock::Mutex;
+
+use darkfi_sdk::crypto::PublicKey;
+
+/// Maximum number of challenges outstanding at once
+pub const MAX_CHALLENGES: usize = 65536;
+
+/// Maximum number of challenges outstanding for a single IP
+pub const MAX_CHALLENGES_PER_IP: usize = 8;
+
+/// An issued challenge
+struct IssuedChallenge {
+    /// Address the challenge is bound to
+    address: PublicKey,
+    /// Timestamp the challenge expires at
+    expiry: u64,
+    /// IP the challenge was requested from
+    ip: Option<String>,
+}
+
+/// Issued challenges state
+#[derive(Default)]
+struct ChallengesState {
+    /// Issued challenges
+    issued: HashMap<[u8; 32], IssuedChallenge>,
+    /// Challenges in the order they were issued, along with their
+    /// expiry, so expired ones get pruned from the front
+    expiries: VecDeque<([u8; 32], u64)>,
+    /// Number of outstanding challenges per IP
+    per_ip: HashMap<String, usize>,
+}
+
+impl ChallengesState {
+    /// Remove a challenge, releasing its IP slot.
+    fn remove(&mut self, challenge: &[u8; 32]) -> Option<IssuedChallenge> {
+        let issued = self.issued.remove(challenge)?;
+        if let Some(ref ip) = issued.ip {
+            if let Some(count) = self.per_ip.get_mut(ip) {
+                *count -= 1;
+                if *count == 0 {
+                    self.per_ip.remove(ip);
+                }
+            }
+        }
+        Some(issued)
+    }
+
+    /// Remove all challenges expired at `now`.
+    fn prune(&mut self, now: u64) {
+        while let Some((challenge, expiry)) = self.expiries.front().copied() {
+            if expiry > now {
+                break
+            }
+            self.expiries.pop_front();
+            self.remove(&challenge);
+        }
+    }
+}
+
+/// Proof-of-work challenges handed out to airdrop requesters.
+///
+/// A challenge is solved by finding a `nonce` such that
+/// `blake3(challenge || address || nonce)` starts with `difficulty`
+/// zero bits, with the nonce encoded as little endian `u64`. Each
+/// challenge is bound to the address it was issued for, expires
+/// after `ttl` seconds, and can only be used once. The number of
+/// outstanding challenges is capped, both overall and per IP.
+pub struct PowChallenges {
+    /// Required number of leading zero bits
+    pub difficulty: u32,
+    /// Seconds a challenge stays valid
+    pub ttl: u64,
+    /// Issued challenges state
+    state: Mutex<ChallengesState>,
+}
+
+impl PowChallenges {
+    pub fn new(difficulty: u32, ttl: u64) -> Self {
+        Self { difficulty, ttl, state: Mutex::new(ChallengesState::default()) }
+    }
+
+    /// Issue a new challenge for the given address, requested from the
+    /// given IP at `now`. Returns `None` when too many challenges are
+    /// outstanding.
+    pub async fn issue(&self, address: &PublicKey, ip: Option<&str>, now: u64) -> Option<[u8; 32]> {
+        let mut state = self.state.lock().await;
+        state.prune(now);
+
+        // Consumed challenges stay queued until they expire, so
+        // their slots only free up then.
+        if state.expiries.len() >= MAX_CHALLENGES {
+            return None
+        }
+        if let Some(ip) = ip {
+            if state.per_ip.get(ip).is_some_and(|count| *count >= MAX_CHALLENGES_PER_IP) {
+                return None
+            }
+            *state.per_ip.entry(ip.to_string()).or_default() += 1;
+        }
+
+        let mut challenge = [0u8; 32];
+        OsRng.fill_bytes(&mut challenge);
+
+        let expiry = now + self.ttl;
+        let issued = IssuedChallenge { address: *address, expiry, ip: ip.map(String::from) };
+        state.issued.insert(challenge, issued);
+        state.expiries.push_back((challenge, expiry));
+
+        Some(challenge)
+    }
+
+    /// Verify a challenge solution for the given address at `now`.
+    /// The challenge is consumed when the solution is valid.
+    pub async fn verify(
+        &self,
+        challenge: &[u8; 32],
+        address: &PublicKey,
+        nonce: u64,
+        now: u64,
+    ) -> bool {
+        let mut state = self.state.lock().await;
+        let Some(issued) = state.issued.get(challenge) else { return false };
+        if issued.address != *address || issued.expiry <= now {
+            return false
+        }
+
+        if leading_zero_bits(&solution_hash(challenge, address, nonce)) < self.difficulty {
+            return false
+        }
+
+        state.remove(challenge);
+        true
+    }
+}
+
+/// Compute the hash a challenge solution is checked against.
+pub fn solution_hash(challenge: &[u8; 32], address: &PublicKey, nonce: u64) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(challenge);
+    hasher.update(&address.to_bytes());
+    hasher.update(&nonce.to_le_bytes());
+    *hasher.finalize().as_bytes()
+}
+
+/// Count the leading zero bits of a hash.
+fn leading_zero_bits(hash: &[u8; 32]) -> u32 {
+    let mut bits = 0;
+    for byte in hash {
+        bits += byte.leading_zeros();
+        if *byte != 0 {
+            break
+        }
+    }
+    bits
+}
+
+#[cfg(test)]
+mod tests {
+    use darkfi_sdk::crypto::Keypair;
+    use rand::rngs::OsRng;
+
+    use super::*;
+
+    #[test]
+    fn pow_challenges() {
+        smol::block_on(async {
+            let challenges = PowChallenges::new(8, 60);
+            let alice = Keypair::random(&mut OsRng).public;
+            let bob = Keypair::random(&mut OsRng).public;
+
+            let challenge = challenges.issue(&alice, None, 1000).await.unwrap();
+            let nonce = (0..)
+                .find(|n| leading_zero_bits(&solution_hash(&challenge, &alice, *n)) >= 8)
+                .unwrap();
+
+            // Bound to the address it was issued for, and expiring
+            assert!(!challenges.verify(&challenge, &bob, nonce, 1000).await);
+            assert!(!challenges.verify(&challenge, &alice, nonce, 1060).await);
+
+            // Single use
+            assert!(challenges.verify(&challenge, &alice, nonce, 1000).await);
+            assert!(!challenges.verify(&challenge, &alice, nonce, 1000).await);
+        });
+    }
+
+    #[test]
+    fn pow_challenges_limits() {
+        smol::block_on(async {
+            let challenges = PowChallenges::new(0, 60);
+            let alice = Keypair::random(&mut OsRng).public;
+
+            // Each IP can only hold so many outstanding challenges
+            let mut issued = vec![];
+            for _ in 0..MAX_CHALLENGES_PER_IP {
+                issued.push(challenges.issue(&alice, Some("1.2.3.4"), 1000).await.unwrap());
+            }
+            assert!(challenges.issue(&alice, Some("1.2.3.4"), 1000).await.is_none());
+            assert!(challenges.issue(&alice, Some("5.6.7.8"), 1000).await.is_some());
+
+            // Consuming a challenge frees its IP slot
+            assert!(challenges.verify(&issued[0], &alice, 0, 1000).await);
+            assert!(challenges.issue(&alice, Some("1.2.3.4"), 1000).await.is_some());
+            assert!(challenges.issue(&alice, Some("1.2.3.4"), 1000).await.is_none());
+
+            // And so does expiry, which prunes everything issued before
+            assert!(challenges.issue(&alice, Some("1.2.3.4"), 1060).await.is_some());
+            let state = challenges.state.lock().await;
+            assert_eq!(state.issued.len(), 1);
+            assert_eq!(state.expiries.len(), 1);
+            assert_eq!(state.per_ip.get("1.2.3.4"), Some(&1));
+            assert_eq!(state.per_ip.len(), 1);
+        });
+    }
+
+    #[test]
+    fn zero_bits() {
+        assert_eq!(leading_zero_bits(&[0xff; 32]), 0);
+        let mut hash = [0u8; 32];
+        hash[1] = 0x10;
+        assert_eq!(leading_zero_bits(&hash), 11);
+        assert_eq!(leading_zero_bits(&[0; 32]), 256);
+    }
+}
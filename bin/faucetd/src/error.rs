@@ -0,0 +1,80 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+pub use darkfi::rpc::jsonrpc::{server_error, server_error_with_details};
+use darkfi::rpc::jsonrpc::{ErrorCategory, ServerErrorKind};
+
+/// Custom RPC errors available for faucetd.
+/// Please sort them sensefully.
+#[derive(Copy, Clone, Debug)]
+pub enum RpcError {
+    // Validation errors
+    InvalidAddress = -32101,
+    InvalidAmount = -32102,
+    RateLimited = -32110,
+    ChallengeRequired = -32120,
+    ChallengeFailed = -32121,
+    ChallengeDisabled = -32122,
+
+    // Wallet errors
+    AirdropFailed = -32200,
+
+    // Network errors
+    BroadcastFailed = -32300,
+
+    // Internal errors
+    DatabaseError = -32400,
+}
+
+impl ServerErrorKind for RpcError {
+    fn code(&self) -> i32 {
+        *self as i32
+    }
+
+    fn message(&self) -> &'static str {
+        match self {
+            // Validation errors
+            Self::InvalidAddress => "Invalid recipient address",
+            Self::InvalidAmount => "Invalid airdrop amount",
+            Self::RateLimited => "Airdrop rate limit reached",
+            Self::ChallengeRequired => "Proof-of-work challenge solution required",
+            Self::ChallengeFailed => "Invalid proof-of-work challenge solution",
+            Self::ChallengeDisabled => "Proof-of-work challenges are disabled",
+            // Wallet errors
+            Self::AirdropFailed => "Failed building airdrop transaction",
+            // Network errors
+            Self::BroadcastFailed => "Failed broadcasting airdrop transaction",
+            // Internal errors
+            Self::DatabaseError => "Faucet database error",
+        }
+    }
+
+    fn category(&self) -> ErrorCategory {
+        match self {
+            Self::InvalidAddress |
+            Self::InvalidAmount |
+            Self::RateLimited |
+            Self::ChallengeRequired |
+            Self::ChallengeFailed |
+            Self::ChallengeDisabled => ErrorCategory::Validation,
+            Self::AirdropFailed => ErrorCategory::Wallet,
+            Self::BroadcastFailed => ErrorCategory::Net,
+            Self::DatabaseError => ErrorCategory::Internal,
+        }
+    }
+}
@@ -0,0 +1,524 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{collections::HashSet, str::FromStr, sync::Arc};
+
+use async_trait::async_trait;
+use log::{error, info, warn};
+use smol::{
+    lock::{Mutex, MutexGuard},
+    stream::StreamExt,
+    Executor,
+};
+use structopt::StructOpt;
+use structopt_toml::StructOptToml;
+use tinyjson::JsonValue;
+use url::Url;
+
+use darkfi::{
+    async_daemonize, cli_desc,
+    rpc::{
+        client::auth_token,
+        jsonrpc::{ErrorCode, JsonError, JsonRequest, JsonResponse, JsonResult},
+        server::{listen_and_serve, RequestHandler},
+        settings::{PermissionClass, RpcSettings, RpcSettingsOpt},
+        util::{json_map, JsonNum, JsonStr},
+    },
+    system::{StoppableTask, StoppableTaskPtr},
+    util::{
+        encoding::base64,
+        parse::{decode_base10, encode_base10},
+        path::expand_path,
+        time::Timestamp,
+    },
+    Error, Result,
+};
+use darkfi_money_contract::model::{TokenId, DARK_TOKEN_ID};
+use darkfi_sdk::crypto::PublicKey;
+use drk::{money::BALANCE_BASE10_DECIMALS, walletdb::derive_wallet_key, Drk};
+
+/// Proof-of-work airdrop challenges
+mod challenge;
+use challenge::PowChallenges;
+
+/// Custom RPC errors
+mod error;
+use error::{server_error, server_error_with_details, RpcError};
+
+/// Persistent airdrop accounting
+mod store;
+use store::AirdropStore;
+
+const CONFIG_FILE: &str = "faucetd_config.toml";
+const CONFIG_FILE_CONTENTS: &str = include_str!("../faucetd_config.toml");
+
+/// JSON-RPC methods anyone can call. Everything else, like
+/// `log.set_level`, needs an `admin` token.
+const PUBLIC_METHODS: [&str; 4] = ["ping", "airdrop", "airdrop.challenge", "airdrop.info"];
+
+/// Cookie the `admin` token gets written to when none is configured
+const DEFAULT_AUTH_COOKIE: &str = "~/.local/share/darkfi/faucetd/rpc.cookie";
+
+#[derive(Clone, Debug, serde::Deserialize, StructOpt, StructOptToml)]
+#[serde(default)]
+#[structopt(name = "faucetd", about = cli_desc!())]
+struct Args {
+    #[structopt(flatten)]
+    /// JSON-RPC settings
+    rpc: RpcSettingsOpt,
+
+    #[structopt(short, long)]
+    /// Configuration file to use
+    config: Option<String>,
+
+    #[structopt(short, long)]
+    /// Set log file to ouput into
+    log: Option<String>,
+
+    #[structopt(long)]
+    /// Serve Prometheus metrics on this URL, e.g. tcp://127.0.0.1:9100
+    metrics_listen: Option<String>,
+
+    #[structopt(short, parse(from_occurrences))]
+    /// Increase verbosity (-vvv supported)
+    verbose: u8,
+
+    #[structopt(long, default_value = "~/.local/share/darkfi/faucetd/airdrops")]
+    /// Path to the airdrop accounting database
+    database: String,
+
+    #[structopt(long, default_value = "~/.local/share/darkfi/faucetd/wallet.db")]
+    /// Path to the faucet wallet database
+    wallet_path: String,
+
+    #[structopt(long, default_value = "changeme")]
+    /// Password for the faucet wallet database
+    wallet_pass: String,
+
+    #[structopt(short, long, default_value = "tcp://127.0.0.1:8240")]
+    /// darkfid JSON-RPC endpoint
+    endpoint: Url,
+
+    #[structopt(long)]
+    /// darkfid JSON-RPC authentication token
+    endpoint_token: Option<String>,
+
+    #[structopt(long)]
+    /// Path to the darkfid JSON-RPC authentication cookie,
+    /// used when no token is configured
+    endpoint_cookie: Option<String>,
+
+    #[structopt(long)]
+    /// Token ID to airdrop, defaults to the native token
+    token: Option<String>,
+
+    #[structopt(long, default_value = "10")]
+    /// Maximum amount of a single airdrop
+    max_amount: String,
+
+    #[structopt(long, default_value = "3600")]
+    /// Seconds an address has to wait between airdrops
+    address_cooldown: u64,
+
+    #[structopt(long, default_value = "600")]
+    /// Seconds an IP has to wait between airdrop requests
+    ip_cooldown: u64,
+
+    #[structopt(long, default_value = "0")]
+    /// Leading zero bits required in proof-of-work solutions, 0 disables them
+    pow_difficulty: u32,
+
+    #[structopt(long, default_value = "300")]
+    /// Seconds a proof-of-work challenge stays valid
+    pow_ttl: u64,
+}
+
+/// Daemon state
+struct Faucetd {
+    /// Faucet wallet
+    drk: Drk,
+    /// Airdrop accounting
+    store: AirdropStore,
+    /// Proof-of-work challenges, if enabled
+    challenges: Option<PowChallenges>,
+    /// Token ID to airdrop
+    token_id: TokenId,
+    /// Maximum amount of a single airdrop
+    max_amount: u64,
+    /// Seconds an address has to wait between airdrops
+    address_cooldown: u64,
+    /// Seconds an IP has to wait between airdrop requests
+    ip_cooldown: u64,
+    /// Airdrops spend wallet coins, so they are made one at a time
+    airdrop_lock: Mutex<()>,
+    /// JSON-RPC connection tracker
+    rpc_connections: Mutex<HashSet<StoppableTaskPtr>>,
+}
+
+impl Faucetd {
+    // RPCAPI:
+    // Returns the faucet address, the token it airdrops and its limits.
+    //
+    // --> {"jsonrpc": "2.0", "method": "airdrop.info", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"address": "...", "token_id": "...", "max_amount": "10", "address_cooldown": 3600, "ip_cooldown": 600, "pow_difficulty": 0}, "id": 1}
+    async fn airdrop_info(&self, id: u16, params: JsonValue) -> JsonResult {
+        if !params.get::<Vec<JsonValue>>().is_some_and(|p| p.is_empty()) {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let address = match self.drk.default_address().await {
+            Ok(a) => a,
+            Err(e) => {
+                error!(target: "faucetd::rpc", "[airdrop_info] Failed fetching faucet address: {e}");
+                return JsonError::new(ErrorCode::InternalError, None, id).into()
+            }
+        };
+
+        let pow_difficulty = self.challenges.as_ref().map_or(0, |c| c.difficulty);
+        let info = json_map([
+            ("address", JsonStr(address.to_string())),
+            ("token_id", JsonStr(self.token_id.to_string())),
+            ("max_amount", JsonStr(encode_base10(self.max_amount, BALANCE_BASE10_DECIMALS))),
+            ("address_cooldown", JsonNum(self.address_cooldown as f64)),
+            ("ip_cooldown", JsonNum(self.ip_cooldown as f64)),
+            ("pow_difficulty", JsonNum(pow_difficulty as f64)),
+        ]);
+
+        JsonResponse::new(info, id).into()
+    }
+
+    // RPCAPI:
+    // Issues a proof-of-work challenge for the given address. A solution
+    // is a `u64` nonce such that `blake3(challenge || address || nonce)`
+    // starts with `difficulty` zero bits, where the address is the
+    // 32 bytes public key and the nonce is encoded as little endian.
+    // The challenge is returned base64 encoded. Each IP can only hold a
+    // few unsolved challenges at once.
+    //
+    // --> {"jsonrpc": "2.0", "method": "airdrop.challenge", "params": ["address"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": ["challenge", 20], "id": 1}
+    async fn airdrop_challenge(&self, id: u16, params: JsonValue, ip: Option<&str>) -> JsonResult {
+        let Some(params) = params.get::<Vec<JsonValue>>() else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let Some(ref challenges) = self.challenges else {
+            return server_error(RpcError::ChallengeDisabled, id, None)
+        };
+
+        let Ok(address) = PublicKey::from_str(params[0].get::<String>().unwrap()) else {
+            return server_error(RpcError::InvalidAddress, id, None)
+        };
+
+        let now = Timestamp::current_time().inner();
+        let Some(challenge) = challenges.issue(&address, ip, now).await else {
+            return server_error(RpcError::RateLimited, id, None)
+        };
+
+        JsonResponse::new(
+            JsonValue::Array(vec![
+                JsonStr(base64::encode(&challenge)),
+                JsonNum(challenges.difficulty as f64),
+            ]),
+            id,
+        )
+        .into()
+    }
+
+    // RPCAPI:
+    // Airdrops the given amount of the faucet token to an address, and
+    // returns the hash of the broadcasted transaction. Each address and
+    // each requesting IP can only be airdropped once per cooldown. When
+    // proof-of-work is enabled, a solved challenge from `airdrop.challenge`
+    // and its nonce, as a string, must be provided. Rate limited requests
+    // carry the seconds to wait as `retry_after` in the error data.
+    //
+    // --> {"jsonrpc": "2.0", "method": "airdrop", "params": ["address", "1.5"], "id": 1}
+    // --> {"jsonrpc": "2.0", "method": "airdrop", "params": ["address", "1.5", "challenge", "nonce"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": "txid", "id": 1}
+    async fn airdrop(&self, id: u16, params: JsonValue, ip: Option<&str>) -> JsonResult {
+        let Some(params) = params.get::<Vec<JsonValue>>() else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+        if (params.len() != 2 && params.len() != 4) || params.iter().any(|p| !p.is_string()) {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let Ok(address) = PublicKey::from_str(params[0].get::<String>().unwrap()) else {
+            return server_error(RpcError::InvalidAddress, id, None)
+        };
+
+        let amount_str = params[1].get::<String>().unwrap();
+        let amount = match decode_base10(amount_str, BALANCE_BASE10_DECIMALS, false) {
+            Ok(v) if v > 0 && v <= self.max_amount => v,
+            _ => return server_error(RpcError::InvalidAmount, id, None),
+        };
+
+        let now = Timestamp::current_time().inner();
+
+        if let Some(ref challenges) = self.challenges {
+            if params.len() != 4 {
+                return server_error(RpcError::ChallengeRequired, id, None)
+            }
+
+            let challenge: Option<[u8; 32]> =
+                base64::decode(params[2].get::<String>().unwrap()).and_then(|c| c.try_into().ok());
+            let nonce = params[3].get::<String>().unwrap().parse::<u64>();
+            let (Some(challenge), Ok(nonce)) = (challenge, nonce) else {
+                return server_error(RpcError::ChallengeFailed, id, None)
+            };
+
+            if !challenges.verify(&challenge, &address, nonce, now).await {
+                return server_error(RpcError::ChallengeFailed, id, None)
+            }
+        }
+
+        // Hold the lock until the airdrop is accounted, so concurrent
+        // requests can't slip through the rate limits or spend the
+        // same coins.
+        let _lock = self.airdrop_lock.lock().await;
+
+        let wait = match self.store.wait(&address, ip, now, self.address_cooldown, self.ip_cooldown)
+        {
+            Ok(w) => w,
+            Err(e) => {
+                error!(target: "faucetd::rpc", "[airdrop] Failed reading accounting: {e}");
+                return server_error(RpcError::DatabaseError, id, None)
+            }
+        };
+        if let Some(wait) = wait {
+            return server_error_with_details(
+                RpcError::RateLimited,
+                id,
+                None,
+                json_map([("retry_after", JsonNum(wait as f64))]),
+            )
+        }
+
+        let tx = match self
+            .drk
+            .transfer(amount_str, self.token_id, address, None, None, false, false)
+            .await
+        {
+            Ok(tx) => tx,
+            Err(e) => {
+                error!(target: "faucetd::rpc", "[airdrop] Failed building transaction: {e}");
+                return server_error(RpcError::AirdropFailed, id, None)
+            }
+        };
+
+        let txid = match self.drk.broadcast_tx(&tx).await {
+            Ok(txid) => txid,
+            Err(e) => {
+                error!(target: "faucetd::rpc", "[airdrop] Failed broadcasting transaction: {e}");
+                return server_error(RpcError::BroadcastFailed, id, None)
+            }
+        };
+
+        // Don't reuse the spent coins before the transaction gets confirmed
+        if let Err(e) = self.drk.mark_tx_spend(&tx).await {
+            warn!(target: "faucetd::rpc", "[airdrop] Failed marking coins of {txid} as spent: {e}");
+        }
+
+        if let Err(e) = self.store.record(&address, ip, amount, now) {
+            error!(target: "faucetd::rpc", "[airdrop] Failed accounting airdrop {txid}: {e}");
+        }
+
+        info!(
+            target: "faucetd::rpc",
+            "Airdropped {} to {address}: {txid}",
+            encode_base10(amount, BALANCE_BASE10_DECIMALS),
+        );
+        JsonResponse::new(JsonStr(txid), id).into()
+    }
+}
+
+#[async_trait]
+impl RequestHandler<()> for Faucetd {
+    async fn handle_request(&self, req: JsonRequest) -> JsonResult {
+        match req.method.as_str() {
+            "ping" => self.pong(req.id, req.params).await,
+            "log.set_level" => self.log_set_level(req.id, req.params).await,
+            "airdrop" => self.airdrop(req.id, req.params, None).await,
+            "airdrop.challenge" => self.airdrop_challenge(req.id, req.params, None).await,
+            "airdrop.info" => self.airdrop_info(req.id, req.params).await,
+            _ => JsonError::new(ErrorCode::MethodNotFound, None, req.id).into(),
+        }
+    }
+
+    /// Airdrops and challenges are rate limited by the requesting IP as well
+    async fn handle_request_from(&self, req: JsonRequest, peer: &Url) -> JsonResult {
+        match req.method.as_str() {
+            "airdrop" => self.airdrop(req.id, req.params, peer.host_str()).await,
+            "airdrop.challenge" => {
+                self.airdrop_challenge(req.id, req.params, peer.host_str()).await
+            }
+            _ => self.handle_request(req).await,
+        }
+    }
+
+    async fn connections_mut(&self) -> MutexGuard<'life0, HashSet<StoppableTaskPtr>> {
+        self.rpc_connections.lock().await
+    }
+}
+
+async_daemonize!(realmain);
+async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
+    info!(target: "faucetd", "Starting faucet daemon...");
+
+    // Script kiddies protection
+    if args.wallet_pass == "changeme" {
+        error!(target: "faucetd", "Please don't use default wallet password...");
+        return Err(Error::ConfigInvalid)
+    }
+
+    // Open the faucet wallet
+    let wallet_key = match derive_wallet_key(&expand_path(&args.wallet_path)?, &args.wallet_pass) {
+        Ok(key) => key,
+        Err(e) => {
+            error!(target: "faucetd", "Failed deriving wallet key: {e}");
+            return Err(Error::ConfigInvalid)
+        }
+    };
+    let endpoint_token = auth_token(args.endpoint_token.clone(), args.endpoint_cookie.clone())?;
+    let drk = Drk::new(
+        args.wallet_path.clone(),
+        &wallet_key,
+        Some(args.endpoint.clone()),
+        endpoint_token.clone(),
+        ex.clone(),
+        false,
+    )
+    .await?;
+    if let Err(e) = drk.initialize_wallet().await {
+        return Err(Error::DatabaseError(format!("Failed initializing wallet: {e}")))
+    }
+    if let Err(e) = drk.initialize_money().await {
+        return Err(Error::DatabaseError(format!("Failed initializing Money schema: {e}")))
+    }
+
+    // Generate the faucet keypair on first start
+    if drk.addresses().await?.is_empty() {
+        if let Err(e) = drk.money_keygen().await {
+            return Err(Error::DatabaseError(format!("Failed generating faucet keypair: {e}")))
+        }
+        if let Err(e) = drk.set_default_address(1) {
+            return Err(Error::DatabaseError(format!("Failed setting faucet address: {e}")))
+        }
+    }
+    info!(target: "faucetd", "Faucet address: {}", drk.default_address().await?);
+
+    let token_id = match args.token {
+        Some(token) => drk.get_token(token).await?,
+        None => *DARK_TOKEN_ID,
+    };
+    let max_amount = decode_base10(&args.max_amount, BALANCE_BASE10_DECIMALS, false)?;
+    info!(target: "faucetd", "Airdropping up to {} of {token_id}", args.max_amount);
+
+    let challenges = match args.pow_difficulty {
+        0 => None,
+        difficulty => Some(PowChallenges::new(difficulty, args.pow_ttl)),
+    };
+
+    // Open the accounting database
+    let db_path = expand_path(&args.database)?;
+    let sled_db = sled_overlay::sled::open(&db_path)?;
+    let store = AirdropStore::new(&sled_db)?;
+    let totals = store.totals()?;
+    info!(
+        target: "faucetd",
+        "Airdropped {} in {} airdrops so far",
+        encode_base10(totals.total, BALANCE_BASE10_DECIMALS),
+        totals.count,
+    );
+
+    let faucetd = Arc::new(Faucetd {
+        drk,
+        store,
+        challenges,
+        token_id,
+        max_amount,
+        address_cooldown: args.address_cooldown,
+        ip_cooldown: args.ip_cooldown,
+        airdrop_lock: Mutex::new(()),
+        rpc_connections: Mutex::new(HashSet::new()),
+    });
+
+    // Keep the wallet in sync with the chain, so the faucet knows its coins
+    info!(target: "faucetd", "Subscribing to blocks from {}", args.endpoint);
+    let faucetd_ = faucetd.clone();
+    let endpoint = args.endpoint.clone();
+    let ex_ = ex.clone();
+    let sync_task = StoppableTask::new();
+    sync_task.clone().start(
+        async move { faucetd_.drk.subscribe_blocks(endpoint, endpoint_token, ex_).await },
+        |res| async move {
+            match res {
+                Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                Err(e) => error!(target: "faucetd", "Wallet sync failed: {e}"),
+            }
+        },
+        Error::DetachedTaskStopped,
+        ex.clone(),
+    );
+
+    // JSON-RPC server. The faucet is meant to be reachable by anyone,
+    // so authentication is always enabled and only the airdrop methods
+    // are public, unless configured otherwise.
+    let mut rpc_settings: RpcSettings = args.rpc.into();
+    if !rpc_settings.auth_enabled() {
+        rpc_settings.auth_cookie = Some(DEFAULT_AUTH_COOKIE.to_string());
+    }
+    for method in PUBLIC_METHODS {
+        rpc_settings.method_permissions.push((method.to_string(), PermissionClass::Public));
+    }
+    info!(target: "faucetd", "Starting JSON-RPC server on {}", rpc_settings.listen);
+    let faucetd_ = faucetd.clone();
+    let rpc_task = StoppableTask::new();
+    rpc_task.clone().start(
+        listen_and_serve(rpc_settings, faucetd.clone(), None, ex.clone()),
+        |res| async move {
+            match res {
+                Ok(()) | Err(Error::RpcServerStopped) => faucetd_.stop_connections().await,
+                Err(e) => error!(target: "faucetd", "Failed starting JSON-RPC server: {e}"),
+            }
+        },
+        Error::RpcServerStopped,
+        ex.clone(),
+    );
+
+    // Signal handling for graceful termination.
+    let (signals_handler, signals_task) = SignalHandler::new(ex)?;
+    signals_handler.wait_termination(signals_task).await?;
+    info!(target: "faucetd", "Caught termination signal, cleaning up and exiting...");
+
+    info!(target: "faucetd", "Stopping JSON-RPC server...");
+    rpc_task.stop().await;
+
+    info!(target: "faucetd", "Stopping wallet sync...");
+    sync_task.stop().await;
+
+    info!(target: "faucetd", "Flushing accounting database...");
+    sled_db.flush_async().await?;
+
+    info!(target: "faucetd", "Bye!");
+    Ok(())
+}
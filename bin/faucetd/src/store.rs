@@ -0,0 +1,187 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use sled_overlay::sled;
+
+use darkfi::Result;
+use darkfi_sdk::crypto::PublicKey;
+use darkfi_serial::{async_trait, deserialize, serialize, SerialDecodable, SerialEncodable};
+
+/// Airdrops by recipient address tree name
+pub const SLED_AIRDROPS_BY_ADDRESS_TREE: &[u8] = b"_airdrops_by_address";
+
+/// Airdrops by requester IP tree name
+pub const SLED_AIRDROPS_BY_IP_TREE: &[u8] = b"_airdrops_by_ip";
+
+/// Accounting of the airdrops made to an address or requested from an IP
+#[derive(Clone, Debug, Default, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct AirdropRecord {
+    /// Number of airdrops
+    pub count: u64,
+    /// Total amount airdropped
+    pub total: u64,
+    /// UNIX timestamp of the last airdrop
+    pub last: u64,
+}
+
+impl AirdropRecord {
+    /// Seconds left until the cooldown since the last airdrop has passed,
+    /// or `None` if a new airdrop is allowed at `now`.
+    pub fn wait(&self, now: u64, cooldown: u64) -> Option<u64> {
+        if self.count == 0 {
+            return None
+        }
+
+        let next = self.last.saturating_add(cooldown);
+        if now >= next {
+            return None
+        }
+
+        Some(next - now)
+    }
+}
+
+/// Persistent airdrop accounting, used to enforce the faucet rate limits
+/// across restarts.
+pub struct AirdropStore {
+    /// Sled tree keyed by recipient public key bytes
+    pub by_address: sled::Tree,
+    /// Sled tree keyed by requester IP
+    pub by_ip: sled::Tree,
+}
+
+impl AirdropStore {
+    /// Opens a new or existing `AirdropStore` on the given sled database.
+    pub fn new(db: &sled::Db) -> Result<Self> {
+        let by_address = db.open_tree(SLED_AIRDROPS_BY_ADDRESS_TREE)?;
+        let by_ip = db.open_tree(SLED_AIRDROPS_BY_IP_TREE)?;
+        Ok(Self { by_address, by_ip })
+    }
+
+    /// Fetch the airdrop record of the given address.
+    pub fn get_address(&self, address: &PublicKey) -> Result<AirdropRecord> {
+        Self::get(&self.by_address, &address.to_bytes())
+    }
+
+    /// Fetch the airdrop record of the given IP.
+    pub fn get_ip(&self, ip: &str) -> Result<AirdropRecord> {
+        Self::get(&self.by_ip, ip.as_bytes())
+    }
+
+    /// Seconds left until both the address and, if known, the IP are
+    /// allowed to receive another airdrop, or `None` if they already are.
+    pub fn wait(
+        &self,
+        address: &PublicKey,
+        ip: Option<&str>,
+        now: u64,
+        address_cooldown: u64,
+        ip_cooldown: u64,
+    ) -> Result<Option<u64>> {
+        let address_wait = self.get_address(address)?.wait(now, address_cooldown);
+        let ip_wait = match ip {
+            Some(ip) => self.get_ip(ip)?.wait(now, ip_cooldown),
+            None => None,
+        };
+
+        Ok(address_wait.max(ip_wait))
+    }
+
+    /// Account an airdrop of `amount` to the given address, requested
+    /// from the given IP at `now`.
+    pub fn record(
+        &self,
+        address: &PublicKey,
+        ip: Option<&str>,
+        amount: u64,
+        now: u64,
+    ) -> Result<()> {
+        Self::add(&self.by_address, &address.to_bytes(), amount, now)?;
+        if let Some(ip) = ip {
+            Self::add(&self.by_ip, ip.as_bytes(), amount, now)?;
+        }
+        Ok(())
+    }
+
+    /// Sum of all the airdrops accounted in the store, as a record.
+    pub fn totals(&self) -> Result<AirdropRecord> {
+        let mut totals = AirdropRecord::default();
+        for record in self.by_address.iter() {
+            let (_, value) = record?;
+            let record: AirdropRecord = deserialize(&value)?;
+            totals.count += record.count;
+            totals.total += record.total;
+            totals.last = totals.last.max(record.last);
+        }
+        Ok(totals)
+    }
+
+    fn get(tree: &sled::Tree, key: &[u8]) -> Result<AirdropRecord> {
+        match tree.get(key)? {
+            Some(found) => Ok(deserialize(&found)?),
+            None => Ok(AirdropRecord::default()),
+        }
+    }
+
+    fn add(tree: &sled::Tree, key: &[u8], amount: u64, now: u64) -> Result<()> {
+        let mut record = Self::get(tree, key)?;
+        record.count += 1;
+        record.total = record.total.saturating_add(amount);
+        record.last = now;
+        tree.insert(key, serialize(&record))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use darkfi_sdk::crypto::{Keypair, PublicKey};
+    use rand::rngs::OsRng;
+
+    use super::AirdropStore;
+
+    #[test]
+    fn airdrop_rate_limits() -> darkfi::Result<()> {
+        let db = sled_overlay::sled::Config::new().temporary(true).open()?;
+        let store = AirdropStore::new(&db)?;
+        let alice: PublicKey = Keypair::random(&mut OsRng).public;
+        let bob: PublicKey = Keypair::random(&mut OsRng).public;
+
+        // Nothing recorded yet
+        assert_eq!(store.wait(&alice, Some("10.0.0.1"), 1000, 60, 30)?, None);
+
+        store.record(&alice, Some("10.0.0.1"), 5, 1000)?;
+
+        // The address cooldown is the longest
+        assert_eq!(store.wait(&alice, Some("10.0.0.1"), 1010, 60, 30)?, Some(50));
+        // Another address from the same IP waits for the IP cooldown
+        assert_eq!(store.wait(&bob, Some("10.0.0.1"), 1010, 60, 30)?, Some(20));
+        // Unknown IPs are only limited by address
+        assert_eq!(store.wait(&bob, None, 1010, 60, 30)?, None);
+        // Cooldowns pass
+        assert_eq!(store.wait(&alice, Some("10.0.0.1"), 1060, 60, 30)?, None);
+
+        store.record(&alice, None, 7, 1060)?;
+        let record = store.get_address(&alice)?;
+        assert_eq!((record.count, record.total, record.last), (2, 12, 1060));
+        assert_eq!(store.get_ip("10.0.0.1")?.count, 1);
+        assert_eq!(store.totals()?.total, 12);
+
+        Ok(())
+    }
+}
@@ -54,6 +54,7 @@ impl RequestHandler<()> for JsonRpcInterface {
     async fn handle_request(&self, req: JsonRequest) -> JsonResult {
         return match req.method.as_str() {
             "ping" => self.pong(req.id, req.params).await,
+            "log.set_level" => self.log_set_level(req.id, req.params).await,
 
             "put" => self.put(req.id, req.params).await,
             "get" => self.get(req.id, req.params).await,
@@ -44,6 +44,10 @@ pub struct Args {
     /// Set log file path to output daemon logs into
     pub log: Option<String>,
 
+    #[structopt(long)]
+    /// Serve Prometheus metrics on this URL, e.g. tcp://127.0.0.1:9100
+    pub metrics_listen: Option<String>,
+
     #[structopt(long, default_value = "~/.local/share/darkfi/fud")]
     /// Base directory for filesystem storage
     pub base_dir: String,
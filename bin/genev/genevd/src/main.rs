@@ -20,7 +20,9 @@ use std::sync::{Arc, OnceLock};
 
 use darkfi::{
     async_daemonize, cli_desc,
-    event_graph::{proto::ProtocolEventGraph, EventGraph, EventGraphPtr, NULL_ID},
+    event_graph::{
+        proto::ProtocolEventGraph, retention::RetentionPolicy, EventGraph, EventGraphPtr, NULL_ID,
+    },
     net::{session::SESSION_DEFAULT, settings::SettingsOpt, P2p},
     rpc::{
         jsonrpc::JsonSubscriber,
@@ -74,6 +76,10 @@ struct Args {
     /// Set log file to ouput into
     log: Option<String>,
 
+    #[structopt(long)]
+    /// Serve Prometheus metrics on this URL, e.g. tcp://127.0.0.1:9100
+    metrics_listen: Option<String>,
+
     #[structopt(long)]
     /// Flag to skip syncing the DAG (no history)
     skip_dag_sync: bool,
@@ -127,6 +133,7 @@ async fn realmain(settings: Args, executor: Arc<smol::Executor<'static>>) -> Res
         replay_mode,
         "genevd_dag",
         1,
+        RetentionPolicy::default(),
         executor.clone(),
     )
     .await?;
@@ -55,6 +55,7 @@ impl RequestHandler<()> for JsonRpcInterface {
             "list" => self.list(req.id, req.params).await,
 
             "ping" => self.pong(req.id, req.params).await,
+            "log.set_level" => self.log_set_level(req.id, req.params).await,
             "dnet.subscribe_events" => self.dnet_subscribe_events(req.id, req.params).await,
             "dnet.switch" => self.dnet_switch(req.id, req.params).await,
             "p2p.get_info" => self.p2p_get_info(req.id, req.params).await,
@@ -41,6 +41,7 @@ use darkfi::{
     async_daemonize, cli_desc,
     net::{self, hosts::HostColor, settings::BanPolicy, P2p, P2pPtr},
     rpc::{
+        health::{HandlerHealth, HealthStatus},
         jsonrpc::*,
         server::{listen_and_serve, RequestHandler},
         settings::{RpcSettings, RpcSettingsOpt},
@@ -50,6 +51,9 @@ use darkfi::{
     Error, Result,
 };
 
+mod probe;
+use probe::{probe_peers, ProbeStats, ProbeStatsPtr};
+
 const CONFIG_FILE: &str = "lilith_config.toml";
 const CONFIG_FILE_CONTENTS: &str = include_str!("../lilith_config.toml");
 
@@ -69,6 +73,10 @@ struct Args {
     /// Set log file to ouput into
     log: Option<String>,
 
+    #[structopt(long)]
+    /// Serve Prometheus metrics on this URL, e.g. tcp://127.0.0.1:9100
+    metrics_listen: Option<String>,
+
     #[structopt(short, parse(from_occurrences))]
     /// Increase verbosity (-vvv supported)
     verbose: u8,
@@ -76,6 +84,18 @@ struct Args {
     #[structopt(long, default_value = "120")]
     /// Interval after which to check whitelist peers
     whitelist_refinery_interval: u64,
+
+    #[structopt(long, default_value = "60")]
+    /// Interval in seconds between peer probing rounds
+    probe_interval: u64,
+
+    #[structopt(long, default_value = "16")]
+    /// Number of peers to probe per round
+    probe_batch: usize,
+
+    #[structopt(long, default_value = "3")]
+    /// Failed probes in a row after which a peer is dropped
+    probe_max_failures: u32,
 }
 
 /// Struct representing a spawned P2P network
@@ -84,6 +104,8 @@ struct Spawn {
     pub name: String,
     /// P2P pointer
     pub p2p: P2pPtr,
+    /// Peer probing stats
+    pub probes: ProbeStatsPtr,
 }
 
 impl Spawn {
@@ -238,6 +260,22 @@ impl Lilith {
 
         JsonResponse::new(json, id).into()
     }
+
+    // RPCAPI:
+    // Returns the peer probing stats of all spawned networks.
+    // --> {"jsonrpc": "2.0", "method": "probes", "params": [], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": {"probes": {"network": probe_stats, ...}}, "id": 42}
+    async fn probes(&self, id: u16, _params: JsonValue) -> JsonResult {
+        let mut probes = HashMap::new();
+        for spawn in &self.networks {
+            probes.insert(spawn.name.clone(), spawn.probes.to_json());
+        }
+
+        let json =
+            JsonValue::Object(HashMap::from([("probes".to_string(), JsonValue::Object(probes))]));
+
+        JsonResponse::new(json, id).into()
+    }
 }
 
 #[async_trait]
@@ -245,7 +283,12 @@ impl RequestHandler<()> for Lilith {
     async fn handle_request(&self, req: JsonRequest) -> JsonResult {
         return match req.method.as_str() {
             "ping" => self.pong(req.id, req.params).await,
+            "log.set_level" => self.log_set_level(req.id, req.params).await,
             "spawns" => self.spawns(req.id, req.params).await,
+            "probes" => self.probes(req.id, req.params).await,
+            "health.ping" => self.health_ping(req.id, req.params).await,
+            "health.ready" => self.health_ready(req.id, req.params).await,
+            "health.info" => self.health_info(req.id, req.params).await,
             _ => JsonError::new(ErrorCode::MethodNotFound, None, req.id).into(),
         }
     }
@@ -255,6 +298,23 @@ impl RequestHandler<()> for Lilith {
     }
 }
 
+#[async_trait]
+impl HandlerHealth for Lilith {
+    fn health_version(&self) -> (&'static str, &'static str) {
+        (env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    }
+
+    /// Lilith has no chain or DAG to sync, so it is ready as soon
+    /// as its networks are up.
+    async fn health_status(&self) -> HealthStatus {
+        HealthStatus {
+            synced: true,
+            peers: self.networks.iter().map(|n| n.p2p.hosts().channels().len()).sum(),
+            db_ok: true,
+        }
+    }
+}
+
 /// Parse a TOML string for any configured network and return a map containing
 /// said configurations.
 fn parse_configured_networks(data: &str) -> Result<HashMap<String, NetInfo>> {
@@ -376,7 +436,11 @@ async fn spawn_net(name: String, info: &NetInfo, ex: Arc<Executor<'static>>) ->
     info!(target: "lilith", "Starting seed network node for \"{name}\" on {addrs_str:?}");
     p2p.clone().start().await?;
 
-    let spawn = Spawn { name, p2p };
+    // Share latency and network diverse sets of the probed hosts
+    let probes = ProbeStats::new();
+    p2p.hosts().container.set_selector(probes.clone());
+
+    let spawn = Spawn { name, p2p, probes };
     Ok(spawn)
 }
 
@@ -407,6 +471,7 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
     // Set up main daemon and background refinery_tasks
     let lilith = Arc::new(Lilith { networks, rpc_connections: Mutex::new(HashSet::new()) });
     let mut refinery_tasks = HashMap::new();
+    let mut probe_tasks = HashMap::new();
     for network in &lilith.networks {
         let name = network.name.clone();
         let task = StoppableTask::new();
@@ -422,6 +487,30 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
             ex.clone(),
         );
         refinery_tasks.insert(network.name.clone(), task);
+
+        let name = network.name.clone();
+        let task = StoppableTask::new();
+        task.clone().start(
+            probe_peers(
+                name.clone(),
+                network.p2p.clone(),
+                network.probes.clone(),
+                args.probe_interval,
+                args.probe_batch,
+                args.probe_max_failures,
+            ),
+            |res| async move {
+                match res {
+                    Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                    Err(e) => {
+                        error!(target: "lilith", "Failed starting probe task for \"{name}\": {e}")
+                    }
+                }
+            },
+            Error::DetachedTaskStopped,
+            ex.clone(),
+        );
+        probe_tasks.insert(network.name.clone(), task);
     }
 
     // JSON-RPC server
@@ -453,6 +542,8 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
     for spawn in &lilith.networks {
         info!(target: "lilith", "Stopping \"{}\" task", spawn.name);
         refinery_tasks.get(&spawn.name).unwrap().stop().await;
+        info!(target: "lilith", "Stopping \"{}\" probe task", spawn.name);
+        probe_tasks.get(&spawn.name).unwrap().stop().await;
         info!(target: "lilith", "Stopping \"{}\" P2P", spawn.name);
         spawn.p2p.stop().await;
     }
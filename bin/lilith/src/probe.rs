@@ -0,0 +1,332 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Active probing of the peers a seed node advertises.
+//!
+//! Every probing round picks the hosts from the white and grey lists that
+//! were probed least recently, dials them and performs a version exchange,
+//! measuring how long it took. Hosts that can't be reached a number of
+//! times in a row, or that fail the handshake, are dropped from the
+//! hostlist. The collected stats are used to share latency and network
+//! diverse sets of hosts with peers asking for addresses.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    net::IpAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, UNIX_EPOCH},
+};
+
+use futures::future::join_all;
+use log::{debug, info};
+use rand::{rngs::OsRng, seq::SliceRandom};
+use tinyjson::JsonValue;
+use url::{Host, Url};
+
+use darkfi::{
+    net::{
+        hosts::{AddrSelector, HostColor},
+        P2pPtr,
+    },
+    system::sleep,
+    Error, Result,
+};
+
+/// Probing results for a single host
+#[derive(Clone, Debug, Default)]
+struct PeerProbe {
+    /// Number of successful probes
+    successes: u64,
+    /// Number of failed probes
+    failures: u64,
+    /// Number of failed probes since the last success
+    consecutive_failures: u32,
+    /// Moving average of the handshake latency in milliseconds
+    latency: Option<u64>,
+    /// UNIX timestamp of the last probe
+    last_probe: u64,
+    /// Error of the last failed probe
+    last_error: Option<String>,
+}
+
+impl PeerProbe {
+    fn to_json(&self, addr: &Url) -> JsonValue {
+        let latency = match self.latency {
+            Some(l) => JsonValue::Number(l as f64),
+            None => JsonValue::Null,
+        };
+        let last_error = match &self.last_error {
+            Some(e) => JsonValue::String(e.clone()),
+            None => JsonValue::Null,
+        };
+
+        JsonValue::Object(HashMap::from([
+            ("addr".to_string(), JsonValue::String(addr.to_string())),
+            ("successes".to_string(), JsonValue::Number(self.successes as f64)),
+            ("failures".to_string(), JsonValue::Number(self.failures as f64)),
+            (
+                "consecutive_failures".to_string(),
+                JsonValue::Number(self.consecutive_failures as f64),
+            ),
+            ("latency_ms".to_string(), latency),
+            ("last_probe".to_string(), JsonValue::Number(self.last_probe as f64)),
+            ("last_error".to_string(), last_error),
+        ]))
+    }
+}
+
+/// Atomic pointer to the probing stats of a network
+pub type ProbeStatsPtr = Arc<ProbeStats>;
+
+/// Probing stats of a single network
+#[derive(Default)]
+pub struct ProbeStats {
+    /// Results per probed host
+    peers: Mutex<HashMap<Url, PeerProbe>>,
+    /// Number of finished probing rounds
+    rounds: AtomicU64,
+    /// Hosts dropped for being unreachable
+    dropped_dead: AtomicU64,
+    /// Hosts dropped for failing the handshake
+    dropped_misbehaving: AtomicU64,
+}
+
+impl ProbeStats {
+    pub fn new() -> ProbeStatsPtr {
+        Arc::new(Self::default())
+    }
+
+    /// UNIX timestamp of the last time we probed a host, 0 if never
+    fn last_probe(&self, addr: &Url) -> u64 {
+        self.peers.lock().unwrap().get(addr).map_or(0, |p| p.last_probe)
+    }
+
+    /// Record a successful probe
+    fn record_success(&self, addr: &Url, latency: Duration) {
+        let latency = latency.as_millis() as u64;
+        let mut peers = self.peers.lock().unwrap();
+        let probe = peers.entry(addr.clone()).or_default();
+        probe.successes += 1;
+        probe.consecutive_failures = 0;
+        probe.latency = Some(match probe.latency {
+            Some(avg) => (avg * 3 + latency) / 4,
+            None => latency,
+        });
+        probe.last_probe = UNIX_EPOCH.elapsed().unwrap().as_secs();
+    }
+
+    /// Record a failed probe, returning the number of failures in a row
+    fn record_failure(&self, addr: &Url, error: &Error) -> u32 {
+        let mut peers = self.peers.lock().unwrap();
+        let probe = peers.entry(addr.clone()).or_default();
+        probe.failures += 1;
+        probe.consecutive_failures += 1;
+        probe.last_probe = UNIX_EPOCH.elapsed().unwrap().as_secs();
+        probe.last_error = Some(error.to_string());
+        probe.consecutive_failures
+    }
+
+    /// Forget about a dropped host
+    fn remove(&self, addr: &Url) {
+        self.peers.lock().unwrap().remove(addr);
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        let peers: Vec<JsonValue> =
+            self.peers.lock().unwrap().iter().map(|(addr, probe)| probe.to_json(addr)).collect();
+
+        JsonValue::Object(HashMap::from([
+            ("rounds".to_string(), JsonValue::Number(self.rounds.load(Ordering::SeqCst) as f64)),
+            (
+                "dropped_dead".to_string(),
+                JsonValue::Number(self.dropped_dead.load(Ordering::SeqCst) as f64),
+            ),
+            (
+                "dropped_misbehaving".to_string(),
+                JsonValue::Number(self.dropped_misbehaving.load(Ordering::SeqCst) as f64),
+            ),
+            ("peers".to_string(), JsonValue::Array(peers)),
+        ]))
+    }
+}
+
+/// Rough network location of a host. Without a GeoIP database we use the
+/// IP prefix as a proxy: hosts in the same /16 (or /32 for IPv6) are likely
+/// run by the same provider in the same region.
+fn network_group(addr: &Url) -> String {
+    match addr.host() {
+        Some(Host::Ipv4(ip)) => {
+            let o = ip.octets();
+            format!("{}.{}", o[0], o[1])
+        }
+        Some(Host::Ipv6(ip)) => {
+            // IPv4-mapped addresses are grouped like IPv4 ones
+            if let IpAddr::V4(ip) = IpAddr::V6(ip).to_canonical() {
+                let o = ip.octets();
+                return format!("{}.{}", o[0], o[1])
+            }
+            let s = ip.segments();
+            format!("{:x}:{:x}", s[0], s[1])
+        }
+        // Anonymity network hosts have no location to go by
+        Some(Host::Domain(d)) if d.ends_with(".onion") || d.ends_with(".i2p") => {
+            addr.scheme().to_string()
+        }
+        Some(Host::Domain(d)) => {
+            let labels: Vec<&str> = d.rsplitn(3, '.').collect();
+            labels.iter().take(2).rev().copied().collect::<Vec<_>>().join(".")
+        }
+        None => String::new(),
+    }
+}
+
+/// Latency band used to spread the shared hosts across
+fn latency_band(latency: Option<u64>) -> u8 {
+    match latency {
+        Some(l) if l < 100 => 0,
+        Some(l) if l < 300 => 1,
+        Some(l) if l < 1000 => 2,
+        Some(_) => 3,
+        None => 4,
+    }
+}
+
+impl AddrSelector for ProbeStats {
+    /// Hosts that failed their last probe are only shared when there is
+    /// nothing better. The rest is grouped by network location and latency
+    /// band, and picked round-robin from the groups, so the peers we hand
+    /// out don't all sit in the same place.
+    fn select(&self, hosts: Vec<(Url, u64)>, n: usize) -> Vec<(Url, u64)> {
+        let peers = self.peers.lock().unwrap();
+
+        let mut buckets: BTreeMap<(String, u8), Vec<(Url, u64)>> = BTreeMap::new();
+        let mut failing = vec![];
+        for host in hosts {
+            let probe = peers.get(&host.0);
+            if probe.is_some_and(|p| p.consecutive_failures > 0) {
+                failing.push(host);
+                continue
+            }
+            let key = (network_group(&host.0), latency_band(probe.and_then(|p| p.latency)));
+            buckets.entry(key).or_default().push(host);
+        }
+        drop(peers);
+
+        let mut buckets: Vec<Vec<(Url, u64)>> = buckets.into_values().collect();
+        buckets.shuffle(&mut OsRng);
+        for bucket in buckets.iter_mut() {
+            bucket.shuffle(&mut OsRng);
+        }
+
+        let mut selected = vec![];
+        while selected.len() < n && buckets.iter().any(|b| !b.is_empty()) {
+            for bucket in buckets.iter_mut() {
+                if selected.len() == n {
+                    break
+                }
+                if let Some(host) = bucket.pop() {
+                    selected.push(host);
+                }
+            }
+        }
+
+        failing.shuffle(&mut OsRng);
+        selected.extend(failing.into_iter().take(n - selected.len()));
+        selected
+    }
+}
+
+/// Periodically probe the hosts of a network, dropping the dead and
+/// misbehaving ones.
+pub async fn probe_peers(
+    network_name: String,
+    p2p: P2pPtr,
+    stats: ProbeStatsPtr,
+    interval: u64,
+    batch: usize,
+    max_failures: u32,
+) -> Result<()> {
+    debug!(target: "lilith::probe", "Starting peer probing for \"{network_name}\"");
+    let hosts = p2p.hosts();
+
+    loop {
+        sleep(interval).await;
+
+        // Probe the hosts we haven't looked at for the longest first
+        let candidates: HashSet<Url> = [HostColor::White, HostColor::Grey]
+            .into_iter()
+            .flat_map(|color| hosts.container.fetch_all(color))
+            .map(|(addr, _)| addr)
+            .collect();
+        let mut candidates: Vec<Url> = candidates.into_iter().collect();
+        candidates.sort_by_key(|addr| stats.last_probe(addr));
+
+        // Skip hosts that are busy, e.g. being refined or connected
+        let mut selected = vec![];
+        for addr in candidates {
+            if selected.len() == batch {
+                break
+            }
+            if hosts.refinable(addr.clone()) {
+                selected.push(addr);
+            }
+        }
+
+        let probes =
+            selected.iter().map(|addr| p2p.session_refine().probe_node(addr.clone(), p2p.clone()));
+        let results = join_all(probes).await;
+
+        for (addr, result) in selected.iter().zip(results) {
+            match result {
+                Ok(latency) => {
+                    debug!(target: "lilith::probe", "[{network_name}] {addr} answered in {latency:?}");
+                    stats.record_success(addr, latency);
+                    let last_seen = UNIX_EPOCH.elapsed().unwrap().as_secs();
+                    hosts.whitelist_host(addr, last_seen).await?;
+                }
+
+                Err(Error::HandshakeFailed(e)) => {
+                    info!(target: "lilith::probe", "[{network_name}] Dropping misbehaving host {addr}: {e}");
+                    stats.remove(addr);
+                    stats.dropped_misbehaving.fetch_add(1, Ordering::SeqCst);
+                    hosts.drop_host(addr);
+                }
+
+                Err(e) => {
+                    let failures = stats.record_failure(addr, &e);
+                    if failures < max_failures {
+                        debug!(target: "lilith::probe", "[{network_name}] {addr} unreachable ({failures}/{max_failures}): {e}");
+                        let last_seen = hosts.fetch_last_seen(addr).unwrap_or(0);
+                        hosts.greylist_host(addr, last_seen).await?;
+                        continue
+                    }
+
+                    info!(target: "lilith::probe", "[{network_name}] Dropping dead host {addr}: {e}");
+                    stats.remove(addr);
+                    stats.dropped_dead.fetch_add(1, Ordering::SeqCst);
+                    hosts.drop_host(addr);
+                }
+            }
+        }
+
+        stats.rounds.fetch_add(1, Ordering::SeqCst);
+    }
+}
@@ -16,10 +16,12 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use darkfi::rpc::jsonrpc::{ErrorCode::ServerError, JsonError, JsonResult};
+pub use darkfi::rpc::jsonrpc::server_error;
+use darkfi::rpc::jsonrpc::{ErrorCategory, ServerErrorKind};
 
 /// Custom RPC errors available for minerd.
 /// Please sort them sensefully.
+#[derive(Copy, Clone, Debug)]
 pub enum RpcError {
     // Parsing errors
     TargetParseError = -32101,
@@ -30,25 +32,26 @@ pub enum RpcError {
     StopFailed = -32202,
 }
 
-fn to_tuple(e: RpcError) -> (i32, String) {
-    let msg = match e {
-        // Parsing errors
-        RpcError::TargetParseError => "Target parse error",
-        RpcError::BlockParseError => "Block parse error",
-        // Miner errors
-        RpcError::MiningFailed => "Mining block failed",
-        RpcError::StopFailed => "Failed to stop previous request",
-    };
-
-    (e as i32, msg.to_string())
-}
-
-pub fn server_error(e: RpcError, id: u16, msg: Option<&str>) -> JsonResult {
-    let (code, default_msg) = to_tuple(e);
+impl ServerErrorKind for RpcError {
+    fn code(&self) -> i32 {
+        *self as i32
+    }
 
-    if let Some(message) = msg {
-        return JsonError::new(ServerError(code), Some(message.to_string()), id).into()
+    fn message(&self) -> &'static str {
+        match self {
+            // Parsing errors
+            Self::TargetParseError => "Target parse error",
+            Self::BlockParseError => "Block parse error",
+            // Miner errors
+            Self::MiningFailed => "Mining block failed",
+            Self::StopFailed => "Failed to stop previous request",
+        }
     }
 
-    JsonError::new(ServerError(code), Some(default_msg), id).into()
+    fn category(&self) -> ErrorCategory {
+        match self {
+            Self::TargetParseError | Self::BlockParseError => ErrorCategory::Validation,
+            Self::MiningFailed | Self::StopFailed => ErrorCategory::Internal,
+        }
+    }
 }
@@ -48,6 +48,7 @@ impl RequestHandler<()> for MinerNode {
 
         match req.method.as_str() {
             "ping" => self.pong(req.id, req.params).await,
+            "log.set_level" => self.log_set_level(req.id, req.params).await,
             "abort" => self.abort(req.id, req.params).await,
             "mine" => self.mine(req.id, req.params).await,
             _ => JsonError::new(ErrorCode::MethodNotFound, None, req.id).into(),
@@ -0,0 +1,107 @@
+# Filter expressions used by `tau show` and bulk edits.
+#
+#   +tag  -tag  @nick  -@nick
+#   key:value  key!=value  key<value  key<=value  key>value  key>=value
+#   --blocked  --ready
+#
+# All terms must match. `none` matches an unset value, e.g. `due:none`.
+import re
+
+ALIASES = {
+    "assignee": "assign",
+    "tag": "tags",
+    "status": "state",
+    "created": "created_at",
+}
+LIST_KEYS = ["tags", "assign", "project"]
+TEXT_KEYS = ["title", "desc"]
+ORDERED_KEYS = ["rank", "due", "created_at"]
+STATES = ["open", "start", "pause", "stop"]
+
+TERM = re.compile(r"^([a-z_]+)(<=|>=|!=|<|>|:)(.+)$")
+
+class FilterError(Exception):
+    pass
+
+def is_filter_term(term):
+    if term in ["--blocked", "--ready"]:
+        return True
+    if term[:1] in ["+", "@", "-"]:
+        return len(term) > 1 and term != "-@"
+    return TERM.match(term) is not None
+
+def parse(terms, convert):
+    """Parse filter terms into (key, op, value) tuples. `convert` turns
+    a raw value into the same type the task attribute has."""
+    filters = []
+    for term in terms:
+        if term in ["--blocked", "--ready"]:
+            filters.append((term[2:], None, None))
+        elif term.startswith("+"):
+            filters.append(("tags", ":", term[1:]))
+        elif term.startswith("-@"):
+            filters.append(("assign", "!=", term[2:]))
+        elif term.startswith("@"):
+            filters.append(("assign", ":", term[1:]))
+        elif term.startswith("-") and len(term) > 1 and not TERM.match(term):
+            filters.append(("tags", "!=", term[1:]))
+        elif (m := TERM.match(term)) is not None:
+            key, op, val = m.groups()
+            key = ALIASES.get(key, key)
+            filters.append((key, op, parse_value(key, op, val, convert)))
+        else:
+            raise FilterError(f"unknown filter '{term}'")
+    return filters
+
+def parse_value(key, op, val, convert):
+    if val.lower() == "none":
+        if op not in [":", "!="] or key == "state":
+            raise FilterError(f"cannot compare {key} with none")
+        return None
+
+    if key == "state":
+        if op not in [":", "!="] or val not in STATES:
+            raise FilterError(f"state can only be matched against {STATES}")
+        return val
+    elif key in LIST_KEYS or key in TEXT_KEYS:
+        if op not in [":", "!="]:
+            raise FilterError(f"{key} can only be matched with ':' or '!='")
+        return val
+    elif key in ORDERED_KEYS:
+        return convert("due" if key == "created_at" else key, val)
+    raise FilterError(f"unknown attribute '{key}'")
+
+def term_matches(task, key, op, val):
+    if key == "blocked":
+        return bool(task.get("blocked_by"))
+    if key == "ready":
+        return not task.get("blocked_by") and task["state"] != "stop"
+
+    attr = task.get(key)
+    # Timestamps are sent as strings
+    if key in ORDERED_KEYS and attr is not None:
+        attr = float(attr)
+    if val is None:
+        unset = attr is None or attr == []
+        return unset if op == ":" else not unset
+
+    if key in LIST_KEYS:
+        found = val in attr
+    elif key in TEXT_KEYS:
+        found = val.lower() in attr.lower()
+    elif op in [":", "!="]:
+        found = attr == val
+    elif attr is None:
+        return False
+    else:
+        return {
+            "<": attr < val,
+            "<=": attr <= val,
+            ">": attr > val,
+            ">=": attr >= val,
+        }[op]
+
+    return found if op == ":" else not found
+
+def matches(task, filters):
+    return all(term_matches(task, key, op, val) for key, op, val in filters)
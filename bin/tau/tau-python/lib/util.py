@@ -36,6 +36,8 @@ task_template = {
     "project": list,
     "due": int,
     "rank": float,
+    "recurrence": dict,
+    "depends_on": list,
     "created_at": int,
     "state": str,
     "events": list,
@@ -16,7 +16,9 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use darkfi::rpc::jsonrpc::{ErrorCode, JsonError, JsonResponse, JsonResult};
+use darkfi::rpc::jsonrpc::{
+    server_error, ErrorCategory, JsonResponse, JsonResult, ServerErrorKind,
+};
 use tinyjson::JsonValue;
 
 #[derive(Debug, thiserror::Error)]
@@ -25,6 +27,8 @@ pub enum TaudError {
     InvalidDueTime,
     #[error("Invalid Id")]
     InvalidId,
+    #[error("Task dependencies form a cycle")]
+    DependencyCycle,
     #[error("Invalid Data/Params: `{0}` ")]
     InvalidData(String),
     #[error("InternalError")]
@@ -53,29 +57,70 @@ impl From<std::io::Error> for TaudError {
     }
 }
 
+/// RPC error codes returned by taud
+#[derive(Copy, Clone, Debug)]
+pub enum TaudRpcError {
+    // Validation errors
+    InvalidDueTime = -32101,
+    InvalidId = -32102,
+    InvalidData = -32103,
+    DependencyCycle = -32104,
+
+    // Internal errors
+    Internal = -32400,
+    EncryptionFailed = -32401,
+    DecryptionFailed = -32402,
+    IoFailed = -32403,
+}
+
+impl ServerErrorKind for TaudRpcError {
+    fn code(&self) -> i32 {
+        *self as i32
+    }
+
+    fn message(&self) -> &'static str {
+        match self {
+            Self::InvalidDueTime => "invalid due time",
+            Self::InvalidId => "invalid task id",
+            Self::InvalidData => "invalid params",
+            Self::DependencyCycle => "dependency cycle",
+            Self::Internal => "internal error",
+            Self::EncryptionFailed => "encryption error",
+            Self::DecryptionFailed => "decryption error",
+            Self::IoFailed => "io error",
+        }
+    }
+
+    fn category(&self) -> ErrorCategory {
+        match self {
+            Self::InvalidDueTime | Self::InvalidId | Self::InvalidData | Self::DependencyCycle => {
+                ErrorCategory::Validation
+            }
+            Self::Internal | Self::EncryptionFailed | Self::DecryptionFailed | Self::IoFailed => {
+                ErrorCategory::Internal
+            }
+        }
+    }
+}
+
 pub fn to_json_result(res: TaudResult<JsonValue>, id: u16) -> JsonResult {
     match res {
         Ok(v) => JsonResponse::new(v, id).into(),
         Err(err) => match err {
-            TaudError::InvalidId => {
-                JsonError::new(ErrorCode::InvalidParams, Some("invalid task id".into()), id).into()
-            }
+            TaudError::InvalidId => server_error(TaudRpcError::InvalidId, id, None),
+            TaudError::DependencyCycle => server_error(TaudRpcError::DependencyCycle, id, None),
             TaudError::InvalidData(e) | TaudError::JsonError(e) => {
-                JsonError::new(ErrorCode::InvalidParams, Some(e), id).into()
-            }
-            TaudError::InvalidDueTime => {
-                JsonError::new(ErrorCode::InvalidParams, Some("invalid due time".into()), id).into()
+                server_error(TaudRpcError::InvalidData, id, Some(&e))
             }
+            TaudError::InvalidDueTime => server_error(TaudRpcError::InvalidDueTime, id, None),
             TaudError::EncryptionError(e) => {
-                JsonError::new(ErrorCode::InternalError, Some(e), id).into()
+                server_error(TaudRpcError::EncryptionFailed, id, Some(&e))
             }
             TaudError::DecryptionError(e) => {
-                JsonError::new(ErrorCode::InternalError, Some(e), id).into()
-            }
-            TaudError::Darkfi(e) => {
-                JsonError::new(ErrorCode::InternalError, Some(e.to_string()), id).into()
+                server_error(TaudRpcError::DecryptionFailed, id, Some(&e))
             }
-            TaudError::IoError(e) => JsonError::new(ErrorCode::InternalError, Some(e), id).into(),
+            TaudError::Darkfi(e) => server_error(TaudRpcError::Internal, id, Some(&e.to_string())),
+            TaudError::IoError(e) => server_error(TaudRpcError::IoFailed, id, Some(&e)),
         },
     }
 }
@@ -32,6 +32,7 @@ use darkfi::{
     event_graph::EventGraphPtr,
     net,
     rpc::{
+        health::{HandlerHealth, HealthStatus},
         jsonrpc::{ErrorCode, JsonError, JsonRequest, JsonResult, JsonSubscriber},
         p2p_method::HandlerP2p,
         server::RequestHandler,
@@ -44,7 +45,8 @@ use darkfi::{
 use taud::{
     error::{to_json_result, TaudError, TaudResult},
     month_tasks::MonthTasks,
-    task_info::{Comment, TaskInfo},
+    search::SearchIndex,
+    task_info::{Comment, Recurrence, TaskInfo},
     util::set_event,
 };
 
@@ -61,7 +63,10 @@ pub struct JsonRpcInterface {
     p2p: net::P2pPtr,
     event_graph: EventGraphPtr,
     dnet_sub: JsonSubscriber,
+    p2p_sub: JsonSubscriber,
     deg_sub: JsonSubscriber,
+    reminder_sub: JsonSubscriber,
+    search_index: Arc<SearchIndex>,
     rpc_connections: Mutex<HashSet<StoppableTaskPtr>>,
 }
 
@@ -82,8 +87,12 @@ impl RequestHandler<()> for JsonRpcInterface {
             "import" => self.import_from(req.params).await,
             "fetch_deactive_tasks" => self.fetch_deactive_tasks(req.params).await,
             "fetch_archive_task" => self.fetch_archive_task(req.params).await,
+            "search" => self.search(req.params).await,
+
+            "reminder.subscribe" => return self.reminder_subscribe(req.id, req.params).await,
 
             "ping" => return self.pong(req.id, req.params).await,
+            "log.set_level" => return self.log_set_level(req.id, req.params).await,
             "dnet.subscribe_events" => return self.dnet_subscribe_events(req.id, req.params).await,
             "dnet.switch" => self.dnet_switch(req.params).await,
 
@@ -92,6 +101,11 @@ impl RequestHandler<()> for JsonRpcInterface {
             "eventgraph.get_info" => return self.eg_get_info(req.id, req.params).await,
 
             "p2p.get_info" => return self.p2p_get_info(req.id, req.params).await,
+            "p2p.subscribe_events" => return self.p2p_subscribe_events(req.id, req.params).await,
+
+            "health.ping" => return self.health_ping(req.id, req.params).await,
+            "health.ready" => return self.health_ready(req.id, req.params).await,
+            "health.info" => return self.health_info(req.id, req.params).await,
             _ => return JsonError::new(ErrorCode::MethodNotFound, None, req.id).into(),
         };
 
@@ -107,6 +121,25 @@ impl HandlerP2p for JsonRpcInterface {
     fn p2p(&self) -> net::P2pPtr {
         self.p2p.clone()
     }
+
+    fn p2p_events(&self) -> Option<JsonSubscriber> {
+        Some(self.p2p_sub.clone())
+    }
+}
+
+#[async_trait]
+impl HandlerHealth for JsonRpcInterface {
+    fn health_version(&self) -> (&'static str, &'static str) {
+        (env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    }
+
+    async fn health_status(&self) -> HealthStatus {
+        HealthStatus {
+            synced: *self.event_graph.synced.read().await,
+            peers: self.p2p.hosts().channels().len(),
+            db_ok: self.event_graph.dag_readable(),
+        }
+    }
 }
 
 impl JsonRpcInterface {
@@ -119,7 +152,10 @@ impl JsonRpcInterface {
         p2p: net::P2pPtr,
         event_graph: EventGraphPtr,
         dnet_sub: JsonSubscriber,
+        p2p_sub: JsonSubscriber,
         deg_sub: JsonSubscriber,
+        reminder_sub: JsonSubscriber,
+        search_index: Arc<SearchIndex>,
     ) -> Self {
         let workspace = Mutex::new(DEFAULT_WORKSPACE.to_string());
         Self {
@@ -132,10 +168,29 @@ impl JsonRpcInterface {
             event_graph,
             rpc_connections: Mutex::new(HashSet::new()),
             dnet_sub,
+            p2p_sub,
             deg_sub,
+            reminder_sub,
+            search_index,
         }
     }
 
+    // RPCAPI:
+    // Initializes a subscription to due date reminders.
+    // Once a subscription is established, `taud` will send a JSON-RPC notification
+    // with the task when it is about to become due.
+    //
+    // --> {"jsonrpc": "2.0", "method": "reminder.subscribe", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "method": "reminder.subscribe", "params": [`task`]}
+    pub async fn reminder_subscribe(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        self.reminder_sub.clone().into()
+    }
+
     // RPCAPI:
     // Activate or deactivate dnet in the P2P stack.
     // By sending `true`, dnet will be activated, and by sending `false` dnet will
@@ -240,7 +295,9 @@ impl JsonRpcInterface {
     //          assign: [..],
     //          project: [..],
     //          "due": ..,
-    //          "rank": ..
+    //          "rank": ..,
+    //          "recurrence": {"freq": "weekly", "interval": 1},
+    //          "depends_on": ["+ref_id", ..]
     //          }],
     //      "id": 1
     //      }
@@ -255,7 +312,9 @@ impl JsonRpcInterface {
 
         let params = params[0].get::<HashMap<String, JsonValue>>().unwrap();
 
-        if params.len() != 9 {
+        // Recurrence and dependencies are optional, so older clients keep working
+        let optional = ["recurrence", "depends_on"];
+        if params.keys().filter(|k| !optional.contains(&k.as_str())).count() != 9 {
             return Err(TaudError::InvalidData("Invalid parameters".to_string()))
         }
 
@@ -271,6 +330,34 @@ impl JsonRpcInterface {
             _ => return Err(TaudError::InvalidData("Invalid parameter \"rank\"".to_string())),
         };
 
+        let recurrence = match params.get("recurrence") {
+            None | Some(JsonValue::Null) => None,
+            Some(v) => Some(Recurrence::try_from(v)?),
+        };
+
+        let depends_on = {
+            let mut depends_on = vec![];
+
+            if let Some(deps) = params.get("depends_on") {
+                let Some(deps) = deps.get::<Vec<JsonValue>>() else {
+                    return Err(TaudError::InvalidData(
+                        "Invalid parameter \"depends_on\"".to_string(),
+                    ))
+                };
+                for val in deps.iter() {
+                    if let Some(dep) = val.get::<String>() {
+                        depends_on.push(dep.clone());
+                    } else {
+                        return Err(TaudError::InvalidData(
+                            "Invalid parameter \"depends_on\"".to_string(),
+                        ))
+                    }
+                }
+            }
+
+            depends_on
+        };
+
         let tags = {
             let mut tags = vec![];
 
@@ -336,6 +423,9 @@ impl JsonRpcInterface {
         new_task.set_project(&projects);
         new_task.set_assign(&assigns);
         new_task.set_tags(&tags);
+        new_task.set_recurrence(recurrence);
+        new_task.set_depends_on(&depends_on)?;
+        new_task.check_dependencies(&self.dataset_path)?;
 
         self.notify_queue_sender.send(new_task.clone()).await.map_err(Error::from)?;
         Ok(new_task.ref_id.clone().into())
@@ -439,13 +529,24 @@ impl JsonRpcInterface {
         let mut task: TaskInfo =
             self.load_task_by_ref_id(params[0].get::<String>().unwrap(), ws)?;
 
+        // Completing a recurring task schedules its next occurrence
+        let mut next = None;
         if states.contains(&state.as_str()) {
+            if state == "stop" && task.get_state() != "stop" {
+                next = task.next_occurrence();
+            }
             task.set_state(state);
             set_event(&mut task, "state", &self.nickname, state);
         }
 
         self.notify_queue_sender.send(task).await.map_err(Error::from)?;
 
+        if let Some(mut next) = next {
+            let due = next.due.unwrap().inner().to_string();
+            set_event(&mut next, "recur", &self.nickname, &due);
+            self.notify_queue_sender.send(next).await.map_err(Error::from)?;
+        }
+
         Ok(JsonValue::Boolean(true))
     }
 
@@ -494,7 +595,12 @@ impl JsonRpcInterface {
 
         let ws = self.workspace.lock().await.clone();
         let task: TaskInfo = self.load_task_by_ref_id(params[0].get::<String>().unwrap(), ws)?;
-        let task: JsonValue = (&task).into();
+        let blocked_by: Vec<JsonValue> =
+            task.blocked_by(&self.dataset_path).into_iter().map(JsonValue::String).collect();
+
+        let mut task: JsonValue = (&task).into();
+        let map = task.get_mut::<HashMap<String, JsonValue>>().unwrap();
+        map.insert("blocked_by".to_string(), JsonValue::Array(blocked_by));
 
         Ok(task)
     }
@@ -563,6 +669,52 @@ impl JsonRpcInterface {
         Ok(task)
     }
 
+    // RPCAPI:
+    // Search tasks of the current workspace, including stopped ones, by
+    // title, description, comments and tags. Results are ordered by
+    // relevance and paginated with `offset` and `limit`.
+    // --> {"jsonrpc": "2.0", "method": "search", "params": [query, offset, limit], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"total": n, "tasks": [task, ...]}, "id": 1}
+    async fn search(&self, params: JsonValue) -> TaudResult<JsonValue> {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        debug!(target: "tau", "JsonRpc::search() params {params:?}");
+
+        if params.len() != 3 ||
+            !params[0].is_string() ||
+            !params[1].is_number() ||
+            !params[2].is_number()
+        {
+            return Err(TaudError::InvalidData("len of params should be 3".into()))
+        }
+
+        let query = params[0].get::<String>().unwrap();
+        let offset = *params[1].get::<f64>().unwrap() as usize;
+        let limit = *params[2].get::<f64>().unwrap() as usize;
+
+        let ws = self.workspace.lock().await.clone();
+
+        let mut tasks = vec![];
+        for (ref_id, score) in self.search_index.search(query)? {
+            let Ok(task) = TaskInfo::load(&ref_id, &self.dataset_path) else { continue };
+            if task.workspace != ws {
+                continue
+            }
+
+            let mut task: JsonValue = (&task).into();
+            let map = task.get_mut::<HashMap<String, JsonValue>>().unwrap();
+            map.insert("score".to_string(), JsonValue::Number(score));
+            tasks.push(task);
+        }
+
+        let total = tasks.len();
+        let tasks: Vec<JsonValue> = tasks.into_iter().skip(offset).take(limit).collect();
+
+        Ok(JsonValue::Object(HashMap::from([
+            ("total".to_string(), JsonValue::Number(total as f64)),
+            ("tasks".to_string(), JsonValue::Array(tasks)),
+        ])))
+    }
+
     // RPCAPI:
     // Switch tasks workspace.
     // --> {"jsonrpc": "2.0", "method": "switch_ws", "params": [workspace], "id": 1}
@@ -728,6 +880,20 @@ impl JsonRpcInterface {
             }
         }
 
+        if fields.contains_key("recurrence") {
+            match &fields["recurrence"] {
+                JsonValue::Null => {
+                    task.set_recurrence(None);
+                    set_event(&mut task, "recurrence", &self.nickname, "None")
+                }
+                v => {
+                    let recurrence = Recurrence::try_from(v)?;
+                    task.set_recurrence(Some(recurrence));
+                    set_event(&mut task, "recurrence", &self.nickname, &recurrence.to_string())
+                }
+            }
+        }
+
         if fields.contains_key("assign") {
             let assign: Vec<String> = fields["assign"]
                 .get::<Vec<JsonValue>>()
@@ -770,6 +936,21 @@ impl JsonRpcInterface {
             }
         }
 
+        if let Some(deps) = fields.get("depends_on") {
+            let invalid = || TaudError::InvalidData("Invalid parameter \"depends_on\"".to_string());
+            let deps = deps.get::<Vec<JsonValue>>().ok_or_else(invalid)?;
+            let depends_on = deps
+                .iter()
+                .map(|dep| dep.get::<String>().cloned().ok_or_else(invalid))
+                .collect::<TaudResult<Vec<String>>>()?;
+
+            if !depends_on.is_empty() {
+                task.set_depends_on(&depends_on)?;
+                task.check_dependencies(&self.dataset_path)?;
+                set_event(&mut task, "depends_on", &self.nickname, &depends_on.join(", "));
+            }
+        }
+
         Ok(task)
     }
 }
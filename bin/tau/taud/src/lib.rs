@@ -18,5 +18,6 @@
 
 pub mod error;
 pub mod month_tasks;
+pub mod search;
 pub mod task_info;
 pub mod util;
@@ -47,15 +47,20 @@ use darkfi::{
     async_daemonize,
     event_graph::{
         proto::{EventPut, ProtocolEventGraph},
+        retention::RetentionPolicy,
         Event, EventGraph, EventGraphPtr,
     },
     net::{session::SESSION_DEFAULT, P2p, P2pPtr},
     rpc::{
         jsonrpc::JsonSubscriber,
+        p2p_method::forward_p2p_events,
         server::{listen_and_serve, RequestHandler},
     },
     system::{sleep, StoppableTask},
-    util::path::{expand_path, get_config_path},
+    util::{
+        path::{expand_path, get_config_path},
+        time::Timestamp,
+    },
     Error, Result,
 };
 
@@ -69,6 +74,8 @@ mod settings;
 
 use taud::{
     error::{TaudError, TaudResult},
+    month_tasks::MonthTasks,
+    search::SearchIndex,
     task_info::{TaskEvent, TaskInfo},
     util::pipe_write,
 };
@@ -108,8 +115,8 @@ struct SignedTask {
 }
 
 impl SignedTask {
-    fn new(task: &TaskInfo, signature: Signature) -> Self {
-        Self { task: serialize(task), signature }
+    fn new(task: Vec<u8>, signature: Signature) -> Self {
+        Self { task, signature }
     }
 }
 
@@ -119,7 +126,8 @@ fn encrypt_sign_task(task: &TaskInfo, workspace: &Workspace) -> TaudResult<Encry
     if workspace.write_key.is_none() {
         error!(target: "taud", "You don't have write access")
     }
-    let signature: Signature = workspace.write_key.as_ref().unwrap().sign(&serialize(task)[..]);
+    let task = task.to_payload();
+    let signature: Signature = workspace.write_key.as_ref().unwrap().sign(&task[..]);
     let signed_task = SignedTask::new(task, signature);
 
     let nonce = ChaChaBox::generate_nonce(&mut OsRng);
@@ -300,6 +308,7 @@ async fn start_sync_loop(
     settings: Args,
     p2p: P2pPtr,
     seen: OnceLock<sled::Tree>,
+    search_index: Arc<SearchIndex>,
 ) -> TaudResult<()> {
     let incoming = event_graph.event_pub.clone().subscribe().await;
 
@@ -345,19 +354,21 @@ async fn start_sync_loop(
                         continue
                     }
                 };
-                on_receive_task(&enc_task, &workspaces, &settings)
-                    .await?;
+                if let Err(e) = on_receive_task(&enc_task, &workspaces, &settings, &search_index).await {
+                    error!(target: "taud", "[TAUD] Failed handling incoming task event {event_id}: {e}");
+                }
             }
         }
     }
 }
 
 /// Handle a received task, decrypt it, verify it, optionally write it
-/// to a named pipe and save it on disk.
+/// to a named pipe, save it on disk and index it for search.
 async fn on_receive_task(
     enc_task: &EncryptedTask,
     workspaces: &HashMap<String, Workspace>,
     settings: &Args,
+    search_index: &SearchIndex,
 ) -> TaudResult<()> {
     for (ws_name, workspace) in workspaces.iter() {
         let signed_task = try_decrypt_task(enc_task, &workspace.read_key);
@@ -375,7 +386,15 @@ async fn on_receive_task(
             continue
         }
 
-        let mut task: TaskInfo = deserialize(&signed_task.unwrap().task)?;
+        // Tasks we can't decode, e.g. from a newer payload version,
+        // are skipped so they don't stop the rest from syncing.
+        let mut task = match TaskInfo::from_payload(&signed_task.unwrap().task) {
+            Ok(task) => task,
+            Err(e) => {
+                error!(target: "taud", "Unable to decode the task: {e}");
+                continue
+            }
+        };
         info!(target: "taud", "Save the task: ref: {}", task.ref_id);
         task.workspace.clone_from(ws_name);
         let datastore_path = expand_path(&settings.datastore)?;
@@ -416,10 +435,55 @@ async fn on_receive_task(
         }
 
         task.save(&datastore_path)?;
+        search_index.index(&task)?;
     }
     Ok(())
 }
 
+/// How often the reminder loop checks for upcoming due dates, in seconds
+const REMINDER_INTERVAL: u64 = 60;
+
+/// Periodically look for open tasks which become due within
+/// `remind_before` seconds and notify the reminder subscribers.
+/// Every (task, due date) pair is only reminded once, so changing
+/// the due date of a task schedules a new reminder.
+async fn start_reminder_loop(
+    workspaces: Arc<HashMap<String, Workspace>>,
+    settings: Args,
+    reminded: sled::Tree,
+    reminder_sub: JsonSubscriber,
+) -> TaudResult<()> {
+    let datastore_path = expand_path(&settings.datastore)?;
+
+    loop {
+        let now = Timestamp::current_time().inner();
+
+        for ws in workspaces.keys() {
+            let tasks = MonthTasks::load_current_tasks(&datastore_path, ws.clone(), false)?;
+
+            for task in tasks {
+                let Some(due) = task.due else { continue };
+                if task.get_state() == "stop" || due.inner() > now + settings.remind_before {
+                    continue
+                }
+
+                let mut key = task.ref_id.as_bytes().to_vec();
+                key.extend_from_slice(&due.inner().to_be_bytes());
+                if reminded.contains_key(&key).map_err(Error::from)? {
+                    continue
+                }
+
+                info!(target: "taud", "Sending reminder for task: ref: {}", task.ref_id);
+                let json: JsonValue = (&task).into();
+                reminder_sub.notify(vec![json].into()).await;
+                reminded.insert(key, &[]).map_err(Error::from)?;
+            }
+        }
+
+        sleep(REMINDER_INTERVAL).await;
+    }
+}
+
 async_daemonize!(realmain);
 async fn realmain(settings: Args, executor: Arc<smol::Executor<'static>>) -> Result<()> {
     let datastore_path = expand_path(&settings.datastore)?;
@@ -526,6 +590,7 @@ async fn realmain(settings: Args, executor: Arc<smol::Executor<'static>>) -> Res
         replay_mode,
         "taud_dag",
         0,
+        RetentionPolicy::default(),
         executor.clone(),
     )
     .await?;
@@ -575,6 +640,15 @@ async fn realmain(settings: Args, executor: Arc<smol::Executor<'static>>) -> Res
     let seen = OnceLock::new();
     seen.set(sled_db.open_tree("tau_seen").unwrap()).unwrap();
 
+    // Tasks saved before search was introduced are not indexed yet
+    let search_index = Arc::new(SearchIndex::new(&sled_db)?);
+    if search_index.is_empty() {
+        info!(target: "taud", "Building the search index");
+        if let Err(e) = search_index.rebuild(&datastore_path) {
+            error!(target: "taud", "Failed building the search index: {e}");
+        }
+    }
+
     ////////////////////
     // get history
     ////////////////////
@@ -592,7 +666,9 @@ async fn realmain(settings: Args, executor: Arc<smol::Executor<'static>>) -> Res
         let Ok((enc_task, _)) = deserialize_async_partial(event.content()).await else { continue };
 
         // Potentially decrypt the privmsg
-        on_receive_task(&enc_task, &workspaces, &settings).await.unwrap();
+        if let Err(e) = on_receive_task(&enc_task, &workspaces, &settings, &search_index).await {
+            error!(target: "taud", "Failed handling task event {event_id} from history: {e}");
+        }
     }
 
     ////////////////////
@@ -610,6 +686,7 @@ async fn realmain(settings: Args, executor: Arc<smol::Executor<'static>>) -> Res
             settings.clone(),
             p2p.clone(),
             seen.clone(),
+            search_index.clone(),
         ),
         |res| async {
             match res {
@@ -650,6 +727,21 @@ async fn realmain(settings: Args, executor: Arc<smol::Executor<'static>>) -> Res
         executor.clone(),
     );
 
+    info!(target: "taud", "Starting P2P events subs task");
+    let p2p_sub = JsonSubscriber::new("p2p.subscribe_events");
+    let p2p_events_task = StoppableTask::new();
+    p2p_events_task.clone().start(
+        forward_p2p_events(p2p.clone(), p2p_sub.clone()),
+        |res| async {
+            match res {
+                Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                Err(e) => error!(target: "taud", "Failed stopping P2P events subs task: {e}"),
+            }
+        },
+        Error::DetachedTaskStopped,
+        executor.clone(),
+    );
+
     info!("Starting deg subs task");
     let deg_sub = JsonSubscriber::new("deg.subscribe_events");
     let deg_sub_ = deg_sub.clone();
@@ -674,6 +766,26 @@ async fn realmain(settings: Args, executor: Arc<smol::Executor<'static>>) -> Res
         executor.clone(),
     );
 
+    info!(target: "taud", "Starting reminder task");
+    let reminder_sub = JsonSubscriber::new("reminder.subscribe");
+    let reminder_task = StoppableTask::new();
+    reminder_task.clone().start(
+        start_reminder_loop(
+            workspaces.clone(),
+            settings.clone(),
+            sled_db.open_tree("tau_reminded")?,
+            reminder_sub.clone(),
+        ),
+        |res| async {
+            match res {
+                Ok(()) | Err(TaudError::Darkfi(Error::DetachedTaskStopped)) => { /* Do nothing */ }
+                Err(e) => error!(target: "taud", "Failed stopping reminder task: {e}"),
+            }
+        },
+        TaudError::Darkfi(Error::DetachedTaskStopped),
+        executor.clone(),
+    );
+
     //
     // RPC interface
     //
@@ -685,7 +797,10 @@ async fn realmain(settings: Args, executor: Arc<smol::Executor<'static>>) -> Res
         p2p.clone(),
         event_graph.clone(),
         json_sub,
+        p2p_sub,
         deg_sub,
+        reminder_sub,
+        search_index,
     ));
     let rpc_task = StoppableTask::new();
     rpc_task.clone().start(
@@ -711,9 +826,13 @@ async fn realmain(settings: Args, executor: Arc<smol::Executor<'static>>) -> Res
     info!(target: "taud", "Stopping sync loop task...");
     sync_loop_task.stop().await;
 
+    info!(target: "taud", "Stopping reminder task...");
+    reminder_task.stop().await;
+
     info!(target: "taud", "Stopping JSON-RPC server...");
     rpc_task.stop().await;
     dnet_task.stop().await;
+    p2p_events_task.stop().await;
     deg_task.stop().await;
 
     info!(target: "taud", "Flushing sled database...");
@@ -0,0 +1,219 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Full-text search over tasks.
+//!
+//! Titles, tags, descriptions and comments are split into lowercase
+//! terms and kept in an inverted index persisted in sled. Results are
+//! ranked by the number of matched query terms first, then by a
+//! tf-idf score where title and tag hits weigh more than text hits.
+
+use std::{collections::HashMap, fs, path::Path};
+
+use darkfi_serial::{deserialize, serialize};
+use log::{debug, warn};
+use sled_overlay::sled;
+
+use darkfi::{Error, Result};
+
+use crate::{error::TaudResult, task_info::TaskInfo};
+
+const TITLE_WEIGHT: u32 = 3;
+const TAG_WEIGHT: u32 = 2;
+const TEXT_WEIGHT: u32 = 1;
+
+pub struct SearchIndex {
+    /// `term || 0x00 || ref_id` => weighted term frequency
+    postings: sled::Tree,
+    /// `ref_id` => terms the task is indexed under
+    documents: sled::Tree,
+}
+
+impl SearchIndex {
+    pub fn new(sled_db: &sled::Db) -> Result<Self> {
+        let postings = sled_db.open_tree("tau_search_postings")?;
+        let documents = sled_db.open_tree("tau_search_documents")?;
+        Ok(Self { postings, documents })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// Index every task found in the datastore.
+    pub fn rebuild(&self, dataset_path: &Path) -> TaudResult<()> {
+        debug!(target: "tau", "SearchIndex::rebuild()");
+        for entry in fs::read_dir(dataset_path.join("task"))? {
+            let ref_id = entry?.file_name().to_string_lossy().to_string();
+            match TaskInfo::load(&ref_id, dataset_path) {
+                Ok(task) => self.index(&task)?,
+                Err(e) => warn!(target: "tau", "Unable to index task {ref_id}: {e}"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Index a task, replacing whatever was indexed for it before.
+    pub fn index(&self, task: &TaskInfo) -> TaudResult<()> {
+        debug!(target: "tau", "SearchIndex::index()");
+        let mut terms: HashMap<String, u32> = HashMap::new();
+        let mut add = |text: &str, weight: u32| {
+            for term in tokenize(text) {
+                *terms.entry(term).or_default() += weight;
+            }
+        };
+
+        add(&task.title, TITLE_WEIGHT);
+        for tag in task.tags.iter() {
+            add(tag, TAG_WEIGHT);
+        }
+        add(&task.desc, TEXT_WEIGHT);
+        for comment in task.comments.iter() {
+            add(comment.content(), TEXT_WEIGHT);
+        }
+
+        let mut batch = self.remove_batch(&task.ref_id)?;
+        for (term, freq) in terms.iter() {
+            batch.insert(posting_key(term, &task.ref_id), freq.to_be_bytes().to_vec());
+        }
+        self.postings.apply_batch(batch).map_err(Error::from)?;
+
+        let terms: Vec<String> = terms.into_keys().collect();
+        self.documents.insert(task.ref_id.as_bytes(), serialize(&terms)).map_err(Error::from)?;
+        Ok(())
+    }
+
+    /// Build a batch removing the postings of a previously indexed task.
+    fn remove_batch(&self, ref_id: &str) -> TaudResult<sled::Batch> {
+        let mut batch = sled::Batch::default();
+        let Some(terms) = self.documents.get(ref_id.as_bytes()).map_err(Error::from)? else {
+            return Ok(batch)
+        };
+
+        let terms: Vec<String> = deserialize(&terms)?;
+        for term in terms.iter() {
+            batch.remove(posting_key(term, ref_id));
+        }
+        Ok(batch)
+    }
+
+    /// Returns the ref IDs of the tasks matching any of the query terms
+    /// together with their score, best matches first.
+    pub fn search(&self, query: &str) -> TaudResult<Vec<(String, f64)>> {
+        debug!(target: "tau", "SearchIndex::search()");
+        let mut terms = tokenize(query);
+        terms.sort();
+        terms.dedup();
+
+        let n_docs = self.documents.len() as f64;
+        let mut matches: HashMap<String, (usize, f64)> = HashMap::new();
+
+        for term in terms.iter() {
+            let mut prefix = term.as_bytes().to_vec();
+            prefix.push(0);
+
+            let mut postings = vec![];
+            for item in self.postings.scan_prefix(&prefix) {
+                let (key, value) = item.map_err(Error::from)?;
+                let ref_id = String::from_utf8_lossy(&key[prefix.len()..]).to_string();
+                let freq = u32::from_be_bytes(value.as_ref().try_into().unwrap());
+                postings.push((ref_id, freq));
+            }
+
+            if postings.is_empty() {
+                continue
+            }
+
+            let idf = (1.0 + n_docs / postings.len() as f64).ln();
+            for (ref_id, freq) in postings {
+                let entry = matches.entry(ref_id).or_default();
+                entry.0 += 1;
+                entry.1 += freq as f64 * idf;
+            }
+        }
+
+        let mut results: Vec<(String, usize, f64)> =
+            matches.into_iter().map(|(ref_id, (hits, score))| (ref_id, hits, score)).collect();
+        results.sort_by(|a, b| b.1.cmp(&a.1).then(b.2.total_cmp(&a.2)).then(a.0.cmp(&b.0)));
+
+        Ok(results.into_iter().map(|(ref_id, _, score)| (ref_id, score)).collect())
+    }
+}
+
+fn posting_key(term: &str, ref_id: &str) -> Vec<u8> {
+    let mut key = term.as_bytes().to_vec();
+    key.push(0);
+    key.extend_from_slice(ref_id.as_bytes());
+    key
+}
+
+/// Split text into lowercase alphanumeric terms, skipping single characters.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() > 1)
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task_info::Comment;
+    use darkfi::util::time::Timestamp;
+
+    fn task(title: &str, desc: &str) -> TaskInfo {
+        TaskInfo::new(
+            "darkfi".to_string(),
+            title,
+            desc,
+            "NICKNAME",
+            None,
+            None,
+            Timestamp::current_time(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn search_ranking() -> TaudResult<()> {
+        let sled_db = sled::Config::new().temporary(true).open().map_err(Error::from)?;
+        let index = SearchIndex::new(&sled_db)?;
+
+        let mut a = task("Fix the p2p handshake", "Peers drop the connection");
+        let b = task("Write docs", "Document the p2p handshake timeout");
+        let c = task("Release", "Tag a new version");
+        index.index(&a)?;
+        index.index(&b)?;
+        index.index(&c)?;
+
+        // Title hits rank above description hits
+        let results = index.search("P2P handshake")?;
+        let ids: Vec<&str> = results.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, vec![a.ref_id.as_str(), b.ref_id.as_str()]);
+
+        // Reindexing replaces the old terms
+        a.set_title("Fix peer discovery");
+        a.set_comment(Comment::new("seems related to seeds", "NICKNAME"));
+        index.index(&a)?;
+        assert_eq!(index.search("handshake")?.len(), 1);
+        assert_eq!(index.search("seeds")?[0].0, a.ref_id);
+        assert!(index.search("nothing")?.is_empty());
+
+        Ok(())
+    }
+}
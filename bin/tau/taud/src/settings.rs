@@ -94,7 +94,15 @@ pub struct Args {
     // Whether to pipe notifications or not
     pub piped: bool,
 
+    #[structopt(long, default_value = "3600")]
+    /// Seconds before a task's due date to send a reminder
+    pub remind_before: u64,
+
     #[structopt(short, long)]
     /// Set log file to ouput into
     pub log: Option<String>,
+
+    #[structopt(long)]
+    /// Serve Prometheus metrics on this URL, e.g. tcp://127.0.0.1:9100
+    pub metrics_listen: Option<String>,
 }
@@ -17,20 +17,20 @@
  */
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt,
     path::{Path, PathBuf},
     str::FromStr,
 };
 
-use darkfi_serial::{async_trait, SerialDecodable, SerialEncodable};
+use darkfi_serial::{async_trait, deserialize, serialize, SerialDecodable, SerialEncodable};
 use log::debug;
 use tinyjson::JsonValue;
 
 use darkfi::{
     util::{
         file::{load_json_file, save_json_file},
-        time::Timestamp,
+        time::{DateTime, Timestamp},
     },
     Error,
 };
@@ -179,6 +179,10 @@ impl From<JsonValue> for Comment {
 }
 
 impl Comment {
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
     pub fn new(content: &str, author: &str) -> Self {
         Self {
             content: content.into(),
@@ -188,6 +192,128 @@ impl Comment {
     }
 }
 
+/// How often a recurring task comes back
+#[derive(Clone, Copy, Debug, SerialEncodable, SerialDecodable, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl fmt::Display for Frequency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Frequency::Daily => write!(f, "daily"),
+            Frequency::Weekly => write!(f, "weekly"),
+            Frequency::Monthly => write!(f, "monthly"),
+        }
+    }
+}
+
+impl FromStr for Frequency {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let result = match s.to_lowercase().as_str() {
+            "daily" => Frequency::Daily,
+            "weekly" => Frequency::Weekly,
+            "monthly" => Frequency::Monthly,
+            _ => return Err(Error::ParseFailed("unable to parse frequency")),
+        };
+        Ok(result)
+    }
+}
+
+/// Recurrence rule of a task, e.g. every 2 weeks.
+#[derive(Clone, Copy, Debug, SerialEncodable, SerialDecodable, PartialEq, Eq)]
+pub struct Recurrence {
+    pub freq: Frequency,
+    pub interval: u32,
+}
+
+impl std::fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}/{}", self.freq, self.interval)
+    }
+}
+
+impl From<&Recurrence> for JsonValue {
+    fn from(recurrence: &Recurrence) -> JsonValue {
+        JsonValue::Object(HashMap::from([
+            ("freq".to_string(), JsonValue::String(recurrence.freq.to_string())),
+            ("interval".to_string(), JsonValue::Number(recurrence.interval.into())),
+        ]))
+    }
+}
+
+impl TryFrom<&JsonValue> for Recurrence {
+    type Error = TaudError;
+
+    fn try_from(value: &JsonValue) -> TaudResult<Recurrence> {
+        let invalid = || TaudError::InvalidData("Invalid parameter \"recurrence\"".to_string());
+
+        let Some(map) = value.get::<HashMap<String, JsonValue>>() else { return Err(invalid()) };
+        let Some(freq) = map.get("freq").and_then(|v| v.get::<String>()) else {
+            return Err(invalid())
+        };
+        let Some(interval) = map.get("interval").and_then(|v| v.get::<f64>()) else {
+            return Err(invalid())
+        };
+
+        let freq = Frequency::from_str(freq).map_err(|_| invalid())?;
+        if *interval < 1.0 || *interval > u32::MAX as f64 || interval.fract() != 0.0 {
+            return Err(invalid())
+        }
+
+        Ok(Recurrence { freq, interval: *interval as u32 })
+    }
+}
+
+impl Recurrence {
+    /// Returns the timestamp one interval after `ts`. Monthly recurrence
+    /// keeps the day of the month, clamped to the length of the target
+    /// month (e.g. Jan 31 is followed by Feb 28).
+    pub fn next(&self, ts: &Timestamp) -> Timestamp {
+        let secs = ts.inner();
+        let next = match self.freq {
+            Frequency::Daily => secs + self.interval as u64 * SECS_IN_DAY,
+            Frequency::Weekly => secs + self.interval as u64 * 7 * SECS_IN_DAY,
+            Frequency::Monthly => {
+                let dt = DateTime::from_timestamp(secs, 0);
+                let months = dt.month as u64 - 1 + self.interval as u64;
+                let year = dt.year as u64 + months / 12;
+                let month = months % 12 + 1;
+                let day = (dt.day as u64).min(days_in_month(year, month));
+                days_from_civil(year, month, day) * SECS_IN_DAY + secs % SECS_IN_DAY
+            }
+        };
+        Timestamp::from_u64(next)
+    }
+}
+
+const SECS_IN_DAY: u64 = 86400;
+
+fn days_in_month(year: u64, month: u64) -> u64 {
+    match month {
+        4 | 6 | 9 | 11 => 30,
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        _ => 31,
+    }
+}
+
+/// Days since the unix epoch for the given date in the proleptic
+/// Gregorian calendar.
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y / 400;
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
 #[derive(Clone, Debug, SerialEncodable, SerialDecodable, PartialEq)]
 pub struct TaskInfo {
     pub ref_id: String,
@@ -204,6 +330,61 @@ pub struct TaskInfo {
     pub state: String,
     pub events: Vec<TaskEvent>,
     pub comments: Vec<Comment>,
+    pub recurrence: Option<Recurrence>,
+    pub depends_on: Vec<String>,
+}
+
+/// Leading bytes of versioned task payloads. Tasks used to be signed and
+/// shared as a bare serialized [`TaskInfoV0`], which starts with the ref
+/// ID length as a `VarInt`. A leading `0xff` announces an 8 bytes length,
+/// which no ref ID has, so the two layouts can't be mistaken for each other.
+const TASK_PAYLOAD_MAGIC: [u8; 4] = [0xff, b't', b'a', b'u'];
+
+/// Task payload version, adding recurrence and dependencies to the
+/// original layout
+const TASK_PAYLOAD_VERSION: u8 = 1;
+
+/// Task layout shared over the event graph before payloads were versioned.
+/// Kept so tasks already in the DAG, or coming from older peers, still decode.
+#[derive(SerialEncodable, SerialDecodable)]
+struct TaskInfoV0 {
+    ref_id: String,
+    workspace: String,
+    title: String,
+    tags: Vec<String>,
+    desc: String,
+    owner: String,
+    assign: Vec<String>,
+    project: Vec<String>,
+    due: Option<Timestamp>,
+    rank: Option<f32>,
+    created_at: Timestamp,
+    state: String,
+    events: Vec<TaskEvent>,
+    comments: Vec<Comment>,
+}
+
+impl From<TaskInfoV0> for TaskInfo {
+    fn from(task: TaskInfoV0) -> TaskInfo {
+        TaskInfo {
+            ref_id: task.ref_id,
+            workspace: task.workspace,
+            title: task.title,
+            tags: task.tags,
+            desc: task.desc,
+            owner: task.owner,
+            assign: task.assign,
+            project: task.project,
+            due: task.due,
+            rank: task.rank,
+            created_at: task.created_at,
+            state: task.state,
+            events: task.events,
+            comments: task.comments,
+            recurrence: None,
+            depends_on: vec![],
+        }
+    }
 }
 
 impl From<&TaskInfo> for JsonValue {
@@ -237,6 +418,12 @@ impl From<&TaskInfo> for JsonValue {
         let state = JsonValue::String(task.state.clone());
         let events: Vec<JsonValue> = task.events.iter().map(|x| x.clone().into()).collect();
         let comments: Vec<JsonValue> = task.comments.iter().map(|x| x.clone().into()).collect();
+        let recurrence = match &task.recurrence {
+            Some(recurrence) => recurrence.into(),
+            None => JsonValue::Null,
+        };
+        let depends_on: Vec<JsonValue> =
+            task.depends_on.iter().map(|x| JsonValue::String(x.clone())).collect();
 
         JsonValue::Object(HashMap::from([
             ("ref_id".to_string(), ref_id),
@@ -253,6 +440,8 @@ impl From<&TaskInfo> for JsonValue {
             ("state".to_string(), state),
             ("events".to_string(), JsonValue::Array(events)),
             ("comments".to_string(), JsonValue::Array(comments)),
+            ("recurrence".to_string(), recurrence),
+            ("depends_on".to_string(), JsonValue::Array(depends_on)),
         ]))
     }
 }
@@ -287,6 +476,23 @@ impl From<JsonValue> for TaskInfo {
             Timestamp::from_u64(u64_str.parse::<u64>().unwrap())
         };
 
+        // Tasks saved before recurrence and dependencies were
+        // introduced have no such keys
+        let map = value.get::<HashMap<String, JsonValue>>().unwrap();
+        let recurrence = match map.get("recurrence") {
+            Some(v) if !v.is_null() => Some(v.try_into().unwrap()),
+            _ => None,
+        };
+        let depends_on = match map.get("depends_on") {
+            Some(v) => v
+                .get::<Vec<JsonValue>>()
+                .unwrap()
+                .iter()
+                .map(|x| x.get::<String>().unwrap().clone())
+                .collect(),
+            None => vec![],
+        };
+
         let events: Vec<TaskEvent> = events.iter().map(|x| x.into()).collect();
         let comments: Vec<Comment> = comments.iter().map(|x| (*x).clone().into()).collect();
 
@@ -305,11 +511,36 @@ impl From<JsonValue> for TaskInfo {
             state: value["state"].get::<String>().unwrap().clone(),
             events,
             comments,
+            recurrence,
+            depends_on,
         }
     }
 }
 
 impl TaskInfo {
+    /// Encode the task into the payload signed and shared over the event graph
+    pub fn to_payload(&self) -> Vec<u8> {
+        let mut payload = TASK_PAYLOAD_MAGIC.to_vec();
+        payload.push(TASK_PAYLOAD_VERSION);
+        payload.extend(serialize(self));
+        payload
+    }
+
+    /// Decode a task from an event graph payload of any known version
+    pub fn from_payload(payload: &[u8]) -> TaudResult<Self> {
+        let Some(versioned) = payload.strip_prefix(&TASK_PAYLOAD_MAGIC[..]) else {
+            return Ok(deserialize::<TaskInfoV0>(payload)?.into())
+        };
+
+        match versioned.split_first() {
+            Some((&TASK_PAYLOAD_VERSION, task)) => Ok(deserialize(task)?),
+            Some((version, _)) => {
+                Err(TaudError::InvalidData(format!("Unknown task payload version {version}")))
+            }
+            None => Err(TaudError::InvalidData("Empty task payload".to_string())),
+        }
+    }
+
     pub fn new(
         workspace: String,
         title: &str,
@@ -343,9 +574,70 @@ impl TaskInfo {
             state: "open".into(),
             comments: vec![],
             events: vec![],
+            recurrence: None,
+            depends_on: vec![],
         })
     }
 
+    /// Create the next occurrence of a recurring task. The new task is
+    /// due one interval after this one, skipping occurrences that are
+    /// already in the past.
+    pub fn next_occurrence(&self) -> Option<Self> {
+        let recurrence = self.recurrence?;
+        let now = Timestamp::current_time();
+
+        let mut due = recurrence.next(&self.due.unwrap_or(now));
+        while due < now {
+            due = recurrence.next(&due);
+        }
+
+        let mut task = self.clone();
+        task.ref_id = gen_id(30);
+        task.due = Some(due);
+        task.created_at = now;
+        task.state = "open".into();
+        task.events = vec![];
+        task.comments = vec![];
+        Some(task)
+    }
+
+    /// Returns the dependencies of this task which are not stopped yet.
+    /// Dependencies we don't know about (e.g. not synced yet) also count
+    /// as blocking.
+    pub fn blocked_by(&self, dataset_path: &Path) -> Vec<String> {
+        debug!(target: "tau", "TaskInfo::blocked_by()");
+        self.depends_on
+            .iter()
+            .filter(|dep| match Self::load(dep, dataset_path) {
+                Ok(task) => task.get_state() != "stop",
+                Err(_) => true,
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Make sure every dependency exists and that following them never
+    /// leads back to this task.
+    pub fn check_dependencies(&self, dataset_path: &Path) -> TaudResult<()> {
+        debug!(target: "tau", "TaskInfo::check_dependencies()");
+        let mut visited = HashSet::new();
+        let mut stack = self.depends_on.clone();
+
+        while let Some(ref_id) = stack.pop() {
+            if ref_id == self.ref_id {
+                return Err(TaudError::DependencyCycle)
+            }
+            if !visited.insert(ref_id.clone()) {
+                continue
+            }
+
+            let task = Self::load(&ref_id, dataset_path).map_err(|_| TaudError::InvalidId)?;
+            stack.extend(task.depends_on);
+        }
+
+        Ok(())
+    }
+
     pub fn load(ref_id: &str, dataset_path: &Path) -> TaudResult<Self> {
         debug!(target: "tau", "TaskInfo::load()");
         let task = load_json_file(&Self::get_path(ref_id, dataset_path))?;
@@ -452,6 +744,37 @@ impl TaskInfo {
         self.due = d;
     }
 
+    /// Add (`+ref_id`) or remove (`-ref_id`) dependencies. Nothing is
+    /// changed if any of the entries is malformed.
+    pub fn set_depends_on(&mut self, deps: &[String]) -> TaudResult<()> {
+        debug!(target: "tau", "TaskInfo::set_depends_on()");
+        let mut changes = Vec::with_capacity(deps.len());
+        for dep in deps.iter() {
+            let change = match (dep.strip_prefix('+'), dep.strip_prefix('-')) {
+                (Some(ref_id), _) if !ref_id.is_empty() => (true, ref_id),
+                (_, Some(ref_id)) if !ref_id.is_empty() => (false, ref_id),
+                _ => return Err(TaudError::InvalidData(format!("Invalid dependency \"{dep}\""))),
+            };
+            changes.push(change);
+        }
+
+        for (add, ref_id) in changes {
+            if add && !self.depends_on.iter().any(|dep| dep == ref_id) {
+                self.depends_on.push(ref_id.to_string());
+            }
+            if !add {
+                self.depends_on.retain(|dep| dep != ref_id);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn set_recurrence(&mut self, r: Option<Recurrence>) {
+        debug!(target: "tau", "TaskInfo::set_recurrence()");
+        self.recurrence = r;
+    }
+
     pub fn set_state(&mut self, state: &str) {
         debug!(target: "tau", "TaskInfo::set_state()");
         if self.get_state() == state {
@@ -460,3 +783,99 @@ impl TaskInfo {
         self.state = state.to_string();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recurrence_next() {
+        // 2024-01-31 18:00:00 UTC
+        let ts = Timestamp::from_u64(1706724000);
+
+        let daily = Recurrence { freq: Frequency::Daily, interval: 2 };
+        assert_eq!(daily.next(&ts).inner(), 1706724000 + 2 * 86400);
+
+        let weekly = Recurrence { freq: Frequency::Weekly, interval: 1 };
+        assert_eq!(weekly.next(&ts).inner(), 1706724000 + 7 * 86400);
+
+        // Clamped to 2024-02-29 18:00:00 UTC
+        let monthly = Recurrence { freq: Frequency::Monthly, interval: 1 };
+        assert_eq!(monthly.next(&ts).inner(), 1709229600);
+
+        // Rolls over into 2025-01-31 18:00:00 UTC
+        let yearly = Recurrence { freq: Frequency::Monthly, interval: 12 };
+        assert_eq!(yearly.next(&ts).inner(), 1738346400);
+    }
+
+    #[test]
+    fn task_payload_versions() {
+        let mut task = TaskInfo::new(
+            "darkfi".to_string(),
+            "title",
+            "desc",
+            "owner",
+            None,
+            None,
+            Timestamp::current_time(),
+        )
+        .unwrap();
+        task.recurrence = Some(Recurrence { freq: Frequency::Weekly, interval: 1 });
+        task.set_depends_on(&["+blocker".to_string()]).unwrap();
+
+        // Current payloads round-trip with all their fields
+        assert_eq!(TaskInfo::from_payload(&task.to_payload()).unwrap(), task);
+
+        // Payloads from before versioning still decode
+        let legacy = TaskInfoV0 {
+            ref_id: task.ref_id.clone(),
+            workspace: task.workspace.clone(),
+            title: task.title.clone(),
+            tags: task.tags.clone(),
+            desc: task.desc.clone(),
+            owner: task.owner.clone(),
+            assign: task.assign.clone(),
+            project: task.project.clone(),
+            due: task.due,
+            rank: task.rank,
+            created_at: task.created_at,
+            state: task.state.clone(),
+            events: task.events.clone(),
+            comments: task.comments.clone(),
+        };
+        let decoded = TaskInfo::from_payload(&serialize(&legacy)).unwrap();
+        task.recurrence = None;
+        task.depends_on = vec![];
+        assert_eq!(decoded, task);
+
+        // Unknown versions are rejected instead of misread
+        let mut payload = task.to_payload();
+        payload[TASK_PAYLOAD_MAGIC.len()] = TASK_PAYLOAD_VERSION + 1;
+        assert!(TaskInfo::from_payload(&payload).is_err());
+    }
+
+    #[test]
+    fn task_depends_on() {
+        let mut task = TaskInfo::new(
+            "darkfi".to_string(),
+            "title",
+            "desc",
+            "owner",
+            None,
+            None,
+            Timestamp::current_time(),
+        )
+        .unwrap();
+
+        task.set_depends_on(&["+a".to_string(), "+b".to_string(), "+a".to_string()]).unwrap();
+        assert_eq!(task.depends_on, vec!["a", "b"]);
+        task.set_depends_on(&["-a".to_string()]).unwrap();
+        assert_eq!(task.depends_on, vec!["b"]);
+
+        // Malformed entries are rejected without applying the others
+        for dep in ["", "+", "-", "c", "éc"] {
+            assert!(task.set_depends_on(&["+c".to_string(), dep.to_string()]).is_err());
+        }
+        assert_eq!(task.depends_on, vec!["b"]);
+    }
+}
@@ -17,9 +17,17 @@
  */
 
 use std::{
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
     process::{exit, ExitCode},
-    sync::{mpsc::channel, Arc},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::channel,
+        Arc,
+    },
     thread::available_parallelism,
+    time::{Duration, Instant},
 };
 
 use arg::Args;
@@ -29,8 +37,10 @@ use darkfi_sdk::crypto::{
     contract_id::MONEY_CONTRACT_ID, poseidon_hash, BaseBlind, ContractId, FuncRef, PublicKey,
     SecretKey,
 };
-use rand::rngs::OsRng;
-use rayon::iter::ParallelIterator;
+use rand::{rngs::OsRng, CryptoRng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use regex::{Regex, RegexBuilder};
 
 const ABOUT: &str =
     concat!("vanityaddr ", env!("CARGO_PKG_VERSION"), '\n', env!("CARGO_PKG_DESCRIPTION"));
@@ -42,13 +52,40 @@ Arguments:
   <PREFIX>    Prefixes to search
 
 Options:
+  -s    Suffix to search, can be repeated
+  -i    Substring to search, can be repeated
+  -r    Regular expression to search, can be repeated
+  -a    Require all patterns to match, instead of any
   -c    Make the search case-sensitive
   -t    Number of threads to use (defaults to number of available CPUs)
+  -k    Checkpoint file to periodically save progress to and resume from
+  -S    Seed to derive candidates from (defaults to a random one)
+  -n    Number of matches to find before exiting (defaults to 1)
+  -o    File to append every match to, as JSON lines
   -A    Search for an address
   -C    Search for a Contract ID
   -T    Search for a Token ID
+
+Patterns are matched against the base58 string.
+
+Candidates are derived from the seed and a counter, so a search
+interrupted with a checkpoint resumes exactly where it stopped.
+Anyone holding the seed can recompute the found secret, so keep
+the seed and the checkpoint file private.
 "#;
 
+/// Number of candidates searched between checkpoints
+const BATCH_SIZE: u64 = 1 << 16;
+
+/// Minimum time between checkpoint writes
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Length of a base58 encoded 32 byte value, used for estimates
+const B58_LEN: usize = 44;
+
+/// The base58 alphabet
+const B58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
 fn usage() {
     print!("{ANSI_LOGO}{ABOUT}\n{USAGE}");
 }
@@ -69,27 +106,139 @@ struct DrkContract {
     pub secret: SecretKey,
 }
 
-trait Prefixable {
-    fn new() -> Self;
-    fn to_string(&self) -> String;
-    fn _get_secret(&self) -> SecretKey;
+/// A pattern to search for in the base58 string
+enum Pattern {
+    Prefix(String),
+    Suffix(String),
+    Contains(String),
+    Regex(Regex),
+}
+
+impl Pattern {
+    /// Probability of a random base58 string matching the pattern.
+    /// Returns `None` for regexes, which we can't estimate.
+    fn probability(&self, case_sensitive: bool) -> Option<f64> {
+        let char_probability = |c: char| {
+            let n = if case_sensitive {
+                1
+            } else {
+                B58_ALPHABET.chars().filter(|a| a.to_ascii_lowercase() == c).count()
+            };
+            n as f64 / 58.0
+        };
+
+        let (s, positions) = match self {
+            Self::Prefix(s) | Self::Suffix(s) => (s, 1),
+            Self::Contains(s) => (s, B58_LEN.saturating_sub(s.len()) + 1),
+            Self::Regex(_) => return None,
+        };
+
+        let p: f64 = s.chars().map(char_probability).product();
+        Some((p * positions as f64).min(1.0))
+    }
+
+    fn matches(&self, s: &str) -> bool {
+        match self {
+            Self::Prefix(p) => s.starts_with(p.as_str()),
+            Self::Suffix(p) => s.ends_with(p.as_str()),
+            Self::Contains(p) => s.contains(p.as_str()),
+            Self::Regex(r) => r.is_match(s),
+        }
+    }
+}
+
+/// Set of patterns a search result has to match
+struct Matcher {
+    patterns: Vec<Pattern>,
+    /// Require all patterns to match, instead of any
+    all: bool,
+    case_sensitive: bool,
+}
+
+impl Matcher {
+    /// Build a matcher, validating the plain patterns are base58
+    /// and compiling the regular expressions.
+    fn new(
+        prefixes: &[String],
+        suffixes: &[String],
+        substrings: &[String],
+        regexes: &[String],
+        all: bool,
+        case_sensitive: bool,
+    ) -> Result<Self, String> {
+        let mut patterns = vec![];
+
+        let plain: [(&str, &[String], fn(String) -> Pattern); 3] = [
+            ("prefix", prefixes, Pattern::Prefix),
+            ("suffix", suffixes, Pattern::Suffix),
+            ("substring", substrings, Pattern::Contains),
+        ];
+        for (kind, values, pattern) in plain {
+            for (idx, value) in values.iter().enumerate() {
+                if let Err(e) = bs58::decode(value).into_vec() {
+                    return Err(format!("Invalid base58 for {kind} #{idx}: {e}"))
+                }
+                let value = if case_sensitive { value.clone() } else { value.to_lowercase() };
+                patterns.push(pattern(value));
+            }
+        }
+
+        for (idx, value) in regexes.iter().enumerate() {
+            match RegexBuilder::new(value).case_insensitive(!case_sensitive).build() {
+                Ok(r) => patterns.push(Pattern::Regex(r)),
+                Err(e) => return Err(format!("Invalid regex #{idx}: {e}")),
+            }
+        }
+
+        Ok(Self { patterns, all, case_sensitive })
+    }
 
-    fn starts_with(&self, prefix: &str, case_sensitive: bool) -> bool {
-        if case_sensitive {
-            self.to_string().starts_with(prefix)
+    /// Estimated number of attempts needed to find a match
+    fn expected_attempts(&self) -> Option<u64> {
+        let mut probabilities = vec![];
+        for pattern in &self.patterns {
+            probabilities.push(pattern.probability(self.case_sensitive)?);
+        }
+
+        let p = if self.all {
+            probabilities.iter().product()
         } else {
-            self.to_string().to_lowercase().starts_with(prefix.to_lowercase().as_str())
+            1.0 - probabilities.iter().map(|p| 1.0 - p).product::<f64>()
+        };
+
+        if p <= 0.0 {
+            return None
         }
+
+        Some((1.0 / p).ceil() as u64)
     }
 
-    fn starts_with_any(&self, prefixes: &[String], case_sensitive: bool) -> bool {
-        prefixes.iter().any(|prefix| self.starts_with(prefix, case_sensitive))
+    fn matches(&self, s: &str) -> bool {
+        // Regexes handle case themselves, lowercasing is harmless to them
+        let s = if self.case_sensitive { s.to_string() } else { s.to_lowercase() };
+
+        if self.all {
+            self.patterns.iter().all(|p| p.matches(&s))
+        } else {
+            self.patterns.iter().any(|p| p.matches(&s))
+        }
+    }
+}
+
+trait Searchable {
+    fn new(rng: &mut (impl CryptoRng + RngCore)) -> Self;
+    fn to_string(&self) -> String;
+    fn to_json(&self, attempts: u64) -> String;
+    fn _get_secret(&self) -> SecretKey;
+
+    fn matches(&self, matcher: &Matcher) -> bool {
+        matcher.matches(&self.to_string())
     }
 }
 
-impl Prefixable for DrkAddr {
-    fn new() -> Self {
-        let secret = SecretKey::random(&mut OsRng);
+impl Searchable for DrkAddr {
+    fn new(rng: &mut (impl CryptoRng + RngCore)) -> Self {
+        let secret = SecretKey::random(rng);
         let public = PublicKey::from_secret(secret);
         Self { public, secret }
     }
@@ -98,16 +247,23 @@ impl Prefixable for DrkAddr {
         self.public.to_string()
     }
 
+    fn to_json(&self, attempts: u64) -> String {
+        format!(
+            "{{\"address\":\"{}\",\"attempts\":{attempts},\"secret\":\"{}\"}}",
+            self.public, self.secret,
+        )
+    }
+
     fn _get_secret(&self) -> SecretKey {
         self.secret
     }
 }
 
-impl Prefixable for DrkToken {
-    fn new() -> Self {
+impl Searchable for DrkToken {
+    fn new(rng: &mut (impl CryptoRng + RngCore)) -> Self {
         // Generate the mint authority secret key and blind
-        let secret = SecretKey::random(&mut OsRng);
-        let blind = BaseBlind::random(&mut OsRng);
+        let secret = SecretKey::random(rng);
+        let blind = BaseBlind::random(rng);
 
         // Create the Auth FuncID
         let func_id = FuncRef {
@@ -130,14 +286,21 @@ impl Prefixable for DrkToken {
         self.token_id.to_string()
     }
 
+    fn to_json(&self, attempts: u64) -> String {
+        format!(
+            "{{\"token_id\":\"{}\",\"attempts\":{attempts},\"secret\":\"{}\",\"blind\":\"{}\"}}",
+            self.token_id, self.secret, self.blind
+        )
+    }
+
     fn _get_secret(&self) -> SecretKey {
         self.secret
     }
 }
 
-impl Prefixable for DrkContract {
-    fn new() -> Self {
-        let secret = SecretKey::random(&mut OsRng);
+impl Searchable for DrkContract {
+    fn new(rng: &mut (impl CryptoRng + RngCore)) -> Self {
+        let secret = SecretKey::random(rng);
         let contract_id = ContractId::derive(secret);
         Self { contract_id, secret }
     }
@@ -146,11 +309,146 @@ impl Prefixable for DrkContract {
         self.contract_id.to_string()
     }
 
+    fn to_json(&self, attempts: u64) -> String {
+        format!(
+            "{{\"contract_id\":\"{}\",\"attempts\":{attempts},\"secret\":\"{}\"}}",
+            self.contract_id, self.secret,
+        )
+    }
+
     fn _get_secret(&self) -> SecretKey {
         self.secret
     }
 }
 
+/// Deterministic search state, persisted in the checkpoint file
+struct SearchState {
+    /// User-provided or random seed string
+    seed: String,
+    /// Hash of the seed, used as the key for deriving candidates
+    key: [u8; 32],
+    /// Description of the search, so a checkpoint can't be resumed
+    /// with different parameters
+    search: String,
+    /// Index of the first candidate not fully searched yet
+    counter: AtomicU64,
+    /// Number of matches found so far
+    found: AtomicU64,
+}
+
+impl SearchState {
+    fn new(seed: String, search: String, counter: u64, found: u64) -> Self {
+        let key = *blake3::hash(seed.as_bytes()).as_bytes();
+        Self { seed, key, search, counter: AtomicU64::new(counter), found: AtomicU64::new(found) }
+    }
+
+    /// Load the search state from a checkpoint file, if it exists
+    fn load(path: &Path) -> Result<Option<Self>, String> {
+        if !path.exists() {
+            return Ok(None)
+        }
+
+        let data = fs::read_to_string(path)
+            .map_err(|e| format!("Failed reading checkpoint {}: {e}", path.display()))?;
+
+        let (mut seed, mut counter, mut found, mut search) = (None, None, None, None);
+        for line in data.lines() {
+            match line.split_once(' ') {
+                Some(("seed", v)) => seed = Some(v.to_string()),
+                Some(("counter", v)) => counter = v.parse::<u64>().ok(),
+                Some(("found", v)) => found = v.parse::<u64>().ok(),
+                Some(("search", v)) => search = Some(v.to_string()),
+                _ => return Err(format!("Invalid checkpoint line: {line}")),
+            }
+        }
+
+        let (Some(seed), Some(counter), Some(found), Some(search)) = (seed, counter, found, search)
+        else {
+            return Err(format!("Incomplete checkpoint {}", path.display()))
+        };
+
+        Ok(Some(Self::new(seed, search, counter, found)))
+    }
+
+    /// Write the search state to a checkpoint file. The file is replaced
+    /// atomically so an interruption can't leave it half written.
+    fn save(&self, path: &Path) -> Result<(), String> {
+        let data = format!(
+            "seed {}\ncounter {}\nfound {}\nsearch {}\n",
+            self.seed,
+            self.counter.load(Ordering::SeqCst),
+            self.found.load(Ordering::SeqCst),
+            self.search
+        );
+
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, data)
+            .and_then(|_| fs::rename(&tmp, path))
+            .map_err(|e| format!("Failed writing checkpoint {}: {e}", path.display()))
+    }
+
+    /// Derive the RNG for the candidate at the given index
+    fn rng(&self, index: u64) -> ChaCha20Rng {
+        ChaCha20Rng::from_seed(*blake3::keyed_hash(&self.key, &index.to_le_bytes()).as_bytes())
+    }
+}
+
+/// Search candidates in batches until `count` matches are found,
+/// periodically saving the checkpoint. Every match is printed and
+/// appended to the output file, if any, as a JSON line.
+fn search<T: Searchable + Send>(
+    matcher: &Matcher,
+    state: &SearchState,
+    count: u64,
+    checkpoint: Option<&Path>,
+    mut output: Option<File>,
+    progress: &ProgressInc,
+) -> Result<(), String> {
+    let mut last_save = Instant::now();
+
+    while state.found.load(Ordering::SeqCst) < count {
+        let start = state.counter.load(Ordering::SeqCst);
+        let remaining = count - state.found.load(Ordering::SeqCst);
+
+        // Matches are handled in index order, so the results are
+        // reproducible for a given seed.
+        let mut matches: Vec<(u64, T)> = (start..start + BATCH_SIZE)
+            .into_par_iter()
+            .inspect(|_| progress.inc(1))
+            .map(|index| (index, T::new(&mut state.rng(index))))
+            .filter(|(_, candidate)| candidate.matches(matcher))
+            .collect();
+        matches.sort_unstable_by_key(|(index, _)| *index);
+        matches.truncate(remaining as usize);
+
+        for (index, candidate) in &matches {
+            let line = candidate.to_json(index + 1);
+            eprint!("\r\x1b[2K");
+            println!("{line}");
+
+            if let Some(file) = output.as_mut() {
+                writeln!(file, "{line}").map_err(|e| format!("Failed writing output: {e}"))?;
+            }
+        }
+
+        state.found.fetch_add(matches.len() as u64, Ordering::SeqCst);
+        state.counter.store(start + BATCH_SIZE, Ordering::SeqCst);
+
+        // Save right away after a match, so it won't be emitted again
+        // when resuming.
+        if let Some(path) = checkpoint {
+            if !matches.is_empty() || last_save.elapsed() >= CHECKPOINT_INTERVAL {
+                if let Err(e) = state.save(path) {
+                    eprintln!("\r\x1b[2KError: {e}");
+                }
+                last_save = Instant::now();
+            }
+        }
+    }
+
+    Ok(())
+}
+
 fn main() -> ExitCode {
     let argv;
     let mut hflag = false;
@@ -158,23 +456,41 @@ fn main() -> ExitCode {
     let mut addrflag = false;
     let mut toknflag = false;
     let mut ctrcflag = false;
+    let mut allflag = false;
+    let mut suffixes = vec![];
+    let mut substrings = vec![];
+    let mut regexes = vec![];
+    let mut checkpoint = None;
+    let mut seed = None;
+    let mut count = 1;
+    let mut output = None;
 
     let mut n_threads = available_parallelism().unwrap().get();
 
     {
         let mut args = Args::new().with_cb(|args, flag| match flag {
             'c' => cflag = true,
+            'a' => allflag = true,
+            's' => suffixes.push(args.eargf().to_string()),
+            'i' => substrings.push(args.eargf().to_string()),
+            'r' => regexes.push(args.eargf().to_string()),
             'A' => addrflag = true,
             'T' => toknflag = true,
             'C' => ctrcflag = true,
             't' => n_threads = args.eargf().parse::<usize>().unwrap(),
+            'k' => checkpoint = Some(PathBuf::from(args.eargf())),
+            'S' => seed = Some(args.eargf().to_string()),
+            'n' => count = args.eargf().parse::<u64>().unwrap(),
+            'o' => output = Some(PathBuf::from(args.eargf())),
             _ => hflag = true,
         });
 
         argv = args.parse();
     }
 
-    if hflag || argv.is_empty() {
+    if hflag ||
+        (argv.is_empty() && suffixes.is_empty() && substrings.is_empty() && regexes.is_empty())
+    {
         usage();
         return ExitCode::FAILURE
     }
@@ -184,16 +500,79 @@ fn main() -> ExitCode {
         return ExitCode::FAILURE
     }
 
-    // Validate search prefixes
-    for (idx, prefix) in argv.iter().enumerate() {
-        match bs58::decode(prefix).into_vec() {
-            Ok(_) => {}
-            Err(e) => {
-                eprintln!("Error: Invalid base58 for prefix #{idx}: {e}");
+    if count == 0 {
+        eprintln!("Error: The number of matches to find must be at least 1");
+        return ExitCode::FAILURE
+    }
+
+    // Validate search patterns
+    let matcher = match Matcher::new(&argv, &suffixes, &substrings, &regexes, allflag, cflag) {
+        Ok(m) => m,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return ExitCode::FAILURE
+        }
+    };
+
+    // Resume from the checkpoint if there is one, otherwise start fresh
+    let kind = if addrflag {
+        "address"
+    } else if toknflag {
+        "token_id"
+    } else {
+        "contract_id"
+    };
+    let description = format!(
+        "{kind} case_sensitive={cflag} all={allflag} prefixes={argv:?} suffixes={suffixes:?} substrings={substrings:?} regexes={regexes:?}"
+    );
+
+    let loaded = match checkpoint.as_deref().map(SearchState::load).transpose() {
+        Ok(v) => v.flatten(),
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return ExitCode::FAILURE
+        }
+    };
+
+    let state = match loaded {
+        Some(state) => {
+            if state.search != description {
+                eprintln!("Error: The checkpoint was made for a different search");
                 return ExitCode::FAILURE
             }
+            if seed.as_ref().is_some_and(|s| s != &state.seed) {
+                eprintln!("Error: The checkpoint was made with a different seed");
+                return ExitCode::FAILURE
+            }
+            eprintln!(
+                "Resuming search from attempt {} with {} matches found",
+                state.counter.load(Ordering::SeqCst),
+                state.found.load(Ordering::SeqCst),
+            );
+            state
         }
-    }
+        None => {
+            let seed = seed.unwrap_or_else(|| {
+                let mut bytes = [0u8; 32];
+                OsRng.fill_bytes(&mut bytes);
+                bs58::encode(bytes).into_string()
+            });
+            SearchState::new(seed, description, 0, 0)
+        }
+    };
+    eprintln!("Searching with seed {}", state.seed);
+    let state = Arc::new(state);
+
+    let output = match output {
+        Some(path) => match OpenOptions::new().create(true).append(true).open(&path) {
+            Ok(file) => Some(file),
+            Err(e) => {
+                eprintln!("Error: Failed opening {}: {e}", path.display());
+                return ExitCode::FAILURE
+            }
+        },
+        None => None,
+    };
 
     // Handle SIGINT
     let (tx, rx) = channel();
@@ -202,60 +581,40 @@ fn main() -> ExitCode {
 
     // Something fancy
     let progress = Arc::new(ProgressInc::new());
+    let counter = state.counter.load(Ordering::SeqCst);
+    progress.resume(counter);
+    if let Some(expected) = matcher.expected_attempts() {
+        let remaining = count.saturating_sub(state.found.load(Ordering::SeqCst));
+        progress.set_expected(counter.saturating_add(expected.saturating_mul(remaining)));
+    }
 
     // Threadpool
     let progress_ = progress.clone();
+    let state_ = state.clone();
+    let checkpoint_ = checkpoint.clone();
     let rayon_pool = rayon::ThreadPoolBuilder::new().num_threads(n_threads).build().unwrap();
     rayon_pool.spawn(move || {
-        if addrflag {
-            let addr = rayon::iter::repeat(DrkAddr::new)
-                .inspect(|_| progress_.inc(1))
-                .map(|create| create())
-                .find_any(|address| address.starts_with_any(&argv, cflag))
-                .expect("Failed to find an address match");
-
-            // The above will keep running until it finds a match or until
-            // the program terminates. Only if a match is found shall the
-            // following code be executed and the program exit successfully:
-            let attempts = progress_.position();
-            progress_.finish_and_clear();
-
-            println!(
-                "{{\"address\":\"{}\",\"attempts\":{attempts},\"secret\":\"{}\"}}",
-                addr.public, addr.secret,
-            );
-        }
-
-        if toknflag {
-            let tid = rayon::iter::repeat(DrkToken::new)
-                .inspect(|_| progress_.inc(1))
-                .map(|create| create())
-                .find_any(|token_id| token_id.starts_with_any(&argv, cflag))
-                .expect("Failed to find a token ID match");
-
-            let attempts = progress_.position();
-            progress_.finish_and_clear();
-
-            println!(
-                "{{\"token_id\":\"{}\",\"attempts\":{attempts},\"secret\":\"{}\",\"blind\":\"{}\"}}",
-                tid.token_id, tid.secret, tid.blind
-            );
+        let checkpoint_ = checkpoint_.as_deref();
+        let result = if addrflag {
+            search::<DrkAddr>(&matcher, &state_, count, checkpoint_, output, &progress_)
+        } else if toknflag {
+            search::<DrkToken>(&matcher, &state_, count, checkpoint_, output, &progress_)
+        } else {
+            search::<DrkContract>(&matcher, &state_, count, checkpoint_, output, &progress_)
+        };
+
+        // The above will keep running until it finds enough matches or
+        // until the program terminates. Only then shall the following
+        // code be executed and the program exit.
+        progress_.finish_and_clear();
+        if let Err(e) = result {
+            eprintln!("Error: {e}");
+            exit(1);
         }
 
-        if ctrcflag {
-            let cid = rayon::iter::repeat(DrkContract::new)
-                .inspect(|_| progress_.inc(1))
-                .map(|create| create())
-                .find_any(|contract_id| contract_id.starts_with_any(&argv, cflag))
-                .expect("Failed to find a contract ID match");
-
-            let attempts = progress_.position();
-            progress_.finish_and_clear();
-
-            println!(
-                "{{\"contract_id\":\"{attempts}\",\"attempts\":{},\"secret\":\"{}\"}}",
-                cid.contract_id, cid.secret,
-            );
+        // The search is done, there is nothing left to resume
+        if let Some(path) = checkpoint_ {
+            let _ = fs::remove_file(path);
         }
 
         exit(0);
@@ -265,5 +624,11 @@ fn main() -> ExitCode {
     rx.recv().expect("Could not receive from channel");
     progress.finish_and_clear();
     eprintln!("\r\x1b[2KCaught SIGINT, exiting...");
+    if let Some(path) = checkpoint {
+        match state.save(&path) {
+            Ok(()) => eprintln!("Saved checkpoint to {}", path.display()),
+            Err(e) => eprintln!("Error: {e}"),
+        }
+    }
     ExitCode::FAILURE
 }
@@ -52,8 +52,10 @@ local instruction = token('instruction', word_match{
   'ec_get_x', 'ec_get_y',
   'base_add', 'base_mul', 'base_sub',
   'poseidon_hash', 'merkle_root',
+  'sparse_merkle_root', 'sparse_merkle_non_member_root',
   'range_check', 'less_than_strict', 'less_than_loose', 'bool_check',
   'cond_select', 'zero_cond', 'witness_base',
+  'elgamal_shared_secret', 'elgamal_encrypt',
   'constrain_equal_base', 'constrain_equal_point',
   'constrain_instance', 'debug',
 })
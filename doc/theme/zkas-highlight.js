@@ -11,8 +11,10 @@ hljs.registerLanguage("zkas", function (hljs) {
       built_in:
         "ec_add ec_mul ec_mul_base ec_mul_short ec_mul_var_base " +
         "ec_get_x ec_get_y base_add base_mul base_sub poseidon_hash " +
-        "merkle_root range_check less_than_strict less_than_loose bool_check " +
+        "merkle_root sparse_merkle_root sparse_merkle_non_member_root " +
+        "range_check less_than_strict less_than_loose bool_check " +
         "cond_select zero_cond witness_base constrain_equal_base " +
+        "elgamal_shared_secret elgamal_encrypt " +
         "constrain_equal_point constrain_instance debug",
     },
     contains: [
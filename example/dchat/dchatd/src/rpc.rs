@@ -41,6 +41,7 @@ impl RequestHandler<()> for Dchat {
             "send" => self.send(req.id, req.params).await,
             "recv" => self.recv(req.id).await,
             "ping" => self.pong(req.id, req.params).await,
+            "log.set_level" => self.log_set_level(req.id, req.params).await,
             "p2p.get_info" => self.p2p_get_info(req.id, req.params).await,
             "dnet.switch" => self.dnet_switch(req.id, req.params).await,
             "dnet.subscribe_events" => self.dnet_subscribe_events(req.id, req.params).await,
@@ -20,6 +20,7 @@ use darkfi::{
     async_daemonize, cli_desc,
     event_graph::{
         proto::{EventPut, ProtocolEventGraph},
+        retention::RetentionPolicy,
         Event, EventGraph, EventGraphPtr,
     },
     net::{
@@ -38,7 +39,7 @@ use darkfi::{
     Error, Result,
 };
 use darkfi_serial::{AsyncDecodable, AsyncEncodable};
-use futures::{FutureExt, AsyncWriteExt};
+use futures::{AsyncWriteExt, FutureExt};
 use log::{debug, error, info};
 use sled_overlay::sled;
 use smol::{fs, lock::Mutex, stream::StreamExt, Executor};
@@ -69,6 +70,10 @@ struct Args {
     /// Set log file output
     log: Option<String>,
 
+    #[structopt(long)]
+    /// Serve Prometheus metrics on this URL, e.g. tcp://127.0.0.1:9100
+    metrics_listen: Option<String>,
+
     #[structopt(long, default_value = "tcp://127.0.0.1:5588")]
     /// RPC server listen address
     daemon_listen: Vec<Url>,
@@ -285,6 +290,7 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
         replay_mode,
         "evgrd_dag",
         1,
+        RetentionPolicy::default(),
         ex.clone(),
     )
     .await?;
@@ -181,6 +181,7 @@ impl LocalEventGraph {
                 content: GENESIS_CONTENTS.to_vec(),
                 parents: [NULL_ID; N_EVENT_PARENTS],
                 layer: 0,
+                topic: NULL_ID,
             };
 
             // Sleep until it's time to rotate.
@@ -235,10 +235,33 @@ impl BlockDifficulty {
     }
 }
 
+/// Record of an unusually long interval between two consecutive blocks.
+///
+/// PoW has no leader slots, so gaps are measured in units of the block
+/// target: `missed` is how many targets elapsed without a block. The
+/// proposal counters are what this node observed while waiting, which
+/// lets auditors tell a network stall, where nothing was proposed, apart
+/// from proposals being produced but never making it into the chain.
+/// They are local observations and not part of consensus.
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct BlockGap {
+    /// Timestamp of the block before the gap
+    pub previous_timestamp: Timestamp,
+    /// Timestamp of the block closing the gap
+    pub timestamp: Timestamp,
+    /// Block targets elapsed without a block
+    pub missed: u64,
+    /// Proposals this node received during the gap
+    pub proposals_received: u64,
+    /// Received proposals that failed verification
+    pub proposals_rejected: u64,
+}
+
 pub const SLED_BLOCK_TREE: &[u8] = b"_blocks";
 pub const SLED_BLOCK_ORDER_TREE: &[u8] = b"_block_order";
 pub const SLED_BLOCK_DIFFICULTY_TREE: &[u8] = b"_block_difficulty";
 pub const SLED_BLOCK_STATE_INVERSE_DIFF_TREE: &[u8] = b"_block_state_inverse_diff";
+pub const SLED_BLOCK_GAP_TREE: &[u8] = b"_block_gaps";
 
 /// The `BlockStore` is a structure representing all `sled` trees related
 /// to storing the blockchain's blocks information.
@@ -259,6 +282,10 @@ pub struct BlockStore {
     /// changes, where the key is the block height number, and the value
     /// is the serialized database inverse diff.
     pub state_inverse_diff: sled::Tree,
+    /// The `sled` tree storing the gaps observed before blocks, where
+    /// the key is the height of the block closing the gap, and the value
+    /// is the serialized [`BlockGap`].
+    pub gaps: sled::Tree,
 }
 
 impl BlockStore {
@@ -268,7 +295,8 @@ impl BlockStore {
         let order = db.open_tree(SLED_BLOCK_ORDER_TREE)?;
         let difficulty = db.open_tree(SLED_BLOCK_DIFFICULTY_TREE)?;
         let state_inverse_diff = db.open_tree(SLED_BLOCK_STATE_INVERSE_DIFF_TREE)?;
-        Ok(Self { main, order, difficulty, state_inverse_diff })
+        let gaps = db.open_tree(SLED_BLOCK_GAP_TREE)?;
+        Ok(Self { main, order, difficulty, state_inverse_diff, gaps })
     }
 
     /// Insert a slice of [`Block`] into the store's main tree.
@@ -306,6 +334,23 @@ impl BlockStore {
         Ok(())
     }
 
+    /// Insert a [`BlockGap`] closed by the block of given height into
+    /// the store's gaps tree.
+    pub fn insert_gap(&self, height: u32, gap: &BlockGap) -> Result<()> {
+        self.gaps.insert(height.to_be_bytes(), serialize(gap))?;
+        Ok(())
+    }
+
+    /// Remove all gap records after given height.
+    pub fn remove_gaps_after(&self, height: u32) -> Result<()> {
+        let mut batch = sled::Batch::default();
+        for record in self.gaps.range((height + 1).to_be_bytes()..) {
+            batch.remove(record?.0);
+        }
+        self.gaps.apply_batch(batch)?;
+        Ok(())
+    }
+
     /// Generate the sled batch corresponding to an insert to the main
     /// tree, so caller can handle the write operation.
     /// The block's hash() function output is used as the key,
@@ -680,6 +725,16 @@ impl BlockStore {
         Ok(ret)
     }
 
+    /// Fetch all gap records of blocks in the height range `[start, end]`.
+    pub fn get_gaps(&self, start: u32, end: u32) -> Result<Vec<(u32, BlockGap)>> {
+        let mut ret = vec![];
+        for record in self.gaps.range(start.to_be_bytes()..=end.to_be_bytes()) {
+            ret.push(parse_u32_key_record(record?)?);
+        }
+
+        Ok(ret)
+    }
+
     /// Retrieve store's order tree records count.
     pub fn len(&self) -> usize {
         self.order.len()
@@ -25,7 +25,7 @@ use darkfi_sdk::{
     },
     monotree::Monotree,
 };
-use darkfi_serial::{deserialize, serialize};
+use darkfi_serial::{deserialize, serialize, SerialDecodable, SerialEncodable};
 use log::{debug, error};
 use sled_overlay::{serial::parse_record, sled, SledDbOverlay};
 
@@ -40,6 +40,68 @@ use super::SledDbOverlayPtr;
 pub const SLED_CONTRACTS_TREE: &[u8] = b"_contracts";
 pub const SLED_BINCODE_TREE: &[u8] = b"_wasm_bincode";
 
+/// Record stored in a contract's zkas tree, keyed by the circuit namespace.
+/// The `VerifyingKey` is built once at deploy time, so verifiers can read it
+/// directly instead of rebuilding it from the circuit. The checksum protects
+/// both blobs against corruption.
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct ZkasRecord {
+    /// Compiled zkas binary
+    pub zkbin: Vec<u8>,
+    /// Serialized `VerifyingKey` of the circuit
+    pub vkbin: Vec<u8>,
+    /// blake3(zkbin || vkbin)
+    pub checksum: [u8; 32],
+}
+
+impl ZkasRecord {
+    pub fn new(zkbin: Vec<u8>, vkbin: Vec<u8>) -> Self {
+        let checksum = Self::compute_checksum(&zkbin, &vkbin);
+        Self { zkbin, vkbin, checksum }
+    }
+
+    fn compute_checksum(zkbin: &[u8], vkbin: &[u8]) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(zkbin);
+        hasher.update(vkbin);
+        *hasher.finalize().as_bytes()
+    }
+
+    /// Deserialize a record from its stored bytes. Records stored before
+    /// checksums were introduced hold just the `(zkbin, vkbin)` pair, and
+    /// are decoded as such.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if let Ok(record) = deserialize(bytes) {
+            return Ok(record)
+        }
+
+        let (zkbin, vkbin): (Vec<u8>, Vec<u8>) = deserialize(bytes)?;
+        Ok(Self::new(zkbin, vkbin))
+    }
+
+    /// Check the stored blobs match their checksum
+    pub fn verify(&self) -> bool {
+        Self::compute_checksum(&self.zkbin, &self.vkbin) == self.checksum
+    }
+
+    /// Verify the record integrity and decode its `ZkBinary` and `VerifyingKey`
+    pub fn decode(&self) -> Result<(ZkBinary, VerifyingKey)> {
+        if !self.verify() {
+            return Err(Error::ZkasRecordCorrupted)
+        }
+
+        let zkbin = ZkBinary::decode(&self.zkbin)?;
+
+        // Construct the circuit to be able to read the VerifyingKey
+        let circuit = ZkCircuit::new(empty_witnesses(&zkbin)?, &zkbin);
+
+        let mut vk_buf = Cursor::new(&self.vkbin);
+        let vk = VerifyingKey::read::<Cursor<&Vec<u8>>, ZkCircuit>(&mut vk_buf, circuit)?;
+
+        Ok((zkbin, vk))
+    }
+}
+
 /// The `ContractStore` is a structure representing all `sled` trees related
 /// to storing the blockchain's contracts information.
 #[derive(Clone)]
@@ -166,21 +228,10 @@ impl ContractStore {
             return Err(Error::ZkasBincodeNotFound)
         };
 
-        // If anything in this function panics, that means corrupted data managed
-        // to get into this sled tree. This should not be possible.
-        let (zkbin, vkbin): (Vec<u8>, Vec<u8>) = deserialize(&zkas_bytes).unwrap();
-
-        // The first vec is the compiled zkas binary
-        let zkbin = ZkBinary::decode(&zkbin).unwrap();
-
-        // Construct the circuit to be able to read the VerifyingKey
-        let circuit = ZkCircuit::new(empty_witnesses(&zkbin).unwrap(), &zkbin);
-
-        // The second one is the serialized VerifyingKey for it
-        let mut vk_buf = Cursor::new(vkbin);
-        let vk = VerifyingKey::read::<Cursor<Vec<u8>>, ZkCircuit>(&mut vk_buf, circuit).unwrap();
-
-        Ok((zkbin, vk))
+        let record = ZkasRecord::from_bytes(&zkas_bytes)?;
+        record.decode().inspect_err(|e| {
+            error!(target: "blockchain::contractstore", "Invalid zkas record for \"{contract_id}:{zkas_ns}\": {e}");
+        })
     }
 
     /// Retrieve all wasm bincodes from the store's wasm tree in the form
@@ -433,21 +484,10 @@ impl ContractStoreOverlay {
             return Err(Error::ZkasBincodeNotFound)
         };
 
-        // If anything in this function panics, that means corrupted data managed
-        // to get into this sled tree. This should not be possible.
-        let (zkbin, vkbin): (Vec<u8>, Vec<u8>) = deserialize(&zkas_bytes).unwrap();
-
-        // The first vec is the compiled zkas binary
-        let zkbin = ZkBinary::decode(&zkbin).unwrap();
-
-        // Construct the circuit to be able to read the VerifyingKey
-        let circuit = ZkCircuit::new(empty_witnesses(&zkbin).unwrap(), &zkbin);
-
-        // The second one is the serialized VerifyingKey for it
-        let mut vk_buf = Cursor::new(vkbin);
-        let vk = VerifyingKey::read::<Cursor<Vec<u8>>, ZkCircuit>(&mut vk_buf, circuit).unwrap();
-
-        Ok((zkbin, vk))
+        let record = ZkasRecord::from_bytes(&zkas_bytes)?;
+        record.decode().inspect_err(|e| {
+            error!(target: "blockchain::contractstore", "Invalid zkas record for \"{contract_id}:{zkas_ns}\": {e}");
+        })
     }
 
     /// Generate a Monotree(SMT) containing all contracts states
@@ -619,3 +659,30 @@ fn sled_overlay_tree_checksum(overlay: &SledDbOverlay, tree_key: &[u8]) -> Resul
     // Return the finalized hasher bytes
     Ok(*hasher.finalize().as_bytes())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zkas_record_legacy_decode() -> Result<()> {
+        let zkbin = vec![1, 2, 3];
+        let vkbin = vec![4, 5, 6, 7];
+
+        // Current records keep their checksum
+        let mut record = ZkasRecord::new(zkbin.clone(), vkbin.clone());
+        let decoded = ZkasRecord::from_bytes(&serialize(&record))?;
+        assert_eq!(decoded.checksum, record.checksum);
+        record.checksum = [0; 32];
+        assert!(!ZkasRecord::from_bytes(&serialize(&record))?.verify());
+
+        // Legacy records are just the pair of blobs
+        let decoded = ZkasRecord::from_bytes(&serialize(&(zkbin.clone(), vkbin.clone())))?;
+        assert_eq!(decoded.zkbin, zkbin);
+        assert_eq!(decoded.vkbin, vkbin);
+        assert!(decoded.verify());
+
+        assert!(ZkasRecord::from_bytes(&[0xff]).is_err());
+        Ok(())
+    }
+}
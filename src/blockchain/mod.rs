@@ -27,8 +27,9 @@ use crate::{tx::Transaction, util::time::Timestamp, Error, Result};
 /// Block related definitions and storage implementations
 pub mod block_store;
 pub use block_store::{
-    Block, BlockDifficulty, BlockInfo, BlockStore, BlockStoreOverlay, SLED_BLOCK_DIFFICULTY_TREE,
-    SLED_BLOCK_ORDER_TREE, SLED_BLOCK_STATE_INVERSE_DIFF_TREE, SLED_BLOCK_TREE,
+    Block, BlockDifficulty, BlockGap, BlockInfo, BlockStore, BlockStoreOverlay,
+    SLED_BLOCK_DIFFICULTY_TREE, SLED_BLOCK_GAP_TREE, SLED_BLOCK_ORDER_TREE,
+    SLED_BLOCK_STATE_INVERSE_DIFF_TREE, SLED_BLOCK_TREE,
 };
 
 /// Header definition and storage implementation
@@ -41,18 +42,29 @@ pub use header_store::{
 pub mod tx_store;
 pub use tx_store::{
     TxStore, TxStoreOverlay, SLED_PENDING_TX_ORDER_TREE, SLED_PENDING_TX_TREE,
-    SLED_TX_LOCATION_TREE, SLED_TX_TREE,
+    SLED_TX_LOCATION_TREE, SLED_TX_METRICS_DAILY_TREE, SLED_TX_METRICS_TREE, SLED_TX_TREE,
 };
 
 /// Contracts and Wasm storage implementations
 pub mod contract_store;
 pub use contract_store::{
-    ContractStore, ContractStoreOverlay, SLED_BINCODE_TREE, SLED_CONTRACTS_TREE,
+    ContractStore, ContractStoreOverlay, ZkasRecord, SLED_BINCODE_TREE, SLED_CONTRACTS_TREE,
 };
 
 /// Monero definitions needed for merge mining
 pub mod monero;
 
+/// Sparse Merkle tree storage on top of sled trees
+pub mod smt;
+
+/// Contract state differences between block heights
+pub mod state_diff;
+pub use state_diff::{StateDiff, StateRecordDiff};
+
+/// Integrity checks and quarantine of corrupt records
+pub mod scrubber;
+pub use scrubber::{Corruption, QuarantineRecord, ScrubFinding, SLED_QUARANTINE_TREE};
+
 /// Structure holding all sled trees that define the concept of Blockchain.
 #[derive(Clone)]
 pub struct Blockchain {
@@ -396,6 +408,9 @@ impl Blockchain {
         drop(lock);
         drop(overlay_lock);
 
+        // Gaps of the removed blocks no longer apply
+        self.blocks.remove_gaps_after(height)?;
+
         Ok(())
     }
 
@@ -437,12 +452,15 @@ impl BlockchainOverlay {
             SLED_BLOCK_ORDER_TREE,
             SLED_BLOCK_DIFFICULTY_TREE,
             SLED_BLOCK_STATE_INVERSE_DIFF_TREE,
+            SLED_BLOCK_GAP_TREE,
             SLED_HEADER_TREE,
             SLED_SYNC_HEADER_TREE,
             SLED_TX_TREE,
             SLED_TX_LOCATION_TREE,
             SLED_PENDING_TX_TREE,
             SLED_PENDING_TX_ORDER_TREE,
+            SLED_TX_METRICS_TREE,
+            SLED_TX_METRICS_DAILY_TREE,
             SLED_CONTRACTS_TREE,
             SLED_BINCODE_TREE,
         ];
@@ -511,6 +529,9 @@ impl BlockchainOverlay {
         // Store transactions locations
         self.transactions.insert_location(&txs_hashes, block.header.height)?;
 
+        // Update the daily transactions metrics
+        self.transactions.update_daily_metrics(block.header.timestamp.inner(), &txs_hashes)?;
+
         Ok(block_hash)
     }
 
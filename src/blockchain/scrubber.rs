@@ -0,0 +1,403 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::{
+    crypto::{MerkleNode, MerkleTree},
+    pasta::{group::ff::FromUniformBytes, pallas},
+    tx::TransactionHash,
+    AsHex,
+};
+#[cfg(feature = "async-serial")]
+use darkfi_serial::async_trait;
+use darkfi_serial::{deserialize, serialize, SerialDecodable, SerialEncodable};
+use log::warn;
+use sled_overlay::sled;
+
+use crate::{tx::Transaction, util::time::Timestamp, Error, Result};
+
+use super::{
+    Block, BlockInfo, Blockchain, Header, HeaderHash, SLED_BLOCK_ORDER_TREE, SLED_BLOCK_TREE,
+    SLED_HEADER_TREE, SLED_TX_LOCATION_TREE, SLED_TX_TREE,
+};
+
+pub const SLED_QUARANTINE_TREE: &[u8] = b"_quarantine";
+
+/// Kind of corruption found in a stored record
+#[derive(Copy, Clone, Debug, PartialEq, Eq, SerialEncodable, SerialDecodable)]
+pub enum Corruption {
+    /// Another tree points to the record, but it doesn't exist
+    Missing,
+    /// The record can't be deserialized
+    Undecodable,
+    /// The record doesn't hash to its key
+    HashMismatch,
+    /// The record contradicts what other trees point to
+    LinkMismatch,
+}
+
+impl Corruption {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Missing => "missing",
+            Self::Undecodable => "undecodable",
+            Self::HashMismatch => "hash_mismatch",
+            Self::LinkMismatch => "link_mismatch",
+        }
+    }
+}
+
+/// A corrupt record found while scrubbing a block
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct ScrubFinding {
+    /// Height of the block the record belongs to
+    pub height: u32,
+    /// Name of the `sled` tree holding the record
+    pub tree: String,
+    /// Key of the record
+    pub key: Vec<u8>,
+    /// What is wrong with the record
+    pub corruption: Corruption,
+}
+
+impl ScrubFinding {
+    fn new(height: u32, tree: &[u8], key: &[u8], corruption: Corruption) -> Self {
+        let tree = String::from_utf8_lossy(tree).to_string();
+        Self { height, tree, key: key.to_vec(), corruption }
+    }
+}
+
+/// A record moved out of its tree by the scrubber.
+/// The raw bytes are kept around for forensics.
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct QuarantineRecord {
+    /// Why the record got quarantined
+    pub finding: ScrubFinding,
+    /// The record's raw bytes, if it existed
+    pub data: Option<Vec<u8>>,
+    /// When the record got quarantined
+    pub timestamp: Timestamp,
+}
+
+/// Key of a quarantine record: `height || tree || key`, so the records
+/// of a block height can be found with a prefix scan.
+fn quarantine_key(finding: &ScrubFinding) -> Vec<u8> {
+    let mut key = finding.height.to_be_bytes().to_vec();
+    key.extend_from_slice(finding.tree.as_bytes());
+    key.extend_from_slice(&finding.key);
+    key
+}
+
+impl Blockchain {
+    /// Re-hash the records of the block at the given height against their
+    /// keys, and verify the links between the order, header, block and
+    /// transaction trees. Nothing gets modified, corrupt records are
+    /// returned as [`ScrubFinding`]s.
+    pub fn scrub_height(&self, height: u32) -> Result<Vec<ScrubFinding>> {
+        let mut findings = vec![];
+        let height_key = height.to_be_bytes();
+
+        // Order record points us to the block hash
+        let Some(order) = self.blocks.order.get(height_key)? else { return Ok(findings) };
+        let Ok(hash) = deserialize::<HeaderHash>(&order) else {
+            findings.push(ScrubFinding::new(
+                height,
+                SLED_BLOCK_ORDER_TREE,
+                &height_key,
+                Corruption::Undecodable,
+            ));
+            return Ok(findings)
+        };
+
+        // Header must hash to its key and match the order height
+        let header = match self.headers.main.get(hash.inner())? {
+            None => {
+                findings.push(ScrubFinding::new(
+                    height,
+                    SLED_HEADER_TREE,
+                    hash.inner(),
+                    Corruption::Missing,
+                ));
+                None
+            }
+            Some(bytes) => match deserialize::<Header>(&bytes) {
+                Err(_) => {
+                    findings.push(ScrubFinding::new(
+                        height,
+                        SLED_HEADER_TREE,
+                        hash.inner(),
+                        Corruption::Undecodable,
+                    ));
+                    None
+                }
+                Ok(header) if header.hash() != hash => {
+                    findings.push(ScrubFinding::new(
+                        height,
+                        SLED_HEADER_TREE,
+                        hash.inner(),
+                        Corruption::HashMismatch,
+                    ));
+                    None
+                }
+                Ok(header) if header.height != height => {
+                    findings.push(ScrubFinding::new(
+                        height,
+                        SLED_HEADER_TREE,
+                        hash.inner(),
+                        Corruption::LinkMismatch,
+                    ));
+                    None
+                }
+                Ok(header) => Some(header),
+            },
+        };
+
+        // Block must point back to its header
+        let block = match self.blocks.main.get(hash.inner())? {
+            None => {
+                findings.push(ScrubFinding::new(
+                    height,
+                    SLED_BLOCK_TREE,
+                    hash.inner(),
+                    Corruption::Missing,
+                ));
+                return Ok(findings)
+            }
+            Some(bytes) => match deserialize::<Block>(&bytes) {
+                Err(_) => {
+                    findings.push(ScrubFinding::new(
+                        height,
+                        SLED_BLOCK_TREE,
+                        hash.inner(),
+                        Corruption::Undecodable,
+                    ));
+                    return Ok(findings)
+                }
+                Ok(block) if block.header != hash => {
+                    findings.push(ScrubFinding::new(
+                        height,
+                        SLED_BLOCK_TREE,
+                        hash.inner(),
+                        Corruption::LinkMismatch,
+                    ));
+                    return Ok(findings)
+                }
+                Ok(block) => block,
+            },
+        };
+
+        // The block transactions list must match the header Merkle root.
+        // A valid header is authoritative since it hashes to its key.
+        if let Some(header) = &header {
+            if transactions_root(&block.txs) != header.transactions_root {
+                findings.push(ScrubFinding::new(
+                    height,
+                    SLED_BLOCK_TREE,
+                    hash.inner(),
+                    Corruption::LinkMismatch,
+                ));
+                return Ok(findings)
+            }
+        }
+
+        // Transactions must hash to their keys and be located in this block
+        for (index, tx_hash) in block.txs.iter().enumerate() {
+            match self.transactions.main.get(tx_hash.inner())? {
+                None => findings.push(ScrubFinding::new(
+                    height,
+                    SLED_TX_TREE,
+                    tx_hash.inner(),
+                    Corruption::Missing,
+                )),
+                Some(bytes) => match deserialize::<Transaction>(&bytes) {
+                    Err(_) => findings.push(ScrubFinding::new(
+                        height,
+                        SLED_TX_TREE,
+                        tx_hash.inner(),
+                        Corruption::Undecodable,
+                    )),
+                    Ok(tx) if tx.hash() != *tx_hash => findings.push(ScrubFinding::new(
+                        height,
+                        SLED_TX_TREE,
+                        tx_hash.inner(),
+                        Corruption::HashMismatch,
+                    )),
+                    Ok(_) => {}
+                },
+            }
+
+            let corruption = match self.transactions.location.get(tx_hash.inner())? {
+                None => Some(Corruption::Missing),
+                Some(bytes) => match deserialize::<(u32, u16)>(&bytes) {
+                    Err(_) => Some(Corruption::Undecodable),
+                    Ok(location) if location != (height, index as u16) => {
+                        Some(Corruption::LinkMismatch)
+                    }
+                    Ok(_) => None,
+                },
+            };
+            if let Some(corruption) = corruption {
+                findings.push(ScrubFinding::new(
+                    height,
+                    SLED_TX_LOCATION_TREE,
+                    tx_hash.inner(),
+                    corruption,
+                ));
+            }
+        }
+
+        Ok(findings)
+    }
+
+    /// Move the corrupt records of the given findings into the quarantine
+    /// tree, so they can't crash readers anymore. Missing records only get
+    /// their finding stored.
+    pub fn quarantine(&self, findings: &[ScrubFinding]) -> Result<()> {
+        let quarantine = self.sled_db.open_tree(SLED_QUARANTINE_TREE)?;
+        let timestamp = Timestamp::current_time();
+
+        for finding in findings {
+            let tree = self.sled_db.open_tree(finding.tree.as_bytes())?;
+            let data = tree.get(&finding.key)?.map(|d| d.to_vec());
+
+            let record =
+                QuarantineRecord { finding: finding.clone(), data: data.clone(), timestamp };
+            quarantine.insert(quarantine_key(finding), serialize(&record))?;
+
+            if data.is_some() {
+                warn!(
+                    target: "blockchain::scrubber::quarantine",
+                    "Quarantining {} record {} of block {} in {}",
+                    finding.corruption.name(),
+                    finding.key.hex(),
+                    finding.height,
+                    finding.tree,
+                );
+                tree.remove(&finding.key)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fetch all quarantine records in the height range `[start, end]`.
+    pub fn get_quarantined(&self, start: u32, end: u32) -> Result<Vec<QuarantineRecord>> {
+        let quarantine = self.sled_db.open_tree(SLED_QUARANTINE_TREE)?;
+
+        let mut ret = vec![];
+        let end = end.checked_add(1).map(|e| e.to_be_bytes());
+        let range = match end {
+            Some(end) => quarantine.range(start.to_be_bytes()..end),
+            None => quarantine.range(start.to_be_bytes()..),
+        };
+        for record in range {
+            let (_, value) = record?;
+            ret.push(deserialize(&value)?);
+        }
+
+        Ok(ret)
+    }
+
+    /// Restore a quarantined block from a copy retrieved from a peer.
+    /// The copy must hash to the block hash our order tree points to at
+    /// its height, and its transactions must match the header Merkle root,
+    /// so a peer can't feed us anything other than the original block.
+    /// On success the quarantine records of the height get dropped.
+    pub fn restore_block(&self, block: &BlockInfo) -> Result<()> {
+        let height = block.header.height;
+        let block_hash = block.hash();
+
+        let Some(order) = self.blocks.order.get(height.to_be_bytes())? else {
+            return Err(Error::BlockNotFound(block_hash.as_string()))
+        };
+        if deserialize::<HeaderHash>(&order)? != block_hash {
+            return Err(Error::BlockIsInvalid(block_hash.as_string()))
+        }
+
+        let txs_hashes: Vec<TransactionHash> = block.txs.iter().map(|tx| tx.hash()).collect();
+        if transactions_root(&txs_hashes) != block.header.transactions_root {
+            return Err(Error::BlockIsInvalid(block_hash.as_string()))
+        }
+
+        self.add_block(block)?;
+
+        let quarantine = self.sled_db.open_tree(SLED_QUARANTINE_TREE)?;
+        let mut batch = sled::Batch::default();
+        for record in quarantine.scan_prefix(height.to_be_bytes()) {
+            let (key, _) = record?;
+            batch.remove(key);
+        }
+        quarantine.apply_batch(batch)?;
+
+        Ok(())
+    }
+}
+
+/// Compute the Merkle root of a block's transactions hashes, the same
+/// way `append_tx_to_merkle_tree()` builds it.
+fn transactions_root(txs: &[TransactionHash]) -> MerkleNode {
+    let mut tree = MerkleTree::new(1);
+    for tx_hash in txs {
+        let mut buf = [0u8; 64];
+        buf[..32].copy_from_slice(tx_hash.inner());
+        tree.append(pallas::Base::from_uniform_bytes(&buf).into());
+    }
+    tree.root(0).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scrub_quarantine_restore() -> Result<()> {
+        let sled_db = sled::Config::new().temporary(true).open()?;
+        let blockchain = Blockchain::new(&sled_db)?;
+
+        let mut block = BlockInfo::new_empty(Header::default());
+        block.append_txs(vec![Transaction::default()]);
+        let block_hash = blockchain.add_block(&block)?;
+        assert!(blockchain.scrub_height(0)?.is_empty());
+
+        // Flip a byte of the stored header
+        let mut header = blockchain.headers.main.get(block_hash.inner())?.unwrap().to_vec();
+        header[1] ^= 0xff;
+        blockchain.headers.main.insert(block_hash.inner(), header)?;
+
+        let findings = blockchain.scrub_height(0)?;
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].corruption, Corruption::HashMismatch);
+        assert_eq!(findings[0].tree.as_bytes(), SLED_HEADER_TREE);
+
+        // The corrupt header gets moved out of the way
+        blockchain.quarantine(&findings)?;
+        assert!(!blockchain.headers.main.contains_key(block_hash.inner())?);
+        assert_eq!(blockchain.get_quarantined(0, 0)?.len(), 1);
+        assert_eq!(blockchain.scrub_height(0)?[0].corruption, Corruption::Missing);
+
+        // A block with different transactions can't replace it
+        let mut forged = block.clone();
+        forged.txs.push(Transaction::default());
+        assert!(blockchain.restore_block(&forged).is_err());
+
+        blockchain.restore_block(&block)?;
+        assert!(blockchain.scrub_height(0)?.is_empty());
+        assert!(blockchain.get_quarantined(0, u32::MAX)?.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::{
+    crypto::{
+        pasta_prelude::*,
+        smt::{PoseidonFp, SparseMerkleTree, StorageAdapter, EMPTY_NODES_FP, SMT_FP_DEPTH},
+    },
+    error::{ContractError, ContractResult},
+    pasta::pallas,
+};
+use log::error;
+use num_bigint::BigUint;
+use sled_overlay::sled;
+
+/// An SMT adapter for a plain sled tree. Nodes are stored the same way
+/// the WasmDb and runtime adapters do, so contract SMT state trees can be
+/// opened and proven against directly from the host.
+pub struct SledTreeStorage {
+    tree: sled::Tree,
+}
+
+impl SledTreeStorage {
+    pub fn new(tree: sled::Tree) -> Self {
+        Self { tree }
+    }
+}
+
+impl StorageAdapter for SledTreeStorage {
+    type Value = pallas::Base;
+
+    fn put(&mut self, key: BigUint, value: pallas::Base) -> ContractResult {
+        if let Err(e) = self.tree.insert(key.to_bytes_le(), &value.to_repr()) {
+            error!(
+                target: "blockchain::smt::SledTreeStorage::put",
+                "Inserting key {key:?}, value {value:?} into DB tree failed: {e}",
+            );
+            return Err(ContractError::SmtPutFailed)
+        }
+
+        Ok(())
+    }
+
+    fn get(&self, key: &BigUint) -> Option<pallas::Base> {
+        let value = match self.tree.get(key.to_bytes_le()) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(
+                    target: "blockchain::smt::SledTreeStorage::get",
+                    "Fetching key {key:?} from DB tree failed: {e}",
+                );
+                return None
+            }
+        };
+
+        let value = value?;
+        let mut repr = [0; 32];
+        repr.copy_from_slice(&value);
+
+        pallas::Base::from_repr(repr).into()
+    }
+
+    fn del(&mut self, key: &BigUint) -> ContractResult {
+        if let Err(e) = self.tree.remove(key.to_bytes_le()) {
+            error!(
+                target: "blockchain::smt::SledTreeStorage::del",
+                "Removing key {key:?} from DB tree failed: {e}",
+            );
+            return Err(ContractError::SmtDelFailed)
+        }
+
+        Ok(())
+    }
+}
+
+pub type SmtSledFp = SparseMerkleTree<
+    'static,
+    SMT_FP_DEPTH,
+    { SMT_FP_DEPTH + 1 },
+    pallas::Base,
+    PoseidonFp,
+    SledTreeStorage,
+>;
+
+/// Open a sled tree holding a Poseidon SMT, like the `Money` contract
+/// nullifiers state tree.
+pub fn smt_from_tree(tree: sled::Tree) -> SmtSledFp {
+    SmtSledFp::new(SledTreeStorage::new(tree), PoseidonFp::new(), &EMPTY_NODES_FP)
+}
@@ -0,0 +1,152 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::HashMap;
+
+use darkfi_sdk::crypto::contract_id::ContractId;
+#[cfg(feature = "async-serial")]
+use darkfi_serial::async_trait;
+use darkfi_serial::{deserialize, SerialDecodable, SerialEncodable};
+use log::debug;
+
+use super::{Blockchain, BlockchainOverlay, BlockchainOverlayPtr, SLED_CONTRACTS_TREE};
+use crate::{Error, Result};
+
+/// A contract state record that differs between two block heights
+#[derive(Clone, Debug, PartialEq, Eq, SerialEncodable, SerialDecodable)]
+pub struct StateRecordDiff {
+    /// Contract the record belongs to
+    pub contract_id: ContractId,
+    /// Contract state tree pointer, `blake3(ContractId || tree_name)`
+    pub tree: [u8; 32],
+    /// Record key
+    pub key: Vec<u8>,
+    /// Record value at the lower height, `None` if it got added
+    pub old: Option<Vec<u8>>,
+    /// Record value at the upper height, `None` if it got removed
+    pub new: Option<Vec<u8>>,
+}
+
+/// Contract state differences between two block heights
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct StateDiff {
+    /// Lower block height
+    pub from: u32,
+    /// Upper block height
+    pub to: u32,
+    /// Records that differ between the two heights
+    pub records: Vec<StateRecordDiff>,
+    /// Flag indicating more records differ than the ones returned
+    pub truncated: bool,
+}
+
+impl Blockchain {
+    /// Compute the contract state differences between block heights
+    /// `from` and `to`, using the stored state inverse diffs to rebuild
+    /// both states in overlays. At most `max_records` records are returned.
+    ///
+    /// Note: this holds all the inverse diffs after `from` in memory,
+    /// so callers should bound how far back they go.
+    pub fn state_diff(&self, from: u32, to: u32, max_records: usize) -> Result<StateDiff> {
+        let (last, _) = self.last()?;
+        if from >= to || to > last {
+            return Err(Error::InvalidStateDiffRange(from, to))
+        }
+        debug!(target: "blockchain::state_diff", "Computing state diff between {from} and {to}");
+
+        let lower = self.overlay_at_height(from, last)?;
+        let upper = self.overlay_at_height(to, last)?;
+        let lower = lower.lock().unwrap();
+        let lower = lower.overlay.lock().unwrap();
+        let upper = upper.lock().unwrap();
+        let mut upper = upper.overlay.lock().unwrap();
+
+        // Map state tree pointers to their contracts, including the
+        // ones that only exist in one of the two states.
+        let mut pointers = HashMap::new();
+        for record in lower.iter(SLED_CONTRACTS_TREE)?.chain(upper.iter(SLED_CONTRACTS_TREE)?) {
+            let (contract_id, state_pointers) = record?;
+            let contract_id: ContractId = deserialize(&contract_id)?;
+            let state_pointers: Vec<[u8; 32]> = deserialize(&state_pointers)?;
+            for state_ptr in state_pointers {
+                pointers.insert(state_ptr, contract_id);
+            }
+        }
+
+        // Every record touched after `to` was also touched after `from`,
+        // so the lower overlay caches contain all candidates.
+        let mut records = vec![];
+        let mut truncated = false;
+        for (tree, cache) in &lower.state.caches {
+            let Ok(state_ptr) = <[u8; 32]>::try_from(tree.as_ref()) else { continue };
+            let Some(contract_id) = pointers.get(&state_ptr) else { continue };
+
+            // Trees created after the upper height don't exist in its state
+            let upper_exists = upper.open_tree(tree, false).is_ok();
+
+            for key in cache.state.cache.keys().chain(cache.state.removed.iter()) {
+                let old = lower.get(tree, key)?.map(|v| v.to_vec());
+                let new = match upper_exists {
+                    true => upper.get(tree, key)?.map(|v| v.to_vec()),
+                    false => None,
+                };
+                if old == new {
+                    continue
+                }
+
+                if records.len() == max_records {
+                    truncated = true;
+                    break
+                }
+
+                records.push(StateRecordDiff {
+                    contract_id: *contract_id,
+                    tree: state_ptr,
+                    key: key.to_vec(),
+                    old,
+                    new,
+                });
+            }
+
+            if truncated {
+                break
+            }
+        }
+
+        Ok(StateDiff { from, to, records, truncated })
+    }
+
+    /// Create an overlay representing the state at given height, by
+    /// adding the inverse diffs of all blocks after it.
+    fn overlay_at_height(&self, height: u32, last: u32) -> Result<BlockchainOverlayPtr> {
+        let heights: Vec<u32> = (height + 1..=last).rev().collect();
+        let inverse_diffs = self.blocks.get_state_inverse_diff(&heights, true)?;
+
+        let overlay = BlockchainOverlay::new(self)?;
+        let overlay_lock = overlay.lock().unwrap();
+        let mut lock = overlay_lock.overlay.lock().unwrap();
+        for inverse_diff in inverse_diffs {
+            // Since we used strict retrieval it's safe to unwrap here
+            lock.add_diff(&inverse_diff.unwrap())?;
+        }
+        drop(lock);
+        drop(overlay_lock);
+
+        Ok(overlay)
+    }
+}
@@ -19,7 +19,9 @@
 use std::collections::HashMap;
 
 use darkfi_sdk::tx::TransactionHash;
-use darkfi_serial::{deserialize, serialize};
+#[cfg(feature = "async-serial")]
+use darkfi_serial::async_trait;
+use darkfi_serial::{deserialize, serialize, SerialDecodable, SerialEncodable};
 use sled_overlay::{
     serial::{parse_record, parse_u64_key_record},
     sled,
@@ -33,6 +35,62 @@ pub const SLED_TX_TREE: &[u8] = b"_transactions";
 pub const SLED_TX_LOCATION_TREE: &[u8] = b"_transaction_location";
 pub const SLED_PENDING_TX_TREE: &[u8] = b"_pending_transactions";
 pub const SLED_PENDING_TX_ORDER_TREE: &[u8] = b"_pending_transactions_order";
+pub const SLED_TX_METRICS_TREE: &[u8] = b"_transaction_metrics";
+pub const SLED_TX_METRICS_DAILY_TREE: &[u8] = b"_transaction_metrics_daily";
+
+/// Number of seconds in a day, used to bucket the daily metrics
+const SECONDS_PER_DAY: u64 = 86400;
+
+/// Metrics recorded for a transaction when it gets verified.
+///
+/// These are local to the node that verified the transaction, so they
+/// must never be part of any consensus data. The verification time in
+/// particular depends on the hardware of the node.
+#[derive(Clone, Debug, Default, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct TxMetrics {
+    /// Serialized transaction size in bytes
+    pub size: u64,
+    /// Number of ZK proofs in the transaction
+    pub proofs: u64,
+    /// Total gas used by the transaction
+    pub gas_used: u64,
+    /// Time it took to verify the transaction, in microseconds
+    pub verify_time: u64,
+}
+
+/// Aggregated [`TxMetrics`] of all the blocks added in a day.
+#[derive(Clone, Debug, Default, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct DailyTxMetrics {
+    /// Number of blocks added
+    pub blocks: u64,
+    /// Number of transactions with recorded metrics
+    pub txs: u64,
+    /// Sum of transactions sizes in bytes
+    pub size: u64,
+    /// Sum of transactions ZK proofs
+    pub proofs: u64,
+    /// Sum of transactions gas used
+    pub gas_used: u64,
+    /// Sum of transactions verification times, in microseconds
+    pub verify_time: u64,
+}
+
+impl DailyTxMetrics {
+    /// Accumulate a transaction's metrics into the aggregate
+    pub fn add(&mut self, metrics: &TxMetrics) {
+        self.txs += 1;
+        self.size += metrics.size;
+        self.proofs += metrics.proofs;
+        self.gas_used += metrics.gas_used;
+        self.verify_time += metrics.verify_time;
+    }
+}
+
+/// Auxiliary function to compute the day index of a UNIX timestamp,
+/// used as the key of the daily metrics tree.
+pub fn metrics_day(timestamp: u64) -> u64 {
+    timestamp / SECONDS_PER_DAY
+}
 
 /// The `TxStore` is a structure representing all `sled` trees related
 /// to storing the blockchain's transactions information.
@@ -55,6 +113,14 @@ pub struct TxStore {
     /// where the key is an incremental value, and the value is the serialized
     /// transaction.
     pub pending_order: sled::Tree,
+    /// The `sled` tree storing the metrics of verified transactions,
+    /// where the key is the transaction hash, and the value is the
+    /// serialized [`TxMetrics`].
+    pub metrics: sled::Tree,
+    /// The `sled` tree storing the daily aggregated transactions metrics,
+    /// where the key is the day index, and the value is the serialized
+    /// [`DailyTxMetrics`].
+    pub metrics_daily: sled::Tree,
 }
 
 impl TxStore {
@@ -64,7 +130,9 @@ impl TxStore {
         let location = db.open_tree(SLED_TX_LOCATION_TREE)?;
         let pending = db.open_tree(SLED_PENDING_TX_TREE)?;
         let pending_order = db.open_tree(SLED_PENDING_TX_ORDER_TREE)?;
-        Ok(Self { main, location, pending, pending_order })
+        let metrics = db.open_tree(SLED_TX_METRICS_TREE)?;
+        let metrics_daily = db.open_tree(SLED_TX_METRICS_DAILY_TREE)?;
+        Ok(Self { main, location, pending, pending_order, metrics, metrics_daily })
     }
 
     /// Insert a slice of [`Transaction`] into the store's main tree.
@@ -246,6 +314,32 @@ impl TxStore {
         Ok(ret)
     }
 
+    /// Fetch given tx hashes metrics from the store's metrics tree.
+    /// The resulting vector contains `Option`, which is `Some` if metrics
+    /// were recorded for the tx, and otherwise it is `None`, if they were
+    /// not, for example because the tx was applied without verification.
+    pub fn get_metrics(&self, tx_hashes: &[TransactionHash]) -> Result<Vec<Option<TxMetrics>>> {
+        let mut ret = Vec::with_capacity(tx_hashes.len());
+
+        for tx_hash in tx_hashes {
+            match self.metrics.get(tx_hash.inner())? {
+                Some(found) => ret.push(Some(deserialize(&found)?)),
+                None => ret.push(None),
+            }
+        }
+
+        Ok(ret)
+    }
+
+    /// Fetch the aggregated transactions metrics of given day index from
+    /// the store's daily metrics tree.
+    pub fn get_daily_metrics(&self, day: u64) -> Result<Option<DailyTxMetrics>> {
+        match self.metrics_daily.get(day.to_be_bytes())? {
+            Some(found) => Ok(Some(deserialize(&found)?)),
+            None => Ok(None),
+        }
+    }
+
     /// Fetch given tx hashes from the store's pending txs tree.
     /// The resulting vector contains `Option`, which is `Some` if the tx
     /// was found in the pending tx store, and otherwise it is `None`, if it has not.
@@ -418,6 +512,8 @@ impl TxStoreOverlay {
     pub fn new(overlay: &SledDbOverlayPtr) -> Result<Self> {
         overlay.lock().unwrap().open_tree(SLED_TX_TREE, true)?;
         overlay.lock().unwrap().open_tree(SLED_TX_LOCATION_TREE, true)?;
+        overlay.lock().unwrap().open_tree(SLED_TX_METRICS_TREE, true)?;
+        overlay.lock().unwrap().open_tree(SLED_TX_METRICS_DAILY_TREE, true)?;
         Ok(Self(overlay.clone()))
     }
 
@@ -453,6 +549,45 @@ impl TxStoreOverlay {
         Ok(())
     }
 
+    /// Insert the [`TxMetrics`] of given tx hash into the overlay's
+    /// metrics tree.
+    pub fn insert_metrics(&self, tx_hash: &TransactionHash, metrics: &TxMetrics) -> Result<()> {
+        self.0.lock().unwrap().insert(
+            SLED_TX_METRICS_TREE,
+            tx_hash.inner(),
+            &serialize(metrics),
+        )?;
+        Ok(())
+    }
+
+    /// Accumulate the recorded metrics of given tx hashes into the
+    /// aggregate of the day the provided block timestamp falls in.
+    /// Transactions without recorded metrics are skipped, but the
+    /// block is always counted.
+    pub fn update_daily_metrics(
+        &self,
+        timestamp: u64,
+        txs_hashes: &[TransactionHash],
+    ) -> Result<()> {
+        let mut lock = self.0.lock().unwrap();
+        let key = metrics_day(timestamp).to_be_bytes();
+
+        let mut daily: DailyTxMetrics = match lock.get(SLED_TX_METRICS_DAILY_TREE, &key)? {
+            Some(found) => deserialize(&found)?,
+            None => DailyTxMetrics::default(),
+        };
+        daily.blocks += 1;
+
+        for tx_hash in txs_hashes {
+            if let Some(found) = lock.get(SLED_TX_METRICS_TREE, tx_hash.inner())? {
+                daily.add(&deserialize(&found)?);
+            }
+        }
+
+        lock.insert(SLED_TX_METRICS_DAILY_TREE, &key, &serialize(&daily))?;
+        Ok(())
+    }
+
     /// Fetch given tx hashes from the overlay's main tree.
     /// The resulting vector contains `Option`, which is `Some` if the tx
     /// was found in the overlay, and otherwise it is `None`, if it has not.
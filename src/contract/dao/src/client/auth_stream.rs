@@ -0,0 +1,95 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use darkfi_money_contract::model::{CoinAttributes, TokenId};
+use darkfi_sdk::{
+    crypto::{BaseBlind, Blind, PublicKey, DAO_CONTRACT_ID},
+    ContractCall,
+};
+use darkfi_serial::serialize;
+use rand::rngs::OsRng;
+
+use darkfi::{ClientFailed, Result};
+
+use crate::{
+    model::{DaoAuthCall, DaoStream},
+    DaoFunction,
+};
+
+/// Builds the calls of a proposal creating a treasury stream.
+///
+/// The proposal needs a `Dao::AuthMoneyTransfer` auth call creating the
+/// coin returned by [`Self::stream_coin_attrs()`], followed by the
+/// `Money::Transfer` one, and then the auth call returned by
+/// [`Self::auth_call()`]. On execution, the call made by [`Self::make()`]
+/// goes after the `Money::Transfer` in the `Dao::Exec` children.
+pub struct DaoAuthCreateStreamCall {
+    /// The stream being created
+    pub stream: DaoStream,
+}
+
+impl DaoAuthCreateStreamCall {
+    /// Create a new stream of `total` funds to `recipient`, releasing
+    /// `rate` on each block since `start_height`.
+    pub fn new(
+        recipient: PublicKey,
+        token_id: TokenId,
+        total: u64,
+        rate: u64,
+        start_height: u32,
+    ) -> Result<Self> {
+        if total == 0 || rate == 0 {
+            return Err(ClientFailed::InvalidAmount(total.min(rate)).into())
+        }
+
+        let stream = DaoStream {
+            recipient,
+            token_id,
+            total,
+            rate,
+            start_height,
+            coin_blind: BaseBlind::random(&mut OsRng),
+            blind: Blind::random(&mut OsRng),
+        };
+
+        Ok(Self { stream })
+    }
+
+    /// Attributes of the coin holding all of the stream funds, which the
+    /// proposal has to create through `Dao::AuthMoneyTransfer`.
+    pub fn stream_coin_attrs(&self) -> CoinAttributes {
+        self.stream.coin_attributes(self.stream.total, self.stream.coin_blind)
+    }
+
+    /// Auth call to add to the proposal
+    pub fn auth_call(&self) -> DaoAuthCall {
+        DaoAuthCall {
+            contract_id: *DAO_CONTRACT_ID,
+            function_code: DaoFunction::AuthCreateStream as u8,
+            auth_data: serialize(&self.stream),
+        }
+    }
+
+    /// Create the `Dao::AuthCreateStream` call. It has no params or proofs,
+    /// since everything gets verified against the proposal auth data.
+    pub fn make(&self) -> ContractCall {
+        ContractCall {
+            contract_id: *DAO_CONTRACT_ID,
+            data: vec![DaoFunction::AuthCreateStream as u8],
+        }
+    }
+}
@@ -0,0 +1,100 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_money_contract::model::CoinAttributes;
+use darkfi_sdk::{
+    crypto::{poseidon_hash, BaseBlind},
+    pasta::pallas,
+};
+
+use rand::rngs::OsRng;
+
+use darkfi::{
+    zk::{halo2::Value, Proof, ProvingKey, Witness, ZkCircuit},
+    zkas::ZkBinary,
+    ClientFailed, Result,
+};
+
+use crate::model::{DaoClaimStreamParams, DaoStream};
+
+pub struct DaoClaimStreamCall {
+    /// The stream being claimed from
+    pub stream: DaoStream,
+    /// Total amount claimed from the stream, including this claim
+    pub claimed: u64,
+    /// User data blind used in the money::transfer() input spending the stream coin
+    pub input_user_data_blind: BaseBlind,
+    /// The change coin going back into the stream. Must be the last
+    /// output of the money::transfer() call.
+    pub change_coin_attrs: CoinAttributes,
+    /// Height of the block the claim is expected to get verified in
+    pub current_height: u32,
+}
+
+impl DaoClaimStreamCall {
+    pub fn make(
+        self,
+        claim_stream_zkbin: &ZkBinary,
+        claim_stream_pk: &ProvingKey,
+    ) -> Result<(DaoClaimStreamParams, Vec<Proof>)> {
+        if self.claimed > self.stream.vested(self.current_height) {
+            return Err(ClientFailed::InvalidAmount(self.claimed).into())
+        }
+
+        let stream_bulla = self.stream.to_bulla();
+        let (recipient_x, recipient_y) = self.stream.recipient.xy();
+
+        let input_user_data_enc =
+            poseidon_hash([stream_bulla.inner(), self.input_user_data_blind.inner()]);
+
+        let prover_witnesses = vec![
+            // Stream params
+            Witness::Base(Value::known(recipient_x)),
+            Witness::Base(Value::known(recipient_y)),
+            Witness::Base(Value::known(self.stream.token_id.inner())),
+            Witness::Base(Value::known(pallas::Base::from(self.stream.total))),
+            Witness::Base(Value::known(pallas::Base::from(self.stream.rate))),
+            Witness::Base(Value::known(pallas::Base::from(self.stream.start_height as u64))),
+            Witness::Base(Value::known(self.stream.blind.inner())),
+            // Stream coin user data blind
+            Witness::Base(Value::known(self.input_user_data_blind.inner())),
+            // Claim
+            Witness::Base(Value::known(pallas::Base::from(self.claimed))),
+            Witness::Base(Value::known(self.change_coin_attrs.blind.inner())),
+            // DAO::claim_stream() func ID
+            Witness::Base(Value::known(self.change_coin_attrs.spend_hook.inner())),
+            Witness::Base(Value::known(pallas::Base::from(self.current_height as u64))),
+        ];
+
+        let public_inputs = vec![
+            stream_bulla.inner(),
+            input_user_data_enc,
+            self.change_coin_attrs.to_coin().inner(),
+            self.change_coin_attrs.spend_hook.inner(),
+            pallas::Base::from(self.claimed),
+            pallas::Base::from(self.current_height as u64),
+        ];
+
+        let circuit = ZkCircuit::new(prover_witnesses, claim_stream_zkbin);
+        let proof = Proof::create(claim_stream_pk, &[circuit], &public_inputs, &mut OsRng)?;
+
+        let params = DaoClaimStreamParams { stream_bulla, claimed: self.claimed };
+
+        Ok((params, vec![proof]))
+    }
+}
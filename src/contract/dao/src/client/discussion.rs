@@ -0,0 +1,155 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Encrypted proposal discussion threads.
+//!
+//! Comments on a proposal are encrypted to the DAO proposals public key,
+//! so only those able to view the proposal can read them, and published
+//! as event graph events. Each event carries a thread tag, derived from
+//! the proposals public key and the proposal bulla, so members can find
+//! the comments of a proposal without trial decrypting everything, while
+//! outsiders can't link a thread to the on-chain proposal. The proposal
+//! expiry is kept in the clear, so any node can prune stale threads.
+
+use std::collections::HashMap;
+
+use darkfi_sdk::{
+    crypto::{
+        note::{AeadEncryptedNote, EncryptableNote},
+        pasta_prelude::*,
+        poseidon_hash, SecretKey,
+    },
+    pasta::pallas,
+};
+use darkfi_serial::{async_trait, SerialDecodable, SerialEncodable};
+use rand::rngs::OsRng;
+
+use darkfi::{ClientFailed, Result};
+
+use crate::model::{Dao, DaoProposal, DaoProposalBulla};
+
+/// A plaintext comment in a proposal discussion thread
+#[derive(Debug, Clone, PartialEq, SerialEncodable, SerialDecodable, EncryptableNote)]
+#[note(version = 1)]
+pub struct DaoComment {
+    /// Name the author chose to post with. This is not authenticated.
+    pub author: String,
+    /// Comment text
+    pub body: String,
+    /// UNIX timestamp of the comment, in seconds
+    pub timestamp: u64,
+}
+
+/// Thread tag of a proposal discussion
+pub fn discussion_tag(dao: &Dao, proposal_bulla: &DaoProposalBulla) -> pallas::Base {
+    let (pub_x, pub_y) = dao.proposals_public_key.xy();
+    poseidon_hash([pub_x, pub_y, proposal_bulla.inner()])
+}
+
+/// An encrypted comment, used as the content of an event graph event
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct DaoDiscussionEvent {
+    /// Thread tag, see [`discussion_tag`]
+    pub tag: pallas::Base,
+    /// Blockwindow the proposal expires at, after which the comment
+    /// can be pruned
+    pub expiry_blockwindow: u64,
+    /// The [`DaoComment`] encrypted to the DAO proposals public key
+    pub note: AeadEncryptedNote,
+}
+
+impl DaoDiscussionEvent {
+    /// Encrypt a comment on the given proposal of `dao`
+    pub fn new(dao: &Dao, proposal: &DaoProposal, comment: &DaoComment) -> Result<Self> {
+        let note = match comment.encrypt_note(&dao.proposals_public_key, &mut OsRng) {
+            Ok(note) => note,
+            Err(e) => {
+                return Err(ClientFailed::InternalError(format!(
+                    "Failed encrypting discussion comment: {e}"
+                ))
+                .into())
+            }
+        };
+
+        Ok(Self {
+            tag: discussion_tag(dao, &proposal.to_bulla()),
+            expiry_blockwindow: proposal.creation_blockwindow + proposal.duration_blockwindows,
+            note,
+        })
+    }
+
+    /// Decrypt the comment using the DAO proposals secret key
+    pub fn decrypt(&self, proposals_secret_key: &SecretKey) -> Result<DaoComment> {
+        match DaoComment::decrypt_note(&self.note, proposals_secret_key) {
+            Ok(comment) => Ok(comment),
+            Err(e) => {
+                Err(ClientFailed::VerifyError(format!("Failed decrypting discussion comment: {e}"))
+                    .into())
+            }
+        }
+    }
+
+    /// Check if the proposal this comment belongs to has expired
+    pub fn is_expired(&self, current_blockwindow: u64) -> bool {
+        current_blockwindow > self.expiry_blockwindow
+    }
+}
+
+/// Local store of the discussion threads of a DAO's proposals
+pub struct DaoDiscussions {
+    /// DAO proposals secret key, used to decrypt comments
+    proposals_secret_key: SecretKey,
+    /// Threads keyed by their tag, holding the expiry blockwindow and
+    /// the comments in the order they were received
+    threads: HashMap<[u8; 32], (u64, Vec<DaoComment>)>,
+}
+
+impl DaoDiscussions {
+    pub fn new(proposals_secret_key: SecretKey) -> Self {
+        Self { proposals_secret_key, threads: HashMap::new() }
+    }
+
+    /// Add a received event to its thread. Events that fail to decrypt
+    /// belong to other DAOs and get rejected.
+    pub fn insert(&mut self, event: &DaoDiscussionEvent) -> Result<()> {
+        let comment = event.decrypt(&self.proposals_secret_key)?;
+        let thread = self
+            .threads
+            .entry(event.tag.to_repr())
+            .or_insert_with(|| (event.expiry_blockwindow, vec![]));
+        thread.1.push(comment);
+        Ok(())
+    }
+
+    /// Fetch the comments of a proposal, ordered by timestamp
+    pub fn get(&self, dao: &Dao, proposal_bulla: &DaoProposalBulla) -> Vec<DaoComment> {
+        let tag = discussion_tag(dao, proposal_bulla).to_repr();
+        let Some((_, comments)) = self.threads.get(&tag) else { return vec![] };
+
+        let mut comments = comments.clone();
+        comments.sort_by_key(|c| c.timestamp);
+        comments
+    }
+
+    /// Drop the threads of expired proposals, returning how many got pruned
+    pub fn prune(&mut self, current_blockwindow: u64) -> usize {
+        let before = self.threads.len();
+        self.threads.retain(|_, (expiry, _)| current_blockwindow <= *expiry);
+        before - self.threads.len()
+    }
+}
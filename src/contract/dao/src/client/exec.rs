@@ -122,6 +122,7 @@ impl DaoExecCall {
         };
         // Rest witnesses
         prover_witnesses.extend_from_slice(&[
+            Witness::Base(Value::known(self.dao.membership_root_inner())),
             Witness::Base(Value::known(self.dao.bulla_blind.inner())),
             // Votes
             Witness::Base(Value::known(pallas::Base::from(self.yes_vote_value))),
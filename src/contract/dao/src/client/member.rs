@@ -0,0 +1,103 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::{
+    bridgetree,
+    crypto::{poseidon_hash, BaseBlind, MerkleNode, MerkleTree, PublicKey},
+    pasta::pallas,
+};
+use darkfi_serial::{async_trait, SerialDecodable, SerialEncodable};
+
+/// A member of an allowlist DAO. Members get enrolled in the DAO
+/// membership set when it is created, and vote with their weight.
+#[derive(Debug, Clone, Copy, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct DaoMember {
+    /// Member public key, whose secret is used to vote
+    pub public_key: PublicKey,
+    /// Voting weight of the member
+    pub weight: u64,
+    /// Blind hiding the member in the membership set
+    pub blind: BaseBlind,
+}
+
+impl DaoMember {
+    /// Commitment to the member, used as the membership tree leaf
+    pub fn to_leaf(&self) -> MerkleNode {
+        let (pub_x, pub_y) = self.public_key.xy();
+        let leaf =
+            poseidon_hash([pub_x, pub_y, pallas::Base::from(self.weight), self.blind.inner()]);
+        MerkleNode::from(leaf)
+    }
+}
+
+/// Set of approved members of an allowlist DAO. Its root is set as the
+/// DAO `membership_root`, and members need their leaf position along
+/// with the Merkle path to vote.
+pub struct DaoMemberSet {
+    tree: MerkleTree,
+    members: Vec<DaoMember>,
+}
+
+impl Default for DaoMemberSet {
+    fn default() -> Self {
+        Self { tree: MerkleTree::new(1), members: vec![] }
+    }
+}
+
+impl DaoMemberSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build the set from a list of members, in enrollment order
+    pub fn from_members(members: &[DaoMember]) -> Self {
+        let mut set = Self::new();
+        for member in members {
+            set.enroll(member);
+        }
+        set
+    }
+
+    /// Enroll a new member, returning its position in the set
+    pub fn enroll(&mut self, member: &DaoMember) -> bridgetree::Position {
+        self.tree.append(member.to_leaf());
+        self.members.push(*member);
+        self.tree.mark().unwrap()
+    }
+
+    /// Enrolled members, in enrollment order
+    pub fn members(&self) -> &[DaoMember] {
+        &self.members
+    }
+
+    /// Root of the set, to be committed in the DAO bulla
+    pub fn root(&self) -> MerkleNode {
+        self.tree.root(0).unwrap()
+    }
+
+    /// Find a member position and its Merkle path in the set
+    pub fn witness(
+        &self,
+        public_key: &PublicKey,
+    ) -> Option<(bridgetree::Position, Vec<MerkleNode>)> {
+        let idx = self.members.iter().position(|m| &m.public_key == public_key)?;
+        let position = bridgetree::Position::from(idx as u64);
+        let path = self.tree.witness(position, 0)?;
+        Some((position, path))
+    }
+}
@@ -61,6 +61,7 @@ pub fn make_mint_call(
         Witness::Base(Value::known(dao_votes_secret_key.inner())),
         Witness::Base(Value::known(dao_exec_secret_key.inner())),
         Witness::Base(Value::known(dao_early_exec_secret_key.inner())),
+        Witness::Base(Value::known(dao.membership_root_inner())),
         Witness::Base(Value::known(dao.bulla_blind.inner())),
     ];
 
@@ -30,12 +30,52 @@ pub use propose::{DaoProposeCall, DaoProposeStakeInput};
 ///
 /// * `DaoVoteInput` are the inputs used in actual voting.
 /// * `DaoVoteCall` is what creates the call data used on chain.
+/// * `DaoVoteMemberInput` are the inputs used when voting in allowlist DAOs.
+/// * `DaoVoteMemberCall` creates the call data for allowlist DAOs.
 /// * `DaoVoteNote` is the secret shared info transmitted between DAO members.
 pub mod vote;
-pub use vote::{DaoVoteCall, DaoVoteInput};
+pub use vote::{DaoVoteCall, DaoVoteInput, DaoVoteMemberCall, DaoVoteMemberInput};
+
+/// Provides the allowlist of DAOs using membership voting
+///
+/// * `DaoMember` is a single allowlisted member and its voting weight.
+/// * `DaoMemberSet` builds the membership tree committed in the DAO bulla.
+pub mod member;
+pub use member::{DaoMember, DaoMemberSet};
 
 pub mod exec;
 pub use exec::DaoExecCall;
 
 pub mod auth_xfer;
 pub use auth_xfer::DaoAuthMoneyTransferCall;
+
+/// Provides the threshold decryption of proposals encrypted tally
+///
+/// * `DaoTallyKeyShare` is a share of the DAO votes secret key.
+/// * `DaoTallyPartial` is a shareholder's partial decryption of a tally.
+pub mod tally;
+pub use tally::{
+    decrypt_tally, decrypt_tally_with_key, DaoTallyKeyShare, DaoTallyPartial, MAX_TALLY_VALUE,
+};
+
+/// Provides core structs for DAO::auth_create_stream()
+///
+/// * `DaoAuthCreateStreamCall` creates the proposal auth call and the
+///   call data used to create a treasury stream.
+pub mod auth_stream;
+pub use auth_stream::DaoAuthCreateStreamCall;
+
+/// Provides core structs for DAO::claim_stream()
+///
+/// * `DaoClaimStreamCall` creates the call data and proof used to withdraw
+///   the vested funds of a treasury stream.
+pub mod claim_stream;
+pub use claim_stream::DaoClaimStreamCall;
+
+/// Provides encrypted proposal discussion threads over the event graph
+///
+/// * `DaoComment` is a plaintext comment on a proposal.
+/// * `DaoDiscussionEvent` is an encrypted comment, used as event content.
+/// * `DaoDiscussions` is the local store of decrypted threads.
+pub mod discussion;
+pub use discussion::{discussion_tag, DaoComment, DaoDiscussionEvent, DaoDiscussions};
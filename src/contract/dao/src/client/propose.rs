@@ -21,7 +21,7 @@ use darkfi_sdk::{
     bridgetree,
     bridgetree::Hashable,
     crypto::{
-        note::AeadEncryptedNote,
+        note::{ElGamalEncryptedNote, EncryptableNote},
         pasta_prelude::*,
         pedersen::pedersen_commitment_u64,
         poseidon_hash,
@@ -187,7 +187,6 @@ impl<T: StorageAdapter<Value = pallas::Base>> DaoProposeCall<'_, T> {
         let dao_approval_ratio_quot = pallas::Base::from(self.dao.approval_ratio_quot);
         let dao_approval_ratio_base = pallas::Base::from(self.dao.approval_ratio_base);
         let (dao_notes_pub_x, dao_notes_pub_y) = self.dao.notes_public_key.xy();
-        let (dao_proposals_pub_x, dao_proposals_pub_y) = self.dao.proposals_public_key.xy();
         let (dao_votes_pub_x, dao_votes_pub_y) = self.dao.votes_public_key.xy();
         let (dao_exec_pub_x, dao_exec_pub_y) = self.dao.exec_public_key.xy();
         let (dao_early_exec_pub_x, dao_early_exec_pub_y) = self.dao.early_exec_public_key.xy();
@@ -199,6 +198,20 @@ impl<T: StorageAdapter<Value = pallas::Base>> DaoProposeCall<'_, T> {
         }
         let proposal_bulla = self.proposal.to_bulla();
 
+        // Verifiable encryption of the proposal bulla preimage
+        let ephem_secret = SecretKey::random(&mut OsRng);
+        let note = [
+            self.proposal.auth_calls.commit(),
+            pallas::Base::from(self.proposal.duration_blockwindows),
+            self.proposal.user_data,
+            self.proposal.blind.inner(),
+        ];
+        let verifiable_note = ElGamalEncryptedNote::encrypt_unsafe(
+            note,
+            &ephem_secret,
+            &self.dao.proposals_public_key,
+        )?;
+
         let prover_witnesses = vec![
             // Proposers total number of gov tokens
             Witness::Base(Value::known(total_funds)),
@@ -221,19 +234,21 @@ impl<T: StorageAdapter<Value = pallas::Base>> DaoProposeCall<'_, T> {
             Witness::Base(Value::known(dao_notes_pub_x)),
             Witness::Base(Value::known(dao_notes_pub_y)),
             Witness::Base(Value::known(dao_proposer_secret_key.inner())),
-            Witness::Base(Value::known(dao_proposals_pub_x)),
-            Witness::Base(Value::known(dao_proposals_pub_y)),
+            Witness::EcNiPoint(Value::known(self.dao.proposals_public_key.inner())),
             Witness::Base(Value::known(dao_votes_pub_x)),
             Witness::Base(Value::known(dao_votes_pub_y)),
             Witness::Base(Value::known(dao_exec_pub_x)),
             Witness::Base(Value::known(dao_exec_pub_y)),
             Witness::Base(Value::known(dao_early_exec_pub_x)),
             Witness::Base(Value::known(dao_early_exec_pub_y)),
+            Witness::Base(Value::known(self.dao.membership_root_inner())),
             Witness::Base(Value::known(self.dao.bulla_blind.inner())),
             Witness::Uint32(Value::known(dao_leaf_position.try_into().unwrap())),
             Witness::MerklePath(Value::known(self.dao_merkle_path.try_into().unwrap())),
+            // Verifiable encryption
+            Witness::Base(Value::known(ephem_secret.inner())),
         ];
-        let public_inputs = vec![
+        let mut public_inputs = vec![
             token_commit,
             self.dao_merkle_root.inner(),
             proposal_bulla.inner(),
@@ -241,6 +256,7 @@ impl<T: StorageAdapter<Value = pallas::Base>> DaoProposeCall<'_, T> {
             *total_funds_coords.x(),
             *total_funds_coords.y(),
         ];
+        public_inputs.extend(verifiable_note.public_inputs());
         //darkfi::zk::export_witness_json("proof/witness/propose-main.json", &prover_witnesses, &public_inputs);
         let circuit = ZkCircuit::new(prover_witnesses, main_zkbin);
 
@@ -248,13 +264,13 @@ impl<T: StorageAdapter<Value = pallas::Base>> DaoProposeCall<'_, T> {
         proofs.push(main_proof);
 
         let enc_note =
-            AeadEncryptedNote::encrypt(&self.proposal, &self.dao.proposals_public_key, &mut OsRng)
-                .unwrap();
+            self.proposal.encrypt_note(&self.dao.proposals_public_key, &mut OsRng).unwrap();
         let params = DaoProposeParams {
             dao_merkle_root: self.dao_merkle_root,
             proposal_bulla,
             token_commit,
             note: enc_note,
+            verifiable_note,
             inputs,
         };
 
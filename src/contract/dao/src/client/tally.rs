@@ -0,0 +1,239 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Threshold decryption of the DAO proposals encrypted tally.
+//!
+//! The DAO votes secret key gets split into `n` shares using Shamir's
+//! secret sharing, so that any `k` of the shareholders can together
+//! decrypt the aggregated tally of a proposal.
+//!
+//! This only hides individual votes from coalitions of less than `k`
+//! shareholders:
+//! * The split is done by a trusted dealer holding the full votes secret
+//!   key, who can decrypt every single vote, including its note. The
+//!   dealer has to discard the key after handing out the shares.
+//! * Anyone else still holding the full key, e.g. a DAO member who kept
+//!   the DAO parameters, can do the same.
+//! * Any `k` shareholders can run the same partial decryption over each
+//!   vote ciphertext published on chain, so they learn individual votes
+//!   as easily as the tally.
+//!
+//! Recovering the values requires a discrete log, so decryption only works
+//! when the total voting power is at most [`MAX_TALLY_VALUE`]. DAOs with a
+//! larger governance token supply have to count the vote notes instead.
+
+use std::collections::HashMap;
+
+use darkfi_sdk::{
+    crypto::{
+        pasta_prelude::*, pedersen_commitment_u64, util::fp_mod_fv, Blind, PublicKey, SecretKey,
+    },
+    pasta::{group::GroupEncoding, pallas},
+};
+use darkfi_serial::{async_trait, SerialDecodable, SerialEncodable};
+use rand::rngs::OsRng;
+
+use darkfi::{ClientFailed, Result};
+
+use crate::model::{DaoEncryptedTally, DaoTallyCiphertext};
+
+/// Largest total vote value a tally can be decrypted for. The baby-step
+/// giant-step table holds `sqrt(MAX_TALLY_VALUE)` points, about 2^20.
+pub const MAX_TALLY_VALUE: u64 = 1 << 40;
+
+/// Encrypt a vote value under the DAO votes public key
+pub fn encrypt_tally_value(
+    value: u64,
+    ephem_secret: &SecretKey,
+    public: &PublicKey,
+) -> DaoTallyCiphertext {
+    let ephem = PublicKey::from_secret(*ephem_secret).inner();
+    let mask = public.inner() * fp_mod_fv(ephem_secret.inner());
+    let masked = pedersen_commitment_u64(value, Blind::ZERO) + mask;
+    DaoTallyCiphertext { ephem, masked }
+}
+
+/// A share of the DAO votes secret key
+#[derive(Debug, Clone, Copy, SerialEncodable, SerialDecodable)]
+pub struct DaoTallyKeyShare {
+    /// Share index, starting from 1
+    pub index: u64,
+    /// Evaluation of the sharing polynomial at `index`
+    pub share: pallas::Scalar,
+}
+
+impl DaoTallyKeyShare {
+    /// Split the DAO votes secret key into `shares` shares,
+    /// any `threshold` of which can decrypt a tally.
+    pub fn split(secret: &SecretKey, threshold: usize, shares: usize) -> Result<Vec<Self>> {
+        if threshold == 0 || threshold > shares {
+            return Err(ClientFailed::VerifyError(format!(
+                "Invalid threshold {threshold} for {shares} shares"
+            ))
+            .into())
+        }
+
+        // Random polynomial of degree threshold - 1, with the secret as
+        // its constant term.
+        let mut coeffs = vec![fp_mod_fv(secret.inner())];
+        for _ in 1..threshold {
+            coeffs.push(pallas::Scalar::random(&mut OsRng));
+        }
+
+        let shares = (1..=shares as u64)
+            .map(|index| {
+                let x = pallas::Scalar::from(index);
+                let share = coeffs.iter().rev().fold(pallas::Scalar::ZERO, |acc, c| acc * x + c);
+                Self { index, share }
+            })
+            .collect();
+
+        Ok(shares)
+    }
+
+    /// Compute this share's partial decryption of a tally
+    pub fn partial_decrypt(&self, tally: &DaoEncryptedTally) -> DaoTallyPartial {
+        DaoTallyPartial {
+            index: self.index,
+            yes: tally.yes.ephem * self.share,
+            all: tally.all.ephem * self.share,
+        }
+    }
+}
+
+/// Partial decryption of a tally, produced by a single shareholder
+#[derive(Debug, Clone, Copy, SerialEncodable, SerialDecodable)]
+pub struct DaoTallyPartial {
+    /// Index of the share used
+    pub index: u64,
+    /// Partial decryption of the yes vote ciphertext
+    pub yes: pallas::Point,
+    /// Partial decryption of the total vote ciphertext
+    pub all: pallas::Point,
+}
+
+/// Combine at least `threshold` partial decryptions of a tally, returning
+/// the yes and total vote values. `max_value` bounds the total vote value,
+/// usually being the governance token supply, and can't be larger than
+/// [`MAX_TALLY_VALUE`].
+pub fn decrypt_tally(
+    tally: &DaoEncryptedTally,
+    partials: &[DaoTallyPartial],
+    max_value: u64,
+) -> Result<(u64, u64)> {
+    if max_value > MAX_TALLY_VALUE {
+        return Err(ClientFailed::VerifyError(format!(
+            "Tally range {max_value} exceeds {MAX_TALLY_VALUE}, count the vote notes instead"
+        ))
+        .into())
+    }
+
+    let mut indexes: Vec<u64> = partials.iter().map(|p| p.index).collect();
+    indexes.sort_unstable();
+    indexes.dedup();
+    if indexes.len() != partials.len() || indexes.contains(&0) {
+        return Err(
+            ClientFailed::VerifyError("Invalid partial decryption indexes".to_string()).into()
+        )
+    }
+
+    // Lagrange interpolation at zero gives us secret * ephem
+    let mut yes_mask = pallas::Point::identity();
+    let mut all_mask = pallas::Point::identity();
+    for partial in partials {
+        let coeff = lagrange_coeff(partial.index, &indexes);
+        yes_mask += partial.yes * coeff;
+        all_mask += partial.all * coeff;
+    }
+
+    let solver = DiscreteLogSolver::new(max_value);
+    let Some(yes) = solver.solve(tally.yes.masked - yes_mask) else {
+        return Err(ClientFailed::VerifyError("Unable to decrypt yes votes tally".to_string()).into())
+    };
+    let Some(all) = solver.solve(tally.all.masked - all_mask) else {
+        return Err(ClientFailed::VerifyError("Unable to decrypt all votes tally".to_string()).into())
+    };
+
+    Ok((yes, all))
+}
+
+/// Decrypt a tally using the full DAO votes secret key
+pub fn decrypt_tally_with_key(
+    tally: &DaoEncryptedTally,
+    secret: &SecretKey,
+    max_value: u64,
+) -> Result<(u64, u64)> {
+    let share = DaoTallyKeyShare { index: 1, share: fp_mod_fv(secret.inner()) };
+    // A single share of a degree 0 polynomial is the secret itself
+    decrypt_tally(tally, &[share.partial_decrypt(tally)], max_value)
+}
+
+/// Lagrange basis polynomial of `index` evaluated at zero
+fn lagrange_coeff(index: u64, indexes: &[u64]) -> pallas::Scalar {
+    let x_i = pallas::Scalar::from(index);
+    let mut num = pallas::Scalar::ONE;
+    let mut den = pallas::Scalar::ONE;
+    for j in indexes.iter().filter(|j| **j != index) {
+        let x_j = pallas::Scalar::from(*j);
+        num *= x_j;
+        den *= x_j - x_i;
+    }
+    // Indexes are distinct, so this is never zero
+    num * den.invert().unwrap()
+}
+
+/// Baby-step giant-step solver for `v * V = P`, with `v <= max_value`
+struct DiscreteLogSolver {
+    /// Number of baby steps
+    step: u64,
+    /// Baby steps table, `j * V => j`
+    table: HashMap<[u8; 32], u64>,
+    /// Giant step point, `step * V`
+    giant: pallas::Point,
+    max_value: u64,
+}
+
+impl DiscreteLogSolver {
+    fn new(max_value: u64) -> Self {
+        let step = ((max_value as f64).sqrt() as u64).saturating_add(1);
+        let generator = pedersen_commitment_u64(1, Blind::ZERO);
+
+        let mut table = HashMap::with_capacity(step as usize);
+        let mut point = pallas::Point::identity();
+        for j in 0..step {
+            table.insert(point.to_bytes(), j);
+            point += generator;
+        }
+
+        Self { step, table, giant: point, max_value }
+    }
+
+    fn solve(&self, target: pallas::Point) -> Option<u64> {
+        let mut point = target;
+        let mut i = 0;
+        while i.saturating_mul(self.step) <= self.max_value {
+            if let Some(j) = self.table.get(&point.to_bytes()) {
+                let value = i * self.step + j;
+                return (value <= self.max_value).then_some(value)
+            }
+            point -= self.giant;
+            i += 1;
+        }
+        None
+    }
+}
@@ -39,9 +39,12 @@ use darkfi::{
     ClientFailed, Result,
 };
 
+use super::{member::DaoMember, tally::encrypt_tally_value};
 use crate::{
     error::DaoError,
-    model::{Dao, DaoProposal, DaoVoteParams, DaoVoteParamsInput, VecAuthCallCommit},
+    model::{
+        Dao, DaoEncryptedTally, DaoProposal, DaoVoteParams, DaoVoteParamsInput, VecAuthCallCommit,
+    },
 };
 
 pub struct DaoVoteInput {
@@ -52,6 +55,14 @@ pub struct DaoVoteInput {
     pub signature_secret: SecretKey,
 }
 
+pub struct DaoVoteMemberInput {
+    pub secret: SecretKey,
+    pub member: DaoMember,
+    pub leaf_position: bridgetree::Position,
+    pub merkle_path: Vec<MerkleNode>,
+    pub signature_secret: SecretKey,
+}
+
 // Inside ZK proof, check proposal is correct.
 pub struct DaoVoteCall<'a, T: StorageAdapter<Value = pallas::Base>> {
     pub money_null_smt:
@@ -76,6 +87,10 @@ impl<T: StorageAdapter<Value = pallas::Base>> DaoVoteCall<'_, T> {
         if self.dao.to_bulla() != self.proposal.dao_bulla {
             return Err(ClientFailed::VerifyError(DaoError::InvalidCalls.to_string()).into())
         }
+        // Allowlist DAOs must be voted on using `DaoVoteMemberCall`
+        if self.dao.membership_root.is_some() {
+            return Err(ClientFailed::VerifyError(DaoError::VoteModeMismatch.to_string()).into())
+        }
         let proposal_bulla = self.proposal.to_bulla();
 
         let mut proofs = vec![];
@@ -88,27 +103,7 @@ impl<T: StorageAdapter<Value = pallas::Base>> DaoVoteCall<'_, T> {
 
         let last_input_idx = self.inputs.len() - 1;
         for (i, input) in self.inputs.into_iter().enumerate() {
-            // Last input
-            // Choose a blinding factor that can be converted to pallas::Base exactly.
-            // We need this so we can verifiably encrypt the sum of input blinds
-            // in the next section.
-            // TODO: make a generalized widget for this, and also picking blinds in money::transfer()
-            let mut value_blind = pallas::Scalar::random(&mut OsRng);
-
-            if i == last_input_idx {
-                // It's near zero chance it ever loops at all.
-                // P(random 𝔽ᵥ ∉ 𝔽ₚ) = (q - p)/q = 2.99 × 10⁻⁵¹
-                loop {
-                    let av_blind = fv_mod_fp_unsafe(all_vote_blind + value_blind);
-
-                    if av_blind.is_none().into() {
-                        value_blind = pallas::Scalar::random(&mut OsRng);
-                        continue
-                    }
-
-                    break
-                }
-            }
+            let value_blind = input_value_blind(all_vote_blind, i == last_input_idx);
 
             all_vote_value += input.note.value;
             all_vote_blind += value_blind;
@@ -155,19 +150,8 @@ impl<T: StorageAdapter<Value = pallas::Base>> DaoVoteCall<'_, T> {
                 Witness::Base(Value::known(input.signature_secret.inner())),
             ];
 
-            let merkle_root = {
-                let position: u64 = input.leaf_position.into();
-                let mut current = MerkleNode::from(coin.inner());
-                for (level, sibling) in input.merkle_path.iter().enumerate() {
-                    let level = level as u8;
-                    current = if position & (1 << level) == 0 {
-                        MerkleNode::combine(level.into(), &current, sibling)
-                    } else {
-                        MerkleNode::combine(level.into(), sibling, &current)
-                    };
-                }
-                current
-            };
+            let merkle_root =
+                merkle_root(leaf_pos, MerkleNode::from(coin.inner()), &input.merkle_path);
 
             let token_commit = poseidon_hash([note.token_id.inner(), gov_token_blind]);
             if note.token_id != self.dao.gov_token_id {
@@ -208,125 +192,326 @@ impl<T: StorageAdapter<Value = pallas::Base>> DaoVoteCall<'_, T> {
             inputs.push(input);
         }
 
-        let token_commit = poseidon_hash([self.dao.gov_token_id.inner(), gov_token_blind]);
-
-        let dao_proposer_limit = pallas::Base::from(self.dao.proposer_limit);
-        let dao_quorum = pallas::Base::from(self.dao.quorum);
-        let dao_early_exec_quorum = pallas::Base::from(self.dao.early_exec_quorum);
-        let dao_approval_ratio_quot = pallas::Base::from(self.dao.approval_ratio_quot);
-        let dao_approval_ratio_base = pallas::Base::from(self.dao.approval_ratio_base);
-        let (dao_notes_pub_x, dao_notes_pub_y) = self.dao.notes_public_key.xy();
-        let (dao_proposer_pub_x, dao_proposer_pub_y) = self.dao.proposer_public_key.xy();
-        let (dao_proposals_pub_x, dao_proposals_pub_y) = self.dao.proposals_public_key.xy();
-        let dao_votes_public_key = self.dao.votes_public_key.inner();
-        let (dao_exec_pub_x, dao_exec_pub_y) = self.dao.exec_public_key.xy();
-        let (dao_early_exec_pub_x, dao_early_exec_pub_y) = self.dao.early_exec_public_key.xy();
-
-        let vote_option = self.vote_option as u64;
-        if vote_option != 0 && vote_option != 1 {
+        let (params, main_proof) = make_main_proof(
+            &self.dao,
+            &self.proposal,
+            self.vote_option,
+            self.current_blockwindow,
+            gov_token_blind,
+            inputs,
+            all_vote_value,
+            all_vote_blind,
+            main_zkbin,
+            main_pk,
+        )?;
+        proofs.push(main_proof);
+
+        Ok((params, proofs))
+    }
+}
+
+// Inside ZK proof, check the voters are members of the allowlist DAO.
+pub struct DaoVoteMemberCall {
+    pub inputs: Vec<DaoVoteMemberInput>,
+    pub vote_option: bool,
+    pub proposal: DaoProposal,
+    pub dao: Dao,
+    pub current_blockwindow: u64,
+}
+
+impl DaoVoteMemberCall {
+    pub fn make(
+        self,
+        member_zkbin: &ZkBinary,
+        member_pk: &ProvingKey,
+        main_zkbin: &ZkBinary,
+        main_pk: &ProvingKey,
+    ) -> Result<(DaoVoteParams, Vec<Proof>)> {
+        debug!(target: "contract::dao::client::vote", "DaoVoteMemberCall::make()");
+
+        if self.dao.to_bulla() != self.proposal.dao_bulla {
+            return Err(ClientFailed::VerifyError(DaoError::InvalidCalls.to_string()).into())
+        }
+        let Some(membership_root) = self.dao.membership_root else {
+            return Err(ClientFailed::VerifyError(DaoError::VoteModeMismatch.to_string()).into())
+        };
+        if self.inputs.is_empty() {
             return Err(ClientFailed::VerifyError(DaoError::VoteInputsEmpty.to_string()).into())
         }
+        let proposal_bulla = self.proposal.to_bulla();
+
+        let mut proofs = vec![];
+
+        let gov_token_blind = pallas::Base::random(&mut OsRng);
+        let token_commit = poseidon_hash([membership_root.inner(), gov_token_blind]);
+
+        let mut inputs = vec![];
+        let mut all_vote_value = 0;
+        let mut all_vote_blind = pallas::Scalar::from(0);
+
+        let last_input_idx = self.inputs.len() - 1;
+        for (i, input) in self.inputs.into_iter().enumerate() {
+            let value_blind = input_value_blind(all_vote_blind, i == last_input_idx);
 
-        // Create a random blind b ∈ 𝔽ᵥ, such that b ∈ 𝔽ₚ
-        let yes_vote_blind = loop {
-            let blind = pallas::Scalar::random(&mut OsRng);
-            if fv_mod_fp_unsafe(blind).is_some().into() {
-                break blind
+            all_vote_value += input.member.weight;
+            all_vote_blind += value_blind;
+
+            if PublicKey::from_secret(input.secret) != input.member.public_key {
+                return Err(ClientFailed::InvalidAddress(input.member.public_key.to_string()).into())
+            }
+
+            let leaf_pos: u64 = input.leaf_position.into();
+            let member = input.member.to_leaf();
+            if merkle_root(leaf_pos, member, &input.merkle_path) != membership_root {
+                return Err(
+                    ClientFailed::VerifyError(DaoError::InvalidInputMerkleRoot.to_string()).into()
+                )
             }
-        };
-        let yes_vote_commit =
-            pedersen_commitment_u64(vote_option * all_vote_value, Blind(yes_vote_blind));
-        let yes_vote_commit_coords = yes_vote_commit.to_affine().coordinates().unwrap();
 
-        let all_vote_commit = pedersen_commitment_u64(all_vote_value, Blind(all_vote_blind));
-        if all_vote_commit != inputs.iter().map(|i| i.vote_commit).sum() {
-            return Err(ClientFailed::VerifyError(DaoError::VoteCommitMismatch.to_string()).into())
+            let signature_public = PublicKey::from_secret(input.signature_secret);
+
+            let prover_witnesses = vec![
+                Witness::Base(Value::known(input.secret.inner())),
+                Witness::Base(Value::known(pallas::Base::from(input.member.weight))),
+                Witness::Base(Value::known(input.member.blind.inner())),
+                Witness::Base(Value::known(proposal_bulla.inner())),
+                Witness::Scalar(Value::known(value_blind)),
+                Witness::Base(Value::known(gov_token_blind)),
+                Witness::Uint32(Value::known(leaf_pos.try_into().unwrap())),
+                Witness::MerklePath(Value::known(input.merkle_path.clone().try_into().unwrap())),
+                Witness::Base(Value::known(input.signature_secret.inner())),
+            ];
+
+            let vote_commit = pedersen_commitment_u64(input.member.weight, Blind(value_blind));
+            let vote_commit_coords = vote_commit.to_affine().coordinates().unwrap();
+
+            let (sig_x, sig_y) = signature_public.xy();
+
+            let vote_nullifier =
+                poseidon_hash([input.secret.inner(), member.inner(), proposal_bulla.inner()]);
+
+            let public_inputs = vec![
+                proposal_bulla.inner(),
+                vote_nullifier,
+                *vote_commit_coords.x(),
+                *vote_commit_coords.y(),
+                token_commit,
+                sig_x,
+                sig_y,
+            ];
+
+            //darkfi::zk::export_witness_json("proof/witness/vote-input-member.json", &prover_witnesses, &public_inputs);
+            let circuit = ZkCircuit::new(prover_witnesses, member_zkbin);
+            debug!(target: "contract::dao::client::vote", "member_proof Proof::create()");
+            let member_proof = Proof::create(member_pk, &[circuit], &public_inputs, &mut OsRng)?;
+            proofs.push(member_proof);
+
+            let input = DaoVoteParamsInput {
+                vote_commit,
+                vote_nullifier: vote_nullifier.into(),
+                signature_public,
+            };
+            inputs.push(input);
         }
-        let all_vote_commit_coords = all_vote_commit.to_affine().coordinates().unwrap();
-
-        // Convert blinds to 𝔽ₚ, which should work fine since we selected them
-        // to be convertable.
-        let yes_vote_blind = Blind(fv_mod_fp_unsafe(yes_vote_blind).unwrap());
-        let all_vote_blind = Blind(fv_mod_fp_unsafe(all_vote_blind).unwrap());
-
-        let vote_option = pallas::Base::from(vote_option);
-        let all_vote_value_fp = pallas::Base::from(all_vote_value);
-        let ephem_secret = SecretKey::random(&mut OsRng);
-        let ephem_pubkey = PublicKey::from_secret(ephem_secret);
-        let (ephem_x, ephem_y) = ephem_pubkey.xy();
-
-        let current_blockwindow = pallas::Base::from(self.current_blockwindow);
-
-        let prover_witnesses = vec![
-            // Proposal params
-            Witness::Base(Value::known(self.proposal.auth_calls.commit())),
-            Witness::Base(Value::known(pallas::Base::from(self.proposal.creation_blockwindow))),
-            Witness::Base(Value::known(pallas::Base::from(self.proposal.duration_blockwindows))),
-            Witness::Base(Value::known(self.proposal.user_data)),
-            Witness::Base(Value::known(self.proposal.blind.inner())),
-            // DAO params
-            Witness::Base(Value::known(dao_proposer_limit)),
-            Witness::Base(Value::known(dao_quorum)),
-            Witness::Base(Value::known(dao_early_exec_quorum)),
-            Witness::Base(Value::known(dao_approval_ratio_quot)),
-            Witness::Base(Value::known(dao_approval_ratio_base)),
-            Witness::Base(Value::known(self.dao.gov_token_id.inner())),
-            Witness::Base(Value::known(dao_notes_pub_x)),
-            Witness::Base(Value::known(dao_notes_pub_y)),
-            Witness::Base(Value::known(dao_proposer_pub_x)),
-            Witness::Base(Value::known(dao_proposer_pub_y)),
-            Witness::Base(Value::known(dao_proposals_pub_x)),
-            Witness::Base(Value::known(dao_proposals_pub_y)),
-            Witness::EcNiPoint(Value::known(dao_votes_public_key)),
-            Witness::Base(Value::known(dao_exec_pub_x)),
-            Witness::Base(Value::known(dao_exec_pub_y)),
-            Witness::Base(Value::known(dao_early_exec_pub_x)),
-            Witness::Base(Value::known(dao_early_exec_pub_y)),
-            Witness::Base(Value::known(self.dao.bulla_blind.inner())),
-            // Vote
-            Witness::Base(Value::known(vote_option)),
-            Witness::Base(Value::known(yes_vote_blind.inner())),
-            // Total number of gov tokens allocated
-            Witness::Base(Value::known(all_vote_value_fp)),
-            Witness::Base(Value::known(all_vote_blind.inner())),
-            // Gov token
-            Witness::Base(Value::known(gov_token_blind)),
-            // Time checks
-            Witness::Base(Value::known(current_blockwindow)),
-            // verifiable encryption
-            Witness::Base(Value::known(ephem_secret.inner())),
-        ];
-
-        let note = [vote_option, yes_vote_blind.inner(), all_vote_value_fp, all_vote_blind.inner()];
-        let enc_note =
-            ElGamalEncryptedNote::encrypt_unsafe(note, &ephem_secret, &self.dao.votes_public_key)?;
-
-        let public_inputs = vec![
-            token_commit,
-            proposal_bulla.inner(),
-            *yes_vote_commit_coords.x(),
-            *yes_vote_commit_coords.y(),
-            *all_vote_commit_coords.x(),
-            *all_vote_commit_coords.y(),
-            current_blockwindow,
-            ephem_x,
-            ephem_y,
-            enc_note.encrypted_values[0],
-            enc_note.encrypted_values[1],
-            enc_note.encrypted_values[2],
-            enc_note.encrypted_values[3],
-        ];
-
-        //darkfi::zk::export_witness_json("proof/witness/vote-main.json", &prover_witnesses, &public_inputs);
-        let circuit = ZkCircuit::new(prover_witnesses, main_zkbin);
-
-        debug!(target: "contract::dao::client::vote", "main_proof = Proof::create()");
-        let main_proof = Proof::create(main_pk, &[circuit], &public_inputs, &mut OsRng)?;
-        proofs.push(main_proof);
 
-        let params =
-            DaoVoteParams { token_commit, proposal_bulla, yes_vote_commit, note: enc_note, inputs };
+        let (params, main_proof) = make_main_proof(
+            &self.dao,
+            &self.proposal,
+            self.vote_option,
+            self.current_blockwindow,
+            gov_token_blind,
+            inputs,
+            all_vote_value,
+            all_vote_blind,
+            main_zkbin,
+            main_pk,
+        )?;
+        proofs.push(main_proof);
 
         Ok((params, proofs))
     }
 }
+
+/// Compute the Merkle root of a leaf, given its position and path
+fn merkle_root(position: u64, leaf: MerkleNode, path: &[MerkleNode]) -> MerkleNode {
+    let mut current = leaf;
+    for (level, sibling) in path.iter().enumerate() {
+        let level = level as u8;
+        current = if position & (1 << level) == 0 {
+            MerkleNode::combine(level.into(), &current, sibling)
+        } else {
+            MerkleNode::combine(level.into(), sibling, &current)
+        };
+    }
+    current
+}
+
+/// Pick a value blind for a vote input.
+///
+/// For the last input, choose a blinding factor such that the sum of all
+/// input blinds can be converted to pallas::Base exactly. We need this so
+/// we can verifiably encrypt the sum of input blinds in the main proof.
+// TODO: make a generalized widget for this, and also picking blinds in money::transfer()
+fn input_value_blind(all_vote_blind: pallas::Scalar, is_last: bool) -> pallas::Scalar {
+    let mut value_blind = pallas::Scalar::random(&mut OsRng);
+
+    if is_last {
+        // It's near zero chance it ever loops at all.
+        // P(random 𝔽ᵥ ∉ 𝔽ₚ) = (q - p)/q = 2.99 × 10⁻⁵¹
+        while fv_mod_fp_unsafe(all_vote_blind + value_blind).is_none().into() {
+            value_blind = pallas::Scalar::random(&mut OsRng);
+        }
+    }
+
+    value_blind
+}
+
+/// Create the main vote proof, shared by token and membership votes
+#[allow(clippy::too_many_arguments)]
+fn make_main_proof(
+    dao: &Dao,
+    proposal: &DaoProposal,
+    vote_option: bool,
+    current_blockwindow: u64,
+    gov_token_blind: pallas::Base,
+    inputs: Vec<DaoVoteParamsInput>,
+    all_vote_value: u64,
+    all_vote_blind: pallas::Scalar,
+    main_zkbin: &ZkBinary,
+    main_pk: &ProvingKey,
+) -> Result<(DaoVoteParams, Proof)> {
+    let proposal_bulla = proposal.to_bulla();
+
+    let token_commit = poseidon_hash([dao.voting_token(), gov_token_blind]);
+
+    let dao_proposer_limit = pallas::Base::from(dao.proposer_limit);
+    let dao_quorum = pallas::Base::from(dao.quorum);
+    let dao_early_exec_quorum = pallas::Base::from(dao.early_exec_quorum);
+    let dao_approval_ratio_quot = pallas::Base::from(dao.approval_ratio_quot);
+    let dao_approval_ratio_base = pallas::Base::from(dao.approval_ratio_base);
+    let (dao_notes_pub_x, dao_notes_pub_y) = dao.notes_public_key.xy();
+    let (dao_proposer_pub_x, dao_proposer_pub_y) = dao.proposer_public_key.xy();
+    let (dao_proposals_pub_x, dao_proposals_pub_y) = dao.proposals_public_key.xy();
+    let dao_votes_public_key = dao.votes_public_key.inner();
+    let (dao_exec_pub_x, dao_exec_pub_y) = dao.exec_public_key.xy();
+    let (dao_early_exec_pub_x, dao_early_exec_pub_y) = dao.early_exec_public_key.xy();
+
+    let vote_option = vote_option as u64;
+    if vote_option != 0 && vote_option != 1 {
+        return Err(ClientFailed::VerifyError(DaoError::VoteInputsEmpty.to_string()).into())
+    }
+
+    // Create a random blind b ∈ 𝔽ᵥ, such that b ∈ 𝔽ₚ
+    let yes_vote_blind = loop {
+        let blind = pallas::Scalar::random(&mut OsRng);
+        if fv_mod_fp_unsafe(blind).is_some().into() {
+            break blind
+        }
+    };
+    let yes_vote_value = vote_option * all_vote_value;
+    let yes_vote_commit = pedersen_commitment_u64(yes_vote_value, Blind(yes_vote_blind));
+    let yes_vote_commit_coords = yes_vote_commit.to_affine().coordinates().unwrap();
+
+    let all_vote_commit = pedersen_commitment_u64(all_vote_value, Blind(all_vote_blind));
+    if all_vote_commit != inputs.iter().map(|i| i.vote_commit).sum() {
+        return Err(ClientFailed::VerifyError(DaoError::VoteCommitMismatch.to_string()).into())
+    }
+    let all_vote_commit_coords = all_vote_commit.to_affine().coordinates().unwrap();
+
+    // Convert blinds to 𝔽ₚ, which should work fine since we selected them
+    // to be convertable.
+    let yes_vote_blind = Blind(fv_mod_fp_unsafe(yes_vote_blind).unwrap());
+    let all_vote_blind = Blind(fv_mod_fp_unsafe(all_vote_blind).unwrap());
+
+    let vote_option = pallas::Base::from(vote_option);
+    let all_vote_value_fp = pallas::Base::from(all_vote_value);
+    let ephem_secret = SecretKey::random(&mut OsRng);
+
+    // Encrypt the vote values for the homomorphic tally
+    let tally_yes_secret = SecretKey::random(&mut OsRng);
+    let tally_all_secret = SecretKey::random(&mut OsRng);
+    let encrypted_tally = DaoEncryptedTally {
+        yes: encrypt_tally_value(yes_vote_value, &tally_yes_secret, &dao.votes_public_key),
+        all: encrypt_tally_value(all_vote_value, &tally_all_secret, &dao.votes_public_key),
+    };
+
+    let current_blockwindow = pallas::Base::from(current_blockwindow);
+
+    let prover_witnesses = vec![
+        // Proposal params
+        Witness::Base(Value::known(proposal.auth_calls.commit())),
+        Witness::Base(Value::known(pallas::Base::from(proposal.creation_blockwindow))),
+        Witness::Base(Value::known(pallas::Base::from(proposal.duration_blockwindows))),
+        Witness::Base(Value::known(proposal.user_data)),
+        Witness::Base(Value::known(proposal.blind.inner())),
+        // DAO params
+        Witness::Base(Value::known(dao_proposer_limit)),
+        Witness::Base(Value::known(dao_quorum)),
+        Witness::Base(Value::known(dao_early_exec_quorum)),
+        Witness::Base(Value::known(dao_approval_ratio_quot)),
+        Witness::Base(Value::known(dao_approval_ratio_base)),
+        Witness::Base(Value::known(dao.gov_token_id.inner())),
+        Witness::Base(Value::known(dao_notes_pub_x)),
+        Witness::Base(Value::known(dao_notes_pub_y)),
+        Witness::Base(Value::known(dao_proposer_pub_x)),
+        Witness::Base(Value::known(dao_proposer_pub_y)),
+        Witness::Base(Value::known(dao_proposals_pub_x)),
+        Witness::Base(Value::known(dao_proposals_pub_y)),
+        Witness::EcNiPoint(Value::known(dao_votes_public_key)),
+        Witness::Base(Value::known(dao_exec_pub_x)),
+        Witness::Base(Value::known(dao_exec_pub_y)),
+        Witness::Base(Value::known(dao_early_exec_pub_x)),
+        Witness::Base(Value::known(dao_early_exec_pub_y)),
+        Witness::Base(Value::known(dao.membership_root_inner())),
+        Witness::Base(Value::known(dao.bulla_blind.inner())),
+        // Vote
+        Witness::Base(Value::known(vote_option)),
+        Witness::Base(Value::known(yes_vote_blind.inner())),
+        // Total number of gov tokens allocated
+        Witness::Base(Value::known(all_vote_value_fp)),
+        Witness::Base(Value::known(all_vote_blind.inner())),
+        // Gov token
+        Witness::Base(Value::known(gov_token_blind)),
+        // Time checks
+        Witness::Base(Value::known(current_blockwindow)),
+        // verifiable encryption
+        Witness::Base(Value::known(ephem_secret.inner())),
+        // tally encryption
+        Witness::Base(Value::known(tally_yes_secret.inner())),
+        Witness::Base(Value::known(tally_all_secret.inner())),
+    ];
+
+    let note = [vote_option, yes_vote_blind.inner(), all_vote_value_fp, all_vote_blind.inner()];
+    let enc_note =
+        ElGamalEncryptedNote::encrypt_unsafe(note, &ephem_secret, &dao.votes_public_key)?;
+
+    let mut public_inputs = vec![
+        token_commit,
+        proposal_bulla.inner(),
+        *yes_vote_commit_coords.x(),
+        *yes_vote_commit_coords.y(),
+        *all_vote_commit_coords.x(),
+        *all_vote_commit_coords.y(),
+        current_blockwindow,
+    ];
+    public_inputs.extend(enc_note.public_inputs());
+    public_inputs.extend(encrypted_tally.public_inputs());
+
+    //darkfi::zk::export_witness_json("proof/witness/vote-main.json", &prover_witnesses, &public_inputs);
+    let circuit = ZkCircuit::new(prover_witnesses, main_zkbin);
+
+    debug!(target: "contract::dao::client::vote", "main_proof = Proof::create()");
+    let main_proof = Proof::create(main_pk, &[circuit], &public_inputs, &mut OsRng)?;
+
+    let params = DaoVoteParams {
+        token_commit,
+        membership: dao.membership_root.is_some(),
+        proposal_bulla,
+        yes_vote_commit,
+        note: enc_note,
+        encrypted_tally,
+        inputs,
+    };
+
+    Ok((params, main_proof))
+}
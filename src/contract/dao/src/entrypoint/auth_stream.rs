@@ -0,0 +1,138 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_money_contract::model::Coin;
+use darkfi_sdk::{
+    crypto::{ContractId, PublicKey, DAO_CONTRACT_ID},
+    dark_tree::DarkLeaf,
+    error::{ContractError, ContractResult},
+    msg,
+    pasta::pallas,
+    wasm, ContractCall,
+};
+use darkfi_serial::{deserialize, serialize, Encodable};
+
+use super::auth_xfer::find_auth_in_parent;
+use crate::{
+    error::DaoError,
+    model::{DaoAuthCreateStreamUpdate, DaoExecParams, DaoStream},
+    DaoFunction, DAO_CONTRACT_DB_STREAMS,
+};
+
+/// `get_metdata` function for `Dao::AuthCreateStream`
+pub(crate) fn dao_auth_stream_get_metadata(
+    _cid: ContractId,
+    _call_idx: usize,
+    _calls: Vec<DarkLeaf<ContractCall>>,
+) -> Result<Vec<u8>, ContractError> {
+    // Everything is verified against the proposal auth calls in process_instruction()
+    let zk_public_inputs: Vec<(String, Vec<pallas::Base>)> = vec![];
+    let signature_pubkeys: Vec<PublicKey> = vec![];
+
+    let mut metadata = vec![];
+    zk_public_inputs.encode(&mut metadata)?;
+    signature_pubkeys.encode(&mut metadata)?;
+
+    Ok(metadata)
+}
+
+/// `process_instruction` function for `Dao::AuthCreateStream`
+pub(crate) fn dao_auth_stream_process_instruction(
+    cid: ContractId,
+    call_idx: usize,
+    calls: Vec<DarkLeaf<ContractCall>>,
+) -> Result<Vec<u8>, ContractError> {
+    ///////////////////////////////////////////////////
+    // 1. Parent should be DAO::exec()
+    ///////////////////////////////////////////////////
+
+    let Some(parent_idx) = calls[call_idx].parent_index else {
+        msg!("[Dao::AuthCreateStream] Error: Call has no parent");
+        return Err(DaoError::InvalidCalls.into())
+    };
+    let exec_callnode = &calls[parent_idx];
+    let function_code = *exec_callnode.data.data.first().ok_or(DaoError::InvalidCalls)?;
+    if exec_callnode.data.contract_id != *DAO_CONTRACT_ID ||
+        function_code != DaoFunction::Exec as u8
+    {
+        msg!("[Dao::AuthCreateStream] Error: Parent call is not DAO::exec()");
+        return Err(DaoError::InvalidCalls.into())
+    }
+    let exec_params: DaoExecParams = deserialize(&exec_callnode.data.data[1..])?;
+
+    ///////////////////////////////////////////////////
+    // 2. Read the stream from the proposal auth data
+    ///////////////////////////////////////////////////
+
+    let Some(auth_call) =
+        find_auth_in_parent(exec_callnode, exec_params.proposal_auth_calls.clone(), call_idx)
+    else {
+        return Err(DaoError::AuthStreamCallNotFoundInParent.into())
+    };
+    let stream: DaoStream = deserialize(&auth_call.auth_data[..])?;
+    let stream_bulla = stream.to_bulla();
+
+    let streams_db = wasm::db::db_lookup(cid, DAO_CONTRACT_DB_STREAMS)?;
+    if wasm::db::db_contains_key(streams_db, &serialize(&stream_bulla))? {
+        msg!("[Dao::AuthCreateStream] Error: Stream already exists {}", stream_bulla);
+        return Err(DaoError::StreamAlreadyExists.into())
+    }
+
+    ///////////////////////////////////////////////////
+    // 3. The stream funds should be sent by the proposal
+    ///////////////////////////////////////////////////
+
+    // Dao::AuthMoneyTransfer enforces its proposal coins get created,
+    // so the stream coin must be one of them.
+    let stream_coin = stream.coin_attributes(stream.total, stream.coin_blind).to_coin();
+    let mut found = false;
+    for auth_call in &exec_params.proposal_auth_calls {
+        if auth_call.contract_id != *DAO_CONTRACT_ID ||
+            auth_call.function_code != DaoFunction::AuthMoneyTransfer as u8 ||
+            auth_call.generic_calldata_hash().is_some()
+        {
+            continue
+        }
+
+        let proposal_coins: Vec<Coin> = deserialize(&auth_call.auth_data[..])?;
+        if proposal_coins.contains(&stream_coin) {
+            found = true;
+            break
+        }
+    }
+    if !found {
+        msg!("[Dao::AuthCreateStream] Error: Stream coin is not sent by the proposal");
+        return Err(DaoError::AuthStreamCoinNotFound.into())
+    }
+
+    // Create state update
+    let update = DaoAuthCreateStreamUpdate { stream_bulla };
+    Ok(serialize(&update))
+}
+
+/// `process_update` function for `Dao::AuthCreateStream`
+pub(crate) fn dao_auth_stream_process_update(
+    cid: ContractId,
+    update: DaoAuthCreateStreamUpdate,
+) -> ContractResult {
+    // Nothing has been claimed yet
+    let streams_db = wasm::db::db_lookup(cid, DAO_CONTRACT_DB_STREAMS)?;
+    wasm::db::db_set(streams_db, &serialize(&update.stream_bulla), &serialize(&0u64))?;
+
+    Ok(())
+}
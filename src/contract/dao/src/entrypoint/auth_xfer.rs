@@ -119,7 +119,7 @@ pub(crate) fn dao_authxfer_get_metadata(
     Ok(metadata)
 }
 
-fn find_auth_in_parent(
+pub(crate) fn find_auth_in_parent(
     exec_callnode: &DarkLeaf<ContractCall>,
     proposal_auth_calls: Vec<DaoAuthCall>,
     self_call_idx: usize,
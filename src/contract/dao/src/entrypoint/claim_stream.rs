@@ -0,0 +1,154 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_money_contract::{model::MoneyTransferParamsV1, MoneyFunction};
+use darkfi_sdk::{
+    crypto::{ContractId, FuncRef, PublicKey, DAO_CONTRACT_ID, MONEY_CONTRACT_ID},
+    dark_tree::DarkLeaf,
+    error::{ContractError, ContractResult},
+    msg,
+    pasta::pallas,
+    wasm, ContractCall,
+};
+use darkfi_serial::{deserialize, serialize, Encodable};
+
+use crate::{
+    error::DaoError,
+    model::{DaoClaimStreamParams, DaoClaimStreamUpdate},
+    DaoFunction, DAO_CONTRACT_DB_STREAMS, DAO_CONTRACT_ZKAS_DAO_CLAIM_STREAM_NS,
+};
+
+/// Returns the `Money::Transfer` spending the stream coin, which must be
+/// the only child of the `Dao::ClaimStream` call.
+fn xfer_child(
+    call_idx: usize,
+    calls: &[DarkLeaf<ContractCall>],
+) -> Result<MoneyTransferParamsV1, ContractError> {
+    let self_ = &calls[call_idx];
+    if self_.children_indexes.len() != 1 {
+        msg!("[Dao::ClaimStream] Error: Call must have a single child");
+        return Err(DaoError::InvalidCalls.into())
+    }
+
+    let xfer_call = &calls[self_.children_indexes[0]].data;
+    let function_code = *xfer_call.data.first().ok_or(DaoError::InvalidCalls)?;
+    if xfer_call.contract_id != *MONEY_CONTRACT_ID ||
+        function_code != MoneyFunction::TransferV1 as u8
+    {
+        msg!("[Dao::ClaimStream] Error: Child call is not money::transfer()");
+        return Err(DaoError::InvalidCalls.into())
+    }
+
+    let xfer_params: MoneyTransferParamsV1 = deserialize(&xfer_call.data[1..])?;
+
+    // The stream coin is the single input, and the change going
+    // back into the stream is the last output.
+    if xfer_params.inputs.len() != 1 || xfer_params.outputs.len() < 2 {
+        msg!("[Dao::ClaimStream] Error: Invalid transfer inputs or outputs");
+        return Err(DaoError::ClaimStreamInvalidTransfer.into())
+    }
+
+    Ok(xfer_params)
+}
+
+/// `get_metdata` function for `Dao::ClaimStream`
+pub(crate) fn dao_claim_stream_get_metadata(
+    _cid: ContractId,
+    call_idx: usize,
+    calls: Vec<DarkLeaf<ContractCall>>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx];
+    let params: DaoClaimStreamParams = deserialize(&self_.data.data[1..])?;
+    let xfer_params = xfer_child(call_idx, &calls)?;
+
+    // Public inputs for the ZK proofs we have to verify
+    let mut zk_public_inputs: Vec<(String, Vec<pallas::Base>)> = vec![];
+    // The recipient signs the money::transfer() input
+    let signature_pubkeys: Vec<PublicKey> = vec![];
+
+    let spend_hook =
+        FuncRef { contract_id: *DAO_CONTRACT_ID, func_code: DaoFunction::ClaimStream as u8 }
+            .to_func_id();
+    let current_height = wasm::util::get_verifying_block_height()?;
+
+    zk_public_inputs.push((
+        DAO_CONTRACT_ZKAS_DAO_CLAIM_STREAM_NS.to_string(),
+        vec![
+            params.stream_bulla.inner(),
+            xfer_params.inputs[0].user_data_enc,
+            xfer_params.outputs.last().unwrap().coin.inner(),
+            spend_hook.inner(),
+            pallas::Base::from(params.claimed),
+            pallas::Base::from(current_height as u64),
+        ],
+    ));
+
+    // Serialize everything gathered and return it
+    let mut metadata = vec![];
+    zk_public_inputs.encode(&mut metadata)?;
+    signature_pubkeys.encode(&mut metadata)?;
+
+    Ok(metadata)
+}
+
+/// `process_instruction` function for `Dao::ClaimStream`
+pub(crate) fn dao_claim_stream_process_instruction(
+    cid: ContractId,
+    call_idx: usize,
+    calls: Vec<DarkLeaf<ContractCall>>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx];
+    let params: DaoClaimStreamParams = deserialize(&self_.data.data[1..])?;
+
+    // Verify the child call format. The input spend hook pointing to us
+    // is enforced by money::transfer(), and the stream coin, the change
+    // and the vested amount are all verified inside ZK.
+    xfer_child(call_idx, &calls)?;
+
+    let streams_db = wasm::db::db_lookup(cid, DAO_CONTRACT_DB_STREAMS)?;
+    let Some(data) = wasm::db::db_get(streams_db, &serialize(&params.stream_bulla))? else {
+        msg!("[Dao::ClaimStream] Error: Stream {} not found", params.stream_bulla);
+        return Err(DaoError::StreamNonexistent.into())
+    };
+    let claimed: u64 = deserialize(&data)?;
+
+    if params.claimed <= claimed {
+        msg!(
+            "[Dao::ClaimStream] Error: Claimed amount {} is not above {}",
+            params.claimed,
+            claimed
+        );
+        return Err(DaoError::ClaimStreamNothingToClaim.into())
+    }
+
+    // Create state update
+    let update =
+        DaoClaimStreamUpdate { stream_bulla: params.stream_bulla, claimed: params.claimed };
+    Ok(serialize(&update))
+}
+
+/// `process_update` function for `Dao::ClaimStream`
+pub(crate) fn dao_claim_stream_process_update(
+    cid: ContractId,
+    update: DaoClaimStreamUpdate,
+) -> ContractResult {
+    let streams_db = wasm::db::db_lookup(cid, DAO_CONTRACT_DB_STREAMS)?;
+    wasm::db::db_set(streams_db, &serialize(&update.stream_bulla), &serialize(&update.claimed))?;
+
+    Ok(())
+}
@@ -29,7 +29,7 @@ use darkfi_serial::{deserialize, serialize, Encodable};
 use crate::{
     blockwindow,
     error::DaoError,
-    model::{DaoExecParams, DaoExecUpdate, DaoProposalMetadata, VecAuthCallCommit},
+    model::{DaoAuthCall, DaoExecParams, DaoExecUpdate, DaoProposalMetadata, VecAuthCallCommit},
     DAO_CONTRACT_DB_PROPOSAL_BULLAS, DAO_CONTRACT_ZKAS_DAO_EARLY_EXEC_NS,
     DAO_CONTRACT_ZKAS_DAO_EXEC_NS,
 };
@@ -112,13 +112,24 @@ pub(crate) fn dao_exec_process_instruction(
         // Doing anything else is potentially risky.
 
         let contract_id = child_call.contract_id;
-        let function_code = child_call.data[0];
+        let Some(&function_code) = child_call.data.first() else {
+            msg!("[Dao::Exec] Error: child call is missing its function code");
+            return Err(DaoError::ExecCallWrongChildCall.into())
+        };
 
         // Check they match the auth call spec
         if contract_id != auth_call.contract_id || function_code != auth_call.function_code {
             msg!("[Dao::Exec] Error: wrong child call");
             return Err(DaoError::ExecCallWrongChildCall.into())
         }
+
+        // Generic calls commit to the exact calldata
+        if let Some(hash) = auth_call.generic_calldata_hash() {
+            if hash != DaoAuthCall::calldata_hash(&child_call.data) {
+                msg!("[Dao::Exec] Error: wrong child call data");
+                return Err(DaoError::ExecCallWrongChildCallData.into())
+            }
+        }
     }
 
     ///////////////////////////////////////////////////
@@ -131,7 +142,7 @@ pub(crate) fn dao_exec_process_instruction(
         msg!("[Dao::Exec] Error: Proposal {:?} not found", params.proposal_bulla);
         return Err(DaoError::ProposalNonexistent.into())
     };
-    let proposal: DaoProposalMetadata = deserialize(&data)?;
+    let proposal = DaoProposalMetadata::decode_stored(&data)?;
 
     // Check yes_vote commit and all_vote_commit are the same as in BlindAggregateVote
     if proposal.vote_aggregate.yes_vote_commit != params.blind_total_vote.yes_vote_commit ||
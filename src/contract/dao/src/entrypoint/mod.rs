@@ -27,10 +27,13 @@ use darkfi_sdk::{
 use darkfi_serial::{deserialize, serialize, Decodable, Encodable, WriteExt};
 
 use crate::{
-    model::{DaoExecUpdate, DaoMintUpdate, DaoProposeUpdate, DaoVoteUpdate},
+    model::{
+        DaoAuthCreateStreamUpdate, DaoClaimStreamUpdate, DaoExecUpdate, DaoMintUpdate,
+        DaoProposeUpdate, DaoVoteUpdate,
+    },
     DaoFunction, DAO_CONTRACT_DB_DAO_BULLAS, DAO_CONTRACT_DB_DAO_MERKLE_ROOTS,
-    DAO_CONTRACT_DB_INFO_TREE, DAO_CONTRACT_DB_PROPOSAL_BULLAS, DAO_CONTRACT_DB_VOTE_NULLIFIERS,
-    DAO_CONTRACT_KEY_DAO_MERKLE_TREE, DAO_CONTRACT_KEY_DB_VERSION,
+    DAO_CONTRACT_DB_INFO_TREE, DAO_CONTRACT_DB_PROPOSAL_BULLAS, DAO_CONTRACT_DB_STREAMS,
+    DAO_CONTRACT_DB_VOTE_NULLIFIERS, DAO_CONTRACT_KEY_DAO_MERKLE_TREE, DAO_CONTRACT_KEY_DB_VERSION,
 };
 
 /// `Dao::Mint` functions
@@ -54,6 +57,20 @@ use exec::{dao_exec_get_metadata, dao_exec_process_instruction, dao_exec_process
 mod auth_xfer;
 use auth_xfer::{dao_authxfer_get_metadata, dao_authxfer_process_instruction};
 
+/// `Dao::AuthCreateStream` functions
+mod auth_stream;
+use auth_stream::{
+    dao_auth_stream_get_metadata, dao_auth_stream_process_instruction,
+    dao_auth_stream_process_update,
+};
+
+/// `Dao::ClaimStream` functions
+mod claim_stream;
+use claim_stream::{
+    dao_claim_stream_get_metadata, dao_claim_stream_process_instruction,
+    dao_claim_stream_process_update,
+};
+
 darkfi_sdk::define_contract!(
     init: init_contract,
     exec: process_instruction,
@@ -72,11 +89,13 @@ fn init_contract(cid: ContractId, _ix: &[u8]) -> ContractResult {
     wasm::db::zkas_db_set(&include_bytes!("../../proof/propose-input.zk.bin")[..])?;
     wasm::db::zkas_db_set(&include_bytes!("../../proof/propose-main.zk.bin")[..])?;
     wasm::db::zkas_db_set(&include_bytes!("../../proof/vote-input.zk.bin")[..])?;
+    wasm::db::zkas_db_set(&include_bytes!("../../proof/vote-input-member.zk.bin")[..])?;
     wasm::db::zkas_db_set(&include_bytes!("../../proof/vote-main.zk.bin")[..])?;
     wasm::db::zkas_db_set(&include_bytes!("../../proof/exec.zk.bin")[..])?;
     wasm::db::zkas_db_set(&include_bytes!("../../proof/early-exec.zk.bin")[..])?;
     wasm::db::zkas_db_set(&include_bytes!("../../proof/auth-money-transfer.zk.bin")[..])?;
     wasm::db::zkas_db_set(&include_bytes!("../../proof/auth-money-transfer-enc-coin.zk.bin")[..])?;
+    wasm::db::zkas_db_set(&include_bytes!("../../proof/claim-stream.zk.bin")[..])?;
 
     // Set up db for general info
     let dao_info_db = match wasm::db::db_lookup(cid, DAO_CONTRACT_DB_INFO_TREE) {
@@ -131,6 +150,14 @@ fn init_contract(cid: ContractId, _ix: &[u8]) -> ContractResult {
         Err(_) => wasm::db::db_init(cid, DAO_CONTRACT_DB_VOTE_NULLIFIERS)?,
     };
 
+    // Set up db for treasury streams
+    // k: DaoStreamBulla
+    // v: u64 (total amount claimed from the stream)
+    let _ = match wasm::db::db_lookup(cid, DAO_CONTRACT_DB_STREAMS) {
+        Ok(v) => v,
+        Err(_) => wasm::db::db_init(cid, DAO_CONTRACT_DB_STREAMS)?,
+    };
+
     // Update db version
     wasm::db::db_set(
         dao_info_db,
@@ -156,6 +183,8 @@ fn get_metadata(cid: ContractId, ix: &[u8]) -> ContractResult {
         DaoFunction::Vote => dao_vote_get_metadata(cid, call_idx, calls)?,
         DaoFunction::Exec => dao_exec_get_metadata(cid, call_idx, calls)?,
         DaoFunction::AuthMoneyTransfer => dao_authxfer_get_metadata(cid, call_idx, calls)?,
+        DaoFunction::AuthCreateStream => dao_auth_stream_get_metadata(cid, call_idx, calls)?,
+        DaoFunction::ClaimStream => dao_claim_stream_get_metadata(cid, call_idx, calls)?,
     };
 
     wasm::util::set_return_data(&metadata)
@@ -175,6 +204,8 @@ fn process_instruction(cid: ContractId, ix: &[u8]) -> ContractResult {
         DaoFunction::Vote => dao_vote_process_instruction(cid, call_idx, calls)?,
         DaoFunction::Exec => dao_exec_process_instruction(cid, call_idx, calls)?,
         DaoFunction::AuthMoneyTransfer => dao_authxfer_process_instruction(cid, call_idx, calls)?,
+        DaoFunction::AuthCreateStream => dao_auth_stream_process_instruction(cid, call_idx, calls)?,
+        DaoFunction::ClaimStream => dao_claim_stream_process_instruction(cid, call_idx, calls)?,
     };
 
     wasm::util::set_return_data(&update_data)
@@ -210,5 +241,15 @@ fn process_update(cid: ContractId, update_data: &[u8]) -> ContractResult {
             // Does nothing, just verifies the other calls are correct
             Ok(())
         }
+
+        DaoFunction::AuthCreateStream => {
+            let update: DaoAuthCreateStreamUpdate = deserialize(&update_data[1..])?;
+            Ok(dao_auth_stream_process_update(cid, update)?)
+        }
+
+        DaoFunction::ClaimStream => {
+            let update: DaoClaimStreamUpdate = deserialize(&update_data[1..])?;
+            Ok(dao_claim_stream_process_update(cid, update)?)
+        }
     }
 }
@@ -35,7 +35,10 @@ use darkfi_serial::{deserialize, serialize, Encodable};
 use crate::{
     blockwindow,
     error::DaoError,
-    model::{DaoBlindAggregateVote, DaoProposalMetadata, DaoProposeParams, DaoProposeUpdate},
+    model::{
+        DaoBlindAggregateVote, DaoEncryptedTally, DaoProposalMetadata, DaoProposeParams,
+        DaoProposeUpdate,
+    },
     DAO_CONTRACT_DB_DAO_MERKLE_ROOTS, DAO_CONTRACT_DB_PROPOSAL_BULLAS,
     DAO_CONTRACT_ZKAS_DAO_PROPOSE_INPUT_NS, DAO_CONTRACT_ZKAS_DAO_PROPOSE_MAIN_NS,
     PROPOSAL_SNAPSHOT_CUTOFF_LIMIT,
@@ -91,17 +94,16 @@ pub(crate) fn dao_propose_get_metadata(
     // ANCHOR_END: dao-blockwindow-example-usage
 
     let total_funds_coords = total_funds_commit.to_affine().coordinates().unwrap();
-    zk_public_inputs.push((
-        DAO_CONTRACT_ZKAS_DAO_PROPOSE_MAIN_NS.to_string(),
-        vec![
-            params.token_commit,
-            params.dao_merkle_root.inner(),
-            params.proposal_bulla.inner(),
-            pallas::Base::from(current_blockwindow),
-            *total_funds_coords.x(),
-            *total_funds_coords.y(),
-        ],
-    ));
+    let mut main_public_inputs = vec![
+        params.token_commit,
+        params.dao_merkle_root.inner(),
+        params.proposal_bulla.inner(),
+        pallas::Base::from(current_blockwindow),
+        *total_funds_coords.x(),
+        *total_funds_coords.y(),
+    ];
+    main_public_inputs.extend(params.verifiable_note.public_inputs());
+    zk_public_inputs.push((DAO_CONTRACT_ZKAS_DAO_PROPOSE_MAIN_NS.to_string(), main_public_inputs));
 
     // Serialize everything gathered and return it
     let mut metadata = vec![];
@@ -234,6 +236,7 @@ pub(crate) fn dao_propose_process_update(
         vote_aggregate: DaoBlindAggregateVote::default(),
         snapshot_coins: update.snapshot_coins,
         snapshot_nulls: update.snapshot_nulls,
+        encrypted_tally: Some(DaoEncryptedTally::default()),
     };
 
     // Set the new proposal in the db
@@ -31,7 +31,8 @@ use crate::{
     error::DaoError,
     model::{DaoProposalMetadata, DaoVoteParams, DaoVoteUpdate},
     DAO_CONTRACT_DB_PROPOSAL_BULLAS, DAO_CONTRACT_DB_VOTE_NULLIFIERS,
-    DAO_CONTRACT_ZKAS_DAO_VOTE_INPUT_NS, DAO_CONTRACT_ZKAS_DAO_VOTE_MAIN_NS,
+    DAO_CONTRACT_ZKAS_DAO_VOTE_INPUT_MEMBER_NS, DAO_CONTRACT_ZKAS_DAO_VOTE_INPUT_NS,
+    DAO_CONTRACT_ZKAS_DAO_VOTE_MAIN_NS,
 };
 
 /// `get_metdata` function for `Dao::Vote`
@@ -63,7 +64,7 @@ pub(crate) fn dao_vote_get_metadata(
         return Err(DaoError::ProposalNonexistent.into())
     };
     // Get the current votes
-    let proposal_metadata: DaoProposalMetadata = deserialize(&data)?;
+    let proposal_metadata = DaoProposalMetadata::decode_stored(&data)?;
 
     // Iterate through inputs
     for input in &params.inputs {
@@ -73,6 +74,24 @@ pub(crate) fn dao_vote_get_metadata(
         let value_coords = input.vote_commit.to_affine().coordinates().unwrap();
         let (sig_x, sig_y) = input.signature_public.xy();
 
+        // Allowlist DAO members prove their membership against the root
+        // committed in the DAO bulla, so the coins snapshot is not used.
+        if params.membership {
+            zk_public_inputs.push((
+                DAO_CONTRACT_ZKAS_DAO_VOTE_INPUT_MEMBER_NS.to_string(),
+                vec![
+                    params.proposal_bulla.inner(),
+                    input.vote_nullifier.inner(),
+                    *value_coords.x(),
+                    *value_coords.y(),
+                    params.token_commit,
+                    sig_x,
+                    sig_y,
+                ],
+            ));
+            continue
+        }
+
         zk_public_inputs.push((
             DAO_CONTRACT_ZKAS_DAO_VOTE_INPUT_NS.to_string(),
             vec![
@@ -95,25 +114,18 @@ pub(crate) fn dao_vote_get_metadata(
     let yes_vote_commit_coords = params.yes_vote_commit.to_affine().coordinates().unwrap();
     let all_vote_commit_coords = all_vote_commit.to_affine().coordinates().unwrap();
 
-    let (ephem_x, ephem_y) = params.note.ephem_public.xy();
-    zk_public_inputs.push((
-        DAO_CONTRACT_ZKAS_DAO_VOTE_MAIN_NS.to_string(),
-        vec![
-            params.token_commit,
-            params.proposal_bulla.inner(),
-            *yes_vote_commit_coords.x(),
-            *yes_vote_commit_coords.y(),
-            *all_vote_commit_coords.x(),
-            *all_vote_commit_coords.y(),
-            pallas::Base::from(current_blockwindow),
-            ephem_x,
-            ephem_y,
-            params.note.encrypted_values[0],
-            params.note.encrypted_values[1],
-            params.note.encrypted_values[2],
-            params.note.encrypted_values[3],
-        ],
-    ));
+    let mut main_public_inputs = vec![
+        params.token_commit,
+        params.proposal_bulla.inner(),
+        *yes_vote_commit_coords.x(),
+        *yes_vote_commit_coords.y(),
+        *all_vote_commit_coords.x(),
+        *all_vote_commit_coords.y(),
+        pallas::Base::from(current_blockwindow),
+    ];
+    main_public_inputs.extend(params.note.public_inputs());
+    main_public_inputs.extend(params.encrypted_tally.public_inputs());
+    zk_public_inputs.push((DAO_CONTRACT_ZKAS_DAO_VOTE_MAIN_NS.to_string(), main_public_inputs));
 
     // Serialize everything gathered and return it
     let mut metadata = vec![];
@@ -141,7 +153,7 @@ pub(crate) fn dao_vote_process_instruction(
     };
 
     // Get the current votes
-    let mut proposal_metadata: DaoProposalMetadata = deserialize(&data)?;
+    let mut proposal_metadata = DaoProposalMetadata::decode_stored(&data)?;
 
     // Check the Merkle root and nullifiers for the input coins are valid
     let dao_vote_nullifier_db = wasm::db::db_lookup(cid, DAO_CONTRACT_DB_VOTE_NULLIFIERS)?;
@@ -164,6 +176,9 @@ pub(crate) fn dao_vote_process_instruction(
     }
 
     proposal_metadata.vote_aggregate.yes_vote_commit += params.yes_vote_commit;
+    if let Some(ref mut encrypted_tally) = proposal_metadata.encrypted_tally {
+        encrypted_tally.aggregate(params.encrypted_tally);
+    }
 
     // Create state update
     let update =
@@ -68,6 +68,9 @@ pub enum DaoError {
     #[error("Child of exec call does not match proposal")]
     ExecCallWrongChildCall,
 
+    #[error("Exec child call data does not match the proposal")]
+    ExecCallWrongChildCallData,
+
     #[error("Exec call has invalid tx format")]
     ExecCallInvalidFormat,
 
@@ -94,6 +97,27 @@ pub enum DaoError {
 
     #[error("Wrong output coin")]
     AuthXferWrongOutputCoin,
+
+    #[error("Stream already exists")]
+    StreamAlreadyExists,
+
+    #[error("Stream doesn't exist")]
+    StreamNonexistent,
+
+    #[error("Stream auth call not found in parent")]
+    AuthStreamCallNotFoundInParent,
+
+    #[error("Stream coin is not created by the proposal")]
+    AuthStreamCoinNotFound,
+
+    #[error("Stream claim has invalid transfer format")]
+    ClaimStreamInvalidTransfer,
+
+    #[error("Stream claim does not increase the claimed amount")]
+    ClaimStreamNothingToClaim,
+
+    #[error("Vote inputs don't match the DAO voting mode")]
+    VoteModeMismatch,
 }
 
 impl From<DaoError> for ContractError {
@@ -124,6 +148,14 @@ impl From<DaoError> for ContractError {
             DaoError::AuthXferCallNotFoundInParent => Self::Custom(23),
             DaoError::AuthXferWrongNumberOutputs => Self::Custom(24),
             DaoError::AuthXferWrongOutputCoin => Self::Custom(25),
+            DaoError::ExecCallWrongChildCallData => Self::Custom(26),
+            DaoError::StreamAlreadyExists => Self::Custom(27),
+            DaoError::StreamNonexistent => Self::Custom(28),
+            DaoError::AuthStreamCallNotFoundInParent => Self::Custom(29),
+            DaoError::AuthStreamCoinNotFound => Self::Custom(30),
+            DaoError::ClaimStreamInvalidTransfer => Self::Custom(31),
+            DaoError::ClaimStreamNothingToClaim => Self::Custom(32),
+            DaoError::VoteModeMismatch => Self::Custom(33),
         }
     }
 }
@@ -29,6 +29,8 @@ pub enum DaoFunction {
     Vote = 0x02,
     Exec = 0x03,
     AuthMoneyTransfer = 0x04,
+    AuthCreateStream = 0x05,
+    ClaimStream = 0x06,
 }
 
 impl TryFrom<u8> for DaoFunction {
@@ -41,6 +43,8 @@ impl TryFrom<u8> for DaoFunction {
             0x02 => Ok(DaoFunction::Vote),
             0x03 => Ok(DaoFunction::Exec),
             0x04 => Ok(DaoFunction::AuthMoneyTransfer),
+            0x05 => Ok(DaoFunction::AuthCreateStream),
+            0x06 => Ok(DaoFunction::ClaimStream),
             _ => Err(ContractError::InvalidFunction),
         }
     }
@@ -66,6 +70,7 @@ pub const DAO_CONTRACT_DB_DAO_BULLAS: &str = "dao_bullas";
 pub const DAO_CONTRACT_DB_DAO_MERKLE_ROOTS: &str = "dao_roots";
 pub const DAO_CONTRACT_DB_PROPOSAL_BULLAS: &str = "dao_proposals";
 pub const DAO_CONTRACT_DB_VOTE_NULLIFIERS: &str = "dao_vote_nullifiers";
+pub const DAO_CONTRACT_DB_STREAMS: &str = "dao_streams";
 
 // These are keys inside the info tree
 pub const DAO_CONTRACT_KEY_DB_VERSION: &[u8] = b"db_version";
@@ -76,6 +81,8 @@ pub const DAO_CONTRACT_KEY_LATEST_DAO_ROOT: &[u8] = b"dao_last_root";
 pub const DAO_CONTRACT_ZKAS_DAO_MINT_NS: &str = "Mint";
 /// zkas dao vote input circuit namespace
 pub const DAO_CONTRACT_ZKAS_DAO_VOTE_INPUT_NS: &str = "VoteInput";
+/// zkas dao allowlist membership vote input circuit namespace
+pub const DAO_CONTRACT_ZKAS_DAO_VOTE_INPUT_MEMBER_NS: &str = "VoteInputMember";
 /// zkas dao vote main circuit namespace
 pub const DAO_CONTRACT_ZKAS_DAO_VOTE_MAIN_NS: &str = "VoteMain";
 /// zkas dao propose input circuit namespace
@@ -90,6 +97,8 @@ pub const DAO_CONTRACT_ZKAS_DAO_EARLY_EXEC_NS: &str = "EarlyExec";
 pub const DAO_CONTRACT_ZKAS_DAO_AUTH_MONEY_TRANSFER_NS: &str = "AuthMoneyTransfer";
 /// zkas dao auth money_transfer encrypted coin circuit namespace
 pub const DAO_CONTRACT_ZKAS_DAO_AUTH_MONEY_TRANSFER_ENC_COIN_NS: &str = "AuthMoneyTransferEncCoin";
+/// zkas dao claim stream circuit namespace
+pub const DAO_CONTRACT_ZKAS_DAO_CLAIM_STREAM_NS: &str = "ClaimStream";
 
 /// Not allowed to make proposals using snapshots with block heights older than this depth
 pub const PROPOSAL_SNAPSHOT_CUTOFF_LIMIT: u32 = 100;
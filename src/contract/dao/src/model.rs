@@ -18,21 +18,23 @@
 
 use core::str::FromStr;
 
-use darkfi_money_contract::model::{Nullifier, TokenId};
+use darkfi_money_contract::model::{CoinAttributes, Nullifier, TokenId};
 use darkfi_sdk::{
     crypto::{
-        note::{AeadEncryptedNote, ElGamalEncryptedNote},
+        note::{AeadEncryptedNote, ElGamalEncryptedNote, EncryptableNote},
         pasta_prelude::*,
-        poseidon_hash, BaseBlind, ContractId, MerkleNode, PublicKey,
+        poseidon_hash, BaseBlind, ContractId, FuncRef, MerkleNode, PublicKey, DAO_CONTRACT_ID,
     },
     error::ContractError,
     pasta::pallas,
 };
-use darkfi_serial::{Encodable, SerialDecodable, SerialEncodable};
+use darkfi_serial::{deserialize, Encodable, SerialDecodable, SerialEncodable};
 
 #[cfg(feature = "client")]
 use darkfi_serial::async_trait;
 
+use crate::DaoFunction;
+
 #[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
 // ANCHOR: dao
 /// DAOs are represented on chain as a commitment to this object
@@ -62,6 +64,10 @@ pub struct Dao {
     pub exec_public_key: PublicKey,
     /// DAO strongly supported proposals executor public key
     pub early_exec_public_key: PublicKey,
+    /// Merkle root of the approved members set. When set, the DAO is in
+    /// allowlist mode and votes are cast by proving membership instead
+    /// of governance token ownership.
+    pub membership_root: Option<MerkleNode>,
     /// DAO bulla blind
     pub bulla_blind: BaseBlind,
 }
@@ -99,10 +105,26 @@ impl Dao {
             exec_pub_y,
             early_exec_pub_x,
             early_exec_pub_y,
+            self.membership_root_inner(),
             self.bulla_blind.inner(),
         ]);
         DaoBulla(bulla)
     }
+
+    /// Membership root as committed in the bulla, zero when the DAO
+    /// is not in allowlist mode.
+    pub fn membership_root_inner(&self) -> pallas::Base {
+        self.membership_root.map_or(pallas::Base::ZERO, |root| root.inner())
+    }
+
+    /// Value the vote inputs token commitment opens to: the membership
+    /// root for allowlist DAOs, otherwise the governance token ID.
+    pub fn voting_token(&self) -> pallas::Base {
+        match self.membership_root {
+            Some(root) => root.inner(),
+            None => self.gov_token_id.inner(),
+        }
+    }
 }
 
 /// A `DaoBulla` represented in the state
@@ -151,6 +173,39 @@ pub struct DaoAuthCall {
 }
 // ANCHOR_END: dao-auth-call
 
+/// Leading byte of the `auth_data` of a generic auth call. A serialized
+/// `Vec<Coin>` used by the money transfer auth module would only start
+/// with it past 65535 coins.
+pub const DAO_GENERIC_CALL_TAG: u8 = 0xfe;
+
+impl DaoAuthCall {
+    /// Create an auth call committing to the exact contract call that must be
+    /// made when the proposal gets executed. This allows proposals to call any
+    /// contract function, not only treasury transfers.
+    /// Returns `None` if the calldata is missing the function code.
+    pub fn generic(contract_id: ContractId, calldata: &[u8]) -> Option<Self> {
+        let function_code = *calldata.first()?;
+        let mut auth_data = vec![DAO_GENERIC_CALL_TAG];
+        auth_data.extend_from_slice(&Self::calldata_hash(calldata));
+        Some(Self { contract_id, function_code, auth_data })
+    }
+
+    /// Returns the committed calldata hash if this is a generic auth call
+    pub fn generic_calldata_hash(&self) -> Option<[u8; 32]> {
+        match self.auth_data.split_first() {
+            Some((&DAO_GENERIC_CALL_TAG, hash)) => hash.try_into().ok(),
+            _ => None,
+        }
+    }
+
+    /// Hash of the full calldata, including the function code
+    pub fn calldata_hash(calldata: &[u8]) -> [u8; 32] {
+        let hash =
+            blake2b_simd::Params::new().hash_length(32).personal(b"DAOgenericCall").hash(calldata);
+        hash.as_bytes().try_into().unwrap()
+    }
+}
+
 pub trait VecAuthCallCommit {
     fn commit(&self) -> pallas::Base;
 }
@@ -176,7 +231,8 @@ impl VecAuthCallCommit for Vec<DaoAuthCall> {
     }
 }
 
-#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable, EncryptableNote)]
+#[note(version = 1)]
 // ANCHOR: dao-proposal
 pub struct DaoProposal {
     pub auth_calls: Vec<DaoAuthCall>,
@@ -270,6 +326,9 @@ pub struct DaoProposeParams {
     pub proposal_bulla: DaoProposalBulla,
     /// Encrypted note
     pub note: AeadEncryptedNote,
+    /// Verifiably encrypted proposal bulla preimage: auth calls commitment,
+    /// duration blockwindows, user data and blind
+    pub verifiable_note: ElGamalEncryptedNote<4>,
     /// Inputs for the proposal
     pub inputs: Vec<DaoProposeParamsInput>,
 }
@@ -310,6 +369,36 @@ pub struct DaoProposalMetadata {
     pub snapshot_coins: MerkleNode,
     /// Snapshotted SMT root in the Money state
     pub snapshot_nulls: pallas::Base,
+    /// Homomorphically aggregated encrypted tally. `None` for proposals
+    /// created before the encrypted tally existed, since their earlier
+    /// votes are missing from it.
+    pub encrypted_tally: Option<DaoEncryptedTally>,
+}
+
+impl DaoProposalMetadata {
+    /// Decode proposal metadata as stored in the contract state,
+    /// migrating records written before the encrypted tally existed.
+    pub fn decode_stored(data: &[u8]) -> Result<Self, ContractError> {
+        if let Ok(metadata) = deserialize(data) {
+            return Ok(metadata)
+        }
+
+        let legacy: DaoProposalMetadataV1 = deserialize(data)?;
+        Ok(Self {
+            vote_aggregate: legacy.vote_aggregate,
+            snapshot_coins: legacy.snapshot_coins,
+            snapshot_nulls: legacy.snapshot_nulls,
+            encrypted_tally: None,
+        })
+    }
+}
+
+/// Layout of [`DaoProposalMetadata`] before the encrypted tally
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+struct DaoProposalMetadataV1 {
+    vote_aggregate: DaoBlindAggregateVote,
+    snapshot_coins: MerkleNode,
+    snapshot_nulls: pallas::Base,
 }
 
 #[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
@@ -318,12 +407,16 @@ pub struct DaoProposalMetadata {
 pub struct DaoVoteParams {
     /// Token commitment for the vote inputs
     pub token_commit: pallas::Base,
+    /// Inputs prove membership of an allowlist DAO instead of coin ownership
+    pub membership: bool,
     /// Proposal bulla being voted on
     pub proposal_bulla: DaoProposalBulla,
     /// Commitment for yes votes
     pub yes_vote_commit: pallas::Point,
     /// Encrypted note
     pub note: ElGamalEncryptedNote<4>,
+    /// Vote values encrypted under the DAO votes public key
+    pub encrypted_tally: DaoEncryptedTally,
     /// Inputs for the vote
     pub inputs: Vec<DaoVoteParamsInput>,
 }
@@ -382,6 +475,66 @@ impl Default for DaoBlindAggregateVote {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, SerialEncodable, SerialDecodable)]
+// ANCHOR: dao-tally-ciphertext
+/// Additively homomorphic EC-ElGamal encryption of a value `v`,
+/// under a public key `P`, using an ephemeral secret `r`:
+/// `(r * G, v * V + r * P)`, where `V` is the value commitment generator.
+pub struct DaoTallyCiphertext {
+    /// Ephemeral public key
+    pub ephem: pallas::Point,
+    /// Masked value point
+    pub masked: pallas::Point,
+}
+// ANCHOR_END: dao-tally-ciphertext
+
+impl DaoTallyCiphertext {
+    /// Aggregate a ciphertext with existing one
+    pub fn aggregate(&mut self, other: Self) {
+        self.ephem += other.ephem;
+        self.masked += other.masked;
+    }
+
+    /// Coordinates of the ciphertext points, in the order they are
+    /// constrained in the vote proof.
+    pub fn public_inputs(&self) -> Vec<pallas::Base> {
+        let ephem_coords = self.ephem.to_affine().coordinates().unwrap();
+        let masked_coords = self.masked.to_affine().coordinates().unwrap();
+        vec![*ephem_coords.x(), *ephem_coords.y(), *masked_coords.x(), *masked_coords.y()]
+    }
+}
+
+impl Default for DaoTallyCiphertext {
+    fn default() -> Self {
+        Self { ephem: pallas::Point::identity(), masked: pallas::Point::identity() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, SerialEncodable, SerialDecodable)]
+/// Encrypted yes and total vote values. Votes are summed up in the contract
+/// state, so the final tally can be decrypted without decrypting each vote.
+/// See `client::tally` for what this does and doesn't hide.
+pub struct DaoEncryptedTally {
+    /// Encrypted yes vote value
+    pub yes: DaoTallyCiphertext,
+    /// Encrypted total vote value
+    pub all: DaoTallyCiphertext,
+}
+
+impl DaoEncryptedTally {
+    /// Aggregate a tally with existing one
+    pub fn aggregate(&mut self, other: Self) {
+        self.yes.aggregate(other.yes);
+        self.all.aggregate(other.all);
+    }
+
+    pub fn public_inputs(&self) -> Vec<pallas::Base> {
+        let mut public_inputs = self.yes.public_inputs();
+        public_inputs.extend(self.all.public_inputs());
+        public_inputs
+    }
+}
+
 #[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
 // ANCHOR: dao-exec-params
 /// Parameters for `Dao::Exec`
@@ -415,3 +568,129 @@ pub struct DaoAuthMoneyTransferParams {
     pub dao_change_attrs: ElGamalEncryptedNote<3>,
 }
 // ANCHOR_END: dao-auth_xfer-params
+
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+// ANCHOR: dao-stream
+/// Treasury funds released to a recipient at a fixed rate per block.
+/// A passed proposal creates the stream using `Dao::AuthCreateStream`
+/// and the recipient withdraws from it using `Dao::ClaimStream`.
+pub struct DaoStream {
+    /// Recipient of the streamed funds
+    pub recipient: PublicKey,
+    /// Token ID of the streamed funds
+    pub token_id: TokenId,
+    /// Total amount being streamed
+    pub total: u64,
+    /// Amount released on each block
+    pub rate: u64,
+    /// Block height the stream starts releasing funds from
+    pub start_height: u32,
+    /// Blind of the coin holding the stream funds when created
+    pub coin_blind: BaseBlind,
+    /// Stream bulla blind
+    pub blind: BaseBlind,
+}
+// ANCHOR_END: dao-stream
+
+impl DaoStream {
+    pub fn to_bulla(&self) -> DaoStreamBulla {
+        let (recipient_x, recipient_y) = self.recipient.xy();
+        let bulla = poseidon_hash([
+            recipient_x,
+            recipient_y,
+            self.token_id.inner(),
+            pallas::Base::from(self.total),
+            pallas::Base::from(self.rate),
+            pallas::Base::from(self.start_height as u64),
+            self.blind.inner(),
+        ]);
+        DaoStreamBulla(bulla)
+    }
+
+    /// Attributes of a coin holding `value` of the stream funds.
+    /// Such coins can only be spent through `Dao::ClaimStream`.
+    pub fn coin_attributes(&self, value: u64, blind: BaseBlind) -> CoinAttributes {
+        let spend_hook =
+            FuncRef { contract_id: *DAO_CONTRACT_ID, func_code: DaoFunction::ClaimStream as u8 }
+                .to_func_id();
+
+        CoinAttributes {
+            public_key: self.recipient,
+            value,
+            token_id: self.token_id,
+            spend_hook,
+            user_data: self.to_bulla().inner(),
+            blind,
+        }
+    }
+
+    /// Total amount released up to given block height
+    pub fn vested(&self, height: u32) -> u64 {
+        let elapsed = height.saturating_sub(self.start_height) as u64;
+        self.rate.saturating_mul(elapsed).min(self.total)
+    }
+}
+
+/// A `DaoStreamBulla` represented in the state
+#[derive(Debug, Copy, Clone, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct DaoStreamBulla(pallas::Base);
+
+impl DaoStreamBulla {
+    /// Reference the raw inner base field element
+    pub fn inner(&self) -> pallas::Base {
+        self.0
+    }
+
+    /// Create a `DaoStreamBulla` object from given bytes, erroring if the
+    /// input bytes are noncanonical.
+    pub fn from_bytes(x: [u8; 32]) -> Result<Self, ContractError> {
+        match pallas::Base::from_repr(x).into() {
+            Some(v) => Ok(Self(v)),
+            None => Err(ContractError::IoError(
+                "Failed to instantiate DaoStreamBulla from bytes".to_string(),
+            )),
+        }
+    }
+
+    /// Convert the `DaoStreamBulla` type into 32 raw bytes
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_repr()
+    }
+}
+
+impl std::hash::Hash for DaoStreamBulla {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        state.write(&self.to_bytes());
+    }
+}
+
+darkfi_sdk::fp_from_bs58!(DaoStreamBulla);
+darkfi_sdk::fp_to_bs58!(DaoStreamBulla);
+darkfi_sdk::ty_from_fp!(DaoStreamBulla);
+
+/// State update for `Dao::AuthCreateStream`
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct DaoAuthCreateStreamUpdate {
+    /// Bulla of the created stream
+    pub stream_bulla: DaoStreamBulla,
+}
+
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+// ANCHOR: dao-claim-stream-params
+/// Parameters for `Dao::ClaimStream`
+pub struct DaoClaimStreamParams {
+    /// Bulla of the stream being claimed from
+    pub stream_bulla: DaoStreamBulla,
+    /// Total amount claimed from the stream, including this claim
+    pub claimed: u64,
+}
+// ANCHOR_END: dao-claim-stream-params
+
+/// State update for `Dao::ClaimStream`
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct DaoClaimStreamUpdate {
+    /// Bulla of the stream being claimed from
+    pub stream_bulla: DaoStreamBulla,
+    /// Updated total amount claimed from the stream
+    pub claimed: u64,
+}
@@ -0,0 +1,171 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Checks of allowlist DAOs, whose votes are weighted by the membership
+//! committed in the DAO bulla instead of governance token holdings.
+
+use darkfi::Result;
+use darkfi_contract_test_harness::{init_logger, Holder, TestHarness};
+use darkfi_dao_contract::client::{DaoMember, DaoMemberSet};
+use darkfi_money_contract::model::DARK_TOKEN_ID;
+use darkfi_sdk::crypto::Blind;
+use log::info;
+use rand::rngs::OsRng;
+
+const HOLDERS: [Holder; 5] =
+    [Holder::Alice, Holder::Bob, Holder::Charlie, Holder::Dao, Holder::Rachel];
+const MEMBERS: [(Holder, u64); 3] =
+    [(Holder::Alice, 100_000_000), (Holder::Bob, 100_000_000), (Holder::Charlie, 100_000_000)];
+/// Charlie holds governance tokens, but isn't part of the allowlist
+const ALLOWLIST: [(Holder, u64); 2] = [(Holder::Alice, 3), (Holder::Bob, 1)];
+const TREASURY: u64 = 1_000_000_000;
+const TRANSFER_AMOUNT: u64 = 250_000_000;
+const PROPOSAL_DURATION_BLOCKWINDOW: u64 = 1;
+
+#[test]
+fn allowlist_dao() -> Result<()> {
+    smol::block_on(async {
+        init_logger();
+
+        let mut th = TestHarness::new(&HOLDERS, false).await?;
+        let mut flow = th
+            .dao_flow_init_allowlist(
+                &HOLDERS,
+                &MEMBERS,
+                &ALLOWLIST,
+                TREASURY,
+                100_000_000,
+                3,
+                (2, 1),
+            )
+            .await?;
+        let member_set = flow.member_set.take().unwrap();
+        assert_eq!(flow.dao.membership_root, Some(member_set.root()));
+
+        let (proposal, proposal_coinattrs) = th
+            .dao_flow_propose_transfer(
+                &mut flow,
+                &Holder::Rachel,
+                TRANSFER_AMOUNT,
+                PROPOSAL_DURATION_BLOCKWINDOW,
+            )
+            .await?;
+
+        info!("[Dao] Voting with governance tokens on an allowlist DAO");
+        assert!(th
+            .dao_vote(&Holder::Charlie, true, &flow.dao, &proposal.proposal, flow.block_height)
+            .await
+            .is_err());
+
+        info!("[Dao] Voting as a non-member");
+        assert!(th
+            .dao_vote_member(
+                &Holder::Charlie,
+                &member_set,
+                true,
+                &flow.dao,
+                &proposal.proposal,
+                flow.block_height,
+            )
+            .await
+            .is_err());
+
+        // A set enrolling Charlie doesn't match the DAO membership root
+        let mut forged_members = member_set.members().to_vec();
+        forged_members.push(DaoMember {
+            public_key: th.holders.get(&Holder::Charlie).unwrap().keypair.public,
+            weight: 100,
+            blind: Blind::random(&mut OsRng),
+        });
+        let forged_set = DaoMemberSet::from_members(&forged_members);
+        assert!(th
+            .dao_vote_member(
+                &Holder::Charlie,
+                &forged_set,
+                true,
+                &flow.dao,
+                &proposal.proposal,
+                flow.block_height,
+            )
+            .await
+            .is_err());
+
+        // Members vote with their allowlist weight
+        flow.member_set = Some(member_set);
+        let votes = [(Holder::Alice, true), (Holder::Bob, false)];
+        let tally = th.dao_flow_vote(&mut flow, &proposal, &votes).await?;
+        assert_eq!(tally.yes_vote_value, 3);
+        assert_eq!(tally.all_vote_value, 4);
+
+        info!("[Dao] Voting twice as the same member");
+        let (tx, _, fee_params) = th
+            .dao_vote_member(
+                &Holder::Alice,
+                flow.member_set.as_ref().unwrap(),
+                true,
+                &flow.dao,
+                &proposal.proposal,
+                flow.block_height,
+            )
+            .await?;
+        for holder in &HOLDERS {
+            assert!(th
+                .execute_dao_vote_tx(holder, tx.clone(), &fee_params, flow.block_height, false)
+                .await
+                .is_err());
+        }
+
+        th.dao_flow_wait_expiry(&mut flow, &proposal, PROPOSAL_DURATION_BLOCKWINDOW).await;
+
+        info!("[Dao] Executing the approved transfer");
+        let (tx, xfer_params, fee_params) = th
+            .dao_exec_transfer(
+                &Holder::Alice,
+                &flow.dao,
+                &flow.exec_keypair.secret,
+                &None,
+                &proposal.proposal,
+                proposal_coinattrs,
+                tally.yes_vote_value,
+                tally.all_vote_value,
+                tally.yes_vote_blind,
+                tally.all_vote_blind,
+                flow.block_height,
+            )
+            .await?;
+        for holder in &HOLDERS {
+            th.execute_dao_exec_tx(
+                holder,
+                tx.clone(),
+                Some(&xfer_params),
+                &fee_params,
+                flow.block_height,
+                true,
+            )
+            .await?;
+        }
+        th.assert_trees(&HOLDERS);
+
+        let rachel_wallet = th.holders.get(&Holder::Rachel).unwrap();
+        assert_eq!(rachel_wallet.unspent_money_coins.len(), 1);
+        assert_eq!(rachel_wallet.unspent_money_coins[0].note.value, TRANSFER_AMOUNT);
+        assert_eq!(rachel_wallet.unspent_money_coins[0].note.token_id, *DARK_TOKEN_ID);
+
+        Ok(())
+    })
+}
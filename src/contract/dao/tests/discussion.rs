@@ -0,0 +1,105 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Encrypted proposal discussion threads, as published on the event graph.
+
+use darkfi_dao_contract::{
+    client::{DaoComment, DaoDiscussionEvent, DaoDiscussions},
+    model::{Dao, DaoProposal},
+};
+use darkfi_money_contract::model::TokenId;
+use darkfi_sdk::{
+    crypto::{pasta_prelude::*, Blind, Keypair},
+    pasta::pallas,
+};
+use darkfi_serial::{deserialize, serialize};
+use rand::rngs::OsRng;
+
+fn dao(proposals_keypair: &Keypair) -> Dao {
+    let keypair = Keypair::random(&mut OsRng);
+    Dao {
+        proposer_limit: 1,
+        quorum: 1,
+        early_exec_quorum: 1,
+        approval_ratio_base: 2,
+        approval_ratio_quot: 1,
+        gov_token_id: TokenId::from(pallas::Base::from(42)),
+        notes_public_key: keypair.public,
+        proposer_public_key: keypair.public,
+        proposals_public_key: proposals_keypair.public,
+        votes_public_key: keypair.public,
+        exec_public_key: keypair.public,
+        early_exec_public_key: keypair.public,
+        membership_root: None,
+        bulla_blind: Blind::random(&mut OsRng),
+    }
+}
+
+fn proposal(dao: &Dao, creation_blockwindow: u64) -> DaoProposal {
+    DaoProposal {
+        auth_calls: vec![],
+        creation_blockwindow,
+        duration_blockwindows: 4,
+        user_data: pallas::Base::ZERO,
+        dao_bulla: dao.to_bulla(),
+        blind: Blind::random(&mut OsRng),
+    }
+}
+
+fn comment(author: &str, body: &str, timestamp: u64) -> DaoComment {
+    DaoComment { author: author.to_string(), body: body.to_string(), timestamp }
+}
+
+#[test]
+fn dao_discussion() {
+    let proposals_keypair = Keypair::random(&mut OsRng);
+    let dao = dao(&proposals_keypair);
+    let first = proposal(&dao, 10);
+    let second = proposal(&dao, 20);
+
+    // Comments go through the event graph serialized
+    let publish = |proposal: &DaoProposal, comment: &DaoComment| {
+        let event = DaoDiscussionEvent::new(&dao, proposal, comment).unwrap();
+        deserialize::<DaoDiscussionEvent>(&serialize(&event)).unwrap()
+    };
+
+    let mut discussions = DaoDiscussions::new(proposals_keypair.secret);
+    discussions.insert(&publish(&first, &comment("bob", "nay", 2))).unwrap();
+    discussions.insert(&publish(&first, &comment("alice", "yay", 1))).unwrap();
+    discussions.insert(&publish(&second, &comment("charlie", "hmm", 3))).unwrap();
+
+    // Threads are kept apart and ordered by timestamp
+    let thread = discussions.get(&dao, &first.to_bulla());
+    assert_eq!(thread, vec![comment("alice", "yay", 1), comment("bob", "nay", 2)]);
+    assert_eq!(discussions.get(&dao, &second.to_bulla()).len(), 1);
+
+    // Comments of other DAOs can't be read
+    let other_keypair = Keypair::random(&mut OsRng);
+    let other_dao = dao(&other_keypair);
+    let other = proposal(&other_dao, 10);
+    let event = DaoDiscussionEvent::new(&other_dao, &other, &comment("eve", "hi", 4)).unwrap();
+    assert!(discussions.insert(&event).is_err());
+
+    // Proposals created at blockwindow 10 expire after blockwindow 14
+    assert!(!event.is_expired(14));
+    assert!(event.is_expired(15));
+    assert_eq!(discussions.prune(14), 0);
+    assert_eq!(discussions.prune(15), 1);
+    assert!(discussions.get(&dao, &first.to_bulla()).is_empty());
+    assert_eq!(discussions.prune(25), 1);
+}
@@ -0,0 +1,163 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Checks of proposals committing to an arbitrary contract call, which
+//! `Dao::Exec` must make with the exact committed calldata.
+
+use darkfi::Result;
+use darkfi_contract_test_harness::{init_logger, Holder, TestHarness};
+use darkfi_dao_contract::model::DaoAuthCall;
+use darkfi_money_contract::MONEY_CONTRACT_TOKEN_FREEZE_TREE;
+use darkfi_sdk::crypto::MONEY_CONTRACT_ID;
+use darkfi_serial::serialize;
+use log::info;
+
+const HOLDERS: [Holder; 5] =
+    [Holder::Alice, Holder::Bob, Holder::Charlie, Holder::Dao, Holder::Rachel];
+const MEMBERS: [(Holder, u64); 3] =
+    [(Holder::Alice, 100_000_000), (Holder::Bob, 100_000_000), (Holder::Charlie, 100_000_000)];
+const TREASURY: u64 = 1_000_000_000;
+const PROPOSAL_DURATION_BLOCKWINDOW: u64 = 1;
+
+#[test]
+fn generic_call() -> Result<()> {
+    smol::block_on(async {
+        init_logger();
+
+        let mut th = TestHarness::new(&HOLDERS, false).await?;
+        let mut flow = th
+            .dao_flow_init(&HOLDERS, &MEMBERS, TREASURY, 100_000_000, 200_000_000, (2, 1))
+            .await?;
+
+        // Auth calls need the function code out of the calldata
+        assert!(DaoAuthCall::generic(*MONEY_CONTRACT_ID, &[]).is_none());
+
+        // Alice lets the DAO decide on freezing the mint of her token.
+        // Freezing doesn't care about its parent call, so `Dao::Exec`
+        // can make it.
+        let (freeze, freeze_params, freeze_secret) = th.token_freeze_call(&Holder::Alice).await?;
+        let (other_freeze, _, other_freeze_secret) = th.token_freeze_call(&Holder::Alice).await?;
+        assert_ne!(freeze.call.data, other_freeze.call.data);
+
+        let auth_call = DaoAuthCall::generic(freeze.call.contract_id, &freeze.call.data).unwrap();
+        let proposal = th
+            .dao_flow_propose_calls(&mut flow, vec![auth_call], PROPOSAL_DURATION_BLOCKWINDOW)
+            .await?;
+
+        let votes = [(Holder::Alice, true), (Holder::Bob, true), (Holder::Charlie, false)];
+        let tally = th.dao_flow_vote(&mut flow, &proposal, &votes).await?;
+        th.dao_flow_wait_expiry(&mut flow, &proposal, PROPOSAL_DURATION_BLOCKWINDOW).await;
+
+        info!("[Dao] Executing the proposal with different calldata");
+        let (tx, fee_params) = th
+            .dao_exec_calls(
+                &Holder::Alice,
+                &flow.dao,
+                &flow.exec_keypair.secret,
+                &None,
+                &proposal.proposal,
+                vec![(other_freeze, vec![other_freeze_secret])],
+                tally.yes_vote_value,
+                tally.all_vote_value,
+                tally.yes_vote_blind,
+                tally.all_vote_blind,
+                flow.block_height,
+            )
+            .await?;
+        for holder in &HOLDERS {
+            assert!(th
+                .execute_dao_exec_tx(
+                    holder,
+                    tx.clone(),
+                    None,
+                    &fee_params,
+                    flow.block_height,
+                    false
+                )
+                .await
+                .is_err());
+        }
+
+        info!("[Dao] Executing the proposal without its call");
+        let (tx, fee_params) = th
+            .dao_exec_calls(
+                &Holder::Alice,
+                &flow.dao,
+                &flow.exec_keypair.secret,
+                &None,
+                &proposal.proposal,
+                vec![],
+                tally.yes_vote_value,
+                tally.all_vote_value,
+                tally.yes_vote_blind,
+                tally.all_vote_blind,
+                flow.block_height,
+            )
+            .await?;
+        for holder in &HOLDERS {
+            assert!(th
+                .execute_dao_exec_tx(
+                    holder,
+                    tx.clone(),
+                    None,
+                    &fee_params,
+                    flow.block_height,
+                    false
+                )
+                .await
+                .is_err());
+        }
+
+        info!("[Dao] Executing the proposal");
+        let (tx, fee_params) = th
+            .dao_exec_calls(
+                &Holder::Alice,
+                &flow.dao,
+                &flow.exec_keypair.secret,
+                &None,
+                &proposal.proposal,
+                vec![(freeze, vec![freeze_secret])],
+                tally.yes_vote_value,
+                tally.all_vote_value,
+                tally.yes_vote_blind,
+                tally.all_vote_blind,
+                flow.block_height,
+            )
+            .await?;
+        for holder in &HOLDERS {
+            th.execute_dao_exec_tx(holder, tx.clone(), None, &fee_params, flow.block_height, true)
+                .await?;
+        }
+        th.assert_trees(&HOLDERS);
+
+        // The committed call froze the token mint
+        let wallet = th.holders.get(&Holder::Alice).unwrap();
+        let blockchain = &wallet.validator.blockchain;
+        assert!(blockchain
+            .contracts
+            .get_state_tree_value(
+                &blockchain.sled_db,
+                &MONEY_CONTRACT_ID,
+                MONEY_CONTRACT_TOKEN_FREEZE_TREE,
+                &serialize(&freeze_params.token_id),
+            )
+            .is_ok());
+
+        Ok(())
+    })
+}
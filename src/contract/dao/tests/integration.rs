@@ -20,7 +20,8 @@ use darkfi::{util::pcg::Pcg32, Result};
 use darkfi_contract_test_harness::{init_logger, Holder, TestHarness};
 use darkfi_dao_contract::{
     blockwindow,
-    model::{Dao, DaoBlindAggregateVote, DaoVoteParams},
+    client::{decrypt_tally, DaoTallyKeyShare, MAX_TALLY_VALUE},
+    model::{Dao, DaoBlindAggregateVote, DaoEncryptedTally, DaoVoteParams},
     DaoFunction,
 };
 use darkfi_money_contract::{
@@ -113,6 +114,7 @@ fn integration_test() -> Result<()> {
             votes_public_key: dao_votes_keypair.public,
             exec_public_key: dao_exec_keypair.public,
             early_exec_public_key: dao_early_exec_keypair.public,
+            membership_root: None,
             bulla_blind: Blind::random(&mut OsRng),
         };
 
@@ -522,11 +524,14 @@ async fn execute_transfer_proposal(
 
     // Count the votes
     let (total_yes_vote_value, total_all_vote_value, total_yes_vote_blind, total_all_vote_blind) =
-        count_votes(&[
-            (vote_note_1, alice_vote_params),
-            (vote_note_2, bob_vote_params),
-            (vote_note_3, charlie_vote_params),
-        ]);
+        count_votes(
+            &[
+                (vote_note_1, alice_vote_params),
+                (vote_note_2, bob_vote_params),
+                (vote_note_3, charlie_vote_params),
+            ],
+            dao_votes_secret_key,
+        );
 
     // Wait until proposal has expired
     if dao_early_exec_secret_key.is_none() {
@@ -694,11 +699,14 @@ async fn execute_generic_proposal(
 
     // Count the votes
     let (total_yes_vote_value, total_all_vote_value, total_yes_vote_blind, total_all_vote_blind) =
-        count_votes(&[
-            (vote_note_1, alice_vote_params),
-            (vote_note_2, bob_vote_params),
-            (vote_note_3, charlie_vote_params),
-        ]);
+        count_votes(
+            &[
+                (vote_note_1, alice_vote_params),
+                (vote_note_2, bob_vote_params),
+                (vote_note_3, charlie_vote_params),
+            ],
+            dao_votes_secret_key,
+        );
 
     // Wait until proposal has expired
     if dao_early_exec_secret_key.is_none() {
@@ -750,10 +758,12 @@ async fn execute_generic_proposal(
 /// Auxiliary function to count proposal votes.
 fn count_votes(
     votes: &[([pallas::Base; 4], DaoVoteParams)],
+    dao_votes_secret_key: &SecretKey,
 ) -> (u64, u64, ScalarBlind, ScalarBlind) {
     let mut total_yes_vote_value = 0;
     let mut total_all_vote_value = 0;
     let mut blind_total_vote = DaoBlindAggregateVote::default();
+    let mut encrypted_tally = DaoEncryptedTally::default();
     let mut total_yes_vote_blind = Blind::ZERO;
     let mut total_all_vote_blind = Blind::ZERO;
 
@@ -784,6 +794,7 @@ fn count_votes(
         let all_vote_commit = params.inputs.iter().map(|i| i.vote_commit).sum();
         let blind_vote = DaoBlindAggregateVote { yes_vote_commit, all_vote_commit };
         blind_total_vote.aggregate(blind_vote);
+        encrypted_tally.aggregate(params.encrypted_tally);
 
         // Just for the debug
         let vote_result = match vote_option != 0 {
@@ -805,5 +816,19 @@ fn count_votes(
             pedersen_commitment_u64(total_yes_vote_value, total_yes_vote_blind)
     );
 
+    // Decrypt the encrypted tally using 2 out of 3 votes key shares
+    let shares = DaoTallyKeyShare::split(dao_votes_secret_key, 2, 3).unwrap();
+    let partials: Vec<_> =
+        shares[1..].iter().map(|share| share.partial_decrypt(&encrypted_tally)).collect();
+    let max_value = ALICE_GOV_SUPPLY + BOB_GOV_SUPPLY + CHARLIE_GOV_SUPPLY;
+    assert_eq!(
+        decrypt_tally(&encrypted_tally, &partials, max_value).unwrap(),
+        (total_yes_vote_value, total_all_vote_value)
+    );
+    // A single share is not enough
+    assert!(decrypt_tally(&encrypted_tally, &partials[..1], max_value).is_err());
+    // Ranges the discrete log can't cover are refused
+    assert!(decrypt_tally(&encrypted_tally, &partials, MAX_TALLY_VALUE + 1).is_err());
+
     (total_yes_vote_value, total_all_vote_value, total_yes_vote_blind, total_all_vote_blind)
 }
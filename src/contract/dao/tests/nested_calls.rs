@@ -0,0 +1,205 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Checks of the call tree composing a treasury transfer:
+//!
+//!   Dao::Exec ->
+//!       Dao::AuthMoneyTransfer
+//!       Money::Transfer
+//!
+//! The treasury coins can only be spent under `Dao::Exec`, which in turn
+//! only accepts the auth calls committed in the proposal, in order.
+
+use darkfi::{
+    tx::{ContractCallLeaf, Transaction, TransactionBuilder},
+    Result,
+};
+use darkfi_contract_test_harness::{init_logger, DaoExecTransferCalls, Holder, TestHarness};
+use darkfi_money_contract::model::DARK_TOKEN_ID;
+use darkfi_sdk::dark_tree::DarkTree;
+use log::info;
+
+const HOLDERS: [Holder; 5] =
+    [Holder::Alice, Holder::Bob, Holder::Charlie, Holder::Dao, Holder::Rachel];
+const MEMBERS: [(Holder, u64); 3] =
+    [(Holder::Alice, 100_000_000), (Holder::Bob, 100_000_000), (Holder::Charlie, 100_000_000)];
+const TREASURY: u64 = 1_000_000_000;
+const TRANSFER_AMOUNT: u64 = 250_000_000;
+const PROPOSAL_DURATION_BLOCKWINDOW: u64 = 1;
+
+/// Build and sign a transaction out of the given call tree
+fn build_tx(
+    calls: &DaoExecTransferCalls,
+    root: ContractCallLeaf,
+    children: Vec<DarkTree<ContractCallLeaf>>,
+    siblings: Vec<ContractCallLeaf>,
+) -> Result<Transaction> {
+    let mut tx_builder = TransactionBuilder::new(root, children)?;
+    for sibling in siblings {
+        tx_builder.append(sibling, vec![])?;
+    }
+    let mut tx = tx_builder.build()?;
+    calls.sign(&mut tx)?;
+    Ok(tx)
+}
+
+fn leaf(call: &ContractCallLeaf) -> DarkTree<ContractCallLeaf> {
+    DarkTree::new(call.clone(), vec![], None, None)
+}
+
+#[test]
+fn nested_calls() -> Result<()> {
+    smol::block_on(async {
+        init_logger();
+
+        let mut th = TestHarness::new(&HOLDERS, false).await?;
+        let mut flow = th
+            .dao_flow_init(&HOLDERS, &MEMBERS, TREASURY, 100_000_000, 200_000_000, (2, 1))
+            .await?;
+
+        let (proposal, proposal_coinattrs) = th
+            .dao_flow_propose_transfer(
+                &mut flow,
+                &Holder::Rachel,
+                TRANSFER_AMOUNT,
+                PROPOSAL_DURATION_BLOCKWINDOW,
+            )
+            .await?;
+
+        let votes = [(Holder::Alice, true), (Holder::Bob, false), (Holder::Charlie, true)];
+        let tally = th.dao_flow_vote(&mut flow, &proposal, &votes).await?;
+        th.dao_flow_wait_expiry(&mut flow, &proposal, PROPOSAL_DURATION_BLOCKWINDOW).await;
+
+        let calls = th
+            .dao_exec_transfer_calls(
+                &flow.dao,
+                &flow.exec_keypair.secret,
+                &None,
+                &proposal.proposal,
+                proposal_coinattrs,
+                tally.yes_vote_value,
+                tally.all_vote_value,
+                tally.yes_vote_blind,
+                tally.all_vote_blind,
+                flow.block_height,
+            )
+            .await?;
+
+        // Every malformed tree must be rejected by all holders
+        let malformed = [
+            (
+                "treasury spend without Dao::Exec",
+                build_tx(&calls, calls.xfer.clone(), vec![], vec![])?,
+            ),
+            (
+                "auth call and transfer as siblings of Dao::Exec",
+                build_tx(
+                    &calls,
+                    calls.exec.clone(),
+                    vec![],
+                    vec![calls.auth_xfer.clone(), calls.xfer.clone()],
+                )?,
+            ),
+            (
+                "transfer before the auth call",
+                build_tx(
+                    &calls,
+                    calls.exec.clone(),
+                    vec![leaf(&calls.xfer), leaf(&calls.auth_xfer)],
+                    vec![],
+                )?,
+            ),
+            (
+                "missing auth call",
+                build_tx(&calls, calls.exec.clone(), vec![leaf(&calls.xfer)], vec![])?,
+            ),
+            (
+                "auth call nested under the transfer",
+                build_tx(
+                    &calls,
+                    calls.exec.clone(),
+                    vec![DarkTree::new(
+                        calls.xfer.clone(),
+                        vec![leaf(&calls.auth_xfer)],
+                        None,
+                        None,
+                    )],
+                    vec![],
+                )?,
+            ),
+        ];
+
+        for (case, tx) in malformed {
+            info!("[Dao] Executing malformed Dao::Exec tx: {case}");
+            for holder in &HOLDERS {
+                assert!(
+                    th.execute_dao_exec_tx(
+                        holder,
+                        tx.clone(),
+                        None,
+                        &None,
+                        flow.block_height,
+                        false
+                    )
+                    .await
+                    .is_err(),
+                    "{case} was accepted"
+                );
+            }
+        }
+
+        // The well formed tree goes through
+        info!("[Dao] Executing Dao::Exec tx");
+        let tx = build_tx(
+            &calls,
+            calls.exec.clone(),
+            vec![leaf(&calls.auth_xfer), leaf(&calls.xfer)],
+            vec![],
+        )?;
+        for holder in &HOLDERS {
+            th.execute_dao_exec_tx(
+                holder,
+                tx.clone(),
+                Some(&calls.xfer_params),
+                &None,
+                flow.block_height,
+                true,
+            )
+            .await?;
+        }
+        th.assert_trees(&HOLDERS);
+
+        let rachel_wallet = th.holders.get(&Holder::Rachel).unwrap();
+        assert_eq!(rachel_wallet.unspent_money_coins.len(), 1);
+        assert_eq!(rachel_wallet.unspent_money_coins[0].note.value, TRANSFER_AMOUNT);
+        assert_eq!(rachel_wallet.unspent_money_coins[0].note.token_id, *DARK_TOKEN_ID);
+
+        let dao_wallet = th.holders.get(&Holder::Dao).unwrap();
+        assert_eq!(dao_wallet.unspent_money_coins[0].note.value, TREASURY - TRANSFER_AMOUNT);
+
+        // The treasury can't be spent twice by replaying the exec
+        for holder in &HOLDERS {
+            assert!(th
+                .execute_dao_exec_tx(holder, tx.clone(), None, &None, flow.block_height, false)
+                .await
+                .is_err());
+        }
+
+        Ok(())
+    })
+}
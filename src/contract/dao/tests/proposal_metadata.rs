@@ -0,0 +1,61 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Decoding of proposal metadata stored before the encrypted tally existed.
+
+use darkfi_dao_contract::model::{DaoBlindAggregateVote, DaoEncryptedTally, DaoProposalMetadata};
+use darkfi_sdk::{
+    crypto::{pasta_prelude::*, MerkleNode},
+    pasta::pallas,
+};
+use darkfi_serial::serialize;
+use rand::rngs::OsRng;
+
+#[test]
+fn migrate_legacy_proposal_metadata() {
+    let vote_aggregate = DaoBlindAggregateVote::default();
+    let snapshot_coins = MerkleNode::from(pallas::Base::random(&mut OsRng));
+    let snapshot_nulls = pallas::Base::random(&mut OsRng);
+
+    // Legacy records lack the trailing encrypted tally
+    let mut legacy = serialize(&vote_aggregate);
+    legacy.extend(serialize(&snapshot_coins));
+    legacy.extend(serialize(&snapshot_nulls));
+
+    let metadata = DaoProposalMetadata::decode_stored(&legacy).unwrap();
+    assert_eq!(metadata.snapshot_coins, snapshot_coins);
+    assert_eq!(metadata.snapshot_nulls, snapshot_nulls);
+    assert!(metadata.encrypted_tally.is_none());
+
+    // Migrated records keep the tally disabled once written back
+    let migrated = DaoProposalMetadata::decode_stored(&serialize(&metadata)).unwrap();
+    assert!(migrated.encrypted_tally.is_none());
+
+    // Current records decode as is
+    let current = DaoProposalMetadata {
+        vote_aggregate,
+        snapshot_coins,
+        snapshot_nulls,
+        encrypted_tally: Some(DaoEncryptedTally::default()),
+    };
+    let decoded = DaoProposalMetadata::decode_stored(&serialize(&current)).unwrap();
+    assert_eq!(decoded.encrypted_tally, Some(DaoEncryptedTally::default()));
+
+    // Garbage is still rejected
+    assert!(DaoProposalMetadata::decode_stored(&legacy[1..]).is_err());
+}
@@ -0,0 +1,302 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Treasury streams: a passed proposal funds a stream coin through
+//! `Dao::AuthCreateStream`, which the recipient then withdraws from as
+//! it vests through `Dao::ClaimStream`.
+
+use darkfi::Result;
+use darkfi_contract_test_harness::{init_logger, Holder, TestHarness};
+use darkfi_money_contract::{client::OwnCoin, model::DARK_TOKEN_ID};
+use darkfi_sdk::{crypto::pasta_prelude::*, pasta::pallas};
+use log::info;
+
+const HOLDERS: [Holder; 5] =
+    [Holder::Alice, Holder::Bob, Holder::Charlie, Holder::Dao, Holder::Rachel];
+const MEMBERS: [(Holder, u64); 3] =
+    [(Holder::Alice, 100_000_000), (Holder::Bob, 100_000_000), (Holder::Charlie, 100_000_000)];
+const TREASURY: u64 = 1_000_000_000;
+/// Proposals take a couple of blockwindows, so the rate is kept low
+/// enough for the stream to not be fully vested on execution.
+const STREAM_TOTAL: u64 = 500_000_000;
+const STREAM_RATE: u64 = 1_000_000;
+const PROPOSAL_DURATION_BLOCKWINDOW: u64 = 1;
+
+/// Find the coin holding what is left of the stream in the holder's wallet
+fn stream_coin(th: &TestHarness, holder: &Holder, stream_bulla: pallas::Base) -> OwnCoin {
+    let wallet = th.holders.get(holder).unwrap();
+    wallet.unspent_money_coins.iter().find(|c| c.note.user_data == stream_bulla).unwrap().clone()
+}
+
+/// Sum of the claimed funds the holder received
+fn claimed_balance(th: &TestHarness, holder: &Holder) -> u64 {
+    let wallet = th.holders.get(holder).unwrap();
+    wallet
+        .unspent_money_coins
+        .iter()
+        .filter(|c| c.note.token_id == *DARK_TOKEN_ID && c.note.user_data == pallas::Base::ZERO)
+        .map(|c| c.note.value)
+        .sum()
+}
+
+#[test]
+fn treasury_stream() -> Result<()> {
+    smol::block_on(async {
+        init_logger();
+
+        let mut th = TestHarness::new(&HOLDERS, false).await?;
+        let mut flow = th
+            .dao_flow_init(&HOLDERS, &MEMBERS, TREASURY, 100_000_000, 200_000_000, (2, 1))
+            .await?;
+
+        let (proposal, stream_call) = th
+            .dao_flow_propose_stream(
+                &mut flow,
+                &Holder::Rachel,
+                STREAM_TOTAL,
+                STREAM_RATE,
+                PROPOSAL_DURATION_BLOCKWINDOW,
+            )
+            .await?;
+        let stream = stream_call.stream.clone();
+        let stream_bulla = stream.to_bulla().inner();
+
+        let votes = [(Holder::Alice, true), (Holder::Bob, true), (Holder::Charlie, false)];
+        let tally = th.dao_flow_vote(&mut flow, &proposal, &votes).await?;
+        th.dao_flow_wait_expiry(&mut flow, &proposal, PROPOSAL_DURATION_BLOCKWINDOW).await;
+
+        info!("[Dao] Executing the stream proposal without creating the stream");
+        let (tx, xfer_params, fee_params) = th
+            .dao_exec_transfer(
+                &Holder::Alice,
+                &flow.dao,
+                &flow.exec_keypair.secret,
+                &None,
+                &proposal.proposal,
+                vec![stream_call.stream_coin_attrs()],
+                tally.yes_vote_value,
+                tally.all_vote_value,
+                tally.yes_vote_blind,
+                tally.all_vote_blind,
+                flow.block_height,
+            )
+            .await?;
+        for holder in &HOLDERS {
+            assert!(th
+                .execute_dao_exec_tx(
+                    holder,
+                    tx.clone(),
+                    Some(&xfer_params),
+                    &fee_params,
+                    flow.block_height,
+                    false,
+                )
+                .await
+                .is_err());
+        }
+
+        info!("[Dao] Executing the stream proposal");
+        let (tx, xfer_params, fee_params) = th
+            .dao_exec_stream(
+                &Holder::Alice,
+                &flow.dao,
+                &flow.exec_keypair.secret,
+                &proposal.proposal,
+                &stream_call,
+                tally.yes_vote_value,
+                tally.all_vote_value,
+                tally.yes_vote_blind,
+                tally.all_vote_blind,
+                flow.block_height,
+            )
+            .await?;
+        for holder in &HOLDERS {
+            th.execute_dao_exec_tx(
+                holder,
+                tx.clone(),
+                Some(&xfer_params),
+                &fee_params,
+                flow.block_height,
+                true,
+            )
+            .await?;
+        }
+        th.assert_trees(&HOLDERS);
+        flow.block_height += 1;
+
+        // Rachel got the whole stream in a single coin, locked under Dao::ClaimStream
+        let coin = stream_coin(&th, &Holder::Rachel, stream_bulla);
+        assert_eq!(coin.note.value, STREAM_TOTAL);
+        assert_eq!(claimed_balance(&th, &Holder::Rachel), 0);
+
+        let vested = stream.vested(flow.block_height);
+        assert!(vested > 0 && vested < STREAM_TOTAL);
+
+        info!("[Rachel] Spending the stream coin outside of Dao::ClaimStream");
+        if let Ok((tx, (params, fee_params), _)) = th
+            .transfer(
+                STREAM_TOTAL,
+                &Holder::Rachel,
+                &Holder::Rachel,
+                &[coin.clone()],
+                *DARK_TOKEN_ID,
+                flow.block_height,
+                false,
+            )
+            .await
+        {
+            assert!(th
+                .execute_transfer_tx(
+                    &Holder::Rachel,
+                    tx,
+                    &params,
+                    &fee_params,
+                    flow.block_height,
+                    false
+                )
+                .await
+                .is_err());
+        }
+
+        info!("[Rachel] Claiming more than what vested");
+        assert!(th
+            .dao_claim_stream(
+                &Holder::Rachel,
+                &stream,
+                &coin,
+                vested + 1,
+                flow.block_height,
+                flow.block_height,
+            )
+            .await
+            .is_err());
+
+        info!("[Rachel] Claiming with a proof made for a later block");
+        let later_height = flow.block_height + 10;
+        let (tx, params, fee_params) = th
+            .dao_claim_stream(
+                &Holder::Rachel,
+                &stream,
+                &coin,
+                stream.vested(later_height),
+                later_height,
+                flow.block_height,
+            )
+            .await?;
+        for holder in &HOLDERS {
+            assert!(th
+                .execute_transfer_tx(
+                    holder,
+                    tx.clone(),
+                    &params,
+                    &fee_params,
+                    flow.block_height,
+                    false
+                )
+                .await
+                .is_err());
+        }
+
+        info!("[Rachel] Claiming half of the vested funds");
+        let first_claim = vested / 2;
+        let (tx, params, fee_params) = th
+            .dao_claim_stream(
+                &Holder::Rachel,
+                &stream,
+                &coin,
+                first_claim,
+                flow.block_height,
+                flow.block_height,
+            )
+            .await?;
+        for holder in &HOLDERS {
+            th.execute_transfer_tx(
+                holder,
+                tx.clone(),
+                &params,
+                &fee_params,
+                flow.block_height,
+                true,
+            )
+            .await?;
+        }
+        th.assert_trees(&HOLDERS);
+        flow.block_height += 1;
+
+        let coin = stream_coin(&th, &Holder::Rachel, stream_bulla);
+        assert_eq!(coin.note.value, STREAM_TOTAL - first_claim);
+        assert_eq!(claimed_balance(&th, &Holder::Rachel), first_claim);
+
+        info!("[Rachel] Claiming without increasing the claimed amount");
+        let (tx, params, fee_params) = th
+            .dao_claim_stream(
+                &Holder::Rachel,
+                &stream,
+                &coin,
+                first_claim,
+                flow.block_height,
+                flow.block_height,
+            )
+            .await?;
+        for holder in &HOLDERS {
+            assert!(th
+                .execute_transfer_tx(
+                    holder,
+                    tx.clone(),
+                    &params,
+                    &fee_params,
+                    flow.block_height,
+                    false
+                )
+                .await
+                .is_err());
+        }
+
+        info!("[Rachel] Claiming everything vested so far");
+        flow.block_height += 10;
+        let second_claim = stream.vested(flow.block_height);
+        let (tx, params, fee_params) = th
+            .dao_claim_stream(
+                &Holder::Rachel,
+                &stream,
+                &coin,
+                second_claim,
+                flow.block_height,
+                flow.block_height,
+            )
+            .await?;
+        for holder in &HOLDERS {
+            th.execute_transfer_tx(
+                holder,
+                tx.clone(),
+                &params,
+                &fee_params,
+                flow.block_height,
+                true,
+            )
+            .await?;
+        }
+        th.assert_trees(&HOLDERS);
+
+        let coin = stream_coin(&th, &Holder::Rachel, stream_bulla);
+        assert_eq!(coin.note.value, STREAM_TOTAL - second_claim);
+        assert_eq!(claimed_balance(&th, &Holder::Rachel), second_claim);
+
+        Ok(())
+    })
+}
@@ -17,7 +17,7 @@
  */
 
 use darkfi::{ClientFailed, Result};
-use darkfi_sdk::{crypto::Keypair, deploy::DeployParamsV1};
+use darkfi_sdk::{crypto::PublicKey, deploy::DeployParamsV1};
 use log::{debug, error};
 
 use crate::error::DeployError;
@@ -28,8 +28,9 @@ pub struct DeployCallDebris {
 
 /// Struct holding necessary information to build a `Deployooor::DeployV1` contract call.
 pub struct DeployCallBuilder {
-    /// Contract deploy keypair
-    pub deploy_keypair: Keypair,
+    /// Contract deploy authority public key. The transaction has to be
+    /// signed with its secret key.
+    pub deploy_public: PublicKey,
     /// WASM bincode to deploy
     pub wasm_bincode: Vec<u8>,
     /// Serialized deployment payload instruction
@@ -46,7 +47,7 @@ impl DeployCallBuilder {
 
         let params = DeployParamsV1 {
             wasm_bincode: self.wasm_bincode.clone(),
-            public_key: self.deploy_keypair.public,
+            public_key: self.deploy_public,
             ix: self.deploy_ix.clone(),
         };
 
@@ -17,7 +17,7 @@
  */
 
 use darkfi::Result;
-use darkfi_sdk::crypto::Keypair;
+use darkfi_sdk::crypto::PublicKey;
 use log::debug;
 
 use crate::model::LockParamsV1;
@@ -28,15 +28,16 @@ pub struct LockCallDebris {
 
 /// Struct holding necessary information to build a `Deployooor::LockV1` contract call.
 pub struct LockCallBuilder {
-    /// Contract deploy keypair
-    pub deploy_keypair: Keypair,
+    /// Contract deploy authority public key. The transaction has to be
+    /// signed with its secret key.
+    pub deploy_public: PublicKey,
 }
 
 impl LockCallBuilder {
     pub fn build(&self) -> Result<LockCallDebris> {
         debug!(target: "contract::deployooor::client::lock", "Building Deployooor::LockV1 contract call");
 
-        let params = LockParamsV1 { public_key: self.deploy_keypair.public };
+        let params = LockParamsV1 { public_key: self.deploy_public };
         let debris = LockCallDebris { params };
 
         Ok(debris)
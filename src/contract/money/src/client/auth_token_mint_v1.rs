@@ -21,7 +21,7 @@ use darkfi::{
     zkas::ZkBinary,
     Result,
 };
-use darkfi_sdk::crypto::{note::AeadEncryptedNote, Blind, Keypair};
+use darkfi_sdk::crypto::{note::EncryptableNote, Blind, Keypair};
 use log::debug;
 use rand::rngs::OsRng;
 
@@ -81,9 +81,10 @@ impl AuthTokenMintCallBuilder {
             value_blind: Blind::random(&mut OsRng),
             token_blind: Blind::ZERO,
             memo: vec![],
+            sender: None,
         };
 
-        let enc_note = AeadEncryptedNote::encrypt(&note, &self.coin_attrs.public_key, &mut OsRng)?;
+        let enc_note = note.encrypt_note(&self.coin_attrs.public_key, &mut OsRng)?;
 
         let params = MoneyAuthTokenMintParamsV1 {
             token_id: self.token_attrs.to_token_id(),
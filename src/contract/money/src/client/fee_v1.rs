@@ -41,6 +41,23 @@ use crate::{
 /// This is the minimum gas any fee-paying transaction will use.
 pub const FEE_CALL_GAS: u64 = 42_000_000;
 
+/// Fee rates are expressed in fee units paid per this amount of gas.
+pub const FEE_RATE_GAS_UNIT: u64 = 1000;
+
+/// Lowest fee rate validators accept, as they require a fee unit
+/// per 100 gas used.
+pub const MIN_FEE_RATE: u64 = FEE_RATE_GAS_UNIT / 100;
+
+/// Compute the value a `Fee_V1` call has to pay for a transaction using
+/// `tx_gas` gas without the fee call, at provided fee rate. The rate is
+/// usually retrieved using the `fee.estimate` darkfid RPC method, and
+/// gets raised to [`MIN_FEE_RATE`] if it is lower.
+pub fn compute_fee_value(tx_gas: u64, fee_rate: u64) -> u64 {
+    let gas = (tx_gas + FEE_CALL_GAS) as u128;
+    let fee_rate = fee_rate.max(MIN_FEE_RATE) as u128;
+    (gas * fee_rate).div_ceil(FEE_RATE_GAS_UNIT as u128) as u64
+}
+
 /// Private values related to the Fee call
 pub struct FeeCallSecrets {
     /// The ZK proof created in this builder
@@ -22,7 +22,7 @@ use darkfi::{
     ClientFailed, Result,
 };
 use darkfi_sdk::{
-    crypto::{note::AeadEncryptedNote, pasta_prelude::*, Blind, FuncId, PublicKey},
+    crypto::{note::EncryptableNote, pasta_prelude::*, Blind, FuncId, PublicKey},
     pasta::pallas,
 };
 use log::debug;
@@ -152,9 +152,10 @@ impl GenesisMintCallBuilder {
                 value_blind,
                 token_blind,
                 memo: vec![],
+                sender: None,
             };
 
-            let encrypted_note = AeadEncryptedNote::encrypt(&note, &public_key, &mut OsRng)?;
+            let encrypted_note = note.encrypt_note(&public_key, &mut OsRng)?;
 
             let output = Output {
                 value_commit: public_inputs.value_commit,
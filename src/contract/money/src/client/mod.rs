@@ -33,8 +33,11 @@ use std::hash::{Hash, Hasher};
 use darkfi_sdk::{
     bridgetree,
     crypto::{
+        note::EncryptableNote,
         pasta_prelude::{Field, PrimeField},
-        poseidon_hash, BaseBlind, Blind, FuncId, ScalarBlind, SecretKey,
+        poseidon_hash,
+        schnorr::{SchnorrPublic, SchnorrSecret, Signature},
+        BaseBlind, Blind, FuncId, PublicKey, ScalarBlind, SecretKey,
     },
     pasta::pallas,
 };
@@ -71,7 +74,8 @@ pub mod token_mint_v1;
 /// It does not store the public key since it's encrypted for that key,
 /// and so is not needed to infer the coin attributes.
 /// All other coin attributes must be present.
-#[derive(Debug, Clone, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Clone, Eq, PartialEq, SerialEncodable, SerialDecodable, EncryptableNote)]
+#[note(version = 1)]
 pub struct MoneyNote {
     /// Value of the coin
     pub value: u64,
@@ -94,6 +98,42 @@ pub struct MoneyNote {
     // should ensure everything else is correct.
     /// Attached memo (arbitrary data)
     pub memo: Vec<u8>,
+    /// Optional sender identity disclosure. Transfers are anonymous
+    /// unless the sender opts into revealing itself to the recipient.
+    pub sender: Option<MoneyNoteSender>,
+}
+
+/// Domain separator for the sender identity signature
+const MONEY_NOTE_SENDER_DOMAIN: &[u8] = b"DarkFi:MoneyNoteSender";
+
+/// Sender identity attached to a `MoneyNote`.
+///
+/// The sender signs the output coin, which commits to the recipient and
+/// a random blind, so the disclosure can't be replayed in another note.
+/// Since it lives inside the note, only the recipient is able to see it.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct MoneyNoteSender {
+    /// Public key of the sender
+    pub public_key: PublicKey,
+    /// Signature over the output coin
+    pub signature: Signature,
+}
+
+impl MoneyNoteSender {
+    /// Create a sender disclosure for the given output coin
+    pub fn new(secret: &SecretKey, coin: &Coin) -> Self {
+        let signature = secret.sign(&Self::message(coin));
+        Self { public_key: PublicKey::from_secret(*secret), signature }
+    }
+
+    /// Verify the disclosure was made by its public key for the given coin
+    pub fn verify(&self, coin: &Coin) -> bool {
+        self.public_key.verify(&Self::message(coin), &self.signature)
+    }
+
+    fn message(coin: &Coin) -> Vec<u8> {
+        [MONEY_NOTE_SENDER_DOMAIN, &coin.inner().to_repr()].concat()
+    }
 }
 
 /// `OwnCoin` is a representation of `Coin` with its respective metadata.
@@ -114,6 +154,13 @@ impl OwnCoin {
     pub fn nullifier(&self) -> Nullifier {
         Nullifier::from(poseidon_hash([self.secret.inner(), self.coin.inner()]))
     }
+
+    /// Return the disclosed sender of this [`OwnCoin`], if there is one
+    /// and its signature over the coin is valid.
+    pub fn sender(&self) -> Option<PublicKey> {
+        let sender = self.note.sender?;
+        sender.verify(&self.coin).then_some(sender.public_key)
+    }
 }
 
 impl Hash for OwnCoin {
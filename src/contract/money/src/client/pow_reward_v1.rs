@@ -23,7 +23,7 @@ use darkfi::{
 };
 use darkfi_sdk::{
     blockchain::expected_reward,
-    crypto::{note::AeadEncryptedNote, pasta_prelude::*, Blind, FuncId, PublicKey},
+    crypto::{note::EncryptableNote, pasta_prelude::*, Blind, FuncId, PublicKey},
     pasta::pallas,
 };
 use log::debug;
@@ -133,9 +133,10 @@ impl PoWRewardCallBuilder {
             value_blind,
             token_blind,
             memo: vec![],
+            sender: None,
         };
 
-        let encrypted_note = AeadEncryptedNote::encrypt(&note, &output.public_key, &mut OsRng)?;
+        let encrypted_note = note.encrypt_note(&output.public_key, &mut OsRng)?;
 
         let c_output = Output {
             value_commit: public_inputs.value_commit,
@@ -25,7 +25,7 @@ use darkfi::{
 };
 use darkfi_sdk::{
     crypto::{
-        note::AeadEncryptedNote, pasta_prelude::*, BaseBlind, Blind, FuncId, MerkleTree, PublicKey,
+        note::EncryptableNote, pasta_prelude::*, BaseBlind, Blind, FuncId, MerkleTree, PublicKey,
         ScalarBlind, SecretKey,
     },
     pasta::pallas,
@@ -194,9 +194,10 @@ impl SwapCallBuilder {
             token_blind: self.token_blinds[1],
             // Here we store our secret key we use for signing
             memo: serialize(&signature_secret),
+            sender: None,
         };
 
-        let encrypted_note = AeadEncryptedNote::encrypt(&note, &self.pubkey, &mut OsRng)?;
+        let encrypted_note = note.encrypt_note(&self.pubkey, &mut OsRng)?;
 
         params.outputs.push(Output {
             value_commit: public_inputs.value_commit,
@@ -25,7 +25,7 @@ use darkfi_sdk::pasta::pallas;
 use log::debug;
 use rand::rngs::OsRng;
 
-use crate::model::{CoinAttributes, MoneyTokenMintParamsV1, TokenAttributes};
+use crate::model::{CoinAttributes, MoneyTokenMintParamsV1, TokenAttributes, TokenSupply};
 
 pub struct TokenMintCallDebris {
     pub params: MoneyTokenMintParamsV1,
@@ -36,6 +36,8 @@ pub struct TokenMintCallDebris {
 pub struct TokenMintCallBuilder {
     pub coin_attrs: CoinAttributes,
     pub token_attrs: TokenAttributes,
+    /// Supply policy of the token
+    pub supply: TokenSupply,
 
     /// `TokenMint_V1` zkas circuit ZkBinary
     pub mint_zkbin: ZkBinary,
@@ -47,6 +49,8 @@ impl TokenMintCallBuilder {
     pub fn build(&self) -> Result<TokenMintCallDebris> {
         debug!(target: "contract::money::client::token_mint", "Building Money::TokenMintV1 contract call");
         let (public_x, public_y) = self.coin_attrs.public_key.xy();
+        let reveal_value = matches!(self.supply, TokenSupply::Fixed(_));
+        let value = if reveal_value { Some(self.coin_attrs.value) } else { None };
 
         let prover_witnesses = vec![
             // Coin attributes
@@ -60,17 +64,26 @@ impl TokenMintCallBuilder {
             Witness::Base(Value::known(self.token_attrs.auth_parent.inner())),
             Witness::Base(Value::known(self.token_attrs.user_data)),
             Witness::Base(Value::known(self.token_attrs.blind.inner())),
+            // Only fixed supply mints reveal their value
+            Witness::Base(Value::known(pallas::Base::from(reveal_value as u64))),
         ];
 
         let coin = self.coin_attrs.to_coin();
 
-        let public_inputs = vec![self.token_attrs.auth_parent.inner(), coin.inner()];
+        let token_id = self.token_attrs.to_token_id();
+        let public_inputs = vec![
+            self.token_attrs.auth_parent.inner(),
+            token_id.inner(),
+            pallas::Base::from(reveal_value as u64),
+            pallas::Base::from(value.unwrap_or(0)),
+            coin.inner(),
+        ];
 
         //darkfi::zk::export_witness_json( "proof/witness/token_mint_v1.json", &prover_witnesses, &public_inputs);
         let circuit = ZkCircuit::new(prover_witnesses, &self.mint_zkbin);
         let proof = Proof::create(&self.mint_pk, &[circuit], &public_inputs, &mut OsRng)?;
 
-        let params = MoneyTokenMintParamsV1 { coin };
+        let params = MoneyTokenMintParamsV1 { token_id, value, supply: self.supply, coin };
         let debris = TokenMintCallDebris { params, proofs: vec![proof] };
         Ok(debris)
     }
@@ -23,7 +23,7 @@ use darkfi::{
 };
 use darkfi_sdk::{
     crypto::{
-        note::AeadEncryptedNote, pasta_prelude::*, BaseBlind, Blind, MerkleNode, ScalarBlind,
+        note::EncryptableNote, pasta_prelude::*, BaseBlind, Blind, MerkleNode, ScalarBlind,
         SecretKey,
     },
     pasta::pallas,
@@ -33,7 +33,7 @@ use rand::rngs::OsRng;
 
 use super::proof::{create_transfer_burn_proof, create_transfer_mint_proof};
 use crate::{
-    client::{compute_remainder_blind, MoneyNote, OwnCoin, TokenId},
+    client::{compute_remainder_blind, MoneyNote, MoneyNoteSender, OwnCoin, TokenId},
     error::MoneyError,
     model::{CoinAttributes, Input, MoneyTransferParamsV1, Output},
 };
@@ -46,6 +46,9 @@ pub struct TransferCallBuilder {
     pub inputs: Vec<TransferCallInput>,
     /// Anonymous outputs
     pub outputs: Vec<TransferCallOutput>,
+    /// Optional sender secret key, used to disclose the sender identity
+    /// to the output recipients. `None` keeps the transfer anonymous.
+    pub sender: Option<SecretKey>,
     /// `Mint_V1` zkas circuit ZkBinary
     pub mint_zkbin: ZkBinary,
     /// Proving key for the `Mint_V1` zk circuit
@@ -163,9 +166,12 @@ impl TransferCallBuilder {
                 value_blind,
                 token_blind,
                 memo: vec![],
+                sender: self
+                    .sender
+                    .map(|secret| MoneyNoteSender::new(&secret, &public_inputs.coin)),
             };
 
-            let encrypted_note = AeadEncryptedNote::encrypt(&note, &output.public_key, &mut OsRng)?;
+            let encrypted_note = note.encrypt_note(&output.public_key, &mut OsRng)?;
             output_notes.push(note);
 
             params.outputs.push(Output {
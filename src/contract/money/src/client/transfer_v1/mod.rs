@@ -82,6 +82,8 @@ pub fn select_coins(coins: Vec<OwnCoin>, min_value: u64) -> Result<(Vec<OwnCoin>
 /// * `burn_pk`: Proving key for the `Burn_V1` zk circuit
 /// * `half_split`: Flag indicating to split the output coin into
 ///   two equal halves.
+/// * `disclose_sender`: Flag indicating to reveal our public key to
+///   the recipient, inside the encrypted note.
 ///
 /// Returns a tuple of:
 ///
@@ -103,6 +105,7 @@ pub fn make_transfer_call(
     burn_zkbin: ZkBinary,
     burn_pk: ProvingKey,
     half_split: bool,
+    disclose_sender: bool,
 ) -> Result<(MoneyTransferParamsV1, TransferCallSecrets, Vec<OwnCoin>)> {
     debug!(target: "contract::money::client::transfer", "Building Money::TransferV1 contract call");
     if value == 0 {
@@ -207,6 +210,7 @@ pub fn make_transfer_call(
         clear_inputs: vec![],
         inputs,
         outputs,
+        sender: disclose_sender.then_some(keypair.secret),
         mint_zkbin,
         mint_pk,
         burn_zkbin,
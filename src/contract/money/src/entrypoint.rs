@@ -38,6 +38,7 @@ use crate::{
     MONEY_CONTRACT_FEES_TREE, MONEY_CONTRACT_INFO_TREE, MONEY_CONTRACT_LATEST_COIN_ROOT,
     MONEY_CONTRACT_LATEST_NULLIFIER_ROOT, MONEY_CONTRACT_NULLIFIERS_TREE,
     MONEY_CONTRACT_NULLIFIER_ROOTS_TREE, MONEY_CONTRACT_TOKEN_FREEZE_TREE,
+    MONEY_CONTRACT_TOKEN_SUPPLY_TREE,
 };
 
 /// `Money::Fee` functions
@@ -174,6 +175,12 @@ fn init_contract(cid: ContractId, _ix: &[u8]) -> ContractResult {
         wasm::db::db_init(cid, MONEY_CONTRACT_TOKEN_FREEZE_TREE)?;
     }
 
+    // Set up a database tree to hold the supply policy and minted amount of tokens
+    // k=TokenId, v=TokenSupplyInfo
+    if wasm::db::db_lookup(cid, MONEY_CONTRACT_TOKEN_SUPPLY_TREE).is_err() {
+        wasm::db::db_init(cid, MONEY_CONTRACT_TOKEN_SUPPLY_TREE)?;
+    }
+
     // Set up a database tree to hold the fees paid for each block
     // k=height_bytes, v=fees_paid_bytes
     if wasm::db::db_lookup(cid, MONEY_CONTRACT_FEES_TREE).is_err() {
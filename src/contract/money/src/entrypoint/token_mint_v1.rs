@@ -28,11 +28,12 @@ use darkfi_serial::{deserialize, serialize, Encodable};
 
 use crate::{
     error::MoneyError,
-    model::{MoneyTokenMintParamsV1, MoneyTokenMintUpdateV1},
+    model::{MoneyTokenMintParamsV1, MoneyTokenMintUpdateV1, TokenSupply, TokenSupplyInfo},
     MONEY_CONTRACT_COINS_TREE, MONEY_CONTRACT_COIN_MERKLE_TREE, MONEY_CONTRACT_COIN_ROOTS_TREE,
     MONEY_CONTRACT_INFO_TREE, MONEY_CONTRACT_LATEST_COIN_ROOT,
     MONEY_CONTRACT_LATEST_NULLIFIER_ROOT, MONEY_CONTRACT_NULLIFIERS_TREE,
-    MONEY_CONTRACT_NULLIFIER_ROOTS_TREE, MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1,
+    MONEY_CONTRACT_NULLIFIER_ROOTS_TREE, MONEY_CONTRACT_TOKEN_SUPPLY_TREE,
+    MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1,
 };
 
 /// `get_metadata` function for `Money::TokenMintV1`
@@ -68,7 +69,13 @@ pub(crate) fn money_token_mint_get_metadata_v1(
 
     zk_public_inputs.push((
         MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1.to_string(),
-        vec![child_func_id.inner(), params.coin.inner()],
+        vec![
+            child_func_id.inner(),
+            params.token_id.inner(),
+            pallas::Base::from(params.value.is_some() as u64),
+            pallas::Base::from(params.value.unwrap_or(0)),
+            params.coin.inner(),
+        ],
     ));
 
     // Serialize everything gathered and return it
@@ -98,8 +105,60 @@ pub(crate) fn money_token_mint_process_instruction_v1(
         return Err(MoneyError::DuplicateCoin.into())
     }
 
-    // Create a state update. We only need the new coin.
-    let update = MoneyTokenMintUpdateV1 { coin: params.coin };
+    // The first mint of a token declares its supply policy, and every
+    // following mint has to respect it.
+    let token_supply_db = wasm::db::db_lookup(cid, MONEY_CONTRACT_TOKEN_SUPPLY_TREE)?;
+    let minted = match wasm::db::db_get(token_supply_db, &serialize(&params.token_id))? {
+        Some(data) => {
+            let info: TokenSupplyInfo = deserialize(&data)?;
+            if info.supply != params.supply {
+                msg!(
+                    "[TokenMintV1] Error: Token {} was declared with supply {:?}",
+                    params.token_id,
+                    info.supply
+                );
+                return Err(MoneyError::TokenSupplyMismatch.into())
+            }
+            info.minted
+        }
+        None => 0,
+    };
+
+    // Only fixed supply mints reveal their value, which we need to enforce
+    // the max supply. Reissuable ones keep it private.
+    let minted = match (params.supply, params.value) {
+        (TokenSupply::Fixed(max_supply), Some(value)) => {
+            let Some(minted) = minted.checked_add(value) else {
+                msg!("[TokenMintV1] Error: Minted supply overflow for token {}", params.token_id);
+                return Err(MoneyError::TokenSupplyExceeded.into())
+            };
+
+            if minted > max_supply {
+                msg!(
+                    "[TokenMintV1] Error: Minting {value} of token {} exceeds max supply {max_supply}",
+                    params.token_id
+                );
+                return Err(MoneyError::TokenSupplyExceeded.into())
+            }
+
+            minted
+        }
+        (TokenSupply::Reissuable, None) => minted,
+        _ => {
+            msg!(
+                "[TokenMintV1] Error: Mint value must be revealed only for fixed supply token {}",
+                params.token_id
+            );
+            return Err(MoneyError::TokenSupplyMismatch.into())
+        }
+    };
+
+    // Create a state update. We need the new coin and the token supply state.
+    let update = MoneyTokenMintUpdateV1 {
+        token_id: params.token_id,
+        supply: TokenSupplyInfo { supply: params.supply, minted },
+        coin: params.coin,
+    };
     Ok(serialize(&update))
 }
 
@@ -114,6 +173,7 @@ pub(crate) fn money_token_mint_process_update_v1(
     let nullifiers_db = wasm::db::db_lookup(cid, MONEY_CONTRACT_NULLIFIERS_TREE)?;
     let coin_roots_db = wasm::db::db_lookup(cid, MONEY_CONTRACT_COIN_ROOTS_TREE)?;
     let nullifier_roots_db = wasm::db::db_lookup(cid, MONEY_CONTRACT_NULLIFIER_ROOTS_TREE)?;
+    let token_supply_db = wasm::db::db_lookup(cid, MONEY_CONTRACT_TOKEN_SUPPLY_TREE)?;
 
     // This will just make a snapshot to match the coins one
     msg!("[TokenMintV1] Updating nullifiers snapshot");
@@ -125,6 +185,9 @@ pub(crate) fn money_token_mint_process_update_v1(
         &[],
     )?;
 
+    msg!("[TokenMintV1] Updating token supply");
+    wasm::db::db_set(token_supply_db, &serialize(&update.token_id), &serialize(&update.supply))?;
+
     msg!("[TokenMintV1] Adding new coin to the set");
     wasm::db::db_set(coins_db, &serialize(&update.coin), &[])?;
 
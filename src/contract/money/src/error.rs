@@ -109,6 +109,12 @@ pub enum MoneyError {
 
     #[error("Children indexes length missmatch")]
     ChildrenIndexesLengthMismatch,
+
+    #[error("Token supply policy does not match the declared one")]
+    TokenSupplyMismatch,
+
+    #[error("Token mint exceeds max supply")]
+    TokenSupplyExceeded,
 }
 
 impl From<MoneyError> for ContractError {
@@ -143,6 +149,8 @@ impl From<MoneyError> for ContractError {
             MoneyError::CoinMerkleRootNotFound => Self::Custom(27),
             MoneyError::RootsValueDataMismatch => Self::Custom(28),
             MoneyError::ChildrenIndexesLengthMismatch => Self::Custom(29),
+            MoneyError::TokenSupplyMismatch => Self::Custom(30),
+            MoneyError::TokenSupplyExceeded => Self::Custom(31),
         }
     }
 }
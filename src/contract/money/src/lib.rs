@@ -19,7 +19,11 @@
 //! DarkFi Money Contract
 //!
 //! Smart contract implementing money transfers, atomic swaps, token
-//! minting and freezing, and staking/unstaking of consensus tokens.
+//! minting and freezing, and proof-of-work block rewards.
+//!
+//! Note: there is no consensus staking in this contract. Blocks are
+//! produced through proof-of-work, so there are no staked positions,
+//! pending staking rewards or unbonding entries to query.
 
 use darkfi_sdk::error::ContractError;
 
@@ -77,6 +81,7 @@ pub const MONEY_CONTRACT_COIN_ROOTS_TREE: &str = "coin_roots";
 pub const MONEY_CONTRACT_NULLIFIERS_TREE: &str = "nullifiers";
 pub const MONEY_CONTRACT_NULLIFIER_ROOTS_TREE: &str = "nullifier_roots";
 pub const MONEY_CONTRACT_TOKEN_FREEZE_TREE: &str = "token_freezes";
+pub const MONEY_CONTRACT_TOKEN_SUPPLY_TREE: &str = "token_supply";
 pub const MONEY_CONTRACT_FEES_TREE: &str = "fees";
 
 // These are keys inside the info tree
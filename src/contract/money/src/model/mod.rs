@@ -228,9 +228,34 @@ pub struct MoneyGenesisMintUpdateV1 {
     pub coins: Vec<Coin>,
 }
 
+/// Supply policy of a token, declared by its first mint
+#[derive(Clone, Copy, Debug, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+pub enum TokenSupply {
+    /// More tokens can be minted until the mint gets frozen
+    Reissuable,
+    /// The total minted amount can never exceed the given max supply
+    Fixed(u64),
+}
+
+/// Supply state of a token, as stored in the Money state
+#[derive(Clone, Copy, Debug, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct TokenSupplyInfo {
+    /// Policy declared by the token's first mint
+    pub supply: TokenSupply,
+    /// Total amount minted so far. Only tracked for fixed supply tokens,
+    /// since reissuable mints don't reveal their value.
+    pub minted: u64,
+}
+
 /// Parameters for `Money::TokenMint`
 #[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
 pub struct MoneyTokenMintParamsV1 {
+    /// Token ID of the minted coin
+    pub token_id: TokenId,
+    /// Minted amount, revealed only by fixed supply mints
+    pub value: Option<u64>,
+    /// Supply policy of the token. Must match the one declared by its first mint.
+    pub supply: TokenSupply,
     /// The newly minted coin
     pub coin: Coin,
 }
@@ -238,6 +263,10 @@ pub struct MoneyTokenMintParamsV1 {
 /// State update for `Money::TokenMint`
 #[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
 pub struct MoneyTokenMintUpdateV1 {
+    /// Token ID of the minted coin
+    pub token_id: TokenId,
+    /// Updated supply state of the token
+    pub supply: TokenSupplyInfo,
     /// The newly minted coin
     pub coin: Coin,
 }
@@ -37,7 +37,7 @@ use darkfi_money_contract::{
 use darkfi_sdk::{
     blockchain::expected_reward,
     crypto::{
-        contract_id::MONEY_CONTRACT_ID, note::AeadEncryptedNote, BaseBlind, FuncId, MerkleNode,
+        contract_id::MONEY_CONTRACT_ID, note::EncryptableNote, BaseBlind, FuncId, MerkleNode,
         ScalarBlind, SecretKey,
     },
     pasta::pallas,
@@ -100,6 +100,7 @@ fn delayed_tx() -> Result<()> {
             burn_zkbin.clone(),
             burn_pk.clone(),
             false,
+            false,
         )?;
 
         let mut output_coins = vec![];
@@ -107,7 +108,7 @@ fn delayed_tx() -> Result<()> {
             money_merkle_tree.append(MerkleNode::from(output.coin.inner()));
 
             // Attempt to decrypt the output note to see if this is a coin for the holder.
-            let Ok(note) = output.note.decrypt::<MoneyNote>(&wallet.keypair.secret) else {
+            let Ok(note) = MoneyNote::decrypt_note(&output.note, &wallet.keypair.secret) else {
                 continue
             };
 
@@ -205,9 +206,10 @@ fn delayed_tx() -> Result<()> {
             value_blind: output_value_blind,
             token_blind,
             memo: vec![],
+            sender: None,
         };
 
-        let encrypted_note = AeadEncryptedNote::encrypt(&note, &output.public_key, &mut OsRng)?;
+        let encrypted_note = note.encrypt_note(&output.public_key, &mut OsRng)?;
 
         let fee_call_params = MoneyFeeParamsV1 {
             input: Input {
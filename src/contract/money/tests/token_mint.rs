@@ -18,6 +18,7 @@
 
 use darkfi::Result;
 use darkfi_contract_test_harness::{init_logger, Holder, TestHarness};
+use darkfi_money_contract::model::TokenSupply;
 use darkfi_sdk::crypto::BaseBlind;
 use log::info;
 use rand::rngs::OsRng;
@@ -53,6 +54,9 @@ fn token_mint() -> Result<()> {
             )
             .await?;
 
+        // Reissuable mints keep their value private
+        assert!(token_mint_params.value.is_none());
+
         for holder in &HOLDERS {
             info!("[{holder:?}] Executing BOB token mint tx");
             th.execute_token_mint_tx(
@@ -92,3 +96,112 @@ fn token_mint() -> Result<()> {
         Ok(())
     })
 }
+
+#[test]
+fn token_mint_fixed_supply() -> Result<()> {
+    smol::block_on(async {
+        init_logger();
+
+        // Holders this test will use
+        const HOLDERS: [Holder; 2] = [Holder::Alice, Holder::Bob];
+
+        // Some numbers we want to assert
+        const BOB_MAX_SUPPLY: u64 = 1000000000; // 10 BOB
+        const BOB_MINTS: [u64; 2] = [600000000, 400000000];
+
+        // Block height to verify against
+        let current_block_height = 0;
+
+        // Initialize harness
+        let mut th = TestHarness::new(&HOLDERS, false).await?;
+        let bob_token_blind = BaseBlind::random(&mut OsRng);
+        let supply = TokenSupply::Fixed(BOB_MAX_SUPPLY);
+
+        for amount in BOB_MINTS {
+            info!("[Bob] Building BOB token mint tx for {amount}");
+            let (token_mint_tx, token_mint_params, token_auth_mint_params, fee_params) = th
+                .token_mint_with_supply(
+                    amount,
+                    &Holder::Bob,
+                    &Holder::Bob,
+                    bob_token_blind,
+                    supply,
+                    None,
+                    None,
+                    current_block_height,
+                )
+                .await?;
+            assert_eq!(token_mint_params.value, Some(amount));
+
+            for holder in &HOLDERS {
+                info!("[{holder:?}] Executing BOB token mint tx");
+                th.execute_token_mint_tx(
+                    holder,
+                    token_mint_tx.clone(),
+                    &token_mint_params,
+                    &token_auth_mint_params,
+                    &fee_params,
+                    current_block_height,
+                    true,
+                )
+                .await?;
+            }
+
+            th.assert_trees(&HOLDERS);
+        }
+
+        info!("[Malicious] Checking BOB token mint over max supply");
+        let (token_mint_tx, token_mint_params, token_auth_mint_params, fee_params) = th
+            .token_mint_with_supply(
+                1,
+                &Holder::Bob,
+                &Holder::Bob,
+                bob_token_blind,
+                supply,
+                None,
+                None,
+                current_block_height,
+            )
+            .await?;
+        assert!(th
+            .execute_token_mint_tx(
+                &Holder::Bob,
+                token_mint_tx,
+                &token_mint_params,
+                &token_auth_mint_params,
+                &fee_params,
+                current_block_height,
+                false,
+            )
+            .await
+            .is_err());
+
+        info!("[Malicious] Checking BOB token mint with a different supply policy");
+        let (token_mint_tx, token_mint_params, token_auth_mint_params, fee_params) = th
+            .token_mint(
+                1,
+                &Holder::Bob,
+                &Holder::Bob,
+                bob_token_blind,
+                None,
+                None,
+                current_block_height,
+            )
+            .await?;
+        assert!(th
+            .execute_token_mint_tx(
+                &Holder::Bob,
+                token_mint_tx,
+                &token_mint_params,
+                &token_auth_mint_params,
+                &fee_params,
+                current_block_height,
+                false,
+            )
+            .await
+            .is_err());
+
+        // Thanks for reading
+        Ok(())
+    })
+}
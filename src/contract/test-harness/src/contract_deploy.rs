@@ -26,7 +26,7 @@ use darkfi_money_contract::{
     model::MoneyFeeParamsV1,
 };
 use darkfi_sdk::{
-    crypto::{contract_id::DEPLOYOOOR_CONTRACT_ID, MerkleNode},
+    crypto::{contract_id::DEPLOYOOOR_CONTRACT_ID, note::EncryptableNote, MerkleNode},
     deploy::DeployParamsV1,
     ContractCall,
 };
@@ -49,7 +49,11 @@ impl TestHarness {
         let deploy_keypair = wallet.contract_deploy_authority;
 
         // Build the contract call
-        let builder = DeployCallBuilder { deploy_keypair, wasm_bincode, deploy_ix: vec![] };
+        let builder = DeployCallBuilder {
+            deploy_public: deploy_keypair.public,
+            wasm_bincode,
+            deploy_ix: vec![],
+        };
         let debris = builder.build()?;
 
         // Encode the call
@@ -129,7 +133,7 @@ impl TestHarness {
 
             wallet.money_merkle_tree.append(MerkleNode::from(fee_params.output.coin.inner()));
 
-            let Ok(note) = fee_params.output.note.decrypt::<MoneyNote>(&wallet.keypair.secret)
+            let Ok(note) = MoneyNote::decrypt_note(&fee_params.output.note, &wallet.keypair.secret)
             else {
                 return Ok(vec![])
             };
@@ -0,0 +1,172 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use darkfi::{
+    tx::{ContractCallLeaf, Transaction, TransactionBuilder},
+    Result,
+};
+use darkfi_dao_contract::{
+    client::DaoClaimStreamCall, model::DaoStream, DaoFunction,
+    DAO_CONTRACT_ZKAS_DAO_CLAIM_STREAM_NS,
+};
+use darkfi_money_contract::{
+    client::{transfer_v1 as xfer, OwnCoin},
+    model::{CoinAttributes, MoneyFeeParamsV1, MoneyTransferParamsV1},
+    MoneyFunction, MONEY_CONTRACT_ZKAS_BURN_NS_V1, MONEY_CONTRACT_ZKAS_MINT_NS_V1,
+};
+use darkfi_sdk::{
+    crypto::{
+        contract_id::{DAO_CONTRACT_ID, MONEY_CONTRACT_ID},
+        Blind, FuncId, SecretKey,
+    },
+    dark_tree::DarkTree,
+    pasta::pallas,
+    ContractCall,
+};
+use darkfi_serial::AsyncEncodable;
+use rand::rngs::OsRng;
+
+use super::{Holder, TestHarness};
+
+impl TestHarness {
+    /// Create a `Dao::ClaimStream` transaction, where the stream recipient
+    /// spends `stream_coin` to bring the total claimed amount to `claimed`.
+    /// The claim proof is made for `claim_height`, which normally is the
+    /// `block_height` the transaction gets executed in.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn dao_claim_stream(
+        &mut self,
+        holder: &Holder,
+        stream: &DaoStream,
+        stream_coin: &OwnCoin,
+        claimed: u64,
+        claim_height: u32,
+        block_height: u32,
+    ) -> Result<(Transaction, MoneyTransferParamsV1, Option<MoneyFeeParamsV1>)> {
+        let wallet = self.holders.get(holder).unwrap();
+
+        let (mint_pk, mint_zkbin) = self.proving_keys.get(MONEY_CONTRACT_ZKAS_MINT_NS_V1).unwrap();
+        let (burn_pk, burn_zkbin) = self.proving_keys.get(MONEY_CONTRACT_ZKAS_BURN_NS_V1).unwrap();
+        let (claim_pk, claim_zkbin) =
+            self.proving_keys.get(DAO_CONTRACT_ZKAS_DAO_CLAIM_STREAM_NS).unwrap();
+
+        // The stream coin holds whatever wasn't claimed so far
+        let already_claimed = stream.total - stream_coin.note.value;
+        let input_user_data_blind = Blind::random(&mut OsRng);
+        let input = xfer::TransferCallInput {
+            coin: stream_coin.clone(),
+            merkle_path: wallet.money_merkle_tree.witness(stream_coin.leaf_position, 0).unwrap(),
+            user_data_blind: input_user_data_blind,
+        };
+
+        // The claimed funds go to the recipient, and the change back into
+        // the stream, as the last output.
+        let payout_attrs = CoinAttributes {
+            public_key: stream.recipient,
+            value: claimed.saturating_sub(already_claimed),
+            token_id: stream.token_id,
+            spend_hook: FuncId::none(),
+            user_data: pallas::Base::ZERO,
+            blind: Blind::random(&mut OsRng),
+        };
+        let change_coin_attrs =
+            stream.coin_attributes(stream.total - claimed, Blind::random(&mut OsRng));
+
+        let xfer_builder = xfer::TransferCallBuilder {
+            clear_inputs: vec![],
+            inputs: vec![input],
+            outputs: vec![payout_attrs, change_coin_attrs.clone()],
+            sender: None,
+            mint_zkbin: mint_zkbin.clone(),
+            mint_pk: mint_pk.clone(),
+            burn_zkbin: burn_zkbin.clone(),
+            burn_pk: burn_pk.clone(),
+        };
+        let (xfer_params, xfer_secrets) = xfer_builder.build()?;
+        let mut data = vec![MoneyFunction::TransferV1 as u8];
+        xfer_params.encode_async(&mut data).await?;
+        let xfer_call = ContractCall { contract_id: *MONEY_CONTRACT_ID, data };
+        let xfer_data = xfer_call.data.clone();
+
+        let claim_builder = DaoClaimStreamCall {
+            stream: stream.clone(),
+            claimed,
+            input_user_data_blind,
+            change_coin_attrs,
+            current_height: claim_height,
+        };
+        let (claim_params, claim_proofs) = claim_builder.make(claim_zkbin, claim_pk)?;
+        let mut data = vec![DaoFunction::ClaimStream as u8];
+        claim_params.encode_async(&mut data).await?;
+        let claim_call = ContractCall { contract_id: *DAO_CONTRACT_ID, data };
+
+        // We need to construct this tree, where claim is the parent:
+        //
+        //   claim ->
+        //       xfer
+        //
+        let mut tx_builder = TransactionBuilder::new(
+            ContractCallLeaf { call: claim_call, proofs: claim_proofs },
+            vec![DarkTree::new(
+                ContractCallLeaf { call: xfer_call, proofs: xfer_secrets.proofs },
+                vec![],
+                None,
+                None,
+            )],
+        )?;
+
+        // If fees are enabled, make an offering
+        let mut fee_params = None;
+        let mut fee_signature_secrets = None;
+        if self.verify_fees {
+            let mut tx = tx_builder.build()?;
+            sign_claim_tx(&mut tx, &xfer_data, &xfer_secrets.signature_secrets)?;
+
+            let (fee_call, fee_proofs, fee_secrets, _spent_fee_coins, fee_call_params) =
+                self.append_fee_call(holder, tx, block_height, &[stream_coin.clone()]).await?;
+
+            // Append the fee call to the transaction
+            tx_builder.append(ContractCallLeaf { call: fee_call, proofs: fee_proofs }, vec![])?;
+            fee_signature_secrets = Some(fee_secrets);
+            fee_params = Some(fee_call_params);
+        }
+
+        // Now build the actual transaction and sign it with necessary keys.
+        let mut tx = tx_builder.build()?;
+        sign_claim_tx(&mut tx, &xfer_data, &xfer_secrets.signature_secrets)?;
+
+        if let Some(fee_signature_secrets) = fee_signature_secrets {
+            // The fee call is the last one, and got an empty signature set
+            let sigs = tx.create_sigs(&fee_signature_secrets)?;
+            *tx.signatures.last_mut().unwrap() = sigs;
+        }
+
+        Ok((tx, xfer_params, fee_params))
+    }
+}
+
+/// Sign the `Money::Transfer` call of a claim transaction. The recipient
+/// signs its input, while `Dao::ClaimStream` carries no signatures.
+fn sign_claim_tx(tx: &mut Transaction, xfer_data: &[u8], xfer_secrets: &[SecretKey]) -> Result<()> {
+    let mut signatures = vec![];
+    for call in &tx.calls {
+        let secrets = if call.data.data == xfer_data { xfer_secrets } else { &[] };
+        signatures.push(tx.create_sigs(secrets)?);
+    }
+    tx.signatures = signatures;
+    Ok(())
+}
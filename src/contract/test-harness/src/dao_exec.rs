@@ -22,7 +22,7 @@ use darkfi::{
 };
 use darkfi_dao_contract::{
     blockwindow,
-    client::{DaoAuthMoneyTransferCall, DaoExecCall},
+    client::{DaoAuthCreateStreamCall, DaoAuthMoneyTransferCall, DaoExecCall},
     model::{Dao, DaoProposal},
     DaoFunction, DAO_CONTRACT_ZKAS_DAO_AUTH_MONEY_TRANSFER_ENC_COIN_NS,
     DAO_CONTRACT_ZKAS_DAO_AUTH_MONEY_TRANSFER_NS, DAO_CONTRACT_ZKAS_DAO_EARLY_EXEC_NS,
@@ -36,6 +36,7 @@ use darkfi_money_contract::{
 use darkfi_sdk::{
     crypto::{
         contract_id::{DAO_CONTRACT_ID, MONEY_CONTRACT_ID},
+        note::EncryptableNote,
         pedersen_commitment_u64, Blind, FuncRef, MerkleNode, ScalarBlind, SecretKey,
     },
     dark_tree::DarkTree,
@@ -47,6 +48,44 @@ use rand::rngs::OsRng;
 
 use super::{Holder, TestHarness};
 
+/// The calls making up a transfer `Dao::Exec` transaction, before they
+/// get assembled into a call tree. Normally `exec` is the parent of
+/// `auth_xfer` and `xfer`, but tests can arrange them differently to
+/// check the contracts reject malformed trees.
+pub struct DaoExecTransferCalls {
+    /// `Dao::Exec` call
+    pub exec: ContractCallLeaf,
+    /// `Dao::AuthMoneyTransfer` call
+    pub auth_xfer: ContractCallLeaf,
+    /// `Money::Transfer` call spending the treasury coins
+    pub xfer: ContractCallLeaf,
+    /// `Money::Transfer` call parameters
+    pub xfer_params: MoneyTransferParamsV1,
+    /// Signature secret of the `Dao::Exec` call
+    pub exec_signature_secret: SecretKey,
+    /// Signature secrets of the `Money::Transfer` call
+    pub xfer_signature_secrets: Vec<SecretKey>,
+}
+
+impl DaoExecTransferCalls {
+    /// Sign the given transaction, built using these calls in any order.
+    pub fn sign(&self, tx: &mut Transaction) -> Result<()> {
+        let mut signatures = vec![];
+        for call in &tx.calls {
+            let secrets = if call.data.data == self.exec.call.data {
+                vec![self.exec_signature_secret]
+            } else if call.data.data == self.xfer.call.data {
+                self.xfer_signature_secrets.clone()
+            } else {
+                vec![]
+            };
+            signatures.push(tx.create_sigs(&secrets)?);
+        }
+        tx.signatures = signatures;
+        Ok(())
+    }
+}
+
 impl TestHarness {
     /// Create a transfer `Dao::Exec` transaction.
     #[allow(clippy::too_many_arguments)]
@@ -64,6 +103,134 @@ impl TestHarness {
         all_vote_blind: ScalarBlind,
         block_height: u32,
     ) -> Result<(Transaction, MoneyTransferParamsV1, Option<MoneyFeeParamsV1>)> {
+        let calls = self
+            .dao_exec_transfer_calls(
+                dao,
+                dao_exec_secret_key,
+                dao_early_exec_secret_key,
+                proposal,
+                proposal_coinattrs,
+                yes_vote_value,
+                all_vote_value,
+                yes_vote_blind,
+                all_vote_blind,
+                block_height,
+            )
+            .await?;
+
+        let (tx, fee_params) =
+            self.dao_exec_transfer_tx(holder, &calls, vec![], block_height).await?;
+        Ok((tx, calls.xfer_params, fee_params))
+    }
+
+    /// Create a `Dao::Exec` transaction for a proposal made using
+    /// `dao_propose_stream()`, funding and creating the stream.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn dao_exec_stream(
+        &mut self,
+        holder: &Holder,
+        dao: &Dao,
+        dao_exec_secret_key: &SecretKey,
+        proposal: &DaoProposal,
+        stream: &DaoAuthCreateStreamCall,
+        yes_vote_value: u64,
+        all_vote_value: u64,
+        yes_vote_blind: ScalarBlind,
+        all_vote_blind: ScalarBlind,
+        block_height: u32,
+    ) -> Result<(Transaction, MoneyTransferParamsV1, Option<MoneyFeeParamsV1>)> {
+        let calls = self
+            .dao_exec_transfer_calls(
+                dao,
+                dao_exec_secret_key,
+                &None,
+                proposal,
+                vec![stream.stream_coin_attrs()],
+                yes_vote_value,
+                all_vote_value,
+                yes_vote_blind,
+                all_vote_blind,
+                block_height,
+            )
+            .await?;
+
+        let auth_stream = ContractCallLeaf { call: stream.make(), proofs: vec![] };
+        let (tx, fee_params) =
+            self.dao_exec_transfer_tx(holder, &calls, vec![auth_stream], block_height).await?;
+        Ok((tx, calls.xfer_params, fee_params))
+    }
+
+    /// Assemble and sign a transfer `Dao::Exec` transaction, with
+    /// `extra_children` following the transfer calls.
+    async fn dao_exec_transfer_tx(
+        &mut self,
+        holder: &Holder,
+        calls: &DaoExecTransferCalls,
+        extra_children: Vec<ContractCallLeaf>,
+        block_height: u32,
+    ) -> Result<(Transaction, Option<MoneyFeeParamsV1>)> {
+        // We need to construct this tree, where exec is the parent:
+        //
+        //   exec ->
+        //       auth_xfer
+        //       xfer
+        //       extra_children...
+        //
+
+        let mut children = vec![
+            DarkTree::new(calls.auth_xfer.clone(), vec![], None, None),
+            DarkTree::new(calls.xfer.clone(), vec![], None, None),
+        ];
+        for child in extra_children {
+            children.push(DarkTree::new(child, vec![], None, None));
+        }
+        let mut tx_builder = TransactionBuilder::new(calls.exec.clone(), children)?;
+
+        // If fees are enabled, make an offering
+        let mut fee_params = None;
+        let mut fee_signature_secrets = None;
+        if self.verify_fees {
+            let mut tx = tx_builder.build()?;
+            calls.sign(&mut tx)?;
+
+            let (fee_call, fee_proofs, fee_secrets, _spent_fee_coins, fee_call_params) =
+                self.append_fee_call(holder, tx, block_height, &[]).await?;
+
+            // Append the fee call to the transaction
+            tx_builder.append(ContractCallLeaf { call: fee_call, proofs: fee_proofs }, vec![])?;
+            fee_signature_secrets = Some(fee_secrets);
+            fee_params = Some(fee_call_params);
+        }
+
+        // Now build the actual transaction and sign it with necessary keys.
+        let mut tx = tx_builder.build()?;
+        calls.sign(&mut tx)?;
+
+        if let Some(fee_signature_secrets) = fee_signature_secrets {
+            // The fee call is the last one, and got an empty signature set
+            let sigs = tx.create_sigs(&fee_signature_secrets)?;
+            *tx.signatures.last_mut().unwrap() = sigs;
+        }
+
+        Ok((tx, fee_params))
+    }
+
+    /// Create the calls of a transfer `Dao::Exec` transaction, without
+    /// assembling them into a transaction.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn dao_exec_transfer_calls(
+        &self,
+        dao: &Dao,
+        dao_exec_secret_key: &SecretKey,
+        dao_early_exec_secret_key: &Option<SecretKey>,
+        proposal: &DaoProposal,
+        proposal_coinattrs: Vec<CoinAttributes>,
+        yes_vote_value: u64,
+        all_vote_value: u64,
+        yes_vote_blind: ScalarBlind,
+        all_vote_blind: ScalarBlind,
+        block_height: u32,
+    ) -> Result<DaoExecTransferCalls> {
         let dao_wallet = self.holders.get(&Holder::Dao).unwrap();
 
         let (mint_pk, mint_zkbin) = self.proving_keys.get(MONEY_CONTRACT_ZKAS_MINT_NS_V1).unwrap();
@@ -128,6 +295,7 @@ impl TestHarness {
             clear_inputs: vec![],
             inputs,
             outputs,
+            sender: None,
             mint_zkbin: mint_zkbin.clone(),
             mint_pk: mint_pk.clone(),
             burn_zkbin: burn_zkbin.clone(),
@@ -193,63 +361,14 @@ impl TestHarness {
         auth_xfer_params.encode_async(&mut data).await?;
         let auth_xfer_call = ContractCall { contract_id: *DAO_CONTRACT_ID, data };
 
-        // We need to construct this tree, where exec is the parent:
-        //
-        //   exec ->
-        //       auth_xfer
-        //       xfer
-        //
-
-        let mut tx_builder = TransactionBuilder::new(
-            ContractCallLeaf { call: exec_call, proofs: exec_proofs },
-            vec![
-                DarkTree::new(
-                    ContractCallLeaf { call: auth_xfer_call, proofs: auth_xfer_proofs },
-                    vec![],
-                    None,
-                    None,
-                ),
-                DarkTree::new(
-                    ContractCallLeaf { call: xfer_call, proofs: xfer_secrets.proofs },
-                    vec![],
-                    None,
-                    None,
-                ),
-            ],
-        )?;
-
-        // If fees are enabled, make an offering
-        let mut fee_params = None;
-        let mut fee_signature_secrets = None;
-        if self.verify_fees {
-            let mut tx = tx_builder.build()?;
-            let auth_xfer_sigs = vec![];
-            let xfer_sigs = tx.create_sigs(&xfer_secrets.signature_secrets)?;
-            let exec_sigs = tx.create_sigs(&[exec_signature_secret])?;
-            tx.signatures = vec![auth_xfer_sigs, xfer_sigs, exec_sigs];
-
-            let (fee_call, fee_proofs, fee_secrets, _spent_fee_coins, fee_call_params) =
-                self.append_fee_call(holder, tx, block_height, &[]).await?;
-
-            // Append the fee call to the transaction
-            tx_builder.append(ContractCallLeaf { call: fee_call, proofs: fee_proofs }, vec![])?;
-            fee_signature_secrets = Some(fee_secrets);
-            fee_params = Some(fee_call_params);
-        }
-
-        // Now build the actual transaction and sign it with necessary keys.
-        let mut tx = tx_builder.build()?;
-        let auth_xfer_sigs = vec![];
-        let xfer_sigs = tx.create_sigs(&xfer_secrets.signature_secrets)?;
-        let exec_sigs = tx.create_sigs(&[exec_signature_secret])?;
-        tx.signatures = vec![auth_xfer_sigs, xfer_sigs, exec_sigs];
-
-        if let Some(fee_signature_secrets) = fee_signature_secrets {
-            let sigs = tx.create_sigs(&fee_signature_secrets)?;
-            tx.signatures.push(sigs);
-        }
-
-        Ok((tx, xfer_params, fee_params))
+        Ok(DaoExecTransferCalls {
+            exec: ContractCallLeaf { call: exec_call, proofs: exec_proofs },
+            auth_xfer: ContractCallLeaf { call: auth_xfer_call, proofs: auth_xfer_proofs },
+            xfer: ContractCallLeaf { call: xfer_call, proofs: xfer_secrets.proofs },
+            xfer_params,
+            exec_signature_secret,
+            xfer_signature_secrets: xfer_secrets.signature_secrets,
+        })
     }
 
     /// Create a generic `Dao::Exec` transaction.
@@ -266,6 +385,39 @@ impl TestHarness {
         yes_vote_blind: ScalarBlind,
         all_vote_blind: ScalarBlind,
         block_height: u32,
+    ) -> Result<(Transaction, Option<MoneyFeeParamsV1>)> {
+        self.dao_exec_calls(
+            holder,
+            dao,
+            dao_exec_secret_key,
+            dao_early_exec_secret_key,
+            proposal,
+            vec![],
+            yes_vote_value,
+            all_vote_value,
+            yes_vote_blind,
+            all_vote_blind,
+            block_height,
+        )
+        .await
+    }
+
+    /// Create a `Dao::Exec` transaction making the given child calls,
+    /// along with the secrets signing each of them.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn dao_exec_calls(
+        &mut self,
+        holder: &Holder,
+        dao: &Dao,
+        dao_exec_secret_key: &SecretKey,
+        dao_early_exec_secret_key: &Option<SecretKey>,
+        proposal: &DaoProposal,
+        children: Vec<(ContractCallLeaf, Vec<SecretKey>)>,
+        yes_vote_value: u64,
+        all_vote_value: u64,
+        yes_vote_blind: ScalarBlind,
+        all_vote_blind: ScalarBlind,
+        block_height: u32,
     ) -> Result<(Transaction, Option<MoneyFeeParamsV1>)> {
         let wallet = self.holders.get_mut(holder).unwrap();
 
@@ -300,36 +452,45 @@ impl TestHarness {
         exec_params.encode_async(&mut data).await?;
         let exec_call = ContractCall { contract_id: *DAO_CONTRACT_ID, data };
 
-        // Create the TransactionBuilder containing the `DAO::Exec` call
+        // Create the TransactionBuilder containing the `DAO::Exec` call,
+        // and its children in the order of the proposal auth calls.
+        let mut signature_secrets = vec![];
+        let mut child_trees = vec![];
+        for (child, secrets) in children {
+            signature_secrets.push(secrets);
+            child_trees.push(DarkTree::new(child, vec![], None, None));
+        }
+        signature_secrets.push(vec![exec_signature_secret]);
         let mut tx_builder = TransactionBuilder::new(
             ContractCallLeaf { call: exec_call, proofs: exec_proofs },
-            vec![],
+            child_trees,
         )?;
 
         // If fees are enabled, make an offering
         let mut fee_params = None;
-        let mut fee_signature_secrets = None;
         if self.verify_fees {
             let mut tx = tx_builder.build()?;
-            let exec_sigs = tx.create_sigs(&[exec_signature_secret])?;
-            tx.signatures = vec![exec_sigs];
+            tx.signatures = vec![];
+            for secrets in &signature_secrets {
+                let sigs = tx.create_sigs(secrets)?;
+                tx.signatures.push(sigs);
+            }
 
             let (fee_call, fee_proofs, fee_secrets, _spent_fee_coins, fee_call_params) =
                 self.append_fee_call(holder, tx, block_height, &[]).await?;
 
             // Append the fee call to the transaction
             tx_builder.append(ContractCallLeaf { call: fee_call, proofs: fee_proofs }, vec![])?;
-            fee_signature_secrets = Some(fee_secrets);
+            signature_secrets.push(fee_secrets);
             fee_params = Some(fee_call_params);
         }
 
         // Now build the actual transaction and sign it with necessary keys.
+        // Children come first in the transaction calls, then the exec call.
         let mut tx = tx_builder.build()?;
-        let exec_sigs = tx.create_sigs(&[exec_signature_secret])?;
-        tx.signatures = vec![exec_sigs];
-
-        if let Some(fee_signature_secrets) = fee_signature_secrets {
-            let sigs = tx.create_sigs(&fee_signature_secrets)?;
+        tx.signatures = vec![];
+        for secrets in &signature_secrets {
+            let sigs = tx.create_sigs(secrets)?;
             tx.signatures.push(sigs);
         }
 
@@ -387,7 +548,7 @@ impl TestHarness {
         for output in outputs {
             wallet.money_merkle_tree.append(MerkleNode::from(output.coin.inner()));
 
-            let Ok(note) = output.note.decrypt::<MoneyNote>(&wallet.keypair.secret) else {
+            let Ok(note) = MoneyNote::decrypt_note(&output.note, &wallet.keypair.secret) else {
                 continue
             };
 
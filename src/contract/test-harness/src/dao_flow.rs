@@ -0,0 +1,530 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::Result;
+use darkfi_dao_contract::{
+    blockwindow,
+    client::{DaoAuthCreateStreamCall, DaoMember, DaoMemberSet},
+    model::{Dao, DaoAuthCall, DaoBlindAggregateVote, DaoProposal},
+    DaoFunction,
+};
+use darkfi_money_contract::{
+    model::{CoinAttributes, TokenAttributes, DARK_TOKEN_ID},
+    MoneyFunction,
+};
+use darkfi_sdk::{
+    crypto::{
+        contract_id::{DAO_CONTRACT_ID, MONEY_CONTRACT_ID},
+        pedersen_commitment_u64, poseidon_hash,
+        util::{fp_mod_fv, fp_to_u64},
+        BaseBlind, Blind, FuncId, FuncRef, Keypair, ScalarBlind,
+    },
+    pasta::pallas,
+};
+use log::info;
+use rand::rngs::OsRng;
+
+use super::{Holder, TestHarness};
+
+/// Fixture for the Money <-> DAO composition flow.
+///
+/// It creates a DAO whose treasury coins are locked with the `Dao::Exec`
+/// spend hook, and mints the governance token to the DAO members, so tests
+/// only have to care about the proposal, vote and exec steps.
+pub struct DaoFlow {
+    /// Holders executing every transaction of the flow
+    pub holders: Vec<Holder>,
+    /// DAO members and their governance token balance
+    pub members: Vec<(Holder, u64)>,
+    /// Membership set of an allowlist DAO, used to vote instead of
+    /// the governance token
+    pub member_set: Option<DaoMemberSet>,
+    /// The DAO
+    pub dao: Dao,
+    pub notes_keypair: Keypair,
+    pub proposer_keypair: Keypair,
+    pub proposals_keypair: Keypair,
+    pub votes_keypair: Keypair,
+    pub exec_keypair: Keypair,
+    pub early_exec_keypair: Keypair,
+    /// Governance token blind, used to mint more of it
+    pub gov_token_blind: BaseBlind,
+    /// Current block height of the flow
+    pub block_height: u32,
+}
+
+/// A proposal made through [`DaoFlow`]
+pub struct DaoFlowProposal {
+    pub proposal: DaoProposal,
+    /// Blockwindow the proposal was created in
+    pub creation_blockwindow: u64,
+}
+
+/// Aggregated votes of a [`DaoFlowProposal`]
+pub struct DaoFlowTally {
+    pub yes_vote_value: u64,
+    pub all_vote_value: u64,
+    pub yes_vote_blind: ScalarBlind,
+    pub all_vote_blind: ScalarBlind,
+}
+
+impl TestHarness {
+    /// Create a [`DaoFlow`]. The first member is the governance token
+    /// authority, and `Holder::Dao` receives the DAO treasury.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn dao_flow_init(
+        &mut self,
+        holders: &[Holder],
+        members: &[(Holder, u64)],
+        treasury: u64,
+        proposer_limit: u64,
+        quorum: u64,
+        approval_ratio: (u64, u64),
+    ) -> Result<DaoFlow> {
+        self.dao_flow_init_inner(
+            holders,
+            members,
+            None,
+            treasury,
+            proposer_limit,
+            quorum,
+            approval_ratio,
+        )
+        .await
+    }
+
+    /// Create an allowlist [`DaoFlow`], whose votes are weighted by the
+    /// given `allowlist` membership. Governance tokens are still minted
+    /// to the members, since they are needed to make proposals.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn dao_flow_init_allowlist(
+        &mut self,
+        holders: &[Holder],
+        members: &[(Holder, u64)],
+        allowlist: &[(Holder, u64)],
+        treasury: u64,
+        proposer_limit: u64,
+        quorum: u64,
+        approval_ratio: (u64, u64),
+    ) -> Result<DaoFlow> {
+        let members_list: Vec<DaoMember> = allowlist
+            .iter()
+            .map(|(holder, weight)| DaoMember {
+                public_key: self.holders.get(holder).unwrap().keypair.public,
+                weight: *weight,
+                blind: Blind::random(&mut OsRng),
+            })
+            .collect();
+        let member_set = DaoMemberSet::from_members(&members_list);
+
+        self.dao_flow_init_inner(
+            holders,
+            members,
+            Some(member_set),
+            treasury,
+            proposer_limit,
+            quorum,
+            approval_ratio,
+        )
+        .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn dao_flow_init_inner(
+        &mut self,
+        holders: &[Holder],
+        members: &[(Holder, u64)],
+        member_set: Option<DaoMemberSet>,
+        treasury: u64,
+        proposer_limit: u64,
+        quorum: u64,
+        approval_ratio: (u64, u64),
+    ) -> Result<DaoFlow> {
+        assert!(!members.is_empty());
+        let authority = members[0].0;
+
+        let mint_authority = self.holders.get(&authority).unwrap().token_mint_authority;
+        let gov_token_blind = BaseBlind::random(&mut OsRng);
+        let auth_func_id = FuncRef {
+            contract_id: *MONEY_CONTRACT_ID,
+            func_code: MoneyFunction::AuthTokenMintV1 as u8,
+        }
+        .to_func_id();
+        let gov_token_id = TokenAttributes {
+            auth_parent: auth_func_id,
+            user_data: poseidon_hash([mint_authority.public.x(), mint_authority.public.y()]),
+            blind: gov_token_blind,
+        }
+        .to_token_id();
+
+        let notes_keypair = self.holders.get(&Holder::Dao).unwrap().keypair;
+        let proposer_keypair = Keypair::random(&mut OsRng);
+        let proposals_keypair = Keypair::random(&mut OsRng);
+        let votes_keypair = Keypair::random(&mut OsRng);
+        let exec_keypair = Keypair::random(&mut OsRng);
+        let early_exec_keypair = Keypair::random(&mut OsRng);
+        let dao = Dao {
+            proposer_limit,
+            quorum,
+            early_exec_quorum: quorum,
+            approval_ratio_base: approval_ratio.0,
+            approval_ratio_quot: approval_ratio.1,
+            gov_token_id,
+            notes_public_key: notes_keypair.public,
+            proposer_public_key: proposer_keypair.public,
+            proposals_public_key: proposals_keypair.public,
+            votes_public_key: votes_keypair.public,
+            exec_public_key: exec_keypair.public,
+            early_exec_public_key: early_exec_keypair.public,
+            membership_root: member_set.as_ref().map(|set| set.root()),
+            bulla_blind: Blind::random(&mut OsRng),
+        };
+
+        let mut flow = DaoFlow {
+            holders: holders.to_vec(),
+            members: members.to_vec(),
+            member_set,
+            dao,
+            notes_keypair,
+            proposer_keypair,
+            proposals_keypair,
+            votes_keypair,
+            exec_keypair,
+            early_exec_keypair,
+            gov_token_blind,
+            block_height: 0,
+        };
+
+        // Lock the treasury under the DAO::Exec spend hook
+        info!("[DaoFlow] Airdropping treasury to the DAO");
+        let spend_hook =
+            FuncRef { contract_id: *DAO_CONTRACT_ID, func_code: DaoFunction::Exec as u8 }
+                .to_func_id();
+        let (tx, params) = self
+            .genesis_mint(
+                &Holder::Dao,
+                &[treasury],
+                Some(spend_hook),
+                Some(flow.dao.to_bulla().inner()),
+            )
+            .await?;
+        for holder in &flow.holders {
+            self.execute_genesis_mint_tx(holder, tx.clone(), &params, flow.block_height, true)
+                .await?;
+        }
+        self.assert_trees(&flow.holders);
+        flow.block_height += 1;
+
+        info!("[DaoFlow] Minting the DAO");
+        let (tx, params, fee_params) = self
+            .dao_mint(
+                &authority,
+                &flow.dao,
+                &flow.notes_keypair.secret,
+                &flow.proposer_keypair.secret,
+                &flow.proposals_keypair.secret,
+                &flow.votes_keypair.secret,
+                &flow.exec_keypair.secret,
+                &flow.early_exec_keypair.secret,
+                flow.block_height,
+            )
+            .await?;
+        for holder in &flow.holders {
+            self.execute_dao_mint_tx(
+                holder,
+                tx.clone(),
+                &params,
+                &fee_params,
+                flow.block_height,
+                true,
+            )
+            .await?;
+        }
+        self.assert_trees(&flow.holders);
+        flow.block_height += 1;
+
+        for (member, value) in members {
+            info!("[DaoFlow] Minting governance tokens for {member:?}");
+            self.dao_flow_mint_gov_tokens(&mut flow, member, *value).await?;
+        }
+        flow.block_height += 1;
+
+        Ok(flow)
+    }
+
+    /// Mint more governance tokens to the given holder
+    pub async fn dao_flow_mint_gov_tokens(
+        &mut self,
+        flow: &mut DaoFlow,
+        recipient: &Holder,
+        value: u64,
+    ) -> Result<()> {
+        let authority = flow.members[0].0;
+        let (tx, params, auth_params, fee_params) = self
+            .token_mint(
+                value,
+                &authority,
+                recipient,
+                flow.gov_token_blind,
+                None,
+                None,
+                flow.block_height,
+            )
+            .await?;
+        for holder in &flow.holders {
+            self.execute_token_mint_tx(
+                holder,
+                tx.clone(),
+                &params,
+                &auth_params,
+                &fee_params,
+                flow.block_height,
+                true,
+            )
+            .await?;
+        }
+        self.assert_trees(&flow.holders);
+        Ok(())
+    }
+
+    /// Propose a treasury transfer of `value` DRK to the recipient.
+    /// Returns the proposal along with its coin attributes.
+    pub async fn dao_flow_propose_transfer(
+        &mut self,
+        flow: &mut DaoFlow,
+        recipient: &Holder,
+        value: u64,
+        duration_blockwindows: u64,
+    ) -> Result<(DaoFlowProposal, Vec<CoinAttributes>)> {
+        let proposal_coinattrs = vec![CoinAttributes {
+            public_key: self.holders.get(recipient).unwrap().keypair.public,
+            value,
+            token_id: *DARK_TOKEN_ID,
+            spend_hook: FuncId::none(),
+            user_data: pallas::Base::ZERO,
+            blind: Blind::random(&mut OsRng),
+        }];
+
+        let creation_blockwindow = self.dao_flow_blockwindow(flow).await;
+        let proposer = flow.members[0].0;
+        let (tx, params, fee_params, proposal) = self
+            .dao_propose_transfer(
+                &proposer,
+                &proposal_coinattrs,
+                pallas::Base::ZERO,
+                &flow.dao,
+                &flow.proposer_keypair.secret,
+                flow.block_height,
+                duration_blockwindows,
+            )
+            .await?;
+        for holder in &flow.holders {
+            self.execute_dao_propose_tx(
+                holder,
+                tx.clone(),
+                &params,
+                &fee_params,
+                flow.block_height,
+                true,
+            )
+            .await?;
+        }
+        self.assert_trees(&flow.holders);
+        flow.block_height += 1;
+
+        Ok((DaoFlowProposal { proposal, creation_blockwindow }, proposal_coinattrs))
+    }
+
+    /// Propose funding a treasury stream of `total` DRK to the recipient,
+    /// releasing `rate` per block once the proposal could be executed.
+    pub async fn dao_flow_propose_stream(
+        &mut self,
+        flow: &mut DaoFlow,
+        recipient: &Holder,
+        total: u64,
+        rate: u64,
+        duration_blockwindows: u64,
+    ) -> Result<(DaoFlowProposal, DaoAuthCreateStreamCall)> {
+        let stream = DaoAuthCreateStreamCall::new(
+            self.holders.get(recipient).unwrap().keypair.public,
+            *DARK_TOKEN_ID,
+            total,
+            rate,
+            flow.block_height,
+        )?;
+
+        let creation_blockwindow = self.dao_flow_blockwindow(flow).await;
+        let proposer = flow.members[0].0;
+        let (tx, params, fee_params, proposal) = self
+            .dao_propose_stream(
+                &proposer,
+                &stream,
+                &flow.dao,
+                &flow.proposer_keypair.secret,
+                flow.block_height,
+                duration_blockwindows,
+            )
+            .await?;
+        for holder in &flow.holders {
+            self.execute_dao_propose_tx(
+                holder,
+                tx.clone(),
+                &params,
+                &fee_params,
+                flow.block_height,
+                true,
+            )
+            .await?;
+        }
+        self.assert_trees(&flow.holders);
+        flow.block_height += 1;
+
+        Ok((DaoFlowProposal { proposal, creation_blockwindow }, stream))
+    }
+
+    /// Propose making the given auth calls, like generic calls to any contract.
+    pub async fn dao_flow_propose_calls(
+        &mut self,
+        flow: &mut DaoFlow,
+        auth_calls: Vec<DaoAuthCall>,
+        duration_blockwindows: u64,
+    ) -> Result<DaoFlowProposal> {
+        let creation_blockwindow = self.dao_flow_blockwindow(flow).await;
+        let proposer = flow.members[0].0;
+        let (tx, params, fee_params, proposal) = self
+            .dao_propose_calls(
+                &proposer,
+                auth_calls,
+                pallas::Base::ZERO,
+                &flow.dao,
+                &flow.proposer_keypair.secret,
+                flow.block_height,
+                duration_blockwindows,
+            )
+            .await?;
+        for holder in &flow.holders {
+            self.execute_dao_propose_tx(
+                holder,
+                tx.clone(),
+                &params,
+                &fee_params,
+                flow.block_height,
+                true,
+            )
+            .await?;
+        }
+        self.assert_trees(&flow.holders);
+        flow.block_height += 1;
+
+        Ok(DaoFlowProposal { proposal, creation_blockwindow })
+    }
+
+    /// Cast the given votes on a proposal, and count them using the
+    /// DAO votes secret key.
+    pub async fn dao_flow_vote(
+        &mut self,
+        flow: &mut DaoFlow,
+        proposal: &DaoFlowProposal,
+        votes: &[(Holder, bool)],
+    ) -> Result<DaoFlowTally> {
+        let mut tally = DaoFlowTally {
+            yes_vote_value: 0,
+            all_vote_value: 0,
+            yes_vote_blind: Blind::ZERO,
+            all_vote_blind: Blind::ZERO,
+        };
+        let mut blind_total_vote = DaoBlindAggregateVote::default();
+
+        for (voter, vote_option) in votes {
+            info!("[DaoFlow] {voter:?} votes {vote_option}");
+            let (tx, params, fee_params) = match flow.member_set {
+                Some(ref member_set) => {
+                    self.dao_vote_member(
+                        voter,
+                        member_set,
+                        *vote_option,
+                        &flow.dao,
+                        &proposal.proposal,
+                        flow.block_height,
+                    )
+                    .await?
+                }
+                None => {
+                    self.dao_vote(
+                        voter,
+                        *vote_option,
+                        &flow.dao,
+                        &proposal.proposal,
+                        flow.block_height,
+                    )
+                    .await?
+                }
+            };
+            for holder in &flow.holders {
+                self.execute_dao_vote_tx(holder, tx.clone(), &fee_params, flow.block_height, true)
+                    .await?;
+            }
+
+            // Note format: [vote_option, yes_vote_blind, all_vote_value, all_vote_blind]
+            let note = params.note.decrypt_unsafe(&flow.votes_keypair.secret).unwrap();
+            let vote_option = fp_to_u64(note[0]).unwrap();
+            let all_vote_value = fp_to_u64(note[2]).unwrap();
+            tally.yes_vote_value += vote_option * all_vote_value;
+            tally.all_vote_value += all_vote_value;
+            tally.yes_vote_blind += Blind(fp_mod_fv(note[1]));
+            tally.all_vote_blind += Blind(fp_mod_fv(note[3]));
+
+            blind_total_vote.aggregate(DaoBlindAggregateVote {
+                yes_vote_commit: params.yes_vote_commit,
+                all_vote_commit: params.inputs.iter().map(|i| i.vote_commit).sum(),
+            });
+        }
+        self.assert_trees(&flow.holders);
+
+        assert_eq!(
+            blind_total_vote.yes_vote_commit,
+            pedersen_commitment_u64(tally.yes_vote_value, tally.yes_vote_blind)
+        );
+        assert_eq!(
+            blind_total_vote.all_vote_commit,
+            pedersen_commitment_u64(tally.all_vote_value, tally.all_vote_blind)
+        );
+
+        Ok(tally)
+    }
+
+    /// Move the flow block height past the proposal voting period
+    pub async fn dao_flow_wait_expiry(
+        &self,
+        flow: &mut DaoFlow,
+        proposal: &DaoFlowProposal,
+        duration_blockwindows: u64,
+    ) {
+        while self.dao_flow_blockwindow(flow).await <=
+            proposal.creation_blockwindow + duration_blockwindows
+        {
+            flow.block_height += 1;
+        }
+    }
+
+    async fn dao_flow_blockwindow(&self, flow: &DaoFlow) -> u64 {
+        let wallet = self.holders.get(&Holder::Dao).unwrap();
+        let block_target = wallet.validator.consensus.module.read().await.target;
+        blockwindow(flow.block_height, block_target)
+    }
+}
@@ -30,7 +30,7 @@ use darkfi_money_contract::{
     model::MoneyFeeParamsV1,
 };
 use darkfi_sdk::{
-    crypto::{contract_id::DAO_CONTRACT_ID, MerkleNode, SecretKey},
+    crypto::{contract_id::DAO_CONTRACT_ID, note::EncryptableNote, MerkleNode, SecretKey},
     ContractCall,
 };
 use darkfi_serial::AsyncEncodable;
@@ -152,7 +152,7 @@ impl TestHarness {
 
             wallet.money_merkle_tree.append(MerkleNode::from(fee_params.output.coin.inner()));
 
-            let Ok(note) = fee_params.output.note.decrypt::<MoneyNote>(&wallet.keypair.secret)
+            let Ok(note) = MoneyNote::decrypt_note(&fee_params.output.note, &wallet.keypair.secret)
             else {
                 return Ok(vec![])
             };
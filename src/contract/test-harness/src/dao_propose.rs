@@ -22,7 +22,7 @@ use darkfi::{
 };
 use darkfi_dao_contract::{
     blockwindow,
-    client::{DaoProposeCall, DaoProposeStakeInput},
+    client::{DaoAuthCreateStreamCall, DaoProposeCall, DaoProposeStakeInput},
     model::{Dao, DaoAuthCall, DaoProposal, DaoProposeParams},
     DaoFunction, DAO_CONTRACT_ZKAS_DAO_PROPOSE_INPUT_NS, DAO_CONTRACT_ZKAS_DAO_PROPOSE_MAIN_NS,
 };
@@ -34,6 +34,7 @@ use darkfi_money_contract::{
 use darkfi_sdk::{
     crypto::{
         contract_id::{DAO_CONTRACT_ID, MONEY_CONTRACT_ID},
+        note::EncryptableNote,
         Blind, MerkleNode, SecretKey,
     },
     pasta::pallas,
@@ -57,6 +58,56 @@ impl TestHarness {
         dao_proposer_secret_key: &SecretKey,
         block_height: u32,
         duration_blockwindows: u64,
+    ) -> Result<(Transaction, DaoProposeParams, Option<MoneyFeeParamsV1>, DaoProposal)> {
+        self.dao_propose_transfer_inner(
+            proposer,
+            proposal_coinattrs,
+            vec![],
+            user_data,
+            dao,
+            dao_proposer_secret_key,
+            block_height,
+            duration_blockwindows,
+        )
+        .await
+    }
+
+    /// Create a `Dao::Propose` transaction funding a treasury stream.
+    pub async fn dao_propose_stream(
+        &mut self,
+        proposer: &Holder,
+        stream: &DaoAuthCreateStreamCall,
+        dao: &Dao,
+        dao_proposer_secret_key: &SecretKey,
+        block_height: u32,
+        duration_blockwindows: u64,
+    ) -> Result<(Transaction, DaoProposeParams, Option<MoneyFeeParamsV1>, DaoProposal)> {
+        self.dao_propose_transfer_inner(
+            proposer,
+            &[stream.stream_coin_attrs()],
+            vec![stream.auth_call()],
+            pallas::Base::ZERO,
+            dao,
+            dao_proposer_secret_key,
+            block_height,
+            duration_blockwindows,
+        )
+        .await
+    }
+
+    /// Create a transfer `Dao::Propose` transaction, with `extra_auth_calls`
+    /// appended after the transfer ones.
+    #[allow(clippy::too_many_arguments)]
+    async fn dao_propose_transfer_inner(
+        &mut self,
+        proposer: &Holder,
+        proposal_coinattrs: &[CoinAttributes],
+        extra_auth_calls: Vec<DaoAuthCall>,
+        user_data: pallas::Base,
+        dao: &Dao,
+        dao_proposer_secret_key: &SecretKey,
+        block_height: u32,
+        duration_blockwindows: u64,
     ) -> Result<(Transaction, DaoProposeParams, Option<MoneyFeeParamsV1>, DaoProposal)> {
         let wallet = self.holders.get(proposer).unwrap();
 
@@ -106,7 +157,7 @@ impl TestHarness {
         proposal_coins.encode_async(&mut proposal_data).await?;
 
         // Create Auth calls
-        let auth_calls = vec![
+        let mut auth_calls = vec![
             DaoAuthCall {
                 contract_id: *DAO_CONTRACT_ID,
                 function_code: DaoFunction::AuthMoneyTransfer as u8,
@@ -118,6 +169,7 @@ impl TestHarness {
                 auth_data: vec![],
             },
         ];
+        auth_calls.extend(extra_auth_calls);
 
         let block_target = wallet.validator.consensus.module.read().await.target;
         let creation_blockwindow = blockwindow(block_height, block_target);
@@ -199,6 +251,31 @@ impl TestHarness {
         dao_proposer_secret_key: &SecretKey,
         block_height: u32,
         duration_blockwindows: u64,
+    ) -> Result<(Transaction, DaoProposeParams, Option<MoneyFeeParamsV1>, DaoProposal)> {
+        self.dao_propose_calls(
+            proposer,
+            vec![],
+            user_data,
+            dao,
+            dao_proposer_secret_key,
+            block_height,
+            duration_blockwindows,
+        )
+        .await
+    }
+
+    /// Create a `Dao::Propose` transaction for a proposal making the
+    /// given auth calls when executed.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn dao_propose_calls(
+        &mut self,
+        proposer: &Holder,
+        auth_calls: Vec<DaoAuthCall>,
+        user_data: pallas::Base,
+        dao: &Dao,
+        dao_proposer_secret_key: &SecretKey,
+        block_height: u32,
+        duration_blockwindows: u64,
     ) -> Result<(Transaction, DaoProposeParams, Option<MoneyFeeParamsV1>, DaoProposal)> {
         let wallet = self.holders.get(proposer).unwrap();
 
@@ -242,7 +319,7 @@ impl TestHarness {
         let block_target = wallet.validator.consensus.module.read().await.target;
         let creation_blockwindow = blockwindow(block_height, block_target);
         let proposal = DaoProposal {
-            auth_calls: vec![],
+            auth_calls,
             creation_blockwindow,
             duration_blockwindows,
             user_data,
@@ -358,7 +435,7 @@ impl TestHarness {
 
             wallet.money_merkle_tree.append(MerkleNode::from(fee_params.output.coin.inner()));
 
-            let Ok(note) = fee_params.output.note.decrypt::<MoneyNote>(&wallet.keypair.secret)
+            let Ok(note) = MoneyNote::decrypt_note(&fee_params.output.note, &wallet.keypair.secret)
             else {
                 return Ok(vec![])
             };
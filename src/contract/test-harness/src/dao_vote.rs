@@ -18,20 +18,22 @@
 
 use darkfi::{
     tx::{ContractCallLeaf, Transaction, TransactionBuilder},
-    Result,
+    zk::Proof,
+    ClientFailed, Result,
 };
 use darkfi_dao_contract::{
     blockwindow,
-    client::{DaoVoteCall, DaoVoteInput},
+    client::{DaoMemberSet, DaoVoteCall, DaoVoteInput, DaoVoteMemberCall, DaoVoteMemberInput},
     model::{Dao, DaoProposal, DaoVoteParams},
-    DaoFunction, DAO_CONTRACT_ZKAS_DAO_VOTE_INPUT_NS, DAO_CONTRACT_ZKAS_DAO_VOTE_MAIN_NS,
+    DaoFunction, DAO_CONTRACT_ZKAS_DAO_VOTE_INPUT_MEMBER_NS, DAO_CONTRACT_ZKAS_DAO_VOTE_INPUT_NS,
+    DAO_CONTRACT_ZKAS_DAO_VOTE_MAIN_NS,
 };
 use darkfi_money_contract::{
     client::{MoneyNote, OwnCoin},
     model::MoneyFeeParamsV1,
 };
 use darkfi_sdk::{
-    crypto::{contract_id::DAO_CONTRACT_ID, MerkleNode, SecretKey},
+    crypto::{contract_id::DAO_CONTRACT_ID, note::EncryptableNote, MerkleNode, SecretKey},
     ContractCall,
 };
 use darkfi_serial::AsyncEncodable;
@@ -96,6 +98,72 @@ impl TestHarness {
             dao_vote_main_pk,
         )?;
 
+        self.build_dao_vote_tx(voter, params, proofs, signature_secret, block_height).await
+    }
+
+    /// Create a `Dao::Vote` transaction for an allowlist DAO, proving
+    /// the voter keypair is a member of the given set.
+    pub async fn dao_vote_member(
+        &mut self,
+        voter: &Holder,
+        member_set: &DaoMemberSet,
+        vote_option: bool,
+        dao: &Dao,
+        proposal: &DaoProposal,
+        block_height: u32,
+    ) -> Result<(Transaction, DaoVoteParams, Option<MoneyFeeParamsV1>)> {
+        let wallet = self.holders.get(voter).unwrap();
+
+        let (dao_vote_member_pk, dao_vote_member_zkbin) =
+            self.proving_keys.get(DAO_CONTRACT_ZKAS_DAO_VOTE_INPUT_MEMBER_NS).unwrap();
+
+        let (dao_vote_main_pk, dao_vote_main_zkbin) =
+            self.proving_keys.get(DAO_CONTRACT_ZKAS_DAO_VOTE_MAIN_NS).unwrap();
+
+        let Some((leaf_position, merkle_path)) = member_set.witness(&wallet.keypair.public) else {
+            return Err(ClientFailed::InvalidAddress(wallet.keypair.public.to_string()).into())
+        };
+        let member = member_set.members()[u64::from(leaf_position) as usize];
+
+        let signature_secret = SecretKey::random(&mut OsRng);
+
+        let input = DaoVoteMemberInput {
+            secret: wallet.keypair.secret,
+            member,
+            leaf_position,
+            merkle_path,
+            signature_secret,
+        };
+
+        let block_target = wallet.validator.consensus.module.read().await.target;
+        let current_blockwindow = blockwindow(block_height, block_target);
+        let call = DaoVoteMemberCall {
+            inputs: vec![input],
+            vote_option,
+            proposal: proposal.clone(),
+            dao: dao.clone(),
+            current_blockwindow,
+        };
+
+        let (params, proofs) = call.make(
+            dao_vote_member_zkbin,
+            dao_vote_member_pk,
+            dao_vote_main_zkbin,
+            dao_vote_main_pk,
+        )?;
+
+        self.build_dao_vote_tx(voter, params, proofs, signature_secret, block_height).await
+    }
+
+    /// Assemble a `Dao::Vote` transaction out of its call params and proofs
+    async fn build_dao_vote_tx(
+        &mut self,
+        voter: &Holder,
+        params: DaoVoteParams,
+        proofs: Vec<Proof>,
+        signature_secret: SecretKey,
+        block_height: u32,
+    ) -> Result<(Transaction, DaoVoteParams, Option<MoneyFeeParamsV1>)> {
         // Encode the call
         let mut data = vec![DaoFunction::Vote as u8];
         params.encode_async(&mut data).await?;
@@ -171,7 +239,7 @@ impl TestHarness {
 
             wallet.money_merkle_tree.append(MerkleNode::from(fee_params.output.coin.inner()));
 
-            let Ok(note) = fee_params.output.note.decrypt::<MoneyNote>(&wallet.keypair.secret)
+            let Ok(note) = MoneyNote::decrypt_note(&fee_params.output.note, &wallet.keypair.secret)
             else {
                 return Ok(vec![])
             };
@@ -27,7 +27,9 @@ use darkfi::{
     tx::Transaction,
     util::{pcg::Pcg32, time::Timestamp},
     validator::{utils::deploy_native_contracts, Validator, ValidatorConfig, ValidatorPtr},
-    zk::{empty_witnesses, halo2::Field, ProvingKey, ZkCircuit},
+    zk::{
+        diagnose, empty_witnesses, halo2::Field, DiagnosticReport, ProvingKey, Witness, ZkCircuit,
+    },
     zkas::ZkBinary,
     Result,
 };
@@ -81,6 +83,14 @@ mod dao_vote;
 
 /// `Dao::Exec` functionality
 mod dao_exec;
+pub use dao_exec::DaoExecTransferCalls;
+
+/// `Dao::ClaimStream` functionality
+mod dao_claim_stream;
+
+/// Money <-> DAO composition flow
+mod dao_flow;
+pub use dao_flow::{DaoFlow, DaoFlowProposal, DaoFlowTally};
 
 /// Initialize the logging mechanism
 pub fn init_logger() {
@@ -299,6 +309,31 @@ impl TestHarness {
         Ok(Self { holders: holders_map, proving_keys, genesis_block, verify_fees })
     }
 
+    /// Run the cached circuit `namespace` over the given witnesses and
+    /// public inputs with the mock prover, and report what fails.
+    pub fn diagnose_circuit(
+        &self,
+        namespace: &str,
+        witnesses: Vec<Witness>,
+        public_inputs: &[pallas::Base],
+    ) -> DiagnosticReport {
+        let (_, zkbin) = self.proving_keys.get(namespace).expect("unknown circuit namespace");
+        diagnose(zkbin, witnesses, public_inputs)
+    }
+
+    /// Assert that the cached circuit `namespace` is satisfied by the given
+    /// witnesses and public inputs. On failure, the panic message points at
+    /// the zkas source line whose constraints don't hold.
+    pub fn assert_circuit_satisfied(
+        &self,
+        namespace: &str,
+        witnesses: Vec<Witness>,
+        public_inputs: &[pallas::Base],
+    ) {
+        let report = self.diagnose_circuit(namespace, witnesses, public_inputs);
+        assert!(report.is_satisfied(), "{report}");
+    }
+
     /// Assert that all holders' trees are the same
     pub fn assert_trees(&self, holders: &[Holder]) {
         assert!(holders.len() > 1);
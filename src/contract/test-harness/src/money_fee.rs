@@ -35,7 +35,7 @@ use darkfi_money_contract::{
 };
 use darkfi_sdk::{
     crypto::{
-        contract_id::MONEY_CONTRACT_ID, note::AeadEncryptedNote, BaseBlind, Blind, FuncId,
+        contract_id::MONEY_CONTRACT_ID, note::EncryptableNote, BaseBlind, Blind, FuncId,
         MerkleNode, ScalarBlind, SecretKey,
     },
     pasta::pallas,
@@ -119,9 +119,10 @@ impl TestHarness {
             value_blind: output_value_blind,
             token_blind,
             memo: vec![],
+            sender: None,
         };
 
-        let encrypted_note = AeadEncryptedNote::encrypt(&note, &output.public_key, &mut OsRng)?;
+        let encrypted_note = note.encrypt_note(&output.public_key, &mut OsRng)?;
 
         let params = MoneyFeeParamsV1 {
             input: Input {
@@ -177,7 +178,7 @@ impl TestHarness {
         wallet.money_merkle_tree.append(MerkleNode::from(params.output.coin.inner()));
 
         // Attempt to decrypt the output note to see if this is a coin for the holder
-        let Ok(note) = params.output.note.decrypt::<MoneyNote>(&wallet.keypair.secret) else {
+        let Ok(note) = MoneyNote::decrypt_note(&params.output.note, &wallet.keypair.secret) else {
             return Ok(vec![])
         };
 
@@ -300,9 +301,10 @@ impl TestHarness {
             value_blind: output_value_blind,
             token_blind,
             memo: vec![],
+            sender: None,
         };
 
-        let encrypted_note = AeadEncryptedNote::encrypt(&note, &output.public_key, &mut OsRng)?;
+        let encrypted_note = note.encrypt_note(&output.public_key, &mut OsRng)?;
 
         let params = MoneyFeeParamsV1 {
             input: Input {
@@ -26,7 +26,7 @@ use darkfi_money_contract::{
     MoneyFunction, MONEY_CONTRACT_ZKAS_MINT_NS_V1,
 };
 use darkfi_sdk::{
-    crypto::{contract_id::MONEY_CONTRACT_ID, FuncId, MerkleNode},
+    crypto::{contract_id::MONEY_CONTRACT_ID, note::EncryptableNote, FuncId, MerkleNode},
     pasta::pallas,
     ContractCall,
 };
@@ -102,7 +102,7 @@ impl TestHarness {
             wallet.money_merkle_tree.append(MerkleNode::from(output.coin.inner()));
 
             // Attempt to decrypt the output note to see if this is a coin for the holder.
-            let Ok(note) = output.note.decrypt::<MoneyNote>(&wallet.keypair.secret) else {
+            let Ok(note) = MoneyNote::decrypt_note(&output.note, &wallet.keypair.secret) else {
                 continue
             };
 
@@ -27,7 +27,9 @@ use darkfi_money_contract::{
     MoneyFunction, MONEY_CONTRACT_ZKAS_BURN_NS_V1, MONEY_CONTRACT_ZKAS_MINT_NS_V1,
 };
 use darkfi_sdk::{
-    crypto::{contract_id::MONEY_CONTRACT_ID, BaseBlind, Blind, FuncId, MerkleNode},
+    crypto::{
+        contract_id::MONEY_CONTRACT_ID, note::EncryptableNote, BaseBlind, Blind, FuncId, MerkleNode,
+    },
     pasta::pallas,
     ContractCall,
 };
@@ -221,7 +223,7 @@ impl TestHarness {
             wallet.money_merkle_tree.append(MerkleNode::from(output.coin.inner()));
 
             // Attempt to decrypt the encrypted note
-            let Ok(note) = output.note.decrypt::<MoneyNote>(&wallet.keypair.secret) else {
+            let Ok(note) = MoneyNote::decrypt_note(&output.note, &wallet.keypair.secret) else {
                 continue
             };
 
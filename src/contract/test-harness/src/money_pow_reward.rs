@@ -28,7 +28,7 @@ use darkfi_money_contract::{
     MoneyFunction, MONEY_CONTRACT_ZKAS_MINT_NS_V1,
 };
 use darkfi_sdk::{
-    crypto::{contract_id::MONEY_CONTRACT_ID, MerkleNode, MerkleTree},
+    crypto::{contract_id::MONEY_CONTRACT_ID, note::EncryptableNote, MerkleNode, MerkleTree},
     ContractCall,
 };
 use darkfi_serial::AsyncEncodable;
@@ -153,7 +153,8 @@ impl TestHarness {
             wallet.money_merkle_tree.append(MerkleNode::from(params.output.coin.inner()));
 
             // Attempt to decrypt the note to see if this is a coin for the holder
-            let Ok(note) = params.output.note.decrypt::<MoneyNote>(&wallet.keypair.secret) else {
+            let Ok(note) = MoneyNote::decrypt_note(&params.output.note, &wallet.keypair.secret)
+            else {
                 continue
             };
 
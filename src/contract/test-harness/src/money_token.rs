@@ -29,12 +29,15 @@ use darkfi_money_contract::{
     },
     model::{
         CoinAttributes, MoneyAuthTokenFreezeParamsV1, MoneyAuthTokenMintParamsV1, MoneyFeeParamsV1,
-        MoneyTokenMintParamsV1, TokenAttributes,
+        MoneyTokenMintParamsV1, TokenAttributes, TokenSupply,
     },
     MoneyFunction, MONEY_CONTRACT_ZKAS_AUTH_TOKEN_MINT_NS_V1, MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1,
 };
 use darkfi_sdk::{
-    crypto::{poseidon_hash, BaseBlind, Blind, FuncId, FuncRef, MerkleNode, MONEY_CONTRACT_ID},
+    crypto::{
+        note::EncryptableNote, poseidon_hash, BaseBlind, Blind, FuncId, FuncRef, MerkleNode,
+        SecretKey, MONEY_CONTRACT_ID,
+    },
     dark_tree::DarkTree,
     pasta::pallas,
     ContractCall,
@@ -46,7 +49,7 @@ use rand::rngs::OsRng;
 use super::{Holder, TestHarness};
 
 impl TestHarness {
-    /// Mint an arbitrary token for a given recipient using `Money::TokenMint`
+    /// Mint an arbitrary reissuable token for a given recipient using `Money::TokenMint`
     #[allow(clippy::too_many_arguments)]
     pub async fn token_mint(
         &mut self,
@@ -62,6 +65,38 @@ impl TestHarness {
         MoneyTokenMintParamsV1,
         MoneyAuthTokenMintParamsV1,
         Option<MoneyFeeParamsV1>,
+    )> {
+        self.token_mint_with_supply(
+            amount,
+            holder,
+            recipient,
+            token_blind,
+            TokenSupply::Reissuable,
+            spend_hook,
+            user_data,
+            block_height,
+        )
+        .await
+    }
+
+    /// Mint an arbitrary token with the given supply policy for a given
+    /// recipient using `Money::TokenMint`
+    #[allow(clippy::too_many_arguments)]
+    pub async fn token_mint_with_supply(
+        &mut self,
+        amount: u64,
+        holder: &Holder,
+        recipient: &Holder,
+        token_blind: BaseBlind,
+        supply: TokenSupply,
+        spend_hook: Option<FuncId>,
+        user_data: Option<pallas::Base>,
+        block_height: u32,
+    ) -> Result<(
+        Transaction,
+        MoneyTokenMintParamsV1,
+        MoneyAuthTokenMintParamsV1,
+        Option<MoneyFeeParamsV1>,
     )> {
         let wallet = self.holders.get(holder).unwrap();
         let mint_authority = wallet.token_mint_authority;
@@ -116,6 +151,7 @@ impl TestHarness {
         let builder = TokenMintCallBuilder {
             coin_attrs,
             token_attrs,
+            supply,
             mint_zkbin: token_mint_zkbin.clone(),
             mint_pk: token_mint_pk.clone(),
         };
@@ -209,7 +245,8 @@ impl TestHarness {
             wallet.money_merkle_tree.append(MerkleNode::from(mint_params.coin.inner()));
 
             // Attempt to decrypt the encrypted note of the minted token
-            if let Ok(note) = auth_params.enc_note.decrypt::<MoneyNote>(&wallet.keypair.secret) {
+            if let Ok(note) = MoneyNote::decrypt_note(&auth_params.enc_note, &wallet.keypair.secret)
+            {
                 let owncoin = OwnCoin {
                     coin: mint_params.coin,
                     note: note.clone(),
@@ -227,7 +264,7 @@ impl TestHarness {
 
                 // Attempt to decrypt the encrypted note in the fee output
                 if let Ok(note) =
-                    fee_params.output.note.decrypt::<MoneyNote>(&wallet.keypair.secret)
+                    MoneyNote::decrypt_note(&fee_params.output.note, &wallet.keypair.secret)
                 {
                     let owncoin = OwnCoin {
                         coin: fee_params.output.coin,
@@ -252,6 +289,47 @@ impl TestHarness {
         holder: &Holder,
         block_height: u32,
     ) -> Result<(Transaction, MoneyAuthTokenFreezeParamsV1, Option<MoneyFeeParamsV1>)> {
+        let (freeze_leaf, freeze_params, freeze_secret) = self.token_freeze_call(holder).await?;
+
+        // Create the TransactionBuilder containing the above call
+        let mut tx_builder = TransactionBuilder::new(freeze_leaf, vec![])?;
+
+        // If we have tx fees enabled, make an offering
+        let mut fee_params = None;
+        let mut fee_signature_secrets = None;
+        if self.verify_fees {
+            let mut tx = tx_builder.build()?;
+            let freeze_sigs = tx.create_sigs(&[freeze_secret])?;
+            tx.signatures = vec![freeze_sigs];
+
+            let (fee_call, fee_proofs, fee_secrets, _spent_fee_coins, fee_call_params) =
+                self.append_fee_call(holder, tx, block_height, &[]).await?;
+
+            // Append the fee call to the transaction
+            tx_builder.append(ContractCallLeaf { call: fee_call, proofs: fee_proofs }, vec![])?;
+            fee_signature_secrets = Some(fee_secrets);
+            fee_params = Some(fee_call_params);
+        }
+
+        // Now build the actual transaction and sign it with necessary keys.
+        let mut tx = tx_builder.build()?;
+        let freeze_sigs = tx.create_sigs(&[freeze_secret])?;
+        tx.signatures = vec![freeze_sigs];
+        if let Some(fee_signature_secrets) = fee_signature_secrets {
+            let sigs = tx.create_sigs(&fee_signature_secrets)?;
+            tx.signatures.push(sigs);
+        }
+
+        Ok((tx, freeze_params, fee_params))
+    }
+
+    /// Create a `Money::AuthTokenFreeze` call for a new token of the holder,
+    /// without assembling it into a transaction.
+    /// Returns the call along with its parameters and signature secret.
+    pub async fn token_freeze_call(
+        &self,
+        holder: &Holder,
+    ) -> Result<(ContractCallLeaf, MoneyAuthTokenFreezeParamsV1, SecretKey)> {
         let wallet = self.holders.get(holder).unwrap();
         let mint_authority = wallet.token_mint_authority;
 
@@ -285,39 +363,11 @@ impl TestHarness {
         freeze_debris.params.encode_async(&mut data).await?;
         let freeze_call = ContractCall { contract_id: *MONEY_CONTRACT_ID, data };
 
-        // Create the TransactionBuilder containing the above call
-        let mut tx_builder = TransactionBuilder::new(
+        Ok((
             ContractCallLeaf { call: freeze_call, proofs: freeze_debris.proofs },
-            vec![],
-        )?;
-
-        // If we have tx fees enabled, make an offering
-        let mut fee_params = None;
-        let mut fee_signature_secrets = None;
-        if self.verify_fees {
-            let mut tx = tx_builder.build()?;
-            let freeze_sigs = tx.create_sigs(&[mint_authority.secret])?;
-            tx.signatures = vec![freeze_sigs];
-
-            let (fee_call, fee_proofs, fee_secrets, _spent_fee_coins, fee_call_params) =
-                self.append_fee_call(holder, tx, block_height, &[]).await?;
-
-            // Append the fee call to the transaction
-            tx_builder.append(ContractCallLeaf { call: fee_call, proofs: fee_proofs }, vec![])?;
-            fee_signature_secrets = Some(fee_secrets);
-            fee_params = Some(fee_call_params);
-        }
-
-        // Now build the actual transaction and sign it with necessary keys.
-        let mut tx = tx_builder.build()?;
-        let freeze_sigs = tx.create_sigs(&[mint_authority.secret])?;
-        tx.signatures = vec![freeze_sigs];
-        if let Some(fee_signature_secrets) = fee_signature_secrets {
-            let sigs = tx.create_sigs(&fee_signature_secrets)?;
-            tx.signatures.push(sigs);
-        }
-
-        Ok((tx, freeze_debris.params, fee_params))
+            freeze_debris.params,
+            mint_authority.secret,
+        ))
     }
 
     /// Execute the transaction created by `token_freeze()` for a given [`Holder`].
@@ -363,7 +413,7 @@ impl TestHarness {
 
                 // Attempt to decrypt the encrypted note
                 if let Ok(note) =
-                    fee_params.output.note.decrypt::<MoneyNote>(&wallet.keypair.secret)
+                    MoneyNote::decrypt_note(&fee_params.output.note, &wallet.keypair.secret)
                 {
                     let owncoin = OwnCoin {
                         coin: fee_params.output.coin,
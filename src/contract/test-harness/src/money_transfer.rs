@@ -26,7 +26,7 @@ use darkfi_money_contract::{
     MoneyFunction, MONEY_CONTRACT_ZKAS_BURN_NS_V1, MONEY_CONTRACT_ZKAS_MINT_NS_V1,
 };
 use darkfi_sdk::{
-    crypto::{contract_id::MONEY_CONTRACT_ID, MerkleNode},
+    crypto::{contract_id::MONEY_CONTRACT_ID, note::EncryptableNote, MerkleNode},
     ContractCall,
 };
 use darkfi_serial::AsyncEncodable;
@@ -69,6 +69,7 @@ impl TestHarness {
             burn_zkbin.clone(),
             burn_pk.clone(),
             half_split,
+            false,
         )?;
 
         // Encode the call
@@ -158,7 +159,7 @@ impl TestHarness {
                 wallet.money_merkle_tree.append(MerkleNode::from(output.coin.inner()));
 
                 // Attempt to decrypt the output note to see if this is a coin for the holder.
-                let Ok(note) = output.note.decrypt::<MoneyNote>(&wallet.keypair.secret) else {
+                let Ok(note) = MoneyNote::decrypt_note(&output.note, &wallet.keypair.secret) else {
                     continue
                 };
 
@@ -203,7 +204,7 @@ impl TestHarness {
 
                 // Attempt to decrypt the output note to see if this is a coin for the holder.
                 if let Ok(note) =
-                    fee_params.output.note.decrypt::<MoneyNote>(&wallet.keypair.secret)
+                    MoneyNote::decrypt_note(&fee_params.output.note, &wallet.keypair.secret)
                 {
                     let owncoin = OwnCoin {
                         coin: fee_params.output.coin,
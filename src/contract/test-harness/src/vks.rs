@@ -24,15 +24,17 @@ use std::{
 };
 
 use darkfi::{
+    blockchain::contract_store::ZkasRecord,
     zk::{empty_witnesses, ProvingKey, VerifyingKey, ZkCircuit},
     zkas::ZkBinary,
     Result,
 };
 use darkfi_dao_contract::{
     DAO_CONTRACT_ZKAS_DAO_AUTH_MONEY_TRANSFER_ENC_COIN_NS,
-    DAO_CONTRACT_ZKAS_DAO_AUTH_MONEY_TRANSFER_NS, DAO_CONTRACT_ZKAS_DAO_EARLY_EXEC_NS,
-    DAO_CONTRACT_ZKAS_DAO_EXEC_NS, DAO_CONTRACT_ZKAS_DAO_MINT_NS,
-    DAO_CONTRACT_ZKAS_DAO_PROPOSE_INPUT_NS, DAO_CONTRACT_ZKAS_DAO_PROPOSE_MAIN_NS,
+    DAO_CONTRACT_ZKAS_DAO_AUTH_MONEY_TRANSFER_NS, DAO_CONTRACT_ZKAS_DAO_CLAIM_STREAM_NS,
+    DAO_CONTRACT_ZKAS_DAO_EARLY_EXEC_NS, DAO_CONTRACT_ZKAS_DAO_EXEC_NS,
+    DAO_CONTRACT_ZKAS_DAO_MINT_NS, DAO_CONTRACT_ZKAS_DAO_PROPOSE_INPUT_NS,
+    DAO_CONTRACT_ZKAS_DAO_PROPOSE_MAIN_NS, DAO_CONTRACT_ZKAS_DAO_VOTE_INPUT_MEMBER_NS,
     DAO_CONTRACT_ZKAS_DAO_VOTE_INPUT_NS, DAO_CONTRACT_ZKAS_DAO_VOTE_MAIN_NS,
 };
 use darkfi_money_contract::{
@@ -133,11 +135,13 @@ pub fn get_cached_pks_and_vks() -> Result<(Pks, Vks)> {
         &include_bytes!("../../dao/proof/propose-input.zk.bin")[..],
         &include_bytes!("../../dao/proof/propose-main.zk.bin")[..],
         &include_bytes!("../../dao/proof/vote-input.zk.bin")[..],
+        &include_bytes!("../../dao/proof/vote-input-member.zk.bin")[..],
         &include_bytes!("../../dao/proof/vote-main.zk.bin")[..],
         &include_bytes!("../../dao/proof/exec.zk.bin")[..],
         &include_bytes!("../../dao/proof/early-exec.zk.bin")[..],
         &include_bytes!("../../dao/proof/auth-money-transfer.zk.bin")[..],
         &include_bytes!("../../dao/proof/auth-money-transfer-enc-coin.zk.bin")[..],
+        &include_bytes!("../../dao/proof/claim-stream.zk.bin")[..],
     ];
 
     let mut pks = vec![];
@@ -197,22 +201,24 @@ pub fn inject(sled_db: &sled::Db, vks: &Vks) -> Result<()> {
             MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1 |
             MONEY_CONTRACT_ZKAS_AUTH_TOKEN_MINT_NS_V1 => {
                 let key = serialize(&namespace.as_str());
-                let value = serialize(&(bincode.clone(), vk.clone()));
+                let value = serialize(&ZkasRecord::new(bincode.clone(), vk.clone()));
                 money_tree.insert(key, value)?;
             }
 
             // DAO contract circuits
             DAO_CONTRACT_ZKAS_DAO_MINT_NS |
             DAO_CONTRACT_ZKAS_DAO_VOTE_INPUT_NS |
+            DAO_CONTRACT_ZKAS_DAO_VOTE_INPUT_MEMBER_NS |
             DAO_CONTRACT_ZKAS_DAO_VOTE_MAIN_NS |
             DAO_CONTRACT_ZKAS_DAO_PROPOSE_INPUT_NS |
             DAO_CONTRACT_ZKAS_DAO_PROPOSE_MAIN_NS |
             DAO_CONTRACT_ZKAS_DAO_EXEC_NS |
             DAO_CONTRACT_ZKAS_DAO_EARLY_EXEC_NS |
             DAO_CONTRACT_ZKAS_DAO_AUTH_MONEY_TRANSFER_NS |
-            DAO_CONTRACT_ZKAS_DAO_AUTH_MONEY_TRANSFER_ENC_COIN_NS => {
+            DAO_CONTRACT_ZKAS_DAO_AUTH_MONEY_TRANSFER_ENC_COIN_NS |
+            DAO_CONTRACT_ZKAS_DAO_CLAIM_STREAM_NS => {
                 let key = serialize(&namespace.as_str());
-                let value = serialize(&(bincode.clone(), vk.clone()));
+                let value = serialize(&ZkasRecord::new(bincode.clone(), vk.clone()));
                 dao_tree.insert(key, value)?;
             }
 
@@ -131,6 +131,9 @@ pub enum Error {
     #[error("Channel timed out")]
     ChannelTimeout,
 
+    #[error("Handshake failed: {0}")]
+    HandshakeFailed(String),
+
     #[error("Failed to reach any seeds")]
     SeedFailed,
 
@@ -152,6 +155,18 @@ pub enum Error {
     #[error("Network operation failed")]
     NetworkOperationFailed,
 
+    #[error("NAT traversal failed: {0}")]
+    NatTraversalFailed(String),
+
+    #[error("Invalid P2P identity key: {0}")]
+    InvalidIdentityKey(String),
+
+    #[error("Peer authentication failed")]
+    PeerAuthFailed,
+
+    #[error("Peer is not in the allowlist")]
+    PeerNotAllowed,
+
     #[error("Missing P2P message dispatcher")]
     MissingDispatcher,
 
@@ -211,6 +226,15 @@ pub enum Error {
     #[error("Wrong witnesses count")]
     WrongWitnessesCount,
 
+    #[error("Missing witness: {0}")]
+    MissingWitness(String),
+
+    #[error("Unknown witness: {0}")]
+    UnknownWitness(String),
+
+    #[error("zkas binary has no debug info, cannot look up witnesses by name")]
+    ZkasDebugInfoMissing,
+
     #[error("Wrong public inputs count")]
     WrongPublicInputsCount,
 
@@ -270,6 +294,10 @@ pub enum Error {
     #[error("JSON-RPC client stopped")]
     RpcClientStopped,
 
+    #[cfg(feature = "rpc")]
+    #[error("JSON-RPC authentication failed")]
+    RpcUnauthorized,
+
     #[error("Unexpected JSON-RPC data received: {0}")]
     UnexpectedJsonRpc(String),
 
@@ -386,9 +414,15 @@ pub enum Error {
     #[error("Block state inverse diff for height number {0} not found in database")]
     BlockStateInverseDiffNotFound(u32),
 
+    #[error("Invalid state diff range: {0} to {1}")]
+    InvalidStateDiffRange(u32, u32),
+
     #[error("Block {0} contains 0 transactions")]
     BlockContainsNoTransactions(String),
 
+    #[error("Nullifier set export mismatch: {0}")]
+    NullifierSetMismatch(String),
+
     #[error("Contract {0} not found in database")]
     ContractNotFound(String),
 
@@ -401,6 +435,9 @@ pub enum Error {
     #[error("zkas bincode not found in sled database")]
     ZkasBincodeNotFound,
 
+    #[error("zkas record integrity check failed")]
+    ZkasRecordCorrupted,
+
     // ===================
     // wasm runtime errors
     // ===================
@@ -558,6 +595,12 @@ pub enum Error {
     #[error("Malicious flood detected")]
     MaliciousFlood,
 
+    #[error("Blob chunk is missing")]
+    BlobChunkMissing,
+
+    #[error("Blob exceeds the chunk cache quota")]
+    BlobTooLarge,
+
     // =========
     // Catch-all
     // =========
@@ -0,0 +1,175 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Delivery and read acknowledgements.
+//!
+//! An [`Ack`] is a small signed event telling the network that an
+//! identity has received, or read, a set of events. Acks are recorded
+//! per event ID as they get inserted into the DAG, so chat frontends
+//! can query who has seen a message, see
+//! [`super::EventGraph::ack_status`].
+
+use darkfi_sdk::crypto::{
+    schnorr::{SchnorrPublic, SchnorrSecret, Signature},
+    PublicKey, SecretKey,
+};
+use darkfi_serial::{async_trait, deserialize, serialize, SerialDecodable, SerialEncodable};
+
+/// Event content prefix marking an acknowledgement
+pub const ACK_MAGIC: [u8; 4] = [0x41, 0x43, 0x4b, 0x53];
+
+/// Maximum number of events a single ack can cover
+pub const MAX_ACK_EVENTS: usize = 64;
+
+/// How far an identity got with an event. A read event is also
+/// considered delivered.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, SerialEncodable, SerialDecodable)]
+pub enum AckKind {
+    /// The event reached the identity's client
+    Delivered,
+    /// The identity has seen the event
+    Read,
+}
+
+/// Signed acknowledgement of a set of events
+#[derive(Clone, Debug, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct Ack {
+    /// Public key of the acknowledging identity
+    pub author: PublicKey,
+    /// Kind of acknowledgement
+    pub kind: AckKind,
+    /// IDs of the acknowledged events
+    pub events: Vec<blake3::Hash>,
+    /// Author signature over the kind and event IDs
+    pub signature: Signature,
+}
+
+impl Ack {
+    /// Sign an acknowledgement of the given events with the identity secret key
+    pub fn new(secret: &SecretKey, kind: AckKind, events: Vec<blake3::Hash>) -> Self {
+        let signature = secret.sign(&Self::message(kind, &events));
+        Self { author: PublicKey::from_secret(*secret), kind, events, signature }
+    }
+
+    fn message(kind: AckKind, events: &[blake3::Hash]) -> Vec<u8> {
+        let mut message = ACK_MAGIC.to_vec();
+        message.extend(serialize(&kind));
+        for event_id in events {
+            message.extend(event_id.as_bytes());
+        }
+        message
+    }
+
+    /// Check the ack is within limits and verify the author signature
+    pub fn verify(&self) -> bool {
+        !self.events.is_empty() &&
+            self.events.len() <= MAX_ACK_EVENTS &&
+            self.author.verify(&Self::message(self.kind, &self.events), &self.signature)
+    }
+
+    /// Event content carrying this ack
+    pub fn to_content(&self) -> Vec<u8> {
+        let mut content = ACK_MAGIC.to_vec();
+        content.extend(serialize(self));
+        content
+    }
+
+    /// Parse an ack out of event content, if there is one
+    pub fn from_content(content: &[u8]) -> Option<Self> {
+        deserialize(content.strip_prefix(&ACK_MAGIC)?).ok()
+    }
+}
+
+/// Key under which the ack of `event_id` by `author` is recorded
+pub(super) fn ack_key(event_id: &blake3::Hash, author: &PublicKey) -> Vec<u8> {
+    let mut key = event_id.as_bytes().to_vec();
+    key.extend(serialize(author));
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::rngs::OsRng;
+    use sled_overlay::sled;
+    use smol::Executor;
+
+    use super::*;
+    use crate::{
+        event_graph::{retention::RetentionPolicy, Event, EventGraph},
+        net::{P2p, Settings},
+        Result,
+    };
+
+    #[test]
+    fn ack_status() -> Result<()> {
+        smol::block_on(async {
+            let ex = Arc::new(Executor::new());
+            let p2p = P2p::new(Settings::default(), ex.clone()).await?;
+            let sled_db = sled::Config::new().temporary(true).open().unwrap();
+            let event_graph = EventGraph::new(
+                p2p,
+                sled_db,
+                "/tmp".into(),
+                false,
+                "dag",
+                1,
+                RetentionPolicy::default(),
+                ex,
+            )
+            .await?;
+
+            let event = Event::new(vec![1], &event_graph).await;
+            event_graph.dag_insert(&[event.clone()]).await?;
+            assert!(event_graph.ack_status(&event.id())?.is_empty());
+
+            let alice = SecretKey::random(&mut OsRng);
+            let bob = SecretKey::random(&mut OsRng);
+
+            // Alice reads the event, then a late delivery ack shows up
+            for (secret, kind) in
+                [(&alice, AckKind::Read), (&alice, AckKind::Delivered), (&bob, AckKind::Delivered)]
+            {
+                let ack = Ack::new(secret, kind, vec![event.id()]);
+                assert!(ack.verify());
+                let ack = Event::new(ack.to_content(), &event_graph).await;
+                event_graph.dag_insert(&[ack]).await?;
+            }
+
+            // Forged acks are not recorded
+            let mut forged = Ack::new(&bob, AckKind::Read, vec![event.id()]);
+            forged.author = PublicKey::from_secret(alice);
+            assert!(!forged.verify());
+            let forged = Event::new(forged.to_content(), &event_graph).await;
+            event_graph.dag_insert(&[forged]).await?;
+
+            let mut status = event_graph.ack_status(&event.id())?;
+            status.sort_by_key(|(_, kind)| *kind);
+            assert_eq!(
+                status,
+                vec![
+                    (PublicKey::from_secret(bob), AckKind::Delivered),
+                    (PublicKey::from_secret(alice), AckKind::Read),
+                ]
+            );
+
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,277 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Chunked blobs for content too large to fit in a single event.
+//!
+//! A blob is split into `BLOB_CHUNK_SIZE` content-addressed chunks.
+//! Only a small [`BlobManifest`] listing the chunk hashes goes into the
+//! DAG, as the content of a regular event. The chunks themselves live
+//! in a local cache bounded by a quota, and are fetched from peers on
+//! demand using `ChunkReq`, see [`super::EventGraph::blob_fetch`].
+
+use std::sync::atomic::{AtomicU64, Ordering::SeqCst};
+
+use darkfi_serial::{async_trait, deserialize, serialize, SerialDecodable, SerialEncodable};
+use log::debug;
+use sled_overlay::sled;
+
+use crate::{Error, Result};
+
+/// Event content prefix marking a blob manifest
+pub const BLOB_MAGIC: [u8; 4] = [0x42, 0x4c, 0x4f, 0x42];
+
+/// Size of a blob chunk (64 KiB). The last chunk may be smaller.
+pub const BLOB_CHUNK_SIZE: usize = 65_536;
+
+/// Default size of the chunk cache (256 MiB)
+pub const DEFAULT_BLOB_QUOTA: u64 = 268_435_456;
+
+/// Manifest of a chunked blob, published as event content
+#[derive(Clone, Debug, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct BlobManifest {
+    /// Name of the blob, e.g. the original file name
+    pub name: String,
+    /// Total size of the blob in bytes
+    pub size: u64,
+    /// Ordered hashes of the chunks making up the blob
+    pub chunks: Vec<blake3::Hash>,
+}
+
+impl BlobManifest {
+    /// The blob ID is the hash of its ordered chunk hashes
+    pub fn id(&self) -> blake3::Hash {
+        let mut hasher = blake3::Hasher::new();
+        for chunk in &self.chunks {
+            hasher.update(chunk.as_bytes());
+        }
+        hasher.finalize()
+    }
+
+    /// Event content announcing this manifest
+    pub fn to_content(&self) -> Vec<u8> {
+        let mut content = BLOB_MAGIC.to_vec();
+        content.extend(serialize(self));
+        content
+    }
+
+    /// Parse a manifest out of event content, if there is one
+    pub fn from_content(content: &[u8]) -> Option<Self> {
+        deserialize(content.strip_prefix(&BLOB_MAGIC)?).ok()
+    }
+}
+
+/// On-disk cache of blob chunks with a size quota. When the quota is
+/// reached, the oldest stored chunks get evicted first.
+pub struct BlobStore {
+    /// Chunk hash => chunk data
+    chunks: sled::Tree,
+    /// Insertion sequence number || chunk hash => (), in eviction order
+    index: sled::Tree,
+    /// Next insertion sequence number
+    next_seq: AtomicU64,
+    /// Bytes currently stored
+    used: AtomicU64,
+    /// Maximum bytes to store
+    quota: AtomicU64,
+}
+
+impl BlobStore {
+    pub fn new(chunks: sled::Tree, index: sled::Tree, quota: u64) -> Result<Self> {
+        let mut used = 0;
+        for entry in chunks.iter() {
+            used += entry?.1.len() as u64;
+        }
+
+        let next_seq = match index.last()? {
+            Some((k, _)) => u64::from_be_bytes(k[..8].try_into().unwrap()) + 1,
+            None => 0,
+        };
+
+        let self_ = Self {
+            chunks,
+            index,
+            next_seq: AtomicU64::new(next_seq),
+            used: AtomicU64::new(used),
+            quota: AtomicU64::new(quota),
+        };
+        self_.evict(0)?;
+        Ok(self_)
+    }
+
+    /// Change the quota, evicting chunks if we're now over it
+    pub fn set_quota(&self, quota: u64) -> Result<()> {
+        self.quota.store(quota, SeqCst);
+        self.evict(0)
+    }
+
+    /// Bytes currently stored
+    pub fn used(&self) -> u64 {
+        self.used.load(SeqCst)
+    }
+
+    pub fn contains(&self, hash: &blake3::Hash) -> Result<bool> {
+        Ok(self.chunks.contains_key(hash.as_bytes())?)
+    }
+
+    pub fn get(&self, hash: &blake3::Hash) -> Result<Option<Vec<u8>>> {
+        Ok(self.chunks.get(hash.as_bytes())?.map(|v| v.to_vec()))
+    }
+
+    /// Store a chunk, returning its hash
+    pub fn insert(&self, data: &[u8]) -> Result<blake3::Hash> {
+        let hash = blake3::hash(data);
+        if self.contains(&hash)? {
+            return Ok(hash)
+        }
+
+        let len = data.len() as u64;
+        if len > self.quota.load(SeqCst) {
+            return Err(Error::BlobTooLarge)
+        }
+        self.evict(len)?;
+
+        let seq = self.next_seq.fetch_add(1, SeqCst);
+        let mut key = seq.to_be_bytes().to_vec();
+        key.extend_from_slice(hash.as_bytes());
+
+        self.chunks.insert(hash.as_bytes(), data)?;
+        self.index.insert(key, &[])?;
+        self.used.fetch_add(len, SeqCst);
+
+        Ok(hash)
+    }
+
+    /// Evict the oldest chunks until `incoming` more bytes fit in the quota
+    fn evict(&self, incoming: u64) -> Result<()> {
+        let quota = self.quota.load(SeqCst);
+        while self.used.load(SeqCst) + incoming > quota {
+            let Some((key, _)) = self.index.pop_min()? else { break };
+            if let Some(data) = self.chunks.remove(&key[8..])? {
+                debug!(
+                    target: "event_graph::blob::evict()",
+                    "Evicting chunk {}", blake3::Hash::from_bytes(key[8..].try_into().unwrap()),
+                );
+                self.used.fetch_sub(data.len() as u64, SeqCst);
+            }
+        }
+        Ok(())
+    }
+
+    /// Drop all the stored chunks
+    pub fn clear(&self) -> Result<()> {
+        self.chunks.clear()?;
+        self.index.clear()?;
+        self.used.store(0, SeqCst);
+        Ok(())
+    }
+
+    /// Split `data` into chunks, store them, and return the manifest
+    /// to publish. Fails if the blob does not fit in the quota.
+    pub fn store_blob(&self, name: &str, data: &[u8]) -> Result<BlobManifest> {
+        if data.len() as u64 > self.quota.load(SeqCst) {
+            return Err(Error::BlobTooLarge)
+        }
+
+        let mut chunks = vec![];
+        for chunk in data.chunks(BLOB_CHUNK_SIZE) {
+            chunks.push(self.insert(chunk)?);
+        }
+
+        Ok(BlobManifest { name: name.to_string(), size: data.len() as u64, chunks })
+    }
+
+    /// Chunks of the manifest we don't have yet
+    pub fn missing(&self, manifest: &BlobManifest) -> Result<Vec<blake3::Hash>> {
+        let mut missing = vec![];
+        for hash in &manifest.chunks {
+            if !self.contains(hash)? && !missing.contains(hash) {
+                missing.push(*hash);
+            }
+        }
+        Ok(missing)
+    }
+
+    /// Reassemble a blob from the stored chunks
+    pub fn reassemble(&self, manifest: &BlobManifest) -> Result<Vec<u8>> {
+        // The size comes from the network, so make sure it is backed by
+        // the listed chunks before allocating anything for it.
+        let max_size = (manifest.chunks.len() as u64).saturating_mul(BLOB_CHUNK_SIZE as u64);
+        if manifest.size > max_size || manifest.size > self.quota.load(SeqCst) {
+            return Err(Error::BlobTooLarge)
+        }
+
+        let mut data = Vec::with_capacity(manifest.size as usize);
+        for hash in &manifest.chunks {
+            let Some(chunk) = self.get(hash)? else { return Err(Error::BlobChunkMissing) };
+            data.extend(chunk);
+        }
+
+        if data.len() as u64 != manifest.size {
+            return Err(Error::BlobChunkMissing)
+        }
+
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(quota: u64) -> BlobStore {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        BlobStore::new(db.open_tree("chunks").unwrap(), db.open_tree("index").unwrap(), quota)
+            .unwrap()
+    }
+
+    #[test]
+    fn blob_roundtrip_and_eviction() {
+        let blobs = store(4 * BLOB_CHUNK_SIZE as u64);
+
+        // 2.5 chunks worth of data
+        let data: Vec<u8> = (0..BLOB_CHUNK_SIZE * 5 / 2).map(|i| i as u8).collect();
+        let manifest = blobs.store_blob("cat.png", &data).unwrap();
+        assert_eq!(manifest.chunks.len(), 3);
+        assert_eq!(BlobManifest::from_content(&manifest.to_content()), Some(manifest.clone()));
+        assert_eq!(BlobManifest::from_content(b"not a manifest"), None);
+        assert_eq!(blobs.reassemble(&manifest).unwrap(), data);
+
+        // Too big for the cache
+        let huge = vec![0u8; 5 * BLOB_CHUNK_SIZE];
+        assert!(matches!(blobs.store_blob("huge", &huge), Err(Error::BlobTooLarge)));
+
+        // Manifests claiming more data than their chunks can hold
+        let mut bogus = manifest.clone();
+        bogus.size = u64::MAX;
+        assert!(matches!(blobs.reassemble(&bogus), Err(Error::BlobTooLarge)));
+        bogus.size = 3 * BLOB_CHUNK_SIZE as u64 + 1;
+        assert!(matches!(blobs.reassemble(&bogus), Err(Error::BlobTooLarge)));
+
+        // Storing a second blob evicts the oldest chunks of the first one
+        let other: Vec<u8> = (0..2 * BLOB_CHUNK_SIZE).map(|i| (i % 251) as u8).collect();
+        let other_manifest = blobs.store_blob("other", &other).unwrap();
+        assert!(blobs.used() <= 4 * BLOB_CHUNK_SIZE as u64);
+        assert_eq!(blobs.missing(&manifest).unwrap(), vec![manifest.chunks[0]]);
+        assert!(matches!(blobs.reassemble(&manifest), Err(Error::BlobChunkMissing)));
+        assert_eq!(blobs.reassemble(&other_manifest).unwrap(), other);
+
+        // Fetching it back evicts the next oldest one
+        blobs.insert(&data[..BLOB_CHUNK_SIZE]).unwrap();
+        assert_eq!(blobs.missing(&manifest).unwrap(), vec![manifest.chunks[1]]);
+    }
+}
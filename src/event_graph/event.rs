@@ -16,20 +16,33 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{collections::HashSet, time::UNIX_EPOCH};
+use std::{
+    collections::HashSet,
+    io::{Error, ErrorKind, Read, Write},
+    time::UNIX_EPOCH,
+};
 
-use darkfi_serial::{async_trait, deserialize_async, Encodable, SerialDecodable, SerialEncodable};
+use darkfi_serial::{
+    async_trait, deserialize_async, AsyncDecodable, AsyncEncodable, AsyncRead, AsyncWrite,
+    Decodable, Encodable,
+};
 use sled_overlay::{sled, SledTreeOverlay};
 
 use crate::Result;
 
 use super::{
-    util::next_rotation_timestamp, EventGraph, EVENT_TIME_DRIFT, INITIAL_GENESIS, NULL_ID,
-    N_EVENT_PARENTS,
+    redact::id_content, util::next_rotation_timestamp, EventGraph, EVENT_TIME_DRIFT,
+    INITIAL_GENESIS, NULL_ID, N_EVENT_PARENTS,
 };
 
+/// Format version flag, set on the encoded timestamp of events tagged
+/// with a topic, which then get their topic appended. Untagged events
+/// keep the original layout and ID, so they stay compatible with nodes
+/// unaware of topics, which in turn reject tagged events as too new.
+const EVENT_TOPIC_FLAG: u64 = 1 << 63;
+
 /// Representation of an event in the Event Graph
-#[derive(Debug, Clone, PartialEq, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Event {
     /// Timestamp of the event in whole seconds
     pub timestamp: u64,
@@ -39,6 +52,14 @@ pub struct Event {
     pub parents: [blake3::Hash; N_EVENT_PARENTS],
     /// DAG layer index of the event
     pub layer: u64,
+    /// Topic the event is tagged with, used for selective sync.
+    /// `NULL_ID` when the event is not tagged.
+    pub topic: blake3::Hash,
+}
+
+/// Derive the topic of a named channel, see [`Event::with_topic`]
+pub fn topic_hash(name: &str) -> blake3::Hash {
+    blake3::Hash::from_bytes(blake3::derive_key("DarkFi:EVENTGRAPH_TOPIC", name.as_bytes()))
 }
 
 impl Event {
@@ -54,25 +75,59 @@ impl Event {
             content: data,
             parents,
             layer,
+            topic: NULL_ID,
         }
     }
 
+    /// Same as `Event::new()` but tags the event with the given topic,
+    /// so nodes selectively syncing the DAG can pick it up.
+    pub async fn with_topic(data: Vec<u8>, topic: blake3::Hash, event_graph: &EventGraph) -> Self {
+        let mut event = Self::new(data, event_graph).await;
+        event.topic = topic;
+        event
+    }
+
     /// Same as `Event::new()` but allows specifying the timestamp explicitly.
     pub async fn with_timestamp(timestamp: u64, data: Vec<u8>, event_graph: &EventGraph) -> Self {
         let (layer, parents) = event_graph.get_next_layer_with_parents().await;
-        Self { timestamp, content: data, parents, layer }
+        Self { timestamp, content: data, parents, layer, topic: NULL_ID }
     }
 
     /// Hash the [`Event`] to retrieve its ID
     pub fn id(&self) -> blake3::Hash {
         let mut hasher = blake3::Hasher::new();
         self.timestamp.encode(&mut hasher).unwrap();
-        self.content.encode(&mut hasher).unwrap();
+        match id_content(&self.content) {
+            Some(content) => content.encode(&mut hasher).unwrap(),
+            None => self.content.encode(&mut hasher).unwrap(),
+        };
         self.parents.encode(&mut hasher).unwrap();
         self.layer.encode(&mut hasher).unwrap();
+        if self.is_tagged() {
+            self.topic.encode(&mut hasher).unwrap();
+        }
         hasher.finalize()
     }
 
+    /// Check if the event is tagged with a topic
+    pub fn is_tagged(&self) -> bool {
+        self.topic != NULL_ID
+    }
+
+    /// Timestamp as encoded, carrying the format version flag
+    fn encoded_timestamp(&self) -> u64 {
+        match self.is_tagged() {
+            true => self.timestamp | EVENT_TOPIC_FLAG,
+            false => self.timestamp,
+        }
+    }
+
+    /// Split an encoded timestamp into the actual timestamp and
+    /// whether a topic follows the event
+    fn decode_timestamp(timestamp: u64) -> (u64, bool) {
+        (timestamp & !EVENT_TOPIC_FLAG, timestamp & EVENT_TOPIC_FLAG != 0)
+    }
+
     /// Return a reference to the event's content
     pub fn content(&self) -> &[u8] {
         &self.content
@@ -95,6 +150,30 @@ impl Event {
         genesis_timestamp: u64,
         days_rotation: u64,
         overlay: Option<&SledTreeOverlay>,
+    ) -> Result<bool> {
+        self.validate_with(dag, genesis_timestamp, days_rotation, overlay, false).await
+    }
+
+    /// Same as [`Event::validate`], but parents missing from the DAG are
+    /// accepted. Used when selectively syncing, where the DAG only holds
+    /// the events of the followed topics.
+    pub async fn validate_partial(
+        &self,
+        dag: &sled::Tree,
+        genesis_timestamp: u64,
+        days_rotation: u64,
+        overlay: Option<&SledTreeOverlay>,
+    ) -> Result<bool> {
+        self.validate_with(dag, genesis_timestamp, days_rotation, overlay, true).await
+    }
+
+    async fn validate_with(
+        &self,
+        dag: &sled::Tree,
+        genesis_timestamp: u64,
+        days_rotation: u64,
+        overlay: Option<&SledTreeOverlay>,
+        allow_missing_parents: bool,
     ) -> Result<bool> {
         // Let's not bother with empty events
         if self.content.is_empty() {
@@ -140,11 +219,15 @@ impl Event {
             } else {
                 dag.get(parent_id.as_bytes())?
             };
-            if parent_bytes.is_none() {
-                return Ok(false)
-            }
+            let Some(parent_bytes) = parent_bytes else {
+                if !allow_missing_parents {
+                    return Ok(false)
+                }
+                seen.insert(parent_id);
+                continue
+            };
 
-            let parent: Event = deserialize_async(&parent_bytes.unwrap()).await?;
+            let parent: Event = deserialize_async(&parent_bytes).await?;
             if self.layer <= parent.layer {
                 return Ok(false)
             }
@@ -209,14 +292,80 @@ impl Event {
     }
 }
 
+impl Encodable for Event {
+    fn encode<S: Write>(&self, s: &mut S) -> std::io::Result<usize> {
+        let mut len = self.encoded_timestamp().encode(s)?;
+        len += self.content.encode(s)?;
+        len += self.parents.encode(s)?;
+        len += self.layer.encode(s)?;
+        if self.is_tagged() {
+            len += self.topic.encode(s)?;
+        }
+        Ok(len)
+    }
+}
+
+#[async_trait]
+impl AsyncEncodable for Event {
+    async fn encode_async<S: AsyncWrite + Unpin + Send>(
+        &self,
+        s: &mut S,
+    ) -> std::io::Result<usize> {
+        let mut len = self.encoded_timestamp().encode_async(s).await?;
+        len += self.content.encode_async(s).await?;
+        len += self.parents.encode_async(s).await?;
+        len += self.layer.encode_async(s).await?;
+        if self.is_tagged() {
+            len += self.topic.encode_async(s).await?;
+        }
+        Ok(len)
+    }
+}
+
+impl Decodable for Event {
+    fn decode<D: Read>(d: &mut D) -> std::io::Result<Self> {
+        let (timestamp, tagged) = Self::decode_timestamp(Decodable::decode(d)?);
+        let content = Decodable::decode(d)?;
+        let parents = Decodable::decode(d)?;
+        let layer = Decodable::decode(d)?;
+        let topic = if tagged { Decodable::decode(d)? } else { NULL_ID };
+
+        // Keep the encoding canonical, as it is what peers relay
+        if tagged && topic == NULL_ID {
+            return Err(Error::new(ErrorKind::InvalidData, "Tagged event with a NULL topic"))
+        }
+
+        Ok(Self { timestamp, content, parents, layer, topic })
+    }
+}
+
+#[async_trait]
+impl AsyncDecodable for Event {
+    async fn decode_async<D: AsyncRead + Unpin + Send>(d: &mut D) -> std::io::Result<Self> {
+        let (timestamp, tagged) = Self::decode_timestamp(AsyncDecodable::decode_async(d).await?);
+        let content = AsyncDecodable::decode_async(d).await?;
+        let parents = AsyncDecodable::decode_async(d).await?;
+        let layer = AsyncDecodable::decode_async(d).await?;
+        let topic = if tagged { AsyncDecodable::decode_async(d).await? } else { NULL_ID };
+
+        // Keep the encoding canonical, as it is what peers relay
+        if tagged && topic == NULL_ID {
+            return Err(Error::new(ErrorKind::InvalidData, "Tagged event with a NULL topic"))
+        }
+
+        Ok(Self { timestamp, content, parents, layer, topic })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
 
+    use darkfi_serial::{deserialize, deserialize_async_partial, serialize, serialize_async};
     use smol::Executor;
 
     use crate::{
-        event_graph::{EventGraph, EventGraphPtr},
+        event_graph::{retention::RetentionPolicy, EventGraph, EventGraphPtr},
         net::{P2p, Settings},
     };
 
@@ -226,7 +375,17 @@ mod tests {
         let ex = Arc::new(Executor::new());
         let p2p = P2p::new(Settings::default(), ex.clone()).await?;
         let sled_db = sled::Config::new().temporary(true).open().unwrap();
-        EventGraph::new(p2p, sled_db, "/tmp".into(), false, "dag", 1, ex).await
+        EventGraph::new(
+            p2p,
+            sled_db,
+            "/tmp".into(),
+            false,
+            "dag",
+            1,
+            RetentionPolicy::default(),
+            ex,
+        )
+        .await
     }
 
     #[test]
@@ -284,4 +443,83 @@ mod tests {
             Ok(())
         })
     }
+
+    #[test]
+    fn topic_events() -> Result<()> {
+        smol::block_on(async {
+            let event_graph = make_event_graph().await?;
+
+            // The topic is part of the event ID
+            let topic = topic_hash("#dev");
+            let event = Event::with_topic(vec![1u8], topic, &event_graph).await;
+            let mut untagged = event.clone();
+            untagged.topic = NULL_ID;
+            assert_ne!(event.id(), untagged.id());
+            assert_ne!(topic, topic_hash("#random"));
+
+            // An event whose parent lives in another topic is only valid
+            // for a selectively synced DAG.
+            let mut event_missing_parent = event.clone();
+            event_missing_parent.parents[0] = blake3::hash(b"not in the DAG");
+            assert!(!event_missing_parent.dag_validate(&event_graph).await?);
+
+            let genesis_timestamp = event_graph.current_genesis.read().await.timestamp;
+            assert!(
+                event_missing_parent
+                    .validate_partial(
+                        &event_graph.dag,
+                        genesis_timestamp,
+                        event_graph.days_rotation,
+                        None
+                    )
+                    .await?
+            );
+
+            // Selective DAGs only keep events of the followed topics
+            event_graph.set_topics(Some(HashSet::from([topic]))).await;
+            event_graph.dag_insert(&[event_missing_parent.clone()]).await?;
+            let events = event_graph.fetch_topic_events(&HashSet::from([topic]), 10).await?;
+            assert_eq!(events, vec![event_missing_parent]);
+            let events = event_graph.fetch_topic_events(&HashSet::from([NULL_ID]), 10).await?;
+            assert!(events.is_empty());
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn event_encoding() -> Result<()> {
+        smol::block_on(async {
+            let event_graph = make_event_graph().await?;
+
+            // Untagged events keep the layout and ID they had before topics
+            let event = Event::new(vec![1u8, 2, 3], &event_graph).await;
+            let mut legacy = vec![];
+            event.timestamp.encode(&mut legacy)?;
+            event.content.encode(&mut legacy)?;
+            event.parents.encode(&mut legacy)?;
+            event.layer.encode(&mut legacy)?;
+            assert_eq!(serialize(&event), legacy);
+            assert_eq!(event.id(), blake3::hash(&legacy));
+            assert_eq!(deserialize::<Event>(&legacy)?, event);
+            assert_eq!(deserialize_async::<Event>(&legacy).await?, event);
+
+            // Tagged events are flagged and carry their topic
+            let tagged = Event::with_topic(vec![1u8, 2, 3], topic_hash("#dev"), &event_graph).await;
+            let bytes = serialize_async(&tagged).await;
+            let timestamp: u64 = deserialize_async_partial(&bytes).await?.0;
+            assert_eq!(timestamp, tagged.timestamp | EVENT_TOPIC_FLAG);
+            assert_eq!(deserialize::<Event>(&bytes)?, tagged);
+            assert_eq!(deserialize_async::<Event>(&bytes).await?, tagged);
+
+            // A flagged event must carry an actual topic
+            let mut forged = legacy.clone();
+            forged[..8].copy_from_slice(&(event.timestamp | EVENT_TOPIC_FLAG).to_le_bytes());
+            forged.extend_from_slice(NULL_ID.as_bytes());
+            assert!(deserialize::<Event>(&forged).is_err());
+            assert!(deserialize_async::<Event>(&forged).await.is_err());
+
+            Ok(())
+        })
+    }
 }
@@ -20,9 +20,11 @@ use std::{
     collections::{BTreeMap, HashMap, HashSet, VecDeque},
     path::PathBuf,
     sync::Arc,
+    time::UNIX_EPOCH,
 };
 
-use darkfi_serial::{deserialize_async, serialize_async};
+use darkfi_sdk::crypto::PublicKey;
+use darkfi_serial::{deserialize, deserialize_async, serialize, serialize_async};
 use log::{debug, error, info, warn};
 use num_bigint::BigUint;
 use sled_overlay::{sled, SledTreeOverlay};
@@ -34,7 +36,7 @@ use tinyjson::JsonValue::{self};
 
 use crate::{
     event_graph::util::replayer_log,
-    net::P2pPtr,
+    net::{scoring::Misbehavior, P2pPtr},
     rpc::{
         jsonrpc::{JsonResponse, JsonResult},
         util::json_map,
@@ -45,15 +47,33 @@ use crate::{
 
 /// An event graph event
 pub mod event;
-pub use event::Event;
+pub use event::{topic_hash, Event};
+
+/// Chunked blobs for large event content
+pub mod blob;
+use blob::{BlobManifest, BlobStore, DEFAULT_BLOB_QUOTA};
+
+/// Authored events and redactions
+pub mod redact;
+use redact::{content_author, redaction_key, AuthoredContent, Redaction, Tombstone};
+
+/// Delivery and read acknowledgements
+pub mod ack;
+use ack::{ack_key, Ack, AckKind};
+
+/// Retention policies for events between DAG rotations
+pub mod retention;
+use retention::{RetentionMetrics, RetentionPolicy, RETENTION_INTERVAL};
 
 /// P2P protocol implementation for the Event Graph
 pub mod proto;
-use proto::{EventRep, EventReq, TipRep, TipReq};
+use proto::{
+    ChunkRep, ChunkReq, EventRep, EventReq, TipRep, TipReq, TopicRep, TopicReq, MAX_CHUNKS_PER_REQ,
+};
 
 /// Utility functions
 pub mod util;
-use util::{generate_genesis, millis_until_next_rotation, next_rotation_timestamp};
+use util::{generate_genesis, next_rotation_timestamp, topic_index_key};
 
 // Debugging event graph
 pub mod deg;
@@ -106,10 +126,26 @@ pub struct EventGraph {
     current_genesis: RwLock<Event>,
     /// Currently configured DAG rotation, in days
     days_rotation: u64,
+    /// Retention policy for events between DAG rotations
+    retention: RetentionPolicy,
+    /// Metrics on the events evicted by the retention policy
+    pub retention_metrics: RetentionMetrics,
     /// Flag signalling DAG has finished initial sync
     pub synced: RwLock<bool>,
+    /// Topics followed when selectively syncing the DAG, or `None`
+    /// when the whole DAG is synced.
+    topics: RwLock<Option<HashSet<blake3::Hash>>>,
     /// Enable graph debugging
     pub deg_enabled: RwLock<bool>,
+    /// Cache of blob chunks referenced by manifest events
+    pub blobs: BlobStore,
+    /// Redactions seen in the DAG, keyed by target event ID and author
+    redactions: sled::Tree,
+    /// Acknowledgements seen in the DAG, keyed by event ID and author
+    acks: sled::Tree,
+    /// Index of the events tagged with a topic, keyed by topic,
+    /// layer and event ID
+    topic_index: sled::Tree,
     /// The publisher for which we can give deg info over
     deg_publisher: PublisherPtr<DegEvent>,
 }
@@ -128,6 +164,7 @@ impl EventGraph {
     /// * `dag_tree_name` the name of disk-backed tree (or DAG name).
     /// * `days_rotation` marks the lifetime of the DAG before it's
     ///   pruned.
+    /// * `retention` bounds the events kept between DAG rotations.
     pub async fn new(
         p2p: P2pPtr,
         sled_db: sled::Db,
@@ -135,9 +172,18 @@ impl EventGraph {
         replay_mode: bool,
         dag_tree_name: &str,
         days_rotation: u64,
+        retention: RetentionPolicy,
         ex: Arc<Executor<'_>>,
     ) -> Result<EventGraphPtr> {
         let dag = sled_db.open_tree(dag_tree_name)?;
+        let blobs = BlobStore::new(
+            sled_db.open_tree(format!("{dag_tree_name}_chunks"))?,
+            sled_db.open_tree(format!("{dag_tree_name}_chunk_index"))?,
+            DEFAULT_BLOB_QUOTA,
+        )?;
+        let redactions = sled_db.open_tree(format!("{dag_tree_name}_redactions"))?;
+        let acks = sled_db.open_tree(format!("{dag_tree_name}_acks"))?;
+        let topic_index = sled_db.open_tree(format!("{dag_tree_name}_topics"))?;
         let unreferenced_tips = RwLock::new(BTreeMap::new());
         let broadcasted_ids = RwLock::new(HashSet::new());
         let event_pub = Publisher::new();
@@ -155,8 +201,15 @@ impl EventGraph {
             event_pub,
             current_genesis: RwLock::new(current_genesis.clone()),
             days_rotation,
+            retention,
+            retention_metrics: RetentionMetrics::default(),
             synced: RwLock::new(false),
+            topics: RwLock::new(None),
             deg_enabled: RwLock::new(false),
+            blobs,
+            redactions,
+            acks,
+            topic_index,
             deg_publisher: Publisher::new(),
         });
 
@@ -170,11 +223,16 @@ impl EventGraph {
             self_.dag_prune(current_genesis).await?;
         }
 
+        // DAGs stored before the topic index existed get it built here
+        if self_.topic_index.is_empty() {
+            self_.topic_reindex().await?;
+        }
+
         // Find the unreferenced tips in the current DAG state.
         *self_.unreferenced_tips.write().await = self_.find_unreferenced_tips().await;
 
         // Spawn the DAG pruning task
-        if days_rotation > 0 {
+        if days_rotation > 0 || self_.retention.is_bounded() {
             let prune_task = StoppableTask::new();
             let _ = self_.prune_task.set(prune_task.clone()).await;
 
@@ -198,8 +256,32 @@ impl EventGraph {
         self.days_rotation
     }
 
+    /// Check that the DAG store can be read
+    pub fn dag_readable(&self) -> bool {
+        self.dag.first().is_ok()
+    }
+
+    /// Only sync and keep the events tagged with one of the given
+    /// topics, or the whole DAG when `None`. Should be set before
+    /// the initial [`EventGraph::dag_sync`].
+    ///
+    /// A selective DAG only holds part of the graph, so the node stops
+    /// serving tips and events to peers doing a full sync.
+    pub async fn set_topics(&self, topics: Option<HashSet<blake3::Hash>>) {
+        *self.topics.write().await = topics;
+    }
+
+    /// Topics followed when selectively syncing the DAG
+    pub async fn topics(&self) -> Option<HashSet<blake3::Hash>> {
+        self.topics.read().await.clone()
+    }
+
     /// Sync the DAG from connected peers
     pub async fn dag_sync(&self) -> Result<()> {
+        if let Some(topics) = self.topics().await {
+            return self.dag_sync_topics(topics).await
+        }
+
         // We do an optimistic sync where we ask all our connected peers for
         // the latest layer DAG tips (unreferenced events) and then we accept
         // the ones we see the most times.
@@ -261,6 +343,7 @@ impl EventGraph {
                     target: "event_graph::dag_sync()",
                     "[EVENTGRAPH] Sync: Peer {url} didn't reply with tips in time, skipping"
                 );
+                channel.penalize(Misbehavior::FailedSync).await;
                 communicated_peers -= 1;
                 continue
             };
@@ -360,6 +443,7 @@ impl EventGraph {
                         target: "event_graph::dag_sync()",
                         "[EVENTGRAPH] Sync: Timeout waiting for parents {missing_parents:?} from {url}"
                     );
+                    channel.penalize(Misbehavior::FailedSync).await;
                     continue
                 };
 
@@ -439,6 +523,187 @@ impl EventGraph {
         Ok(())
     }
 
+    /// Sync only the events of the given topics from connected peers.
+    /// Each peer replies with all of its events tagged with one of the
+    /// topics, and we accept the ones we see from more than 2/3 of the
+    /// peers, same as the tips in a full sync.
+    async fn dag_sync_topics(&self, topics: HashSet<blake3::Hash>) -> Result<()> {
+        let channels = self.p2p.hosts().peers();
+        let mut communicated_peers = channels.len();
+        info!(
+            target: "event_graph::dag_sync_topics()",
+            "[EVENTGRAPH] Syncing {} topics from {communicated_peers} peers...", topics.len(),
+        );
+
+        let request: Vec<_> = topics.iter().copied().collect();
+
+        // Here we keep track of the events and how many times we've seen them
+        let mut seen_events: HashMap<blake3::Hash, (Event, usize)> = HashMap::new();
+
+        for channel in channels.iter() {
+            let url = channel.address();
+
+            let topic_rep_sub = match channel.subscribe_msg::<TopicRep>().await {
+                Ok(v) => v,
+                Err(e) => {
+                    error!(
+                        target: "event_graph::dag_sync_topics()",
+                        "[EVENTGRAPH] Sync: Couldn't subscribe TopicRep for peer {url}, skipping ({e})"
+                    );
+                    communicated_peers -= 1;
+                    continue
+                }
+            };
+
+            if let Err(e) = channel.send(&TopicReq(request.clone())).await {
+                error!(
+                    target: "event_graph::dag_sync_topics()",
+                    "[EVENTGRAPH] Sync: Couldn't contact peer {url}, skipping ({e})"
+                );
+                communicated_peers -= 1;
+                continue
+            };
+
+            // Node waits for response
+            let Ok(peer_events) = topic_rep_sub
+                .receive_with_timeout(self.p2p.settings().read().await.outbound_connect_timeout)
+                .await
+            else {
+                error!(
+                    target: "event_graph::dag_sync_topics()",
+                    "[EVENTGRAPH] Sync: Peer {url} didn't reply with topic events in time, skipping"
+                );
+                channel.penalize(Misbehavior::FailedSync).await;
+                communicated_peers -= 1;
+                continue
+            };
+
+            // A peer only gets counted once per event
+            let mut peer_ids = HashSet::new();
+            for event in peer_events.0.iter() {
+                if !topics.contains(&event.topic) {
+                    error!(
+                        target: "event_graph::dag_sync_topics()",
+                        "[EVENTGRAPH] Sync: Peer {url} replied with an event of another topic: {}",
+                        event.id()
+                    );
+                    continue
+                }
+
+                let event_id = event.id();
+                if !peer_ids.insert(event_id) {
+                    continue
+                }
+
+                if let Some(seen_event) = seen_events.get_mut(&event_id) {
+                    seen_event.1 += 1;
+                } else {
+                    seen_events.insert(event_id, (event.clone(), 1));
+                }
+            }
+        }
+
+        if communicated_peers == 0 {
+            error!(
+                target: "event_graph::dag_sync_topics()",
+                "[EVENTGRAPH] Sync: Could not sync topics from any peer",
+            );
+            return Err(Error::DagSyncFailed)
+        }
+
+        // Insert the considered events we don't have yet, ordered by layer
+        let consideration_threshold = communicated_peers * 2 / 3;
+        let mut events: Vec<Event> = seen_events
+            .into_iter()
+            .filter(|(id, (_, amount))| {
+                amount > &consideration_threshold && !self.dag.contains_key(id.as_bytes()).unwrap()
+            })
+            .map(|(_, (event, _))| event)
+            .collect();
+        events.sort_by(|a, b| a.layer.cmp(&b.layer));
+
+        info!(
+            target: "event_graph::dag_sync_topics()",
+            "[EVENTGRAPH] Inserting {} topic events", events.len(),
+        );
+        self.dag_insert(&events).await?;
+
+        *self.synced.write().await = true;
+
+        info!(target: "event_graph::dag_sync_topics()", "[EVENTGRAPH] DAG synced successfully!");
+        Ok(())
+    }
+
+    /// Fetch the chunks of a blob we don't have yet from connected peers,
+    /// then reassemble it.
+    pub async fn blob_fetch(&self, manifest: &BlobManifest) -> Result<Vec<u8>> {
+        let mut missing = self.blobs.missing(manifest)?;
+
+        for channel in self.p2p.hosts().peers().iter() {
+            if missing.is_empty() {
+                break
+            }
+            let url = channel.address();
+
+            let chunk_rep_sub = match channel.subscribe_msg::<ChunkRep>().await {
+                Ok(v) => v,
+                Err(e) => {
+                    error!(
+                        target: "event_graph::blob_fetch()",
+                        "[EVENTGRAPH] Couldn't subscribe ChunkRep for peer {url}, skipping ({e})"
+                    );
+                    continue
+                }
+            };
+
+            for request in missing.clone().chunks(MAX_CHUNKS_PER_REQ) {
+                if let Err(e) = channel.send(&ChunkReq(request.to_vec())).await {
+                    error!(
+                        target: "event_graph::blob_fetch()",
+                        "[EVENTGRAPH] Couldn't contact peer {url}, skipping ({e})"
+                    );
+                    break
+                }
+
+                let Ok(chunks) = chunk_rep_sub
+                    .receive_with_timeout(self.p2p.settings().read().await.outbound_connect_timeout)
+                    .await
+                else {
+                    debug!(
+                        target: "event_graph::blob_fetch()",
+                        "Peer {url} didn't reply with chunks in time, skipping"
+                    );
+                    break
+                };
+
+                // Peers only reply with the chunks they have, and we
+                // only keep the ones we asked for.
+                for chunk in chunks.0.iter() {
+                    let hash = blake3::hash(chunk);
+                    if !request.contains(&hash) {
+                        warn!(
+                            target: "event_graph::blob_fetch()",
+                            "[EVENTGRAPH] Peer {url} replied with an unrequested chunk {hash}"
+                        );
+                        continue
+                    }
+                    self.blobs.insert(chunk)?;
+                    missing.retain(|h| h != &hash);
+                }
+            }
+        }
+
+        if !missing.is_empty() {
+            error!(
+                target: "event_graph::blob_fetch()",
+                "[EVENTGRAPH] Could not find {} chunks of blob {}", missing.len(), manifest.id(),
+            );
+            return Err(Error::BlobChunkMissing)
+        }
+
+        self.blobs.reassemble(manifest)
+    }
+
     /// Atomically prune the DAG and insert the given event as genesis.
     async fn dag_prune(&self, genesis_event: Event) -> Result<()> {
         debug!(target: "event_graph::dag_prune()", "Pruning DAG...");
@@ -464,6 +729,12 @@ impl EventGraph {
             panic!("Failed pruning DAG, sled apply_batch error: {e}");
         }
 
+        // The manifests are gone, so are the chunks they referenced
+        self.blobs.clear()?;
+        self.redactions.clear()?;
+        self.acks.clear()?;
+        self.topic_index.clear()?;
+
         // Clear unreferenced tips and bcast ids
         *unreferenced_tips = BTreeMap::new();
         unreferenced_tips.insert(0, HashSet::from([genesis_event.id()]));
@@ -483,36 +754,153 @@ impl EventGraph {
         // parameter. By pruning, we should deterministically replace the
         // genesis event (can use a deterministic timestamp) and drop everything
         // in the DAG, leaving just the new genesis event.
+        // In between rotations, a bounded retention policy is enforced every
+        // `RETENTION_INTERVAL`.
         debug!(target: "event_graph::dag_prune_task()", "Spawned background DAG pruning task");
 
+        // Find the next rotation timestamp, if the DAG rotates at all
+        let mut next_rotation =
+            (days_rotation > 0).then(|| next_rotation_timestamp(INITIAL_GENESIS, days_rotation));
+
         loop {
-            // Find the next rotation timestamp:
-            let next_rotation = next_rotation_timestamp(INITIAL_GENESIS, days_rotation);
-
-            // Prepare the new genesis event
-            let current_genesis = Event {
-                timestamp: next_rotation,
-                content: GENESIS_CONTENTS.to_vec(),
-                parents: [NULL_ID; N_EVENT_PARENTS],
-                layer: 0,
+            // Sleep until it's time to rotate, or to enforce the retention policy
+            let now = UNIX_EPOCH.elapsed().unwrap().as_millis() as u64;
+            let mut s = match next_rotation {
+                Some(rotation) => rotation.saturating_sub(now),
+                None => RETENTION_INTERVAL,
             };
-
-            // Sleep until it's time to rotate.
-            let s = millis_until_next_rotation(next_rotation);
+            if self.retention.is_bounded() {
+                s = s.min(RETENTION_INTERVAL);
+            }
 
             debug!(target: "event_graph::dag_prune_task()", "Sleeping {s}ms until next DAG prune");
             msleep(s).await;
-            debug!(target: "event_graph::dag_prune_task()", "Rotation period reached");
 
-            // Trigger DAG prune
-            self.dag_prune(current_genesis).await?;
+            let now = UNIX_EPOCH.elapsed().unwrap().as_millis() as u64;
+            match next_rotation {
+                Some(rotation) if now >= rotation => {
+                    debug!(target: "event_graph::dag_prune_task()", "Rotation period reached");
+
+                    // Prepare the new genesis event
+                    let current_genesis = Event {
+                        timestamp: rotation,
+                        content: GENESIS_CONTENTS.to_vec(),
+                        parents: [NULL_ID; N_EVENT_PARENTS],
+                        layer: 0,
+                        topic: NULL_ID,
+                    };
+
+                    // Trigger DAG prune
+                    self.dag_prune(current_genesis).await?;
+                    next_rotation = Some(next_rotation_timestamp(INITIAL_GENESIS, days_rotation));
+                }
+                _ => self.dag_enforce_retention().await?,
+            }
         }
     }
 
+    /// Evict the events the retention policy no longer allows us to keep.
+    /// Events older than their maximum age go first, then the oldest ones
+    /// until the DAG fits the maximum size. The genesis event and the
+    /// unreferenced tips are always kept, as new events build on them.
+    pub async fn dag_enforce_retention(&self) -> Result<()> {
+        let unreferenced_tips = self.unreferenced_tips.read().await;
+        let mut broadcasted_ids = self.broadcasted_ids.write().await;
+        let genesis_id = self.current_genesis.read().await.id();
+        let now = UNIX_EPOCH.elapsed().unwrap().as_millis() as u64;
+
+        let is_tip = |id: &blake3::Hash| unreferenced_tips.values().any(|tips| tips.contains(id));
+
+        // Go through the DAG, evicting expired events and noting down
+        // the size and age of the rest
+        let mut batch = sled::Batch::default();
+        let mut index_batch = sled::Batch::default();
+        let mut evicted = vec![];
+        let (mut by_age, mut evicted_bytes, mut dag_size) = (0, 0, 0);
+        let mut kept = vec![];
+        for item in self.dag.iter() {
+            let (key, value) = item?;
+            let event: Event = deserialize_async(&value).await?;
+            let event_id = event.id();
+            let size = (key.len() + value.len()) as u64;
+
+            if event_id == genesis_id || is_tip(&event_id) {
+                dag_size += size;
+                continue
+            }
+
+            let expired = match self.retention.max_age_millis(&event.topic) {
+                Some(max_age) => now.saturating_sub(event.timestamp) > max_age,
+                None => false,
+            };
+
+            let index_key = event.is_tagged().then(|| topic_index_key(&event));
+
+            if expired {
+                batch.remove(key);
+                if let Some(index_key) = index_key {
+                    index_batch.remove(index_key);
+                }
+                evicted.push(event_id);
+                by_age += 1;
+                evicted_bytes += size;
+                continue
+            }
+
+            dag_size += size;
+            kept.push((event.timestamp, key, size, index_key));
+        }
+
+        // Evict the oldest remaining events until we fit the size limit
+        let mut by_size = 0;
+        if let Some(max_size) = self.retention.max_size {
+            kept.sort_unstable_by_key(|(timestamp, _, _, _)| *timestamp);
+            for (_, key, size, index_key) in kept {
+                if dag_size <= max_size {
+                    break
+                }
+                evicted.push(blake3::Hash::from_bytes((&key as &[u8]).try_into().unwrap()));
+                batch.remove(key);
+                if let Some(index_key) = index_key {
+                    index_batch.remove(index_key);
+                }
+                by_size += 1;
+                evicted_bytes += size;
+                dag_size -= size;
+            }
+        }
+
+        if !evicted.is_empty() {
+            info!(
+                target: "event_graph::dag_enforce_retention()",
+                "[EVENTGRAPH] Evicting {} events ({evicted_bytes} bytes) from the DAG",
+                evicted.len(),
+            );
+
+            if let Err(e) = self.dag.apply_batch(batch) {
+                panic!("Failed applying dag_enforce_retention batch to sled: {e}");
+            }
+            self.topic_index.apply_batch(index_batch)?;
+
+            for event_id in &evicted {
+                broadcasted_ids.remove(event_id);
+            }
+        }
+
+        self.retention_metrics.record(by_age, by_size, evicted_bytes, dag_size);
+
+        drop(unreferenced_tips);
+        drop(broadcasted_ids);
+
+        Ok(())
+    }
+
     /// Atomically insert given events into the DAG and return the event IDs.
     /// All provided events must be valid. An overlay is used over the DAG tree,
     /// temporary writting each event in order. After all events have been
     /// validated and inserted successfully, we write the overlay to sled.
+    /// Authored events redacted by their author are stored as tombstones,
+    /// and a tombstone is only accepted when its redaction is known.
     /// This will append the new events into the unreferenced tips set, and
     /// remove the events' parents from it. It will also append the events'
     /// level-1 parents to the `broadcasted_ids` set, so the P2P protocol
@@ -538,6 +926,22 @@ impl EventGraph {
         // Grab genesis timestamp
         let genesis_timestamp = self.current_genesis.read().await.timestamp;
 
+        // A selective DAG is missing the parents from other topics,
+        // and a bounded one the parents it evicted
+        let selective = self.topics.read().await.is_some() || self.retention.is_bounded();
+
+        // Redactions in this batch, so a tombstone can be synced along
+        // with the redaction of its event
+        let batch_redactions: HashSet<Vec<u8>> = events
+            .iter()
+            .filter_map(|event| Redaction::from_content(&event.content))
+            .filter(|redaction| redaction.verify())
+            .map(|redaction| redaction_key(&redaction.target, &redaction.author))
+            .collect();
+
+        // Here we keep the events in the form they got stored
+        let mut stored = Vec::with_capacity(events.len());
+
         // Iterate over given events to validate them and
         // write them to the overlay
         for event in events {
@@ -547,15 +951,48 @@ impl EventGraph {
                 "Inserting event {event_id} into the DAG"
             );
 
-            if !event
-                .validate(&self.dag, genesis_timestamp, self.days_rotation, Some(&overlay))
-                .await?
-            {
+            let valid = if selective {
+                event
+                    .validate_partial(
+                        &self.dag,
+                        genesis_timestamp,
+                        self.days_rotation,
+                        Some(&overlay),
+                    )
+                    .await?
+            } else {
+                event
+                    .validate(&self.dag, genesis_timestamp, self.days_rotation, Some(&overlay))
+                    .await?
+            };
+
+            if !valid {
                 error!(target: "event_graph::dag_insert()", "Event {event_id} is invalid!");
                 return Err(Error::EventIsInvalid)
             }
 
-            let event_se = serialize_async(event).await;
+            // Authored content already redacted by its author is stored
+            // as a tombstone right away.
+            let mut event = event.clone();
+            if let Some(author) = content_author(&event.content) {
+                let key = redaction_key(&event_id, &author);
+                let redacted =
+                    batch_redactions.contains(&key) || self.redactions.contains_key(&key)?;
+
+                if let Some(authored) = AuthoredContent::from_content(&event.content) {
+                    if redacted {
+                        event.content = authored.tombstone().to_content();
+                    }
+                } else if !redacted {
+                    error!(
+                        target: "event_graph::dag_insert()",
+                        "Event {event_id} is a tombstone without a redaction",
+                    );
+                    return Err(Error::EventIsInvalid)
+                }
+            }
+
+            let event_se = serialize_async(&event).await;
 
             // Add the event to the overlay
             overlay.insert(event_id.as_bytes(), &event_se)?;
@@ -563,8 +1000,18 @@ impl EventGraph {
             if self.replay_mode {
                 replayer_log(&self.datastore, "insert".to_owned(), event_se)?;
             }
+
+            // Redact the target event if we have it and it belongs
+            // to the redaction author
+            if let Some(redaction) = Redaction::from_content(&event.content) {
+                if redaction.verify() {
+                    self.redact(&mut overlay, &redaction).await?;
+                }
+            }
+
             // Note down the event ID to return
             ids.push(event_id);
+            stored.push(event);
         }
 
         // Aggregate changes into a single batch
@@ -576,9 +1023,30 @@ impl EventGraph {
             panic!("Failed applying dag_insert batch to sled: {e}");
         }
 
-        // Iterate over given events to update references and
+        // Remember the redactions, in case their target arrives later
+        for key in batch_redactions {
+            self.redactions.insert(key, vec![])?;
+        }
+
+        // Index the tagged events
+        for event in stored.iter().filter(|event| event.is_tagged()) {
+            self.topic_index.insert(topic_index_key(event), vec![])?;
+        }
+
+        // Record the acknowledgements
+        for event in &stored {
+            let Some(ack) = Ack::from_content(&event.content) else { continue };
+            if !ack.verify() {
+                continue
+            }
+            for event_id in &ack.events {
+                self.ack_record(event_id, &ack.author, ack.kind)?;
+            }
+        }
+
+        // Iterate over stored events to update references and
         // send out notifications about them
-        for event in events {
+        for event in &stored {
             let event_id = event.id();
 
             // Update the unreferenced DAG tips set
@@ -632,6 +1100,64 @@ impl EventGraph {
         Ok(ids)
     }
 
+    /// Replace the content of the event targeted by the given redaction
+    /// with its tombstone in the overlay. Events of other authors, or
+    /// events we don't have, are left untouched.
+    async fn redact(&self, overlay: &mut SledTreeOverlay, redaction: &Redaction) -> Result<()> {
+        let Some(bytes) = overlay.get(redaction.target.as_bytes())? else { return Ok(()) };
+        let mut target: Event = deserialize_async(&bytes).await?;
+
+        let Some(authored) = AuthoredContent::from_content(&target.content) else { return Ok(()) };
+        if authored.author != redaction.author {
+            warn!(
+                target: "event_graph::redact()",
+                "[EVENTGRAPH] Ignoring redaction of {} by someone else than its author",
+                redaction.target,
+            );
+            return Ok(())
+        }
+
+        debug!(target: "event_graph::redact()", "Redacting event {}", redaction.target);
+        target.content = authored.tombstone().to_content();
+        let target_se = serialize_async(&target).await;
+        overlay.insert(redaction.target.as_bytes(), &target_se)?;
+
+        if self.replay_mode {
+            replayer_log(&self.datastore, "insert".to_owned(), target_se)?;
+        }
+
+        Ok(())
+    }
+
+    /// Record an acknowledgement, keeping the furthest kind per identity
+    fn ack_record(&self, event_id: &blake3::Hash, author: &PublicKey, kind: AckKind) -> Result<()> {
+        let key = ack_key(event_id, author);
+        if let Some(prev) = self.acks.get(&key)? {
+            if deserialize::<AckKind>(&prev)? >= kind {
+                return Ok(())
+            }
+        }
+        self.acks.insert(key, serialize(&kind))?;
+        Ok(())
+    }
+
+    /// Identities which acknowledged the given event, along with how far
+    /// they got with it. The event itself does not need to be in our DAG.
+    pub fn ack_status(&self, event_id: &blake3::Hash) -> Result<Vec<(PublicKey, AckKind)>> {
+        let mut ret = vec![];
+        for item in self.acks.scan_prefix(event_id.as_bytes()) {
+            let (key, value) = item?;
+            ret.push((deserialize(&key[blake3::OUT_LEN..])?, deserialize(&value)?));
+        }
+        Ok(ret)
+    }
+
+    /// Check if an event in the DAG has been redacted by its author
+    pub async fn is_redacted(&self, event_id: &blake3::Hash) -> Result<bool> {
+        let Some(event) = self.dag_get(event_id).await? else { return Ok(false) };
+        Ok(Tombstone::from_content(&event.content).is_some())
+    }
+
     /// Fetch an event from the DAG
     pub async fn dag_get(&self, event_id: &blake3::Hash) -> Result<Option<Event>> {
         let Some(bytes) = self.dag.get(event_id.as_bytes())? else { return Ok(None) };
@@ -812,13 +1338,60 @@ impl EventGraph {
                 (key, value)
             })
             .collect();
-        let values = json_map([("dag", JsonValue::Object(json_graph))]);
+        let values = json_map([
+            ("dag", JsonValue::Object(json_graph)),
+            ("retention", self.retention_metrics.to_json()),
+        ]);
 
         let result = JsonValue::Object(HashMap::from([("eventgraph_info".to_string(), values)]));
 
         JsonResponse::new(result, id).into()
     }
 
+    /// Fetch all the events tagged with one of the given topics,
+    /// ordered by their layer.
+    pub async fn fetch_topic_events(
+        &self,
+        topics: &HashSet<blake3::Hash>,
+        limit: usize,
+    ) -> Result<Vec<Event>> {
+        let mut result = vec![];
+        for topic in topics {
+            // Untagged events are not indexed
+            if topic == &NULL_ID {
+                continue
+            }
+
+            // Walk the topic index from its highest layer down
+            for key in self.topic_index.scan_prefix(topic.as_bytes()).keys().rev().take(limit) {
+                let key = key?;
+                let event_id = &key[blake3::OUT_LEN + 8..];
+                let Some(event) = self.dag.get(event_id)? else { continue };
+                result.push(deserialize_async::<Event>(&event).await?);
+            }
+        }
+
+        // Keep the most recent events, ordered by their layer
+        result.sort_by(|a, b| b.layer.cmp(&a.layer));
+        result.truncate(limit);
+        result.reverse();
+
+        Ok(result)
+    }
+
+    /// Rebuild the topic index from the events in the DAG
+    async fn topic_reindex(&self) -> Result<()> {
+        self.topic_index.clear()?;
+        for item in self.dag.iter() {
+            let (_, value) = item?;
+            let event: Event = deserialize_async(&value).await?;
+            if event.is_tagged() {
+                self.topic_index.insert(topic_index_key(&event), vec![])?;
+            }
+        }
+        Ok(())
+    }
+
     /// Fetch all the events that are on a higher layers than the
     /// provided ones.
     pub async fn fetch_successors_of(
@@ -24,16 +24,16 @@ use std::{
     },
 };
 
-use darkfi_serial::{async_trait, SerialDecodable, SerialEncodable};
+use darkfi_serial::{async_trait, serialize_async, SerialDecodable, SerialEncodable};
 use log::{debug, error, trace, warn};
 use smol::Executor;
 
-use super::{Event, EventGraphPtr, NULL_ID};
+use super::{blob::BLOB_CHUNK_SIZE, Event, EventGraphPtr, NULL_ID};
 use crate::{
     impl_p2p_message,
     net::{
         metering::{MeteringConfiguration, DEFAULT_METERING_CONFIGURATION},
-        ChannelPtr, Message, MessageSubscription, ProtocolBase, ProtocolBasePtr,
+        ChannelPtr, Message, MessagePriority, MessageSubscription, ProtocolBase, ProtocolBasePtr,
         ProtocolJobsManager, ProtocolJobsManagerPtr,
     },
     system::msleep,
@@ -45,6 +45,15 @@ use crate::{
 /// drop the peer from our P2P connection.
 const MALICIOUS_THRESHOLD: usize = 5;
 
+/// Maximum number of topics a peer can request at once
+const MAX_TOPICS: usize = 64;
+
+/// Maximum number of events replied to a `TopicReq`
+pub const MAX_TOPIC_EVENTS: usize = 2048;
+
+/// Maximum number of blob chunks a peer can request at once
+pub const MAX_CHUNKS_PER_REQ: usize = 8;
+
 /// Global limit of messages per window
 const WINDOW_MAXSIZE: usize = 200;
 /// Rolling length of the window
@@ -112,6 +121,14 @@ pub struct ProtocolEventGraph {
     tip_req_sub: MessageSubscription<TipReq>,
     /// `MessageSubscriber` for `TipRep`
     _tip_rep_sub: MessageSubscription<TipRep>,
+    /// `MessageSubscriber` for `TopicReq`
+    topic_req_sub: MessageSubscription<TopicReq>,
+    /// `MessageSubscriber` for `TopicRep`
+    _topic_rep_sub: MessageSubscription<TopicRep>,
+    /// `MessageSubscriber` for `ChunkReq`
+    chunk_req_sub: MessageSubscription<ChunkReq>,
+    /// `MessageSubscriber` for `ChunkRep`
+    _chunk_rep_sub: MessageSubscription<ChunkRep>,
     /// Peer malicious message count
     malicious_count: AtomicUsize,
     /// P2P jobs manager pointer
@@ -126,27 +143,140 @@ pub struct ProtocolEventGraph {
 /// A P2P message representing publishing an event on the network
 #[derive(Clone, SerialEncodable, SerialDecodable)]
 pub struct EventPut(pub Event);
-impl_p2p_message!(EventPut, "EventGraph::EventPut", 0, 0, DEFAULT_METERING_CONFIGURATION);
+impl_p2p_message!(
+    EventPut,
+    "EventGraph::EventPut",
+    0,
+    0,
+    DEFAULT_METERING_CONFIGURATION,
+    MessagePriority::Gossip
+);
 
 /// A P2P message representing an event request
 #[derive(Clone, SerialEncodable, SerialDecodable)]
 pub struct EventReq(pub Vec<blake3::Hash>);
-impl_p2p_message!(EventReq, "EventGraph::EventReq", 0, 0, DEFAULT_METERING_CONFIGURATION);
+impl_p2p_message!(
+    EventReq,
+    "EventGraph::EventReq",
+    0,
+    0,
+    DEFAULT_METERING_CONFIGURATION,
+    MessagePriority::Gossip
+);
 
 /// A P2P message representing an event reply
 #[derive(Clone, SerialEncodable, SerialDecodable)]
 pub struct EventRep(pub Vec<Event>);
-impl_p2p_message!(EventRep, "EventGraph::EventRep", 0, 0, DEFAULT_METERING_CONFIGURATION);
+impl_p2p_message!(
+    EventRep,
+    "EventGraph::EventRep",
+    0,
+    0,
+    DEFAULT_METERING_CONFIGURATION,
+    MessagePriority::Gossip
+);
 
 /// A P2P message representing a request for a peer's DAG tips
 #[derive(Clone, SerialEncodable, SerialDecodable)]
 pub struct TipReq {}
-impl_p2p_message!(TipReq, "EventGraph::TipReq", 0, 0, DEFAULT_METERING_CONFIGURATION);
+impl_p2p_message!(
+    TipReq,
+    "EventGraph::TipReq",
+    0,
+    0,
+    DEFAULT_METERING_CONFIGURATION,
+    MessagePriority::Gossip
+);
 
 /// A P2P message representing a reply for the peer's DAG tips
 #[derive(Clone, SerialEncodable, SerialDecodable)]
 pub struct TipRep(pub BTreeMap<u64, HashSet<blake3::Hash>>);
-impl_p2p_message!(TipRep, "EventGraph::TipRep", 0, 0, DEFAULT_METERING_CONFIGURATION);
+impl_p2p_message!(
+    TipRep,
+    "EventGraph::TipRep",
+    0,
+    0,
+    DEFAULT_METERING_CONFIGURATION,
+    MessagePriority::Gossip
+);
+
+/// A P2P message representing a request for all events tagged with
+/// one of the given topics
+#[derive(Clone, SerialEncodable, SerialDecodable)]
+pub struct TopicReq(pub Vec<blake3::Hash>);
+
+/// Topic requests are only sent once per sync, so peers sending
+/// more than a couple of them per minute get throttled.
+pub const TOPIC_REQ_METERING_CONFIGURATION: MeteringConfiguration = MeteringConfiguration {
+    threshold: 2,
+    sleep_step: 10_000,
+    expiry_time: NanoTimestamp::from_secs(60),
+};
+
+/// TopicReq message fields size:
+/// * topics = 9 (vec_len) + MAX_TOPICS * 32
+pub const TOPIC_REQ_MAX_BYTES: u64 = 9 + (MAX_TOPICS * blake3::OUT_LEN) as u64;
+
+impl_p2p_message!(
+    TopicReq,
+    "EventGraph::TopicReq",
+    TOPIC_REQ_MAX_BYTES,
+    1,
+    TOPIC_REQ_METERING_CONFIGURATION,
+    MessagePriority::Gossip
+);
+
+/// A P2P message representing a reply with the requested topics' events
+#[derive(Clone, SerialEncodable, SerialDecodable)]
+pub struct TopicRep(pub Vec<Event>);
+
+/// Upper bound of a `TopicRep` message. Replies get trimmed
+/// down to their most recent events to fit in it.
+pub const TOPIC_REP_MAX_BYTES: u64 = 16 * 1024 * 1024;
+
+impl_p2p_message!(
+    TopicRep,
+    "EventGraph::TopicRep",
+    TOPIC_REP_MAX_BYTES,
+    1,
+    DEFAULT_METERING_CONFIGURATION,
+    MessagePriority::Gossip
+);
+
+/// A P2P message representing a request for blob chunks
+#[derive(Clone, SerialEncodable, SerialDecodable)]
+pub struct ChunkReq(pub Vec<blake3::Hash>);
+
+/// ChunkReq message fields size:
+/// * hashes = 9 (vec_len) + MAX_CHUNKS_PER_REQ * 32
+pub const CHUNK_REQ_MAX_BYTES: u64 = 9 + (MAX_CHUNKS_PER_REQ * blake3::OUT_LEN) as u64;
+
+impl_p2p_message!(
+    ChunkReq,
+    "EventGraph::ChunkReq",
+    CHUNK_REQ_MAX_BYTES,
+    1,
+    DEFAULT_METERING_CONFIGURATION,
+    MessagePriority::Gossip
+);
+
+/// A P2P message representing a reply with the requested blob chunks
+/// we have
+#[derive(Clone, SerialEncodable, SerialDecodable)]
+pub struct ChunkRep(pub Vec<Vec<u8>>);
+
+/// ChunkRep message fields size:
+/// * chunks = 9 (vec_len) + MAX_CHUNKS_PER_REQ * (9 (vec_len) + BLOB_CHUNK_SIZE)
+pub const CHUNK_REP_MAX_BYTES: u64 = 9 + (MAX_CHUNKS_PER_REQ * (9 + BLOB_CHUNK_SIZE)) as u64;
+
+impl_p2p_message!(
+    ChunkRep,
+    "EventGraph::ChunkRep",
+    CHUNK_REP_MAX_BYTES,
+    1,
+    DEFAULT_METERING_CONFIGURATION,
+    MessagePriority::Gossip
+);
 
 #[async_trait]
 impl ProtocolBase for ProtocolEventGraph {
@@ -155,6 +285,8 @@ impl ProtocolBase for ProtocolEventGraph {
         self.jobsman.clone().spawn(self.clone().handle_event_put(), ex.clone()).await;
         self.jobsman.clone().spawn(self.clone().handle_event_req(), ex.clone()).await;
         self.jobsman.clone().spawn(self.clone().handle_tip_req(), ex.clone()).await;
+        self.jobsman.clone().spawn(self.clone().handle_topic_req(), ex.clone()).await;
+        self.jobsman.clone().spawn(self.clone().handle_chunk_req(), ex.clone()).await;
         self.jobsman.clone().spawn(self.clone().broadcast_rate_limiter(), ex.clone()).await;
         Ok(())
     }
@@ -172,12 +304,20 @@ impl ProtocolEventGraph {
         msg_subsystem.add_dispatch::<EventRep>().await;
         msg_subsystem.add_dispatch::<TipReq>().await;
         msg_subsystem.add_dispatch::<TipRep>().await;
+        msg_subsystem.add_dispatch::<TopicReq>().await;
+        msg_subsystem.add_dispatch::<TopicRep>().await;
+        msg_subsystem.add_dispatch::<ChunkReq>().await;
+        msg_subsystem.add_dispatch::<ChunkRep>().await;
 
         let ev_put_sub = channel.subscribe_msg::<EventPut>().await?;
         let ev_req_sub = channel.subscribe_msg::<EventReq>().await?;
         let ev_rep_sub = channel.subscribe_msg::<EventRep>().await?;
         let tip_req_sub = channel.subscribe_msg::<TipReq>().await?;
         let _tip_rep_sub = channel.subscribe_msg::<TipRep>().await?;
+        let topic_req_sub = channel.subscribe_msg::<TopicReq>().await?;
+        let _topic_rep_sub = channel.subscribe_msg::<TopicRep>().await?;
+        let chunk_req_sub = channel.subscribe_msg::<ChunkReq>().await?;
+        let _chunk_rep_sub = channel.subscribe_msg::<ChunkRep>().await?;
 
         let (broadcaster_push, broadcaster_pull) = smol::channel::unbounded();
 
@@ -189,6 +329,10 @@ impl ProtocolEventGraph {
             ev_rep_sub,
             tip_req_sub,
             _tip_rep_sub,
+            topic_req_sub,
+            _topic_rep_sub,
+            chunk_req_sub,
+            _chunk_rep_sub,
             malicious_count: AtomicUsize::new(0),
             jobsman: ProtocolJobsManager::new("ProtocolEventGraph", channel.clone()),
             broadcaster_push,
@@ -242,6 +386,18 @@ impl ProtocolEventGraph {
                 continue
             }
 
+            // When selectively syncing, we only care about our topics
+            let topics = self.event_graph.topics().await;
+            if let Some(topics) = &topics {
+                if !topics.contains(&event.topic) {
+                    debug!(
+                        target: "event_graph::protocol::handle_event_put()",
+                        "Event {} is not in a followed topic", event.id(),
+                    );
+                    continue
+                }
+            }
+
             // If we have already seen the event, we'll stay quiet.
             let event_id = event.id();
             if self.event_graph.dag.contains_key(event_id.as_bytes()).unwrap() {
@@ -297,11 +453,13 @@ impl ProtocolEventGraph {
                 "Event {event_id} is new"
             );
 
+            // A selective DAG does not hold the parents from other topics,
+            // so we don't go fetching them.
             let mut missing_parents = HashSet::new();
             for parent_id in event.parents.iter() {
                 // `event.validate_new()` should have already made sure that
                 // not all parents are NULL, and that there are no duplicates.
-                if parent_id == &NULL_ID {
+                if parent_id == &NULL_ID || topics.is_some() {
                     continue
                 }
 
@@ -460,6 +618,15 @@ impl ProtocolEventGraph {
                 continue
             }
 
+            // A selective DAG can't serve the parents of its events
+            if self.event_graph.topics().await.is_some() {
+                debug!(
+                    target: "event_graph::protocol::handle_event_req()",
+                    "DAG is selectively synced, skipping..."
+                );
+                continue
+            }
+
             // We received an event request from somebody.
             // If we do have it, we will send it back to them as `EventRep`.
             // Otherwise, we'll stay quiet. An honest node should always have
@@ -556,6 +723,15 @@ impl ProtocolEventGraph {
                 continue
             }
 
+            // The tips of a selective DAG are not the tips of the whole DAG
+            if self.event_graph.topics().await.is_some() {
+                debug!(
+                    target: "event_graph::protocol::handle_tip_req()",
+                    "DAG is selectively synced, skipping..."
+                );
+                continue
+            }
+
             // TODO: Rate limit
 
             // We received a tip request. Let's find them, add them to
@@ -573,6 +749,86 @@ impl ProtocolEventGraph {
         }
     }
 
+    /// Protocol function handling `TopicReq`.
+    /// This is triggered when someone selectively syncing the DAG
+    /// requests the events of their topics.
+    async fn handle_topic_req(self: Arc<Self>) -> Result<()> {
+        loop {
+            let topics = match self.topic_req_sub.receive().await {
+                Ok(v) => v.0.clone(),
+                Err(_) => continue,
+            };
+            trace!(
+                target: "event_graph::protocol::handle_topic_req()",
+                "Got TopicReq: {topics:?} [{}]", self.channel.address(),
+            );
+
+            // Check if node has finished syncing its DAG
+            if !*self.event_graph.synced.read().await {
+                debug!(
+                    target: "event_graph::protocol::handle_topic_req()",
+                    "DAG is still syncing, skipping..."
+                );
+                continue
+            }
+
+            // Untagged events can't be requested by topic
+            if topics.len() > MAX_TOPICS || topics.contains(&NULL_ID) {
+                self.clone().increase_malicious_count().await?;
+                continue
+            }
+
+            // A selective DAG still has every event of its own topics,
+            // so we reply with whatever we have for the requested ones.
+            let topics = topics.into_iter().collect();
+            let events = self.event_graph.fetch_topic_events(&topics, MAX_TOPIC_EVENTS).await?;
+
+            // Drop the oldest events until the reply fits its size limit
+            let mut size = 9;
+            let mut first = events.len();
+            while first > 0 {
+                let event_size = serialize_async(&events[first - 1]).await.len() as u64;
+                if size + event_size > TOPIC_REP_MAX_BYTES {
+                    break
+                }
+                size += event_size;
+                first -= 1;
+            }
+
+            self.channel.send(&TopicRep(events[first..].to_vec())).await?;
+        }
+    }
+
+    /// Protocol function handling `ChunkReq`.
+    /// This is triggered when someone fetches the chunks of a blob.
+    /// We reply with the ones we have in our cache.
+    async fn handle_chunk_req(self: Arc<Self>) -> Result<()> {
+        loop {
+            let hashes = match self.chunk_req_sub.receive().await {
+                Ok(v) => v.0.clone(),
+                Err(_) => continue,
+            };
+            trace!(
+                target: "event_graph::protocol::handle_chunk_req()",
+                "Got ChunkReq: {hashes:?} [{}]", self.channel.address(),
+            );
+
+            if hashes.len() > MAX_CHUNKS_PER_REQ {
+                self.clone().increase_malicious_count().await?;
+                continue
+            }
+
+            let mut chunks = vec![];
+            for hash in hashes.iter() {
+                if let Some(chunk) = self.event_graph.blobs.get(hash)? {
+                    chunks.push(chunk);
+                }
+            }
+
+            self.channel.send(&ChunkRep(chunks)).await?;
+        }
+    }
+
     /// We need to rate limit message propagation so malicious nodes don't get us banned
     /// for flooding. We do that by aggregating messages here into a queue then apply
     /// rate limit logic before broadcasting.
@@ -0,0 +1,253 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Authored events and redactions.
+//!
+//! Event content can be signed by its author using [`AuthoredContent`].
+//! The author can later publish a [`Redaction`] event, telling honest
+//! nodes to delete the content of the referenced event and stop serving
+//! it. A redacted event stays in the DAG as a [`Tombstone`], holding the
+//! author and a hash of the removed content. The event ID is computed
+//! over the tombstone form of authored content, so a tombstone has the
+//! same ID as the original event and the DAG linkage is preserved.
+
+use darkfi_sdk::crypto::{
+    schnorr::{SchnorrPublic, SchnorrSecret, Signature},
+    PublicKey, SecretKey,
+};
+use darkfi_serial::{async_trait, deserialize, serialize, SerialDecodable, SerialEncodable};
+
+/// Event content prefix marking authored content
+pub const AUTHORED_MAGIC: [u8; 4] = [0x41, 0x55, 0x54, 0x48];
+
+/// Event content prefix marking a redaction
+pub const REDACTION_MAGIC: [u8; 4] = [0x52, 0x44, 0x43, 0x54];
+
+/// Event content prefix marking a tombstone of redacted content
+pub const TOMBSTONE_MAGIC: [u8; 4] = [0x54, 0x4f, 0x4d, 0x42];
+
+/// Event content signed by its author
+#[derive(Clone, Debug, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct AuthoredContent {
+    /// Public key of the author
+    pub author: PublicKey,
+    /// The actual content
+    pub content: Vec<u8>,
+    /// Author signature over the content
+    pub signature: Signature,
+}
+
+impl AuthoredContent {
+    /// Sign the given content with the author secret key
+    pub fn new(secret: &SecretKey, content: Vec<u8>) -> Self {
+        let signature = secret.sign(&[&AUTHORED_MAGIC[..], &content].concat());
+        Self { author: PublicKey::from_secret(*secret), content, signature }
+    }
+
+    /// Verify the author signature
+    pub fn verify(&self) -> bool {
+        self.author.verify(&[&AUTHORED_MAGIC[..], &self.content].concat(), &self.signature)
+    }
+
+    /// The tombstone left in the DAG once this content gets redacted
+    pub fn tombstone(&self) -> Tombstone {
+        let body = blake3::hash(&serialize(&(&self.content, &self.signature)));
+        Tombstone { author: self.author, body }
+    }
+
+    /// Event content carrying this authored content
+    pub fn to_content(&self) -> Vec<u8> {
+        let mut content = AUTHORED_MAGIC.to_vec();
+        content.extend(serialize(self));
+        content
+    }
+
+    /// Parse authored content out of event content, if there is one
+    pub fn from_content(content: &[u8]) -> Option<Self> {
+        deserialize(content.strip_prefix(&AUTHORED_MAGIC)?).ok()
+    }
+}
+
+/// Redacted authored content, as kept in the DAG
+#[derive(Clone, Debug, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct Tombstone {
+    /// Public key of the author
+    pub author: PublicKey,
+    /// Hash of the removed content and signature
+    pub body: blake3::Hash,
+}
+
+impl Tombstone {
+    /// Event content carrying this tombstone
+    pub fn to_content(&self) -> Vec<u8> {
+        let mut content = TOMBSTONE_MAGIC.to_vec();
+        content.extend(serialize(self));
+        content
+    }
+
+    /// Parse a tombstone out of event content, if there is one
+    pub fn from_content(content: &[u8]) -> Option<Self> {
+        deserialize(content.strip_prefix(&TOMBSTONE_MAGIC)?).ok()
+    }
+}
+
+/// Request by an author to redact one of their events
+#[derive(Clone, Debug, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct Redaction {
+    /// Public key of the author
+    pub author: PublicKey,
+    /// ID of the event to redact
+    pub target: blake3::Hash,
+    /// Author signature over the target ID
+    pub signature: Signature,
+}
+
+impl Redaction {
+    /// Sign a redaction of the given event ID with the author secret key
+    pub fn new(secret: &SecretKey, target: blake3::Hash) -> Self {
+        let signature = secret.sign(&[&REDACTION_MAGIC[..], target.as_bytes()].concat());
+        Self { author: PublicKey::from_secret(*secret), target, signature }
+    }
+
+    /// Verify the author signature
+    pub fn verify(&self) -> bool {
+        self.author
+            .verify(&[&REDACTION_MAGIC[..], self.target.as_bytes()].concat(), &self.signature)
+    }
+
+    /// Event content carrying this redaction
+    pub fn to_content(&self) -> Vec<u8> {
+        let mut content = REDACTION_MAGIC.to_vec();
+        content.extend(serialize(self));
+        content
+    }
+
+    /// Parse a redaction out of event content, if there is one
+    pub fn from_content(content: &[u8]) -> Option<Self> {
+        deserialize(content.strip_prefix(&REDACTION_MAGIC)?).ok()
+    }
+}
+
+/// Author of the given event content, if it is authored or a tombstone
+pub fn content_author(content: &[u8]) -> Option<PublicKey> {
+    if let Some(authored) = AuthoredContent::from_content(content) {
+        return Some(authored.author)
+    }
+    Tombstone::from_content(content).map(|t| t.author)
+}
+
+/// Key under which a redaction of `target` by `author` is recorded
+pub(super) fn redaction_key(target: &blake3::Hash, author: &PublicKey) -> Vec<u8> {
+    let mut key = target.as_bytes().to_vec();
+    key.extend(serialize(author));
+    key
+}
+
+/// Content an event ID is computed over, when it differs from the event
+/// content. Authored content is replaced by its tombstone, so redacting
+/// it does not change the event ID.
+pub(super) fn id_content(content: &[u8]) -> Option<Vec<u8>> {
+    AuthoredContent::from_content(content).map(|authored| authored.tombstone().to_content())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::rngs::OsRng;
+    use sled_overlay::sled;
+    use smol::Executor;
+
+    use super::*;
+    use crate::{
+        event_graph::{retention::RetentionPolicy, Event, EventGraph},
+        net::{P2p, Settings},
+        Error, Result,
+    };
+
+    #[test]
+    fn redact_event() -> Result<()> {
+        smol::block_on(async {
+            let ex = Arc::new(Executor::new());
+            let p2p = P2p::new(Settings::default(), ex.clone()).await?;
+            let sled_db = sled::Config::new().temporary(true).open().unwrap();
+            let event_graph = EventGraph::new(
+                p2p,
+                sled_db,
+                "/tmp".into(),
+                false,
+                "dag",
+                1,
+                RetentionPolicy::default(),
+                ex,
+            )
+            .await?;
+
+            let secret = SecretKey::random(&mut OsRng);
+            let authored = AuthoredContent::new(&secret, b"hello".to_vec());
+            assert!(authored.verify());
+
+            // Redacting the content keeps the event ID
+            let event = Event::new(authored.to_content(), &event_graph).await;
+            let mut tombstoned = event.clone();
+            tombstoned.content = authored.tombstone().to_content();
+            assert_eq!(event.id(), tombstoned.id());
+            event_graph.dag_insert(&[event.clone()]).await?;
+
+            // A tombstone is not accepted without a redaction
+            assert!(matches!(
+                event_graph.dag_insert(&[tombstoned.clone()]).await,
+                Err(Error::EventIsInvalid)
+            ));
+
+            // Someone else can't redact the event
+            let other = SecretKey::random(&mut OsRng);
+            let redaction = Redaction::new(&other, event.id());
+            let other_redaction = Event::new(redaction.to_content(), &event_graph).await;
+            event_graph.dag_insert(&[other_redaction.clone()]).await?;
+            assert_eq!(event_graph.dag_get(&event.id()).await?, Some(event.clone()));
+
+            // The author can
+            let redaction = Redaction::new(&secret, event.id());
+            let redaction = Event::new(redaction.to_content(), &event_graph).await;
+            event_graph.dag_insert(&[redaction.clone()]).await?;
+            assert_eq!(event_graph.dag_get(&event.id()).await?, Some(tombstoned.clone()));
+            assert!(event_graph.is_redacted(&event.id()).await?);
+
+            // Syncing nodes accept the tombstone along with its redaction
+            let ex = Arc::new(Executor::new());
+            let p2p = P2p::new(Settings::default(), ex.clone()).await?;
+            let sled_db = sled::Config::new().temporary(true).open().unwrap();
+            let event_graph = EventGraph::new(
+                p2p,
+                sled_db,
+                "/tmp".into(),
+                false,
+                "dag",
+                1,
+                RetentionPolicy::default(),
+                ex,
+            )
+            .await?;
+            event_graph.dag_insert(&[tombstoned.clone(), other_redaction, redaction]).await?;
+            assert_eq!(event_graph.dag_get(&event.id()).await?, Some(tombstoned));
+
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,162 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Retention policies bounding what an [`super::EventGraph`] keeps
+//! between DAG rotations.
+//!
+//! The DAG rotation drops everything at once. A [`RetentionPolicy`]
+//! additionally evicts single events once they get older than a maximum
+//! age, optionally overridden per topic, and evicts the oldest events
+//! when the DAG grows past a maximum size. Evicted parents are missing
+//! from the DAG afterwards, so a bounded DAG validates new events the
+//! same way a selectively synced one does.
+
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering::SeqCst},
+};
+
+use tinyjson::JsonValue;
+
+use crate::rpc::util::json_map;
+
+/// How often a bounded retention policy gets enforced, in milliseconds
+pub const RETENTION_INTERVAL: u64 = 60_000;
+
+/// Retention policy of an [`super::EventGraph`] instance.
+/// The default policy keeps every event until the DAG rotation.
+#[derive(Clone, Debug, Default)]
+pub struct RetentionPolicy {
+    /// Maximum age of an event in seconds
+    pub max_age: Option<u64>,
+    /// Maximum size of the stored events in bytes
+    pub max_size: Option<u64>,
+    /// Per-topic maximum age overrides, `None` keeping the topic's
+    /// events until the DAG rotation
+    pub topic_max_age: HashMap<blake3::Hash, Option<u64>>,
+}
+
+impl RetentionPolicy {
+    /// Whether the policy evicts anything before the DAG rotation
+    pub fn is_bounded(&self) -> bool {
+        self.max_age.is_some() ||
+            self.max_size.is_some() ||
+            self.topic_max_age.values().any(|max_age| max_age.is_some())
+    }
+
+    /// Maximum age in milliseconds of an event tagged with the given topic
+    pub fn max_age_millis(&self, topic: &blake3::Hash) -> Option<u64> {
+        let max_age = match self.topic_max_age.get(topic) {
+            Some(max_age) => *max_age,
+            None => self.max_age,
+        };
+        max_age.map(|secs| secs.saturating_mul(1000))
+    }
+}
+
+/// Counters of the events evicted by the retention policy
+#[derive(Debug, Default)]
+pub struct RetentionMetrics {
+    /// Events evicted because of their age
+    pub evicted_by_age: AtomicU64,
+    /// Events evicted because of the size limit
+    pub evicted_by_size: AtomicU64,
+    /// Total size of the evicted events in bytes
+    pub evicted_bytes: AtomicU64,
+    /// Size of the stored events after the last enforcement, in bytes
+    pub dag_size: AtomicU64,
+}
+
+impl RetentionMetrics {
+    /// Record a run of the retention policy
+    pub(super) fn record(&self, by_age: u64, by_size: u64, bytes: u64, dag_size: u64) {
+        self.evicted_by_age.fetch_add(by_age, SeqCst);
+        self.evicted_by_size.fetch_add(by_size, SeqCst);
+        self.evicted_bytes.fetch_add(bytes, SeqCst);
+        self.dag_size.store(dag_size, SeqCst);
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        json_map([
+            ("evicted_by_age", JsonValue::Number(self.evicted_by_age.load(SeqCst) as f64)),
+            ("evicted_by_size", JsonValue::Number(self.evicted_by_size.load(SeqCst) as f64)),
+            ("evicted_bytes", JsonValue::Number(self.evicted_bytes.load(SeqCst) as f64)),
+            ("dag_size", JsonValue::Number(self.dag_size.load(SeqCst) as f64)),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use sled_overlay::sled;
+    use smol::Executor;
+
+    use super::*;
+    use crate::{
+        event_graph::{topic_hash, Event, EventGraph},
+        net::{P2p, Settings},
+        Result,
+    };
+
+    #[test]
+    fn retention_evicts_expired_events() -> Result<()> {
+        smol::block_on(async {
+            let topic = topic_hash("#archive");
+            let policy = RetentionPolicy {
+                max_age: Some(1),
+                max_size: None,
+                topic_max_age: HashMap::from([(topic, None)]),
+            };
+            assert!(policy.is_bounded());
+            assert!(!RetentionPolicy::default().is_bounded());
+
+            let ex = Arc::new(Executor::new());
+            let p2p = P2p::new(Settings::default(), ex.clone()).await?;
+            let sled_db = sled::Config::new().temporary(true).open().unwrap();
+            let event_graph =
+                EventGraph::new(p2p, sled_db, "/tmp".into(), false, "dag", 1, policy, ex).await?;
+
+            // Two events from the start of the rotation, one of them
+            // in a topic kept until the rotation
+            let timestamp = event_graph.current_genesis.read().await.timestamp + 1;
+            let old = Event::with_timestamp(timestamp, vec![1], &event_graph).await;
+            event_graph.dag_insert(&[old.clone()]).await?;
+            let mut archived = Event::with_timestamp(timestamp, vec![2], &event_graph).await;
+            archived.topic = topic;
+            event_graph.dag_insert(&[archived.clone()]).await?;
+
+            // A fresh tip, which is always kept
+            let fresh = Event::new(vec![3], &event_graph).await;
+            event_graph.dag_insert(&[fresh.clone()]).await?;
+
+            event_graph.dag_enforce_retention().await?;
+            assert_eq!(event_graph.dag_get(&old.id()).await?, None);
+            assert_eq!(event_graph.dag_get(&archived.id()).await?, Some(archived));
+            assert_eq!(event_graph.dag_get(&fresh.id()).await?, Some(fresh));
+
+            let metrics = &event_graph.retention_metrics;
+            assert_eq!(metrics.evicted_by_age.load(SeqCst), 1);
+            assert_eq!(metrics.evicted_by_size.load(SeqCst), 0);
+            assert!(metrics.dag_size.load(SeqCst) > 0);
+
+            Ok(())
+        })
+    }
+}
@@ -29,6 +29,7 @@ use url::Url;
 use crate::{
     event_graph::{
         proto::{EventPut, ProtocolEventGraph},
+        retention::RetentionPolicy,
         Event, EventGraph,
     },
     net::{session::SESSION_DEFAULT, P2p, Settings},
@@ -92,10 +93,18 @@ async fn spawn_node(
 
     let p2p = P2p::new(settings, ex.clone()).await.unwrap();
     let sled_db = sled::Config::new().temporary(true).open().unwrap();
-    let event_graph =
-        EventGraph::new(p2p.clone(), sled_db, "/tmp".into(), false, "dag", 1, ex.clone())
-            .await
-            .unwrap();
+    let event_graph = EventGraph::new(
+        p2p.clone(),
+        sled_db,
+        "/tmp".into(),
+        false,
+        "dag",
+        1,
+        RetentionPolicy::default(),
+        ex.clone(),
+    )
+    .await
+    .unwrap();
     *event_graph.synced.write().await = true;
     let event_graph_ = event_graph.clone();
 
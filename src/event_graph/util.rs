@@ -130,9 +130,20 @@ pub fn generate_genesis(days_rotation: u64) -> Event {
         content: GENESIS_CONTENTS.to_vec(),
         parents: [NULL_ID; N_EVENT_PARENTS],
         layer: 0,
+        topic: NULL_ID,
     }
 }
 
+/// Key of a tagged event in the topic index: its topic, then its
+/// layer so the events of a topic are ordered, and its ID.
+pub(super) fn topic_index_key(event: &Event) -> Vec<u8> {
+    let mut key = Vec::with_capacity(blake3::OUT_LEN * 2 + 8);
+    key.extend_from_slice(event.topic.as_bytes());
+    key.extend_from_slice(&event.layer.to_be_bytes());
+    key.extend_from_slice(event.id().as_bytes());
+    key
+}
+
 pub(super) fn replayer_log(datastore: &Path, cmd: String, value: Vec<u8>) -> Result<()> {
     fs::create_dir_all(datastore)?;
     let datastore = datastore.join("replayer.log");
@@ -63,17 +63,21 @@ impl Acceptor {
 
     /// Start accepting inbound socket connections
     pub async fn start(self: Arc<Self>, endpoint: Url, ex: Arc<Executor<'_>>) -> Result<()> {
-        let datastore =
-            self.session.upgrade().unwrap().p2p().settings().read().await.p2p_datastore.clone();
+        let settings = self.session.upgrade().unwrap().p2p().settings();
+        let settings = settings.read().await;
+        let datastore = settings.p2p_datastore.clone();
+        let i2p_sam_address = settings.i2p_sam_address.clone();
+        drop(settings);
 
         // Initialize listener
-        let listener = Listener::new(endpoint.clone(), datastore).await?;
+        let listener = Listener::new(endpoint.clone(), datastore, Some(i2p_sam_address)).await?;
 
         // Open socket
         let ptlistener = listener.listen().await?;
 
-        #[cfg(feature = "p2p-tor")]
-        if endpoint.scheme() == "tor" {
+        // Hidden service addresses are only known once listening
+        #[cfg(any(feature = "p2p-tor", feature = "p2p-i2p"))]
+        if ["tor", "tor+tls", "i2p"].contains(&endpoint.scheme()) {
             let onion_addr = listener.endpoint().await;
             info!("[P2P] Adding {onion_addr} to external_addrs");
             self.session
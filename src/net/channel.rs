@@ -20,14 +20,15 @@ use std::{
     collections::HashMap,
     fmt,
     sync::{
-        atomic::{AtomicBool, Ordering::SeqCst},
+        atomic::{AtomicBool, AtomicU64, Ordering::SeqCst},
         Arc,
     },
-    time::UNIX_EPOCH,
+    time::{Duration, UNIX_EPOCH},
 };
 
 use darkfi_serial::{
-    async_trait, AsyncDecodable, AsyncEncodable, SerialDecodable, SerialEncodable, VarInt,
+    async_trait, serialize, AsyncDecodable, AsyncEncodable, SerialDecodable, SerialEncodable,
+    VarInt,
 };
 use log::{debug, error, info, trace, warn};
 use rand::{rngs::OsRng, Rng};
@@ -39,21 +40,27 @@ use smol::{
 use url::Url;
 
 use super::{
+    compression::{self, CompressedHeader, CompressionStats},
     dnet::{self, dnetev, DnetEvent},
     hosts::{HostColor, HostsPtr},
     message,
-    message::{SerializedMessage, VersionMessage, MAX_COMMAND_LENGTH},
+    message::{MessagePriority, SerializedMessage, VersionMessage, MAX_COMMAND_LENGTH},
     message_publisher::{MessageSubscription, MessageSubsystem},
     metering::{MeteringConfiguration, MeteringQueue},
     p2p::P2pPtr,
+    scoring::{Misbehavior, ScoreAction},
     session::{
         Session, SessionBitFlag, SessionWeakPtr, SESSION_ALL, SESSION_INBOUND, SESSION_REFINE,
     },
+    throttle::{CountingStream, Throttle},
     transport::PtStream,
 };
 use crate::{
     net::BanPolicy,
-    system::{msleep, Publisher, PublisherPtr, StoppableTask, StoppableTaskPtr, Subscription},
+    system::{
+        msleep, PriorityLock, Publisher, PublisherPtr, StoppableTask, StoppableTaskPtr,
+        Subscription, METRICS,
+    },
     util::time::NanoTimestamp,
     Error, Result,
 };
@@ -76,12 +83,48 @@ impl ChannelInfo {
     }
 }
 
+/// Message counters of a channel
+#[derive(Default)]
+pub struct MessageStats {
+    sent: AtomicU64,
+    recv: AtomicU64,
+    last_recv: AtomicU64,
+}
+
+impl MessageStats {
+    fn record_sent(&self) {
+        self.sent.fetch_add(1, SeqCst);
+    }
+
+    fn record_recv(&self) {
+        self.recv.fetch_add(1, SeqCst);
+        self.last_recv.store(UNIX_EPOCH.elapsed().unwrap().as_secs(), SeqCst);
+    }
+
+    /// Number of messages sent
+    pub fn sent(&self) -> u64 {
+        self.sent.load(SeqCst)
+    }
+
+    /// Number of messages received
+    pub fn recv(&self) -> u64 {
+        self.recv.load(SeqCst)
+    }
+
+    /// UNIX timestamp of the last message received, 0 if none yet
+    pub fn last_recv(&self) -> u64 {
+        self.last_recv.load(SeqCst)
+    }
+}
+
 /// Async channel for communication between nodes.
 pub struct Channel {
     /// The reading half of the transport stream
     reader: AsyncMutex<ReadHalf<Box<dyn PtStream>>>,
     /// The writing half of the transport stream
     writer: AsyncMutex<WriteHalf<Box<dyn PtStream>>>,
+    /// Orders concurrent senders by their message priority
+    send_queue: PriorityLock<MessagePriority>,
     /// The message subsystem instance for this channel
     message_subsystem: MessageSubsystem,
     /// Publisher listening for stop signal for closing this channel
@@ -96,11 +139,26 @@ pub struct Channel {
     /// Some if the version exchange has already occurred, None
     /// otherwise.
     pub version: OnceCell<Arc<VersionMessage>>,
+    /// Identity public key the peer proved ownership of, when
+    /// running in authenticated mode.
+    pub identity: OnceCell<[u8; 32]>,
+    /// Keying material of the transport TLS session, which the
+    /// authentication handshake is bound to
+    pub(in crate::net) channel_binding: Option<[u8; 32]>,
     /// Channel debug info
     pub info: ChannelInfo,
     /// Map holding a `MeteringQueue` for each [`Message`] to perform
     /// rate limiting of propagation towards the stream.
     metering_map: AsyncMutex<HashMap<String, MeteringQueue>>,
+    /// Bandwidth budget and traffic counters of this channel
+    pub throttle: Throttle,
+    /// Bytes read from the stream which were not yet charged
+    /// to the throttles
+    recv_pending: Arc<AtomicU64>,
+    /// Counters of the compressed traffic of this channel
+    pub compression: CompressionStats,
+    /// Counters of the messages going over this channel
+    pub messages: MessageStats,
 }
 
 impl Channel {
@@ -113,6 +171,15 @@ impl Channel {
         connect_addr: Url,
         session: SessionWeakPtr,
     ) -> Arc<Self> {
+        let throttle = {
+            let settings = session.upgrade().unwrap().p2p().settings();
+            let settings = settings.read().await;
+            Throttle::new(settings.channel_send_limit, settings.channel_recv_limit)
+        };
+
+        let channel_binding = stream.channel_binding();
+        let recv_pending = Arc::new(AtomicU64::new(0));
+        let stream: Box<dyn PtStream> = Box::new(CountingStream::new(stream, recv_pending.clone()));
         let (reader, writer) = io::split(stream);
         let reader = AsyncMutex::new(reader);
         let writer = AsyncMutex::new(writer);
@@ -127,14 +194,21 @@ impl Channel {
         Arc::new(Self {
             reader,
             writer,
+            send_queue: PriorityLock::new(),
             message_subsystem,
             stop_publisher: Publisher::new(),
             receive_task: StoppableTask::new(),
             stopped: AtomicBool::new(false),
             session,
             version: OnceCell::new(),
+            identity: OnceCell::new(),
+            channel_binding,
             info,
             metering_map,
+            throttle,
+            recv_pending,
+            compression: CompressionStats::default(),
+            messages: MessageStats::default(),
         })
     }
 
@@ -142,6 +216,8 @@ impl Channel {
     async fn setup_dispatchers(subsystem: &MessageSubsystem) {
         subsystem.add_dispatch::<message::VersionMessage>().await;
         subsystem.add_dispatch::<message::VerackMessage>().await;
+        subsystem.add_dispatch::<message::AuthChallengeMessage>().await;
+        subsystem.add_dispatch::<message::AuthResponseMessage>().await;
         subsystem.add_dispatch::<message::PingMessage>().await;
         subsystem.add_dispatch::<message::PongMessage>().await;
         subsystem.add_dispatch::<message::GetAddrsMessage>().await;
@@ -153,6 +229,8 @@ impl Channel {
     pub fn start(self: Arc<Self>, executor: Arc<Executor<'_>>) {
         debug!(target: "net::channel::start()", "START {self:?}");
 
+        METRICS.gauge("darkfi_p2p_channels", "Open P2P channels", &[]).inc();
+
         let self_ = self.clone();
         self.receive_task.clone().start(
             self.clone().main_receive_loop(),
@@ -277,6 +355,12 @@ impl Channel {
     async fn send_message(&self, message: &SerializedMessage) -> Result<()> {
         assert!(!message.command.is_empty());
 
+        // Large messages go out compressed if the peer supports it
+        let compressed = self.compress(message).await;
+        let message = compressed.as_ref().unwrap_or(message);
+
+        // Wait for our turn, behind any pending higher priority messages
+        let _send_guard = self.send_queue.lock(message.priority).await;
         let stream = &mut *self.writer.lock().await;
         let mut written: usize = 0;
 
@@ -306,10 +390,114 @@ impl Channel {
             message.payload.len());
 
         stream.flush().await?;
+        self.messages.record_sent();
+
+        // Hold the writer lock while waiting, so the budget is shared
+        // by everything sent over this channel.
+        let p2p = self.p2p();
+        let wait = self.throttle.consume_send(written).max(p2p.throttle().consume_send(written));
+        self.throttle_wait(wait, "send").await;
 
         Ok(())
     }
 
+    /// Wrap `message` into a compressed frame, if the peer supports it
+    /// and the payload is large enough to be worth compressing.
+    async fn compress(&self, message: &SerializedMessage) -> Option<SerializedMessage> {
+        let settings = self.p2p().settings();
+        let settings = settings.read().await;
+        let enabled =
+            settings.compression && message.payload.len() >= settings.compression_threshold;
+        drop(settings);
+
+        if !enabled || !compression::is_supported(&self.version.get()?.features) {
+            return None
+        }
+
+        let frame = compression::wrap(message)?;
+        self.compression.record(message.payload.len(), frame.payload.len());
+        self.p2p().compression().record(message.payload.len(), frame.payload.len());
+        Some(frame)
+    }
+
+    /// Read the payload of a compressed frame. Returns the command of the
+    /// wrapped message, along with a reader over its decompressed payload
+    /// framed the same way as a plain message.
+    async fn read_compressed<R: AsyncRead + Unpin + Send + Sized>(
+        &self,
+        stream: &mut R,
+    ) -> Result<(String, io::Cursor<Vec<u8>>)> {
+        // Peers only compress if we advertised support for it
+        if !self.p2p().settings().read().await.compression {
+            return Err(Error::MessageInvalid)
+        }
+
+        let length = VarInt::decode_async(stream).await?.0;
+
+        // Check the claimed lengths against the wrapped command limit
+        // before reading or inflating anything.
+        let header = CompressedHeader::read(stream).await?;
+        let Some(max_bytes) = self.message_subsystem.max_bytes(&header.command).await else {
+            return Err(Error::MissingDispatcher)
+        };
+        if max_bytes > 0 && header.length > max_bytes {
+            error!(
+                target: "net::channel::read_compressed()",
+                "Message length ({}) exceeds configured limit ({max_bytes}). Dropping...",
+                header.length,
+            );
+            return Err(Error::MessageInvalid)
+        }
+
+        let Some(data_len) = length.checked_sub(header.offset as u64) else {
+            return Err(Error::MessageInvalid)
+        };
+        if data_len > header.max_compressed_len() {
+            return Err(Error::MessageInvalid)
+        }
+
+        // The buffer grows as the data arrives, so a peer claiming a
+        // large frame has to actually send it to make us allocate.
+        let mut data = vec![];
+        stream.take(data_len).read_to_end(&mut data).await?;
+        if data.len() as u64 != data_len {
+            return Err(Error::Io(io::ErrorKind::UnexpectedEof))
+        }
+
+        let raw = header.decompress(&data)?;
+        self.compression.record(raw.len(), length as usize);
+        self.p2p().compression().record(raw.len(), length as usize);
+
+        let mut frame = serialize(&VarInt(raw.len() as u64));
+        frame.extend_from_slice(&raw);
+        Ok((header.command, io::Cursor::new(frame)))
+    }
+
+    /// Charge the bytes read since the last message to the throttles,
+    /// and hold off reading the next message until they fit the budget.
+    /// The peer gets slowed down by the transport's flow control.
+    async fn throttle_recv(&self) {
+        let read = self.recv_pending.swap(0, SeqCst) as usize;
+        let p2p = self.p2p();
+        let wait = self.throttle.consume_recv(read).max(p2p.throttle().consume_recv(read));
+        self.throttle_wait(wait, "recv").await;
+    }
+
+    async fn throttle_wait(&self, wait: Duration, direction: &str) {
+        if wait.is_zero() {
+            return
+        }
+
+        debug!(
+            target: "net::channel::throttle_wait()",
+            "[P2P] Bandwidth limit reached, delaying {direction} on {self:?} for {} ms",
+            wait.as_millis(),
+        );
+        self.throttle.record_throttled(wait);
+        self.p2p().throttle().record_throttled(wait);
+        msleep(wait.as_millis() as u64).await;
+    }
+
     /// Returns a decoded Message command. We start by extracting the length
     /// from the stream, then allocate the precise buffer for this length
     /// using stream.take(). This manual deserialization provides a basic
@@ -375,6 +563,7 @@ impl Channel {
         debug!(target: "net::channel::handle_stop()", "[START] {self:?}");
 
         self.stopped.store(true, SeqCst);
+        METRICS.gauge("darkfi_p2p_channels", "Open P2P channels", &[]).dec();
 
         match result {
             Ok(()) => panic!("Channel task should never complete without error status"),
@@ -403,20 +592,20 @@ impl Channel {
                     if Self::is_eof_error(&err) {
                         info!(
                             target: "net::channel::main_receive_loop()",
+                            peer = self.address().as_str();
                             "[P2P] Channel {} disconnected",
                             self.address()
                         );
                     } else if let Error::MessageInvalid = err {
-                        // The command name length has exceeded the limit, this is possibly a malicious attack so ban it
-                        if let BanPolicy::Strict = self.p2p().settings().read().await.ban_policy {
-                            self.ban().await;
-                        }
+                        // The command name length has exceeded the limit, this is possibly a malicious attack
+                        self.penalize(Misbehavior::InvalidMessage).await;
                     } else if self.session.upgrade().unwrap().type_id() &
                         (SESSION_ALL & !SESSION_REFINE) !=
                         0
                     {
                         error!(
                             target: "net::channel::main_receive_loop()",
+                            peer = self.address().as_str();
                             "[P2P] Read error on channel {}: {err}",
                             self.address()
                         );
@@ -430,20 +619,42 @@ impl Channel {
                 }
             };
 
+            self.messages.record_recv();
             dnetev!(self, RecvMessage, {
                 chan: self.info.clone(),
                 cmd: command.clone(),
                 time: NanoTimestamp::current_time(),
             });
 
+            // Compressed frames get unwrapped and dispatched like plain messages
+            let result = if command == compression::COMPRESSED_COMMAND {
+                match self.read_compressed(reader).await {
+                    Ok((command, mut frame)) => {
+                        self.message_subsystem.notify(&command, &mut frame).await
+                    }
+                    Err(Error::Io(e)) => {
+                        debug!(
+                            target: "net::channel::main_receive_loop()",
+                            "Stopping channel {self:?} on compressed frame read error: {e:?}"
+                        );
+                        return Err(Error::ChannelStopped)
+                    }
+                    Err(e) => Err(e),
+                }
+            } else {
+                self.message_subsystem.notify(&command, reader).await
+            };
+
             // Send result to our publishers
-            match self.message_subsystem.notify(&command, reader).await {
-                Ok(()) => {}
-                Err(Error::MissingDispatcher) |
-                Err(Error::MessageInvalid) |
-                Err(Error::MeteringLimitExceeded) => {
+            match result {
+                Ok(()) => self.throttle_recv().await,
+                Err(
+                    err @ (Error::MissingDispatcher |
+                    Error::MessageInvalid |
+                    Error::MeteringLimitExceeded),
+                ) => {
                     // If we're getting messages without dispatchers or its invalid,
-                    // it's spam. We therefore ban this channel if:
+                    // it's spam. We therefore penalize this channel if:
                     //
                     // 1) This channel is NOT part of a refine session.
                     //
@@ -451,23 +662,23 @@ impl Channel {
                     // dispatchers during the refinery process. If that happens
                     // we simply ignore it. Otherwise, it's spam.
                     //
-                    // 2) BanPolicy is set to Strict.
-                    //
-                    // We only ban if the BanPolicy is set to Strict, which is
-                    // the default setting for most nodes. The exception to
-                    // this is a seed node like Lilith which has BanPolicy::Relaxed
-                    // since it regularly forms connections with nodes sending
-                    // messages it does not have dispatchers for.
+                    // Should the score reach the ban threshold, we only ban if
+                    // the BanPolicy is set to Strict, which is the default
+                    // setting for most nodes. The exception to this is a seed
+                    // node like Lilith which has BanPolicy::Relaxed since it
+                    // regularly forms connections with nodes sending messages
+                    // it does not have dispatchers for.
                     if self.session.upgrade().unwrap().type_id() != SESSION_REFINE {
                         warn!(
                         target: "net::channel::main_receive_loop()",
                         "MissingDispatcher|MessageInvalid|MeteringLimitExceeded for command={command}, channel={self:?}"
                         );
 
-                        if let BanPolicy::Strict = self.p2p().settings().read().await.ban_policy {
-                            self.ban().await;
-                        }
-
+                        let misbehavior = match err {
+                            Error::MeteringLimitExceeded => Misbehavior::ExcessiveBandwidth,
+                            _ => Misbehavior::InvalidMessage,
+                        };
+                        self.penalize(misbehavior).await;
                         return Err(Error::ChannelStopped)
                     }
                 }
@@ -525,6 +736,56 @@ impl Channel {
         debug!(target: "net::channel::ban()", "STOP {self:?}");
     }
 
+    /// The peer address misbehavior scores are tracked under. Inbound
+    /// peers connect from random ports, so only their host is used.
+    pub fn score_key(&self) -> Url {
+        let mut addr = self.address().clone();
+        if self.session_type_id() & SESSION_INBOUND != 0 && addr.host().is_some() {
+            let _ = addr.set_port(None);
+        }
+        addr
+    }
+
+    /// Penalize the peer for misbehaving. Once its score reaches the
+    /// configured thresholds, the channel gets stopped or the peer banned.
+    pub async fn penalize(&self, misbehavior: Misbehavior) {
+        let settings = self.p2p().settings();
+        let settings = settings.read().await;
+        let (score, action) =
+            self.p2p().scores().penalize(&self.score_key(), misbehavior, &settings);
+        let ban_policy = settings.ban_policy.clone();
+        drop(settings);
+
+        debug!(
+            target: "net::channel::penalize()",
+            "Peer {} penalized for {}, score={score}", self.address(), misbehavior.name(),
+        );
+
+        dnetev!(self, PeerPenalized, {
+            chan: self.info.clone(),
+            misbehavior: misbehavior.name(),
+            score,
+        });
+
+        match action {
+            ScoreAction::Keep => {}
+            ScoreAction::Demote => {
+                info!(
+                    target: "net::channel::penalize()",
+                    "[P2P] Disconnecting misbehaving peer {} (score={score})", self.address(),
+                );
+                self.stop().await;
+            }
+            ScoreAction::Ban => {
+                if let BanPolicy::Strict = ban_policy {
+                    self.ban().await;
+                } else {
+                    self.stop().await;
+                }
+            }
+        }
+    }
+
     /// Returns the relevant socket address for this connection.  If this is
     /// an outbound connection, the transport-processed resolve_addr will
     /// be returned.  Otherwise for inbound connections it will default
@@ -558,6 +819,17 @@ impl Channel {
         self.version.get().unwrap().clone()
     }
 
+    /// Set the identity public key of the peer. Called once the peer
+    /// passed the challenge in `ProtocolAuth`.
+    pub(crate) async fn set_identity(&self, public_key: [u8; 32]) {
+        self.identity.set(public_key).await.unwrap();
+    }
+
+    /// Returns the authenticated identity of the peer, if any
+    pub fn get_identity(&self) -> Option<[u8; 32]> {
+        self.identity.get().copied()
+    }
+
     /// Returns the inner [`MessageSubsystem`] reference
     pub fn message_subsystem(&self) -> &MessageSubsystem {
         &self.message_subsystem
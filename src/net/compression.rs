@@ -0,0 +1,230 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Per-channel message compression.
+//!
+//! Nodes with `compression` enabled advertise [`ZSTD_FEATURE`] in their
+//! version message. Once we know the version of a peer which advertises
+//! it as well, messages with a payload of at least `compression_threshold`
+//! bytes are sent wrapped in a [`COMPRESSED_COMMAND`] frame, holding the
+//! original command and the zstd compressed payload. We only compress
+//! towards peers which told us they are able to decompress, so this needs
+//! no extra round trip in the handshake.
+
+use std::{
+    io::Cursor,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use darkfi_serial::{serialize, AsyncDecodable, Decodable, VarInt};
+use smol::io::{AsyncRead, AsyncReadExt};
+
+use super::message::{SerializedMessage, MAX_COMMAND_LENGTH};
+use crate::{Error, Result};
+
+/// Name of the version message feature announcing zstd support
+pub const ZSTD_FEATURE: &str = "zstd";
+/// Version of the compressed frame format
+pub const ZSTD_FEATURE_VERSION: u32 = 1;
+/// Command of the frames wrapping a compressed message
+pub const COMPRESSED_COMMAND: &str = "zstd";
+/// Upper bound of a decompressed payload, so compression bombs can't
+/// exhaust our memory through messages without a size limit.
+pub const MAX_DECOMPRESSED_BYTES: u64 = 64 * 1024 * 1024;
+/// zstd compression level, favouring speed over ratio
+const ZSTD_LEVEL: i32 = 3;
+
+/// Check if the features of a version message include zstd support
+pub fn is_supported(features: &[(String, u32)]) -> bool {
+    features.iter().any(|(name, version)| name == ZSTD_FEATURE && *version == ZSTD_FEATURE_VERSION)
+}
+
+/// Wrap `message` into a compressed frame. Returns `None` if compression
+/// would not make the message any smaller.
+pub fn wrap(message: &SerializedMessage) -> Option<SerializedMessage> {
+    let compressed = zstd::bulk::compress(&message.payload, ZSTD_LEVEL).ok()?;
+
+    let mut payload = serialize(&message.command);
+    payload.extend_from_slice(&serialize(&VarInt(message.payload.len() as u64)));
+    payload.extend_from_slice(&compressed);
+
+    if payload.len() >= message.payload.len() {
+        return None
+    }
+
+    Some(SerializedMessage {
+        command: COMPRESSED_COMMAND.to_string(),
+        payload,
+        priority: message.priority,
+    })
+}
+
+/// Header of a compressed frame
+pub struct CompressedHeader {
+    /// Command of the wrapped message
+    pub command: String,
+    /// Length of the decompressed payload
+    pub length: u64,
+    /// Size of the header in the frame, which the compressed data follows
+    pub offset: usize,
+}
+
+impl CompressedHeader {
+    /// Decode the header of a compressed frame payload
+    pub fn decode(payload: &[u8]) -> Result<Self> {
+        let mut cursor = Cursor::new(payload);
+        let Ok(command) = String::decode(&mut cursor) else { return Err(Error::MessageInvalid) };
+        let Ok(length) = VarInt::decode(&mut cursor) else { return Err(Error::MessageInvalid) };
+
+        if command.len() > MAX_COMMAND_LENGTH as usize {
+            return Err(Error::MessageInvalid)
+        }
+
+        Self::new(command, length.0, cursor.position() as usize)
+    }
+
+    /// Read the header of a compressed frame off `stream`, leaving it
+    /// at the start of the compressed data. The command gets bounded
+    /// before reading it, so a peer can't make us allocate for it.
+    pub async fn read<R: AsyncRead + Unpin + Send + Sized>(stream: &mut R) -> Result<Self> {
+        let cmd_len = VarInt::decode_async(stream).await?.0;
+        if cmd_len > MAX_COMMAND_LENGTH as u64 {
+            return Err(Error::MessageInvalid)
+        }
+
+        let mut bytes = vec![0; cmd_len as usize];
+        stream.read_exact(&mut bytes).await?;
+        let Ok(command) = String::from_utf8(bytes) else { return Err(Error::MessageInvalid) };
+
+        let length = VarInt::decode_async(stream).await?.0;
+        let offset = VarInt(cmd_len).length() + cmd_len as usize + VarInt(length).length();
+
+        Self::new(command, length, offset)
+    }
+
+    fn new(command: String, length: u64, offset: usize) -> Result<Self> {
+        if command == COMPRESSED_COMMAND || length > MAX_DECOMPRESSED_BYTES {
+            return Err(Error::MessageInvalid)
+        }
+
+        Ok(Self { command, length, offset })
+    }
+
+    /// Upper bound of the compressed data size, as zstd never grows
+    /// its input by more than this.
+    pub fn max_compressed_len(&self) -> u64 {
+        zstd::zstd_safe::compress_bound(self.length as usize) as u64
+    }
+
+    /// Decompress the compressed data following the header
+    pub fn decompress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let Ok(raw) = zstd::bulk::decompress(data, self.length as usize) else {
+            return Err(Error::MessageInvalid)
+        };
+
+        if raw.len() as u64 != self.length {
+            return Err(Error::MessageInvalid)
+        }
+
+        Ok(raw)
+    }
+}
+
+/// Counters of compressed traffic, in both directions.
+/// Used both per channel and globally for the whole P2P instance.
+#[derive(Default)]
+pub struct CompressionStats {
+    messages: AtomicU64,
+    raw_bytes: AtomicU64,
+    wire_bytes: AtomicU64,
+}
+
+impl CompressionStats {
+    /// Account for a message of `raw` bytes which took `wire` bytes
+    pub fn record(&self, raw: usize, wire: usize) {
+        self.messages.fetch_add(1, Ordering::Relaxed);
+        self.raw_bytes.fetch_add(raw as u64, Ordering::Relaxed);
+        self.wire_bytes.fetch_add(wire as u64, Ordering::Relaxed);
+    }
+
+    /// Number of messages sent or received compressed
+    pub fn messages(&self) -> u64 {
+        self.messages.load(Ordering::Relaxed)
+    }
+
+    /// Number of bytes compression kept off the wire
+    pub fn bytes_saved(&self) -> u64 {
+        let raw = self.raw_bytes.load(Ordering::Relaxed);
+        raw.saturating_sub(self.wire_bytes.load(Ordering::Relaxed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::message::MessagePriority;
+
+    #[test]
+    fn compressed_frame_roundtrip() {
+        let message = SerializedMessage {
+            command: "blocks".to_string(),
+            payload: b"darkfi".repeat(1000),
+            priority: MessagePriority::High,
+        };
+
+        let frame = wrap(&message).unwrap();
+        assert_eq!(frame.command, COMPRESSED_COMMAND);
+        assert_eq!(frame.priority, MessagePriority::High);
+        assert!(frame.payload.len() < message.payload.len());
+
+        let header = CompressedHeader::decode(&frame.payload).unwrap();
+        assert_eq!(header.command, "blocks");
+        assert_eq!(header.length, message.payload.len() as u64);
+        assert_eq!(header.decompress(&frame.payload[header.offset..]).unwrap(), message.payload);
+        assert!(frame.payload.len() - header.offset <= header.max_compressed_len() as usize);
+
+        // Reading the header off a stream gives the same result
+        let mut stream = smol::io::Cursor::new(frame.payload.clone());
+        let read = smol::block_on(CompressedHeader::read(&mut stream)).unwrap();
+        assert_eq!(read.command, header.command);
+        assert_eq!(read.length, header.length);
+        assert_eq!(read.offset, header.offset);
+        assert_eq!(stream.position() as usize, header.offset);
+
+        // Oversized commands get rejected before reading them
+        let mut oversized = serialize(&VarInt(MAX_COMMAND_LENGTH as u64 + 1));
+        oversized.extend_from_slice(&frame.payload);
+        let mut stream = smol::io::Cursor::new(oversized);
+        assert!(smol::block_on(CompressedHeader::read(&mut stream)).is_err());
+
+        // Lying about the decompressed length gets caught
+        let mut forged = serialize(&"blocks".to_string());
+        forged.extend_from_slice(&serialize(&VarInt(10)));
+        forged.extend_from_slice(&frame.payload[header.offset..]);
+        let header = CompressedHeader::decode(&forged).unwrap();
+        assert!(header.decompress(&forged[header.offset..]).is_err());
+
+        // Nothing to gain on tiny messages
+        let message = SerializedMessage {
+            command: "ping".to_string(),
+            payload: vec![0x42, 0x13],
+            priority: MessagePriority::Low,
+        };
+        assert!(wrap(&message).is_none());
+    }
+}
@@ -83,6 +83,13 @@ pub struct OutboundPeerDiscovery {
     pub state: &'static str,
 }
 
+#[derive(Clone, Debug)]
+pub struct PeerPenalized {
+    pub chan: ChannelInfo,
+    pub misbehavior: &'static str,
+    pub score: u32,
+}
+
 #[derive(Clone, Debug)]
 pub enum DnetEvent {
     SendMessage(MessageInfo),
@@ -94,4 +101,5 @@ pub enum DnetEvent {
     OutboundSlotConnected(OutboundSlotConnected),
     OutboundSlotDisconnected(OutboundSlotDisconnected),
     OutboundPeerDiscovery(OutboundPeerDiscovery),
+    PeerPenalized(PeerPenalized),
 }
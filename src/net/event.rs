@@ -0,0 +1,58 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::time::UNIX_EPOCH;
+
+use url::Url;
+
+use super::{
+    channel::ChannelPtr,
+    session::{session_name, SessionBitFlag},
+};
+
+/// Channel details attached to [`P2pEvent`]s
+#[derive(Clone, Debug)]
+pub struct ChannelEventInfo {
+    pub id: u32,
+    pub addr: Url,
+    /// Name of the session the channel belongs to
+    pub session: &'static str,
+    /// UNIX timestamp of the event
+    pub time: u64,
+}
+
+impl ChannelEventInfo {
+    pub(super) fn new(channel: &ChannelPtr, type_id: SessionBitFlag) -> Self {
+        Self {
+            id: channel.info.id,
+            addr: channel.address().clone(),
+            session: session_name(type_id),
+            time: UNIX_EPOCH.elapsed().unwrap().as_secs(),
+        }
+    }
+}
+
+/// Connectivity events of the P2P network. Unlike [`super::dnet::DnetEvent`]s
+/// these are cheap, so they are always published.
+#[derive(Clone, Debug)]
+pub enum P2pEvent {
+    /// A channel finished its handshake and got registered
+    ChannelConnected(ChannelEventInfo),
+    /// A registered channel was stopped
+    ChannelDisconnected { info: ChannelEventInfo, reason: String },
+}
@@ -33,6 +33,7 @@ use std::{
 use url::{Host, Url};
 
 use super::{
+    scoring::PeerScores,
     session::{SESSION_REFINE, SESSION_SEED},
     settings::Settings,
     ChannelPtr,
@@ -41,7 +42,6 @@ use crate::{
     system::{Publisher, PublisherPtr, Subscription},
     util::{
         file::{load_file, save_file},
-        most_frequent_or_any,
         path::expand_path,
         ringbuffer::RingBuffer,
     },
@@ -93,6 +93,19 @@ const WHITELIST_MAX_LEN: usize = 5000;
 const GREYLIST_MAX_LEN: usize = 2000;
 const DARKLIST_MAX_LEN: usize = 1000;
 
+/// Version of the hostlist file format. Version 1 files had no header
+/// and no peer scores.
+const HOSTLIST_VERSION: u32 = 2;
+
+/// Number of external address observations we keep around
+const AUTO_ADDR_OBSERVATIONS: usize = 20;
+/// Minimum number of distinct peers that must report the same external
+/// address before we advertise it
+const AUTO_ADDR_MIN_REPORTERS: usize = 3;
+/// Share of the reporting peers that must agree on the external address
+/// before we advertise it
+const AUTO_ADDR_MIN_CONFIDENCE: f32 = 0.5;
+
 /// Atomic pointer to hosts object
 pub type HostsPtr = Arc<Hosts>;
 
@@ -337,11 +350,23 @@ impl TryFrom<usize> for HostColor {
     }
 }
 
+/// Atomic pointer to an address selector
+pub type AddrSelectorPtr = Arc<dyn AddrSelector>;
+
+/// Picks the hosts we share with peers asking us for addresses.
+/// Without one, hosts are picked uniformly at random.
+pub trait AddrSelector: Send + Sync {
+    /// Select up to `n` entries out of `hosts`
+    fn select(&self, hosts: Vec<(Url, u64)>, n: usize) -> Vec<(Url, u64)>;
+}
+
 /// A Container for managing Grey, White, Gold and Black hostlists. Exposes
 /// a common interface for writing to and querying hostlists.
 // TODO: Benchmark hostlist operations when the hostlist is at max size.
 pub struct HostContainer {
     pub(in crate::net) hostlists: [RwLock<Vec<(Url, u64)>>; 5],
+    /// Optional custom selection of the hosts we share
+    selector: RwLock<Option<AddrSelectorPtr>>,
 }
 
 impl HostContainer {
@@ -354,7 +379,25 @@ impl HostContainer {
             RwLock::new(Vec::new()),
         ];
 
-        Self { hostlists }
+        Self { hostlists, selector: RwLock::new(None) }
+    }
+
+    /// Set a custom selection for the hosts we share with other peers
+    pub fn set_selector(&self, selector: AddrSelectorPtr) {
+        *self.selector.write().unwrap() = Some(selector);
+    }
+
+    /// Pick up to n entries out of `hosts`, using the custom selector if
+    /// one is set, otherwise at random.
+    fn select_n(&self, hosts: Vec<(Url, u64)>, n: usize) -> Vec<(Url, u64)> {
+        if let Some(selector) = self.selector.read().unwrap().as_ref() {
+            let mut selected = selector.select(hosts, n);
+            selected.truncate(n);
+            return selected
+        }
+
+        let urls = hosts.iter().choose_multiple(&mut OsRng, n.min(hosts.len()));
+        urls.iter().map(|&url| url.clone()).collect()
     }
 
     /// Append host to a hostlist. Called when initalizing the hostlist in load_hosts().
@@ -624,9 +667,8 @@ impl HostContainer {
             return hosts
         }
 
-        // Grab random ones
-        let urls = hosts.iter().choose_multiple(&mut OsRng, n.min(hosts.len()));
-        urls.iter().map(|&url| url.clone()).collect()
+        // Grab random ones, or let the selector pick
+        self.select_n(hosts, n)
     }
 
     /// Get up to n random peers that match the given transport schemes.
@@ -651,9 +693,8 @@ impl HostContainer {
             return hosts
         }
 
-        // Grab random ones
-        let urls = hosts.iter().choose_multiple(&mut OsRng, n.min(hosts.len()));
-        urls.iter().map(|&url| url.clone()).collect()
+        // Grab random ones, or let the selector pick
+        self.select_n(hosts, n)
     }
 
     /// Get up to n random peers that don't match the given transport schemes
@@ -679,9 +720,8 @@ impl HostContainer {
             return hosts
         }
 
-        // Grab random ones
-        let urls = hosts.iter().choose_multiple(&mut OsRng, n.min(hosts.len()));
-        urls.iter().map(|&url| url.clone()).collect()
+        // Grab random ones, or let the selector pick
+        self.select_n(hosts, n)
     }
 
     /// Remove an entry from a hostlist if it exists.
@@ -788,8 +828,9 @@ impl HostContainer {
         }
     }
 
-    /// Load the hostlists from a file.
-    pub(in crate::net) fn load_all(&self, path: &str) -> Result<()> {
+    /// Load the hostlists and peer scores from a file. Files written by
+    /// older versions are read as well, and migrated on the next save.
+    pub(in crate::net) fn load_all(&self, path: &str, scores: &PeerScores) -> Result<()> {
         let path = expand_path(path)?;
 
         if !path.exists() {
@@ -806,8 +847,43 @@ impl HostContainer {
             return Ok(())
         }
 
-        for line in contents.unwrap().lines() {
+        let contents = contents.unwrap();
+        let mut lines = contents.lines().peekable();
+
+        // Version 1 files have no header
+        let mut version = 1;
+        if let Some(v) = lines.peek().and_then(|header| header.strip_prefix("version\t")) {
+            version = match v.parse::<u32>() {
+                Ok(v) => v,
+                Err(e) => {
+                    warn!(target: "net::hosts::load_hosts()", "Malformed hostlist version: {e}");
+                    return Ok(())
+                }
+            };
+            lines.next();
+        }
+
+        if version > HOSTLIST_VERSION {
+            warn!(
+                target: "net::hosts::load_hosts()",
+                "Hostlist {path:?} has unknown version {version}, ignoring it",
+            );
+            return Ok(())
+        }
+
+        if version < HOSTLIST_VERSION {
+            info!(
+                target: "net::hosts::load_hosts()",
+                "Migrating hostlist {path:?} from version {version} to {HOSTLIST_VERSION}",
+            );
+        }
+
+        for line in lines {
             let data: Vec<&str> = line.split('\t').collect();
+            if data.len() < 3 {
+                debug!(target: "net::hosts::load_hosts()", "Skipping malformed line");
+                continue
+            }
 
             let url = match Url::parse(data[1]) {
                 Ok(u) => u,
@@ -817,6 +893,18 @@ impl HostContainer {
                 }
             };
 
+            // score\t<url>\t<score>\t<updated>
+            if data[0] == "score" {
+                let score = data[2].parse::<u32>();
+                let updated = data.get(3).map(|u| u.parse::<u64>());
+                let (Ok(score), Some(Ok(updated))) = (score, updated) else {
+                    debug!(target: "net::hosts::load_hosts()", "Skipping malformed score");
+                    continue
+                };
+                scores.import(url, score, updated);
+                continue
+            }
+
             let last_seen = match data[2].parse::<u64>() {
                 Ok(t) => t,
                 Err(e) => {
@@ -858,8 +946,8 @@ impl HostContainer {
         Ok(())
     }
 
-    /// Save the hostlist to a file.
-    pub(in crate::net) fn save_all(&self, path: &str) -> Result<()> {
+    /// Save the hostlists and peer scores to a file.
+    pub(in crate::net) fn save_all(&self, path: &str, scores: &PeerScores) -> Result<()> {
         let path = expand_path(path)?;
 
         let mut tsv = String::new();
@@ -876,9 +964,22 @@ impl HostContainer {
             }
         }
 
+        for (url, score, updated) in scores.export() {
+            tsv.push_str(&format!("score\t{url}\t{score}\t{updated}\n"));
+        }
+
         if !tsv.is_empty() {
             info!(target: "net::hosts::save_hosts()", "Saving hosts to: {path:?}");
-            if let Err(e) = save_file(&path, &tsv) {
+            let tsv = format!("version\t{HOSTLIST_VERSION}\n{tsv}");
+
+            // Write to a temporary file first, so getting killed halfway
+            // through doesn't leave a truncated hostlist behind.
+            let tmp_path = path.with_extension("tmp");
+            if let Err(e) = save_file(&tmp_path, &tsv) {
+                error!(target: "net::hosts::save_hosts()", "Failed saving hosts: {e}");
+                return Ok(())
+            }
+            if let Err(e) = fs::rename(&tmp_path, &path) {
                 error!(target: "net::hosts::save_hosts()", "Failed saving hosts: {e}");
             }
         }
@@ -917,8 +1018,9 @@ pub struct Hosts {
     /// Marker for IPv6 availability
     pub(in crate::net) ipv6_available: AtomicBool,
 
-    /// Auto self discovered addresses. Used for filtering self connections.
-    auto_self_addrs: SyncMutex<RingBuffer<Ipv6Addr, 20>>,
+    /// Our external address as observed by the peers we dialed, along
+    /// with the host of the peer that reported it.
+    auto_self_addrs: SyncMutex<RingBuffer<(String, IpAddr), AUTO_ADDR_OBSERVATIONS>>,
 
     /// Pointer to configured P2P settings
     settings: Arc<AsyncRwLock<Settings>>,
@@ -1144,12 +1246,16 @@ impl Hosts {
     }
 
     /// Add a channel to the set of connected channels
-    pub(in crate::net) async fn register_channel(&self, channel: ChannelPtr) {
+    ///
+    /// Fails if the address is already registered in a conflicting state.
+    /// The caller must then drop the channel without unregistering the
+    /// address, since that would free up the state of the other channel.
+    pub(in crate::net) async fn register_channel(&self, channel: ChannelPtr) -> Result<()> {
         let address = channel.address().clone();
 
         // This is an attempt to skip any Tor (and similar-behaving) inbound connections
         if channel.p2p().settings().read().await.inbound_addrs.contains(&address) {
-            return
+            return Ok(())
         }
 
         // This will error if we are already connected to this peer, this peer
@@ -1157,7 +1263,7 @@ impl Hosts {
         // None of these scenarios should ever happen.
         if let Err(e) = self.try_register(address.clone(), HostState::Connected(channel.clone())) {
             warn!(target: "net::hosts::register_channel", "Error while registering channel {channel:?}: {e:?}");
-            return
+            return Err(e)
         }
 
         // Notify that channel processing was successful
@@ -1165,6 +1271,7 @@ impl Hosts {
 
         let mut last_online = self.last_connection.lock().unwrap();
         *last_online = Instant::now();
+        Ok(())
     }
 
     /// Get notified when new hosts have been inserted into a hostlist.
@@ -1383,6 +1490,14 @@ impl Hosts {
                     );
                 }
 
+                #[cfg(feature = "p2p-ws")]
+                "ws" | "wss" => {
+                    trace!(
+                        target: "net::hosts::filter_addresses",
+                        "[WebSocket] Valid: {host_str}"
+                    );
+                }
+
                 #[cfg(feature = "p2p-i2p")]
                 "i2p" | "i2p+tls" => {
                     if !Self::is_i2p_host(host_str) {
@@ -1470,6 +1585,18 @@ impl Hosts {
         Ok(())
     }
 
+    /// Forget about a host, removing it from the grey, white and gold lists.
+    /// Unlike blacklisting, the host can be learned about again later.
+    pub fn drop_host(&self, addr: &Url) {
+        debug!(target: "net::hosts::drop_host()", "Dropping addr={addr}");
+        for color in [HostColor::Grey, HostColor::White, HostColor::Gold] {
+            self.container.remove_if_exists(color, addr);
+        }
+
+        // Free up this addr for future operations.
+        self.unregister(addr);
+    }
+
     /// A single function for moving hosts between hostlists. Called on the following occasions:
     ///
     /// * When we cannot connect to a peer: move to grey, remove from white and gold.
@@ -1551,24 +1678,46 @@ impl Hosts {
         Ok(())
     }
 
-    /// Upon version exchange, the node reports our external network address to us.
-    /// Accumulate them here in a ring buffer.
-    pub(in crate::net) fn add_auto_addr(&self, addr: Ipv6Addr) {
+    /// Upon version exchange, the node reports the address it sees our
+    /// connection coming from. Accumulate them here in a ring buffer.
+    pub(in crate::net) fn add_auto_addr(&self, reporter: &str, addr: IpAddr) {
         let mut auto_addrs = self.auto_self_addrs.lock().unwrap();
-        auto_addrs.push(addr);
+        auto_addrs.push((reporter.to_string(), addr));
     }
 
-    /// Pick the most frequent occuring reported external address from other nodes as
-    /// our auto ipv6 address.
-    pub fn guess_auto_addr(&self) -> Option<Ipv6Addr> {
-        let mut auto_addrs = self.auto_self_addrs.lock().unwrap();
-        let items = auto_addrs.make_contiguous();
-        most_frequent_or_any(items)
+    /// Pick our external address for the given IP family out of the
+    /// addresses reported by other nodes. Only the latest report of each
+    /// peer is counted, so a single peer cannot skew the result. The most
+    /// reported address is returned along with the share of peers agreeing
+    /// on it, provided enough peers agree.
+    pub fn guess_auto_addr(&self, ipv6: bool) -> Option<(IpAddr, f32)> {
+        let auto_addrs = self.auto_self_addrs.lock().unwrap();
+
+        let mut latest: HashMap<&str, IpAddr> = HashMap::new();
+        for (reporter, addr) in auto_addrs.iter().rev() {
+            if addr.is_ipv6() == ipv6 {
+                latest.entry(reporter.as_str()).or_insert(*addr);
+            }
+        }
+
+        let mut votes: HashMap<IpAddr, usize> = HashMap::new();
+        for addr in latest.values() {
+            *votes.entry(*addr).or_default() += 1;
+        }
+
+        let (addr, count) = votes.into_iter().max_by_key(|(_, count)| *count)?;
+        let confidence = count as f32 / latest.len() as f32;
+        if count < AUTO_ADDR_MIN_REPORTERS || confidence <= AUTO_ADDR_MIN_CONFIDENCE {
+            return None
+        }
+
+        Some((addr, confidence))
     }
 
     /// The external_addrs is set by the user but we need the actual addresses.
-    /// If the external_addr is set to `[::]` (unspecified), then replace it with the
-    /// the best guess from `guess_auto_addr()`.
+    /// If the external_addr is set to `0.0.0.0` or `[::]` (unspecified), then replace
+    /// it with the best guess from `guess_auto_addr()`, or leave it out if there is
+    /// no confident guess yet.
     /// Also if the port is 0, we lookup the port from the `InboundSession`.
     pub async fn external_addrs(&self) -> Vec<Url> {
         let mut external_addrs = self.settings.read().await.external_addrs.clone();
@@ -1578,29 +1727,46 @@ impl Hosts {
             let _ = self.patch_port(ext_addr);
             let _ = self.patch_auto_addr(ext_addr);
         }
+
+        // Never advertise an unspecified address, peers can't reach it
+        external_addrs.retain(|addr| !Self::is_unspecified(addr));
         external_addrs
     }
 
-    /// Make a best effort guess from the most frequently reported ipv6 auto address
-    /// to set any unspecified ipv6 addrs: `external_addrs = ["tcp://[::]:1365"]`.
-    fn patch_auto_addr(&self, ext_addr: &mut Url) -> Option<()> {
-        if ext_addr.scheme() != "tcp" && ext_addr.scheme() != "tcp+tls" {
-            return None
+    /// Check whether the URL host is `0.0.0.0` or `[::]`
+    fn is_unspecified(url: &Url) -> bool {
+        match url.host() {
+            Some(Host::Ipv4(ip)) => ip.is_unspecified(),
+            Some(Host::Ipv6(ip)) => ip.is_unspecified(),
+            _ => false,
         }
+    }
 
-        let ext_host = ext_addr.host()?;
-        // Is it an Ipv6 listener?
-        let Host::Ipv6(ext_ip) = ext_host else { return None };
-        // We are only interested if it's [::]
-        if !ext_ip.is_unspecified() {
+    /// Make a best effort guess from the addresses reported by peers to set any
+    /// unspecified addrs: `external_addrs = ["tcp://0.0.0.0:1365", "tcp://[::]:1365"]`.
+    /// Only the host is replaced, the port stays the one we configured since peers
+    /// only get to see the ephemeral port of our outbound connections.
+    fn patch_auto_addr(&self, ext_addr: &mut Url) -> Option<()> {
+        if !matches!(ext_addr.scheme(), "tcp" | "tcp+tls" | "ws" | "wss") {
             return None
         }
 
+        // We are only interested if it's 0.0.0.0 or [::]
+        let ipv6 = match ext_addr.host()? {
+            Host::Ipv4(ip) if ip.is_unspecified() => false,
+            Host::Ipv6(ip) if ip.is_unspecified() => true,
+            _ => return None,
+        };
+
         // Get our auto-discovered IP
-        let auto_addr = self.guess_auto_addr()?;
+        let (auto_addr, confidence) = self.guess_auto_addr(ipv6)?;
+        trace!(
+            target: "net::hosts::patch_auto_addr",
+            "Using discovered external address {auto_addr} (confidence {confidence:.2})"
+        );
 
         // Do the actual replacement of the host part of the URL
-        ext_addr.set_ip_host(IpAddr::V6(auto_addr)).ok()?;
+        ext_addr.set_ip_host(auto_addr).ok()?;
         Some(())
     }
 
@@ -1719,6 +1885,43 @@ mod tests {
     use super::*;
     use crate::system::sleep;
 
+    #[test]
+    fn test_guess_auto_addr() {
+        let hosts = Hosts::new(Arc::new(AsyncRwLock::new(Settings::default())));
+        let ours: IpAddr = "77.168.10.65".parse().unwrap();
+        let other: IpAddr = "77.168.10.66".parse().unwrap();
+
+        // A single peer repeating itself is not enough
+        for _ in 0..5 {
+            hosts.add_auto_addr("1.1.1.1", ours);
+        }
+        assert_eq!(hosts.guess_auto_addr(false), None);
+
+        hosts.add_auto_addr("2.2.2.2", ours);
+        hosts.add_auto_addr("3.3.3.3", ours);
+        assert_eq!(hosts.guess_auto_addr(false), Some((ours, 1.0)));
+        assert_eq!(hosts.guess_auto_addr(true), None);
+
+        // Without a majority there is no guess
+        hosts.add_auto_addr("4.4.4.4", other);
+        hosts.add_auto_addr("5.5.5.5", other);
+        hosts.add_auto_addr("6.6.6.6", other);
+        assert_eq!(hosts.guess_auto_addr(false), None);
+
+        // Only the latest report of a peer counts
+        hosts.add_auto_addr("1.1.1.1", other);
+        assert_eq!(hosts.guess_auto_addr(false), Some((other, 4.0 / 6.0)));
+
+        // Unspecified addresses get patched or left out
+        let mut ext_addr = Url::parse("tcp+tls://0.0.0.0:26661").unwrap();
+        assert!(hosts.patch_auto_addr(&mut ext_addr).is_some());
+        assert_eq!(ext_addr.as_str(), "tcp+tls://77.168.10.66:26661");
+
+        let mut ext_addr = Url::parse("tcp+tls://[::]:26661").unwrap();
+        assert!(hosts.patch_auto_addr(&mut ext_addr).is_none());
+        assert!(Hosts::is_unspecified(&ext_addr));
+    }
+
     #[test]
     fn test_is_local_host() {
         let settings = Settings {
@@ -2050,4 +2253,47 @@ mod tests {
         let endpoints: Vec<_> = fetched_hosts.iter().map(|item| item.0.scheme()).collect();
         assert!(endpoints.iter().all(|&scheme| scheme == "tor" || scheme == "socks5"));
     }
+
+    #[test]
+    fn test_save_load() {
+        let dir = std::env::temp_dir().join(format!("darkfi-hostlist-{}", OsRng.gen::<u64>()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("hostlist.tsv");
+        let path_str = path.to_str().unwrap();
+
+        let gold = Url::parse("tcp+tls://dark.fi:26661").unwrap();
+        let grey = Url::parse("tcp+tls://foo.bar:26661").unwrap();
+
+        // Version 1 files have no header and no scores
+        let v1 = format!("gold\t{gold}\t1720000000\ngrey\t{grey}\t1720000001\nbroken\n");
+        save_file(&path, &v1).unwrap();
+
+        let container = HostContainer::new();
+        let scores = PeerScores::default();
+        container.load_all(path_str, &scores).unwrap();
+        assert!(container.contains(HostColor::Gold as usize, &gold));
+        assert!(container.contains(HostColor::Grey as usize, &grey));
+        assert!(scores.export().is_empty());
+
+        // Saving migrates to the current version, keeping the scores
+        scores.import(grey.clone(), 40, 1720000002);
+        container.save_all(path_str, &scores).unwrap();
+        let saved = load_file(&path).unwrap();
+        assert!(saved.starts_with(&format!("version\t{HOSTLIST_VERSION}\n")));
+
+        let container = HostContainer::new();
+        let scores = PeerScores::default();
+        container.load_all(path_str, &scores).unwrap();
+        assert_eq!(container.fetch_all(HostColor::Gold), vec![(gold, 1720000000)]);
+        assert_eq!(container.fetch_all(HostColor::Grey), vec![(grey.clone(), 1720000001)]);
+        assert_eq!(scores.export(), vec![(grey, 40, 1720000002)]);
+
+        // Files from the future are left alone
+        save_file(&path, "version\t999\ngold\ttcp+tls://dark.fi:26661\t1\n").unwrap();
+        let container = HostContainer::new();
+        container.load_all(path_str, &PeerScores::default()).unwrap();
+        assert!(container.is_empty(HostColor::Gold));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -0,0 +1,278 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Local network peer discovery.
+//!
+//! Nodes on the same LAN can find each other without any seed. When
+//! `lan_discovery` is enabled, every node periodically multicasts a
+//! small beacon holding the magic bytes of its network and the inbound
+//! addresses it accepts connections on. Addresses from beacons of the
+//! same network get greylisted, and the refinery picks them up like
+//! any other address.
+//!
+//! Inbound addresses listening on an unspecified host (`0.0.0.0` or
+//! `[::]`) are announced as such, and the receiving end fills in the
+//! address the beacon came from. Only beacons sent from private,
+//! link-local or loopback addresses are accepted, and only addresses
+//! on the sender's own IP are taken from them, so a beacon can't make
+//! us dial anyone else. LAN addresses are private, so `localnet` has
+//! to be enabled for them to pass the hosts filter.
+
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4},
+    sync::{Arc, Weak},
+    time::{Duration, Instant, UNIX_EPOCH},
+};
+
+use darkfi_serial::{deserialize, serialize, SerialDecodable, SerialEncodable};
+use log::{debug, info};
+use rand::{rngs::OsRng, Rng};
+use smol::net::UdpSocket;
+use socket2::{Domain, Protocol, Socket, Type};
+use url::{Host, Url};
+
+use super::{hosts::HostColor, p2p::P2p};
+use crate::{system::timeout::timeout, Result};
+
+/// Multicast group beacons are sent to
+const LAN_GROUP: Ipv4Addr = Ipv4Addr::new(239, 255, 77, 77);
+/// UDP port beacons are sent to
+const LAN_PORT: u16 = 26600;
+/// Beacons bigger than this are ignored
+const MAX_BEACON_SIZE: usize = 1024;
+/// Maximum number of addresses taken from a single beacon
+const MAX_BEACON_ADDRS: usize = 8;
+/// Transports reachable on a local network
+const LAN_TRANSPORTS: [&str; 2] = ["tcp", "tcp+tls"];
+
+/// Announcement multicast by every node on the LAN
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+struct Beacon {
+    /// Magic bytes of the sender's P2P network
+    magic: [u8; 4],
+    /// Random per-process value so we can ignore our own beacons
+    nonce: u64,
+    /// Inbound addresses of the sender
+    addrs: Vec<Url>,
+}
+
+pub type LanDiscoveryPtr = Arc<LanDiscovery>;
+
+/// Announces our inbound addresses on the LAN and greylists the
+/// addresses announced by other nodes of the same network.
+pub struct LanDiscovery {
+    p2p: Weak<P2p>,
+    nonce: u64,
+}
+
+impl LanDiscovery {
+    pub fn new(p2p: Weak<P2p>) -> LanDiscoveryPtr {
+        Arc::new(Self { p2p, nonce: OsRng.gen() })
+    }
+
+    fn p2p(&self) -> Arc<P2p> {
+        self.p2p.upgrade().unwrap()
+    }
+
+    /// Join the multicast group and send a beacon every
+    /// `lan_discovery_interval` seconds, handling the beacons of other
+    /// nodes in between.
+    pub async fn run(self: Arc<Self>) -> Result<()> {
+        let socket = bind_multicast()?;
+        info!(
+            target: "net::lan::run",
+            "[P2P] LAN discovery listening on {LAN_GROUP}:{LAN_PORT}",
+        );
+
+        let mut buf = [0u8; MAX_BEACON_SIZE];
+        loop {
+            let interval = self.p2p().settings().read().await.lan_discovery_interval.max(1);
+            self.announce(&socket).await;
+
+            let deadline = Instant::now() + Duration::from_secs(interval);
+            loop {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                let Ok(received) = timeout(remaining, socket.recv_from(&mut buf)).await else {
+                    break
+                };
+
+                let (len, from) = received?;
+                self.handle_beacon(&buf[..len], from).await;
+            }
+        }
+    }
+
+    /// Multicast our LAN reachable inbound addresses, if we have any
+    async fn announce(&self, socket: &UdpSocket) {
+        let settings = self.p2p().settings();
+        let settings = settings.read().await;
+        let addrs: Vec<Url> = settings
+            .inbound_addrs
+            .iter()
+            .filter(|addr| LAN_TRANSPORTS.contains(&addr.scheme()))
+            .cloned()
+            .collect();
+
+        if addrs.is_empty() {
+            return
+        }
+
+        let beacon = Beacon { magic: settings.magic_bytes.0, nonce: self.nonce, addrs };
+        drop(settings);
+
+        let group = SocketAddr::V4(SocketAddrV4::new(LAN_GROUP, LAN_PORT));
+        if let Err(e) = socket.send_to(&serialize(&beacon), group).await {
+            debug!(target: "net::lan::announce", "[P2P] Failed sending LAN beacon: {e}");
+        }
+    }
+
+    /// Greylist the addresses of a beacon sent by another node of our network
+    async fn handle_beacon(&self, buf: &[u8], from: SocketAddr) {
+        let Ok(beacon) = deserialize::<Beacon>(buf) else {
+            debug!(target: "net::lan::handle_beacon", "[P2P] Invalid LAN beacon from {from}");
+            return
+        };
+
+        if !is_lan_ip(from.ip()) {
+            debug!(target: "net::lan::handle_beacon", "[P2P] Ignoring LAN beacon from non-local {from}");
+            return
+        }
+
+        let p2p = self.p2p();
+        if beacon.nonce == self.nonce || beacon.magic != p2p.settings().read().await.magic_bytes.0 {
+            return
+        }
+
+        let now = UNIX_EPOCH.elapsed().unwrap().as_secs();
+        let addrs: Vec<(Url, u64)> = beacon
+            .addrs
+            .iter()
+            .take(MAX_BEACON_ADDRS)
+            .filter_map(|addr| resolve_addr(addr, from.ip()))
+            .map(|addr| (addr, now))
+            .collect();
+
+        debug!(target: "net::lan::handle_beacon", "[P2P] LAN beacon from {from}: {addrs:?}");
+        p2p.hosts().insert(HostColor::Grey, &addrs).await;
+    }
+}
+
+/// Create a UDP socket bound to the LAN discovery port and joined to the
+/// multicast group. Several nodes on the same machine can share the port.
+fn bind_multicast() -> Result<UdpSocket> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_reuse_address(true)?;
+    #[cfg(target_family = "unix")]
+    socket.set_reuse_port(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, LAN_PORT)).into())?;
+    socket.join_multicast_v4(&LAN_GROUP, &Ipv4Addr::UNSPECIFIED)?;
+    socket.set_multicast_loop_v4(true)?;
+
+    Ok(UdpSocket::try_from(std::net::UdpSocket::from(socket))?)
+}
+
+/// Check if a beacon sender is on the local network, i.e. it has a
+/// private, link-local or loopback address.
+fn is_lan_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_private() || ip.is_link_local() || ip.is_loopback(),
+        IpAddr::V6(ip) => {
+            // Unique local fc00::/7 and link-local fe80::/10
+            let segment = ip.segments()[0];
+            (segment & 0xfe00) == 0xfc00 || (segment & 0xffc0) == 0xfe80 || ip.is_loopback()
+        }
+    }
+}
+
+/// Turn an announced address into one we can dial. Unspecified hosts
+/// are replaced by the address the beacon came from. Returns `None` for
+/// transports not reachable on the LAN, and for addresses other than
+/// the one the beacon came from.
+fn resolve_addr(addr: &Url, from: IpAddr) -> Option<Url> {
+    if !LAN_TRANSPORTS.contains(&addr.scheme()) || addr.port().is_none() {
+        return None
+    }
+
+    let host = match addr.host()? {
+        Host::Ipv4(ip) => IpAddr::V4(ip),
+        Host::Ipv6(ip) => IpAddr::V6(ip),
+        Host::Domain(_) => return None,
+    };
+
+    let mut addr = addr.clone();
+    if host.is_unspecified() {
+        addr.set_ip_host(from).ok()?;
+    } else if host != from {
+        return None
+    }
+
+    Some(addr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lan_resolve_addr() {
+        let from = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 7));
+
+        let addr = Url::parse("tcp+tls://0.0.0.0:26661").unwrap();
+        assert_eq!(
+            resolve_addr(&addr, from),
+            Some(Url::parse("tcp+tls://192.168.1.7:26661").unwrap())
+        );
+
+        let addr = Url::parse("tcp://192.168.1.7:26661").unwrap();
+        assert_eq!(resolve_addr(&addr, from), Some(addr));
+
+        // Addresses other than the sender's are dropped
+        let addr = Url::parse("tcp://192.168.1.8:26661").unwrap();
+        assert_eq!(resolve_addr(&addr, from), None);
+
+        let addr = Url::parse("tcp://example.com:26661").unwrap();
+        assert_eq!(resolve_addr(&addr, from), None);
+
+        let addr = Url::parse("tor://192.168.1.7:26661").unwrap();
+        assert_eq!(resolve_addr(&addr, from), None);
+
+        let addr = Url::parse("tcp://192.168.1.7").unwrap();
+        assert_eq!(resolve_addr(&addr, from), None);
+
+        let beacon = Beacon { magic: [1, 2, 3, 4], nonce: 42, addrs: vec![addr] };
+        let decoded: Beacon = deserialize(&serialize(&beacon)).unwrap();
+        assert_eq!(decoded.nonce, beacon.nonce);
+        assert_eq!(decoded.addrs, beacon.addrs);
+    }
+
+    #[test]
+    fn lan_beacon_sources() {
+        assert!(is_lan_ip("192.168.1.7".parse().unwrap()));
+        assert!(is_lan_ip("10.0.0.1".parse().unwrap()));
+        assert!(is_lan_ip("172.16.5.4".parse().unwrap()));
+        assert!(is_lan_ip("169.254.1.1".parse().unwrap()));
+        assert!(is_lan_ip("127.0.0.1".parse().unwrap()));
+        assert!(is_lan_ip("fd00::1".parse().unwrap()));
+        assert!(is_lan_ip("fe80::1".parse().unwrap()));
+
+        assert!(!is_lan_ip("8.8.8.8".parse().unwrap()));
+        assert!(!is_lan_ip("172.32.0.1".parse().unwrap()));
+        assert!(!is_lan_ip("2001:db8::1".parse().unwrap()));
+    }
+}
@@ -16,7 +16,7 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::net::Ipv6Addr;
+use std::net::IpAddr;
 
 use darkfi_serial::{
     async_trait, serialize_async, AsyncDecodable, AsyncEncodable, SerialDecodable, SerialEncodable,
@@ -37,17 +37,39 @@ pub trait Message: 'static + Send + Sync + AsyncDecodable + AsyncEncodable {
     /// Message metering configuration for rate limit.
     /// Use `MeteringConfiguration::default()` for no limit.
     const METERING_CONFIGURATION: MeteringConfiguration;
+    /// Message send priority.
+    const PRIORITY: MessagePriority = MessagePriority::Normal;
+}
+
+/// Send priority of a message. When several messages are waiting to be
+/// written to the same channel, the ones with higher priority go first,
+/// so bulk traffic can't hold back block propagation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MessagePriority {
+    /// Keepalives and address exchange
+    Low,
+    /// Event graph gossip and DAG sync
+    Gossip,
+    /// Transactions and everything else
+    Normal,
+    /// Consensus messages and block propagation
+    High,
 }
 
 /// Generic serialized message template.
 pub struct SerializedMessage {
     pub command: String,
     pub payload: Vec<u8>,
+    pub priority: MessagePriority,
 }
 
 impl SerializedMessage {
     pub async fn new<M: Message>(message: &M) -> Self {
-        Self { command: M::NAME.to_string(), payload: serialize_async(message).await }
+        Self {
+            command: M::NAME.to_string(),
+            payload: serialize_async(message).await,
+            priority: M::PRIORITY,
+        }
     }
 }
 
@@ -61,6 +83,15 @@ macro_rules! impl_p2p_message {
             const METERING_CONFIGURATION: MeteringConfiguration = $mc;
         }
     };
+    ($st:ty, $nm:expr, $mb:expr, $ms:expr, $mc:expr, $pr:expr) => {
+        impl Message for $st {
+            const NAME: &'static str = $nm;
+            const MAX_BYTES: u64 = $mb;
+            const METERING_SCORE: u64 = $ms;
+            const METERING_CONFIGURATION: MeteringConfiguration = $mc;
+            const PRIORITY: MessagePriority = $pr;
+        }
+    };
 }
 
 /// Maximum command (message name) length in bytes.
@@ -84,14 +115,28 @@ pub const PING_PONG_MAX_BYTES: u64 = 2;
 pub struct PingMessage {
     pub nonce: u16,
 }
-impl_p2p_message!(PingMessage, "ping", PING_PONG_MAX_BYTES, 1, PING_PONG_METERING_CONFIGURATION);
+impl_p2p_message!(
+    PingMessage,
+    "ping",
+    PING_PONG_MAX_BYTES,
+    1,
+    PING_PONG_METERING_CONFIGURATION,
+    MessagePriority::Low
+);
 
 /// Inbound keepalive message.
 #[derive(Debug, Copy, Clone, SerialEncodable, SerialDecodable)]
 pub struct PongMessage {
     pub nonce: u16,
 }
-impl_p2p_message!(PongMessage, "pong", PING_PONG_MAX_BYTES, 1, PING_PONG_METERING_CONFIGURATION);
+impl_p2p_message!(
+    PongMessage,
+    "pong",
+    PING_PONG_MAX_BYTES,
+    1,
+    PING_PONG_METERING_CONFIGURATION,
+    MessagePriority::Low
+);
 
 /// Requests address of outbound connection.
 #[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
@@ -122,7 +167,8 @@ impl_p2p_message!(
     "getaddr",
     GET_ADDRS_MAX_BYTES,
     1,
-    GET_ADDRS_METERING_CONFIGURATION
+    GET_ADDRS_METERING_CONFIGURATION,
+    MessagePriority::Low
 );
 
 /// Sends address information to inbound connection.
@@ -142,7 +188,14 @@ pub const ADDRS_METERING_CONFIGURATION: MeteringConfiguration = MeteringConfigur
 /// Url type is estimated to be max 128 bytes here and for other message below.
 pub const ADDRS_MAX_BYTES: u64 = 65281;
 
-impl_p2p_message!(AddrsMessage, "addr", ADDRS_MAX_BYTES, 1, ADDRS_METERING_CONFIGURATION);
+impl_p2p_message!(
+    AddrsMessage,
+    "addr",
+    ADDRS_MAX_BYTES,
+    1,
+    ADDRS_METERING_CONFIGURATION,
+    MessagePriority::Low
+);
 
 /// Requests version information of outbound connection.
 #[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
@@ -154,7 +207,9 @@ pub struct VersionMessage {
     /// UNIX timestamp of when the VersionMessage was created.
     pub timestamp: u64,
     /// Network address of the node receiving this message (before
-    /// resolving).
+    /// resolving). On inbound connections this is the address the
+    /// connection was observed coming from, which lets nodes behind
+    /// NAT discover their external address.
     pub connect_recv_addr: Url,
     /// Network address of the node receiving this message (after
     /// resolving). Optional because only used by outbound connections.
@@ -185,12 +240,13 @@ pub const VERSION_MAX_BYTES: u64 = 2043;
 impl_p2p_message!(VersionMessage, "version", VERSION_MAX_BYTES, 1, VERSION_METERING_CONFIGURATION);
 
 impl VersionMessage {
-    pub(in crate::net) fn get_ipv6_addr(&self) -> Option<Ipv6Addr> {
+    /// The address the sender sees us at, if it is an IP address
+    pub(in crate::net) fn get_observed_addr(&self) -> Option<IpAddr> {
         let host = self.connect_recv_addr.host()?;
-        // Check the reported address is Ipv6
         match host {
-            Host::Ipv6(addr) => Some(addr),
-            _ => None,
+            Host::Ipv4(addr) => Some(IpAddr::V4(addr)),
+            Host::Ipv6(addr) => Some(IpAddr::V6(addr)),
+            Host::Domain(_) => None,
         }
     }
 }
@@ -215,3 +271,30 @@ pub const VERACK_METERING_CONFIGURATION: MeteringConfiguration = MeteringConfigu
 pub const VERACK_MAX_BYTES: u64 = 128;
 
 impl_p2p_message!(VerackMessage, "verack", VERACK_MAX_BYTES, 1, VERACK_METERING_CONFIGURATION);
+
+/// Random challenge sent by nodes running in authenticated mode once
+/// the version exchange is done. The other end has to sign it with
+/// its identity key.
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct AuthChallengeMessage {
+    pub nonce: [u8; 32],
+}
+pub const AUTH_METERING_CONFIGURATION: MeteringConfiguration = MeteringConfiguration {
+    threshold: 4,
+    sleep_step: 1000,
+    expiry_time: NanoTimestamp::from_secs(10),
+};
+
+impl_p2p_message!(AuthChallengeMessage, "authchallenge", 32, 1, AUTH_METERING_CONFIGURATION);
+
+/// Response to `AuthChallengeMessage`, proving ownership of the
+/// identity key.
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct AuthResponseMessage {
+    /// Ed25519 public key of the sender's static identity
+    pub public_key: [u8; 32],
+    /// Signature over the received challenge
+    pub signature: [u8; 64],
+}
+
+impl_p2p_message!(AuthResponseMessage, "authresponse", 96, 1, AUTH_METERING_CONFIGURATION);
@@ -22,12 +22,15 @@ use async_trait::async_trait;
 use futures::stream::{FuturesUnordered, StreamExt};
 use log::{debug, error};
 use rand::{rngs::OsRng, Rng};
-use smol::{io::AsyncReadExt, lock::Mutex};
+use smol::{
+    io::{AsyncRead, AsyncReadExt},
+    lock::Mutex,
+};
 
 use super::message::Message;
 use crate::{
-    net::{metering::MeteringQueue, transport::PtStream},
-    system::{msleep, timeout::timeout},
+    net::metering::MeteringQueue,
+    system::{msleep, timeout::timeout, METRICS},
     Error, Result,
 };
 use darkfi_serial::{AsyncDecodable, VarInt};
@@ -221,10 +224,9 @@ impl<M: Message> MessageSubscription<M> {
 /// Generic interface for the message dispatcher.
 #[async_trait]
 trait MessageDispatcherInterface: Send + Sync {
-    async fn trigger(
-        &self,
-        stream: &mut smol::io::ReadHalf<Box<dyn PtStream + 'static>>,
-    ) -> Result<()>;
+    async fn trigger(&self, stream: &mut (dyn AsyncRead + Unpin + Send)) -> Result<()>;
+
+    fn max_bytes(&self) -> u64;
 
     async fn trigger_error(&self, err: Error);
 
@@ -242,12 +244,9 @@ impl<M: Message> MessageDispatcherInterface for MessageDispatcher<M> {
     ///
     /// We extract the message length from the stream and use `take()`
     /// to allocate an appropriately sized buffer as a basic DDOS protection.
-    async fn trigger(
-        &self,
-        stream: &mut smol::io::ReadHalf<Box<dyn PtStream + 'static>>,
-    ) -> Result<()> {
+    async fn trigger(&self, mut stream: &mut (dyn AsyncRead + Unpin + Send)) -> Result<()> {
         // Parse message length
-        let length = match VarInt::decode_async(stream).await {
+        let length = match VarInt::decode_async(&mut stream).await {
             Ok(int) => int.0,
             Err(err) => {
                 error!(
@@ -291,6 +290,11 @@ impl<M: Message> MessageDispatcherInterface for MessageDispatcher<M> {
         self._trigger_all(Err(err)).await;
     }
 
+    /// Message length limit of this dispatcher's message type.
+    fn max_bytes(&self) -> u64 {
+        M::MAX_BYTES
+    }
+
     /// Internal function to retrieve metering queue current total score,
     /// after prunning expired metering information.
     async fn metering_score(&self) -> u64 {
@@ -361,12 +365,19 @@ impl MessageSubsystem {
         Ok(sub)
     }
 
+    /// Returns the length limit of the message dispatched for `command`,
+    /// or `None` if we have no dispatcher for it. A limit of 0 means the
+    /// message has no limit.
+    pub async fn max_bytes(&self, command: &str) -> Option<u64> {
+        self.dispatchers.lock().await.get(command).map(|x| x.max_bytes())
+    }
+
     /// Transmits a payload to a dispatcher.
     /// Returns an error if the payload fails to transmit.
     pub async fn notify(
         &self,
         command: &str,
-        reader: &mut smol::io::ReadHalf<Box<dyn PtStream + 'static>>,
+        reader: &mut (dyn AsyncRead + Unpin + Send),
     ) -> Result<()> {
         // Iterate over dispatchers and keep track of their current
         // metering score
@@ -377,6 +388,16 @@ impl MessageSubsystem {
             if name == &command {
                 dispatcher.trigger(reader).await?;
                 found = true;
+
+                // Only dispatched commands are counted, so the label
+                // values are bounded by the registered message types.
+                METRICS
+                    .counter(
+                        "darkfi_p2p_messages_received_total",
+                        "P2P messages received, by command",
+                        &[("command", command)],
+                    )
+                    .inc();
             }
 
             // Grab its total score
@@ -26,7 +26,7 @@ mod tests;
 /// Implements a type called `Packet` which is the base message type.
 /// Packets are converted into messages and passed to an event loop.
 pub mod message;
-pub use message::Message;
+pub use message::{Message, MessagePriority};
 
 /// Generic publish/subscribe class that can dispatch any kind of message
 /// to a subscribed list of dispatchers.
@@ -110,6 +110,30 @@ pub use protocol::{
 /// common functions across all sessions.
 pub mod session;
 
+/// Peer reputation. Misbehavior of peers adds up to a score which
+/// decays over time. Peers reaching the configured thresholds get
+/// disconnected or banned.
+pub mod scoring;
+
+/// Bandwidth throttling. Token buckets limiting the traffic of single
+/// channels and of the whole P2P instance, along with traffic counters.
+pub mod throttle;
+
+/// Message compression. Large messages get zstd compressed towards
+/// peers which announced support for it in the version handshake.
+pub mod compression;
+
+/// NAT traversal. Maps the inbound ports on the local gateway using
+/// NAT-PMP or UPnP, and advertises the resulting external addresses.
+pub mod nat;
+
+pub mod lan;
+
+/// Kademlia-style DHT overlay storing signed peer records. Used by the
+/// outbound session to discover peers before falling back to seeds.
+#[cfg(feature = "dht")]
+pub mod peer_dht;
+
 /// Handles the acceptance of inbound socket connections.
 /// Used to start listening on a local socket, to accept incoming connections,
 /// and to handle network errors.
@@ -122,12 +146,16 @@ pub mod connector;
 /// Network configuration settings. This holds the configured P2P instance
 /// behaviour and is controlled by clients of this API.
 pub mod settings;
-pub use settings::{BanPolicy, Settings};
+pub use settings::{BanPolicy, Keepalive, NodeRole, Settings};
 
 /// Optional events based debug-notify subsystem. Off by default. Enabled in P2P instance,
 /// and then call `p2p.dnet_sub()` to start receiving events.
 #[macro_use]
 pub mod dnet;
 
+/// Connectivity events, always published. Call `p2p.subscribe_events()`
+/// to start receiving them.
+pub mod event;
+
 /// Metering related definitions.
 pub mod metering;
@@ -0,0 +1,554 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! NAT traversal for inbound connections.
+//!
+//! Nodes behind a home router can't accept inbound connections unless
+//! a port forward exists. When `nat_traversal` is enabled, the ports of
+//! the configured inbound addresses get mapped on the local gateway using
+//! NAT-PMP (RFC 6886), falling back to UPnP IGD. Leases are refreshed
+//! periodically, and the mapped external address gets added to the
+//! external addresses we advertise to peers.
+
+use std::{
+    io::ErrorKind,
+    net::{Ipv4Addr, SocketAddrV4},
+    sync::{Arc, Weak},
+    time::Duration,
+};
+
+use log::{debug, info, warn};
+use smol::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    lock::Mutex,
+    net::{TcpStream, UdpSocket},
+};
+use url::{Host, Url};
+
+use super::p2p::P2p;
+use crate::{
+    system::{io_timeout, sleep},
+    Error, Result,
+};
+
+/// NAT-PMP server port on the gateway
+const NATPMP_PORT: u16 = 5351;
+/// SSDP multicast address used for UPnP gateway discovery
+const SSDP_ADDR: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::new(239, 255, 255, 250), 1900);
+/// UPnP services able to map ports
+const UPNP_SERVICES: [&str; 2] = [
+    "urn:schemas-upnp-org:service:WANIPConnection:1",
+    "urn:schemas-upnp-org:service:WANPPPConnection:1",
+];
+/// Timeout of a single request to the gateway
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+/// Seconds to wait before retrying a failed mapping
+const RETRY_INTERVAL: u64 = 300;
+
+/// Gateway holding our port mappings
+#[derive(Clone, Debug)]
+enum Gateway {
+    NatPmp(Ipv4Addr),
+    Upnp { control_url: Url, service_type: String, local_ip: Ipv4Addr },
+}
+
+/// Port mapped on the gateway for one of our inbound addresses
+#[derive(Clone, Debug)]
+struct PortMapping {
+    gateway: Gateway,
+    /// Port we listen on locally
+    internal_port: u16,
+    /// Port assigned by the gateway
+    external_port: u16,
+    /// External address we advertise for this mapping, if it's public
+    advertised: Option<Url>,
+}
+
+pub type PortMapperPtr = Arc<PortMapper>;
+
+/// Maps the inbound ports on the gateway and keeps the leases alive
+pub struct PortMapper {
+    p2p: Weak<P2p>,
+    mappings: Mutex<Vec<PortMapping>>,
+}
+
+impl PortMapper {
+    pub fn new(p2p: Weak<P2p>) -> PortMapperPtr {
+        Arc::new(Self { p2p, mappings: Mutex::new(vec![]) })
+    }
+
+    fn p2p(&self) -> Arc<P2p> {
+        self.p2p.upgrade().unwrap()
+    }
+
+    /// Map the inbound ports and refresh the leases at half their lifetime.
+    /// Failed mappings are retried every [`RETRY_INTERVAL`] seconds.
+    pub async fn run(self: Arc<Self>) -> Result<()> {
+        loop {
+            let settings = self.p2p().settings();
+            let settings = settings.read().await;
+            let inbound_addrs = settings.inbound_addrs.clone();
+            let lease = settings.nat_lease_time;
+            drop(settings);
+
+            let mut interval = (lease / 2).max(1);
+            for addr in &inbound_addrs {
+                let Some(port) = mappable_port(addr) else { continue };
+
+                if let Err(e) = self.map_port(addr, port, lease as u32).await {
+                    warn!(
+                        target: "net::nat::run",
+                        "[P2P] Failed mapping inbound port {port} on the gateway: {e}"
+                    );
+                    interval = interval.min(RETRY_INTERVAL);
+                }
+            }
+
+            sleep(interval).await;
+        }
+    }
+
+    /// Remove all port mappings from the gateway and stop advertising
+    /// their external addresses.
+    pub async fn stop(&self) {
+        let mappings = std::mem::take(&mut *self.mappings.lock().await);
+        for mapping in mappings {
+            if let Err(e) =
+                mapping.gateway.unmap(mapping.internal_port, mapping.external_port).await
+            {
+                debug!(
+                    target: "net::nat::stop",
+                    "[P2P] Failed removing mapping of port {}: {e}", mapping.internal_port,
+                );
+            }
+
+            if let Some(advertised) = mapping.advertised {
+                self.p2p().settings().write().await.external_addrs.retain(|a| a != &advertised);
+            }
+        }
+    }
+
+    /// Create or refresh the mapping of the given inbound address
+    async fn map_port(&self, addr: &Url, port: u16, lease: u32) -> Result<()> {
+        let mut mappings = self.mappings.lock().await;
+        let existing = mappings.iter().position(|m| m.internal_port == port);
+
+        let (gateway, preferred_port) = match existing {
+            Some(i) => (mappings[i].gateway.clone(), mappings[i].external_port),
+            None => (discover_gateway().await?, port),
+        };
+
+        let (external_ip, external_port) = gateway.map(port, preferred_port, lease).await?;
+
+        let advertised = if is_public(&external_ip) {
+            let mut url = addr.clone();
+            let _ = url.set_ip_host(external_ip.into());
+            let _ = url.set_port(Some(external_port));
+            Some(url)
+        } else {
+            warn!(
+                target: "net::nat::map_port",
+                "[P2P] Gateway external address {external_ip} is not public, \
+                 there is another NAT in front of it"
+            );
+            None
+        };
+
+        let previous = existing.and_then(|i| mappings[i].advertised.clone());
+        if previous != advertised {
+            if let Some(advertised) = &advertised {
+                info!(
+                    target: "net::nat::map_port",
+                    "[P2P] Mapped inbound port {port}, advertising {advertised}"
+                );
+            }
+
+            let settings = self.p2p().settings();
+            let mut settings = settings.write().await;
+            if let Some(previous) = &previous {
+                settings.external_addrs.retain(|a| a != previous);
+            }
+            if let Some(advertised) = &advertised {
+                if !settings.external_addrs.contains(advertised) {
+                    settings.external_addrs.push(advertised.clone());
+                }
+            }
+        }
+
+        let mapping = PortMapping { gateway, internal_port: port, external_port, advertised };
+        match existing {
+            Some(i) => mappings[i] = mapping,
+            None => mappings.push(mapping),
+        }
+
+        Ok(())
+    }
+}
+
+impl Gateway {
+    /// Map `external_port` to our `internal_port`. Returns the external
+    /// address of the gateway and the port it actually assigned.
+    async fn map(
+        &self,
+        internal_port: u16,
+        external_port: u16,
+        lease: u32,
+    ) -> Result<(Ipv4Addr, u16)> {
+        match self {
+            Self::NatPmp(gateway) => {
+                let external_ip = natpmp_external_ip(*gateway).await?;
+                let port = natpmp_map(*gateway, internal_port, external_port, lease).await?;
+                Ok((external_ip, port))
+            }
+
+            Self::Upnp { control_url, service_type, local_ip } => {
+                let args = [
+                    ("NewRemoteHost", String::new()),
+                    ("NewExternalPort", external_port.to_string()),
+                    ("NewProtocol", "TCP".to_string()),
+                    ("NewInternalPort", internal_port.to_string()),
+                    ("NewInternalClient", local_ip.to_string()),
+                    ("NewEnabled", "1".to_string()),
+                    ("NewPortMappingDescription", "darkfi".to_string()),
+                    ("NewLeaseDuration", lease.to_string()),
+                ];
+                soap_call(control_url, service_type, "AddPortMapping", &args).await?;
+
+                let body =
+                    soap_call(control_url, service_type, "GetExternalIPAddress", &[]).await?;
+                let Some(external_ip) =
+                    xml_tag(&body, "NewExternalIPAddress").and_then(|ip| ip.trim().parse().ok())
+                else {
+                    return Err(Error::NatTraversalFailed("Missing UPnP external address".into()))
+                };
+
+                Ok((external_ip, external_port))
+            }
+        }
+    }
+
+    /// Remove a mapping from the gateway
+    async fn unmap(&self, internal_port: u16, external_port: u16) -> Result<()> {
+        match self {
+            // RFC 6886: A lifetime of zero deletes the mapping, and the
+            // suggested external port must then be zero as well.
+            Self::NatPmp(gateway) => {
+                natpmp_map(*gateway, internal_port, 0, 0).await?;
+            }
+
+            Self::Upnp { control_url, service_type, .. } => {
+                let args = [
+                    ("NewRemoteHost", String::new()),
+                    ("NewExternalPort", external_port.to_string()),
+                    ("NewProtocol", "TCP".to_string()),
+                ];
+                soap_call(control_url, service_type, "DeletePortMapping", &args).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Local port of an inbound address we are able to map. NAT-PMP and
+/// UPnP IGDv1 only deal with IPv4 TCP ports.
+fn mappable_port(addr: &Url) -> Option<u16> {
+    if !matches!(addr.scheme(), "tcp" | "tcp+tls" | "ws" | "wss") {
+        return None
+    }
+
+    match addr.host()? {
+        Host::Ipv4(ip) if ip.is_unspecified() || ip.is_private() => {}
+        _ => return None,
+    }
+
+    addr.port().filter(|port| *port != 0)
+}
+
+/// Check whether the gateway external address is reachable from the
+/// internet. Carrier-grade NAT uses the shared `100.64.0.0/10` range.
+fn is_public(ip: &Ipv4Addr) -> bool {
+    let shared = ip.octets()[0] == 100 && (ip.octets()[1] & 0b1100_0000) == 0b0100_0000;
+    !(ip.is_private() ||
+        ip.is_loopback() ||
+        ip.is_link_local() ||
+        ip.is_unspecified() ||
+        ip.is_broadcast() ||
+        shared)
+}
+
+/// Find a gateway able to map ports, trying NAT-PMP on the default
+/// route first and UPnP discovery second.
+async fn discover_gateway() -> Result<Gateway> {
+    if let Some(gateway) = default_gateway() {
+        match natpmp_external_ip(gateway).await {
+            Ok(_) => return Ok(Gateway::NatPmp(gateway)),
+            Err(e) => {
+                debug!(target: "net::nat", "[P2P] NAT-PMP unavailable on {gateway}: {e}")
+            }
+        }
+    }
+
+    upnp_discover().await
+}
+
+/// Default IPv4 gateway, read from the kernel routing table
+#[cfg(target_os = "linux")]
+fn default_gateway() -> Option<Ipv4Addr> {
+    let routes = std::fs::read_to_string("/proc/net/route").ok()?;
+
+    for line in routes.lines().skip(1) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 3 || fields[1] != "00000000" {
+            continue
+        }
+
+        // The kernel prints the address as a native endian integer
+        let gateway = u32::from_str_radix(fields[2], 16).ok()?;
+        return Some(Ipv4Addr::from(gateway.to_ne_bytes()))
+    }
+
+    None
+}
+
+#[cfg(not(target_os = "linux"))]
+fn default_gateway() -> Option<Ipv4Addr> {
+    None
+}
+
+/// Send a NAT-PMP request to the gateway and return the response.
+/// Requests are retransmitted with a doubling timeout, as RFC 6886
+/// suggests.
+async fn natpmp_request(gateway: Ipv4Addr, request: &[u8]) -> Result<Vec<u8>> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+    socket.connect((gateway, NATPMP_PORT)).await?;
+
+    let mut wait = Duration::from_millis(250);
+    let mut buf = [0u8; 16];
+    for _ in 0..4 {
+        socket.send(request).await?;
+
+        match io_timeout(wait, socket.recv(&mut buf)).await {
+            // The response opcode is the request opcode + 128
+            Ok(n) if n >= 4 && buf[0] == 0 && buf[1] == request[1] + 128 => {
+                let result = u16::from_be_bytes([buf[2], buf[3]]);
+                if result != 0 {
+                    return Err(Error::NatTraversalFailed(format!(
+                        "NAT-PMP request failed with result code {result}"
+                    )))
+                }
+                return Ok(buf[..n].to_vec())
+            }
+            Ok(_) => continue,
+            Err(e) if e.kind() == ErrorKind::TimedOut => wait *= 2,
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Err(Error::NatTraversalFailed(format!("NAT-PMP gateway {gateway} did not respond")))
+}
+
+/// Query the external address of a NAT-PMP gateway
+async fn natpmp_external_ip(gateway: Ipv4Addr) -> Result<Ipv4Addr> {
+    let response = natpmp_request(gateway, &[0, 0]).await?;
+    if response.len() < 12 {
+        return Err(Error::NatTraversalFailed("Short NAT-PMP response".into()))
+    }
+
+    Ok(Ipv4Addr::new(response[8], response[9], response[10], response[11]))
+}
+
+/// Request a TCP mapping from a NAT-PMP gateway, returning the
+/// external port it assigned.
+async fn natpmp_map(
+    gateway: Ipv4Addr,
+    internal_port: u16,
+    external_port: u16,
+    lease: u32,
+) -> Result<u16> {
+    let mut request = vec![0, 2, 0, 0];
+    request.extend_from_slice(&internal_port.to_be_bytes());
+    request.extend_from_slice(&external_port.to_be_bytes());
+    request.extend_from_slice(&lease.to_be_bytes());
+
+    let response = natpmp_request(gateway, &request).await?;
+    if response.len() < 16 {
+        return Err(Error::NatTraversalFailed("Short NAT-PMP response".into()))
+    }
+
+    Ok(u16::from_be_bytes([response[10], response[11]]))
+}
+
+/// Discover an UPnP internet gateway device on the local network
+async fn upnp_discover() -> Result<Gateway> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+    let request = format!(
+        "M-SEARCH * HTTP/1.1\r\n\
+         HOST: {SSDP_ADDR}\r\n\
+         MAN: \"ssdp:discover\"\r\n\
+         MX: 2\r\n\
+         ST: urn:schemas-upnp-org:device:InternetGatewayDevice:1\r\n\r\n"
+    );
+    socket.send_to(request.as_bytes(), SSDP_ADDR).await?;
+
+    let mut buf = [0u8; 2048];
+    let (n, _) = io_timeout(REQUEST_TIMEOUT, socket.recv_from(&mut buf)).await?;
+    let response = String::from_utf8_lossy(&buf[..n]);
+
+    let Some(location) = header_value(&response, "location") else {
+        return Err(Error::NatTraversalFailed("UPnP response without location".into()))
+    };
+    let location = Url::parse(location)?;
+
+    let description = http_request(&location, None).await?;
+    for service_type in UPNP_SERVICES {
+        let Some(start) = description.find(&format!("<serviceType>{service_type}</serviceType>"))
+        else {
+            continue
+        };
+        let Some(control) = xml_tag(&description[start..], "controlURL") else { continue };
+
+        let control_url = location.join(control.trim())?;
+        let local_ip = local_ip_towards(&control_url).await?;
+
+        return Ok(Gateway::Upnp { control_url, service_type: service_type.to_string(), local_ip })
+    }
+
+    Err(Error::NatTraversalFailed(format!("No port mapping service found at {location}")))
+}
+
+/// Our LAN address on the interface reaching the given URL
+async fn local_ip_towards(url: &Url) -> Result<Ipv4Addr> {
+    let Some(Host::Ipv4(ip)) = url.host() else {
+        return Err(Error::NatTraversalFailed(format!("Gateway {url} is not an IPv4 host")))
+    };
+
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+    socket.connect((ip, url.port_or_known_default().unwrap_or(80))).await?;
+
+    match socket.local_addr()?.ip() {
+        std::net::IpAddr::V4(ip) => Ok(ip),
+        std::net::IpAddr::V6(_) => unreachable!(),
+    }
+}
+
+/// Invoke a SOAP action on the UPnP control URL, returning the response body
+async fn soap_call(
+    control_url: &Url,
+    service_type: &str,
+    action: &str,
+    args: &[(&str, String)],
+) -> Result<String> {
+    let args: String = args.iter().map(|(k, v)| format!("<{k}>{v}</{k}>")).collect();
+    let body = format!(
+        "<?xml version=\"1.0\"?>\
+         <s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\" \
+         s:encodingStyle=\"http://schemas.xmlsoap.org/soap/encoding/\">\
+         <s:Body><u:{action} xmlns:u=\"{service_type}\">{args}</u:{action}></s:Body>\
+         </s:Envelope>"
+    );
+
+    http_request(control_url, Some((&format!("{service_type}#{action}"), &body))).await
+}
+
+/// Minimal HTTP/1.1 client for talking to the gateway. Performs a GET
+/// request, or a SOAP POST when `soap` holds the action and the body.
+async fn http_request(url: &Url, soap: Option<(&str, &str)>) -> Result<String> {
+    let Some(host) = url.host_str() else { return Err(Error::NoUrlFound) };
+    let port = url.port_or_known_default().unwrap_or(80);
+
+    let mut stream = io_timeout(REQUEST_TIMEOUT, TcpStream::connect((host, port))).await?;
+
+    let path = match url.query() {
+        Some(query) => format!("{}?{query}", url.path()),
+        None => url.path().to_string(),
+    };
+    let request = match soap {
+        None => format!("GET {path} HTTP/1.1\r\nHost: {host}:{port}\r\nConnection: close\r\n\r\n"),
+        Some((action, body)) => format!(
+            "POST {path} HTTP/1.1\r\n\
+             Host: {host}:{port}\r\n\
+             Content-Type: text/xml; charset=\"utf-8\"\r\n\
+             SOAPAction: \"{action}\"\r\n\
+             Content-Length: {}\r\n\
+             Connection: close\r\n\r\n{body}",
+            body.len()
+        ),
+    };
+    stream.write_all(request.as_bytes()).await?;
+
+    let mut response = vec![];
+    io_timeout(REQUEST_TIMEOUT, stream.read_to_end(&mut response)).await?;
+    let response = String::from_utf8_lossy(&response);
+
+    let Some((head, body)) = response.split_once("\r\n\r\n") else {
+        return Err(Error::NatTraversalFailed(format!("Malformed HTTP response from {url}")))
+    };
+
+    if head.split_whitespace().nth(1) != Some("200") {
+        let reason = xml_tag(body, "errorDescription").unwrap_or(head.lines().next().unwrap_or(""));
+        return Err(Error::NatTraversalFailed(format!("Gateway request failed: {reason}")))
+    }
+
+    Ok(body.to_string())
+}
+
+/// Value of a header in an HTTP response
+fn header_value<'a>(response: &'a str, name: &str) -> Option<&'a str> {
+    response.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.trim().eq_ignore_ascii_case(name).then(|| value.trim())
+    })
+}
+
+/// Contents of the first `<tag>` element in an XML document
+fn xml_tag<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{tag}>");
+    let start = xml.find(&open)? + open.len();
+    let end = xml[start..].find(&format!("</{tag}>"))?;
+    Some(&xml[start..start + end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nat_helpers() {
+        let addr = Url::parse("tcp+tls://0.0.0.0:26661").unwrap();
+        assert_eq!(mappable_port(&addr), Some(26661));
+        let addr = Url::parse("tcp+tls://[::]:26661").unwrap();
+        assert_eq!(mappable_port(&addr), None);
+        let addr = Url::parse("tor://127.0.0.1:26661").unwrap();
+        assert_eq!(mappable_port(&addr), None);
+
+        assert!(is_public(&Ipv4Addr::new(1, 2, 3, 4)));
+        assert!(!is_public(&Ipv4Addr::new(192, 168, 1, 1)));
+        assert!(!is_public(&Ipv4Addr::new(100, 64, 0, 1)));
+        assert!(is_public(&Ipv4Addr::new(100, 128, 0, 1)));
+
+        let response = "HTTP/1.1 200 OK\r\nLOCATION: http://192.168.1.1:5000/rootDesc.xml\r\n";
+        assert_eq!(
+            header_value(response, "location"),
+            Some("http://192.168.1.1:5000/rootDesc.xml")
+        );
+
+        let body = "<u:R><NewExternalIPAddress>1.2.3.4</NewExternalIPAddress></u:R>";
+        assert_eq!(xml_tag(body, "NewExternalIPAddress"), Some("1.2.3.4"));
+    }
+}
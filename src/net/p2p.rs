@@ -16,9 +16,14 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc,
+#[cfg(feature = "dht")]
+use std::sync::{RwLock as SyncRwLock, Weak};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::UNIX_EPOCH,
 };
 
 use futures::{stream::FuturesUnordered, TryFutureExt};
@@ -27,18 +32,33 @@ use log::{debug, error, info, warn};
 use smol::{fs, lock::RwLock as AsyncRwLock, stream::StreamExt};
 use url::Url;
 
+#[cfg(feature = "dht")]
+use darkfi_sdk::crypto::SecretKey;
+#[cfg(feature = "dht")]
+use rand::rngs::OsRng;
+
+#[cfg(feature = "dht")]
+use super::peer_dht::{PeerDht, PeerDhtPtr};
 use super::{
     channel::ChannelPtr,
+    compression::CompressionStats,
     dnet::DnetEvent,
-    hosts::{Hosts, HostsPtr},
+    event::P2pEvent,
+    hosts::{HostColor, Hosts, HostsPtr},
     message::{Message, SerializedMessage},
-    protocol::{protocol_registry::ProtocolRegistry, register_default_protocols},
+    protocol::{
+        protocol_auth::Identity, protocol_registry::ProtocolRegistry, register_default_protocols,
+    },
+    scoring::{PeerScores, PeerScoresPtr},
     session::{
         InboundSession, InboundSessionPtr, ManualSession, ManualSessionPtr, OutboundSession,
         OutboundSessionPtr, RefineSession, RefineSessionPtr, SeedSyncSession, SeedSyncSessionPtr,
     },
     settings::Settings,
+    throttle::Throttle,
 };
+#[cfg(feature = "dht")]
+use crate::dht::DhtSettings;
 use crate::{
     system::{ExecutorPtr, Publisher, PublisherPtr, Subscription},
     util::path::expand_path,
@@ -57,6 +77,8 @@ pub struct P2p {
     executor: ExecutorPtr,
     /// Known hosts (peers)
     hosts: HostsPtr,
+    /// Misbehavior scores of peers
+    scores: PeerScoresPtr,
     /// Protocol registry
     protocol_registry: ProtocolRegistry,
     /// P2P network settings
@@ -75,6 +97,23 @@ pub struct P2p {
     pub dnet_enabled: AtomicBool,
     /// The publisher for which we can give dnet info over
     dnet_publisher: PublisherPtr<DnetEvent>,
+    /// The publisher for connectivity events
+    event_publisher: PublisherPtr<P2pEvent>,
+    /// Global bandwidth budget and traffic counters of all channels
+    throttle: Throttle,
+    /// Set by the library user while catching up with the network
+    syncing: AtomicBool,
+    /// Counters of the compressed traffic of all channels
+    compression: CompressionStats,
+    /// Static identity of this node, when running in authenticated mode
+    identity: Option<Arc<Identity>>,
+    /// Peer DHT queried by outbound peer discovery, if the application runs one
+    #[cfg(feature = "dht")]
+    peer_dht: SyncRwLock<Weak<PeerDht>>,
+    /// Peer DHT we run ourselves when enabled in the settings. It points
+    /// back to us, so it gets dropped when stopping.
+    #[cfg(feature = "dht")]
+    own_peer_dht: SyncRwLock<Option<PeerDhtPtr>>,
 }
 
 impl P2p {
@@ -99,12 +138,28 @@ impl P2p {
         // Register a CryptoProvider for rustls
         let _ = CryptoProvider::install_default(ring::default_provider());
 
+        let throttle = Throttle::new(settings.global_send_limit, settings.global_recv_limit);
+
+        // Refuse to start with a broken identity rather than silently
+        // running unauthenticated
+        let identity = Identity::from_settings(&settings)?.map(Arc::new);
+        if let Some(ref identity) = identity {
+            info!(
+                target: "net::p2p::new",
+                "[P2P] Authenticated mode enabled, identity={}", identity.public_key(),
+            );
+        }
+
+        #[cfg(feature = "dht")]
+        let peer_dht = settings.peer_dht;
+
         // Wrap the Settings into an Arc<RwLock>
         let settings = Arc::new(AsyncRwLock::new(settings));
 
         let self_ = Arc::new_cyclic(|p2p| Self {
             executor,
             hosts: Hosts::new(Arc::clone(&settings)),
+            scores: PeerScores::new(),
             protocol_registry: ProtocolRegistry::new(),
             settings,
             session_manual: ManualSession::new(p2p.clone()),
@@ -114,10 +169,33 @@ impl P2p {
             session_seedsync: SeedSyncSession::new(p2p.clone()),
             dnet_enabled: AtomicBool::new(false),
             dnet_publisher: Publisher::new(),
+            event_publisher: Publisher::new(),
+            throttle,
+            syncing: AtomicBool::new(false),
+            compression: CompressionStats::default(),
+            identity,
+            #[cfg(feature = "dht")]
+            peer_dht: SyncRwLock::new(Weak::new()),
+            #[cfg(feature = "dht")]
+            own_peer_dht: SyncRwLock::new(None),
         });
 
         register_default_protocols(self_.clone()).await;
 
+        // The record key only identifies us in the DHT keyspace, so a
+        // fresh one on every run is fine.
+        #[cfg(feature = "dht")]
+        if peer_dht {
+            let peer_dht = PeerDht::new(
+                &DhtSettings::default(),
+                SecretKey::random(&mut OsRng),
+                self_.clone(),
+                self_.executor(),
+            )
+            .await;
+            *self_.own_peer_dht.write().unwrap() = Some(peer_dht);
+        }
+
         Ok(self_)
     }
 
@@ -127,6 +205,10 @@ impl P2p {
                self.settings.read().await.magic_bytes.0);
         info!(target: "net::p2p::start", "[P2P] Starting P2P subsystem");
 
+        // Load the peers known from the last run before any session
+        // starts, so we can reconnect without going through the seeds.
+        self.load_hosts().await;
+
         // Start the inbound session
         if let Err(err) = self.session_inbound().start().await {
             error!(target: "net::p2p::start", "Failed to start inbound session!: {err}");
@@ -146,6 +228,15 @@ impl P2p {
         // Start the refine session
         self.session_refine().start().await;
 
+        // Start our peer DHT
+        #[cfg(feature = "dht")]
+        {
+            let peer_dht = self.own_peer_dht.read().unwrap().clone();
+            if let Some(peer_dht) = peer_dht {
+                peer_dht.start().await;
+            }
+        }
+
         info!(target: "net::p2p::start", "[P2P] P2P subsystem started successfully");
         Ok(())
     }
@@ -162,12 +253,62 @@ impl P2p {
 
     /// Stop the running P2P subsystem
     pub async fn stop(&self) {
+        // Save the peers first, since stopping the channels downgrades
+        // our gold peers to the greylist.
+        self.save_hosts().await;
+
         // Stop the sessions
         self.session_manual().stop().await;
         self.session_inbound().stop().await;
         self.session_seedsync().stop().await;
         self.session_outbound().stop().await;
         self.session_refine().stop().await;
+
+        // Stop our peer DHT
+        #[cfg(feature = "dht")]
+        {
+            let peer_dht = self.own_peer_dht.write().unwrap().take();
+            if let Some(peer_dht) = peer_dht {
+                peer_dht.stop().await;
+            }
+        }
+    }
+
+    /// Load the hostlists and peer scores from the configured hostlist file
+    async fn load_hosts(&self) {
+        let Some(ref hostlist) = self.settings.read().await.hostlist else { return };
+        match self.hosts.container.load_all(hostlist, &self.scores) {
+            Ok(()) => {
+                debug!(target: "net::p2p::load_hosts()", "Load hosts successful!");
+            }
+            Err(e) => {
+                warn!(target: "net::p2p::load_hosts()", "Error loading hosts {e}");
+            }
+        }
+    }
+
+    /// Save the hostlists and peer scores to the configured hostlist file
+    async fn save_hosts(&self) {
+        let Some(ref hostlist) = self.settings.read().await.hostlist else { return };
+
+        // Peers we are still connected to were seen just now
+        let now = UNIX_EPOCH.elapsed().unwrap().as_secs();
+        for channel in self.hosts.peers() {
+            self.hosts.container.update_last_seen(
+                HostColor::Gold as usize,
+                channel.address().clone(),
+                now,
+            );
+        }
+
+        match self.hosts.container.save_all(hostlist, &self.scores) {
+            Ok(()) => {
+                debug!(target: "net::p2p::save_hosts()", "Save hosts successful!");
+            }
+            Err(e) => {
+                warn!(target: "net::p2p::save_hosts()", "Error saving hosts {e}");
+            }
+        }
     }
 
     /// Broadcasts a message concurrently across all active peers.
@@ -224,6 +365,11 @@ impl P2p {
         self.hosts.clone()
     }
 
+    /// Return an atomic pointer to the peer scores
+    pub fn scores(&self) -> PeerScoresPtr {
+        self.scores.clone()
+    }
+
     /// Reference the global executor
     pub fn executor(&self) -> ExecutorPtr {
         self.executor.clone()
@@ -234,6 +380,19 @@ impl P2p {
         &self.protocol_registry
     }
 
+    /// Attach a [`PeerDht`] that outbound peer discovery queries before
+    /// asking the configured seeds. Only a weak reference is kept.
+    #[cfg(feature = "dht")]
+    pub fn set_peer_dht(&self, peer_dht: &PeerDhtPtr) {
+        *self.peer_dht.write().unwrap() = Arc::downgrade(peer_dht);
+    }
+
+    /// Return the attached [`PeerDht`], if any
+    #[cfg(feature = "dht")]
+    pub fn peer_dht(&self) -> Option<PeerDhtPtr> {
+        self.peer_dht.read().unwrap().upgrade()
+    }
+
     /// Get pointer to manual session
     pub fn session_manual(&self) -> ManualSessionPtr {
         self.session_manual.clone()
@@ -281,10 +440,56 @@ impl P2p {
         self.dnet_publisher.notify(event).await;
     }
 
+    /// Subscribe to connectivity events
+    pub async fn subscribe_events(&self) -> Subscription<P2pEvent> {
+        self.event_publisher.clone().subscribe().await
+    }
+
+    /// Send a connectivity event over the publisher
+    pub(super) async fn notify_event(&self, event: P2pEvent) {
+        self.event_publisher.notify(event).await;
+    }
+
     /// Grab the channel pointer of provided channel ID, if it exists.
     pub fn get_channel(&self, id: u32) -> Option<ChannelPtr> {
         self.hosts.get_channel(id)
     }
+
+    /// Total number of bytes sent over all channels since startup
+    pub fn bytes_sent(&self) -> u64 {
+        self.throttle.bytes_sent()
+    }
+
+    /// Total number of bytes received over all channels since startup
+    pub fn bytes_recv(&self) -> u64 {
+        self.throttle.bytes_recv()
+    }
+
+    /// Counters of the compressed traffic of all channels
+    pub fn compression(&self) -> &CompressionStats {
+        &self.compression
+    }
+
+    /// Static identity of this node, when running in authenticated mode
+    pub fn identity(&self) -> Option<&Arc<Identity>> {
+        self.identity.as_ref()
+    }
+
+    /// Global bandwidth throttle shared by all channels
+    pub fn throttle(&self) -> &Throttle {
+        &self.throttle
+    }
+
+    /// Mark the node as syncing or synced. While syncing, the adaptive
+    /// outbound session will use more slots to catch up faster.
+    pub fn set_syncing(&self, syncing: bool) {
+        self.syncing.store(syncing, Ordering::SeqCst);
+    }
+
+    /// Returns true if the node is currently syncing
+    pub fn is_syncing(&self) -> bool {
+        self.syncing.load(Ordering::SeqCst)
+    }
 }
 
 /// Auxiliary function to broadcast a serialized message concurrently to all given peers.
@@ -0,0 +1,700 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Kademlia-style peer discovery overlay.
+//!
+//! Every node publishes a [`PeerRecord`] with its external addresses,
+//! signed by a node key. The record is keyed by the hash of the public
+//! key, which is also the node ID in the DHT keyspace. Records received
+//! from other peers are only accepted if their signature checks out and
+//! they have not expired, so nobody can advertise addresses on behalf of
+//! another node.
+//!
+//! Outbound peer discovery asks the DHT for random keys before falling
+//! back to the configured seeds. Since records are self-signed, anyone
+//! can advertise any address, so the addresses of the nodes met along
+//! the way only get stored in the greylist once we managed to handshake
+//! with them, and only a handful of them per lookup. Buckets are
+//! refreshed periodically, and our own record gets republished to the
+//! nodes closest to our ID.
+//!
+//! Values are generic signed records on purpose, so service records can
+//! be layered on top of the same overlay later.
+//!
+//! The P2P network runs its own peer DHT when `peer_dht` is enabled in
+//! the settings.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use darkfi_sdk::crypto::{
+    schnorr::{SchnorrPublic, SchnorrSecret, Signature},
+    PublicKey, SecretKey,
+};
+use darkfi_serial::{serialize, SerialDecodable, SerialEncodable};
+use futures::future::join_all;
+use log::{debug, error, info, warn};
+use rand::{rngs::OsRng, Rng};
+use smol::Executor;
+use url::Url;
+
+use super::{
+    channel::ChannelPtr,
+    hosts::HostColor,
+    message::{Message, MessagePriority},
+    message_publisher::MessageSubscription,
+    metering::{MeteringConfiguration, DEFAULT_METERING_CONFIGURATION},
+    p2p::P2pPtr,
+    protocol::{
+        protocol_base::{ProtocolBase, ProtocolBasePtr},
+        protocol_jobs_manager::{ProtocolJobsManager, ProtocolJobsManagerPtr},
+    },
+    scoring::Misbehavior,
+    session::SESSION_DEFAULT,
+};
+use crate::{
+    dht::{
+        impl_dht_node_defaults, tasks::channel_task, Dht, DhtHandler, DhtLookupReply, DhtNode,
+        DhtSettings,
+    },
+    impl_p2p_message,
+    system::{sleep, ExecutorPtr, StoppableTask, StoppableTaskPtr},
+    util::time::Timestamp,
+    Error, Result,
+};
+
+/// Seconds after which a peer record is considered stale
+pub const PEER_RECORD_EXPIRY: u64 = 3600;
+/// Seconds between bucket refreshes and republishing our own record
+pub const BUCKET_REFRESH_INTERVAL: u64 = 600;
+/// Maximum number of addresses a single peer record can carry
+const MAX_RECORD_ADDRS: usize = 8;
+/// Seconds of clock drift tolerated on record timestamps
+const MAX_CLOCK_DRIFT: u64 = 60;
+/// Maximum number of nodes sent back in a single reply
+const MAX_REPLY_NODES: usize = 16;
+/// Maximum number of new addresses a single lookup can probe and
+/// hand over to the greylist
+const MAX_DISCOVERED_ADDRS: usize = 16;
+
+/// PeerRecord fields size:
+/// * public_key = 32
+/// * addresses = 1 (vec_len) + MAX_RECORD_ADDRS * 128 = 1025
+/// * timestamp = 8
+/// * signature = 64
+///
+/// Url type is estimated to be max 128 bytes, like in the address messages.
+const PEER_RECORD_MAX_BYTES: u64 = 1129;
+
+/// PeerDhtPingReply message fields size:
+/// * record = 1129
+/// * signature = 64
+pub const PEER_DHT_PING_REPLY_MAX_BYTES: u64 = 1193;
+
+/// PeerDhtFindNodesReply message fields size:
+/// * nodes = 1 (vec_len) + MAX_REPLY_NODES * 1129 = 18065
+pub const PEER_DHT_FIND_NODES_REPLY_MAX_BYTES: u64 = 18065;
+
+/// PeerDhtFindValueReply message fields size:
+/// * record = 1 (option) + 1129 = 1130
+/// * nodes = 1 (vec_len) + MAX_REPLY_NODES * 1129 = 18065
+pub const PEER_DHT_FIND_VALUE_REPLY_MAX_BYTES: u64 = 19195;
+
+/// Atomic pointer to the peer DHT
+pub type PeerDhtPtr = Arc<PeerDht>;
+
+/// Signed record advertising the addresses of a node.
+/// It is used both as the DHT node and as the DHT value.
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct PeerRecord {
+    /// Node key, the node ID is derived from it
+    pub public_key: PublicKey,
+    /// External addresses of the node
+    pub addresses: Vec<Url>,
+    /// UNIX timestamp of when the record got signed
+    pub timestamp: u64,
+    /// Signature over all of the above
+    pub signature: Signature,
+}
+impl_dht_node_defaults!(PeerRecord);
+
+impl DhtNode for PeerRecord {
+    fn id(&self) -> blake3::Hash {
+        blake3::hash(&self.public_key.to_bytes())
+    }
+
+    fn addresses(&self) -> Vec<Url> {
+        self.addresses.clone()
+    }
+}
+
+impl PeerRecord {
+    /// Create a new record for `addresses`, signed with `secret_key`
+    pub fn new(secret_key: &SecretKey, mut addresses: Vec<Url>) -> Self {
+        addresses.truncate(MAX_RECORD_ADDRS);
+        let public_key = PublicKey::from_secret(*secret_key);
+        let timestamp = Timestamp::current_time().inner();
+        let signature = secret_key.sign(&serialize(&(public_key, addresses.clone(), timestamp)));
+        Self { public_key, addresses, timestamp, signature }
+    }
+
+    /// Check the record signature and that it is neither expired nor
+    /// coming from the future.
+    pub fn verify(&self) -> bool {
+        if self.addresses.len() > MAX_RECORD_ADDRS {
+            return false
+        }
+
+        let now = Timestamp::current_time().inner();
+        if self.timestamp > now + MAX_CLOCK_DRIFT || self.is_expired(now) {
+            return false
+        }
+
+        let message = serialize(&(self.public_key, self.addresses.clone(), self.timestamp));
+        self.public_key.verify(&message, &self.signature)
+    }
+
+    /// Check if the record is older than [`PEER_RECORD_EXPIRY`]
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.timestamp + PEER_RECORD_EXPIRY < now
+    }
+}
+
+/// Peer DHT ping request, used to learn the record of a connected peer
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct PeerDhtPingRequest {
+    pub nonce: u64,
+}
+impl_p2p_message!(
+    PeerDhtPingRequest,
+    "PeerDhtPingRequest",
+    8,
+    1,
+    DEFAULT_METERING_CONFIGURATION,
+    MessagePriority::Low
+);
+
+/// Peer DHT ping reply
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct PeerDhtPingReply {
+    pub record: PeerRecord,
+    /// Signature of the request nonce, proving ownership of the record key
+    pub signature: Signature,
+}
+impl_p2p_message!(
+    PeerDhtPingReply,
+    "PeerDhtPingReply",
+    PEER_DHT_PING_REPLY_MAX_BYTES,
+    1,
+    DEFAULT_METERING_CONFIGURATION,
+    MessagePriority::Low
+);
+
+/// Request for the nodes closest to `key`
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct PeerDhtFindNodesRequest {
+    pub key: blake3::Hash,
+}
+impl_p2p_message!(
+    PeerDhtFindNodesRequest,
+    "PeerDhtFindNodesRequest",
+    32,
+    1,
+    DEFAULT_METERING_CONFIGURATION,
+    MessagePriority::Low
+);
+
+/// Reply with the nodes closest to the requested key
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct PeerDhtFindNodesReply {
+    pub nodes: Vec<PeerRecord>,
+}
+impl_p2p_message!(
+    PeerDhtFindNodesReply,
+    "PeerDhtFindNodesReply",
+    PEER_DHT_FIND_NODES_REPLY_MAX_BYTES,
+    1,
+    DEFAULT_METERING_CONFIGURATION,
+    MessagePriority::Low
+);
+
+/// Request for the record stored under `key`
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct PeerDhtFindValueRequest {
+    pub key: blake3::Hash,
+}
+impl_p2p_message!(
+    PeerDhtFindValueRequest,
+    "PeerDhtFindValueRequest",
+    32,
+    1,
+    DEFAULT_METERING_CONFIGURATION,
+    MessagePriority::Low
+);
+
+/// Reply with the requested record, if we have it, and the closest nodes
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct PeerDhtFindValueReply {
+    pub record: Option<PeerRecord>,
+    pub nodes: Vec<PeerRecord>,
+}
+impl_p2p_message!(
+    PeerDhtFindValueReply,
+    "PeerDhtFindValueReply",
+    PEER_DHT_FIND_VALUE_REPLY_MAX_BYTES,
+    1,
+    DEFAULT_METERING_CONFIGURATION,
+    MessagePriority::Low
+);
+
+/// Ask a node to store a record
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct PeerDhtStore {
+    pub record: PeerRecord,
+}
+impl_p2p_message!(
+    PeerDhtStore,
+    "PeerDhtStore",
+    PEER_RECORD_MAX_BYTES,
+    1,
+    DEFAULT_METERING_CONFIGURATION,
+    MessagePriority::Low
+);
+
+/// DHT overlay used for peer discovery
+pub struct PeerDht {
+    /// Underlying Kademlia DHT
+    pub dht: Arc<Dht<PeerDht>>,
+    /// Node key signing our own record
+    secret_key: SecretKey,
+    /// P2P network pointer
+    p2p: P2pPtr,
+    /// Task pinging new channels to fill the buckets
+    channel_task: StoppableTaskPtr,
+    /// Bucket refresh and record republishing task
+    refresh_task: StoppableTaskPtr,
+}
+
+impl PeerDht {
+    /// Create a new peer DHT on top of `p2p` and register its protocol.
+    /// Must be called before the P2P network is started.
+    pub async fn new(
+        settings: &DhtSettings,
+        secret_key: SecretKey,
+        p2p: P2pPtr,
+        ex: ExecutorPtr,
+    ) -> PeerDhtPtr {
+        let dht = Arc::new(Dht::new(settings, p2p.clone(), ex).await);
+        let self_ = Arc::new(Self {
+            dht: dht.clone(),
+            secret_key,
+            p2p: p2p.clone(),
+            channel_task: StoppableTask::new(),
+            refresh_task: StoppableTask::new(),
+        });
+        *dht.handler.write().await = Arc::downgrade(&self_);
+
+        let peer_dht = self_.clone();
+        p2p.protocol_registry()
+            .register(SESSION_DEFAULT, move |channel, _| {
+                let peer_dht = peer_dht.clone();
+                async move { ProtocolPeerDht::init(peer_dht, channel).await.unwrap() }
+            })
+            .await;
+        p2p.set_peer_dht(&self_);
+
+        self_
+    }
+
+    /// Start the background tasks of the DHT
+    pub async fn start(self: Arc<Self>) {
+        info!(target: "net::peer_dht::start()", "[P2P] Starting peer DHT");
+        let ex = self.p2p.executor();
+
+        self.channel_task.clone().start(
+            channel_task::<PeerDht>(self.clone()),
+            |res| async {
+                match res {
+                    Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                    Err(e) => error!(target: "net::peer_dht::start()", "Failed starting DHT channel task: {e}"),
+                }
+            },
+            Error::DetachedTaskStopped,
+            ex.clone(),
+        );
+
+        self.refresh_task.clone().start(
+            self.clone().refresh(),
+            |res| async {
+                match res {
+                    Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                    Err(e) => error!(target: "net::peer_dht::start()", "Failed starting DHT refresh task: {e}"),
+                }
+            },
+            Error::DetachedTaskStopped,
+            ex,
+        );
+    }
+
+    /// Stop the background tasks of the DHT
+    pub async fn stop(&self) {
+        info!(target: "net::peer_dht::stop()", "[P2P] Stopping peer DHT");
+        self.channel_task.stop().await;
+        self.refresh_task.stop().await;
+    }
+
+    /// Look up a random key and store the reachable addresses of the nodes
+    /// met along the way in the greylist. Returns the number of addresses
+    /// stored.
+    pub async fn discover(&self) -> Result<usize> {
+        let key = blake3::Hash::from(OsRng.gen::<[u8; 32]>());
+        let nodes = self.dht.lookup_nodes(&key).await?;
+
+        let self_id = self.node().await.id();
+        let mut candidates: Vec<(Url, u64)> = vec![];
+        'nodes: for node in nodes.iter().filter(|node| node.id() != self_id) {
+            for addr in &node.addresses {
+                if candidates.len() >= MAX_DISCOVERED_ADDRS {
+                    break 'nodes
+                }
+                if !candidates.iter().any(|(a, _)| a == addr) {
+                    candidates.push((addr.clone(), node.timestamp));
+                }
+            }
+        }
+
+        // Anybody can sign a record for somebody else's address, so only
+        // keep the addresses we can actually handshake with.
+        let refine_session = self.p2p.session_refine();
+        let probes = candidates
+            .iter()
+            .map(|(addr, _)| refine_session.clone().handshake_node(addr.clone(), self.p2p.clone()));
+        let reachable = join_all(probes).await;
+        let addrs: Vec<(Url, u64)> = candidates
+            .into_iter()
+            .zip(reachable)
+            .filter_map(|(addr, reachable)| reachable.then_some(addr))
+            .collect();
+
+        debug!(
+            target: "net::peer_dht::discover()",
+            "Found {} reachable addresses from {} nodes", addrs.len(), nodes.len(),
+        );
+        self.p2p.hosts().insert(HostColor::Grey, &addrs).await;
+
+        Ok(addrs.len())
+    }
+
+    /// Periodically refresh our buckets, drop expired records and
+    /// republish our own record to the nodes closest to us.
+    async fn refresh(self: Arc<Self>) -> Result<()> {
+        loop {
+            sleep(BUCKET_REFRESH_INTERVAL).await;
+
+            let now = Timestamp::current_time().inner();
+            self.dht.hash_table.write().await.retain(|_, record| !record.is_expired(now));
+
+            if !self.dht.is_bootstrapped().await {
+                self.dht.bootstrap().await;
+                continue
+            }
+
+            // Looking up a random key refreshes the buckets far from us,
+            // while announcing our record refreshes the ones close to us.
+            if let Err(e) = self.discover().await {
+                warn!(target: "net::peer_dht::refresh()", "[P2P] Bucket refresh failed: {e}");
+            }
+
+            let record = self.node().await;
+            if record.addresses.is_empty() {
+                continue
+            }
+            let message = PeerDhtStore { record: record.clone() };
+            if let Err(e) = self.dht.announce(&record.id(), &record, &message).await {
+                warn!(target: "net::peer_dht::refresh()", "[P2P] Republishing our record failed: {e}");
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl DhtHandler for PeerDht {
+    type Value = PeerRecord;
+    type Node = PeerRecord;
+
+    fn dht(&self) -> Arc<Dht<Self>> {
+        self.dht.clone()
+    }
+
+    async fn node(&self) -> PeerRecord {
+        let addresses = self
+            .p2p
+            .hosts()
+            .external_addrs()
+            .await
+            .into_iter()
+            .filter(|addr| !addr.to_string().contains("[::]"))
+            .collect();
+        PeerRecord::new(&self.secret_key, addresses)
+    }
+
+    async fn ping(&self, channel: ChannelPtr) -> Result<PeerRecord> {
+        debug!(target: "net::peer_dht::ping()", "Sending ping to channel {}", channel.info.id);
+        let msg_subsystem = channel.message_subsystem();
+        msg_subsystem.add_dispatch::<PeerDhtPingReply>().await;
+        let msg_subscriber = channel.subscribe_msg::<PeerDhtPingReply>().await?;
+
+        let request = PeerDhtPingRequest { nonce: OsRng.gen() };
+        channel.send(&request).await?;
+
+        let reply = msg_subscriber.receive_with_timeout(self.dht.settings.timeout).await;
+        msg_subscriber.unsubscribe().await;
+        let reply = reply?;
+
+        let record = &reply.record;
+        if !record.verify() ||
+            !record.public_key.verify(&request.nonce.to_be_bytes(), &reply.signature)
+        {
+            channel.penalize(Misbehavior::InvalidMessage).await;
+            return Err(Error::InvalidSignature)
+        }
+
+        Ok(reply.record.clone())
+    }
+
+    async fn on_new_node(&self, node: &PeerRecord) -> Result<()> {
+        debug!(target: "net::peer_dht::on_new_node()", "New node {}", Self::key_to_string(&node.id()));
+
+        if !self.dht.is_bootstrapped().await {
+            self.dht.bootstrap().await;
+        }
+
+        Ok(())
+    }
+
+    async fn find_nodes(&self, node: &PeerRecord, key: &blake3::Hash) -> Result<Vec<PeerRecord>> {
+        debug!(target: "net::peer_dht::find_nodes()", "Fetching nodes close to {} from node {}", Self::key_to_string(key), Self::key_to_string(&node.id()));
+
+        let channel = self.dht.get_channel(node, None).await?;
+        let msg_subsystem = channel.message_subsystem();
+        msg_subsystem.add_dispatch::<PeerDhtFindNodesReply>().await;
+        let msg_subscriber = channel.subscribe_msg::<PeerDhtFindNodesReply>().await?;
+
+        let request = PeerDhtFindNodesRequest { key: *key };
+        channel.send(&request).await?;
+
+        let reply = msg_subscriber.receive_with_timeout(self.dht.settings.timeout).await;
+        msg_subscriber.unsubscribe().await;
+        self.dht.cleanup_channel(channel).await;
+
+        // Drop anything that isn't properly signed
+        Ok(reply?.nodes.iter().filter(|n| n.verify()).cloned().collect())
+    }
+
+    async fn find_value(
+        &self,
+        node: &PeerRecord,
+        key: &blake3::Hash,
+    ) -> Result<DhtLookupReply<PeerRecord, PeerRecord>> {
+        debug!(target: "net::peer_dht::find_value()", "Fetching record {} from node {}", Self::key_to_string(key), Self::key_to_string(&node.id()));
+
+        let channel = self.dht.get_channel(node, None).await?;
+        let msg_subsystem = channel.message_subsystem();
+        msg_subsystem.add_dispatch::<PeerDhtFindValueReply>().await;
+        let msg_subscriber = channel.subscribe_msg::<PeerDhtFindValueReply>().await?;
+
+        let request = PeerDhtFindValueRequest { key: *key };
+        channel.send(&request).await?;
+
+        let reply = msg_subscriber.receive_with_timeout(self.dht.settings.timeout).await;
+        msg_subscriber.unsubscribe().await;
+        self.dht.cleanup_channel(channel).await;
+
+        let reply = reply?;
+        let nodes: Vec<PeerRecord> = reply.nodes.iter().filter(|n| n.verify()).cloned().collect();
+        match &reply.record {
+            Some(record) if record.id() == *key && record.verify() => {
+                Ok(DhtLookupReply::NodesAndValue(nodes, record.clone()))
+            }
+            _ => Ok(DhtLookupReply::Nodes(nodes)),
+        }
+    }
+
+    async fn add_value(&self, key: &blake3::Hash, value: &PeerRecord) {
+        if value.id() != *key || !value.verify() {
+            return
+        }
+
+        // Only keep the most recent record of each node
+        let mut hash_table = self.dht.hash_table.write().await;
+        match hash_table.get(key) {
+            Some(existing) if existing.timestamp >= value.timestamp => {}
+            _ => {
+                hash_table.insert(*key, value.clone());
+            }
+        }
+    }
+
+    fn key_to_string(key: &blake3::Hash) -> String {
+        bs58::encode(key.as_bytes()).into_string()
+    }
+}
+
+/// P2P protocol answering peer DHT requests
+pub struct ProtocolPeerDht {
+    channel: ChannelPtr,
+    ping_request_sub: MessageSubscription<PeerDhtPingRequest>,
+    find_nodes_request_sub: MessageSubscription<PeerDhtFindNodesRequest>,
+    find_value_request_sub: MessageSubscription<PeerDhtFindValueRequest>,
+    store_sub: MessageSubscription<PeerDhtStore>,
+    peer_dht: PeerDhtPtr,
+    jobsman: ProtocolJobsManagerPtr,
+}
+
+const PROTO_NAME: &str = "ProtocolPeerDht";
+
+impl ProtocolPeerDht {
+    pub async fn init(peer_dht: PeerDhtPtr, channel: ChannelPtr) -> Result<ProtocolBasePtr> {
+        let msg_subsystem = channel.message_subsystem();
+        msg_subsystem.add_dispatch::<PeerDhtPingRequest>().await;
+        msg_subsystem.add_dispatch::<PeerDhtFindNodesRequest>().await;
+        msg_subsystem.add_dispatch::<PeerDhtFindValueRequest>().await;
+        msg_subsystem.add_dispatch::<PeerDhtStore>().await;
+
+        Ok(Arc::new(Self {
+            channel: channel.clone(),
+            ping_request_sub: channel.subscribe_msg::<PeerDhtPingRequest>().await?,
+            find_nodes_request_sub: channel.subscribe_msg::<PeerDhtFindNodesRequest>().await?,
+            find_value_request_sub: channel.subscribe_msg::<PeerDhtFindValueRequest>().await?,
+            store_sub: channel.subscribe_msg::<PeerDhtStore>().await?,
+            peer_dht,
+            jobsman: ProtocolJobsManager::new(PROTO_NAME, channel),
+        }))
+    }
+
+    /// Mark the node behind this channel as recently seen
+    async fn update_node(&self) {
+        let dht = &self.peer_dht.dht;
+        if let Some(node) = dht.get_node_from_channel(self.channel.info.id).await {
+            dht.update_node(&node).await;
+        }
+    }
+
+    async fn handle_ping_request(self: Arc<Self>) -> Result<()> {
+        loop {
+            let request = self.ping_request_sub.receive().await?;
+
+            let reply = PeerDhtPingReply {
+                record: self.peer_dht.node().await,
+                signature: self.peer_dht.secret_key.sign(&request.nonce.to_be_bytes()),
+            };
+            self.channel.send(&reply).await?;
+        }
+    }
+
+    async fn handle_find_nodes_request(self: Arc<Self>) -> Result<()> {
+        loop {
+            let request = self.find_nodes_request_sub.receive().await?;
+            self.update_node().await;
+
+            let dht = &self.peer_dht.dht;
+            let nodes = dht.find_neighbors(&request.key, dht.settings.k.min(MAX_REPLY_NODES)).await;
+            self.channel.send(&PeerDhtFindNodesReply { nodes }).await?;
+        }
+    }
+
+    async fn handle_find_value_request(self: Arc<Self>) -> Result<()> {
+        loop {
+            let request = self.find_value_request_sub.receive().await?;
+            self.update_node().await;
+
+            let dht = &self.peer_dht.dht;
+            let record = dht.hash_table.read().await.get(&request.key).cloned();
+            let nodes = dht.find_neighbors(&request.key, dht.settings.k.min(MAX_REPLY_NODES)).await;
+            self.channel.send(&PeerDhtFindValueReply { record, nodes }).await?;
+        }
+    }
+
+    async fn handle_store(self: Arc<Self>) -> Result<()> {
+        loop {
+            let request = self.store_sub.receive().await?;
+            self.update_node().await;
+
+            let record = &request.record;
+            if !record.verify() {
+                debug!(
+                    target: "net::peer_dht::handle_store()",
+                    "Rejected invalid record from {}", self.channel.address(),
+                );
+                self.channel.penalize(Misbehavior::InvalidMessage).await;
+                continue
+            }
+
+            self.peer_dht.add_value(&record.id(), record).await;
+        }
+    }
+}
+
+#[async_trait]
+impl ProtocolBase for ProtocolPeerDht {
+    async fn start(self: Arc<Self>, ex: Arc<Executor<'_>>) -> Result<()> {
+        debug!(target: "net::peer_dht::start()", "START => address={}", self.channel.address());
+        self.jobsman.clone().start(ex.clone());
+        self.jobsman.clone().spawn(self.clone().handle_ping_request(), ex.clone()).await;
+        self.jobsman.clone().spawn(self.clone().handle_find_nodes_request(), ex.clone()).await;
+        self.jobsman.clone().spawn(self.clone().handle_find_value_request(), ex.clone()).await;
+        self.jobsman.clone().spawn(self.clone().handle_store(), ex).await;
+        debug!(target: "net::peer_dht::start()", "END => address={}", self.channel.address());
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        PROTO_NAME
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peer_record_signature() {
+        let secret_key = SecretKey::random(&mut OsRng);
+        let addrs = vec![Url::parse("tcp+tls://127.0.0.1:26661").unwrap()];
+
+        let record = PeerRecord::new(&secret_key, addrs.clone());
+        assert!(record.verify());
+        assert_eq!(record.id(), blake3::hash(&PublicKey::from_secret(secret_key).to_bytes()));
+
+        // Tampering with the addresses invalidates the record
+        let mut forged = record.clone();
+        forged.addresses = vec![Url::parse("tcp+tls://127.0.0.2:26661").unwrap()];
+        assert!(!forged.verify());
+
+        // So does signing with another key
+        let mut forged = record.clone();
+        forged.public_key = PublicKey::from_secret(SecretKey::random(&mut OsRng));
+        assert!(!forged.verify());
+
+        // Expired records are rejected
+        let mut expired = record.clone();
+        expired.timestamp -= PEER_RECORD_EXPIRY + 1;
+        assert!(expired.is_expired(Timestamp::current_time().inner()));
+        assert!(!expired.verify());
+    }
+}
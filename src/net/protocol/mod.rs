@@ -39,6 +39,16 @@ pub mod protocol_jobs_manager;
 pub mod protocol_version;
 pub use protocol_version::ProtocolVersion;
 
+/// Protocol for authenticating peers with static identity keys.
+///
+/// When an identity secret is configured, both ends send a random
+/// challenge after the version exchange and sign the one they got
+/// with their identity key. Peers which fail to prove ownership of
+/// their key, or which are not in the configured allowlist, get
+/// disconnected before the channel is registered.
+pub mod protocol_auth;
+pub use protocol_auth::ProtocolAuth;
+
 /// Protocol for ping-pong keepalive messages.
 ///
 /// Implements ping message and pong response. These messages are like the
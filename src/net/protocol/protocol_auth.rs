@@ -0,0 +1,260 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::sync::Arc;
+
+use ed25519_compact::{KeyPair, Noise, PublicKey, Seed, Signature};
+use log::{debug, error};
+use rand::{rngs::OsRng, Rng};
+
+use super::super::{
+    channel::ChannelPtr,
+    message::{AuthChallengeMessage, AuthResponseMessage},
+    message_publisher::MessageSubscription,
+    session::SESSION_INBOUND,
+    settings::Settings,
+};
+use crate::{util::encoding::base64, Error, Result};
+
+/// Feature advertised in the version message by nodes running
+/// in authenticated mode
+pub const AUTH_FEATURE: &str = "auth";
+pub const AUTH_FEATURE_VERSION: u32 = 1;
+
+/// Domain separator for the signed challenges
+const AUTH_DOMAIN: &[u8] = b"DarkFi:P2P_AUTH_v1";
+
+/// Static identity of this node along with the peers it accepts
+pub struct Identity {
+    keypair: KeyPair,
+    allowed_peers: Vec<PublicKey>,
+}
+
+impl Identity {
+    /// Parse the identity from the P2P settings. Returns `None` when
+    /// authenticated mode is disabled.
+    pub fn from_settings(settings: &Settings) -> Result<Option<Self>> {
+        let Some(secret) = &settings.identity_secret else {
+            if !settings.allowed_peers.is_empty() {
+                return Err(Error::InvalidIdentityKey(
+                    "allowed_peers requires identity_secret to be set".to_string(),
+                ))
+            }
+            return Ok(None)
+        };
+
+        let Some(seed) = base64::decode(secret).and_then(|s| Seed::from_slice(&s).ok()) else {
+            return Err(Error::InvalidIdentityKey("identity_secret".to_string()))
+        };
+        let keypair = KeyPair::from_seed(seed);
+
+        let mut allowed_peers = Vec::with_capacity(settings.allowed_peers.len());
+        for peer in &settings.allowed_peers {
+            allowed_peers.push(decode_public_key(peer)?);
+        }
+
+        Ok(Some(Self { keypair, allowed_peers }))
+    }
+
+    /// Base64 encoded public key, as used in `allowed_peers`
+    pub fn public_key(&self) -> String {
+        base64::encode(self.keypair.pk.as_ref())
+    }
+
+    fn is_allowed(&self, public_key: &PublicKey) -> bool {
+        self.allowed_peers.is_empty() || self.allowed_peers.contains(public_key)
+    }
+}
+
+/// Generate a new base64 encoded identity secret
+pub fn generate_identity_secret() -> String {
+    base64::encode(Seed::generate().as_ref())
+}
+
+/// Decode a base64 encoded identity public key
+pub fn decode_public_key(public_key: &str) -> Result<PublicKey> {
+    match base64::decode(public_key).and_then(|pk| PublicKey::from_slice(&pk).ok()) {
+        Some(pk) => Ok(pk),
+        None => Err(Error::InvalidIdentityKey(public_key.to_string())),
+    }
+}
+
+/// The message signed in response to a challenge. The role of the
+/// signer is included so a peer can't reflect our own challenge back
+/// to us and replay our response, and the channel binding so the
+/// response is only valid over the TLS session it was made on.
+fn transcript(
+    magic_bytes: &[u8; 4],
+    initiator: bool,
+    nonce: &[u8; 32],
+    channel_binding: &[u8; 32],
+) -> Vec<u8> {
+    let mut msg = Vec::with_capacity(AUTH_DOMAIN.len() + 4 + 1 + 32 + 32);
+    msg.extend_from_slice(AUTH_DOMAIN);
+    msg.extend_from_slice(magic_bytes);
+    msg.push(initiator as u8);
+    msg.extend_from_slice(nonce);
+    msg.extend_from_slice(channel_binding);
+    msg
+}
+
+/// Implements the challenge-response handshake run by `ProtocolVersion`
+/// after the version exchange when authenticated mode is enabled. Both
+/// ends prove they own their static identity key by signing a random
+/// challenge sent by the other end, and the peer identity is checked
+/// against the configured allowlist.
+///
+/// The signed transcript includes keying material exported from the
+/// channel TLS session. A man in the middle relaying the handshake
+/// terminates a different TLS session with each end, so the responses
+/// it relays never verify. The TLS session keys then protect the rest
+/// of the traffic, so authenticated mode needs a TLS transport.
+pub struct ProtocolAuth {
+    channel: ChannelPtr,
+    challenge_sub: MessageSubscription<AuthChallengeMessage>,
+    response_sub: MessageSubscription<AuthResponseMessage>,
+    identity: Arc<Identity>,
+    magic_bytes: [u8; 4],
+}
+
+impl ProtocolAuth {
+    /// Create a new auth protocol. Subscriptions are made right away
+    /// so the peer challenge can't get lost while the version exchange
+    /// is still ongoing.
+    pub async fn new(
+        channel: ChannelPtr,
+        identity: Arc<Identity>,
+        settings: &Settings,
+    ) -> Arc<Self> {
+        let magic_bytes = settings.magic_bytes.0;
+
+        let challenge_sub = channel
+            .subscribe_msg::<AuthChallengeMessage>()
+            .await
+            .expect("Missing authchallenge dispatcher!");
+
+        let response_sub = channel
+            .subscribe_msg::<AuthResponseMessage>()
+            .await
+            .expect("Missing authresponse dispatcher!");
+
+        Arc::new(Self { channel, challenge_sub, response_sub, identity, magic_bytes })
+    }
+
+    /// Exchange challenges with the peer and verify its response.
+    /// Must run after the version exchange.
+    pub async fn run(&self) -> Result<()> {
+        debug!(target: "net::protocol_auth::run()", "START => address={}", self.channel.address());
+
+        // Don't wait around for peers which will never answer
+        let version = self.channel.get_version();
+        if !version.features.iter().any(|(name, _)| name == AUTH_FEATURE) {
+            return Err(Error::PeerAuthFailed)
+        }
+
+        let Some(channel_binding) = self.channel.channel_binding else {
+            error!(
+                target: "net::protocol_auth::run()",
+                "[P2P] Channel {} has no TLS session to bind authentication to",
+                self.channel.address(),
+            );
+            return Err(Error::PeerAuthFailed)
+        };
+
+        let initiator = self.channel.session_type_id() & SESSION_INBOUND == 0;
+
+        let nonce: [u8; 32] = OsRng.gen();
+        self.channel.send(&AuthChallengeMessage { nonce }).await?;
+
+        // Answer the peer challenge
+        let challenge = self.challenge_sub.receive().await?;
+        if challenge.nonce == nonce {
+            return Err(Error::PeerAuthFailed)
+        }
+        let msg = transcript(&self.magic_bytes, initiator, &challenge.nonce, &channel_binding);
+        let signature = self.identity.keypair.sk.sign(&msg, Some(Noise::generate()));
+        let response =
+            AuthResponseMessage { public_key: *self.identity.keypair.pk, signature: *signature };
+        self.channel.send(&response).await?;
+
+        // Check the peer answer to ours
+        let response = self.response_sub.receive().await?;
+        let public_key = PublicKey::new(response.public_key);
+        let signature = Signature::new(response.signature);
+        let msg = transcript(&self.magic_bytes, !initiator, &nonce, &channel_binding);
+        if public_key.verify(&msg, &signature).is_err() {
+            return Err(Error::PeerAuthFailed)
+        }
+
+        if !self.identity.is_allowed(&public_key) {
+            error!(
+                target: "net::protocol_auth::run()",
+                "[P2P] Peer {} is not allowed: {}",
+                self.channel.address(), base64::encode(public_key.as_ref()),
+            );
+            return Err(Error::PeerNotAllowed)
+        }
+
+        self.channel.set_identity(response.public_key).await;
+        debug!(
+            target: "net::protocol_auth::run()",
+            "END => address={}, identity={}",
+            self.channel.address(), base64::encode(public_key.as_ref()),
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity_from_settings() {
+        let mut settings = Settings::default();
+        assert!(Identity::from_settings(&settings).unwrap().is_none());
+
+        // An allowlist is useless without an identity
+        let peer_secret = generate_identity_secret();
+        settings.identity_secret = Some(peer_secret);
+        let peer = Identity::from_settings(&settings).unwrap().unwrap();
+
+        settings.identity_secret = None;
+        settings.allowed_peers = vec![peer.public_key()];
+        assert!(Identity::from_settings(&settings).is_err());
+
+        settings.identity_secret = Some(generate_identity_secret());
+        let identity = Identity::from_settings(&settings).unwrap().unwrap();
+        assert!(identity.is_allowed(&peer.keypair.pk));
+        assert!(!identity.is_allowed(&identity.keypair.pk));
+
+        // Responses only verify for the role and channel they were signed for
+        let nonce = [7u8; 32];
+        let binding = [1u8; 32];
+        let msg = transcript(&settings.magic_bytes.0, true, &nonce, &binding);
+        let signature = peer.keypair.sk.sign(&msg, None);
+        assert!(peer.keypair.pk.verify(&msg, &signature).is_ok());
+        let reflected = transcript(&settings.magic_bytes.0, false, &nonce, &binding);
+        assert!(peer.keypair.pk.verify(&reflected, &signature).is_err());
+        let relayed = transcript(&settings.magic_bytes.0, true, &nonce, &[2u8; 32]);
+        assert!(peer.keypair.pk.verify(&relayed, &signature).is_err());
+
+        settings.allowed_peers = vec!["not a key".to_string()];
+        assert!(Identity::from_settings(&settings).is_err());
+    }
+}
@@ -32,6 +32,7 @@ use super::{
         message::{PingMessage, PongMessage},
         message_publisher::MessageSubscription,
         p2p::P2pPtr,
+        scoring::Misbehavior,
         settings::Settings,
     },
     protocol_base::{ProtocolBase, ProtocolBasePtr},
@@ -74,20 +75,27 @@ impl ProtocolPing {
     }
 
     /// Runs the ping-pong protocol. Creates a subscription to pong, then
-    /// starts a loop. Loop sleeps for the duration of the channel heartbeat,
-    /// then sends a ping message with a random nonce. Loop starts a timer,
-    /// waits for the pong reply and ensures the nonce is the same.
+    /// starts a loop. Loop sends a ping message with a random nonce and
+    /// waits for the pong reply carrying the same nonce, then sleeps for
+    /// the ping interval. A ping without a reply within the pong timeout
+    /// counts as missed and is retried right away. Once the session's
+    /// missed pings threshold is reached the channel is stopped.
     async fn run_ping_pong(self: Arc<Self>) -> Result<()> {
         debug!(
             target: "net::protocol_ping::run_ping_pong()",
             "START => address={}", self.channel.address(),
         );
 
+        let session_type = self.channel.session_type_id();
+
+        // Consecutive pings that went unanswered
+        let mut missed = 0;
+        // Nonces of the missed pings, so their late pongs are not
+        // mistaken for bogus replies.
+        let mut pending = vec![];
+
         loop {
-            let settings = self.settings.read().await;
-            let outbound_connect_timeout = settings.outbound_connect_timeout;
-            let channel_heartbeat_interval = settings.channel_heartbeat_interval;
-            drop(settings);
+            let keepalive = self.settings.read().await.keepalive(session_type);
 
             // Create a random nonce.
             let nonce = Self::random_nonce();
@@ -98,41 +106,65 @@ impl ProtocolPing {
 
             // Start the timer for the ping timer
             let timer = Instant::now();
+            let pong_timeout = Duration::from_secs(keepalive.pong_timeout);
 
             // Wait for pong, check nonce matches.
-            let pong_msg = match timeout(
-                Duration::from_secs(outbound_connect_timeout),
-                self.pong_sub.receive(),
-            )
-            .await
-            {
-                Ok(msg) => {
+            let received = loop {
+                let remaining = pong_timeout.saturating_sub(timer.elapsed());
+                let pong_msg = match timeout(remaining, self.pong_sub.receive()).await {
                     // msg will be an error when the channel is stopped
                     // so just yield out of this function.
-                    msg?
+                    Ok(msg) => msg?,
+                    Err(_e) => break false,
+                };
+
+                if pong_msg.nonce == nonce {
+                    break true
                 }
-                Err(_e) => {
-                    // Pong timeout. We didn't receive any message back
-                    // so close the connection.
-                    warn!(
-                        target: "net::protocol_ping::run_ping_pong()",
-                        "[P2P] Ping-Pong protocol timed out for {}", self.channel.address(),
-                    );
-                    self.channel.stop().await;
-                    return Err(Error::ChannelStopped)
+
+                if let Some(i) = pending.iter().position(|n| *n == pong_msg.nonce) {
+                    pending.swap_remove(i);
+                    continue
                 }
-            };
 
-            if pong_msg.nonce != nonce {
                 error!(
                     target: "net::protocol_ping::run_ping_pong()",
                     "[P2P] Wrong nonce in pingpong, disconnecting {}",
                     self.channel.address(),
                 );
+                self.channel.penalize(Misbehavior::StalePing).await;
                 self.channel.stop().await;
                 return Err(Error::ChannelStopped)
+            };
+
+            if !received {
+                missed += 1;
+                pending.push(nonce);
+
+                if missed >= keepalive.max_missed_pings.max(1) {
+                    // The peer stopped answering, so consider the
+                    // connection dead and close it.
+                    warn!(
+                        target: "net::protocol_ping::run_ping_pong()",
+                        "[P2P] Ping-Pong protocol timed out for {} after {} missed pings",
+                        self.channel.address(), missed,
+                    );
+                    self.channel.penalize(Misbehavior::StalePing).await;
+                    self.channel.stop().await;
+                    return Err(Error::ChannelStopped)
+                }
+
+                debug!(
+                    target: "net::protocol_ping::run_ping_pong()",
+                    "Missed Pong from {} ({}/{})",
+                    self.channel.address(), missed, keepalive.max_missed_pings,
+                );
+                continue
             }
 
+            missed = 0;
+            pending.clear();
+
             debug!(
                 target: "net::protocol_ping::run_ping_pong()",
                 "Received Pong from {}: {:?}",
@@ -141,7 +173,7 @@ impl ProtocolPing {
             );
 
             // Sleep until next heartbeat
-            sleep(channel_heartbeat_interval).await;
+            sleep(keepalive.ping_interval).await;
         }
     }
 
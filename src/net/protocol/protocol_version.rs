@@ -27,11 +27,16 @@ use std::{
     time::{Duration, UNIX_EPOCH},
 };
 
-use super::super::{
-    channel::ChannelPtr,
-    message::{VerackMessage, VersionMessage},
-    message_publisher::MessageSubscription,
-    settings::Settings,
+use super::{
+    super::{
+        channel::ChannelPtr,
+        compression::{ZSTD_FEATURE, ZSTD_FEATURE_VERSION},
+        message::{VerackMessage, VersionMessage},
+        message_publisher::MessageSubscription,
+        session::SESSION_INBOUND,
+        settings::Settings,
+    },
+    protocol_auth::{ProtocolAuth, AUTH_FEATURE, AUTH_FEATURE_VERSION},
 };
 use crate::{Error, Result};
 
@@ -42,6 +47,8 @@ pub struct ProtocolVersion {
     version_sub: MessageSubscription<VersionMessage>,
     verack_sub: MessageSubscription<VerackMessage>,
     settings: Arc<AsyncRwLock<Settings>>,
+    /// Peer authentication, when running in authenticated mode
+    auth: Option<Arc<ProtocolAuth>>,
 }
 
 impl ProtocolVersion {
@@ -58,12 +65,22 @@ impl ProtocolVersion {
         let verack_sub =
             channel.subscribe_msg::<VerackMessage>().await.expect("Missing verack dispatcher!");
 
-        Arc::new(Self { channel, version_sub, verack_sub, settings })
+        // Creates the auth subscriptions when we have an identity
+        let auth = match channel.p2p().identity() {
+            Some(identity) => {
+                let settings = settings.read().await;
+                Some(ProtocolAuth::new(channel.clone(), identity.clone(), &settings).await)
+            }
+            None => None,
+        };
+
+        Arc::new(Self { channel, version_sub, verack_sub, settings, auth })
     }
 
     /// Start version information exchange. Start the timer. Send version
     /// info and wait for version ack. Wait for version info and send
-    /// version ack.
+    /// version ack. In authenticated mode, the peer then has to prove
+    /// its identity before the timer runs out.
     pub async fn run(self: Arc<Self>, executor: Arc<Executor<'_>>) -> Result<()> {
         debug!(target: "net::protocol_version::run()", "START => address={}", self.channel.address());
         let timeout =
@@ -134,6 +151,16 @@ impl ProtocolVersion {
             return Err(e.clone())
         }
 
+        if let Some(auth) = &self.auth {
+            if let Err(e) = auth.run().await {
+                error!(
+                    target: "net::protocol_version::exchange_versions()",
+                    "Peer authentication failed: {e}"
+                );
+                return Err(e)
+            }
+        }
+
         debug!(
             target: "net::protocol_version::exchange_versions()",
             "END => address={}", self.channel.address(),
@@ -141,6 +168,36 @@ impl ProtocolVersion {
         Ok(())
     }
 
+    /// When we dialed the peer over clearnet, the peer reports the address
+    /// our connection came from, i.e. our external address. Record it so
+    /// we can figure out what to advertise when we're behind NAT.
+    async fn observe_external_addr(&self, version: &VersionMessage) {
+        // Inbound peers report back the address they dialed, which is
+        // whatever we already advertise.
+        if self.channel.session_type_id() & SESSION_INBOUND != 0 {
+            return
+        }
+
+        // Anything proxied would report the address of the proxy
+        let address = self.channel.address();
+        if !matches!(address.scheme(), "tcp" | "tcp+tls" | "ws" | "wss") {
+            return
+        }
+
+        let hosts = self.channel.hosts();
+        let Some(addr) = version.get_observed_addr() else { return };
+        if !self.settings.read().await.localnet && hosts.is_local_host(&version.connect_recv_addr) {
+            return
+        }
+
+        let Some(reporter) = address.host_str() else { return };
+        debug!(
+            target: "net::protocol_version::observe_external_addr()",
+            "{reporter} sees us as {addr}",
+        );
+        hosts.add_auto_addr(reporter, addr);
+    }
+
     /// Send version info and wait for version acknowledgement.
     /// Ensures that the app version is the same.
     async fn send_version(self: Arc<Self>) -> Result<()> {
@@ -152,8 +209,17 @@ impl ProtocolVersion {
         let settings = self.settings.read().await;
         let node_id = settings.node_id.clone();
         let app_version = settings.app_version.clone();
+        let compression = settings.compression;
         drop(settings);
 
+        let mut features = vec![];
+        if compression {
+            features.push((ZSTD_FEATURE.to_string(), ZSTD_FEATURE_VERSION));
+        }
+        if self.auth.is_some() {
+            features.push((AUTH_FEATURE.to_string(), AUTH_FEATURE_VERSION));
+        }
+
         let external_addrs = self.channel.hosts().external_addrs().await;
 
         let version = VersionMessage {
@@ -166,7 +232,7 @@ impl ProtocolVersion {
             /* NOTE: `features` is a list of enabled features in the
             format Vec<(service, version)>. In the future, Protocols will
             add their own data to this field when they are attached.*/
-            features: vec![],
+            features,
         };
         self.channel.send(&version).await?;
 
@@ -212,10 +278,7 @@ impl ProtocolVersion {
 
         // Receive version message
         let version = self.version_sub.receive().await?;
-        if let Some(ipv6_addr) = version.get_ipv6_addr() {
-            let hosts = self.channel.p2p().hosts();
-            hosts.add_auto_addr(ipv6_addr);
-        }
+        self.observe_external_addr(&version).await;
         self.channel.set_version(version).await;
 
         // Send verack
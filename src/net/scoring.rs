@@ -0,0 +1,238 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex as SyncMutex},
+    time::UNIX_EPOCH,
+};
+
+use url::Url;
+
+use super::settings::Settings;
+
+/// Atomic pointer to the peer scores
+pub type PeerScoresPtr = Arc<PeerScores>;
+
+/// Kinds of peer misbehavior that add to the peer score
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Misbehavior {
+    /// Peer sent a message we don't understand or didn't ask for
+    InvalidMessage,
+    /// Peer did not serve a sync request in time
+    FailedSync,
+    /// Peer exceeded the message rate limits
+    ExcessiveBandwidth,
+    /// Peer did not answer a ping in time or answered it wrong
+    StalePing,
+}
+
+impl Misbehavior {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::InvalidMessage => "invalid_message",
+            Self::FailedSync => "failed_sync",
+            Self::ExcessiveBandwidth => "excessive_bandwidth",
+            Self::StalePing => "stale_ping",
+        }
+    }
+
+    /// The configured penalty for this misbehavior
+    fn penalty(&self, settings: &Settings) -> u32 {
+        match self {
+            Self::InvalidMessage => settings.score_invalid_message,
+            Self::FailedSync => settings.score_failed_sync,
+            Self::ExcessiveBandwidth => settings.score_excessive_bandwidth,
+            Self::StalePing => settings.score_stale_ping,
+        }
+    }
+}
+
+/// What should happen to a peer after it got penalized
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ScoreAction {
+    /// Score is still below the thresholds
+    Keep,
+    /// Drop the connection, moving the peer back to the greylist
+    Demote,
+    /// Blacklist the peer
+    Ban,
+}
+
+#[derive(Clone, Debug)]
+struct PeerScore {
+    /// Accumulated penalties
+    score: u32,
+    /// UNIX timestamp the score was last decayed at
+    updated: u64,
+}
+
+impl PeerScore {
+    /// Take one point off the score for every `interval` seconds that
+    /// passed since the last update.
+    fn decay(&mut self, now: u64, interval: u64) {
+        if interval == 0 || now <= self.updated {
+            return
+        }
+
+        let points = (now - self.updated) / interval;
+        self.score = self.score.saturating_sub(points.min(u32::MAX as u64) as u32);
+        self.updated = if self.score == 0 { now } else { self.updated + points * interval };
+    }
+}
+
+/// Misbehavior scores of the peers we talk to.
+///
+/// Peers are keyed the same way they'd be blacklisted, so inbound peers
+/// are tracked by host regardless of the port they connect from. Scores
+/// slowly decay over time, so only peers misbehaving repeatedly within a
+/// short time reach the thresholds.
+#[derive(Default)]
+pub struct PeerScores {
+    scores: SyncMutex<HashMap<Url, PeerScore>>,
+}
+
+impl PeerScores {
+    pub fn new() -> PeerScoresPtr {
+        Arc::new(Self::default())
+    }
+
+    /// Add the penalty for `misbehavior` to the peer score. Returns the
+    /// new score along with what should happen to the peer.
+    pub(in crate::net) fn penalize(
+        &self,
+        peer: &Url,
+        misbehavior: Misbehavior,
+        settings: &Settings,
+    ) -> (u32, ScoreAction) {
+        let now = UNIX_EPOCH.elapsed().unwrap().as_secs();
+        self.penalize_at(peer, misbehavior, settings, now)
+    }
+
+    fn penalize_at(
+        &self,
+        peer: &Url,
+        misbehavior: Misbehavior,
+        settings: &Settings,
+        now: u64,
+    ) -> (u32, ScoreAction) {
+        let mut scores = self.scores.lock().unwrap();
+        let entry = scores.entry(peer.clone()).or_insert(PeerScore { score: 0, updated: now });
+        entry.decay(now, settings.score_decay_interval);
+        entry.score = entry.score.saturating_add(misbehavior.penalty(settings));
+        let score = entry.score;
+
+        if score >= settings.score_ban_threshold {
+            // The blacklist takes over from here
+            scores.remove(peer);
+            return (score, ScoreAction::Ban)
+        }
+
+        if score >= settings.score_demote_threshold {
+            return (score, ScoreAction::Demote)
+        }
+
+        (score, ScoreAction::Keep)
+    }
+
+    /// Current score of the given peer
+    pub fn score(&self, peer: &Url, settings: &Settings) -> u32 {
+        let now = UNIX_EPOCH.elapsed().unwrap().as_secs();
+        let mut scores = self.scores.lock().unwrap();
+        let Some(entry) = scores.get_mut(peer) else { return 0 };
+        entry.decay(now, settings.score_decay_interval);
+        entry.score
+    }
+
+    /// Current scores of all peers with a non-zero score. Peers whose
+    /// score decayed to zero are forgotten.
+    pub fn scores(&self, settings: &Settings) -> Vec<(Url, u32)> {
+        let now = UNIX_EPOCH.elapsed().unwrap().as_secs();
+        let mut scores = self.scores.lock().unwrap();
+        scores.retain(|_, entry| {
+            entry.decay(now, settings.score_decay_interval);
+            entry.score > 0
+        });
+        scores.iter().map(|(peer, entry)| (peer.clone(), entry.score)).collect()
+    }
+
+    /// Raw scores along with their last decay timestamp, for persisting
+    /// them across restarts.
+    pub(in crate::net) fn export(&self) -> Vec<(Url, u32, u64)> {
+        let scores = self.scores.lock().unwrap();
+        scores.iter().map(|(peer, entry)| (peer.clone(), entry.score, entry.updated)).collect()
+    }
+
+    /// Restore a score saved with [`PeerScores::export`]. The time spent
+    /// offline counts towards the decay.
+    pub(in crate::net) fn import(&self, peer: Url, score: u32, updated: u64) {
+        self.scores.lock().unwrap().insert(peer, PeerScore { score, updated });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peer_score_thresholds() {
+        let settings = Settings {
+            score_invalid_message: 100,
+            score_stale_ping: 20,
+            score_demote_threshold: 50,
+            score_ban_threshold: 100,
+            score_decay_interval: 10,
+            ..Default::default()
+        };
+        let scores = PeerScores::default();
+        let peer = Url::parse("tcp+tls://77.168.10.65:26661").unwrap();
+
+        assert_eq!(
+            scores.penalize_at(&peer, Misbehavior::StalePing, &settings, 0),
+            (20, ScoreAction::Keep)
+        );
+        assert_eq!(
+            scores.penalize_at(&peer, Misbehavior::StalePing, &settings, 0),
+            (40, ScoreAction::Keep)
+        );
+
+        // 100 seconds take 10 points off
+        assert_eq!(
+            scores.penalize_at(&peer, Misbehavior::StalePing, &settings, 100),
+            (50, ScoreAction::Demote)
+        );
+
+        // Partial intervals are kept for the next decay
+        assert_eq!(
+            scores.penalize_at(&peer, Misbehavior::StalePing, &settings, 105),
+            (70, ScoreAction::Demote)
+        );
+        assert_eq!(
+            scores.penalize_at(&peer, Misbehavior::StalePing, &settings, 115),
+            (89, ScoreAction::Demote)
+        );
+
+        assert_eq!(
+            scores.penalize_at(&peer, Misbehavior::InvalidMessage, &settings, 115),
+            (189, ScoreAction::Ban)
+        );
+
+        // Banned peers start over
+        assert!(scores.scores.lock().unwrap().is_empty());
+    }
+}
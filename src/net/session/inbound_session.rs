@@ -35,6 +35,7 @@ use super::{
         acceptor::{Acceptor, AcceptorPtr},
         channel::ChannelPtr,
         dnet::{self, dnetev, DnetEvent},
+        nat::{PortMapper, PortMapperPtr},
         p2p::{P2p, P2pPtr},
     },
     Session, SessionBitFlag, SESSION_INBOUND,
@@ -51,15 +52,19 @@ pub struct InboundSession {
     pub(in crate::net) p2p: Weak<P2p>,
     acceptors: Mutex<Vec<AcceptorPtr>>,
     accept_tasks: Mutex<Vec<StoppableTaskPtr>>,
+    port_mapper: PortMapperPtr,
+    nat_task: StoppableTaskPtr,
 }
 
 impl InboundSession {
     /// Create a new inbound session
     pub fn new(p2p: Weak<P2p>) -> InboundSessionPtr {
         Arc::new(Self {
-            p2p,
+            p2p: p2p.clone(),
             acceptors: Mutex::new(Vec::new()),
             accept_tasks: Mutex::new(Vec::new()),
+            port_mapper: PortMapper::new(p2p),
+            nat_task: StoppableTask::new(),
         })
     }
 
@@ -108,6 +113,21 @@ impl InboundSession {
                 .await?;
         }
 
+        // Map the inbound ports on the gateway once we're listening
+        if self.p2p().settings().read().await.nat_traversal {
+            self.nat_task.clone().start(
+                self.port_mapper.clone().run(),
+                |res| async {
+                    match res {
+                        Ok(()) | Err(Error::NetworkServiceStopped) => {}
+                        Err(e) => error!(target: "net::inbound_session", "[P2P] NAT traversal failed: {e}"),
+                    }
+                },
+                Error::NetworkServiceStopped,
+                ex,
+            );
+        }
+
         Ok(())
     }
 
@@ -127,6 +147,11 @@ impl InboundSession {
         for accept_task in accept_tasks {
             accept_task.stop().await;
         }
+
+        if self.p2p().settings().read().await.nat_traversal {
+            self.nat_task.stop().await;
+            self.port_mapper.stop().await;
+        }
     }
 
     /// Start accepting connections for inbound session.
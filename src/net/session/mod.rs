@@ -25,15 +25,25 @@ use async_trait::async_trait;
 use log::{debug, error, trace};
 use smol::Executor;
 
-use super::{channel::ChannelPtr, hosts::HostColor, p2p::P2pPtr, protocol::ProtocolVersion};
+use super::{
+    channel::ChannelPtr,
+    event::{ChannelEventInfo, P2pEvent},
+    hosts::HostColor,
+    p2p::P2pPtr,
+    protocol::ProtocolVersion,
+};
 use crate::{system::Subscription, Error, Result};
 
+#[cfg(test)]
+mod tests;
+
 pub mod inbound_session;
 pub use inbound_session::{InboundSession, InboundSessionPtr};
 pub mod manual_session;
 pub use manual_session::{ManualSession, ManualSessionPtr};
 pub mod outbound_session;
 pub use outbound_session::{OutboundSession, OutboundSessionPtr};
+mod outbound_target;
 pub mod seedsync_session;
 pub use seedsync_session::{SeedSyncSession, SeedSyncSessionPtr};
 pub mod refine_session;
@@ -50,6 +60,18 @@ pub const SESSION_REFINE: SessionBitFlag = 0b10000;
 pub const SESSION_DEFAULT: SessionBitFlag = 0b00111;
 pub const SESSION_ALL: SessionBitFlag = 0b11111;
 
+/// Human readable name of a session type
+pub fn session_name(type_id: SessionBitFlag) -> &'static str {
+    match type_id {
+        SESSION_INBOUND => "inbound",
+        SESSION_OUTBOUND => "outbound",
+        SESSION_MANUAL => "manual",
+        SESSION_REFINE => "refine",
+        SESSION_SEED => "seed",
+        _ => "unknown",
+    }
+}
+
 pub type SessionWeakPtr = Weak<dyn Session + Send + Sync + 'static>;
 
 /// Removes channel from the list of connected channels when a stop signal
@@ -64,7 +86,7 @@ pub async fn remove_sub_on_stop(
     let hosts = p2p.hosts();
     let addr = channel.address();
 
-    stop_sub.receive().await;
+    let reason = stop_sub.receive().await;
 
     debug!(
         target: "net::session::remove_sub_on_stop()",
@@ -101,6 +123,9 @@ pub async fn remove_sub_on_stop(
     // happens in the refinery directly.
     if type_id & SESSION_REFINE == 0 {
         hosts.unregister(channel.address());
+
+        let info = ChannelEventInfo::new(&channel, type_id);
+        p2p.notify_event(P2pEvent::ChannelDisconnected { info, reason: reason.to_string() }).await;
     }
 
     if !p2p.is_connected() {
@@ -209,8 +234,18 @@ pub trait Session: Sync {
                         .await?;
                 }
 
-                // Attempt to add channel to registry
-                self.p2p().hosts().register_channel(channel.clone()).await;
+                // Attempt to add channel to registry. A duplicate channel must
+                // not unregister the address on stop, so drop it right here.
+                if let Err(e) = self.p2p().hosts().register_channel(channel.clone()).await {
+                    channel.stop().await;
+                    return Err(e)
+                }
+
+                // Refinery probes are short lived, don't report them
+                if self.type_id() & SESSION_REFINE == 0 {
+                    let info = ChannelEventInfo::new(&channel, self.type_id());
+                    self.p2p().notify_event(P2pEvent::ChannelConnected(info)).await;
+                }
 
                 // Subscribe to stop, so we can remove from registry
                 executor
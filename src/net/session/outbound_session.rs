@@ -28,7 +28,7 @@
 
 use std::{
     sync::{
-        atomic::{AtomicU32, Ordering},
+        atomic::{AtomicU32, AtomicUsize, Ordering},
         Arc, Weak,
     },
     time::{Duration, Instant},
@@ -46,9 +46,11 @@ use super::{
         connector::Connector,
         dnet::{self, dnetev, DnetEvent},
         hosts::{HostColor, HostState},
+        lan::{LanDiscovery, LanDiscoveryPtr},
         message::GetAddrsMessage,
         p2p::{P2p, P2pPtr},
     },
+    outbound_target::{desired_slots, role_bounds, OutboundTarget},
     Session, SessionBitFlag, SESSION_OUTBOUND,
 };
 use crate::{
@@ -58,6 +60,10 @@ use crate::{
 
 pub type OutboundSessionPtr = Arc<OutboundSession>;
 
+/// Maximum number of known peers asked for addresses when the seeds
+/// are unreachable
+const MAX_BOOTSTRAP_PEERS: usize = 8;
+
 /// Defines outbound connections session.
 pub struct OutboundSession {
     /// Weak pointer to parent p2p object
@@ -66,23 +72,46 @@ pub struct OutboundSession {
     slots: Mutex<Vec<Arc<Slot>>>,
     /// Peer discovery task
     peer_discovery: Arc<PeerDiscovery>,
+    /// Number of slots allowed to hold a connection. Slots with an
+    /// index at or above this stay idle.
+    target: AtomicUsize,
+    /// Adaptive outbound target task
+    adapter: StoppableTaskPtr,
+    /// Local network discovery
+    lan_discovery: LanDiscoveryPtr,
+    /// Local network discovery task
+    lan_task: StoppableTaskPtr,
 }
 
 impl OutboundSession {
     /// Create a new outbound session.
     pub(crate) fn new(p2p: Weak<P2p>) -> OutboundSessionPtr {
         Arc::new_cyclic(|session| Self {
-            p2p,
+            p2p: p2p.clone(),
             slots: Mutex::new(Vec::new()),
             peer_discovery: PeerDiscovery::new(session.clone()),
+            target: AtomicUsize::new(0),
+            adapter: StoppableTask::new(),
+            lan_discovery: LanDiscovery::new(p2p),
+            lan_task: StoppableTask::new(),
         })
     }
 
     /// Start the outbound session. Runs the channel connect loop.
     pub(crate) async fn start(self: Arc<Self>) {
-        let n_slots = self.p2p().settings().read().await.outbound_connections;
+        let settings = self.p2p().settings().read_arc().await;
+        let n_slots = settings.outbound_connections;
+        let adaptive = settings.adaptive_outbound;
+        let node_role = settings.node_role;
+        let lan_discovery = settings.lan_discovery;
+        drop(settings);
         info!(target: "net::outbound_session", "[P2P] Starting {n_slots} outbound connection slots.");
 
+        // With adaptive outbound enabled, start at the idle count for
+        // our role and let the adapter move the target from there.
+        let target = if adaptive { role_bounds(node_role, n_slots).1 } else { n_slots };
+        self.target.store(target, Ordering::SeqCst);
+
         // Activate mutex lock on connection slots.
         let mut slots = self.slots.lock().await;
 
@@ -99,6 +128,38 @@ impl OutboundSession {
         while (futures.next().await).is_some() {}
 
         self.peer_discovery.clone().start().await;
+
+        if lan_discovery {
+            self.lan_task.clone().start(
+                self.lan_discovery.clone().run(),
+                |res| async {
+                    match res {
+                        Ok(()) | Err(Error::NetworkServiceStopped) => {}
+                        Err(e) => error!(target: "net::outbound_session", "[P2P] LAN discovery failed: {e}"),
+                    }
+                },
+                Error::NetworkServiceStopped,
+                self.p2p().executor(),
+            );
+        }
+
+        if adaptive {
+            info!(
+                target: "net::outbound_session",
+                "[P2P] Adaptive outbound enabled for {node_role:?} role, starting with {target} active slots"
+            );
+            let self_ = self.clone();
+            self.adapter.clone().start(
+                async move {
+                    self_.adapt().await;
+                    unreachable!();
+                },
+                // Ignore stop handler
+                |_| async {},
+                Error::NetworkServiceStopped,
+                self.p2p().executor(),
+            );
+        }
     }
 
     /// Stops the outbound session.
@@ -113,6 +174,17 @@ impl OutboundSession {
 
         while (futures.next().await).is_some() {}
 
+        let settings = self.p2p().settings().read_arc().await;
+        let adaptive = settings.adaptive_outbound;
+        let lan_discovery = settings.lan_discovery;
+        drop(settings);
+
+        if adaptive {
+            self.adapter.stop().await;
+        }
+        if lan_discovery {
+            self.lan_task.stop().await;
+        }
         self.peer_discovery.clone().stop().await;
         debug!(target: "net::outbound_session", "Outbound session stopped!");
     }
@@ -126,6 +198,66 @@ impl OutboundSession {
         info
     }
 
+    /// Number of outbound slots currently allowed to hold a connection
+    pub fn target(&self) -> usize {
+        self.target.load(Ordering::SeqCst)
+    }
+
+    /// Periodically recompute the desired number of active slots and
+    /// move the target towards it. Shrinking the target disconnects the
+    /// channel held by the slot that goes idle.
+    async fn adapt(self: Arc<Self>) {
+        let p2p = self.p2p();
+        let mut tracker = OutboundTarget::new(self.target());
+        let mut last_sent = p2p.bytes_sent();
+        let mut last_instant = Instant::now();
+
+        loop {
+            let interval = p2p.settings().read().await.outbound_adapt_interval.max(1);
+            sleep(interval).await;
+
+            let sent = p2p.bytes_sent();
+            let elapsed = last_instant.elapsed().as_secs().max(1);
+            let bytes_per_sec = (sent - last_sent) / elapsed;
+            last_sent = sent;
+            last_instant = Instant::now();
+
+            let settings = p2p.settings().read_arc().await;
+            let desired = desired_slots(
+                settings.node_role,
+                settings.outbound_connections,
+                p2p.is_syncing(),
+                settings.outbound_bandwidth_limit,
+                bytes_per_sec,
+                p2p.peers_count(),
+            );
+            drop(settings);
+
+            let Some(new_target) = tracker.update(desired) else { continue };
+            let old_target = self.target.swap(new_target, Ordering::SeqCst);
+
+            debug!(
+                target: "net::outbound_session::adapt()",
+                "[P2P] Outbound target {old_target} -> {new_target} (desired={desired}, {bytes_per_sec} B/s)"
+            );
+
+            if new_target > old_target {
+                self.wakeup_slots().await;
+                continue
+            }
+
+            // Disconnect the slots that just went idle
+            let slots = self.slots.lock().await.clone();
+            for slot in &slots[new_target..old_target] {
+                let channel_id = slot.channel_id.load(Ordering::Relaxed);
+                if let Some(channel) = p2p.get_channel(channel_id) {
+                    channel.stop().await;
+                }
+                slot.notify();
+            }
+        }
+    }
+
     fn wakeup_peer_discovery(&self) {
         self.peer_discovery.notify()
     }
@@ -274,6 +406,16 @@ impl Slot {
         let hosts = self.p2p().hosts();
 
         loop {
+            // Stay idle while this slot is above the outbound target
+            self.wakeup_self.reset();
+            if self.slot as usize >= self.session().target() {
+                dnetev!(self, OutboundSlotSleeping, {
+                    slot: self.slot,
+                });
+                self.wakeup_self.wait().await;
+                continue
+            }
+
             // Activate the slot
             debug!(
                 target: "net::outbound_session::try_connect()",
@@ -481,8 +623,10 @@ pub trait PeerDiscoveryBase {
 
 /// Main PeerDiscovery process that loops through connected peers
 /// and sends out a `GetAddrs` when it is active. If there are no
-/// connected peers after two attempts, connect to our seed nodes
-/// and perform `SeedSyncSession`.
+/// connected peers after two attempts, look up peers in the DHT if
+/// one is attached, and otherwise connect to our seed nodes and
+/// perform `SeedSyncSession`. When the seeds are unreachable, fall
+/// back to exchanging addresses with peers we already know.
 struct PeerDiscovery {
     process: StoppableTaskPtr,
     wakeup_self: CondVar,
@@ -493,6 +637,85 @@ impl PeerDiscovery {
     fn new(session: Weak<OutboundSession>) -> Arc<Self> {
         Arc::new(Self { process: StoppableTask::new(), wakeup_self: CondVar::new(), session })
     }
+
+    /// Ask the peer DHT for new addresses, if the application attached
+    /// one. Returns `true` if the lookup came back with any hosts.
+    #[cfg(feature = "dht")]
+    async fn dht_discovery(&self, current_attempt: u32) -> bool {
+        let Some(peer_dht) = self.p2p().peer_dht() else { return false };
+        if !peer_dht.dht.is_bootstrapped().await {
+            return false
+        }
+
+        info!(
+            target: "net::outbound_session::peer_discovery()",
+            "[P2P] [PEER DISCOVERY] Asking the DHT for new peers to connect to...");
+
+        dnetev!(self, OutboundPeerDiscovery, {
+            attempt: current_attempt,
+            state: "dht",
+        });
+
+        match peer_dht.discover().await {
+            Ok(0) => false,
+            Ok(addrs_len) => {
+                info!(
+                    target: "net::outbound_session::peer_discovery()",
+                    "[P2P] [PEER DISCOVERY] Discovered {addrs_len} peers through the DHT"
+                );
+                true
+            }
+            Err(e) => {
+                warn!(
+                    target: "net::outbound_session::peer_discovery()",
+                    "[P2P] [PEER DISCOVERY] DHT lookup failed: {e}"
+                );
+                false
+            }
+        }
+    }
+
+    #[cfg(not(feature = "dht"))]
+    async fn dht_discovery(&self, _current_attempt: u32) -> bool {
+        false
+    }
+
+    /// Exchange addresses with the configured bootstrap peers and the
+    /// gold and white entries of our hostlist, just like we would with
+    /// seeds. Allows running without seeds, or with all of them down.
+    async fn bootstrap(&self, current_attempt: u32) {
+        let p2p = self.p2p();
+        let mut addrs = p2p.settings().read().await.bootstrap_peers.clone();
+        for color in [HostColor::Gold, HostColor::White] {
+            for (addr, _) in p2p.hosts().container.fetch_all(color) {
+                if !addrs.contains(&addr) {
+                    addrs.push(addr);
+                }
+            }
+        }
+        addrs.truncate(MAX_BOOTSTRAP_PEERS);
+
+        if addrs.is_empty() {
+            return
+        }
+
+        info!(
+            target: "net::outbound_session::peer_discovery()",
+            "[P2P] [PEER DISCOVERY] Seeds unavailable, asking {} known peers for new peers...",
+            addrs.len(),
+        );
+
+        dnetev!(self, OutboundPeerDiscovery, {
+            attempt: current_attempt,
+            state: "bootstrap",
+        });
+
+        let synced = p2p.session_seedsync().bootstrap(addrs).await;
+        info!(
+            target: "net::outbound_session::peer_discovery()",
+            "[P2P] [PEER DISCOVERY] Synced with {synced} bootstrap peers"
+        );
+    }
 }
 
 #[async_trait]
@@ -521,9 +744,12 @@ impl PeerDiscoveryBase for PeerDiscovery {
     /// the hosts list.  
     ///
     /// On the third attempt, and if we still haven't made any connections,
-    /// this function will then call `p2p.seed()` which triggers a
-    /// `SeedSyncSession` that will connect to configured seeds and request
-    /// peers from them.
+    /// this function will look up random keys in the peer DHT if one is
+    /// attached. If that yields nothing, it will call `p2p.seed()` which
+    /// triggers a `SeedSyncSession` that will connect to configured seeds
+    /// and request peers from them. If there are no seeds, or the last
+    /// seeding round failed, it will also ask the bootstrap peers and the
+    /// peers of our hostlist.
     ///
     /// This function will also sleep `outbound_peer_discovery_attempt_time`
     /// seconds after broadcasting in order to let the P2P stack receive and
@@ -626,17 +852,29 @@ impl PeerDiscoveryBase for PeerDiscovery {
                 // Drop. For now it's sufficient for publishers to be
                 // de-allocated when the Session completes.
                 store_sub.unsubscribe().await;
-            } else if !seeds.is_empty() {
-                info!(
-                    target: "net::outbound_session::peer_discovery()",
-                    "[P2P] [PEER DISCOVERY] Asking seeds for new peers to connect to...");
+            } else if self.dht_discovery(current_attempt).await {
+                // The DHT gave us fresh hosts, no need to bother the seeds
+            } else {
+                // Failed seeds get retried, but meanwhile fall back to
+                // the peers we already know about.
+                let seeds_failed = self.p2p().session_seedsync().failed().await;
+
+                if !seeds.is_empty() {
+                    info!(
+                        target: "net::outbound_session::peer_discovery()",
+                        "[P2P] [PEER DISCOVERY] Asking seeds for new peers to connect to...");
+
+                    dnetev!(self, OutboundPeerDiscovery, {
+                        attempt: current_attempt,
+                        state: "seed",
+                    });
 
-                dnetev!(self, OutboundPeerDiscovery, {
-                    attempt: current_attempt,
-                    state: "seed",
-                });
+                    self.p2p().seed().await;
+                }
 
-                self.p2p().seed().await;
+                if seeds_failed {
+                    self.bootstrap(current_attempt).await;
+                }
             }
 
             self.wakeup_self.reset();
@@ -0,0 +1,177 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Adaptive outbound slot target.
+//!
+//! When `adaptive_outbound` is enabled, the outbound session does not keep
+//! all `outbound_connections` slots busy. Instead, it periodically computes
+//! a desired number of active slots from the node role, the sync state and
+//! the measured outbound bandwidth, and moves towards it one slot at a time.
+//! A change in direction has to be observed for a few consecutive rounds
+//! before it is applied, so short bandwidth spikes don't make the node
+//! churn its connections.
+
+use super::super::settings::NodeRole;
+
+/// Number of consecutive rounds a direction must be requested in before
+/// the target is moved.
+const HYSTERESIS_ROUNDS: usize = 3;
+
+/// Returns the `(min, idle, ceil)` number of active outbound slots for the
+/// given role, where `max` is the configured `outbound_connections`.
+pub(super) fn role_bounds(role: NodeRole, max: usize) -> (usize, usize, usize) {
+    let (min, idle, ceil) = match role {
+        NodeRole::Wallet => (1, max / 4, max / 2),
+        NodeRole::Light => (max / 4, max / 2, max),
+        NodeRole::Full => (max / 2, max * 3 / 4, max),
+        NodeRole::Validator => (max * 3 / 4, max, max),
+    };
+
+    let clamp = |n: usize| n.clamp(1, max.max(1));
+    (clamp(min), clamp(idle), clamp(ceil))
+}
+
+/// Compute the number of active outbound slots we want right now.
+///
+/// While syncing we use the role ceiling, otherwise the idle count. If a
+/// bandwidth limit is set and the measured rate exceeds it, the target is
+/// reduced to however many peers fit into the limit at the current
+/// per-peer rate. The result always stays within the role bounds.
+pub(super) fn desired_slots(
+    role: NodeRole,
+    max: usize,
+    syncing: bool,
+    bandwidth_limit: u64,
+    bytes_per_sec: u64,
+    peers: usize,
+) -> usize {
+    let (min, idle, ceil) = role_bounds(role, max);
+    let mut desired = if syncing { ceil } else { idle };
+
+    if bandwidth_limit > 0 && bytes_per_sec > bandwidth_limit && peers > 0 {
+        let per_peer = (bytes_per_sec / peers as u64).max(1);
+        let fits = (bandwidth_limit / per_peer) as usize;
+        desired = desired.min(fits);
+    }
+
+    desired.clamp(min, ceil)
+}
+
+/// Tracks the current outbound slot target and applies hysteresis to
+/// requested changes.
+pub(super) struct OutboundTarget {
+    current: usize,
+    /// Number of consecutive rounds asking to grow (positive) or
+    /// shrink (negative) the target.
+    streak: isize,
+}
+
+impl OutboundTarget {
+    pub(super) fn new(initial: usize) -> Self {
+        Self { current: initial, streak: 0 }
+    }
+
+    pub(super) fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Feed the desired slot count for this round. Returns the new target
+    /// if it changed.
+    pub(super) fn update(&mut self, desired: usize) -> Option<usize> {
+        if desired == self.current {
+            self.streak = 0;
+            return None
+        }
+
+        if desired > self.current {
+            self.streak = self.streak.max(0) + 1;
+        } else {
+            self.streak = self.streak.min(0) - 1;
+        }
+
+        if self.streak.unsigned_abs() < HYSTERESIS_ROUNDS {
+            return None
+        }
+
+        self.streak = 0;
+        if desired > self.current {
+            self.current += 1;
+        } else {
+            self.current -= 1;
+        }
+
+        Some(self.current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn role_bounds_clamped() {
+        assert_eq!(role_bounds(NodeRole::Wallet, 8), (1, 2, 4));
+        assert_eq!(role_bounds(NodeRole::Light, 8), (2, 4, 8));
+        assert_eq!(role_bounds(NodeRole::Full, 8), (4, 6, 8));
+        assert_eq!(role_bounds(NodeRole::Validator, 8), (6, 8, 8));
+
+        // Tiny configurations still get at least one slot
+        assert_eq!(role_bounds(NodeRole::Wallet, 1), (1, 1, 1));
+        assert_eq!(role_bounds(NodeRole::Light, 2), (1, 1, 2));
+    }
+
+    #[test]
+    fn desired_slots_bandwidth() {
+        // Syncing uses the ceiling, idle uses the idle count
+        assert_eq!(desired_slots(NodeRole::Full, 8, true, 0, 0, 0), 8);
+        assert_eq!(desired_slots(NodeRole::Full, 8, false, 0, 0, 0), 6);
+
+        // 6 peers at 1000 B/s each against a 3000 B/s budget
+        assert_eq!(desired_slots(NodeRole::Light, 8, false, 3000, 6000, 6), 3);
+
+        // Never below the role minimum
+        assert_eq!(desired_slots(NodeRole::Full, 8, true, 1000, 8000, 8), 4);
+
+        // Below the limit nothing changes
+        assert_eq!(desired_slots(NodeRole::Full, 8, false, 10000, 6000, 6), 6);
+    }
+
+    #[test]
+    fn target_hysteresis() {
+        let mut target = OutboundTarget::new(4);
+
+        assert_eq!(target.update(8), None);
+        assert_eq!(target.update(8), None);
+        assert_eq!(target.update(8), Some(5));
+
+        // A change in direction resets the streak
+        assert_eq!(target.update(8), None);
+        assert_eq!(target.update(2), None);
+        assert_eq!(target.update(8), None);
+        assert_eq!(target.update(8), None);
+        assert_eq!(target.update(8), Some(6));
+
+        // Reaching the desired count resets the streak too
+        assert_eq!(target.update(2), None);
+        assert_eq!(target.update(6), None);
+        assert_eq!(target.update(2), None);
+        assert_eq!(target.update(2), None);
+        assert_eq!(target.update(2), Some(5));
+        assert_eq!(target.current(), 5);
+    }
+}
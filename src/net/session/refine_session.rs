@@ -49,7 +49,7 @@ use crate::{
         session::{Session, SessionBitFlag, SESSION_REFINE},
     },
     system::{sleep, StoppableTask, StoppableTaskPtr},
-    Error,
+    Error, Result,
 };
 
 pub type RefineSessionPtr = Arc<RefineSession>;
@@ -69,17 +69,6 @@ impl RefineSession {
 
     /// Start the refinery and self handshake processes.
     pub(crate) async fn start(self: Arc<Self>) {
-        if let Some(ref hostlist) = self.p2p().settings().read().await.hostlist {
-            match self.p2p().hosts().container.load_all(hostlist) {
-                Ok(()) => {
-                    debug!(target: "net::refine_session::start", "Load hosts successful!");
-                }
-                Err(e) => {
-                    warn!(target: "net::refine_session::start", "Error loading hosts {e}");
-                }
-            }
-        }
-
         match self.p2p().hosts().import_blacklist().await {
             Ok(()) => {
                 debug!(target: "net::refine_session::start", "Import blacklist successful!");
@@ -98,39 +87,38 @@ impl RefineSession {
     pub(crate) async fn stop(&self) {
         debug!(target: "net::refine_session", "Stopping refinery process");
         self.refinery.clone().stop().await;
-
-        if let Some(ref hostlist) = self.p2p().settings().read().await.hostlist {
-            match self.p2p().hosts().container.save_all(hostlist) {
-                Ok(()) => {
-                    debug!(target: "net::refine_session::stop()", "Save hosts successful!");
-                }
-                Err(e) => {
-                    warn!(target: "net::refine_session::stop()", "Error saving hosts {e}");
-                }
-            }
-        }
     }
 
     /// Globally accessible function to perform a version exchange with a
     /// given address.  Returns `true` if an address is accessible, false
-    /// otherwise.  
+    /// otherwise.
     pub async fn handshake_node(self: Arc<Self>, addr: Url, p2p: P2pPtr) -> bool {
+        self.probe_node(addr, p2p).await.is_ok()
+    }
+
+    /// Perform a version exchange with a given address, like `handshake_node()`,
+    /// but return the time it took to connect and finish the handshake.
+    /// If we connected but the handshake itself failed, e.g. because of a
+    /// version mismatch, `Error::HandshakeFailed` is returned.  Any other
+    /// error means the node could not be reached.
+    pub async fn probe_node(self: Arc<Self>, addr: Url, p2p: P2pPtr) -> Result<Duration> {
         let self_ = Arc::downgrade(&self);
         let connector = Connector::new(self.p2p().settings(), self_);
 
-        debug!(target: "net::refinery::handshake_node()", "Attempting to connect to {addr}");
+        debug!(target: "net::refinery::probe_node()", "Attempting to connect to {addr}");
+        let start = Instant::now();
         match connector.connect(&addr).await {
             Ok((url, channel)) => {
-                debug!(target: "net::refinery::handshake_node()", "Successfully created a channel with {url}");
+                debug!(target: "net::refinery::probe_node()", "Successfully created a channel with {url}");
                 // First initialize the version protocol and its Version, Verack subscriptions.
                 let proto_ver = ProtocolVersion::new(channel.clone(), p2p.settings()).await;
 
-                debug!(target: "net::refinery::handshake_node()", "Performing handshake protocols with {url}");
+                debug!(target: "net::refinery::probe_node()", "Performing handshake protocols with {url}");
                 // Then run the version exchange, store the channel and subscribe to a stop signal.
                 let handshake =
                     self.perform_handshake_protocols(proto_ver, channel.clone(), p2p.executor());
 
-                debug!(target: "net::refinery::handshake_node()", "Starting channel {url}");
+                debug!(target: "net::refinery::probe_node()", "Starting channel {url}");
                 channel.clone().start(p2p.executor());
 
                 // Ensure the channel gets stopped by adding a timeout to the handshake. Otherwise if
@@ -143,28 +131,28 @@ impl RefineSession {
 
                 let result = match select(handshake, timeout).await {
                     Either::Left((Ok(_), _)) => {
-                        debug!(target: "net::refinery::handshake_node()", "Handshake success!");
-                        true
+                        debug!(target: "net::refinery::probe_node()", "Handshake success!");
+                        Ok(start.elapsed())
                     }
                     Either::Left((Err(e), _)) => {
-                        debug!(target: "net::refinery::handshake_node()", "Handshake error={e}");
-                        false
+                        debug!(target: "net::refinery::probe_node()", "Handshake error={e}");
+                        Err(Error::HandshakeFailed(e.to_string()))
                     }
                     Either::Right((_, _)) => {
-                        debug!(target: "net::refinery::handshake_node()", "Handshake timed out");
-                        false
+                        debug!(target: "net::refinery::probe_node()", "Handshake timed out");
+                        Err(Error::ChannelTimeout)
                     }
                 };
 
-                debug!(target: "net::refinery::handshake_node()", "Stopping channel {url}");
+                debug!(target: "net::refinery::probe_node()", "Stopping channel {url}");
                 channel.stop().await;
 
                 result
             }
 
             Err(e) => {
-                debug!(target: "net::refinery::handshake_node()", "Failed to connect to {addr}, ({e})");
-                false
+                debug!(target: "net::refinery::probe_node()", "Failed to connect to {addr}, ({e})");
+                Err(e)
             }
         }
     }
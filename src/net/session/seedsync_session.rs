@@ -128,10 +128,62 @@ impl SeedSyncSession {
         debug!(target: "net::seedsync_session", "Seed sync session stopped!");
     }
 
-    /// Returns true if every seed attempt per slot has failed.
-    async fn _failed(&self) -> bool {
+    /// Returns true if every seed attempt per slot has failed, or if
+    /// there are no seeds configured at all.
+    pub(crate) async fn failed(&self) -> bool {
         let slots = &*self.slots.lock().await;
-        slots.iter().all(|s| s._failed())
+        slots.iter().all(|s| s.failed())
+    }
+
+    /// Exchange addresses with the given peers the same way we do with
+    /// seeds. Used to bootstrap from already known peers when none of the
+    /// seeds is reachable. Returns the number of peers we synced with.
+    pub(crate) async fn bootstrap(self: Arc<Self>, addrs: Vec<Url>) -> usize {
+        let p2p = self.p2p();
+        let ex = p2p.executor();
+        let hosts = p2p.hosts();
+        let connector = Connector::new(p2p.settings(), Arc::downgrade(&self));
+
+        let mut futures = FuturesUnordered::new();
+        for addr in addrs {
+            if let Err(e) = hosts.try_register(addr.clone(), HostState::Connect) {
+                debug!(target: "net::seedsync_session::bootstrap()",
+                    "Cannot connect to peer={addr}, err={e}");
+                continue
+            }
+
+            let (self_, connector, ex) = (self.clone(), &connector, ex.clone());
+            futures.push(async move {
+                let (url, ch) = match connector.connect(&addr).await {
+                    Ok(v) => v,
+                    Err(e) => {
+                        warn!(target: "net::seedsync_session::bootstrap()",
+                            "[P2P] Unable to connect to bootstrap peer [{addr}]: {e}");
+                        self_.p2p().hosts().unregister(&addr);
+                        return false
+                    }
+                };
+
+                if let Err(e) = self_.register_channel(ch.clone(), ex).await {
+                    warn!(target: "net::seedsync_session::bootstrap()",
+                        "[P2P] Unable to sync with bootstrap peer [{url}]: {e}");
+                    self_.p2p().hosts().unregister(&url);
+                    return false
+                }
+
+                info!(target: "net::seedsync_session::bootstrap()",
+                    "[P2P] Synced with bootstrap peer [{url}]");
+                ch.stop().await;
+                true
+            });
+        }
+
+        let mut synced = 0;
+        while let Some(ok) = futures.next().await {
+            synced += ok as usize;
+        }
+
+        synced
     }
 }
 
@@ -276,7 +328,7 @@ impl Slot {
         self.reset();
     }
 
-    fn _failed(&self) -> bool {
+    fn failed(&self) -> bool {
         self.failed.load(SeqCst)
     }
 
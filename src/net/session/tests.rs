@@ -0,0 +1,507 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Deterministic tests for the handshake and the host state machine.
+//!
+//! Every scenario runs once per seed in `SEEDS`. The seed decides the
+//! order in which both ends of a connection get started, along with
+//! small delays injected before each of them, and is printed when an
+//! assertion fails so the run can be replayed.
+
+use std::{
+    io,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, UNIX_EPOCH},
+};
+
+use futures::{AsyncRead, AsyncWrite};
+use rand::{seq::SliceRandom, Rng};
+use smol::{net::unix::UnixStream, Executor, Timer};
+use url::Url;
+
+use super::{Session, SessionWeakPtr};
+use crate::{
+    net::{
+        channel::{Channel, ChannelPtr},
+        hosts::HostState,
+        protocol::protocol_auth::generate_identity_secret,
+        transport::PtStream,
+        P2p, P2pPtr, Settings,
+    },
+    util::{encoding::base64, pcg::Pcg32},
+    Error, Result,
+};
+
+const SEEDS: [u64; 4] = [0, 1, 0x5eed, 0xdeadbeef];
+
+/// Upper bound of the delay injected before starting a handshake end
+const MAX_DELAY_MS: u64 = 50;
+
+fn settings(node_id: &str) -> Settings {
+    Settings {
+        localnet: true,
+        node_id: node_id.to_string(),
+        channel_handshake_timeout: 1,
+        allowed_transports: vec!["unix".to_string()],
+        ..Default::default()
+    }
+}
+
+fn auth_settings(node_id: &str) -> Settings {
+    Settings { identity_secret: Some(generate_identity_secret()), ..settings(node_id) }
+}
+
+fn addr(name: &str) -> Url {
+    Url::parse(&format!("unix:///tmp/darkfi-test-{name}.sock")).unwrap()
+}
+
+/// Run `f` for every seed on a single threaded executor
+fn run_seeded<F, Fut>(f: F)
+where
+    F: Fn(u64, Arc<Executor<'static>>) -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    for seed in SEEDS {
+        let ex = Arc::new(Executor::new());
+        smol::block_on(ex.run(f(seed, ex.clone())));
+    }
+}
+
+/// A unix stream reporting a fixed channel binding, standing in for
+/// the TLS session a real transport would export it from
+struct BoundStream {
+    inner: UnixStream,
+    binding: [u8; 32],
+}
+
+impl AsyncRead for BoundStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for BoundStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+impl PtStream for BoundStream {
+    fn channel_binding(&self) -> Option<[u8; 32]> {
+        Some(self.binding)
+    }
+}
+
+/// One end of a connection: the node, the session it belongs to and
+/// its channel over a unix socket pair.
+struct End {
+    p2p: P2pPtr,
+    session: Arc<dyn Session + Send + Sync>,
+    channel: ChannelPtr,
+}
+
+/// Connect `dialer` to `listener` over a fresh socket pair. The dialer
+/// side is a manual session, the listener side an inbound one.
+async fn connect(
+    dialer: &P2pPtr,
+    dialer_addr: &Url,
+    listener: &P2pPtr,
+    listener_addr: &Url,
+) -> (End, End, UnixStream, UnixStream) {
+    let (stream_a, stream_b) = UnixStream::pair().unwrap();
+    let (end_a, end_b) = connect_streams(
+        dialer,
+        dialer_addr,
+        listener,
+        listener_addr,
+        Box::new(stream_a.clone()),
+        Box::new(stream_b.clone()),
+    )
+    .await;
+
+    (end_a, end_b, stream_a, stream_b)
+}
+
+/// Set up both ends of a connection over the given streams
+async fn connect_streams(
+    dialer: &P2pPtr,
+    dialer_addr: &Url,
+    listener: &P2pPtr,
+    listener_addr: &Url,
+    stream_a: Box<dyn PtStream>,
+    stream_b: Box<dyn PtStream>,
+) -> (End, End) {
+    let session_a: Arc<dyn Session + Send + Sync> = dialer.session_manual();
+    let weak_a: SessionWeakPtr = Arc::downgrade(&session_a);
+    let channel_a = Channel::new(stream_a, None, listener_addr.clone(), weak_a).await;
+
+    let session_b: Arc<dyn Session + Send + Sync> = listener.session_inbound();
+    let weak_b: SessionWeakPtr = Arc::downgrade(&session_b);
+    let channel_b = Channel::new(stream_b, None, dialer_addr.clone(), weak_b).await;
+
+    (
+        End { p2p: dialer.clone(), session: session_a, channel: channel_a },
+        End { p2p: listener.clone(), session: session_b, channel: channel_b },
+    )
+}
+
+/// Run the handshakes of all `ends` concurrently. They get started in
+/// a seeded order, each after a seeded delay. Results are returned in
+/// the order of `ends`.
+async fn handshake(rng: &mut Pcg32, ex: &Arc<Executor<'static>>, ends: &[&End]) -> Vec<Result<()>> {
+    let mut order: Vec<usize> = (0..ends.len()).collect();
+    order.shuffle(rng);
+
+    let mut tasks = vec![];
+    for &i in &order {
+        let delay = Duration::from_millis(rng.gen_range(0..MAX_DELAY_MS));
+        let session = ends[i].session.clone();
+        let channel = ends[i].channel.clone();
+        let ex_ = ex.clone();
+        let task = ex.spawn(async move {
+            Timer::after(delay).await;
+            session.register_channel(channel, ex_).await
+        });
+        tasks.push((i, task));
+    }
+
+    let mut results: Vec<Option<Result<()>>> = (0..ends.len()).map(|_| None).collect();
+    for (i, task) in tasks {
+        results[i] = Some(task.await);
+    }
+    results.into_iter().map(|r| r.unwrap()).collect()
+}
+
+/// Invariants which must hold for a node after any scenario: every
+/// registered channel is alive, and no address is registered twice.
+fn check_invariants(seed: u64, p2p: &P2pPtr) {
+    let peers = p2p.hosts().peers();
+    for (i, channel) in peers.iter().enumerate() {
+        assert!(!channel.is_stopped(), "[seed={seed}] stopped channel is registered");
+        assert!(
+            peers[i + 1..].iter().all(|c| c.address() != channel.address()),
+            "[seed={seed}] {} is registered twice",
+            channel.address()
+        );
+    }
+}
+
+#[test]
+fn handshake_succeeds() {
+    run_seeded(|seed, ex| async move {
+        let mut rng = Pcg32::new(seed);
+        let a = P2p::new(settings("a"), ex.clone()).await.unwrap();
+        let b = P2p::new(settings("b"), ex.clone()).await.unwrap();
+
+        let (end_a, end_b, _sa, _sb) = connect(&a, &addr("a"), &b, &addr("b")).await;
+        let results = handshake(&mut rng, &ex, &[&end_a, &end_b]).await;
+        assert!(results.iter().all(|r| r.is_ok()), "[seed={seed}] {results:?}");
+
+        assert!(end_a.p2p.hosts().peers().iter().any(|c| c.address() == &addr("b")));
+        assert!(end_b.p2p.hosts().peers().iter().any(|c| c.address() == &addr("a")));
+        assert_eq!(end_a.channel.get_version().node_id, "b", "[seed={seed}]");
+        assert_eq!(end_b.channel.get_version().node_id, "a", "[seed={seed}]");
+        check_invariants(seed, &a);
+        check_invariants(seed, &b);
+
+        a.stop().await;
+        b.stop().await;
+    });
+}
+
+#[test]
+fn handshake_version_mismatch() {
+    run_seeded(|seed, ex| async move {
+        let mut rng = Pcg32::new(seed);
+        let a = P2p::new(settings("a"), ex.clone()).await.unwrap();
+        let mut settings_b = settings("b");
+        settings_b.app_version = semver::Version::new(99, 0, 0);
+        let b = P2p::new(settings_b, ex.clone()).await.unwrap();
+
+        let (end_a, end_b, _sa, _sb) = connect(&a, &addr("a"), &b, &addr("b")).await;
+        let results = handshake(&mut rng, &ex, &[&end_a, &end_b]).await;
+
+        // Both ends see the mismatch and drop the connection
+        assert!(results.iter().all(|r| r.is_err()), "[seed={seed}] {results:?}");
+        assert!(end_a.channel.is_stopped(), "[seed={seed}]");
+        assert!(end_b.channel.is_stopped(), "[seed={seed}]");
+        assert!(a.hosts().peers().is_empty(), "[seed={seed}]");
+        assert!(b.hosts().peers().is_empty(), "[seed={seed}]");
+    });
+}
+
+#[test]
+fn handshake_duplicate_connect() {
+    run_seeded(|seed, ex| async move {
+        let mut rng = Pcg32::new(seed);
+        let a = P2p::new(settings("a"), ex.clone()).await.unwrap();
+        let b = P2p::new(settings("b"), ex.clone()).await.unwrap();
+
+        let (first_a, first_b, _s0, _s1) = connect(&a, &addr("a0"), &b, &addr("b")).await;
+        let results = handshake(&mut rng, &ex, &[&first_a, &first_b]).await;
+        assert!(results.iter().all(|r| r.is_ok()), "[seed={seed}] {results:?}");
+
+        // Dial the same address again. The listener sees a new address,
+        // just like an inbound connection from a different port.
+        let (dup_a, dup_b, _s2, _s3) = connect(&a, &addr("a1"), &b, &addr("b")).await;
+        let results = handshake(&mut rng, &ex, &[&dup_a, &dup_b]).await;
+        assert!(
+            matches!(results[0], Err(Error::HostStateBlocked(_, _))),
+            "[seed={seed}] {results:?}"
+        );
+        assert!(dup_a.channel.is_stopped(), "[seed={seed}]");
+
+        // Dropping the duplicate must leave the first connection alone
+        Timer::after(Duration::from_millis(MAX_DELAY_MS)).await;
+        let peers = a.hosts().peers();
+        assert_eq!(peers.len(), 1, "[seed={seed}]");
+        assert!(Arc::ptr_eq(&peers[0], &first_a.channel), "[seed={seed}]");
+        check_invariants(seed, &a);
+        check_invariants(seed, &b);
+
+        a.stop().await;
+        b.stop().await;
+    });
+}
+
+#[test]
+fn handshake_simultaneous_open() {
+    run_seeded(|seed, ex| async move {
+        let mut rng = Pcg32::new(seed);
+        let a = P2p::new(settings("a"), ex.clone()).await.unwrap();
+        let b = P2p::new(settings("b"), ex.clone()).await.unwrap();
+
+        // Both nodes dial each other at the same time
+        let (ab_a, ab_b, _s0, _s1) = connect(&a, &addr("a-out"), &b, &addr("b-in")).await;
+        let (ba_b, ba_a, _s2, _s3) = connect(&b, &addr("b-out"), &a, &addr("a-in")).await;
+        let results = handshake(&mut rng, &ex, &[&ab_a, &ab_b, &ba_b, &ba_a]).await;
+        assert!(results.iter().all(|r| r.is_ok()), "[seed={seed}] {results:?}");
+
+        assert_eq!(a.hosts().peers().len(), 2, "[seed={seed}]");
+        assert_eq!(b.hosts().peers().len(), 2, "[seed={seed}]");
+        check_invariants(seed, &a);
+        check_invariants(seed, &b);
+
+        a.stop().await;
+        b.stop().await;
+    });
+}
+
+#[test]
+fn handshake_slow_reader() {
+    run_seeded(|seed, ex| async move {
+        let mut rng = Pcg32::new(seed);
+        let a = P2p::new(settings("a"), ex.clone()).await.unwrap();
+        let b = P2p::new(settings("b"), ex.clone()).await.unwrap();
+
+        // The listener end is never started, so nothing ever gets read
+        // or answered on its side of the socket.
+        let (end_a, _end_b, _sa, _sb) = connect(&a, &addr("a"), &b, &addr("b")).await;
+        let results = handshake(&mut rng, &ex, &[&end_a]).await;
+        assert!(matches!(results[0], Err(Error::ChannelTimeout)), "[seed={seed}] {results:?}");
+        assert!(end_a.channel.is_stopped(), "[seed={seed}]");
+        assert!(a.hosts().peers().is_empty(), "[seed={seed}]");
+    });
+}
+
+#[test]
+fn handshake_auth() {
+    run_seeded(|seed, ex| async move {
+        let mut rng = Pcg32::new(seed);
+        let a = P2p::new(auth_settings("a"), ex.clone()).await.unwrap();
+        let b = P2p::new(auth_settings("b"), ex.clone()).await.unwrap();
+
+        // Both ends share the same TLS session
+        let (stream_a, stream_b) = UnixStream::pair().unwrap();
+        let (end_a, end_b) = connect_streams(
+            &a,
+            &addr("a"),
+            &b,
+            &addr("b"),
+            Box::new(BoundStream { inner: stream_a, binding: [1; 32] }),
+            Box::new(BoundStream { inner: stream_b, binding: [1; 32] }),
+        )
+        .await;
+        let results = handshake(&mut rng, &ex, &[&end_a, &end_b]).await;
+        assert!(results.iter().all(|r| r.is_ok()), "[seed={seed}] {results:?}");
+
+        let identity_b = base64::encode(end_a.channel.identity.get().unwrap());
+        assert_eq!(identity_b, b.identity().unwrap().public_key(), "[seed={seed}]");
+        let identity_a = base64::encode(end_b.channel.identity.get().unwrap());
+        assert_eq!(identity_a, a.identity().unwrap().public_key(), "[seed={seed}]");
+
+        a.stop().await;
+        b.stop().await;
+    });
+}
+
+#[test]
+fn handshake_auth_relay() {
+    run_seeded(|seed, ex| async move {
+        let mut rng = Pcg32::new(seed);
+        let a = P2p::new(auth_settings("a"), ex.clone()).await.unwrap();
+        let b = P2p::new(auth_settings("b"), ex.clone()).await.unwrap();
+
+        // A man in the middle forwards all bytes between both ends, but
+        // terminates a distinct TLS session with each of them.
+        let (stream_a, relay_a) = UnixStream::pair().unwrap();
+        let (relay_b, stream_b) = UnixStream::pair().unwrap();
+        let (reader, mut writer) = (relay_a.clone(), relay_b.clone());
+        let forward = ex.spawn(async move { smol::io::copy(reader, &mut writer).await });
+        let (reader, mut writer) = (relay_b, relay_a);
+        let backward = ex.spawn(async move { smol::io::copy(reader, &mut writer).await });
+
+        let (end_a, end_b) = connect_streams(
+            &a,
+            &addr("a"),
+            &b,
+            &addr("b"),
+            Box::new(BoundStream { inner: stream_a, binding: [1; 32] }),
+            Box::new(BoundStream { inner: stream_b, binding: [2; 32] }),
+        )
+        .await;
+        let results = handshake(&mut rng, &ex, &[&end_a, &end_b]).await;
+
+        // The relayed responses don't verify, so no end accepts the other
+        assert!(results.iter().all(|r| r.is_err()), "[seed={seed}] {results:?}");
+        assert!(end_a.channel.identity.get().is_none(), "[seed={seed}]");
+        assert!(end_b.channel.identity.get().is_none(), "[seed={seed}]");
+        assert!(a.hosts().peers().is_empty(), "[seed={seed}]");
+        assert!(b.hosts().peers().is_empty(), "[seed={seed}]");
+
+        drop(forward);
+        drop(backward);
+        a.stop().await;
+        b.stop().await;
+    });
+}
+
+#[test]
+fn handshake_auth_unbound() {
+    run_seeded(|seed, ex| async move {
+        let mut rng = Pcg32::new(seed);
+        let a = P2p::new(auth_settings("a"), ex.clone()).await.unwrap();
+        let b = P2p::new(auth_settings("b"), ex.clone()).await.unwrap();
+
+        // Plain unix sockets have no TLS session to bind to
+        let (end_a, end_b, _sa, _sb) = connect(&a, &addr("a"), &b, &addr("b")).await;
+        let results = handshake(&mut rng, &ex, &[&end_a, &end_b]).await;
+        assert!(results.iter().all(|r| r.is_err()), "[seed={seed}] {results:?}");
+        assert!(end_a.channel.identity.get().is_none(), "[seed={seed}]");
+        assert!(end_b.channel.identity.get().is_none(), "[seed={seed}]");
+        assert!(a.hosts().peers().is_empty(), "[seed={seed}]");
+        assert!(b.hosts().peers().is_empty(), "[seed={seed}]");
+    });
+}
+
+/// States in the order used by `TRANSITIONS`
+const STATE_NAMES: [&str; 7] =
+    ["Insert", "Refine", "Connect", "Suspend", "Connected", "Move", "Free"];
+
+/// Allowed host state transitions, `TRANSITIONS[from][to]`
+const TRANSITIONS: [[bool; 7]; 7] = [
+    // Insert Refine Connect Suspend Connected Move  Free
+    [false, false, false, false, false, false, true], // Insert
+    [false, false, false, false, true, true, true],   // Refine
+    [false, false, false, false, true, true, true],   // Connect
+    [false, true, false, false, false, false, true],  // Suspend
+    [false, false, false, false, false, true, true],  // Connected
+    [false, false, false, true, true, false, true],   // Move
+    [true, true, true, false, true, true, true],      // Free
+];
+
+fn host_state(i: usize, channel: &ChannelPtr) -> HostState {
+    match i {
+        0 => HostState::Insert,
+        1 => HostState::Refine,
+        2 => HostState::Connect,
+        3 => HostState::Suspend,
+        4 => HostState::Connected(channel.clone()),
+        5 => HostState::Move,
+        6 => HostState::Free(UNIX_EPOCH.elapsed().unwrap().as_secs()),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn host_state_transitions() {
+    run_seeded(|seed, ex| async move {
+        let mut rng = Pcg32::new(seed);
+        let a = P2p::new(settings("a"), ex.clone()).await.unwrap();
+        let b = P2p::new(settings("b"), ex.clone()).await.unwrap();
+        let (end_a, _end_b, _sa, _sb) = connect(&a, &addr("a"), &b, &addr("b")).await;
+        let hosts = a.hosts();
+
+        // Every transition out of every state. Unknown hosts accept any
+        // state, which is how each host gets into its starting state.
+        for from in 0..STATE_NAMES.len() {
+            for to in 0..STATE_NAMES.len() {
+                let host = addr(&format!("table-{from}-{to}"));
+                hosts.try_register(host.clone(), host_state(from, &end_a.channel)).unwrap();
+                let res = hosts.try_register(host, host_state(to, &end_a.channel));
+                assert_eq!(
+                    res.is_ok(),
+                    TRANSITIONS[from][to],
+                    "{} -> {}",
+                    STATE_NAMES[from],
+                    STATE_NAMES[to]
+                );
+            }
+        }
+
+        // Seeded random walk over a few hosts, checked against the table
+        let walk: Vec<Url> = (0..4).map(|i| addr(&format!("walk-{i}"))).collect();
+        let mut model = vec![6; walk.len()];
+        for host in &walk {
+            hosts.try_register(host.clone(), host_state(6, &end_a.channel)).unwrap();
+        }
+        for step in 0..1000 {
+            let i = rng.gen_range(0..walk.len());
+            let to = rng.gen_range(0..STATE_NAMES.len());
+            let res = hosts.try_register(walk[i].clone(), host_state(to, &end_a.channel));
+            assert_eq!(
+                res.is_ok(),
+                TRANSITIONS[model[i]][to],
+                "[seed={seed}, step={step}] {} -> {}",
+                STATE_NAMES[model[i]],
+                STATE_NAMES[to]
+            );
+            if res.is_ok() {
+                model[i] = to;
+            }
+        }
+    });
+}
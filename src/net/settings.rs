@@ -19,6 +19,8 @@
 use structopt::StructOpt;
 use url::Url;
 
+use super::session::{SessionBitFlag, SESSION_INBOUND, SESSION_MANUAL, SESSION_OUTBOUND};
+
 type BlacklistEntry = (String, Vec<String>, Vec<u16>);
 
 /// Ban policies definitions.
@@ -39,6 +41,58 @@ pub enum BanPolicy {
     Relaxed,
 }
 
+/// Role of the node on the network, used to pick the number of
+/// outbound connections when `adaptive_outbound` is enabled.
+///
+/// Wallets only need a couple of peers to broadcast transactions and
+/// follow the chain, while validators want as many as they can get so
+/// their proposals propagate fast.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeRole {
+    Wallet,
+
+    Light,
+
+    #[default]
+    Full,
+
+    Validator,
+}
+
+/// Keepalive parameters used by the ping-pong protocol of a channel.
+///
+/// A ping is sent every `ping_interval` seconds and counts as missed
+/// when its pong doesn't arrive within `pong_timeout` seconds. The
+/// channel is stopped once `max_missed_pings` pings in a row went
+/// unanswered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct Keepalive {
+    pub ping_interval: u64,
+    pub pong_timeout: u64,
+    pub max_missed_pings: u32,
+}
+
+/// Keepalive overrides for a single session type, as found in the
+/// `keepalive_inbound`, `keepalive_outbound` and `keepalive_manual`
+/// TOML tables. Unset fields fall back to the `channel_*` settings.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+pub struct KeepaliveOpt {
+    pub ping_interval: Option<u64>,
+    pub pong_timeout: Option<u64>,
+    pub max_missed_pings: Option<u32>,
+}
+
+impl KeepaliveOpt {
+    fn resolve(self, base: Keepalive) -> Keepalive {
+        Keepalive {
+            ping_interval: self.ping_interval.unwrap_or(base.ping_interval),
+            pong_timeout: self.pong_timeout.unwrap_or(base.pong_timeout),
+            max_missed_pings: self.max_missed_pings.unwrap_or(base.max_missed_pings),
+        }
+    }
+}
+
 /// P2P network settings. The scope of this is a P2P network instance
 /// configured by the library user.
 #[derive(Debug, Clone)]
@@ -48,13 +102,18 @@ pub struct Settings {
     /// P2P accept addresses the instance listens on for inbound connections
     pub inbound_addrs: Vec<Url>,
     /// P2P external addresses the instance advertises so other peers can
-    /// reach us and connect to us, as long as inbound addrs are configured
+    /// reach us and connect to us, as long as inbound addrs are configured.
+    /// An unspecified host (`0.0.0.0` or `[::]`) gets replaced by the
+    /// external address reported by our peers once enough of them agree.
     pub external_addrs: Vec<Url>,
     /// Peer nodes to manually connect to
     pub peers: Vec<Url>,
     /// Seed nodes to connect to for peer discovery and/or advertising our
     /// own external addresses
     pub seeds: Vec<Url>,
+    /// Known peers to exchange addresses with when none of the seeds
+    /// is reachable, tried along with the peers of our hostlist
+    pub bootstrap_peers: Vec<Url>,
     /// Magic bytes should be unique per P2P network.
     /// Avoid bleeding of networks.
     pub magic_bytes: MagicBytes,
@@ -82,6 +141,8 @@ pub struct Settings {
     pub nym_socks5_proxy: Option<Url>,
     /// I2p Socks5 proxy to connect to i2p eepsite (hidden services)
     pub i2p_socks5_proxy: Url,
+    /// I2p SAM bridge used to create the destination for `i2p` inbound addresses
+    pub i2p_sam_address: Url,
     /// Outbound connection slots number, this many connections will be
     /// attempted. (This does not include manual connections)
     pub outbound_connections: usize,
@@ -94,6 +155,18 @@ pub struct Settings {
     pub channel_handshake_timeout: u64,
     /// Ping-pong exchange execution interval (in seconds)
     pub channel_heartbeat_interval: u64,
+    /// Time to wait for a pong reply before the ping counts as missed
+    /// (in seconds)
+    pub channel_pong_timeout: u64,
+    /// Number of consecutive missed pings after which the channel is
+    /// considered dead and gets stopped
+    pub channel_max_missed_pings: u32,
+    /// Keepalive overrides for inbound channels
+    pub keepalive_inbound: Option<Keepalive>,
+    /// Keepalive overrides for outbound channels
+    pub keepalive_outbound: Option<Keepalive>,
+    /// Keepalive overrides for manual channels
+    pub keepalive_manual: Option<Keepalive>,
     /// Allow localnet hosts
     pub localnet: bool,
     /// Cooling off time for peer discovery when unsuccessful
@@ -125,6 +198,71 @@ pub struct Settings {
     /// Do not ban nodes that send messages without dispatchers if set
     /// to `Relaxed`. For most uses, should be set to `Strict`.
     pub ban_policy: BanPolicy,
+    /// Role of this node, see [`NodeRole`]
+    pub node_role: NodeRole,
+    /// If this is true, the number of active outbound slots is adjusted
+    /// at runtime based on `node_role`, the sync state and the measured
+    /// bandwidth. `outbound_connections` becomes the upper bound.
+    pub adaptive_outbound: bool,
+    /// Outbound bandwidth budget in bytes per second used by the
+    /// adaptive outbound target. 0 means unlimited.
+    pub outbound_bandwidth_limit: u64,
+    /// Number of seconds between adaptive outbound target updates
+    pub outbound_adapt_interval: u64,
+    /// Send budget of a single channel in bytes per second.
+    /// 0 means unlimited.
+    pub channel_send_limit: u64,
+    /// Receive budget of a single channel in bytes per second.
+    /// 0 means unlimited.
+    pub channel_recv_limit: u64,
+    /// Send budget shared by all channels in bytes per second.
+    /// 0 means unlimited.
+    pub global_send_limit: u64,
+    /// Receive budget shared by all channels in bytes per second.
+    /// 0 means unlimited.
+    pub global_recv_limit: u64,
+    /// Score penalty for sending invalid or unexpected messages
+    pub score_invalid_message: u32,
+    /// Score penalty for failing to serve sync requests
+    pub score_failed_sync: u32,
+    /// Score penalty for exceeding the message rate limits
+    pub score_excessive_bandwidth: u32,
+    /// Score penalty for missing or wrong ping replies
+    pub score_stale_ping: u32,
+    /// Score at which a peer gets disconnected and downgraded
+    pub score_demote_threshold: u32,
+    /// Score at which a peer gets banned, following `ban_policy`
+    pub score_ban_threshold: u32,
+    /// Number of seconds it takes for a peer score to drop by one point
+    pub score_decay_interval: u64,
+    /// Map the inbound ports on the local gateway using NAT-PMP or UPnP,
+    /// and advertise the external address of the gateway
+    pub nat_traversal: bool,
+    /// Lifetime of the gateway port mappings in seconds.
+    /// Mappings get refreshed at half their lifetime.
+    pub nat_lease_time: u64,
+    /// Announce our inbound addresses on the local network and learn
+    /// the addresses of other nodes of the network through multicast.
+    /// Requires `localnet` for the LAN addresses to be accepted.
+    pub lan_discovery: bool,
+    /// Number of seconds between LAN discovery announcements
+    pub lan_discovery_interval: u64,
+    /// Run a peer DHT and ask it for new peers before going to the
+    /// seeds. Only has an effect when built with the `dht` feature.
+    pub peer_dht: bool,
+    /// Compress large messages towards peers supporting it
+    pub compression: bool,
+    /// Minimum payload size in bytes for a message to get compressed.
+    /// Smaller messages are not worth the effort.
+    pub compression_threshold: usize,
+    /// Base64 encoded Ed25519 seed of the static identity of this node.
+    /// When set, peers have to prove ownership of their identity key
+    /// during the handshake. The proof is bound to the TLS session of
+    /// the connection, so only TLS transports can be used.
+    pub identity_secret: Option<String>,
+    /// Base64 encoded identity public keys of peers we accept
+    /// connections with. Empty means any authenticated peer.
+    pub allowed_peers: Vec<String>,
 }
 
 impl Default for Settings {
@@ -139,17 +277,24 @@ impl Default for Settings {
             magic_bytes: Default::default(),
             peers: vec![],
             seeds: vec![],
+            bootstrap_peers: vec![],
             app_version,
             allowed_transports: vec!["tcp+tls".to_string()],
             mixed_transports: vec![],
             tor_socks5_proxy: None,
             nym_socks5_proxy: None,
             i2p_socks5_proxy: Url::parse("socks5://127.0.0.1:4447").unwrap(),
+            i2p_sam_address: Url::parse("tcp://127.0.0.1:7656").unwrap(),
             outbound_connections: 8,
             inbound_connections: 8,
             outbound_connect_timeout: 15,
             channel_handshake_timeout: 10,
             channel_heartbeat_interval: 30,
+            channel_pong_timeout: 15,
+            channel_max_missed_pings: 1,
+            keepalive_inbound: None,
+            keepalive_outbound: None,
+            keepalive_manual: None,
             localnet: false,
             outbound_peer_discovery_cooloff_time: 30,
             outbound_peer_discovery_attempt_time: 5,
@@ -162,10 +307,53 @@ impl Default for Settings {
             time_with_no_connections: 30,
             blacklist: vec![],
             ban_policy: BanPolicy::Strict,
+            node_role: NodeRole::Full,
+            adaptive_outbound: false,
+            outbound_bandwidth_limit: 0,
+            outbound_adapt_interval: 30,
+            channel_send_limit: 0,
+            channel_recv_limit: 0,
+            global_send_limit: 0,
+            global_recv_limit: 0,
+            score_invalid_message: 100,
+            score_failed_sync: 10,
+            score_excessive_bandwidth: 50,
+            score_stale_ping: 10,
+            score_demote_threshold: 50,
+            score_ban_threshold: 100,
+            score_decay_interval: 60,
+            nat_traversal: false,
+            nat_lease_time: 3600,
+            lan_discovery: false,
+            lan_discovery_interval: 10,
+            peer_dht: false,
+            compression: false,
+            compression_threshold: 4096,
+            identity_secret: None,
+            allowed_peers: vec![],
         }
     }
 }
 
+impl Settings {
+    /// Keepalive parameters for channels of the given session type.
+    /// Sessions without overrides use the `channel_*` settings.
+    pub fn keepalive(&self, type_id: SessionBitFlag) -> Keepalive {
+        let keepalive = match type_id {
+            SESSION_INBOUND => self.keepalive_inbound,
+            SESSION_OUTBOUND => self.keepalive_outbound,
+            SESSION_MANUAL => self.keepalive_manual,
+            _ => None,
+        };
+
+        keepalive.unwrap_or(Keepalive {
+            ping_interval: self.channel_heartbeat_interval,
+            pong_timeout: self.channel_pong_timeout,
+            max_missed_pings: self.channel_max_missed_pings,
+        })
+    }
+}
+
 // The following is used so we can have P2P settings configurable
 // from TOML files.
 
@@ -204,7 +392,8 @@ pub struct SettingsOpt {
 
     /// P2P external addresses node advertises so other peers can
     /// reach us and connect to us, as long as inbound addresses
-    /// are also configured
+    /// are also configured. Use `0.0.0.0` or `[::]` as the host to
+    /// advertise the address discovered through our peers.
     #[serde(default)]
     #[structopt(long)]
     pub external_addrs: Vec<Url>,
@@ -220,6 +409,11 @@ pub struct SettingsOpt {
     #[structopt(long)]
     pub seeds: Vec<Url>,
 
+    /// Known peers to exchange addresses with when no seed is reachable
+    #[serde(default)]
+    #[structopt(long = "bootstrap-peer")]
+    pub bootstrap_peers: Vec<Url>,
+
     /// Connection establishment timeout in seconds
     #[structopt(skip)]
     pub outbound_connect_timeout: Option<u64>,
@@ -232,6 +426,30 @@ pub struct SettingsOpt {
     #[structopt(skip)]
     pub channel_heartbeat_interval: Option<u64>,
 
+    /// Time to wait for a pong reply in seconds.
+    /// Defaults to the outbound connection timeout.
+    #[structopt(skip)]
+    pub channel_pong_timeout: Option<u64>,
+
+    /// Consecutive missed pings after which a channel is stopped
+    #[structopt(skip)]
+    pub channel_max_missed_pings: Option<u32>,
+
+    /// Keepalive overrides for inbound channels
+    #[serde(default)]
+    #[structopt(skip)]
+    pub keepalive_inbound: Option<KeepaliveOpt>,
+
+    /// Keepalive overrides for outbound channels
+    #[serde(default)]
+    #[structopt(skip)]
+    pub keepalive_outbound: Option<KeepaliveOpt>,
+
+    /// Keepalive overrides for manual channels
+    #[serde(default)]
+    #[structopt(skip)]
+    pub keepalive_manual: Option<KeepaliveOpt>,
+
     /// Only used for debugging. Compromises privacy when set.
     #[serde(default)]
     #[structopt(skip)]
@@ -271,6 +489,10 @@ pub struct SettingsOpt {
     #[structopt(long)]
     pub i2p_socks5_proxy: Option<Url>,
 
+    /// I2p SAM bridge used to create the destination for `i2p` inbound addresses
+    #[structopt(long)]
+    pub i2p_sam_address: Option<Url>,
+
     /// If this is true, strictly follow the gold_connect_count and
     /// white_connect_percent settings. Otherwise, connect to greylist
     /// entries if we have no white or gold connections.
@@ -331,12 +553,126 @@ pub struct SettingsOpt {
     #[serde(default)]
     #[structopt(skip)]
     pub ban_policy: BanPolicy,
+
+    /// Role of this node: `wallet`, `light`, `full` or `validator`
+    #[serde(default)]
+    #[structopt(skip)]
+    pub node_role: NodeRole,
+
+    /// Adjust the number of active outbound slots at runtime
+    #[serde(default)]
+    #[structopt(long)]
+    pub adaptive_outbound: bool,
+
+    /// Outbound bandwidth budget in bytes per second (0 for unlimited)
+    #[structopt(skip)]
+    pub outbound_bandwidth_limit: Option<u64>,
+
+    /// Number of seconds between adaptive outbound target updates
+    #[structopt(skip)]
+    pub outbound_adapt_interval: Option<u64>,
+
+    /// Per-channel send budget in bytes per second (0 for unlimited)
+    #[structopt(skip)]
+    pub channel_send_limit: Option<u64>,
+
+    /// Per-channel receive budget in bytes per second (0 for unlimited)
+    #[structopt(skip)]
+    pub channel_recv_limit: Option<u64>,
+
+    /// Global send budget in bytes per second (0 for unlimited)
+    #[structopt(skip)]
+    pub global_send_limit: Option<u64>,
+
+    /// Global receive budget in bytes per second (0 for unlimited)
+    #[structopt(skip)]
+    pub global_recv_limit: Option<u64>,
+
+    /// Score penalty for sending invalid or unexpected messages
+    #[structopt(skip)]
+    pub score_invalid_message: Option<u32>,
+
+    /// Score penalty for failing to serve sync requests
+    #[structopt(skip)]
+    pub score_failed_sync: Option<u32>,
+
+    /// Score penalty for exceeding the message rate limits
+    #[structopt(skip)]
+    pub score_excessive_bandwidth: Option<u32>,
+
+    /// Score penalty for missing or wrong ping replies
+    #[structopt(skip)]
+    pub score_stale_ping: Option<u32>,
+
+    /// Score at which a peer gets disconnected and downgraded
+    #[structopt(skip)]
+    pub score_demote_threshold: Option<u32>,
+
+    /// Score at which a peer gets banned
+    #[structopt(skip)]
+    pub score_ban_threshold: Option<u32>,
+
+    /// Number of seconds it takes for a peer score to drop by one point
+    #[structopt(skip)]
+    pub score_decay_interval: Option<u64>,
+
+    /// Map the inbound ports on the local gateway using NAT-PMP or UPnP
+    #[serde(default)]
+    #[structopt(long)]
+    pub nat_traversal: bool,
+
+    /// Lifetime of the gateway port mappings in seconds
+    #[structopt(skip)]
+    pub nat_lease_time: Option<u64>,
+
+    /// Discover other nodes of the network on the local network
+    #[serde(default)]
+    #[structopt(long)]
+    pub lan_discovery: bool,
+
+    /// Number of seconds between LAN discovery announcements
+    #[structopt(skip)]
+    pub lan_discovery_interval: Option<u64>,
+
+    /// Discover peers through a DHT before asking the seeds
+    #[serde(default)]
+    #[structopt(long)]
+    pub peer_dht: bool,
+
+    /// Compress large messages towards peers supporting it
+    #[serde(default)]
+    #[structopt(long)]
+    pub compression: bool,
+
+    /// Minimum payload size in bytes for a message to get compressed
+    #[structopt(skip)]
+    pub compression_threshold: Option<usize>,
+
+    /// Base64 encoded Ed25519 seed of the static identity of this node
+    #[structopt(skip)]
+    pub identity_secret: Option<String>,
+
+    /// Base64 encoded identity public keys of peers we accept connections with
+    #[serde(default)]
+    #[structopt(long = "allowed-peer")]
+    pub allowed_peers: Vec<String>,
 }
 
 impl From<SettingsOpt> for Settings {
     fn from(opt: SettingsOpt) -> Self {
         let def = Settings::default();
 
+        let outbound_connect_timeout =
+            opt.outbound_connect_timeout.unwrap_or(def.outbound_connect_timeout);
+
+        // Pong timeout used to be the connect timeout, so keep following
+        // it for configs tuned for slow transports.
+        let keepalive = Keepalive {
+            ping_interval: opt.channel_heartbeat_interval.unwrap_or(def.channel_heartbeat_interval),
+            pong_timeout: opt.channel_pong_timeout.unwrap_or(outbound_connect_timeout),
+            max_missed_pings: opt.channel_max_missed_pings.unwrap_or(def.channel_max_missed_pings),
+        };
+
         Self {
             node_id: opt.node_id,
             inbound_addrs: opt.inbound,
@@ -344,23 +680,26 @@ impl From<SettingsOpt> for Settings {
             magic_bytes: opt.magic_bytes,
             peers: opt.peers,
             seeds: opt.seeds,
+            bootstrap_peers: opt.bootstrap_peers,
             app_version: def.app_version,
             allowed_transports: opt.allowed_transports.unwrap_or(def.allowed_transports),
             mixed_transports: opt.mixed_transports.unwrap_or(def.mixed_transports),
             tor_socks5_proxy: opt.tor_socks5_proxy,
             nym_socks5_proxy: opt.nym_socks5_proxy,
             i2p_socks5_proxy: opt.i2p_socks5_proxy.unwrap_or(def.i2p_socks5_proxy),
+            i2p_sam_address: opt.i2p_sam_address.unwrap_or(def.i2p_sam_address),
             outbound_connections: opt.outbound_connections.unwrap_or(def.outbound_connections),
             inbound_connections: opt.inbound_connections.unwrap_or(def.inbound_connections),
-            outbound_connect_timeout: opt
-                .outbound_connect_timeout
-                .unwrap_or(def.outbound_connect_timeout),
+            outbound_connect_timeout,
             channel_handshake_timeout: opt
                 .channel_handshake_timeout
                 .unwrap_or(def.channel_handshake_timeout),
-            channel_heartbeat_interval: opt
-                .channel_heartbeat_interval
-                .unwrap_or(def.channel_heartbeat_interval),
+            channel_heartbeat_interval: keepalive.ping_interval,
+            channel_pong_timeout: keepalive.pong_timeout,
+            channel_max_missed_pings: keepalive.max_missed_pings,
+            keepalive_inbound: opt.keepalive_inbound.map(|k| k.resolve(keepalive)),
+            keepalive_outbound: opt.keepalive_outbound.map(|k| k.resolve(keepalive)),
+            keepalive_manual: opt.keepalive_manual.map(|k| k.resolve(keepalive)),
             localnet: opt.localnet,
             outbound_peer_discovery_cooloff_time: opt
                 .outbound_peer_discovery_cooloff_time
@@ -381,6 +720,40 @@ impl From<SettingsOpt> for Settings {
                 .unwrap_or(def.time_with_no_connections),
             blacklist: opt.blacklist,
             ban_policy: opt.ban_policy,
+            node_role: opt.node_role,
+            adaptive_outbound: opt.adaptive_outbound,
+            outbound_bandwidth_limit: opt
+                .outbound_bandwidth_limit
+                .unwrap_or(def.outbound_bandwidth_limit),
+            outbound_adapt_interval: opt
+                .outbound_adapt_interval
+                .unwrap_or(def.outbound_adapt_interval),
+            channel_send_limit: opt.channel_send_limit.unwrap_or(def.channel_send_limit),
+            channel_recv_limit: opt.channel_recv_limit.unwrap_or(def.channel_recv_limit),
+            global_send_limit: opt.global_send_limit.unwrap_or(def.global_send_limit),
+            global_recv_limit: opt.global_recv_limit.unwrap_or(def.global_recv_limit),
+            score_invalid_message: opt.score_invalid_message.unwrap_or(def.score_invalid_message),
+            score_failed_sync: opt.score_failed_sync.unwrap_or(def.score_failed_sync),
+            score_excessive_bandwidth: opt
+                .score_excessive_bandwidth
+                .unwrap_or(def.score_excessive_bandwidth),
+            score_stale_ping: opt.score_stale_ping.unwrap_or(def.score_stale_ping),
+            score_demote_threshold: opt
+                .score_demote_threshold
+                .unwrap_or(def.score_demote_threshold),
+            score_ban_threshold: opt.score_ban_threshold.unwrap_or(def.score_ban_threshold),
+            score_decay_interval: opt.score_decay_interval.unwrap_or(def.score_decay_interval),
+            nat_traversal: opt.nat_traversal,
+            nat_lease_time: opt.nat_lease_time.unwrap_or(def.nat_lease_time),
+            lan_discovery: opt.lan_discovery,
+            lan_discovery_interval: opt
+                .lan_discovery_interval
+                .unwrap_or(def.lan_discovery_interval),
+            peer_dht: opt.peer_dht,
+            compression: opt.compression,
+            compression_threshold: opt.compression_threshold.unwrap_or(def.compression_threshold),
+            identity_secret: opt.identity_secret,
+            allowed_peers: opt.allowed_peers,
         }
     }
 }
@@ -0,0 +1,218 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex as SyncMutex,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use smol::io::{AsyncRead, AsyncWrite};
+
+use super::transport::PtStream;
+
+/// Token bucket holding a byte budget which refills at a constant rate.
+///
+/// The bucket is allowed to go into debt, so messages larger than the
+/// burst size still get through. The caller is then told how long to wait
+/// until the debt is paid off.
+pub struct TokenBucket {
+    /// Refill rate in bytes per second, 0 means unlimited
+    rate: u64,
+    /// Available bytes, negative when in debt
+    tokens: f64,
+    /// Last time the bucket got refilled
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Create a new bucket. The burst size equals one second of traffic.
+    pub fn new(rate: u64) -> Self {
+        Self { rate, tokens: rate as f64, last_refill: Instant::now() }
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.rate == 0
+    }
+
+    /// Take `bytes` from the bucket and return how long the caller
+    /// should wait before the traffic fits into the budget.
+    pub fn consume(&mut self, bytes: u64) -> Duration {
+        self.consume_at(bytes, Instant::now())
+    }
+
+    fn consume_at(&mut self, bytes: u64, now: Instant) -> Duration {
+        if self.is_unlimited() {
+            return Duration::ZERO
+        }
+
+        let rate = self.rate as f64;
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(rate);
+        self.last_refill = now;
+
+        self.tokens -= bytes as f64;
+        if self.tokens >= 0.0 {
+            return Duration::ZERO
+        }
+
+        Duration::from_secs_f64(-self.tokens / rate)
+    }
+}
+
+/// Send and receive budgets along with traffic counters.
+/// Used both per channel and globally for the whole P2P instance.
+pub struct Throttle {
+    send: SyncMutex<TokenBucket>,
+    recv: SyncMutex<TokenBucket>,
+    bytes_sent: AtomicU64,
+    bytes_recv: AtomicU64,
+    /// Total time traffic was held back by the budgets, in milliseconds
+    throttled_ms: AtomicU64,
+}
+
+impl Throttle {
+    /// Create a new throttle with the given budgets in bytes per second.
+    /// Use 0 for unlimited.
+    pub fn new(send_limit: u64, recv_limit: u64) -> Self {
+        Self {
+            send: SyncMutex::new(TokenBucket::new(send_limit)),
+            recv: SyncMutex::new(TokenBucket::new(recv_limit)),
+            bytes_sent: AtomicU64::new(0),
+            bytes_recv: AtomicU64::new(0),
+            throttled_ms: AtomicU64::new(0),
+        }
+    }
+
+    /// Account for sent bytes and return the time to wait
+    pub fn consume_send(&self, bytes: usize) -> Duration {
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.send.lock().unwrap().consume(bytes as u64)
+    }
+
+    /// Account for received bytes and return the time to wait
+    pub fn consume_recv(&self, bytes: usize) -> Duration {
+        self.bytes_recv.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.recv.lock().unwrap().consume(bytes as u64)
+    }
+
+    /// Account for time spent waiting on the budget
+    pub fn record_throttled(&self, wait: Duration) {
+        self.throttled_ms.fetch_add(wait.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent.load(Ordering::Relaxed)
+    }
+
+    pub fn bytes_recv(&self) -> u64 {
+        self.bytes_recv.load(Ordering::Relaxed)
+    }
+
+    pub fn throttled_ms(&self) -> u64 {
+        self.throttled_ms.load(Ordering::Relaxed)
+    }
+}
+
+/// Stream wrapper counting the bytes read from the inner stream, so
+/// received traffic can be charged to the throttles after each message.
+pub(super) struct CountingStream {
+    inner: Box<dyn PtStream>,
+    read: Arc<AtomicU64>,
+}
+
+impl CountingStream {
+    pub(super) fn new(inner: Box<dyn PtStream>, read: Arc<AtomicU64>) -> Self {
+        Self { inner, read }
+    }
+}
+
+impl AsyncRead for CountingStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(n)) = poll {
+            self.read.fetch_add(n as u64, Ordering::Relaxed);
+        }
+        poll
+    }
+}
+
+impl AsyncWrite for CountingStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+impl PtStream for CountingStream {
+    fn channel_binding(&self) -> Option<[u8; 32]> {
+        self.inner.channel_binding()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_bucket_consume() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(1000);
+        bucket.last_refill = start;
+
+        // The first second worth of traffic passes straight away
+        assert_eq!(bucket.consume_at(600, start), Duration::ZERO);
+        assert_eq!(bucket.consume_at(400, start), Duration::ZERO);
+
+        // Going over the budget means waiting for the debt to refill
+        assert_eq!(bucket.consume_at(500, start), Duration::from_millis(500));
+
+        // Half a second later the debt is paid off
+        let later = start + Duration::from_millis(500);
+        assert_eq!(bucket.consume_at(0, later), Duration::ZERO);
+
+        // Refill never exceeds the burst size
+        let much_later = later + Duration::from_secs(10);
+        assert_eq!(bucket.consume_at(1000, much_later), Duration::ZERO);
+        assert_eq!(bucket.consume_at(250, much_later), Duration::from_millis(250));
+
+        // Unlimited buckets never wait
+        let mut bucket = TokenBucket::new(0);
+        assert_eq!(bucket.consume(u64::MAX), Duration::ZERO);
+    }
+}
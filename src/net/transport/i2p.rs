@@ -0,0 +1,364 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! I2P listener using the SAMv3 bridge.
+//!
+//! Dialing goes through the bridge's SOCKS5 proxy, see [`super::socks5`].
+//! Listening creates a streaming session on the SAM bridge and accepts
+//! incoming streams on it. The session destination private key is kept
+//! in the node datastore, so the `.b32.i2p` address survives restarts.
+//!
+//! Failures talking to the bridge while accepting streams are retried
+//! with an exponential backoff, so a restarting bridge doesn't bring the
+//! listener down.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    io::Write,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use async_trait::async_trait;
+use futures::{AsyncReadExt, AsyncWriteExt};
+use log::{debug, error, info, warn};
+use sha2::{Digest, Sha256};
+use smol::{lock::OnceCell, net::TcpStream};
+use url::Url;
+
+use super::{PtListener, PtStream};
+use crate::{
+    system::msleep,
+    util::{
+        encoding::{base32, base64},
+        path::expand_path,
+    },
+};
+
+/// File in the datastore holding the destination private key
+const I2P_DESTINATION_FILE: &str = "i2p_destination.key";
+
+/// Signature type of generated destinations (EdDSA_SHA512_Ed25519)
+const SIGNATURE_TYPE: u8 = 7;
+
+/// Longest line we accept from the SAM bridge
+const MAX_LINE_LEN: usize = 8192;
+
+/// Initial delay in milliseconds before retrying a failed stream accept
+const ACCEPT_RETRY_MIN_DELAY: u64 = 500;
+
+/// Longest delay in milliseconds between stream accept retries
+const ACCEPT_RETRY_MAX_DELAY: u64 = 60_000;
+
+/// Parsed SAM bridge reply, e.g. `SESSION STATUS RESULT=OK DESTINATION=...`
+struct SamReply {
+    line: String,
+    values: HashMap<String, String>,
+}
+
+impl SamReply {
+    fn parse(line: String) -> Self {
+        let mut values = HashMap::new();
+        for token in line.split_whitespace() {
+            if let Some((key, value)) = token.split_once('=') {
+                values.insert(key.to_string(), value.trim_matches('"').to_string());
+            }
+        }
+        Self { line, values }
+    }
+
+    fn get(&self, key: &str) -> io::Result<&str> {
+        match self.values.get(key) {
+            Some(v) => Ok(v),
+            None => {
+                error!(target: "net::i2p", "[P2P] SAM reply is missing {key}: {}", self.line);
+                Err(io::ErrorKind::InvalidData.into())
+            }
+        }
+    }
+
+    fn ensure_ok(&self) -> io::Result<()> {
+        if self.get("RESULT")? != "OK" {
+            error!(target: "net::i2p", "[P2P] SAM request failed: {}", self.line);
+            return Err(io::Error::new(io::ErrorKind::ConnectionRefused, "SAM request failed"))
+        }
+        Ok(())
+    }
+}
+
+/// Connection to the SAM bridge, after the version handshake
+struct SamConn {
+    stream: TcpStream,
+}
+
+impl SamConn {
+    async fn connect(sam: &Url) -> io::Result<Self> {
+        let (Some(host), Some(port)) = (sam.host_str(), sam.port()) else {
+            return Err(io::ErrorKind::InvalidInput.into())
+        };
+
+        let stream = TcpStream::connect((host, port)).await?;
+        let mut conn = Self { stream };
+        conn.command("HELLO VERSION MIN=3.1 MAX=3.3").await?.ensure_ok()?;
+        Ok(conn)
+    }
+
+    async fn command(&mut self, command: &str) -> io::Result<SamReply> {
+        self.stream.write_all(format!("{command}\n").as_bytes()).await?;
+        Ok(SamReply::parse(self.read_line().await?))
+    }
+
+    /// Read a single line. This goes byte by byte since after some replies
+    /// the connection turns into a data stream we must not read into.
+    async fn read_line(&mut self) -> io::Result<String> {
+        let mut line = vec![];
+        let mut byte = [0u8; 1];
+        loop {
+            self.stream.read_exact(&mut byte).await?;
+            if byte[0] == b'\n' {
+                break
+            }
+            if line.len() >= MAX_LINE_LEN {
+                return Err(io::ErrorKind::InvalidData.into())
+            }
+            line.push(byte[0]);
+        }
+
+        String::from_utf8(line).map_err(|_| io::ErrorKind::InvalidData.into())
+    }
+}
+
+/// Derive the `.b32.i2p` address of a base64 encoded public destination
+fn b32_address(destination: &str) -> io::Result<String> {
+    // I2P uses `-` and `~` in place of `+` and `/`
+    let destination = destination.replace('-', "+").replace('~', "/");
+    let Some(destination) = base64::decode(&destination) else {
+        return Err(io::ErrorKind::InvalidData.into())
+    };
+
+    let hash = Sha256::digest(&destination);
+    Ok(format!("{}.b32.i2p", base32::encode(false, &hash).to_lowercase()))
+}
+
+/// Write the destination private key, only readable by the current user
+fn write_destination_key(path: &Path, key: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut opts = fs::OpenOptions::new();
+    opts.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        opts.mode(0o600);
+    }
+
+    let mut file = opts.open(path)?;
+    file.write_all(key.as_bytes())
+}
+
+/// I2P Listener implementation
+#[derive(Clone, Debug)]
+pub struct I2pListener {
+    sam: Url,
+    datastore: Option<String>,
+    pub endpoint: Arc<OnceCell<Url>>,
+}
+
+impl I2pListener {
+    /// Instantiate a new [`I2pListener`] using the SAM bridge at `sam`
+    pub async fn new(sam: Url, datastore: Option<String>) -> io::Result<Self> {
+        Ok(Self { sam, datastore, endpoint: Arc::new(OnceCell::new()) })
+    }
+
+    /// Internal listen function
+    pub(crate) async fn do_listen(&self, port: u16) -> io::Result<I2pListenerIntern> {
+        let key_path = match &self.datastore {
+            Some(datastore) => match expand_path(datastore) {
+                Ok(path) => Some(path.join(I2P_DESTINATION_FILE)),
+                Err(e) => {
+                    error!(target: "net::i2p::do_listen", "[P2P] Invalid datastore path {datastore}: {e}");
+                    return Err(io::ErrorKind::InvalidInput.into())
+                }
+            },
+            None => None,
+        };
+        let saved_key = key_path.as_ref().and_then(|path| fs::read_to_string(path).ok());
+
+        // Keys saved by older versions might be readable by other users
+        #[cfg(unix)]
+        if let (Some(path), Some(_)) = (&key_path, &saved_key) {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+        }
+
+        // The session lives as long as the control connection stays open
+        let mut control = SamConn::connect(&self.sam).await?;
+        let session_id = format!("darkfi-{}-{port}", std::process::id());
+        let destination = saved_key.as_deref().map(str::trim).unwrap_or("TRANSIENT");
+        let reply = control
+            .command(&format!(
+                "SESSION CREATE STYLE=STREAM ID={session_id} DESTINATION={destination} SIGNATURE_TYPE={SIGNATURE_TYPE}"
+            ))
+            .await?;
+        reply.ensure_ok()?;
+
+        if saved_key.is_none() {
+            if let Some(path) = &key_path {
+                debug!(target: "net::i2p::do_listen", "Saving I2P destination to {}", path.display());
+                write_destination_key(path, reply.get("DESTINATION")?)?;
+            }
+        }
+
+        let reply = control.command("NAMING LOOKUP NAME=ME").await?;
+        reply.ensure_ok()?;
+        let address = b32_address(reply.get("VALUE")?)?;
+
+        info!(
+            target: "net::i2p::do_listen",
+            "[P2P] Established I2P listener on i2p://{address}:{port}",
+        );
+
+        let endpoint = Url::parse(&format!("i2p://{address}:{port}")).unwrap();
+        self.endpoint.set(endpoint).await.expect("fatal endpoint already set for I2pListener");
+
+        Ok(I2pListenerIntern {
+            sam: self.sam.clone(),
+            session_id,
+            port,
+            retry_delay: AtomicU64::new(0),
+            _control: control,
+        })
+    }
+}
+
+/// Internal I2P Listener implementation, used with `PtListener`
+pub struct I2pListenerIntern {
+    sam: Url,
+    session_id: String,
+    port: u16,
+    /// Current delay in milliseconds before retrying a failed accept
+    retry_delay: AtomicU64,
+    _control: SamConn,
+}
+
+impl I2pListenerIntern {
+    /// Wait for a peer on the session, returning its stream and the
+    /// bridge line describing it.
+    async fn accept(&self) -> io::Result<(SamConn, SamReply)> {
+        let mut conn = SamConn::connect(&self.sam).await?;
+        let reply =
+            conn.command(&format!("STREAM ACCEPT ID={} SILENT=false", self.session_id)).await?;
+        reply.ensure_ok()?;
+
+        // Once a peer connects, the bridge sends its destination and ports,
+        // and the connection becomes the stream.
+        let peer = SamReply::parse(conn.read_line().await?);
+        Ok((conn, peer))
+    }
+}
+
+/// Next retry delay after a failed accept, doubling the current one
+fn next_retry_delay(delay: u64) -> u64 {
+    (delay * 2).clamp(ACCEPT_RETRY_MIN_DELAY, ACCEPT_RETRY_MAX_DELAY)
+}
+
+#[async_trait]
+impl PtListener for I2pListenerIntern {
+    async fn next(&self) -> io::Result<(Box<dyn PtStream>, Url)> {
+        let (conn, peer) = match self.accept().await {
+            Ok(v) => {
+                self.retry_delay.store(0, Ordering::SeqCst);
+                v
+            }
+            Err(e) => {
+                // The bridge might be restarting, so back off and let the
+                // acceptor retry instead of treating this as fatal.
+                let delay = next_retry_delay(self.retry_delay.load(Ordering::SeqCst));
+                self.retry_delay.store(delay, Ordering::SeqCst);
+                warn!(
+                    target: "net::i2p::next",
+                    "[P2P] Failed accepting I2P stream: {e}, retrying in {delay}ms",
+                );
+                msleep(delay).await;
+                return Err(io::Error::new(io::ErrorKind::ConnectionAborted, e))
+            }
+        };
+
+        if let Some(to_port) = peer.values.get("TO_PORT") {
+            if to_port != "0" && to_port != &self.port.to_string() {
+                return Err(io::Error::new(io::ErrorKind::ConnectionAborted, "Connection Aborted"))
+            }
+        }
+
+        Ok((Box::new(conn.stream), Url::parse(&format!("i2p://127.0.0.1:{}", self.port)).unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sam_reply_parse() {
+        let reply = SamReply::parse(
+            "SESSION STATUS RESULT=DUPLICATED_ID MESSAGE=\"Duplicate\"".to_string(),
+        );
+        assert_eq!(reply.get("RESULT").unwrap(), "DUPLICATED_ID");
+        assert_eq!(reply.get("MESSAGE").unwrap(), "Duplicate");
+        assert!(reply.ensure_ok().is_err());
+        assert!(reply.get("DESTINATION").is_err());
+    }
+
+    #[test]
+    fn i2p_b32_address() {
+        let address = b32_address("AAAA").unwrap();
+        let expected = base32::encode(false, &Sha256::digest([0u8, 0, 0])).to_lowercase();
+        assert_eq!(address, format!("{expected}.b32.i2p"));
+        assert_eq!(address.len(), 52 + 8);
+
+        assert!(b32_address("not base64!").is_err());
+    }
+
+    #[test]
+    fn i2p_accept_retry_delay() {
+        assert_eq!(next_retry_delay(0), ACCEPT_RETRY_MIN_DELAY);
+        assert_eq!(next_retry_delay(ACCEPT_RETRY_MIN_DELAY), ACCEPT_RETRY_MIN_DELAY * 2);
+        assert_eq!(next_retry_delay(ACCEPT_RETRY_MAX_DELAY), ACCEPT_RETRY_MAX_DELAY);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn i2p_destination_key_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("darkfi-i2p-test-{}", std::process::id()));
+        let path = dir.join(I2P_DESTINATION_FILE);
+        write_destination_key(&path, "secret").unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "secret");
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -40,6 +40,10 @@ pub(crate) mod tcp;
 /// Tor transport
 pub(crate) mod tor;
 
+#[cfg(feature = "p2p-i2p")]
+/// I2P transport
+pub(crate) mod i2p;
+
 #[cfg(feature = "p2p-nym")]
 /// Nym transport
 pub(crate) mod nym;
@@ -48,6 +52,10 @@ pub(crate) mod nym;
 #[cfg(feature = "p2p-unix")]
 pub(crate) mod unix;
 
+#[cfg(feature = "p2p-ws")]
+/// WebSocket transport
+pub(crate) mod ws;
+
 /// Dialer variants
 #[derive(Debug, Clone)]
 pub enum DialerVariant {
@@ -84,6 +92,14 @@ pub enum DialerVariant {
     /// SOCKS5 proxy with TLS
     #[cfg(feature = "p2p-socks5")]
     Socks5Tls(socks5::Socks5Dialer),
+
+    #[cfg(feature = "p2p-ws")]
+    /// WebSocket
+    Ws(ws::WsDialer),
+
+    #[cfg(feature = "p2p-ws")]
+    /// WebSocket with TLS
+    Wss(ws::WsDialer),
 }
 
 /// Listener variants
@@ -99,9 +115,25 @@ pub enum ListenerVariant {
     /// Tor
     Tor(tor::TorListener),
 
+    #[cfg(feature = "p2p-tor")]
+    /// Tor with TLS
+    TorTls(tor::TorListener),
+
+    #[cfg(feature = "p2p-i2p")]
+    /// I2P
+    I2p(i2p::I2pListener),
+
     /// Unix socket
     #[cfg(feature = "p2p-unix")]
     Unix(unix::UnixListener),
+
+    #[cfg(feature = "p2p-ws")]
+    /// WebSocket
+    Ws(tcp::TcpListener),
+
+    #[cfg(feature = "p2p-ws")]
+    /// WebSocket with TLS
+    Wss(tcp::TcpListener),
 }
 
 /// A dialer that is able to transparently operate over arbitrary transports.
@@ -120,6 +152,16 @@ macro_rules! enforce_hostport {
     };
 }
 
+/// `ws` and `wss` have default ports, so those are allowed to be omitted.
+#[cfg(feature = "p2p-ws")]
+macro_rules! enforce_host {
+    ($endpoint:ident) => {
+        if $endpoint.host_str().is_none() || $endpoint.port_or_known_default().is_none() {
+            return Err(io::Error::from_raw_os_error(libc::ENETUNREACH))
+        }
+    };
+}
+
 #[cfg(feature = "p2p-unix")]
 macro_rules! enforce_abspath {
     ($endpoint:ident) => {
@@ -243,6 +285,24 @@ impl Dialer {
                 Ok(Self { endpoint, variant })
             }
 
+            #[cfg(feature = "p2p-ws")]
+            "ws" => {
+                // Build a WebSocket dialer
+                enforce_host!(endpoint);
+                let variant = ws::WsDialer::new().await?;
+                let variant = DialerVariant::Ws(variant);
+                Ok(Self { endpoint, variant })
+            }
+
+            #[cfg(feature = "p2p-ws")]
+            "wss" => {
+                // Build a WebSocket dialer wrapped with TLS
+                enforce_host!(endpoint);
+                let variant = ws::WsDialer::new().await?;
+                let variant = DialerVariant::Wss(variant);
+                Ok(Self { endpoint, variant })
+            }
+
             x => {
                 error!("[P2P] Requested unsupported transport: {x}");
                 Err(io::Error::from_raw_os_error(libc::ENETUNREACH))
@@ -323,6 +383,18 @@ impl Dialer {
                 let stream = tlsupgrade.upgrade_dialer_tls(stream).await?;
                 Ok(Box::new(stream))
             }
+
+            #[cfg(feature = "p2p-ws")]
+            DialerVariant::Ws(dialer) => {
+                let stream = dialer.do_dial(&self.endpoint, timeout).await?;
+                Ok(Box::new(stream))
+            }
+
+            #[cfg(feature = "p2p-ws")]
+            DialerVariant::Wss(dialer) => {
+                let stream = dialer.do_dial_tls(&self.endpoint, timeout).await?;
+                Ok(Box::new(stream))
+            }
         }
     }
 
@@ -343,7 +415,11 @@ pub struct Listener {
 impl Listener {
     /// Instantiate a new [`Listener`] with the given [`Url`] and datastore path.
     /// Must contain a scheme, host string, and a port.
-    pub async fn new(endpoint: Url, datastore: Option<String>) -> io::Result<Self> {
+    pub async fn new(
+        endpoint: Url,
+        datastore: Option<String>,
+        i2p_sam_address: Option<Url>,
+    ) -> io::Result<Self> {
         match endpoint.scheme().to_lowercase().as_str() {
             "tcp" => {
                 // Build a TCP listener
@@ -370,6 +446,30 @@ impl Listener {
                 Ok(Self { endpoint, variant })
             }
 
+            #[cfg(feature = "p2p-tor")]
+            "tor+tls" => {
+                // Build a Tor Hidden Service listener wrapped with TLS
+                enforce_hostport!(endpoint);
+                let variant = tor::TorListener::new(datastore).await?;
+                let variant = ListenerVariant::TorTls(variant);
+                Ok(Self { endpoint, variant })
+            }
+
+            #[cfg(feature = "p2p-i2p")]
+            "i2p" => {
+                // Build an I2P listener on top of the SAM bridge
+                enforce_hostport!(endpoint);
+                let Some(i2p_sam_address) = i2p_sam_address else {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "I2P listeners need a SAM bridge address",
+                    ))
+                };
+                let variant = i2p::I2pListener::new(i2p_sam_address, datastore).await?;
+                let variant = ListenerVariant::I2p(variant);
+                Ok(Self { endpoint, variant })
+            }
+
             #[cfg(feature = "p2p-unix")]
             "unix" => {
                 enforce_abspath!(endpoint);
@@ -378,6 +478,24 @@ impl Listener {
                 Ok(Self { endpoint, variant })
             }
 
+            #[cfg(feature = "p2p-ws")]
+            "ws" => {
+                // Build a WebSocket listener
+                enforce_host!(endpoint);
+                let variant = tcp::TcpListener::new(1024).await?;
+                let variant = ListenerVariant::Ws(variant);
+                Ok(Self { endpoint, variant })
+            }
+
+            #[cfg(feature = "p2p-ws")]
+            "wss" => {
+                // Build a WebSocket listener wrapped with TLS
+                enforce_host!(endpoint);
+                let variant = tcp::TcpListener::new(1024).await?;
+                let variant = ListenerVariant::Wss(variant);
+                Ok(Self { endpoint, variant })
+            }
+
             x => {
                 error!("[P2P] Requested unsupported transport: {x}");
                 Err(io::Error::from_raw_os_error(libc::ENETUNREACH))
@@ -405,6 +523,22 @@ impl Listener {
 
             #[cfg(feature = "p2p-tor")]
             ListenerVariant::Tor(listener) => {
+                let port = self.endpoint.port().unwrap();
+                let l = listener.do_listen("tor", port).await?;
+                Ok(Box::new(l))
+            }
+
+            #[cfg(feature = "p2p-tor")]
+            ListenerVariant::TorTls(listener) => {
+                let port = self.endpoint.port().unwrap();
+                let l = listener.do_listen("tor+tls", port).await?;
+                let tlsupgrade = tls::TlsUpgrade::new().await;
+                let l = tlsupgrade.upgrade_listener_tor_tls(l).await?;
+                Ok(Box::new(l))
+            }
+
+            #[cfg(feature = "p2p-i2p")]
+            ListenerVariant::I2p(listener) => {
                 let port = self.endpoint.port().unwrap();
                 let l = listener.do_listen(port).await?;
                 Ok(Box::new(l))
@@ -419,6 +553,22 @@ impl Listener {
                 let l = listener.do_listen(&path).await?;
                 Ok(Box::new(l))
             }
+
+            #[cfg(feature = "p2p-ws")]
+            ListenerVariant::Ws(listener) => {
+                let sockaddr = self.endpoint.socket_addrs(|| None)?;
+                let l = listener.do_listen(sockaddr[0]).await?;
+                Ok(Box::new(ws::WsListenerIntern::new(l, None)))
+            }
+
+            #[cfg(feature = "p2p-ws")]
+            ListenerVariant::Wss(listener) => {
+                let sockaddr = self.endpoint.socket_addrs(|| None)?;
+                let l = listener.do_listen(sockaddr[0]).await?;
+                let tlsupgrade = tls::TlsUpgrade::new().await;
+                let l = tlsupgrade.upgrade_listener_ws_tls(l).await?;
+                Ok(Box::new(l))
+            }
         }
     }
 
@@ -444,7 +594,25 @@ impl Listener {
                 endpoint
             }
             #[cfg(feature = "p2p-tor")]
-            ListenerVariant::Tor(listener) => listener.endpoint.get().unwrap().clone(),
+            ListenerVariant::Tor(listener) | ListenerVariant::TorTls(listener) => {
+                listener.endpoint.get().unwrap().clone()
+            }
+            #[cfg(feature = "p2p-i2p")]
+            ListenerVariant::I2p(listener) => listener.endpoint.get().unwrap().clone(),
+            #[cfg(feature = "p2p-ws")]
+            ListenerVariant::Ws(listener) | ListenerVariant::Wss(listener) => {
+                let mut endpoint = self.endpoint.clone();
+
+                // The port may be omitted here and fall back to the scheme
+                // default, in which case there is nothing to patch.
+                if self.endpoint.port() == Some(0) {
+                    if let Some(actual_port) = listener.port.get() {
+                        endpoint.set_port(Some(*actual_port)).unwrap();
+                    }
+                }
+
+                endpoint
+            }
             #[allow(unreachable_patterns)]
             _ => self.endpoint.clone(),
         }
@@ -452,21 +620,54 @@ impl Listener {
 }
 
 /// Wrapper trait for async streams
-pub trait PtStream: AsyncRead + AsyncWrite + Unpin + Send {}
+pub trait PtStream: AsyncRead + AsyncWrite + Unpin + Send {
+    /// Keying material unique to this connection, exported from its TLS
+    /// session. Handshakes signing it can't be relayed by a man in the
+    /// middle, since it terminates a distinct TLS session on each side.
+    /// `None` for transports without TLS.
+    fn channel_binding(&self) -> Option<[u8; 32]> {
+        None
+    }
+}
 
 impl PtStream for smol::net::TcpStream {}
 
-impl PtStream for futures_rustls::TlsStream<smol::net::TcpStream> {}
+impl PtStream for futures_rustls::TlsStream<smol::net::TcpStream> {
+    fn channel_binding(&self) -> Option<[u8; 32]> {
+        tls::channel_binding(self)
+    }
+}
 
 #[cfg(feature = "p2p-tor")]
 impl PtStream for arti_client::DataStream {}
 
 #[cfg(feature = "p2p-tor")]
-impl PtStream for futures_rustls::TlsStream<arti_client::DataStream> {}
+impl PtStream for futures_rustls::TlsStream<arti_client::DataStream> {
+    fn channel_binding(&self) -> Option<[u8; 32]> {
+        tls::channel_binding(self)
+    }
+}
 
 #[cfg(feature = "p2p-unix")]
 impl PtStream for smol::net::unix::UnixStream {}
 
+#[cfg(feature = "p2p-ws")]
+impl PtStream for ws::WsStream<smol::net::TcpStream> {}
+
+#[cfg(feature = "p2p-ws")]
+impl PtStream for ws::WsStream<futures_rustls::TlsStream<smol::net::TcpStream>> {
+    fn channel_binding(&self) -> Option<[u8; 32]> {
+        self.get_ref().channel_binding()
+    }
+}
+
+#[cfg(feature = "p2p-ws")]
+impl PtStream for ws::WsStream<Box<dyn PtStream>> {
+    fn channel_binding(&self) -> Option<[u8; 32]> {
+        self.get_ref().channel_binding()
+    }
+}
+
 /// Wrapper trait for async listeners
 #[async_trait]
 pub trait PtListener: Send + Unpin {
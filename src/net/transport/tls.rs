@@ -36,6 +36,30 @@ use x509_parser::{
     prelude::{GeneralName, ParsedExtension, X509Certificate},
 };
 
+/// Label of the keying material exported for [`channel_binding`]
+const CHANNEL_BINDING_LABEL: &[u8] = b"EXPORTER-DarkFi-P2P-channel-binding";
+
+/// Export keying material unique to the TLS session of `stream`,
+/// as described in RFC 5705.
+pub(crate) fn channel_binding<IO>(stream: &TlsStream<IO>) -> Option<[u8; 32]> {
+    let material = match stream {
+        TlsStream::Client(s) => {
+            s.get_ref().1.export_keying_material([0u8; 32], CHANNEL_BINDING_LABEL, None)
+        }
+        TlsStream::Server(s) => {
+            s.get_ref().1.export_keying_material([0u8; 32], CHANNEL_BINDING_LABEL, None)
+        }
+    };
+
+    match material {
+        Ok(m) => Some(m),
+        Err(e) => {
+            error!(target: "net::tls", "[P2P] Failed exporting TLS keying material: {e}");
+            None
+        }
+    }
+}
+
 /// Validate certificate DNSName.
 fn validate_dnsname(cert: &X509Certificate) -> std::result::Result<(), rustls::Error> {
     #[rustfmt::skip]
@@ -312,4 +336,21 @@ impl TlsUpgrade {
     ) -> io::Result<(TlsAcceptor, smol::net::TcpListener)> {
         Ok((TlsAcceptor::from(self.server_config), listener))
     }
+
+    #[cfg(feature = "p2p-tor")]
+    pub async fn upgrade_listener_tor_tls(
+        self,
+        listener: super::tor::TorListenerIntern,
+    ) -> io::Result<(TlsAcceptor, super::tor::TorListenerIntern)> {
+        Ok((TlsAcceptor::from(self.server_config), listener))
+    }
+
+    #[cfg(feature = "p2p-ws")]
+    pub async fn upgrade_listener_ws_tls(
+        self,
+        listener: smol::net::TcpListener,
+    ) -> io::Result<super::ws::WsListenerIntern> {
+        let acceptor = TlsAcceptor::from(self.server_config);
+        Ok(super::ws::WsListenerIntern::new(listener, Some(acceptor)))
+    }
 }
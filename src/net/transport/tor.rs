@@ -36,6 +36,7 @@ use futures::{
     stream::StreamExt,
     Stream,
 };
+use futures_rustls::{TlsAcceptor, TlsStream};
 use log::{debug, error, info, warn};
 use smol::{
     lock::{Mutex as AsyncMutex, OnceCell},
@@ -173,8 +174,9 @@ impl TorListener {
         Ok(Self { datastore, endpoint: Arc::new(OnceCell::new()) })
     }
 
-    /// Internal listen function
-    pub(crate) async fn do_listen(&self, port: u16) -> io::Result<TorListenerIntern> {
+    /// Internal listen function. `scheme` is the one the published onion
+    /// endpoint should use, i.e. `tor` or `tor+tls`.
+    pub(crate) async fn do_listen(&self, scheme: &str, port: u16) -> io::Result<TorListenerIntern> {
         // Initialize or fetch the static TOR_CLIENT that should be reused in
         // the Tor dialer
         let client = match TOR_CLIENT
@@ -238,16 +240,17 @@ impl TorListener {
 
         info!(
             target: "net::tor::do_listen",
-            "[P2P] Established Tor listener on tor://{}:{port}",
+            "[P2P] Established Tor listener on {scheme}://{}:{port}",
             onion_service.onion_address().unwrap()
         );
 
         let endpoint =
-            Url::parse(&format!("tor://{}:{port}", onion_service.onion_address().unwrap()))
+            Url::parse(&format!("{scheme}://{}:{port}", onion_service.onion_address().unwrap()))
                 .unwrap();
         self.endpoint.set(endpoint).await.expect("fatal endpoint already set for TorListener");
 
         Ok(TorListenerIntern {
+            scheme: scheme.to_string(),
             port,
             _onion_service: onion_service,
             rendreq_stream: AsyncMutex::new(Box::pin(rendreq_stream)),
@@ -257,6 +260,7 @@ impl TorListener {
 
 /// Internal Tor Listener implementation, used with `PtListener`
 pub struct TorListenerIntern {
+    scheme: String,
     port: u16,
     _onion_service: Arc<RunningOnionService>,
     //rendreq_stream: Mutex<BoxStream<'a, RendRequest>>,
@@ -265,9 +269,9 @@ pub struct TorListenerIntern {
 
 unsafe impl Sync for TorListenerIntern {}
 
-#[async_trait]
-impl PtListener for TorListenerIntern {
-    async fn next(&self) -> io::Result<(Box<dyn PtStream>, Url)> {
+impl TorListenerIntern {
+    /// Wait for the next rendezvous request and accept its stream
+    async fn accept_stream(&self) -> io::Result<DataStream> {
         let mut rendreq_stream = self.rendreq_stream.lock().await;
 
         let Some(rendrequest) = rendreq_stream.next().await else {
@@ -312,6 +316,27 @@ impl PtListener for TorListenerIntern {
             }
         };
 
-        Ok((Box::new(stream), Url::parse(&format!("tor://127.0.0.1:{}", self.port)).unwrap()))
+        Ok(stream)
+    }
+
+    fn peer_url(&self) -> Url {
+        Url::parse(&format!("{}://127.0.0.1:{}", self.scheme, self.port)).unwrap()
+    }
+}
+
+#[async_trait]
+impl PtListener for TorListenerIntern {
+    async fn next(&self) -> io::Result<(Box<dyn PtStream>, Url)> {
+        let stream = self.accept_stream().await?;
+        Ok((Box::new(stream), self.peer_url()))
+    }
+}
+
+#[async_trait]
+impl PtListener for (TlsAcceptor, TorListenerIntern) {
+    async fn next(&self) -> io::Result<(Box<dyn PtStream>, Url)> {
+        let stream = self.1.accept_stream().await?;
+        let stream = self.0.accept(stream).await?;
+        Ok((Box::new(TlsStream::Server(stream)), self.1.peer_url()))
     }
 }
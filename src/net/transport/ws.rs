@@ -0,0 +1,680 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! WebSocket transport (RFC 6455)
+//!
+//! Peers are reached over `ws://` and `wss://` endpoints, so nodes can sit
+//! behind HTTP reverse proxies and firewalls that only let web traffic
+//! through, and browser light clients can connect to them. The connection
+//! is established with a regular HTTP/1.1 Upgrade handshake, after which
+//! the P2P byte stream is carried in binary frames.
+//!
+//! `wss` is terminated by the node itself using the same self-signed TLS
+//! setup as `tcp+tls`. Browsers will refuse such certificates, so nodes
+//! serving browsers should additionally listen on `ws` behind a
+//! TLS-terminating proxy. The proxy address is only meant for browsers,
+//! other nodes dialing `wss` expect the DarkFi certificate.
+//!
+//! The JSON-RPC server and client use [`WsStream::with_messages()`], in
+//! which case every line written is sent as its own text frame and every
+//! incoming message is read back as a line, so browsers can exchange JSON
+//! messages with them directly.
+
+use std::{
+    collections::HashMap,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use futures::ready;
+use futures_rustls::{TlsAcceptor, TlsStream};
+use log::debug;
+use sha1::{Digest, Sha1};
+use smol::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{TcpListener as SmolTcpListener, TcpStream},
+};
+use url::Url;
+
+use super::{PtListener, PtStream};
+use crate::util::encoding::base64;
+
+/// GUID appended to the client key when computing `Sec-WebSocket-Accept`
+const WS_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Upper bound on the size of the HTTP handshake headers
+const MAX_HANDSHAKE_LEN: usize = 8192;
+
+/// Time a peer is given to complete the HTTP handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Upper bound on the payload of a single incoming frame
+const MAX_FRAME_LEN: u64 = 16 * 1024 * 1024;
+
+/// Upper bound on the payload of a single outgoing frame
+const MAX_WRITE_FRAME_LEN: usize = 64 * 1024;
+
+const OP_CONTINUATION: u8 = 0x0;
+const OP_TEXT: u8 = 0x1;
+const OP_BINARY: u8 = 0x2;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xa;
+
+/// Compute the `Sec-WebSocket-Accept` value for the given client key
+fn accept_key(key: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(key.as_bytes());
+    hasher.update(WS_GUID.as_bytes());
+    base64::encode(&hasher.finalize())
+}
+
+/// Read an HTTP request or response head from the stream. Returns the
+/// start line along with the headers, whose names are lowercased.
+///
+/// The head is read byte by byte so nothing following it, i.e. the first
+/// frames, gets consumed from the stream.
+async fn read_http_head<S: AsyncRead + Unpin>(
+    stream: &mut S,
+) -> io::Result<(String, HashMap<String, String>)> {
+    let mut buf = Vec::with_capacity(512);
+    let mut byte = [0u8; 1];
+
+    while !buf.ends_with(b"\r\n\r\n") {
+        if buf.len() >= MAX_HANDSHAKE_LEN {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "WebSocket handshake too long"))
+        }
+        stream.read_exact(&mut byte).await?;
+        buf.push(byte[0]);
+    }
+
+    let Ok(head) = String::from_utf8(buf) else {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid WebSocket handshake"))
+    };
+
+    let mut lines = head.split("\r\n").filter(|l| !l.is_empty());
+    let start_line = lines.next().unwrap_or_default().to_string();
+
+    let mut headers = HashMap::new();
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid WebSocket handshake"))
+        };
+        headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+    }
+
+    Ok((start_line, headers))
+}
+
+/// Check that a comma-separated header contains the given token
+fn header_has_token(headers: &HashMap<String, String>, name: &str, token: &str) -> bool {
+    match headers.get(name) {
+        Some(v) => v.split(',').any(|t| t.trim().eq_ignore_ascii_case(token)),
+        None => false,
+    }
+}
+
+/// Run `fut`, failing if it does not complete within [`HANDSHAKE_TIMEOUT`]
+async fn with_timeout<T>(fut: impl std::future::Future<Output = io::Result<T>>) -> io::Result<T> {
+    smol::future::or(fut, async {
+        smol::Timer::after(HANDSHAKE_TIMEOUT).await;
+        Err(io::ErrorKind::TimedOut.into())
+    })
+    .await
+}
+
+/// Encode a single final frame. Frames sent by the client must be masked.
+fn encode_frame(opcode: u8, payload: &[u8], mask: Option<[u8; 4]>) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(payload.len() + 14);
+    frame.push(0x80 | opcode);
+
+    let mask_bit = if mask.is_some() { 0x80 } else { 0x00 };
+    if payload.len() < 126 {
+        frame.push(mask_bit | payload.len() as u8);
+    } else if payload.len() <= u16::MAX as usize {
+        frame.push(mask_bit | 126);
+        frame.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+    } else {
+        frame.push(mask_bit | 127);
+        frame.extend_from_slice(&(payload.len() as u64).to_be_bytes());
+    }
+
+    match mask {
+        Some(mask) => {
+            frame.extend_from_slice(&mask);
+            frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+        }
+        None => frame.extend_from_slice(payload),
+    }
+
+    frame
+}
+
+/// Try to decode a single frame from the start of `buf`. Returns the
+/// opcode, the unmasked payload and the number of bytes consumed, or
+/// `None` if more data is needed. `masked` tells whether the peer is
+/// required to mask its frames, which is the case for clients.
+fn decode_frame(buf: &[u8], masked: bool) -> io::Result<Option<(u8, Vec<u8>, usize)>> {
+    if buf.len() < 2 {
+        return Ok(None)
+    }
+
+    let opcode = buf[0] & 0x0f;
+    if buf[0] & 0x70 != 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Unexpected WebSocket RSV bits"))
+    }
+
+    if (buf[1] & 0x80 != 0) != masked {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid WebSocket frame masking"))
+    }
+
+    let (len, mut offset) = match buf[1] & 0x7f {
+        126 => {
+            if buf.len() < 4 {
+                return Ok(None)
+            }
+            (u16::from_be_bytes([buf[2], buf[3]]) as u64, 4)
+        }
+        127 => {
+            if buf.len() < 10 {
+                return Ok(None)
+            }
+            (u64::from_be_bytes(buf[2..10].try_into().unwrap()), 10)
+        }
+        n => (n as u64, 2),
+    };
+
+    if len > MAX_FRAME_LEN {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "WebSocket frame too large"))
+    }
+
+    let mask = if masked {
+        if buf.len() < offset + 4 {
+            return Ok(None)
+        }
+        let mask: [u8; 4] = buf[offset..offset + 4].try_into().unwrap();
+        offset += 4;
+        Some(mask)
+    } else {
+        None
+    };
+
+    let end = offset + len as usize;
+    if buf.len() < end {
+        return Ok(None)
+    }
+
+    let mut payload = buf[offset..end].to_vec();
+    if let Some(mask) = mask {
+        payload.iter_mut().enumerate().for_each(|(i, b)| *b ^= mask[i % 4]);
+    }
+
+    Ok(Some((opcode, payload, end)))
+}
+
+/// A byte stream carried over a WebSocket connection.
+///
+/// Writes are sent as binary frames, and the payload of incoming data
+/// frames is handed to the reader as a contiguous stream. Pings are
+/// answered transparently and a close frame is seen as EOF.
+pub struct WsStream<S> {
+    /// Underlying transport
+    inner: S,
+    /// Whether we initiated the connection, in which case our frames
+    /// have to be masked
+    client: bool,
+    /// Raw bytes read from `inner` which do not yet form a full frame
+    read_buf: Vec<u8>,
+    /// Payload of the last data frame
+    payload: Vec<u8>,
+    /// Position of the reader inside `payload`
+    payload_pos: usize,
+    /// Encoded frames not yet written to `inner`
+    write_buf: Vec<u8>,
+    /// Whether a close frame was received
+    closed: bool,
+    /// Whether lines are carried in text messages rather than the byte
+    /// stream in binary frames
+    messages: bool,
+    /// Written bytes not yet forming a full line, in message mode
+    line_buf: Vec<u8>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> WsStream<S> {
+    fn new(inner: S, client: bool) -> Self {
+        Self {
+            inner,
+            client,
+            read_buf: vec![],
+            payload: vec![],
+            payload_pos: 0,
+            write_buf: vec![],
+            closed: false,
+            messages: false,
+            line_buf: vec![],
+        }
+    }
+
+    /// Switch the stream to message mode. Each line written, terminated
+    /// by LF or CRLF, is sent as a single text frame, and each incoming
+    /// message is read as a single line. Line breaks inside incoming
+    /// messages are replaced by spaces, which is harmless for JSON since
+    /// they can only appear there as whitespace.
+    pub(crate) fn with_messages(mut self) -> Self {
+        self.messages = true;
+        self
+    }
+
+    /// Reference the underlying transport
+    pub(crate) fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Perform the client side of the Upgrade handshake
+    pub(crate) async fn connect(mut inner: S, host: &str, path: &str) -> io::Result<Self> {
+        let key = base64::encode(&rand::random::<[u8; 16]>());
+
+        let request = format!(
+            "GET {path} HTTP/1.1\r\n\
+             Host: {host}\r\n\
+             Upgrade: websocket\r\n\
+             Connection: Upgrade\r\n\
+             Sec-WebSocket-Key: {key}\r\n\
+             Sec-WebSocket-Version: 13\r\n\r\n"
+        );
+
+        let handshake = async {
+            inner.write_all(request.as_bytes()).await?;
+            inner.flush().await?;
+            read_http_head(&mut inner).await
+        };
+        let (status, headers) = with_timeout(handshake).await?;
+
+        if status.split_whitespace().nth(1) != Some("101") {
+            debug!(target: "net::ws::connect", "Upgrade refused by {host}: {status}");
+            return Err(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                "WebSocket upgrade refused",
+            ))
+        }
+
+        if !header_has_token(&headers, "upgrade", "websocket") ||
+            headers.get("sec-websocket-accept") != Some(&accept_key(&key))
+        {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid WebSocket handshake"))
+        }
+
+        Ok(Self::new(inner, true))
+    }
+
+    /// Perform the server side of the Upgrade handshake. Browsers always
+    /// send the origin of the page opening the connection, so upgrades
+    /// carrying an `Origin` not in `allowed_origins` are refused, keeping
+    /// arbitrary websites from talking to the server through the browser
+    /// of its user. Other clients don't send one.
+    pub(crate) async fn accept(mut inner: S, allowed_origins: &[String]) -> io::Result<Self> {
+        let (request, headers) = with_timeout(read_http_head(&mut inner)).await?;
+
+        if let Some(origin) = headers.get("origin") {
+            if !allowed_origins.contains(origin) {
+                debug!(target: "net::ws::accept", "Refusing upgrade from origin {origin}");
+                let response = "HTTP/1.1 403 Forbidden\r\n\
+                                Content-Length: 0\r\n\r\n";
+                let _ = inner.write_all(response.as_bytes()).await;
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    "WebSocket origin not allowed",
+                ))
+            }
+        }
+
+        let key = match headers.get("sec-websocket-key") {
+            Some(key)
+                if request.starts_with("GET ") &&
+                    header_has_token(&headers, "upgrade", "websocket") &&
+                    header_has_token(&headers, "connection", "upgrade") &&
+                    headers.get("sec-websocket-version").map(|v| v.as_str()) == Some("13") =>
+            {
+                key.clone()
+            }
+            _ => {
+                let response = "HTTP/1.1 400 Bad Request\r\n\
+                                Sec-WebSocket-Version: 13\r\n\
+                                Content-Length: 0\r\n\r\n";
+                let _ = inner.write_all(response.as_bytes()).await;
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid WebSocket upgrade"))
+            }
+        };
+
+        let response = format!(
+            "HTTP/1.1 101 Switching Protocols\r\n\
+             Upgrade: websocket\r\n\
+             Connection: Upgrade\r\n\
+             Sec-WebSocket-Accept: {}\r\n\r\n",
+            accept_key(&key)
+        );
+        inner.write_all(response.as_bytes()).await?;
+        inner.flush().await?;
+
+        Ok(Self::new(inner, false))
+    }
+
+    /// Queue a frame to be written to the underlying stream
+    fn queue_frame(&mut self, opcode: u8, payload: &[u8]) {
+        let mask = if self.client { Some(rand::random()) } else { None };
+        self.write_buf.extend_from_slice(&encode_frame(opcode, payload, mask));
+    }
+
+    /// Write out everything in `write_buf`
+    fn poll_write_buf(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while !self.write_buf.is_empty() {
+            let n = ready!(Pin::new(&mut self.inner).poll_write(cx, &self.write_buf))?;
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()))
+            }
+            self.write_buf.drain(..n);
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncRead for WsStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+
+        loop {
+            if this.payload_pos < this.payload.len() {
+                let n = buf.len().min(this.payload.len() - this.payload_pos);
+                buf[..n].copy_from_slice(&this.payload[this.payload_pos..this.payload_pos + n]);
+                this.payload_pos += n;
+                return Poll::Ready(Ok(n))
+            }
+
+            if this.closed {
+                return Poll::Ready(Ok(0))
+            }
+
+            let Some((opcode, mut payload, consumed)) = decode_frame(&this.read_buf, !this.client)?
+            else {
+                let mut tmp = [0u8; 8192];
+                let n = ready!(Pin::new(&mut this.inner).poll_read(cx, &mut tmp))?;
+                if n == 0 {
+                    return Poll::Ready(Ok(0))
+                }
+                this.read_buf.extend_from_slice(&tmp[..n]);
+                continue
+            };
+            let fin = this.read_buf[0] & 0x80 != 0;
+            this.read_buf.drain(..consumed);
+
+            match opcode {
+                // Fragmentation does not matter for a byte stream,
+                // so all data frames are handled the same way. In
+                // message mode, the final frame terminates the line.
+                OP_CONTINUATION | OP_TEXT | OP_BINARY => {
+                    if this.messages {
+                        for b in payload.iter_mut().filter(|b| **b == b'\n' || **b == b'\r') {
+                            *b = b' ';
+                        }
+                        if fin {
+                            payload.push(b'\n');
+                        }
+                    }
+                    this.payload = payload;
+                    this.payload_pos = 0;
+                }
+
+                // Control frames get their payload echoed back. Writing is
+                // best effort here, whatever is left gets flushed by the
+                // next write.
+                OP_PING => {
+                    this.queue_frame(OP_PONG, &payload);
+                    if let Poll::Ready(Err(e)) = this.poll_write_buf(cx) {
+                        return Poll::Ready(Err(e))
+                    }
+                }
+
+                OP_CLOSE => {
+                    this.closed = true;
+                    this.queue_frame(OP_CLOSE, &payload);
+                    if let Poll::Ready(Err(e)) = this.poll_write_buf(cx) {
+                        return Poll::Ready(Err(e))
+                    }
+                }
+
+                OP_PONG => {}
+
+                _ => {
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Unknown WebSocket opcode",
+                    )))
+                }
+            }
+        }
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncWrite for WsStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+
+        // Previous frames must be out before accepting new data
+        ready!(this.poll_write_buf(cx))?;
+
+        let n = if this.messages {
+            // Frame every complete line, without its terminator
+            this.line_buf.extend_from_slice(buf);
+            while let Some(pos) = this.line_buf.iter().position(|b| *b == b'\n') {
+                let mut line: Vec<u8> = this.line_buf.drain(..=pos).collect();
+                line.pop();
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+                if !line.is_empty() {
+                    this.queue_frame(OP_TEXT, &line);
+                }
+            }
+            buf.len()
+        } else {
+            let n = buf.len().min(MAX_WRITE_FRAME_LEN);
+            this.queue_frame(OP_BINARY, &buf[..n]);
+            n
+        };
+
+        // The data is accepted once it is framed. Anything that could
+        // not be written yet is flushed by the next write or flush.
+        if let Poll::Ready(Err(e)) = this.poll_write_buf(cx) {
+            return Poll::Ready(Err(e))
+        }
+
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        ready!(this.poll_write_buf(cx))?;
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        ready!(this.poll_write_buf(cx))?;
+        Pin::new(&mut this.inner).poll_close(cx)
+    }
+}
+
+/// WebSocket Dialer implementation
+#[derive(Debug, Clone)]
+pub struct WsDialer {
+    /// Dialer used for the underlying TCP connection
+    tcp: super::tcp::TcpDialer,
+}
+
+impl WsDialer {
+    /// Instantiate a new [`WsDialer`]
+    pub(crate) async fn new() -> io::Result<Self> {
+        Ok(Self { tcp: super::tcp::TcpDialer::new(None).await? })
+    }
+
+    /// Internal dial function for `ws`
+    pub(crate) async fn do_dial(
+        &self,
+        endpoint: &Url,
+        timeout: Option<Duration>,
+    ) -> io::Result<WsStream<TcpStream>> {
+        let (sockaddr, host, path) = Self::target(endpoint)?;
+        debug!(target: "net::ws::do_dial", "Dialing {endpoint} with WebSocket...");
+        let stream = self.tcp.do_dial(sockaddr, timeout).await?;
+        WsStream::connect(stream, &host, &path).await
+    }
+
+    /// Internal dial function for `wss`. TLS is set up on the TCP
+    /// connection before the WebSocket handshake.
+    pub(crate) async fn do_dial_tls(
+        &self,
+        endpoint: &Url,
+        timeout: Option<Duration>,
+    ) -> io::Result<WsStream<TlsStream<TcpStream>>> {
+        let (sockaddr, host, path) = Self::target(endpoint)?;
+        debug!(target: "net::ws::do_dial_tls", "Dialing {endpoint} with secure WebSocket...");
+        let stream = self.tcp.do_dial(sockaddr, timeout).await?;
+        let tlsupgrade = super::tls::TlsUpgrade::new().await;
+        let stream = tlsupgrade.upgrade_dialer_tls(stream).await?;
+        WsStream::connect(stream, &host, &path).await
+    }
+
+    /// Resolve the socket address, `Host` header and request path
+    fn target(endpoint: &Url) -> io::Result<(smol::net::SocketAddr, String, String)> {
+        // NOTE: sockaddr here is an array, can contain both ipv4 and ipv6
+        let sockaddr = endpoint.socket_addrs(|| None)?;
+        let host = match endpoint.port() {
+            Some(port) => format!("{}:{port}", endpoint.host_str().unwrap()),
+            None => endpoint.host_str().unwrap().to_string(),
+        };
+        Ok((sockaddr[0], host, endpoint.path().to_string()))
+    }
+}
+
+/// WebSocket listener, optionally terminating TLS
+pub struct WsListenerIntern {
+    listener: SmolTcpListener,
+    tls: Option<TlsAcceptor>,
+}
+
+impl WsListenerIntern {
+    pub(crate) fn new(listener: SmolTcpListener, tls: Option<TlsAcceptor>) -> Self {
+        Self { listener, tls }
+    }
+}
+
+#[async_trait]
+impl PtListener for WsListenerIntern {
+    async fn next(&self) -> io::Result<(Box<dyn PtStream>, Url)> {
+        let (stream, peer_addr) = self.listener.accept().await?;
+
+        match &self.tls {
+            None => {
+                let stream = WsStream::accept(stream, &[]).await?;
+                let url = Url::parse(&format!("ws://{peer_addr}")).unwrap();
+                Ok((Box::new(stream), url))
+            }
+            Some(acceptor) => {
+                let stream = TlsStream::Server(acceptor.accept(stream).await?);
+                let stream = WsStream::accept(stream, &[]).await?;
+                let url = Url::parse(&format!("wss://{peer_addr}")).unwrap();
+                Ok((Box::new(stream), url))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ws_accept_key() {
+        // Example from RFC 6455 section 1.3
+        assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+    }
+
+    #[test]
+    fn ws_frame_roundtrip() {
+        for len in [0, 125, 126, 65535, 65536] {
+            let payload: Vec<u8> = (0..len).map(|i| i as u8).collect();
+
+            let frame = encode_frame(OP_BINARY, &payload, Some([1, 2, 3, 4]));
+            let (opcode, decoded, consumed) = decode_frame(&frame, true).unwrap().unwrap();
+            assert_eq!(opcode, OP_BINARY);
+            assert_eq!(decoded, payload);
+            assert_eq!(consumed, frame.len());
+
+            // Partial frames need more data
+            assert!(decode_frame(&frame[..frame.len() - 1], true).unwrap().is_none());
+
+            // Masking must match the peer role
+            assert!(decode_frame(&frame, false).is_err());
+        }
+    }
+
+    #[test]
+    fn ws_accept_origin() {
+        let allowed = vec!["https://wallet.example".to_string()];
+        let upgrade = |origin: Option<&str>| {
+            let origin = origin.map(|o| format!("Origin: {o}\r\n")).unwrap_or_default();
+            format!(
+                "GET / HTTP/1.1\r\n\
+                 Host: localhost\r\n\
+                 Upgrade: websocket\r\n\
+                 Connection: Upgrade\r\n\
+                 Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+                 {origin}\
+                 Sec-WebSocket-Version: 13\r\n\r\n"
+            )
+        };
+
+        smol::block_on(async {
+            for (origin, ok) in [
+                (None, true),
+                (Some("https://wallet.example"), true),
+                (Some("https://evil.example"), false),
+            ] {
+                let (mut client, server) = smol::net::unix::UnixStream::pair().unwrap();
+                client.write_all(upgrade(origin).as_bytes()).await.unwrap();
+                let res = WsStream::accept(server, &allowed).await;
+                assert_eq!(res.is_ok(), ok, "{origin:?}");
+                if !ok {
+                    assert_eq!(res.err().unwrap().kind(), io::ErrorKind::PermissionDenied);
+                }
+            }
+        });
+    }
+}
@@ -16,7 +16,7 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use log::{debug, error};
 use smol::{channel, io::BufReader, Executor};
@@ -26,21 +26,62 @@ use url::Url;
 use super::{
     common::{
         http_read_from_stream_response, http_write_to_stream, read_from_stream, write_to_stream,
-        INIT_BUF_SIZE, READ_TIMEOUT,
+        ws_transport_url, INIT_BUF_SIZE, READ_TIMEOUT,
     },
     jsonrpc::*,
 };
 use crate::{
-    net::transport::{Dialer, PtStream},
+    net::transport::{ws::WsStream, Dialer, PtStream},
     system::{io_timeout, PublisherPtr, StoppableTask, StoppableTaskPtr},
+    util::path::expand_path,
     Error, Result,
 };
 
+/// Auxiliary function to dial a JSON-RPC server, rewriting `http+` and
+/// WebSocket endpoints into the transport they run on. WebSocket
+/// connections carry one JSON-RPC message per text frame.
+async fn dial_endpoint(endpoint: &Url) -> Result<Box<dyn PtStream>> {
+    // Figure out if we're using HTTP and rewrite the URL accordingly.
+    let mut dialer_url = endpoint.clone();
+    if endpoint.scheme().starts_with("http+") {
+        let scheme = endpoint.scheme().strip_prefix("http+").unwrap();
+        let url_str = endpoint.as_str().replace(endpoint.scheme(), scheme);
+        dialer_url = url_str.parse()?;
+    }
+
+    let ws_url = ws_transport_url(endpoint)?;
+    if let Some(ref url) = ws_url {
+        dialer_url = url.clone();
+    }
+
+    // Instantiate Dialer and dial the server
+    // TODO: Could add a timeout here
+    let dialer = Dialer::new(dialer_url, None, None).await?;
+    let stream = dialer.dial(None).await?;
+
+    let Some(url) = ws_url else { return Ok(stream) };
+    let host = format!("{}:{}", url.host_str().unwrap(), url.port().unwrap());
+    let stream = WsStream::connect(stream, &host, endpoint.path()).await?;
+    Ok(Box::new(stream.with_messages()))
+}
+
+/// Auxiliary function to pick the token a client authenticates with,
+/// either given directly or read from a server cookie file.
+pub fn auth_token(token: Option<String>, cookie: Option<String>) -> Result<Option<String>> {
+    if token.is_some() {
+        return Ok(token)
+    }
+
+    let Some(cookie) = cookie else { return Ok(None) };
+    let token = std::fs::read_to_string(expand_path(&cookie)?)?;
+    Ok(Some(token.trim().to_string()))
+}
+
 /// JSON-RPC client implementation using asynchronous channels.
 pub struct RpcClient {
-    /// The channel used to send JSON-RPC request objects.
+    /// The channel used to send JSON-RPC request objects, or batches of them.
     /// The `bool` marks if we should have a reply read timeout.
-    req_send: channel::Sender<(JsonRequest, bool)>,
+    req_send: channel::Sender<(JsonResult, bool)>,
     /// The channel used to read the JSON-RPC response object.
     rep_recv: channel::Receiver<JsonResult>,
     /// The channel used to skip waiting for a JSON-RPC client request
@@ -54,31 +95,33 @@ impl RpcClient {
     /// The function takes an `Executor` object, which is needed to start the
     /// `StoppableTask` which represents the client-server connection.
     pub async fn new(endpoint: Url, ex: Arc<Executor<'_>>) -> Result<Self> {
+        Self::with_token(endpoint, None, ex).await
+    }
+
+    /// Instantiate a new JSON-RPC client that connects to the given endpoint,
+    /// authenticating with the given token. Over HTTP, the token is sent as
+    /// an `Authorization: Bearer` header with every request. Otherwise, the
+    /// connection gets authenticated once, using the `auth` method.
+    pub async fn with_token(
+        endpoint: Url,
+        token: Option<String>,
+        ex: Arc<Executor<'_>>,
+    ) -> Result<Self> {
         // Instantiate communication channels
         let (req_send, req_recv) = channel::unbounded();
         let (rep_send, rep_recv) = channel::unbounded();
         let (req_skip_send, req_skip_recv) = channel::unbounded();
 
-        // Figure out if we're using HTTP and rewrite the URL accordingly.
-        let mut dialer_url = endpoint.clone();
-        if endpoint.scheme().starts_with("http+") {
-            let scheme = endpoint.scheme().strip_prefix("http+").unwrap();
-            let url_str = endpoint.as_str().replace(endpoint.scheme(), scheme);
-            dialer_url = url_str.parse()?;
-        }
         let use_http = endpoint.scheme().starts_with("http+");
-
-        // Instantiate Dialer and dial the server
-        // TODO: Could add a timeout here
-        let dialer = Dialer::new(dialer_url, None, None).await?;
-        let stream = dialer.dial(None).await?;
+        let stream = dial_endpoint(&endpoint).await?;
+        let bearer = if use_http { token.clone() } else { None };
 
         // Create the StoppableTask running the request-reply loop.
         // This represents the actual connection, which can be stopped
         // using `RpcClient::stop()`.
         let task = StoppableTask::new();
         task.clone().start(
-            Self::reqrep_loop(use_http, stream, rep_send, req_recv, req_skip_recv),
+            Self::reqrep_loop(use_http, bearer, stream, rep_send, req_recv, req_skip_recv),
             |res| async move {
                 match res {
                     Ok(()) | Err(Error::RpcClientStopped) => {}
@@ -89,7 +132,16 @@ impl RpcClient {
             ex.clone(),
         );
 
-        Ok(Self { req_send, rep_recv, task, req_skip_send })
+        let client = Self { req_send, rep_recv, task, req_skip_send };
+
+        if let (false, Some(token)) = (use_http, token) {
+            if let Err(e) = client.auth(&token).await {
+                client.stop().await;
+                return Err(e)
+            }
+        }
+
+        Ok(client)
     }
 
     /// Stop the JSON-RPC client. This will trigger `stop()` on the inner
@@ -102,9 +154,10 @@ impl RpcClient {
     /// Internal function that loops on a given stream and multiplexes the data
     async fn reqrep_loop(
         use_http: bool,
+        bearer: Option<String>,
         stream: Box<dyn PtStream>,
         rep_send: channel::Sender<JsonResult>,
-        req_recv: channel::Receiver<(JsonRequest, bool)>,
+        req_recv: channel::Receiver<(JsonResult, bool)>,
         req_skip_recv: channel::Receiver<()>,
     ) -> Result<()> {
         debug!(target: "rpc::client::reqrep_loop()", "Starting reqrep loop");
@@ -123,9 +176,8 @@ impl RpcClient {
                     let (request, timeout) = req_recv.recv().await?;
                     with_timeout = timeout;
 
-                    let request = JsonResult::Request(request);
                     if use_http {
-                        http_write_to_stream(&mut writer, &request).await?;
+                        http_write_to_stream(&mut writer, &request, bearer.as_deref()).await?;
                     } else {
                         write_to_stream(&mut writer, &request).await?;
                     }
@@ -173,7 +225,7 @@ impl RpcClient {
 
         // If the connection is closed, the sender will get an error
         // for sending to a closed channel.
-        self.req_send.send((req, true)).await?;
+        self.req_send.send((JsonResult::Request(req), true)).await?;
 
         // If the connection is closed, the receiver will get an error
         // for waiting on a closed channel.
@@ -210,7 +262,7 @@ impl RpcClient {
                 Err(Error::JsonRpcError((e.error.code, e.error.message)))
             }
 
-            JsonResult::Subscriber(_) => {
+            JsonResult::Subscriber(_) | JsonResult::Batch(_) => {
                 // When?
                 let e = JsonError::new(ErrorCode::InvalidReply, None, req_id);
                 Err(Error::JsonRpcError((e.error.code, e.error.message)))
@@ -218,6 +270,101 @@ impl RpcClient {
         }
     }
 
+    /// Send a batch of JSON-RPC requests over the instantiated client, in a
+    /// single message. Returns the result of each request, in the order they
+    /// were given, so a failing request doesn't fail the rest of the batch.
+    /// The request IDs get replaced by their position in the batch, in order
+    /// to match the replies without random ID collisions.
+    pub async fn batch_request(
+        &self,
+        mut reqs: Vec<JsonRequest>,
+    ) -> Result<Vec<Result<JsonValue>>> {
+        if reqs.is_empty() {
+            return Ok(vec![])
+        }
+        if reqs.len() > u16::MAX as usize {
+            let e = JsonError::new(ErrorCode::InvalidRequest, None, 0);
+            return Err(Error::JsonRpcError((e.error.code, e.error.message)))
+        }
+
+        let batch_len = reqs.len();
+        for (i, req) in reqs.iter_mut().enumerate() {
+            req.id = i as u16;
+        }
+        let batch: Vec<JsonResult> = reqs.into_iter().map(JsonResult::Request).collect();
+        debug!(target: "rpc::client", "--> {}", JsonResult::stringify_batch(&batch)?);
+
+        // If the connection is closed, the sender will get an error
+        // for sending to a closed channel.
+        self.req_send.send((JsonResult::Batch(batch), true)).await?;
+
+        // If the connection is closed, the receiver will get an error
+        // for waiting on a closed channel.
+        let reply = self.rep_recv.recv().await?;
+
+        // Handle the response
+        let items = match reply {
+            JsonResult::Batch(items) => {
+                debug!(target: "rpc::client", "<-- {}", JsonResult::stringify_batch(&items)?);
+                items
+            }
+
+            JsonResult::Error(e) => {
+                debug!(target: "rpc::client", "<-- {}", e.stringify()?);
+                return Err(Error::JsonRpcError((e.error.code, e.error.message)))
+            }
+
+            _ => {
+                let e = JsonError::new(ErrorCode::InvalidReply, None, 0);
+                return Err(Error::JsonRpcError((e.error.code, e.error.message)))
+            }
+        };
+
+        // Servers may reply in any order, so we match the replies by ID
+        let mut replies = HashMap::with_capacity(items.len());
+        for item in items {
+            match item {
+                JsonResult::Response(rep) => {
+                    replies.insert(rep.id, Ok(rep.result));
+                }
+
+                JsonResult::Error(e) => {
+                    replies.insert(e.id, Err(Error::JsonRpcError((e.error.code, e.error.message))));
+                }
+
+                _ => {
+                    let e = JsonError::new(ErrorCode::InvalidReply, None, 0);
+                    return Err(Error::JsonRpcError((e.error.code, e.error.message)))
+                }
+            }
+        }
+
+        let mut ret = Vec::with_capacity(batch_len);
+        for id in 0..batch_len as u16 {
+            let rep = replies.remove(&id).unwrap_or_else(|| {
+                let e = JsonError::new(ErrorCode::IdMismatch, None, id);
+                Err(Error::JsonRpcError((e.error.code, e.error.message)))
+            });
+            ret.push(rep);
+        }
+
+        Ok(ret)
+    }
+
+    /// Authenticate the connection with the given token, returning the
+    /// permission class the server granted.
+    pub async fn auth(&self, token: &str) -> Result<String> {
+        let req =
+            JsonRequest::new("auth", JsonValue::Array(vec![JsonValue::String(token.to_string())]));
+        let rep = self.request(req).await?;
+        let Some(class) = rep.get::<String>() else {
+            let e = JsonError::new(ErrorCode::InvalidReply, None, 0);
+            return Err(Error::JsonRpcError((e.error.code, e.error.message)))
+        };
+
+        Ok(class.clone())
+    }
+
     /// Oneshot send a given JSON-RPC request over the instantiated client
     /// and immediately close the channels upon receiving a reply.
     pub async fn oneshot_request(&self, req: JsonRequest) -> Result<JsonValue> {
@@ -246,7 +393,7 @@ impl RpcClient {
 
         // If the connection is closed, the sender will get an error for
         // sending to a closed channel.
-        self.req_send.send((req, false)).await?;
+        self.req_send.send((JsonResult::Request(req), false)).await?;
 
         // Now loop and listen to notifications
         loop {
@@ -280,7 +427,7 @@ impl RpcClient {
                     return Err(Error::JsonRpcError((e.error.code, e.error.message)))
                 }
 
-                JsonResult::Subscriber(_) => {
+                JsonResult::Subscriber(_) | JsonResult::Batch(_) => {
                     // When?
                     let e = JsonError::new(ErrorCode::InvalidReply, None, req_id);
                     return Err(Error::JsonRpcError((e.error.code, e.error.message)))
@@ -307,30 +454,30 @@ impl RpcChadClient {
     /// The function takes an `Executor` object, which is needed to start the
     /// `StoppableTask` which represents the client-server connection.
     pub async fn new(endpoint: Url, ex: Arc<Executor<'_>>) -> Result<Self> {
+        Self::with_token(endpoint, None, ex).await
+    }
+
+    /// Instantiate a new JSON-RPC client that connects to the given endpoint,
+    /// authenticating with the given token, same as [`RpcClient::with_token`].
+    pub async fn with_token(
+        endpoint: Url,
+        token: Option<String>,
+        ex: Arc<Executor<'_>>,
+    ) -> Result<Self> {
         // Instantiate communication channels
         let (req_send, req_recv) = channel::unbounded();
         let (rep_send, rep_recv) = channel::unbounded();
 
-        // Figure out if we're using HTTP and rewrite the URL accordingly.
-        let mut dialer_url = endpoint.clone();
-        if endpoint.scheme().starts_with("http+") {
-            let scheme = endpoint.scheme().strip_prefix("http+").unwrap();
-            let url_str = endpoint.as_str().replace(endpoint.scheme(), scheme);
-            dialer_url = url_str.parse()?;
-        }
         let use_http = endpoint.scheme().starts_with("http+");
-
-        // Instantiate Dialer and dial the server
-        // TODO: Could add a timeout here
-        let dialer = Dialer::new(dialer_url, None, None).await?;
-        let stream = dialer.dial(None).await?;
+        let stream = dial_endpoint(&endpoint).await?;
+        let bearer = if use_http { token.clone() } else { None };
 
         // Create the StoppableTask running the request-reply loop.
         // This represents the actual connection, which can be stopped
         // using `RpcChadClient::stop()`.
         let task = StoppableTask::new();
         task.clone().start(
-            Self::reqrep_loop(use_http, stream, rep_send, req_recv),
+            Self::reqrep_loop(use_http, bearer, stream, rep_send, req_recv),
             |res| async move {
                 match res {
                     Ok(()) | Err(Error::RpcClientStopped) => {}
@@ -341,7 +488,17 @@ impl RpcChadClient {
             ex.clone(),
         );
 
-        Ok(Self { req_send, rep_recv, task })
+        let client = Self { req_send, rep_recv, task };
+
+        if let (false, Some(token)) = (use_http, token) {
+            let params = JsonValue::Array(vec![JsonValue::String(token)]);
+            if let Err(e) = client.request(JsonRequest::new("auth", params)).await {
+                client.stop().await;
+                return Err(e)
+            }
+        }
+
+        Ok(client)
     }
 
     /// Stop the JSON-RPC client. This will trigger `stop()` on the inner
@@ -354,6 +511,7 @@ impl RpcChadClient {
     /// Internal function that loops on a given stream and multiplexes the data
     async fn reqrep_loop(
         use_http: bool,
+        bearer: Option<String>,
         stream: Box<dyn PtStream>,
         rep_send: channel::Sender<JsonResult>,
         req_recv: channel::Receiver<JsonRequest>,
@@ -372,7 +530,7 @@ impl RpcChadClient {
                     let request = req_recv.recv().await?;
                     let request = JsonResult::Request(request);
                     if use_http {
-                        http_write_to_stream(&mut writer, &request).await?;
+                        http_write_to_stream(&mut writer, &request, bearer.as_deref()).await?;
                     } else {
                         write_to_stream(&mut writer, &request).await?;
                     }
@@ -450,7 +608,7 @@ impl RpcChadClient {
                     return Err(Error::JsonRpcError((e.error.code, e.error.message)))
                 }
 
-                JsonResult::Subscriber(_) => {
+                JsonResult::Subscriber(_) | JsonResult::Batch(_) => {
                     // When?
                     let e = JsonError::new(ErrorCode::InvalidReply, None, req_id);
                     return Err(Error::JsonRpcError((e.error.code, e.error.message)))
@@ -20,20 +20,42 @@ use std::{io, time::Duration};
 
 use log::error;
 use smol::io::{AsyncReadExt, AsyncWriteExt, BufReader, ReadHalf, WriteHalf};
+use url::Url;
 
 use super::jsonrpc::*;
-use crate::net::transport::PtStream;
+use crate::{net::transport::PtStream, Error, Result};
 
 pub(super) const INIT_BUF_SIZE: usize = 4096; // 4K
 pub(super) const MAX_BUF_SIZE: usize = 1024 * 1024 * 16; // 16M
 pub(super) const READ_TIMEOUT: Duration = Duration::from_secs(30);
 
+/// Rewrite a `ws` or `wss` endpoint into the `tcp` or `tcp+tls` endpoint
+/// the WebSocket connection runs on. Returns `None` for other schemes.
+/// The port falls back to the scheme default when omitted.
+pub(super) fn ws_transport_url(endpoint: &Url) -> Result<Option<Url>> {
+    let scheme = match endpoint.scheme() {
+        "ws" => "tcp",
+        "wss" => "tcp+tls",
+        _ => return Ok(None),
+    };
+
+    let Some(port) = endpoint.port_or_known_default() else {
+        return Err(Error::ParseFailed("WebSocket endpoint is missing a port"))
+    };
+    let Some(host) = endpoint.host_str() else {
+        return Err(Error::ParseFailed("WebSocket endpoint is missing a host"))
+    };
+
+    Ok(Some(format!("{scheme}://{host}:{port}").parse()?))
+}
+
 /// Internal read function that reads from the active stream into a buffer.
-/// Performs HTTP POST request parsing. Returns the request body length.
+/// Performs HTTP POST request parsing. Returns the request body length,
+/// along with the `Authorization: Bearer` token if the request had one.
 pub(super) async fn http_read_from_stream_request(
     reader: &mut BufReader<ReadHalf<Box<dyn PtStream>>>,
     buf: &mut Vec<u8>,
-) -> io::Result<usize> {
+) -> io::Result<(usize, Option<String>)> {
     let mut total_read = 0;
 
     // Intermediate buffer we use to read byte-by-byte.
@@ -64,7 +86,7 @@ pub(super) async fn http_read_from_stream_request(
     }
 
     // Here we parse the HTTP for correctness and find Content-Length
-    let mut headers = [httparse::EMPTY_HEADER; 8];
+    let mut headers = [httparse::EMPTY_HEADER; 16];
     let mut req = httparse::Request::new(&mut headers);
     let _body_offset = match req.parse(buf) {
         Ok(v) => v.unwrap(), // TODO: This should check httparse::Status::is_partial()
@@ -75,14 +97,23 @@ pub(super) async fn http_read_from_stream_request(
     };
 
     let mut content_length: usize = 0;
+    let mut bearer = None;
     for header in headers {
-        if header.name.to_lowercase() == "content-length" {
+        if header.name.eq_ignore_ascii_case("content-length") {
             let s = String::from_utf8_lossy(header.value);
             content_length = match s.parse() {
                 Ok(v) => v,
                 Err(_) => return Err(io::ErrorKind::InvalidData.into()),
             };
         }
+
+        if header.name.eq_ignore_ascii_case("authorization") {
+            let s = String::from_utf8_lossy(header.value);
+            let Some(token) = s.strip_prefix("Bearer ") else {
+                return Err(io::ErrorKind::InvalidData.into())
+            };
+            bearer = Some(token.trim().to_string());
+        }
     }
 
     if content_length == 0 || content_length > MAX_BUF_SIZE {
@@ -95,7 +126,7 @@ pub(super) async fn http_read_from_stream_request(
     reader.read(buf).await?;
 
     assert!(buf.len() == content_length);
-    Ok(content_length)
+    Ok((content_length, bearer))
 }
 
 /// Internal read function that reads from the active stream into a buffer.
@@ -210,21 +241,33 @@ pub(super) async fn read_from_stream(
 }
 
 /// Internal write function that writes a JSON-RPC object to the active stream.
-/// Sent as an HTTP response.
+/// Sent as an HTTP response, or as an HTTP request carrying the given
+/// bearer token, if any.
 pub(super) async fn http_write_to_stream(
     writer: &mut WriteHalf<Box<dyn PtStream>>,
     object: &JsonResult,
+    bearer: Option<&str>,
 ) -> io::Result<()> {
     let (status_line, object_str) = match object {
         JsonResult::Notification(v) => ("HTTP/1.1 200 OK", v.stringify().unwrap()),
         JsonResult::Response(v) => ("HTTP/1.1 200 OK", v.stringify().unwrap()),
         JsonResult::Error(v) => ("HTTP/1.1 400 Bad Request", v.stringify().unwrap()),
         JsonResult::Request(v) => ("POST /json_rpc HTTP/1.1", v.stringify().unwrap()),
+        JsonResult::Batch(v) if matches!(v.first(), Some(JsonResult::Request(_))) => {
+            ("POST /json_rpc HTTP/1.1", JsonResult::stringify_batch(v).unwrap())
+        }
+        JsonResult::Batch(v) => ("HTTP/1.1 200 OK", JsonResult::stringify_batch(v).unwrap()),
         _ => unreachable!(),
     };
 
     let length = object_str.len();
-    let data = format!("{status_line}\r\nContent-Length: {length}\r\nContent-Type: application/json\r\n\r\n{object_str}");
+    let auth = match bearer {
+        Some(token) if status_line.starts_with("POST") => {
+            format!("Authorization: Bearer {token}\r\n")
+        }
+        _ => String::new(),
+    };
+    let data = format!("{status_line}\r\n{auth}Content-Length: {length}\r\nContent-Type: application/json\r\n\r\n{object_str}");
 
     writer.write_all(data.as_bytes()).await?;
     writer.flush().await?;
@@ -242,6 +285,7 @@ pub(super) async fn write_to_stream(
         JsonResult::Response(v) => v.stringify().unwrap(),
         JsonResult::Error(v) => v.stringify().unwrap(),
         JsonResult::Request(v) => v.stringify().unwrap(),
+        JsonResult::Batch(v) => JsonResult::stringify_batch(v).unwrap(),
         _ => unreachable!(),
     };
 
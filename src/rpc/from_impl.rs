@@ -95,6 +95,17 @@ impl From<net::dnet::OutboundPeerDiscovery> for JsonValue {
     }
 }
 
+#[cfg(feature = "net")]
+impl From<net::dnet::PeerPenalized> for JsonValue {
+    fn from(info: net::dnet::PeerPenalized) -> JsonValue {
+        json_map([
+            ("chan", info.chan.into()),
+            ("misbehavior", JsonStr(info.misbehavior.to_string())),
+            ("score", JsonNum(info.score.into())),
+        ])
+    }
+}
+
 #[cfg(feature = "net")]
 impl From<net::dnet::DnetEvent> for JsonValue {
     fn from(event: net::dnet::DnetEvent) -> JsonValue {
@@ -126,6 +137,38 @@ impl From<net::dnet::DnetEvent> for JsonValue {
             net::dnet::DnetEvent::OutboundPeerDiscovery(info) => {
                 json_map([("event", json_str("outbound_peer_discovery")), ("info", info.into())])
             }
+            net::dnet::DnetEvent::PeerPenalized(info) => {
+                json_map([("event", json_str("peer_penalized")), ("info", info.into())])
+            }
+        }
+    }
+}
+
+#[cfg(feature = "net")]
+impl From<net::event::ChannelEventInfo> for JsonValue {
+    fn from(info: net::event::ChannelEventInfo) -> JsonValue {
+        json_map([
+            ("id", JsonNum(info.id.into())),
+            ("url", JsonStr(info.addr.to_string())),
+            ("transport", JsonStr(info.addr.scheme().to_string())),
+            ("session", json_str(info.session)),
+            ("time", JsonNum(info.time as f64)),
+        ])
+    }
+}
+
+#[cfg(feature = "net")]
+impl From<net::event::P2pEvent> for JsonValue {
+    fn from(event: net::event::P2pEvent) -> JsonValue {
+        match event {
+            net::event::P2pEvent::ChannelConnected(info) => {
+                json_map([("event", json_str("channel_connected")), ("info", info.into())])
+            }
+            net::event::P2pEvent::ChannelDisconnected { info, reason } => json_map([
+                ("event", json_str("channel_disconnected")),
+                ("info", info.into()),
+                ("reason", JsonStr(reason)),
+            ]),
         }
     }
 }
@@ -140,6 +183,7 @@ impl From<event_graph::Event> for JsonValue {
             ("content", JsonStr(bs58::encode(event.content()).into_string())),
             ("parents", JsonArray(parents)),
             ("layer", JsonNum(event.layer as f64)),
+            ("topic", JsonStr(event.topic.to_string())),
         ])
     }
 }
@@ -0,0 +1,128 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{sync::LazyLock, time::Instant};
+
+use async_trait::async_trait;
+
+use super::{
+    jsonrpc::{ErrorCode, JsonError, JsonResponse, JsonResult},
+    util::*,
+};
+
+/// Time the JSON-RPC server started, reported as the daemon uptime
+pub(super) static STARTED: LazyLock<Instant> = LazyLock::new(Instant::now);
+
+/// Readiness checks of a daemon, reported by `health.ready`
+pub struct HealthStatus {
+    /// Whether the daemon caught up with its network
+    pub synced: bool,
+    /// Number of connected peers
+    pub peers: usize,
+    /// Whether the database can be read
+    pub db_ok: bool,
+}
+
+impl HealthStatus {
+    /// A daemon is ready when it is synced, connected and its
+    /// database works
+    pub fn ready(&self) -> bool {
+        self.synced && self.peers > 0 && self.db_ok
+    }
+}
+
+/// Standard health methods, so orchestration tooling can probe all
+/// daemons the same way
+#[async_trait]
+pub trait HandlerHealth: Sync + Send {
+    // RPCAPI:
+    // Liveness probe, replying as long as the daemon serves requests.
+    //
+    // --> {"jsonrpc": "2.0", "method": "health.ping", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": "pong", "id": 1}
+    async fn health_ping(&self, id: u16, params: JsonValue) -> JsonResult {
+        if !params.get::<Vec<JsonValue>>().is_some_and(|p| p.is_empty()) {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        JsonResponse::new(json_str("pong"), id).into()
+    }
+
+    // RPCAPI:
+    // Readiness probe. Reports whether the daemon is synced, how many
+    // peers it is connected to and whether its database can be read.
+    // `ready` is only true when all the checks pass.
+    //
+    // --> {"jsonrpc": "2.0", "method": "health.ready", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"ready": true, "synced": true, "peers": 8, "db_ok": true}, "id": 1}
+    async fn health_ready(&self, id: u16, params: JsonValue) -> JsonResult {
+        if !params.get::<Vec<JsonValue>>().is_some_and(|p| p.is_empty()) {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let status = self.health_status().await;
+        let result = json_map([
+            ("ready", JsonValue::Boolean(status.ready())),
+            ("synced", JsonValue::Boolean(status.synced)),
+            ("peers", JsonNum(status.peers as f64)),
+            ("db_ok", JsonValue::Boolean(status.db_ok)),
+        ]);
+        JsonResponse::new(result, id).into()
+    }
+
+    // RPCAPI:
+    // Reports the daemon name and version, its uptime in seconds and,
+    // for daemons following the blockchain, the height and hash of its
+    // chain tip, which is `null` otherwise.
+    //
+    // --> {"jsonrpc": "2.0", "method": "health.info", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"name": "darkfid", "version": "0.5.0", "uptime": 3600, "chain_tip": {"height": 1234, "hash": "..."}}, "id": 1}
+    async fn health_info(&self, id: u16, params: JsonValue) -> JsonResult {
+        if !params.get::<Vec<JsonValue>>().is_some_and(|p| p.is_empty()) {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let (name, version) = self.health_version();
+        let chain_tip = match self.health_chain_tip().await {
+            Some((height, hash)) => {
+                json_map([("height", JsonNum(height as f64)), ("hash", JsonStr(hash))])
+            }
+            None => JsonValue::Null,
+        };
+
+        let result = json_map([
+            ("name", json_str(name)),
+            ("version", json_str(version)),
+            ("uptime", JsonNum(STARTED.elapsed().as_secs() as f64)),
+            ("chain_tip", chain_tip),
+        ]);
+        JsonResponse::new(result, id).into()
+    }
+
+    /// Daemon name and version, usually the `CARGO_PKG_NAME` and
+    /// `CARGO_PKG_VERSION` of the daemon crate
+    fn health_version(&self) -> (&'static str, &'static str);
+
+    /// Current readiness checks of the daemon
+    async fn health_status(&self) -> HealthStatus;
+
+    /// Height and hash of the chain tip, for daemons following the blockchain
+    async fn health_chain_tip(&self) -> Option<(u32, String)> {
+        None
+    }
+}
@@ -47,6 +47,8 @@ pub enum ErrorCode {
     IdMismatch,
     /// Invalid/Unexpected reply
     InvalidReply,
+    /// Missing authentication or permission for the method
+    Unauthorized,
     /// Reserved for implementation-defined server-errors.
     ServerError(i32),
 }
@@ -61,6 +63,7 @@ impl ErrorCode {
             Self::InternalError => -32603,
             Self::IdMismatch => -32360,
             Self::InvalidReply => -32361,
+            Self::Unauthorized => -32362,
             Self::ServerError(c) => c,
         }
     }
@@ -74,6 +77,7 @@ impl ErrorCode {
             Self::InternalError => "internal error".to_string(),
             Self::IdMismatch => "id mismatch".to_string(),
             Self::InvalidReply => "invalid reply".to_string(),
+            Self::Unauthorized => "unauthorized".to_string(),
             Self::ServerError(_) => "server error".to_string(),
         }
     }
@@ -83,6 +87,71 @@ impl ErrorCode {
     }
 }
 
+/// Categories of implementation-defined server errors.
+/// Server error codes predate the categories, so they can't be derived
+/// from the code itself. Each daemon maps its codes explicitly, and the
+/// category is sent along in the error `data` payload.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ErrorCategory {
+    /// Invalid input or rejected operations
+    Validation,
+    /// Wallet and key management failures
+    Wallet,
+    /// Network and node state failures
+    Net,
+    /// Internal daemon failures
+    Internal,
+}
+
+impl ErrorCategory {
+    /// Parse a category out of its name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "validation" => Some(Self::Validation),
+            "wallet" => Some(Self::Wallet),
+            "net" => Some(Self::Net),
+            "internal" => Some(Self::Internal),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Validation => "validation",
+            Self::Wallet => "wallet",
+            Self::Net => "net",
+            Self::Internal => "internal",
+        }
+    }
+}
+
+/// Trait implemented by daemon-specific server error enums.
+pub trait ServerErrorKind {
+    /// Error code
+    fn code(&self) -> i32;
+    /// Default error message
+    fn message(&self) -> &'static str;
+    /// Category of the error
+    fn category(&self) -> ErrorCategory;
+}
+
+/// Create a server error [`JsonResult`] for the given error kind.
+/// `msg` overrides the default error message.
+pub fn server_error<E: ServerErrorKind>(e: E, id: u16, msg: Option<&str>) -> JsonResult {
+    JsonError::server(&e, msg, None, id).into()
+}
+
+/// Create a server error [`JsonResult`] for the given error kind, with
+/// additional machine-readable details in its `data` payload.
+pub fn server_error_with_details<E: ServerErrorKind>(
+    e: E,
+    id: u16,
+    msg: Option<&str>,
+    details: JsonValue,
+) -> JsonResult {
+    JsonError::server(&e, msg, Some(details), id).into()
+}
+
 // ANCHOR: jsonresult
 /// Wrapping enum around the available JSON-RPC object types
 #[derive(Clone, Debug)]
@@ -94,10 +163,23 @@ pub enum JsonResult {
     Subscriber(JsonSubscriber),
     SubscriberWithReply(JsonSubscriber, JsonResponse),
     Request(JsonRequest),
+    /// Batch of requests, or of their responses and errors, in order
+    Batch(Vec<JsonResult>),
 }
 
 impl JsonResult {
     pub fn try_from_value(value: &JsonValue) -> Result<Self> {
+        if let Some(items) = value.get::<Vec<JsonValue>>() {
+            let mut batch = Vec::with_capacity(items.len());
+            for item in items {
+                if item.is_array() {
+                    return Err(RpcError::InvalidJson("Nested JSON batch".to_string()).into())
+                }
+                batch.push(Self::try_from_value(item)?);
+            }
+            return Ok(Self::Batch(batch))
+        }
+
         if let Ok(response) = JsonResponse::try_from(value) {
             return Ok(Self::Response(response))
         }
@@ -112,6 +194,25 @@ impl JsonResult {
 
         Err(RpcError::InvalidJson("Invalid JSON Result".to_string()).into())
     }
+
+    /// Convert the items of a [`JsonResult::Batch`] into a JSON string.
+    /// Only requests, responses and errors can be batched.
+    pub fn stringify_batch(items: &[JsonResult]) -> Result<String> {
+        let mut values = Vec::with_capacity(items.len());
+        for item in items {
+            let value = match item {
+                Self::Request(v) => v.into(),
+                Self::Response(v) => v.into(),
+                Self::Error(v) => v.into(),
+                _ => {
+                    return Err(RpcError::InvalidJson("Invalid JSON batch item".to_string()).into())
+                }
+            };
+            values.push(value);
+        }
+
+        Ok(JsonValue::Array(values).stringify()?)
+    }
 }
 
 impl From<JsonResponse> for JsonResult {
@@ -411,17 +512,19 @@ pub struct JsonError {
     pub jsonrpc: &'static str,
     /// Request ID
     pub id: u16,
-    /// JSON-RPC error (code and message)
+    /// JSON-RPC error (code, message and optional data)
     pub error: JsonErrorVal,
 }
 
-/// A JSON-RPC error value (code and message)
+/// A JSON-RPC error value (code, message and optional data)
 #[derive(Clone, Debug)]
 pub struct JsonErrorVal {
     /// Error code
     pub code: i32,
     /// Error message
     pub message: String,
+    /// Additional error information
+    pub data: Option<JsonValue>,
 }
 
 impl JsonError {
@@ -429,10 +532,45 @@ impl JsonError {
     /// message, and a response ID.
     /// Creating a `JsonError` implies that the method call was unsuccessful.
     pub fn new(c: ErrorCode, message: Option<String>, id: u16) -> Self {
-        let error = JsonErrorVal { code: c.code(), message: message.unwrap_or(c.message()) };
+        let error =
+            JsonErrorVal { code: c.code(), message: message.unwrap_or(c.message()), data: None };
         Self { jsonrpc: "2.0", id, error }
     }
 
+    /// Create a new server [`JsonError`] for the given error kind.
+    /// The `data` payload carries the error category name, along with
+    /// the given details if any.
+    pub fn server<E: ServerErrorKind>(
+        e: &E,
+        message: Option<&str>,
+        details: Option<JsonValue>,
+        id: u16,
+    ) -> Self {
+        let mut data = HashMap::from([(
+            "category".to_string(),
+            JsonValue::String(e.category().name().to_string()),
+        )]);
+        if let Some(details) = details {
+            data.insert("details".to_string(), details);
+        }
+
+        let message = message.unwrap_or(e.message()).to_string();
+        Self::new(ErrorCode::ServerError(e.code()), Some(message), id)
+            .with_data(JsonValue::Object(data))
+    }
+
+    /// Attach a `data` payload to the error
+    pub fn with_data(mut self, data: JsonValue) -> Self {
+        self.error.data = Some(data);
+        self
+    }
+
+    /// Category of the error, if it is a categorized server error
+    pub fn category(&self) -> Option<ErrorCategory> {
+        let data: &HashMap<String, JsonValue> = self.error.data.as_ref()?.get()?;
+        ErrorCategory::from_name(data.get("category")?.get::<String>()?)
+    }
+
     /// Convert the object into a JSON string
     pub fn stringify(&self) -> Result<String> {
         let v: JsonValue = self.into();
@@ -442,10 +580,14 @@ impl JsonError {
 
 impl From<&JsonError> for JsonValue {
     fn from(err: &JsonError) -> JsonValue {
-        let errmap = JsonValue::Object(HashMap::from([
+        let mut errmap = HashMap::from([
             ("code".to_string(), JsonValue::Number(err.error.code.into())),
             ("message".to_string(), JsonValue::String(err.error.message.clone())),
-        ]));
+        ]);
+        if let Some(data) = &err.error.data {
+            errmap.insert("data".to_string(), data.clone());
+        }
+        let errmap = JsonValue::Object(errmap);
 
         JsonValue::Object(HashMap::from([
             ("jsonrpc".to_string(), JsonValue::String(err.jsonrpc.to_string())),
@@ -511,12 +653,15 @@ impl TryFrom<&JsonValue> for JsonError {
             ))
         }
 
+        let errmap: &HashMap<String, JsonValue> = map["error"].get().unwrap();
+
         Ok(Self {
             jsonrpc: "2.0",
             id: *map["id"].get::<f64>().unwrap() as u16,
             error: JsonErrorVal {
                 code: *map["error"]["code"].get::<f64>().unwrap() as i32,
                 message: map["error"]["message"].get::<String>().unwrap().to_string(),
+                data: errmap.get("data").cloned(),
             },
         })
     }
@@ -37,6 +37,9 @@ pub mod from_impl;
 /// Provides optional `p2p.get_info()` method
 pub mod p2p_method;
 
+/// Provides optional `health.*` methods
+pub mod health;
+
 /// Json helper methods and types
 pub mod util;
 
@@ -19,40 +19,119 @@
 use async_trait::async_trait;
 
 use super::{
-    jsonrpc::{JsonResponse, JsonResult},
+    jsonrpc::{ErrorCode, JsonError, JsonResponse, JsonResult, JsonSubscriber},
     util::*,
 };
-use crate::net;
+use crate::{net, Result};
+
+/// Forward the P2P connectivity events to a JSON-RPC subscriber.
+/// Meant to be run as a background task by daemons serving
+/// `p2p.subscribe_events`.
+pub async fn forward_p2p_events(p2p: net::P2pPtr, subscriber: JsonSubscriber) -> Result<()> {
+    let event_sub = p2p.subscribe_events().await;
+    loop {
+        let event = event_sub.receive().await;
+        subscriber.notify(vec![event.into()].into()).await;
+    }
+}
 
 #[async_trait]
 pub trait HandlerP2p: Sync + Send {
+    // RPCAPI:
+    // Returns the connected channels along with their session type, transport
+    // and message counters, as well as the P2P bandwidth, compression and
+    // peer score stats. `last_seen` is the UNIX timestamp of the last message
+    // received on a channel.
+    //
+    // --> {"jsonrpc": "2.0", "method": "p2p.get_info", "params": [], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": {"channels": [...], ...}, "id": 42}
     async fn p2p_get_info(&self, id: u16, _params: JsonValue) -> JsonResult {
+        let settings = self.p2p().settings();
+        let settings = settings.read().await;
+        let scores = self.p2p().scores();
+
         let mut channels = Vec::new();
         for channel in self.p2p().hosts().channels() {
-            let session = match channel.session_type_id() {
-                net::session::SESSION_INBOUND => "inbound",
-                net::session::SESSION_OUTBOUND => "outbound",
-                net::session::SESSION_MANUAL => "manual",
-                net::session::SESSION_REFINE => "refine",
-                net::session::SESSION_SEED => "seed",
-                _ => panic!("invalid result from channel.session_type_id()"),
-            };
+            let session = net::session::session_name(channel.session_type_id());
             channels.push(json_map([
                 ("url", JsonStr(channel.address().clone().into())),
+                ("transport", JsonStr(channel.address().scheme().to_string())),
                 ("session", json_str(session)),
                 ("id", JsonNum(channel.info.id.into())),
+                ("connected_since", JsonNum(channel.info.start_time as f64)),
+                ("last_seen", JsonNum(channel.messages.last_recv() as f64)),
+                ("messages_sent", JsonNum(channel.messages.sent() as f64)),
+                ("messages_recv", JsonNum(channel.messages.recv() as f64)),
+                ("score", JsonNum(scores.score(&channel.score_key(), &settings).into())),
+                ("bytes_sent", JsonNum(channel.throttle.bytes_sent() as f64)),
+                ("bytes_recv", JsonNum(channel.throttle.bytes_recv() as f64)),
+                ("throttled_ms", JsonNum(channel.throttle.throttled_ms() as f64)),
+                ("compression_saved", JsonNum(channel.compression.bytes_saved() as f64)),
             ]));
         }
 
+        let mut peer_scores = Vec::new();
+        for (peer, score) in scores.scores(&settings) {
+            peer_scores
+                .push(json_map([("url", JsonStr(peer.into())), ("score", JsonNum(score.into()))]));
+        }
+        drop(settings);
+
         let mut slots = Vec::new();
         for channel_id in self.p2p().session_outbound().slot_info().await {
             slots.push(JsonNum(channel_id.into()));
         }
 
-        let result =
-            json_map([("channels", JsonArray(channels)), ("outbound_slots", JsonArray(slots))]);
+        let throttle = self.p2p().throttle();
+        let bandwidth = json_map([
+            ("bytes_sent", JsonNum(throttle.bytes_sent() as f64)),
+            ("bytes_recv", JsonNum(throttle.bytes_recv() as f64)),
+            ("throttled_ms", JsonNum(throttle.throttled_ms() as f64)),
+        ]);
+
+        let compression = self.p2p().compression();
+        let compression = json_map([
+            ("messages", JsonNum(compression.messages() as f64)),
+            ("bytes_saved", JsonNum(compression.bytes_saved() as f64)),
+        ]);
+
+        let result = json_map([
+            ("channels", JsonArray(channels)),
+            ("bandwidth", bandwidth),
+            ("compression", compression),
+            ("outbound_slots", JsonArray(slots)),
+            ("scores", JsonArray(peer_scores)),
+        ]);
         JsonResponse::new(result, id).into()
     }
 
+    // RPCAPI:
+    // Initializes a subscription to P2P connectivity events: channels getting
+    // connected and disconnected. Unlike `dnet.subscribe_events`, these are
+    // always published and don't require enabling dnet.
+    //
+    // --> {"jsonrpc": "2.0", "method": "p2p.subscribe_events", "params": [], "id": 42}
+    // <-- {"jsonrpc": "2.0", "method": "p2p.subscribe_events", "params": [`event`]}
+    async fn p2p_subscribe_events(&self, id: u16, params: JsonValue) -> JsonResult {
+        let Some(subscriber) = self.p2p_events() else {
+            return JsonError::new(ErrorCode::MethodNotFound, None, id).into()
+        };
+
+        let Some(params) = params.get::<Vec<JsonValue>>() else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+        if !params.is_empty() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        subscriber.into()
+    }
+
     fn p2p(&self) -> net::P2pPtr;
+
+    /// Subscriber fed by [`forward_p2p_events`], if the daemon serves
+    /// `p2p.subscribe_events`
+    fn p2p_events(&self) -> Option<JsonSubscriber> {
+        None
+    }
 }
@@ -16,11 +16,19 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{collections::HashSet, io::ErrorKind, sync::Arc};
+use std::{
+    collections::HashSet,
+    fs::OpenOptions,
+    io::{ErrorKind, Write},
+    sync::{Arc, LazyLock},
+    time::Instant,
+};
 
 use async_trait::async_trait;
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
+use rand::{rngs::OsRng, RngCore};
 use smol::{
+    future::FutureExt,
     io::{BufReader, ReadHalf, WriteHalf},
     lock::{Mutex, MutexGuard},
 };
@@ -30,14 +38,16 @@ use url::Url;
 use super::{
     common::{
         http_read_from_stream_request, http_write_to_stream, read_from_stream, write_to_stream,
-        INIT_BUF_SIZE,
+        ws_transport_url, INIT_BUF_SIZE,
     },
+    health::STARTED,
     jsonrpc::*,
-    settings::RpcSettings,
+    settings::{PermissionClass, RpcSettings},
 };
 use crate::{
-    net::transport::{Listener, PtListener, PtStream},
-    system::{StoppableTask, StoppableTaskPtr},
+    net::transport::{ws::WsStream, Listener, PtListener, PtStream},
+    system::{metrics::DEFAULT_BUCKETS, StoppableTask, StoppableTaskPtr, METRICS},
+    util::{logger::set_target_level, path::expand_path},
     Error, Result,
 };
 
@@ -46,10 +56,47 @@ use crate::{
 pub trait RequestHandler<T>: Sync + Send {
     async fn handle_request(&self, req: JsonRequest) -> JsonResult;
 
+    /// Handle a request along with the address of the peer that sent it.
+    /// Handlers needing the peer address, e.g. for rate limiting, can
+    /// override this. Note that behind a reverse proxy, this is the
+    /// address of the proxy.
+    async fn handle_request_from(&self, req: JsonRequest, _peer: &Url) -> JsonResult {
+        self.handle_request(req).await
+    }
+
     async fn pong(&self, id: u16, _params: JsonValue) -> JsonResult {
         JsonResponse::new(JsonValue::String("pong".to_string()), id).into()
     }
 
+    // RPCAPI:
+    // Sets the log level of a target and the targets nested under it.
+    // The `*` target sets the default level. Valid levels are `off`,
+    // `error`, `warn`, `info`, `debug` and `trace`.
+    //
+    // --> {"jsonrpc": "2.0", "method": "log.set_level", "params": ["net::channel", "debug"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": true, "id": 1}
+    async fn log_set_level(&self, id: u16, params: JsonValue) -> JsonResult {
+        let Some(params) = params.get::<Vec<JsonValue>>() else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+        if params.len() != 2 || !params[0].is_string() || !params[1].is_string() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let target = params[0].get::<String>().unwrap();
+        let Ok(level) = params[1].get::<String>().unwrap().parse() else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+
+        if let Err(e) = set_target_level(target, level) {
+            error!(target: "rpc::server", "[RPC] Failed setting log level: {e}");
+            return JsonError::new(ErrorCode::InternalError, None, id).into()
+        }
+
+        info!(target: "rpc::server", "[RPC] Set log level of {target} to {level}");
+        JsonResponse::new(JsonValue::Boolean(true), id).into()
+    }
+
     async fn connections_mut(&self) -> MutexGuard<'life0, HashSet<StoppableTaskPtr>>;
 
     async fn connections(&self) -> Vec<StoppableTaskPtr> {
@@ -77,6 +124,174 @@ pub trait RequestHandler<T>: Sync + Send {
     }
 }
 
+/// Auxiliary function to pass a request to the [`RequestHandler`],
+/// unless its method is disabled or needs more permissions than the
+/// connection was granted.
+async fn dispatch_request<T>(
+    rh: &Arc<impl RequestHandler<T> + 'static>,
+    settings: &RpcSettings,
+    granted: PermissionClass,
+    peer: &Url,
+    req: JsonRequest,
+) -> JsonResult {
+    if settings.is_method_disabled(&req.method) {
+        debug!(target: "rpc::server", "RPC method {} is disabled", req.method);
+        return JsonError::new(ErrorCode::MethodNotFound, None, req.id).into()
+    }
+
+    let required = settings.method_permission(&req.method);
+    if required > granted {
+        debug!(
+            target: "rpc::server",
+            "RPC method {} requires {} permission, connection has {}",
+            req.method, required.name(), granted.name(),
+        );
+        return JsonError::new(ErrorCode::Unauthorized, None, req.id).into()
+    }
+
+    let method = req.method.clone();
+    let start = Instant::now();
+    let rep = rh.handle_request_from(req, peer).await;
+
+    // Unknown methods are not recorded, so clients can't grow the label set
+    if !matches!(&rep, JsonResult::Error(e) if e.error.code == ErrorCode::MethodNotFound.code()) {
+        METRICS
+            .histogram(
+                "darkfi_rpc_request_duration_seconds",
+                "JSON-RPC request handling time, by method",
+                &[("method", &method)],
+                DEFAULT_BUCKETS,
+            )
+            .observe_duration(start.elapsed());
+    }
+
+    rep
+}
+
+/// Auxiliary function returning the permission class granted by the
+/// given token, if it is valid.
+fn token_class(settings: &RpcSettings, token: &str) -> Option<PermissionClass> {
+    // Without authentication everything is allowed anyway
+    if !settings.auth_enabled() {
+        return Some(PermissionClass::Admin)
+    }
+
+    settings.token_permission(token)
+}
+
+/// Auxiliary function to handle an `auth` request, carrying the token as
+/// its single parameter. Returns the reply, along with the permission
+/// class the connection got granted, if the token was valid.
+fn handle_auth_request(
+    settings: &RpcSettings,
+    req: &JsonRequest,
+) -> (JsonResult, Option<PermissionClass>) {
+    let token = match req.params.get::<Vec<JsonValue>>() {
+        Some(params) if params.len() == 1 => params[0].get::<String>(),
+        _ => None,
+    };
+    let Some(token) = token else {
+        return (JsonError::new(ErrorCode::InvalidParams, None, req.id).into(), None)
+    };
+
+    match token_class(settings, token) {
+        Some(class) => (
+            JsonResponse::new(JsonValue::String(class.name().to_string()), req.id).into(),
+            Some(class),
+        ),
+        None => (JsonError::new(ErrorCode::Unauthorized, None, req.id).into(), None),
+    }
+}
+
+/// Auxiliary function to write a random `admin` token to the cookie file
+/// at the given path, only readable by the current user.
+fn write_auth_cookie(path: &str) -> Result<String> {
+    let path = expand_path(path)?;
+
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let token: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+
+    let mut opts = OpenOptions::new();
+    opts.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        opts.mode(0o600);
+        // The mode only applies to new files
+        if path.exists() {
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        }
+    }
+
+    let mut file = opts.open(&path)?;
+    file.write_all(token.as_bytes())?;
+
+    Ok(token)
+}
+
+/// Auxiliary function to write a reply to the stream.
+async fn write_reply(
+    writer: &Arc<Mutex<WriteHalf<Box<dyn PtStream>>>>,
+    settings: &RpcSettings,
+    rep: &JsonResult,
+) -> Result<()> {
+    let mut writer_lock = writer.lock().await;
+    if settings.use_http() {
+        http_write_to_stream(&mut writer_lock, rep, None).await?;
+    } else {
+        write_to_stream(&mut writer_lock, rep).await?;
+    }
+
+    Ok(())
+}
+
+/// Auxiliary function to handle a batch of requests in the background.
+/// Requests are handled in order, and their replies are written back
+/// as a single array. Invalid items and subscriptions get an error in
+/// their place, while an empty batch gets a single error.
+async fn handle_batch_request<T>(
+    writer: Arc<Mutex<WriteHalf<Box<dyn PtStream>>>>,
+    addr: Url,
+    rh: Arc<impl RequestHandler<T> + 'static>,
+    settings: RpcSettings,
+    granted: PermissionClass,
+    items: Vec<JsonValue>,
+) -> Result<()> {
+    let mut reps = Vec::with_capacity(items.len());
+    for item in &items {
+        let req = match JsonRequest::try_from(item) {
+            Ok(v) => v,
+            Err(e) => {
+                debug!(target: "rpc::server", "Invalid batch item from {addr}: {e}");
+                reps.push(JsonError::new(ErrorCode::InvalidRequest, None, 0).into());
+                continue
+            }
+        };
+
+        let id = req.id;
+        let rep = match dispatch_request(&rh, &settings, granted, &addr, req).await {
+            JsonResult::Subscriber(_) | JsonResult::SubscriberWithReply(_, _) => JsonError::new(
+                ErrorCode::InvalidRequest,
+                Some("subscriptions can't be batched".to_string()),
+                id,
+            )
+            .into(),
+            rep => rep,
+        };
+        reps.push(rep);
+    }
+
+    let rep = if reps.is_empty() {
+        JsonError::new(ErrorCode::InvalidRequest, None, 0).into()
+    } else {
+        debug!(target: "rpc::server", "{addr} <-- {}", JsonResult::stringify_batch(&reps)?);
+        JsonResult::Batch(reps)
+    };
+
+    write_reply(&writer, &settings, &rep).await
+}
+
 /// Auxiliary function to handle a request in the background.
 async fn handle_request<T>(
     writer: Arc<Mutex<WriteHalf<Box<dyn PtStream>>>>,
@@ -85,15 +300,10 @@ async fn handle_request<T>(
     ex: Arc<smol::Executor<'_>>,
     tasks: Arc<Mutex<HashSet<Arc<StoppableTask>>>>,
     settings: RpcSettings,
+    granted: PermissionClass,
     req: JsonRequest,
 ) -> Result<()> {
-    // Handle disabled RPC methods
-    let rep = if settings.is_method_disabled(&req.method) {
-        debug!(target: "rpc::server", "RPC method {} is disabled", req.method);
-        JsonError::new(ErrorCode::MethodNotFound, None, req.id).into()
-    } else {
-        rh.handle_request(req).await
-    };
+    let rep = dispatch_request(&rh, &settings, granted, &addr, req).await;
 
     match rep {
         JsonResult::Subscriber(subscriber) => {
@@ -122,7 +332,7 @@ async fn handle_request<T>(
 
                         #[allow(clippy::collapsible_else_if)]
                         if settings.use_http() {
-                            if let Err(e) = http_write_to_stream(&mut writer_lock, &notification).await {
+                            if let Err(e) = http_write_to_stream(&mut writer_lock, &notification, None).await {
                                 subscription.unsubscribe().await;
                                 return Err(e.into())
                             }
@@ -156,7 +366,7 @@ async fn handle_request<T>(
             debug!(target: "rpc::server", "{addr} <-- {}", reply.stringify()?);
             let mut writer_lock = writer.lock().await;
             if settings.use_http() {
-                http_write_to_stream(&mut writer_lock, &reply.into()).await?;
+                http_write_to_stream(&mut writer_lock, &reply.into(), None).await?;
             } else {
                 write_to_stream(&mut writer_lock, &reply.into()).await?;
             }
@@ -185,7 +395,7 @@ async fn handle_request<T>(
                         let mut writer_lock = writer_.lock().await;
                         #[allow(clippy::collapsible_else_if)]
                         if settings.use_http() {
-                            if let Err(e) = http_write_to_stream(&mut writer_lock, &notification).await {
+                            if let Err(e) = http_write_to_stream(&mut writer_lock, &notification, None).await {
                                 subscription.unsubscribe().await;
                                 drop(writer_lock);
                                 return Err(e.into())
@@ -215,30 +425,18 @@ async fn handle_request<T>(
             tasks.lock().await.insert(task);
         }
 
-        JsonResult::Request(_) | JsonResult::Notification(_) => {
+        JsonResult::Request(_) | JsonResult::Notification(_) | JsonResult::Batch(_) => {
             unreachable!("Should never happen")
         }
 
         JsonResult::Response(ref v) => {
             debug!(target: "rpc::server", "{addr} <-- {}", v.stringify()?);
-            let mut writer_lock = writer.lock().await;
-            if settings.use_http() {
-                http_write_to_stream(&mut writer_lock, &rep).await?;
-            } else {
-                write_to_stream(&mut writer_lock, &rep).await?;
-            }
-            drop(writer_lock);
+            write_reply(&writer, &settings, &rep).await?;
         }
 
         JsonResult::Error(ref v) => {
             debug!(target: "rpc::server", "{addr} <-- {}", v.stringify()?);
-            let mut writer_lock = writer.lock().await;
-            if settings.use_http() {
-                http_write_to_stream(&mut writer_lock, &rep).await?;
-            } else {
-                write_to_stream(&mut writer_lock, &rep).await?;
-            }
-            drop(writer_lock);
+            write_reply(&writer, &settings, &rep).await?;
         }
     }
 
@@ -272,17 +470,37 @@ pub async fn accept<'a, T: 'a>(
     // We'll hold our background tasks here
     let tasks = Arc::new(Mutex::new(HashSet::new()));
 
+    // Permission class granted to this connection
+    let mut granted = settings.initial_permission();
+
     loop {
         let mut buf = Vec::with_capacity(INIT_BUF_SIZE);
 
         let mut reader_lock = reader.lock().await;
-        if settings.use_http() {
-            let _ = http_read_from_stream_request(&mut reader_lock, &mut buf).await?;
+        let bearer = if settings.use_http() {
+            http_read_from_stream_request(&mut reader_lock, &mut buf).await?.1
         } else {
             let _ = read_from_stream(&mut reader_lock, &mut buf).await?;
-        }
+            None
+        };
         drop(reader_lock);
 
+        // HTTP clients usually open a connection per request, so they
+        // authenticate with an `Authorization: Bearer` header instead
+        // of calling `auth`. Invalid tokens get the connection closed.
+        if let Some(token) = bearer {
+            let Some(class) = token_class(&settings, &token) else {
+                warn!(
+                    target: "rpc::server::accept()",
+                    "[RPC SERVER] Bearer authentication from {addr} failed"
+                );
+                let rep = JsonError::new(ErrorCode::Unauthorized, None, 0).into();
+                write_reply(&writer, &settings, &rep).await?;
+                return Err(Error::RpcUnauthorized)
+            };
+            granted = class;
+        }
+
         let line = match String::from_utf8(buf) {
             Ok(v) => v,
             Err(e) => {
@@ -306,20 +524,62 @@ pub async fn accept<'a, T: 'a>(
             }
         };
 
-        // Cast to JsonRequest
-        let req = match JsonRequest::try_from(&val) {
-            Ok(v) => v,
-            Err(e) => {
-                error!(
-                    target: "rpc::server::accept()",
-                    "[RPC SERVER] Failed casting JSON to a JsonRequest: {e}"
-                );
-                return Err(e.into())
+        debug!(target: "rpc::server", "{addr} --> {}", val.stringify()?);
+
+        // Batches are handled as a whole, otherwise cast to JsonRequest
+        let handler = match val.get::<Vec<JsonValue>>() {
+            Some(items) => handle_batch_request(
+                writer.clone(),
+                addr.clone(),
+                rh.clone(),
+                settings.clone(),
+                granted,
+                items.clone(),
+            )
+            .boxed(),
+            None => {
+                let req = match JsonRequest::try_from(&val) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        error!(
+                            target: "rpc::server::accept()",
+                            "[RPC SERVER] Failed casting JSON to a JsonRequest: {e}"
+                        );
+                        return Err(e.into())
+                    }
+                };
+
+                // Authentication changes the permissions of the requests
+                // following it, so we handle it in place. Connections
+                // failing to authenticate get closed.
+                if req.method == "auth" {
+                    let (rep, class) = handle_auth_request(&settings, &req);
+                    write_reply(&writer, &settings, &rep).await?;
+                    let Some(class) = class else {
+                        warn!(
+                            target: "rpc::server::accept()",
+                            "[RPC SERVER] Authentication from {addr} failed"
+                        );
+                        return Err(Error::RpcUnauthorized)
+                    };
+                    granted = class;
+                    continue
+                }
+
+                handle_request(
+                    writer.clone(),
+                    addr.clone(),
+                    rh.clone(),
+                    ex.clone(),
+                    tasks.clone(),
+                    settings.clone(),
+                    granted,
+                    req,
+                )
+                .boxed()
             }
         };
 
-        debug!(target: "rpc::server", "{addr} --> {}", val.stringify()?);
-
         // Create a new task to handle request in the background
         let task = StoppableTask::new();
 
@@ -329,15 +589,7 @@ pub async fn accept<'a, T: 'a>(
 
         // Detach the task
         task.clone().start(
-            handle_request(
-                writer.clone(),
-                addr.clone(),
-                rh.clone(),
-                ex.clone(),
-                tasks.clone(),
-                settings.clone(),
-                req,
-            ),
+            handler,
             move |_| async move {
                 debug!(
                     target: "rpc::server",
@@ -354,6 +606,29 @@ pub async fn accept<'a, T: 'a>(
     }
 }
 
+/// Auxiliary function to run [`accept()`] on an incoming connection,
+/// performing the WebSocket handshake first if the server uses it.
+async fn serve_conn<'a, T: 'a>(
+    stream: Box<dyn PtStream>,
+    addr: Url,
+    rh: Arc<impl RequestHandler<T> + 'static>,
+    conn_limit: Option<usize>,
+    settings: RpcSettings,
+    ex: Arc<smol::Executor<'a>>,
+) -> Result<()> {
+    let stream: Box<dyn PtStream> = if settings.use_ws() {
+        Box::new(WsStream::accept(stream, &settings.allowed_origins).await?.with_messages())
+    } else {
+        stream
+    };
+
+    let (reader, writer) = smol::io::split(stream);
+    let reader = Arc::new(Mutex::new(BufReader::new(reader)));
+    let writer = Arc::new(Mutex::new(writer));
+
+    accept(reader, writer, addr, rh, conn_limit, settings, ex).await
+}
+
 /// Wrapper function around [`accept()`] to take the incoming connection and
 /// pass it forward.
 async fn run_accept_loop<'a, T: 'a>(
@@ -369,23 +644,11 @@ async fn run_accept_loop<'a, T: 'a>(
                 let rh_ = rh.clone();
                 info!(target: "rpc::server", "[RPC] Server accepted conn from {url}");
 
-                let (reader, writer) = smol::io::split(stream);
-                let reader = Arc::new(Mutex::new(BufReader::new(reader)));
-                let writer = Arc::new(Mutex::new(writer));
-
                 let task = StoppableTask::new();
                 let task_ = task.clone();
                 let ex_ = ex.clone();
                 task.clone().start(
-                    accept(
-                        reader,
-                        writer,
-                        url.clone(),
-                        rh.clone(),
-                        conn_limit,
-                        settings.clone(),
-                        ex_,
-                    ),
+                    serve_conn(stream, url.clone(), rh.clone(), conn_limit, settings.clone(), ex_),
                     |_| async move {
                         info!(target: "rpc::server", "[RPC] Closed conn from {url}");
                         rh_.clone().unmark_connection(task_.clone()).await;
@@ -437,8 +700,18 @@ async fn run_accept_loop<'a, T: 'a>(
 ///
 /// The supported network schemes can be prefixed with `http+` to serve
 /// JSON-RPC over HTTP/1.1.
+///
+/// With the `ws` and `wss` schemes, JSON-RPC is served over WebSocket,
+/// with every request, reply and subscription notification carried in
+/// its own text frame. `wss` uses the same self-signed certificate as
+/// `tcp+tls`, so browsers should rather reach `ws` through a proxy.
+///
+/// When authentication is configured, connections start with the `public`
+/// permission class, and call the `auth` method with a token to be granted
+/// its class. HTTP clients can instead send the token in an
+/// `Authorization: Bearer` header. An invalid token closes the connection.
 pub async fn listen_and_serve<'a, T: 'a>(
-    settings: RpcSettings,
+    mut settings: RpcSettings,
     rh: Arc<impl RequestHandler<T> + 'static>,
     conn_limit: Option<usize>,
     ex: Arc<smol::Executor<'a>>,
@@ -450,8 +723,19 @@ pub async fn listen_and_serve<'a, T: 'a>(
         let url_str = settings.listen.as_str().replace(settings.listen.scheme(), scheme);
         listen_url = url_str.parse()?;
     }
+    if let Some(url) = ws_transport_url(&settings.listen)? {
+        listen_url = url;
+    }
 
-    let listener = Listener::new(listen_url, None).await?.listen().await?;
+    let listener = Listener::new(listen_url, None, None).await?.listen().await?;
+    LazyLock::force(&STARTED);
+
+    // Write a fresh cookie token, so local clients can authenticate
+    if let Some(ref cookie) = settings.auth_cookie {
+        let token = write_auth_cookie(cookie)?;
+        info!(target: "rpc::server", "[RPC] Wrote authentication cookie to {cookie}");
+        settings.auth_tokens.push((token, PermissionClass::Admin));
+    }
 
     run_accept_loop(listener, rh, conn_limit, settings, ex.clone()).await
 }
@@ -460,7 +744,11 @@ pub async fn listen_and_serve<'a, T: 'a>(
 mod tests {
     use super::*;
     use crate::{rpc::client::RpcClient, system::msleep};
-    use smol::{net::TcpListener, Executor};
+    use smol::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+        Executor,
+    };
 
     struct RpcServer {
         rpc_connections: Mutex<HashSet<StoppableTaskPtr>>,
@@ -471,7 +759,7 @@ mod tests {
         async fn handle_request(&self, req: JsonRequest) -> JsonResult {
             match req.method.as_str() {
                 "ping" => return self.pong(req.id, req.params).await,
-                _ => panic!(),
+                _ => JsonError::new(ErrorCode::MethodNotFound, None, req.id).into(),
             }
         }
 
@@ -495,7 +783,7 @@ mod tests {
             let sockaddr = listener.local_addr()?;
             let settings = RpcSettings {
                 listen: Url::parse(&format!("tcp://127.0.0.1:{}", sockaddr.port()))?,
-                disabled_methods: vec![],
+                ..RpcSettings::default()
             };
             drop(listener);
 
@@ -555,4 +843,257 @@ mod tests {
             Ok(())
         }))
     }
+
+    #[test]
+    fn batch_request() -> Result<()> {
+        let executor = Arc::new(Executor::new());
+
+        smol::block_on(executor.run(async {
+            // Find an available port
+            let listener = TcpListener::bind("127.0.0.1:0").await?;
+            let sockaddr = listener.local_addr()?;
+            let settings = RpcSettings {
+                listen: Url::parse(&format!("tcp://127.0.0.1:{}", sockaddr.port()))?,
+                disabled_methods: vec!["disabled".to_string()],
+                ..RpcSettings::default()
+            };
+            drop(listener);
+
+            let rpc_server = Arc::new(RpcServer { rpc_connections: Mutex::new(HashSet::new()) });
+
+            let server_task = StoppableTask::new();
+            server_task.clone().start(
+                listen_and_serve(settings.clone(), rpc_server.clone(), None, executor.clone()),
+                |_| async move {},
+                Error::RpcServerStopped,
+                executor.clone(),
+            );
+
+            // Let the server spawn
+            msleep(500).await;
+
+            let rpc_client = RpcClient::new(settings.listen.clone(), executor.clone()).await?;
+            let params = JsonValue::Array(vec![]);
+            let reqs = vec![
+                JsonRequest::new("ping", params.clone()),
+                JsonRequest::new("unknown", params.clone()),
+                JsonRequest::new("disabled", params.clone()),
+                JsonRequest::new("ping", params.clone()),
+            ];
+
+            // Replies keep the requests order, with per-request errors
+            let reps = rpc_client.batch_request(reqs).await?;
+            assert_eq!(reps.len(), 4);
+            assert_eq!(reps[0].as_ref().unwrap(), &JsonValue::String("pong".to_string()));
+            assert!(reps[1].is_err());
+            assert!(reps[2].is_err());
+            assert_eq!(reps[3].as_ref().unwrap(), &JsonValue::String("pong".to_string()));
+
+            // Single requests still work on the same connection
+            let rep = rpc_client.request(JsonRequest::new("ping", params)).await?;
+            assert_eq!(rep, JsonValue::String("pong".to_string()));
+
+            rpc_client.stop().await;
+            server_task.stop().await;
+
+            Ok(())
+        }))
+    }
+
+    #[test]
+    fn authentication() -> Result<()> {
+        let executor = Arc::new(Executor::new());
+
+        smol::block_on(executor.run(async {
+            // Find an available port
+            let listener = TcpListener::bind("127.0.0.1:0").await?;
+            let sockaddr = listener.local_addr()?;
+            let settings = RpcSettings {
+                listen: Url::parse(&format!("tcp://127.0.0.1:{}", sockaddr.port()))?,
+                auth_tokens: vec![("readtoken".to_string(), PermissionClass::Read)],
+                method_permissions: vec![("p*".to_string(), PermissionClass::Read)],
+                ..RpcSettings::default()
+            };
+            drop(listener);
+
+            let rpc_server = Arc::new(RpcServer { rpc_connections: Mutex::new(HashSet::new()) });
+
+            let server_task = StoppableTask::new();
+            server_task.clone().start(
+                listen_and_serve(settings.clone(), rpc_server.clone(), None, executor.clone()),
+                |_| async move {},
+                Error::RpcServerStopped,
+                executor.clone(),
+            );
+
+            // Let the server spawn
+            msleep(500).await;
+
+            let params = JsonValue::Array(vec![]);
+
+            // Unauthenticated connections can only call public methods
+            let rpc_client = RpcClient::new(settings.listen.clone(), executor.clone()).await?;
+            assert!(rpc_client.request(JsonRequest::new("ping", params.clone())).await.is_err());
+
+            // Authenticating grants the token class
+            assert_eq!(rpc_client.auth("readtoken").await?, "read");
+            let rep = rpc_client.request(JsonRequest::new("ping", params.clone())).await?;
+            assert_eq!(rep, JsonValue::String("pong".to_string()));
+
+            // Methods not in the settings need the admin class
+            assert!(rpc_client.request(JsonRequest::new("unknown", params)).await.is_err());
+            rpc_client.stop().await;
+
+            // Invalid tokens get rejected
+            let rpc_client = RpcClient::new(settings.listen.clone(), executor.clone()).await?;
+            assert!(rpc_client.auth("wrongtoken").await.is_err());
+            rpc_client.stop().await;
+
+            // Clients can authenticate right when connecting
+            let rpc_client = RpcClient::with_token(
+                settings.listen.clone(),
+                Some("readtoken".to_string()),
+                executor.clone(),
+            )
+            .await?;
+            let rep = rpc_client.request(JsonRequest::new("ping", params.clone())).await?;
+            assert_eq!(rep, JsonValue::String("pong".to_string()));
+            rpc_client.stop().await;
+
+            let token = Some("wrongtoken".to_string());
+            assert!(RpcClient::with_token(settings.listen.clone(), token, executor.clone())
+                .await
+                .is_err());
+
+            server_task.stop().await;
+
+            Ok(())
+        }))
+    }
+
+    #[test]
+    fn http_bearer_authentication() -> Result<()> {
+        let executor = Arc::new(Executor::new());
+
+        smol::block_on(executor.run(async {
+            // Find an available port
+            let listener = TcpListener::bind("127.0.0.1:0").await?;
+            let sockaddr = listener.local_addr()?;
+            let settings = RpcSettings {
+                listen: Url::parse(&format!("http+tcp://127.0.0.1:{}", sockaddr.port()))?,
+                auth_tokens: vec![("readtoken".to_string(), PermissionClass::Read)],
+                method_permissions: vec![("p*".to_string(), PermissionClass::Read)],
+                ..RpcSettings::default()
+            };
+            drop(listener);
+
+            let rpc_server = Arc::new(RpcServer { rpc_connections: Mutex::new(HashSet::new()) });
+
+            let server_task = StoppableTask::new();
+            server_task.clone().start(
+                listen_and_serve(settings.clone(), rpc_server.clone(), None, executor.clone()),
+                |_| async move {},
+                Error::RpcServerStopped,
+                executor.clone(),
+            );
+
+            // Let the server spawn
+            msleep(500).await;
+
+            let params = JsonValue::Array(vec![]);
+
+            // Every request carries the bearer token
+            let rpc_client = RpcClient::with_token(
+                settings.listen.clone(),
+                Some("readtoken".to_string()),
+                executor.clone(),
+            )
+            .await?;
+            let rep = rpc_client.request(JsonRequest::new("ping", params.clone())).await?;
+            assert_eq!(rep, JsonValue::String("pong".to_string()));
+            let rep = rpc_client.request(JsonRequest::new("ping", params.clone())).await?;
+            assert_eq!(rep, JsonValue::String("pong".to_string()));
+            rpc_client.stop().await;
+
+            // Plain requests without the header only get the public class
+            let body = JsonRequest::new("ping", params).stringify()?;
+            let request = |auth: &str| {
+                format!(
+                    "POST /json_rpc HTTP/1.1\r\n{auth}Content-Length: {}\r\n\r\n{body}",
+                    body.len()
+                )
+            };
+
+            let mut stream = TcpStream::connect(sockaddr).await?;
+            stream.write_all(request("").as_bytes()).await?;
+            let mut buf = vec![0u8; 1024];
+            let n = stream.read(&mut buf).await?;
+            assert!(buf[..n].starts_with(b"HTTP/1.1 400"));
+
+            // Invalid tokens get rejected
+            let mut stream = TcpStream::connect(sockaddr).await?;
+            stream.write_all(request("Authorization: Bearer wrongtoken\r\n").as_bytes()).await?;
+            let mut buf = vec![];
+            stream.read_to_end(&mut buf).await?;
+            assert!(buf.starts_with(b"HTTP/1.1 400"));
+
+            server_task.stop().await;
+
+            Ok(())
+        }))
+    }
+
+    #[test]
+    fn websocket() -> Result<()> {
+        let executor = Arc::new(Executor::new());
+
+        smol::block_on(executor.run(async {
+            // Find an available port
+            let listener = TcpListener::bind("127.0.0.1:0").await?;
+            let sockaddr = listener.local_addr()?;
+            let settings = RpcSettings {
+                listen: Url::parse(&format!("ws://127.0.0.1:{}/rpc", sockaddr.port()))?,
+                ..RpcSettings::default()
+            };
+            drop(listener);
+
+            let rpc_server = Arc::new(RpcServer { rpc_connections: Mutex::new(HashSet::new()) });
+
+            let server_task = StoppableTask::new();
+            server_task.clone().start(
+                listen_and_serve(settings.clone(), rpc_server.clone(), None, executor.clone()),
+                |_| async move {},
+                Error::RpcServerStopped,
+                executor.clone(),
+            );
+
+            // Let the server spawn
+            msleep(500).await;
+
+            let rpc_client = RpcClient::new(settings.listen.clone(), executor.clone()).await?;
+            let params = JsonValue::Array(vec![]);
+
+            let rep = rpc_client.request(JsonRequest::new("ping", params.clone())).await?;
+            assert_eq!(rep, JsonValue::String("pong".to_string()));
+
+            let reqs =
+                vec![JsonRequest::new("ping", params.clone()), JsonRequest::new("unknown", params)];
+            let reps = rpc_client.batch_request(reqs).await?;
+            assert_eq!(reps[0].as_ref().unwrap(), &JsonValue::String("pong".to_string()));
+            assert!(reps[1].is_err());
+
+            rpc_client.stop().await;
+
+            // Requests without the Upgrade handshake get refused
+            let mut stream = TcpStream::connect(sockaddr).await?;
+            stream.write_all(b"GET /rpc HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n").await?;
+            let mut buf = vec![];
+            stream.read_to_end(&mut buf).await?;
+            assert!(buf.starts_with(b"HTTP/1.1 400"));
+
+            server_task.stop().await;
+
+            Ok(())
+        }))
+    }
 }
@@ -16,13 +16,101 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::str::FromStr;
+
+use log::warn;
 use structopt::StructOpt;
 use url::Url;
 
+use crate::{Error, Result};
+
+/// Permission classes of JSON-RPC methods, from least to most privileged.
+/// A connection can call the methods whose class is up to the one it was
+/// granted.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PermissionClass {
+    /// Methods safe to expose to anyone
+    Public,
+    /// Methods reading node state
+    Read,
+    /// Methods submitting data or handling wallet operations
+    Wallet,
+    /// Methods managing the daemon
+    Admin,
+}
+
+impl PermissionClass {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Public => "public",
+            Self::Read => "read",
+            Self::Wallet => "wallet",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+impl FromStr for PermissionClass {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "public" => Ok(Self::Public),
+            "read" => Ok(Self::Read),
+            "wallet" => Ok(Self::Wallet),
+            "admin" => Ok(Self::Admin),
+            _ => Err(Error::ParseFailed("Invalid JSON-RPC permission class")),
+        }
+    }
+}
+
+/// Auxiliary function to parse a `key:class` settings entry.
+/// Invalid entries are skipped, so they can't grant any access.
+fn parse_class_entries(
+    entries: Option<Vec<String>>,
+    setting: &str,
+) -> Vec<(String, PermissionClass)> {
+    let mut ret = vec![];
+    for entry in entries.unwrap_or_default() {
+        let parsed = entry
+            .rsplit_once(':')
+            .filter(|(key, _)| !key.is_empty())
+            .and_then(|(key, class)| Some((key.to_string(), class.parse().ok()?)));
+        match parsed {
+            Some(v) => ret.push(v),
+            None => {
+                warn!(target: "rpc::settings", "[RPC] Skipping invalid {setting} entry: {entry}")
+            }
+        }
+    }
+    ret
+}
+
+/// Auxiliary function to compare two strings in constant time,
+/// with respect to their contents.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    if a.len() != b.len() {
+        return false
+    }
+    a.bytes().zip(b.bytes()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
 #[derive(Clone)]
 pub struct RpcSettings {
     pub listen: Url,
     pub disabled_methods: Vec<String>,
+    /// Tokens and the permission class connections authenticating
+    /// with them get granted
+    pub auth_tokens: Vec<(String, PermissionClass)>,
+    /// Path of the cookie file a random `admin` token gets written to
+    /// when the server starts
+    pub auth_cookie: Option<String>,
+    /// Methods and their permission class. Methods ending with `*` match
+    /// by prefix, and unlisted methods are `admin`.
+    pub method_permissions: Vec<(String, PermissionClass)>,
+    /// Web origins allowed to open WebSocket connections. Browsers
+    /// from any other origin get refused.
+    pub allowed_origins: Vec<String>,
 }
 
 impl RpcSettings {
@@ -32,11 +120,65 @@ impl RpcSettings {
     pub fn use_http(&self) -> bool {
         self.listen.scheme().starts_with("http+")
     }
+    pub fn use_ws(&self) -> bool {
+        matches!(self.listen.scheme(), "ws" | "wss")
+    }
+
+    /// Authentication is enabled when any token or cookie is configured.
+    /// Otherwise all connections can call every method, as before.
+    pub fn auth_enabled(&self) -> bool {
+        !self.auth_tokens.is_empty() || self.auth_cookie.is_some()
+    }
+
+    /// Permission class granted to new connections
+    pub fn initial_permission(&self) -> PermissionClass {
+        if self.auth_enabled() {
+            PermissionClass::Public
+        } else {
+            PermissionClass::Admin
+        }
+    }
+
+    /// Permission class granted by the given token, if it is valid
+    pub fn token_permission(&self, token: &str) -> Option<PermissionClass> {
+        let mut ret = None;
+        // Check all the tokens, to not leak which one matched
+        for (t, class) in &self.auth_tokens {
+            if constant_time_eq(t, token) {
+                ret = Some(*class);
+            }
+        }
+        ret
+    }
+
+    /// Permission class of the given method. Exact entries take
+    /// precedence, then the longest matching prefix entry.
+    pub fn method_permission(&self, method: &str) -> PermissionClass {
+        let mut best: Option<(usize, PermissionClass)> = None;
+        for (m, class) in &self.method_permissions {
+            if m == method {
+                return *class
+            }
+            if let Some(prefix) = m.strip_suffix('*') {
+                if method.starts_with(prefix) && best.is_none_or(|(len, _)| prefix.len() > len) {
+                    best = Some((prefix.len(), *class));
+                }
+            }
+        }
+        best.map_or(PermissionClass::Admin, |(_, class)| class)
+    }
 }
 
 impl Default for RpcSettings {
     fn default() -> Self {
-        Self { listen: Url::parse("tcp://127.0.0.1:22222").unwrap(), disabled_methods: vec![] }
+        Self {
+            listen: Url::parse("tcp://127.0.0.1:22222").unwrap(),
+            disabled_methods: vec![],
+            auth_tokens: vec![],
+            auth_cookie: None,
+            method_permissions: vec![],
+            allowed_origins: vec![],
+        }
     }
 }
 
@@ -52,6 +194,22 @@ pub struct RpcSettingsOpt {
     /// Disabled JSON-RPC methods
     #[structopt(long, use_delimiter = true)]
     pub rpc_disabled_methods: Option<Vec<String>>,
+
+    /// JSON-RPC authentication tokens, as `token:class` pairs
+    #[structopt(long, use_delimiter = true)]
+    pub rpc_auth_tokens: Option<Vec<String>>,
+
+    /// Path to write a random JSON-RPC `admin` authentication token to
+    #[structopt(long)]
+    pub rpc_auth_cookie: Option<String>,
+
+    /// JSON-RPC methods permission classes, as `method:class` pairs
+    #[structopt(long, use_delimiter = true)]
+    pub rpc_method_permissions: Option<Vec<String>>,
+
+    /// Web origins allowed to open JSON-RPC WebSocket connections
+    #[structopt(long, use_delimiter = true)]
+    pub rpc_allowed_origins: Option<Vec<String>>,
 }
 
 impl From<RpcSettingsOpt> for RpcSettings {
@@ -59,6 +217,13 @@ impl From<RpcSettingsOpt> for RpcSettings {
         Self {
             listen: opt.rpc_listen,
             disabled_methods: opt.rpc_disabled_methods.unwrap_or_default(),
+            auth_tokens: parse_class_entries(opt.rpc_auth_tokens, "rpc_auth_tokens"),
+            auth_cookie: opt.rpc_auth_cookie,
+            method_permissions: parse_class_entries(
+                opt.rpc_method_permissions,
+                "rpc_method_permissions",
+            ),
+            allowed_origins: opt.rpc_allowed_origins.unwrap_or_default(),
         }
     }
 }
@@ -28,6 +28,7 @@ use wasmer::{FunctionEnvMut, WasmPtr};
 
 use super::acl::acl_allow;
 use crate::{
+    blockchain::contract_store::ZkasRecord,
     runtime::vm_runtime::{ContractSection, Env},
     zk::{empty_witnesses, VerifyingKey, ZkCircuit},
     zkas::ZkBinary,
@@ -902,8 +903,9 @@ pub(crate) fn zkas_db_set(mut ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, ptr_le
     }
 
     // Check if there is existing bincode and compare it. Return DB_SUCCESS if
-    // they're the same. The assumption should be that VerifyingKey was generated
-    // already so we can skip things after this guard.
+    // they're the same and the record passes its integrity check, since the
+    // VerifyingKey was generated already and we can skip things after this guard.
+    // A corrupted record gets rebuilt.
     match env
         .blockchain
         .lock()
@@ -915,16 +917,35 @@ pub(crate) fn zkas_db_set(mut ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, ptr_le
     {
         Ok(v) => {
             if let Some(bytes) = v {
-                // We allow a panic here because this db should never be corrupted in this way.
-                let (existing_zkbin, _): (Vec<u8>, Vec<u8>) =
-                    deserialize(&bytes).expect("deserialize tuple");
-
-                if existing_zkbin == zkbin_bytes {
-                    debug!(
-                        target: "runtime::db::zkas_db_set",
-                        "[WASM] [{cid}] zkas_db_set(): Existing zkas bincode is the same. Skipping."
-                    );
-                    return wasm::entrypoint::SUCCESS
+                match deserialize::<ZkasRecord>(&bytes) {
+                    Ok(existing) if existing.zkbin == zkbin_bytes && existing.verify() => {
+                        debug!(
+                            target: "runtime::db::zkas_db_set",
+                            "[WASM] [{cid}] zkas_db_set(): Existing zkas bincode is the same. Skipping."
+                        );
+                        return wasm::entrypoint::SUCCESS
+                    }
+                    Ok(existing) if existing.zkbin == zkbin_bytes => {
+                        error!(
+                            target: "runtime::db::zkas_db_set",
+                            "[WASM] [{cid}] zkas_db_set(): Existing zkas record is corrupted, rebuilding"
+                        );
+                    }
+                    Ok(_) => {}
+                    // Records stored before checksums were introduced get
+                    // rewritten in the current format.
+                    Err(_) if ZkasRecord::from_bytes(&bytes).is_ok() => {
+                        info!(
+                            target: "runtime::db::zkas_db_set",
+                            "[WASM] [{cid}] zkas_db_set(): Upgrading legacy zkas record"
+                        );
+                    }
+                    Err(e) => {
+                        error!(
+                            target: "runtime::db::zkas_db_set",
+                            "[WASM] [{cid}] zkas_db_set(): Existing zkas record is malformed, rebuilding: {e}"
+                        );
+                    }
                 }
             }
         }
@@ -969,7 +990,7 @@ pub(crate) fn zkas_db_set(mut ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, ptr_le
 
     // Insert the key-value pair into the database.
     let key = serialize(&zkbin.namespace);
-    let value = serialize(&(zkbin_bytes, vk_buf));
+    let value = serialize(&ZkasRecord::new(zkbin_bytes, vk_buf));
     if env
         .blockchain
         .lock()
@@ -18,7 +18,10 @@
 
 use std::io::Cursor;
 
-use darkfi_sdk::wasm;
+use darkfi_sdk::{
+    blockchain::{block_epoch, BlockContext},
+    wasm,
+};
 use darkfi_serial::Decodable;
 use log::{debug, error};
 use wasmer::{FunctionEnvMut, WasmPtr};
@@ -393,6 +396,74 @@ pub(crate) fn get_last_block_height(mut ctx: FunctionEnvMut<Env>) -> i64 {
     (objects.len() - 1) as i64
 }
 
+/// Builds the [`BlockContext`] of the runtime configured verifying block
+/// height and copies it to the VM's object store.
+///
+/// The previous block is looked up by height rather than using the last
+/// block, so the context stays the same for mempool pre-validation and
+/// block verification. At genesis there is no previous block, so the
+/// timestamp and hash are zeroed.
+///
+/// On success, returns the index of the new object in the object store.
+/// Otherwise, returns an error code.
+///
+/// Permissions: deploy, metadata, exec
+pub(crate) fn get_block_context(mut ctx: FunctionEnvMut<Env>) -> i64 {
+    let (env, mut store) = ctx.data_and_store_mut();
+    let cid = &env.contract_id;
+
+    // Enforce function ACL
+    if let Err(e) =
+        acl_allow(env, &[ContractSection::Deploy, ContractSection::Metadata, ContractSection::Exec])
+    {
+        error!(
+            target: "runtime::util::get_block_context",
+            "[WASM] [{cid}] get_block_context(): Called in unauthorized section: {e}"
+        );
+        return darkfi_sdk::error::CALLER_ACCESS_DENIED
+    }
+
+    let height = env.verifying_block_height;
+    let (timestamp, previous) = if height == 0 {
+        (0, [0u8; 32])
+    } else {
+        // Grab the previous block header
+        let blockchain = env.blockchain.lock().unwrap();
+        let header = match blockchain.blocks.get_order(&[height - 1], true) {
+            Ok(hashes) => blockchain.headers.get(&[hashes[0].unwrap()], true),
+            Err(e) => Err(e),
+        };
+        match header {
+            Ok(headers) => {
+                let header = headers[0].as_ref().unwrap();
+                (header.timestamp.inner(), *header.hash().inner())
+            }
+            Err(e) => {
+                error!(
+                    target: "runtime::util::get_block_context",
+                    "[WASM] [{cid}] get_block_context(): Internal error getting previous block: {e}"
+                );
+                return darkfi_sdk::error::DB_GET_FAILED
+            }
+        }
+    };
+
+    let block_ctx = BlockContext { height, epoch: block_epoch(height), timestamp, previous };
+    let ret = darkfi_serial::serialize(&block_ctx);
+
+    // Subtract used gas. Here we count the size of the object.
+    env.subtract_gas(&mut store, ret.len() as u64);
+
+    // Copy Vec<u8> to the VM
+    let mut objects = env.objects.borrow_mut();
+    objects.push(ret);
+    if objects.len() > u32::MAX as usize {
+        return darkfi_sdk::error::DATA_TOO_LARGE
+    }
+
+    (objects.len() - 1) as i64
+}
+
 /// Reads a transaction by hash from the transactions store.
 ///
 /// This function can be called from the Exec or Metadata [`ContractSection`].
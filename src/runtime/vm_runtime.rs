@@ -326,6 +326,12 @@ impl Runtime {
                     import::util::get_last_block_height,
                 ),
 
+                "get_block_context_" => Function::new_typed_with_env(
+                    &mut store,
+                    &ctx,
+                    import::util::get_block_context,
+                ),
+
                 "get_tx_" => Function::new_typed_with_env(
                     &mut store,
                     &ctx,
@@ -0,0 +1,83 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use proc_macro_crate::{crate_name, FoundCrate};
+use quote::quote;
+use syn::{parse_macro_input, DeriveInput, Ident, LitInt};
+
+/// Implements `darkfi_sdk::crypto::note::EncryptableNote` for a type
+/// which already implements `Encodable` and `Decodable`.
+///
+/// ```ignore
+/// #[derive(SerialEncodable, SerialDecodable, EncryptableNote)]
+/// #[note(version = 1, max_size = 1024)]
+/// pub struct MyNote { .. }
+/// ```
+#[proc_macro_derive(EncryptableNote, attributes(note))]
+pub fn derive_encryptable_note(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let found_crate = crate_name("darkfi-sdk").expect("darkfi-sdk is found in Cargo.toml");
+    let cratename = match found_crate {
+        FoundCrate::Name(name) => Ident::new(&name, Span::call_site()),
+        FoundCrate::Itself => Ident::new("crate", Span::call_site()),
+    };
+
+    let mut version: Option<LitInt> = None;
+    let mut max_size: Option<LitInt> = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("note")) {
+        let res = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("version") {
+                version = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("max_size") {
+                max_size = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `version` or `max_size`"))
+            }
+        });
+        if let Err(e) = res {
+            return e.to_compile_error().into()
+        }
+    }
+
+    let Some(version) = version else {
+        return syn::Error::new_spanned(
+            &input.ident,
+            "EncryptableNote requires #[note(version = N)]",
+        )
+        .to_compile_error()
+        .into()
+    };
+
+    let max_size = max_size.map(|max_size| quote! { const MAX_NOTE_SIZE: usize = #max_size; });
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics #cratename::crypto::note::EncryptableNote for #name #ty_generics #where_clause {
+            const NOTE_VERSION: u8 = #version;
+            #max_size
+        }
+    }
+    .into()
+}
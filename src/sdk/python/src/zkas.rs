@@ -285,6 +285,7 @@ impl Proof {
             literals: Vec::new(),
             witnesses: Vec::new(),
             opcodes: Vec::new(),
+            debug_info: None,
         };
         let empty_circuit = zk::vm::ZkCircuit::new(Vec::new(), &zkbin);
         let empty_py_circuit = ZkCircuit(empty_circuit, Vec::new(), zkbin);
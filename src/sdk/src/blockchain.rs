@@ -16,6 +16,29 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+#[cfg(feature = "async")]
+use darkfi_serial::async_trait;
+use darkfi_serial::{SerialDecodable, SerialEncodable};
+
+/// Read-only context of the block a contract call is verified in.
+///
+/// Everything in here is derived from the verifying block height and the
+/// block right before it, so a call sees the same context whether it is
+/// pre-validated in the mempool or verified as part of a proposed block.
+/// This is what contracts should use for timelocks and expiries.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct BlockContext {
+    /// Height (slot) of the block being verified
+    pub height: u32,
+    /// Epoch of the block being verified, as given by [`block_epoch`]
+    pub epoch: u8,
+    /// Timestamp of the previous block, in seconds since UNIX epoch.
+    /// The verifying block's own timestamp is not known before it is mined.
+    pub timestamp: u64,
+    /// Hash of the previous block
+    pub previous: [u8; 32],
+}
+
 /// Auxiliary function to calculate provided block height block version.
 /// Currently, a single version(1) exists.
 pub fn block_version(_height: u32) -> u8 {
@@ -44,18 +44,27 @@ impl AeadEncryptedNote {
         note: &impl Encodable,
         public: &PublicKey,
         rng: &mut (impl CryptoRng + RngCore),
+    ) -> Result<Self, ContractError> {
+        let mut input = Vec::new();
+        note.encode(&mut input)?;
+        Self::encrypt_bytes(&input, public, rng)
+    }
+
+    /// Encrypt raw plaintext bytes, without any serialization
+    pub fn encrypt_bytes(
+        input: &[u8],
+        public: &PublicKey,
+        rng: &mut (impl CryptoRng + RngCore),
     ) -> Result<Self, ContractError> {
         let ephem_secret = SecretKey::random(rng);
         let ephem_public = PublicKey::from_secret(ephem_secret);
         let shared_secret = diffie_hellman::sapling_ka_agree(&ephem_secret, public)?;
         let key = diffie_hellman::kdf_sapling(&shared_secret, &ephem_public);
 
-        let mut input = Vec::new();
-        note.encode(&mut input)?;
         let input_len = input.len();
 
         let mut ciphertext = vec![0_u8; input_len + AEAD_TAG_SIZE];
-        ciphertext[..input_len].copy_from_slice(&input);
+        ciphertext[..input_len].copy_from_slice(input);
 
         ChaCha20Poly1305::new(key.as_ref().into())
             .encrypt_in_place([0u8; 12][..].into(), &[], &mut ciphertext)
@@ -65,10 +74,20 @@ impl AeadEncryptedNote {
     }
 
     pub fn decrypt<D: Decodable>(&self, secret: &SecretKey) -> Result<D, ContractError> {
+        let plaintext = self.decrypt_bytes(secret)?;
+        let mut cursor = Cursor::new(&plaintext);
+        Ok(D::decode(&mut cursor)?)
+    }
+
+    /// Decrypt into the raw plaintext bytes, without any deserialization
+    pub fn decrypt_bytes(&self, secret: &SecretKey) -> Result<Vec<u8>, ContractError> {
         let shared_secret = diffie_hellman::sapling_ka_agree(secret, &self.ephem_public)?;
         let key = diffie_hellman::kdf_sapling(&shared_secret, &self.ephem_public);
 
         let ct_len = self.ciphertext.len();
+        if ct_len < AEAD_TAG_SIZE {
+            return Err(ContractError::IoError("Note ciphertext too short".to_string()))
+        }
         let mut plaintext = vec![0_u8; ct_len];
         plaintext.copy_from_slice(&self.ciphertext);
 
@@ -78,14 +97,90 @@ impl AeadEncryptedNote {
             &mut plaintext,
         ) {
             Ok(()) => {
-                let mut cursor = Cursor::new(&plaintext[..ct_len - AEAD_TAG_SIZE]);
-                Ok(D::decode(&mut cursor)?)
+                plaintext.truncate(ct_len - AEAD_TAG_SIZE);
+                Ok(plaintext)
             }
             Err(e) => Err(ContractError::IoError(format!("Note decrypt failed: {e}"))),
         }
     }
 }
 
+/// Derive macro implementing [`EncryptableNote`], configured with
+/// `#[note(version = N, max_size = M)]`. `max_size` is optional.
+pub use darkfi_sdk_derive::EncryptableNote;
+
+/// Default upper bound of a serialized [`EncryptableNote`], in bytes
+pub const DEFAULT_MAX_NOTE_SIZE: usize = 4096;
+
+/// Note types encrypted into an [`AeadEncryptedNote`].
+/// This is normally implemented with `#[derive(EncryptableNote)]`.
+///
+/// The plaintext is the note version byte followed by the serialized
+/// note, so a note format can change without old notes decoding into
+/// garbage. Notes bigger than `MAX_NOTE_SIZE` are refused when
+/// encrypting and when decrypting.
+///
+/// Notes encrypted before versioning was introduced are still on chain,
+/// so plaintext which isn't a valid versioned note is also tried as a
+/// plain serialized note.
+pub trait EncryptableNote: Encodable + Decodable {
+    /// Version of the note serialization format
+    const NOTE_VERSION: u8;
+    /// Maximum size of the serialized note, excluding the version byte
+    const MAX_NOTE_SIZE: usize = DEFAULT_MAX_NOTE_SIZE;
+
+    /// Encrypt the note to given public key
+    fn encrypt_note(
+        &self,
+        public: &PublicKey,
+        rng: &mut (impl CryptoRng + RngCore),
+    ) -> Result<AeadEncryptedNote, ContractError> {
+        let mut plaintext = vec![Self::NOTE_VERSION];
+        self.encode(&mut plaintext)?;
+        if plaintext.len() - 1 > Self::MAX_NOTE_SIZE {
+            return Err(ContractError::IoError(format!(
+                "Note size {} exceeds limit of {} bytes",
+                plaintext.len() - 1,
+                Self::MAX_NOTE_SIZE
+            )))
+        }
+
+        AeadEncryptedNote::encrypt_bytes(&plaintext, public, rng)
+    }
+
+    /// Decrypt a note of this type using given secret key
+    fn decrypt_note(note: &AeadEncryptedNote, secret: &SecretKey) -> Result<Self, ContractError> {
+        // Refuse oversized notes before spending time decrypting them
+        if note.ciphertext.len() > Self::MAX_NOTE_SIZE + 1 + AEAD_TAG_SIZE {
+            return Err(ContractError::IoError("Note exceeds size limit".to_string()))
+        }
+
+        let plaintext = note.decrypt_bytes(secret)?;
+        let Some((version, data)) = plaintext.split_first() else {
+            return Err(ContractError::IoError("Note is empty".to_string()))
+        };
+        if *version == Self::NOTE_VERSION {
+            if let Ok(ret) = decode_exact(data) {
+                return Ok(ret)
+            }
+        }
+
+        // Legacy unversioned note
+        decode_exact(&plaintext)
+            .map_err(|_| ContractError::IoError(format!("Unsupported note version {version}")))
+    }
+}
+
+/// Decode `data` into `T`, refusing any trailing bytes
+fn decode_exact<T: Decodable>(data: &[u8]) -> Result<T, ContractError> {
+    let mut cursor = Cursor::new(data);
+    let ret = T::decode(&mut cursor)?;
+    if cursor.position() as usize != data.len() {
+        return Err(ContractError::IoError("Note has trailing bytes".to_string()))
+    }
+    Ok(ret)
+}
+
 /// An encrypted note using an ElGamal scheme verifiable in ZK.
 ///
 /// **WARNING:**
@@ -155,12 +250,26 @@ impl<const N: usize> ElGamalEncryptedNote<N> {
 
         Ok(decrypted_values)
     }
+
+    /// Public inputs of the note as constrained by verifiable encryption in ZK
+    /// using the `elgamal_shared_secret` and `elgamal_encrypt` zkas opcodes:
+    /// the ephemeral public key coordinates, followed by the encrypted values.
+    /// The values must be encrypted in order, using nonces `1..=N`.
+    pub fn public_inputs(&self) -> Vec<pallas::Base> {
+        let (ephem_x, ephem_y) = self.ephem_public.xy();
+        let mut public_inputs = Vec::with_capacity(N + 2);
+        public_inputs.push(ephem_x);
+        public_inputs.push(ephem_y);
+        public_inputs.extend_from_slice(&self.encrypted_values);
+        public_inputs
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::crypto::Keypair;
+    use darkfi_serial::serialize;
 
     use rand::rngs::OsRng;
 
@@ -177,6 +286,39 @@ mod tests {
         assert_eq!(plaintext, plaintext2);
     }
 
+    #[derive(Debug, PartialEq, SerialEncodable, SerialDecodable, EncryptableNote)]
+    #[note(version = 2, max_size = 16)]
+    struct TestNote {
+        value: u64,
+        memo: Vec<u8>,
+    }
+
+    #[test]
+    fn test_encryptable_note() {
+        let keypair = Keypair::random(&mut OsRng);
+
+        let note = TestNote { value: 42, memo: b"gm".to_vec() };
+        let encrypted_note = note.encrypt_note(&keypair.public, &mut OsRng).unwrap();
+        assert_eq!(TestNote::decrypt_note(&encrypted_note, &keypair.secret).unwrap(), note);
+
+        // Notes of another version are refused
+        let other = AeadEncryptedNote::encrypt_bytes(
+            &[&[1u8][..], &serialize(&note)].concat(),
+            &keypair.public,
+            &mut OsRng,
+        )
+        .unwrap();
+        assert!(TestNote::decrypt_note(&other, &keypair.secret).is_err());
+
+        // Notes from before versioning still decrypt
+        let unversioned = AeadEncryptedNote::encrypt(&note, &keypair.public, &mut OsRng).unwrap();
+        assert_eq!(TestNote::decrypt_note(&unversioned, &keypair.secret).unwrap(), note);
+
+        // Oversized notes can't be encrypted
+        let note = TestNote { value: 42, memo: vec![0u8; 16] };
+        assert!(note.encrypt_note(&keypair.public, &mut OsRng).is_err());
+    }
+
     #[test]
     fn test_elgamal_note() {
         const N_MSGS: usize = 10;
@@ -45,16 +45,75 @@ impl Signature {
     }
 }
 
+/// Schnorr adaptor signature (pre-signature) bound to an adaptor point `T = t·G`.
+///
+/// It is not a valid signature by itself, but anyone knowing the adaptor
+/// secret `t` can turn it into one with [`AdaptorSignature::complete`].
+/// Conversely, once the completed signature is published, the holder of
+/// the pre-signature learns `t` with [`AdaptorSignature::extract`].
+/// This is the building block for atomic swaps with other chains.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct AdaptorSignature {
+    commit: pallas::Point,
+    adaptor: pallas::Point,
+    response: pallas::Scalar,
+}
+
+impl AdaptorSignature {
+    /// Return the adaptor point this pre-signature is bound to
+    pub fn adaptor(&self) -> pallas::Point {
+        self.adaptor
+    }
+
+    /// Complete the pre-signature into a valid [`Signature`] using the
+    /// adaptor secret. Returns `None` if the secret does not match the
+    /// adaptor point.
+    pub fn complete(&self, adaptor_secret: &pallas::Scalar) -> Option<Signature> {
+        if NullifierK.generator() * adaptor_secret != self.adaptor {
+            return None
+        }
+
+        Some(Signature {
+            commit: self.commit + self.adaptor,
+            response: self.response + adaptor_secret,
+        })
+    }
+
+    /// Extract the adaptor secret from a signature completed out of this
+    /// pre-signature. Returns `None` if the signature was not produced
+    /// from it.
+    pub fn extract(&self, signature: &Signature) -> Option<pallas::Scalar> {
+        if signature.commit != self.commit + self.adaptor {
+            return None
+        }
+
+        let adaptor_secret = signature.response - self.response;
+        if NullifierK.generator() * adaptor_secret != self.adaptor {
+            return None
+        }
+
+        Some(adaptor_secret)
+    }
+}
+
 /// Trait for secret keys that implements a signature creation
 pub trait SchnorrSecret {
     /// Sign a given message
     fn sign(&self, message: &[u8]) -> Signature;
+
+    /// Create an adaptor signature of a given message, bound to the given
+    /// adaptor point.
+    fn pre_sign(&self, message: &[u8], adaptor: &pallas::Point) -> AdaptorSignature;
 }
 
 /// Trait for public keys that implements a signature verification
 pub trait SchnorrPublic {
     /// Verify a given message is valid given a signature.
     fn verify(&self, message: &[u8], signature: &Signature) -> bool;
+
+    /// Verify a given adaptor signature is valid for a given message, meaning
+    /// it completes into a valid signature once the adaptor secret is known.
+    fn verify_pre_signature(&self, message: &[u8], pre_signature: &AdaptorSignature) -> bool;
 }
 
 /// Schnorr signature trait implementations for the stuff in `keypair.rs`
@@ -74,6 +133,25 @@ impl SchnorrSecret for SecretKey {
 
         Signature { commit, response }
     }
+
+    fn pre_sign(&self, message: &[u8], adaptor: &pallas::Point) -> AdaptorSignature {
+        // Derive a deterministic nonce, also bound to the adaptor point
+        let adaptor_bytes = adaptor.to_bytes();
+        let mask =
+            hash_to_scalar(DRK_SCHNORR_DOMAIN, &[&self.inner().to_repr(), &adaptor_bytes, message]);
+
+        let commit = NullifierK.generator() * mask;
+
+        // The challenge commits to the final nonce point R + T
+        let commit_bytes = (commit + adaptor).to_bytes();
+        let pubkey_bytes = PublicKey::from_secret(*self).to_bytes();
+        let transcript = &[&commit_bytes, &pubkey_bytes, message];
+
+        let challenge = hash_to_scalar(DRK_SCHNORR_DOMAIN, transcript);
+        let response = mask + challenge * fp_mod_fv(self.inner());
+
+        AdaptorSignature { commit, adaptor: *adaptor, response }
+    }
 }
 
 impl SchnorrPublic for PublicKey {
@@ -85,12 +163,23 @@ impl SchnorrPublic for PublicKey {
         let challenge = hash_to_scalar(DRK_SCHNORR_DOMAIN, transcript);
         NullifierK.generator() * signature.response - self.inner() * challenge == signature.commit
     }
+
+    fn verify_pre_signature(&self, message: &[u8], pre_signature: &AdaptorSignature) -> bool {
+        let commit_bytes = (pre_signature.commit + pre_signature.adaptor).to_bytes();
+        let pubkey_bytes = self.to_bytes();
+        let transcript = &[&commit_bytes, &pubkey_bytes, message];
+
+        let challenge = hash_to_scalar(DRK_SCHNORR_DOMAIN, transcript);
+        NullifierK.generator() * pre_signature.response - self.inner() * challenge ==
+            pre_signature.commit
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use darkfi_serial::{deserialize, serialize};
+    use pasta_curves::group::ff::Field;
     use rand::rngs::OsRng;
 
     #[test]
@@ -106,4 +195,34 @@ mod tests {
         let de = deserialize(&ser).unwrap();
         assert!(public.verify(message, &de));
     }
+
+    #[test]
+    fn test_schnorr_adaptor_signature() {
+        let secret = SecretKey::random(&mut OsRng);
+        let public = PublicKey::from_secret(secret);
+        let message: &[u8] = b"swap all the things";
+
+        let adaptor_secret = pallas::Scalar::random(&mut OsRng);
+        let adaptor = NullifierK.generator() * adaptor_secret;
+
+        // The pre-signature verifies, but is not a valid signature on its own
+        let pre_signature = secret.pre_sign(message, &adaptor);
+        assert!(public.verify_pre_signature(message, &pre_signature));
+        assert!(!public.verify_pre_signature(b"something else", &pre_signature));
+        let forged = Signature { commit: pre_signature.commit, response: pre_signature.response };
+        assert!(!public.verify(message, &forged));
+
+        // Completing requires the right adaptor secret
+        assert!(pre_signature.complete(&pallas::Scalar::random(&mut OsRng)).is_none());
+        let signature = pre_signature.complete(&adaptor_secret).unwrap();
+        assert!(public.verify(message, &signature));
+
+        // The adaptor secret can be recovered from the published signature
+        assert_eq!(pre_signature.extract(&signature), Some(adaptor_secret));
+        assert_eq!(pre_signature.extract(&secret.sign(message)), None);
+
+        // Check out if it's also fine with serialization
+        let de: AdaptorSignature = deserialize(&serialize(&pre_signature)).unwrap();
+        assert!(public.verify_pre_signature(message, &de));
+    }
 }
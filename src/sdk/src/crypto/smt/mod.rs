@@ -277,6 +277,21 @@ impl<
         self.get_node(&leaf_idx)
     }
 
+    /// Returns `true` if the leaf at `pos` is not the empty leaf.
+    pub fn contains(&self, pos: &F) -> bool {
+        self.get_leaf(pos) != self.empty_nodes[N]
+    }
+
+    /// Give the path leading from the empty leaf at `pos` up to the root,
+    /// proving nothing is stored there. Returns `None` if the leaf is set.
+    pub fn prove_non_membership(&self, pos: &F) -> Option<Path<N, F, H>> {
+        if self.contains(pos) {
+            return None
+        }
+
+        Some(self.prove_membership(pos))
+    }
+
     fn get_node(&self, idx: &BigUint) -> F {
         let lvl = util::log2(idx);
         let empty_node = self.empty_nodes[lvl as usize];
@@ -325,6 +340,12 @@ impl<const N: usize, F: FieldElement, H: FieldHasher<F, 2>> Path<N, F, H> {
 
         current_node == *root
     }
+
+    /// Verify that the leaf at `pos` is empty. This assumes the tree was
+    /// built using a zero empty leaf, as [`EMPTY_NODES_FP`] is.
+    pub fn verify_non_membership(&self, root: &F, pos: &F) -> bool {
+        self.verify(root, &F::ZERO, pos)
+    }
 }
 
 /// A function to generate empty hashes with a given `default_leaf`.
@@ -158,3 +158,36 @@ fn poseidon_smt_incl_proof() {
     smt.remove_leaves(vec![(pos, leaf)]).unwrap();
     assert!(!path.verify(&smt.root(), &leaf, &pos));
 }
+
+#[test]
+fn poseidon_smt_excl_proof() {
+    const HEIGHT: usize = 3;
+    let hasher = Poseidon::<Fp, 2>::new();
+    let empty_leaf = Fp::from(0);
+    let empty_nodes = gen_empty_nodes::<{ HEIGHT + 1 }, _, _>(&hasher, empty_leaf);
+
+    let store = MemoryStorage::<Fp>::new();
+    let mut smt = SparseMerkleTree::<HEIGHT, { HEIGHT + 1 }, _, _, _>::new(
+        store,
+        hasher.clone(),
+        &empty_nodes,
+    );
+
+    let leaves = vec![(Fp::from(1), Fp::random(&mut OsRng)), (Fp::from(2), Fp::random(&mut OsRng))];
+    smt.insert_batch(leaves.clone()).unwrap();
+
+    // Set leaves have no non-membership proof
+    let (pos, leaf) = leaves[1];
+    assert!(smt.contains(&pos));
+    assert!(smt.prove_non_membership(&pos).is_none());
+
+    // Position 5 is empty
+    let empty_pos = Fp::from(5);
+    assert!(!smt.contains(&empty_pos));
+    let path = smt.prove_non_membership(&empty_pos).unwrap();
+    assert!(path.verify_non_membership(&smt.root(), &empty_pos));
+
+    // Which stops holding once something gets stored there
+    smt.insert_batch(vec![(empty_pos, leaf)]).unwrap();
+    assert!(!path.verify_non_membership(&smt.root(), &empty_pos));
+}
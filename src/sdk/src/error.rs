@@ -231,3 +231,28 @@ pub enum DarkTreeError {
     #[error("DarkTree max capacity has been exceeded")]
     MaxCapacityExceeded,
 }
+
+/// Main result type used by secure storage backends.
+pub type SecureStorageResult<T> = ResultGeneric<T, SecureStorageError>;
+
+/// General secure storage related errors.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum SecureStorageError {
+    #[error("Secure storage backend is unavailable: {0}")]
+    Unavailable(String),
+
+    #[error("Secure storage backend error: {0}")]
+    Backend(String),
+
+    #[error("Secure storage IO error: {0}")]
+    IoError(String),
+
+    #[error("Failed to decrypt secret \"{0}\"")]
+    DecryptFailed(String),
+}
+
+impl From<std::io::Error> for SecureStorageError {
+    fn from(err: std::io::Error) -> Self {
+        Self::IoError(format!("{err}"))
+    }
+}
@@ -54,6 +54,10 @@ pub use tx::ContractCall;
 /// Convenience utilities
 pub mod util;
 
+#[cfg(feature = "secure-storage")]
+/// Platform secure storage for wallet secrets
+pub mod secure_storage;
+
 #[macro_use]
 #[cfg(feature = "wasm")]
 /// WASM API functions
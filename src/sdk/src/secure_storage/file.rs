@@ -0,0 +1,154 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use argon2::Argon2;
+use chacha20poly1305::{aead::Aead, ChaCha20Poly1305, KeyInit};
+use rand::{rngs::OsRng, RngCore};
+
+use super::SecureStorage;
+use crate::error::{SecureStorageError, SecureStorageResult};
+
+/// Salt length in bytes
+const SALT_SIZE: usize = 16;
+/// Nonce length in bytes
+const NONCE_SIZE: usize = 12;
+
+/// Fallback [`SecureStorage`] keeping each secret in its own file inside a
+/// directory, encrypted with a key derived from a passphrase using Argon2.
+///
+/// File layout is `salt || nonce || ciphertext`, and the secret name is used
+/// as associated data so files can't be swapped around.
+pub struct EncryptedFileStorage {
+    dir: PathBuf,
+    passphrase: Vec<u8>,
+}
+
+impl EncryptedFileStorage {
+    /// Create a new storage in `dir`, which gets created if missing.
+    ///
+    /// An empty passphrase is accepted for platforms where the user is never
+    /// prompted, but then the files are only obfuscated, not protected.
+    pub fn new(dir: &Path, passphrase: &[u8]) -> SecureStorageResult<Self> {
+        fs::create_dir_all(dir)?;
+        Ok(Self { dir: dir.to_path_buf(), passphrase: passphrase.to_vec() })
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        // Hash the name so arbitrary names map to valid file names
+        self.dir.join(blake3::hash(name.as_bytes()).to_hex().as_str())
+    }
+
+    fn cipher(&self, salt: &[u8]) -> SecureStorageResult<ChaCha20Poly1305> {
+        let mut key = [0u8; 32];
+        if let Err(e) = Argon2::default().hash_password_into(&self.passphrase, salt, &mut key) {
+            return Err(SecureStorageError::Backend(format!("Key derivation failed: {e}")))
+        }
+        Ok(ChaCha20Poly1305::new(&key.into()))
+    }
+}
+
+impl SecureStorage for EncryptedFileStorage {
+    fn get(&self, name: &str) -> SecureStorageResult<Option<Vec<u8>>> {
+        let path = self.path(name);
+        if !path.exists() {
+            return Ok(None)
+        }
+
+        let bytes = fs::read(path)?;
+        if bytes.len() < SALT_SIZE + NONCE_SIZE {
+            return Err(SecureStorageError::DecryptFailed(name.to_string()))
+        }
+        let (salt, rest) = bytes.split_at(SALT_SIZE);
+        let (nonce, ciphertext) = rest.split_at(NONCE_SIZE);
+
+        let payload = chacha20poly1305::aead::Payload { msg: ciphertext, aad: name.as_bytes() };
+        match self.cipher(salt)?.decrypt(nonce.into(), payload) {
+            Ok(secret) => Ok(Some(secret)),
+            Err(_) => Err(SecureStorageError::DecryptFailed(name.to_string())),
+        }
+    }
+
+    fn set(&self, name: &str, secret: &[u8]) -> SecureStorageResult<()> {
+        let mut salt = [0u8; SALT_SIZE];
+        let mut nonce = [0u8; NONCE_SIZE];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+
+        let payload = chacha20poly1305::aead::Payload { msg: secret, aad: name.as_bytes() };
+        let Ok(ciphertext) = self.cipher(&salt)?.encrypt(&nonce.into(), payload) else {
+            return Err(SecureStorageError::Backend(format!("Failed to encrypt secret \"{name}\"")))
+        };
+
+        let mut bytes = Vec::with_capacity(SALT_SIZE + NONCE_SIZE + ciphertext.len());
+        bytes.extend_from_slice(&salt);
+        bytes.extend_from_slice(&nonce);
+        bytes.extend_from_slice(&ciphertext);
+
+        // Write to a temporary file first so a crash can't leave a truncated secret
+        let path = self.path(name);
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, bytes)?;
+        fs::rename(tmp_path, path)?;
+
+        Ok(())
+    }
+
+    fn delete(&self, name: &str) -> SecureStorageResult<()> {
+        let path = self.path(name);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypted_file_storage() {
+        let dir = std::env::temp_dir().join(format!("darkfi-secure-storage-{}", OsRng.next_u64()));
+        let storage = EncryptedFileStorage::new(&dir, b"hunter2").unwrap();
+
+        assert_eq!(storage.get("wallet").unwrap(), None);
+        storage.set("wallet", b"top secret").unwrap();
+        assert_eq!(storage.get("wallet").unwrap(), Some(b"top secret".to_vec()));
+
+        // Wrong passphrase can't decrypt
+        let other = EncryptedFileStorage::new(&dir, b"hunter3").unwrap();
+        assert!(matches!(other.get("wallet"), Err(SecureStorageError::DecryptFailed(_))));
+
+        // get_or_create keeps existing secrets
+        let secret = storage.get_or_create("wallet", &|| b"new".to_vec()).unwrap();
+        assert_eq!(secret, b"top secret".to_vec());
+        let secret = storage.get_or_create("other", &|| b"new".to_vec()).unwrap();
+        assert_eq!(secret, b"new".to_vec());
+
+        storage.delete("wallet").unwrap();
+        storage.delete("wallet").unwrap();
+        assert_eq!(storage.get("wallet").unwrap(), None);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
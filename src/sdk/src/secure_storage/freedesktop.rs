@@ -0,0 +1,89 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::HashMap;
+
+use secret_service::{
+    blocking::{Collection, SecretService},
+    EncryptionType,
+};
+
+use super::SecureStorage;
+use crate::error::{SecureStorageError, SecureStorageResult};
+
+/// [`SecureStorage`] backed by the freedesktop secret-service API
+/// (GNOME Keyring, KWallet, KeePassXC, ...).
+///
+/// Secrets are stored in the default collection, tagged with the
+/// `service` and `name` attributes.
+pub struct SecretServiceStorage {
+    service: String,
+    ss: SecretService<'static>,
+}
+
+impl SecretServiceStorage {
+    /// Connect to the session secret-service daemon
+    pub fn new(service: &str) -> SecureStorageResult<Self> {
+        let ss = match SecretService::connect(EncryptionType::Dh) {
+            Ok(ss) => ss,
+            Err(e) => return Err(SecureStorageError::Unavailable(format!("{e}"))),
+        };
+
+        Ok(Self { service: service.to_string(), ss })
+    }
+
+    fn collection(&self) -> SecureStorageResult<Collection<'_>> {
+        let collection = self.ss.get_default_collection().map_err(backend_err)?;
+        if collection.is_locked().map_err(backend_err)? {
+            collection.unlock().map_err(backend_err)?;
+        }
+        Ok(collection)
+    }
+
+    fn attributes<'a>(&'a self, name: &'a str) -> HashMap<&'a str, &'a str> {
+        HashMap::from([("service", self.service.as_str()), ("name", name)])
+    }
+}
+
+fn backend_err(e: secret_service::Error) -> SecureStorageError {
+    SecureStorageError::Backend(format!("{e}"))
+}
+
+impl SecureStorage for SecretServiceStorage {
+    fn get(&self, name: &str) -> SecureStorageResult<Option<Vec<u8>>> {
+        let items = self.collection()?.search_items(self.attributes(name)).map_err(backend_err)?;
+        let Some(item) = items.first() else { return Ok(None) };
+        Ok(Some(item.get_secret().map_err(backend_err)?))
+    }
+
+    fn set(&self, name: &str, secret: &[u8]) -> SecureStorageResult<()> {
+        let label = format!("{} {name}", self.service);
+        self.collection()?
+            .create_item(&label, self.attributes(name), secret, true, "application/octet-stream")
+            .map_err(backend_err)?;
+        Ok(())
+    }
+
+    fn delete(&self, name: &str) -> SecureStorageResult<()> {
+        let items = self.collection()?.search_items(self.attributes(name)).map_err(backend_err)?;
+        for item in items {
+            item.delete().map_err(backend_err)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use security_framework::passwords::{
+    delete_generic_password, get_generic_password, set_generic_password,
+};
+
+use super::SecureStorage;
+use crate::error::{SecureStorageError, SecureStorageResult};
+
+/// `errSecItemNotFound` from the Security framework
+const ERR_SEC_ITEM_NOT_FOUND: i32 = -25300;
+
+/// [`SecureStorage`] backed by the macOS Keychain, storing secrets as
+/// generic passwords with `service` as the service and `name` as the account.
+pub struct KeychainStorage {
+    service: String,
+}
+
+impl KeychainStorage {
+    pub fn new(service: &str) -> Self {
+        Self { service: service.to_string() }
+    }
+}
+
+fn backend_err(e: security_framework::base::Error) -> SecureStorageError {
+    SecureStorageError::Backend(format!("{e}"))
+}
+
+impl SecureStorage for KeychainStorage {
+    fn get(&self, name: &str) -> SecureStorageResult<Option<Vec<u8>>> {
+        match get_generic_password(&self.service, name) {
+            Ok(secret) => Ok(Some(secret)),
+            Err(e) if e.code() == ERR_SEC_ITEM_NOT_FOUND => Ok(None),
+            Err(e) => Err(backend_err(e)),
+        }
+    }
+
+    fn set(&self, name: &str, secret: &[u8]) -> SecureStorageResult<()> {
+        set_generic_password(&self.service, name, secret).map_err(backend_err)
+    }
+
+    fn delete(&self, name: &str) -> SecureStorageResult<()> {
+        match delete_generic_password(&self.service, name) {
+            Ok(()) => Ok(()),
+            Err(e) if e.code() == ERR_SEC_ITEM_NOT_FOUND => Ok(()),
+            Err(e) => Err(backend_err(e)),
+        }
+    }
+}
@@ -0,0 +1,87 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Platform secure storage for wallet secrets.
+//!
+//! Wallets should keep their keys in the OS keystore when one is
+//! available, and fall back to [`EncryptedFileStorage`] otherwise.
+//! Android has no Rust-accessible keystore, so its backend is
+//! implemented by the application over JNI.
+
+use crate::error::SecureStorageResult;
+
+/// Encrypted file fallback
+mod file;
+pub use file::EncryptedFileStorage;
+
+/// Linux secret-service backend
+#[cfg(target_os = "linux")]
+mod freedesktop;
+#[cfg(target_os = "linux")]
+pub use freedesktop::SecretServiceStorage;
+
+/// macOS Keychain backend
+#[cfg(target_os = "macos")]
+mod keychain;
+#[cfg(target_os = "macos")]
+pub use keychain::KeychainStorage;
+
+/// Storage for small named secrets, like wallet keys or database passwords
+pub trait SecureStorage: Send + Sync {
+    /// Retrieve the secret stored under `name`, if any
+    fn get(&self, name: &str) -> SecureStorageResult<Option<Vec<u8>>>;
+
+    /// Store a secret under `name`, replacing any existing one
+    fn set(&self, name: &str, secret: &[u8]) -> SecureStorageResult<()>;
+
+    /// Remove the secret stored under `name`. Removing a
+    /// missing secret is not an error.
+    fn delete(&self, name: &str) -> SecureStorageResult<()>;
+
+    /// Retrieve the secret stored under `name`, generating and
+    /// storing a new one with `f` if it doesn't exist.
+    fn get_or_create(&self, name: &str, f: &dyn Fn() -> Vec<u8>) -> SecureStorageResult<Vec<u8>> {
+        if let Some(secret) = self.get(name)? {
+            return Ok(secret)
+        }
+
+        let secret = f();
+        self.set(name, &secret)?;
+        Ok(secret)
+    }
+}
+
+/// Open the OS keystore of the current platform, with secrets namespaced
+/// under `service`. Returns an error on platforms without a supported
+/// keystore, or when the keystore can't be reached (e.g. no secret-service
+/// daemon is running), in which case callers should use the fallback.
+pub fn platform_storage(service: &str) -> SecureStorageResult<Box<dyn SecureStorage>> {
+    #[cfg(target_os = "linux")]
+    return Ok(Box::new(SecretServiceStorage::new(service)?));
+
+    #[cfg(target_os = "macos")]
+    return Ok(Box::new(KeychainStorage::new(service)));
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        let _ = service;
+        Err(crate::error::SecureStorageError::Unavailable(
+            "no supported keystore on this platform".to_string(),
+        ))
+    }
+}
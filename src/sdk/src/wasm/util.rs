@@ -20,6 +20,7 @@ use darkfi_serial::{Decodable, Encodable};
 use std::io::Cursor;
 
 use crate::{
+    blockchain::BlockContext,
     error::{ContractError, GenericResult},
     tx::TransactionHash,
 };
@@ -161,6 +162,19 @@ pub fn get_last_block_height() -> GenericResult<Option<Vec<u8>>> {
     parse_ret(ret)
 }
 
+/// Only deploy(), metadata() and exec() can call this. Will return the
+/// context of the block the current call is verified in.
+///
+/// ```
+/// ctx = get_block_context()?;
+/// ```
+pub fn get_block_context() -> GenericResult<BlockContext> {
+    let ret = unsafe { get_block_context_() };
+    let ctx_data = parse_ret(ret)?.ok_or(ContractError::DbGetFailed)?;
+    let mut cursor = Cursor::new(ctx_data);
+    Ok(Decodable::decode(&mut cursor)?)
+}
+
 /// Only metadata() and exec() can call this. Will return transaction
 /// bytes by provided hash.
 ///
@@ -203,6 +217,7 @@ extern "C" {
     fn get_call_index_() -> i64;
     fn get_blockchain_time_() -> i64;
     fn get_last_block_height_() -> i64;
+    fn get_block_context_() -> i64;
     fn get_tx_(ptr: *const u8) -> i64;
     fn get_tx_location_(ptr: *const u8) -> i64;
 }
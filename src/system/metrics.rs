@@ -0,0 +1,410 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Prometheus metrics
+//!
+//! Metrics are kept in the process-wide [`METRICS`] registry, where they
+//! are created on first use and identified by their name and labels:
+//!
+//! ```ignore
+//! METRICS.counter("darkfi_p2p_messages_received_total", "P2P messages received", &[("command", "ping")]).inc();
+//! ```
+//!
+//! Daemons built with `async_daemonize!` serve the registry in the
+//! Prometheus text format on `/metrics` when `metrics_listen` is set.
+//! Label values should come from a bounded set, as every combination
+//! of them is kept in memory for the lifetime of the process.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicI64, AtomicU64, Ordering},
+        Arc, LazyLock, Mutex,
+    },
+    time::Duration,
+};
+
+use log::{debug, error, info};
+use smol::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    Executor,
+};
+use url::Url;
+
+use crate::{Error, Result};
+
+/// Process-wide metrics registry
+pub static METRICS: LazyLock<Registry> = LazyLock::new(Registry::default);
+
+/// Histogram buckets, in seconds, suited for request and verification times
+pub const DEFAULT_BUCKETS: &[f64] =
+    &[0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Upper bound on the size of an HTTP request head sent to the exporter
+const MAX_REQUEST_LEN: usize = 8192;
+
+/// Time a client is given to send its HTTP request to the exporter
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Monotonically increasing value
+#[derive(Default)]
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    pub fn inc(&self) {
+        self.inc_by(1);
+    }
+
+    pub fn inc_by(&self, v: u64) {
+        self.0.fetch_add(v, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Value that can go up and down
+#[derive(Default)]
+pub struct Gauge(AtomicI64);
+
+impl Gauge {
+    pub fn set(&self, v: i64) {
+        self.0.store(v, Ordering::Relaxed);
+    }
+
+    pub fn inc(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn dec(&self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> i64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Distribution of observed values over a set of buckets
+pub struct Histogram {
+    /// Upper bounds of the buckets, in increasing order
+    bounds: Vec<f64>,
+    /// Observations per bucket, not cumulative. The last one counts the
+    /// values above all bounds.
+    buckets: Vec<AtomicU64>,
+    /// Sum of all observed values, as `f64` bits
+    sum: AtomicU64,
+}
+
+impl Histogram {
+    fn new(bounds: &[f64]) -> Self {
+        let buckets = (0..=bounds.len()).map(|_| AtomicU64::new(0)).collect();
+        Self { bounds: bounds.to_vec(), buckets, sum: AtomicU64::new(0f64.to_bits()) }
+    }
+
+    pub fn observe(&self, v: f64) {
+        let idx = self.bounds.iter().position(|b| v <= *b).unwrap_or(self.bounds.len());
+        self.buckets[idx].fetch_add(1, Ordering::Relaxed);
+
+        let mut sum = self.sum.load(Ordering::Relaxed);
+        loop {
+            let new = (f64::from_bits(sum) + v).to_bits();
+            match self.sum.compare_exchange_weak(sum, new, Ordering::Relaxed, Ordering::Relaxed) {
+                Ok(_) => break,
+                Err(current) => sum = current,
+            }
+        }
+    }
+
+    /// Observe a duration in seconds
+    pub fn observe_duration(&self, d: Duration) {
+        self.observe(d.as_secs_f64());
+    }
+
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().map(|b| b.load(Ordering::Relaxed)).sum()
+    }
+
+    pub fn sum(&self) -> f64 {
+        f64::from_bits(self.sum.load(Ordering::Relaxed))
+    }
+}
+
+#[derive(Clone)]
+enum Metric {
+    Counter(Arc<Counter>),
+    Gauge(Arc<Gauge>),
+    Histogram(Arc<Histogram>),
+}
+
+impl Metric {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Counter(_) => "counter",
+            Self::Gauge(_) => "gauge",
+            Self::Histogram(_) => "histogram",
+        }
+    }
+}
+
+/// Label set of a series, sorted by label name
+type Labels = Vec<(String, String)>;
+
+/// All series sharing a metric name
+struct Family {
+    help: String,
+    series: BTreeMap<Labels, Metric>,
+}
+
+/// Registry of metrics, rendered in the Prometheus text format
+#[derive(Default)]
+pub struct Registry {
+    families: Mutex<BTreeMap<String, Family>>,
+}
+
+impl Registry {
+    /// Grab the counter with the given name and labels, creating it if needed
+    pub fn counter(&self, name: &str, help: &str, labels: &[(&str, &str)]) -> Arc<Counter> {
+        match self.get_or_insert(name, help, labels, || Metric::Counter(Default::default())) {
+            Metric::Counter(c) => c,
+            m => panic!("Metric {name} is registered as a {}", m.kind()),
+        }
+    }
+
+    /// Grab the gauge with the given name and labels, creating it if needed
+    pub fn gauge(&self, name: &str, help: &str, labels: &[(&str, &str)]) -> Arc<Gauge> {
+        match self.get_or_insert(name, help, labels, || Metric::Gauge(Default::default())) {
+            Metric::Gauge(g) => g,
+            m => panic!("Metric {name} is registered as a {}", m.kind()),
+        }
+    }
+
+    /// Grab the histogram with the given name and labels, creating it with
+    /// the given bucket bounds if needed
+    pub fn histogram(
+        &self,
+        name: &str,
+        help: &str,
+        labels: &[(&str, &str)],
+        bounds: &[f64],
+    ) -> Arc<Histogram> {
+        let new = || Metric::Histogram(Arc::new(Histogram::new(bounds)));
+        match self.get_or_insert(name, help, labels, new) {
+            Metric::Histogram(h) => h,
+            m => panic!("Metric {name} is registered as a {}", m.kind()),
+        }
+    }
+
+    fn get_or_insert(
+        &self,
+        name: &str,
+        help: &str,
+        labels: &[(&str, &str)],
+        new: impl FnOnce() -> Metric,
+    ) -> Metric {
+        let mut labels: Labels =
+            labels.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        labels.sort();
+
+        let mut families = self.families.lock().unwrap();
+        let family = families
+            .entry(name.to_string())
+            .or_insert_with(|| Family { help: help.to_string(), series: BTreeMap::new() });
+
+        let metric = family.series.entry(labels).or_insert_with(new).clone();
+        if let Some(other) = family.series.values().next() {
+            assert_eq!(metric.kind(), other.kind(), "Metric {name} registered with mixed types");
+        }
+
+        metric
+    }
+
+    /// Render all metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let families = self.families.lock().unwrap();
+        let mut out = String::new();
+
+        for (name, family) in families.iter() {
+            let Some(first) = family.series.values().next() else { continue };
+            let _ = writeln!(out, "# HELP {name} {}", escape(&family.help, false));
+            let _ = writeln!(out, "# TYPE {name} {}", first.kind());
+
+            for (labels, metric) in &family.series {
+                match metric {
+                    Metric::Counter(c) => {
+                        let _ = writeln!(out, "{name}{} {}", fmt_labels(labels, None), c.get());
+                    }
+                    Metric::Gauge(g) => {
+                        let _ = writeln!(out, "{name}{} {}", fmt_labels(labels, None), g.get());
+                    }
+                    Metric::Histogram(h) => {
+                        let mut cumulative = 0;
+                        for (i, bucket) in h.buckets.iter().enumerate() {
+                            cumulative += bucket.load(Ordering::Relaxed);
+                            let le = match h.bounds.get(i) {
+                                Some(b) => b.to_string(),
+                                None => "+Inf".to_string(),
+                            };
+                            let labels = fmt_labels(labels, Some(&le));
+                            let _ = writeln!(out, "{name}_bucket{labels} {cumulative}");
+                        }
+                        let labels = fmt_labels(labels, None);
+                        let _ = writeln!(out, "{name}_sum{labels} {}", h.sum());
+                        let _ = writeln!(out, "{name}_count{labels} {cumulative}");
+                    }
+                }
+            }
+        }
+
+        out
+    }
+}
+
+/// Escape a HELP text or label value
+fn escape(s: &str, quote: bool) -> String {
+    let mut ret = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => ret.push_str("\\\\"),
+            '\n' => ret.push_str("\\n"),
+            '"' if quote => ret.push_str("\\\""),
+            c => ret.push(c),
+        }
+    }
+    ret
+}
+
+/// Format a label set, optionally with a histogram `le` label
+fn fmt_labels(labels: &Labels, le: Option<&str>) -> String {
+    let mut pairs: Vec<String> =
+        labels.iter().map(|(k, v)| format!("{k}=\"{}\"", escape(v, true))).collect();
+    if let Some(le) = le {
+        pairs.push(format!("le=\"{le}\""));
+    }
+
+    if pairs.is_empty() {
+        return String::new()
+    }
+    format!("{{{}}}", pairs.join(","))
+}
+
+/// Start serving [`METRICS`] over HTTP on `/metrics`, on the given
+/// `tcp://` listen URL. Returns once the socket is bound, leaving the
+/// exporter running in the background.
+pub async fn serve(listen: &str, ex: Arc<Executor<'static>>) -> Result<()> {
+    let url = Url::parse(listen)?;
+    if url.scheme() != "tcp" {
+        return Err(Error::UnsupportedTransport(url.scheme().to_string()))
+    }
+
+    let sockaddr = url.socket_addrs(|| None)?;
+    let listener = TcpListener::bind(sockaddr[0]).await?;
+    info!(target: "system::metrics", "Serving metrics on {listen}");
+
+    let ex_ = ex.clone();
+    ex.spawn(async move {
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(v) => v,
+                Err(e) => {
+                    error!(target: "system::metrics", "Metrics exporter failed accepting: {e}");
+                    continue
+                }
+            };
+
+            ex_.spawn(async move {
+                if let Err(e) = handle_conn(stream).await {
+                    debug!(target: "system::metrics", "Metrics request from {peer} failed: {e}");
+                }
+            })
+            .detach();
+        }
+    })
+    .detach();
+
+    Ok(())
+}
+
+/// Answer a single HTTP request and close the connection
+async fn handle_conn(mut stream: TcpStream) -> std::io::Result<()> {
+    let read_head = async {
+        let mut head = Vec::with_capacity(512);
+        let mut byte = [0u8; 1];
+        while !head.ends_with(b"\r\n\r\n") && head.len() < MAX_REQUEST_LEN {
+            stream.read_exact(&mut byte).await?;
+            head.push(byte[0]);
+        }
+        Ok(head)
+    };
+    let head = smol::future::or(read_head, async {
+        smol::Timer::after(REQUEST_TIMEOUT).await;
+        Err(std::io::ErrorKind::TimedOut.into())
+    })
+    .await?;
+
+    let request_line = head.split(|b| *b == b'\r').next().unwrap_or_default();
+    let mut parts = request_line.split(|b| *b == b' ');
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some(b"GET"), Some(b"/metrics")) => ("200 OK", METRICS.render()),
+        _ => ("404 Not Found", String::new()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {status}\r\n\
+         Content-Type: text/plain; version=0.0.4\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_metrics() {
+        let registry = Registry::default();
+
+        registry.counter("test_messages_total", "Messages", &[("command", "ping")]).inc_by(2);
+        registry.counter("test_messages_total", "Messages", &[("command", "pong")]).inc();
+        registry.gauge("test_height", "Height", &[]).set(42);
+
+        let h = registry.histogram("test_seconds", "Time", &[], &[0.3, 1.0]);
+        h.observe(0.25);
+        h.observe(0.5);
+        h.observe(4.0);
+        assert_eq!(h.count(), 3);
+
+        let out = registry.render();
+        assert!(out.contains("# TYPE test_messages_total counter\n"));
+        assert!(out.contains("test_messages_total{command=\"ping\"} 2\n"));
+        assert!(out.contains("test_messages_total{command=\"pong\"} 1\n"));
+        assert!(out.contains("test_height 42\n"));
+        assert!(out.contains("test_seconds_bucket{le=\"0.3\"} 1\n"));
+        assert!(out.contains("test_seconds_bucket{le=\"1\"} 2\n"));
+        assert!(out.contains("test_seconds_bucket{le=\"+Inf\"} 3\n"));
+        assert!(out.contains("test_seconds_sum 4.75\n"));
+        assert!(out.contains("test_seconds_count 3\n"));
+    }
+}
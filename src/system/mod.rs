@@ -29,6 +29,14 @@ pub use condvar::CondVar;
 pub mod stoppable_task;
 pub use stoppable_task::{StoppableTask, StoppableTaskPtr};
 
+/// Prometheus metrics registry and exporter
+pub mod metrics;
+pub use metrics::METRICS;
+
+/// Async lock handed out by priority instead of arrival order
+pub mod priority_lock;
+pub use priority_lock::{PriorityLock, PriorityLockGuard};
+
 /// Simple broadcast (publish-subscribe) class
 pub mod publisher;
 pub use publisher::{Publisher, PublisherPtr, Subscription};
@@ -0,0 +1,213 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{cmp::Ordering, collections::BinaryHeap, sync::Mutex};
+
+use smol::channel::{bounded, Receiver, Sender};
+
+/// Async lock which is handed out by priority rather than arrival order.
+/// Waiters with equal priority are served first come, first served.
+/// ```rust
+///    let lock = PriorityLock::new();
+///
+///    // Waits until every higher priority waiter is done
+///    let _guard = lock.lock(priority).await;
+/// ```
+pub struct PriorityLock<P: Ord> {
+    state: Mutex<PriorityLockState<P>>,
+}
+
+struct PriorityLockState<P: Ord> {
+    is_locked: bool,
+    waiters: BinaryHeap<Waiter<P>>,
+    next_seq: u64,
+}
+
+struct Waiter<P: Ord> {
+    priority: P,
+    seq: u64,
+    wake: Sender<()>,
+}
+
+impl<P: Ord> PartialEq for Waiter<P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<P: Ord> Eq for Waiter<P> {}
+
+impl<P: Ord> PartialOrd for Waiter<P> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<P: Ord> Ord for Waiter<P> {
+    /// Highest priority first, then the oldest waiter
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority.cmp(&other.priority).then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl<P: Ord> PriorityLock<P> {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(PriorityLockState {
+                is_locked: false,
+                waiters: BinaryHeap::new(),
+                next_seq: 0,
+            }),
+        }
+    }
+
+    /// Acquire the lock, waiting behind all higher priority waiters.
+    pub async fn lock(&self, priority: P) -> PriorityLockGuard<'_, P> {
+        let wake = {
+            let mut state = self.state.lock().unwrap();
+            if !state.is_locked {
+                state.is_locked = true;
+                return PriorityLockGuard { lock: self }
+            }
+
+            let (wake_tx, wake_rx) = bounded(1);
+            let seq = state.next_seq;
+            state.next_seq += 1;
+            state.waiters.push(Waiter { priority, seq, wake: wake_tx });
+            wake_rx
+        };
+
+        let mut wait = PriorityLockWait { lock: self, wake, is_done: false };
+        // The sender is only dropped after handing us the lock
+        let _ = wait.wake.recv().await;
+        wait.is_done = true;
+
+        PriorityLockGuard { lock: self }
+    }
+
+    /// Hand the lock over to the next waiter, or unlock it if there is none.
+    fn release(&self) {
+        let mut state = self.state.lock().unwrap();
+        while let Some(waiter) = state.waiters.pop() {
+            // Fails if the waiter has given up in the meantime
+            if waiter.wake.try_send(()).is_ok() {
+                return
+            }
+        }
+        state.is_locked = false;
+    }
+}
+
+impl<P: Ord> Default for PriorityLock<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Pending lock acquisition. If it gets cancelled after the lock
+/// was handed over, the lock is passed on to the next waiter.
+struct PriorityLockWait<'a, P: Ord> {
+    lock: &'a PriorityLock<P>,
+    wake: Receiver<()>,
+    is_done: bool,
+}
+
+impl<P: Ord> Drop for PriorityLockWait<'_, P> {
+    fn drop(&mut self) {
+        if self.is_done {
+            return
+        }
+
+        self.wake.close();
+        if self.wake.try_recv().is_ok() {
+            self.lock.release();
+        }
+    }
+}
+
+/// Releases the [`PriorityLock`] when dropped
+pub struct PriorityLockGuard<'a, P: Ord> {
+    lock: &'a PriorityLock<P>,
+}
+
+impl<P: Ord> Drop for PriorityLockGuard<'_, P> {
+    fn drop(&mut self) {
+        self.lock.release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smol::Executor;
+    use std::sync::Arc;
+
+    #[test]
+    fn priority_lock_order() {
+        let executor = Arc::new(Executor::new());
+        let executor_ = executor.clone();
+        smol::block_on(executor.run(async move {
+            let lock = Arc::new(PriorityLock::new());
+            let order = Arc::new(Mutex::new(vec![]));
+
+            let guard = lock.lock(0).await;
+
+            let mut tasks = vec![];
+            for (name, priority) in [("low", 0), ("high", 2), ("mid", 1), ("high2", 2)] {
+                let lock_ = lock.clone();
+                let order_ = order.clone();
+                tasks.push(executor_.spawn(async move {
+                    let _guard = lock_.lock(priority).await;
+                    order_.lock().unwrap().push(name);
+                }));
+
+                // Let the task queue up before spawning the next one
+                while lock.state.lock().unwrap().waiters.len() < tasks.len() {
+                    smol::future::yield_now().await;
+                }
+            }
+
+            drop(guard);
+            for task in tasks {
+                task.await;
+            }
+
+            assert_eq!(*order.lock().unwrap(), vec!["high", "high2", "mid", "low"]);
+        }))
+    }
+
+    #[test]
+    fn priority_lock_cancel() {
+        smol::block_on(async {
+            let lock = PriorityLock::new();
+            let guard = lock.lock(0).await;
+
+            // Give up waiting, then release the lock
+            {
+                let wait = lock.lock(1);
+                futures::pin_mut!(wait);
+                assert!(futures::poll!(wait.as_mut()).is_pending());
+            }
+            drop(guard);
+
+            // The lock must be free again
+            let _guard = lock.lock(0).await;
+            assert!(lock.state.lock().unwrap().waiters.is_empty());
+        })
+    }
+}
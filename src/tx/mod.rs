@@ -41,6 +41,12 @@ use crate::{
     Error, Result,
 };
 
+/// Transaction signers
+pub mod signer;
+#[cfg(feature = "rpc")]
+pub use signer::ExternalSigner;
+pub use signer::{SecretKeySigner, TransactionSigner};
+
 macro_rules! zip {
     ($x:expr) => ($x);
     ($x:expr, $($y:expr), +) => (
@@ -119,13 +125,18 @@ impl Transaction {
         Ok(())
     }
 
-    /// Verify Schnorr signatures for the entire transaction.
-    pub fn verify_sigs(&self, pub_table: Vec<Vec<PublicKey>>) -> Result<()> {
-        // Hash the transaction without the signatures
+    /// Hash the transaction without the signatures. This is the message
+    /// all of the transaction signatures are created over.
+    pub fn sigs_data_hash(&self) -> Result<blake3::Hash> {
         let mut hasher = blake3::Hasher::new();
         self.calls.encode(&mut hasher)?;
         self.proofs.encode(&mut hasher)?;
-        let data_hash = hasher.finalize();
+        Ok(hasher.finalize())
+    }
+
+    /// Verify Schnorr signatures for the entire transaction.
+    pub fn verify_sigs(&self, pub_table: Vec<Vec<PublicKey>>) -> Result<()> {
+        let data_hash = self.sigs_data_hash()?;
 
         debug!(target: "tx::verify_sigs", "tx.verify_sigs: data_hash: {data_hash}");
 
@@ -150,11 +161,7 @@ impl Transaction {
 
     /// Create Schnorr signatures for the entire transaction.
     pub fn create_sigs(&self, secret_keys: &[SecretKey]) -> Result<Vec<Signature>> {
-        // Hash the transaction without the signatures
-        let mut hasher = blake3::Hasher::new();
-        self.calls.encode(&mut hasher)?;
-        self.proofs.encode(&mut hasher)?;
-        let data_hash = hasher.finalize();
+        let data_hash = self.sigs_data_hash()?;
 
         debug!(target: "tx::create_sigs", "[TX] tx.create_sigs: data_hash: {data_hash}");
 
@@ -171,6 +178,22 @@ impl Transaction {
         Ok(sigs)
     }
 
+    /// Create Schnorr signatures for the entire transaction through a
+    /// [`TransactionSigner`], for each of the provided public keys.
+    pub async fn create_sigs_with(
+        &self,
+        signer: &dyn TransactionSigner,
+        public_keys: &[PublicKey],
+    ) -> Result<Vec<Signature>> {
+        let mut sigs = Vec::with_capacity(public_keys.len());
+        for public in public_keys {
+            debug!(target: "tx::create_sigs_with", "[TX] Requesting signature with public key: {public}");
+            sigs.push(signer.sign(self, public).await?);
+        }
+
+        Ok(sigs)
+    }
+
     /// Get the transaction hash
     pub fn hash(&self) -> TransactionHash {
         let mut hasher = blake3::Hasher::new();
@@ -0,0 +1,191 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Transaction signers.
+//!
+//! Signature creation goes through [`TransactionSigner`], so keys which are
+//! only ever used to sign can stay in an external process or device instead
+//! of being loaded into memory. Keys witnessed in ZK proofs, like coin
+//! secrets or the ephemeral keys call builders create for input signatures,
+//! are still needed by the prover and are signed with [`SecretKeySigner`].
+//!
+//! [`ExternalSigner`] talks to a signer process over newline-delimited
+//! JSON-RPC on its stdin/stdout. It sends requests of the form:
+//! ```text
+//! --> {"jsonrpc": "2.0", "method": "sign", "params": ["public_key", "base64encodedTX"], "id": 1}
+//! <-- {"jsonrpc": "2.0", "result": "base64encodedSignature", "id": 1}
+//! ```
+//! The full transaction is sent so devices can show what they are signing.
+//! The signature has to be over [`Transaction::sigs_data_hash`].
+//!
+//! `drk` uses it for contract deploy authorities, whose keys only ever
+//! sign deployment and lock transactions.
+
+use darkfi_sdk::crypto::{
+    schnorr::{SchnorrSecret, Signature},
+    PublicKey, SecretKey,
+};
+use darkfi_serial::async_trait;
+
+use super::Transaction;
+use crate::{Error, Result};
+
+/// Creates Schnorr signatures over transactions
+#[async_trait]
+pub trait TransactionSigner: Send + Sync {
+    /// Sign the transaction with the secret key of `public`
+    async fn sign(&self, tx: &Transaction, public: &PublicKey) -> Result<Signature>;
+}
+
+/// [`TransactionSigner`] holding its secret keys in memory
+pub struct SecretKeySigner(pub Vec<SecretKey>);
+
+#[async_trait]
+impl TransactionSigner for SecretKeySigner {
+    async fn sign(&self, tx: &Transaction, public: &PublicKey) -> Result<Signature> {
+        let Some(secret) = self.0.iter().find(|s| PublicKey::from_secret(**s) == *public) else {
+            return Err(Error::Custom(format!("No secret key found for {public}")))
+        };
+
+        Ok(secret.sign(tx.sigs_data_hash()?.as_bytes()))
+    }
+}
+
+#[cfg(feature = "rpc")]
+pub use external::ExternalSigner;
+
+#[cfg(feature = "rpc")]
+mod external {
+    use darkfi_sdk::crypto::{
+        schnorr::{SchnorrPublic, Signature},
+        PublicKey,
+    };
+    use darkfi_serial::{async_trait, deserialize_async, serialize_async};
+    use log::debug;
+    use smol::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        lock::Mutex,
+        process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+    };
+    use tinyjson::JsonValue;
+
+    use super::{Transaction, TransactionSigner};
+    use crate::{
+        rpc::jsonrpc::{JsonRequest, JsonResponse},
+        util::encoding::base64,
+        Error, Result,
+    };
+
+    /// [`TransactionSigner`] delegating signatures to an external process
+    pub struct ExternalSigner {
+        io: Mutex<(ChildStdin, BufReader<ChildStdout>)>,
+        _child: Child,
+    }
+
+    impl ExternalSigner {
+        /// Spawn the signer process `program` with provided arguments.
+        pub fn new(program: &str, args: &[String]) -> Result<Self> {
+            let mut child = Command::new(program)
+                .args(args)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .kill_on_drop(true)
+                .spawn()?;
+
+            let stdin = child.stdin.take().unwrap();
+            let stdout = BufReader::new(child.stdout.take().unwrap());
+
+            Ok(Self { io: Mutex::new((stdin, stdout)), _child: child })
+        }
+    }
+
+    #[async_trait]
+    impl TransactionSigner for ExternalSigner {
+        async fn sign(&self, tx: &Transaction, public: &PublicKey) -> Result<Signature> {
+            let req = JsonRequest::new(
+                "sign",
+                JsonValue::Array(vec![
+                    JsonValue::String(public.to_string()),
+                    JsonValue::String(base64::encode(&serialize_async(tx).await)),
+                ]),
+            );
+            debug!(target: "tx::signer", "[TX] Requesting signature for {public} from external signer");
+
+            // Hold the lock for the whole exchange so responses can't interleave
+            let mut io = self.io.lock().await;
+            io.0.write_all(format!("{}\n", req.stringify()?).as_bytes()).await?;
+            io.0.flush().await?;
+
+            let mut line = String::new();
+            if io.1.read_line(&mut line).await? == 0 {
+                return Err(Error::Custom("External signer closed its output".to_string()))
+            }
+            drop(io);
+
+            let rep: JsonValue = line.trim().parse()?;
+            let rep = match JsonResponse::try_from(&rep) {
+                Ok(r) => r,
+                Err(e) => return Err(Error::Custom(format!("External signer failed: {e:?}"))),
+            };
+            if rep.id != req.id {
+                return Err(Error::Custom("External signer response ID mismatch".to_string()))
+            }
+
+            let Some(sig) = rep.result.get::<String>() else {
+                return Err(Error::Custom("External signer returned invalid result".to_string()))
+            };
+            let Some(sig_bytes) = base64::decode(sig) else {
+                return Err(Error::Custom("External signer returned invalid base64".to_string()))
+            };
+            let signature: Signature = deserialize_async(&sig_bytes).await?;
+
+            // Don't trust the device to have signed the right thing
+            if !public.verify(tx.sigs_data_hash()?.as_bytes(), &signature) {
+                return Err(Error::InvalidSignature)
+            }
+
+            Ok(signature)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use darkfi_sdk::crypto::Keypair;
+    use rand::rngs::OsRng;
+
+    use super::*;
+
+    #[test]
+    fn secret_key_signer() {
+        smol::block_on(async {
+            let keypairs = [Keypair::random(&mut OsRng), Keypair::random(&mut OsRng)];
+            let signer = SecretKeySigner(keypairs.iter().map(|k| k.secret).collect());
+            let publics: Vec<PublicKey> = keypairs.iter().map(|k| k.public).collect();
+
+            let mut tx = Transaction::default();
+            let sigs = tx.create_sigs_with(&signer, &publics).await.unwrap();
+            tx.signatures.push(sigs);
+            assert!(tx.verify_sigs(vec![publics]).is_ok());
+
+            // Keys the signer doesn't hold are refused
+            let other = Keypair::random(&mut OsRng).public;
+            assert!(tx.create_sigs_with(&signer, &[other]).await.is_err());
+        });
+    }
+}
@@ -22,7 +22,7 @@ use std::{
     path::Path,
     str,
     sync::{Arc, Mutex},
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 use simplelog::ConfigBuilder;
@@ -123,7 +123,6 @@ pub fn get_log_config(verbosity_level: u8) -> simplelog::Config {
 /// easy-parallel = "3.2.0"
 /// signal-hook-async-std = "0.2.2"
 /// signal-hook = "0.3.15"
-/// simplelog = "0.12.0"
 /// smol = "1.2.5"
 ///
 /// # Argument parsing
@@ -153,6 +152,10 @@ pub fn get_log_config(verbosity_level: u8) -> simplelog::Config {
 ///     /// Set log file to ouput into
 ///     log: Option<String>,
 ///
+///     #[structopt(long)]
+///     /// Serve Prometheus metrics on this URL
+///     metrics_listen: Option<String>,
+///
 ///     #[structopt(short, parse(from_occurrences))]
 ///     /// Increase verbosity (-vvv supported)
 ///     verbose: u8,
@@ -205,48 +208,10 @@ macro_rules! async_daemonize {
                 }
             };
 
-            let log_level = darkfi::util::cli::get_log_level(args.verbose);
-            let log_config = darkfi::util::cli::get_log_config(args.verbose);
-
-            // Setup terminal logger
-            let term_logger = simplelog::TermLogger::new(
-                log_level,
-                log_config.clone(),
-                simplelog::TerminalMode::Mixed,
-                simplelog::ColorChoice::Auto,
-            );
-
-            // If a log file has been configured, also create a write logger.
-            // Otherwise, output to terminal logger only.
-            match args.log {
-                Some(ref log_path) => {
-                    let log_path = match darkfi::util::path::expand_path(log_path) {
-                        Ok(v) => v,
-                        Err(e) => {
-                            eprintln!("Expanding log path failed `{log_path:?}`: {e}");
-                            return Err(e)
-                        }
-                    };
-                    let log_file = match std::fs::File::create(&log_path) {
-                        Ok(v) => v,
-                        Err(e) => {
-                            eprintln!("Creating log file failed `{log_path:?}`: {e}");
-                            return Err(e.into())
-                        }
-                    };
-                    let write_logger = simplelog::WriteLogger::new(log_level, log_config, log_file);
-                    if let Err(e) = simplelog::CombinedLogger::init(vec![term_logger, write_logger])
-                    {
-                        eprintln!("Unable to init logger with term + logfile combo: {e}");
-                        return Err(e.into())
-                    }
-                }
-                None => {
-                    if let Err(e) = simplelog::CombinedLogger::init(vec![term_logger]) {
-                        eprintln!("Unable to init term logger: {e}");
-                        return Err(e.into())
-                    }
-                }
+            // Setup terminal and log file logger
+            if let Err(e) = darkfi::util::logger::setup_logging(args.verbose, args.log.as_deref()) {
+                eprintln!("Unable to init logger: {e}");
+                return Err(e)
             }
 
             // https://docs.rs/smol/latest/smol/struct.Executor.html#examples
@@ -259,6 +224,10 @@ macro_rules! async_daemonize {
                 // Run the main future on the current thread.
                 .finish(|| {
                     smol::future::block_on(async {
+                        // Serve Prometheus metrics if configured
+                        if let Some(ref metrics_listen) = args.metrics_listen {
+                            darkfi::system::metrics::serve(metrics_listen, ex.clone()).await?;
+                        }
                         $realmain(args, ex.clone()).await?;
                         drop(signal);
                         Ok::<(), darkfi::Error>(())
@@ -382,6 +351,10 @@ pub fn fg_reset() -> String {
 pub struct ProgressInc {
     position: Arc<Mutex<u64>>,
     timer: Arc<Mutex<Option<Instant>>>,
+    /// Position the timer was started at, used to compute the rate
+    start: Arc<Mutex<u64>>,
+    /// Expected final position, used to print an ETA
+    expected: Arc<Mutex<Option<u64>>>,
 }
 
 impl Default for ProgressInc {
@@ -393,7 +366,25 @@ impl Default for ProgressInc {
 impl ProgressInc {
     pub fn new() -> Self {
         eprint!("\x1b[?25l");
-        Self { position: Arc::new(Mutex::new(0)), timer: Arc::new(Mutex::new(None)) }
+        Self {
+            position: Arc::new(Mutex::new(0)),
+            timer: Arc::new(Mutex::new(None)),
+            start: Arc::new(Mutex::new(0)),
+            expected: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Continue counting from a previously reached position
+    pub fn resume(&self, position: u64) {
+        let mut pos = self.position.lock().unwrap();
+        *pos = position;
+        *self.start.lock().unwrap() = position;
+        *self.timer.lock().unwrap() = Some(Instant::now());
+    }
+
+    /// Set the expected final position, enabling the ETA display
+    pub fn set_expected(&self, expected: u64) {
+        *self.expected.lock().unwrap() = Some(expected);
     }
 
     pub fn inc(&self, n: u64) {
@@ -410,7 +401,21 @@ impl ProgressInc {
         let elapsed = elapsed.elapsed();
         let pos = *position;
 
-        eprint!("\r[{elapsed:?}] {pos} attempts");
+        let Some(expected) = *self.expected.lock().unwrap() else {
+            eprint!("\r[{elapsed:?}] {pos} attempts");
+            return
+        };
+
+        // Attempts per second since the timer was started
+        let done = pos - *self.start.lock().unwrap();
+        let rate = done as f64 / elapsed.as_secs_f64();
+        if pos >= expected || !rate.is_normal() {
+            eprint!("\r\x1b[2K[{elapsed:?}] {pos}/~{expected} attempts");
+            return
+        }
+
+        let eta = Duration::from_secs(((expected - pos) as f64 / rate) as u64);
+        eprint!("\r\x1b[2K[{elapsed:?}] {pos}/~{expected} attempts, ETA {eta:?}");
     }
 
     pub fn position(&self) -> u64 {
@@ -0,0 +1,317 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Daemon logging
+//!
+//! [`setup_logging()`] installs the logger used by daemons built with
+//! `async_daemonize!`. Records go to the terminal, errors to stderr and
+//! everything else to stdout, and optionally to a log file.
+//!
+//! Setting `LOG_FORMAT=json` writes every record as a single JSON object
+//! with its time, level, target and message. Key-values attached to the
+//! record, e.g. `info!(target: "net", peer = addr.as_str(); "Connected")`,
+//! become fields of the object, so log processors can filter by peer
+//! (`peer`), block height (`slot`) or transaction hash (`tx`).
+//!
+//! Levels can be overridden per target at runtime with
+//! [`set_target_level()`], which daemons expose over JSON-RPC as
+//! `log.set_level`. A target level applies to the target itself and all
+//! targets nested under it, e.g. `net` covers `net::channel`.
+
+use std::{
+    env,
+    fmt::Write as _,
+    fs::File,
+    io::{IsTerminal, Write},
+    str::FromStr,
+    sync::{Mutex, OnceLock, RwLock},
+    time::UNIX_EPOCH,
+};
+
+use log::{
+    kv::{Key, Value, VisitSource},
+    Level, LevelFilter, Log, Metadata, Record,
+};
+use tinyjson::JsonValue;
+
+use super::{cli::get_log_level, path::expand_path, time::DateTime};
+use crate::{Error, Result};
+
+/// The installed logger, used to adjust levels at runtime
+static LOGGER: OnceLock<&'static Logger> = OnceLock::new();
+
+/// Output format of log records
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human readable lines
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(Error::ParseFailed("Invalid log format")),
+        }
+    }
+}
+
+/// Log levels, by target
+struct Levels {
+    /// Level of targets without an override
+    default: LevelFilter,
+    /// Overridden targets and their level
+    targets: Vec<(String, LevelFilter)>,
+}
+
+impl Levels {
+    /// Level of the given target. The most specific override wins.
+    fn level(&self, target: &str) -> LevelFilter {
+        let mut best: Option<(usize, LevelFilter)> = None;
+        for (t, level) in &self.targets {
+            let matches = target == t ||
+                (target.starts_with(t.as_str()) && target[t.len()..].starts_with("::"));
+            if matches && best.is_none_or(|(len, _)| t.len() > len) {
+                best = Some((t.len(), *level));
+            }
+        }
+        best.map_or(self.default, |(_, level)| level)
+    }
+
+    /// Most verbose level any target can log at
+    fn max(&self) -> LevelFilter {
+        self.targets.iter().map(|(_, level)| *level).fold(self.default, Ord::max)
+    }
+
+    fn set(&mut self, target: &str, level: LevelFilter) {
+        match self.targets.iter_mut().find(|(t, _)| t == target) {
+            Some(entry) => entry.1 = level,
+            None => self.targets.push((target.to_string(), level)),
+        }
+    }
+}
+
+/// Logger writing to the terminal and an optional file
+struct Logger {
+    levels: RwLock<Levels>,
+    format: LogFormat,
+    /// Whether to print the target in text records
+    show_target: bool,
+    /// Whether to color the level in terminal text records
+    color: bool,
+    file: Option<Mutex<File>>,
+}
+
+impl Logger {
+    fn format_record(&self, record: &Record, color: bool) -> String {
+        let now = UNIX_EPOCH.elapsed().unwrap_or_default();
+        let time = DateTime::from_timestamp(now.as_secs(), now.subsec_nanos());
+
+        let mut fields = FieldCollector(vec![]);
+        let _ = record.key_values().visit(&mut fields);
+
+        match self.format {
+            LogFormat::Json => {
+                let mut out = format!(
+                    "{{\"time\":\"{time}.{:03}Z\",\"level\":\"{}\",\"target\":{},\"msg\":{}",
+                    time.nanos / 1_000_000,
+                    record.level(),
+                    json_string(record.target()),
+                    json_string(&record.args().to_string()),
+                );
+                for (key, value) in fields.0 {
+                    let _ = write!(out, ",{}:{}", json_string(&key), json_string(&value));
+                }
+                out.push('}');
+                out
+            }
+
+            LogFormat::Text => {
+                let level = match (color, record.level()) {
+                    (false, level) => format!("[{level}]"),
+                    (true, Level::Error) => format!("[\x1b[31m{}\x1b[0m]", Level::Error),
+                    (true, Level::Warn) => format!("[\x1b[33m{}\x1b[0m]", Level::Warn),
+                    (true, Level::Info) => format!("[\x1b[32m{}\x1b[0m]", Level::Info),
+                    (true, Level::Debug) => format!("[\x1b[34m{}\x1b[0m]", Level::Debug),
+                    (true, Level::Trace) => format!("[\x1b[35m{}\x1b[0m]", Level::Trace),
+                };
+
+                let mut out = format!("{:02}:{:02}:{:02} {level} ", time.hour, time.min, time.sec);
+                if self.show_target {
+                    let _ = write!(out, "({}) ", record.target());
+                }
+                let _ = write!(out, "{}", record.args());
+                for (key, value) in fields.0 {
+                    let _ = write!(out, " {key}={value}");
+                }
+                out
+            }
+        }
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.levels.read().unwrap().level(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return
+        }
+
+        // Write errors have nowhere to be reported, so they are ignored
+        let line = self.format_record(record, self.color);
+        if record.level() == Level::Error {
+            let _ = writeln!(std::io::stderr(), "{line}");
+        } else {
+            let _ = writeln!(std::io::stdout(), "{line}");
+        }
+
+        if let Some(file) = &self.file {
+            let line = if self.color { self.format_record(record, false) } else { line };
+            let _ = writeln!(file.lock().unwrap(), "{line}");
+        }
+    }
+
+    fn flush(&self) {
+        let _ = std::io::stdout().flush();
+        if let Some(file) = &self.file {
+            let _ = file.lock().unwrap().flush();
+        }
+    }
+}
+
+/// Collects the key-values of a record as strings
+struct FieldCollector(Vec<(String, String)>);
+
+impl<'kvs> VisitSource<'kvs> for FieldCollector {
+    fn visit_pair(
+        &mut self,
+        key: Key<'kvs>,
+        value: Value<'kvs>,
+    ) -> std::result::Result<(), log::kv::Error> {
+        self.0.push((key.to_string(), value.to_string()));
+        Ok(())
+    }
+}
+
+/// Encode a string as a JSON string literal
+fn json_string(s: &str) -> String {
+    JsonValue::String(s.to_string()).stringify().unwrap()
+}
+
+/// Install the daemon logger. The default level follows the `-v` flag
+/// occurrences, and records are additionally written to `log_file` if
+/// given. The output format is picked with the `LOG_FORMAT` environment
+/// variable.
+///
+/// The `LOG_TARGETS` environment variable keeps working as before: a
+/// comma separated list of targets to log, with `!`-prefixed targets
+/// being silenced instead.
+pub fn setup_logging(verbosity: u8, log_file: Option<&str>) -> Result<()> {
+    let format = match env::var("LOG_FORMAT") {
+        Ok(v) => v.parse()?,
+        Err(_) => LogFormat::Text,
+    };
+
+    let mut levels = Levels { default: get_log_level(verbosity), targets: vec![] };
+    if let Ok(targets) = env::var("LOG_TARGETS") {
+        let default = levels.default;
+        for target in targets.split(',').filter(|t| !t.is_empty()) {
+            match target.strip_prefix('!') {
+                Some(target) => levels.set(target, LevelFilter::Off),
+                None => {
+                    // Listing targets to log silences all the others
+                    levels.default = LevelFilter::Off;
+                    levels.set(target, default);
+                }
+            }
+        }
+    }
+
+    let file = match log_file {
+        Some(path) => Some(Mutex::new(File::create(expand_path(path)?)?)),
+        None => None,
+    };
+
+    let max_level = levels.max();
+    let logger = Logger {
+        levels: RwLock::new(levels),
+        format,
+        show_target: verbosity > 0,
+        color: format == LogFormat::Text && std::io::stdout().is_terminal(),
+        file,
+    };
+
+    let logger: &'static Logger = Box::leak(Box::new(logger));
+    if log::set_logger(logger).is_err() {
+        return Err(Error::Custom("A logger is already installed".to_string()))
+    }
+    log::set_max_level(max_level);
+    let _ = LOGGER.set(logger);
+
+    Ok(())
+}
+
+/// Set the log level of the given target and the targets nested under
+/// it. The `*` target sets the default level of all other targets.
+pub fn set_target_level(target: &str, level: LevelFilter) -> Result<()> {
+    let Some(logger) = LOGGER.get() else {
+        return Err(Error::Custom("Logger is not installed".to_string()))
+    };
+
+    let mut levels = logger.levels.write().unwrap();
+    if target == "*" {
+        levels.default = level;
+    } else {
+        levels.set(target, level);
+    }
+    log::set_max_level(levels.max());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_levels() {
+        let mut levels = Levels { default: LevelFilter::Info, targets: vec![] };
+        levels.set("net", LevelFilter::Debug);
+        levels.set("net::channel", LevelFilter::Off);
+
+        assert_eq!(levels.level("validator"), LevelFilter::Info);
+        assert_eq!(levels.level("net"), LevelFilter::Debug);
+        assert_eq!(levels.level("net::hosts"), LevelFilter::Debug);
+        assert_eq!(levels.level("net::channel::send()"), LevelFilter::Off);
+        // Only whole path segments match
+        assert_eq!(levels.level("network"), LevelFilter::Info);
+        assert_eq!(levels.max(), LevelFilter::Debug);
+
+        levels.set("net", LevelFilter::Warn);
+        assert_eq!(levels.level("net::hosts"), LevelFilter::Warn);
+        assert_eq!(levels.targets.len(), 2);
+    }
+}
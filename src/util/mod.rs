@@ -25,6 +25,9 @@ pub mod encoding;
 /// Filesystem utilities
 pub mod file;
 
+/// Daemon logging setup
+pub mod logger;
+
 /// Parsing helpers
 pub mod parse;
 
@@ -16,11 +16,14 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::atomic::AtomicU64,
+};
 
 use darkfi_sdk::{crypto::MerkleTree, monotree::Monotree, tx::TransactionHash};
-use darkfi_serial::{async_trait, SerialDecodable, SerialEncodable};
-use log::{debug, info, warn};
+use darkfi_serial::{async_trait, serialize, SerialDecodable, SerialEncodable};
+use log::{debug, info};
 use num_bigint::BigUint;
 use sled_overlay::database::SledDbOverlayStateDiff;
 use smol::lock::RwLock;
@@ -34,6 +37,7 @@ use crate::{
     tx::{Transaction, MAX_TX_CALLS},
     validator::{
         pow::PoWModule,
+        template::{TemplateTotals, TxDecision, TxSelectionLimits, TxSelectionPolicy},
         utils::{best_fork_index, block_rank, find_extended_fork_index},
         verification::{verify_proposal, verify_transaction},
     },
@@ -44,6 +48,10 @@ use crate::{
 /// Gas limit for total block transactions(50 full transactions).
 pub const BLOCK_GAS_LIMIT: u64 = GAS_LIMIT * MAX_TX_CALLS as u64 * 50;
 
+/// Number of block targets that must elapse between two blocks before
+/// the interval gets recorded as a [`BlockGap`](crate::blockchain::BlockGap).
+pub const BLOCK_GAP_THRESHOLD: u64 = 3;
+
 /// This struct represents the information required by the consensus algorithm
 pub struct Consensus {
     /// Canonical (confirmed) blockchain
@@ -56,6 +64,10 @@ pub struct Consensus {
     pub module: RwLock<PoWModule>,
     /// Lock to restrict when proposals appends can happen
     pub append_lock: RwLock<()>,
+    /// Proposals received since last confirmation
+    pub proposals_received: AtomicU64,
+    /// Proposals rejected since last confirmation
+    pub proposals_rejected: AtomicU64,
 }
 
 impl Consensus {
@@ -74,7 +86,15 @@ impl Consensus {
             None,
         )?);
         let append_lock = RwLock::new(());
-        Ok(Self { blockchain, confirmation_threshold, forks, module, append_lock })
+        Ok(Self {
+            blockchain,
+            confirmation_threshold,
+            forks,
+            module,
+            append_lock,
+            proposals_received: AtomicU64::new(0),
+            proposals_rejected: AtomicU64::new(0),
+        })
     }
 
     /// Generate a new empty fork.
@@ -798,6 +818,8 @@ impl Fork {
     /// along with their total gas used, total paid fees and the overlay
     /// used to verify the transactions for further processing.
     ///
+    /// Transactions are selected using the default [`TxSelectionLimits`].
+    ///
     /// Note: Always remember to purge new trees from the overlay if not needed.
     pub async fn unproposed_txs(
         &self,
@@ -805,6 +827,29 @@ impl Fork {
         verifying_block_height: u32,
         block_target: u32,
         verify_fees: bool,
+    ) -> Result<(Vec<Transaction>, u64, u64, BlockchainOverlayPtr)> {
+        self.unproposed_txs_with_policy(
+            blockchain,
+            verifying_block_height,
+            block_target,
+            verify_fees,
+            &TxSelectionLimits::default(),
+        )
+        .await
+    }
+
+    /// Same as [`Fork::unproposed_txs`], but each verified transaction is
+    /// passed through the provided [`TxSelectionPolicy`], which decides
+    /// whether it gets included, skipped, or ends the selection.
+    ///
+    /// Note: Always remember to purge new trees from the overlay if not needed.
+    pub async fn unproposed_txs_with_policy(
+        &self,
+        blockchain: &Blockchain,
+        verifying_block_height: u32,
+        block_target: u32,
+        verify_fees: bool,
+        policy: &dyn TxSelectionPolicy,
     ) -> Result<(Vec<Transaction>, u64, u64, BlockchainOverlayPtr)> {
         // Clone forks' overlay
         let overlay = self.overlay.lock().unwrap().full_clone()?;
@@ -817,9 +862,8 @@ impl Fork {
         // Transactions Merkle tree
         let mut tree = MerkleTree::new(1);
 
-        // Total gas accumulators
-        let mut total_gas_used = 0;
-        let mut total_gas_paid = 0;
+        // Selected transactions totals
+        let mut totals = TemplateTotals::default();
 
         // Map of ZK proof verifying keys for the current transaction batch
         let mut vks: HashMap<[u8; 32], HashMap<String, VerifyingKey>> = HashMap::new();
@@ -865,31 +909,32 @@ impl Fork {
                 }
             };
 
-            // Store the gas used by the verified transaction
-            let tx_gas_used = gas_data.total_gas_used();
-
-            // Calculate current accumulated gas usage
-            let accumulated_gas_usage = total_gas_used + tx_gas_used;
-
-            // Check gas limit - if accumulated gas used exceeds it, break out of loop
-            if accumulated_gas_usage > BLOCK_GAS_LIMIT {
-                warn!(
-                    target: "validator::consensus::unproposed_txs",
-                    "Retrieving transaction {tx} would exceed configured unproposed transaction gas limit: {accumulated_gas_usage} - {BLOCK_GAS_LIMIT}"
-                );
-                overlay.lock().unwrap().revert_to_checkpoint()?;
-                break
+            // Ask the policy what to do with the verified transaction
+            let tx_size = serialize(&unproposed_tx).len();
+            match policy.accept(&unproposed_tx, tx_size, &gas_data, &totals) {
+                TxDecision::Include => {}
+                TxDecision::Skip => {
+                    debug!(target: "validator::consensus::unproposed_txs", "Skipping transaction {tx} by selection policy");
+                    overlay.lock().unwrap().revert_to_checkpoint()?;
+                    continue
+                }
+                TxDecision::Stop => {
+                    overlay.lock().unwrap().revert_to_checkpoint()?;
+                    break
+                }
             }
 
-            // Update accumulated total gas
-            total_gas_used += tx_gas_used;
-            total_gas_paid += gas_data.paid;
+            // Update the selection totals
+            totals.txs += 1;
+            totals.size += tx_size;
+            totals.gas_used += gas_data.total_gas_used();
+            totals.gas_paid += gas_data.paid;
 
             // Push the tx hash into the unproposed transactions vector
             unproposed_txs.push(unproposed_tx);
         }
 
-        Ok((unproposed_txs, total_gas_used, total_gas_paid, overlay))
+        Ok((unproposed_txs, totals.gas_used, totals.gas_paid, overlay))
     }
 
     /// Auxiliary function to create a full clone using BlockchainOverlay::full_clone.
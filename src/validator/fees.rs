@@ -72,6 +72,7 @@ pub fn circuit_gas_use(zkbin: &ZkBinary) -> u64 {
             Opcode::PoseidonHash => 20 + 10 * opcode.1.len() as u64,
             Opcode::MerkleRoot => 10 * MERKLE_DEPTH_ORCHARD as u64,
             Opcode::SparseMerkleRoot => 10 * SPARSE_MERKLE_DEPTH as u64,
+            Opcode::SparseMerkleNonMemberRoot => 10 * SPARSE_MERKLE_DEPTH as u64,
             Opcode::BaseAdd => 15,
             Opcode::BaseMul => 15,
             Opcode::BaseSub => 15,
@@ -82,6 +83,8 @@ pub fn circuit_gas_use(zkbin: &ZkBinary) -> u64 {
             Opcode::BoolCheck => 20,
             Opcode::CondSelect => 10,
             Opcode::ZeroCondSelect => 10,
+            Opcode::ElGamalSharedSecret => 80,
+            Opcode::ElGamalEncrypt => 55,
             Opcode::ConstrainEqualBase => 10,
             Opcode::ConstrainEqualPoint => 20,
             Opcode::ConstrainInstance => 10,
@@ -16,7 +16,10 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{atomic::Ordering, Arc},
+};
 
 use darkfi_sdk::crypto::MerkleTree;
 use log::{debug, error, info, warn};
@@ -26,10 +29,11 @@ use smol::lock::RwLock;
 
 use crate::{
     blockchain::{
-        block_store::{BlockDifficulty, BlockInfo, BlockRanks},
+        block_store::{BlockDifficulty, BlockGap, BlockInfo, BlockRanks},
         Blockchain, BlockchainOverlay, HeaderHash,
     },
     error::TxVerifyFailed,
+    system::METRICS,
     tx::Transaction,
     zk::VerifyingKey,
     Error, Result,
@@ -37,7 +41,7 @@ use crate::{
 
 /// DarkFi consensus module
 pub mod consensus;
-use consensus::{Consensus, Fork, Proposal};
+use consensus::{Consensus, Fork, Proposal, BLOCK_GAP_THRESHOLD};
 
 /// DarkFi PoW module
 pub mod pow;
@@ -54,6 +58,9 @@ use verification::{
 pub mod fees;
 use fees::compute_fee;
 
+/// Block template builder
+pub mod template;
+
 /// Helper utilities
 pub mod utils;
 use utils::{best_fork_index, block_rank, deploy_native_contracts};
@@ -326,6 +333,12 @@ impl Validator {
         // Execute append
         let result = self.consensus.append_proposal(proposal, self.verify_fees).await;
 
+        // Keep track of what we saw, for block gap records
+        self.consensus.proposals_received.fetch_add(1, Ordering::Relaxed);
+        if result.is_err() {
+            self.consensus.proposals_rejected.fetch_add(1, Ordering::Relaxed);
+        }
+
         // Release append lock
         drop(append_lock);
 
@@ -383,7 +396,7 @@ impl Validator {
         let mut state_inverse_diffs = vec![];
         info!(target: "validator::confirmation", "Confirming proposals:");
         for (index, proposal) in confirmed_proposals.iter().enumerate() {
-            info!(target: "validator::confirmation", "\t{proposal} - {}", confirmed_blocks[index].header.height);
+            info!(target: "validator::confirmation", slot = confirmed_blocks[index].header.height; "\t{proposal} - {}", confirmed_blocks[index].header.height);
             fork.overlay.lock().unwrap().overlay.lock().unwrap().apply_diff(&diffs[index])?;
             let next_difficulty = module.next_difficulty()?;
             module.append(confirmed_blocks[index].header.timestamp, &next_difficulty);
@@ -391,6 +404,7 @@ impl Validator {
             state_inverse_diffs_heights.push(confirmed_blocks[index].header.height);
             state_inverse_diffs.push(diffs[index].inverse());
         }
+        let pow_target = module.target;
         drop(module);
         drop(forks);
 
@@ -399,16 +413,75 @@ impl Validator {
             .blocks
             .insert_state_inverse_diff(&state_inverse_diffs_heights, &state_inverse_diffs)?;
 
+        // Record any gaps the confirmed blocks closed
+        self.record_block_gaps(&confirmed_blocks, pow_target)?;
+
         // Reset forks starting with the confirmed blocks
         self.consensus.reset_forks(&confirmed_proposals, &confirmed_fork, &confirmed_txs).await?;
         info!(target: "validator::confirmation", "Confirmation completed!");
 
+        if let Some(block) = confirmed_blocks.last() {
+            record_confirmed_height(block.header.height);
+        }
+
         // Release append lock
         drop(append_lock);
 
         Ok(confirmed_blocks)
     }
 
+    /// Record a [`BlockGap`] for every confirmed block that arrived more than
+    /// [`BLOCK_GAP_THRESHOLD`] block targets after its predecessor. The
+    /// proposals observed since the last confirmation are attributed to
+    /// the first such gap.
+    fn record_block_gaps(&self, blocks: &[BlockInfo], pow_target: u32) -> Result<()> {
+        let mut proposals_received = self.consensus.proposals_received.swap(0, Ordering::Relaxed);
+        let mut proposals_rejected = self.consensus.proposals_rejected.swap(0, Ordering::Relaxed);
+        let target = pow_target as u64;
+
+        let mut previous_timestamp = None;
+        for block in blocks {
+            // Genesis has no predecessor
+            if block.header.height == 0 {
+                previous_timestamp = Some(block.header.timestamp);
+                continue
+            }
+
+            let previous_timestamp = match previous_timestamp.replace(block.header.timestamp) {
+                Some(timestamp) => timestamp,
+                None => {
+                    self.blockchain.headers.get(&[block.header.previous], true)?[0]
+                        .as_ref()
+                        .unwrap()
+                        .timestamp
+                }
+            };
+
+            let elapsed = block.header.timestamp.inner().saturating_sub(previous_timestamp.inner());
+            if elapsed < target * BLOCK_GAP_THRESHOLD {
+                continue
+            }
+
+            let gap = BlockGap {
+                previous_timestamp,
+                timestamp: block.header.timestamp,
+                missed: elapsed / target - 1,
+                proposals_received,
+                proposals_rejected,
+            };
+            warn!(
+                target: "validator::confirmation",
+                "Block {} closed a gap of {} missed blocks ({} proposals received, {} rejected)",
+                block.header.height, gap.missed, gap.proposals_received, gap.proposals_rejected,
+            );
+            self.blockchain.blocks.insert_gap(block.header.height, &gap)?;
+            proposals_received = 0;
+            proposals_rejected = 0;
+        }
+
+        Ok(())
+    }
+
     /// Apply provided set of [`BlockInfo`] without doing formal verification.
     /// A set of ['HeaderHash`] is also provided, to verify that the provided
     /// block hash matches the expected header one.
@@ -526,6 +599,10 @@ impl Validator {
         *self.consensus.forks.write().await =
             vec![Fork::new(self.blockchain.clone(), module).await?];
 
+        if let Some(block) = blocks.last() {
+            record_confirmed_height(block.header.height);
+        }
+
         Ok(())
     }
 
@@ -943,3 +1020,10 @@ impl Validator {
         Ok(())
     }
 }
+
+/// Auxiliary function to export the last confirmed block height
+fn record_confirmed_height(height: u32) {
+    METRICS
+        .gauge("darkfi_blockchain_height", "Height of the last confirmed block", &[])
+        .set(height as i64);
+}
@@ -0,0 +1,261 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::sync::Arc;
+
+use darkfi_sdk::crypto::MerkleTree;
+use log::{debug, warn};
+use num_bigint::BigUint;
+
+use crate::{
+    blockchain::{BlockInfo, BlockchainOverlayPtr, Header},
+    tx::Transaction,
+    util::time::Timestamp,
+    validator::{
+        consensus::{Fork, BLOCK_GAS_LIMIT},
+        fees::GasData,
+        verification::apply_producer_transaction,
+    },
+    Error, Result,
+};
+
+/// Accumulated totals of the transactions selected so far
+#[derive(Clone, Debug, Default)]
+pub struct TemplateTotals {
+    /// Number of selected transactions
+    pub txs: usize,
+    /// Serialized size of selected transactions
+    pub size: usize,
+    /// Total gas used by selected transactions
+    pub gas_used: u64,
+    /// Total fees paid by selected transactions
+    pub gas_paid: u64,
+}
+
+/// Outcome of a [`TxSelectionPolicy`] check
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TxDecision {
+    /// Include the transaction in the template
+    Include,
+    /// Leave the transaction out, but keep going
+    Skip,
+    /// Leave the transaction out and finish the selection
+    Stop,
+}
+
+/// Policy deciding which verified mempool transactions end up in a
+/// block template. Transactions are offered in mempool order.
+pub trait TxSelectionPolicy: Send + Sync {
+    /// Decide on a verified transaction, given its serialized size, its
+    /// gas data and the totals of the transactions selected before it.
+    fn accept(
+        &self,
+        tx: &Transaction,
+        tx_size: usize,
+        gas: &GasData,
+        totals: &TemplateTotals,
+    ) -> TxDecision;
+}
+
+/// Default [`TxSelectionPolicy`], bounding the template by gas, size and
+/// transactions count, and skipping transactions paying less than a
+/// minimum fee.
+#[derive(Clone, Debug)]
+pub struct TxSelectionLimits {
+    /// Maximum total gas used by the selected transactions
+    pub max_gas: u64,
+    /// Optional maximum total serialized size of the selected transactions
+    pub max_size: Option<usize>,
+    /// Optional maximum number of selected transactions
+    pub max_txs: Option<usize>,
+    /// Minimum fee a transaction must pay to get selected
+    pub min_fee: u64,
+}
+
+impl Default for TxSelectionLimits {
+    fn default() -> Self {
+        Self { max_gas: BLOCK_GAS_LIMIT, max_size: None, max_txs: None, min_fee: 0 }
+    }
+}
+
+impl TxSelectionPolicy for TxSelectionLimits {
+    fn accept(
+        &self,
+        tx: &Transaction,
+        tx_size: usize,
+        gas: &GasData,
+        totals: &TemplateTotals,
+    ) -> TxDecision {
+        if let Some(max_txs) = self.max_txs {
+            if totals.txs >= max_txs {
+                return TxDecision::Stop
+            }
+        }
+
+        // Check gas limit - if accumulated gas used exceeds it, stop the selection
+        let accumulated_gas_usage = totals.gas_used + gas.total_gas_used();
+        if accumulated_gas_usage > self.max_gas {
+            warn!(
+                target: "validator::template::accept",
+                "Retrieving transaction {} would exceed configured unproposed transaction gas limit: {accumulated_gas_usage} - {}",
+                tx.hash(), self.max_gas,
+            );
+            return TxDecision::Stop
+        }
+
+        // A smaller transaction might still fit, so we just skip this one
+        if let Some(max_size) = self.max_size {
+            if totals.size + tx_size > max_size {
+                return TxDecision::Skip
+            }
+        }
+
+        if gas.paid < self.min_fee {
+            return TxDecision::Skip
+        }
+
+        TxDecision::Include
+    }
+}
+
+/// Transactions selected for a block template, along with the overlay
+/// they were applied on.
+pub struct TxSelection {
+    /// Selected transactions, in mempool order
+    pub txs: Vec<Transaction>,
+    /// Total gas used by the selected transactions
+    pub gas_used: u64,
+    /// Total fees paid by the selected transactions
+    pub fees: u64,
+    /// Overlay containing the selected transactions state changes
+    overlay: BlockchainOverlayPtr,
+}
+
+/// A block ready to be mined. Only the header nonce and the block
+/// signature are left to be filled by the producer.
+pub struct BlockTemplate {
+    /// The unsealed block
+    pub block: BlockInfo,
+    /// Target the block must be mined against
+    pub target: BigUint,
+    /// Total gas used by the block transactions, excluding the producer one
+    pub gas_used: u64,
+    /// Total fees paid by the block transactions
+    pub fees: u64,
+}
+
+/// Builder producing the next [`BlockTemplate`] of a fork.
+///
+/// Building happens in two steps, since the producer transaction usually
+/// depends on the selected transactions fees:
+/// ```ignore
+/// let builder = BlockTemplateBuilder::new(&mut fork, block_target, verify_fees);
+/// let selection = builder.select_txs().await?;
+/// let producer_tx = create_reward_tx(builder.next_height()?, selection.fees)?;
+/// let template = builder.build(selection, producer_tx).await?;
+/// ```
+pub struct BlockTemplateBuilder<'a> {
+    fork: &'a mut Fork,
+    block_target: u32,
+    verify_fees: bool,
+    policy: Arc<dyn TxSelectionPolicy>,
+}
+
+impl<'a> BlockTemplateBuilder<'a> {
+    pub fn new(fork: &'a mut Fork, block_target: u32, verify_fees: bool) -> Self {
+        Self { fork, block_target, verify_fees, policy: Arc::new(TxSelectionLimits::default()) }
+    }
+
+    /// Use a custom transaction selection policy
+    pub fn with_policy(mut self, policy: Arc<dyn TxSelectionPolicy>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Height of the block being built
+    pub fn next_height(&self) -> Result<u32> {
+        Ok(self.fork.last_proposal()?.block.header.height + 1)
+    }
+
+    /// Select the fork's unproposed transactions, subject to the policy
+    pub async fn select_txs(&self) -> Result<TxSelection> {
+        let (txs, gas_used, fees, overlay) = self
+            .fork
+            .unproposed_txs_with_policy(
+                &self.fork.blockchain,
+                self.next_height()?,
+                self.block_target,
+                self.verify_fees,
+                self.policy.as_ref(),
+            )
+            .await?;
+        debug!(
+            target: "validator::template::select_txs",
+            "Selected {} transactions using {gas_used} gas and paying {fees} fees",
+            txs.len(),
+        );
+
+        Ok(TxSelection { txs, gas_used, fees, overlay })
+    }
+
+    /// Apply the producer transaction on top of the selection, recompute
+    /// the contracts states root and generate the block template.
+    pub async fn build(
+        self,
+        selection: TxSelection,
+        producer_tx: Transaction,
+    ) -> Result<BlockTemplate> {
+        let last_proposal = self.fork.last_proposal()?;
+        let next_block_height = last_proposal.block.header.height + 1;
+
+        // Apply producer transaction in the overlay
+        let TxSelection { mut txs, gas_used, fees, overlay } = selection;
+        let _ = apply_producer_transaction(
+            &overlay,
+            next_block_height,
+            self.block_target,
+            &producer_tx,
+            &mut MerkleTree::new(1),
+        )
+        .await?;
+        txs.push(producer_tx);
+
+        // Grab the updated contracts states root
+        overlay.lock().unwrap().contracts.update_state_monotree(&mut self.fork.state_monotree)?;
+        let Some(state_root) = self.fork.state_monotree.get_headroot()? else {
+            return Err(Error::ContractsStatesRootNotFoundError);
+        };
+
+        // Drop new trees opened by the unproposed transactions overlay
+        overlay.lock().unwrap().overlay.lock().unwrap().purge_new_trees()?;
+
+        // Generate the new header
+        let mut header =
+            Header::new(last_proposal.hash, next_block_height, Timestamp::current_time(), 0);
+        header.state_root = state_root;
+
+        // Generate the block and add the transactions to it
+        let mut block = BlockInfo::new_empty(header);
+        block.append_txs(txs);
+
+        // Grab the next mine target
+        let target = self.fork.module.next_mine_target()?;
+
+        Ok(BlockTemplate { block, target, gas_used, fees })
+    }
+}
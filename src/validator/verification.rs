@@ -16,7 +16,7 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Instant};
 
 use darkfi_sdk::{
     blockchain::block_version,
@@ -36,11 +36,12 @@ use smol::io::Cursor;
 
 use crate::{
     blockchain::{
-        block_store::append_tx_to_merkle_tree, header_store::PowData, BlockInfo, Blockchain,
-        BlockchainOverlayPtr, HeaderHash,
+        block_store::append_tx_to_merkle_tree, header_store::PowData, tx_store::TxMetrics,
+        BlockInfo, Blockchain, BlockchainOverlayPtr, HeaderHash,
     },
     error::TxVerifyFailed,
     runtime::vm_runtime::Runtime,
+    system::{metrics::DEFAULT_BUCKETS, METRICS},
     tx::{Transaction, MAX_TX_CALLS, MIN_TX_CALLS},
     validator::{
         consensus::{Consensus, Fork, Proposal, BLOCK_GAS_LIMIT},
@@ -51,6 +52,19 @@ use crate::{
     Error, Result,
 };
 
+/// Auxiliary function to export the time spent verifying the ZK proofs
+/// of a transaction
+fn record_zkps_verification(start: Instant) {
+    METRICS
+        .histogram(
+            "darkfi_zk_verification_seconds",
+            "Time spent verifying the ZK proofs of a transaction",
+            &[],
+            DEFAULT_BUCKETS,
+        )
+        .observe_duration(start.elapsed());
+}
+
 /// Verify given genesis [`BlockInfo`], and apply it to the provided overlay.
 pub async fn verify_genesis_block(
     overlay: &BlockchainOverlayPtr,
@@ -385,7 +399,7 @@ pub async fn verify_producer_transaction(
     tree: &mut MerkleTree,
 ) -> Result<PublicKey> {
     let tx_hash = tx.hash();
-    debug!(target: "validator::verification::verify_producer_transaction", "Validating producer transaction {tx_hash}");
+    debug!(target: "validator::verification::verify_producer_transaction", tx = tx_hash.to_string().as_str(), slot = verifying_block_height; "Validating producer transaction {tx_hash}");
 
     // Transaction must be a PoW reward one
     if !tx.is_pow_reward() {
@@ -482,22 +496,25 @@ pub async fn verify_producer_transaction(
     // First we verify the signatures as that's cheaper, and then finally we verify the ZK proofs.
     debug!(target: "validator::verification::verify_producer_transaction", "Verifying signatures for transaction {tx_hash}");
     if sig_table.len() != tx.signatures.len() {
-        error!(target: "validator::verification::verify_producer_transaction", "Incorrect number of signatures in tx {tx_hash}");
+        error!(target: "validator::verification::verify_producer_transaction", tx = tx_hash.to_string().as_str(), slot = verifying_block_height; "Incorrect number of signatures in tx {tx_hash}");
         return Err(TxVerifyFailed::MissingSignatures.into())
     }
 
     // TODO: Go through the ZK circuits that have to be verified and account for the opcodes.
 
     if let Err(e) = tx.verify_sigs(sig_table) {
-        error!(target: "validator::verification::verify_producer_transaction", "Signature verification for tx {tx_hash} failed: {e}");
+        error!(target: "validator::verification::verify_producer_transaction", tx = tx_hash.to_string().as_str(), slot = verifying_block_height; "Signature verification for tx {tx_hash} failed: {e}");
         return Err(TxVerifyFailed::InvalidSignature.into())
     }
 
     debug!(target: "validator::verification::verify_producer_transaction", "Signature verification successful");
 
     debug!(target: "validator::verification::verify_producer_transaction", "Verifying ZK proofs for transaction {tx_hash}");
-    if let Err(e) = tx.verify_zkps(&verifying_keys, zkp_table).await {
-        error!(target: "validator::verification::verify_producer_transaction", "ZK proof verification for tx {tx_hash} failed: {e}");
+    let zkps_start = Instant::now();
+    let zkps_result = tx.verify_zkps(&verifying_keys, zkp_table).await;
+    record_zkps_verification(zkps_start);
+    if let Err(e) = zkps_result {
+        error!(target: "validator::verification::verify_producer_transaction", tx = tx_hash.to_string().as_str(), slot = verifying_block_height; "ZK proof verification for tx {tx_hash} failed: {e}");
         return Err(TxVerifyFailed::InvalidZkProof.into())
     }
     debug!(target: "validator::verification::verify_producer_transaction", "ZK proof verification successful");
@@ -505,7 +522,7 @@ pub async fn verify_producer_transaction(
     // Append hash to merkle tree
     append_tx_to_merkle_tree(tree, tx);
 
-    debug!(target: "validator::verification::verify_producer_transaction", "Producer transaction {tx_hash} verified successfully");
+    debug!(target: "validator::verification::verify_producer_transaction", tx = tx_hash.to_string().as_str(), slot = verifying_block_height; "Producer transaction {tx_hash} verified successfully");
 
     Ok(signature_public_key)
 }
@@ -520,7 +537,7 @@ pub async fn apply_producer_transaction(
     tree: &mut MerkleTree,
 ) -> Result<PublicKey> {
     let tx_hash = tx.hash();
-    debug!(target: "validator::verification::apply_producer_transaction", "Applying producer transaction {tx_hash}");
+    debug!(target: "validator::verification::apply_producer_transaction", tx = tx_hash.to_string().as_str(), slot = verifying_block_height; "Applying producer transaction {tx_hash}");
 
     // Producer transactions must contain a single, non-empty call
     if !tx.is_single_call() {
@@ -582,7 +599,7 @@ pub async fn apply_producer_transaction(
     // Append hash to merkle tree
     append_tx_to_merkle_tree(tree, tx);
 
-    debug!(target: "validator::verification::apply_producer_transaction", "Producer transaction {tx_hash} executed successfully");
+    debug!(target: "validator::verification::apply_producer_transaction", tx = tx_hash.to_string().as_str(), slot = verifying_block_height; "Producer transaction {tx_hash} executed successfully");
 
     Ok(signature_public_key)
 }
@@ -600,7 +617,8 @@ pub async fn verify_transaction(
     verify_fee: bool,
 ) -> Result<GasData> {
     let tx_hash = tx.hash();
-    debug!(target: "validator::verification::verify_transaction", "Validating transaction {tx_hash}");
+    debug!(target: "validator::verification::verify_transaction", tx = tx_hash.to_string().as_str(), slot = verifying_block_height; "Validating transaction {tx_hash}");
+    let verify_start = Instant::now();
 
     // Create a FeeData instance to hold the calculated fee data
     let mut gas_data = GasData::default();
@@ -638,6 +656,7 @@ pub async fn verify_transaction(
         if !found_fee {
             error!(
                 target: "validator::verification::verify_transcation",
+                tx = tx_hash.to_string().as_str(), slot = verifying_block_height;
                 "[VALIDATOR] Transaction {tx_hash} does not contain fee payment call"
             );
             return Err(TxVerifyFailed::InvalidFee.into())
@@ -687,6 +706,7 @@ pub async fn verify_transaction(
         if decoder.position() != metadata.len() as u64 {
             error!(
                 target: "validator::verification::verify_transaction",
+                tx = tx_hash.to_string().as_str(), slot = verifying_block_height;
                 "[VALIDATOR] Failed decoding entire metadata buffer for {tx_hash}:{idx}"
             );
             return Err(TxVerifyFailed::ErroneousTxs(vec![tx.clone()]).into())
@@ -768,8 +788,8 @@ pub async fn verify_transaction(
     }
 
     // The signature fee is tx_size + fixed_sig_fee * n_signatures
-    gas_data.signatures = (PALLAS_SCHNORR_SIGNATURE_FEE * tx.signatures.len() as u64) +
-        serialize_async(tx).await.len() as u64;
+    let tx_size = serialize_async(tx).await.len() as u64;
+    gas_data.signatures = (PALLAS_SCHNORR_SIGNATURE_FEE * tx.signatures.len() as u64) + tx_size;
     debug!(target: "validator::verification::verify_transaction", "The gas used for signature of transaction {tx_hash}: {}", gas_data.signatures);
 
     // The ZK circuit fee is calculated using a function in validator/fees.rs
@@ -791,6 +811,7 @@ pub async fn verify_transaction(
             Err(e) => {
                 error!(
                     target: "validator::verification::verify_transaction",
+                    tx = tx_hash.to_string().as_str(), slot = verifying_block_height;
                     "[VALIDATOR] Failed deserializing tx {tx_hash} fee call: {e}"
                 );
                 return Err(TxVerifyFailed::InvalidFee.into())
@@ -804,6 +825,7 @@ pub async fn verify_transaction(
         if required_fee > fee {
             error!(
                 target: "validator::verification::verify_transaction",
+                tx = tx_hash.to_string().as_str(), slot = verifying_block_height;
                 "[VALIDATOR] Transaction {tx_hash} has insufficient fee. Required: {required_fee}, Paid: {fee}"
             );
             return Err(TxVerifyFailed::InsufficientFee.into())
@@ -822,6 +844,7 @@ pub async fn verify_transaction(
     if sig_table.len() != tx.signatures.len() {
         error!(
             target: "validator::verification::verify_transaction",
+            tx = tx_hash.to_string().as_str(), slot = verifying_block_height;
             "[VALIDATOR] Incorrect number of signatures in tx {tx_hash}"
         );
         return Err(TxVerifyFailed::MissingSignatures.into())
@@ -830,6 +853,7 @@ pub async fn verify_transaction(
     if let Err(e) = tx.verify_sigs(sig_table) {
         error!(
             target: "validator::verification::verify_transaction",
+            tx = tx_hash.to_string().as_str(), slot = verifying_block_height;
             "[VALIDATOR] Signature verification for tx {tx_hash} failed: {e}"
         );
         return Err(TxVerifyFailed::InvalidSignature.into())
@@ -837,9 +861,13 @@ pub async fn verify_transaction(
     debug!(target: "validator::verification::verify_transaction", "Signature verification successful");
 
     debug!(target: "validator::verification::verify_transaction", "Verifying ZK proofs for transaction {tx_hash}");
-    if let Err(e) = tx.verify_zkps(verifying_keys, zkp_table).await {
+    let zkps_start = Instant::now();
+    let zkps_result = tx.verify_zkps(verifying_keys, zkp_table).await;
+    record_zkps_verification(zkps_start);
+    if let Err(e) = zkps_result {
         error!(
             target: "validator::verification::verify_transaction",
+            tx = tx_hash.to_string().as_str(), slot = verifying_block_height;
             "[VALIDATOR] ZK proof verification for tx {tx_hash} failed: {e}"
         );
         return Err(TxVerifyFailed::InvalidZkProof.into())
@@ -849,8 +877,17 @@ pub async fn verify_transaction(
     // Append hash to merkle tree
     append_tx_to_merkle_tree(tree, tx);
 
+    // Record the transaction metrics for analytics
+    let metrics = TxMetrics {
+        size: tx_size,
+        proofs: tx.proofs.iter().map(|p| p.len() as u64).sum(),
+        gas_used: total_gas_used,
+        verify_time: verify_start.elapsed().as_micros() as u64,
+    };
+    overlay.lock().unwrap().transactions.insert_metrics(&tx_hash, &metrics)?;
+
     debug!(target: "validator::verification::verify_transaction", "The total gas used for transaction {tx_hash}: {total_gas_used}");
-    debug!(target: "validator::verification::verify_transaction", "Transaction {tx_hash} verified successfully");
+    debug!(target: "validator::verification::verify_transaction", tx = tx_hash.to_string().as_str(), slot = verifying_block_height; "Transaction {tx_hash} verified successfully");
     Ok(gas_data)
 }
 
@@ -864,7 +901,7 @@ pub async fn apply_transaction(
     tree: &mut MerkleTree,
 ) -> Result<()> {
     let tx_hash = tx.hash();
-    debug!(target: "validator::verification::apply_transaction", "Applying transaction {tx_hash}");
+    debug!(target: "validator::verification::apply_transaction", tx = tx_hash.to_string().as_str(), slot = verifying_block_height; "Applying transaction {tx_hash}");
 
     // Write the transaction calls payload data
     let mut payload = vec![];
@@ -926,7 +963,7 @@ pub async fn apply_transaction(
     // Append hash to merkle tree
     append_tx_to_merkle_tree(tree, tx);
 
-    debug!(target: "validator::verification::apply_transaction", "Transaction {tx_hash} applied successfully");
+    debug!(target: "validator::verification::apply_transaction", tx = tx_hash.to_string().as_str(), slot = verifying_block_height; "Transaction {tx_hash} applied successfully");
     Ok(())
 }
 
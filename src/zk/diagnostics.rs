@@ -0,0 +1,245 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Constraint failure diagnostics
+//!
+//! `Proof::create` happily produces a proof for an unsatisfied circuit,
+//! and verification then fails without telling which constraint broke.
+//! [`diagnose`] runs the circuit through halo2's `MockProver` instead,
+//! and finds the first opcode whose constraints don't hold by running
+//! truncated copies of the circuit. Using the source lines found in the
+//! binary's `.debug` section, that opcode is mapped back to the zkas
+//! source.
+use std::fmt;
+
+use darkfi_sdk::pasta::pallas;
+use halo2_proofs::dev::MockProver;
+
+use super::{Witness, ZkCircuit};
+use crate::zkas::{Opcode, ZkBinary};
+
+/// The opcode responsible for a constraint failure
+#[derive(Clone, Debug)]
+pub struct FailingOpcode {
+    /// Index of the opcode in the binary's `.circuit` section
+    pub index: usize,
+    /// Opcode name, as written in zkas source
+    pub opcode: String,
+    /// Source line of the opcode, if the binary holds debug info
+    pub line: Option<usize>,
+}
+
+/// Structured result of [`diagnose`]
+#[derive(Clone, Debug)]
+pub struct DiagnosticReport {
+    /// Namespace of the diagnosed circuit
+    pub namespace: String,
+    /// First opcode whose constraints are not satisfied
+    pub failing_opcode: Option<FailingOpcode>,
+    /// Failures reported by the mock prover over the whole circuit.
+    /// These name the gadget regions and cells involved.
+    pub failures: Vec<String>,
+}
+
+impl DiagnosticReport {
+    /// Returns `true` if the circuit is satisfied by the given witnesses
+    pub fn is_satisfied(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl fmt::Display for DiagnosticReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_satisfied() {
+            return write!(f, "Circuit \"{}\" is satisfied", self.namespace)
+        }
+
+        write!(f, "Circuit \"{}\" is not satisfied", self.namespace)?;
+        if let Some(op) = &self.failing_opcode {
+            match op.line {
+                Some(line) => write!(f, " at line {line}: `{}` (opcode {})", op.opcode, op.index)?,
+                None => write!(f, " at `{}` (opcode {})", op.opcode, op.index)?,
+            }
+        }
+
+        for failure in &self.failures {
+            write!(f, "\n  {failure}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Run the circuit of `zkbin` over the given witnesses and public inputs
+/// with the `MockProver`, and report which constraints fail.
+/// This is slow and meant to be used for debugging only.
+pub fn diagnose(
+    zkbin: &ZkBinary,
+    witnesses: Vec<Witness>,
+    public_inputs: &[pallas::Base],
+) -> DiagnosticReport {
+    let circuit = ZkCircuit::new(witnesses, zkbin);
+    let failures = mock_failures(zkbin.k, &circuit, public_inputs);
+
+    if failures.is_empty() {
+        return DiagnosticReport {
+            namespace: zkbin.namespace.clone(),
+            failing_opcode: None,
+            failures,
+        }
+    }
+
+    // Failures can only pile up as opcodes get added, so we bisect for the
+    // shortest prefix of the circuit that fails. Its last opcode is the
+    // first one with broken constraints.
+    let (mut lo, mut hi) = (1, circuit.opcodes.len());
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if prefix_fails(zkbin.k, &circuit, mid, public_inputs) {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+
+    // Only pin an opcode if the failure is reproducible within the opcodes,
+    // otherwise it comes from something like a wrong amount of instances.
+    let failing_opcode = if hi > 0 && prefix_fails(zkbin.k, &circuit, hi, public_inputs) {
+        let index = hi - 1;
+        Some(FailingOpcode {
+            index,
+            opcode: circuit.opcodes[index].0.name().to_string(),
+            line: zkbin.opcode_line(index),
+        })
+    } else {
+        None
+    };
+
+    DiagnosticReport { namespace: zkbin.namespace.clone(), failing_opcode, failures }
+}
+
+/// Check if the circuit truncated to its first `len` opcodes fails.
+/// Only the public inputs the truncated circuit constrains are passed.
+fn prefix_fails(k: u32, circuit: &ZkCircuit, len: usize, public_inputs: &[pallas::Base]) -> bool {
+    let mut prefix = circuit.clone();
+    prefix.opcodes.truncate(len);
+
+    let n_instances =
+        prefix.opcodes.iter().filter(|(op, _)| *op == Opcode::ConstrainInstance).count();
+    let public_inputs = &public_inputs[..n_instances.min(public_inputs.len())];
+
+    !mock_failures(k, &prefix, public_inputs).is_empty()
+}
+
+fn mock_failures(k: u32, circuit: &ZkCircuit, public_inputs: &[pallas::Base]) -> Vec<String> {
+    let prover = match MockProver::run(k, circuit, vec![public_inputs.to_vec()]) {
+        Ok(v) => v,
+        Err(e) => return vec![format!("Synthesis failed: {e}")],
+    };
+
+    match prover.verify() {
+        Ok(()) => vec![],
+        Err(failures) => failures.iter().map(|x| x.to_string()).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::circuit::Value;
+
+    use super::*;
+    use crate::zkas::{Analyzer, Compiler, Lexer, Parser};
+
+    const SOURCE: &str = r#"
+k = 11;
+field = "pallas";
+
+constant "Diagnostics" {}
+
+witness "Diagnostics" {
+    Base a,
+    Base b,
+}
+
+circuit "Diagnostics" {
+    sum = base_add(a, b);
+    constrain_instance(sum);
+    constrain_equal_base(a, b);
+}
+"#;
+
+    fn compile() -> ZkBinary {
+        let lexer = Lexer::new("test.zk", SOURCE.chars());
+        let tokens = lexer.lex().unwrap();
+        let parser = Parser::new("test.zk", SOURCE.chars(), tokens);
+        let (namespace, k, constants, witnesses, statements) = parser.parse().unwrap();
+        let mut analyzer =
+            Analyzer::new("test.zk", SOURCE.chars(), constants, witnesses, statements);
+        analyzer.analyze_types().unwrap();
+
+        let compiler = Compiler::new(
+            "test.zk",
+            SOURCE.chars(),
+            namespace,
+            k,
+            analyzer.constants,
+            analyzer.witnesses,
+            analyzer.statements,
+            analyzer.literals,
+            true,
+        );
+
+        ZkBinary::decode(&compiler.compile().unwrap()).unwrap()
+    }
+
+    fn witnesses(a: u64, b: u64) -> Vec<Witness> {
+        vec![
+            Witness::Base(Value::known(pallas::Base::from(a))),
+            Witness::Base(Value::known(pallas::Base::from(b))),
+        ]
+    }
+
+    fn source_line(needle: &str) -> usize {
+        SOURCE.lines().position(|x| x.contains(needle)).unwrap() + 1
+    }
+
+    #[test]
+    fn diagnose_failing_opcode() {
+        let zkbin = compile();
+        assert_eq!(zkbin.opcode_line(0), Some(source_line("base_add")));
+
+        let report = diagnose(&zkbin, witnesses(2, 2), &[pallas::Base::from(4)]);
+        assert!(report.is_satisfied());
+        assert!(report.failing_opcode.is_none());
+
+        // Wrong public input
+        let report = diagnose(&zkbin, witnesses(2, 2), &[pallas::Base::from(5)]);
+        assert!(!report.is_satisfied());
+        let op = report.failing_opcode.unwrap();
+        assert_eq!(op.index, 1);
+        assert_eq!(op.opcode, "constrain_instance");
+        assert_eq!(op.line, Some(source_line("constrain_instance")));
+
+        // Unequal witnesses
+        let report = diagnose(&zkbin, witnesses(1, 2), &[pallas::Base::from(3)]);
+        assert!(!report.is_satisfied());
+        let op = report.failing_opcode.unwrap();
+        assert_eq!(op.index, 2);
+        assert_eq!(op.line, Some(source_line("constrain_equal_base")));
+    }
+}
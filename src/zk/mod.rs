@@ -24,6 +24,10 @@ pub use vm::ZkCircuit;
 pub mod vm_heap;
 pub use vm_heap::{empty_witnesses, Witness};
 
+/// Named witness construction using zkas debug symbols
+pub mod witness_map;
+pub use witness_map::WitnessMap;
+
 /// ZK gadget implementations
 pub mod gadget;
 
@@ -37,8 +41,12 @@ pub use tracer::DebugOpValue;
 
 mod debug;
 pub use debug::zkas_type_checks;
+
+/// Map constraint failures back to zkas source lines
+pub mod diagnostics;
 #[cfg(feature = "tinyjson")]
 pub use debug::{export_witness_json, import_witness_json};
+pub use diagnostics::{diagnose, DiagnosticReport};
 
 pub mod halo2 {
     pub use halo2_proofs::{
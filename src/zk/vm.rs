@@ -341,6 +341,7 @@ impl Circuit<pallas::Base> for ZkCircuit {
             opcodes.contains(&Opcode::EcGetX) ||
             opcodes.contains(&Opcode::EcGetY) ||
             opcodes.contains(&Opcode::ConstrainEqualPoint) ||
+            opcodes.contains(&Opcode::ElGamalSharedSecret) ||
             self.witnesses.iter().any(|x| {
                 matches!(x, Witness::EcPoint(_)) ||
                     matches!(x, Witness::EcNiPoint(_)) ||
@@ -349,7 +350,9 @@ impl Circuit<pallas::Base> for ZkCircuit {
             });
 
         // Conditions on which we enable the Poseidon hash chip
-        let init_poseidon = opcodes.contains(&Opcode::PoseidonHash);
+        let init_poseidon = opcodes.contains(&Opcode::PoseidonHash) ||
+            opcodes.contains(&Opcode::ElGamalSharedSecret) ||
+            opcodes.contains(&Opcode::ElGamalEncrypt);
 
         // Conditions on which we enable the Sinsemilla and Merkle chips
         let init_sinsemilla = opcodes.contains(&Opcode::MerkleRoot);
@@ -357,7 +360,8 @@ impl Circuit<pallas::Base> for ZkCircuit {
         // Conditions on which we enable the base field Arithmetic chip
         let init_arithmetic = opcodes.contains(&Opcode::BaseAdd) ||
             opcodes.contains(&Opcode::BaseSub) ||
-            opcodes.contains(&Opcode::BaseMul);
+            opcodes.contains(&Opcode::BaseMul) ||
+            opcodes.contains(&Opcode::ElGamalEncrypt);
 
         // Conditions on which we enable the native range check chips
         // TODO: Separate 253 and 64.
@@ -1013,6 +1017,31 @@ impl Circuit<pallas::Base> for ZkCircuit {
                     heap.push(HeapVar::Base(root));
                 }
 
+                Opcode::SparseMerkleNonMemberRoot => {
+                    trace!(target: "zk::vm", "Executing `SparseMerkleNonMemberRoot{:?}` opcode", opcode.1);
+                    let args = &opcode.1;
+
+                    let pos = heap[args[0].1].clone().try_into()?;
+                    let path: Value<[Fp; SMT_FP_DEPTH]> = heap[args[1].1].clone().try_into()?;
+
+                    // Empty leaves are zero, so constrain the leaf to be zero
+                    let leaf = assign_free_advice(
+                        layouter.namespace(|| "SparseMerkleNonMemberRoot empty leaf"),
+                        config.witness,
+                        Value::known(Fp::from(0)),
+                    )?;
+                    layouter.assign_region(
+                        || "constrain empty leaf",
+                        |mut region| region.constrain_constant(leaf.cell(), Fp::from(0)),
+                    )?;
+
+                    let root = smt_chip.check_membership(&mut layouter, pos, path, leaf)?;
+
+                    trace!(target: "zk::vm", "Pushing sparse merkle root to heap address {}", heap.len());
+                    self.tracer.push_base(&root);
+                    heap.push(HeapVar::Base(root));
+                }
+
                 Opcode::BaseAdd => {
                     trace!(target: "zk::vm", "Executing `BaseAdd{:?}` opcode", opcode.1);
                     let args = &opcode.1;
@@ -1208,6 +1237,88 @@ impl Circuit<pallas::Base> for ZkCircuit {
                     heap.push(HeapVar::Base(out));
                 }
 
+                Opcode::ElGamalSharedSecret => {
+                    trace!(target: "zk::vm", "Executing `ElGamalSharedSecret{:?}` opcode", opcode.1);
+                    let args = &opcode.1;
+
+                    let public: NonIdentityPoint<pallas::Affine, EccChip<OrchardFixedBases>> =
+                        heap[args[1].1].clone().try_into()?;
+
+                    let secret: AssignedCell<Fp, Fp> = heap[args[0].1].clone().try_into()?;
+                    let secret = ScalarVar::from_base(
+                        ecc_chip.as_ref().unwrap().clone(),
+                        layouter.namespace(|| "ElGamalSharedSecret::from_base()"),
+                        &secret,
+                    )?;
+
+                    // Diffie-Hellman shared point
+                    let (shared_point, _) =
+                        public.mul(layouter.namespace(|| "ElGamalSharedSecret::mul()"), secret)?;
+
+                    // The shared secret is the hash of the shared point coordinates
+                    let hasher = PoseidonHash::<
+                        _,
+                        _,
+                        poseidon::P128Pow5T3,
+                        poseidon::ConstantLength<2>,
+                        3,
+                        2,
+                    >::init(
+                        config.poseidon_chip().unwrap(),
+                        layouter.namespace(|| "ElGamalSharedSecret::hash init"),
+                    )?;
+
+                    let shared_secret: AssignedCell<Fp, Fp> = hasher
+                        .hash(
+                            layouter.namespace(|| "ElGamalSharedSecret::hash"),
+                            [shared_point.inner().x(), shared_point.inner().y()],
+                        )?
+                        .into();
+
+                    trace!(target: "zk::vm", "Pushing shared secret to heap address {}", heap.len());
+                    self.tracer.push_base(&shared_secret);
+                    heap.push(HeapVar::Base(shared_secret));
+                }
+
+                Opcode::ElGamalEncrypt => {
+                    trace!(target: "zk::vm", "Executing `ElGamalEncrypt{:?}` opcode", opcode.1);
+                    let args = &opcode.1;
+
+                    let value: AssignedCell<Fp, Fp> = heap[args[0].1].clone().try_into()?;
+                    let shared_secret: AssignedCell<Fp, Fp> = heap[args[1].1].clone().try_into()?;
+                    let nonce: AssignedCell<Fp, Fp> = heap[args[2].1].clone().try_into()?;
+
+                    // Derive the blind using the shared secret and the nonce
+                    let hasher = PoseidonHash::<
+                        _,
+                        _,
+                        poseidon::P128Pow5T3,
+                        poseidon::ConstantLength<2>,
+                        3,
+                        2,
+                    >::init(
+                        config.poseidon_chip().unwrap(),
+                        layouter.namespace(|| "ElGamalEncrypt::hash init"),
+                    )?;
+
+                    let blind: AssignedCell<Fp, Fp> = hasher
+                        .hash(
+                            layouter.namespace(|| "ElGamalEncrypt::hash"),
+                            [shared_secret, nonce],
+                        )?
+                        .into();
+
+                    let ciphertext = arith_chip.as_ref().unwrap().add(
+                        layouter.namespace(|| "ElGamalEncrypt::add()"),
+                        &value,
+                        &blind,
+                    )?;
+
+                    trace!(target: "zk::vm", "Pushing ciphertext to heap address {}", heap.len());
+                    self.tracer.push_base(&ciphertext);
+                    heap.push(HeapVar::Base(ciphertext));
+                }
+
                 Opcode::ConstrainEqualBase => {
                     trace!(target: "zk::vm", "Executing `ConstrainEqualBase{:?}` opcode", opcode.1);
                     let args = &opcode.1;
@@ -0,0 +1,199 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Named witness construction
+use std::collections::HashMap;
+
+use log::error;
+
+use super::Witness;
+use crate::{
+    zkas::{decoder::ZkBinary, VarType},
+    Error, Result,
+};
+
+/// Builder for prover witnesses keyed by the names used in the zkas
+/// source. The positional `Vec<Witness>` expected by `ZkCircuit` is
+/// produced with [`WitnessMap::build`], using the witness symbols found
+/// in the binary's `.debug` section.
+#[derive(Clone, Default)]
+pub struct WitnessMap {
+    witnesses: HashMap<String, Witness>,
+}
+
+impl WitnessMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert a witness under the given name. An existing witness
+    /// with the same name gets replaced.
+    pub fn insert(&mut self, name: &str, witness: Witness) -> &mut Self {
+        self.witnesses.insert(name.to_string(), witness);
+        self
+    }
+
+    /// Produce the ordered witness vector for the given binary.
+    /// Fails if a witness is missing, has the wrong type, or if the
+    /// map contains names the binary does not know about.
+    pub fn build(mut self, zkbin: &ZkBinary) -> Result<Vec<Witness>> {
+        let Some(debug_info) = &zkbin.debug_info else {
+            error!("zkas binary \"{}\" was compiled without debug info", zkbin.namespace);
+            return Err(Error::ZkasDebugInfoMissing)
+        };
+
+        let mut ret = Vec::with_capacity(zkbin.witnesses.len());
+
+        for (i, (name, var_type)) in debug_info.witnesses.iter().zip(&zkbin.witnesses).enumerate() {
+            let Some(witness) = self.witnesses.remove(name) else {
+                return Err(Error::MissingWitness(name.clone()))
+            };
+
+            if !witness_matches(&witness, var_type) {
+                error!(
+                    "Wrong type for witness \"{}\". Expected '{}', but instead got '{}'.",
+                    name,
+                    var_type.name(),
+                    witness.name()
+                );
+                return Err(Error::WrongWitnessType(i))
+            }
+
+            ret.push(witness);
+        }
+
+        // Anything left over is most likely a typo on the caller side
+        if let Some(name) = self.witnesses.into_keys().min() {
+            return Err(Error::UnknownWitness(name))
+        }
+
+        Ok(ret)
+    }
+}
+
+fn witness_matches(witness: &Witness, var_type: &VarType) -> bool {
+    match witness {
+        Witness::EcPoint(_) => *var_type == VarType::EcPoint,
+        Witness::EcNiPoint(_) => *var_type == VarType::EcNiPoint,
+        Witness::EcFixedPoint(_) => *var_type == VarType::EcFixedPoint,
+        Witness::Base(_) => *var_type == VarType::Base,
+        Witness::Scalar(_) => *var_type == VarType::Scalar,
+        Witness::MerklePath(_) => *var_type == VarType::MerklePath,
+        Witness::SparseMerklePath(_) => *var_type == VarType::SparseMerklePath,
+        Witness::Uint32(_) => *var_type == VarType::Uint32,
+        Witness::Uint64(_) => *var_type == VarType::Uint64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use darkfi_sdk::pasta::pallas;
+    use halo2_proofs::circuit::Value;
+
+    use super::*;
+    use crate::zkas::{Analyzer, Compiler, Lexer, Parser};
+
+    const SOURCE: &str = r#"
+k = 11;
+field = "pallas";
+
+constant "WitnessMap" {}
+
+witness "WitnessMap" {
+    Base a,
+    Base b,
+    Scalar blind,
+}
+
+circuit "WitnessMap" {
+    sum = base_add(a, b);
+    constrain_instance(sum);
+}
+"#;
+
+    fn compile(debug_info: bool) -> ZkBinary {
+        let lexer = Lexer::new("test.zk", SOURCE.chars());
+        let tokens = lexer.lex().unwrap();
+        let parser = Parser::new("test.zk", SOURCE.chars(), tokens);
+        let (namespace, k, constants, witnesses, statements) = parser.parse().unwrap();
+        let mut analyzer =
+            Analyzer::new("test.zk", SOURCE.chars(), constants, witnesses, statements);
+        analyzer.analyze_types().unwrap();
+
+        let compiler = Compiler::new(
+            "test.zk",
+            SOURCE.chars(),
+            namespace,
+            k,
+            analyzer.constants,
+            analyzer.witnesses,
+            analyzer.statements,
+            analyzer.literals,
+            debug_info,
+        );
+
+        ZkBinary::decode(&compiler.compile().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn witness_map_build() {
+        let zkbin = compile(true);
+        assert_eq!(zkbin.witness_index("blind"), Some(2));
+
+        // Insertion order should not matter
+        let mut map = WitnessMap::new();
+        map.insert("blind", Witness::Scalar(Value::known(pallas::Scalar::from(3))))
+            .insert("b", Witness::Base(Value::known(pallas::Base::from(2))))
+            .insert("a", Witness::Base(Value::known(pallas::Base::from(1))));
+
+        let witnesses = map.build(&zkbin).unwrap();
+        let names: Vec<&str> = witnesses.iter().map(|w| w.name()).collect();
+        assert_eq!(names, vec!["Base", "Base", "Scalar"]);
+
+        // Missing witness
+        let mut map = WitnessMap::new();
+        map.insert("a", Witness::Base(Value::known(pallas::Base::from(1))))
+            .insert("blind", Witness::Scalar(Value::known(pallas::Scalar::from(3))));
+        assert!(matches!(map.build(&zkbin), Err(Error::MissingWitness(n)) if n == "b"));
+
+        // Mistyped witness
+        let mut map = WitnessMap::new();
+        map.insert("a", Witness::Base(Value::known(pallas::Base::from(1))))
+            .insert("b", Witness::Base(Value::known(pallas::Base::from(2))))
+            .insert("blind", Witness::Base(Value::known(pallas::Base::from(3))));
+        assert!(matches!(map.build(&zkbin), Err(Error::WrongWitnessType(2))));
+
+        // Misspelled witness
+        let mut map = WitnessMap::new();
+        map.insert("a", Witness::Base(Value::known(pallas::Base::from(1))))
+            .insert("b", Witness::Base(Value::known(pallas::Base::from(2))))
+            .insert("blind", Witness::Scalar(Value::known(pallas::Scalar::from(3))))
+            .insert("blnd", Witness::Scalar(Value::known(pallas::Scalar::from(3))));
+        assert!(matches!(map.build(&zkbin), Err(Error::UnknownWitness(n)) if n == "blnd"));
+    }
+
+    #[test]
+    fn witness_map_stripped_binary() {
+        let zkbin = compile(false);
+        assert!(zkbin.debug_info.is_none());
+
+        let mut map = WitnessMap::new();
+        map.insert("a", Witness::Base(Value::known(pallas::Base::from(1))));
+        assert!(matches!(map.build(&zkbin), Err(Error::ZkasDebugInfoMissing)));
+    }
+}
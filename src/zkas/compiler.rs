@@ -155,7 +155,16 @@ impl Compiler {
             return Ok(bincode)
         }
 
-        // TODO: Otherwise, we proceed appending debug info.
+        // Otherwise, we proceed appending debug info. The .debug section
+        // holds the witness names in order of appearance so provers are
+        // able to construct their witness vectors by name, followed by the
+        // source line of every opcode so constraint failures can be traced
+        // back to the source.
+        bincode.extend_from_slice(b".debug");
+        let witness_names: Vec<String> = self.witnesses.iter().map(|x| x.name.clone()).collect();
+        bincode.extend_from_slice(&serialize(&witness_names));
+        let opcode_lines: Vec<u64> = self.statements.iter().map(|x| x.line as u64).collect();
+        bincode.extend_from_slice(&serialize(&opcode_lines));
 
         Ok(bincode)
     }
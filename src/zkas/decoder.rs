@@ -36,6 +36,18 @@ pub struct ZkBinary {
     pub literals: Vec<(LitType, String)>,
     pub witnesses: Vec<VarType>,
     pub opcodes: Vec<(Opcode, Vec<(HeapType, usize)>)>,
+    pub debug_info: Option<DebugInfo>,
+}
+
+/// Debug symbols found in the optional `.debug` section of a zkas binary.
+/// Binaries compiled with stripped symbols will not contain these.
+#[derive(Clone, Debug)]
+pub struct DebugInfo {
+    /// Witness names, in the same order as `ZkBinary::witnesses`
+    pub witnesses: Vec<String>,
+    /// Source lines of the opcodes, in the same order as `ZkBinary::opcodes`.
+    /// Empty for binaries compiled before these were recorded.
+    pub opcode_lines: Vec<usize>,
 }
 
 // https://stackoverflow.com/questions/35901547/how-can-i-find-a-subsequence-in-a-u8-slice
@@ -124,9 +136,14 @@ impl ZkBinary {
         let witnesses = ZkBinary::parse_witness(witness_section)?;
         let opcodes = ZkBinary::parse_circuit(circuit_section)?;
 
-        // TODO: Debug info
+        let debug_info = if debug_offset < bytes.len() {
+            let debug_section = &bytes[debug_offset + b".debug".len()..];
+            Some(ZkBinary::parse_debug(debug_section, witnesses.len(), opcodes.len())?)
+        } else {
+            None
+        };
 
-        Ok(Self { namespace, k, constants, literals, witnesses, opcodes })
+        Ok(Self { namespace, k, constants, literals, witnesses, opcodes, debug_info })
     }
 
     fn parse_constants(bytes: &[u8]) -> Result<Vec<(VarType, String)>> {
@@ -200,6 +217,46 @@ impl ZkBinary {
         Ok(witnesses)
     }
 
+    fn parse_debug(bytes: &[u8], witnesses_len: usize, opcodes_len: usize) -> Result<DebugInfo> {
+        let (witnesses, offset) = deserialize_partial::<Vec<String>>(bytes)?;
+
+        if witnesses.len() != witnesses_len {
+            return Err(ZkasErr(format!(
+                "Debug info has {} witness names, but binary has {} witnesses",
+                witnesses.len(),
+                witnesses_len,
+            )))
+        }
+
+        // Opcode lines were added later, so older binaries end here
+        if offset == bytes.len() {
+            return Ok(DebugInfo { witnesses, opcode_lines: vec![] })
+        }
+
+        let (opcode_lines, _) = deserialize_partial::<Vec<u64>>(&bytes[offset..])?;
+        if opcode_lines.len() != opcodes_len {
+            return Err(ZkasErr(format!(
+                "Debug info has {} opcode lines, but binary has {} opcodes",
+                opcode_lines.len(),
+                opcodes_len,
+            )))
+        }
+        let opcode_lines = opcode_lines.into_iter().map(|x| x as usize).collect();
+
+        Ok(DebugInfo { witnesses, opcode_lines })
+    }
+
+    /// Find the index of a named witness, if the binary holds debug info.
+    pub fn witness_index(&self, name: &str) -> Option<usize> {
+        self.debug_info.as_ref()?.witnesses.iter().position(|x| x == name)
+    }
+
+    /// Find the source line of the opcode at `index`, if the binary
+    /// holds debug info.
+    pub fn opcode_line(&self, index: usize) -> Option<usize> {
+        self.debug_info.as_ref()?.opcode_lines.get(index).copied()
+    }
+
     #[allow(clippy::type_complexity)]
     fn parse_circuit(bytes: &[u8]) -> Result<Vec<(Opcode, Vec<(HeapType, usize)>)>> {
         let mut opcodes = vec![];
@@ -55,6 +55,9 @@ pub enum Opcode {
     /// Calculate sparse Merkle root, given the position, path and a member
     SparseMerkleRoot = 0x21,
 
+    /// Calculate sparse Merkle root of an empty leaf, given the position and path
+    SparseMerkleNonMemberRoot = 0x22,
+
     /// Base field element addition
     BaseAdd = 0x30,
 
@@ -87,6 +90,12 @@ pub enum Opcode {
     /// Conditionally select between a and b (return a if a is zero, and b if a is nonzero)
     ZeroCondSelect = 0x61,
 
+    /// Derive an ElGamal shared secret from a secret and a public key using Diffie-Hellman
+    ElGamalSharedSecret = 0x70,
+
+    /// Encrypt a Base field element with an ElGamal shared secret, given a nonce
+    ElGamalEncrypt = 0x71,
+
     /// Constrain equality of two Base field elements inside the circuit
     ConstrainEqualBase = 0xe0,
 
@@ -113,6 +122,7 @@ impl Opcode {
             "poseidon_hash" => Some(Self::PoseidonHash),
             "merkle_root" => Some(Self::MerkleRoot),
             "sparse_merkle_root" => Some(Self::SparseMerkleRoot),
+            "sparse_merkle_non_member_root" => Some(Self::SparseMerkleNonMemberRoot),
             "base_add" => Some(Self::BaseAdd),
             "base_mul" => Some(Self::BaseMul),
             "base_sub" => Some(Self::BaseSub),
@@ -123,6 +133,8 @@ impl Opcode {
             "bool_check" => Some(Self::BoolCheck),
             "cond_select" => Some(Self::CondSelect),
             "zero_cond" => Some(Self::ZeroCondSelect),
+            "elgamal_shared_secret" => Some(Self::ElGamalSharedSecret),
+            "elgamal_encrypt" => Some(Self::ElGamalEncrypt),
             "constrain_equal_base" => Some(Self::ConstrainEqualBase),
             "constrain_equal_point" => Some(Self::ConstrainEqualPoint),
             "constrain_instance" => Some(Self::ConstrainInstance),
@@ -143,6 +155,7 @@ impl Opcode {
             0x10 => Some(Self::PoseidonHash),
             0x20 => Some(Self::MerkleRoot),
             0x21 => Some(Self::SparseMerkleRoot),
+            0x22 => Some(Self::SparseMerkleNonMemberRoot),
             0x30 => Some(Self::BaseAdd),
             0x31 => Some(Self::BaseMul),
             0x32 => Some(Self::BaseSub),
@@ -153,6 +166,8 @@ impl Opcode {
             0x53 => Some(Self::BoolCheck),
             0x60 => Some(Self::CondSelect),
             0x61 => Some(Self::ZeroCondSelect),
+            0x70 => Some(Self::ElGamalSharedSecret),
+            0x71 => Some(Self::ElGamalEncrypt),
             0xe0 => Some(Self::ConstrainEqualBase),
             0xe1 => Some(Self::ConstrainEqualPoint),
             0xf0 => Some(Self::ConstrainInstance),
@@ -174,6 +189,7 @@ impl Opcode {
             Self::PoseidonHash => "poseidon_hash",
             Self::MerkleRoot => "merkle_root",
             Self::SparseMerkleRoot => "sparse_merkle_root",
+            Self::SparseMerkleNonMemberRoot => "sparse_merkle_non_member_root",
             Self::BaseAdd => "base_add",
             Self::BaseMul => "base_mul",
             Self::BaseSub => "base_sub",
@@ -184,6 +200,8 @@ impl Opcode {
             Self::BoolCheck => "bool_check",
             Self::CondSelect => "cond_select",
             Self::ZeroCondSelect => "zero_cond",
+            Self::ElGamalSharedSecret => "elgamal_shared_secret",
+            Self::ElGamalEncrypt => "elgamal_encrypt",
             Self::ConstrainEqualBase => "constrain_equal_base",
             Self::ConstrainEqualPoint => "constrain_equal_point",
             Self::ConstrainInstance => "constrain_instance",
@@ -227,6 +245,10 @@ impl Opcode {
                 (vec![VarType::Base], vec![VarType::Base, VarType::SparseMerklePath, VarType::Base])
             }
 
+            Opcode::SparseMerkleNonMemberRoot => {
+                (vec![VarType::Base], vec![VarType::Base, VarType::SparseMerklePath])
+            }
+
             Opcode::BaseAdd => (vec![VarType::Base], vec![VarType::Base, VarType::Base]),
 
             Opcode::BaseMul => (vec![VarType::Base], vec![VarType::Base, VarType::Base]),
@@ -249,6 +271,14 @@ impl Opcode {
 
             Opcode::ZeroCondSelect => (vec![VarType::Base], vec![VarType::Base, VarType::Base]),
 
+            Opcode::ElGamalSharedSecret => {
+                (vec![VarType::Base], vec![VarType::Base, VarType::EcNiPoint])
+            }
+
+            Opcode::ElGamalEncrypt => {
+                (vec![VarType::Base], vec![VarType::Base, VarType::Base, VarType::Base])
+            }
+
             Opcode::ConstrainEqualBase => (vec![], vec![VarType::Base, VarType::Base]),
 
             Opcode::ConstrainEqualPoint => (vec![], vec![VarType::EcPoint, VarType::EcPoint]),
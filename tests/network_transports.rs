@@ -28,7 +28,8 @@ fn tcp_transport() {
     let url = Url::parse("tcp://127.0.0.1:5432").unwrap();
 
     smol::block_on(executor.run(async {
-        let listener = Listener::new(url.clone(), None).await.unwrap().listen().await.unwrap();
+        let listener =
+            Listener::new(url.clone(), None, None).await.unwrap().listen().await.unwrap();
         executor
             .spawn(async move {
                 let (stream, _) = listener.next().await.unwrap();
@@ -59,7 +60,8 @@ fn tcp_tls_transport() {
     let url = Url::parse("tcp+tls://127.0.0.1:5433").unwrap();
 
     smol::block_on(executor.run(async {
-        let listener = Listener::new(url.clone(), None).await.unwrap().listen().await.unwrap();
+        let listener =
+            Listener::new(url.clone(), None, None).await.unwrap().listen().await.unwrap();
         executor
             .spawn(async move {
                 let (stream, _) = listener.next().await.unwrap();
@@ -92,7 +94,8 @@ fn unix_transport() {
     .unwrap();
 
     smol::block_on(executor.run(async {
-        let listener = Listener::new(url.clone(), None).await.unwrap().listen().await.unwrap();
+        let listener =
+            Listener::new(url.clone(), None, None).await.unwrap().listen().await.unwrap();
         executor
             .spawn(async move {
                 let (stream, _) = listener.next().await.unwrap();
@@ -112,3 +115,63 @@ fn unix_transport() {
         assert_eq!(buf, payload);
     }));
 }
+
+#[test]
+fn ws_transport() {
+    let executor = LocalExecutor::new();
+    let url = Url::parse("ws://127.0.0.1:5434").unwrap();
+
+    smol::block_on(executor.run(async {
+        let listener =
+            Listener::new(url.clone(), None, None).await.unwrap().listen().await.unwrap();
+        executor
+            .spawn(async move {
+                let (stream, _) = listener.next().await.unwrap();
+                let (mut reader, mut writer) = smol::io::split(stream);
+                io::copy(&mut reader, &mut writer).await.unwrap();
+            })
+            .detach();
+
+        let payload = "ohai ws";
+
+        let dialer = Dialer::new(url, None, None).await.unwrap();
+        let mut client = dialer.dial(None).await.unwrap();
+        payload.encode_async(&mut client).await.unwrap();
+
+        let buf: String = AsyncDecodable::decode_async(&mut client).await.unwrap();
+
+        assert_eq!(buf, payload);
+    }));
+}
+
+#[test]
+fn wss_transport() {
+    // Register a CryptoProvider for rustls
+    use futures_rustls::rustls::crypto::{ring, CryptoProvider};
+    let _ = CryptoProvider::install_default(ring::default_provider());
+
+    let executor = LocalExecutor::new();
+    let url = Url::parse("wss://127.0.0.1:5435").unwrap();
+
+    smol::block_on(executor.run(async {
+        let listener =
+            Listener::new(url.clone(), None, None).await.unwrap().listen().await.unwrap();
+        executor
+            .spawn(async move {
+                let (stream, _) = listener.next().await.unwrap();
+                let (mut reader, mut writer) = smol::io::split(stream);
+                io::copy(&mut reader, &mut writer).await.unwrap();
+            })
+            .detach();
+
+        let payload = "ohai wss";
+
+        let dialer = Dialer::new(url, None, None).await.unwrap();
+        let mut client = dialer.dial(None).await.unwrap();
+        payload.encode_async(&mut client).await.unwrap();
+
+        let buf: String = AsyncDecodable::decode_async(&mut client).await.unwrap();
+
+        assert_eq!(buf, payload);
+    }));
+}
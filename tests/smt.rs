@@ -77,3 +77,55 @@ fn zkvm_smt() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn zkvm_smt_non_member() -> Result<()> {
+    let bincode = include_bytes!("../proof/smt_non_member.zk.bin");
+    let zkbin = ZkBinary::decode(bincode)?;
+
+    let hasher = PoseidonFp::new();
+    let store = MemoryStorageFp::new();
+    let mut smt = SmtMemoryFp::new(store, hasher.clone(), &EMPTY_NODES_FP);
+
+    let leaves = vec![Fp::random(&mut OsRng), Fp::random(&mut OsRng), Fp::random(&mut OsRng)];
+    let leaves: Vec<_> = leaves.into_iter().map(|l| (l, l)).collect();
+    smt.insert_batch(leaves.clone()).unwrap();
+
+    // Prove that a fresh position is not in the tree
+    let pos = Fp::random(&mut OsRng);
+    let root = smt.root();
+    let path = smt.prove_non_membership(&pos).unwrap();
+    assert!(path.verify_non_membership(&root, &pos));
+
+    // Values for the proof
+    let prover_witnesses =
+        vec![Witness::SparseMerklePath(Value::known(path.path)), Witness::Base(Value::known(pos))];
+
+    let public_inputs = vec![pos, root];
+
+    let circuit = ZkCircuit::new(prover_witnesses, &zkbin);
+
+    let mockprover = MockProver::run(zkbin.k, &circuit, vec![public_inputs.clone()])?;
+    mockprover.assert_satisfied();
+
+    let proving_key = ProvingKey::build(zkbin.k, &circuit);
+    let proof = Proof::create(&proving_key, &[circuit], &public_inputs, &mut OsRng)?;
+
+    let verifier_witnesses = empty_witnesses(&zkbin)?;
+    let circuit = ZkCircuit::new(verifier_witnesses, &zkbin);
+    let verifying_key = VerifyingKey::build(zkbin.k, &circuit);
+    proof.verify(&verifying_key, &public_inputs)?;
+
+    // Members of the tree can't be proven absent
+    let (member, _) = leaves[0];
+    let path = smt.prove_membership(&member);
+    let prover_witnesses = vec![
+        Witness::SparseMerklePath(Value::known(path.path)),
+        Witness::Base(Value::known(member)),
+    ];
+    let circuit = ZkCircuit::new(prover_witnesses, &zkbin);
+    let mockprover = MockProver::run(zkbin.k, &circuit, vec![vec![member, root]])?;
+    assert!(mockprover.verify().is_err());
+
+    Ok(())
+}
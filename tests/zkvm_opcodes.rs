@@ -17,8 +17,8 @@
  */
 
 use darkfi_sdk::crypto::{
-    pedersen::pedersen_commitment_u64, util::fp_mod_fv, Blind, MerkleNode, MerkleTree, PublicKey,
-    SecretKey,
+    note::ElGamalEncryptedNote, pedersen::pedersen_commitment_u64, util::fp_mod_fv, Blind,
+    MerkleNode, MerkleTree, PublicKey, SecretKey,
 };
 use halo2_gadgets::poseidon::{
     primitives as poseidon,
@@ -105,6 +105,9 @@ fn zkvm_opcodes() -> Result<()> {
     let public = PublicKey::from_secret(SecretKey::from(secret));
     let (pub_x, pub_y) = public.xy();
 
+    let enc_note =
+        ElGamalEncryptedNote::encrypt_unsafe([a], &ephem_secret, &PublicKey::try_from(pubkey)?)?;
+
     let public_inputs = vec![
         *value_coords.x(),
         *value_coords.y(),
@@ -117,6 +120,7 @@ fn zkvm_opcodes() -> Result<()> {
         ephem_y,
         a,
         pallas::Base::ZERO,
+        enc_note.encrypted_values[0],
     ];
 
     //darkfi::zk::export_witness_json("proof/witness/opcodes.json", &prover_witnesses, &public_inputs);